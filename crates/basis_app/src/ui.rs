@@ -666,6 +666,9 @@ async fn draw_create_note(app: &mut App) -> Result<()> {
                                 amount,
                                 timestamp,
                                 signature: hex::encode(signature),
+                                signing_version: 1,
+                                tracker_id: None,
+                                encrypted_payload: None,
                             };
 
                             match app.client.create_note(request).await {
@@ -876,13 +879,22 @@ async fn draw_redeem_note(app: &mut App) -> Result<()> {
                                     };
 
                                     match app.client.complete_redemption(complete_request).await {
-                                        Ok(_) => {
+                                        Ok(true) => {
                                             app.set_notification(
                                                 format!("Redeemed {} nanoERG", amount),
                                                 false,
                                             );
                                             app.refresh_data().await?;
                                         }
+                                        Ok(false) => {
+                                            app.set_notification(
+                                                format!(
+                                                    "Redemption of {} nanoERG completes once confirmed on chain",
+                                                    amount
+                                                ),
+                                                false,
+                                            );
+                                        }
                                         Err(e) => {
                                             app.set_notification(
                                                 format!("Failed to complete redemption: {}", e),
@@ -1,6 +1,8 @@
 use crate::config::{AccountConfig, ConfigManager};
-use crate::crypto::{KeyPair, PubKey};
+use crate::crypto::{pubkey_to_address, KeyPair, PubKey};
+use crate::hd::{self, ExtendedKey};
 use anyhow::Result;
+use basis_store::Network;
 use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
@@ -8,6 +10,8 @@ pub struct Account {
     pub name: String,
     pub keypair: KeyPair,
     pub created_at: u64,
+    /// EIP-3 path of wallet-derived accounts
+    pub derivation_path: Option<String>,
 }
 
 impl Account {
@@ -21,6 +25,7 @@ impl Account {
             name,
             keypair,
             created_at,
+            derivation_path: None,
         })
     }
 
@@ -29,9 +34,16 @@ impl Account {
             name: config.name.clone(),
             keypair,
             created_at: config.created_at,
+            derivation_path: config.derivation_path.clone(),
         }
     }
 
+    pub fn from_extended_key(name: &str, key: &ExtendedKey, derivation_path: &str) -> Result<Self> {
+        let mut account = Self::from_private_key_hex(name, &hex::encode(key.secret_key_bytes()))?;
+        account.derivation_path = Some(derivation_path.to_string());
+        Ok(account)
+    }
+
     pub fn from_private_key_hex(name: &str, private_key_hex: &str) -> Result<Self> {
         let private_key_bytes = hex::decode(private_key_hex)
             .map_err(|e| anyhow::anyhow!("Invalid hex encoding: {}", e))?;
@@ -49,6 +61,7 @@ impl Account {
             name: name.to_string(),
             keypair,
             created_at,
+            derivation_path: None,
         })
     }
 
@@ -56,6 +69,11 @@ impl Account {
         hex::encode(self.keypair.get_public_key_bytes())
     }
 
+    /// P2PK address of the account key on `network`
+    pub fn get_address(&self, network: Network) -> Result<String> {
+        pubkey_to_address(&self.get_pubkey_hex(), network)
+    }
+
     pub fn get_private_key_hex(&self) -> String {
         hex::encode(self.keypair.get_private_key_bytes())
     }
@@ -69,6 +87,8 @@ impl Account {
 pub struct AccountManager {
     pub config_manager: ConfigManager,
     pub accounts: HashMap<String, Account>,
+    /// Network addresses are shown for: the configured one unless overridden by `--network`
+    pub network: Network,
 }
 
 impl AccountManager {
@@ -78,42 +98,137 @@ impl AccountManager {
         // Load accounts from config with persistent private keys
         for account_config in config_manager.list_accounts() {
             // Load account with persistent private key from config
-            let account = Account::from_private_key_hex(
+            let mut account = Account::from_private_key_hex(
                 &account_config.name,
                 &account_config.private_key_hex,
             )?;
+            account.created_at = account_config.created_at;
+            account.derivation_path = account_config.derivation_path.clone();
             accounts.insert(account_config.name.clone(), account);
         }
 
         Ok(Self {
+            network: config_manager.network(),
             config_manager,
             accounts,
         })
     }
 
+    /// Create an account derived from the HD wallet at the next unused address index
+    ///
+    /// A new 24-word wallet is generated when none exists yet; its mnemonic
+    /// can be shown with `wallet_mnemonic`.
     pub fn create_account(&mut self, name: &str) -> Result<Account> {
+        self.create_derived_account(name, None)
+    }
+
+    /// Create an account derived from the HD wallet at an address index
+    pub fn create_derived_account(&mut self, name: &str, index: Option<u32>) -> Result<Account> {
         if self.accounts.contains_key(name) {
             return Err(anyhow::anyhow!("Account '{}' already exists", name));
         }
+        if !self.has_wallet() {
+            self.init_wallet(24)?;
+        }
+
+        let index = match index {
+            Some(index) => index,
+            None => (0..)
+                .find(|index| !self.is_path_used(&hd::eip3_path(0, *index)))
+                .expect("address indices are unbounded"),
+        };
+        let path = hd::eip3_path(0, index);
+        if self.is_path_used(&path) {
+            return Err(anyhow::anyhow!("Address index {} is already used by another account", index));
+        }
 
-        let account = Account::new(name.to_string())?;
+        let account = Account::from_extended_key(name, &self.derive_key(index)?, &path)?;
         let pubkey_hex = account.get_pubkey_hex();
         let private_key_hex = account.get_private_key_hex();
 
         // Save to config with private key for persistence
         self.config_manager
-            .add_account(name, &pubkey_hex, &private_key_hex)?;
+            .add_derived_account(name, &pubkey_hex, &private_key_hex, Some(&path))?;
 
         self.accounts.insert(name.to_string(), account.clone());
 
         // Set as current if no current account
-        if self.config_manager.get_config().current_account.is_none() {
+        if self.config_manager.current_account_name().is_none() {
             self.config_manager.set_current_account(name)?;
         }
 
         Ok(account)
     }
 
+    fn is_path_used(&self, path: &str) -> bool {
+        self.accounts
+            .values()
+            .any(|account| account.derivation_path.as_deref() == Some(path))
+    }
+
+    pub fn has_wallet(&self) -> bool {
+        self.config_manager.get_config().wallet.is_some()
+    }
+
+    pub fn wallet_mnemonic(&self) -> Option<&str> {
+        self.config_manager
+            .get_config()
+            .wallet
+            .as_ref()
+            .map(|wallet| wallet.mnemonic.as_str())
+    }
+
+    /// Generate a new HD wallet and return its mnemonic
+    pub fn init_wallet(&mut self, word_count: usize) -> Result<String> {
+        if self.has_wallet() {
+            return Err(anyhow::anyhow!("A wallet already exists"));
+        }
+        let mnemonic = hd::generate_mnemonic(word_count)?;
+        self.config_manager.set_wallet(&mnemonic, "")?;
+        Ok(mnemonic)
+    }
+
+    /// Use an existing mnemonic, e.g. from an Ergo wallet, as the HD wallet
+    pub fn import_mnemonic(&mut self, mnemonic: &str, passphrase: &str) -> Result<()> {
+        if self.has_wallet() {
+            return Err(anyhow::anyhow!("A wallet already exists"));
+        }
+        let mnemonic = hd::validate_mnemonic(mnemonic)?;
+        // Fail on unusable passphrases before anything is saved
+        hd::mnemonic_to_seed(&mnemonic, passphrase)?;
+        self.config_manager.set_wallet(&mnemonic, passphrase)
+    }
+
+    // Master key of the wallet seed
+    fn wallet_master(&self) -> Result<ExtendedKey> {
+        let wallet = self
+            .config_manager
+            .get_config()
+            .wallet
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No wallet. Create or import one first"))?;
+        let seed = hd::mnemonic_to_seed(&wallet.mnemonic, &wallet.passphrase)?;
+        ExtendedKey::master(&seed)
+    }
+
+    /// Key at an address index of the wallet's first account
+    pub fn derive_key(&self, index: u32) -> Result<ExtendedKey> {
+        self.wallet_master()?.derive_path(&hd::eip3_path(0, index))
+    }
+
+    /// (path, public key, address) of the first `count` wallet addresses
+    pub fn wallet_addresses(&self, count: u32) -> Result<Vec<(String, PubKey, String)>> {
+        let master = self.wallet_master()?;
+        (0..count)
+            .map(|index| {
+                let path = hd::eip3_path(0, index);
+                let pubkey = master.derive_path(&path)?.public_key();
+                let address = pubkey_to_address(&hex::encode(pubkey), self.network)?;
+                Ok((path, pubkey, address))
+            })
+            .collect()
+    }
+
     pub fn switch_account(&mut self, name: &str) -> Result<()> {
         if !self.accounts.contains_key(name) {
             return Err(anyhow::anyhow!("Account '{}' not found", name));
@@ -123,15 +238,12 @@ impl AccountManager {
     }
 
     pub fn list_accounts(&self) -> Vec<&Account> {
-        if self.accounts.is_empty() {
-            println!("No accounts in memory. Use 'basis-cli account create <name>' to create one.");
-        }
         self.accounts.values().collect()
     }
 
     pub fn get_current(&self) -> Option<&Account> {
-        // Get current account name from config
-        let current_account_name = self.config_manager.get_config().current_account.as_ref()?;
+        // Get current account name from the active profile or config
+        let current_account_name = self.config_manager.current_account_name()?;
 
         // Return the account with that name
         self.accounts.get(current_account_name)
@@ -158,3 +270,38 @@ impl AccountManager {
         current.sign_message(message)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accounts_derived_from_imported_mnemonic() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("basis_cli_hd_{}.toml", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let mnemonic = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+        let mut manager = AccountManager::new(ConfigManager::new(Some(path.clone()))?)?;
+        manager.import_mnemonic(mnemonic, "")?;
+        let first = manager.create_account("alice")?;
+        let second = manager.create_account("bob")?;
+        assert_eq!(first.derivation_path.as_deref(), Some("m/44'/429'/0'/0/0"));
+        assert_eq!(second.derivation_path.as_deref(), Some("m/44'/429'/0'/0/1"));
+        assert!(manager.create_derived_account("carol", Some(1)).is_err());
+
+        let addresses = manager.wallet_addresses(2)?;
+        assert_eq!(addresses[0].1, first.keypair.get_public_key_bytes());
+        assert_eq!(addresses[1].2, second.get_address(manager.network)?);
+
+        // Derivation paths survive a reload of the config
+        let reloaded = AccountManager::new(ConfigManager::new(Some(path.clone()))?)?;
+        assert_eq!(reloaded.wallet_mnemonic(), Some(mnemonic));
+        assert_eq!(
+            reloaded.get_account("bob").and_then(|a| a.derivation_path.clone()).as_deref(),
+            Some("m/44'/429'/0'/0/1")
+        );
+
+        std::fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -9,6 +9,14 @@ pub struct CreateNoteRequest {
     pub amount: u64,
     pub timestamp: u64,
     pub signature: String,
+    /// Version of the signing message the signature covers (1 or 2)
+    pub signing_version: u8,
+    /// Tracker a version 2 note is bound to (hex-encoded NFT ID)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracker_id: Option<String>,
+    /// Payload envelope encrypted for the recipient (hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encrypted_payload: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +39,16 @@ impl SerializableIouNote {
     }
 }
 
+/// Encrypted payload the tracker holds for a note
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotePayloadResponse {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    /// Timestamp of the note version the payload was written for
+    pub timestamp: u64,
+    pub encrypted_payload: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KeyStatusResponse {
     pub total_debt: u64,
@@ -153,6 +171,8 @@ pub struct TrackerSignatureResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackerEvent {
     pub id: u64,
+    #[serde(default)]
+    pub seq: u64,
     pub event_type: String,
     pub timestamp: u64,
     pub issuer_pubkey: Option<String>,
@@ -171,14 +191,179 @@ pub struct ApiResponse<T> {
     pub error: Option<String>,
 }
 
+// Fields of GET /audit/report identifying the state a tracker serves
+#[derive(Debug, Clone, Deserialize)]
+struct TrackerStateSummary {
+    tracker_public_key: Option<String>,
+    local_root_digest: Option<String>,
+    local_root_height: Option<u64>,
+}
+
+/// Outcome of probing one tracker endpoint
+#[derive(Debug, Clone)]
+pub struct TrackerProbe {
+    pub url: String,
+    /// Round trip time of the state query
+    pub latency: std::time::Duration,
+    pub tracker_pubkey: Option<String>,
+    /// AVL root digest of the tracker's latest recorded state (hex)
+    pub state_digest: Option<String>,
+    pub state_height: Option<u64>,
+    /// Why the endpoint is unusable, if it is
+    pub error: Option<String>,
+}
+
+impl TrackerProbe {
+    pub fn is_healthy(&self) -> bool {
+        self.error.is_none()
+    }
+
+    // What two endpoints serving the same tracker state report identically
+    fn state(&self) -> (Option<&str>, Option<&str>) {
+        (self.tracker_pubkey.as_deref(), self.state_digest.as_deref())
+    }
+}
+
+/// Whether healthy endpoints report different tracker states
+pub fn trackers_disagree(probes: &[TrackerProbe]) -> bool {
+    let mut states = probes.iter().filter(|p| p.is_healthy()).map(TrackerProbe::state);
+    match states.next() {
+        Some(first) => states.any(|state| state != first),
+        None => false,
+    }
+}
+
+/// Index of the endpoint to use: the fastest healthy one among those
+/// reporting the state most endpoints agree on
+pub fn choose_tracker(probes: &[TrackerProbe]) -> Option<usize> {
+    let healthy: Vec<usize> = (0..probes.len()).filter(|&i| probes[i].is_healthy()).collect();
+    let agreeing = |i: usize| {
+        healthy
+            .iter()
+            .filter(|&&j| probes[j].state() == probes[i].state())
+            .count()
+    };
+    healthy
+        .iter()
+        .copied()
+        .max_by(|&a, &b| {
+            agreeing(a)
+                .cmp(&agreeing(b))
+                .then(probes[b].latency.cmp(&probes[a].latency))
+        })
+}
+
 #[derive(Debug)]
 pub struct TrackerClient {
     base_url: String,
+    /// Every configured tracker endpoint; `base_url` is the one in use
+    urls: Vec<String>,
 }
 
 impl TrackerClient {
     pub fn new(base_url: String) -> Self {
-        Self { base_url }
+        Self {
+            urls: vec![base_url.clone()],
+            base_url,
+        }
+    }
+
+    /// Client for several endpoints of the same tracker, using the first
+    /// until `select_tracker` picks another
+    pub fn with_urls(urls: Vec<String>) -> Result<Self> {
+        let base_url = urls
+            .first()
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("At least one tracker URL is required"))?;
+        Ok(Self {
+            urls,
+            ..Self::new(base_url)
+        })
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    pub fn urls(&self) -> &[String] {
+        &self.urls
+    }
+
+    /// Query every endpoint for the tracker state it serves
+    pub async fn probe_trackers(&self) -> Vec<TrackerProbe> {
+        self.urls
+            .iter()
+            .map(|url| {
+                let started = std::time::Instant::now();
+                let result = ureq::get(&format!("{}/audit/report", url))
+                    .timeout(std::time::Duration::from_secs(5))
+                    .call()
+                    .map_err(|e| e.to_string())
+                    .and_then(|response| {
+                        response
+                            .into_json::<ApiResponse<TrackerStateSummary>>()
+                            .map_err(|e| e.to_string())
+                    })
+                    .and_then(|api_response| {
+                        api_response
+                            .data
+                            .ok_or_else(|| format!("API error: {:?}", api_response.error))
+                    });
+                let latency = started.elapsed();
+                match result {
+                    Ok(summary) => TrackerProbe {
+                        url: url.clone(),
+                        latency,
+                        tracker_pubkey: summary.tracker_public_key,
+                        state_digest: summary.local_root_digest,
+                        state_height: summary.local_root_height,
+                        error: None,
+                    },
+                    Err(error) => TrackerProbe {
+                        url: url.clone(),
+                        latency,
+                        tracker_pubkey: None,
+                        state_digest: None,
+                        state_height: None,
+                        error: Some(error),
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// Probe every endpoint and switch to the preferred one
+    ///
+    /// Warns when endpoints are down or report different tracker states. If no
+    /// endpoint answers, the current one is kept.
+    pub async fn select_tracker(&mut self) -> Vec<TrackerProbe> {
+        let probes = self.probe_trackers().await;
+
+        for probe in probes.iter().filter(|p| !p.is_healthy()) {
+            eprintln!(
+                "⚠️  Tracker {} is unavailable: {}",
+                probe.url,
+                probe.error.as_deref().unwrap_or_default()
+            );
+        }
+        if trackers_disagree(&probes) {
+            eprintln!("🚨 WARNING: tracker endpoints report DIFFERENT states - do not trust a single one:");
+            for probe in probes.iter().filter(|p| p.is_healthy()) {
+                eprintln!(
+                    "🚨   {} tracker={} digest={} height={}",
+                    probe.url,
+                    probe.tracker_pubkey.as_deref().unwrap_or("unknown"),
+                    probe.state_digest.as_deref().unwrap_or("none"),
+                    probe.state_height.map(|h| h.to_string()).unwrap_or_else(|| "none".to_string())
+                );
+            }
+        }
+
+        match choose_tracker(&probes) {
+            Some(index) => self.base_url = probes[index].url.clone(),
+            None => eprintln!("⚠️  No tracker endpoint answered, using {}", self.base_url),
+        }
+        probes
     }
 
     pub async fn health_check(&self) -> Result<bool> {
@@ -265,6 +450,30 @@ impl TrackerClient {
         }
     }
 
+    /// Encrypted payload of the current version of a note, if the issuer attached one
+    pub async fn get_note_payload(&self, issuer: &str, recipient: &str) -> Result<Option<NotePayloadResponse>> {
+        let url = format!(
+            "{}/notes/issuer/{}/recipient/{}/payload",
+            self.base_url, issuer, recipient
+        );
+        let response = match ureq::get(&url).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(404, _)) => return Ok(None),
+            Err(ureq::Error::Status(_, response)) => {
+                let error_text = response.into_string()?;
+                return Err(anyhow::anyhow!("Failed to get note payload: {}", error_text));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let api_response: ApiResponse<NotePayloadResponse> = response.into_json()?;
+        if api_response.success {
+            Ok(api_response.data)
+        } else {
+            Err(anyhow::anyhow!("API error: {:?}", api_response.error))
+        }
+    }
+
     // Reserve operations
     pub async fn get_reserve_status(&self, pubkey: &str) -> Result<KeyStatusResponse> {
         let url = format!("{}/key-status/{}", self.base_url, pubkey);
@@ -319,10 +528,15 @@ impl TrackerClient {
         }
     }
 
-    pub async fn complete_redemption(&self, request: CompleteRedemptionRequest) -> Result<()> {
+    /// Complete a redemption
+    ///
+    /// Returns `false` if the tracker completes redemptions itself once it
+    /// sees them on chain, so the note is updated after confirmation instead.
+    pub async fn complete_redemption(&self, request: CompleteRedemptionRequest) -> Result<bool> {
         let url = format!("{}/redeem/complete", self.base_url);
         let response = match ureq::post(&url).send_json(serde_json::to_value(request)?) {
             Ok(resp) => resp,
+            Err(ureq::Error::Status(409, _)) => return Ok(false),
             Err(ureq::Error::Status(code, resp)) => {
                 let error_text = resp.into_string().unwrap_or_else(|_| format!("HTTP {}", code));
                 return Err(anyhow::anyhow!(
@@ -336,7 +550,7 @@ impl TrackerClient {
         };
 
         if response.status() == 200 {
-            Ok(())
+            Ok(true)
         } else {
             let error_text = response.into_string()?;
             Err(anyhow::anyhow!(
@@ -763,10 +977,67 @@ impl TrackerClient {
             ))
         }
     }
+
+    /// Download a report (e.g. "notes.csv") into `out`, copying it as it arrives
+    ///
+    /// `query` holds the report filters; returns the number of bytes written.
+    pub async fn download_report(
+        &self,
+        report: &str,
+        query: &[(&str, String)],
+        out: &mut dyn std::io::Write,
+    ) -> Result<u64> {
+        let url = format!("{}/reports/{}", self.base_url, report);
+        let mut request = ureq::get(&url);
+        for (name, value) in query {
+            request = request.query(name, value);
+        }
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => {
+                let error_text = response.into_string()?;
+                return Err(anyhow::anyhow!("Failed to get report {}: {}", report, error_text));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(std::io::copy(&mut response.into_reader(), out)?)
+    }
+
+    /// Settlements netting the tracker's offsetting debts, optionally only those involving `pubkey`
+    pub async fn get_netting_suggestions(
+        &self,
+        pubkey: Option<&str>,
+        max_cycle_length: Option<usize>,
+    ) -> Result<basis_store::NettingPlan> {
+        let url = format!("{}/netting/suggestions", self.base_url);
+        let mut request = ureq::get(&url);
+        if let Some(pubkey) = pubkey {
+            request = request.query("pubkey", pubkey);
+        }
+        if let Some(max_cycle_length) = max_cycle_length {
+            request = request.query("max_cycle_length", &max_cycle_length.to_string());
+        }
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(_, response)) => {
+                let error_text = response.into_string()?;
+                return Err(anyhow::anyhow!("Failed to get netting suggestions: {}", error_text));
+            }
+            Err(e) => return Err(e.into()),
+        };
+
+        let api_response: ApiResponse<basis_store::NettingPlan> = response.into_json()?;
+        match api_response.data {
+            Some(plan) if api_response.success => Ok(plan),
+            _ => Err(anyhow::anyhow!("API error: {:?}", api_response.error)),
+        }
+    }
 }
 
 // Define the ErgoBoxDetails struct for parsing box data from the Ergo node
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ErgoBoxDetails {
     pub box_id: String,
     pub value: u64,
@@ -779,6 +1050,7 @@ pub struct ErgoBoxDetails {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct Token {
     pub token_id: String,
     pub amount: u64,
@@ -802,3 +1074,44 @@ impl SerializableIouNoteWithAge {
         self.amount_collected.saturating_sub(self.amount_redeemed)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn probe(url: &str, latency_ms: u64, digest: Option<&str>) -> TrackerProbe {
+        TrackerProbe {
+            url: url.to_string(),
+            latency: Duration::from_millis(latency_ms),
+            tracker_pubkey: Some("02aa".to_string()),
+            state_digest: digest.map(str::to_string),
+            state_height: Some(100),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn test_choose_fastest_agreeing_tracker() {
+        let mut down = probe("http://down", 1, None);
+        down.error = Some("connection refused".to_string());
+        let probes = vec![
+            down,
+            probe("http://slow", 300, Some("aa")),
+            probe("http://fast", 20, Some("aa")),
+        ];
+        assert!(!trackers_disagree(&probes));
+        assert_eq!(choose_tracker(&probes), Some(2));
+
+        // The fastest endpoint loses to the majority when it reports another state
+        let probes = vec![
+            probe("http://a", 300, Some("aa")),
+            probe("http://b", 10, Some("bb")),
+            probe("http://c", 200, Some("aa")),
+        ];
+        assert!(trackers_disagree(&probes));
+        assert_eq!(choose_tracker(&probes), Some(2));
+
+        assert_eq!(choose_tracker(&[]), None);
+    }
+}
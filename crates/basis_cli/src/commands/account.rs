@@ -1,14 +1,19 @@
 use crate::account::Account;
 use crate::account::AccountManager;
+use crate::output::OutputFormat;
 use anyhow::Result;
 use clap::Subcommand;
+use serde::Serialize;
 
 #[derive(Subcommand)]
 pub enum AccountCommands {
-    /// Create a new account
+    /// Create a new account derived from the HD wallet
     Create {
         /// Account name
         name: String,
+        /// Wallet address index to derive (default: next unused)
+        #[arg(long)]
+        index: Option<u32>,
     },
     /// List all accounts
     List,
@@ -31,18 +36,120 @@ pub enum AccountCommands {
         /// Private key in hex format
         private_key: String,
     },
+    /// Use an existing (e.g. Ergo wallet) mnemonic as the HD wallet
+    ImportMnemonic {
+        /// Mnemonic phrase, quoted
+        mnemonic: String,
+        /// Optional mnemonic passphrase
+        #[arg(long, default_value = "")]
+        passphrase: String,
+    },
+    /// Show the HD wallet mnemonic
+    ExportMnemonic,
+    /// List public keys and addresses derived from the HD wallet
+    Addresses {
+        /// Number of addresses to list
+        #[arg(long, default_value_t = 5)]
+        count: u32,
+    },
+}
+
+/// An account as printed by `account create`, `info` and `list`
+#[derive(Debug, Serialize)]
+pub struct AccountOutput {
+    pub name: String,
+    pub pubkey: String,
+    pub address: String,
+    /// EIP-3 path of wallet-derived accounts; `None` for imported keys
+    pub derivation_path: Option<String>,
+    pub created_at: u64,
+    pub current: bool,
+}
+
+impl AccountOutput {
+    fn new(account: &Account, account_manager: &AccountManager) -> Result<Self> {
+        Ok(Self {
+            name: account.name.clone(),
+            pubkey: account.get_pubkey_hex(),
+            address: account.get_address(account_manager.network)?,
+            derivation_path: account.derivation_path.clone(),
+            created_at: account.created_at,
+            current: account_manager
+                .get_current()
+                .is_some_and(|current| current.name == account.name),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CreatedAccountOutput {
+    #[serde(flatten)]
+    account: AccountOutput,
+    /// Mnemonic of the HD wallet generated for the first account
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mnemonic: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct PrivateKeyOutput {
+    name: String,
+    private_key: String,
+}
+
+#[derive(Debug, Serialize)]
+struct MnemonicOutput {
+    mnemonic: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct WalletAddressOutput {
+    derivation_path: String,
+    pubkey: String,
+    address: String,
 }
 
 pub async fn handle_account_command(
     cmd: AccountCommands,
     account_manager: &mut AccountManager,
+    output: OutputFormat,
 ) -> Result<()> {
     match cmd {
-        AccountCommands::Create { name } => {
-            let account = account_manager.create_account(&name)?;
-            println!("✅ Created account '{}'", name);
-            println!("  Public Key: {}", account.get_pubkey_hex());
-            println!("  Created at: {}", account.created_at);
+        AccountCommands::Create { name, index } => {
+            let new_wallet = !account_manager.has_wallet();
+            let account = account_manager.create_derived_account(&name, index)?;
+            let created = CreatedAccountOutput {
+                account: AccountOutput::new(&account, account_manager)?,
+                mnemonic: account_manager
+                    .wallet_mnemonic()
+                    .filter(|_| new_wallet)
+                    .map(str::to_string),
+            };
+            output.print(&created, |created| {
+                if let Some(mnemonic) = &created.mnemonic {
+                    println!("🔑 Created a new HD wallet. Write down its mnemonic:");
+                    println!("  {}", mnemonic);
+                    println!(
+                        "\n⚠️  WARNING: Anyone with this mnemonic controls every account derived from it."
+                    );
+                }
+                let account = &created.account;
+                println!("✅ Created account '{}'", account.name);
+                println!("  Public Key: {}", account.pubkey);
+                println!("  Address: {}", account.address);
+                if let Some(path) = &account.derivation_path {
+                    println!("  Derivation path: {}", path);
+                }
+                println!("  Created at: {}", account.created_at);
+            })?;
+        }
+        AccountCommands::List if output.is_json() => {
+            let mut accounts = account_manager
+                .list_accounts()
+                .into_iter()
+                .map(|account| AccountOutput::new(account, account_manager))
+                .collect::<Result<Vec<_>>>()?;
+            accounts.sort_by(|a, b| a.name.cmp(&b.name));
+            output.print(&accounts, |_| {})?;
         }
         AccountCommands::List => {
             let in_memory_accounts = account_manager.list_accounts();
@@ -60,9 +167,13 @@ pub async fn handle_account_command(
                             .unwrap_or(false);
 
                         let current_indicator = if is_current { " ⭐ (current)" } else { "" };
+                        let path = account_config
+                            .derivation_path
+                            .as_deref()
+                            .unwrap_or("imported");
                         println!(
-                            "  {}: {}{}",
-                            account_config.name, account_config.pubkey_hex, current_indicator
+                            "  {}: {} [{}]{}",
+                            account_config.name, account_config.pubkey_hex, path, current_indicator
                         );
                     }
                 }
@@ -87,30 +198,49 @@ pub async fn handle_account_command(
         }
         AccountCommands::Switch { name } => {
             account_manager.switch_account(&name)?;
-            println!("✅ Switched to account '{}'", name);
+            let account = account_manager
+                .get_account(&name)
+                .map(|account| AccountOutput::new(account, account_manager))
+                .transpose()?;
+            output.print(&account, |_| println!("✅ Switched to account '{}'", name))?;
         }
         AccountCommands::Info => {
-            if let Some(account) = account_manager.get_current() {
-                println!("⭐ Current Account: {}", account.name);
-                println!("  Public Key: {}", account.get_pubkey_hex());
-                println!("  Created at: {}", account.created_at);
-            } else {
-                println!("No current account selected.");
-                println!("Use 'basis-cli account create <name>' to create an account.");
-                println!("Use 'basis-cli account switch <name>' to select an existing account.");
-            }
+            let account = account_manager
+                .get_current()
+                .map(|account| AccountOutput::new(account, account_manager))
+                .transpose()?;
+            output.print(&account, |account| match account {
+                Some(account) => {
+                    println!("⭐ Current Account: {}", account.name);
+                    println!("  Public Key: {}", account.pubkey);
+                    println!("  Address: {}", account.address);
+                    if let Some(path) = &account.derivation_path {
+                        println!("  Derivation path: {}", path);
+                    }
+                    println!("  Created at: {}", account.created_at);
+                }
+                None => {
+                    println!("No current account selected.");
+                    println!("Use 'basis-cli account create <name>' to create an account.");
+                    println!("Use 'basis-cli account switch <name>' to select an existing account.");
+                }
+            })?;
         }
         AccountCommands::Export { name } => {
-            if let Some(account) = account_manager.get_account(&name) {
-                let private_key_hex = account.get_private_key_hex();
-                println!("Private key for account '{}':", name);
-                println!("{}", private_key_hex);
-                println!(
-                    "\n⚠️  WARNING: Keep this private key secure! Do not share it with anyone."
-                );
-            } else {
-                println!("Account '{}' not found in current session.", name);
-            }
+            let key = account_manager.get_account(&name).map(|account| PrivateKeyOutput {
+                name: name.clone(),
+                private_key: account.get_private_key_hex(),
+            });
+            output.print(&key, |key| match key {
+                Some(key) => {
+                    println!("Private key for account '{}':", key.name);
+                    println!("{}", key.private_key);
+                    println!(
+                        "\n⚠️  WARNING: Keep this private key secure! Do not share it with anyone."
+                    );
+                }
+                None => println!("Account '{}' not found in current session.", name),
+            })?;
         }
         AccountCommands::Import { name, private_key } => {
             if account_manager.get_account(&name).is_some() {
@@ -126,12 +256,64 @@ pub async fn handle_account_command(
                 .add_account(&name, &pubkey_hex, &private_key)?;
 
             // Add to in-memory accounts
+            let imported = AccountOutput::new(&account, account_manager)?;
             account_manager.accounts.insert(name.clone(), account);
 
-            println!("✅ Successfully imported account '{}'", name);
-            println!("Public Key: {}", pubkey_hex);
+            output.print(&imported, |imported| {
+                println!("✅ Successfully imported account '{}'", imported.name);
+                println!("Public Key: {}", imported.pubkey);
+            })?;
+        }
+        AccountCommands::ImportMnemonic {
+            mnemonic,
+            passphrase,
+        } => {
+            account_manager.import_mnemonic(&mnemonic, &passphrase)?;
+            let addresses = wallet_addresses(account_manager, 5)?;
+            output.print(&addresses, |addresses| {
+                println!("✅ Imported HD wallet");
+                print_addresses(addresses);
+                println!("\nUse 'basis-cli account create <name> --index <n>' to use one of these keys.");
+            })?;
+        }
+        AccountCommands::ExportMnemonic => {
+            let wallet = MnemonicOutput {
+                mnemonic: account_manager.wallet_mnemonic().map(str::to_string),
+            };
+            output.print(&wallet, |wallet| match &wallet.mnemonic {
+                Some(mnemonic) => {
+                    println!("HD wallet mnemonic:");
+                    println!("{}", mnemonic);
+                    println!(
+                        "\n⚠️  WARNING: Anyone with this mnemonic controls every account derived from it."
+                    );
+                }
+                None => println!("No HD wallet. Use 'basis-cli account create <name>' to create one."),
+            })?;
+        }
+        AccountCommands::Addresses { count } => {
+            output.print(&wallet_addresses(account_manager, count)?, |addresses| print_addresses(addresses))?
         }
     }
 
     Ok(())
 }
+
+fn wallet_addresses(account_manager: &AccountManager, count: u32) -> Result<Vec<WalletAddressOutput>> {
+    Ok(account_manager
+        .wallet_addresses(count)?
+        .into_iter()
+        .map(|(derivation_path, pubkey, address)| WalletAddressOutput {
+            derivation_path,
+            pubkey: hex::encode(pubkey),
+            address,
+        })
+        .collect())
+}
+
+fn print_addresses(addresses: &[WalletAddressOutput]) {
+    println!("HD wallet addresses:");
+    for address in addresses {
+        println!("  {}  {}  {}", address.derivation_path, address.pubkey, address.address);
+    }
+}
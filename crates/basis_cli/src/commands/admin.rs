@@ -0,0 +1,81 @@
+use crate::account::AccountManager;
+use crate::crypto::KeyPair;
+use crate::output::OutputFormat;
+use anyhow::{anyhow, Result};
+use basis_store::chaincash_import::import_chaincash;
+use basis_store::TrackerStateManager;
+use clap::Subcommand;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum AdminCommands {
+    /// Import outstanding notes from a chaincash-rs SQLite database into tracker storage
+    ///
+    /// Notes are re-signed with their issuers' keys: those of local accounts
+    /// and any given with --issuer-key. The tracker server must be stopped.
+    ImportChaincash {
+        /// Path to the chaincash-rs database
+        db: PathBuf,
+        /// Tracker note storage directory
+        #[arg(long, default_value = "crates/basis_server/data/notes")]
+        notes_path: PathBuf,
+        /// Tracker AVL tree storage directory
+        #[arg(long, default_value = "crates/basis_server/data/avl_tree")]
+        avl_tree_path: PathBuf,
+        /// Issuer private key in hex format (repeatable)
+        #[arg(long = "issuer-key")]
+        issuer_keys: Vec<String>,
+    },
+}
+
+pub async fn handle_admin_command(
+    cmd: AdminCommands,
+    account_manager: &AccountManager,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        AdminCommands::ImportChaincash {
+            db,
+            notes_path,
+            avl_tree_path,
+            issuer_keys,
+        } => {
+            let mut keys = HashMap::new();
+            for account in account_manager.list_accounts() {
+                keys.insert(
+                    account.keypair.get_public_key_bytes(),
+                    account.keypair.get_private_key_bytes(),
+                );
+            }
+            for key_hex in &issuer_keys {
+                let bytes: [u8; 32] = hex::decode(key_hex)?
+                    .try_into()
+                    .map_err(|_| anyhow!("Issuer key must be 32 bytes"))?;
+                let keypair = KeyPair::from_private_key_bytes(&bytes)?;
+                keys.insert(keypair.get_public_key_bytes(), bytes);
+            }
+
+            let mut tracker = TrackerStateManager::with_storage_paths(&notes_path, &avl_tree_path);
+            let report = import_chaincash(&db, &mut tracker, &keys)?;
+            tracker
+                .flush()
+                .map_err(|e| anyhow!("Failed to flush tracker storage: {:?}", e))?;
+
+            output.print(&report, |report| {
+                println!("✅ Imported chaincash-rs database {}", db.display());
+                println!("  Notes read: {}", report.notes_read);
+                println!("  Debts imported: {}", report.debts_imported);
+                println!("  Amount imported: {} nanoERG", report.amount_imported);
+                if !report.skipped.is_empty() {
+                    println!("\n⚠️  Skipped {}:", report.skipped.len());
+                    for skipped in &report.skipped {
+                        println!("  {}: {}", skipped.note_identifiers.join(", "), skipped.reason);
+                    }
+                }
+            })?;
+        }
+    }
+
+    Ok(())
+}
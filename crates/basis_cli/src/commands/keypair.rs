@@ -1,19 +1,21 @@
 use anyhow::Result;
 use clap::Args;
 use crate::crypto::KeyPair;
+use crate::output::OutputFormat;
+use serde_json::json;
 
 #[derive(Args)]
 #[command(name = "generate-keypair", about = "Generate a new secp256k1 keypair")]
 pub struct GenerateKeypairArgs {}
 
-pub async fn handle_generate_keypair_command(_args: GenerateKeypairArgs) -> Result<()> {
+pub async fn handle_generate_keypair_command(_args: GenerateKeypairArgs, output: OutputFormat) -> Result<()> {
     let keypair = KeyPair::new()?;
-    let public_key = keypair.get_public_key_bytes();
-    let private_key = keypair.get_private_key_bytes();
+    let public_key = hex::encode(keypair.get_public_key_bytes());
+    let private_key = hex::encode(keypair.get_private_key_bytes());
 
-    println!("Keypair generated successfully!");
-    println!("Public Key (hex): {}", hex::encode(public_key));
-    println!("Private Key (hex): {}", hex::encode(private_key));
-
-    Ok(())
+    output.print(&json!({ "public_key": public_key, "private_key": private_key }), |_| {
+        println!("Keypair generated successfully!");
+        println!("Public Key (hex): {}", public_key);
+        println!("Private Key (hex): {}", private_key);
+    })
 }
\ No newline at end of file
@@ -1,6 +1,10 @@
 pub mod account;
+pub mod admin;
 pub mod keypair;
+pub mod netting;
 pub mod note;
+pub mod profile;
+pub mod report;
 pub mod reserve;
 pub mod status;
 pub mod transaction;
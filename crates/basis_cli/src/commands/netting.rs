@@ -0,0 +1,42 @@
+use crate::api::TrackerClient;
+use crate::output::OutputFormat;
+use anyhow::Result;
+use clap::Args;
+
+#[derive(Args)]
+pub struct NettingArgs {
+    /// Only show settlements involving this key (hex public key)
+    #[arg(long)]
+    pubkey: Option<String>,
+    /// Most parties a settlement may involve (2 to 8; the tracker defaults to 4)
+    #[arg(long)]
+    max_cycle_length: Option<usize>,
+}
+
+pub async fn handle_netting_command(args: NettingArgs, client: &TrackerClient, output: OutputFormat) -> Result<()> {
+    let plan = client
+        .get_netting_suggestions(args.pubkey.as_deref(), args.max_cycle_length)
+        .await?;
+    output.print(&plan, print_plan)
+}
+
+fn print_plan(plan: &basis_store::NettingPlan) {
+    if plan.suggestions.is_empty() {
+        println!("No offsetting debts within {} parties", plan.max_cycle_length);
+    }
+    for (i, suggestion) in plan.suggestions.iter().enumerate() {
+        let mut parties: Vec<&str> = suggestion.cycle.iter().map(|key| &key[..16]).collect();
+        parties.push(parties[0]);
+        println!(
+            "{}. Forgive {} nanoERG along {} ({} notes cleared)",
+            i + 1,
+            suggestion.amount,
+            parties.join(" -> "),
+            suggestion.notes_cleared
+        );
+    }
+    println!(
+        "\nOutstanding debt: {} -> {} nanoERG, {} of {} notes cleared",
+        plan.total_outstanding_before, plan.total_outstanding_after, plan.notes_cleared, plan.notes
+    );
+}
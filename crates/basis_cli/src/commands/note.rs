@@ -3,7 +3,10 @@ use crate::api::{
     CompleteRedemptionRequest, CreateNoteRequest, KeyStatusResponse, RedeemRequest, TrackerClient,
 };
 use crate::demo_keys;
+use crate::output::OutputFormat;
+use crate::proof_cache::{CachedProof, Commitment, ProofCache, ProofFreshness};
 use anyhow::Result;
+use basis_core::{NoteUri, PubKey, SigningFormat};
 use clap::Subcommand;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -24,6 +27,16 @@ pub struct DemoNote {
     pub noteKey: String,
 }
 
+/// Note details the issuer encrypts for the recipient, stored by the tracker as an opaque payload
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct NoteMemo {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memo: Option<String>,
+    /// Free-form description of what the amount covers, e.g. "3 hours of tutoring"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_hint: Option<String>,
+}
+
 /// Signature component (a point and z scalar)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignatureComponent {
@@ -47,6 +60,12 @@ pub enum NoteCommands {
         /// Output file (default: stdout)
         #[arg(long)]
         output: Option<PathBuf>,
+        /// Memo for the recipient, encrypted so that only they can read it
+        #[arg(long)]
+        memo: Option<String>,
+        /// What the amount covers, encrypted along with the memo
+        #[arg(long)]
+        amount_hint: Option<String>,
     },
     /// List notes
     List {
@@ -66,6 +85,12 @@ pub enum NoteCommands {
         #[arg(long)]
         recipient: String,
     },
+    /// Decrypt the memo an issuer attached to a note owed to you
+    Decrypt {
+        /// Issuer public key (hex)
+        #[arg(long)]
+        issuer: String,
+    },
     /// Redeem a note
     Redeem {
         /// Issuer public key (hex)
@@ -75,26 +100,161 @@ pub enum NoteCommands {
         #[arg(long)]
         amount: u64,
     },
+    /// Sign a note offline and print it as a basis:note URI, e.g. to show as a QR code
+    Encode {
+        /// Recipient public key (hex)
+        #[arg(long)]
+        recipient: String,
+        /// Total debt owed to the recipient in nanoERG
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Check a basis:note URI received out-of-band
+    Decode {
+        /// The basis:note URI
+        uri: String,
+        /// Submit the note to the tracker once checked
+        #[arg(long, default_value = "false")]
+        submit: bool,
+    },
+    /// Fetch and cache the tracker proof of a note owed to you, and check it
+    /// against the latest on-chain commitment
+    Prove {
+        /// Issuer public key (hex)
+        #[arg(long)]
+        issuer: String,
+        /// Recipient public key (hex); defaults to the current account
+        #[arg(long)]
+        recipient: Option<String>,
+        /// Fetch a new proof even if one is cached
+        #[arg(long, default_value = "false")]
+        refresh: bool,
+        /// Ergo node to read the tracker box commitment from
+        #[arg(long, default_value = basis_store::ergo_scanner::DEFAULT_NODE_URL)]
+        node_url: String,
+        /// Ergo node API key
+        #[arg(long)]
+        node_api_key: Option<String>,
+    },
+}
+
+/// A note created with `note create`, with the issuer's key status around it
+#[derive(Debug, Serialize)]
+struct CreatedNoteOutput {
+    issuer_pubkey: String,
+    recipient_pubkey: String,
+    amount: u64,
+    timestamp: u64,
+    /// Whether a memo was encrypted for the recipient
+    memo: bool,
+    status_before: KeyStatusResponse,
+    status_after: KeyStatusResponse,
+}
+
+#[derive(Debug, Serialize)]
+struct DecryptedMemoOutput {
+    issuer_pubkey: String,
+    timestamp: u64,
+    #[serde(flatten)]
+    memo: NoteMemo,
+}
+
+#[derive(Debug, Serialize)]
+struct RedemptionOutput {
+    redemption_id: String,
+    amount: u64,
+    proof_available: bool,
+    /// Whether the tracker completed the redemption right away; otherwise it
+    /// does once the transaction is confirmed
+    completed: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct DecodedNoteOutput {
+    issuer_pubkey: String,
+    recipient_pubkey: String,
+    total_debt: u64,
+    timestamp: u64,
+    /// Tracker the signature is bound to, if any
+    tracker_id: Option<String>,
+    /// Whether the note is owed to the current account
+    current_account: bool,
+    submitted: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct ProofOutput {
+    #[serde(flatten)]
+    proof: CachedProof,
+    /// Where the proof is cached
+    path: PathBuf,
+    /// Whether the proof was fetched now rather than read from the cache
+    fetched: bool,
+    /// Latest on-chain commitment, if it could be read
+    commitment: Option<Commitment>,
+    freshness: Option<ProofFreshness>,
 }
 
 pub async fn handle_note_command(
     cmd: NoteCommands,
     account_manager: &AccountManager,
     client: &TrackerClient,
+    output: OutputFormat,
 ) -> Result<()> {
     match cmd {
-        NoteCommands::Create { recipient, amount, demo, output } => {
+        NoteCommands::Create { recipient, amount, demo, output: path, memo, amount_hint } => {
+            let memo = (memo.is_some() || amount_hint.is_some()).then_some(NoteMemo { memo, amount_hint });
             if demo {
+                if memo.is_some() {
+                    return Err(anyhow::anyhow!("--memo and --amount-hint are not supported in demo mode"));
+                }
                 // Demo mode: Alice → Bob with tracker signature
-                create_demo_note(amount, output).await?
+                create_demo_note(amount, path, output).await?
             } else {
                 // Normal mode: use CLI accounts
                 let recipient = recipient
                     .ok_or_else(|| anyhow::anyhow!("--recipient required in non-demo mode"))?;
                 
-                create_normal_note(account_manager, client, &recipient, amount).await?
+                create_normal_note(account_manager, client, &recipient, amount, memo.as_ref(), output).await?
             }
         }
+        NoteCommands::Decrypt { issuer } => {
+            let current_account = account_manager
+                .get_current()
+                .ok_or_else(|| anyhow::anyhow!("No current account selected"))?;
+
+            let payload = client
+                .get_note_payload(&issuer, &current_account.get_pubkey_hex())
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No encrypted memo for the current version of this note"))?;
+            let envelope = hex::decode(&payload.encrypted_payload)
+                .map_err(|e| anyhow::anyhow!("Invalid payload hex: {}", e))?;
+            let plaintext = basis_core::decrypt_note_payload(
+                &current_account.keypair.get_private_key_bytes(),
+                &parse_pubkey(&issuer, "issuer")?,
+                payload.timestamp,
+                &envelope,
+            )
+            .map_err(|e| anyhow::anyhow!("Cannot decrypt payload: {}", e))?;
+            let memo: NoteMemo = serde_json::from_slice(&plaintext)
+                .map_err(|e| anyhow::anyhow!("Payload is not a note memo: {}", e))?;
+
+            let decrypted = DecryptedMemoOutput {
+                issuer_pubkey: issuer,
+                timestamp: payload.timestamp,
+                memo,
+            };
+            output.print(&decrypted, |decrypted| {
+                println!("🔓 Note memo from {}", decrypted.issuer_pubkey);
+                println!("  Note timestamp: {}", decrypted.timestamp);
+                if let Some(memo) = &decrypted.memo.memo {
+                    println!("  Memo: {}", memo);
+                }
+                if let Some(amount_hint) = &decrypted.memo.amount_hint {
+                    println!("  Amount hint: {}", amount_hint);
+                }
+            })?;
+        }
         NoteCommands::List { issuer, recipient } => {
             let current_account = account_manager
                 .get_current()
@@ -104,7 +264,9 @@ pub async fn handle_note_command(
                 let notes = client
                     .get_issuer_notes(&current_account.get_pubkey_hex())
                     .await?;
-                if notes.is_empty() {
+                if output.is_json() {
+                    output.print(&notes, |_| {})?;
+                } else if notes.is_empty() {
                     println!("No notes found where you are the issuer");
                 } else {
                     println!("Notes where you are the issuer:");
@@ -123,7 +285,9 @@ pub async fn handle_note_command(
                 let notes = client
                     .get_recipient_notes(&current_account.get_pubkey_hex())
                     .await?;
-                if notes.is_empty() {
+                if output.is_json() {
+                    output.print(&notes, |_| {})?;
+                } else if notes.is_empty() {
                     println!("No notes found where you are the recipient");
                 } else {
                     println!("Notes where you are the recipient:");
@@ -138,6 +302,8 @@ pub async fn handle_note_command(
                         println!("    Created: {}", note.timestamp);
                     }
                 }
+            } else if output.is_json() {
+                return Err(anyhow::anyhow!("Please specify --issuer or --recipient"));
             } else {
                 println!("Please specify --issuer or --recipient");
             }
@@ -145,20 +311,21 @@ pub async fn handle_note_command(
         NoteCommands::Get { issuer, recipient } => {
             let note = client.get_note(&issuer, &recipient).await?;
 
-            if let Some(note) = note {
-                println!("Note found:");
-                println!("  Issuer: {}", note.issuer_pubkey);
-                println!("  Recipient: {}", note.recipient_pubkey);
-                println!("  Amount: {} nanoERG", note.amount_collected);
-                println!("  Redeemed: {} nanoERG", note.amount_redeemed);
-                println!(
-                    "  Outstanding: {} nanoERG",
-                    note.amount_collected - note.amount_redeemed
-                );
-                println!("  Created: {}", note.timestamp);
-            } else {
-                println!("Note not found");
-            }
+            output.print(&note, |note| match note {
+                Some(note) => {
+                    println!("Note found:");
+                    println!("  Issuer: {}", note.issuer_pubkey);
+                    println!("  Recipient: {}", note.recipient_pubkey);
+                    println!("  Amount: {} nanoERG", note.amount_collected);
+                    println!("  Redeemed: {} nanoERG", note.amount_redeemed);
+                    println!(
+                        "  Outstanding: {} nanoERG",
+                        note.amount_collected - note.amount_redeemed
+                    );
+                    println!("  Created: {}", note.timestamp);
+                }
+                None => println!("Note not found"),
+            })?;
         }
         NoteCommands::Redeem { issuer, amount } => {
             let current_account = account_manager
@@ -225,10 +392,10 @@ pub async fn handle_note_command(
             };
 
             let response = client.initiate_redemption(redeem_request).await?;
-            println!("✅ Redemption initiated");
-            println!("  Redemption ID: {}", response.redemption_id);
-            println!("  Amount: {} nanoERG", response.amount);
-            println!("  Proof available: {}", response.proof_available);
+            output.progress("✅ Redemption initiated");
+            output.progress(format!("  Redemption ID: {}", response.redemption_id));
+            output.progress(format!("  Amount: {} nanoERG", response.amount));
+            output.progress(format!("  Proof available: {}", response.proof_available));
 
             // Complete redemption
             let complete_request = CompleteRedemptionRequest {
@@ -237,16 +404,203 @@ pub async fn handle_note_command(
                 redeemed_amount: amount,
             };
 
-            client.complete_redemption(complete_request).await?;
-            println!("✅ Redemption completed");
+            let redemption = RedemptionOutput {
+                redemption_id: response.redemption_id,
+                amount: response.amount,
+                proof_available: response.proof_available,
+                completed: client.complete_redemption(complete_request).await?,
+            };
+            output.print(&redemption, |redemption| {
+                if redemption.completed {
+                    println!("✅ Redemption completed");
+                } else {
+                    println!("⏳ The tracker completes the redemption once the transaction is confirmed on chain");
+                }
+            })?;
+        }
+        NoteCommands::Encode { recipient, amount } => {
+            let current_account = account_manager
+                .get_current()
+                .ok_or_else(|| anyhow::anyhow!("No current account selected"))?;
+
+            let mut note = NoteUri {
+                issuer_pubkey: parse_pubkey(&current_account.get_pubkey_hex(), "issuer")?,
+                recipient_pubkey: parse_pubkey(&recipient, "recipient")?,
+                total_debt: amount,
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)?
+                    .as_millis() as u64,
+                signature: [0u8; 65],
+                signing_format: SigningFormat::V1,
+            };
+            note.signature = current_account.sign_message(&note.signing_message())?;
+
+            // The URI goes to stdout alone so it can be piped into a QR encoder
+            output.print(&serde_json::json!({ "uri": note.to_string() }), |_| println!("{}", note))?;
+        }
+        NoteCommands::Decode { uri, submit } => {
+            let note: NoteUri = uri.parse().map_err(|e| anyhow::anyhow!("Invalid note URI: {}", e))?;
+            note.verify().map_err(|e| anyhow::anyhow!("{}", e))?;
+
+            let recipient = hex::encode(note.recipient_pubkey);
+            let decoded = DecodedNoteOutput {
+                issuer_pubkey: hex::encode(note.issuer_pubkey),
+                recipient_pubkey: recipient.clone(),
+                total_debt: note.total_debt,
+                timestamp: note.timestamp,
+                tracker_id: note.signing_format.tracker_id().map(hex::encode),
+                current_account: account_manager
+                    .get_current()
+                    .is_some_and(|account| account.get_pubkey_hex() == recipient),
+                submitted: submit,
+            };
+            output.progress("✅ Note signature valid");
+
+            if submit {
+                client
+                    .create_note(CreateNoteRequest {
+                        issuer_pubkey: hex::encode(note.issuer_pubkey),
+                        recipient_pubkey: recipient,
+                        amount: note.total_debt,
+                        timestamp: note.timestamp,
+                        signature: hex::encode(note.signature),
+                        signing_version: note.signing_format.version(),
+                        tracker_id: note.signing_format.tracker_id().map(hex::encode),
+                        encrypted_payload: None,
+                    })
+                    .await?;
+            }
+
+            output.print(&decoded, |decoded| {
+                let is_current = if decoded.current_account { " (current account)" } else { "" };
+                println!("  Issuer: {}", decoded.issuer_pubkey);
+                println!("  Recipient: {}{}", decoded.recipient_pubkey, is_current);
+                println!("  Total debt: {} nanoERG", decoded.total_debt);
+                println!("  Timestamp: {}", decoded.timestamp);
+                if let Some(tracker_id) = &decoded.tracker_id {
+                    println!("  Bound to tracker: {}", tracker_id);
+                }
+                if decoded.submitted {
+                    println!("✅ Note submitted to the tracker");
+                }
+            })?;
+        }
+        NoteCommands::Prove { issuer, recipient, refresh, node_url, node_api_key } => {
+            let recipient = match recipient {
+                Some(recipient) => recipient,
+                None => account_manager
+                    .get_current()
+                    .ok_or_else(|| anyhow::anyhow!("No current account selected; pass --recipient"))?
+                    .get_pubkey_hex(),
+            };
+            parse_pubkey(&issuer, "issuer")?;
+            parse_pubkey(&recipient, "recipient")?;
+            let cache = ProofCache::new(account_manager.config_manager.proof_cache_dir());
+
+            let latest = match latest_commitment(client, &node_url, node_api_key.as_deref()).await {
+                Ok(latest) => Some(latest),
+                Err(e) => {
+                    output.progress(format!("⚠️  Could not read the latest on-chain commitment: {}", e));
+                    None
+                }
+            };
+
+            let (proof, fetched) = match cache.load(&issuer, &recipient)? {
+                Some(proof) if !refresh => {
+                    output.progress(format!("📁 Using cached proof {}", cache.path(&issuer, &recipient).display()));
+                    (proof, false)
+                }
+                _ => {
+                    let fetched = client.get_tracker_proof(&issuer, &recipient).await?;
+                    let proof = CachedProof {
+                        issuer_pubkey: issuer.clone(),
+                        recipient_pubkey: recipient.clone(),
+                        total_debt: fetched.total_debt,
+                        key: fetched.key,
+                        value: fetched.value,
+                        proof: fetched.proof,
+                        tracker_state_digest: fetched.tracker_state_digest,
+                        fetched_at: std::time::SystemTime::now()
+                            .duration_since(std::time::UNIX_EPOCH)?
+                            .as_millis() as u64,
+                        commitment_box_id: latest.as_ref().map(|latest| latest.box_id.clone()),
+                    };
+                    output.progress(format!("✅ Proof fetched and cached in {}", cache.store(&proof)?.display()));
+                    (proof, true)
+                }
+            };
+
+            let proved = ProofOutput {
+                path: cache.path(&issuer, &recipient),
+                fetched,
+                freshness: latest.as_ref().map(|latest| proof.freshness(latest)),
+                commitment: latest,
+                proof,
+            };
+            output.print(&proved, print_proof)?;
         }
     }
 
     Ok(())
 }
 
+fn print_proof(proved: &ProofOutput) {
+    let proof = &proved.proof;
+    println!("  Issuer: {}", proof.issuer_pubkey);
+    println!("  Recipient: {}", proof.recipient_pubkey);
+    println!("  Total debt: {} nanoERG", proof.total_debt);
+    println!("  Tracker state digest: {}", proof.tracker_state_digest);
+    println!("  Fetched at: {}", proof.fetched_at);
+
+    if let (Some(latest), Some(freshness)) = (&proved.commitment, proved.freshness) {
+        match freshness {
+            ProofFreshness::Committed => println!(
+                "✅ Proof matches the commitment in tracker box {} (height {})",
+                latest.box_id, latest.height
+            ),
+            ProofFreshness::Pending => println!(
+                "⏳ Proof is against state not committed yet; the latest commitment is in tracker box {} (height {})",
+                latest.box_id, latest.height
+            ),
+            ProofFreshness::Stale => println!(
+                "⚠️  Cached proof is stale: tracker box {} (height {}) committed state {} after the proof was generated; run with --refresh",
+                latest.box_id, latest.height, latest.digest
+            ),
+        }
+    }
+}
+
+/// Read the tracker's latest commitment from R5 of its tracker box
+///
+/// The tracker only names the box; its contents come from the node.
+async fn latest_commitment(client: &TrackerClient, node_url: &str, node_api_key: Option<&str>) -> Result<Commitment> {
+    let latest = client.get_latest_tracker_box_id().await?;
+    let tracker_box = client
+        .get_box_from_node(&latest.tracker_box_id, node_url, node_api_key)
+        .await?;
+    let r5 = tracker_box
+        .additional_registers
+        .get("R5")
+        .ok_or_else(|| anyhow::anyhow!("Tracker box {} has no R5 register", latest.tracker_box_id))?;
+    let digest = basis_store::commitment_verification::commitment_digest(r5)
+        .ok_or_else(|| anyhow::anyhow!("R5 of tracker box {} is not an AVL tree", latest.tracker_box_id))?;
+
+    Ok(Commitment {
+        box_id: latest.tracker_box_id,
+        height: latest.height,
+        digest: hex::encode(digest),
+    })
+}
+
+fn parse_pubkey(value: &str, name: &str) -> Result<PubKey> {
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| anyhow::anyhow!("Invalid {} public key: expected 33 hex-encoded bytes", name))
+}
+
 /// Create a demo note (Alice → Bob with tracker signature)
-async fn create_demo_note(amount: u64, output: Option<PathBuf>) -> Result<()> {
+async fn create_demo_note(amount: u64, path: Option<PathBuf>, output: OutputFormat) -> Result<()> {
     let alice = demo_keys::alice();
     let bob = demo_keys::bob();
     
@@ -326,11 +680,12 @@ async fn create_demo_note(amount: u64, output: Option<PathBuf>) -> Result<()> {
     
     let note_json = serde_json::to_string_pretty(&note)?;
     
-    // Output JSON
-    if let Some(path) = output {
-        fs::write(&path, &note_json)?;
+    // Output JSON, which also goes to stdout in JSON mode
+    if let Some(path) = &path {
+        fs::write(path, &note_json)?;
         eprintln!("✓ Note saved to: {}", path.display());
-    } else {
+    }
+    if path.is_none() || output.is_json() {
         println!("{}", note_json);
     }
     
@@ -356,6 +711,8 @@ async fn create_normal_note(
     client: &TrackerClient,
     recipient: &str,
     amount: u64,
+    memo: Option<&NoteMemo>,
+    output: OutputFormat,
 ) -> Result<()> {
     let current_account = account_manager
         .get_current()
@@ -367,9 +724,7 @@ async fn create_normal_note(
         .as_millis() as u64;
 
     // Get reserve status before note creation
-    println!("📊 Reserve Status Before Note Creation:");
     let status_before = client.get_reserve_status(&issuer_pubkey).await?;
-    print_reserve_status(&status_before);
 
     // Create signing message: key || totalDebt || timestamp (48 bytes)
     // where key = blake2b256(ownerKey || receiverKey)
@@ -391,33 +746,64 @@ async fn create_normal_note(
     let signature = current_account.sign_message(&message)?;
     let signature_hex = hex::encode(signature);
 
+    // The memo is bound to this note version and readable by the recipient only
+    let encrypted_payload = match memo {
+        Some(memo) => Some(hex::encode(
+            basis_core::encrypt_note_payload(
+                &current_account.keypair.get_private_key_bytes(),
+                &parse_pubkey(recipient, "recipient")?,
+                timestamp,
+                &serde_json::to_vec(memo)?,
+            )
+            .map_err(|e| anyhow::anyhow!("Cannot encrypt memo: {}", e))?,
+        )),
+        None => None,
+    };
+
     let request = CreateNoteRequest {
         issuer_pubkey: issuer_pubkey.clone(),
         recipient_pubkey: recipient.to_string(),
         amount,
         timestamp,
         signature: signature_hex,
+        signing_version: 1,
+        tracker_id: None,
+        encrypted_payload,
     };
 
     client.create_note(request).await?;
 
     // Get reserve status after note creation
-    println!("\n📊 Reserve Status After Note Creation:");
-    let status_after = client.get_reserve_status(&issuer_pubkey).await?;
-    print_reserve_status(&status_after);
-
-    println!("\n✅ Note created successfully");
-    println!("📝 Note Details:");
-    println!("  Issuer: {}", issuer_pubkey);
-    println!("  Recipient: {}", recipient);
-    println!(
-        "  Amount: {} nanoERG ({:.6} ERG)",
+    let created = CreatedNoteOutput {
+        status_after: client.get_reserve_status(&issuer_pubkey).await?,
+        status_before,
+        issuer_pubkey,
+        recipient_pubkey: recipient.to_string(),
         amount,
-        amount as f64 / 1_000_000_000.0
-    );
-    println!("  Timestamp: {}", timestamp);
-    
-    Ok(())
+        timestamp,
+        memo: memo.is_some(),
+    };
+
+    output.print(&created, |created| {
+        println!("📊 Reserve Status Before Note Creation:");
+        print_reserve_status(&created.status_before);
+        println!("\n📊 Reserve Status After Note Creation:");
+        print_reserve_status(&created.status_after);
+
+        println!("\n✅ Note created successfully");
+        println!("📝 Note Details:");
+        println!("  Issuer: {}", created.issuer_pubkey);
+        println!("  Recipient: {}", created.recipient_pubkey);
+        println!(
+            "  Amount: {} nanoERG ({:.6} ERG)",
+            created.amount,
+            created.amount as f64 / 1_000_000_000.0
+        );
+        println!("  Timestamp: {}", created.timestamp);
+        if created.memo {
+            println!("  Memo: encrypted for the recipient");
+        }
+    })
 }
 
 fn print_reserve_status(status: &KeyStatusResponse) {
@@ -0,0 +1,122 @@
+use crate::config::{ConfigManager, ProfileConfig};
+use crate::output::OutputFormat;
+use anyhow::Result;
+use clap::Subcommand;
+use serde::Serialize;
+
+#[derive(Subcommand)]
+pub enum ProfileCommands {
+    /// Add a profile combining an account, tracker and network
+    Add {
+        /// Profile name
+        name: String,
+        /// Account the profile signs with
+        #[arg(long)]
+        account: Option<String>,
+        /// Tracker URL; repeat or separate with commas for several endpoints
+        #[arg(long, value_delimiter = ',')]
+        server_url: Vec<String>,
+        /// Ergo network (mainnet or testnet)
+        #[arg(long)]
+        network: Option<basis_store::Network>,
+    },
+    /// List all profiles
+    List,
+    /// Use a profile when `--profile` is not given
+    Switch {
+        /// Profile name
+        name: String,
+    },
+    /// Remove a profile, keeping its account
+    Remove {
+        /// Profile name
+        name: String,
+    },
+}
+
+/// A profile as printed by `profile add` and `list`
+#[derive(Debug, Serialize)]
+struct ProfileOutput {
+    name: String,
+    #[serde(flatten)]
+    profile: ProfileConfig,
+    current: bool,
+}
+
+fn profile_output(config_manager: &ConfigManager, name: &str) -> Option<ProfileOutput> {
+    let profile = config_manager.get_config().profiles.get(name)?;
+    Some(ProfileOutput {
+        name: name.to_string(),
+        profile: profile.clone(),
+        current: config_manager.active_profile().map(|(active, _)| active) == Some(name),
+    })
+}
+
+pub async fn handle_profile_command(
+    cmd: ProfileCommands,
+    config_manager: &mut ConfigManager,
+    output: OutputFormat,
+) -> Result<()> {
+    match cmd {
+        ProfileCommands::Add {
+            name,
+            account,
+            server_url,
+            network,
+        } => {
+            config_manager.add_profile(
+                &name,
+                ProfileConfig {
+                    account,
+                    server_urls: server_url,
+                    network,
+                },
+            )?;
+            output.print(&profile_output(config_manager, &name), |_| {
+                println!("✅ Added profile '{}'", name);
+                println!("Use 'basis-cli profile switch {}' or '--profile {}' to use it.", name, name);
+            })?;
+        }
+        ProfileCommands::List => {
+            let profiles: Vec<ProfileOutput> = config_manager
+                .get_config()
+                .profiles
+                .keys()
+                .filter_map(|name| profile_output(config_manager, name))
+                .collect();
+            output.print(&profiles, |profiles| {
+                if profiles.is_empty() {
+                    println!("No profiles found. Use 'basis-cli profile add <name>' to create one.");
+                }
+                for ProfileOutput { name, profile, current } in profiles {
+                    let current_indicator = if *current { " ⭐ (current)" } else { "" };
+                    println!("  {}{}", name, current_indicator);
+                    println!("    Account: {}", profile.account.as_deref().unwrap_or("(current account)"));
+                    if profile.server_urls.is_empty() {
+                        println!("    Server: (default)");
+                    } else {
+                        println!("    Server: {}", profile.server_urls.join(", "));
+                    }
+                    match profile.network {
+                        Some(network) => println!("    Network: {}", network.name()),
+                        None => println!("    Network: (default)"),
+                    }
+                }
+            })?;
+        }
+        ProfileCommands::Switch { name } => {
+            config_manager.switch_profile(&name)?;
+            output.print(&profile_output(config_manager, &name), |_| {
+                println!("✅ Switched to profile '{}'", name)
+            })?;
+        }
+        ProfileCommands::Remove { name } => {
+            config_manager.remove_profile(&name)?;
+            output.print(&serde_json::json!({ "removed": name }), |_| {
+                println!("✅ Removed profile '{}'", name)
+            })?;
+        }
+    }
+
+    Ok(())
+}
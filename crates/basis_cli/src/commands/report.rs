@@ -0,0 +1,126 @@
+use crate::api::TrackerClient;
+use crate::output::OutputFormat;
+use anyhow::{anyhow, Result};
+use basis_core::MILLIS_PER_DAY;
+use basis_store::reports::parse_date;
+use clap::{Args, Subcommand, ValueEnum};
+use serde_json::json;
+use std::path::PathBuf;
+
+#[derive(Subcommand)]
+pub enum ReportCommands {
+    /// Export notes, oldest first
+    Notes(ReportArgs),
+    /// Export reserves, ordered by last update
+    Reserves(ReportArgs),
+    /// Export an account's note history as a double-entry journal, oldest first
+    Ledger(LedgerArgs),
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum ReportFormat {
+    Csv,
+    Json,
+}
+
+#[derive(Args)]
+pub struct ReportArgs {
+    /// Output format
+    #[arg(long, value_enum, default_value = "csv")]
+    format: ReportFormat,
+    /// Only include this issuer (hex public key; the owner for reserves)
+    #[arg(long)]
+    issuer: Option<String>,
+    /// First day to include, as YYYY-MM-DD (UTC)
+    #[arg(long)]
+    from: Option<String>,
+    /// Last day to include, as YYYY-MM-DD (UTC)
+    #[arg(long)]
+    to: Option<String>,
+    /// File to write the report to instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum LedgerFormat {
+    Hledger,
+    Beancount,
+}
+
+#[derive(Args)]
+pub struct LedgerArgs {
+    /// Account to journal the notes of, as issuer or recipient (hex public key)
+    #[arg(long)]
+    account: String,
+    /// Journal format
+    #[arg(long, value_enum, default_value = "hledger")]
+    format: LedgerFormat,
+    /// First day to include, as YYYY-MM-DD (UTC)
+    #[arg(long)]
+    from: Option<String>,
+    /// Last day to include, as YYYY-MM-DD (UTC)
+    #[arg(long)]
+    to: Option<String>,
+    /// File to write the journal to instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+fn parse_day(date: &str) -> Result<u64> {
+    parse_date(date).ok_or_else(|| anyhow!("Invalid date {}, expected YYYY-MM-DD", date))
+}
+
+type ReportRequest = (&'static str, String, Option<String>, Option<String>, Option<PathBuf>);
+
+fn report_args(name: &'static str, args: ReportArgs, query: &mut Vec<(&str, String)>) -> ReportRequest {
+    let report = match args.format {
+        ReportFormat::Csv => format!("{}.csv", name),
+        ReportFormat::Json => format!("{}.json", name),
+    };
+    if let Some(issuer) = args.issuer {
+        query.push(("issuer", issuer));
+    }
+    (name, report, args.from, args.to, args.output)
+}
+
+/// Reports already are CSV, JSON or journal files; with `--output json` only
+/// writing one to a file prints a JSON summary instead of the report itself
+pub async fn handle_report_command(cmd: ReportCommands, client: &TrackerClient, output: OutputFormat) -> Result<()> {
+    let mut query = Vec::new();
+    let (name, report, from, to, path) = match cmd {
+        ReportCommands::Notes(args) => report_args("notes", args, &mut query),
+        ReportCommands::Reserves(args) => report_args("reserves", args, &mut query),
+        ReportCommands::Ledger(args) => {
+            let report = match args.format {
+                LedgerFormat::Hledger => "ledger.journal",
+                LedgerFormat::Beancount => "ledger.beancount",
+            };
+            query.push(("account", args.account));
+            ("ledger", report.to_string(), args.from, args.to, args.output)
+        }
+    };
+    if let Some(from) = &from {
+        query.push(("from_timestamp", parse_day(from)?.to_string()));
+    }
+    if let Some(to) = &to {
+        // The whole of the last day is included
+        query.push(("to_timestamp", (parse_day(to)? + MILLIS_PER_DAY - 1).to_string()));
+    }
+
+    match &path {
+        Some(path) => {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            let written = client.download_report(&report, &query, &mut file).await?;
+            std::io::Write::flush(&mut file)?;
+            output.print(&json!({ "report": name, "path": path, "bytes": written }), |_| {
+                println!("✅ Wrote {} report to {} ({} bytes)", name, path.display(), written)
+            })?;
+        }
+        None => {
+            client.download_report(&report, &query, &mut std::io::stdout().lock()).await?;
+        }
+    }
+
+    Ok(())
+}
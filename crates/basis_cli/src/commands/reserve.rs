@@ -1,7 +1,9 @@
 use crate::account::AccountManager;
-use crate::api::{CreateReserveRequest, TrackerClient};
+use crate::api::{CreateReserveRequest, KeyStatusResponse, TrackerClient};
+use crate::output::OutputFormat;
 use anyhow::Result;
 use clap::Subcommand;
+use serde::Serialize;
 
 #[derive(Subcommand)]
 pub enum ReserveCommands {
@@ -31,12 +33,122 @@ pub enum ReserveCommands {
         #[arg(long)]
         issuer: Option<String>,
     },
+    /// Watch collateralization, alerting when it crosses a threshold
+    Watch {
+        /// Issuer public key (hex)
+        #[arg(long)]
+        issuer: Option<String>,
+
+        /// Seconds between polls of the key status
+        #[arg(long, default_value = "30")]
+        interval: u64,
+
+        /// Collateralization ratios to alert at, comma-separated
+        #[arg(long, value_delimiter = ',', default_value = "1.5,1.0")]
+        threshold: Vec<f64>,
+
+        /// Shell command to run when a threshold is crossed (repeatable)
+        ///
+        /// The command gets BASIS_ISSUER, BASIS_RATIO, BASIS_THRESHOLD,
+        /// BASIS_DIRECTION (below or above), BASIS_COLLATERAL and
+        /// BASIS_TOTAL_DEBT in its environment.
+        #[arg(long)]
+        exec: Vec<String>,
+
+        /// Stop after this many polls
+        #[arg(long)]
+        count: Option<u64>,
+    },
+}
+
+/// Direction in which the collateralization ratio crossed a threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Crossing {
+    Below,
+    Above,
+}
+
+impl Crossing {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Crossing::Below => "below",
+            Crossing::Above => "above",
+        }
+    }
+}
+
+/// Thresholds the ratio crossed since the previous poll, highest first
+///
+/// The first poll has no previous ratio; it reports the thresholds the ratio
+/// starts out below.
+fn crossed_thresholds(previous: Option<f64>, current: f64, thresholds: &[f64]) -> Vec<(f64, Crossing)> {
+    let mut crossed: Vec<(f64, Crossing)> = thresholds
+        .iter()
+        .filter_map(|&threshold| {
+            let was_below = previous.is_some_and(|previous| previous < threshold);
+            match (was_below, current < threshold) {
+                (false, true) => Some((threshold, Crossing::Below)),
+                (true, false) => Some((threshold, Crossing::Above)),
+                _ => None,
+            }
+        })
+        .collect();
+    crossed.sort_by(|a, b| b.0.total_cmp(&a.0));
+    crossed
+}
+
+/// Run a `--exec` hook for a crossed threshold, reporting but not failing on errors
+async fn run_hook(command: &str, status: &KeyStatusResponse, threshold: f64, crossing: Crossing) {
+    let result = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .env("BASIS_ISSUER", &status.issuer_pubkey)
+        .env("BASIS_RATIO", status.collateralization_ratio.to_string())
+        .env("BASIS_THRESHOLD", threshold.to_string())
+        .env("BASIS_DIRECTION", crossing.as_str())
+        .env("BASIS_COLLATERAL", status.collateral.to_string())
+        .env("BASIS_TOTAL_DEBT", status.total_debt.to_string())
+        .status()
+        .await;
+    match result {
+        Ok(exit) if exit.success() => {}
+        Ok(exit) => eprintln!("⚠️  Hook `{}` exited with {}", command, exit),
+        Err(e) => eprintln!("⚠️  Failed to run hook `{}`: {}", command, e),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CollateralizationOutput {
+    issuer_pubkey: String,
+    collateralization_ratio: f64,
+    status: &'static str,
+}
+
+/// Line printed by `reserve watch` on each change of the ratio or threshold crossing
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WatchOutput<'a> {
+    Status {
+        timestamp: u64,
+        #[serde(flatten)]
+        status: &'a KeyStatusResponse,
+        collateralization: &'static str,
+    },
+    Alert {
+        timestamp: u64,
+        issuer_pubkey: &'a str,
+        collateralization_ratio: f64,
+        threshold: f64,
+        direction: Crossing,
+    },
 }
 
 pub async fn handle_reserve_command(
     cmd: ReserveCommands,
     account_manager: &AccountManager,
     client: &TrackerClient,
+    output: OutputFormat,
 ) -> Result<()> {
     match cmd {
         ReserveCommands::Create { nft_id, owner, amount } => {
@@ -54,10 +166,10 @@ pub async fn handle_reserve_command(
                 return Err(anyhow::anyhow!("Owner public key must be 33 bytes (66 hex characters), got {} characters", owner_pubkey.len()));
             }
 
-            println!("Creating reserve with:");
-            println!("  NFT ID: {}", nft_id);
-            println!("  Owner: {}", owner_pubkey);
-            println!("  Amount: {} nanoERG", amount);
+            output.progress("Creating reserve with:");
+            output.progress(format!("  NFT ID: {}", nft_id));
+            output.progress(format!("  Owner: {}", owner_pubkey));
+            output.progress(format!("  Amount: {} nanoERG", amount));
 
             // Create the reserve creation request
             let request = CreateReserveRequest {
@@ -69,38 +181,40 @@ pub async fn handle_reserve_command(
             // Call the API to create the reserve payload
             let response = client.create_reserve(request).await?;
 
-            println!("\n✅ Reserve creation payload created successfully!");
-            println!("The following payload can be used with the Ergo wallet API:");
-            println!();
-
-            // Print the response in a readable format
-            println!("Requests:");
-            for (i, req) in response.requests.iter().enumerate() {
-                println!("  Request {}: {{", i + 1);
-                println!("    address: \"{}\"", req.address);
-                println!("    value: {}", req.value);
-                println!("    assets: [");
-                for asset in &req.assets {
-                    println!("      {{ token_id: \"{}\", amount: {} }},", asset.token_id, asset.amount);
-                }
-                println!("    ]");
-                println!("    registers: {{");
-                for (key, value) in &req.registers {
-                    println!("      \"{}\": \"{}\",", key, value);
+            output.print(&response, |response| {
+                println!("\n✅ Reserve creation payload created successfully!");
+                println!("The following payload can be used with the Ergo wallet API:");
+                println!();
+
+                // Print the response in a readable format
+                println!("Requests:");
+                for (i, req) in response.requests.iter().enumerate() {
+                    println!("  Request {}: {{", i + 1);
+                    println!("    address: \"{}\"", req.address);
+                    println!("    value: {}", req.value);
+                    println!("    assets: [");
+                    for asset in &req.assets {
+                        println!("      {{ token_id: \"{}\", amount: {} }},", asset.token_id, asset.amount);
+                    }
+                    println!("    ]");
+                    println!("    registers: {{");
+                    for (key, value) in &req.registers {
+                        println!("      \"{}\": \"{}\",", key, value);
+                    }
+                    println!("    }}");
+                    println!("  }}");
                 }
-                println!("    }}");
-                println!("  }}");
-            }
-            println!();
-            println!("Fee: {} nanoERG", response.fee);
-            println!("Change address: {}", response.change_address);
-
-            println!();
-            println!("💡 To create the reserve, submit this payload to your Ergo wallet using:");
-            println!("   curl -X POST http://your-ergo-node:9053/wallet/payment/send \\");
-            println!("        -H \"Content-Type: application/json\" \\");
-            println!("        -H \"api_key: your-api-key\" \\");
-            println!("        -d '...' # (replace with the full payload above)");
+                println!();
+                println!("Fee: {} nanoERG", response.fee);
+                println!("Change address: {}", response.change_address);
+
+                println!();
+                println!("💡 To create the reserve, submit this payload to your Ergo wallet using:");
+                println!("   curl -X POST http://your-ergo-node:9053/wallet/payment/send \\");
+                println!("        -H \"Content-Type: application/json\" \\");
+                println!("        -H \"api_key: your-api-key\" \\");
+                println!("        -d '...' # (replace with the full payload above)");
+            })?;
         }
         ReserveCommands::Status { issuer } => {
             let pubkey = if let Some(issuer) = issuer {
@@ -113,23 +227,25 @@ pub async fn handle_reserve_command(
 
             let status = client.get_reserve_status(&pubkey).await?;
 
-            println!("Reserve Status for {}:", status.issuer_pubkey);
-            println!("  Total Debt: {} nanoERG", status.total_debt);
-            println!("  Collateral: {} nanoERG", status.collateral);
-            println!(
-                "  Collateralization Ratio: {:.2}",
-                status.collateralization_ratio
-            );
-            println!("  Note Count: {}", status.note_count);
-            println!("  Last Updated: {}", status.last_updated);
-
-            // Calculate ERG values
-            let debt_erg = status.total_debt as f64 / 1_000_000_000.0;
-            let collateral_erg = status.collateral as f64 / 1_000_000_000.0;
-
-            println!("\nIn ERG:");
-            println!("  Total Debt: {:.6} ERG", debt_erg);
-            println!("  Collateral: {:.6} ERG", collateral_erg);
+            output.print(&status, |status| {
+                println!("Reserve Status for {}:", status.issuer_pubkey);
+                println!("  Total Debt: {} nanoERG", status.total_debt);
+                println!("  Collateral: {} nanoERG", status.collateral);
+                println!(
+                    "  Collateralization Ratio: {:.2}",
+                    status.collateralization_ratio
+                );
+                println!("  Note Count: {}", status.note_count);
+                println!("  Last Updated: {}", status.last_updated);
+
+                // Calculate ERG values
+                let debt_erg = status.total_debt as f64 / 1_000_000_000.0;
+                let collateral_erg = status.collateral as f64 / 1_000_000_000.0;
+
+                println!("\nIn ERG:");
+                println!("  Total Debt: {:.6} ERG", debt_erg);
+                println!("  Collateral: {:.6} ERG", collateral_erg);
+            })?;
         }
         ReserveCommands::Collateralization { issuer } => {
             let pubkey = if let Some(issuer) = issuer {
@@ -141,18 +257,104 @@ pub async fn handle_reserve_command(
             };
 
             let status = client.get_reserve_status(&pubkey).await?;
+            let collateralization = CollateralizationOutput {
+                status: get_collateralization_status(status.collateralization_ratio),
+                issuer_pubkey: status.issuer_pubkey,
+                collateralization_ratio: status.collateralization_ratio,
+            };
+
+            output.print(&collateralization, |collateralization| {
+                println!("Collateralization for {}:", collateralization.issuer_pubkey);
+                println!("  Ratio: {:.4}", collateralization.collateralization_ratio);
+                println!("  Status: {}", collateralization.status);
 
-            println!("Collateralization for {}:", status.issuer_pubkey);
-            println!("  Ratio: {:.4}", status.collateralization_ratio);
-            println!(
-                "  Status: {}",
-                get_collateralization_status(status.collateralization_ratio)
-            );
-
-            if status.collateralization_ratio < 1.0 {
-                println!("⚠️  WARNING: Under-collateralized!");
-            } else if status.collateralization_ratio < 1.5 {
-                println!("⚠️  WARNING: Low collateralization");
+                if collateralization.collateralization_ratio < 1.0 {
+                    println!("⚠️  WARNING: Under-collateralized!");
+                } else if collateralization.collateralization_ratio < 1.5 {
+                    println!("⚠️  WARNING: Low collateralization");
+                }
+            })?;
+        }
+        ReserveCommands::Watch {
+            issuer,
+            interval,
+            threshold,
+            exec,
+            count,
+        } => {
+            let pubkey = if let Some(issuer) = issuer {
+                issuer
+            } else {
+                account_manager.get_current_pubkey_hex().ok_or_else(|| {
+                    anyhow::anyhow!("No current account selected and no issuer specified")
+                })?
+            };
+            if threshold.iter().any(|threshold| !threshold.is_finite() || *threshold < 0.0) {
+                return Err(anyhow::anyhow!("Thresholds must be non-negative ratios"));
+            }
+
+            output.progress(format!(
+                "Watching collateralization of {} every {}s (Ctrl+C to stop)",
+                pubkey, interval
+            ));
+            let mut previous: Option<f64> = None;
+            let mut polls = 0u64;
+            loop {
+                match client.get_reserve_status(&pubkey).await {
+                    Ok(status) => {
+                        let ratio = status.collateralization_ratio;
+                        let timestamp = now_millis();
+                        if previous != Some(ratio) {
+                            let line = WatchOutput::Status {
+                                timestamp,
+                                status: &status,
+                                collateralization: get_collateralization_status(ratio),
+                            };
+                            output.print_line(&line, |_| {
+                                println!(
+                                    "[{}] Ratio {:.4} ({}), collateral {} nanoERG, debt {} nanoERG",
+                                    basis_store::reports::format_utc(timestamp),
+                                    ratio,
+                                    get_collateralization_status(ratio),
+                                    status.collateral,
+                                    status.total_debt
+                                )
+                            })?;
+                        }
+                        for (threshold, crossing) in crossed_thresholds(previous, ratio, &threshold) {
+                            let alert = WatchOutput::Alert {
+                                timestamp,
+                                issuer_pubkey: &status.issuer_pubkey,
+                                collateralization_ratio: ratio,
+                                threshold,
+                                direction: crossing,
+                            };
+                            output.print_line(&alert, |_| match crossing {
+                                // The terminal bell sounds the alert
+                                Crossing::Below => println!(
+                                    "\x07🚨 ALERT: collateralization {:.4} fell below {}",
+                                    ratio, threshold
+                                ),
+                                Crossing::Above => println!(
+                                    "✅ Collateralization {:.4} is back above {}",
+                                    ratio, threshold
+                                ),
+                            })?;
+                            for command in &exec {
+                                run_hook(command, &status, threshold, crossing).await;
+                            }
+                        }
+                        previous = Some(ratio);
+                    }
+                    // The tracker may be restarting; keep watching
+                    Err(e) => eprintln!("⚠️  Failed to get key status: {}", e),
+                }
+
+                polls += 1;
+                if count.is_some_and(|count| polls >= count) {
+                    break;
+                }
+                tokio::time::sleep(std::time::Duration::from_secs(interval)).await;
             }
         }
     }
@@ -169,3 +371,35 @@ fn get_collateralization_status(ratio: f64) -> &'static str {
         _ => "EXCELLENT",
     }
 }
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crossed_thresholds() {
+        let thresholds = [1.0, 1.5];
+        // A first poll alerts on the thresholds the ratio is already below
+        assert_eq!(crossed_thresholds(None, 2.0, &thresholds), vec![]);
+        assert_eq!(
+            crossed_thresholds(None, 0.8, &thresholds),
+            vec![(1.5, Crossing::Below), (1.0, Crossing::Below)]
+        );
+
+        assert_eq!(crossed_thresholds(Some(2.0), 1.2, &thresholds), vec![(1.5, Crossing::Below)]);
+        // Staying on the same side alerts nothing
+        assert_eq!(crossed_thresholds(Some(1.2), 1.1, &thresholds), vec![]);
+        assert_eq!(crossed_thresholds(Some(1.2), 0.9, &thresholds), vec![(1.0, Crossing::Below)]);
+        assert_eq!(
+            crossed_thresholds(Some(0.9), 1.6, &thresholds),
+            vec![(1.5, Crossing::Above), (1.0, Crossing::Above)]
+        );
+    }
+}
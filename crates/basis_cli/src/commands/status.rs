@@ -1,22 +1,80 @@
-use crate::api::TrackerClient;
+use crate::api::{TrackerClient, TrackerEvent};
+use crate::output::OutputFormat;
 use anyhow::Result;
+use serde::Serialize;
 
-pub async fn handle_status_command(client: &TrackerClient) -> Result<()> {
-    // Check server health
-    let is_healthy = client.health_check().await?;
+/// One tracker endpoint as printed by `status`
+#[derive(Debug, Serialize)]
+struct EndpointOutput {
+    url: String,
+    /// The endpoint the CLI uses
+    selected: bool,
+    latency_ms: u128,
+    state_digest: Option<String>,
+    state_height: Option<u64>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct StatusOutput {
+    /// Every configured endpoint; empty with a single one
+    endpoints: Vec<EndpointOutput>,
+    healthy: bool,
+    events: Vec<TrackerEvent>,
+}
+
+pub async fn handle_status_command(client: &TrackerClient, output: OutputFormat) -> Result<()> {
+    // Compare every configured endpoint of the tracker
+    let mut endpoints = Vec::new();
+    if client.urls().len() > 1 {
+        for probe in client.probe_trackers().await {
+            endpoints.push(EndpointOutput {
+                selected: probe.url == client.base_url(),
+                url: probe.url,
+                latency_ms: probe.latency.as_millis(),
+                state_digest: probe.state_digest,
+                state_height: probe.state_height,
+                error: probe.error,
+            });
+        }
+    }
 
-    if is_healthy {
+    // Check server health, then get recent events
+    let healthy = client.health_check().await?;
+    let events = if healthy { client.get_recent_events().await? } else { Vec::new() };
+
+    output.print(&StatusOutput { endpoints, healthy, events }, print_status)
+}
+
+fn print_status(status: &StatusOutput) {
+    if !status.endpoints.is_empty() {
+        println!("Tracker endpoints:");
+        for endpoint in &status.endpoints {
+            let marker = if endpoint.selected { "*" } else { " " };
+            match &endpoint.error {
+                None => println!(
+                    " {} {} ({} ms) digest {} at height {}",
+                    marker,
+                    endpoint.url,
+                    endpoint.latency_ms,
+                    endpoint.state_digest.as_deref().unwrap_or("none"),
+                    endpoint.state_height.map(|h| h.to_string()).unwrap_or_else(|| "none".to_string())
+                ),
+                Some(error) => println!(" {} {} unavailable: {}", marker, endpoint.url, error),
+            }
+        }
+        println!();
+    }
+
+    if status.healthy {
         println!("✅ Server is healthy");
     } else {
         println!("❌ Server is not responding");
-        return Ok(());
+        return;
     }
 
-    // Get recent events
-    let events = client.get_recent_events().await?;
-
-    println!("\nRecent Events (last {}):", events.len());
-    for event in events {
+    println!("\nRecent Events (last {}):", status.events.len());
+    for event in status.events.iter().cloned() {
         let event_summary = match event.event_type.as_str() {
             "NoteUpdated" => {
                 if let (Some(issuer), Some(recipient), Some(amount)) =
@@ -87,6 +145,13 @@ pub async fn handle_status_command(client: &TrackerClient) -> Result<()> {
                     "State commitment".to_string()
                 }
             }
+            "CommitmentDivergence" => {
+                if let Some(height) = event.height {
+                    format!("Tracker commitment diverged from local state at height {}", height)
+                } else {
+                    "Tracker commitment diverged from local state".to_string()
+                }
+            }
             "CollateralAlert" => "Collateral alert".to_string(),
             _ => {
                 format!("{} event", event.event_type)
@@ -103,6 +168,4 @@ pub async fn handle_status_command(client: &TrackerClient) -> Result<()> {
                 .unwrap_or_default()
         );
     }
-
-    Ok(())
 }
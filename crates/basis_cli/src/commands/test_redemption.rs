@@ -1,5 +1,8 @@
 use crate::api::TrackerClient;
+use crate::crypto::pubkey_to_address;
+use crate::output::OutputFormat;
 use anyhow::Result;
+use basis_store::Network;
 use clap::Subcommand;
 use serde_json::json;
 use std::fs;
@@ -28,6 +31,8 @@ pub enum TestCommands {
 pub async fn handle_test_command(
     cmd: TestCommands,
     client: &TrackerClient,
+    network: Network,
+    output: OutputFormat,
 ) -> Result<()> {
     match cmd {
         TestCommands::TestRedemption {
@@ -35,25 +40,27 @@ pub async fn handle_test_command(
             amount,
             poll_interval,
         } => {
-            test_redemption_transaction(client, output_file, amount, poll_interval).await
+            test_redemption_transaction(client, network, output_file, amount, poll_interval, output).await
         }
     }
 }
 
 async fn test_redemption_transaction(
     client: &TrackerClient,
+    network: Network,
     output_file: Option<String>,
     amount: Option<u64>,
     poll_interval: u64,
+    output: OutputFormat,
 ) -> Result<()> {
-    println!("🚀 Starting redemption transaction test...");
-    println!("📡 Connecting to server: {}", "configured server URL");
+    output.progress("🚀 Starting redemption transaction test...");
+    output.progress(format!("📡 Connecting to server: {}", "configured server URL"));
     
     // Verify server health
     match client.health_check().await {
         Ok(healthy) => {
             if healthy {
-                println!("✅ Server connection verified");
+                output.progress("✅ Server connection verified");
             } else {
                 return Err(anyhow::anyhow!("❌ Server health check failed"));
             }
@@ -63,27 +70,27 @@ async fn test_redemption_transaction(
         }
     }
     
-    println!("🔄 Starting note polling loop (checking every {} seconds)...", poll_interval);
+    output.progress(format!("🔄 Starting note polling loop (checking every {} seconds)...", poll_interval));
     
     loop {
-        println!("🔍 Polling for notes...");
+        output.progress("🔍 Polling for notes...");
         
         // Get all notes from the server
         let notes = match client.get_all_notes().await {
             Ok(notes) => notes,
             Err(e) => {
                 eprintln!("⚠️  Failed to retrieve notes: {}", e);
-                println!("⏳ Retrying in {} seconds...", poll_interval);
+                output.progress(format!("⏳ Retrying in {} seconds...", poll_interval));
                 thread::sleep(Duration::from_secs(poll_interval));
                 continue;
             }
         };
 
-        println!("📊 Retrieved {} notes", notes.len());
+        output.progress(format!("📊 Retrieved {} notes", notes.len()));
 
         // Find a note with sufficient collateral
-        if let Some((note, reserve_info)) = find_note_with_sufficient_collateral(client, &notes, amount).await {
-            println!("✅ Found suitable note with sufficient collateral!");
+        if let Some((note, reserve_info)) = find_note_with_sufficient_collateral(client, &notes, amount, output).await {
+            output.progress("✅ Found suitable note with sufficient collateral!");
             
             // Determine redemption amount
             let redemption_amount = amount.unwrap_or_else(|| {
@@ -92,40 +99,41 @@ async fn test_redemption_transaction(
             });
             
             if redemption_amount == 0 {
-                println!("⚠️  Redemption amount is 0, skipping this note");
-                println!("⏳ Continuing to poll for notes...");
+                output.progress("⚠️  Redemption amount is 0, skipping this note");
+                output.progress("⏳ Continuing to poll for notes...");
                 thread::sleep(Duration::from_secs(poll_interval));
                 continue;
             }
             
-            println!("💰 Redemption amount: {} nanoERG", redemption_amount);
+            output.progress(format!("💰 Redemption amount: {} nanoERG", redemption_amount));
             
             // Prepare redemption data
-            println!("🔧 Preparing redemption data...");
+            output.progress("🔧 Preparing redemption data...");
             let redemption_data = match client.prepare_redemption(&note.issuer_pubkey, &note.recipient_pubkey, redemption_amount).await {
                 Ok(data) => data,
                 Err(e) => {
                     eprintln!("⚠️  Failed to prepare redemption: {}", e);
-                    println!("⏳ Continuing to poll for notes...");
+                    output.progress("⏳ Continuing to poll for notes...");
                     thread::sleep(Duration::from_secs(poll_interval));
                     continue;
                 }
             };
             
-            println!("✅ Redemption data prepared successfully");
-            println!("   - AVL proof: {} bytes", redemption_data.avl_proof.len());
-            println!("   - Tracker signature: {} bytes", redemption_data.tracker_signature.len());
-            println!("   - Tracker state digest: {}", redemption_data.tracker_state_digest);
-            println!("   - Block height: {}", redemption_data.block_height);
+            output.progress("✅ Redemption data prepared successfully");
+            output.progress(format!("   - AVL proof: {} bytes", redemption_data.avl_proof.len()));
+            output.progress(format!("   - Tracker signature: {} bytes", redemption_data.tracker_signature.len()));
+            output.progress(format!("   - Tracker state digest: {}", redemption_data.tracker_state_digest));
+            output.progress(format!("   - Block height: {}", redemption_data.block_height));
             
             // Generate unsigned transaction JSON
-            println!("📝 Generating unsigned transaction...");
+            output.progress("📝 Generating unsigned transaction...");
             let transaction_json = generate_unsigned_transaction(
                 &note.issuer_pubkey,
                 &note.recipient_pubkey,
                 redemption_amount,
                 &redemption_data,
-                &reserve_info
+                &reserve_info,
+                network,
             );
             
             // Determine output file name
@@ -138,21 +146,27 @@ async fn test_redemption_transaction(
             };
             
             // Write transaction to file
-            println!("💾 Writing transaction to file: {}", filename);
+            output.progress(format!("💾 Writing transaction to file: {}", filename));
             fs::write(&filename, serde_json::to_string_pretty(&transaction_json)?)?;
             
-            println!("🎉 Redemption transaction test completed successfully!");
-            println!("📋 Transaction details:");
-            println!("   - Issuer: {}", note.issuer_pubkey);
-            println!("   - Recipient: {}", note.recipient_pubkey);
-            println!("   - Redemption amount: {} nanoERG", redemption_amount);
-            println!("   - Transaction saved to: {}", filename);
-            println!("   - Source Ergo node: 159.89.116.15:11088");
-            
-            return Ok(());
+            let result = json!({
+                "issuer_pubkey": note.issuer_pubkey,
+                "recipient_pubkey": note.recipient_pubkey,
+                "amount": redemption_amount,
+                "output_file": filename,
+            });
+            return output.print(&result, |_| {
+                println!("🎉 Redemption transaction test completed successfully!");
+                println!("📋 Transaction details:");
+                println!("   - Issuer: {}", note.issuer_pubkey);
+                println!("   - Recipient: {}", note.recipient_pubkey);
+                println!("   - Redemption amount: {} nanoERG", redemption_amount);
+                println!("   - Transaction saved to: {}", filename);
+                println!("   - Source Ergo node: 159.89.116.15:11088");
+            });
         } else {
-            println!("⚠️  No suitable notes found with sufficient collateral");
-            println!("⏳ Continuing to poll for notes...");
+            output.progress("⚠️  No suitable notes found with sufficient collateral");
+            output.progress("⏳ Continuing to poll for notes...");
             thread::sleep(Duration::from_secs(poll_interval));
         }
     }
@@ -162,6 +176,7 @@ async fn find_note_with_sufficient_collateral(
     client: &TrackerClient,
     notes: &[crate::api::SerializableIouNoteWithAge],
     requested_amount: Option<u64>,
+    output: OutputFormat,
 ) -> Option<(crate::api::SerializableIouNoteWithAge, basis_store::ExtendedReserveInfo)> {
     for note in notes {
         // Get the issuer's reserve information
@@ -179,11 +194,11 @@ async fn find_note_with_sufficient_collateral(
 
             // Check if the note has sufficient collateral
             if outstanding_debt > 0 && available_collateral >= check_amount {
-                println!("🎯 Found suitable note:");
-                println!("   - Issuer: {}", note.issuer_pubkey);
-                println!("   - Recipient: {}", note.recipient_pubkey);
-                println!("   - Outstanding debt: {} nanoERG", outstanding_debt);
-                println!("   - Available collateral: {} nanoERG", available_collateral);
+                output.progress("🎯 Found suitable note:");
+                output.progress(format!("   - Issuer: {}", note.issuer_pubkey));
+                output.progress(format!("   - Recipient: {}", note.recipient_pubkey));
+                output.progress(format!("   - Outstanding debt: {} nanoERG", outstanding_debt));
+                output.progress(format!("   - Available collateral: {} nanoERG", available_collateral));
 
                 return Some((note.clone(), reserve_info.clone()));
             }
@@ -199,9 +214,10 @@ fn generate_unsigned_transaction(
     amount: u64,
     redemption_data: &crate::api::RedemptionPreparationResponse,
     reserve_info: &basis_store::ExtendedReserveInfo,
+    network: Network,
 ) -> serde_json::Value {
     // Convert public keys to proper P2PK addresses
-    let recipient_address = pubkey_to_address(recipient_pubkey)
+    let recipient_address = pubkey_to_address(recipient_pubkey, network)
         .unwrap_or_else(|_| format!("invalid_recipient_{}", &recipient_pubkey[..16]));
     
     // Calculate remaining collateral after redemption
@@ -253,27 +269,3 @@ fn generate_unsigned_transaction(
         }
     })
 }
-
-// Helper function to convert public key to a P2PK address using ergo-lib
-fn pubkey_to_address(pubkey_hex: &str) -> Result<String> {
-    use ergo_lib::ergotree_ir::address::{Address, NetworkPrefix};
-    use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
-    use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
-    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
-
-    let pubkey_bytes = hex::decode(pubkey_hex)
-        .map_err(|e| anyhow::anyhow!("Invalid public key hex: {}", e))?;
-
-    if pubkey_bytes.len() != 33 {
-        return Err(anyhow::anyhow!("Public key must be 33 bytes"));
-    }
-
-    let ec_point = EcPoint::sigma_parse_bytes(&pubkey_bytes)
-        .map_err(|e| anyhow::anyhow!("Invalid public key format: {}", e))?;
-
-    let prove_dlog = ProveDlog::new(ec_point);
-    let address = Address::P2Pk(prove_dlog);
-
-    let encoder = ergo_lib::ergotree_ir::address::AddressEncoder::new(NetworkPrefix::Mainnet);
-    Ok(encoder.address_to_str(&address))
-}
\ No newline at end of file
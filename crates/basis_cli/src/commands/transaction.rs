@@ -1,6 +1,10 @@
 use crate::api::TrackerClient;
+use crate::crypto::pubkey_to_address;
+use crate::output::OutputFormat;
 use anyhow::Result;
+use basis_store::Network;
 use clap::Subcommand;
+use serde::Serialize;
 use serde_json::json;
 use std::collections::HashMap;
 use std::fs;
@@ -27,10 +31,30 @@ pub enum TransactionCommands {
     },
 }
 
+/// A redemption transaction generated by `transaction generate-redemption`
+#[derive(Debug, Serialize)]
+struct RedemptionTransactionOutput {
+    /// Request for the node's `/wallet/transaction/send`
+    transaction: serde_json::Value,
+    /// File the transaction was written to
+    output_file: Option<String>,
+    issuer_pubkey: String,
+    recipient_pubkey: String,
+    amount: u64,
+    total_debt: u64,
+    already_redeemed: u64,
+    reserve_box_id: String,
+    tracker_box_id: String,
+    fee: u64,
+    emergency: bool,
+    first_redemption: bool,
+}
+
 pub async fn handle_transaction_command(
     cmd: TransactionCommands,
     client: &TrackerClient,
     account_manager: &crate::account::AccountManager,
+    output: OutputFormat,
 ) -> Result<()> {
     match cmd {
         TransactionCommands::GenerateRedemption {
@@ -40,11 +64,12 @@ pub async fn handle_transaction_command(
             output_file,
             emergency,
         } => {
-            generate_redemption_transaction(client, account_manager, &issuer_pubkey, &recipient_pubkey, amount, output_file, emergency).await
+            generate_redemption_transaction(client, account_manager, &issuer_pubkey, &recipient_pubkey, amount, output_file, emergency, output).await
         }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn generate_redemption_transaction(
     client: &TrackerClient,
     account_manager: &crate::account::AccountManager,
@@ -53,6 +78,7 @@ async fn generate_redemption_transaction(
     amount: u64,
     output_file: Option<String>,
     emergency: bool,
+    output: OutputFormat,
 ) -> Result<()> {
     // Validate public keys
     if hex::decode(issuer_pubkey).map_err(|e| anyhow::anyhow!("Invalid issuer public key: {}", e))?.len() != 33 {
@@ -63,7 +89,7 @@ async fn generate_redemption_transaction(
         return Err(anyhow::anyhow!("Recipient public key must be 33 bytes (66 hex characters)"));
     }
 
-    println!("🔍 Retrieving note information...");
+    output.progress("🔍 Retrieving note information...");
     let note = client.get_note(issuer_pubkey, recipient_pubkey).await?
         .ok_or_else(|| anyhow::anyhow!("Note not found for issuer {} and recipient {}", issuer_pubkey, recipient_pubkey))?;
 
@@ -73,7 +99,7 @@ async fn generate_redemption_transaction(
             note.outstanding_debt(), amount));
     }
 
-    println!("🔍 Retrieving issuer's reserve box...");
+    output.progress("🔍 Retrieving issuer's reserve box...");
     let reserves_response = client.get_reserves_by_issuer(issuer_pubkey).await?;
     let reserve_box = reserves_response.first()
         .ok_or_else(|| anyhow::anyhow!("No reserve box found for issuer {}", issuer_pubkey))?;
@@ -87,11 +113,11 @@ async fn generate_redemption_transaction(
     let reserve_box_id = &reserve_box.box_id;
     let tracker_nft_id = &reserve_box.base_info.tracker_nft_id;
 
-    println!("🔍 Retrieving latest tracker box...");
+    output.progress("🔍 Retrieving latest tracker box...");
     let tracker_box_response = client.get_latest_tracker_box_id().await;
     let tracker_box_id = match tracker_box_response {
         Ok(response) => {
-            println!("✅ Found tracker box: {}", &response.tracker_box_id[..16]);
+            output.progress(format!("✅ Found tracker box: {}", &response.tracker_box_id[..16]));
             response.tracker_box_id
         },
         Err(e) => {
@@ -102,11 +128,11 @@ async fn generate_redemption_transaction(
         }
     };
 
-    println!("🔗 Converting public keys to addresses...");
-    let recipient_address = pubkey_to_address(recipient_pubkey)?;
+    output.progress("🔗 Converting public keys to addresses...");
+    let recipient_address = pubkey_to_address(recipient_pubkey, account_manager.network)?;
 
     // Get tracker lookup proof for context var #8 from server
-    println!("🔍 Retrieving tracker lookup proof from server...");
+    output.progress("🔍 Retrieving tracker lookup proof from server...");
     let tracker_proof = client.get_tracker_proof(issuer_pubkey, recipient_pubkey).await?;
     let total_debt = tracker_proof.total_debt;
     let tracker_lookup_proof = hex::decode(&tracker_proof.proof)
@@ -119,9 +145,20 @@ async fn generate_redemption_transaction(
     let r5_hex = hex::encode(&r5_bytes);
 
     // Get the reserve contract P2S address from the server configuration
-    println!("🔍 Retrieving reserve contract P2S address from server configuration...");
+    output.progress("🔍 Retrieving reserve contract P2S address from server configuration...");
     let reserve_contract_p2s = client.get_basis_reserve_contract_p2s().await
         .map_err(|e| anyhow::anyhow!("Failed to retrieve reserve contract P2S address from server: {}", e))?;
+    // A tracker on the other network would have the transaction paying addresses nobody can spend
+    let tracker_network = Network::of_address(&reserve_contract_p2s)?;
+    if tracker_network != account_manager.network {
+        return Err(anyhow::anyhow!(
+            "Tracker runs on {}, but the CLI is set to {}; pass --network {} or use a {} tracker",
+            tracker_network,
+            account_manager.network,
+            tracker_network,
+            account_manager.network
+        ));
+    }
 
     // Calculate remaining collateral after redemption
     let remaining_collateral = reserve_box.base_info.collateral_amount - amount;
@@ -131,7 +168,7 @@ async fn generate_redemption_transaction(
     let is_first_redemption = note.amount_redeemed == 0;
     let (reserve_lookup_proof, reserve_insert_proof) = if is_first_redemption {
         // For first redemption, no lookup proof needed, but we still need insert proof
-        println!("🔍 First redemption - generating reserve insert proof...");
+        output.progress("🔍 First redemption - generating reserve insert proof...");
         // For first redemption, insert proof can be generated (lookup proof is omitted)
         let reserve_proof = client.get_reserve_proof(issuer_pubkey, recipient_pubkey).await
             .map_err(|e| anyhow::anyhow!("Failed to get reserve proof for first redemption: {}", e))?;
@@ -140,11 +177,11 @@ async fn generate_redemption_transaction(
         (None, insert_proof)
     } else {
         // For subsequent redemptions, get reserve lookup proof and insert proof from server
-        println!("🔍 Retrieving reserve proofs from server...");
+        output.progress("🔍 Retrieving reserve proofs from server...");
         let reserve_proof = client.get_reserve_proof(issuer_pubkey, recipient_pubkey).await
             .map_err(|e| anyhow::anyhow!("Failed to get reserve proof for subsequent redemption: {}", e))?;
-        println!("✅ Got reserve proof: already_redeemed={} nanoERG, is_first={}",
-            reserve_proof.already_redeemed, reserve_proof.is_first_redemption);
+        output.progress(format!("✅ Got reserve proof: already_redeemed={} nanoERG, is_first={}",
+            reserve_proof.already_redeemed, reserve_proof.is_first_redemption));
         // Decode the hex-encoded proofs
         let lookup_proof = if let Some(proof_hex) = &reserve_proof.proof {
             Some(hex::decode(proof_hex)
@@ -158,24 +195,24 @@ async fn generate_redemption_transaction(
     };
 
     // Verify tracker box exists on Ergo node
-    println!("🔍 Verifying tracker box on Ergo node...");
+    output.progress("🔍 Verifying tracker box on Ergo node...");
     client.get_box_from_node(&tracker_box_id, "http://159.89.116.15:11088", Some("hello")).await
         .map_err(|e| anyhow::anyhow!("Failed to retrieve tracker box {} from Ergo node: {}. Cannot generate redemption transaction.", tracker_box_id, e))?;
 
     // Retrieve the actual reserve box from the Ergo node
-    println!("🔍 Retrieving reserve box from Ergo node...");
+    output.progress("🔍 Retrieving reserve box from Ergo node...");
     let reserve_box_details = client.get_box_from_node(reserve_box_id, "http://159.89.116.15:11088", Some("hello")).await
         .map_err(|e| anyhow::anyhow!("Failed to retrieve reserve box from Ergo node: {}", e))?;
 
     // Serialize boxes to hex-encoded bytes for Ergo node API
     // The Ergo node expects inputsRaw and dataInputsRaw to contain hex-encoded box IDs
     // The node will fetch the full box details internally
-    println!("📦 Preparing box IDs for transaction...");
+    output.progress("📦 Preparing box IDs for transaction...");
     let tracker_box_bytes = tracker_box_id.clone();
     let reserve_box_bytes = reserve_box_details.box_id.clone();
 
     // Get issuer signature from CLI wallet
-    println!("🔑 Signing redemption with issuer key...");
+    output.progress("🔑 Signing redemption with issuer key...");
     let current_account = account_manager.get_current()
         .ok_or_else(|| anyhow::anyhow!("No current account selected"))?;
 
@@ -286,43 +323,56 @@ async fn generate_redemption_transaction(
 
     let json_string = serde_json::to_string_pretty(&transaction_json)?;
 
-    match output_file {
-        Some(file_path) => {
-            fs::write(&file_path, &json_string)?;
-            println!("✅ Transaction JSON written to: {}", file_path);
-        }
-        None => {
-            println!("{}", json_string);
-        }
+    if let Some(file_path) = &output_file {
+        fs::write(file_path, &json_string)?;
     }
 
-    println!("✅ Redemption transaction generated successfully!");
-    println!("📋 Transaction details:");
-    println!("   Issuer: {}", issuer_pubkey);
-    println!("   Recipient: {}", recipient_pubkey);
-    println!("   Redemption amount: {} nanoERG", amount);
-    println!("   Total debt: {} nanoERG", total_debt);
-    println!("   Already redeemed: {} nanoERG", note.amount_redeemed);
-    println!("   Reserve box ID: {}", reserve_box_id);
-    println!("   Tracker box ID: {}", tracker_box_id);
-    println!("   Transaction fee: {} nanoERG", transaction_fee);
-    println!("   Emergency redemption: {}", emergency);
-    println!("   First redemption: {}", is_first_redemption);
-    println!("📝 Context Extension Variables:");
-    println!("   #0 (action): 0x00 (redemption)");
-    println!("   #1 (receiver): {}", recipient_pubkey);
-    println!("   #2 (reserveSig): {} bytes", issuer_signature.len());
-    println!("   #3 (totalDebt): {}", total_debt);
-    println!("   #5 (insertProof): {} bytes", insert_proof.len());
-    println!("   #6 (trackerSig): {} bytes", tracker_signature.len());
-    if let Some(ref proof) = reserve_lookup_proof {
-        println!("   #7 (reserveLookupProof): {} bytes", proof.as_slice().len());
-    } else {
-        println!("   #7 (reserveLookupProof): omitted (first redemption)");
-    }
-    println!("   #8 (trackerLookupProof): {} bytes", tracker_lookup_proof.len());
+    let generated = RedemptionTransactionOutput {
+        transaction: transaction_json,
+        output_file,
+        issuer_pubkey: issuer_pubkey.to_string(),
+        recipient_pubkey: recipient_pubkey.to_string(),
+        amount,
+        total_debt,
+        already_redeemed: note.amount_redeemed,
+        reserve_box_id: reserve_box_id.clone(),
+        tracker_box_id,
+        fee: transaction_fee,
+        emergency,
+        first_redemption: is_first_redemption,
+    };
+    output.print(&generated, |generated| {
+        match &generated.output_file {
+            Some(file_path) => println!("✅ Transaction JSON written to: {}", file_path),
+            None => println!("{}", json_string),
+        }
 
-    Ok(())
+        println!("✅ Redemption transaction generated successfully!");
+        println!("📋 Transaction details:");
+        println!("   Issuer: {}", generated.issuer_pubkey);
+        println!("   Recipient: {}", generated.recipient_pubkey);
+        println!("   Redemption amount: {} nanoERG", generated.amount);
+        println!("   Total debt: {} nanoERG", generated.total_debt);
+        println!("   Already redeemed: {} nanoERG", generated.already_redeemed);
+        println!("   Reserve box ID: {}", generated.reserve_box_id);
+        println!("   Tracker box ID: {}", generated.tracker_box_id);
+        println!("   Transaction fee: {} nanoERG", generated.fee);
+        println!("   Emergency redemption: {}", generated.emergency);
+        println!("   First redemption: {}", generated.first_redemption);
+        println!("📝 Context Extension Variables:");
+        println!("   #0 (action): 0x00 (redemption)");
+        println!("   #1 (receiver): {}", generated.recipient_pubkey);
+        println!("   #2 (reserveSig): {} bytes", issuer_signature.len());
+        println!("   #3 (totalDebt): {}", generated.total_debt);
+        println!("   #5 (insertProof): {} bytes", insert_proof.len());
+        println!("   #6 (trackerSig): {} bytes", tracker_signature.len());
+        if let Some(ref proof) = reserve_lookup_proof {
+            println!("   #7 (reserveLookupProof): {} bytes", proof.as_slice().len());
+        } else {
+            println!("   #7 (reserveLookupProof): omitted (first redemption)");
+        }
+        println!("   #8 (trackerLookupProof): {} bytes", tracker_lookup_proof.len());
+    })
 }
 
 /// Helper function to build serialized SAvlTree from tracker state digest
@@ -360,30 +410,3 @@ fn blake2b256_hash(data: &[u8]) -> [u8; 32] {
     hasher.update(data);
     hasher.finalize().to_vec().try_into().unwrap()
 }
-
-// Helper function to convert public key to a P2PK address using ergo-lib
-fn pubkey_to_address(pubkey_hex: &str) -> Result<String> {
-    use ergo_lib::ergotree_ir::address::{Address, NetworkPrefix};
-    use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
-    use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
-    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
-
-    let pubkey_bytes = hex::decode(pubkey_hex)
-        .map_err(|e| anyhow::anyhow!("Invalid public key hex: {}", e))?;
-
-    if pubkey_bytes.len() != 33 {
-        return Err(anyhow::anyhow!("Public key must be 33 bytes"));
-    }
-
-    // Parse public key as EcPoint (compressed secp256k1 point)
-    let ec_point = EcPoint::sigma_parse_bytes(&pubkey_bytes)
-        .map_err(|e| anyhow::anyhow!("Invalid public key format: {}", e))?;
-
-    // Create P2PK address from EcPoint
-    let prove_dlog = ProveDlog::new(ec_point);
-    let address = Address::P2Pk(prove_dlog);
-
-    // Encode address as base58 string (using mainnet prefix by default)
-    let encoder = ergo_lib::ergotree_ir::address::AddressEncoder::new(NetworkPrefix::Mainnet);
-    Ok(encoder.address_to_str(&address))
-}
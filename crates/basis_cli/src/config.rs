@@ -1,6 +1,7 @@
 use anyhow::Result;
+use basis_store::Network;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
 use std::path::PathBuf;
 
@@ -9,6 +10,39 @@ pub struct CliConfig {
     pub current_account: Option<String>,
     pub accounts: HashMap<String, AccountConfig>,
     pub server_url: String,
+    /// Ergo network addresses are encoded for
+    #[serde(default)]
+    pub network: Network,
+    /// HD wallet accounts are derived from, if one was created or imported
+    #[serde(default)]
+    pub wallet: Option<WalletConfig>,
+    /// Profile used when `--profile` is not given
+    #[serde(default)]
+    pub current_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, ProfileConfig>,
+}
+
+/// Named account, tracker and network to use together, for users running
+/// several issuer identities or connecting to several trackers
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProfileConfig {
+    /// Account the profile signs with; the global current account if `None`
+    #[serde(default)]
+    pub account: Option<String>,
+    /// Tracker endpoints; `server_url` if empty
+    #[serde(default)]
+    pub server_urls: Vec<String>,
+    /// `network` if `None`
+    #[serde(default)]
+    pub network: Option<Network>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WalletConfig {
+    pub mnemonic: String,
+    #[serde(default)]
+    pub passphrase: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,12 +51,17 @@ pub struct AccountConfig {
     pub pubkey_hex: String,
     pub private_key_hex: String,
     pub created_at: u64,
+    /// EIP-3 path the key was derived along; `None` for imported keys
+    #[serde(default)]
+    pub derivation_path: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConfigManager {
     config_path: PathBuf,
     config: CliConfig,
+    /// Profile in use for this run
+    profile: Option<String>,
 }
 
 impl ConfigManager {
@@ -47,21 +86,110 @@ impl ConfigManager {
                 current_account: None,
                 accounts: HashMap::new(),
                 server_url: "http://127.0.0.1:3048".to_string(),
+                network: Network::default(),
+                wallet: None,
+                current_profile: None,
+                profiles: BTreeMap::new(),
             }
         };
 
         Ok(Self {
             config_path,
             config,
+            profile: None,
         })
     }
 
+    /// Use the named profile for this run, or the current one if `None`
+    pub fn use_profile(&mut self, name: Option<&str>) -> Result<()> {
+        let name = name.or(self.config.current_profile.as_deref());
+        if let Some(name) = name {
+            if !self.config.profiles.contains_key(name) {
+                return Err(anyhow::anyhow!("Profile '{}' not found", name));
+            }
+        }
+        self.profile = name.map(str::to_string);
+        Ok(())
+    }
+
+    pub fn active_profile(&self) -> Option<(&str, &ProfileConfig)> {
+        let name = self.profile.as_deref()?;
+        self.config
+            .profiles
+            .get(name)
+            .map(|profile| (name, profile))
+    }
+
+    /// Tracker endpoints of the active profile, or the configured `server_url`
+    pub fn server_urls(&self) -> Vec<String> {
+        match self.active_profile() {
+            Some((_, profile)) if !profile.server_urls.is_empty() => profile.server_urls.clone(),
+            _ => self
+                .config
+                .server_url
+                .split(',')
+                .map(|url| url.trim().to_string())
+                .collect(),
+        }
+    }
+
+    /// Network of the active profile, or the configured one
+    pub fn network(&self) -> Network {
+        self.active_profile()
+            .and_then(|(_, profile)| profile.network)
+            .unwrap_or(self.config.network)
+    }
+
+    pub fn add_profile(&mut self, name: &str, profile: ProfileConfig) -> Result<()> {
+        if self.config.profiles.contains_key(name) {
+            return Err(anyhow::anyhow!("Profile '{}' already exists", name));
+        }
+        if let Some(account) = &profile.account {
+            if !self.config.accounts.contains_key(account) {
+                return Err(anyhow::anyhow!("Account '{}' not found", account));
+            }
+        }
+        self.config.profiles.insert(name.to_string(), profile);
+        self.save()
+    }
+
+    /// Make the named profile the one used when `--profile` is not given
+    pub fn switch_profile(&mut self, name: &str) -> Result<()> {
+        if !self.config.profiles.contains_key(name) {
+            return Err(anyhow::anyhow!("Profile '{}' not found", name));
+        }
+        self.config.current_profile = Some(name.to_string());
+        self.profile = Some(name.to_string());
+        self.save()
+    }
+
+    pub fn remove_profile(&mut self, name: &str) -> Result<()> {
+        if self.config.profiles.remove(name).is_none() {
+            return Err(anyhow::anyhow!("Profile '{}' not found", name));
+        }
+        if self.config.current_profile.as_deref() == Some(name) {
+            self.config.current_profile = None;
+        }
+        if self.profile.as_deref() == Some(name) {
+            self.profile = None;
+        }
+        self.save()
+    }
+
     pub fn save(&self) -> Result<()> {
         let content = toml::to_string_pretty(&self.config)?;
         fs::write(&self.config_path, content)?;
         Ok(())
     }
 
+    /// Directory of cached note proofs, next to the configuration file
+    pub fn proof_cache_dir(&self) -> PathBuf {
+        self.config_path
+            .parent()
+            .map(|dir| dir.join("proofs"))
+            .unwrap_or_else(|| PathBuf::from("proofs"))
+    }
+
     pub fn get_config(&self) -> &CliConfig {
         &self.config
     }
@@ -70,16 +198,38 @@ impl ConfigManager {
         &mut self.config
     }
 
+    /// Select the account of the active profile, or the global current account
     pub fn set_current_account(&mut self, name: &str) -> Result<()> {
-        self.config.current_account = Some(name.to_string());
+        let profile = self.profile.clone();
+        match profile.and_then(|profile| self.config.profiles.get_mut(&profile)) {
+            Some(profile) => profile.account = Some(name.to_string()),
+            None => self.config.current_account = Some(name.to_string()),
+        }
         self.save()
     }
 
+    /// Name of the account in use: the active profile's, or the global current account
+    pub fn current_account_name(&self) -> Option<&str> {
+        self.active_profile()
+            .and_then(|(_, profile)| profile.account.as_deref())
+            .or(self.config.current_account.as_deref())
+    }
+
     pub fn add_account(
         &mut self,
         name: &str,
         pubkey_hex: &str,
         private_key_hex: &str,
+    ) -> Result<()> {
+        self.add_derived_account(name, pubkey_hex, private_key_hex, None)
+    }
+
+    pub fn add_derived_account(
+        &mut self,
+        name: &str,
+        pubkey_hex: &str,
+        private_key_hex: &str,
+        derivation_path: Option<&str>,
     ) -> Result<()> {
         let account_config = AccountConfig {
             name: name.to_string(),
@@ -88,6 +238,7 @@ impl ConfigManager {
             created_at: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)?
                 .as_secs(),
+            derivation_path: derivation_path.map(str::to_string),
         };
 
         self.config
@@ -96,6 +247,14 @@ impl ConfigManager {
         self.save()
     }
 
+    pub fn set_wallet(&mut self, mnemonic: &str, passphrase: &str) -> Result<()> {
+        self.config.wallet = Some(WalletConfig {
+            mnemonic: mnemonic.to_string(),
+            passphrase: passphrase.to_string(),
+        });
+        self.save()
+    }
+
     pub fn get_account(&self, name: &str) -> Option<&AccountConfig> {
         self.config.accounts.get(name)
     }
@@ -105,9 +264,70 @@ impl ConfigManager {
     }
 
     pub fn get_current_account(&self) -> Option<&AccountConfig> {
-        self.config
-            .current_account
-            .as_ref()
+        self.current_account_name()
             .and_then(|name| self.config.accounts.get(name))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles_select_account_tracker_and_network() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("basis_cli_profiles_{}.toml", std::process::id()));
+        let _ = fs::remove_file(&path);
+
+        let mut manager = ConfigManager::new(Some(path.clone()))?;
+        manager.add_account("alice", "02aa", "11")?;
+        manager.add_account("bob", "02bb", "22")?;
+        manager.set_current_account("alice")?;
+        manager.add_profile(
+            "testnet-bob",
+            ProfileConfig {
+                account: Some("bob".to_string()),
+                server_urls: vec!["http://tracker-b:3048".to_string()],
+                network: Some(Network::Testnet),
+            },
+        )?;
+        assert!(manager.add_profile("testnet-bob", ProfileConfig::default()).is_err());
+        assert!(manager
+            .add_profile(
+                "carol",
+                ProfileConfig {
+                    account: Some("carol".to_string()),
+                    ..ProfileConfig::default()
+                }
+            )
+            .is_err());
+
+        // Without a profile the global settings apply
+        assert_eq!(manager.current_account_name(), Some("alice"));
+        assert_eq!(manager.server_urls(), vec!["http://127.0.0.1:3048".to_string()]);
+        assert_eq!(manager.network(), Network::Mainnet);
+
+        // `--profile` applies for one run only
+        let mut reloaded = ConfigManager::new(Some(path.clone()))?;
+        reloaded.use_profile(Some("testnet-bob"))?;
+        assert_eq!(reloaded.current_account_name(), Some("bob"));
+        assert_eq!(reloaded.server_urls(), vec!["http://tracker-b:3048".to_string()]);
+        assert_eq!(reloaded.network(), Network::Testnet);
+        assert!(reloaded.use_profile(Some("missing")).is_err());
+
+        // Switching persists, and switching accounts within a profile changes only the profile
+        manager.switch_profile("testnet-bob")?;
+        manager.set_current_account("alice")?;
+        let mut reloaded = ConfigManager::new(Some(path.clone()))?;
+        reloaded.use_profile(None)?;
+        assert_eq!(reloaded.active_profile().map(|(name, _)| name), Some("testnet-bob"));
+        assert_eq!(reloaded.current_account_name(), Some("alice"));
+        assert_eq!(reloaded.get_config().current_account.as_deref(), Some("alice"));
+
+        reloaded.remove_profile("testnet-bob")?;
+        assert_eq!(reloaded.get_config().current_profile, None);
+        assert_eq!(reloaded.network(), Network::Mainnet);
+
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::Result;
 use secp256k1::{KeyPair as SecpKeyPair, Secp256k1, SecretKey};
 use basis_core::traits::SignatureVerifier;
+use basis_store::Network;
 
 pub type PubKey = [u8; 33];
 pub type Signature = [u8; 65];
@@ -139,6 +140,32 @@ impl KeyPair {
     }
 }
 
+// Helper function to convert public key to a P2PK address on `network` using ergo-lib
+pub fn pubkey_to_address(pubkey_hex: &str, network: Network) -> Result<String> {
+    use ergo_lib::ergotree_ir::address::Address;
+    use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+    use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+    let pubkey_bytes = hex::decode(pubkey_hex)
+        .map_err(|e| anyhow::anyhow!("Invalid public key hex: {}", e))?;
+
+    if pubkey_bytes.len() != 33 {
+        return Err(anyhow::anyhow!("Public key must be 33 bytes"));
+    }
+
+    // Parse public key as EcPoint (compressed secp256k1 point)
+    let ec_point = EcPoint::sigma_parse_bytes(&pubkey_bytes)
+        .map_err(|e| anyhow::anyhow!("Invalid public key format: {}", e))?;
+
+    // Create P2PK address from EcPoint
+    let prove_dlog = ProveDlog::new(ec_point);
+    let address = Address::P2Pk(prove_dlog);
+
+    // Encode address as base58 string with the network's prefix
+    Ok(network.encode_address(&address))
+}
+
 fn blake2b_hash(data: &[u8]) -> [u8; 32] {
     use blake2::{Blake2b, Digest};
 
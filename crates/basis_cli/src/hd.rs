@@ -0,0 +1,136 @@
+//! Hierarchical deterministic keys (BIP39 mnemonics, BIP32 derivation)
+//!
+//! Accounts are derived along EIP-3 paths `m/44'/429'/account'/0/index`,
+//! the same paths Ergo wallets use, so a wallet mnemonic yields the wallet's
+//! own addresses here. Mnemonics and derivation are left to the `bip39` and
+//! `bip32` crates.
+
+use std::str::FromStr;
+
+use anyhow::Result;
+use bip32::{ChildNumber, DerivationPath, XPrv};
+use bip39::{Language, Mnemonic};
+
+use crate::crypto::PubKey;
+
+/// BIP44 coin type registered for Ergo
+pub const ERGO_COIN_TYPE: u32 = 429;
+
+/// Generate a new mnemonic of 12, 15, 18, 21 or 24 words
+pub fn generate_mnemonic(word_count: usize) -> Result<String> {
+    let mnemonic = Mnemonic::generate_in(Language::English, word_count)
+        .map_err(|e| anyhow::anyhow!("Cannot generate mnemonic: {}", e))?;
+    Ok(mnemonic.to_string())
+}
+
+fn parse_mnemonic(mnemonic: &str) -> Result<Mnemonic> {
+    Mnemonic::parse_in(Language::English, mnemonic.to_lowercase())
+        .map_err(|e| anyhow::anyhow!("Invalid mnemonic: {}", e))
+}
+
+/// Check that a mnemonic consists of wordlist words with a valid checksum
+///
+/// Returns the phrase normalised to single spaces and lower case.
+pub fn validate_mnemonic(mnemonic: &str) -> Result<String> {
+    Ok(parse_mnemonic(mnemonic)?.to_string())
+}
+
+/// Stretch a mnemonic and optional passphrase into a 64-byte seed
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> Result<[u8; 64]> {
+    Ok(parse_mnemonic(mnemonic)?.to_seed(passphrase))
+}
+
+/// BIP32 extended private key
+#[derive(Clone)]
+pub struct ExtendedKey(XPrv);
+
+impl std::fmt::Debug for ExtendedKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExtendedKey")
+            .field("pubkey", &hex::encode(self.public_key()))
+            .finish_non_exhaustive()
+    }
+}
+
+impl ExtendedKey {
+    /// Master key of a seed
+    pub fn master(seed: &[u8]) -> Result<Self> {
+        XPrv::new(seed)
+            .map(Self)
+            .map_err(|e| anyhow::anyhow!("Cannot derive master key: {}", e))
+    }
+
+    /// Derive the child at `index`, hardened when `index >= 2^31`
+    pub fn derive_child(&self, index: u32) -> Result<Self> {
+        self.0
+            .derive_child(ChildNumber(index))
+            .map(Self)
+            .map_err(|e| anyhow::anyhow!("Derived key at index {} is invalid: {}", index, e))
+    }
+
+    /// Derive a descendant along a path such as `m/44'/429'/0'/0/0`
+    pub fn derive_path(&self, path: &str) -> Result<Self> {
+        let path = DerivationPath::from_str(path)
+            .map_err(|_| anyhow::anyhow!("Invalid derivation path '{}'", path))?;
+        path.into_iter()
+            .try_fold(self.clone(), |key, child| key.derive_child(child.0))
+    }
+
+    pub fn secret_key_bytes(&self) -> [u8; 32] {
+        self.0.private_key().to_bytes().into()
+    }
+
+    pub fn public_key(&self) -> PubKey {
+        self.0.public_key().to_bytes()
+    }
+}
+
+/// EIP-3 path of address `index` in wallet account `account`
+pub fn eip3_path(account: u32, index: u32) -> String {
+    format!("m/44'/{}'/{}'/0/{}", ERGO_COIN_TYPE, account, index)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEST_MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn test_mnemonic_seed_vector() {
+        let seed = mnemonic_to_seed(TEST_MNEMONIC, "TREZOR").unwrap();
+        assert_eq!(
+            hex::encode(seed),
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04"
+        );
+
+        assert!(validate_mnemonic(&TEST_MNEMONIC.replace("about", "abandon")).is_err());
+        assert!(validate_mnemonic(&TEST_MNEMONIC.replace("about", "notaword")).is_err());
+        assert_eq!(validate_mnemonic(&TEST_MNEMONIC.to_uppercase()).unwrap(), TEST_MNEMONIC);
+
+        let generated = generate_mnemonic(24).unwrap();
+        assert_eq!(generated.split(' ').count(), 24);
+        assert_eq!(validate_mnemonic(&generated).unwrap(), generated);
+    }
+
+    #[test]
+    fn test_bip32_derivation_vector() {
+        let seed = hex::decode("000102030405060708090a0b0c0d0e0f").unwrap();
+        let master = ExtendedKey::master(&seed).unwrap();
+        assert_eq!(
+            hex::encode(master.secret_key_bytes()),
+            "e8f32e723decf4051aefac8e2c93c9c5b214313817cdb01a1494b917c8436b35"
+        );
+        assert_eq!(
+            hex::encode(master.derive_path("m/0'").unwrap().secret_key_bytes()),
+            "edb2e14f9ee77d26dd93b4ecede8d16ed408ce149b6cd80b0715a2d911a0afea"
+        );
+        assert_eq!(
+            hex::encode(master.derive_path("m/0'/1/2'/2/1000000000").unwrap().secret_key_bytes()),
+            "471b76e389e528d6de6d816857e012c5455051cad6660850e58372a6c3e6e7c8"
+        );
+        assert!(master.derive_path("0'/1").is_err());
+        assert!(master.derive_path("m/2147483648").is_err());
+    }
+}
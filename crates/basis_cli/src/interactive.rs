@@ -1,6 +1,7 @@
 use crate::account::AccountManager;
 use crate::api::TrackerClient;
 use crate::commands::{account, note, reserve, status};
+use crate::output::OutputFormat;
 use anyhow::Result;
 use std::io::{self, Write};
 
@@ -48,7 +49,7 @@ impl InteractiveMode {
                     self.show_help();
                 }
                 "status" | "s" => {
-                    status::handle_status_command(&self.client).await?;
+                    status::handle_status_command(&self.client, OutputFormat::Table).await?;
                 }
                 _ => {
                     self.handle_command(input).await?;
@@ -93,23 +94,24 @@ impl InteractiveMode {
                             let name = parts[2];
                             let cmd = account::AccountCommands::Create {
                                 name: name.to_string(),
+                                index: None,
                             };
-                            account::handle_account_command(cmd, &mut self.account_manager).await?;
+                            account::handle_account_command(cmd, &mut self.account_manager, OutputFormat::Table).await?;
                         }
                         "list" => {
                             let cmd = account::AccountCommands::List;
-                            account::handle_account_command(cmd, &mut self.account_manager).await?;
+                            account::handle_account_command(cmd, &mut self.account_manager, OutputFormat::Table).await?;
                         }
                         "switch" if parts.len() >= 3 => {
                             let name = parts[2];
                             let cmd = account::AccountCommands::Switch {
                                 name: name.to_string(),
                             };
-                            account::handle_account_command(cmd, &mut self.account_manager).await?;
+                            account::handle_account_command(cmd, &mut self.account_manager, OutputFormat::Table).await?;
                         }
                         "info" => {
                             let cmd = account::AccountCommands::Info;
-                            account::handle_account_command(cmd, &mut self.account_manager).await?;
+                            account::handle_account_command(cmd, &mut self.account_manager, OutputFormat::Table).await?;
                         }
                         _ => {
                             println!("Unknown account command. Use 'help' for available commands.");
@@ -152,8 +154,10 @@ impl InteractiveMode {
                                     amount,
                                     demo: false,
                                     output: None,
+                                    memo: None,
+                                    amount_hint: None,
                                 };
-                                note::handle_note_command(cmd, &self.account_manager, &self.client)
+                                note::handle_note_command(cmd, &self.account_manager, &self.client, OutputFormat::Table)
                                     .await?;
                             } else {
                                 println!("Note create requires --recipient <pubkey> and --amount <amount>");
@@ -172,7 +176,7 @@ impl InteractiveMode {
                             }
 
                             let cmd = note::NoteCommands::List { issuer, recipient };
-                            note::handle_note_command(cmd, &self.account_manager, &self.client)
+                            note::handle_note_command(cmd, &self.account_manager, &self.client, OutputFormat::Table)
                                 .await?;
                         }
                         _ => {
@@ -209,6 +213,7 @@ impl InteractiveMode {
                                 cmd,
                                 &self.account_manager,
                                 &self.client,
+                                OutputFormat::Table,
                             )
                             .await?;
                         }
@@ -233,6 +238,7 @@ impl InteractiveMode {
                                 cmd,
                                 &self.account_manager,
                                 &self.client,
+                                OutputFormat::Table,
                             )
                             .await?;
                         }
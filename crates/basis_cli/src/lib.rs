@@ -4,4 +4,7 @@ pub mod commands;
 pub mod config;
 pub mod crypto;
 pub mod demo_keys;
+pub mod hd;
 pub mod interactive;
+pub mod output;
+pub mod proof_cache;
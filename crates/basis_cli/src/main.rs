@@ -4,10 +4,14 @@ mod commands;
 mod config;
 mod crypto;
 mod demo_keys;
+mod hd;
 mod interactive;
+mod output;
+mod proof_cache;
 
 use anyhow::Result;
-use clap::{Parser, Subcommand};
+use clap::{CommandFactory, Parser, Subcommand};
+use output::{ErrorOutput, OutputFormat};
 use std::path::PathBuf;
 
 #[derive(Parser)]
@@ -18,11 +22,26 @@ struct Cli {
     #[command(subcommand)]
     command: Commands,
 
-    #[arg(long, default_value = "http://127.0.0.1:3048")]
-    server_url: String,
+    /// Tracker URL; repeat or separate with commas to fail over between
+    /// endpoints of the same tracker. Defaults to the profile's or the one
+    /// in the CLI config (http://127.0.0.1:3048)
+    #[arg(long, value_delimiter = ',')]
+    server_url: Vec<String>,
 
     #[arg(long)]
     config: Option<PathBuf>,
+
+    /// Ergo network (mainnet or testnet); defaults to the one in the CLI config
+    #[arg(long)]
+    network: Option<basis_store::Network>,
+
+    /// CLI profile (account, tracker and network) to use instead of the current one
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Print results as tables for people or as JSON for scripts
+    #[arg(long, value_enum, default_value = "table")]
+    output: OutputFormat,
 }
 
 #[derive(Subcommand)]
@@ -32,6 +51,11 @@ enum Commands {
         #[command(subcommand)]
         cmd: commands::account::AccountCommands,
     },
+    /// Profile management
+    Profile {
+        #[command(subcommand)]
+        cmd: commands::profile::ProfileCommands,
+    },
     /// Generate a new secp256k1 keypair
     GenerateKeypair(commands::keypair::GenerateKeypairArgs),
     /// Note operations
@@ -58,40 +82,117 @@ enum Commands {
     Interactive,
     /// Server status
     Status,
+    /// Export notes or reserves for accounting
+    Report {
+        #[command(subcommand)]
+        cmd: commands::report::ReportCommands,
+    },
+    /// Suggest settlements netting offsetting debts
+    Netting(commands::netting::NettingArgs),
+    /// Tracker administration
+    Admin {
+        #[command(subcommand)]
+        cmd: commands::admin::AdminCommands,
+    },
+    /// Print a shell completion script, e.g. `basis-cli completions bash > /etc/bash_completion.d/basis-cli`
+    Completions {
+        /// Shell to complete in
+        shell: clap_complete::Shell,
+    },
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    let output = cli.output;
+
+    match run(cli).await {
+        Err(e) if output.is_json() => {
+            output.print(&ErrorOutput { error: format!("{:#}", e) }, |_| {})?;
+            std::process::exit(1);
+        }
+        result => result,
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let output = cli.output;
+    if let Commands::Completions { shell } = cli.command {
+        clap_complete::generate(shell, &mut Cli::command(), "basis-cli", &mut std::io::stdout());
+        return Ok(());
+    }
 
     // Load configuration
-    let config_manager = config::ConfigManager::new(cli.config)?;
+    let mut config_manager = config::ConfigManager::new(cli.config)?;
+    config_manager.use_profile(cli.profile.as_deref())?;
     let mut account_manager = account::AccountManager::new(config_manager.clone())?;
-    let client = api::TrackerClient::new(cli.server_url);
+    if let Some(network) = cli.network {
+        account_manager.network = network;
+    }
+    let server_urls = if cli.server_url.is_empty() {
+        config_manager.server_urls()
+    } else {
+        cli.server_url
+    };
+    let mut client = api::TrackerClient::with_urls(server_urls)?;
+    if client.urls().len() > 1 {
+        client.select_tracker().await;
+    }
 
     match cli.command {
         Commands::Account { cmd } => {
-            commands::account::handle_account_command(cmd, &mut account_manager).await
+            commands::account::handle_account_command(cmd, &mut account_manager, output).await
+        }
+        Commands::Profile { cmd } => {
+            commands::profile::handle_profile_command(cmd, &mut account_manager.config_manager, output).await
         }
         Commands::GenerateKeypair(args) => {
-            commands::keypair::handle_generate_keypair_command(args).await
+            commands::keypair::handle_generate_keypair_command(args, output).await
         }
         Commands::Note { cmd } => {
-            commands::note::handle_note_command(cmd, &account_manager, &client).await
+            commands::note::handle_note_command(cmd, &account_manager, &client, output).await
         }
         Commands::Reserve { cmd } => {
-            commands::reserve::handle_reserve_command(cmd, &account_manager, &client).await
+            commands::reserve::handle_reserve_command(cmd, &account_manager, &client, output).await
         }
         Commands::Transaction { cmd } => {
-            commands::transaction::handle_transaction_command(cmd, &client, &account_manager).await
+            commands::transaction::handle_transaction_command(cmd, &client, &account_manager, output).await
         }
         Commands::Test { cmd } => {
-            commands::test_redemption::handle_test_command(cmd, &client).await
+            commands::test_redemption::handle_test_command(cmd, &client, account_manager.network, output).await
         }
         Commands::Interactive => {
+            if output.is_json() {
+                return Err(anyhow::anyhow!("Interactive mode has no JSON output"));
+            }
             let mut interactive = interactive::InteractiveMode::new(account_manager, client);
             interactive.run().await
         }
-        Commands::Status => commands::status::handle_status_command(&client).await,
+        Commands::Status => commands::status::handle_status_command(&client, output).await,
+        Commands::Report { cmd } => commands::report::handle_report_command(cmd, &client, output).await,
+        Commands::Netting(args) => commands::netting::handle_netting_command(args, &client, output).await,
+        Commands::Admin { cmd } => commands::admin::handle_admin_command(cmd, &account_manager, output).await,
+        Commands::Completions { .. } => unreachable!("handled before loading the configuration"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_completions_cover_every_command() {
+        Cli::command().debug_assert();
+        for shell in [clap_complete::Shell::Bash, clap_complete::Shell::Zsh, clap_complete::Shell::Fish] {
+            let mut script = Vec::new();
+            clap_complete::generate(shell, &mut Cli::command(), "basis-cli", &mut script);
+            let script = String::from_utf8(script).unwrap();
+            for command in ["profile", "generate-keypair", "collateralization", "completions"] {
+                assert!(script.contains(command), "{} completions lack {}", shell, command);
+            }
+        }
+
+        let cli = Cli::try_parse_from(["basis-cli", "--output", "json", "status"]).unwrap();
+        assert_eq!(cli.output, OutputFormat::Json);
     }
 }
@@ -0,0 +1,59 @@
+//! How commands print their results: tables for people, or JSON for scripts
+//!
+//! With `--output json` a command prints a single JSON document on stdout,
+//! or one document per line for commands that keep running (`reserve watch`).
+//! Progress messages go to stderr then, and failures print
+//! `{"error": "..."}` and exit with status 1. Fields of the documents are
+//! only ever added, never renamed or removed, so scripts can rely on them.
+
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    #[default]
+    Table,
+    /// JSON documents with stable fields
+    Json,
+}
+
+impl OutputFormat {
+    pub fn is_json(self) -> bool {
+        self == OutputFormat::Json
+    }
+
+    /// Print a command's result as JSON, or through `table` for people
+    pub fn print<T: Serialize>(self, value: &T, table: impl FnOnce(&T)) -> Result<()> {
+        match self {
+            OutputFormat::Table => table(value),
+            OutputFormat::Json => println!("{}", serde_json::to_string_pretty(value)?),
+        }
+        Ok(())
+    }
+
+    /// Print one result of a running command, on a line of its own in JSON
+    pub fn print_line<T: Serialize>(self, value: &T, table: impl FnOnce(&T)) -> Result<()> {
+        match self {
+            OutputFormat::Table => table(value),
+            OutputFormat::Json => println!("{}", serde_json::to_string(value)?),
+        }
+        Ok(())
+    }
+
+    /// Report progress, on stderr in JSON mode so stdout stays parseable
+    pub fn progress(self, message: impl Display) {
+        match self {
+            OutputFormat::Table => println!("{}", message),
+            OutputFormat::Json => eprintln!("{}", message),
+        }
+    }
+}
+
+/// Document printed for a failed command in JSON mode
+#[derive(Debug, Serialize)]
+pub struct ErrorOutput {
+    pub error: String,
+}
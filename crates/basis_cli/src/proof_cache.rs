@@ -0,0 +1,165 @@
+//! Local cache of note membership proofs
+//!
+//! A recipient keeps the tracker's proof that their note is in its AVL tree,
+//! so they can still redeem if the tracker later withholds it. Each proof is
+//! stored as one JSON file per note together with the tracker box that held
+//! the latest on-chain commitment when the proof was fetched, which is what
+//! tells a proof the chain has moved past from one not committed yet.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+
+/// A tracker membership proof for one note, as fetched from the tracker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedProof {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    pub total_debt: u64,
+    /// AVL tree key: hash(issuer || recipient) (hex)
+    pub key: String,
+    /// AVL tree value: total debt as 8 bytes big-endian (hex)
+    pub value: String,
+    /// AVL lookup proof (hex)
+    pub proof: String,
+    /// Root digest of the tracker state the proof was generated against (hex)
+    pub tracker_state_digest: String,
+    /// When the proof was fetched, in milliseconds since the Unix epoch
+    pub fetched_at: u64,
+    /// Tracker box holding the latest commitment when the proof was fetched
+    pub commitment_box_id: Option<String>,
+}
+
+/// The tracker state digest committed on-chain in a tracker box
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Commitment {
+    pub box_id: String,
+    pub height: u64,
+    /// Committed root digest (hex)
+    pub digest: String,
+}
+
+/// How a cached proof relates to the latest on-chain commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProofFreshness {
+    /// The proof was generated against the committed state
+    Committed,
+    /// The proof is newer than the latest commitment, which predates it
+    Pending,
+    /// State was committed after the proof was generated, so the proof no
+    /// longer matches the chain
+    Stale,
+}
+
+impl CachedProof {
+    /// Compare the proof with the latest commitment
+    pub fn freshness(&self, latest: &Commitment) -> ProofFreshness {
+        if self.tracker_state_digest.eq_ignore_ascii_case(&latest.digest) {
+            ProofFreshness::Committed
+        } else if self.commitment_box_id.as_deref() == Some(latest.box_id.as_str()) {
+            ProofFreshness::Pending
+        } else {
+            ProofFreshness::Stale
+        }
+    }
+}
+
+/// Directory of cached proofs, one file per issuer and recipient
+#[derive(Debug, Clone)]
+pub struct ProofCache {
+    dir: PathBuf,
+}
+
+impl ProofCache {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    /// File holding the proof of the note from `issuer` to `recipient`
+    pub fn path(&self, issuer_pubkey: &str, recipient_pubkey: &str) -> PathBuf {
+        self.dir.join(format!(
+            "{}_{}.json",
+            issuer_pubkey.to_lowercase(),
+            recipient_pubkey.to_lowercase()
+        ))
+    }
+
+    /// The cached proof of a note, if any
+    pub fn load(&self, issuer_pubkey: &str, recipient_pubkey: &str) -> Result<Option<CachedProof>> {
+        let path = self.path(issuer_pubkey, recipient_pubkey);
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&path)?;
+        let proof = serde_json::from_str(&content)
+            .map_err(|e| anyhow::anyhow!("Corrupt cached proof {}: {}", path.display(), e))?;
+        Ok(Some(proof))
+    }
+
+    /// Cache a proof, replacing the one held for the same note
+    pub fn store(&self, proof: &CachedProof) -> Result<PathBuf> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(&proof.issuer_pubkey, &proof.recipient_pubkey);
+        // Write aside and rename, so an interrupted write keeps the old proof
+        let partial = path.with_extension("json.partial");
+        fs::write(&partial, serde_json::to_string_pretty(proof)?)?;
+        fs::rename(&partial, &path)?;
+        Ok(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn proof(digest: &str, commitment_box_id: Option<&str>) -> CachedProof {
+        CachedProof {
+            issuer_pubkey: "02aa".to_string(),
+            recipient_pubkey: "03BB".to_string(),
+            total_debt: 1_000,
+            key: "00".repeat(32),
+            value: hex::encode(1_000u64.to_be_bytes()),
+            proof: "0102".to_string(),
+            tracker_state_digest: digest.to_string(),
+            fetched_at: 1_700_000_000_000,
+            commitment_box_id: commitment_box_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_freshness_against_latest_commitment() {
+        let latest = Commitment {
+            box_id: "box2".to_string(),
+            height: 1_200,
+            digest: "aa".repeat(33),
+        };
+
+        assert_eq!(proof(&"AA".repeat(33), Some("box1")).freshness(&latest), ProofFreshness::Committed);
+        // Fetched after box2 was committed, against state box2 does not hold yet
+        assert_eq!(proof(&"cc".repeat(33), Some("box2")).freshness(&latest), ProofFreshness::Pending);
+        // Box2 was committed after the proof was fetched
+        assert_eq!(proof(&"cc".repeat(33), Some("box1")).freshness(&latest), ProofFreshness::Stale);
+        assert_eq!(proof(&"cc".repeat(33), None).freshness(&latest), ProofFreshness::Stale);
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir = std::env::temp_dir().join(format!("basis_cli_proofs_{}", std::process::id()));
+        let cache = ProofCache::new(dir.clone());
+        assert_eq!(cache.load("02aa", "03bb").unwrap(), None);
+
+        let cached = proof(&"aa".repeat(33), Some("box1"));
+        let path = cache.store(&cached).unwrap();
+        assert_eq!(path, cache.path("02AA", "03bb"));
+        assert_eq!(cache.load("02aa", "03bb").unwrap(), Some(cached.clone()));
+
+        // A refreshed proof replaces the cached one
+        let refreshed = proof(&"cc".repeat(33), Some("box2"));
+        cache.store(&refreshed).unwrap();
+        assert_eq!(cache.load("02aa", "03bb").unwrap(), Some(refreshed));
+
+        let _ = fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,231 @@
+//! Typed client for the tracker HTTP API
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::error::ClientError;
+use crate::types::{
+    ApiResponse, CreateNoteRequest, Note, NotePayload, ProofResponse, RedeemRequest, RedeemResponse, TrackerEvent,
+};
+
+/// Request header carrying the API key for state-changing endpoints
+const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Events requested per page while following the event log
+const EVENT_PAGE_SIZE: usize = 100;
+
+/// Async client for one tracker
+#[derive(Debug, Clone)]
+pub struct TrackerApiClient {
+    base_url: String,
+    api_key: Option<String>,
+    http: reqwest::Client,
+}
+
+impl TrackerApiClient {
+    /// Client for the tracker at `base_url`, e.g. `http://localhost:3048`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Send an API key with every POST request
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Base URL of the tracker
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Whether the tracker answers at all
+    pub async fn health_check(&self) -> Result<bool, ClientError> {
+        let response = self.http.get(format!("{}/", self.base_url)).send().await?;
+        Ok(response.status().is_success())
+    }
+
+    /// Submit a signed note
+    pub async fn create_note(&self, request: &CreateNoteRequest) -> Result<(), ClientError> {
+        self.post::<_, Option<serde_json::Value>>("/notes", request).await?;
+        Ok(())
+    }
+
+    /// Every note held by the tracker
+    pub async fn get_notes(&self) -> Result<Vec<Note>, ClientError> {
+        self.get("/notes", &[]).await
+    }
+
+    /// Notes issued by a public key (hex)
+    pub async fn get_issuer_notes(&self, issuer_pubkey: &str) -> Result<Vec<Note>, ClientError> {
+        self.get(&format!("/notes/issuer/{}", issuer_pubkey), &[]).await
+    }
+
+    /// Notes received by a public key (hex)
+    pub async fn get_recipient_notes(&self, recipient_pubkey: &str) -> Result<Vec<Note>, ClientError> {
+        self.get(&format!("/notes/recipient/{}", recipient_pubkey), &[]).await
+    }
+
+    /// The note between an issuer and a recipient, if any
+    pub async fn get_note(&self, issuer_pubkey: &str, recipient_pubkey: &str) -> Result<Option<Note>, ClientError> {
+        self.get(
+            &format!("/notes/issuer/{}/recipient/{}", issuer_pubkey, recipient_pubkey),
+            &[],
+        )
+        .await
+    }
+
+    /// Encrypted payload of the current version of a note, if the issuer attached one
+    ///
+    /// Decrypt it with the recipient's [`crate::NoteSigner::decrypt_payload`].
+    pub async fn get_note_payload(
+        &self,
+        issuer_pubkey: &str,
+        recipient_pubkey: &str,
+    ) -> Result<Option<NotePayload>, ClientError> {
+        let path = format!("/notes/issuer/{}/recipient/{}/payload", issuer_pubkey, recipient_pubkey);
+        match self.get(&path, &[]).await {
+            Ok(payload) => Ok(Some(payload)),
+            Err(ClientError::Api { status: 404, .. }) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Proof that a note is committed in the tracker's AVL tree
+    pub async fn get_proof(&self, issuer_pubkey: &str, recipient_pubkey: &str) -> Result<ProofResponse, ClientError> {
+        self.get(
+            "/proof/redemption",
+            &[("issuer_pubkey", issuer_pubkey), ("recipient_pubkey", recipient_pubkey)],
+        )
+        .await
+    }
+
+    /// Start redeeming a note against the issuer's reserve
+    pub async fn initiate_redemption(&self, request: &RedeemRequest) -> Result<RedeemResponse, ClientError> {
+        self.post("/redeem", request).await
+    }
+
+    /// One page of the tracker event log, oldest first
+    pub async fn get_events(&self, page: usize, page_size: usize) -> Result<Vec<TrackerEvent>, ClientError> {
+        self.get(
+            "/events/paginated",
+            &[("page", &page.to_string()), ("page_size", &page_size.to_string())],
+        )
+        .await
+    }
+
+    /// Up to `limit` events following the event with sequence number `since_seq`
+    pub async fn get_events_since(&self, since_seq: u64, limit: usize) -> Result<Vec<TrackerEvent>, ClientError> {
+        self.get(
+            "/events/paginated",
+            &[("since_seq", &since_seq.to_string()), ("page_size", &limit.to_string())],
+        )
+        .await
+    }
+
+    /// Follow the event log, polling every `poll_interval`
+    ///
+    /// The subscription starts with the oldest event held by the tracker.
+    pub fn subscribe_events(&self, poll_interval: Duration) -> EventSubscription {
+        self.subscribe_events_since(poll_interval, 0)
+    }
+
+    /// Follow the event log after the event with sequence number `since_seq`,
+    /// e.g. the `seq` of the last event a previous subscription returned
+    pub fn subscribe_events_since(&self, poll_interval: Duration, since_seq: u64) -> EventSubscription {
+        EventSubscription {
+            client: self.clone(),
+            poll_interval,
+            last_seq: since_seq,
+            pending: VecDeque::new(),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str, query: &[(&str, &str)]) -> Result<T, ClientError> {
+        let response = self
+            .http
+            .get(format!("{}{}", self.base_url, path))
+            .query(query)
+            .send()
+            .await?;
+        parse_response(response).await
+    }
+
+    async fn post<B: Serialize, T: DeserializeOwned>(&self, path: &str, body: &B) -> Result<T, ClientError> {
+        let mut request = self.http.post(format!("{}{}", self.base_url, path)).json(body);
+        if let Some(api_key) = &self.api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        parse_response(request.send().await?).await
+    }
+}
+
+// Unwrap the response envelope, turning error statuses and `success: false` into errors
+async fn parse_response<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, ClientError> {
+    let status = response.status();
+    let body = response.bytes().await?;
+
+    let envelope: ApiResponse<T> = match serde_json::from_slice(&body) {
+        Ok(envelope) => envelope,
+        Err(_) if !status.is_success() => {
+            return Err(ClientError::Api {
+                status: status.as_u16(),
+                message: String::from_utf8_lossy(&body).into_owned(),
+            })
+        }
+        Err(e) => return Err(ClientError::InvalidResponse(e.to_string())),
+    };
+
+    if !status.is_success() || !envelope.success {
+        return Err(ClientError::Api {
+            status: status.as_u16(),
+            message: envelope.error.unwrap_or_else(|| status.to_string()),
+        });
+    }
+
+    match envelope.data {
+        Some(data) => Ok(data),
+        // Endpoints without a payload answer with `data: null`
+        None => serde_json::from_value(serde_json::Value::Null)
+            .map_err(|_| ClientError::InvalidResponse("missing data".to_string())),
+    }
+}
+
+/// Stream of tracker events obtained by polling the event log
+#[derive(Debug)]
+pub struct EventSubscription {
+    client: TrackerApiClient,
+    poll_interval: Duration,
+    last_seq: u64,
+    pending: VecDeque<TrackerEvent>,
+}
+
+impl EventSubscription {
+    /// Wait for the next event
+    ///
+    /// A failed poll is returned as an error; calling `next` again retries it.
+    pub async fn next(&mut self) -> Result<TrackerEvent, ClientError> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(event);
+            }
+
+            let new_events = self.client.get_events_since(self.last_seq, EVENT_PAGE_SIZE).await?;
+            match new_events.last() {
+                Some(last) => self.last_seq = last.seq,
+                None => {
+                    tokio::time::sleep(self.poll_interval).await;
+                    continue;
+                }
+            }
+            self.pending.extend(new_events);
+        }
+    }
+}
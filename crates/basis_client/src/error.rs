@@ -0,0 +1,22 @@
+//! Errors returned by the client
+
+use basis_core::{CryptoError, NotePayloadError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    /// The request could not be sent or its response could not be read
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+    /// The tracker answered with an error
+    #[error("API error ({status}): {message}")]
+    Api { status: u16, message: String },
+    /// The tracker answered with something the client does not understand
+    #[error("Invalid response: {0}")]
+    InvalidResponse(String),
+    /// A key or signature could not be used
+    #[error("Crypto error: {0}")]
+    Crypto(#[from] CryptoError),
+    /// An encrypted note payload could not be sealed or opened
+    #[error("Payload error: {0}")]
+    Payload(#[from] NotePayloadError),
+}
@@ -0,0 +1,15 @@
+//! Client SDK for the Basis tracker API
+//!
+//! [`TrackerApiClient`] wraps the tracker's HTTP API with typed requests and
+//! responses, and [`NoteSigner`] creates note signatures locally so that
+//! wallets never send secret keys to the tracker.
+
+pub mod client;
+pub mod error;
+pub mod signing;
+pub mod types;
+
+pub use client::{EventSubscription, TrackerApiClient};
+pub use error::ClientError;
+pub use signing::{verify_note_signature, NoteSigner};
+pub use types::*;
@@ -0,0 +1,173 @@
+//! Local note signing
+//!
+//! Notes are signed over the Basis signing message
+//! `blake2b256(issuer || recipient) || total_debt || timestamp`, the same
+//! message the tracker and the reserve contract verify.
+
+use basis_core::{
+    decrypt_note_payload, encrypt_note_payload, schnorr_sign, schnorr_verify, signing_message, CryptoError,
+    NotePayloadError, PubKey, Signature,
+};
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+use crate::error::ClientError;
+use crate::types::{CreateNoteRequest, NotePayload, RedeemRequest};
+
+/// Issuer key signing notes on the client side
+#[derive(Clone)]
+pub struct NoteSigner {
+    secret_key: [u8; 32],
+    pubkey: PubKey,
+}
+
+impl std::fmt::Debug for NoteSigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NoteSigner")
+            .field("pubkey", &hex::encode(self.pubkey))
+            .finish_non_exhaustive()
+    }
+}
+
+impl NoteSigner {
+    /// Signer for an existing secret key
+    pub fn from_secret_key(secret_key: [u8; 32]) -> Result<Self, CryptoError> {
+        let secp = Secp256k1::new();
+        let key = SecretKey::from_slice(&secret_key)
+            .map_err(|_| CryptoError::InternalError("Invalid secret key".to_string()))?;
+        let pubkey = PublicKey::from_secret_key(&secp, &key).serialize();
+        Ok(Self { secret_key, pubkey })
+    }
+
+    /// Signer for a freshly generated key
+    pub fn generate() -> Self {
+        let (secret_key, pubkey) = basis_core::generate_keypair();
+        Self { secret_key, pubkey }
+    }
+
+    /// Compressed public key of the signer
+    pub fn pubkey(&self) -> PubKey {
+        self.pubkey
+    }
+
+    /// Secret key bytes, for the wallet to store
+    pub fn secret_key(&self) -> [u8; 32] {
+        self.secret_key
+    }
+
+    /// Sign the total debt owed to a recipient at a timestamp
+    pub fn sign(&self, recipient: &PubKey, total_debt: u64, timestamp: u64) -> Result<Signature, CryptoError> {
+        let message = signing_message(&self.pubkey, recipient, total_debt, timestamp);
+        schnorr_sign(&message, &self.secret_key, &self.pubkey)
+    }
+
+    /// Signed `POST /notes` body
+    pub fn create_note_request(
+        &self,
+        recipient: &PubKey,
+        total_debt: u64,
+        timestamp: u64,
+    ) -> Result<CreateNoteRequest, CryptoError> {
+        let signature = self.sign(recipient, total_debt, timestamp)?;
+        Ok(CreateNoteRequest {
+            issuer_pubkey: hex::encode(self.pubkey),
+            recipient_pubkey: hex::encode(recipient),
+            amount: total_debt,
+            timestamp,
+            signature: hex::encode(signature),
+            encrypted_payload: None,
+        })
+    }
+
+    /// Encrypt a memo or other note details for the recipient of the note at `timestamp`
+    ///
+    /// The result goes in [`CreateNoteRequest::encrypted_payload`]; the
+    /// tracker stores it without being able to read it.
+    pub fn encrypt_payload(
+        &self,
+        recipient: &PubKey,
+        timestamp: u64,
+        plaintext: &[u8],
+    ) -> Result<String, NotePayloadError> {
+        encrypt_note_payload(&self.secret_key, recipient, timestamp, plaintext).map(hex::encode)
+    }
+
+    /// Decrypt a payload the tracker holds for a note received by this key
+    pub fn decrypt_payload(&self, payload: &NotePayload) -> Result<Vec<u8>, ClientError> {
+        let invalid = |field: &str| ClientError::InvalidResponse(format!("invalid {} in note payload", field));
+        let issuer: PubKey = hex::decode(&payload.issuer_pubkey)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| invalid("issuer_pubkey"))?;
+        let envelope = hex::decode(&payload.encrypted_payload).map_err(|_| invalid("encrypted_payload"))?;
+        Ok(decrypt_note_payload(&self.secret_key, &issuer, payload.timestamp, &envelope)?)
+    }
+
+    /// Signed `POST /redeem` body, leaving the remaining fields to the tracker
+    pub fn redeem_request(
+        &self,
+        recipient: &PubKey,
+        total_debt: u64,
+        timestamp: u64,
+    ) -> Result<RedeemRequest, CryptoError> {
+        let signature = self.sign(recipient, total_debt, timestamp)?;
+        Ok(RedeemRequest {
+            issuer_pubkey: hex::encode(self.pubkey),
+            recipient_pubkey: hex::encode(recipient),
+            amount: total_debt,
+            timestamp,
+            issuer_signature: hex::encode(signature),
+            ..Default::default()
+        })
+    }
+}
+
+/// Check an issuer signature over a note
+pub fn verify_note_signature(
+    issuer: &PubKey,
+    recipient: &PubKey,
+    total_debt: u64,
+    timestamp: u64,
+    signature: &Signature,
+) -> Result<(), CryptoError> {
+    let message = signing_message(issuer, recipient, total_debt, timestamp);
+    schnorr_verify(signature, &message, issuer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_note_verifies() {
+        let issuer = NoteSigner::generate();
+        let recipient = NoteSigner::generate().pubkey();
+
+        let request = issuer.create_note_request(&recipient, 1_500, 1_700_000_000_000).unwrap();
+        let signature: Signature = hex::decode(&request.signature).unwrap().try_into().unwrap();
+        assert!(verify_note_signature(&issuer.pubkey(), &recipient, 1_500, 1_700_000_000_000, &signature).is_ok());
+        assert!(verify_note_signature(&issuer.pubkey(), &recipient, 1_501, 1_700_000_000_000, &signature).is_err());
+
+        let restored = NoteSigner::from_secret_key(issuer.secret_key()).unwrap();
+        assert_eq!(restored.pubkey(), issuer.pubkey());
+    }
+
+    #[test]
+    fn test_payload_opens_for_recipient_only() {
+        let issuer = NoteSigner::generate();
+        let recipient = NoteSigner::generate();
+
+        let payload = NotePayload {
+            issuer_pubkey: hex::encode(issuer.pubkey()),
+            recipient_pubkey: hex::encode(recipient.pubkey()),
+            timestamp: 1_700_000_000_000,
+            encrypted_payload: issuer
+                .encrypt_payload(&recipient.pubkey(), 1_700_000_000_000, b"table 4, two espressos")
+                .unwrap(),
+        };
+        assert_eq!(recipient.decrypt_payload(&payload).unwrap(), b"table 4, two espressos");
+        assert!(matches!(
+            NoteSigner::generate().decrypt_payload(&payload),
+            Err(ClientError::Payload(NotePayloadError::Unauthenticated))
+        ));
+    }
+}
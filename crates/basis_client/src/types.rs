@@ -0,0 +1,159 @@
+//! Request and response types of the tracker API
+
+use serde::{Deserialize, Serialize};
+
+/// Envelope wrapping every tracker response
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiResponse<T> {
+    pub success: bool,
+    pub data: Option<T>,
+    pub error: Option<String>,
+}
+
+/// Body of `POST /notes`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CreateNoteRequest {
+    /// Hex-encoded issuer public key (33 bytes)
+    pub issuer_pubkey: String,
+    /// Hex-encoded recipient public key (33 bytes)
+    pub recipient_pubkey: String,
+    /// Total cumulative debt of the issuer to the recipient
+    pub amount: u64,
+    /// Payment timestamp in milliseconds since Unix epoch
+    pub timestamp: u64,
+    /// Hex-encoded issuer signature (65 bytes)
+    pub signature: String,
+    /// Hex-encoded payload envelope only the recipient can decrypt
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encrypted_payload: Option<String>,
+}
+
+/// A note as returned by the tracker
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Note {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    pub amount_collected: u64,
+    pub amount_redeemed: u64,
+    pub timestamp: u64,
+    pub signature: String,
+    /// Recipient acknowledgment signature, if the recipient has co-signed the note
+    #[serde(default)]
+    pub recipient_signature: Option<String>,
+    #[serde(default)]
+    pub acknowledged: bool,
+    /// Reference the issuer signed with the note, such as an invoice ID
+    #[serde(default)]
+    pub memo: Option<String>,
+    /// Present only on `GET /notes`
+    #[serde(default)]
+    pub age_seconds: Option<u64>,
+}
+
+impl Note {
+    /// Debt not yet redeemed
+    pub fn outstanding_debt(&self) -> u64 {
+        self.amount_collected.saturating_sub(self.amount_redeemed)
+    }
+}
+
+/// Response of `GET /proof/redemption`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofResponse {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    /// Hex-encoded AVL lookup proof
+    pub proof_data: String,
+    /// Hex-encoded tracker AVL root digest (33 bytes)
+    pub tracker_state_digest: String,
+    pub block_height: u64,
+    pub timestamp: u64,
+}
+
+/// Response of `GET /notes/issuer/{issuer}/recipient/{recipient}/payload`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotePayload {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    /// Timestamp of the note version the payload was written for
+    pub timestamp: u64,
+    /// Hex-encoded payload envelope
+    pub encrypted_payload: String,
+}
+
+/// Body of `POST /redeem`
+///
+/// Fields left empty are filled in by the tracker.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedeemRequest {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    pub amount: u64,
+    pub timestamp: u64,
+    #[serde(default)]
+    pub reserve_box_id: String,
+    #[serde(default)]
+    pub tracker_box_id: String,
+    #[serde(default)]
+    pub tracker_nft_id: String,
+    #[serde(default)]
+    pub current_height: u64,
+    #[serde(default)]
+    pub recipient_address: String,
+    #[serde(default)]
+    pub change_address: String,
+    /// Hex-encoded issuer signature (65 bytes)
+    pub issuer_signature: String,
+    #[serde(default)]
+    pub emergency: bool,
+    #[serde(default)]
+    pub tracker_signature: Option<String>,
+}
+
+/// Response of `POST /redeem`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RedeemResponse {
+    pub redemption_id: String,
+    pub amount: u64,
+    pub timestamp: u64,
+    pub proof_available: bool,
+    pub transaction_pending: bool,
+}
+
+/// Kind of a tracker event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum EventType {
+    NoteUpdated,
+    ReserveCreated,
+    ReserveToppedUp,
+    ReserveRedeemed,
+    ReserveSpent,
+    Commitment,
+    CommitmentDivergence,
+    CollateralAlert { ratio: f64 },
+    DisputeFiled,
+    DisputeResolved,
+    KeyRotated,
+    /// An event type added to the tracker after this client was built
+    #[serde(other)]
+    Unknown,
+}
+
+/// An event recorded by the tracker
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrackerEvent {
+    pub id: u64,
+    /// Position in the tracker's event stream, to resume after with `since_seq`
+    #[serde(default)]
+    pub seq: u64,
+    pub event_type: EventType,
+    pub timestamp: u64,
+    pub issuer_pubkey: Option<String>,
+    pub recipient_pubkey: Option<String>,
+    pub amount: Option<u64>,
+    pub reserve_box_id: Option<String>,
+    pub collateral_amount: Option<u64>,
+    pub redeemed_amount: Option<u64>,
+    pub height: Option<u64>,
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    routing::{get, post},
+    Json, Router,
+};
+use basis_client::{
+    verify_note_signature, ClientError, CreateNoteRequest, EventType, NoteSigner, TrackerApiClient,
+};
+use serde_json::{json, Value};
+
+#[derive(Clone, Default)]
+struct MockTracker {
+    notes: Arc<Mutex<Vec<CreateNoteRequest>>>,
+    events: Arc<Mutex<Vec<Value>>>,
+}
+
+async fn create_note(State(mock): State<MockTracker>, Json(note): Json<CreateNoteRequest>) -> (StatusCode, Json<Value>) {
+    let decode = |hex_str: &str| hex::decode(hex_str).unwrap();
+    let issuer: [u8; 33] = decode(&note.issuer_pubkey).try_into().unwrap();
+    let recipient: [u8; 33] = decode(&note.recipient_pubkey).try_into().unwrap();
+    let signature: [u8; 65] = decode(&note.signature).try_into().unwrap();
+    if verify_note_signature(&issuer, &recipient, note.amount, note.timestamp, &signature).is_err() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"success": false, "data": null, "error": "Invalid signature"})),
+        );
+    }
+
+    let mut events = mock.events.lock().unwrap();
+    let id = events.len() as u64 + 1;
+    events.push(json!({
+        "id": id,
+        "seq": id,
+        "event_type": {"type": "NoteUpdated"},
+        "timestamp": note.timestamp,
+        "issuer_pubkey": note.issuer_pubkey,
+        "recipient_pubkey": note.recipient_pubkey,
+        "amount": note.amount,
+        "reserve_box_id": null,
+        "collateral_amount": null,
+        "redeemed_amount": null,
+        "height": null,
+    }));
+    drop(events);
+    mock.notes.lock().unwrap().push(note);
+    (StatusCode::CREATED, Json(json!({"success": true, "data": null, "error": null})))
+}
+
+async fn get_notes(State(mock): State<MockTracker>) -> Json<Value> {
+    let notes: Vec<Value> = mock
+        .notes
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|note| {
+            json!({
+                "issuer_pubkey": note.issuer_pubkey,
+                "recipient_pubkey": note.recipient_pubkey,
+                "amount_collected": note.amount,
+                "amount_redeemed": 0,
+                "timestamp": note.timestamp,
+                "signature": note.signature,
+                "recipient_signature": null,
+                "acknowledged": false,
+                "age_seconds": 0,
+            })
+        })
+        .collect();
+    Json(json!({"success": true, "data": notes, "error": null}))
+}
+
+async fn get_events(State(mock): State<MockTracker>, Query(params): Query<HashMap<String, usize>>) -> Json<Value> {
+    let events = mock.events.lock().unwrap();
+    let start = match params.get("since_seq") {
+        // Sequence numbers start at 1 and have no gaps here
+        Some(&since_seq) => since_seq.min(events.len()),
+        None => params["page"] * params["page_size"],
+    };
+    let end = (start + params["page_size"]).min(events.len());
+    Json(json!({"success": true, "data": events[start..end], "error": null}))
+}
+
+async fn redeem() -> (StatusCode, Json<Value>) {
+    (
+        StatusCode::FORBIDDEN,
+        Json(json!({"success": false, "data": null, "error": "Endpoint is disabled"})),
+    )
+}
+
+async fn spawn_mock_tracker() -> TrackerApiClient {
+    let app = Router::new()
+        .route("/notes", post(create_note).get(get_notes))
+        .route("/events/paginated", get(get_events))
+        .route("/redeem", post(redeem))
+        .with_state(MockTracker::default());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    TrackerApiClient::new(format!("http://{}/", addr))
+}
+
+#[tokio::test]
+async fn test_signed_note_round_trip() {
+    let client = spawn_mock_tracker().await;
+    let issuer = NoteSigner::generate();
+    let recipient = NoteSigner::generate().pubkey();
+
+    let request = issuer.create_note_request(&recipient, 2_500, 1_700_000_000_000).unwrap();
+    client.create_note(&request).await.unwrap();
+
+    // A note whose amount differs from what was signed is rejected
+    let mut tampered = request.clone();
+    tampered.amount += 1;
+    match client.create_note(&tampered).await {
+        Err(ClientError::Api { status: 400, message }) => assert_eq!(message, "Invalid signature"),
+        other => panic!("expected an API error, got {:?}", other),
+    }
+
+    let notes = client.get_notes().await.unwrap();
+    assert_eq!(notes.len(), 1);
+    assert_eq!(notes[0].amount_collected, 2_500);
+    assert_eq!(notes[0].outstanding_debt(), 2_500);
+    assert_eq!(notes[0].age_seconds, Some(0));
+
+    let redeem = issuer.redeem_request(&recipient, 2_500, 1_700_000_000_000).unwrap();
+    assert!(matches!(
+        client.initiate_redemption(&redeem).await,
+        Err(ClientError::Api { status: 403, .. })
+    ));
+}
+
+#[tokio::test]
+async fn test_subscription_follows_event_log() {
+    let client = spawn_mock_tracker().await;
+    let issuer = NoteSigner::generate();
+    let recipient = NoteSigner::generate().pubkey();
+    let mut events = client.subscribe_events(Duration::from_millis(10));
+
+    for amount in [100, 200] {
+        let request = issuer.create_note_request(&recipient, amount, 1_700_000_000_000).unwrap();
+        client.create_note(&request).await.unwrap();
+    }
+    let first = events.next().await.unwrap();
+    assert_eq!((first.id, first.amount), (1, Some(100)));
+    assert_eq!(first.event_type, EventType::NoteUpdated);
+
+    // Events recorded after the subscription started are picked up by polling
+    let later = client.clone();
+    let request = issuer.create_note_request(&recipient, 300, 1_700_000_000_000).unwrap();
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        later.create_note(&request).await.unwrap();
+    });
+    assert_eq!(events.next().await.unwrap().amount, Some(200));
+    let third = events.next().await.unwrap();
+    assert_eq!((third.seq, third.amount), (3, Some(300)));
+
+    // A new subscription resumes after the last event seen
+    let mut resumed = client.subscribe_events_since(Duration::from_millis(10), first.seq);
+    assert_eq!(resumed.next().await.unwrap().amount, Some(200));
+}
@@ -1,20 +1,28 @@
 //! Core implementations for Basis Tracker system
 
-use crate::traits::{SignatureVerifier, CryptoError};
-use crate::types::{PubKey, Signature};
+use crate::traits::{SignatureScheme, SignatureVerifier, CryptoError};
+use crate::types::{PubKey, Signature, SignatureSchemeId};
 use blake2::{Blake2b, Digest};
 use generic_array::typenum::U32;
 use secp256k1::{self, PublicKey, SecretKey};
 use std::convert::TryInto;
+use std::sync::OnceLock;
+
+/// Context shared by all signing and verification
+///
+/// Creating a context builds its precomputed multiplication tables, which
+/// costs more than a verification, so it is done once per process.
+fn secp() -> &'static secp256k1::Secp256k1<secp256k1::All> {
+    static SECP: OnceLock<secp256k1::Secp256k1<secp256k1::All>> = OnceLock::new();
+    SECP.get_or_init(secp256k1::Secp256k1::new)
+}
 
 /// Canonical Schnorr signature verifier implementation
 pub struct SchnorrVerifier;
 
 impl SignatureVerifier for SchnorrVerifier {
     fn verify_signature(&self, signature: &Signature, message: &[u8], public_key: &PubKey) -> Result<(), CryptoError> {
-        use secp256k1::Secp256k1;
-
-        let secp = Secp256k1::new();
+        let secp = secp();
 
         // Validate signature format first
         validate_signature_format(signature)?;
@@ -40,11 +48,11 @@ impl SignatureVerifier for SchnorrVerifier {
         let z_scalar = SecretKey::from_slice(z_bytes).map_err(|_| CryptoError::InvalidSignature)?;
 
         // Compute g^z (generator point raised to z power)
-        let g_z = secp256k1::PublicKey::from_secret_key(&secp, &z_scalar);
+        let g_z = secp256k1::PublicKey::from_secret_key(secp, &z_scalar);
 
         // Compute x^e (issuer public key raised to e power)
         let x_e_tweak = issuer_key
-            .mul_tweak(&secp, &e_scalar)
+            .mul_tweak(secp, &e_scalar)
             .map_err(|_| CryptoError::InvalidSignature)?;
 
         // Compute a * x^e (point addition)
@@ -66,6 +74,54 @@ impl SignatureVerifier for SchnorrVerifier {
     }
 }
 
+impl SignatureScheme for SchnorrVerifier {
+    fn id(&self) -> SignatureSchemeId {
+        SignatureSchemeId::Schnorr
+    }
+
+    fn public_key_len(&self) -> usize {
+        33
+    }
+
+    fn signature_len(&self) -> usize {
+        65
+    }
+
+    fn public_key(&self, secret_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let secret_key = SecretKey::from_slice(secret_key).map_err(|_| CryptoError::InvalidSignature)?;
+        Ok(PublicKey::from_secret_key(secp(), &secret_key).serialize().to_vec())
+    }
+
+    fn sign(&self, message: &[u8], secret_key: &[u8], public_key: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let secret_key: &[u8; 32] = secret_key.try_into().map_err(|_| CryptoError::InvalidSignature)?;
+        let public_key: &PubKey = public_key.try_into().map_err(|_| CryptoError::InvalidPublicKey)?;
+        Ok(schnorr_sign(message, secret_key, public_key)?.to_vec())
+    }
+
+    fn verify(&self, signature: &[u8], message: &[u8], public_key: &[u8]) -> Result<(), CryptoError> {
+        let signature: &Signature = signature.try_into().map_err(|_| CryptoError::InvalidSignatureFormat)?;
+        let public_key: &PubKey = public_key.try_into().map_err(|_| CryptoError::InvalidPublicKey)?;
+        self.verify_signature(signature, message, public_key)
+    }
+}
+
+/// The implementation of a signature scheme
+pub fn signature_scheme(id: SignatureSchemeId) -> &'static dyn SignatureScheme {
+    match id {
+        SignatureSchemeId::Schnorr => &SchnorrVerifier,
+    }
+}
+
+/// Verify a signature with the scheme it was made with
+pub fn verify_with_scheme(
+    id: SignatureSchemeId,
+    signature: &[u8],
+    message: &[u8],
+    public_key: &[u8],
+) -> Result<(), CryptoError> {
+    signature_scheme(id).verify(signature, message, public_key)
+}
+
 /// Validate that a public key is a valid compressed secp256k1 point
 pub fn validate_public_key(pubkey: &PubKey) -> Result<(), CryptoError> {
     use secp256k1::PublicKey;
@@ -188,6 +244,30 @@ mod tests {
 
         assert!(result.is_err(), "Verification should fail with tampered message");
     }
+
+    #[test]
+    fn test_schnorr_scheme_dispatch() {
+        let scheme = signature_scheme(SignatureSchemeId::Schnorr);
+        assert_eq!(scheme.id(), SignatureSchemeId::Schnorr);
+        assert_eq!(SignatureSchemeId::from_code(scheme.id().code()), Some(SignatureSchemeId::Schnorr));
+        assert_eq!(SignatureSchemeId::from_code(1), None);
+
+        let (secret_key, public_key) = generate_keypair();
+        assert_eq!(scheme.public_key(&secret_key).unwrap(), public_key.to_vec());
+        let message = crate::types::signing_message(&public_key, &public_key, 1000, 1);
+        let signature = scheme.sign(&message, &secret_key, &public_key).unwrap();
+        assert_eq!((signature.len(), public_key.len()), (scheme.signature_len(), scheme.public_key_len()));
+
+        // Signatures made through the scheme verify with the plain verifier, and back
+        let fixed: Signature = signature.as_slice().try_into().unwrap();
+        SchnorrVerifier.verify_signature(&fixed, &message, &public_key).unwrap();
+        verify_with_scheme(SignatureSchemeId::Schnorr, &signature, &message, &public_key).unwrap();
+        assert_eq!(
+            verify_with_scheme(SignatureSchemeId::Schnorr, &signature[..64], &message, &public_key),
+            Err(CryptoError::InvalidSignatureFormat)
+        );
+        assert!(verify_with_scheme(SignatureSchemeId::Schnorr, &signature, &message[1..], &public_key).is_err());
+    }
 }
 
 /// Generate a new keypair for testing and development
@@ -245,13 +325,45 @@ pub fn schnorr_sign(
     secret_key_bytes: &[u8; 32],
     issuer_pubkey: &PubKey,
 ) -> Result<Signature, CryptoError> {
-    use secp256k1::Secp256k1;
+    loop {
+        // Generate a random nonce for the Schnorr signature
+        let nonce = SecretKey::new(&mut secp256k1::rand::thread_rng()).secret_bytes();
+        if let Some(signature) = sign_with_nonce(message, secret_key_bytes, issuer_pubkey, &nonce)? {
+            return Ok(signature);
+        }
+        // Retry with new nonce (matching Scala behavior)
+    }
+}
 
-    let secp = Secp256k1::new();
+/// Schnorr signature with a caller-chosen nonce
+///
+/// Returns `None` when the nonce gives a response the Scala verifier rejects
+/// (`z.bitLength > 255`), in which case another nonce must be tried. Reusing a
+/// nonce for two messages reveals the secret key: this is meant for
+/// reproducible test vectors, with nonces derived from the key and message,
+/// and is only available with the `test-support` feature.
+#[cfg(any(test, feature = "test-support"))]
+pub fn schnorr_sign_with_nonce(
+    message: &[u8],
+    secret_key_bytes: &[u8; 32],
+    issuer_pubkey: &PubKey,
+    nonce: &[u8; 32],
+) -> Result<Option<Signature>, CryptoError> {
+    sign_with_nonce(message, secret_key_bytes, issuer_pubkey, nonce)
+}
+
+fn sign_with_nonce(
+    message: &[u8],
+    secret_key_bytes: &[u8; 32],
+    issuer_pubkey: &PubKey,
+    nonce: &[u8; 32],
+) -> Result<Option<Signature>, CryptoError> {
+    let secp = secp();
 
     // Parse the secret key
     let secret_key = SecretKey::from_slice(secret_key_bytes)
         .map_err(|_| CryptoError::InvalidSignature)?;
+    let nonce_secret = SecretKey::from_slice(nonce).map_err(|_| CryptoError::InvalidSignature)?;
 
     // Curve order for secp256k1
     let n = num_bigint::BigUint::from_bytes_be(&[
@@ -260,51 +372,46 @@ pub fn schnorr_sign(
         0x41, 0x41,
     ]);
 
-    loop {
-        // Generate a random nonce for the Schnorr signature
-        let nonce_secret = SecretKey::new(&mut secp256k1::rand::thread_rng());
-        let a_point = secp256k1::PublicKey::from_secret_key(&secp, &nonce_secret);
-        let a_bytes = a_point.serialize();
+    let a_point = secp256k1::PublicKey::from_secret_key(secp, &nonce_secret);
+    let a_bytes = a_point.serialize();
 
-        // Compute challenge e = H(a || message || issuer_pubkey)
-        let e_scalar = compute_challenge(&a_bytes, message, issuer_pubkey)?;
-
-        // Convert scalars to their big integer representations for modular arithmetic
-        let k_big = num_bigint::BigUint::from_bytes_be(&nonce_secret.secret_bytes());
-        let s_big = num_bigint::BigUint::from_bytes_be(&secret_key.secret_bytes());
-        let e_big = num_bigint::BigUint::from_bytes_be(&e_scalar.to_be_bytes());
-
-        // Compute z = k + e * s (mod n)
-        let e_times_s = (&e_big * &s_big) % &n;
-        let z_big = (&k_big + &e_times_s) % &n;
-
-        // Ensure z is in the valid range [1, n-1] and bitLength <= 255
-        // Scala compatibility: reject signatures where z.bitLength > 255 and retry
-        let z_bit_length = z_big.bits();
-        if z_bit_length > 255 || z_big == num_bigint::BigUint::from(0u32) || z_big >= n {
-            // Retry with new nonce (matching Scala behavior)
-            continue;
-        }
+    // Compute challenge e = H(a || message || issuer_pubkey)
+    let e_scalar = compute_challenge(&a_bytes, message, issuer_pubkey)?;
 
-        // Convert back to bytes
-        let z_vec = z_big.to_bytes_be();
-        let mut z_bytes = [0u8; 32];
-        if z_vec.len() > 32 {
-            z_bytes.copy_from_slice(&z_vec[z_vec.len() - 32..]);
-        } else if z_vec.len() < 32 {
-            let start_idx = 32 - z_vec.len();
-            z_bytes[start_idx..].copy_from_slice(&z_vec);
-        } else {
-            z_bytes.copy_from_slice(&z_vec);
-        }
+    // Convert scalars to their big integer representations for modular arithmetic
+    let k_big = num_bigint::BigUint::from_bytes_be(&nonce_secret.secret_bytes());
+    let s_big = num_bigint::BigUint::from_bytes_be(&secret_key.secret_bytes());
+    let e_big = num_bigint::BigUint::from_bytes_be(&e_scalar.to_be_bytes());
+
+    // Compute z = k + e * s (mod n)
+    let e_times_s = (&e_big * &s_big) % &n;
+    let z_big = (&k_big + &e_times_s) % &n;
 
-        // Create the signature (a || z) - 33 bytes for a, 32 bytes for z
-        let mut signature = [0u8; 65];
-        signature[0..33].copy_from_slice(&a_bytes);
-        signature[33..65].copy_from_slice(&z_bytes);
+    // Ensure z is in the valid range [1, n-1] and bitLength <= 255
+    // Scala compatibility: reject signatures where z.bitLength > 255
+    let z_bit_length = z_big.bits();
+    if z_bit_length > 255 || z_big == num_bigint::BigUint::from(0u32) || z_big >= n {
+        return Ok(None);
+    }
 
-        return Ok(signature);
+    // Convert back to bytes
+    let z_vec = z_big.to_bytes_be();
+    let mut z_bytes = [0u8; 32];
+    if z_vec.len() > 32 {
+        z_bytes.copy_from_slice(&z_vec[z_vec.len() - 32..]);
+    } else if z_vec.len() < 32 {
+        let start_idx = 32 - z_vec.len();
+        z_bytes[start_idx..].copy_from_slice(&z_vec);
+    } else {
+        z_bytes.copy_from_slice(&z_vec);
     }
+
+    // Create the signature (a || z) - 33 bytes for a, 32 bytes for z
+    let mut signature = [0u8; 65];
+    signature[0..33].copy_from_slice(&a_bytes);
+    signature[33..65].copy_from_slice(&z_bytes);
+
+    Ok(Some(signature))
 }
 
 /// Schnorr signature verification following chaincash-rs approach
@@ -4,7 +4,14 @@
 pub mod traits;
 pub mod types;
 pub mod impls;
+pub mod note_uri;
+pub mod note_payload;
 
 pub use traits::*;
 pub use types::*;
-pub use impls::*;
\ No newline at end of file
+pub use impls::*;
+pub use note_uri::{NoteUri, NoteUriError, NOTE_URI_PREFIX};
+pub use note_payload::{
+    check_note_payload, decrypt_note_payload, encrypt_note_payload, NotePayloadError, MAX_NOTE_PAYLOAD_LEN,
+    NOTE_PAYLOAD_VERSION,
+};
\ No newline at end of file
@@ -0,0 +1,232 @@
+//! End-to-end encryption of note payloads
+//!
+//! The tracker has to see a note's amounts to commit to them, but a memo or
+//! other details of the payment are only meant for the recipient. The issuer
+//! encrypts them to the recipient's key; the tracker stores the envelope next
+//! to the note and hands it back unopened.
+//!
+//! ```text
+//! envelope = version (1) || nonce (16) || ciphertext || tag (32)
+//! ```
+//!
+//! Both keys are derived from the secp256k1 ECDH secret of the issuer and
+//! recipient keys, which either side can compute from its own secret key and
+//! the other's public key. The ciphertext is the plaintext XORed with
+//! `blake2b256(key || nonce || counter)` blocks. The tag covers the note
+//! timestamp, so an envelope only opens against the note version it was
+//! written for.
+
+use blake2::{Blake2b, Digest};
+use generic_array::typenum::U32;
+use secp256k1::ecdh::SharedSecret;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+use crate::types::PubKey;
+
+/// Envelope format version written by [`encrypt_note_payload`]
+pub const NOTE_PAYLOAD_VERSION: u8 = 1;
+
+/// Largest plaintext accepted, in bytes
+pub const MAX_NOTE_PAYLOAD_LEN: usize = 1024;
+
+const NONCE_LEN: usize = 16;
+const TAG_LEN: usize = 32;
+const HEADER_LEN: usize = 1 + NONCE_LEN;
+const DOMAIN_TAG: &[u8] = b"BASIS-NOTE-PAYLOAD/1";
+
+/// Reasons a payload cannot be sealed or opened
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NotePayloadError {
+    #[error("payload is longer than {MAX_NOTE_PAYLOAD_LEN} bytes")]
+    TooLong,
+    #[error("payload envelope is truncated")]
+    Truncated,
+    #[error("unsupported payload envelope version {0}")]
+    UnsupportedVersion(u8),
+    #[error("invalid key")]
+    InvalidKey,
+    #[error("payload does not open with this key pair, or was altered")]
+    Unauthenticated,
+}
+
+/// Encrypt `plaintext` from the issuer to the recipient of the note with `timestamp`
+pub fn encrypt_note_payload(
+    issuer_secret: &[u8; 32],
+    recipient_pubkey: &PubKey,
+    timestamp: u64,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, NotePayloadError> {
+    if plaintext.len() > MAX_NOTE_PAYLOAD_LEN {
+        return Err(NotePayloadError::TooLong);
+    }
+
+    let secret = SecretKey::from_slice(issuer_secret).map_err(|_| NotePayloadError::InvalidKey)?;
+    let issuer_pubkey = PublicKey::from_secret_key(&Secp256k1::new(), &secret).serialize();
+    let (cipher_key, mac_key) = payload_keys(issuer_secret, recipient_pubkey, &issuer_pubkey, recipient_pubkey)?;
+
+    let nonce: [u8; NONCE_LEN] = rand::random();
+    let mut envelope = Vec::with_capacity(HEADER_LEN + plaintext.len() + TAG_LEN);
+    envelope.push(NOTE_PAYLOAD_VERSION);
+    envelope.extend_from_slice(&nonce);
+    envelope.extend_from_slice(plaintext);
+    apply_keystream(&cipher_key, &nonce, &mut envelope[HEADER_LEN..]);
+    let tag = payload_tag(&mac_key, timestamp, &nonce, &envelope[HEADER_LEN..]);
+    envelope.extend_from_slice(&tag);
+
+    Ok(envelope)
+}
+
+/// Decrypt an envelope the issuer sealed for the note with `timestamp`
+pub fn decrypt_note_payload(
+    recipient_secret: &[u8; 32],
+    issuer_pubkey: &PubKey,
+    timestamp: u64,
+    envelope: &[u8],
+) -> Result<Vec<u8>, NotePayloadError> {
+    check_note_payload(envelope)?;
+
+    let secret = SecretKey::from_slice(recipient_secret).map_err(|_| NotePayloadError::InvalidKey)?;
+    let recipient_pubkey = PublicKey::from_secret_key(&Secp256k1::new(), &secret).serialize();
+    let (cipher_key, mac_key) = payload_keys(recipient_secret, issuer_pubkey, issuer_pubkey, &recipient_pubkey)?;
+
+    let nonce = &envelope[1..HEADER_LEN];
+    let (ciphertext, tag) = envelope[HEADER_LEN..].split_at(envelope.len() - HEADER_LEN - TAG_LEN);
+    let expected = payload_tag(&mac_key, timestamp, nonce, ciphertext);
+    // Compare without an early exit so timing does not reveal the tag
+    if expected.iter().zip(tag).fold(0u8, |acc, (a, b)| acc | (a ^ b)) != 0 {
+        return Err(NotePayloadError::Unauthenticated);
+    }
+
+    let mut plaintext = ciphertext.to_vec();
+    apply_keystream(&cipher_key, nonce, &mut plaintext);
+    Ok(plaintext)
+}
+
+/// Check the shape of an envelope without opening it
+///
+/// This is all a tracker can check, as it holds neither key.
+pub fn check_note_payload(envelope: &[u8]) -> Result<(), NotePayloadError> {
+    let version = *envelope.first().ok_or(NotePayloadError::Truncated)?;
+    if version != NOTE_PAYLOAD_VERSION {
+        return Err(NotePayloadError::UnsupportedVersion(version));
+    }
+    if envelope.len() < HEADER_LEN + TAG_LEN {
+        return Err(NotePayloadError::Truncated);
+    }
+    if envelope.len() - HEADER_LEN - TAG_LEN > MAX_NOTE_PAYLOAD_LEN {
+        return Err(NotePayloadError::TooLong);
+    }
+    Ok(())
+}
+
+/// Cipher and MAC keys shared by the issuer and recipient
+fn payload_keys(
+    own_secret: &[u8; 32],
+    counterparty: &PubKey,
+    issuer_pubkey: &PubKey,
+    recipient_pubkey: &PubKey,
+) -> Result<([u8; 32], [u8; 32]), NotePayloadError> {
+    let secret = SecretKey::from_slice(own_secret).map_err(|_| NotePayloadError::InvalidKey)?;
+    let point = PublicKey::from_slice(counterparty).map_err(|_| NotePayloadError::InvalidKey)?;
+    let shared = SharedSecret::new(&point, &secret);
+
+    let derive = |purpose: u8| {
+        let mut hasher = Blake2b::<U32>::new();
+        hasher.update(DOMAIN_TAG);
+        hasher.update([purpose]);
+        hasher.update(shared.secret_bytes());
+        hasher.update(issuer_pubkey);
+        hasher.update(recipient_pubkey);
+        let mut key = [0u8; 32];
+        key.copy_from_slice(&hasher.finalize());
+        key
+    };
+
+    Ok((derive(1), derive(2)))
+}
+
+fn apply_keystream(key: &[u8; 32], nonce: &[u8], data: &mut [u8]) {
+    for (counter, chunk) in data.chunks_mut(32).enumerate() {
+        let mut hasher = Blake2b::<U32>::new();
+        hasher.update(key);
+        hasher.update(nonce);
+        hasher.update((counter as u32).to_be_bytes());
+        let block = hasher.finalize();
+        for (byte, mask) in chunk.iter_mut().zip(block.iter()) {
+            *byte ^= mask;
+        }
+    }
+}
+
+fn payload_tag(key: &[u8; 32], timestamp: u64, nonce: &[u8], ciphertext: &[u8]) -> [u8; TAG_LEN] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(key);
+    hasher.update(timestamp.to_be_bytes());
+    hasher.update(nonce);
+    hasher.update(ciphertext);
+    let mut tag = [0u8; TAG_LEN];
+    tag.copy_from_slice(&hasher.finalize());
+    tag
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impls::generate_keypair;
+
+    const TIMESTAMP: u64 = 1_743_379_200_000;
+
+    #[test]
+    fn test_recipient_opens_what_issuer_sealed() {
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (recipient_secret, recipient_pubkey) = generate_keypair();
+        let plaintext = br#"{"memo":"invoice 42, coffee beans","amount_hint":"40 kg"}"#;
+
+        let envelope = encrypt_note_payload(&issuer_secret, &recipient_pubkey, TIMESTAMP, plaintext).unwrap();
+        assert_eq!(envelope.len(), HEADER_LEN + plaintext.len() + TAG_LEN);
+        assert!(!envelope.windows(5).any(|w| w == b"invoi"));
+        assert_eq!(check_note_payload(&envelope), Ok(()));
+
+        let opened = decrypt_note_payload(&recipient_secret, &issuer_pubkey, TIMESTAMP, &envelope).unwrap();
+        assert_eq!(opened, plaintext);
+    }
+
+    #[test]
+    fn test_payload_does_not_open_for_others() {
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (recipient_secret, recipient_pubkey) = generate_keypair();
+        let (outsider_secret, _) = generate_keypair();
+        let envelope = encrypt_note_payload(&issuer_secret, &recipient_pubkey, TIMESTAMP, b"memo").unwrap();
+
+        assert_eq!(
+            decrypt_note_payload(&outsider_secret, &issuer_pubkey, TIMESTAMP, &envelope),
+            Err(NotePayloadError::Unauthenticated)
+        );
+        // Bound to the note version it was written for
+        assert_eq!(
+            decrypt_note_payload(&recipient_secret, &issuer_pubkey, TIMESTAMP + 1, &envelope),
+            Err(NotePayloadError::Unauthenticated)
+        );
+
+        let mut altered = envelope.clone();
+        altered[HEADER_LEN] ^= 1;
+        assert_eq!(
+            decrypt_note_payload(&recipient_secret, &issuer_pubkey, TIMESTAMP, &altered),
+            Err(NotePayloadError::Unauthenticated)
+        );
+    }
+
+    #[test]
+    fn test_envelope_shape_checks() {
+        let (issuer_secret, _) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+
+        assert_eq!(check_note_payload(&[]), Err(NotePayloadError::Truncated));
+        assert_eq!(check_note_payload(&[NOTE_PAYLOAD_VERSION; 20]), Err(NotePayloadError::Truncated));
+        assert_eq!(check_note_payload(&[2; 64]), Err(NotePayloadError::UnsupportedVersion(2)));
+        assert_eq!(
+            encrypt_note_payload(&issuer_secret, &recipient_pubkey, TIMESTAMP, &[0; MAX_NOTE_PAYLOAD_LEN + 1]),
+            Err(NotePayloadError::TooLong)
+        );
+    }
+}
@@ -0,0 +1,215 @@
+//! Compact URI encoding of signed notes for out-of-band transfer
+//!
+//! A payer can hand a signed note to the payee as a string or QR code, with
+//! no network connection between them; either party submits it to the
+//! tracker later.
+//!
+//! ```text
+//! basis:note?issuer=<hex>&to=<hex>&amount=<total debt>&ts=<millis>&sig=<hex>[&v=2[&tracker=<hex>]]
+//! ```
+//!
+//! `v` is the note's signing message version, 1 when absent, and `tracker` the
+//! tracker a version 2 note is bound to. Decoding ignores case, so a URI can be
+//! uppercased to fit the denser alphanumeric mode of QR codes.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::impls::schnorr_verify;
+use crate::traits::CryptoError;
+use crate::types::{PubKey, Signature, SigningFormat};
+
+/// Scheme and path every note URI starts with
+pub const NOTE_URI_PREFIX: &str = "basis:note?";
+
+/// Reasons a string is not a note URI
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum NoteUriError {
+    #[error("not a note URI: expected it to start with {NOTE_URI_PREFIX}")]
+    NotANoteUri,
+    #[error("missing parameter '{0}'")]
+    MissingParameter(&'static str),
+    #[error("parameter '{0}' is given more than once")]
+    DuplicateParameter(String),
+    #[error("invalid parameter '{name}': {reason}")]
+    InvalidParameter { name: &'static str, reason: String },
+    #[error("note signature does not verify: {0}")]
+    InvalidSignature(CryptoError),
+}
+
+/// A signed note as carried in a note URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteUri {
+    pub issuer_pubkey: PubKey,
+    pub recipient_pubkey: PubKey,
+    /// Total debt owed to the recipient, in nanoERG
+    pub total_debt: u64,
+    /// Note timestamp in milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub signature: Signature,
+    pub signing_format: SigningFormat,
+}
+
+impl NoteUri {
+    /// Message the issuer signed
+    pub fn signing_message(&self) -> Vec<u8> {
+        self.signing_format.signing_message(
+            &self.issuer_pubkey,
+            &self.recipient_pubkey,
+            self.total_debt,
+            self.timestamp,
+        )
+    }
+
+    /// Check the issuer's signature, so a note can be checked before it reaches a tracker
+    pub fn verify(&self) -> Result<(), NoteUriError> {
+        schnorr_verify(&self.signature, &self.signing_message(), &self.issuer_pubkey)
+            .map_err(NoteUriError::InvalidSignature)
+    }
+}
+
+impl fmt::Display for NoteUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}issuer={}&to={}&amount={}&ts={}&sig={}",
+            NOTE_URI_PREFIX,
+            hex::encode(self.issuer_pubkey),
+            hex::encode(self.recipient_pubkey),
+            self.total_debt,
+            self.timestamp,
+            hex::encode(self.signature)
+        )?;
+        if let SigningFormat::V2 { tracker_id } = &self.signing_format {
+            f.write_str("&v=2")?;
+            if let Some(tracker_id) = tracker_id {
+                write!(f, "&tracker={}", hex::encode(tracker_id))?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for NoteUri {
+    type Err = NoteUriError;
+
+    fn from_str(uri: &str) -> Result<Self, Self::Err> {
+        let uri = uri.trim().to_ascii_lowercase();
+        let query = uri.strip_prefix(NOTE_URI_PREFIX).ok_or(NoteUriError::NotANoteUri)?;
+
+        let mut params: Vec<(&str, &str)> = Vec::new();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (name, value) = pair.split_once('=').unwrap_or((pair, ""));
+            if params.iter().any(|(seen, _)| *seen == name) {
+                return Err(NoteUriError::DuplicateParameter(name.to_string()));
+            }
+            params.push((name, value));
+        }
+        // Unknown parameters are ignored, so later versions can add some
+        let param = |name: &'static str| {
+            params
+                .iter()
+                .find(|(seen, _)| *seen == name)
+                .map(|(_, value)| *value)
+        };
+        let required = |name: &'static str| param(name).ok_or(NoteUriError::MissingParameter(name));
+
+        let signing_format = match param("v").unwrap_or("1") {
+            "1" if param("tracker").is_some() => {
+                return Err(invalid("tracker", "only version 2 notes are bound to a tracker".to_string()))
+            }
+            "1" => SigningFormat::V1,
+            "2" => SigningFormat::V2 {
+                tracker_id: param("tracker").map(|value| decode_hex("tracker", value)).transpose()?,
+            },
+            other => return Err(invalid("v", format!("unsupported version {}", other))),
+        };
+
+        Ok(Self {
+            issuer_pubkey: decode_hex("issuer", required("issuer")?)?,
+            recipient_pubkey: decode_hex("to", required("to")?)?,
+            total_debt: decode_number("amount", required("amount")?)?,
+            timestamp: decode_number("ts", required("ts")?)?,
+            signature: decode_hex("sig", required("sig")?)?,
+            signing_format,
+        })
+    }
+}
+
+fn invalid(name: &'static str, reason: String) -> NoteUriError {
+    NoteUriError::InvalidParameter { name, reason }
+}
+
+fn decode_hex<const N: usize>(name: &'static str, value: &str) -> Result<[u8; N], NoteUriError> {
+    hex::decode(value)
+        .map_err(|e| invalid(name, e.to_string()))?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| invalid(name, format!("expected {} bytes, got {}", N, bytes.len())))
+}
+
+fn decode_number(name: &'static str, value: &str) -> Result<u64, NoteUriError> {
+    value
+        .parse()
+        .map_err(|_| invalid(name, format!("'{}' is not a non-negative integer", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::impls::{generate_keypair, schnorr_sign};
+
+    fn signed(signing_format: SigningFormat) -> NoteUri {
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let mut note = NoteUri {
+            issuer_pubkey,
+            recipient_pubkey,
+            total_debt: 2_500_000_000,
+            timestamp: 1_743_379_200_000,
+            signature: [0u8; 65],
+            signing_format,
+        };
+        note.signature = schnorr_sign(&note.signing_message(), &issuer_secret, &issuer_pubkey).unwrap();
+        note
+    }
+
+    #[test]
+    fn test_note_uri_roundtrip() {
+        for format in [SigningFormat::V1, SigningFormat::V2 { tracker_id: Some([9u8; 32]) }] {
+            let note = signed(format);
+            let uri = note.to_string();
+            assert!(uri.starts_with("basis:note?issuer="));
+            assert_eq!(uri.parse::<NoteUri>(), Ok(note.clone()));
+            // QR codes may carry the URI uppercased
+            assert_eq!(uri.to_ascii_uppercase().parse::<NoteUri>(), Ok(note.clone()));
+            assert!(note.verify().is_ok());
+        }
+
+        let mut tampered = signed(SigningFormat::V1);
+        tampered.total_debt += 1;
+        assert!(matches!(tampered.verify(), Err(NoteUriError::InvalidSignature(_))));
+    }
+
+    #[test]
+    fn test_note_uri_errors() {
+        let uri = signed(SigningFormat::V1).to_string();
+        assert_eq!("bitcoin:abc".parse::<NoteUri>(), Err(NoteUriError::NotANoteUri));
+        assert_eq!(
+            uri.replace("&ts=", "&time=").parse::<NoteUri>(),
+            Err(NoteUriError::MissingParameter("ts"))
+        );
+        assert_eq!(
+            format!("{}&amount=1", uri).parse::<NoteUri>(),
+            Err(NoteUriError::DuplicateParameter("amount".to_string()))
+        );
+        assert!(matches!(
+            uri.replace("&sig=", "&sig=00").parse::<NoteUri>(),
+            Err(NoteUriError::InvalidParameter { name: "sig", .. })
+        ));
+        assert!(matches!(
+            format!("{}&v=3", uri).parse::<NoteUri>(),
+            Err(NoteUriError::InvalidParameter { name: "v", .. })
+        ));
+        assert!(format!("{}&memo=lunch", uri).parse::<NoteUri>().is_ok());
+    }
+}
@@ -1,10 +1,10 @@
 //! Core traits for Basis Tracker system
 
-use crate::types::{PubKey, Signature};
+use crate::types::{PubKey, Signature, SignatureSchemeId};
 use thiserror::Error;
 
 /// Error types for cryptographic operations
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
 pub enum CryptoError {
     #[error("Invalid signature")]
     InvalidSignature,
@@ -25,6 +25,31 @@ pub trait SignatureVerifier {
     fn sign_message(&self, message: &[u8], secret_key: &[u8; 32], public_key: &PubKey) -> Result<Signature, CryptoError>;
 }
 
+/// A signature scheme notes can be signed with
+///
+/// Keys and signatures are byte slices so that schemes with other sizes than
+/// secp256k1 Schnorr fit the same interface. Implementations are looked up
+/// by identifier with [`signature_scheme`](crate::impls::signature_scheme).
+pub trait SignatureScheme: Send + Sync {
+    /// Identifier of the scheme in serialized notes
+    fn id(&self) -> SignatureSchemeId;
+
+    /// Length of an encoded public key
+    fn public_key_len(&self) -> usize;
+
+    /// Length of an encoded signature
+    fn signature_len(&self) -> usize;
+
+    /// Derive the public key of a secret key
+    fn public_key(&self, secret_key: &[u8]) -> Result<Vec<u8>, CryptoError>;
+
+    /// Sign a message with a secret key
+    fn sign(&self, message: &[u8], secret_key: &[u8], public_key: &[u8]) -> Result<Vec<u8>, CryptoError>;
+
+    /// Verify a signature over a message
+    fn verify(&self, signature: &[u8], message: &[u8], public_key: &[u8]) -> Result<(), CryptoError>;
+}
+
 /// Trait for AVL tree operations
 pub trait AvlTree {
     type Error;
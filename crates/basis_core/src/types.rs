@@ -9,6 +9,47 @@ pub type PubKey = [u8; 33];
 /// Signature type (Secp256k1 Schnorr) - 65 bytes (33 for 'a' component, 32 for 'z' component)
 pub type Signature = [u8; 65];
 
+/// Signature scheme a note is signed with
+///
+/// Schnorr over secp256k1 is what the reserve contract verifies and the only
+/// scheme so far. The identifier byte leaves room for schemes that off-chain
+/// peers or a future contract could use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum SignatureSchemeId {
+    #[default]
+    Schnorr,
+}
+
+impl SignatureSchemeId {
+    /// Byte identifying the scheme in serialized notes
+    pub fn code(&self) -> u8 {
+        match self {
+            SignatureSchemeId::Schnorr => 0,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(SignatureSchemeId::Schnorr),
+            _ => None,
+        }
+    }
+
+    /// Name as carried in API requests and responses
+    pub fn name(&self) -> &'static str {
+        match self {
+            SignatureSchemeId::Schnorr => "schnorr_secp256k1",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "schnorr_secp256k1" => Some(SignatureSchemeId::Schnorr),
+            _ => None,
+        }
+    }
+}
+
 /// Generate the signing message following the Basis protocol specification.
 ///
 /// message = blake2b256(ownerKeyBytes || receiverKeyBytes) || longToByteArray(totalDebt) || longToByteArray(timestamp)
@@ -48,4 +89,216 @@ pub fn signing_message(
     message.extend_from_slice(&timestamp.to_be_bytes());
 
     message
-}
\ No newline at end of file
+}
+
+/// Domain separation tag that opens every version 2 signing message
+pub const NOTE_DOMAIN_TAG: &[u8; 16] = b"BASIS-IOU-NOTE/2";
+
+/// Format of the message a note signature covers
+///
+/// Version 1 is the original 48-byte message and the only one the reserve
+/// contract verifies, so it stays the default. Version 2 prefixes a domain
+/// tag and names the issuer and recipient keys explicitly, and may bind the
+/// note to a single tracker so the signature cannot be replayed elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SigningFormat {
+    #[default]
+    V1,
+    V2 {
+        /// Tracker NFT ID the note is bound to, if any
+        tracker_id: Option<[u8; 32]>,
+    },
+}
+
+impl SigningFormat {
+    /// Numeric version as carried in API requests and storage records
+    pub fn version(&self) -> u8 {
+        match self {
+            SigningFormat::V1 => 1,
+            SigningFormat::V2 { .. } => 2,
+        }
+    }
+
+    /// Tracker the note is bound to, if any
+    pub fn tracker_id(&self) -> Option<&[u8; 32]> {
+        match self {
+            SigningFormat::V1 => None,
+            SigningFormat::V2 { tracker_id } => tracker_id.as_ref(),
+        }
+    }
+
+    /// Build the message a note in this format is signed over
+    pub fn signing_message(
+        &self,
+        owner_key: &PubKey,
+        receiver_key: &PubKey,
+        total_debt: u64,
+        timestamp: u64,
+    ) -> Vec<u8> {
+        match self {
+            SigningFormat::V1 => signing_message(owner_key, receiver_key, total_debt, timestamp),
+            SigningFormat::V2 { tracker_id } => {
+                signing_message_v2(owner_key, receiver_key, total_debt, timestamp, tracker_id.as_ref())
+            }
+        }
+    }
+}
+
+/// Generate a version 2 signing message.
+///
+/// message = "BASIS-IOU-NOTE/2" || trackerId || ownerKeyBytes || receiverKeyBytes || longToByteArray(totalDebt) || longToByteArray(timestamp)
+///
+/// An unbound note uses 32 zero bytes as the tracker ID.
+///
+/// # Returns
+/// * 130 bytes: tag (16) || trackerId (32) || ownerKey (33) || receiverKey (33) || totalDebt (8 BE) || timestamp (8 BE)
+pub fn signing_message_v2(
+    owner_key: &PubKey,
+    receiver_key: &PubKey,
+    total_debt: u64,
+    timestamp: u64,
+    tracker_id: Option<&[u8; 32]>,
+) -> Vec<u8> {
+    let mut message = Vec::with_capacity(130);
+    message.extend_from_slice(NOTE_DOMAIN_TAG);
+    message.extend_from_slice(tracker_id.unwrap_or(&[0u8; 32]));
+    message.extend_from_slice(owner_key);
+    message.extend_from_slice(receiver_key);
+    message.extend_from_slice(&total_debt.to_be_bytes());
+    message.extend_from_slice(&timestamp.to_be_bytes());
+
+    message
+}
+
+/// Milliseconds in a day, the compounding period of [`AccrualMethod::CompoundDaily`]
+pub const MILLIS_PER_DAY: u64 = 24 * 60 * 60 * 1000;
+
+/// Milliseconds in the 365-day year interest rates are quoted over
+pub const MILLIS_PER_YEAR: u64 = 365 * MILLIS_PER_DAY;
+
+/// How interest on a note's outstanding debt accrues
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccrualMethod {
+    /// Linear in the elapsed time
+    Simple,
+    /// Compounded once per elapsed whole day
+    CompoundDaily,
+}
+
+impl AccrualMethod {
+    /// Byte identifying the method in signing messages and storage records
+    pub fn code(&self) -> u8 {
+        match self {
+            AccrualMethod::Simple => 0,
+            AccrualMethod::CompoundDaily => 1,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(AccrualMethod::Simple),
+            1 => Some(AccrualMethod::CompoundDaily),
+            _ => None,
+        }
+    }
+
+    /// Name as carried in API requests and responses
+    pub fn name(&self) -> &'static str {
+        match self {
+            AccrualMethod::Simple => "simple",
+            AccrualMethod::CompoundDaily => "compound_daily",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "simple" => Some(AccrualMethod::Simple),
+            "compound_daily" => Some(AccrualMethod::CompoundDaily),
+            _ => None,
+        }
+    }
+}
+
+/// Interest terms an issuer signs into a note
+///
+/// Interest accrues on the outstanding debt from the note's timestamp, so
+/// each newly signed version of a note starts a new accrual period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InterestTerms {
+    /// Annual rate in basis points (1/100 of a percent)
+    pub rate_bps: u32,
+    pub method: AccrualMethod,
+}
+
+impl InterestTerms {
+    /// Length of the encoded terms: rate (4 BE) || method (1)
+    pub const ENCODED_LEN: usize = 5;
+
+    pub fn new(rate_bps: u32, method: AccrualMethod) -> Self {
+        Self { rate_bps, method }
+    }
+
+    /// Encoding appended to a note's signing message and storage record
+    pub fn to_bytes(&self) -> [u8; Self::ENCODED_LEN] {
+        let mut bytes = [0u8; Self::ENCODED_LEN];
+        bytes[..4].copy_from_slice(&self.rate_bps.to_be_bytes());
+        bytes[4] = self.method.code();
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; Self::ENCODED_LEN]) -> Option<Self> {
+        let rate_bps = u32::from_be_bytes(bytes[..4].try_into().ok()?);
+        Some(Self::new(rate_bps, AccrualMethod::from_code(bytes[4])?))
+    }
+
+    /// Interest accrued on `principal` over `elapsed_millis`, rounded down
+    pub fn accrued(&self, principal: u64, elapsed_millis: u64) -> u64 {
+        match self.method {
+            AccrualMethod::Simple => {
+                (principal as u128 * self.rate_bps as u128)
+                    .checked_mul(elapsed_millis as u128)
+                    .map(|interest| interest / (10_000 * MILLIS_PER_YEAR as u128))
+                    .and_then(|interest| u64::try_from(interest).ok())
+                    .unwrap_or(u64::MAX)
+            }
+            AccrualMethod::CompoundDaily => {
+                let days = elapsed_millis / MILLIS_PER_DAY;
+                let daily_rate = self.rate_bps as f64 / 10_000.0 / 365.0;
+                let growth = (days as f64 * daily_rate.ln_1p()).exp_m1();
+                // Float to integer casts saturate, so a runaway accrual stops at u64::MAX
+                (principal as f64 * growth) as u64
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interest_accrual() {
+        let simple = InterestTerms::new(1_000, AccrualMethod::Simple); // 10% a year
+        assert_eq!(simple.accrued(1_000_000, MILLIS_PER_YEAR), 100_000);
+        assert_eq!(simple.accrued(1_000_000, MILLIS_PER_YEAR / 2), 50_000);
+        assert_eq!(simple.accrued(1_000_000, 0), 0);
+
+        // Daily compounding beats simple interest over a year but ignores partial days
+        let compound = InterestTerms::new(1_000, AccrualMethod::CompoundDaily);
+        let yearly = compound.accrued(1_000_000, MILLIS_PER_YEAR);
+        assert!((105_150..=105_160).contains(&yearly), "{}", yearly);
+        assert_eq!(compound.accrued(1_000_000, MILLIS_PER_DAY - 1), 0);
+
+        assert_eq!(InterestTerms::new(u32::MAX, AccrualMethod::Simple).accrued(u64::MAX, u64::MAX), u64::MAX);
+        assert_eq!(InterestTerms::new(u32::MAX, AccrualMethod::CompoundDaily).accrued(u64::MAX, u64::MAX), u64::MAX);
+    }
+
+    #[test]
+    fn test_interest_terms_encoding() {
+        let terms = InterestTerms::new(250, AccrualMethod::CompoundDaily);
+        assert_eq!(terms.to_bytes(), [0, 0, 0, 250, 1]);
+        assert_eq!(InterestTerms::from_bytes(&terms.to_bytes()), Some(terms));
+        assert_eq!(InterestTerms::from_bytes(&[0, 0, 0, 250, 7]), None);
+        assert_eq!(AccrualMethod::from_name("compound_daily"), Some(AccrualMethod::CompoundDaily));
+    }
+}
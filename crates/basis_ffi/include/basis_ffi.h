@@ -0,0 +1,133 @@
+#ifndef BASIS_FFI_H
+#define BASIS_FFI_H
+
+/* Generated by cbindgen from crates/basis_ffi. Do not edit by hand. */
+
+#include <stdint.h>
+#include <stddef.h>
+
+/**
+ * Length of a secret key in bytes
+ */
+#define BASIS_SECRET_KEY_LEN 32
+
+/**
+ * Length of a compressed public key in bytes
+ */
+#define BASIS_PUBKEY_LEN 33
+
+/**
+ * Length of a Schnorr signature in bytes
+ */
+#define BASIS_SIGNATURE_LEN 65
+
+/**
+ * Length of an AVL tree digest in bytes
+ */
+#define BASIS_DIGEST_LEN 33
+
+/**
+ * Result of every exported function
+ */
+typedef enum BasisStatus {
+  BASIS_STATUS_OK = 0,
+  /**
+   * A required pointer was null
+   */
+  BASIS_STATUS_NULL_POINTER = 1,
+  /**
+   * A secret or public key is not a valid secp256k1 key
+   */
+  BASIS_STATUS_INVALID_KEY = 2,
+  /**
+   * The signature is malformed or does not match
+   */
+  BASIS_STATUS_INVALID_SIGNATURE = 3,
+  /**
+   * The proof does not show the expected debt under the given digest
+   */
+  BASIS_STATUS_INVALID_PROOF = 4,
+  /**
+   * Signing failed unexpectedly
+   */
+  BASIS_STATUS_INTERNAL_ERROR = 5,
+} BasisStatus;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Static, NUL-terminated description of a status
+ */
+const char *basis_status_message(enum BasisStatus status);
+
+/**
+ * Generate a new key pair
+ *
+ * # Safety
+ *
+ * `secret_key_out` must point to 32 writable bytes and `pubkey_out` to 33.
+ */
+enum BasisStatus basis_generate_keypair(uint8_t *secret_key_out, uint8_t *pubkey_out);
+
+/**
+ * Derive the compressed public key of a secret key
+ *
+ * # Safety
+ *
+ * `secret_key` must point to 32 readable bytes and `pubkey_out` to 33 writable bytes.
+ */
+enum BasisStatus basis_public_key(const uint8_t *secret_key, uint8_t *pubkey_out);
+
+/**
+ * Sign the total debt owed to a recipient at a timestamp (milliseconds since Unix epoch)
+ *
+ * # Safety
+ *
+ * `secret_key` must point to 32 readable bytes, `recipient` to 33 readable
+ * bytes and `signature_out` to 65 writable bytes.
+ */
+enum BasisStatus basis_sign_note(const uint8_t *secret_key,
+                                 const uint8_t *recipient,
+                                 uint64_t total_debt,
+                                 uint64_t timestamp,
+                                 uint8_t *signature_out);
+
+/**
+ * Check an issuer signature over a note
+ *
+ * # Safety
+ *
+ * `issuer` and `recipient` must point to 33 readable bytes and `signature` to 65.
+ */
+enum BasisStatus basis_verify_note_signature(const uint8_t *issuer,
+                                             const uint8_t *recipient,
+                                             uint64_t total_debt,
+                                             uint64_t timestamp,
+                                             const uint8_t *signature);
+
+/**
+ * Check a tracker lookup proof for a note
+ *
+ * Succeeds when the proof shows `total_debt` stored for the (issuer,
+ * recipient) pair in the tracker tree with root `digest`, e.g. the digest
+ * committed in R5 of the tracker box.
+ *
+ * # Safety
+ *
+ * `digest`, `issuer` and `recipient` must point to 33 readable bytes and
+ * `proof` to `proof_len` readable bytes.
+ */
+enum BasisStatus basis_verify_note_proof(const uint8_t *digest,
+                                         const uint8_t *issuer,
+                                         const uint8_t *recipient,
+                                         uint64_t total_debt,
+                                         const uint8_t *proof,
+                                         size_t proof_len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* BASIS_FFI_H */
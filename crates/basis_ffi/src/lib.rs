@@ -0,0 +1,309 @@
+//! C ABI bindings for mobile wallets
+//!
+//! Exposes the parts of the Basis off-chain note format a wallet needs
+//! without a tracker: key generation, note signing, signature verification
+//! and verification of tracker lookup proofs. Every function takes fixed-size
+//! byte buffers owned by the caller and returns a [`BasisStatus`]; nothing is
+//! allocated across the boundary. The C header is generated by cbindgen into
+//! `include/basis_ffi.h`.
+
+use std::ffi::c_char;
+
+use basis_core::{schnorr_sign, schnorr_verify, signing_message, PubKey};
+use blake2::{Blake2b, Digest};
+use generic_array::typenum::U32;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
+
+/// Length of a secret key in bytes
+pub const BASIS_SECRET_KEY_LEN: usize = 32;
+/// Length of a compressed public key in bytes
+pub const BASIS_PUBKEY_LEN: usize = 33;
+/// Length of a Schnorr signature in bytes
+pub const BASIS_SIGNATURE_LEN: usize = 65;
+/// Length of an AVL tree digest in bytes
+pub const BASIS_DIGEST_LEN: usize = 33;
+
+/// Result of every exported function
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BasisStatus {
+    Ok = 0,
+    /// A required pointer was null
+    NullPointer = 1,
+    /// A secret or public key is not a valid secp256k1 key
+    InvalidKey = 2,
+    /// The signature is malformed or does not match
+    InvalidSignature = 3,
+    /// The proof does not show the expected debt under the given digest
+    InvalidProof = 4,
+    /// Signing failed unexpectedly
+    InternalError = 5,
+}
+
+/// Static, NUL-terminated description of a status
+#[no_mangle]
+pub extern "C" fn basis_status_message(status: BasisStatus) -> *const c_char {
+    let message: &'static [u8] = match status {
+        BasisStatus::Ok => b"ok\0",
+        BasisStatus::NullPointer => b"null pointer\0",
+        BasisStatus::InvalidKey => b"invalid key\0",
+        BasisStatus::InvalidSignature => b"invalid signature\0",
+        BasisStatus::InvalidProof => b"invalid proof\0",
+        BasisStatus::InternalError => b"internal error\0",
+    };
+    message.as_ptr().cast()
+}
+
+// Copy a caller buffer of known length into an array
+unsafe fn read_array<const N: usize>(ptr: *const u8) -> Option<[u8; N]> {
+    if ptr.is_null() {
+        return None;
+    }
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(std::slice::from_raw_parts(ptr, N));
+    Some(bytes)
+}
+
+// Write an array into a caller buffer of the same length
+unsafe fn write_array<const N: usize>(ptr: *mut u8, bytes: &[u8; N]) {
+    std::ptr::copy_nonoverlapping(bytes.as_ptr(), ptr, N);
+}
+
+fn public_key_for(secret_key: &[u8; 32]) -> Option<PubKey> {
+    let key = SecretKey::from_slice(secret_key).ok()?;
+    Some(PublicKey::from_secret_key(&Secp256k1::new(), &key).serialize())
+}
+
+/// AVL tree key of a note: blake2b256(issuer || recipient)
+fn note_key(issuer: &PubKey, recipient: &PubKey) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(issuer);
+    hasher.update(recipient);
+    hasher.finalize().into()
+}
+
+/// Generate a new key pair
+///
+/// # Safety
+///
+/// `secret_key_out` must point to 32 writable bytes and `pubkey_out` to 33.
+#[no_mangle]
+pub unsafe extern "C" fn basis_generate_keypair(secret_key_out: *mut u8, pubkey_out: *mut u8) -> BasisStatus {
+    if secret_key_out.is_null() || pubkey_out.is_null() {
+        return BasisStatus::NullPointer;
+    }
+    let (secret_key, pubkey) = basis_core::generate_keypair();
+    write_array(secret_key_out, &secret_key);
+    write_array(pubkey_out, &pubkey);
+    BasisStatus::Ok
+}
+
+/// Derive the compressed public key of a secret key
+///
+/// # Safety
+///
+/// `secret_key` must point to 32 readable bytes and `pubkey_out` to 33 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn basis_public_key(secret_key: *const u8, pubkey_out: *mut u8) -> BasisStatus {
+    let Some(secret_key) = read_array::<BASIS_SECRET_KEY_LEN>(secret_key) else {
+        return BasisStatus::NullPointer;
+    };
+    if pubkey_out.is_null() {
+        return BasisStatus::NullPointer;
+    }
+    match public_key_for(&secret_key) {
+        Some(pubkey) => {
+            write_array(pubkey_out, &pubkey);
+            BasisStatus::Ok
+        }
+        None => BasisStatus::InvalidKey,
+    }
+}
+
+/// Sign the total debt owed to a recipient at a timestamp (milliseconds since Unix epoch)
+///
+/// # Safety
+///
+/// `secret_key` must point to 32 readable bytes, `recipient` to 33 readable
+/// bytes and `signature_out` to 65 writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn basis_sign_note(
+    secret_key: *const u8,
+    recipient: *const u8,
+    total_debt: u64,
+    timestamp: u64,
+    signature_out: *mut u8,
+) -> BasisStatus {
+    let (Some(secret_key), Some(recipient)) = (
+        read_array::<BASIS_SECRET_KEY_LEN>(secret_key),
+        read_array::<BASIS_PUBKEY_LEN>(recipient),
+    ) else {
+        return BasisStatus::NullPointer;
+    };
+    if signature_out.is_null() {
+        return BasisStatus::NullPointer;
+    }
+    let Some(issuer) = public_key_for(&secret_key) else {
+        return BasisStatus::InvalidKey;
+    };
+    if basis_core::validate_public_key(&recipient).is_err() {
+        return BasisStatus::InvalidKey;
+    }
+
+    let message = signing_message(&issuer, &recipient, total_debt, timestamp);
+    match schnorr_sign(&message, &secret_key, &issuer) {
+        Ok(signature) => {
+            write_array(signature_out, &signature);
+            BasisStatus::Ok
+        }
+        Err(_) => BasisStatus::InternalError,
+    }
+}
+
+/// Check an issuer signature over a note
+///
+/// # Safety
+///
+/// `issuer` and `recipient` must point to 33 readable bytes and `signature` to 65.
+#[no_mangle]
+pub unsafe extern "C" fn basis_verify_note_signature(
+    issuer: *const u8,
+    recipient: *const u8,
+    total_debt: u64,
+    timestamp: u64,
+    signature: *const u8,
+) -> BasisStatus {
+    let (Some(issuer), Some(recipient), Some(signature)) = (
+        read_array::<BASIS_PUBKEY_LEN>(issuer),
+        read_array::<BASIS_PUBKEY_LEN>(recipient),
+        read_array::<BASIS_SIGNATURE_LEN>(signature),
+    ) else {
+        return BasisStatus::NullPointer;
+    };
+    if basis_core::validate_public_key(&issuer).is_err() {
+        return BasisStatus::InvalidKey;
+    }
+
+    let message = signing_message(&issuer, &recipient, total_debt, timestamp);
+    match schnorr_verify(&signature, &message, &issuer) {
+        Ok(()) => BasisStatus::Ok,
+        Err(_) => BasisStatus::InvalidSignature,
+    }
+}
+
+/// Check a tracker lookup proof for a note
+///
+/// Succeeds when the proof shows `total_debt` stored for the (issuer,
+/// recipient) pair in the tracker tree with root `digest`, e.g. the digest
+/// committed in R5 of the tracker box.
+///
+/// # Safety
+///
+/// `digest`, `issuer` and `recipient` must point to 33 readable bytes and
+/// `proof` to `proof_len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn basis_verify_note_proof(
+    digest: *const u8,
+    issuer: *const u8,
+    recipient: *const u8,
+    total_debt: u64,
+    proof: *const u8,
+    proof_len: usize,
+) -> BasisStatus {
+    let (Some(digest), Some(issuer), Some(recipient)) = (
+        read_array::<BASIS_DIGEST_LEN>(digest),
+        read_array::<BASIS_PUBKEY_LEN>(issuer),
+        read_array::<BASIS_PUBKEY_LEN>(recipient),
+    ) else {
+        return BasisStatus::NullPointer;
+    };
+    if proof.is_null() {
+        return BasisStatus::NullPointer;
+    }
+    let proof = std::slice::from_raw_parts(proof, proof_len);
+
+    let key = note_key(&issuer, &recipient);
+    let Ok(operations) = basis_trees::encode_operations(&[
+        basis_trees::ergo_avltree_rust::operation::Operation::Lookup(key.to_vec().into()),
+    ]) else {
+        return BasisStatus::InternalError;
+    };
+
+    match basis_trees::verify_operations(&digest, proof, &operations) {
+        Ok(verified)
+            if verified.digest == digest
+                && verified.results.first().cloned().flatten() == Some(total_debt.to_be_bytes().to_vec()) =>
+        {
+            BasisStatus::Ok
+        }
+        _ => BasisStatus::InvalidProof,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair() -> ([u8; 32], [u8; 33]) {
+        let (mut secret_key, mut pubkey) = ([0u8; 32], [0u8; 33]);
+        let status = unsafe { basis_generate_keypair(secret_key.as_mut_ptr(), pubkey.as_mut_ptr()) };
+        assert_eq!(status, BasisStatus::Ok);
+        (secret_key, pubkey)
+    }
+
+    #[test]
+    fn test_sign_and_verify_note() {
+        let (issuer_secret, issuer) = keypair();
+        let (_, recipient) = keypair();
+
+        let mut derived = [0u8; 33];
+        assert_eq!(
+            unsafe { basis_public_key(issuer_secret.as_ptr(), derived.as_mut_ptr()) },
+            BasisStatus::Ok
+        );
+        assert_eq!(derived, issuer);
+
+        let mut signature = [0u8; 65];
+        let status = unsafe {
+            basis_sign_note(issuer_secret.as_ptr(), recipient.as_ptr(), 700, 1_700_000_000_000, signature.as_mut_ptr())
+        };
+        assert_eq!(status, BasisStatus::Ok);
+
+        let verify = |total_debt| unsafe {
+            basis_verify_note_signature(issuer.as_ptr(), recipient.as_ptr(), total_debt, 1_700_000_000_000, signature.as_ptr())
+        };
+        assert_eq!(verify(700), BasisStatus::Ok);
+        assert_eq!(verify(701), BasisStatus::InvalidSignature);
+        assert_eq!(
+            unsafe { basis_verify_note_signature(std::ptr::null(), recipient.as_ptr(), 700, 0, signature.as_ptr()) },
+            BasisStatus::NullPointer
+        );
+    }
+
+    #[test]
+    fn test_verify_note_proof() {
+        let (_, issuer) = keypair();
+        let (_, recipient) = keypair();
+        let key = note_key(&issuer, &recipient);
+
+        let mut tree = basis_trees::BasisAvlTree::new().unwrap();
+        tree.insert(key.to_vec(), 1_200u64.to_be_bytes().to_vec()).unwrap();
+        tree.insert([7u8; 32].to_vec(), 5u64.to_be_bytes().to_vec()).unwrap();
+        let proof = tree.lookup_proof(&key).unwrap();
+        let digest = tree.root_digest();
+
+        let verify = |total_debt: u64, digest: &[u8; 33]| unsafe {
+            basis_verify_note_proof(
+                digest.as_ptr(),
+                issuer.as_ptr(),
+                recipient.as_ptr(),
+                total_debt,
+                proof.avl_proof.as_ptr(),
+                proof.avl_proof.len(),
+            )
+        };
+        assert_eq!(verify(1_200, &digest), BasisStatus::Ok);
+        assert_eq!(verify(1_199, &digest), BasisStatus::InvalidProof);
+        assert_eq!(verify(1_200, &[0u8; 33]), BasisStatus::InvalidProof);
+    }
+}
@@ -12,11 +12,19 @@
 //! - Schnorr signatures (issuer and tracker)
 //! - AVL tree proofs for debt verification
 //!
-//! When blockchain integration is complete, this will use ergo-lib to build actual transactions
-//! that can be submitted to the Ergo network.
+//! This crate holds the types shared by every transaction builder: the fee and height context,
+//! the errors and the reserve contract's context extension variables (#0-#8).
+//! `basis_store::transaction_builder` assembles redemptions from an IOU note and the boxes it
+//! spends into the serialized unsigned transaction a wallet signs.
 
-use thiserror::Error;
+use std::collections::BTreeMap;
 
+use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ContextExtension as ErgoContextExtension;
+use ergo_lib::ergotree_ir::mir::constant::Constant;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+use ergo_lib::ergotree_ir::types::stype::SType;
+use thiserror::Error;
 
 /// Public key type (Secp256k1)
 pub type PubKey = [u8; 33];
@@ -31,6 +39,196 @@ pub enum TransactionBuilderError {
     Configuration(String),
 }
 
+/// Context extension variables for redemption transaction
+/// Following specs/server/redemption_transaction_format_spec.md
+#[derive(Debug, Clone)]
+pub struct ContextExtension {
+    /// #0: Action byte (action*10 + output_index, 0x00 for redemption at index 0)
+    pub action: u8,
+    /// #1: Receiver's public key (33 bytes compressed)
+    pub receiver_pubkey: Vec<u8>,
+    /// #2: Reserve owner's Schnorr signature (65 bytes)
+    pub reserve_signature: Vec<u8>,
+    /// #3: Total debt amount
+    pub total_debt: u64,
+    /// #4: Payment timestamp (milliseconds since Unix epoch)
+    pub timestamp: u64,
+    /// #5: AVL insert proof for reserve tree
+    pub insert_proof: Vec<u8>,
+    /// #6: Tracker's Schnorr signature (65 bytes)
+    pub tracker_signature: Vec<u8>,
+    /// #7: AVL lookup proof for reserve tree (None for first redemption)
+    pub reserve_lookup_proof: Option<Vec<u8>>,
+    /// #8: AVL lookup proof for tracker tree
+    pub tracker_lookup_proof: Vec<u8>,
+}
+
+impl ContextExtension {
+    /// Context extension of the reserve input, as ergo-lib constants
+    pub fn to_ergo_extension(&self) -> Result<ErgoContextExtension, TransactionBuilderError> {
+        let receiver = EcPoint::sigma_parse_bytes(&self.receiver_pubkey)
+            .map_err(|e| TransactionBuilderError::Configuration(format!("Invalid receiver public key: {}", e)))?;
+
+        let mut extension = ErgoContextExtension::empty();
+        let values = &mut extension.values;
+        values.insert(0, Constant::from(self.action as i8));
+        values.insert(1, Constant::from(receiver));
+        values.insert(2, Constant::from(self.reserve_signature.clone()));
+        values.insert(3, Constant::from(self.total_debt as i64));
+        values.insert(4, Constant::from(self.timestamp as i64));
+        values.insert(5, Constant::from(self.insert_proof.clone()));
+        values.insert(6, Constant::from(self.tracker_signature.clone()));
+        if let Some(proof) = &self.reserve_lookup_proof {
+            values.insert(7, Constant::from(proof.clone()));
+        }
+        values.insert(8, Constant::from(self.tracker_lookup_proof.clone()));
+        Ok(extension)
+    }
+
+    /// Serialized constants (hex) by variable ID, as in an EIP-12 input's `extension`
+    pub fn to_serialized_map(&self) -> Result<BTreeMap<String, String>, TransactionBuilderError> {
+        Ok(self
+            .to_ergo_extension()?
+            .values
+            .iter()
+            .map(|(id, constant)| (id.to_string(), hex::encode(constant.sigma_serialize_bytes())))
+            .collect())
+    }
+}
+
+/// Reserve contract action redeeming a note (`action` in var #0)
+pub const REDEMPTION_ACTION: u8 = 0;
+
+/// Reserve contract action topping up the reserve (`action` in var #0)
+pub const TOP_UP_ACTION: u8 = 1;
+
+/// Context variables the reserve contract reads on redemption, by ID, with their types
+///
+/// Follows contract/basis.es: #7 is optional, all others are required.
+pub fn redemption_var_layout() -> Vec<(u8, SType)> {
+    let bytes = || SType::SColl(Box::new(SType::SByte));
+    vec![
+        (0, SType::SByte),
+        (1, SType::SGroupElement),
+        (2, bytes()),
+        (3, SType::SLong),
+        (4, SType::SLong),
+        (5, bytes()),
+        (6, bytes()),
+        (7, bytes()),
+        (8, bytes()),
+    ]
+}
+
+/// Builder for the context extension of a reserve input
+///
+/// Checks each variable against what the contract expects before it is
+/// encoded: the receiver must be a valid group element and signatures must be
+/// 65 bytes, the 33-byte `a` point followed by the 32-byte `z` scalar.
+#[derive(Debug, Clone, Default)]
+pub struct ContextExtensionBuilder {
+    action: u8,
+    receiver_pubkey: Option<Vec<u8>>,
+    reserve_signature: Option<Vec<u8>>,
+    debt: Option<(u64, u64)>,
+    insert_proof: Option<Vec<u8>>,
+    tracker_signature: Option<Vec<u8>>,
+    reserve_lookup_proof: Option<Vec<u8>>,
+    tracker_lookup_proof: Option<Vec<u8>>,
+}
+
+impl ContextExtensionBuilder {
+    /// Start a redemption whose updated reserve is output `reserve_output_index`
+    pub fn redemption(reserve_output_index: u8) -> Self {
+        Self {
+            action: REDEMPTION_ACTION * 10 + reserve_output_index,
+            ..Self::default()
+        }
+    }
+
+    /// #1: receiver's public key (33 bytes compressed)
+    pub fn receiver(mut self, pubkey: &PubKey) -> Self {
+        self.receiver_pubkey = Some(pubkey.to_vec());
+        self
+    }
+
+    /// #2: reserve owner's signature on `key || totalDebt || timestamp`
+    pub fn reserve_signature(mut self, signature: &[u8]) -> Self {
+        self.reserve_signature = Some(signature.to_vec());
+        self
+    }
+
+    /// #3 and #4: total debt of the note and the timestamp of its latest payment
+    pub fn debt(mut self, total_debt: u64, timestamp: u64) -> Self {
+        self.debt = Some((total_debt, timestamp));
+        self
+    }
+
+    /// #5: AVL proof inserting the new redeemed amount into the reserve tree
+    pub fn insert_proof(mut self, proof: Vec<u8>) -> Self {
+        self.insert_proof = Some(proof);
+        self
+    }
+
+    /// #6: tracker's signature; empty once the emergency period has passed
+    pub fn tracker_signature(mut self, signature: &[u8]) -> Self {
+        self.tracker_signature = Some(signature.to_vec());
+        self
+    }
+
+    /// #7: AVL proof looking up the redeemed amount in the reserve tree, None for first redemption
+    pub fn reserve_lookup_proof(mut self, proof: Option<Vec<u8>>) -> Self {
+        self.reserve_lookup_proof = proof;
+        self
+    }
+
+    /// #8: AVL proof looking up the total debt in the tracker tree
+    pub fn tracker_lookup_proof(mut self, proof: Vec<u8>) -> Self {
+        self.tracker_lookup_proof = Some(proof);
+        self
+    }
+
+    /// Check the variables and assemble the extension
+    pub fn build(self) -> Result<ContextExtension, TransactionBuilderError> {
+        let missing = |var: &str| TransactionBuilderError::Configuration(format!("Context variable {} is required", var));
+
+        let receiver_pubkey = self.receiver_pubkey.ok_or_else(|| missing("#1 (receiver)"))?;
+        EcPoint::sigma_parse_bytes(&receiver_pubkey)
+            .map_err(|e| TransactionBuilderError::Configuration(format!("Invalid receiver public key: {}", e)))?;
+
+        let reserve_signature = self.reserve_signature.ok_or_else(|| missing("#2 (reserve signature)"))?;
+        if reserve_signature.len() != 65 {
+            return Err(TransactionBuilderError::Configuration(
+                "Reserve owner signature must be 65 bytes".to_string(),
+            ));
+        }
+        let tracker_signature = self.tracker_signature.ok_or_else(|| missing("#6 (tracker signature)"))?;
+        if !tracker_signature.is_empty() && tracker_signature.len() != 65 {
+            return Err(TransactionBuilderError::Configuration(
+                "Tracker signature must be 65 bytes, or empty after the emergency period".to_string(),
+            ));
+        }
+        let (total_debt, timestamp) = self.debt.ok_or_else(|| missing("#3 and #4 (debt)"))?;
+        if total_debt > i64::MAX as u64 || timestamp > i64::MAX as u64 {
+            return Err(TransactionBuilderError::Configuration(
+                "Total debt and timestamp must fit in a Long".to_string(),
+            ));
+        }
+
+        Ok(ContextExtension {
+            action: self.action,
+            receiver_pubkey,
+            reserve_signature,
+            total_debt,
+            timestamp,
+            insert_proof: self.insert_proof.ok_or_else(|| missing("#5 (insert proof)"))?,
+            tracker_signature,
+            reserve_lookup_proof: self.reserve_lookup_proof,
+            tracker_lookup_proof: self.tracker_lookup_proof.ok_or_else(|| missing("#8 (tracker lookup proof)"))?,
+        })
+    }
+}
+
 /// Context for transaction building containing blockchain and fee parameters
 ///
 /// This structure holds all the contextual information needed to build a valid
@@ -58,53 +256,10 @@ impl Default for TxContext {
     }
 }
 
-/// Complete redemption transaction data structure
-///
-/// This structure contains all the components needed to build a redemption transaction
-/// that follows the Basis contract specification. The transaction structure is:
-///
-/// - Inputs: [Reserve box] (spent)
-/// - Data Inputs: [Tracker box] (for AVL proof verification)
-/// - Outputs: [Updated reserve box, Redemption output box, Change box (optional)]
-/// - Context Extension: Contract parameters (action, signatures, proofs, amounts)
-#[derive(Debug, Clone)]
-pub struct RedemptionTransactionData {
-    /// Reserve box ID being spent (contains collateral backing the debt)
-    pub reserve_box_id: String,
-    /// Tracker box ID used as data input (contains AVL tree commitment)
-    pub tracker_box_id: String,
-    /// Amount being redeemed from the reserve (debt amount)
-    pub redemption_amount: u64,
-    /// Recipient address where redeemed funds are sent
-    pub recipient_address: String,
-    /// AVL proof bytes proving the debt exists in the tracker's state
-    pub avl_proof: Vec<u8>,
-    /// Issuer's 65-byte Schnorr signature authorizing the redemption
-    pub issuer_signature: Vec<u8>,
-    /// Tracker's 65-byte Schnorr signature validating the debt
-    pub tracker_signature: Vec<u8>,
-    /// Transaction fee in nanoERG
-    pub fee: u64,
-    /// Tracker NFT ID from R6 register (hex-encoded serialized SColl(SByte) format following byte_array_register_serialization.md spec)
-    pub tracker_nft_id: String,
-}
-
-/// Builder for redemption transactions following the Basis contract specification
-///
-/// This builder assembles all components needed for a redemption transaction:
-/// - Validates redemption parameters (sufficient funds, time locks)
-/// - Prepares transaction structure with proper inputs/outputs
-/// - Ensures Schnorr signature compatibility (65-byte format)
-/// - Estimates transaction size for fee calculation
-pub struct RedemptionTransactionBuilder;
-
-impl RedemptionTransactionBuilder {
-
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use basis_core::generate_keypair;
 
     #[test]
     fn test_transaction_context() {
@@ -123,4 +278,100 @@ mod tests {
         assert_eq!(default_context.fee, 1000000);
         assert_eq!(default_context.network_prefix, 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_context_extension_round_trip() {
+        let (_, receiver) = generate_keypair();
+        let extension = ContextExtension {
+            action: 0x00,
+            receiver_pubkey: receiver.to_vec(),
+            reserve_signature: vec![1u8; 65],
+            total_debt: 100000000,
+            timestamp: 1743379200000,
+            insert_proof: vec![0x01, 0x02],
+            tracker_signature: vec![2u8; 65],
+            reserve_lookup_proof: Some(vec![0x05]),
+            tracker_lookup_proof: vec![0x03, 0x04],
+        };
+
+        let ergo_extension = extension.to_ergo_extension().unwrap();
+        let parsed = ErgoContextExtension::sigma_parse_bytes(&ergo_extension.sigma_serialize_bytes()).unwrap();
+        assert_eq!(parsed, ergo_extension);
+        assert_eq!(parsed.values[&0], Constant::from(0i8));
+        assert_eq!(parsed.values[&1], Constant::from(EcPoint::sigma_parse_bytes(&receiver).unwrap()));
+        assert_eq!(parsed.values[&3], Constant::from(100000000i64));
+        assert_eq!(parsed.values[&4], Constant::from(1743379200000i64));
+        assert_eq!(parsed.values[&7], Constant::from(vec![0x05u8]));
+
+        // Longs are ZigZag VLQ and collections carry a VLQ length
+        let serialized = extension.to_serialized_map().unwrap();
+        assert_eq!(serialized["0"], "0200");
+        assert_eq!(serialized["1"], format!("07{}", hex::encode(receiver)));
+        assert_eq!(serialized["2"], format!("0e41{}", "01".repeat(65)));
+        assert_eq!(serialized["3"], "058084af5f");
+        assert_eq!(serialized.len(), 9);
+
+        let invalid = ContextExtension {
+            receiver_pubkey: vec![0x03; 33],
+            ..extension
+        };
+        assert!(matches!(
+            invalid.to_ergo_extension(),
+            Err(TransactionBuilderError::Configuration(_))
+        ));
+    }
+
+    fn builder(receiver: &PubKey) -> ContextExtensionBuilder {
+        ContextExtensionBuilder::redemption(0)
+            .receiver(receiver)
+            .reserve_signature(&[1u8; 65])
+            .debt(100000000, 1743379200000)
+            .insert_proof(vec![0x01, 0x02])
+            .tracker_signature(&[2u8; 65])
+            .tracker_lookup_proof(vec![0x03, 0x04])
+    }
+
+    #[test]
+    fn test_context_extension_builder_matches_var_layout() {
+        let (_, receiver) = generate_keypair();
+        let layout = redemption_var_layout();
+
+        let extension = builder(&receiver)
+            .reserve_lookup_proof(Some(vec![0x05]))
+            .build()
+            .unwrap()
+            .to_ergo_extension()
+            .unwrap();
+        assert_eq!(extension.values.len(), layout.len());
+        for (id, tpe) in &layout {
+            assert_eq!(&extension.values[id].tpe, tpe, "type of context variable #{}", id);
+        }
+
+        // #7 is left out of first redemptions
+        let first = builder(&receiver).build().unwrap().to_ergo_extension().unwrap();
+        assert!(!first.values.contains_key(&7));
+        assert_eq!(first.values.len(), layout.len() - 1);
+
+        // The action byte carries the reserve output index
+        let extension = ContextExtensionBuilder { action: 2, ..builder(&receiver) }.build().unwrap();
+        assert_eq!(extension.action, ContextExtensionBuilder::redemption(2).action);
+        assert_eq!(extension.to_serialized_map().unwrap()["0"], "0202");
+    }
+
+    #[test]
+    fn test_context_extension_builder_rejects_invalid_vars() {
+        let (_, receiver) = generate_keypair();
+        let configuration_error = |builder: ContextExtensionBuilder| {
+            matches!(builder.build(), Err(TransactionBuilderError::Configuration(_)))
+        };
+
+        assert!(configuration_error(ContextExtensionBuilder::redemption(0)));
+        assert!(configuration_error(builder(&receiver).reserve_signature(&[1u8; 64])));
+        assert!(configuration_error(builder(&receiver).tracker_signature(&[2u8; 33])));
+        assert!(configuration_error(builder(&receiver).debt(u64::MAX, 1743379200000)));
+        assert!(configuration_error(builder(&[0x03; 33])));
+
+        // The tracker signature may be empty after the emergency period
+        assert!(builder(&receiver).tracker_signature(&[]).build().is_ok());
+    }
+}
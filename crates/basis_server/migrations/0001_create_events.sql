@@ -0,0 +1,16 @@
+-- Tracker events, with the columns events are queried by pulled out of the
+-- JSON payload so they can be indexed
+CREATE TABLE events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_type TEXT NOT NULL,
+    issuer_pubkey TEXT,
+    recipient_pubkey TEXT,
+    height INTEGER,
+    timestamp INTEGER NOT NULL,
+    payload TEXT NOT NULL
+);
+
+CREATE INDEX idx_events_event_type ON events (event_type, id);
+CREATE INDEX idx_events_issuer_pubkey ON events (issuer_pubkey, id);
+CREATE INDEX idx_events_recipient_pubkey ON events (recipient_pubkey, id);
+CREATE INDEX idx_events_height ON events (height, id);
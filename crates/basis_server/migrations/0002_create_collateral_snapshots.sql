@@ -0,0 +1,13 @@
+-- Periodic snapshots of each issuer's outstanding debt and collateral, for
+-- charting collateralization over time
+CREATE TABLE collateral_snapshots (
+    issuer_pubkey TEXT NOT NULL,
+    timestamp INTEGER NOT NULL,
+    total_debt INTEGER NOT NULL,
+    collateral INTEGER NOT NULL,
+    collateralization_ratio REAL,
+    note_count INTEGER NOT NULL,
+    PRIMARY KEY (issuer_pubkey, timestamp)
+);
+
+CREATE INDEX idx_collateral_snapshots_timestamp ON collateral_snapshots (timestamp);
@@ -0,0 +1,6 @@
+-- Position of every event in the event stream, so subscribers can resume
+-- after the last event they saw; events stored before take their ID
+ALTER TABLE events ADD COLUMN seq INTEGER;
+UPDATE events SET seq = id;
+
+CREATE UNIQUE INDEX idx_events_seq ON events (seq);
@@ -0,0 +1,277 @@
+//! Operator API under /admin/
+//!
+//! Every route here is guarded by `admin_token_layer`: requests need the
+//! `X-Admin-Token` header matching `auth.admin_token`, and the whole API is
+//! refused while no admin token is configured.
+
+use axum::{extract::State, http::StatusCode, Json};
+
+use crate::{
+    issuer_policy::{parse_issuer, IssuerLists},
+    maintenance::MaintenanceRun,
+    models::{
+        error_response, success_response, AdminActionResponse, ApiResponse, IssuerPolicyRequest, IssuerTagsRequest,
+        MaintenanceRequest, MaintenanceStatus, RatesResponse, RemoveRateRequest, RescanRequest, SetRateRequest,
+    },
+    pricing::check_rate,
+    requests,
+    tracker_request::TrackerUnavailable,
+    AppState,
+};
+
+fn done(message: &str) -> AdminActionResponse {
+    AdminActionResponse {
+        message: message.to_string(),
+        ..AdminActionResponse::default()
+    }
+}
+
+/// Commit the current AVL root on-chain without waiting for the commitment schedule
+///
+/// The commitment is made by the tracker box updater, so the request is
+/// accepted rather than completed.
+pub async fn force_commit(State(state): State<AppState>) -> (StatusCode, Json<ApiResponse<AdminActionResponse>>) {
+    tracing::info!("Admin requested an immediate tracker commitment");
+    state.shared_tracker_state.lock().await.request_commitment();
+    (StatusCode::ACCEPTED, Json(success_response(done("Commitment requested"))))
+}
+
+/// Rescan reserve boxes from a height
+pub async fn rescan_reserves(
+    State(state): State<AppState>,
+    Json(payload): Json<RescanRequest>,
+) -> (StatusCode, Json<ApiResponse<AdminActionResponse>>) {
+    tracing::info!("Admin requested a reserve rescan from height {}", payload.from_height);
+    let scanner = state.ergo_scanner.lock().await;
+    match scanner.rescan_from(payload.from_height).await {
+        Ok(()) => (
+            StatusCode::ACCEPTED,
+            Json(success_response(done(&format!("Rescanning from height {}", payload.from_height)))),
+        ),
+        Err(e) => {
+            tracing::warn!("Failed to start rescan: {:?}", e);
+            (StatusCode::CONFLICT, Json(error_response(format!("Failed to start rescan: {}", e))))
+        }
+    }
+}
+
+/// Rebuild the AVL tree from the stored notes and return its new root digest
+pub async fn rebuild_avl_tree(State(state): State<AppState>) -> (StatusCode, Json<ApiResponse<AdminActionResponse>>) {
+    tracing::info!("Admin requested an AVL tree rebuild");
+    match state.send(requests::RebuildAvlTree {}).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(root_digest)) => (
+            StatusCode::OK,
+            Json(success_response(AdminActionResponse {
+                root_digest: Some(hex::encode(root_digest)),
+                ..done("AVL tree rebuilt")
+            })),
+        ),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to rebuild AVL tree: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response(format!("Failed to rebuild AVL tree: {:?}", e))),
+            )
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
+
+/// Move the signing audit log aside so a fresh one is started
+pub async fn rotate_logs(State(state): State<AppState>) -> (StatusCode, Json<ApiResponse<AdminActionResponse>>) {
+    tracing::info!("Admin requested audit log rotation");
+    match state.signing_service.audit_log().rotate().await {
+        Ok(rotated) => {
+            let message = if rotated.is_some() { "Audit log rotated" } else { "Audit log is empty" };
+            (
+                StatusCode::OK,
+                Json(success_response(AdminActionResponse {
+                    rotated_log: rotated.map(|path| path.display().to_string()),
+                    ..done(message)
+                })),
+            )
+        }
+        Err(e) => {
+            tracing::error!("Failed to rotate audit log: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response(format!("Failed to rotate audit log: {}", e))),
+            )
+        }
+    }
+}
+
+/// Flush tracker storage to disk
+pub async fn flush_storage(State(state): State<AppState>) -> (StatusCode, Json<ApiResponse<AdminActionResponse>>) {
+    tracing::info!("Admin requested a storage flush");
+    match state.send(requests::Flush {}).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(())) => (StatusCode::OK, Json(success_response(done("Storage flushed")))),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to flush storage: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response(format!("Failed to flush storage: {:?}", e))),
+            )
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
+
+/// Current database sizes and the outcome of the last maintenance run
+pub async fn get_maintenance(State(state): State<AppState>) -> (StatusCode, Json<ApiResponse<MaintenanceStatus>>) {
+    let status = MaintenanceStatus {
+        storages: state.maintenance.reports(),
+        last_run: state.maintenance.last_run(),
+    };
+    (StatusCode::OK, Json(success_response(status)))
+}
+
+/// Run storage maintenance now and answer with its outcome
+pub async fn run_maintenance(
+    State(state): State<AppState>,
+    Json(payload): Json<MaintenanceRequest>,
+) -> (StatusCode, Json<ApiResponse<MaintenanceRun>>) {
+    let compact = payload.compact.unwrap_or(state.config.maintenance.compact);
+    let repair = payload.repair.unwrap_or(state.config.maintenance.repair);
+    tracing::info!("Admin requested storage maintenance (compact: {}, repair: {})", compact, repair);
+    match state.maintenance.run(&state.tx, "admin", compact, repair).await {
+        Ok(run) => (StatusCode::OK, Json(success_response(run))),
+        Err(busy) => (StatusCode::CONFLICT, Json(error_response(busy.to_string()))),
+    }
+}
+
+/// Allow and deny lists of the issuer policy in force
+pub async fn get_issuer_policy(State(state): State<AppState>) -> (StatusCode, Json<ApiResponse<IssuerLists>>) {
+    (StatusCode::OK, Json(success_response(state.issuer_policy.lists())))
+}
+
+// Apply a change to the issuer policy for one issuer key and answer with the new lists
+fn update_issuer_policy(
+    state: &AppState,
+    issuer_pubkey: &str,
+    change: impl FnOnce(&mut IssuerLists, String),
+) -> (StatusCode, Json<ApiResponse<IssuerLists>>) {
+    let Some(issuer) = parse_issuer(issuer_pubkey) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(error_response("issuer_pubkey must be 33 hex-encoded bytes".to_string())),
+        );
+    };
+    match state.issuer_policy.update(|lists| change(lists, hex::encode(issuer))) {
+        Ok(lists) => (StatusCode::OK, Json(success_response(lists))),
+        Err(e) => {
+            tracing::error!("Failed to save issuer policy: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response(format!("Policy changed but not saved: {}", e))),
+            )
+        }
+    }
+}
+
+/// Put an issuer on the allowlist, taking it off the denylist
+pub async fn allow_issuer(
+    State(state): State<AppState>,
+    Json(payload): Json<IssuerPolicyRequest>,
+) -> (StatusCode, Json<ApiResponse<IssuerLists>>) {
+    tracing::info!("Admin allowed issuer {}", payload.issuer_pubkey);
+    update_issuer_policy(&state, &payload.issuer_pubkey, |lists, key| {
+        lists.deny.remove(&key);
+        lists.allow.insert(key);
+    })
+}
+
+/// Put an issuer on the denylist, taking it off the allowlist
+pub async fn deny_issuer(
+    State(state): State<AppState>,
+    Json(payload): Json<IssuerPolicyRequest>,
+) -> (StatusCode, Json<ApiResponse<IssuerLists>>) {
+    tracing::info!("Admin denied issuer {}", payload.issuer_pubkey);
+    update_issuer_policy(&state, &payload.issuer_pubkey, |lists, key| {
+        lists.allow.remove(&key);
+        lists.deny.insert(key);
+    })
+}
+
+/// Take an issuer off both lists
+pub async fn clear_issuer(
+    State(state): State<AppState>,
+    Json(payload): Json<IssuerPolicyRequest>,
+) -> (StatusCode, Json<ApiResponse<IssuerLists>>) {
+    tracing::info!("Admin cleared issuer {} from the issuer policy", payload.issuer_pubkey);
+    update_issuer_policy(&state, &payload.issuer_pubkey, |lists, key| {
+        lists.allow.remove(&key);
+        lists.deny.remove(&key);
+    })
+}
+
+/// Replace the tags of an issuer key
+pub async fn set_issuer_tags(
+    State(state): State<AppState>,
+    Json(payload): Json<IssuerTagsRequest>,
+) -> (StatusCode, Json<ApiResponse<IssuerLists>>) {
+    tracing::info!("Admin tagged issuer {} with {:?}", payload.issuer_pubkey, payload.tags);
+    let tags = payload.tags;
+    update_issuer_policy(&state, &payload.issuer_pubkey, |lists, key| {
+        if tags.is_empty() {
+            lists.tags.remove(&key);
+        } else {
+            lists.tags.insert(key, tags.into_iter().collect());
+        }
+    })
+}
+
+fn rates_response(state: &AppState) -> RatesResponse {
+    RatesResponse {
+        quote_currency: state.pricing.quote_currency().map(str::to_string),
+        rates: state.pricing.rates(),
+    }
+}
+
+/// Conversion rates in force
+pub async fn get_rates(State(state): State<AppState>) -> (StatusCode, Json<ApiResponse<RatesResponse>>) {
+    (StatusCode::OK, Json(success_response(rates_response(&state))))
+}
+
+/// Set a conversion rate, replacing the rate of the pair in either direction
+pub async fn set_rate(
+    State(state): State<AppState>,
+    Json(payload): Json<SetRateRequest>,
+) -> (StatusCode, Json<ApiResponse<RatesResponse>>) {
+    tracing::info!("Admin set rate {}/{} to {}", payload.base, payload.quote, payload.rate);
+    if let Err(message) = check_rate(&payload.base, &payload.quote, payload.rate) {
+        return (StatusCode::BAD_REQUEST, Json(error_response(message)));
+    }
+    match state.pricing.set_rate(&payload.base, &payload.quote, payload.rate).await {
+        Ok(_) => (StatusCode::OK, Json(success_response(rates_response(&state)))),
+        Err(e) => {
+            tracing::error!("Failed to record rate change: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response(format!("Rate changed but not recorded: {}", e))),
+            )
+        }
+    }
+}
+
+/// Remove a conversion rate
+pub async fn remove_rate(
+    State(state): State<AppState>,
+    Json(payload): Json<RemoveRateRequest>,
+) -> (StatusCode, Json<ApiResponse<RatesResponse>>) {
+    tracing::info!("Admin removed rate {}/{}", payload.base, payload.quote);
+    match state.pricing.remove_rate(&payload.base, &payload.quote).await {
+        Ok(Some(_)) => (StatusCode::OK, Json(success_response(rates_response(&state)))),
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(error_response(format!("No rate for {}/{}", payload.base, payload.quote))),
+        ),
+        Err(e) => {
+            tracing::error!("Failed to record rate change: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response(format!("Rate changed but not recorded: {}", e))),
+            )
+        }
+    }
+}
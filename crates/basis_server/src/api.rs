@@ -4,318 +4,581 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use crate::{
     models::{
-        ApiResponse, CheckAcceptanceRequest, CheckAcceptanceResponse,
+        AcknowledgeNoteRequest, ApiResponse, CheckAcceptanceRequest, CheckAcceptanceResponse,
         CompleteRedemptionRequest, CreateNoteRequest, CreateReserveRequest,
         KeyStatusResponse, ProofResponse, RedeemRequest, RedeemResponse,
         ReserveCreationResponse, ReservePaymentRequest, Asset,
         SerializableIouNote, TrackerEvent, TrackerSignatureRequest,
         TrackerSignatureResponse, RedemptionPreparationRequest,
-        RedemptionPreparationResponse,
+        RedemptionPreparationResponse, ValidationReport,
     },
-    AppState, TrackerCommand,
+    signing_service::{RedemptionSigningRequest, SignedRedemption},
+    requests,
+    AppState,
 };
-use basis_store::{IouNote, NoteError, PubKey, Signature};
-use ergo_lib::ergotree_ir::address::AddressEncoder;
-use basis_store::reqwest;
-use serde::{Deserialize, Serialize};
-
-// Structs for the Schnorr signing API
-#[derive(Serialize, Deserialize)]
-struct SchnorrSignRequest {
-    address: String,
-    message: String,
-}
+use basis_store::{check_note_memo, AccrualMethod, InterestTerms, IouNote, NoteError, PubKey, Signature, SigningFormat};
 
-#[derive(Deserialize)]
-struct SchnorrSignResponse {
-    #[serde(rename = "signedMessage")]
-    signed_message: String,
-    signature: String,
-    #[serde(rename = "publicKey")]
-    public_key: String,
+// Basic handler that responds with a static string
+pub async fn root() -> &'static str {
+    "Hello, Basis Tracker API!"
 }
 
-#[derive(Deserialize)]
-struct ErrorResponse {
-    error: Option<ErrorMessage>,
-}
+// Create a new IOU note
+#[axum::debug_handler]
+pub async fn create_note(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateNoteRequest>,
+) -> (StatusCode, Json<ApiResponse<()>>) {
+    tracing::debug!("Creating new note: {:?}", payload);
+
+    let parsed = note_from_request(&state, &payload)
+        .and_then(|(issuer_pubkey, note)| Ok((issuer_pubkey, note, requested_payload(&payload)?)));
+    let (issuer_pubkey, note, encrypted_payload) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(message)),
+            )
+        }
+    };
+    if let Err(refused) = state.issuer_policy.check(&issuer_pubkey) {
+        tracing::info!("Refused note: {}", refused);
+        return (
+            StatusCode::FORBIDDEN,
+            Json(crate::models::error_response(refused.to_string())),
+        );
+    }
+    let recipient_pubkey = note.recipient_pubkey;
+
+    let request = requests::AddNote {
+        issuer_pubkey,
+        note,
+        encrypted_payload,
+    };
+    match state.send(request).await {
+        Ok(Ok(())) => {
+            tracing::info!(
+                "Successfully created note from {} to {}",
+                hex::encode(&issuer_pubkey),
+                hex::encode(&recipient_pubkey)
+            );
+
+            // Store event in event store
+            let event = TrackerEvent {
+                id: 0, // Will be set by event store
+                seq: 0,
+                event_type: crate::models::EventType::NoteUpdated,
+                timestamp: payload.timestamp,
+                issuer_pubkey: Some(hex::encode(&issuer_pubkey)),
+                recipient_pubkey: Some(hex::encode(&recipient_pubkey)),
+                amount: Some(payload.amount),
+                reserve_box_id: None,
+                collateral_amount: None,
+                redeemed_amount: None,
+                height: None,
+            };
 
-#[derive(Deserialize)]
-struct ErrorMessage {
-    code: String,
-    message: String,
+            match state.event_store.add_event(event).await {
+                Ok(event_id) => {
+                    tracing::debug!("Stored note creation event with ID: {}", event_id);
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to store event: {:?}", e);
+                }
+            }
+
+            (
+                StatusCode::CREATED,
+                Json(crate::models::success_response(())),
+            )
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Failed to create note: {:?}", e);
+            let error_message = note_error_message(&e);
+            (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(error_message)),
+            )
+        }
+        Err(e) => e.response(),
+    }
 }
 
-/// Call the Ergo node's schnorrSign API to generate a tracker signature
-async fn call_schnorr_sign_api(
-    node_url: &str,
-    api_key: Option<&str>,
-    address: &str,
-    message: &str,
-) -> Result<String, String> {
-    let client = reqwest::Client::new();
+// Dry run of note creation: report every reason the note would be rejected
+// without storing it
+#[axum::debug_handler]
+pub async fn validate_note(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateNoteRequest>,
+) -> (StatusCode, Json<ApiResponse<ValidationReport>>) {
+    tracing::debug!("Validating note: {:?}", payload);
+
+    let parsed = note_from_request(&state, &payload)
+        .and_then(|parsed| requested_payload(&payload).map(|_| parsed));
+    let (issuer_pubkey, note) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(message)),
+            )
+        }
+    };
 
-    let request_body = SchnorrSignRequest {
-        address: address.to_string(),
-        message: message.to_string(),
+    let request = requests::ValidateNote {
+        issuer_pubkey,
+        note,
     };
+    match state.send(request).await {
+        Ok(errors) => (
+            StatusCode::OK,
+            Json(crate::models::success_response(validation_report(
+                state
+                    .issuer_policy
+                    .check(&issuer_pubkey)
+                    .err()
+                    .map(|refused| refused.to_string())
+                    .into_iter()
+                    .chain(errors.iter().map(note_error_message))
+                    .collect(),
+            ))),
+        ),
+        Err(e) => e.response(),
+    }
+}
 
-    let url = format!("{}/utils/schnorrSign", node_url.trim_end_matches('/'));
-    let mut request_builder = client.post(&url);
+fn validation_report(errors: Vec<String>) -> ValidationReport {
+    ValidationReport {
+        valid: errors.is_empty(),
+        errors,
+    }
+}
 
-    // Add API key if provided
-    if let Some(key) = api_key {
-        request_builder = request_builder.header("api_key", key);
+// Parse the issuer public key and note of a note creation request
+fn note_from_request(
+    state: &AppState,
+    payload: &CreateNoteRequest,
+) -> Result<(PubKey, IouNote), String> {
+    // Validate and convert hex-encoded strings to fixed-size arrays
+    fn decode<const N: usize>(value: &str, field: &str) -> Result<[u8; N], String> {
+        hex::decode(value)
+            .map_err(|_| format!("{} must be hex-encoded", field))?
+            .try_into()
+            .map_err(|_| format!("{} must be {} bytes", field, N))
     }
 
-    let response = request_builder
-        .json(&request_body)
-        .send()
-        .await
-        .map_err(|e| format!("Failed to send request: {}", e))?;
+    let recipient_pubkey: PubKey = decode(&payload.recipient_pubkey, "recipient_pubkey")?;
+    let signature: Signature = decode(&payload.signature, "signature")?;
+    let issuer_pubkey: PubKey = decode(&payload.issuer_pubkey, "issuer_pubkey")?;
+    let signing_format = requested_signing_format(payload, state.config.ergo.tracker_nft_id.as_deref())?;
+    let interest = requested_interest(payload)?;
+    let memo = requested_memo(payload)?;
 
-    let status = response.status();
-    let response_text = response.text().await
-        .map_err(|e| format!("Failed to read response: {}", e))?;
+    // Create the IOU note
+    let mut note = IouNote::new(
+        recipient_pubkey,
+        payload.amount,
+        0, // amount_redeemed
+        payload.timestamp,
+        signature,
+    )
+    .with_signing_format(signing_format);
+    note.interest = interest;
+    note.memo = memo;
 
-    if status.is_success() {
-        let sign_response: SchnorrSignResponse = serde_json::from_str(&response_text)
-            .map_err(|e| format!("Failed to parse response: {}", e))?;
+    Ok((issuer_pubkey, note))
+}
 
-        Ok(sign_response.signature)
-    } else {
-        // Try to parse error response
-        let error_response: Result<ErrorResponse, _> = serde_json::from_str(&response_text);
-        let error_msg = match error_response {
-            Ok(err_resp) => {
-                if let Some(err) = err_resp.error {
-                    format!("{}: {}", err.code, err.message)
-                } else {
-                    response_text
-                }
-            }
-            Err(_) => response_text,
-        };
-        Err(format!("API error {}: {}", status.as_u16(), error_msg))
+// Encrypted payload attached to a note creation request. The tracker cannot
+// open it, so only the shape of the envelope is checked.
+fn requested_payload(payload: &CreateNoteRequest) -> Result<Option<Vec<u8>>, String> {
+    let Some(encoded) = &payload.encrypted_payload else {
+        return Ok(None);
+    };
+    let envelope = hex::decode(encoded).map_err(|_| "encrypted_payload must be hex-encoded".to_string())?;
+    basis_store::check_note_payload(&envelope).map_err(|e| format!("Invalid encrypted_payload: {}", e))?;
+    Ok(Some(envelope))
+}
+
+// Interest terms declared by a note creation request, which only version 2
+// signatures can cover
+fn requested_interest(payload: &CreateNoteRequest) -> Result<Option<InterestTerms>, String> {
+    let rate_bps = match (payload.interest_rate_bps, &payload.accrual_method) {
+        (Some(rate_bps), _) => rate_bps,
+        (None, None) => return Ok(None),
+        (None, Some(_)) => return Err("accrual_method requires interest_rate_bps".to_string()),
+    };
+    if payload.signing_version != 2 {
+        return Err("interest_rate_bps requires signing_version 2".to_string());
     }
+    let method = match payload.accrual_method.as_deref() {
+        None => AccrualMethod::Simple,
+        Some(name) => AccrualMethod::from_name(name)
+            .ok_or_else(|| format!("Unknown accrual_method '{}'", name))?,
+    };
+    Ok(Some(InterestTerms::new(rate_bps, method)))
 }
 
-/// Verify that a signature from the Ergo node is compatible with the Basis server's verification algorithm
-/// This is needed because the Ergo node's Schnorr implementation has been found to be incompatible
-/// with the Basis server's verification algorithm
-async fn verify_ergo_node_signature_compatibility(
-    signature_hex: &str,
-    message_hex: &str,
-    public_key_bytes: &[u8; 33],
-) -> Result<(), String> {
-    // Decode the signature and message
-    let signature_bytes = hex::decode(signature_hex)
-        .map_err(|e| format!("Failed to decode signature: {}", e))?;
-    let message_bytes = hex::decode(message_hex)
-        .map_err(|e| format!("Failed to decode message: {}", e))?;
-
-    // Check signature length
-    if signature_bytes.len() != 65 {
-        return Err(format!("Signature is not 65 bytes: {}", signature_bytes.len()));
+// Memo declared by a note creation request, which like interest terms only
+// version 2 signatures can cover
+fn requested_memo(payload: &CreateNoteRequest) -> Result<Option<String>, String> {
+    let Some(memo) = &payload.memo else {
+        return Ok(None);
+    };
+    if payload.signing_version != 2 {
+        return Err("memo requires signing_version 2".to_string());
     }
+    check_note_memo(memo)?;
+    Ok(Some(memo.clone()))
+}
 
-    // Convert to fixed-size arrays
-    let mut signature_array = [0u8; 65];
-    signature_array.copy_from_slice(&signature_bytes);
-
-    // Try to verify using the same algorithm as basis_offchain
-    // This will help detect compatibility issues
-    match basis_offchain::schnorr::schnorr_verify(&signature_array, &message_bytes, public_key_bytes) {
-        Ok(()) => {
-            // Verification succeeded
-            Ok(())
-        },
-        Err(_) => {
-            // Verification failed - this indicates the signature is not compatible
-            Err("Signature verification failed with Basis server algorithm".to_string())
-        }
+// Message reported for a rejected note
+fn note_error_message(error: &NoteError) -> String {
+    match error {
+        NoteError::InvalidSignature => "Invalid signature".to_string(),
+        NoteError::AmountOverflow => "Amount overflow".to_string(),
+        NoteError::FutureTimestamp => "Future timestamp".to_string(),
+        NoteError::PastTimestamp => "Past timestamp".to_string(),
+        NoteError::RedemptionTooEarly => "Redemption too early".to_string(),
+        NoteError::InsufficientCollateral => "Insufficient collateral".to_string(),
+        NoteError::StorageError(msg) => format!("Storage error: {}", msg),
+        NoteError::UnsupportedOperation => "Operation not supported".to_string(),
+        NoteError::AmountDecreased => "Amount decreased".to_string(),
+        NoteError::RedeemedExceedsCollected => "Redeemed amount exceeds collected amount".to_string(),
+        NoteError::KeyRotated => "Issuer key has been rotated".to_string(),
+        NoteError::AmountBelowMinimum { minimum } => format!("Amount below the minimum of {}", minimum),
+        NoteError::AmountAboveMaximum { maximum } => format!("Amount above the maximum of {}", maximum),
+        NoteError::TooManyNotes { maximum } => format!("Issuer already holds the maximum of {} notes", maximum),
     }
 }
 
-// Basic handler that responds with a static string
-pub async fn root() -> &'static str {
-    "Hello, Basis Tracker API!"
+// Signing format declared by a note creation request. Version 2 notes bound to
+// a tracker are only accepted by the tracker whose NFT ID they name.
+fn requested_signing_format(
+    payload: &CreateNoteRequest,
+    own_tracker_id: Option<&str>,
+) -> Result<SigningFormat, String> {
+    let tracker_id: Option<[u8; 32]> = match &payload.tracker_id {
+        Some(tracker_id) => Some(
+            hex::decode(tracker_id)
+                .ok()
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or_else(|| "tracker_id must be 32 hex-encoded bytes".to_string())?,
+        ),
+        None => None,
+    };
+
+    match (payload.signing_version, tracker_id) {
+        (1, None) => Ok(SigningFormat::V1),
+        (1, Some(_)) => Err("tracker_id requires signing_version 2".to_string()),
+        (2, Some(tracker_id))
+            if own_tracker_id.and_then(|own| hex::decode(own).ok()).as_deref() != Some(&tracker_id[..]) =>
+        {
+            Err("Note is bound to a different tracker".to_string())
+        }
+        (2, tracker_id) => Ok(SigningFormat::V2 { tracker_id }),
+        (version, _) => Err(format!("Unsupported signing_version {}", version)),
+    }
 }
 
-// Create a new IOU note
+// Attach a recipient acknowledgment (co-signature) to an existing note
 #[axum::debug_handler]
-pub async fn create_note(
+pub async fn acknowledge_note(
     State(state): State<AppState>,
-    Json(payload): Json<CreateNoteRequest>,
-) -> (StatusCode, Json<ApiResponse<()>>) {
-    tracing::debug!("Creating new note: {:?}", payload);
+    Json(payload): Json<AcknowledgeNoteRequest>,
+) -> (StatusCode, Json<ApiResponse<SerializableIouNote>>) {
+    tracing::debug!("Acknowledging note: {:?}", payload);
 
-    // Validate and convert hex-encoded strings to fixed-size arrays
-    let recipient_pubkey_bytes = match hex::decode(&payload.recipient_pubkey) {
-        Ok(bytes) => bytes,
-        Err(_) => {
+    let issuer_pubkey: PubKey = match hex::decode(&payload.issuer_pubkey)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+    {
+        Some(arr) => arr,
+        None => {
             return (
                 StatusCode::BAD_REQUEST,
                 Json(crate::models::error_response(
-                    "recipient_pubkey must be hex-encoded".to_string(),
+                    "issuer_pubkey must be 33 hex-encoded bytes".to_string(),
                 )),
             )
         }
     };
 
-    let recipient_pubkey: PubKey = match recipient_pubkey_bytes.try_into() {
-        Ok(arr) => arr,
-        Err(_) => {
+    let recipient_pubkey: PubKey = match hex::decode(&payload.recipient_pubkey)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+    {
+        Some(arr) => arr,
+        None => {
             return (
                 StatusCode::BAD_REQUEST,
                 Json(crate::models::error_response(
-                    "recipient_pubkey must be 33 bytes".to_string(),
+                    "recipient_pubkey must be 33 hex-encoded bytes".to_string(),
                 )),
             )
         }
     };
 
-    let signature_bytes = match hex::decode(&payload.signature) {
-        Ok(bytes) => bytes,
-        Err(_) => {
+    let recipient_signature: Signature = match hex::decode(&payload.recipient_signature)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+    {
+        Some(arr) => arr,
+        None => {
             return (
                 StatusCode::BAD_REQUEST,
                 Json(crate::models::error_response(
-                    "signature must be hex-encoded".to_string(),
+                    "recipient_signature must be 65 hex-encoded bytes".to_string(),
                 )),
             )
         }
     };
 
-    let signature: Signature = match signature_bytes.try_into() {
-        Ok(arr) => arr,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(crate::models::error_response(
-                    "signature must be 65 bytes".to_string(),
-                )),
+    let request = requests::AcknowledgeNote {
+        issuer_pubkey,
+        recipient_pubkey,
+        recipient_signature,
+    };
+    match state.send(request).await {
+        Ok(Ok(note)) => {
+            tracing::info!(
+                "Recipient {} acknowledged note from {}",
+                payload.recipient_pubkey,
+                payload.issuer_pubkey
+            );
+            let mut serializable_note = SerializableIouNote::from(note);
+            serializable_note.issuer_pubkey = payload.issuer_pubkey.clone();
+            (
+                StatusCode::OK,
+                Json(crate::models::success_response(serializable_note)),
             )
         }
-    };
+        Ok(Err(e)) => {
+            tracing::error!("Failed to acknowledge note: {:?}", e);
+            let (status, error_message) = match e {
+                NoteError::InvalidSignature => {
+                    (StatusCode::BAD_REQUEST, "Invalid recipient signature".to_string())
+                }
+                NoteError::StorageError(msg) if msg == "Note not found" => {
+                    (StatusCode::NOT_FOUND, "Note not found".to_string())
+                }
+                NoteError::StorageError(msg) => {
+                    (StatusCode::INTERNAL_SERVER_ERROR, format!("Storage error: {}", msg))
+                }
+                other => (StatusCode::BAD_REQUEST, format!("{:?}", other)),
+            };
+            (status, Json(crate::models::error_response(error_message)))
+        }
+        Err(e) => e.response(),
+    }
+}
 
-    let issuer_pubkey_bytes = match hex::decode(&payload.issuer_pubkey) {
-        Ok(bytes) => bytes,
-        Err(_) => {
+// Get the recorded versions of a note, oldest first
+// Query: issuer, recipient (hex public keys), page (default 0), page_size (default 20)
+#[axum::debug_handler]
+pub async fn get_note_history(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<crate::models::NoteHistoryResponse>>) {
+    tracing::debug!("Getting note history with params: {:?}", params);
+
+    let parse_pubkey = |name: &str| -> Result<PubKey, String> {
+        let value = params
+            .get(name)
+            .ok_or_else(|| format!("{} parameter is required", name))?;
+        hex::decode(value)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| format!("{} must be 33 bytes hex-encoded", name))
+    };
+
+    let (issuer_pubkey, recipient_pubkey) = match (parse_pubkey("issuer"), parse_pubkey("recipient")) {
+        (Ok(issuer), Ok(recipient)) => (issuer, recipient),
+        (Err(message), _) | (_, Err(message)) => {
             return (
                 StatusCode::BAD_REQUEST,
-                Json(crate::models::error_response(
-                    "issuer_pubkey must be hex-encoded".to_string(),
-                )),
-            )
+                Json(crate::models::error_response(message)),
+            );
         }
     };
 
-    let issuer_pubkey: PubKey = match issuer_pubkey_bytes.try_into() {
-        Ok(arr) => arr,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
+    // Parse pagination parameters with defaults
+    let page: usize = params.get("page").and_then(|p| p.parse().ok()).unwrap_or(0);
+    let page_size: usize = params
+        .get("page_size")
+        .and_then(|ps| ps.parse().ok())
+        .unwrap_or(20);
+
+    let request = requests::GetNoteHistory {
+        issuer_pubkey,
+        recipient_pubkey,
+        offset: page.saturating_mul(page_size),
+        limit: page_size,
+    };
+    match state.send(request).await {
+        Ok(Ok((notes, total_versions))) => {
+            let issuer_hex = hex::encode(issuer_pubkey);
+            let notes = notes
+                .into_iter()
+                .map(|note| {
+                    let mut serializable = SerializableIouNote::from(note);
+                    serializable.issuer_pubkey = issuer_hex.clone();
+                    serializable
+                })
+                .collect();
+
+            (
+                StatusCode::OK,
+                Json(crate::models::success_response(
+                    crate::models::NoteHistoryResponse {
+                        issuer_pubkey: issuer_hex,
+                        recipient_pubkey: hex::encode(recipient_pubkey),
+                        total_versions,
+                        page,
+                        page_size,
+                        notes,
+                    },
+                )),
+            )
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get note history: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
                 Json(crate::models::error_response(
-                    "issuer_pubkey must be 33 bytes".to_string(),
+                    "Failed to retrieve note history".to_string(),
                 )),
             )
         }
-    };
-
-    // Create the IOU note
-    let note = IouNote::new(
-        recipient_pubkey,
-        payload.amount,
-        0, // amount_redeemed
-        payload.timestamp,
-        signature,
-    );
+        Err(e) => e.response(),
+    }
+}
 
-    // Send command to tracker thread
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+// Search notes with any combination of filters
+// Query: issuer, recipient (hex public keys), min_amount, max_amount,
+// from_timestamp, to_timestamp, outstanding (true/false), sort (timestamp|amount,
+// default timestamp), order (asc|desc, default desc), page (default 0),
+// page_size (default 20, at most 1000)
+#[axum::debug_handler]
+pub async fn search_notes(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<crate::models::NoteSearchResponse>>) {
+    use basis_store::note_search::{NoteQuery, NoteSortField};
+
+    tracing::debug!("Searching notes with params: {:?}", params);
+
+    let parse_query = || -> Result<NoteQuery, String> {
+        let pubkey = |name: &str| -> Result<Option<PubKey>, String> {
+            params
+                .get(name)
+                .map(|value| {
+                    hex::decode(value)
+                        .ok()
+                        .and_then(|bytes| bytes.try_into().ok())
+                        .ok_or_else(|| format!("{} must be 33 bytes hex-encoded", name))
+                })
+                .transpose()
+        };
+        let number = |name: &str| -> Result<Option<u64>, String> {
+            params
+                .get(name)
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| format!("{} must be a non-negative integer", name))
+                })
+                .transpose()
+        };
 
-    if let Err(e) = state
-        .tx
-        .send(crate::TrackerCommand::AddNote {
-            issuer_pubkey,
-            note,
-            response_tx,
+        let sort = match params.get("sort").map(String::as_str) {
+            None | Some("timestamp") => NoteSortField::Timestamp,
+            Some("amount") => NoteSortField::Amount,
+            Some(other) => return Err(format!("sort must be 'timestamp' or 'amount', got '{}'", other)),
+        };
+        let descending = match params.get("order").map(String::as_str) {
+            None | Some("desc") => true,
+            Some("asc") => false,
+            Some(other) => return Err(format!("order must be 'asc' or 'desc', got '{}'", other)),
+        };
+        let outstanding_only = match params.get("outstanding").map(String::as_str) {
+            None | Some("false") => false,
+            Some("true") => true,
+            Some(other) => return Err(format!("outstanding must be 'true' or 'false', got '{}'", other)),
+        };
+        let page = number("page")?.unwrap_or(0) as usize;
+        let page_size = number("page_size")?.unwrap_or(20) as usize;
+        if page_size == 0 || page_size > 1000 {
+            return Err("page_size must be between 1 and 1000".to_string());
+        }
+        let memo = params.get("memo").cloned();
+        if let Some(memo) = &memo {
+            check_note_memo(memo)?;
+        }
+
+        Ok(NoteQuery {
+            issuer: pubkey("issuer")?,
+            recipient: pubkey("recipient")?,
+            min_amount: number("min_amount")?,
+            max_amount: number("max_amount")?,
+            from_timestamp: number("from_timestamp")?,
+            to_timestamp: number("to_timestamp")?,
+            outstanding_only,
+            memo,
+            sort,
+            descending,
+            offset: page.saturating_mul(page_size),
+            limit: page_size,
         })
-        .await
-    {
-        tracing::error!("Failed to send to tracker thread: {:?}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
+    };
 
-    // Wait for response from tracker thread
-    match response_rx.await {
-        Ok(Ok(())) => {
-            tracing::info!(
-                "Successfully created note from {} to {}",
-                hex::encode(&issuer_pubkey),
-                hex::encode(&recipient_pubkey)
+    let query = match parse_query() {
+        Ok(query) => query,
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(message)),
             );
+        }
+    };
+    let page_size = query.limit;
+    let page = query.offset / page_size;
 
-            // Store event in event store
-            let event = TrackerEvent {
-                id: 0, // Will be set by event store
-                event_type: crate::models::EventType::NoteUpdated,
-                timestamp: payload.timestamp,
-                issuer_pubkey: Some(hex::encode(&issuer_pubkey)),
-                recipient_pubkey: Some(hex::encode(&recipient_pubkey)),
-                amount: Some(payload.amount),
-                reserve_box_id: None,
-                collateral_amount: None,
-                redeemed_amount: None,
-                height: None,
-            };
-
-            match state.event_store.add_event(event).await {
-                Ok(event_id) => {
-                    tracing::debug!("Stored note creation event with ID: {}", event_id);
-                }
-                Err(e) => {
-                    tracing::warn!("Failed to store event: {:?}", e);
-                }
-            }
+    match state.send(requests::SearchNotes { query }).await {
+        Ok(Ok(result)) => {
+            let notes = result
+                .notes
+                .into_iter()
+                .map(|(issuer_pubkey, note)| {
+                    let mut serializable = SerializableIouNote::from(note);
+                    serializable.issuer_pubkey = hex::encode(issuer_pubkey);
+                    serializable
+                })
+                .collect();
 
             (
-                StatusCode::CREATED,
-                Json(crate::models::success_response(())),
+                StatusCode::OK,
+                Json(crate::models::success_response(
+                    crate::models::NoteSearchResponse {
+                        total: result.total,
+                        page,
+                        page_size,
+                        notes,
+                    },
+                )),
             )
         }
         Ok(Err(e)) => {
-            tracing::error!("Failed to create note: {:?}", e);
-            let error_message = match e {
-                NoteError::InvalidSignature => "Invalid signature".to_string(),
-                NoteError::AmountOverflow => "Amount overflow".to_string(),
-                NoteError::FutureTimestamp => "Future timestamp".to_string(),
-                NoteError::PastTimestamp => "Past timestamp".to_string(),
-                NoteError::RedemptionTooEarly => "Redemption too early".to_string(),
-                NoteError::InsufficientCollateral => "Insufficient collateral".to_string(),
-                NoteError::StorageError(msg) => format!("Storage error: {}", msg),
-                NoteError::UnsupportedOperation => "Operation not supported".to_string(),
-            };
-            (
-                StatusCode::BAD_REQUEST,
-                Json(crate::models::error_response(error_message)),
-            )
-        }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
+            tracing::error!("Failed to search notes: {:?}", e);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(crate::models::error_response(
-                    "Internal server error".to_string(),
+                    "Failed to search notes".to_string(),
                 )),
             )
         }
+        Err(e) => e.response(),
     }
 }
 
@@ -353,32 +616,9 @@ pub async fn get_notes_by_issuer(
         }
     };
 
-    // Send command to tracker thread
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-
     tracing::debug!("Sending GetNotesByIssuer command to tracker thread");
 
-    if let Err(e) = state
-        .tx
-        .send(crate::TrackerCommand::GetNotesByIssuer {
-            issuer_pubkey,
-            response_tx,
-        })
-        .await
-    {
-        tracing::error!("Failed to send to tracker thread: {:?}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
-
-    tracing::debug!("GetNotesByIssuer command sent successfully");
-
-    // Wait for response from tracker thread
-    match response_rx.await {
+    match state.send(requests::GetNotesByIssuer { issuer_pubkey }).await {
         Ok(Ok(notes)) => {
             tracing::info!(
                 "Successfully retrieved {} notes for issuer {}",
@@ -412,30 +652,13 @@ pub async fn get_notes_by_issuer(
         }
         Ok(Err(e)) => {
             tracing::error!("Failed to get notes: {:?}", e);
-            let error_message = match e {
-                NoteError::InvalidSignature => "Invalid signature".to_string(),
-                NoteError::AmountOverflow => "Amount overflow".to_string(),
-                NoteError::FutureTimestamp => "Future timestamp".to_string(),
-                NoteError::PastTimestamp => "Past timestamp".to_string(),
-                NoteError::RedemptionTooEarly => "Redemption too early".to_string(),
-                NoteError::InsufficientCollateral => "Insufficient collateral".to_string(),
-                NoteError::StorageError(msg) => format!("Storage error: {}", msg),
-                NoteError::UnsupportedOperation => "Operation not supported".to_string(),
-            };
+            let error_message = note_error_message(&e);
             (
                 StatusCode::BAD_REQUEST,
                 Json(crate::models::error_response(error_message)),
             )
         }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Internal server error".to_string(),
-                )),
-            )
-        }
+        Err(e) => e.response(),
     }
 }
 
@@ -473,28 +696,7 @@ pub async fn get_notes_by_recipient(
         }
     };
 
-    // Send command to tracker thread
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-
-    if let Err(e) = state
-        .tx
-        .send(crate::TrackerCommand::GetNotesByRecipientWithIssuer {
-            recipient_pubkey,
-            response_tx,
-        })
-        .await
-    {
-        tracing::error!("Failed to send to tracker thread: {:?}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
-
-    // Wait for response from tracker thread
-    match response_rx.await {
+    match state.send(requests::GetNotesByRecipientWithIssuer { recipient_pubkey }).await {
         Ok(Ok(notes_with_issuer)) => {
             tracing::info!(
                 "Successfully retrieved {} notes for recipient {}",
@@ -518,30 +720,13 @@ pub async fn get_notes_by_recipient(
         }
         Ok(Err(e)) => {
             tracing::error!("Failed to get notes: {:?}", e);
-            let error_message = match e {
-                NoteError::InvalidSignature => "Invalid signature".to_string(),
-                NoteError::AmountOverflow => "Amount overflow".to_string(),
-                NoteError::FutureTimestamp => "Future timestamp".to_string(),
-                NoteError::PastTimestamp => "Past timestamp".to_string(),
-                NoteError::RedemptionTooEarly => "Redemption too early".to_string(),
-                NoteError::InsufficientCollateral => "Insufficient collateral".to_string(),
-                NoteError::StorageError(msg) => format!("Storage error: {}", msg),
-                NoteError::UnsupportedOperation => "Operation not supported".to_string(),
-            };
+            let error_message = note_error_message(&e);
             (
                 StatusCode::BAD_REQUEST,
                 Json(crate::models::error_response(error_message)),
             )
         }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Internal server error".to_string(),
-                )),
-            )
-        }
+        Err(e) => e.response(),
     }
 }
 
@@ -610,28 +795,11 @@ pub async fn get_note_by_issuer_and_recipient(
         }
     };
 
-    // Send command to tracker thread
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-
-    if let Err(_) = state
-        .tx
-        .send(crate::TrackerCommand::GetNoteByIssuerAndRecipient {
-            issuer_pubkey,
-            recipient_pubkey,
-            response_tx,
-        })
-        .await
-    {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
-
-    // Wait for response from tracker thread
-    match response_rx.await {
+    let request = requests::GetNoteByIssuerAndRecipient {
+        issuer_pubkey,
+        recipient_pubkey,
+    };
+    match state.send(request).await {
         Ok(Ok(Some(note))) => {
             tracing::info!(
                 "Successfully retrieved note from {} to {}",
@@ -659,30 +827,13 @@ pub async fn get_note_by_issuer_and_recipient(
         }
         Ok(Err(e)) => {
             tracing::error!("Failed to get note: {:?}", e);
-            let error_message = match e {
-                NoteError::InvalidSignature => "Invalid signature".to_string(),
-                NoteError::AmountOverflow => "Amount overflow".to_string(),
-                NoteError::FutureTimestamp => "Future timestamp".to_string(),
-                NoteError::PastTimestamp => "Past timestamp".to_string(),
-                NoteError::RedemptionTooEarly => "Redemption too early".to_string(),
-                NoteError::InsufficientCollateral => "Insufficient collateral".to_string(),
-                NoteError::StorageError(msg) => format!("Storage error: {}", msg),
-                NoteError::UnsupportedOperation => "Operation not supported".to_string(),
-            };
+            let error_message = note_error_message(&e);
             (
                 StatusCode::BAD_REQUEST,
                 Json(crate::models::error_response(error_message)),
             )
         }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Internal server error".to_string(),
-                )),
-            )
-        }
+        Err(e) => e.response(),
     }
 }
 
@@ -693,26 +844,7 @@ pub async fn get_all_notes(
 ) -> (StatusCode, Json<ApiResponse<Vec<crate::models::SerializableIouNoteWithAge>>>) {
     tracing::debug!("Getting all notes");
 
-    // Send command to tracker thread
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-
-    if let Err(_) = state
-        .tx
-        .send(crate::TrackerCommand::GetNotes {
-            response_tx,
-        })
-        .await
-    {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
-
-    // Wait for response from tracker thread
-    match response_rx.await {
+    match state.send(requests::GetNotes {}).await {
         Ok(Ok(notes_with_issuer)) => {
             tracing::info!("Successfully retrieved {} notes", notes_with_issuer.len());
 
@@ -723,18 +855,9 @@ pub async fn get_all_notes(
                 .as_millis() as u64;
 
             let serializable_notes: Vec<crate::models::SerializableIouNoteWithAge> = notes_with_issuer
-                .into_iter()
+                .iter()
                 .map(|(issuer_pubkey, note)| {
-                    let age_seconds = current_time_ms.saturating_sub(note.timestamp) / 1000;
-                    crate::models::SerializableIouNoteWithAge {
-                        issuer_pubkey: hex::encode(issuer_pubkey),
-                        recipient_pubkey: hex::encode(note.recipient_pubkey),
-                        amount_collected: note.amount_collected,
-                        amount_redeemed: note.amount_redeemed,
-                        timestamp: note.timestamp,
-                        signature: hex::encode(note.signature),
-                        age_seconds,
-                    }
+                    crate::models::SerializableIouNoteWithAge::new(issuer_pubkey, note, current_time_ms)
                 })
                 .collect();
 
@@ -745,30 +868,13 @@ pub async fn get_all_notes(
         }
         Ok(Err(e)) => {
             tracing::error!("Failed to get all notes: {:?}", e);
-            let error_message = match e {
-                NoteError::InvalidSignature => "Invalid signature".to_string(),
-                NoteError::AmountOverflow => "Amount overflow".to_string(),
-                NoteError::FutureTimestamp => "Future timestamp".to_string(),
-                NoteError::PastTimestamp => "Past timestamp".to_string(),
-                NoteError::RedemptionTooEarly => "Redemption too early".to_string(),
-                NoteError::InsufficientCollateral => "Insufficient collateral".to_string(),
-                NoteError::StorageError(msg) => format!("Storage error: {}", msg),
-                NoteError::UnsupportedOperation => "Operation not supported".to_string(),
-            };
+            let error_message = note_error_message(&e);
             (
                 StatusCode::BAD_REQUEST,
                 Json(crate::models::error_response(error_message)),
             )
         }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Internal server error".to_string(),
-                )),
-            )
-        }
+        Err(e) => e.response(),
     }
 }
 
@@ -807,8 +913,7 @@ pub async fn check_acceptance(
 
         // Get the acceptance predicate from state
         let result = if let Some(predicate) = &state.acceptance_predicate {
-            // Clone reserve tracker from mutex
-            let reserve_tracker = state.reserve_tracker.lock().await.clone();
+            let reserve_tracker = state.reserve_tracker.clone();
             
             // Build context
             let ctx = crate::acceptance::PredicateContext {
@@ -857,7 +962,30 @@ pub async fn check_acceptance(
     )
 }
 
+/// Filters of an events request: type (event type name), pubkey (issuer or
+/// recipient), from_height, to_height and since_seq (only events after that
+/// sequence number)
+pub(crate) fn event_filters(params: &HashMap<String, String>) -> Result<crate::store::EventQuery, String> {
+    let number = |name: &str, what: &str| -> Result<Option<u64>, String> {
+        params
+            .get(name)
+            .map(|value| value.parse().map_err(|_| format!("{} must be {}", name, what)))
+            .transpose()
+    };
+    Ok(crate::store::EventQuery {
+        event_type: params.get("type").cloned(),
+        pubkey: params.get("pubkey").map(|pubkey| pubkey.to_lowercase()),
+        from_height: number("from_height", "a block height")?,
+        to_height: number("to_height", "a block height")?,
+        since_seq: number("since_seq", "an event sequence number")?,
+        ..crate::store::EventQuery::default()
+    })
+}
+
 // Get paginated tracker events from event store
+// Query: page (default 0), page_size (default 20), and optional filters type
+// (event type name), pubkey (issuer or recipient), from_height, to_height,
+// since_seq
 #[axum::debug_handler]
 pub async fn get_events_paginated(
     State(state): State<AppState>,
@@ -866,18 +994,29 @@ pub async fn get_events_paginated(
     tracing::debug!("Getting paginated events: {:?}", params);
 
     // Parse pagination parameters with defaults
-    let page = params.get("page").and_then(|p| p.parse().ok()).unwrap_or(0);
-    let page_size = params
+    let page: usize = params.get("page").and_then(|p| p.parse().ok()).unwrap_or(0);
+    let page_size: usize = params
         .get("page_size")
         .and_then(|ps| ps.parse().ok())
         .unwrap_or(20);
 
+    // Optional filters, served by the event store's indexes
+    let query = match event_filters(&params) {
+        Ok(filters) => crate::store::EventQuery {
+            offset: page.saturating_mul(page_size),
+            limit: page_size,
+            ..filters
+        },
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(message)),
+            );
+        }
+    };
+
     // Get events from event store
-    let events = match state
-        .event_store
-        .get_events_paginated(page, page_size)
-        .await
-    {
+    let events = match state.event_store.query_events(&query).await {
         Ok(events) => events,
         Err(e) => {
             tracing::error!("Failed to retrieve events: {:?}", e);
@@ -903,15 +1042,84 @@ pub async fn get_events_paginated(
     )
 }
 
+// Get the timeline of one issuer: note updates, reserve changes, redemptions
+// and alerts concerning the issuer, oldest first
+// Query: page (default 0), page_size (default 50, at most 1000)
+#[axum::debug_handler]
+pub async fn get_issuer_events(
+    State(state): State<AppState>,
+    axum::extract::Path(pubkey_hex): axum::extract::Path<String>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<Vec<TrackerEvent>>>) {
+    tracing::debug!("Getting events for issuer: {}", pubkey_hex);
+
+    let issuer_pubkey = match hex::decode(&pubkey_hex) {
+        Ok(bytes) if bytes.len() == 33 => hex::encode(bytes),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(
+                    "Issuer public key must be 33 bytes hex-encoded".to_string(),
+                )),
+            );
+        }
+    };
+
+    let page: usize = params.get("page").and_then(|p| p.parse().ok()).unwrap_or(0);
+    let page_size: usize = params
+        .get("page_size")
+        .and_then(|ps| ps.parse().ok())
+        .unwrap_or(50)
+        .clamp(1, 1000);
+
+    let query = crate::store::EventQuery {
+        issuer: Some(issuer_pubkey),
+        offset: page.saturating_mul(page_size),
+        limit: page_size,
+        ..crate::store::EventQuery::default()
+    };
+    match state.event_store.query_events(&query).await {
+        Ok(events) => (StatusCode::OK, Json(crate::models::success_response(events))),
+        Err(e) => {
+            tracing::error!("Failed to retrieve issuer events: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(crate::models::error_response(
+                    "Failed to retrieve events".to_string(),
+                )),
+            )
+        }
+    }
+}
+
 // Get recent tracker events (simple events endpoint)
+// Query: since_seq, to get the 50 events following that sequence number
 #[axum::debug_handler]
 pub async fn get_events(
     State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
 ) -> (StatusCode, Json<ApiResponse<Vec<TrackerEvent>>>) {
-    tracing::debug!("Getting recent events");
+    tracing::debug!("Getting recent events: {:?}", params);
+
+    let since_seq = match params.get("since_seq").map(|value| value.parse::<u64>()).transpose() {
+        Ok(since_seq) => since_seq,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(
+                    "since_seq must be an event sequence number".to_string(),
+                )),
+            );
+        }
+    };
 
     // Get recent events (last 50 events by default)
-    let events = match state.event_store.get_events_paginated(0, 50).await {
+    let query = crate::store::EventQuery {
+        since_seq,
+        limit: 50,
+        ..crate::store::EventQuery::default()
+    };
+    let events = match state.event_store.query_events(&query).await {
         Ok(events) => events,
         Err(e) => {
             tracing::error!("Failed to retrieve events: {:?}", e);
@@ -932,6 +1140,60 @@ pub async fn get_events(
     )
 }
 
+// Report which part of a recipient's notes their share of each issuer's reserves covers
+#[axum::debug_handler]
+pub async fn get_coverage(
+    State(state): State<AppState>,
+    axum::extract::Path(recipient_pubkey_hex): axum::extract::Path<String>,
+) -> (StatusCode, Json<ApiResponse<basis_store::RecipientCoverage>>) {
+    tracing::debug!("Computing coverage for recipient: {}", recipient_pubkey_hex);
+
+    let recipient_pubkey: PubKey = match hex::decode(&recipient_pubkey_hex).ok().and_then(|bytes| bytes.try_into().ok()) {
+        Some(pubkey) => pubkey,
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(
+                    "recipient_pubkey must be 33 hex-encoded bytes".to_string(),
+                )),
+            )
+        }
+    };
+
+    // Claims of other recipients count too, so every note is needed
+    let notes = match state.send(requests::GetNotes {}).await {
+        Ok(Ok(notes)) => notes,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get notes: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(crate::models::error_response(
+                    "Failed to retrieve notes".to_string(),
+                )),
+            );
+        }
+        Err(e) => return e.response(),
+    };
+
+    let reserves = state.reserve_tracker.get_all_reserves();
+    let collateral = |issuer: &PubKey| {
+        let owner = basis_store::normalize_public_key(&hex::encode(issuer));
+        reserves
+            .iter()
+            .filter(|reserve| basis_store::normalize_public_key(&reserve.owner_pubkey) == owner)
+            .fold(0u64, |acc, reserve| acc.saturating_add(reserve.base_info.collateral_amount))
+    };
+
+    (
+        StatusCode::OK,
+        Json(crate::models::success_response(basis_store::recipient_coverage(
+            &recipient_pubkey,
+            &notes,
+            collateral,
+        ))),
+    )
+}
+
 // Get key status information
 #[axum::debug_handler]
 pub async fn get_key_status(
@@ -959,34 +1221,15 @@ pub async fn get_key_status(
         Err(_) => {
             return (
                 StatusCode::BAD_REQUEST,
-                Json(crate::models::error_response(
-                    "Public key must be 33 bytes".to_string(),
-                )),
-            )
-        }
-    };
-
-    // Get total debt from note storage
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-
-    if let Err(e) = state
-        .tx
-        .send(crate::TrackerCommand::GetNotesByIssuer {
-            issuer_pubkey,
-            response_tx,
-        })
-        .await
-    {
-        tracing::error!("Failed to send to tracker thread: {:?}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
+                Json(crate::models::error_response(
+                    "Public key must be 33 bytes".to_string(),
+                )),
+            )
+        }
+    };
 
-    let notes = match response_rx.await {
+    // Get total debt from note storage
+    let notes = match state.send(requests::GetNotesByIssuer { issuer_pubkey }).await {
         Ok(Ok(notes)) => notes,
         Ok(Err(e)) => {
             tracing::error!("Failed to get notes: {:?}", e);
@@ -997,24 +1240,20 @@ pub async fn get_key_status(
                 )),
             );
         }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Internal server error".to_string(),
-                )),
-            );
-        }
+        Err(e) => return e.response(),
     };
 
     // Calculate total debt and note count
     let total_debt: u64 = notes.iter().map(|note| note.outstanding_debt()).sum();
+    let acknowledged_debt: u64 = notes
+        .iter()
+        .filter(|note| note.is_acknowledged())
+        .map(|note| note.outstanding_debt())
+        .sum();
     let note_count = notes.len();
 
-    // Get collateral from reserve tracker
-    let tracker = state.reserve_tracker.lock().await;
-    let all_reserves = tracker.get_all_reserves();
+    // Get collateral from the reserves the scanner found
+    let all_reserves = state.reserve_tracker.get_all_reserves();
 
     // Normalize the public key to handle different representations (e.g., 07 prefix for GroupElement)
     let normalized_pubkey = basis_store::normalize_public_key(&pubkey_hex);
@@ -1054,11 +1293,13 @@ pub async fn get_key_status(
 
     let status = KeyStatusResponse {
         total_debt,
+        acknowledged_debt,
         collateral,
         collateralization_ratio,
         note_count,
         last_updated,
         issuer_pubkey: pubkey_hex.clone(),
+        valuation: state.pricing.valuation(collateral, total_debt),
     };
 
     tracing::info!(
@@ -1086,7 +1327,7 @@ pub async fn initiate_redemption(
     // Convert recipient public key to P2PK address
     let recipient_address = {
         // Convert the public key to a P2PK address
-        use ergo_lib::ergotree_ir::address::{Address, NetworkPrefix};
+        use ergo_lib::ergotree_ir::address::Address;
         use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
         use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
         use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
@@ -1111,9 +1352,7 @@ pub async fn initiate_redemption(
                 // Create a P2PK address from the public key
                 let prove_dlog = ProveDlog::from(ec_point);
                 let address = Address::P2Pk(prove_dlog);
-                // Use mainnet prefix by default, could be configurable
-                let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
-                encoder.address_to_str(&address)
+                state.config.network().encode_address(&address)
             },
             Err(_) => {
                 // If conversion fails, abort redemption
@@ -1148,53 +1387,9 @@ pub async fn initiate_redemption(
             }
         };
 
-        // Normalize the issuer public key
-        let normalized_issuer_key = basis_store::normalize_public_key(&payload.issuer_pubkey);
-
-        // Find a reserve where the owner key matches (considering normalized forms)
-        let mut found_box_id = String::new();
-        for reserve in &all_reserves {
-            // Handle the case where the owner key might be double-encoded
-            // The database might store the hex string as ASCII characters, which are hex-encoded again
-            let actual_owner_key = {
-                // Try to decode the stored key as hex to get the original hex string
-                if let Ok(decoded_bytes) = hex::decode(&reserve.owner_pubkey) {
-                    // If successful, try to interpret as ASCII string
-                    if let Ok(decoded_string) = String::from_utf8(decoded_bytes) {
-                        // Check if this looks like a valid hex string (all valid hex chars)
-                        if decoded_string.chars().all(|c| c.is_ascii_hexdigit()) {
-                            decoded_string
-                        } else {
-                            // If not a valid hex string, use the original
-                            reserve.owner_pubkey.clone()
-                        }
-                    } else {
-                        // If not valid UTF-8, use the original
-                        reserve.owner_pubkey.clone()
-                    }
-                } else {
-                    // If hex decoding fails, use the original
-                    reserve.owner_pubkey.clone()
-                }
-            };
-
-            let normalized_actual_key = basis_store::normalize_public_key(&actual_owner_key);
-            let original_reserve_key = &reserve.owner_pubkey;
-
-            // Debug: Print the values being compared
-            tracing::debug!("Comparing keys - Issuer: {}, Normalized Issuer: {}, Actual Owner Key: {}, Normalized Actual: {}, Stored: {}",
-                           payload.issuer_pubkey, normalized_issuer_key, actual_owner_key, normalized_actual_key, original_reserve_key);
-
-            // Since we now strip the 0x07 prefix when reading from registers,
-            // we only need to match normalized keys (handles any remaining edge cases)
-            let matches = normalized_issuer_key == normalized_actual_key;
-
-            if matches {
-                tracing::debug!("Key match found! Reserve box ID: {}", reserve.box_id);
-                found_box_id = reserve.box_id.clone();
-                break;
-            }
-        }
+        let found_box_id = find_issuer_reserve(&all_reserves, &payload.issuer_pubkey)
+            .map(|reserve| reserve.box_id.clone())
+            .unwrap_or_default();
 
         if found_box_id.is_empty() {
             tracing::warn!("No reserve found for issuer: {}", payload.issuer_pubkey);
@@ -1288,21 +1483,33 @@ pub async fn initiate_redemption(
 
     // Get tracker signature for normal redemption (not needed for emergency)
     let tracker_signature_hex = if !payload.emergency {
-        match get_tracker_signature_for_redemption(
-            &state,
-            &payload.issuer_pubkey,
-            &payload.recipient_pubkey,
-            payload.amount,
-            payload.timestamp,
-            payload.emergency,
-        ).await {
-            Ok(sig) => Some(sig),
-            Err((status_code, error_resp)) => {
-                // Convert the error response to the correct type
+        let keys = (
+            basis_store::schnorr::pubkey_from_hex(&payload.issuer_pubkey),
+            basis_store::schnorr::pubkey_from_hex(&payload.recipient_pubkey),
+        );
+        let (Ok(issuer_pubkey), Ok(recipient_pubkey)) = keys else {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(
+                    "Public keys must be 33 bytes hex-encoded".to_string(),
+                )),
+            );
+        };
+        let request = RedemptionSigningRequest {
+            issuer_pubkey,
+            recipient_pubkey,
+            total_debt: payload.amount,
+            timestamp: payload.timestamp,
+            redeem_amount: None,
+            purpose: "redeem",
+        };
+        match sign_redemption(&state, request).await {
+            Ok(signed) => Some(hex::encode(signed.signature)),
+            Err((status_code, message)) => {
                 return (
                     status_code,
                     Json(crate::models::error_response(
-                        format!("Failed to get tracker signature: {:?}", error_resp.0.error)
+                        format!("Failed to get tracker signature: {}", message)
                     )),
                 );
             }
@@ -1319,6 +1526,21 @@ pub async fn initiate_redemption(
             recipient_address.clone() // Use recipient address as fallback (not ideal but safe)
         });
 
+    // The transaction spends the reserve box as it is on chain
+    let ids = crate::wallet_api::node_id(&reserve_box_id)
+        .and_then(|reserve_box_id| Ok((reserve_box_id, crate::wallet_api::node_id(&tracker_box_id)?)));
+    let (reserve_box_id, tracker_box_id) = match ids {
+        Ok(ids) => ids,
+        Err((status, message)) => return (status, Json(crate::models::error_response(message))),
+    };
+    let reserve_box = match crate::wallet_api::node_box(&state, &reserve_box_id).await {
+        Ok(reserve_box) => reserve_box,
+        Err((status, message)) => {
+            tracing::error!("Failed to read reserve box {}: {}", reserve_box_id, message);
+            return (status, Json(crate::models::error_response(message)));
+        }
+    };
+
     // Create redemption request with blockchain data
     let redemption_request = basis_store::RedemptionRequest {
         issuer_pubkey: payload.issuer_pubkey.clone(),
@@ -1334,28 +1556,15 @@ pub async fn initiate_redemption(
         issuer_signature: payload.issuer_signature.clone(),
         emergency: payload.emergency,
         tracker_signature: tracker_signature_hex,
+        reserve_box: Some(reserve_box),
+        reserve_insert_proof: payload.reserve_insert_proof.clone(),
     };
 
     // Send command to tracker thread to initiate redemption
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-
-    let cmd = TrackerCommand::InitiateRedemption {
+    let request = requests::InitiateRedemption {
         request: redemption_request,
-        response_tx,
     };
-
-    if let Err(e) = state.tx.send(cmd).await {
-        tracing::error!("Failed to send redemption command to tracker: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Failed to process redemption request".to_string(),
-            )),
-        );
-    }
-
-    // Wait for response from tracker thread
-    match response_rx.await {
+    match state.send(request).await {
         Ok(Ok(redemption_data)) => {
             // Get tracker NFT ID from configuration
             let tracker_nft_id = match state.config.tracker_nft_bytes() {
@@ -1423,6 +1632,9 @@ pub async fn initiate_redemption(
                 basis_store::RedemptionError::InsufficientCollateral(_, _) => "failed_insufficient_collateral".to_string(),
                 basis_store::RedemptionError::RedemptionTooEarly(_, _) => "failed_too_early".to_string(),
                 basis_store::RedemptionError::StorageError(_) => "failed_storage_error".to_string(),
+                basis_store::RedemptionError::NoteDisputed(_) => "failed_note_disputed".to_string(),
+                basis_store::RedemptionError::AccruedInterestUnpaid(_, _) => "failed_interest_unpaid".to_string(),
+                basis_store::RedemptionError::UnredeemableSigningFormat(_) => "failed_signing_format".to_string(),
                 _ => "failed_other_error".to_string(),
             };
 
@@ -1442,26 +1654,298 @@ pub async fn initiate_redemption(
                 Json(crate::models::error_response(error_msg)),
             )
         }
-        Err(_) => {
-            tracing::error!("Failed to receive redemption response from tracker");
-            (
+        Err(e) => e.response(),
+    }
+}
+
+// Dry run of a redemption: report every reason it would fail, including the
+// issuer's reserve and the emergency time lock, without building a transaction
+#[axum::debug_handler]
+pub async fn validate_redemption(
+    State(state): State<AppState>,
+    Json(payload): Json<RedeemRequest>,
+) -> (StatusCode, Json<ApiResponse<ValidationReport>>) {
+    tracing::debug!("Validating redemption: {:?}", payload);
+
+    let request = requests::ValidateRedemption {
+        issuer_pubkey: payload.issuer_pubkey.clone(),
+        recipient_pubkey: payload.recipient_pubkey.clone(),
+        amount: payload.amount,
+    };
+    let mut errors: Vec<String> = match state.send(request).await {
+        Ok(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        Err(e) => return e.response(),
+    };
+
+    let signature_valid = hex::decode(&payload.issuer_signature).is_ok_and(|bytes| bytes.len() == 65);
+    if !signature_valid {
+        errors.push("issuer_signature must be 65 hex-encoded bytes".to_string());
+    }
+
+    let scanner = state.ergo_scanner.lock().await;
+
+    // The issuer's reserve must hold enough collateral for the redemption
+    match scanner.reserve_storage().get_all_reserves() {
+        Ok(reserves) => match find_issuer_reserve(&reserves, &payload.issuer_pubkey) {
+            Some(reserve) if reserve.base_info.collateral_amount < payload.amount => errors.push(
+                basis_store::RedemptionError::InsufficientCollateral(
+                    reserve.base_info.collateral_amount,
+                    payload.amount,
+                )
+                .to_string(),
+            ),
+            Some(_) => {}
+            None => errors.push(
+                basis_store::RedemptionError::ReserveNotFound(payload.issuer_pubkey.clone()).to_string(),
+            ),
+        },
+        Err(e) => {
+            tracing::error!("Failed to read reserves from database: {:?}", e);
+            return (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 Json(crate::models::error_response(
-                    "Failed to process redemption request".to_string(),
+                    "Failed to read reserves from database".to_string(),
                 )),
-            )
+            );
+        }
+    }
+
+    // Emergency redemption is only accepted once the tracker box is old enough
+    if payload.emergency {
+        let tracker_box = state
+            .tracker_storage
+            .get_latest_tracker_box_id()
+            .ok()
+            .flatten()
+            .and_then(|box_id| state.tracker_storage.get_tracker_box(&box_id).ok().flatten());
+        match (tracker_box, scanner.get_current_height().await) {
+            (Some(tracker_box), Ok(current_height)) => {
+                if let Err(e) = basis_store::check_emergency_time_lock(current_height, tracker_box.creation_height) {
+                    errors.push(e.to_string());
+                }
+            }
+            (None, _) => errors.push("No tracker boxes found in storage".to_string()),
+            (_, Err(e)) => errors.push(format!("Failed to get blockchain height: {}", e)),
+        }
+    }
+
+    (
+        StatusCode::OK,
+        Json(crate::models::success_response(validation_report(errors))),
+    )
+}
+
+// Check whether a note can be redeemed now, and when it can if not
+// Query: issuer and recipient (hex public keys), and optional amount (default
+// the outstanding debt with interest) and emergency (default false)
+#[axum::debug_handler]
+pub async fn get_redemption_eligibility(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<crate::models::RedemptionEligibility>>) {
+    tracing::debug!("Checking redemption eligibility: {:?}", params);
+
+    let pubkey = |name: &str| {
+        params
+            .get(name)
+            .and_then(|hex_key| basis_store::schnorr::pubkey_from_hex(hex_key).ok())
+            .ok_or_else(|| format!("{} must be a 33-byte hex-encoded public key", name))
+    };
+    let parsed = pubkey("issuer").and_then(|issuer| Ok((issuer, pubkey("recipient")?))).and_then(|keys| {
+        let amount = params.get("amount").map(|amount| amount.parse::<u64>()).transpose();
+        let emergency = params.get("emergency").map(|emergency| emergency.parse::<bool>()).transpose();
+        match (amount, emergency) {
+            (Ok(amount), Ok(emergency)) => Ok((keys, amount, emergency.unwrap_or(false))),
+            (Err(_), _) => Err("amount must be a number of nanoERG".to_string()),
+            (_, Err(_)) => Err("emergency must be true or false".to_string()),
+        }
+    });
+    let ((issuer_pubkey, recipient_pubkey), amount, emergency) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(crate::models::error_response(message))),
+    };
+    let (issuer_hex, recipient_hex) = (hex::encode(issuer_pubkey), hex::encode(recipient_pubkey));
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+
+    // Without an amount, the whole outstanding debt is checked
+    let amount = match amount {
+        Some(amount) => amount,
+        None => {
+            let request = requests::GetNoteByIssuerAndRecipient {
+                issuer_pubkey,
+                recipient_pubkey,
+            };
+            match state.send(request).await {
+                Ok(Ok(note)) => note.map_or(0, |note| note.outstanding_debt_with_interest(now)),
+                Ok(Err(e)) => {
+                    tracing::error!("Failed to get note: {:?}", e);
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(crate::models::error_response("Failed to retrieve note".to_string())),
+                    );
+                }
+                Err(e) => return e.response(),
+            }
+        }
+    };
+
+    // Signature, dispute and outstanding debt of the note
+    let request = requests::ValidateRedemption {
+        issuer_pubkey: issuer_hex.clone(),
+        recipient_pubkey: recipient_hex,
+        amount,
+    };
+    let note = match state.send(request).await {
+        Ok(errors) => errors.iter().map(|e| e.to_string()).collect(),
+        Err(e) => return e.response(),
+    };
+
+    let request = requests::GenerateProof {
+        issuer_pubkey,
+        recipient_pubkey,
+    };
+    let proof = match state.send(request).await {
+        Ok(Ok(_)) => Vec::new(),
+        Ok(Err(e)) => vec![format!("Failed to generate proof: {:?}", e)],
+        Err(e) => return e.response(),
+    };
+
+    let scanner = state.ergo_scanner.lock().await;
+    let reserve = match scanner.reserve_storage().get_all_reserves() {
+        Ok(reserves) => find_issuer_reserve(&reserves, &issuer_hex).cloned(),
+        Err(e) => {
+            tracing::error!("Failed to read reserves from database: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(crate::models::error_response(
+                    "Failed to read reserves from database".to_string(),
+                )),
+            );
+        }
+    };
+    let current_height = scanner.get_current_height().await;
+    drop(scanner);
+
+    let (reserve_reasons, collateral_reasons) = match &reserve {
+        Some(reserve) if reserve.base_info.collateral_amount < amount => (
+            Vec::new(),
+            vec![basis_store::RedemptionError::InsufficientCollateral(reserve.base_info.collateral_amount, amount)
+                .to_string()],
+        ),
+        Some(_) => (Vec::new(), Vec::new()),
+        None => (
+            vec![basis_store::RedemptionError::ReserveNotFound(issuer_hex.clone()).to_string()],
+            vec!["No reserve to hold collateral".to_string()],
+        ),
+    };
+
+    // The contract only locks emergency redemptions, which go without the
+    // tracker's signature
+    let unlock_height = state
+        .tracker_storage
+        .get_latest_tracker_box_id()
+        .ok()
+        .flatten()
+        .and_then(|box_id| state.tracker_storage.get_tracker_box(&box_id).ok().flatten())
+        .map(|tracker_box| basis_store::emergency_unlock_height(tracker_box.creation_height));
+    let mut time_lock_reasons = Vec::new();
+    if emergency {
+        match (unlock_height, &current_height) {
+            (Some(unlock_height), Ok(current_height)) if *current_height < unlock_height => time_lock_reasons.push(
+                basis_store::RedemptionError::RedemptionTooEarly(*current_height, unlock_height).to_string(),
+            ),
+            (Some(_), Ok(_)) => {}
+            (None, _) => time_lock_reasons.push("No tracker boxes found in storage".to_string()),
+            (_, Err(e)) => time_lock_reasons.push(format!("Failed to get blockchain height: {}", e)),
         }
     }
+    let current_height = current_height.ok();
+
+    let checks = [&note, &time_lock_reasons, &reserve_reasons, &collateral_reasons, &proof];
+    let eligible = checks.iter().all(|reasons| reasons.is_empty());
+    // Only the time lock passes by itself
+    let earliest_eligible_timestamp = if eligible {
+        Some(now)
+    } else if checks.iter().filter(|reasons| !reasons.is_empty()).count() == 1 && !time_lock_reasons.is_empty() {
+        unlock_height
+            .zip(current_height)
+            .map(|(unlock_height, current_height)| {
+                now + unlock_height.saturating_sub(current_height) * basis_store::BLOCK_INTERVAL_MILLIS
+            })
+    } else {
+        None
+    };
+
+    let eligibility = crate::models::RedemptionEligibility {
+        eligible,
+        earliest_eligible_timestamp,
+        amount,
+        emergency,
+        note: crate::models::EligibilityCheck::from_reasons(note),
+        time_lock: crate::models::EligibilityCheck::from_reasons(time_lock_reasons),
+        reserve: crate::models::EligibilityCheck::from_reasons(reserve_reasons),
+        collateral: crate::models::EligibilityCheck::from_reasons(collateral_reasons),
+        proof: crate::models::EligibilityCheck::from_reasons(proof),
+        current_height,
+        unlock_height,
+        reserve_box_id: reserve.as_ref().map(|reserve| reserve.box_id.clone()),
+        collateral_amount: reserve.map(|reserve| reserve.base_info.collateral_amount),
+    };
+    (StatusCode::OK, Json(crate::models::success_response(eligibility)))
+}
+
+// Find the reserve owned by an issuer, comparing normalized keys
+fn find_issuer_reserve<'a>(
+    reserves: &'a [basis_store::ExtendedReserveInfo],
+    issuer_pubkey: &str,
+) -> Option<&'a basis_store::ExtendedReserveInfo> {
+    // Normalize the issuer public key
+    let normalized_issuer_key = basis_store::normalize_public_key(issuer_pubkey);
+
+    // Find a reserve where the owner key matches (considering normalized forms)
+    reserves.iter().find(|reserve| {
+        // Handle the case where the owner key might be double-encoded
+        // The database might store the hex string as ASCII characters, which are hex-encoded again
+        let actual_owner_key = hex::decode(&reserve.owner_pubkey)
+            .ok()
+            .and_then(|decoded_bytes| String::from_utf8(decoded_bytes).ok())
+            .filter(|decoded_string| decoded_string.chars().all(|c| c.is_ascii_hexdigit()))
+            .unwrap_or_else(|| reserve.owner_pubkey.clone());
+
+        let normalized_actual_key = basis_store::normalize_public_key(&actual_owner_key);
+
+        tracing::debug!(
+            "Comparing keys - Issuer: {}, Normalized Issuer: {}, Actual Owner Key: {}, Normalized Actual: {}, Stored: {}",
+            issuer_pubkey, normalized_issuer_key, actual_owner_key, normalized_actual_key, reserve.owner_pubkey
+        );
+
+        // Since we now strip the 0x07 prefix when reading from registers,
+        // we only need to match normalized keys (handles any remaining edge cases)
+        normalized_issuer_key == normalized_actual_key
+    })
 }
 
 // Complete redemption process by removing the note from tracker state
 #[axum::debug_handler]
 pub async fn complete_redemption(
-    State(_state): State<AppState>,
+    State(state): State<AppState>,
     Json(payload): Json<CompleteRedemptionRequest>,
 ) -> (StatusCode, Json<ApiResponse<()>>) {
     tracing::debug!("Completing redemption: {:?}", payload);
 
+    // The scanner completes redemptions once it sees them on chain
+    if state.config.scanner.auto_complete_redemptions {
+        return (
+            StatusCode::CONFLICT,
+            Json(crate::models::error_response(
+                "Redemptions are completed when observed on chain".to_string(),
+            )),
+        );
+    }
+
     // Parse public keys
     let issuer_pubkey = match hex::decode(&payload.issuer_pubkey) {
         Ok(bytes) => bytes,
@@ -1512,30 +1996,12 @@ pub async fn complete_redemption(
     };
 
     // Send command to tracker thread to complete redemption
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-
-    let cmd = TrackerCommand::CompleteRedemption {
+    let request = requests::CompleteRedemption {
         issuer_pubkey,
         recipient_pubkey,
         redeemed_amount: payload.redeemed_amount,
-        response_tx,
     };
-
-    if let Err(e) = _state.tx.send(cmd).await {
-        tracing::error!(
-            "Failed to send complete redemption command to tracker: {}",
-            e
-        );
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Failed to complete redemption".to_string(),
-            )),
-        );
-    }
-
-    // Wait for response from tracker thread
-    match response_rx.await {
+    match state.send(request).await {
         Ok(Ok(())) => {
             tracing::info!(
                 "Redemption completed successfully for {} -> {}",
@@ -1555,15 +2021,7 @@ pub async fn complete_redemption(
                 ))),
             )
         }
-        Err(_) => {
-            tracing::error!("Failed to receive redemption completion response from tracker");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Failed to complete redemption".to_string(),
-                )),
-            )
-        }
+        Err(e) => e.response(),
     }
 }
 
@@ -1646,24 +2104,11 @@ pub async fn get_tracker_proof(
     };
 
     // Request tracker lookup proof from tracker thread
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-
-    if let Err(e) = state.tx.send(TrackerCommand::GetTrackerLookupProof {
+    let request = requests::GetTrackerLookupProof {
         issuer_pubkey,
         recipient_pubkey,
-        response_tx,
-    }).await {
-        tracing::error!("Failed to send tracker proof command: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
-
-    // Wait for response from tracker thread
-    match response_rx.await {
+    };
+    match state.send(request).await {
         Ok(Ok(proof)) => {
             // Extract total debt from proof value
             let total_debt = if proof.value.len() == 8 {
@@ -1700,15 +2145,7 @@ pub async fn get_tracker_proof(
                 )),
             )
         }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Internal server error".to_string(),
-                )),
-            )
-        }
+        Err(e) => e.response(),
     }
 }
 
@@ -1785,24 +2222,11 @@ pub async fn get_reserve_proof(
     };
 
     // Request reserve lookup proof from tracker thread
-    let (response_tx, response_rx) = tokio::sync::oneshot::channel();
-
-    if let Err(e) = state.tx.send(TrackerCommand::GetReserveLookupProof {
+    let request = requests::GetReserveLookupProof {
         issuer_pubkey,
         recipient_pubkey,
-        response_tx,
-    }).await {
-        tracing::error!("Failed to send reserve proof command: {}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
-
-    // Wait for response from tracker thread
-    match response_rx.await {
+    };
+    match state.send(request).await {
         Ok(Ok(proof)) => {
             // Extract timestamp and already_redeemed from proof value (16 bytes: timestamp || already_redeemed)
             let (stored_timestamp, already_redeemed) = if proof.value.len() == 16 {
@@ -1825,46 +2249,24 @@ pub async fn get_reserve_proof(
             let new_already_redeemed = already_redeemed;
 
             // Request reserve insert proof from tracker thread
-            let (insert_proof_tx, insert_proof_rx) = tokio::sync::oneshot::channel();
-            let insert_proof = match state.tx.send(TrackerCommand::GetReserveInsertProof {
+            let request = requests::GetReserveInsertProof {
                 issuer_pubkey,
                 recipient_pubkey,
                 timestamp: stored_timestamp,
                 new_already_redeemed,
-                response_tx: insert_proof_tx,
-            }).await {
-                Ok(_) => {
-                    match insert_proof_rx.await {
-                        Ok(Ok(proof_bytes)) => proof_bytes,
-                        Ok(Err(e)) => {
-                            tracing::warn!("Failed to generate reserve insert proof: {:?}", e);
-                            return (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(crate::models::error_response(
-                                    format!("Failed to generate reserve insert proof: {:?}", e),
-                                )),
-                            );
-                        }
-                        Err(_) => {
-                            tracing::error!("Tracker thread response channel closed for insert proof");
-                            return (
-                                StatusCode::INTERNAL_SERVER_ERROR,
-                                Json(crate::models::error_response(
-                                    "Tracker thread unavailable".to_string(),
-                                )),
-                            );
-                        }
-                    }
-                }
-                Err(e) => {
-                    tracing::error!("Failed to send reserve insert proof command: {}", e);
+            };
+            let insert_proof = match state.send(request).await {
+                Ok(Ok(proof_bytes)) => proof_bytes,
+                Ok(Err(e)) => {
+                    tracing::warn!("Failed to generate reserve insert proof: {:?}", e);
                     return (
                         StatusCode::INTERNAL_SERVER_ERROR,
                         Json(crate::models::error_response(
-                            "Tracker thread unavailable".to_string(),
+                            format!("Failed to generate reserve insert proof: {:?}", e),
                         )),
                     );
                 }
+                Err(e) => return e.response(),
             };
 
             let proof_data = crate::models::ReserveProofData {
@@ -1895,15 +2297,7 @@ pub async fn get_reserve_proof(
                 )),
             )
         }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Internal server error".to_string(),
-                )),
-            )
-        }
+        Err(e) => e.response(),
     }
 }
 
@@ -1941,131 +2335,23 @@ pub async fn request_tracker_signature(
         }
     };
 
-    // Get tracker public key from configuration
-    let tracker_pubkey_bytes = match state.config.tracker_public_key_bytes() {
-        Ok(Some(key)) => key,
-        Ok(None) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Tracker public key not configured".to_string(),
-                )),
-            );
-        }
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    format!("Invalid tracker public key format: {}", e),
-                )),
-            );
-        }
+    let request = RedemptionSigningRequest {
+        issuer_pubkey: issuer_pubkey_bytes.try_into().expect("length checked above"),
+        recipient_pubkey: recipient_pubkey_bytes.try_into().expect("length checked above"),
+        total_debt: payload.total_debt,
+        timestamp: payload.timestamp,
+        redeem_amount: None,
+        purpose: "tracker_signature",
     };
-
-    // Create message to be signed following the Basis protocol specification.
-    // message = key || longToByteArray(totalDebt) || longToByteArray(timestamp)
-    // where key = blake2b256(ownerKeyBytes || receiverBytes)
-    // Total: 48 bytes (32 + 8 + 8)
-    // Both normal and emergency redemption use the SAME message format.
-    // For emergency redemption, the tracker signature simply becomes optional.
-    let mut key_hash_input = Vec::new();
-    key_hash_input.extend_from_slice(&issuer_pubkey_bytes);
-    key_hash_input.extend_from_slice(&recipient_pubkey_bytes);
-    let key: [u8; 32] = basis_store::blake2b256_hash(&key_hash_input);
-
-    let mut message_to_sign_bytes = Vec::with_capacity(48);
-    message_to_sign_bytes.extend_from_slice(&key);
-    message_to_sign_bytes.extend_from_slice(&payload.total_debt.to_be_bytes());
-    message_to_sign_bytes.extend_from_slice(&payload.timestamp.to_be_bytes());
-
-    let message_to_sign = hex::encode(&message_to_sign_bytes);
-
-    // Try local signing first if tracker secret key is configured
-    let tracker_signature = if let Some(tracker_secret) = state.config.tracker_secret_key_bytes() {
-        tracing::info!("Signing tracker signature locally using configured secret key");
-        
-        match basis_store::schnorr::schnorr_sign(
-            &message_to_sign_bytes,
-            &tracker_secret,
-            &tracker_pubkey_bytes,
-        ) {
-            Ok(signature) => {
-                let sig_hex = hex::encode(&signature);
-                tracing::info!("Local tracker signature generated successfully");
-                sig_hex
-            }
-            Err(e) => {
-                tracing::error!("Failed to sign locally: {:?}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(crate::models::error_response(
-                        format!("Failed to sign locally: {:?}", e),
-                    )),
-                );
-            }
-        }
-    } else {
-        // Fall back to Ergo node API
-        tracing::info!("No tracker secret key configured, using Ergo node API");
-        
-        // Convert tracker public key to P2PK address format for the Ergo node API
-        use ergo_lib::ergotree_ir::address::{Address, NetworkPrefix};
-        use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
-        use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
-        use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
-
-        let tracker_ec_point = match EcPoint::sigma_parse_bytes(&tracker_pubkey_bytes) {
-            Ok(point) => point,
-            Err(e) => {
-                tracing::error!("Failed to parse tracker public key as EcPoint: {:?}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(crate::models::error_response(
-                        format!("Failed to parse tracker public key: {}", e),
-                    )),
-                );
-            }
-        };
-
-        let prove_dlog = ProveDlog::from(tracker_ec_point);
-        let tracker_address = Address::P2Pk(prove_dlog);
-        let encoder = AddressEncoder::new(NetworkPrefix::Mainnet); // Use appropriate network prefix
-        let tracker_p2pk_address = encoder.address_to_str(&tracker_address);
-
-        // Get node URL and API key from configuration
-        let node_url = &state.config.ergo.node.node_url;
-        let api_key = state.config.ergo.node.api_key.as_deref();
-
-        // Call the Ergo node's schnorrSign API to generate the tracker signature
-        match call_schnorr_sign_api(
-            node_url,
-            api_key,
-            &tracker_p2pk_address,
-            &message_to_sign,
-        ).await {
-            Ok(signature) => signature,
-            Err(e) => {
-                tracing::error!("Failed to generate tracker signature via Ergo node API: {}", e);
-                return (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(crate::models::error_response(
-                        format!("Failed to generate tracker signature: {}", e),
-                    )),
-                );
-            }
+    let signed = match sign_redemption(&state, request).await {
+        Ok(signed) => signed,
+        Err((status, message)) => {
+            return (status, Json(crate::models::error_response(message)));
         }
     };
-
-    // Verify that the signature is compatible with our verification algorithm
-    if let Err(verification_error) = verify_ergo_node_signature_compatibility(
-        &tracker_signature,
-        &message_to_sign,
-        &tracker_pubkey_bytes,
-    ).await {
-        tracing::warn!("Signature compatibility warning: {}", verification_error);
-    }
-
-    let tracker_pubkey = hex::encode(&tracker_pubkey_bytes);
+    let tracker_signature = hex::encode(signed.signature);
+    let tracker_pubkey = hex::encode(signed.tracker_pubkey);
+    let message_to_sign = hex::encode(&signed.message);
 
     let response = TrackerSignatureResponse {
         success: true,
@@ -2085,112 +2371,66 @@ pub async fn request_tracker_signature(
     (StatusCode::OK, Json(crate::models::success_response(response)))
 }
 
-/// Helper function to get tracker signature for redemption
-/// Used by the redemption flow to include tracker signature in the request
-/// 
-/// If tracker_secret_key is configured, signs locally. Otherwise, falls back to Ergo node API.
-async fn get_tracker_signature_for_redemption(
+/// Have the tracker sign a redemption after checking it against the stored
+/// note and the collateral of the issuer's reserve
+async fn sign_redemption(
     state: &AppState,
-    issuer_pubkey: &str,
-    recipient_pubkey: &str,
-    total_debt: u64,
-    timestamp: u64,
-    _emergency: bool,
-) -> Result<String, (StatusCode, Json<ApiResponse<()>>)> {
-    // Decode public keys
-    let issuer_pubkey_bytes = hex::decode(issuer_pubkey)
-        .map_err(|_| (
-            StatusCode::BAD_REQUEST,
-            Json(crate::models::error_response("Invalid issuer pubkey hex".to_string())),
-        ))?;
-
-    let recipient_pubkey_bytes = hex::decode(recipient_pubkey)
-        .map_err(|_| (
-            StatusCode::BAD_REQUEST,
-            Json(crate::models::error_response("Invalid recipient pubkey hex".to_string())),
-        ))?;
+    request: RedemptionSigningRequest,
+) -> Result<SignedRedemption, (StatusCode, String)> {
+    let note = lookup_note_for_signing(state, &request.issuer_pubkey, &request.recipient_pubkey).await?;
+    sign_redemption_of_note(state, request, note).await
+}
 
-    // Get tracker public key from configuration
-    let tracker_pubkey_bytes = state.config.tracker_public_key_bytes()
-        .ok()
-        .flatten()
-        .ok_or_else(|| (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response("Tracker public key not configured".to_string())),
-        ))?;
-
-    // Build signing message: key || totalDebt || timestamp (48 bytes)
-    // Note: We use total_debt (cumulative debt) not the redemption amount for the message
-    // This matches the contract expectation that the message covers the full debt state
-    let mut key_hash_input = Vec::new();
-    key_hash_input.extend_from_slice(&issuer_pubkey_bytes);
-    key_hash_input.extend_from_slice(&recipient_pubkey_bytes);
-    let key: [u8; 32] = basis_store::blake2b256_hash(&key_hash_input);
-
-    let mut message_to_sign_bytes = Vec::with_capacity(48);
-    message_to_sign_bytes.extend_from_slice(&key);
-    message_to_sign_bytes.extend_from_slice(&total_debt.to_be_bytes());
-    message_to_sign_bytes.extend_from_slice(&timestamp.to_be_bytes());
-
-    // Check if we have a tracker secret key for local signing
-    if let Some(tracker_secret) = state.config.tracker_secret_key_bytes() {
-        tracing::info!("Signing tracker signature locally using configured secret key");
-        
-        // Sign locally using our schnorr implementation
-        let signature = basis_store::schnorr::schnorr_sign(
-            &message_to_sign_bytes,
-            &tracker_secret,
-            &tracker_pubkey_bytes,
-        ).map_err(|e| {
-            tracing::error!("Failed to sign locally: {:?}", e);
-            (
+async fn lookup_note_for_signing(
+    state: &AppState,
+    issuer_pubkey: &PubKey,
+    recipient_pubkey: &PubKey,
+) -> Result<Option<IouNote>, (StatusCode, String)> {
+    let request = requests::GetNoteByIssuerAndRecipient {
+        issuer_pubkey: *issuer_pubkey,
+        recipient_pubkey: *recipient_pubkey,
+    };
+    match state.send(request).await {
+        Ok(Ok(note)) => Ok(note),
+        Ok(Err(NoteError::StorageError(msg))) if msg == "Note not found" => Ok(None),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to look up note for signing: {:?}", e);
+            Err((
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(format!("Failed to sign locally: {:?}", e))),
-            )
-        })?;
-
-        let signature_hex = hex::encode(&signature);
-        tracing::info!("Local tracker signature generated successfully");
-        return Ok(signature_hex);
+                format!("Failed to look up note: {:?}", e),
+            ))
+        }
+        Err(e) => Err(e.status()),
     }
+}
 
-    // Fall back to Ergo node API if no local secret key is configured
-    tracing::info!("No tracker secret key configured, falling back to Ergo node API");
-    
-    let message_to_sign = hex::encode(&message_to_sign_bytes);
-
-    // Convert tracker public key to P2PK address
-    use ergo_lib::ergotree_ir::address::{Address, NetworkPrefix};
-    use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
-    use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
-    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+async fn sign_redemption_of_note(
+    state: &AppState,
+    request: RedemptionSigningRequest,
+    note: Option<IouNote>,
+) -> Result<SignedRedemption, (StatusCode, String)> {
+    crate::dispute_api::ensure_not_disputed(state, &request.issuer_pubkey, &request.recipient_pubkey).await?;
+    let collateral = issuer_collateral(state, &hex::encode(request.issuer_pubkey)).await;
+
+    state
+        .signing_service
+        .sign_redemption(&request, note.as_ref(), collateral)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Refused to sign redemption: {}", e);
+            (e.status_code(), e.to_string())
+        })
+}
 
-    let tracker_ec_point = EcPoint::sigma_parse_bytes(&tracker_pubkey_bytes)
-        .map_err(|e| (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(format!("Failed to parse tracker public key: {}", e))),
-        ))?;
-
-    let prove_dlog = ProveDlog::from(tracker_ec_point);
-    let tracker_address = Address::P2Pk(prove_dlog);
-    let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
-    let tracker_p2pk_address = encoder.address_to_str(&tracker_address);
-
-    // Get node URL and API key from configuration
-    let node_url = &state.config.ergo.node.node_url;
-    let api_key = state.config.ergo.node.api_key.as_deref();
-
-    // Call the Ergo node's schnorrSign API
-    call_schnorr_sign_api(
-        node_url,
-        api_key,
-        &tracker_p2pk_address,
-        &message_to_sign,
-    ).await
-    .map_err(|e| (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(crate::models::error_response(format!("Failed to generate tracker signature: {}", e))),
-    ))
+/// Collateral of the issuer's reserve, if the issuer has one
+async fn issuer_collateral(state: &AppState, issuer_pubkey_hex: &str) -> Option<u64> {
+    let normalized_issuer = basis_store::normalize_public_key(issuer_pubkey_hex);
+    state
+        .reserve_tracker
+        .get_all_reserves()
+        .into_iter()
+        .find(|reserve| basis_store::normalize_public_key(&reserve.owner_pubkey) == normalized_issuer)
+        .map(|reserve| reserve.base_info.collateral_amount)
 }
 
 // Prepare redemption with all necessary data
@@ -2212,27 +2452,6 @@ pub async fn prepare_redemption(
         );
     }
 
-    // Get tracker public key from configuration
-    let tracker_pubkey_bytes = match state.config.tracker_public_key_bytes() {
-        Ok(Some(key)) => key,
-        Ok(None) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Tracker public key not configured".to_string(),
-                )),
-            );
-        }
-        Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    format!("Invalid tracker public key format: {}", e),
-                )),
-            );
-        }
-    };
-
     // Generate a unique redemption ID
     let redemption_id = format!("redemption_{}_{}_{}",
         &payload.issuer_pubkey[..8],
@@ -2240,122 +2459,43 @@ pub async fn prepare_redemption(
         payload.timestamp
     );
 
-    // Decode public keys for message generation
-    let issuer_pubkey_bytes = match hex::decode(&payload.issuer_pubkey) {
-        Ok(bytes) if bytes.len() == 33 => bytes,
-        _ => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(crate::models::error_response(
-                    "issuer_pubkey must be 33 bytes hex-encoded".to_string(),
-                )),
-            );
-        }
+    let keys = (
+        basis_store::schnorr::pubkey_from_hex(&payload.issuer_pubkey),
+        basis_store::schnorr::pubkey_from_hex(&payload.recipient_pubkey),
+    );
+    let (Ok(issuer_pubkey), Ok(recipient_pubkey)) = keys else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(crate::models::error_response(
+                "Public keys must be 33 bytes hex-encoded".to_string(),
+            )),
+        );
     };
 
-    let recipient_pubkey_bytes = match hex::decode(&payload.recipient_pubkey) {
-        Ok(bytes) if bytes.len() == 33 => bytes,
-        _ => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(crate::models::error_response(
-                    "recipient_pubkey must be 33 bytes hex-encoded".to_string(),
-                )),
-            );
+    // The tracker signs the note's total debt (cumulative, as stored in the
+    // AVL tree), not the amount being redeemed; the contract verifies the
+    // signature against the total debt state
+    let note = match lookup_note_for_signing(&state, &issuer_pubkey, &recipient_pubkey).await {
+        Ok(note) => note,
+        Err((status, message)) => {
+            return (status, Json(crate::models::error_response(message)));
         }
     };
-
-    // Create message to be signed following specs/server/redemption_transaction_format_spec.md
-    // message = key || longToByteArray(totalDebt) || longToByteArray(timestamp)
-    // where key = blake2b256(ownerKeyBytes || receiverBytes)
-    //
-    // IMPORTANT: The message uses totalDebt (cumulative debt from tracker's AVL tree),
-    // NOT the redemption amount. This is required by the ErgoScript contract (basis.es)
-    // which verifies the signature against the total debt state, not the partial redemption amount.
-    let mut key_hash_input = Vec::new();
-    key_hash_input.extend_from_slice(&issuer_pubkey_bytes);
-    key_hash_input.extend_from_slice(&recipient_pubkey_bytes);
-    let key: [u8; 32] = basis_store::blake2b256_hash(&key_hash_input);
-
-    // Fetch total_debt from tracker storage (cumulative debt, not redemption amount)
-    // The contract requires totalDebt for signature verification
-    let total_debt = payload.amount; // TODO: Fetch actual total_debt from tracker AVL tree
-    // For now, we use payload.amount as a placeholder. In production, this should be
-    // fetched from the tracker's AVL tree lookup for the (issuer, recipient) key.
-
-    let mut message_to_sign_bytes = Vec::with_capacity(48);
-    message_to_sign_bytes.extend_from_slice(&key);
-    message_to_sign_bytes.extend_from_slice(&total_debt.to_be_bytes());
-    message_to_sign_bytes.extend_from_slice(&payload.timestamp.to_be_bytes());
-
-    let message_to_sign = hex::encode(&message_to_sign_bytes);
-
-    // Convert tracker public key to P2PK address format for the Ergo node API
-    use ergo_lib::ergotree_ir::address::{Address, NetworkPrefix};
-    use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
-    use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
-    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
-
-    let tracker_ec_point = match EcPoint::sigma_parse_bytes(&tracker_pubkey_bytes) {
-        Ok(point) => point,
-        Err(e) => {
-            tracing::error!("Failed to parse tracker public key as EcPoint: {:?}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    format!("Failed to parse tracker public key: {}", e),
-                )),
-            );
-        }
+    let request = RedemptionSigningRequest {
+        issuer_pubkey,
+        recipient_pubkey,
+        total_debt: note.as_ref().map_or(payload.amount, |note| note.amount_collected),
+        timestamp: payload.timestamp,
+        redeem_amount: Some(payload.amount),
+        purpose: "prepare",
     };
-
-    let prove_dlog = ProveDlog::from(tracker_ec_point);
-    let tracker_address = Address::P2Pk(prove_dlog);
-    let encoder = AddressEncoder::new(NetworkPrefix::Mainnet); // Use appropriate network prefix
-    let tracker_p2pk_address = encoder.address_to_str(&tracker_address);
-
-    // Get node URL and API key from configuration
-    let node_url = &state.config.ergo.node.node_url;
-    let api_key = state.config.ergo.node.api_key.as_deref();
-
-    // Call the Ergo node's schnorrSign API to generate the tracker signature
-    let tracker_signature = match call_schnorr_sign_api(
-        node_url,
-        api_key,
-        &tracker_p2pk_address,
-        &message_to_sign,
-    ).await {
-        Ok(signature) => signature,
-        Err(e) => {
-            tracing::error!("Failed to generate tracker signature via Ergo node API: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    format!("Failed to generate tracker signature: {}", e),
-                )),
-            );
+    let signed = match sign_redemption_of_note(&state, request, note).await {
+        Ok(signed) => signed,
+        Err((status, message)) => {
+            return (status, Json(crate::models::error_response(message)));
         }
     };
-
-    // Verify that the signature from the Ergo node is compatible with our verification algorithm
-    // Due to compatibility issues discovered between Ergo node and Basis server Schnorr implementations
-    // CRITICAL: If the Ergo node signature is incompatible, we REJECT it and require local signing.
-    // The Ergo node's /utils/schnorrSign API generates signatures that are NOT compatible with
-    // the Basis contract verification (basis.es). Only locally-generated signatures with proper
-    // z.bitLength <= 255 constraint will verify correctly on-chain.
-    if let Err(verification_error) = verify_ergo_node_signature_compatibility(
-        &tracker_signature,
-        &message_to_sign,
-        &tracker_pubkey_bytes,
-    ).await {
-        tracing::error!("Ergo node signature is INCOMPATIBLE with Basis verification: {}. Rejecting signature.", verification_error);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                format!("Tracker signature incompatible with Basis contract: {}. Please configure tracker_secret_key for local signing.", verification_error),
-            )),
-        );
-    }
+    let tracker_signature = hex::encode(signed.signature);
 
     // Get the current tracker state digest from shared tracker state
     let tracker_state_digest = {
@@ -2368,8 +2508,6 @@ pub async fn prepare_redemption(
 
     // Generate a real AVL proof for the note
     // Send command to tracker thread to generate the proof
-    let (proof_response_tx, proof_response_rx) = tokio::sync::oneshot::channel();
-
     let issuer_pubkey_bytes = match hex::decode(&payload.issuer_pubkey) {
         Ok(bytes) => {
             match bytes.try_into() {
@@ -2418,22 +2556,11 @@ pub async fn prepare_redemption(
         }
     };
 
-    if let Err(e) = state.tx.send(TrackerCommand::GenerateProof {
+    let request = requests::GenerateProof {
         issuer_pubkey: issuer_pubkey_bytes,
         recipient_pubkey: recipient_pubkey_bytes,
-        response_tx: proof_response_tx,
-    }).await {
-        tracing::error!("Failed to send proof generation command to tracker thread: {:?}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
-
-    // Wait for response from tracker thread
-    let proof_result = match proof_response_rx.await {
+    };
+    let proof_result = match state.send(request).await {
         Ok(Ok(note_proof)) => {
             // Convert the proof to a hex string for transmission
             hex::encode(&note_proof.avl_proof)
@@ -2447,19 +2574,11 @@ pub async fn prepare_redemption(
                 )),
             );
         }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Internal server error".to_string(),
-                )),
-            );
-        }
+        Err(e) => return e.response(),
     };
 
     let avl_proof = proof_result;
-    let tracker_pubkey = hex::encode(&tracker_pubkey_bytes);
+    let tracker_pubkey = hex::encode(signed.tracker_pubkey);
 
     // Get current blockchain height from scanner
     let block_height = {
@@ -2552,8 +2671,6 @@ pub async fn get_redemption_proof(
 
     // Generate a real AVL proof for the note
     // Send command to tracker thread to generate the proof
-    let (proof_response_tx, proof_response_rx) = tokio::sync::oneshot::channel();
-
     let issuer_pubkey_bytes = match hex::decode(issuer_pubkey) {
         Ok(bytes) => {
             match bytes.try_into() {
@@ -2602,22 +2719,11 @@ pub async fn get_redemption_proof(
         }
     };
 
-    if let Err(e) = state.tx.send(TrackerCommand::GenerateProof {
+    let request = requests::GenerateProof {
         issuer_pubkey: issuer_pubkey_bytes,
         recipient_pubkey: recipient_pubkey_bytes,
-        response_tx: proof_response_tx,
-    }).await {
-        tracing::error!("Failed to send proof generation command to tracker thread: {:?}", e);
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(crate::models::error_response(
-                "Tracker thread unavailable".to_string(),
-            )),
-        );
-    }
-
-    // Wait for response from tracker thread
-    let proof_result = match proof_response_rx.await {
+    };
+    let proof_result = match state.send(request).await {
         Ok(Ok(note_proof)) => {
             // Convert the proof to a hex string for transmission
             hex::encode(&note_proof.avl_proof)
@@ -2631,15 +2737,7 @@ pub async fn get_redemption_proof(
                 )),
             );
         }
-        Err(_) => {
-            tracing::error!("Tracker thread response channel closed");
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Internal server error".to_string(),
-                )),
-            );
-        }
+        Err(e) => return e.response(),
     };
 
     // Get current blockchain height from scanner
@@ -2684,6 +2782,312 @@ pub async fn get_redemption_proof(
     (StatusCode::OK, Json(crate::models::success_response(proof)))
 }
 
+// Export every note owed to a recipient with the proofs and signatures needed
+// to redeem it without the tracker - GET /proof/bundle?recipient=...
+#[axum::debug_handler]
+pub async fn get_proof_bundle(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<basis_trees::ProofBundle>>) {
+    tracing::debug!("Exporting proof bundle with params: {:?}", params);
+
+    let recipient_pubkey: PubKey = match params
+        .get("recipient")
+        .and_then(|recipient| hex::decode(recipient).ok())
+        .and_then(|bytes| bytes.try_into().ok())
+    {
+        Some(pubkey) => pubkey,
+        None => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response(
+                    "recipient must be a 33-byte hex-encoded public key".to_string(),
+                )),
+            );
+        }
+    };
+
+    let recipient_proofs = match state.send(requests::GenerateRecipientProofs { recipient_pubkey }).await {
+        Ok(Ok(recipient_proofs)) => recipient_proofs,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to generate recipient proofs: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(crate::models::error_response(format!(
+                    "Failed to generate proofs: {:?}",
+                    e
+                ))),
+            );
+        }
+        Err(e) => return e.response(),
+    };
+
+    let mut notes = Vec::with_capacity(recipient_proofs.proofs.len());
+    for (issuer_pubkey, proof) in recipient_proofs.proofs {
+        let note = proof.note;
+        // Notes the tracker would not sign a redemption for now are still
+        // exported, without the tracker signature
+        let request = RedemptionSigningRequest {
+            issuer_pubkey,
+            recipient_pubkey,
+            total_debt: note.amount_collected,
+            timestamp: note.timestamp,
+            redeem_amount: None,
+            purpose: "proof_bundle",
+        };
+        let tracker_signature = sign_redemption_of_note(&state, request, Some(note.clone()))
+            .await
+            .ok()
+            .map(|signed| hex::encode(signed.signature));
+
+        notes.push(basis_trees::BundledNote {
+            issuer_pubkey: hex::encode(issuer_pubkey),
+            amount_collected: note.amount_collected,
+            amount_redeemed: note.amount_redeemed,
+            timestamp: note.timestamp,
+            issuer_signature: hex::encode(note.signature),
+            signing_version: note.signing_format.version(),
+            tracker_id: note.signing_format.tracker_id().map(hex::encode),
+            tracker_signature,
+            tracker_lookup_proof: hex::encode(&proof.avl_proof),
+        });
+    }
+
+    let last_commitment_height = state
+        .tracker_storage
+        .get_latest_tracker_box_id()
+        .ok()
+        .flatten()
+        .and_then(|box_id| state.tracker_storage.get_tracker_box(&box_id).ok().flatten())
+        .map(|tracker_box| tracker_box.creation_height);
+
+    let mut bundle = basis_trees::ProofBundle {
+        version: basis_trees::PROOF_BUNDLE_VERSION,
+        recipient_pubkey: hex::encode(recipient_pubkey),
+        tracker_pubkey: String::new(),
+        tracker_state_digest: hex::encode(recipient_proofs.root_digest),
+        last_commitment_height,
+        created_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64,
+        notes,
+        tracker_signature: String::new(),
+    };
+    if let Err(e) = state.signing_service.sign_proof_bundle(&mut bundle).await {
+        tracing::warn!("Failed to sign proof bundle: {}", e);
+        return (e.status_code(), Json(crate::models::error_response(e.to_string())));
+    }
+
+    tracing::info!(
+        "Exported proof bundle for {} with {} notes",
+        bundle.recipient_pubkey,
+        bundle.notes.len()
+    );
+
+    (StatusCode::OK, Json(crate::models::success_response(bundle)))
+}
+
+// Get reserve scanner progress, including backfill status
+#[axum::debug_handler]
+pub async fn get_scanner_status(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ApiResponse<basis_store::ScannerProgress>>) {
+    tracing::debug!("Getting scanner status");
+
+    let progress = state.ergo_scanner.lock().await.progress().await;
+
+    (
+        StatusCode::OK,
+        Json(crate::models::success_response(progress)),
+    )
+}
+
+// Get the result of cross-verifying tracker box commitments against local state
+#[axum::debug_handler]
+pub async fn get_verification_status(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ApiResponse<basis_store::VerificationStatus>>) {
+    tracing::debug!("Getting commitment verification status");
+
+    match state.tracker_storage.get_commitment_checks() {
+        Ok(checks) => {
+            let mut status = basis_store::VerificationStatus::from_checks(&checks);
+            status.degraded = state.cold_start.is_degraded();
+            status.cold_start = state.cold_start.check();
+            (StatusCode::OK, Json(crate::models::success_response(status)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to retrieve commitment checks: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(crate::models::error_response(
+                    "Failed to retrieve verification status".to_string(),
+                )),
+            )
+        }
+    }
+}
+
+// List every commitment check ordered by height - GET /commitments
+#[axum::debug_handler]
+pub async fn get_commitments(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ApiResponse<Vec<basis_store::CommitmentCheck>>>) {
+    tracing::debug!("Listing commitment checks");
+
+    match state.tracker_storage.get_commitment_checks() {
+        Ok(checks) => (StatusCode::OK, Json(crate::models::success_response(checks))),
+        Err(e) => {
+            tracing::error!("Failed to retrieve commitment checks: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(crate::models::error_response(
+                    "Failed to retrieve commitment checks".to_string(),
+                )),
+            )
+        }
+    }
+}
+
+// Get the notes changed between the previous commitment and the one at a height,
+// signed by the tracker - GET /commitments/{height}/delta
+#[axum::debug_handler]
+pub async fn get_commitment_delta(
+    State(state): State<AppState>,
+    axum::extract::Path(height): axum::extract::Path<u64>,
+) -> (StatusCode, Json<ApiResponse<basis_trees::CommitmentDelta>>) {
+    tracing::debug!("Getting commitment delta at height {}", height);
+
+    let mut delta = match basis_store::commitment_delta(&state.tracker_storage, height) {
+        Ok(delta) => delta,
+        Err(e) => {
+            let status = match e {
+                basis_store::DeltaUnavailable::NoCommitment(_) => StatusCode::NOT_FOUND,
+                basis_store::DeltaUnavailable::UnknownState(_) => StatusCode::CONFLICT,
+                basis_store::DeltaUnavailable::StorageError(_) => {
+                    tracing::error!("Failed to build commitment delta: {}", e);
+                    StatusCode::INTERNAL_SERVER_ERROR
+                }
+            };
+            return (status, Json(crate::models::error_response(e.to_string())));
+        }
+    };
+    if let Err(e) = state.signing_service.sign_commitment_delta(&mut delta).await {
+        tracing::warn!("Failed to sign commitment delta: {}", e);
+        return (e.status_code(), Json(crate::models::error_response(e.to_string())));
+    }
+
+    (StatusCode::OK, Json(crate::models::success_response(delta)))
+}
+
+// Report how the tracker's on-chain commitments compare with the notes held locally
+#[axum::debug_handler]
+pub async fn get_audit_report(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ApiResponse<crate::models::AuditReport>>) {
+    tracing::debug!("Building audit report");
+
+    let internal_error = |message: &str| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(crate::models::error_response(message.to_string())),
+        )
+    };
+
+    let checks = match state.tracker_storage.get_commitment_checks() {
+        Ok(checks) => checks,
+        Err(e) => {
+            tracing::error!("Failed to retrieve commitment checks: {:?}", e);
+            return internal_error("Failed to retrieve verification status");
+        }
+    };
+    let latest_root = match state.tracker_storage.latest_local_root() {
+        Ok(latest_root) => latest_root,
+        Err(e) => {
+            tracing::error!("Failed to retrieve latest local root: {:?}", e);
+            return internal_error("Failed to retrieve local root");
+        }
+    };
+
+    let notes_ingested = match state.send(requests::GetNotes {}).await {
+        Ok(Ok(notes)) => notes.len(),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to retrieve notes: {:?}", e);
+            return internal_error("Failed to retrieve notes");
+        }
+        Err(_) => return internal_error("Tracker thread unavailable"),
+    };
+
+    let verification = basis_store::VerificationStatus::from_checks(&checks);
+    let latest_commitment_consistent = verification
+        .latest_check
+        .as_ref()
+        .filter(|check| check.status != basis_store::CommitmentStatus::Unverifiable)
+        .map(|check| check.status == basis_store::CommitmentStatus::Consistent);
+
+    let report = crate::models::AuditReport {
+        mode: if state.config.watcher.enabled { "watcher" } else { "tracker" }.to_string(),
+        tracker_public_key: state.config.tracker_public_key_hex(),
+        notes_ingested,
+        local_root_digest: latest_root.map(|(_, digest)| hex::encode(digest)),
+        local_root_height: latest_root.map(|(height, _)| height),
+        latest_commitment_consistent,
+        verification,
+    };
+
+    (StatusCode::OK, Json(crate::models::success_response(report)))
+}
+
+// Get the current state digest with the latest on-chain commitment - GET /state
+#[axum::debug_handler]
+pub async fn get_tracker_state(
+    State(state): State<AppState>,
+) -> (StatusCode, Json<ApiResponse<crate::models::TrackerStateResponse>>) {
+    tracing::debug!("Getting tracker state");
+
+    let internal_error = |message: &str| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(crate::models::error_response(message.to_string())),
+        )
+    };
+
+    let notes = match state.send(requests::GetNotes {}).await {
+        Ok(Ok(notes)) => notes,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to retrieve notes: {:?}", e);
+            return internal_error("Failed to retrieve notes");
+        }
+        Err(e) => return e.response(),
+    };
+    // Read after the notes, so the digest is never older than the count
+    let avl_root_digest = state.shared_tracker_state.lock().await.get_avl_root_digest();
+
+    let tracker_boxes = match state.tracker_storage.get_all_tracker_boxes() {
+        Ok(boxes) => boxes,
+        Err(e) => {
+            tracing::error!("Failed to retrieve tracker boxes: {:?}", e);
+            return internal_error("Failed to retrieve tracker boxes");
+        }
+    };
+    let last_commitment = tracker_boxes
+        .into_iter()
+        .max_by_key(|tracker_box| (tracker_box.last_verified_height, tracker_box.creation_height));
+
+    let response = crate::models::TrackerStateResponse {
+        avl_root_digest: hex::encode(avl_root_digest),
+        last_commitment_height: last_commitment.as_ref().map(|tracker_box| tracker_box.last_verified_height),
+        last_commitment_tx_id: last_commitment.as_ref().and_then(|tracker_box| tracker_box.transaction_id.clone()),
+        last_commitment_box_id: last_commitment.map(|tracker_box| tracker_box.box_id),
+        note_count: notes.len(),
+        last_updated: notes.iter().map(|(_, note)| note.timestamp).max(),
+        tracker_pubkey: state.config.tracker_public_key_hex(),
+    };
+
+    (StatusCode::OK, Json(crate::models::success_response(response)))
+}
+
 // Get the latest tracker box ID from the tracker storage
 #[axum::debug_handler]
 pub async fn get_latest_tracker_box_id(
@@ -2798,18 +3202,7 @@ pub async fn create_reserve_payload(
     }
 
     // Get the hardcoded reserve contract P2S address from configuration
-    let config = match crate::config::AppConfig::load() {
-        Ok(config) => config,
-        Err(e) => {
-            tracing::error!("Failed to load configuration: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Failed to load server configuration".to_string(),
-                )),
-            );
-        }
-    };
+    let config = state.config.as_ref().clone();
 
     let reserve_contract_address = config.ergo.basis_reserve_contract_p2s;
 
@@ -2903,18 +3296,7 @@ pub async fn get_basis_reserve_contract_p2s(
     tracing::debug!("Getting Basis reserve contract P2S address from configuration");
 
     // Get the reserve contract address from the server configuration
-    let config = match crate::config::AppConfig::load() {
-        Ok(config) => config,
-        Err(e) => {
-            tracing::error!("Failed to load configuration: {}", e);
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(crate::models::error_response(
-                    "Failed to load server configuration".to_string(),
-                )),
-            );
-        }
-    };
+    let config = state.config.as_ref().clone();
 
     let reserve_contract_address = config.basis_reserve_contract_p2s();
 
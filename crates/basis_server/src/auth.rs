@@ -0,0 +1,120 @@
+//! API key authentication for state-changing endpoints
+//!
+//! When `auth.api_keys` is configured, every request other than GET, HEAD and
+//! OPTIONS must carry one of the keys in the `X-API-Key` header. Read-only
+//! endpoints stay public so that wallets and explorers can query notes and
+//! proofs without credentials.
+//!
+//! Operator endpoints under `/admin/` are a separate role: they take the
+//! `auth.admin_token` in the `X-Admin-Token` header instead of an API key, and
+//! are disabled when no admin token is configured.
+
+use crate::config::AuthConfig;
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+/// Request header carrying the API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Request header carrying the admin token
+pub const ADMIN_TOKEN_HEADER: &str = "x-admin-token";
+
+/// Path prefix of the endpoints guarded by the admin token
+pub const ADMIN_PATH_PREFIX: &str = "/admin/";
+
+// Compare without short-circuiting on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Whether the request needs an API key under the given configuration
+pub fn requires_api_key(auth: &AuthConfig, method: &Method) -> bool {
+    !auth.api_keys.is_empty() && !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Middleware rejecting state-changing requests without a configured API key
+pub async fn api_key_layer(
+    State(auth): State<Arc<AuthConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    // Admin endpoints are guarded by the admin token instead
+    if request.uri().path().starts_with(ADMIN_PATH_PREFIX) || !requires_api_key(&auth, request.method()) {
+        return next.run(request).await;
+    }
+
+    let provided = request
+        .headers()
+        .get(API_KEY_HEADER)
+        .map(|value| value.as_bytes());
+    let authorized = provided.is_some_and(|provided| {
+        auth.api_keys
+            .iter()
+            .any(|key| constant_time_eq(key.as_bytes(), provided))
+    });
+
+    if !authorized {
+        tracing::warn!(
+            "Rejected unauthenticated {} {}",
+            request.method(),
+            request.uri().path()
+        );
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(crate::models::error_response::<()>(
+                "Missing or invalid X-API-Key header".to_string(),
+            )),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
+
+/// Middleware guarding `/admin/` endpoints with the admin token
+pub async fn admin_token_layer(
+    State(auth): State<Arc<AuthConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !request.uri().path().starts_with(ADMIN_PATH_PREFIX) || request.method() == Method::OPTIONS {
+        return next.run(request).await;
+    }
+
+    let Some(admin_token) = auth.admin_token.as_deref() else {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(crate::models::error_response::<()>(
+                "Admin API is disabled: no auth.admin_token configured".to_string(),
+            )),
+        )
+            .into_response();
+    };
+
+    let authorized = request
+        .headers()
+        .get(ADMIN_TOKEN_HEADER)
+        .is_some_and(|provided| constant_time_eq(admin_token.as_bytes(), provided.as_bytes()));
+    if !authorized {
+        tracing::warn!(
+            "Rejected unauthenticated admin request {} {}",
+            request.method(),
+            request.uri().path()
+        );
+        return (
+            StatusCode::UNAUTHORIZED,
+            Json(crate::models::error_response::<()>(
+                "Missing or invalid X-Admin-Token header".to_string(),
+            )),
+        )
+            .into_response();
+    }
+
+    next.run(request).await
+}
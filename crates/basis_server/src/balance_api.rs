@@ -0,0 +1,112 @@
+//! Bilateral balance endpoint
+//!
+//! Two keys can owe each other at the same time, through a note in each
+//! direction. `GET /balance` reports both notes and nets them, so peers see
+//! who owes whom, and how much, at a glance.
+
+use axum::{extract::State, http::StatusCode, Json};
+use std::collections::HashMap;
+
+use basis_store::PubKey;
+
+use crate::{
+    models::{error_response, success_response, ApiResponse, BalanceResponse, PairPosition},
+    requests,
+    tracker_request::TrackerUnavailable,
+    AppState,
+};
+
+fn pubkey_param(params: &HashMap<String, String>, name: &str) -> Result<PubKey, String> {
+    let value = params.get(name).ok_or_else(|| format!("{} is required", name))?;
+    hex::decode(value)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("{} must be 33 hex-encoded bytes", name))
+}
+
+// What `issuer_pubkey` owes `recipient_pubkey`
+async fn pair_position(
+    state: &AppState,
+    issuer_pubkey: PubKey,
+    recipient_pubkey: PubKey,
+) -> Result<PairPosition, (StatusCode, String)> {
+    // A missing note is an error to the single note lookup, so pick it from the issuer's notes
+    let request = requests::GetNotesByIssuer { issuer_pubkey };
+    let note = match state.send(request).await.map_err(TrackerUnavailable::status)? {
+        Ok(notes) => notes.into_iter().find(|note| note.recipient_pubkey == recipient_pubkey),
+        Err(e) => {
+            tracing::error!("Failed to get note: {:?}", e);
+            return Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to retrieve notes".to_string()));
+        }
+    };
+
+    let proof_available = match &note {
+        Some(_) => {
+            let request = requests::GenerateProof {
+                issuer_pubkey,
+                recipient_pubkey,
+            };
+            matches!(state.send(request).await, Ok(Ok(_)))
+        }
+        None => false,
+    };
+    Ok(PairPosition {
+        issuer_pubkey: hex::encode(issuer_pubkey),
+        recipient_pubkey: hex::encode(recipient_pubkey),
+        has_note: note.is_some(),
+        collected: note.as_ref().map_or(0, |note| note.amount_collected),
+        redeemed: note.as_ref().map_or(0, |note| note.amount_redeemed),
+        outstanding: note.as_ref().map_or(0, |note| note.outstanding_debt()),
+        last_activity: note.as_ref().map(|note| note.timestamp),
+        acknowledged: note.as_ref().is_some_and(|note| note.is_acknowledged()),
+        proof_available,
+    })
+}
+
+/// Get the notes between two keys in both directions and their net balance
+#[axum::debug_handler]
+pub async fn get_balance(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<BalanceResponse>>) {
+    tracing::debug!("Getting balance with params: {:?}", params);
+
+    let keys = pubkey_param(&params, "issuer").and_then(|issuer| {
+        let recipient = pubkey_param(&params, "recipient")?;
+        if issuer == recipient {
+            return Err("issuer and recipient must differ".to_string());
+        }
+        Ok((issuer, recipient))
+    });
+    let (issuer_pubkey, recipient_pubkey) = match keys {
+        Ok(keys) => keys,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+
+    let positions = match pair_position(&state, issuer_pubkey, recipient_pubkey).await {
+        Ok(position) => pair_position(&state, recipient_pubkey, issuer_pubkey)
+            .await
+            .map(|reverse| (position, reverse)),
+        Err(e) => Err(e),
+    };
+    let (position, reverse) = match positions {
+        Ok(positions) => positions,
+        Err((status, message)) => return (status, Json(error_response(message))),
+    };
+
+    let (net_debtor, net_creditor) = match position.outstanding.cmp(&reverse.outstanding) {
+        std::cmp::Ordering::Greater => (Some(&position.issuer_pubkey), Some(&position.recipient_pubkey)),
+        std::cmp::Ordering::Less => (Some(&reverse.issuer_pubkey), Some(&reverse.recipient_pubkey)),
+        std::cmp::Ordering::Equal => (None, None),
+    };
+    let response = BalanceResponse {
+        net_debtor: net_debtor.cloned(),
+        net_creditor: net_creditor.cloned(),
+        net_amount: position.outstanding.abs_diff(reverse.outstanding),
+        gross_amount: position.outstanding.saturating_add(reverse.outstanding),
+        last_activity: position.last_activity.max(reverse.last_activity),
+        position,
+        reverse,
+    };
+    (StatusCode::OK, Json(success_response(response)))
+}
@@ -0,0 +1,101 @@
+//! Cold-start verification of local state against the chain
+//!
+//! After the tracker rebuilds its AVL tree at startup, the root is compared
+//! with the commitment in the latest tracker box. If local state is neither
+//! the committed state nor a state the tracker moved on to from it, for
+//! instance after restoring an older backup, the server enters a read-only
+//! degraded mode: notes and redemptions are refused until the state is fixed,
+//! so no signature is produced over a tree the chain does not know.
+
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use basis_store::{persistence::TrackerStorage, verify_cold_start, ColdStartCheck, ColdStartStatus};
+use std::sync::{Arc, RwLock};
+
+/// Endpoints refused in degraded mode
+pub const DEGRADED_DISABLED_PATHS: &[&str] = &[
+    "/notes",
+    "/notes/ack",
+    "/notes/split",
+    "/redeem",
+    "/redeem/complete",
+    "/tracker/signature",
+    "/redemption/prepare",
+];
+
+/// Outcome of the startup check, shared with request handlers
+#[derive(Debug, Clone, Default)]
+pub struct ColdStartState(Arc<RwLock<Option<ColdStartCheck>>>);
+
+impl ColdStartState {
+    pub fn record(&self, check: ColdStartCheck) {
+        if let Ok(mut current) = self.0.write() {
+            *current = Some(check);
+        }
+    }
+
+    /// Startup check, if one was made
+    pub fn check(&self) -> Option<ColdStartCheck> {
+        self.0.read().ok().and_then(|check| check.clone())
+    }
+
+    /// Whether local state diverged from the chain at startup
+    pub fn is_degraded(&self) -> bool {
+        self.check()
+            .is_some_and(|check| check.status == ColdStartStatus::Diverged)
+    }
+}
+
+/// Compare a freshly rebuilt AVL root with the latest stored tracker box
+///
+/// Must run before the rebuilt root is recorded. Returns `None` when no
+/// tracker box is known yet.
+pub fn check_local_state(tracker_storage: &TrackerStorage, local_root: &[u8; 33]) -> Option<ColdStartCheck> {
+    let latest_box = match tracker_storage.get_latest_tracker_box_id() {
+        Ok(Some(box_id)) => tracker_storage.get_tracker_box(&box_id).ok().flatten()?,
+        Ok(None) => return None,
+        Err(e) => {
+            tracing::warn!("Failed to read the latest tracker box: {:?}", e);
+            return None;
+        }
+    };
+    let last_recorded = tracker_storage.latest_local_root().ok().flatten().map(|(_, root)| root);
+    let recorded_roots = tracker_storage.all_local_roots().unwrap_or_default();
+
+    Some(verify_cold_start(
+        &latest_box,
+        local_root,
+        last_recorded.as_ref(),
+        &recorded_roots,
+    ))
+}
+
+/// Whether the request is refused in the given state
+pub fn disabled_in_degraded_mode(state: &ColdStartState, method: &Method, path: &str) -> bool {
+    *method == Method::POST && DEGRADED_DISABLED_PATHS.contains(&path) && state.is_degraded()
+}
+
+/// Middleware refusing state-changing requests while degraded
+pub async fn degraded_layer(State(state): State<ColdStartState>, request: Request, next: Next) -> Response {
+    if !disabled_in_degraded_mode(&state, request.method(), request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    tracing::debug!(
+        "Refused {} {} in degraded mode",
+        request.method(),
+        request.uri().path()
+    );
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(crate::models::error_response::<()>(
+            "Tracker is read-only: local state diverges from the latest on-chain commitment".to_string(),
+        )),
+    )
+        .into_response()
+}
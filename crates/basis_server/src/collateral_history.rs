@@ -0,0 +1,223 @@
+//! Collateralization history of issuers
+//!
+//! Every `collateral_history.interval_secs` the outstanding debt, collateral
+//! and collateralization ratio of every issuer with notes or a reserve is
+//! recorded in the event store. `GET /key-status/{pubkey}/history` serves an
+//! issuer's snapshots for charting, optionally keeping one per `resolution`
+//! seconds. Snapshots older than `retention_days` are deleted as new ones are
+//! taken.
+
+use std::collections::{BTreeMap, HashMap};
+
+use axum::{extract::State, http::StatusCode, Json};
+use serde::{Deserialize, Serialize};
+
+use basis_store::debt_policy::issuer_collateral;
+
+use crate::{
+    models::{error_response, success_response, ApiResponse, KeyStatusHistoryResponse},
+    requests,
+    store::{downsample, CollateralSnapshot},
+    util::now_secs,
+    AppState,
+};
+
+/// `[collateral_history]` section of the server configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CollateralHistoryConfig {
+    /// Record snapshots on a schedule
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Seconds between snapshots
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// Days snapshots are kept; 0 keeps every snapshot
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    3600
+}
+
+fn default_retention_days() -> u64 {
+    365
+}
+
+impl Default for CollateralHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+/// Days of history returned when `from` is not given
+const DEFAULT_HISTORY_DAYS: u64 = 30;
+const DAY_SECS: u64 = 86400;
+
+/// Snapshot every issuer with notes or a tracked reserve, as of `timestamp`
+/// seconds, returning how many issuers were recorded
+pub async fn record_snapshots(state: &AppState, timestamp: u64) -> Result<usize, String> {
+    let notes = state
+        .send(requests::GetNotes {})
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("{:?}", e))?;
+
+    // (outstanding debt, note count) per issuer
+    let mut issuers: BTreeMap<basis_store::PubKey, (u64, usize)> = BTreeMap::new();
+    for (issuer_pubkey, note) in &notes {
+        let entry = issuers.entry(*issuer_pubkey).or_default();
+        entry.0 = entry.0.saturating_add(note.outstanding_debt());
+        entry.1 += 1;
+    }
+    // Issuers with collateral but no notes yet have a history too
+    for reserve in state.reserve_tracker.get_all_reserves() {
+        if let Ok(Ok(owner)) = hex::decode(&reserve.owner_pubkey).map(basis_store::PubKey::try_from) {
+            issuers.entry(owner).or_default();
+        }
+    }
+
+    let snapshots: Vec<CollateralSnapshot> = issuers
+        .into_iter()
+        .map(|(issuer_pubkey, (total_debt, note_count))| {
+            let collateral = issuer_collateral(&state.reserve_tracker, &issuer_pubkey);
+            CollateralSnapshot {
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                timestamp,
+                total_debt,
+                collateral,
+                collateralization_ratio: (total_debt > 0).then(|| collateral as f64 / total_debt as f64),
+                note_count,
+            }
+        })
+        .collect();
+    state
+        .event_store
+        .add_collateral_snapshots(&snapshots)
+        .await
+        .map_err(|e| e.to_string())?;
+    Ok(snapshots.len())
+}
+
+/// Record snapshots every `config.interval_secs` until shutdown
+pub async fn collateral_history_task(
+    state: AppState,
+    config: CollateralHistoryConfig,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let interval = std::time::Duration::from_secs(config.interval_secs);
+    // The first snapshot waits a full interval, so the scanner has found the reserves
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            _ = ticker.tick() => {}
+        }
+        let now = now_secs();
+        match record_snapshots(&state, now).await {
+            Ok(issuers) => tracing::debug!("Recorded collateral snapshots of {} issuers", issuers),
+            Err(e) => tracing::warn!("Failed to record collateral snapshots: {}", e),
+        }
+        if config.retention_days > 0 {
+            let before = now.saturating_sub(config.retention_days.saturating_mul(DAY_SECS));
+            if let Err(e) = state.event_store.prune_collateral_snapshots(before).await {
+                tracing::warn!("Failed to prune collateral snapshots: {}", e);
+            }
+        }
+    }
+}
+
+fn seconds_param(params: &HashMap<String, String>, name: &str) -> Result<Option<u64>, String> {
+    params
+        .get(name)
+        .map(|value| {
+            value
+                .parse()
+                .map_err(|_| format!("{} must be a Unix timestamp in seconds", name))
+        })
+        .transpose()
+}
+
+/// Get the collateralization history of an issuer
+#[axum::debug_handler]
+pub async fn get_key_status_history(
+    State(state): State<AppState>,
+    axum::extract::Path(pubkey_hex): axum::extract::Path<String>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<KeyStatusHistoryResponse>>) {
+    tracing::debug!("Getting collateral history for {} with params: {:?}", pubkey_hex, params);
+
+    let issuer_pubkey = match hex::decode(&pubkey_hex) {
+        Ok(bytes) if bytes.len() == 33 => hex::encode(bytes),
+        Ok(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(error_response("Public key must be 33 bytes".to_string())),
+            )
+        }
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(error_response("Invalid hex encoding".to_string())),
+            )
+        }
+    };
+
+    let range = seconds_param(&params, "to").and_then(|to| {
+        let to = to.unwrap_or_else(now_secs);
+        let from = seconds_param(&params, "from")?
+            .unwrap_or_else(|| to.saturating_sub(DEFAULT_HISTORY_DAYS * DAY_SECS));
+        if from > to {
+            return Err("from must not be after to".to_string());
+        }
+        let resolution = match params.get("resolution") {
+            None => None,
+            Some(value) => Some(
+                value
+                    .parse()
+                    .ok()
+                    .filter(|resolution| *resolution > 0)
+                    .ok_or_else(|| "resolution must be a number of seconds greater than 0".to_string())?,
+            ),
+        };
+        Ok((from, to, resolution))
+    });
+    let (from, to, resolution) = match range {
+        Ok(range) => range,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+
+    let snapshots = match state.event_store.collateral_history(&issuer_pubkey, from, to).await {
+        Ok(snapshots) => snapshots,
+        Err(e) => {
+            tracing::error!("Failed to read collateral history: {}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response("Failed to retrieve collateral history".to_string())),
+            );
+        }
+    };
+    let snapshots = match resolution {
+        Some(resolution) => downsample(snapshots, resolution),
+        None => snapshots,
+    };
+
+    (
+        StatusCode::OK,
+        Json(success_response(KeyStatusHistoryResponse {
+            issuer_pubkey,
+            from,
+            to,
+            resolution,
+            snapshots,
+        })),
+    )
+}
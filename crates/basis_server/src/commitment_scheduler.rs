@@ -0,0 +1,239 @@
+//! Policies deciding when to commit the tracker state on chain
+//!
+//! By default the tracker box updater commits on a fixed timer. With a
+//! schedule configured it instead checks the state every few seconds and
+//! commits once any enabled trigger fires:
+//!
+//! - `every_blocks`: that many blocks passed since the last commitment
+//! - `every_note_updates`: that many notes were created, updated or redeemed
+//! - `debt_delta_threshold`: debt issued plus debt redeemed reached that amount
+//!
+//! Commitments cost a transaction fee, so with `skip_unchanged` (the default)
+//! no trigger fires while the AVL root equals the last committed one.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::tracker_box_updater::SharedTrackerState;
+
+/// Triggers and cost policy of the commitment scheduler
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct CommitmentScheduleConfig {
+    /// Use the triggers below instead of the fixed tracker box update interval
+    #[serde(default)]
+    pub enabled: bool,
+    /// Commit once this many blocks passed since the last commitment
+    #[serde(default)]
+    pub every_blocks: Option<u64>,
+    /// Commit once this many note updates accumulated
+    #[serde(default)]
+    pub every_note_updates: Option<u64>,
+    /// Commit once issued plus redeemed debt since the last commitment reaches this amount (nanoERG)
+    #[serde(default)]
+    pub debt_delta_threshold: Option<u64>,
+    /// Never commit a root that is already on chain
+    #[serde(default = "default_skip_unchanged")]
+    pub skip_unchanged: bool,
+    /// Seconds between evaluations of the triggers
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+}
+
+fn default_skip_unchanged() -> bool {
+    true
+}
+
+fn default_check_interval_secs() -> u64 {
+    30
+}
+
+impl Default for CommitmentScheduleConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            every_blocks: None,
+            every_note_updates: None,
+            debt_delta_threshold: None,
+            skip_unchanged: default_skip_unchanged(),
+            check_interval_secs: default_check_interval_secs(),
+        }
+    }
+}
+
+impl CommitmentScheduleConfig {
+    /// Whether any trigger is configured
+    pub fn has_trigger(&self) -> bool {
+        self.every_blocks.is_some() || self.every_note_updates.is_some() || self.debt_delta_threshold.is_some()
+    }
+}
+
+/// Reason a commitment was scheduled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommitmentTrigger {
+    Blocks { elapsed: u64 },
+    NoteUpdates { count: u64 },
+    DebtDelta { delta: u64 },
+}
+
+impl fmt::Display for CommitmentTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Blocks { elapsed } => write!(f, "{} blocks since the last commitment", elapsed),
+            Self::NoteUpdates { count } => write!(f, "{} note updates since the last commitment", count),
+            Self::DebtDelta { delta } => write!(f, "debt changed by {} since the last commitment", delta),
+        }
+    }
+}
+
+/// Tracker state the triggers are evaluated against
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitmentSnapshot {
+    /// Current blockchain height, if known
+    pub height: Option<u64>,
+    pub root: [u8; 33],
+    /// Note updates since the tracker started
+    pub note_updates: u64,
+    /// Debt issued plus redeemed since the tracker started
+    pub debt_changed: u64,
+}
+
+impl CommitmentSnapshot {
+    /// Current state of the tracker thread's counters
+    pub fn capture(shared_state: &SharedTrackerState, height: Option<u64>) -> Self {
+        Self {
+            height,
+            root: shared_state.get_avl_root_digest(),
+            note_updates: shared_state.note_update_count(),
+            debt_changed: shared_state.debt_changed(),
+        }
+    }
+}
+
+/// Decides when the next commitment is due
+#[derive(Debug, Clone)]
+pub struct CommitmentScheduler {
+    config: CommitmentScheduleConfig,
+    /// State at the last commitment, or at startup
+    last_commit: Option<CommitmentSnapshot>,
+}
+
+impl CommitmentScheduler {
+    pub fn new(config: CommitmentScheduleConfig) -> Self {
+        Self {
+            config,
+            last_commit: None,
+        }
+    }
+
+    /// Trigger firing for the given state, if any
+    ///
+    /// The first evaluation only records a baseline, except for the block
+    /// trigger which needs a previous height anyway.
+    pub fn evaluate(&mut self, snapshot: &CommitmentSnapshot) -> Option<CommitmentTrigger> {
+        let last = match self.last_commit {
+            Some(last) => last,
+            None => {
+                self.last_commit = Some(*snapshot);
+                return None;
+            }
+        };
+        if self.config.skip_unchanged && snapshot.root == last.root {
+            return None;
+        }
+
+        let note_updates = snapshot.note_updates.saturating_sub(last.note_updates);
+        let debt_delta = snapshot.debt_changed.saturating_sub(last.debt_changed);
+        let elapsed_blocks = snapshot
+            .height
+            .zip(last.height)
+            .map(|(height, last_height)| height.saturating_sub(last_height));
+
+        if let (Some(every), Some(elapsed)) = (self.config.every_blocks, elapsed_blocks) {
+            if elapsed >= every {
+                return Some(CommitmentTrigger::Blocks { elapsed });
+            }
+        }
+        if self.config.every_note_updates.is_some_and(|every| note_updates >= every) {
+            return Some(CommitmentTrigger::NoteUpdates { count: note_updates });
+        }
+        if self.config.debt_delta_threshold.is_some_and(|threshold| debt_delta >= threshold) {
+            return Some(CommitmentTrigger::DebtDelta { delta: debt_delta });
+        }
+        None
+    }
+
+    /// Record that the state in `snapshot` was committed
+    pub fn committed(&mut self, snapshot: CommitmentSnapshot) {
+        self.last_commit = Some(snapshot);
+    }
+
+    /// Whether the block trigger needs the current height
+    pub fn needs_height(&self) -> bool {
+        self.config.every_blocks.is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(height: u64, root: u8, note_updates: u64, debt_changed: u64) -> CommitmentSnapshot {
+        CommitmentSnapshot {
+            height: Some(height),
+            root: [root; 33],
+            note_updates,
+            debt_changed,
+        }
+    }
+
+    #[test]
+    fn test_triggers_fire_on_accumulated_changes() {
+        let mut scheduler = CommitmentScheduler::new(CommitmentScheduleConfig {
+            enabled: true,
+            every_blocks: Some(100),
+            every_note_updates: Some(10),
+            debt_delta_threshold: Some(5_000),
+            ..CommitmentScheduleConfig::default()
+        });
+
+        assert_eq!(scheduler.evaluate(&snapshot(1_000, 1, 0, 0)), None);
+        assert_eq!(scheduler.evaluate(&snapshot(1_050, 2, 3, 1_000)), None);
+        assert_eq!(
+            scheduler.evaluate(&snapshot(1_060, 2, 3, 6_000)),
+            Some(CommitmentTrigger::DebtDelta { delta: 6_000 })
+        );
+        scheduler.committed(snapshot(1_060, 2, 3, 6_000));
+
+        assert_eq!(
+            scheduler.evaluate(&snapshot(1_070, 3, 13, 6_100)),
+            Some(CommitmentTrigger::NoteUpdates { count: 10 })
+        );
+        assert_eq!(
+            scheduler.evaluate(&snapshot(1_160, 3, 13, 6_100)),
+            Some(CommitmentTrigger::Blocks { elapsed: 100 })
+        );
+    }
+
+    #[test]
+    fn test_unchanged_root_is_not_committed() {
+        let config = CommitmentScheduleConfig {
+            enabled: true,
+            every_blocks: Some(10),
+            ..CommitmentScheduleConfig::default()
+        };
+        let mut scheduler = CommitmentScheduler::new(config.clone());
+        scheduler.evaluate(&snapshot(100, 1, 0, 0));
+        assert_eq!(scheduler.evaluate(&snapshot(500, 1, 0, 0)), None);
+
+        // Without cost awareness the block trigger acts as a liveness heartbeat
+        let mut scheduler = CommitmentScheduler::new(CommitmentScheduleConfig {
+            skip_unchanged: false,
+            ..config
+        });
+        scheduler.evaluate(&snapshot(100, 1, 0, 0));
+        assert_eq!(
+            scheduler.evaluate(&snapshot(500, 1, 0, 0)),
+            Some(CommitmentTrigger::Blocks { elapsed: 400 })
+        );
+    }
+}
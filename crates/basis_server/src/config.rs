@@ -1,12 +1,27 @@
 //! Configuration management for Basis Server
 
 use crate::acceptance::config::AcceptanceConfig;
-use basis_store::ergo_scanner::NodeConfig;
+use crate::commitment_scheduler::CommitmentScheduleConfig;
+use crate::daemon::DaemonConfig;
+use crate::tracker_queue::TrackerQueueConfig;
+use crate::idempotency::IdempotencyConfig;
+use crate::issuer_policy::IssuerPolicyConfig;
+use crate::logging::LoggingConfig;
+use crate::maintenance::MaintenanceConfig;
+use crate::collateral_history::CollateralHistoryConfig;
+use crate::request_audit::RequestAuditConfig;
+use crate::outbound_tx::OutboundTxConfig;
+use crate::pricing::PricingConfig;
+use crate::replica::ReplicaConfig;
+use crate::tracker_signer::{SignerConfig, SignerKind};
+use crate::webhooks::{WebhookConfig, WEBHOOK_EVENT_TYPES};
+use basis_store::debt_policy::DebtPolicyConfig;
+use basis_store::NoteLimits;
+use basis_store::ergo_scanner::{NodeConfig, DEFAULT_NODE_URL};
+use basis_store::storage_encryption::{self, StorageCipher};
+use basis_store::{ContractRegistry, ContractRegistryError, Network, NetworkError, ReserveContract};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
-
-// Import Ergo address handling for P2PK address support
-use ergo_lib::ergotree_ir::address::{AddressEncoder, NetworkPrefix};
+use std::path::{Path, PathBuf};
 
 /// Main application configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,6 +35,69 @@ pub struct AppConfig {
     /// Acceptance predicate configuration
     #[serde(default)]
     pub acceptance: AcceptanceConfig,
+    /// Per-issuer debt ceiling configuration
+    #[serde(default)]
+    pub debt_policy: DebtPolicyConfig,
+    /// Dust threshold, largest note amount and notes per issuer
+    #[serde(default)]
+    pub note_limits: NoteLimits,
+    /// Idempotency-Key handling for POST endpoints
+    #[serde(default)]
+    pub idempotency: IdempotencyConfig,
+    /// Database locations
+    #[serde(default)]
+    pub storage: StorageConfig,
+    /// Background task intervals
+    #[serde(default)]
+    pub scanner: ScannerConfig,
+    /// Bounds on transaction fees
+    #[serde(default)]
+    pub fee_policy: FeePolicyConfig,
+    /// API key authentication for state-changing endpoints
+    #[serde(default)]
+    pub auth: AuthConfig,
+    /// Read-only auditor deployment
+    #[serde(default)]
+    pub watcher: WatcherConfig,
+    /// Signed callbacks for collateral, redemption and commitment events
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    /// Adaptive triggers for on-chain tracker commitments
+    #[serde(default)]
+    pub commitment_schedule: CommitmentScheduleConfig,
+    /// Where the tracker signing key is held
+    #[serde(default)]
+    pub signer: SignerConfig,
+    /// Capacity, timeouts and priorities of the tracker command queue
+    #[serde(default)]
+    pub tracker_queue: TrackerQueueConfig,
+    /// Read-only mirror of another tracker
+    #[serde(default)]
+    pub replica: ReplicaConfig,
+    /// Issuers allowed or refused to submit notes
+    #[serde(default)]
+    pub issuer_policy: IssuerPolicyConfig,
+    /// Scheduled compaction and integrity checks of the databases
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    /// Periodic snapshots of each issuer's debt and collateral
+    #[serde(default)]
+    pub collateral_history: CollateralHistoryConfig,
+    /// Manual conversion rates for valuing collateral and debt
+    #[serde(default)]
+    pub pricing: PricingConfig,
+    /// Persistent queue of signed transactions submitted to the node
+    #[serde(default)]
+    pub outbound_tx: OutboundTxConfig,
+    /// Log format, levels and destinations
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    /// PID file for running under a service manager
+    #[serde(default)]
+    pub daemon: DaemonConfig,
+    /// Audit trail of state-changing requests
+    #[serde(default)]
+    pub request_audit: RequestAuditConfig,
 }
 
 /// Server-specific configuration
@@ -31,24 +109,53 @@ pub struct ServerConfig {
     pub port: u16,
     /// Database path (if using persistent storage)
     pub database_url: Option<String>,
+    /// Seconds to wait for in-flight work and background tasks on shutdown
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    crate::shutdown::DEFAULT_SHUTDOWN_TIMEOUT_SECS
 }
 
 /// Ergo blockchain configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErgoConfig {
+    /// Network the tracker runs on; every configured address must belong to it
+    #[serde(default)]
+    pub network: Network,
     /// Ergo node configuration
     pub node: NodeConfig,
     /// Basis reserve contract P2S address
     pub basis_reserve_contract_p2s: String,
+    /// Version of `basis_reserve_contract_p2s`, under which new reserves are created
+    #[serde(default = "default_reserve_contract_version")]
+    pub reserve_contract_version: u32,
+    /// Earlier reserve contract versions whose reserves are still tracked and spendable
+    #[serde(default)]
+    pub legacy_reserve_contracts: Vec<LegacyReserveContract>,
     /// Tracker NFT ID (hex-encoded) - identifies the tracker server for reserve contracts
     pub tracker_nft_id: Option<String>,
     /// Tracker server's public key for the Ergo blockchain (hex-encoded, 33 bytes for compressed format)
     pub tracker_public_key: Option<String>,
     /// Tracker server's secret key for local signing (hex-encoded, 32 bytes)
-    /// If provided, the server will sign redemption transactions locally instead of using the Ergo node API
+    /// If provided and no other signer is configured, the server signs redemptions locally instead of using the Ergo node API
     pub tracker_secret_key: Option<String>,
 }
 
+fn default_reserve_contract_version() -> u32 {
+    basis_store::BASIS_RESERVE_CONTRACT_VERSION
+}
+
+/// Reserve contract version retired by an upgrade
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LegacyReserveContract {
+    /// Contract version, below `ergo.reserve_contract_version`
+    pub version: u32,
+    /// P2S address of the contract
+    pub p2s: String,
+}
+
 /// Transaction configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TransactionConfig {
@@ -59,6 +166,414 @@ pub struct TransactionConfig {
     pub change_address: Option<String>,
 }
 
+/// Database locations, relative to the working directory unless absolute
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct StorageConfig {
+    /// Note storage
+    #[serde(default = "default_notes_path")]
+    pub notes_path: PathBuf,
+    /// Persistent tracker AVL tree
+    #[serde(default = "default_avl_tree_path")]
+    pub avl_tree_path: PathBuf,
+    /// Reserve scanner metadata
+    #[serde(default = "default_scanner_metadata_path")]
+    pub scanner_metadata_path: PathBuf,
+    /// Reserves found by the reserve scanner
+    #[serde(default = "default_reserves_path")]
+    pub reserves_path: PathBuf,
+    /// Tracker scanner metadata
+    #[serde(default = "default_tracker_scanner_metadata_path")]
+    pub tracker_scanner_metadata_path: PathBuf,
+    /// Tracker commitment boxes
+    #[serde(default = "default_tracker_boxes_path")]
+    pub tracker_boxes_path: PathBuf,
+    /// Append-only log of every redemption signature the tracker produced
+    #[serde(default = "default_signing_audit_log_path")]
+    pub signing_audit_log_path: PathBuf,
+    /// Encryption of note and reserve storage at rest
+    #[serde(default)]
+    pub encryption: StorageEncryptionConfig,
+}
+
+/// `[storage.encryption]`: AES-256-GCM keys sealing stored notes and reserves
+///
+/// Keys are 64 hex characters, given inline or, preferably, in environment
+/// variables filled by a secret manager. Values are written with the current
+/// key; previous keys only read values written before a rotation.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct StorageEncryptionConfig {
+    /// Current storage key
+    #[serde(default)]
+    pub key: Option<String>,
+    /// Environment variable holding the current storage key
+    #[serde(default)]
+    pub key_env: Option<String>,
+    /// Keys rotated out, still read
+    #[serde(default)]
+    pub previous_keys: Vec<String>,
+    /// Environment variables holding keys rotated out
+    #[serde(default)]
+    pub previous_key_envs: Vec<String>,
+}
+
+impl StorageEncryptionConfig {
+    /// Storage cipher of the configured keys, without keys if none is set
+    ///
+    /// Errors name the offending config path.
+    pub fn cipher(&self) -> Result<StorageCipher, (String, String)> {
+        let from_env = |path: &str, var: &str| {
+            std::env::var(var).map_err(|_| (path.to_string(), format!("environment variable {} is not set", var)))
+        };
+        let parse = |path: &str, key: &str| {
+            storage_encryption::parse_key(key).map_err(|e| (path.to_string(), e.to_string()))
+        };
+
+        let current = match (&self.key, &self.key_env) {
+            (Some(_), Some(_)) => {
+                return Err((
+                    "storage.encryption.key".to_string(),
+                    "set either key or key_env, not both".to_string(),
+                ))
+            }
+            (Some(key), None) => parse("storage.encryption.key", key)?,
+            (None, Some(var)) => parse("storage.encryption.key_env", &from_env("storage.encryption.key_env", var)?)?,
+            (None, None) => {
+                if self.previous_keys.is_empty() && self.previous_key_envs.is_empty() {
+                    return Ok(StorageCipher::default());
+                }
+                return Err((
+                    "storage.encryption.key".to_string(),
+                    "previous keys are set but no current key".to_string(),
+                ));
+            }
+        };
+        let mut previous = Vec::new();
+        for key in &self.previous_keys {
+            previous.push(parse("storage.encryption.previous_keys", key)?);
+        }
+        for var in &self.previous_key_envs {
+            let path = "storage.encryption.previous_key_envs";
+            previous.push(parse(path, &from_env(path, var)?)?);
+        }
+        Ok(StorageCipher::new(current, &previous))
+    }
+}
+
+fn default_notes_path() -> PathBuf {
+    PathBuf::from("crates/basis_server/data/notes")
+}
+
+fn default_avl_tree_path() -> PathBuf {
+    PathBuf::from("crates/basis_server/data/avl_tree")
+}
+
+fn default_scanner_metadata_path() -> PathBuf {
+    PathBuf::from("crates/basis_server/data/scanner_metadata")
+}
+
+fn default_reserves_path() -> PathBuf {
+    PathBuf::from("crates/basis_server/data/reserves")
+}
+
+fn default_tracker_scanner_metadata_path() -> PathBuf {
+    PathBuf::from("data/tracker_scanner_metadata")
+}
+
+fn default_tracker_boxes_path() -> PathBuf {
+    PathBuf::from("data/tracker_boxes")
+}
+
+fn default_signing_audit_log_path() -> PathBuf {
+    PathBuf::from("data/signing_audit.jsonl")
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            notes_path: default_notes_path(),
+            avl_tree_path: default_avl_tree_path(),
+            scanner_metadata_path: default_scanner_metadata_path(),
+            reserves_path: default_reserves_path(),
+            tracker_scanner_metadata_path: default_tracker_scanner_metadata_path(),
+            tracker_boxes_path: default_tracker_boxes_path(),
+            signing_audit_log_path: default_signing_audit_log_path(),
+            encryption: StorageEncryptionConfig::default(),
+        }
+    }
+}
+
+/// Where the reserve and tracker scanners read boxes from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScanBackend {
+    /// Scans registered on `ergo.node.node_url` through its `/scan` API
+    #[default]
+    Node,
+    /// Unspent boxes listed by `scanner.explorer_url`, for nodes without the scan API
+    Explorer,
+}
+
+/// Background task intervals
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ScannerConfig {
+    /// Seconds between tracker box updates
+    #[serde(default = "default_tracker_box_update_interval_secs")]
+    pub tracker_box_update_interval_secs: u64,
+    /// Blocks covered by one backfill batch (backfill runs when `ergo.node.start_height` is set)
+    #[serde(default = "default_backfill_batch_blocks")]
+    pub backfill_batch_blocks: u64,
+    /// Boxes requested per node call during backfill
+    #[serde(default = "default_backfill_page_size")]
+    pub backfill_page_size: u64,
+    /// Milliseconds to pause between node requests during backfill
+    #[serde(default = "default_backfill_request_delay_ms")]
+    pub backfill_request_delay_ms: u64,
+    /// Seconds between checks of tracker box commitments against local state
+    #[serde(default = "default_tracker_verification_interval_secs")]
+    pub tracker_verification_interval_secs: u64,
+    /// Explorer API used at startup to discover reserves created before the node scan,
+    /// or for all scanning with the explorer backend
+    #[serde(default)]
+    pub explorer_url: Option<String>,
+    /// Where the scanners read boxes from
+    #[serde(default)]
+    pub backend: ScanBackend,
+    /// Requests per second sent to the explorer by the explorer backend
+    #[serde(default = "default_explorer_requests_per_second")]
+    pub explorer_requests_per_second: u32,
+    /// Seconds the explorer backend reuses an explorer response
+    #[serde(default = "default_explorer_cache_ttl_secs")]
+    pub explorer_cache_ttl_secs: u64,
+    /// Seconds between reserve scan cycles
+    #[serde(default = "default_scan_interval_secs")]
+    pub scan_interval_secs: u64,
+    /// Seconds to wait after a failed scan cycle
+    #[serde(default = "default_retry_backoff_secs")]
+    pub retry_backoff_secs: u64,
+    /// Failed scan cycles in a row before the scanner pauses for twice `scan_interval_secs`
+    #[serde(default = "default_max_consecutive_failures")]
+    pub max_consecutive_failures: u32,
+    /// Random spread of scanner pauses, in percent of their length
+    #[serde(default = "default_scan_jitter_percent")]
+    pub scan_jitter_percent: u64,
+    /// Blocks live scanning may fall behind the chain tip before a `ScannerStalled` event is recorded
+    #[serde(default = "default_max_block_lag")]
+    pub max_block_lag: u64,
+    /// Complete redemptions when the scanner sees them on chain, instead of on `POST /redeem/complete`
+    #[serde(default = "default_auto_complete_redemptions")]
+    pub auto_complete_redemptions: bool,
+}
+
+fn default_backfill_batch_blocks() -> u64 {
+    basis_store::ergo_scanner::DEFAULT_BACKFILL_BATCH_BLOCKS
+}
+
+fn default_backfill_page_size() -> u64 {
+    basis_store::ergo_scanner::DEFAULT_BACKFILL_PAGE_SIZE
+}
+
+fn default_backfill_request_delay_ms() -> u64 {
+    basis_store::ergo_scanner::DEFAULT_BACKFILL_REQUEST_DELAY_MS
+}
+
+fn default_explorer_requests_per_second() -> u32 {
+    basis_store::explorer_backend::DEFAULT_EXPLORER_REQUESTS_PER_SECOND
+}
+
+fn default_explorer_cache_ttl_secs() -> u64 {
+    basis_store::explorer_backend::DEFAULT_EXPLORER_CACHE_TTL_SECS
+}
+
+fn default_scan_interval_secs() -> u64 {
+    basis_store::ergo_scanner::DEFAULT_SCAN_INTERVAL_SECS
+}
+
+fn default_retry_backoff_secs() -> u64 {
+    basis_store::ergo_scanner::DEFAULT_RETRY_BACKOFF_SECS
+}
+
+fn default_max_consecutive_failures() -> u32 {
+    basis_store::ergo_scanner::DEFAULT_MAX_CONSECUTIVE_FAILURES
+}
+
+fn default_scan_jitter_percent() -> u64 {
+    basis_store::ergo_scanner::DEFAULT_SCAN_JITTER_PERCENT
+}
+
+fn default_max_block_lag() -> u64 {
+    basis_store::ergo_scanner::DEFAULT_MAX_BLOCK_LAG
+}
+
+fn default_auto_complete_redemptions() -> bool {
+    false
+}
+
+fn default_tracker_box_update_interval_secs() -> u64 {
+    600
+}
+
+fn default_tracker_verification_interval_secs() -> u64 {
+    300
+}
+
+impl Default for ScannerConfig {
+    fn default() -> Self {
+        Self {
+            tracker_box_update_interval_secs: default_tracker_box_update_interval_secs(),
+            backfill_batch_blocks: default_backfill_batch_blocks(),
+            backfill_page_size: default_backfill_page_size(),
+            backfill_request_delay_ms: default_backfill_request_delay_ms(),
+            tracker_verification_interval_secs: default_tracker_verification_interval_secs(),
+            explorer_url: None,
+            backend: ScanBackend::default(),
+            explorer_requests_per_second: default_explorer_requests_per_second(),
+            explorer_cache_ttl_secs: default_explorer_cache_ttl_secs(),
+            scan_interval_secs: default_scan_interval_secs(),
+            retry_backoff_secs: default_retry_backoff_secs(),
+            max_consecutive_failures: default_max_consecutive_failures(),
+            scan_jitter_percent: default_scan_jitter_percent(),
+            max_block_lag: default_max_block_lag(),
+            auto_complete_redemptions: default_auto_complete_redemptions(),
+        }
+    }
+}
+
+/// Bounds on transaction fees, in nanoERG
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct FeePolicyConfig {
+    /// Smallest fee the server will use
+    #[serde(default = "default_min_fee")]
+    pub min_fee: u64,
+    /// Largest fee the server will use
+    #[serde(default = "default_max_fee")]
+    pub max_fee: u64,
+}
+
+fn default_min_fee() -> u64 {
+    1_000_000 // 0.001 ERG
+}
+
+fn default_max_fee() -> u64 {
+    100_000_000 // 0.1 ERG
+}
+
+impl Default for FeePolicyConfig {
+    fn default() -> Self {
+        Self {
+            min_fee: default_min_fee(),
+            max_fee: default_max_fee(),
+        }
+    }
+}
+
+/// API key authentication for state-changing endpoints
+///
+/// When no keys are configured every endpoint is open, as before.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct AuthConfig {
+    /// Keys accepted in the `X-API-Key` header of POST requests
+    #[serde(default)]
+    pub api_keys: Vec<String>,
+    /// Token required in the `X-Admin-Token` header of `/admin` endpoints,
+    /// which are disabled without one
+    #[serde(default)]
+    pub admin_token: Option<String>,
+}
+
+/// Watcher mode: audit another tracker instead of acting as one
+///
+/// A watcher holds no tracker signing key. It scans the chain, ingests notes
+/// submitted by recipients and checks the official tracker's on-chain
+/// commitments against the AVL roots it rebuilds from them.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct WatcherConfig {
+    /// Run as a watcher
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+/// A single invalid configuration value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigIssue {
+    /// Dotted path of the offending key, e.g. `ergo.tracker_nft_id`
+    pub field: String,
+    /// What is wrong and how to fix it
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.field, self.message)
+    }
+}
+
+/// Errors produced while loading configuration
+#[derive(Debug, thiserror::Error)]
+pub enum ConfigError {
+    #[error("failed to load configuration: {0}")]
+    Load(#[from] config::ConfigError),
+    #[error("invalid configuration:\n{}", format_issues(.0))]
+    Invalid(Vec<ConfigIssue>),
+}
+
+fn format_issues(issues: &[ConfigIssue]) -> String {
+    issues
+        .iter()
+        .map(|issue| format!("  - {}", issue))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Command-line flags for the server binary
+///
+/// Flags take precedence over environment variables, which take precedence
+/// over the configuration file.
+#[derive(Debug, Clone, Default, clap::Parser)]
+#[command(name = "basis_server", about = "Basis tracker server")]
+pub struct ServerArgs {
+    /// Configuration file (default: config/basis.toml, optional)
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Address to bind to
+    #[arg(long)]
+    pub host: Option<String>,
+    /// Port to listen on
+    #[arg(long)]
+    pub port: Option<u16>,
+    /// Ergo node URL
+    #[arg(long)]
+    pub node_url: Option<String>,
+    /// Ergo node API key
+    #[arg(long)]
+    pub node_api_key: Option<String>,
+    /// Ergo network: mainnet or testnet
+    #[arg(long)]
+    pub network: Option<Network>,
+    /// Run as a read-only watcher auditing the configured tracker
+    #[arg(long)]
+    pub watcher: bool,
+    /// Run as a read-only replica of the tracker at this URL
+    #[arg(long, value_name = "URL")]
+    pub replica_of: Option<String>,
+    /// File to write the process ID to while the server runs
+    #[arg(long, value_name = "PATH")]
+    pub pid_file: Option<PathBuf>,
+    /// Validate the configuration and exit
+    #[arg(long)]
+    pub check_config: bool,
+    /// Seal the note and reserve databases under the current storage key and
+    /// exit, encrypting plaintext records and re-encrypting rotated ones
+    #[arg(long)]
+    pub encrypt_storage: bool,
+}
+
+/// Prefix of environment variables overriding configuration values
+pub const ENV_PREFIX: &str = "BASIS";
+
+/// Separator between nested keys in environment variable names,
+/// e.g. `BASIS_ERGO__NODE__NODE_URL` sets `ergo.node.node_url`
+pub const ENV_SEPARATOR: &str = "__";
+
 impl AppConfig {
     /// Load configuration from file
     pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, config::ConfigError> {
@@ -70,34 +585,453 @@ impl AppConfig {
     }
 
     /// Load configuration from default locations
-    pub fn load() -> Result<Self, config::ConfigError> {
-        let config = config::Config::builder()
+    ///
+    /// Equivalent to [`AppConfig::load_layered`] without command-line flags.
+    pub fn load() -> Result<Self, ConfigError> {
+        Self::load_layered(&ServerArgs::default())
+    }
+
+    /// Load and validate configuration from defaults, the configuration file,
+    /// `BASIS_*` environment variables and command-line flags, in increasing
+    /// order of precedence
+    pub fn load_layered(args: &ServerArgs) -> Result<Self, ConfigError> {
+        Self::load_with_env(args, None)
+    }
+
+    // `env` replaces the process environment, so tests do not depend on it
+    fn load_with_env(
+        args: &ServerArgs,
+        env: Option<config::Map<String, String>>,
+    ) -> Result<Self, ConfigError> {
+        let file = match &args.config {
+            // An explicitly requested file must exist
+            Some(path) => config::File::from(path.as_path()).required(true),
+            None => config::File::with_name("config/basis").required(false),
+        };
+
+        let builder = config::Config::builder()
             // Default configuration
             .set_default("server.host", "0.0.0.0")?
             .set_default("server.port", 3048)?
             .set_default("server.database_url", "sqlite:data/basis.db")?
             // Node configuration defaults
-            .set_default("ergo.node.start_height", "")?
-            .set_default("ergo.node.reserve_contract_p2s", "")?
-            .set_default("ergo.node.node_url", "http://159.89.116.15:11088")?
+            .set_default("ergo.node.node_url", DEFAULT_NODE_URL)?
             .set_default("ergo.node.scan_name", "Basis Reserve Scanner")?
-            .set_default("ergo.node.api_key", "hello")?
+            // No contract default: the server cannot work without a real one
+            .set_default("ergo.basis_reserve_contract_p2s", "")?
             // Transaction configuration defaults
             .set_default("transaction.fee", 1000000)? // 0.001 ERG
-            // Tracker public key (optional)
-            .set_default("ergo.tracker_public_key", "")?
-            // Tracker secret key (optional - for local signing)
-            .set_default("ergo.tracker_secret_key", "")?
             // Acceptance predicate configuration (optional)
             .set_default("acceptance.default", "reject")?
             .set_default("acceptance.predicates", Vec::<String>::new())?
-            // Environment variables
-            .add_source(config::Environment::with_prefix("BASIS"))
             // Configuration file
-            .add_source(config::File::with_name("config/basis").required(false))
-            .build()?;
+            .add_source(file)
+            // Environment variables
+            .add_source(
+                config::Environment::with_prefix(ENV_PREFIX)
+                    .prefix_separator("_")
+                    .separator(ENV_SEPARATOR)
+                    .list_separator(",")
+                    .with_list_parse_key("auth.api_keys")
+                    .try_parsing(true)
+                    .source(env),
+            )
+            // Command-line flags
+            .set_override_option("server.host", args.host.clone())?
+            .set_override_option("server.port", args.port)?
+            .set_override_option("ergo.node.node_url", args.node_url.clone())?
+            .set_override_option("ergo.node.api_key", args.node_api_key.clone())?
+            .set_override_option("ergo.network", args.network.map(|network| network.to_string()))?
+            .set_override_option("watcher.enabled", args.watcher.then_some(true))?
+            .set_override_option("replica.primary_url", args.replica_of.clone())?
+            .set_override_option("daemon.pid_file", args.pid_file.as_ref().map(|path| path.display().to_string()))?;
 
-        config.try_deserialize()
+        let config: Self = builder.build()?.try_deserialize()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check every value that would otherwise only fail at runtime
+    ///
+    /// All problems are reported together rather than stopping at the first.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let mut issues = Vec::new();
+        let mut issue = |field: &str, message: String| {
+            issues.push(ConfigIssue {
+                field: field.to_string(),
+                message,
+            })
+        };
+
+        if format!("{}:{}", self.server.host, self.server.port)
+            .parse::<std::net::SocketAddr>()
+            .is_err()
+        {
+            issue(
+                "server.host",
+                format!("'{}' is not an IP address to bind to, e.g. 0.0.0.0", self.server.host),
+            );
+        }
+
+        let node_url = &self.ergo.node.node_url;
+        let has_scheme = node_url.starts_with("http://") || node_url.starts_with("https://");
+        let host = node_url.split_once("://").map(|(_, rest)| rest).unwrap_or("");
+        if !has_scheme || host.is_empty() || host.starts_with('/') {
+            issue(
+                "ergo.node.node_url",
+                format!("'{}' is not an http(s) URL, e.g. {}", node_url, DEFAULT_NODE_URL),
+            );
+        }
+
+        let contract = &self.ergo.basis_reserve_contract_p2s;
+        if contract.is_empty() {
+            issue(
+                "ergo.basis_reserve_contract_p2s",
+                "is required: set it to the P2S address of the Basis reserve contract".to_string(),
+            );
+        } else {
+            match self.ergo.network.parse_address(contract) {
+                Ok(ergo_lib::ergotree_ir::address::Address::P2Pk(_)) => issue(
+                    "ergo.basis_reserve_contract_p2s",
+                    "is a P2PK address; expected the P2S address of the reserve contract".to_string(),
+                ),
+                Ok(_) => {}
+                Err(e @ NetworkError::WrongNetwork { .. }) => issue("ergo.basis_reserve_contract_p2s", e.to_string()),
+                Err(e) => issue(
+                    "ergo.basis_reserve_contract_p2s",
+                    format!("is not a valid contract address: {}", e),
+                ),
+            }
+        }
+
+        for (i, legacy) in self.ergo.legacy_reserve_contracts.iter().enumerate() {
+            let field = format!("ergo.legacy_reserve_contracts[{}]", i);
+            if legacy.version >= self.ergo.reserve_contract_version {
+                issue(
+                    &field,
+                    format!(
+                        "version {} is not below ergo.reserve_contract_version ({})",
+                        legacy.version, self.ergo.reserve_contract_version
+                    ),
+                );
+            }
+            match self.ergo.network.parse_address(&legacy.p2s) {
+                Ok(ergo_lib::ergotree_ir::address::Address::P2Pk(_)) => {
+                    issue(&field, "is a P2PK address; expected the P2S address of a reserve contract".to_string())
+                }
+                Ok(_) => {}
+                Err(e @ NetworkError::WrongNetwork { .. }) => issue(&field, e.to_string()),
+                Err(e) => issue(&field, format!("is not a valid contract address: {}", e)),
+            }
+        }
+        if !contract.is_empty() {
+            if let Err(e @ (ContractRegistryError::VersionInUse(_) | ContractRegistryError::TemplateInUse(_))) =
+                self.contract_registry()
+            {
+                issue("ergo.legacy_reserve_contracts", e.to_string());
+            }
+        }
+
+        match self.ergo.tracker_nft_id.as_deref() {
+            None | Some("") => issue(
+                "ergo.tracker_nft_id",
+                "is required: set it to the hex-encoded token ID of the tracker NFT".to_string(),
+            ),
+            Some(nft_id) => match hex::decode(nft_id) {
+                Ok(bytes) if bytes.len() == 32 => {}
+                Ok(bytes) => issue(
+                    "ergo.tracker_nft_id",
+                    format!("must be 32 bytes (64 hex characters), got {} bytes", bytes.len()),
+                ),
+                Err(e) => issue("ergo.tracker_nft_id", format!("is not valid hex: {}", e)),
+            },
+        }
+
+        match self.tracker_public_key_bytes() {
+            Ok(Some(_)) => {}
+            Ok(None) => issue(
+                "ergo.tracker_public_key",
+                "is required: set it to a hex-encoded public key or a P2PK address".to_string(),
+            ),
+            Err(e) => issue("ergo.tracker_public_key", e.to_string()),
+        }
+
+        if let Some(secret) = self.ergo.tracker_secret_key.as_deref() {
+            if !secret.is_empty() && self.watcher.enabled {
+                issue(
+                    "ergo.tracker_secret_key",
+                    "must not be set when watcher.enabled is true: a watcher does not sign".to_string(),
+                );
+            } else if !secret.is_empty() && self.tracker_secret_key_bytes().is_none() {
+                issue(
+                    "ergo.tracker_secret_key",
+                    "must be 32 bytes (64 hex characters)".to_string(),
+                );
+            }
+        }
+
+        let has_secret_key = self.ergo.tracker_secret_key.as_deref().is_some_and(|secret| !secret.is_empty());
+        let signer = &self.signer;
+        match signer.resolved_kind(has_secret_key) {
+            _ if self.watcher.enabled && signer.kind != SignerKind::Auto => issue(
+                "signer.kind",
+                "must not be set when watcher.enabled is true: a watcher does not sign".to_string(),
+            ),
+            SignerKind::Local => {
+                if let Some(path) = &signer.key_file {
+                    if has_secret_key {
+                        issue(
+                            "signer.key_file",
+                            "must not be set together with ergo.tracker_secret_key".to_string(),
+                        );
+                    } else if let Err(e) = crate::tracker_signer::read_key_file(path) {
+                        issue("signer.key_file", e);
+                    }
+                } else if !has_secret_key {
+                    issue(
+                        "signer.key_file",
+                        "is required for the local signer unless ergo.tracker_secret_key is set".to_string(),
+                    );
+                }
+            }
+            SignerKind::Remote => match (signer.url.as_deref(), &signer.socket_path) {
+                (Some(url), None) if !(url.starts_with("http://") || url.starts_with("https://")) => issue(
+                    "signer.url",
+                    format!("'{}' is not an http(s) URL", url),
+                ),
+                (Some(_), None) | (None, Some(_)) => {}
+                (Some(_), Some(_)) => issue(
+                    "signer.socket_path",
+                    "must not be set together with signer.url".to_string(),
+                ),
+                (None, None) => issue(
+                    "signer.url",
+                    "is required for the remote signer unless signer.socket_path is set".to_string(),
+                ),
+            },
+            _ => {}
+        }
+        if signer.timeout_secs == 0 {
+            issue("signer.timeout_secs", "must be greater than 0".to_string());
+        }
+
+        if let Some(address) = self.transaction.change_address.as_deref() {
+            if !address.is_empty() {
+                match self.ergo.network.parse_address(address) {
+                    Ok(_) => {}
+                    Err(e @ NetworkError::WrongNetwork { .. }) => issue("transaction.change_address", e.to_string()),
+                    Err(e) => issue(
+                        "transaction.change_address",
+                        format!("is not a valid address: {}", e),
+                    ),
+                }
+            }
+        }
+
+        if self.fee_policy.min_fee > self.fee_policy.max_fee {
+            issue(
+                "fee_policy.min_fee",
+                format!(
+                    "({}) is greater than fee_policy.max_fee ({})",
+                    self.fee_policy.min_fee, self.fee_policy.max_fee
+                ),
+            );
+        } else if !(self.fee_policy.min_fee..=self.fee_policy.max_fee).contains(&self.transaction.fee) {
+            issue(
+                "transaction.fee",
+                format!(
+                    "({}) is outside the fee policy range {}..={} nanoERG",
+                    self.transaction.fee, self.fee_policy.min_fee, self.fee_policy.max_fee
+                ),
+            );
+        }
+
+        if self.scanner.tracker_box_update_interval_secs == 0 {
+            issue(
+                "scanner.tracker_box_update_interval_secs",
+                "must be greater than 0".to_string(),
+            );
+        }
+        if self.scanner.tracker_verification_interval_secs == 0 {
+            issue(
+                "scanner.tracker_verification_interval_secs",
+                "must be greater than 0".to_string(),
+            );
+        }
+
+        if self.scanner.backfill_batch_blocks == 0 {
+            issue(
+                "scanner.backfill_batch_blocks",
+                "must be greater than 0".to_string(),
+            );
+        }
+        if self.scanner.backfill_page_size == 0 {
+            issue(
+                "scanner.backfill_page_size",
+                "must be greater than 0".to_string(),
+            );
+        }
+        if self.scanner.scan_interval_secs == 0 {
+            issue(
+                "scanner.scan_interval_secs",
+                "must be greater than 0".to_string(),
+            );
+        }
+        if self.scanner.retry_backoff_secs == 0 {
+            issue(
+                "scanner.retry_backoff_secs",
+                "must be greater than 0".to_string(),
+            );
+        }
+        if self.scanner.max_consecutive_failures == 0 {
+            issue(
+                "scanner.max_consecutive_failures",
+                "must be greater than 0".to_string(),
+            );
+        }
+        if self.scanner.backend == ScanBackend::Explorer {
+            match self.scanner.explorer_url.as_deref().filter(|url| !url.is_empty()) {
+                None => issue(
+                    "scanner.explorer_url",
+                    format!("is required with the explorer backend, e.g. {}", basis_store::explorer_backend::DEFAULT_EXPLORER_URL),
+                ),
+                Some(url) if !(url.starts_with("http://") || url.starts_with("https://")) => {
+                    issue("scanner.explorer_url", format!("'{}' is not an http(s) URL", url))
+                }
+                Some(_) => {}
+            }
+            if self.scanner.explorer_requests_per_second == 0 {
+                issue("scanner.explorer_requests_per_second", "must be greater than 0".to_string());
+            }
+            if self.scanner.auto_complete_redemptions {
+                issue(
+                    "scanner.auto_complete_redemptions",
+                    "must be false with the explorer backend: the explorer does not serve the redemption receiver".to_string(),
+                );
+            }
+        }
+        if self.scanner.scan_jitter_percent > 100 {
+            issue(
+                "scanner.scan_jitter_percent",
+                format!("must be at most 100, got {}", self.scanner.scan_jitter_percent),
+            );
+        }
+
+        if self.auth.api_keys.iter().any(|key| key.trim().is_empty()) {
+            issue("auth.api_keys", "must not contain empty keys".to_string());
+        }
+        if let Some(admin_token) = &self.auth.admin_token {
+            if admin_token.trim().is_empty() {
+                issue("auth.admin_token", "must not be empty".to_string());
+            } else if self.auth.api_keys.contains(admin_token) {
+                issue("auth.admin_token", "must differ from every auth.api_keys entry".to_string());
+            }
+        }
+
+        for (i, endpoint) in self.webhooks.endpoints.iter().enumerate() {
+            let has_scheme = endpoint.url.starts_with("http://") || endpoint.url.starts_with("https://");
+            let host = endpoint.url.split_once("://").map(|(_, rest)| rest).unwrap_or("");
+            if !has_scheme || host.is_empty() || host.starts_with('/') {
+                issue(
+                    &format!("webhooks.endpoints[{}].url", i),
+                    format!("'{}' is not an http(s) URL", endpoint.url),
+                );
+            }
+            if endpoint.secret.trim().is_empty() {
+                issue(
+                    &format!("webhooks.endpoints[{}].secret", i),
+                    "is required: receivers use it to verify the X-Basis-Signature header".to_string(),
+                );
+            }
+            for event in &endpoint.events {
+                if !WEBHOOK_EVENT_TYPES.contains(&event.as_str()) {
+                    issue(
+                        &format!("webhooks.endpoints[{}].events", i),
+                        format!("unknown event '{}', expected one of {}", event, WEBHOOK_EVENT_TYPES.join(", ")),
+                    );
+                }
+            }
+        }
+        if self.webhooks.max_attempts == 0 {
+            issue("webhooks.max_attempts", "must be greater than 0".to_string());
+        }
+        if self.commitment_schedule.enabled && !self.commitment_schedule.has_trigger() {
+            issue(
+                "commitment_schedule",
+                "enabled schedule needs every_blocks, every_note_updates or debt_delta_threshold".to_string(),
+            );
+        }
+        if self.commitment_schedule.check_interval_secs == 0 {
+            issue("commitment_schedule.check_interval_secs", "must be greater than 0".to_string());
+        }
+        if self.tracker_queue.capacity == 0 {
+            issue("tracker_queue.capacity", "must be greater than 0".to_string());
+        }
+        if self.tracker_queue.read_burst == 0 {
+            issue("tracker_queue.read_burst", "must be greater than 0 or writes would go first".to_string());
+        }
+        for (i, endpoint) in self.tracker_queue.endpoints.iter().enumerate() {
+            if !endpoint.path.starts_with('/') {
+                issue(
+                    &format!("tracker_queue.endpoints[{}].path", i),
+                    format!("'{}' must start with '/'", endpoint.path),
+                );
+            }
+        }
+        if let Some(primary_url) = self.replica.primary_url.as_deref() {
+            if !(primary_url.starts_with("http://") || primary_url.starts_with("https://")) {
+                issue("replica.primary_url", format!("'{}' is not an http(s) URL", primary_url));
+            } else if self.watcher.enabled {
+                issue(
+                    "replica.primary_url",
+                    "must not be set when watcher.enabled is true".to_string(),
+                );
+            } else if has_secret_key || signer.kind != SignerKind::Auto {
+                issue(
+                    "replica.primary_url",
+                    "must not be set together with a tracker signing key: a replica does not sign".to_string(),
+                );
+            }
+        }
+        if self.replica.sync_interval_secs == 0 {
+            issue("replica.sync_interval_secs", "must be greater than 0".to_string());
+        }
+        for (path, key) in self.issuer_policy.invalid_keys() {
+            issue(&path, format!("'{}' is not a 33-byte hex public key", key));
+        }
+        if let Err((path, message)) = self.storage.encryption.cipher() {
+            issue(&path, message);
+        }
+        if self.maintenance.enabled && self.maintenance.interval_secs == 0 {
+            issue("maintenance.interval_secs", "must be greater than 0".to_string());
+        }
+        if self.collateral_history.enabled && self.collateral_history.interval_secs == 0 {
+            issue("collateral_history.interval_secs", "must be greater than 0".to_string());
+        }
+        for (path, message) in self.pricing.invalid_settings() {
+            issue(&path, message);
+        }
+        if self.outbound_tx.poll_interval_secs == 0 {
+            issue("outbound_tx.poll_interval_secs", "must be greater than 0".to_string());
+        }
+        if self.outbound_tx.max_attempts == 0 {
+            issue("outbound_tx.max_attempts", "must be greater than 0".to_string());
+        }
+        if self.outbound_tx.fee_bump_after_blocks > 0 && self.outbound_tx.fee_bump_step == 0 {
+            issue("outbound_tx.fee_bump_step", "must be greater than 0 when fees are bumped".to_string());
+        }
+        for (path, message) in self.logging.invalid_settings() {
+            issue(&path, message);
+        }
+        for message in self.note_limits.invalid_settings() {
+            issue("note_limits.min_amount", message);
+        }
+
+        if issues.is_empty() {
+            Ok(())
+        } else {
+            Err(ConfigError::Invalid(issues))
+        }
     }
 
     /// Get the socket address for the server
@@ -107,16 +1041,89 @@ impl AppConfig {
             .expect("Invalid socket address")
     }
 
+    /// Get the reserve scanner backfill configuration
+    pub fn backfill_config(&self) -> basis_store::BackfillConfig {
+        basis_store::BackfillConfig {
+            batch_blocks: self.scanner.backfill_batch_blocks,
+            page_size: self.scanner.backfill_page_size,
+            request_delay: std::time::Duration::from_millis(self.scanner.backfill_request_delay_ms),
+        }
+    }
+
+    /// Explorer the scanners read boxes from, with the explorer backend
+    pub fn explorer_config(&self) -> Option<basis_store::explorer_backend::ExplorerConfig> {
+        if self.scanner.backend != ScanBackend::Explorer {
+            return None;
+        }
+        Some(basis_store::explorer_backend::ExplorerConfig {
+            url: self.scanner.explorer_url.clone()?,
+            requests_per_second: self.scanner.explorer_requests_per_second,
+            cache_ttl: std::time::Duration::from_secs(self.scanner.explorer_cache_ttl_secs),
+            page_size: self.scanner.backfill_page_size,
+        })
+    }
+
+    /// Get the reserve scan loop timing
+    pub fn scan_loop_config(&self) -> basis_store::ScanLoopConfig {
+        basis_store::ScanLoopConfig {
+            scan_interval: std::time::Duration::from_secs(self.scanner.scan_interval_secs),
+            retry_backoff: std::time::Duration::from_secs(self.scanner.retry_backoff_secs),
+            max_consecutive_failures: self.scanner.max_consecutive_failures,
+            jitter: self.scanner.scan_jitter_percent as f64 / 100.0,
+        }
+    }
+
+    /// Take the settings a running server applies on reload from `loaded`:
+    /// the scan loop timing and the debt ceiling
+    pub fn apply_reloadable(&mut self, loaded: &AppConfig) {
+        self.scanner.scan_interval_secs = loaded.scanner.scan_interval_secs;
+        self.scanner.retry_backoff_secs = loaded.scanner.retry_backoff_secs;
+        self.scanner.max_consecutive_failures = loaded.scanner.max_consecutive_failures;
+        self.scanner.scan_jitter_percent = loaded.scanner.scan_jitter_percent;
+        self.debt_policy = loaded.debt_policy.clone();
+    }
+
+    /// Top-level sections whose values differ from those of `other`
+    pub fn changed_sections(&self, other: &AppConfig) -> Vec<String> {
+        match (serde_json::to_value(self), serde_json::to_value(other)) {
+            (Ok(serde_json::Value::Object(ours)), Ok(serde_json::Value::Object(theirs))) => theirs
+                .into_iter()
+                .filter(|(section, value)| ours.get(section) != Some(value))
+                .map(|(section, _)| section)
+                .collect(),
+            _ => vec!["*".to_string()],
+        }
+    }
+
     /// Get the Ergo node configuration
     pub fn ergo_node_config(&self) -> NodeConfig {
         self.ergo.node.clone()
     }
 
+    /// Get the Ergo network the tracker runs on
+    pub fn network(&self) -> Network {
+        self.ergo.network
+    }
+
     /// Get the Basis reserve contract P2S address
     pub fn basis_reserve_contract_p2s(&self) -> &str {
         &self.ergo.basis_reserve_contract_p2s
     }
 
+    /// Get the reserve contract versions in use: the configured contract and
+    /// the legacy versions still tracked
+    pub fn contract_registry(&self) -> Result<ContractRegistry, ContractRegistryError> {
+        let mut registry = ContractRegistry::new();
+        registry.register(ReserveContract::from_p2s(
+            self.ergo.reserve_contract_version,
+            &self.ergo.basis_reserve_contract_p2s,
+        )?)?;
+        for legacy in &self.ergo.legacy_reserve_contracts {
+            registry.register(ReserveContract::from_p2s(legacy.version, &legacy.p2s)?)?;
+        }
+        Ok(registry)
+    }
+
     /// Get the tracker NFT ID bytes (required - server will fail if not configured)
     pub fn tracker_nft_bytes(&self) -> Result<Vec<u8>, hex::FromHexError> {
         match &self.ergo.tracker_nft_id {
@@ -153,8 +1160,7 @@ impl AppConfig {
                 }
 
                 // If hex decoding failed or wrong length, try parsing as P2PK address
-                let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
-                match encoder.parse_address_from_str(pubkey_input) {
+                match self.ergo.network.parse_address(pubkey_input) {
                     Ok(ergo_lib::ergotree_ir::address::Address::P2Pk(pubkey)) => {
                         tracing::info!("Successfully parsed as P2PK address, extracting public key");
                         // Use sigma serialization to get the compressed public key bytes
@@ -175,6 +1181,7 @@ impl AppConfig {
                         tracing::info!("Address is not P2PK format");
                         Err("Address is not P2PK format".into())
                     },
+                    Err(e @ NetworkError::WrongNetwork { .. }) => Err(e.to_string().into()),
                     Err(_) => {
                         tracing::info!("Failed to parse as either hex public key or P2PK address");
                         Err("Invalid hex public key or P2PK address format".into())
@@ -198,8 +1205,7 @@ impl AppConfig {
                 }
 
                 // If input is P2PK address, extract and return the public key as hex
-                let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
-                if let Ok(ergo_lib::ergotree_ir::address::Address::P2Pk(pubkey)) = encoder.parse_address_from_str(pubkey_input) {
+                if let Ok(ergo_lib::ergotree_ir::address::Address::P2Pk(pubkey)) = self.ergo.network.parse_address(pubkey_input) {
                     use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
                     let pubkey_bytes = pubkey.h.sigma_serialize_bytes();
                     if pubkey_bytes.len() == 33 {
@@ -268,7 +1274,7 @@ impl AppConfig {
         match &self.ergo.tracker_public_key {
             Some(pubkey_input) if !pubkey_input.is_empty() => {
                 // Check if it's already an address
-                if pubkey_input.starts_with('9') || pubkey_input.starts_with('3') {
+                if Network::of_address(pubkey_input).is_ok() {
                     Ok(pubkey_input.clone())
                 } else {
                     // It's a hex public key, derive address
@@ -278,7 +1284,7 @@ impl AppConfig {
                         return Err("Invalid tracker public key length".into());
                     }
 
-                    use ergo_lib::ergotree_ir::address::{Address, NetworkPrefix};
+                    use ergo_lib::ergotree_ir::address::Address;
                     use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
                     use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
                     use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
@@ -286,8 +1292,7 @@ impl AppConfig {
                     let ec_point = EcPoint::sigma_parse_bytes(&pubkey_bytes)?;
                     let prove_dlog = ProveDlog::new(ec_point);
                     let address = Address::P2Pk(prove_dlog);
-                    let encoder = AddressEncoder::new(NetworkPrefix::Mainnet);
-                    Ok(encoder.address_to_str(&address))
+                    Ok(self.ergo.network.encode_address(&address))
                 }
             }
             _ => {
@@ -310,8 +1315,10 @@ mod tests {
                 host: "127.0.0.1".to_string(),
                 port: 3000,
                 database_url: Some("sqlite:test.db".to_string()),
+                shutdown_timeout_secs: crate::shutdown::DEFAULT_SHUTDOWN_TIMEOUT_SECS,
             },
             ergo: ErgoConfig {
+                network: Default::default(),
                 node: NodeConfig {
                     start_height: None,
                     reserve_contract_p2s: None,
@@ -320,6 +1327,8 @@ mod tests {
                     api_key: Some("test".to_string()),
                 },
                 basis_reserve_contract_p2s: "test".to_string(),
+                reserve_contract_version: 1,
+                legacy_reserve_contracts: Vec::new(),
                 tracker_nft_id: None,
                 tracker_public_key: Some("02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7".to_string()),
                 tracker_secret_key: None,
@@ -329,6 +1338,27 @@ mod tests {
                         change_address: None,
             },
             acceptance: AcceptanceConfig::empty(),
+            debt_policy: DebtPolicyConfig::default(),
+            note_limits: NoteLimits::default(),
+            idempotency: IdempotencyConfig::default(),
+            storage: StorageConfig::default(),
+            scanner: ScannerConfig::default(),
+            fee_policy: FeePolicyConfig::default(),
+            auth: AuthConfig::default(),
+            watcher: WatcherConfig::default(),
+            webhooks: WebhookConfig::default(),
+            commitment_schedule: CommitmentScheduleConfig::default(),
+            signer: SignerConfig::default(),
+            tracker_queue: TrackerQueueConfig::default(),
+            replica: ReplicaConfig::default(),
+            issuer_policy: IssuerPolicyConfig::default(),
+            maintenance: MaintenanceConfig::default(),
+            collateral_history: CollateralHistoryConfig::default(),
+            pricing: PricingConfig::default(),
+            outbound_tx: OutboundTxConfig::default(),
+            logging: LoggingConfig::default(),
+            daemon: DaemonConfig::default(),
+            request_audit: RequestAuditConfig::default(),
         };
 
         // Test hex format
@@ -341,6 +1371,323 @@ mod tests {
         assert_eq!(hex_result.unwrap(), "02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7");
     }
 
+    fn repo_config_args() -> ServerArgs {
+        ServerArgs {
+            config: Some(Path::new(env!("CARGO_MANIFEST_DIR")).join("../../config/basis.toml")),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_load_layered_precedence() {
+        let mut env = config::Map::new();
+        env.insert("BASIS_SERVER__PORT".to_string(), "4000".to_string());
+        env.insert("BASIS_ERGO__NODE__NODE_URL".to_string(), "http://10.0.0.1:9053".to_string());
+        env.insert("BASIS_AUTH__API_KEYS".to_string(), "first,second".to_string());
+
+        let mut args = repo_config_args();
+        args.node_url = Some("https://node.example.com".to_string());
+
+        let config = AppConfig::load_with_env(&args, Some(env)).unwrap();
+        // Environment overrides the file, flags override the environment
+        assert_eq!(config.server.port, 4000);
+        assert_eq!(config.ergo.node.node_url, "https://node.example.com");
+        assert_eq!(config.auth.api_keys, vec!["first".to_string(), "second".to_string()]);
+        // Sections missing from the file fall back to their defaults
+        assert_eq!(config.storage, StorageConfig::default());
+        assert_eq!(config.fee_policy, FeePolicyConfig::default());
+    }
+
+    #[test]
+    fn test_missing_config_file_is_an_error() {
+        let args = ServerArgs {
+            config: Some(PathBuf::from("does/not/exist.toml")),
+            ..Default::default()
+        };
+        assert!(matches!(
+            AppConfig::load_with_env(&args, Some(config::Map::new())),
+            Err(ConfigError::Load(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_reports_every_issue() {
+        let mut config = AppConfig::load_with_env(&repo_config_args(), Some(config::Map::new())).unwrap();
+        config.ergo.basis_reserve_contract_p2s = "not-an-address".to_string();
+        config.ergo.tracker_nft_id = Some("abcd".to_string());
+        config.ergo.node.node_url = "localhost:9053".to_string();
+        config.transaction.fee = 1;
+
+        let issues = match config.validate() {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+        assert_eq!(
+            fields,
+            vec![
+                "ergo.node.node_url",
+                "ergo.basis_reserve_contract_p2s",
+                "ergo.tracker_nft_id",
+                "transaction.fee",
+            ]
+        );
+        assert!(issues[2].message.contains("32 bytes"));
+    }
+
+    #[test]
+    fn test_addresses_must_match_network() {
+        let mut args = repo_config_args();
+        args.network = Some(Network::Testnet);
+        let issues = match AppConfig::load_with_env(&args, Some(config::Map::new())) {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+        assert_eq!(fields, vec!["ergo.basis_reserve_contract_p2s", "ergo.tracker_public_key"]);
+        assert!(issues[0].message.contains("mainnet address, but the deployment runs on testnet"));
+
+        // The same keys work on testnet with testnet addresses
+        let mut env = config::Map::new();
+        env.insert(
+            "BASIS_ERGO__BASIS_RESERVE_CONTRACT_P2S".to_string(),
+            basis_store::contract_compiler::get_basis_reserve_contract_p2s_for(Network::Testnet).unwrap(),
+        );
+        env.insert(
+            "BASIS_ERGO__TRACKER_PUBLIC_KEY".to_string(),
+            "02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7".to_string(),
+        );
+        let config = AppConfig::load_with_env(&args, Some(env)).unwrap();
+        assert_eq!(config.network(), Network::Testnet);
+        assert!(config.get_change_address().unwrap().starts_with('3'));
+    }
+
+    #[test]
+    fn test_legacy_reserve_contracts() {
+        let mut config = AppConfig::load_with_env(&repo_config_args(), Some(config::Map::new())).unwrap();
+        let current = config.contract_registry().unwrap();
+        assert_eq!(current.len(), 1);
+        assert_eq!(current.latest().unwrap().version, basis_store::BASIS_RESERVE_CONTRACT_VERSION);
+
+        // The configured contract was upgraded; reserves of the old one are still tracked
+        let legacy = ReserveContract::from_ergo_tree_hex(1, "10010101d17300").unwrap();
+        config.ergo.reserve_contract_version = 2;
+        config.ergo.legacy_reserve_contracts = vec![LegacyReserveContract {
+            version: 1,
+            p2s: legacy.p2s(Network::Mainnet),
+        }];
+        config.validate().unwrap();
+        let registry = config.contract_registry().unwrap();
+        assert_eq!(registry.contracts().map(|c| c.version).collect::<Vec<_>>(), vec![1, 2]);
+        assert_eq!(registry.get(1), Some(&legacy));
+        assert_eq!(registry.latest().unwrap().p2s(Network::Mainnet), config.ergo.basis_reserve_contract_p2s);
+
+        // Legacy versions are older than the current one and use their own template
+        config.ergo.legacy_reserve_contracts.push(LegacyReserveContract {
+            version: 3,
+            p2s: config.ergo.basis_reserve_contract_p2s.clone(),
+        });
+        let issues = match config.validate() {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+        assert_eq!(fields, vec!["ergo.legacy_reserve_contracts[1]", "ergo.legacy_reserve_contracts"]);
+        assert!(issues[1].message.contains("already registered as version 2"));
+    }
+
+    #[test]
+    fn test_scan_loop_config() {
+        let mut env = config::Map::new();
+        env.insert("BASIS_SCANNER__SCAN_INTERVAL_SECS".to_string(), "2".to_string());
+        env.insert("BASIS_SCANNER__SCAN_JITTER_PERCENT".to_string(), "0".to_string());
+        let config = AppConfig::load_with_env(&repo_config_args(), Some(env)).unwrap();
+        let scan_loop = config.scan_loop_config();
+        assert_eq!(scan_loop.scan_interval, std::time::Duration::from_secs(2));
+        assert_eq!(scan_loop.retry_backoff, basis_store::ScanLoopConfig::default().retry_backoff);
+        assert_eq!(scan_loop.wait(0), std::time::Duration::from_secs(2));
+
+        let mut config = config;
+        config.scanner.scan_interval_secs = 0;
+        config.scanner.scan_jitter_percent = 150;
+        let issues = match config.validate() {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+        assert_eq!(fields, vec!["scanner.scan_interval_secs", "scanner.scan_jitter_percent"]);
+    }
+
+    #[test]
+    fn test_explorer_scan_backend() {
+        // The explorer must be set, and redemptions completed by clients
+        let mut env = config::Map::new();
+        env.insert("BASIS_SCANNER__BACKEND".to_string(), "explorer".to_string());
+        env.insert("BASIS_SCANNER__AUTO_COMPLETE_REDEMPTIONS".to_string(), "true".to_string());
+        let issues = match AppConfig::load_with_env(&repo_config_args(), Some(env.clone())) {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+        assert_eq!(fields, vec!["scanner.explorer_url", "scanner.auto_complete_redemptions"]);
+
+        env.insert("BASIS_SCANNER__EXPLORER_URL".to_string(), "https://api.ergoplatform.com".to_string());
+        env.insert("BASIS_SCANNER__AUTO_COMPLETE_REDEMPTIONS".to_string(), "false".to_string());
+        env.insert("BASIS_SCANNER__EXPLORER_CACHE_TTL_SECS".to_string(), "30".to_string());
+        let mut config = AppConfig::load_with_env(&repo_config_args(), Some(env)).unwrap();
+        assert_eq!(config.scanner.backend, ScanBackend::Explorer);
+        let explorer = config.explorer_config().unwrap();
+        assert_eq!(explorer.url, "https://api.ergoplatform.com");
+        assert_eq!(explorer.cache_ttl, std::time::Duration::from_secs(30));
+        assert_eq!(explorer.requests_per_second, basis_store::explorer_backend::DEFAULT_EXPLORER_REQUESTS_PER_SECOND);
+        assert_eq!(explorer.page_size, config.scanner.backfill_page_size);
+
+        // An explorer URL alone only discovers reserves
+        config.scanner.backend = ScanBackend::Node;
+        assert_eq!(config.explorer_config(), None);
+    }
+
+    #[test]
+    fn test_reload_applies_scan_timing_and_debt_ceiling() {
+        let mut running = AppConfig::load_with_env(&repo_config_args(), Some(config::Map::new())).unwrap();
+        let mut env = config::Map::new();
+        env.insert("BASIS_SCANNER__SCAN_INTERVAL_SECS".to_string(), "5".to_string());
+        env.insert("BASIS_DEBT_POLICY__MAX_DEBT_RATIO".to_string(), "0.5".to_string());
+        env.insert("BASIS_SERVER__PORT".to_string(), "4000".to_string());
+        let loaded = AppConfig::load_with_env(&repo_config_args(), Some(env)).unwrap();
+        assert_eq!(running.changed_sections(&loaded), vec!["debt_policy", "scanner", "server"]);
+
+        running.apply_reloadable(&loaded);
+        assert_eq!(running.scan_loop_config().scan_interval, std::time::Duration::from_secs(5));
+        assert_eq!(running.debt_policy, loaded.debt_policy);
+        // The listening port only changes on restart
+        assert_eq!(running.changed_sections(&loaded), vec!["server"]);
+    }
+
+    #[test]
+    fn test_watcher_must_not_hold_signing_key() {
+        let mut args = repo_config_args();
+        args.watcher = true;
+        let issues = match AppConfig::load_with_env(&args, Some(config::Map::new())) {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "ergo.tracker_secret_key");
+
+        let mut env = config::Map::new();
+        env.insert("BASIS_ERGO__TRACKER_SECRET_KEY".to_string(), String::new());
+        let config = AppConfig::load_with_env(&args, Some(env)).unwrap();
+        assert!(config.watcher.enabled);
+    }
+
+    #[test]
+    fn test_replica_must_not_hold_signing_key() {
+        let mut args = repo_config_args();
+        args.replica_of = Some("http://primary.example.com:3048".to_string());
+        let issues = match AppConfig::load_with_env(&args, Some(config::Map::new())) {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "replica.primary_url");
+
+        let mut env = config::Map::new();
+        env.insert("BASIS_ERGO__TRACKER_SECRET_KEY".to_string(), String::new());
+        let config = AppConfig::load_with_env(&args, Some(env.clone())).unwrap();
+        assert!(config.replica.is_enabled());
+
+        args.replica_of = Some("primary.example.com".to_string());
+        let issues = match AppConfig::load_with_env(&args, Some(env)) {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        assert_eq!(issues[0].message, "'primary.example.com' is not an http(s) URL");
+    }
+
+    #[test]
+    fn test_admin_token_must_differ_from_api_keys() {
+        let mut env = config::Map::new();
+        env.insert("BASIS_AUTH__API_KEYS".to_string(), "operator".to_string());
+        env.insert("BASIS_AUTH__ADMIN_TOKEN".to_string(), "operator".to_string());
+        let issues = match AppConfig::load_with_env(&repo_config_args(), Some(env.clone())) {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "auth.admin_token");
+
+        env.insert("BASIS_AUTH__ADMIN_TOKEN".to_string(), "admin".to_string());
+        let config = AppConfig::load_with_env(&repo_config_args(), Some(env)).unwrap();
+        assert_eq!(config.auth.admin_token.as_deref(), Some("admin"));
+    }
+
+    #[test]
+    fn test_storage_encryption_keys() {
+        assert!(!StorageEncryptionConfig::default().cipher().unwrap().is_enabled());
+
+        let var = format!("BASIS_TEST_STORAGE_KEY_{}", std::process::id());
+        std::env::set_var(&var, "22".repeat(32));
+        let config = StorageEncryptionConfig {
+            key_env: Some(var.clone()),
+            previous_keys: vec!["11".repeat(32)],
+            ..Default::default()
+        };
+        let rotated = config.cipher().unwrap();
+        assert_eq!(rotated.key_ids(), StorageCipher::new([0x22; 32], &[[0x11; 32]]).key_ids());
+
+        let config = StorageEncryptionConfig {
+            key: Some("11".repeat(32)),
+            key_env: Some(var.clone()),
+            ..Default::default()
+        };
+        assert_eq!(config.cipher().unwrap_err().0, "storage.encryption.key");
+        let config = StorageEncryptionConfig {
+            previous_key_envs: vec![format!("{}_UNSET", var)],
+            ..Default::default()
+        };
+        assert_eq!(config.cipher().unwrap_err().0, "storage.encryption.key");
+
+        let mut env = config::Map::new();
+        env.insert("BASIS_STORAGE__ENCRYPTION__KEY".to_string(), "abcd".to_string());
+        let issues = match AppConfig::load_with_env(&repo_config_args(), Some(env)) {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "storage.encryption.key");
+        std::env::remove_var(&var);
+    }
+
+    #[test]
+    fn test_signer_config() {
+        let mut env = config::Map::new();
+        env.insert("BASIS_SIGNER__KIND".to_string(), "remote".to_string());
+        let issues = match AppConfig::load_with_env(&repo_config_args(), Some(env.clone())) {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        let fields: Vec<&str> = issues.iter().map(|issue| issue.field.as_str()).collect();
+        assert_eq!(fields, vec!["signer.url"]);
+
+        env.insert("BASIS_SIGNER__SOCKET_PATH".to_string(), "/run/basis-signer.sock".to_string());
+        let config = AppConfig::load_with_env(&repo_config_args(), Some(env)).unwrap();
+        assert_eq!(config.signer.resolved_kind(true), SignerKind::Remote);
+
+        // The repository configuration already holds the secret key
+        let mut config = config;
+        config.signer = SignerConfig {
+            key_file: Some(PathBuf::from("tracker.key")),
+            ..SignerConfig::default()
+        };
+        let issues = match config.validate() {
+            Err(ConfigError::Invalid(issues)) => issues,
+            other => panic!("expected validation issues, got {:?}", other),
+        };
+        assert_eq!(issues[0].field, "signer.key_file");
+        assert!(issues[0].message.contains("ergo.tracker_secret_key"));
+    }
+
     #[test]
     fn test_tracker_public_key_p2pk_address_format() {
         // This test would validate P2PK address parsing, but to avoid complex ergo-lib
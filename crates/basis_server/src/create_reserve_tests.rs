@@ -11,13 +11,13 @@ mod create_reserve_tests {
     use crate::{
         api::create_reserve_payload,
         models::{CreateReserveRequest, ReserveCreationResponse},
-        AppState, TrackerCommand,
+        AppState,
     };
     use basis_store::ergo_scanner::{NodeConfig, ServerState};
 
     // Helper function to create a test AppState that doesn't require file system access
     fn create_test_app_state() -> AppState {
-        let (tx, _rx) = tokio::sync::mpsc::channel::<TrackerCommand>(100);
+        let (tx, _rx) = crate::tracker_queue::tracker_channel(Default::default());
         let event_store = std::sync::Arc::new(crate::store::EventStore::new_in_memory());
 
         // Create a minimal configuration
@@ -43,13 +43,17 @@ mod create_reserve_tests {
                 host: "127.0.0.1".to_string(),
                 port: 3048,
                 database_url: Some("sqlite::memory:".to_string()),
+                shutdown_timeout_secs: crate::shutdown::DEFAULT_SHUTDOWN_TIMEOUT_SECS,
             },
             ergo: crate::config::ErgoConfig {
+                network: Default::default(),
                 node: NodeConfig {
                     node_url: "http://example.com".to_string(),
                     ..Default::default()
                 },
                 basis_reserve_contract_p2s: "test".to_string(),
+                reserve_contract_version: 1,
+                legacy_reserve_contracts: Vec::new(),
                 tracker_nft_id: Some("69c5d7a4df2e72252b0015d981876fe338ca240d5576d4e731dfd848ae18fe2b".to_string()),
                 tracker_public_key: Some("9fRusAarL1KkrWQVsxSRVYnvWxaAT2A96cKtNn9tvPh5XUyCisr33".to_string()),
                 tracker_secret_key: None,
@@ -59,21 +63,49 @@ mod create_reserve_tests {
                         change_address: None,
             },
             acceptance: crate::acceptance::config::AcceptanceConfig::empty(),
+            debt_policy: basis_store::debt_policy::DebtPolicyConfig::default(),
+            note_limits: basis_store::NoteLimits::default(),
+            idempotency: crate::idempotency::IdempotencyConfig::default(),
+            storage: crate::config::StorageConfig::default(),
+            scanner: crate::config::ScannerConfig::default(),
+            fee_policy: crate::config::FeePolicyConfig::default(),
+            auth: crate::config::AuthConfig::default(),
+            watcher: crate::config::WatcherConfig::default(),
+            webhooks: crate::webhooks::WebhookConfig::default(),
+            commitment_schedule: crate::commitment_scheduler::CommitmentScheduleConfig::default(),
+            signer: crate::tracker_signer::SignerConfig::default(),
+            tracker_queue: crate::tracker_queue::TrackerQueueConfig::default(),
+            replica: crate::replica::ReplicaConfig::default(),
+            issuer_policy: crate::issuer_policy::IssuerPolicyConfig::default(),
+            maintenance: crate::maintenance::MaintenanceConfig::default(),
+            collateral_history: crate::collateral_history::CollateralHistoryConfig::default(),
+            pricing: crate::pricing::PricingConfig::default(),
+            outbound_tx: crate::outbound_tx::OutboundTxConfig::default(),
+            logging: crate::logging::LoggingConfig::default(),
+            daemon: crate::daemon::DaemonConfig::default(),
+            request_audit: crate::request_audit::RequestAuditConfig::default(),
         });
 
-        let reserve_tracker = Arc::new(Mutex::new(basis_store::ReserveTracker::new()));
-
         AppState {
             tx,
             event_store,
+            reserve_tracker: scanner.reserve_tracker().clone(),
             ergo_scanner: Arc::new(Mutex::new(scanner)),
-            reserve_tracker,
-            config: test_config,
+            config: test_config.clone(),
             shared_tracker_state: Arc::new(tokio::sync::Mutex::new(crate::tracker_box_updater::SharedTrackerState::new())),
             tracker_storage: basis_store::persistence::TrackerStorage::open("test_tracker").unwrap_or_else(|_| {
                 basis_store::persistence::TrackerStorage::open("test_tracker_fallback").unwrap()
             }),
             acceptance_predicate: None,
+            signing_service: Arc::new(crate::signing_service::SigningService::from_config(&test_config)),
+            cold_start: crate::cold_start::ColdStartState::default(),
+            replica: crate::replica::ReplicaState::default(),
+            issuer_policy: crate::issuer_policy::IssuerPolicy::default(),
+            maintenance: crate::maintenance::StorageMaintenance::default(),
+            pricing: crate::pricing::Pricing::default(),
+            outbound_tx: crate::outbound_tx::OutboundTxQueue::default(),
+            request_audit: crate::request_audit::RequestAuditLog::default(),
+            contracts: basis_store::ContractRegistry::builtin(),
         }
     }
 
@@ -114,7 +146,8 @@ mod create_reserve_tests {
             if !reserve_response.requests.is_empty() {
                 assert_eq!(reserve_response.requests[0].value, 1000000000);
                 assert_eq!(reserve_response.requests[0].assets[0].token_id, "1234567890abcdef1234567890abcdef1234567890abcdef1234567890abcdef");
-                assert_eq!(reserve_response.requests[0].registers.get("R4").unwrap(), "03e8c3e4877e2f7b79e0e407421a81a1619ea64e37e5e4e77454d1e361e6f80b12");
+                // R4 holds the owner key as a serialized GroupElement constant
+                assert_eq!(reserve_response.requests[0].registers.get("R4").unwrap(), "0703e8c3e4877e2f7b79e0e407421a81a1619ea64e37e5e4e77454d1e361e6f80b12");
                 assert!(reserve_response.fee > 0); // Should be the configured fee amount
             }
         }
@@ -0,0 +1,219 @@
+//! Running under systemd or another service manager
+//!
+//! - Socket activation: started with sockets passed by systemd
+//!   (`LISTEN_FDS`), the server serves the first one instead of binding
+//!   `server.host:server.port` itself.
+//! - Readiness: `READY=1` is sent to `NOTIFY_SOCKET` once the scanner and the
+//!   tracker thread are running and the listener is open, so `Type=notify`
+//!   units start their dependents only then; `STOPPING=1` when shutdown
+//!   starts. Without `NOTIFY_SOCKET` nothing is sent.
+//! - PID file: `daemon.pid_file` (or `--pid-file`) holds the process ID while
+//!   the server runs.
+//! - Reload: on SIGHUP the configuration is loaded again. The scan loop
+//!   timing and the debt ceiling take effect at once, without touching open
+//!   connections; changes to any other section are logged as needing a
+//!   restart. A configuration that does not load or validate is reported and
+//!   the running one kept.
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+
+use basis_store::ergo_scanner::ServerState;
+
+use crate::config::{AppConfig, ServerArgs};
+use crate::requests;
+use crate::tracker_queue::TrackerSender;
+
+/// `[daemon]` section of the server configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct DaemonConfig {
+    /// File to write the process ID to while the server runs
+    #[serde(default)]
+    pub pid_file: Option<PathBuf>,
+}
+
+/// PID file removed again when dropped
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+}
+
+impl PidFile {
+    /// Write the ID of this process to `path`, replacing a stale file
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, format!("{}\n", std::process::id()))?;
+        Ok(Self { path: path.to_path_buf() })
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // Leave the file alone if another process has taken it over
+        let ours = std::fs::read_to_string(&self.path)
+            .is_ok_and(|contents| contents.trim() == std::process::id().to_string());
+        if ours {
+            if let Err(e) = std::fs::remove_file(&self.path) {
+                tracing::warn!("Failed to remove PID file {}: {}", self.path.display(), e);
+            }
+        }
+    }
+}
+
+/// Listening socket passed by systemd socket activation, if any
+pub fn inherited_listener() -> std::io::Result<Option<tokio::net::TcpListener>> {
+    match listenfd::ListenFd::from_env().take_tcp_listener(0)? {
+        Some(listener) => {
+            listener.set_nonblocking(true)?;
+            Ok(Some(tokio::net::TcpListener::from_std(listener)?))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Tell the service manager the server is ready to serve requests
+pub fn notify_ready(status: &str) {
+    service_manager::notify_ready(status);
+}
+
+/// Tell the service manager the server is stopping
+pub fn notify_stopping() {
+    service_manager::notify_stopping();
+}
+
+#[cfg(unix)]
+mod service_manager {
+    use sd_notify::NotifyState;
+
+    pub fn notify_ready(status: &str) {
+        notify(&[NotifyState::Ready, NotifyState::Status(status)]);
+    }
+
+    pub fn notify_stopping() {
+        notify(&[NotifyState::Stopping]);
+    }
+
+    pub fn notify_reloading() {
+        // `Type=notify-reload` units need the time the reload started
+        match NotifyState::monotonic_usec_now() {
+            Ok(now) => notify(&[NotifyState::Reloading, now]),
+            Err(_) => notify(&[NotifyState::Reloading]),
+        }
+    }
+
+    fn notify(state: &[NotifyState]) {
+        if let Err(e) = sd_notify::notify(false, state) {
+            tracing::warn!("Failed to notify the service manager: {}", e);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+mod service_manager {
+    pub fn notify_ready(_status: &str) {}
+
+    pub fn notify_stopping() {}
+}
+
+/// Reload the configuration on every SIGHUP until shutdown
+///
+/// `running` is the configuration the server started with; `args` are its
+/// command-line flags, which keep precedence over the reloaded file.
+#[cfg(unix)]
+pub async fn reload_on_sighup(
+    args: ServerArgs,
+    mut running: AppConfig,
+    scanner: ServerState,
+    tracker: TrackerSender,
+    mut shutdown_rx: broadcast::Receiver<()>,
+) {
+    let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+        Ok(hangup) => hangup,
+        Err(e) => {
+            tracing::error!("Failed to listen for SIGHUP, configuration reload disabled: {}", e);
+            return;
+        }
+    };
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            _ = hangup.recv() => {}
+        }
+        tracing::info!("Received SIGHUP, reloading configuration");
+        service_manager::notify_reloading();
+        reload(&args, &mut running, &scanner, &tracker).await;
+        notify_ready("Configuration reloaded");
+    }
+}
+
+#[cfg(unix)]
+async fn reload(args: &ServerArgs, running: &mut AppConfig, scanner: &ServerState, tracker: &TrackerSender) {
+    let mut loaded = match AppConfig::load_layered(args) {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            tracing::error!("Configuration not reloaded, keeping the running one: {}", e);
+            return;
+        }
+    };
+
+    if loaded.scan_loop_config() != running.scan_loop_config() {
+        scanner.set_scan_loop_config(loaded.scan_loop_config());
+        tracing::info!("Reserve scan loop timing reloaded");
+    }
+    if loaded.debt_policy != running.debt_policy {
+        match set_debt_policy(&loaded.debt_policy, tracker).await {
+            Ok(()) => tracing::info!("Debt ceiling reloaded"),
+            Err(e) => {
+                tracing::error!("Debt ceiling not reloaded, keeping the running one: {}", e);
+                loaded.debt_policy = running.debt_policy.clone();
+            }
+        }
+    }
+
+    running.apply_reloadable(&loaded);
+    let needing_restart = running.changed_sections(&loaded);
+    if !needing_restart.is_empty() {
+        tracing::warn!("Changes to [{}] take effect on restart", needing_restart.join("], ["));
+    }
+}
+
+#[cfg(unix)]
+async fn set_debt_policy(config: &basis_store::debt_policy::DebtPolicyConfig, tracker: &TrackerSender) -> Result<(), String> {
+    let policy = if config.enabled {
+        Some(basis_store::DebtCeilingPolicy::from_config(config).map_err(|e| e.to_string())?)
+    } else {
+        None
+    };
+    tracker
+        .request(requests::SetDebtPolicy { policy })
+        .await
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_file_is_removed_on_drop() {
+        let dir = std::env::temp_dir().join(format!("basis_pid_test_{}", std::process::id()));
+        let path = dir.join("run").join("basis_server.pid");
+
+        let pid_file = PidFile::create(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), format!("{}\n", std::process::id()));
+        drop(pid_file);
+        assert!(!path.exists());
+
+        // A file rewritten by another process is left in place
+        let pid_file = PidFile::create(&path).unwrap();
+        std::fs::write(&path, "1\n").unwrap();
+        drop(pid_file);
+        assert!(path.exists());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,244 @@
+//! API handlers for disputes filed against notes
+
+use axum::{extract::State, http::StatusCode, Json};
+use std::collections::HashMap;
+
+use basis_store::{Dispute, DisputeError, DisputeFiling, DisputeStatus, IouNote, PubKey, Signature, SigningFormat};
+
+use crate::{
+    models::{
+        error_response, success_response, ApiResponse, DisputeEvidenceRequest, EventType, FileDisputeRequest,
+        ResolveDisputeRequest, TrackerEvent,
+    },
+    requests,
+    tracker_request::TrackerUnavailable,
+    AppState,
+};
+
+fn decode<const N: usize>(value: &str, field: &str) -> Result<[u8; N], String> {
+    hex::decode(value)
+        .map_err(|_| format!("{} must be hex-encoded", field))?
+        .try_into()
+        .map_err(|_| format!("{} must be {} bytes", field, N))
+}
+
+// Rebuild a note presented as evidence for the given recipient
+fn evidence_note(recipient_pubkey: PubKey, evidence: &DisputeEvidenceRequest) -> Result<IouNote, String> {
+    let signature: Signature = decode(&evidence.signature, "evidence signature")?;
+    let signing_format = match (evidence.signing_version, &evidence.tracker_id) {
+        (1, None) => SigningFormat::V1,
+        (2, tracker_id) => SigningFormat::V2 {
+            tracker_id: tracker_id
+                .as_deref()
+                .map(|tracker_id| decode(tracker_id, "evidence tracker_id"))
+                .transpose()?,
+        },
+        (1, Some(_)) => return Err("evidence tracker_id requires signing_version 2".to_string()),
+        (version, _) => return Err(format!("Unsupported evidence signing_version {}", version)),
+    };
+    Ok(IouNote::new(recipient_pubkey, evidence.amount, 0, evidence.timestamp, signature)
+        .with_signing_format(signing_format))
+}
+
+fn filing_from_request(payload: &FileDisputeRequest) -> Result<DisputeFiling, String> {
+    let issuer_pubkey: PubKey = decode(&payload.issuer_pubkey, "issuer_pubkey")?;
+    let recipient_pubkey: PubKey = decode(&payload.recipient_pubkey, "recipient_pubkey")?;
+    let signature: Signature = decode(&payload.signature, "signature")?;
+    let evidence = payload
+        .evidence
+        .iter()
+        .map(|evidence| evidence_note(recipient_pubkey, evidence))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(DisputeFiling {
+        issuer_pubkey,
+        recipient_pubkey,
+        filed_by: payload.filed_by,
+        reason: payload.reason.clone(),
+        evidence,
+        filed_at: payload.filed_at,
+        signature,
+    })
+}
+
+fn dispute_error_status(error: &DisputeError) -> StatusCode {
+    match error {
+        DisputeError::NoteNotFound | DisputeError::NotFound(_) => StatusCode::NOT_FOUND,
+        DisputeError::AlreadyOpen(_) | DisputeError::AlreadyClosed(_) => StatusCode::CONFLICT,
+        DisputeError::StorageError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        DisputeError::InvalidSignature
+        | DisputeError::InvalidEvidence(_)
+        | DisputeError::FutureTimestamp
+        | DisputeError::InvalidResolution => StatusCode::BAD_REQUEST,
+    }
+}
+
+async fn record_dispute_event(state: &AppState, event_type: EventType, dispute: &Dispute) {
+    let event = TrackerEvent {
+        id: 0, // Will be set by event store
+        seq: 0,
+        event_type,
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        issuer_pubkey: Some(dispute.issuer_pubkey.clone()),
+        recipient_pubkey: Some(dispute.recipient_pubkey.clone()),
+        amount: None,
+        reserve_box_id: None,
+        collateral_amount: None,
+        redeemed_amount: None,
+        height: None,
+    };
+    if let Err(e) = state.event_store.add_event(event).await {
+        tracing::warn!("Failed to store dispute event: {:?}", e);
+    }
+}
+
+/// File a signed dispute against a note, freezing its redemption
+#[axum::debug_handler]
+pub async fn file_dispute(
+    State(state): State<AppState>,
+    Json(payload): Json<FileDisputeRequest>,
+) -> (StatusCode, Json<ApiResponse<Dispute>>) {
+    tracing::debug!("Filing dispute: {:?}", payload);
+
+    let filing = match filing_from_request(&payload) {
+        Ok(filing) => filing,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+
+    match state.send(requests::FileDispute { filing }).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(dispute)) => {
+            record_dispute_event(&state, EventType::DisputeFiled, &dispute).await;
+            (StatusCode::CREATED, Json(success_response(dispute)))
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("Refused dispute: {}", e);
+            (dispute_error_status(&e), Json(error_response(e.to_string())))
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
+
+/// List disputes, oldest first
+/// Query: issuer, recipient (hex public keys), status (open|upheld|dismissed)
+#[axum::debug_handler]
+pub async fn get_disputes(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<Vec<Dispute>>>) {
+    tracing::debug!("Getting disputes with params: {:?}", params);
+
+    let status = match params.get("status").map(String::as_str) {
+        None => None,
+        Some("open") => Some(DisputeStatus::Open),
+        Some("upheld") => Some(DisputeStatus::Upheld),
+        Some("dismissed") => Some(DisputeStatus::Dismissed),
+        Some(other) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(error_response(format!("Unknown dispute status '{}'", other))),
+            )
+        }
+    };
+    let issuer = params.get("issuer").map(|issuer| issuer.to_lowercase());
+    let recipient = params.get("recipient").map(|recipient| recipient.to_lowercase());
+
+    match state.send(requests::GetDisputes {}).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(disputes)) => {
+            let disputes = disputes
+                .into_iter()
+                .filter(|dispute| {
+                    status.is_none_or(|status| dispute.status == status)
+                        && issuer.as_deref().is_none_or(|issuer| dispute.issuer_pubkey == issuer)
+                        && recipient.as_deref().is_none_or(|recipient| dispute.recipient_pubkey == recipient)
+                })
+                .collect();
+            (StatusCode::OK, Json(success_response(disputes)))
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get disputes: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response("Failed to retrieve disputes".to_string())),
+            )
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
+
+/// Get a dispute by ID
+#[axum::debug_handler]
+pub async fn get_dispute(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+) -> (StatusCode, Json<ApiResponse<Dispute>>) {
+    tracing::debug!("Getting dispute {}", id);
+
+    match state.send(requests::GetDispute { id }).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(Some(dispute))) => (StatusCode::OK, Json(success_response(dispute))),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(error_response(DisputeError::NotFound(id).to_string())),
+        ),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get dispute {}: {:?}", id, e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response("Failed to retrieve dispute".to_string())),
+            )
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
+
+/// Close a dispute as upheld or dismissed, unfreezing its note
+#[axum::debug_handler]
+pub async fn resolve_dispute(
+    State(state): State<AppState>,
+    axum::extract::Path(id): axum::extract::Path<u64>,
+    Json(payload): Json<ResolveDisputeRequest>,
+) -> (StatusCode, Json<ApiResponse<Dispute>>) {
+    tracing::debug!("Resolving dispute {}: {:?}", id, payload);
+
+    let request = requests::CloseDispute {
+        id,
+        status: payload.status,
+        note: payload.note,
+    };
+    match state.send(request).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(dispute)) => {
+            record_dispute_event(&state, EventType::DisputeResolved, &dispute).await;
+            (StatusCode::OK, Json(success_response(dispute)))
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("Failed to resolve dispute {}: {}", id, e);
+            (dispute_error_status(&e), Json(error_response(e.to_string())))
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
+
+/// Refuse to act on a redemption of a note frozen by an open dispute
+pub(crate) async fn ensure_not_disputed(
+    state: &AppState,
+    issuer_pubkey: &PubKey,
+    recipient_pubkey: &PubKey,
+) -> Result<(), (StatusCode, String)> {
+    let request = requests::GetOpenDispute {
+        issuer_pubkey: *issuer_pubkey,
+        recipient_pubkey: *recipient_pubkey,
+    };
+    match state.send(request).await.map_err(TrackerUnavailable::status)? {
+        Ok(None) => Ok(()),
+        Ok(Some(dispute)) => Err((
+            StatusCode::CONFLICT,
+            basis_store::RedemptionError::NoteDisputed(dispute.id).to_string(),
+        )),
+        Err(e) => {
+            tracing::error!("Failed to look up disputes: {:?}", e);
+            Err((StatusCode::INTERNAL_SERVER_ERROR, "Failed to look up disputes".to_string()))
+        }
+    }
+}
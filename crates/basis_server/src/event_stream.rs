@@ -0,0 +1,226 @@
+//! Live stream of tracker events over a WebSocket
+//!
+//! `GET /events/stream` upgrades to a WebSocket that receives every stored
+//! event as a JSON text message, the same shape `/events` returns, in sequence
+//! order. With `?since_seq=N` the stream starts with the stored events after
+//! sequence number `N`, then continues with new events as they are stored, so
+//! a client that reconnects with the last `seq` it received misses and repeats
+//! nothing. The `type`, `pubkey`, `from_height` and `to_height` filters of
+//! `/events/paginated` apply too.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use tokio::sync::broadcast;
+
+use crate::models::TrackerEvent;
+use crate::store::{EventQuery, EventStore};
+use crate::AppState;
+
+/// Stored events read at a time while catching up
+const CATCH_UP_PAGE_SIZE: usize = 500;
+
+/// Events of a subscription: the stored ones after its starting point, then
+/// new ones as they are stored
+///
+/// The feed subscribes to the store before reading stored events, so an event
+/// stored meanwhile is in both and is passed on once. When the feed falls too
+/// far behind the store's notifications, it reads the missed events back from
+/// the store.
+pub struct EventFeed {
+    event_store: Arc<EventStore>,
+    live: broadcast::Receiver<TrackerEvent>,
+    filters: EventQuery,
+    /// Sequence number of the last event looked at
+    last_seq: u64,
+    backlog: VecDeque<TrackerEvent>,
+    caught_up: bool,
+}
+
+impl EventFeed {
+    /// Follow the events matching `filters`, starting after its `since_seq`
+    /// or with the first event stored
+    pub fn new(event_store: Arc<EventStore>, filters: EventQuery) -> Self {
+        Self {
+            live: event_store.subscribe(),
+            last_seq: filters.since_seq.unwrap_or(0),
+            event_store,
+            filters,
+            backlog: VecDeque::new(),
+            caught_up: false,
+        }
+    }
+
+    /// The next matching event, waiting for one to be stored if needed
+    ///
+    /// Cancelling the call loses no event.
+    pub async fn next(&mut self) -> Result<TrackerEvent, Box<dyn std::error::Error>> {
+        loop {
+            if let Some(event) = self.backlog.pop_front() {
+                self.last_seq = event.seq;
+                return Ok(event);
+            }
+            if !self.caught_up {
+                let page = self
+                    .event_store
+                    .query_events(&EventQuery {
+                        since_seq: Some(self.last_seq),
+                        offset: 0,
+                        limit: CATCH_UP_PAGE_SIZE,
+                        ..self.filters.clone()
+                    })
+                    .await?;
+                self.caught_up = page.len() < CATCH_UP_PAGE_SIZE;
+                self.backlog.extend(page);
+                continue;
+            }
+            match self.live.recv().await {
+                Ok(event) if event.seq > self.last_seq => {
+                    self.last_seq = event.seq;
+                    if self.filters.matches(&event) {
+                        return Ok(event);
+                    }
+                }
+                Ok(_) => {}
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::debug!("Event stream missed {} notifications, reading them back", missed);
+                    self.caught_up = false;
+                }
+                Err(broadcast::error::RecvError::Closed) => return Err("event store closed".into()),
+            }
+        }
+    }
+}
+
+// Stream tracker events over a WebSocket
+// Query: since_seq, and the filters of /events/paginated
+pub async fn stream_events(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let filters = match crate::api::event_filters(&params) {
+        Ok(filters) => filters,
+        Err(message) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(crate::models::error_response::<()>(message)),
+            )
+                .into_response()
+        }
+    };
+    tracing::debug!("Streaming events: {:?}", filters);
+
+    let feed = EventFeed::new(state.event_store.clone(), filters);
+    ws.on_upgrade(move |socket| send_events(socket, feed))
+}
+
+async fn send_events(mut socket: WebSocket, mut feed: EventFeed) {
+    loop {
+        let event = tokio::select! {
+            event = feed.next() => event.map_err(|e| e.to_string()),
+            message = socket.recv() => match message {
+                // Pings are answered by axum; clients have nothing else to say
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+        };
+        let event = match event {
+            Ok(event) => event,
+            Err(e) => {
+                tracing::warn!("Event stream stopped: {}", e);
+                break;
+            }
+        };
+        let text = match serde_json::to_string(&event) {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::warn!("Failed to serialize event {}: {}", event.seq, e);
+                break;
+            }
+        };
+        if socket.send(Message::Text(text.into())).await.is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EventType;
+
+    fn event(event_type: EventType) -> TrackerEvent {
+        TrackerEvent {
+            id: 0,
+            seq: 0,
+            event_type,
+            timestamp: 1_700_000_000,
+            issuer_pubkey: Some("aa".to_string()),
+            recipient_pubkey: None,
+            amount: None,
+            reserve_box_id: None,
+            collateral_amount: None,
+            redeemed_amount: None,
+            height: None,
+        }
+    }
+
+    async fn next_seqs(feed: &mut EventFeed, count: usize) -> Vec<u64> {
+        let mut seqs = Vec::new();
+        for _ in 0..count {
+            seqs.push(feed.next().await.unwrap().seq);
+        }
+        seqs
+    }
+
+    #[tokio::test]
+    async fn test_feed_resumes_after_since_seq_without_gaps_or_repeats() {
+        for store in [EventStore::new_in_memory(), EventStore::open("sqlite::memory:").await.unwrap()] {
+            let store = Arc::new(store);
+            store
+                .add_events(vec![event(EventType::NoteUpdated), event(EventType::Commitment), event(EventType::NoteUpdated)])
+                .await
+                .unwrap();
+
+            let mut feed = EventFeed::new(store.clone(), EventQuery { since_seq: Some(1), ..EventQuery::default() });
+            // Stored after subscribing but before catching up: sent once
+            store.add_event(event(EventType::ReserveCreated)).await.unwrap();
+            assert_eq!(next_seqs(&mut feed, 3).await, vec![2, 3, 4]);
+
+            store.add_event(event(EventType::NoteUpdated)).await.unwrap();
+            assert_eq!(next_seqs(&mut feed, 1).await, vec![5]);
+
+            let filters = EventQuery {
+                event_type: Some("NoteUpdated".to_string()),
+                since_seq: Some(3),
+                ..EventQuery::default()
+            };
+            let mut filtered = EventFeed::new(store.clone(), filters);
+            store.add_event(event(EventType::Commitment)).await.unwrap();
+            store.add_event(event(EventType::NoteUpdated)).await.unwrap();
+            assert_eq!(next_seqs(&mut filtered, 2).await, vec![5, 7]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_lagging_feed_reads_missed_events_from_store() {
+        let store = Arc::new(EventStore::open("sqlite::memory:").await.unwrap());
+        let mut feed = EventFeed::new(store.clone(), EventQuery::default());
+        store.add_event(event(EventType::NoteUpdated)).await.unwrap();
+        assert_eq!(next_seqs(&mut feed, 1).await, vec![1]);
+
+        // More events than the notification channel holds
+        let count = crate::store::EVENT_CHANNEL_CAPACITY + 50;
+        store
+            .add_events((0..count).map(|_| event(EventType::NoteUpdated)).collect())
+            .await
+            .unwrap();
+        assert_eq!(next_seqs(&mut feed, count).await, (2..count as u64 + 2).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,484 @@
+//! Idempotency-Key support for state-changing POST endpoints
+//!
+//! Clients on unreliable networks retry requests whose response they never
+//! received. Without deduplication a retried `POST /notes` is rejected with
+//! `PastTimestamp` even though the first attempt succeeded. When a request
+//! carries an `Idempotency-Key` header, the response is stored together with a
+//! fingerprint of the request and replayed for retries within the configured
+//! window. Reusing a key with a different request body is rejected.
+//!
+//! Keys are scoped to the credential the request presents and to its method
+//! and path, so clients sharing a tracker cannot replay or block each other's
+//! keys. Entries expire lazily, oldest first, as new keys arrive.
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{Request, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use blake2::{Blake2b, Digest};
+use generic_array::typenum::U32;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::auth::{ADMIN_TOKEN_HEADER, API_KEY_HEADER};
+
+/// Request header carrying the client-chosen idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Response header set when a stored response is replayed
+pub const IDEMPOTENT_REPLAYED_HEADER: &str = "idempotent-replayed";
+
+/// Maximum accepted length of an idempotency key
+pub const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+// Upper bound on buffered request and response bodies (matches axum's default body limit)
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_window_secs() -> u64 {
+    24 * 60 * 60
+}
+
+fn default_max_entries() -> usize {
+    10_000
+}
+
+/// Idempotency key configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct IdempotencyConfig {
+    /// Whether Idempotency-Key headers are honoured
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// How long a stored response is replayed for, in seconds
+    #[serde(default = "default_window_secs")]
+    pub window_secs: u64,
+    /// Maximum number of stored responses; the oldest are evicted first
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            window_secs: default_window_secs(),
+            max_entries: default_max_entries(),
+        }
+    }
+}
+
+/// Response captured for replay
+#[derive(Debug, Clone)]
+struct StoredResponse {
+    status: StatusCode,
+    content_type: Option<HeaderValue>,
+    body: axum::body::Bytes,
+}
+
+#[derive(Debug, Clone)]
+enum EntryState {
+    /// The first request with this key is still being processed
+    InFlight,
+    Completed(StoredResponse),
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Position of the entry in `Entries::by_age`
+    seq: u64,
+    fingerprint: [u8; 32],
+    created_at: Instant,
+    state: EntryState,
+}
+
+/// Stored entries by scoped key, and the same keys oldest first
+#[derive(Debug, Default)]
+struct Entries {
+    by_key: HashMap<[u8; 32], Entry>,
+    by_age: BTreeMap<u64, [u8; 32]>,
+    next_seq: u64,
+}
+
+impl Entries {
+    fn remove(&mut self, key: &[u8; 32]) {
+        if let Some(entry) = self.by_key.remove(key) {
+            self.by_age.remove(&entry.seq);
+        }
+    }
+
+    // Entries are created in sequence order, so the expired ones come first
+    fn expire(&mut self, window: Duration) {
+        while let Some((_, key)) = self.by_age.first_key_value() {
+            let key = *key;
+            if self.by_key[&key].created_at.elapsed() < window {
+                break;
+            }
+            self.remove(&key);
+        }
+    }
+
+    // Requests still in flight are skipped, so the store can briefly exceed
+    // `max_entries` by the number of concurrent requests
+    fn evict_to(&mut self, max_entries: usize) {
+        while self.by_key.len() >= max_entries {
+            let oldest = self
+                .by_age
+                .values()
+                .find(|key| matches!(self.by_key[*key].state, EntryState::Completed(_)))
+                .copied();
+            match oldest {
+                Some(oldest) => self.remove(&oldest),
+                None => break,
+            }
+        }
+    }
+}
+
+/// Outcome of looking up a key before running the handler
+enum Lookup {
+    /// No usable entry; the caller now owns the entry with this sequence number
+    Proceed(u64),
+    Replay(StoredResponse),
+    InFlight,
+    Mismatch,
+}
+
+/// In-memory store of request fingerprints and responses
+pub struct IdempotencyStore {
+    config: IdempotencyConfig,
+    entries: Mutex<Entries>,
+}
+
+impl IdempotencyStore {
+    /// Create a store with the given configuration
+    pub fn new(config: IdempotencyConfig) -> Self {
+        Self {
+            config,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Number of stored keys, including requests still in flight
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().by_key.len()
+    }
+
+    /// Whether no keys are stored
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn window(&self) -> Duration {
+        Duration::from_secs(self.config.window_secs)
+    }
+
+    fn begin(&self, key: [u8; 32], fingerprint: [u8; 32]) -> Lookup {
+        let mut entries = self.entries.lock().unwrap();
+        entries.expire(self.window());
+
+        if let Some(entry) = entries.by_key.get(&key) {
+            if entry.fingerprint != fingerprint {
+                return Lookup::Mismatch;
+            }
+            return match &entry.state {
+                EntryState::InFlight => Lookup::InFlight,
+                EntryState::Completed(response) => Lookup::Replay(response.clone()),
+            };
+        }
+
+        entries.evict_to(self.config.max_entries.max(1));
+
+        let seq = entries.next_seq;
+        entries.next_seq += 1;
+        entries.by_age.insert(seq, key);
+        entries.by_key.insert(
+            key,
+            Entry {
+                seq,
+                fingerprint,
+                created_at: Instant::now(),
+                state: EntryState::InFlight,
+            },
+        );
+        Lookup::Proceed(seq)
+    }
+
+    // An entry that expired meanwhile may have been replaced under the same
+    // key, so both only touch the entry with the caller's sequence number
+    fn complete(&self, key: &[u8; 32], seq: u64, response: StoredResponse) {
+        if let Some(entry) = self.entries.lock().unwrap().by_key.get_mut(key) {
+            if entry.seq == seq {
+                entry.state = EntryState::Completed(response);
+            }
+        }
+    }
+
+    fn abandon(&self, key: &[u8; 32], seq: u64) {
+        let mut entries = self.entries.lock().unwrap();
+        if entries.by_key.get(key).is_some_and(|entry| entry.seq == seq) {
+            entries.remove(key);
+        }
+    }
+}
+
+// Releases an in-flight key if the request is dropped before completing,
+// e.g. when the client disconnects mid-request
+struct InFlightGuard {
+    store: Arc<IdempotencyStore>,
+    key: [u8; 32],
+    seq: u64,
+    completed: bool,
+}
+
+impl Drop for InFlightGuard {
+    fn drop(&mut self) {
+        if !self.completed {
+            self.store.abandon(&self.key, self.seq);
+        }
+    }
+}
+
+// Key under which a client key is stored: the credential presented, the
+// endpoint and the client key, hashed so that credentials are not kept
+fn scoped_key(headers: &HeaderMap, method: &str, path: &str, key: &str) -> [u8; 32] {
+    let credential = match (headers.get(API_KEY_HEADER), headers.get(ADMIN_TOKEN_HEADER)) {
+        (Some(api_key), _) => [b"api-key:".as_slice(), api_key.as_bytes()].concat(),
+        (None, Some(admin_token)) => [b"admin-token:".as_slice(), admin_token.as_bytes()].concat(),
+        (None, None) => Vec::new(),
+    };
+    let mut hasher = Blake2b::<U32>::new();
+    for part in [credential.as_slice(), method.as_bytes(), path.as_bytes(), key.as_bytes()] {
+        hasher.update((part.len() as u64).to_be_bytes());
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+// Fingerprint of everything that identifies the request
+fn request_fingerprint(method: &str, path: &str, body: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(method.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(path.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(body);
+    hasher.finalize().into()
+}
+
+fn error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        Json(crate::models::error_response::<()>(message.to_string())),
+    )
+        .into_response()
+}
+
+fn replay(stored: StoredResponse) -> Response {
+    let mut response = Response::new(Body::from(stored.body));
+    *response.status_mut() = stored.status;
+    if let Some(content_type) = stored.content_type {
+        response.headers_mut().insert(header::CONTENT_TYPE, content_type);
+    }
+    response
+        .headers_mut()
+        .insert(IDEMPOTENT_REPLAYED_HEADER, HeaderValue::from_static("true"));
+    response
+}
+
+/// Middleware deduplicating requests that carry an Idempotency-Key header
+///
+/// Requests without the header pass through unchanged. Server errors are not
+/// stored, so a retry after a 5xx response is processed again.
+pub async fn idempotency_layer(
+    State(store): State<Arc<IdempotencyStore>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !store.config.enabled {
+        return next.run(request).await;
+    }
+
+    let key = match request.headers().get(IDEMPOTENCY_KEY_HEADER) {
+        None => return next.run(request).await,
+        Some(value) => match value.to_str() {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => key.to_string(),
+            _ => {
+                return error(
+                    StatusCode::BAD_REQUEST,
+                    "Idempotency-Key must be 1 to 255 visible ASCII characters",
+                )
+            }
+        },
+    };
+
+    let method = request.method().to_string();
+    let path = request.uri().path().to_string();
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => return error(StatusCode::PAYLOAD_TOO_LARGE, "Request body too large"),
+    };
+
+    let scoped_key = scoped_key(&parts.headers, &method, &path, &key);
+    let fingerprint = request_fingerprint(&method, &path, &body);
+
+    let seq = match store.begin(scoped_key, fingerprint) {
+        Lookup::Proceed(seq) => seq,
+        Lookup::Replay(stored) => {
+            tracing::info!("Replaying stored response for idempotency key {}", key);
+            return replay(stored);
+        }
+        Lookup::InFlight => {
+            return error(
+                StatusCode::CONFLICT,
+                "A request with this Idempotency-Key is still being processed",
+            )
+        }
+        Lookup::Mismatch => {
+            return error(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Idempotency-Key was already used with a different request",
+            )
+        }
+    };
+
+    let mut guard = InFlightGuard {
+        store: store.clone(),
+        key: scoped_key,
+        seq,
+        completed: false,
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    if response.status().is_server_error() {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::error!("Failed to buffer response for idempotency key {}: {}", key, e);
+            return error(StatusCode::INTERNAL_SERVER_ERROR, "Failed to read response");
+        }
+    };
+
+    store.complete(
+        &guard.key,
+        guard.seq,
+        StoredResponse {
+            status: parts.status,
+            content_type: parts.headers.get(header::CONTENT_TYPE).cloned(),
+            body: body.clone(),
+        },
+    );
+    guard.completed = true;
+
+    Response::from_parts(parts, Body::from(body))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tower::util::ServiceExt;
+
+    fn key(i: u8) -> [u8; 32] {
+        [i; 32]
+    }
+
+    fn stored() -> StoredResponse {
+        StoredResponse {
+            status: StatusCode::CREATED,
+            content_type: None,
+            body: axum::body::Bytes::new(),
+        }
+    }
+
+    fn proceed(lookup: Lookup) -> u64 {
+        match lookup {
+            Lookup::Proceed(seq) => seq,
+            _ => panic!("expected the key to be new"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_scoped_by_credential() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let store = Arc::new(IdempotencyStore::new(IdempotencyConfig::default()));
+        let app = Router::new()
+            .route(
+                "/notes",
+                post(move || async move {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    StatusCode::CREATED
+                }),
+            )
+            .layer(middleware::from_fn_with_state(store.clone(), idempotency_layer));
+        let request = |api_key: &str| {
+            Request::post("/notes")
+                .header(API_KEY_HEADER, api_key)
+                .header(IDEMPOTENCY_KEY_HEADER, "retry-1")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let replayed = |response: Response| response.headers().contains_key(IDEMPOTENT_REPLAYED_HEADER);
+
+        assert!(!replayed(app.clone().oneshot(request("key-a")).await.unwrap()));
+        assert!(!replayed(app.clone().oneshot(request("key-b")).await.unwrap()));
+        assert!(replayed(app.oneshot(request("key-a")).await.unwrap()));
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_eviction_skips_requests_in_flight() {
+        let store = IdempotencyStore::new(IdempotencyConfig {
+            max_entries: 2,
+            ..IdempotencyConfig::default()
+        });
+        let first = proceed(store.begin(key(1), [0; 32]));
+        store.complete(&key(1), first, stored());
+        proceed(store.begin(key(2), [0; 32]));
+
+        // The completed entry makes room; the one in flight stays
+        proceed(store.begin(key(3), [0; 32]));
+        assert_eq!(store.len(), 2);
+        assert!(matches!(store.begin(key(2), [0; 32]), Lookup::InFlight));
+        proceed(store.begin(key(1), [0; 32]));
+
+        // With only requests in flight the limit is exceeded rather than losing one
+        assert_eq!(store.len(), 3);
+    }
+
+    #[test]
+    fn test_expired_entries_are_replaced() {
+        let store = IdempotencyStore::new(IdempotencyConfig {
+            window_secs: 0,
+            ..IdempotencyConfig::default()
+        });
+        let stale = proceed(store.begin(key(1), [0; 32]));
+        let fresh = proceed(store.begin(key(1), [1; 32]));
+        assert_ne!(stale, fresh);
+
+        // The request owning the expired entry cannot touch its replacement
+        store.complete(&key(1), stale, stored());
+        store.abandon(&key(1), stale);
+        let entries = store.entries.lock().unwrap();
+        assert!(matches!(entries.by_key[&key(1)].state, EntryState::InFlight));
+        assert_eq!(entries.by_age.len(), 1);
+    }
+}
@@ -0,0 +1,111 @@
+//! API handlers for issuer identities and the rotation of their keys
+
+use axum::{extract::State, http::StatusCode, Json};
+
+use basis_store::{KeyRotation, KeyRotationError, PubKey};
+
+use crate::{
+    models::{
+        error_response, success_response, ApiResponse, EventType, IdentityKeysResponse, RotateKeyRequest,
+        SerializableKeyRotation, TrackerEvent,
+    },
+    requests,
+    tracker_request::TrackerUnavailable,
+    AppState,
+};
+
+fn decode<const N: usize>(value: &str, field: &str) -> Result<[u8; N], String> {
+    hex::decode(value)
+        .map_err(|_| format!("{} must be hex-encoded", field))?
+        .try_into()
+        .map_err(|_| format!("{} must be {} bytes", field, N))
+}
+
+fn rotation_from_request(payload: &RotateKeyRequest) -> Result<KeyRotation, String> {
+    Ok(KeyRotation {
+        old_pubkey: decode(&payload.old_pubkey, "old_pubkey")?,
+        new_pubkey: decode(&payload.new_pubkey, "new_pubkey")?,
+        timestamp: payload.timestamp,
+        signature: decode(&payload.signature, "signature")?,
+    })
+}
+
+fn key_rotation_error_status(error: &KeyRotationError) -> StatusCode {
+    match error {
+        KeyRotationError::AlreadyRotated(_) | KeyRotationError::KeyInUse(_) => StatusCode::CONFLICT,
+        KeyRotationError::StorageError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        KeyRotationError::InvalidSignature | KeyRotationError::SameKey | KeyRotationError::FutureTimestamp => {
+            StatusCode::BAD_REQUEST
+        }
+    }
+}
+
+/// Rotate an issuer key to a new key, signed by the old key
+#[axum::debug_handler]
+pub async fn rotate_issuer_key(
+    State(state): State<AppState>,
+    Json(payload): Json<RotateKeyRequest>,
+) -> (StatusCode, Json<ApiResponse<SerializableKeyRotation>>) {
+    tracing::debug!("Rotating issuer key: {:?}", payload);
+
+    let rotation = match rotation_from_request(&payload) {
+        Ok(rotation) => rotation,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+
+    let request = requests::RotateIssuerKey {
+        rotation: rotation.clone(),
+    };
+    match state.send(request).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(())) => {
+            let event = TrackerEvent {
+                id: 0, // Will be set by event store
+                seq: 0,
+                event_type: EventType::KeyRotated,
+                timestamp: rotation.timestamp,
+                issuer_pubkey: Some(hex::encode(rotation.old_pubkey)),
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: None,
+                collateral_amount: None,
+                redeemed_amount: None,
+                height: None,
+            };
+            if let Err(e) = state.event_store.add_event(event).await {
+                tracing::warn!("Failed to store key rotation event: {:?}", e);
+            }
+            (StatusCode::CREATED, Json(success_response(SerializableKeyRotation::from(&rotation))))
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("Refused key rotation: {}", e);
+            (key_rotation_error_status(&e), Json(error_response(e.to_string())))
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
+
+/// Get every key of the identity a key belongs to, with the rotations linking them
+#[axum::debug_handler]
+pub async fn get_identity_keys(
+    State(state): State<AppState>,
+    axum::extract::Path(pubkey): axum::extract::Path<String>,
+) -> (StatusCode, Json<ApiResponse<IdentityKeysResponse>>) {
+    tracing::debug!("Getting identity keys of {}", pubkey);
+
+    let pubkey: PubKey = match decode(&pubkey, "pubkey") {
+        Ok(pubkey) => pubkey,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+
+    match state.send(requests::GetKeyHistory { pubkey }).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(history)) => (StatusCode::OK, Json(success_response(IdentityKeysResponse::from(&history)))),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get key history: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response("Failed to retrieve identity keys".to_string())),
+            )
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
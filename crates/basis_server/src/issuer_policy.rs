@@ -0,0 +1,267 @@
+//! Issuer allow and deny lists
+//!
+//! Public trackers need a way to keep abusive issuers out. The policy decides
+//! which issuer keys may submit notes, in this order:
+//!
+//! 1. A key on the denylist is refused.
+//! 2. A key carrying a denied tag is refused. Tags are free-form labels an
+//!    operator attaches to keys, e.g. `jurisdiction:xx`.
+//! 3. If an allowlist or allowed tags are configured, only keys on the
+//!    allowlist or carrying an allowed tag are accepted.
+//! 4. Any other key is accepted.
+//!
+//! Refused submissions are answered with `403 Forbidden`. The lists start out
+//! from `[issuer_policy]` and can be changed through the admin API; with
+//! `issuer_policy.state_file` set, changes are saved there and the saved lists
+//! replace the configured ones on the next start.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use basis_store::PubKey;
+use serde::{Deserialize, Serialize};
+
+use crate::util::write_file_atomically;
+
+/// `[issuer_policy]` section of the server configuration
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct IssuerPolicyConfig {
+    /// Issuer keys that may submit notes; when set (or `allow_tags` is), no others may
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Issuer keys that may never submit notes
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Tags of issuer keys, by hex public key
+    #[serde(default)]
+    pub tags: BTreeMap<String, Vec<String>>,
+    /// Tags admitting a key as if it were on the allowlist
+    #[serde(default)]
+    pub allow_tags: Vec<String>,
+    /// Tags refusing a key as if it were on the denylist
+    #[serde(default)]
+    pub deny_tags: Vec<String>,
+    /// File admin changes to the lists are saved to
+    #[serde(default)]
+    pub state_file: Option<String>,
+}
+
+impl IssuerPolicyConfig {
+    /// Configured keys that are not 33-byte hex public keys, by config path
+    pub fn invalid_keys(&self) -> Vec<(String, String)> {
+        let lists = [("issuer_policy.allow", &self.allow), ("issuer_policy.deny", &self.deny)];
+        lists
+            .into_iter()
+            .flat_map(|(path, keys)| keys.iter().map(move |key| (path.to_string(), key.clone())))
+            .chain(self.tags.keys().map(|key| ("issuer_policy.tags".to_string(), key.clone())))
+            .filter(|(_, key)| parse_issuer(key).is_none())
+            .collect()
+    }
+}
+
+/// Allow and deny lists in force; hex keys are lowercase
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct IssuerLists {
+    #[serde(default)]
+    pub allow: BTreeSet<String>,
+    #[serde(default)]
+    pub deny: BTreeSet<String>,
+    #[serde(default)]
+    pub tags: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default)]
+    pub allow_tags: BTreeSet<String>,
+    #[serde(default)]
+    pub deny_tags: BTreeSet<String>,
+}
+
+impl IssuerLists {
+    fn from_config(config: &IssuerPolicyConfig) -> Self {
+        let keys = |keys: &[String]| keys.iter().map(|key| key.to_lowercase()).collect();
+        Self {
+            allow: keys(&config.allow),
+            deny: keys(&config.deny),
+            tags: config
+                .tags
+                .iter()
+                .map(|(key, tags)| (key.to_lowercase(), tags.iter().cloned().collect()))
+                .collect(),
+            allow_tags: config.allow_tags.iter().cloned().collect(),
+            deny_tags: config.deny_tags.iter().cloned().collect(),
+        }
+    }
+
+    /// Whether submissions are restricted to allowed issuers
+    pub fn restricts(&self) -> bool {
+        !self.allow.is_empty() || !self.allow_tags.is_empty()
+    }
+
+    /// Check an issuer against the lists
+    pub fn check(&self, issuer_pubkey: &PubKey) -> Result<(), IssuerRefused> {
+        let key = hex::encode(issuer_pubkey);
+        if self.deny.contains(&key) {
+            return Err(IssuerRefused::Denied(key));
+        }
+        let tags = self.tags.get(&key);
+        if let Some(tag) = tags.and_then(|tags| tags.iter().find(|tag| self.deny_tags.contains(*tag))) {
+            return Err(IssuerRefused::DeniedTag(key, tag.clone()));
+        }
+        let allowed_by_tag = tags.is_some_and(|tags| !tags.is_disjoint(&self.allow_tags));
+        if self.restricts() && !self.allow.contains(&key) && !allowed_by_tag {
+            return Err(IssuerRefused::NotAllowed(key));
+        }
+        Ok(())
+    }
+}
+
+/// Why an issuer may not submit notes
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum IssuerRefused {
+    #[error("Issuer {0} is on the tracker's denylist")]
+    Denied(String),
+    #[error("Issuer {0} is tagged '{1}', which the tracker does not accept")]
+    DeniedTag(String, String),
+    #[error("Issuer {0} is not on the tracker's allowlist")]
+    NotAllowed(String),
+}
+
+/// Issuer policy, shared between request handlers and the admin API
+#[derive(Debug, Clone, Default)]
+pub struct IssuerPolicy {
+    lists: Arc<RwLock<IssuerLists>>,
+    state_file: Option<PathBuf>,
+}
+
+impl IssuerPolicy {
+    /// Policy from configuration, or from the state file if one was saved
+    pub fn from_config(config: &IssuerPolicyConfig) -> std::io::Result<Self> {
+        let state_file = config.state_file.as_ref().map(PathBuf::from);
+        let lists = match &state_file {
+            Some(path) if path.exists() => serde_json::from_slice(&std::fs::read(path)?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            _ => IssuerLists::from_config(config),
+        };
+        Ok(Self {
+            lists: Arc::new(RwLock::new(lists)),
+            state_file,
+        })
+    }
+
+    /// Lists in force
+    pub fn lists(&self) -> IssuerLists {
+        self.lists.read().map(|lists| lists.clone()).unwrap_or_default()
+    }
+
+    /// Check whether an issuer may submit notes
+    pub fn check(&self, issuer_pubkey: &PubKey) -> Result<(), IssuerRefused> {
+        match self.lists.read() {
+            Ok(lists) => lists.check(issuer_pubkey),
+            Err(_) => Ok(()),
+        }
+    }
+
+    /// Change the lists and save them to the state file, if any
+    ///
+    /// The change is kept in memory even if saving fails.
+    pub fn update(&self, change: impl FnOnce(&mut IssuerLists)) -> std::io::Result<IssuerLists> {
+        let lists = {
+            let mut lists = self
+                .lists
+                .write()
+                .map_err(|_| std::io::Error::other("issuer policy lock poisoned"))?;
+            change(&mut lists);
+            lists.clone()
+        };
+        if let Some(path) = &self.state_file {
+            let json = serde_json::to_vec_pretty(&lists).map_err(std::io::Error::other)?;
+            write_file_atomically(path, &json)?;
+        }
+        Ok(lists)
+    }
+}
+
+/// Parse a hex issuer public key as used in the lists
+pub fn parse_issuer(key: &str) -> Option<PubKey> {
+    hex::decode(key).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(n: u8) -> PubKey {
+        let mut key = [n; 33];
+        key[0] = 0x02;
+        key
+    }
+
+    #[test]
+    fn test_policy_order() {
+        let mut config = IssuerPolicyConfig::default();
+        let lists = IssuerLists::from_config(&config);
+        assert!(!lists.restricts());
+        assert_eq!(lists.check(&key(1)), Ok(()));
+
+        config.deny = vec![hex::encode(key(1)).to_uppercase()];
+        config.tags.insert(hex::encode(key(2)), vec!["jurisdiction:xx".to_string()]);
+        config.tags.insert(hex::encode(key(3)), vec!["verified".to_string()]);
+        config.deny_tags = vec!["jurisdiction:xx".to_string()];
+        let lists = IssuerLists::from_config(&config);
+        assert!(matches!(lists.check(&key(1)), Err(IssuerRefused::Denied(_))));
+        assert_eq!(
+            lists.check(&key(2)),
+            Err(IssuerRefused::DeniedTag(hex::encode(key(2)), "jurisdiction:xx".to_string()))
+        );
+        assert_eq!(lists.check(&key(4)), Ok(()));
+
+        // With an allowlist, only listed or allow-tagged keys get through
+        config.allow = vec![hex::encode(key(1)), hex::encode(key(5))];
+        config.allow_tags = vec!["verified".to_string()];
+        let lists = IssuerLists::from_config(&config);
+        assert!(matches!(lists.check(&key(1)), Err(IssuerRefused::Denied(_))));
+        assert_eq!(lists.check(&key(3)), Ok(()));
+        assert_eq!(lists.check(&key(5)), Ok(()));
+        assert!(matches!(lists.check(&key(4)), Err(IssuerRefused::NotAllowed(_))));
+    }
+
+    #[test]
+    fn test_updates_survive_restart() {
+        let path = std::env::temp_dir().join(format!("basis_issuer_policy_{}.json", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config = IssuerPolicyConfig {
+            allow: vec![hex::encode(key(1))],
+            state_file: Some(path.display().to_string()),
+            ..IssuerPolicyConfig::default()
+        };
+
+        let policy = IssuerPolicy::from_config(&config).unwrap();
+        assert!(policy.check(&key(2)).is_err());
+        policy.update(|lists| {
+            lists.allow.insert(hex::encode(key(2)));
+        })
+        .unwrap();
+        assert_eq!(policy.check(&key(2)), Ok(()));
+
+        // The saved lists replace the configured ones
+        let restarted = IssuerPolicy::from_config(&config).unwrap();
+        assert_eq!(restarted.lists(), policy.lists());
+        assert_eq!(restarted.check(&key(2)), Ok(()));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn test_invalid_keys() {
+        let mut config = IssuerPolicyConfig {
+            deny: vec!["zz".to_string(), hex::encode(key(1))],
+            ..IssuerPolicyConfig::default()
+        };
+        config.tags.insert("02ab".to_string(), vec![]);
+        assert_eq!(
+            config.invalid_keys(),
+            vec![
+                ("issuer_policy.deny".to_string(), "zz".to_string()),
+                ("issuer_policy.tags".to_string(), "02ab".to_string()),
+            ]
+        );
+    }
+}
@@ -1,24 +1,70 @@
 //! Basis Server library
 
 pub mod acceptance;
+pub mod admin_api;
 pub mod api;
+pub mod auth;
+pub mod balance_api;
+pub mod cold_start;
+pub mod collateral_history;
+pub mod commitment_scheduler;
 pub mod config;
+pub mod daemon;
+pub mod dispute_api;
+pub mod event_stream;
+pub mod idempotency;
+pub mod metrics_api;
+pub mod identity_api;
+pub mod issuer_policy;
+pub mod logging;
+pub mod maintenance;
 pub mod models;
+pub mod netting_api;
+pub mod note_payload_api;
+pub mod outbound_tx;
+pub mod pricing;
+pub mod replica;
+pub mod report_api;
+pub mod request_audit;
+pub mod request_id;
 pub mod reserve_api;
+pub mod server;
+pub mod shutdown;
+pub mod signing_service;
+pub mod split_note_api;
+pub mod stats_api;
 pub mod store;
 pub mod tracker_box_updater;
+pub mod tracker_queue;
+pub mod tracker_request;
+pub mod tracker_signer;
+mod util;
+pub mod wallet_api;
+pub mod watcher;
+pub mod webhooks;
 
 #[cfg(test)]
 mod create_reserve_tests;
 
 use tokio::sync::Mutex;
 
+use tracker_request::tracker_requests;
+
 // Re-export main types for external use
 pub use acceptance::*;
 pub use api::*;
+pub use balance_api::*;
 pub use config::*;
+pub use dispute_api::*;
+pub use identity_api::*;
 pub use models::*;
+pub use netting_api::*;
+pub use note_payload_api::*;
+pub use report_api::*;
 pub use reserve_api::*;
+pub use server::{run, Server, ServerBuilder, ServerError};
+pub use split_note_api::*;
+pub use stats_api::*;
 pub use store::*;
 pub use tracker_box_updater::*;
 
@@ -33,84 +79,172 @@ pub use models::{
 // Application state that holds a channel to communicate with the tracker thread
 #[derive(Clone)]
 pub struct AppState {
-    pub tx: tokio::sync::mpsc::Sender<TrackerCommand>,
+    pub tx: tracker_queue::TrackerSender,
     pub event_store: std::sync::Arc<EventStore>,
     pub ergo_scanner: std::sync::Arc<Mutex<basis_store::ergo_scanner::ServerState>>,
-    pub reserve_tracker: std::sync::Arc<Mutex<basis_store::ReserveTracker>>,
+    /// Reserves found by the scanner: a handle on `ergo_scanner`'s tracker,
+    /// sharing its state, so both always see the same reserves
+    pub reserve_tracker: basis_store::ReserveTracker,
     pub config: std::sync::Arc<AppConfig>,
     pub shared_tracker_state: std::sync::Arc<tokio::sync::Mutex<tracker_box_updater::SharedTrackerState>>,
     pub tracker_storage: basis_store::persistence::TrackerStorage,
     pub acceptance_predicate: Option<std::sync::Arc<dyn acceptance::NotePredicate>>,
+    pub signing_service: std::sync::Arc<signing_service::SigningService>,
+    /// Startup comparison with the chain; degraded mode refuses state changes
+    pub cold_start: cold_start::ColdStartState,
+    /// How far a replica has followed its primary
+    pub replica: replica::ReplicaState,
+    /// Issuers allowed or refused to submit notes, changeable through the admin API
+    pub issuer_policy: issuer_policy::IssuerPolicy,
+    /// Database handles and the last maintenance run
+    pub maintenance: maintenance::StorageMaintenance,
+    /// Conversion rates set by the operator, for valuing collateral and debt
+    pub pricing: pricing::Pricing,
+    /// Signed transactions waiting for submission or confirmation
+    pub outbound_tx: outbound_tx::OutboundTxQueue,
+    /// Record of state-changing requests, read through the admin API
+    pub request_audit: request_audit::RequestAuditLog,
+    /// Reserve contract versions whose reserves are tracked and spendable
+    pub contracts: basis_store::ContractRegistry,
     // Note: tracker_scanner is not stored here due to Send trait bounds
     // Tracker box ID is fetched from tracker_storage directly
 }
 
-// Commands that can be sent to the tracker thread
-#[derive(Debug)]
-pub enum TrackerCommand {
-    AddNote {
+// Commands that can be sent to the tracker thread, the queue each waits in
+// and its response (see `tracker_request`)
+tracker_requests! {
+    write AddNote {
+        issuer_pubkey: basis_store::PubKey,
+        note: basis_store::IouNote,
+        /// Payload envelope the issuer encrypted for the recipient
+        encrypted_payload: Option<Vec<u8>>,
+    } -> Result<(), basis_store::NoteError>;
+    read ValidateNote {
         issuer_pubkey: basis_store::PubKey,
         note: basis_store::IouNote,
-        response_tx: tokio::sync::oneshot::Sender<Result<(), basis_store::NoteError>>,
-    },
-    GetNotesByIssuer {
+    } -> Vec<basis_store::NoteError>;
+    write AcknowledgeNote {
+        issuer_pubkey: basis_store::PubKey,
+        recipient_pubkey: basis_store::PubKey,
+        recipient_signature: basis_store::Signature,
+    } -> Result<basis_store::IouNote, basis_store::NoteError>;
+    read GetNoteHistory {
+        issuer_pubkey: basis_store::PubKey,
+        recipient_pubkey: basis_store::PubKey,
+        offset: usize,
+        limit: usize,
+    } -> Result<(Vec<basis_store::IouNote>, usize), basis_store::NoteError>;
+    read SearchNotes {
+        query: basis_store::note_search::NoteQuery,
+    } -> Result<basis_store::note_search::NoteSearchPage, basis_store::NoteError>;
+    read GetNotesByIssuer {
         issuer_pubkey: basis_store::PubKey,
-        response_tx:
-            tokio::sync::oneshot::Sender<Result<Vec<basis_store::IouNote>, basis_store::NoteError>>,
-    },
-    GetNotesByRecipient {
+    } -> Result<Vec<basis_store::IouNote>, basis_store::NoteError>;
+    read GetNotesByRecipient {
         recipient_pubkey: basis_store::PubKey,
-        response_tx:
-            tokio::sync::oneshot::Sender<Result<Vec<basis_store::IouNote>, basis_store::NoteError>>,
-    },
-    GetNotesByRecipientWithIssuer {
+    } -> Result<Vec<basis_store::IouNote>, basis_store::NoteError>;
+    read GetNotesByRecipientWithIssuer {
         recipient_pubkey: basis_store::PubKey,
-        response_tx:
-            tokio::sync::oneshot::Sender<Result<Vec<(basis_store::PubKey, basis_store::IouNote)>, basis_store::NoteError>>,
-    },
-    GetNoteByIssuerAndRecipient {
+    } -> Result<Vec<(basis_store::PubKey, basis_store::IouNote)>, basis_store::NoteError>;
+    read GetNoteByIssuerAndRecipient {
         issuer_pubkey: basis_store::PubKey,
         recipient_pubkey: basis_store::PubKey,
-        response_tx: tokio::sync::oneshot::Sender<
-            Result<Option<basis_store::IouNote>, basis_store::NoteError>,
-        >,
-    },
-    GetNotes {
-        response_tx:
-            tokio::sync::oneshot::Sender<Result<Vec<(basis_store::PubKey, basis_store::IouNote)>, basis_store::NoteError>>,
-    },
-    InitiateRedemption {
+    } -> Result<Option<basis_store::IouNote>, basis_store::NoteError>;
+    read GetNotePayload {
+        issuer_pubkey: basis_store::PubKey,
+        recipient_pubkey: basis_store::PubKey,
+    } -> Result<Option<(u64, Vec<u8>)>, basis_store::NoteError>;
+    read GetNetworkStats {
+        top_issuers: usize,
+        days: u64,
+    } -> Result<basis_store::network_stats::NetworkStats, basis_store::NoteError>;
+    read GetAccountJournal {
+        account: basis_store::PubKey,
+    } -> Result<Vec<basis_store::ledger::JournalEntry>, basis_store::NoteError>;
+    read GetNotes {} -> Result<Vec<(basis_store::PubKey, basis_store::IouNote)>, basis_store::NoteError>;
+    read ValidateRedemption {
+        issuer_pubkey: String,
+        recipient_pubkey: String,
+        amount: u64,
+    } -> Vec<basis_store::RedemptionError>;
+    write InitiateRedemption {
         request: basis_store::RedemptionRequest,
-        response_tx: tokio::sync::oneshot::Sender<
-            Result<basis_store::RedemptionData, basis_store::RedemptionError>,
-        >,
-    },
-    CompleteRedemption {
+    } -> Result<basis_store::RedemptionData, basis_store::RedemptionError>;
+    write CompleteRedemption {
         issuer_pubkey: basis_store::PubKey,
         recipient_pubkey: basis_store::PubKey,
         redeemed_amount: u64,
-        response_tx: tokio::sync::oneshot::Sender<Result<(), basis_store::RedemptionError>>,
-    },
-    GenerateProof {
+    } -> Result<(), basis_store::RedemptionError>;
+    read GenerateProof {
         issuer_pubkey: basis_store::PubKey,
         recipient_pubkey: basis_store::PubKey,
-        response_tx: tokio::sync::oneshot::Sender<Result<basis_store::NoteProof, basis_store::NoteError>>,
-    },
-    GetTrackerLookupProof {
+    } -> Result<basis_store::NoteProof, basis_store::NoteError>;
+    read GenerateRecipientProofs {
+        recipient_pubkey: basis_store::PubKey,
+    } -> Result<basis_store::RecipientProofs, basis_store::NoteError>;
+    read GetTrackerLookupProof {
         issuer_pubkey: basis_store::PubKey,
         recipient_pubkey: basis_store::PubKey,
-        response_tx: tokio::sync::oneshot::Sender<Result<basis_store::TrackerLookupProof, basis_store::NoteError>>,
-    },
-    GetReserveLookupProof {
+    } -> Result<basis_store::TrackerLookupProof, basis_store::NoteError>;
+    read GetReserveLookupProof {
         issuer_pubkey: basis_store::PubKey,
         recipient_pubkey: basis_store::PubKey,
-        response_tx: tokio::sync::oneshot::Sender<Result<basis_store::ReserveLookupProof, basis_store::NoteError>>,
-    },
-    GetReserveInsertProof {
+    } -> Result<basis_store::ReserveLookupProof, basis_store::NoteError>;
+    write FileDispute {
+        filing: basis_store::DisputeFiling,
+    } -> Result<basis_store::Dispute, basis_store::DisputeError>;
+    write CloseDispute {
+        id: u64,
+        status: basis_store::DisputeStatus,
+        note: String,
+    } -> Result<basis_store::Dispute, basis_store::DisputeError>;
+    read GetDispute {
+        id: u64,
+    } -> Result<Option<basis_store::Dispute>, basis_store::NoteError>;
+    read GetDisputes {} -> Result<Vec<basis_store::Dispute>, basis_store::NoteError>;
+    read GetOpenDispute {
+        issuer_pubkey: basis_store::PubKey,
+        recipient_pubkey: basis_store::PubKey,
+    } -> Result<Option<basis_store::Dispute>, basis_store::NoteError>;
+    write AddSplitNote {
+        issuer_pubkey: basis_store::PubKey,
+        split: basis_store::SplitNote,
+    } -> Result<[u8; 32], basis_store::SplitNoteError>;
+    read GetSplitNotesByIssuer {
+        issuer_pubkey: basis_store::PubKey,
+    } -> Result<Vec<basis_store::SplitNote>, basis_store::NoteError>;
+    write RotateIssuerKey {
+        rotation: basis_store::KeyRotation,
+    } -> Result<(), basis_store::KeyRotationError>;
+    read GetKeyHistory {
+        pubkey: basis_store::PubKey,
+    } -> Result<basis_store::KeyHistory, basis_store::NoteError>;
+    read ScanNotes {
+        query: basis_store::note_search::NoteQuery,
+        after: Option<basis_store::note_search::NoteCursor>,
+        limit: usize,
+    } -> Result<Vec<(basis_store::PubKey, basis_store::IouNote)>, basis_store::NoteError>;
+    /// Rebuild the AVL tree from stored notes, answering with the resulting root digest
+    write RebuildAvlTree {} -> Result<[u8; 33], basis_store::NoteError>;
+    /// Check the stored notes against their history and the AVL tree,
+    /// repairing what can be repaired if asked
+    write CheckStorage {
+        repair: bool,
+    } -> Result<basis_store::storage_maintenance::NoteIntegrity, basis_store::NoteError>;
+    /// Store notes from a verified commitment delta of the primary, returning the new root
+    write ApplyReplicatedNotes {
+        notes: Vec<(basis_store::PubKey, basis_store::IouNote)>,
+    } -> Result<[u8; 33], basis_store::NoteError>;
+    /// Sync note and AVL tree storage to disk
+    write Flush {} -> Result<(), basis_store::NoteError>;
+    /// Replace the debt ceiling, or stop enforcing it with `None`
+    write SetDebtPolicy {
+        policy: Option<basis_store::DebtCeilingPolicy>,
+    } -> ();
+    read GetReserveInsertProof {
         issuer_pubkey: basis_store::PubKey,
         recipient_pubkey: basis_store::PubKey,
         timestamp: u64,
         new_already_redeemed: u64,
-        response_tx: tokio::sync::oneshot::Sender<Result<Vec<u8>, basis_store::NoteError>>,
-    },
+    } -> Result<Vec<u8>, basis_store::NoteError>;
 }
@@ -0,0 +1,294 @@
+//! Log output of the server
+//!
+//! The `[logging]` section picks the log format, a default level with
+//! per-module overrides, and where logs go: standard output, a rotated log
+//! file, or both. A `RUST_LOG` variable, when set, replaces the configured
+//! levels, so a deployment can be debugged without editing its config.
+//!
+//! ```toml
+//! [logging]
+//! level = "info"
+//! format = "json"
+//!
+//! [logging.modules]
+//! basis_store = "debug"
+//! tower_http = "warn"
+//!
+//! [logging.file]
+//! enabled = true
+//! directory = "/var/log/basis"
+//! rotation = "daily"
+//! max_files = 14
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer, Registry};
+
+/// `[logging]` section of the server configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LoggingConfig {
+    /// Level of modules without an entry in `modules`
+    #[serde(default = "default_level")]
+    pub level: String,
+    /// Level per module path, e.g. `basis_store = "debug"`
+    #[serde(default = "default_modules")]
+    pub modules: BTreeMap<String, String>,
+    /// `text` for human readable lines, `json` for one JSON object per line
+    #[serde(default)]
+    pub format: LogFormat,
+    /// Write logs to standard output
+    #[serde(default = "default_stdout")]
+    pub stdout: bool,
+    /// Rotated log files
+    #[serde(default)]
+    pub file: LogFileConfig,
+}
+
+/// `[logging.file]` section of the server configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LogFileConfig {
+    /// Write logs to files in `directory`
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_directory")]
+    pub directory: PathBuf,
+    /// File names are the prefix, the date and time of the period and `.log`
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+    /// How often a new file is started
+    #[serde(default)]
+    pub rotation: LogRotation,
+    /// Files kept, oldest deleted first; 0 keeps every file
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+}
+
+/// Format of log lines
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// Period of a log file
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LogRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    /// A single file that is never rotated
+    Never,
+}
+
+fn default_level() -> String {
+    "info".to_string()
+}
+
+fn default_modules() -> BTreeMap<String, String> {
+    ["basis_server", "basis_store", "tower_http", "axum"]
+        .into_iter()
+        .map(|module| (module.to_string(), "debug".to_string()))
+        .collect()
+}
+
+fn default_stdout() -> bool {
+    true
+}
+
+fn default_directory() -> PathBuf {
+    PathBuf::from("logs")
+}
+
+fn default_prefix() -> String {
+    "basis_server".to_string()
+}
+
+fn default_max_files() -> usize {
+    7
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: default_level(),
+            modules: default_modules(),
+            format: LogFormat::default(),
+            stdout: default_stdout(),
+            file: LogFileConfig::default(),
+        }
+    }
+}
+
+impl Default for LogFileConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: default_directory(),
+            prefix: default_prefix(),
+            rotation: LogRotation::default(),
+            max_files: default_max_files(),
+        }
+    }
+}
+
+impl From<LogRotation> for Rotation {
+    fn from(rotation: LogRotation) -> Self {
+        match rotation {
+            LogRotation::Minutely => Rotation::MINUTELY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+            LogRotation::Never => Rotation::NEVER,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// Filter directives of the configured levels, default level first
+    pub fn directives(&self) -> String {
+        std::iter::once(self.level.clone())
+            .chain(self.modules.iter().map(|(module, level)| format!("{}={}", module, level)))
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// Invalid levels and destinations, as (field path, message)
+    pub fn invalid_settings(&self) -> Vec<(String, String)> {
+        let mut invalid = Vec::new();
+        if self.level.parse::<tracing::Level>().is_err() && self.level != "off" {
+            invalid.push(("logging.level".to_string(), format!("'{}' is not a log level", self.level)));
+        }
+        for (module, level) in &self.modules {
+            if level.parse::<tracing::Level>().is_err() && level != "off" {
+                invalid.push((format!("logging.modules.{}", module), format!("'{}' is not a log level", level)));
+            }
+        }
+        if !self.stdout && !self.file.enabled {
+            invalid.push((
+                "logging.stdout".to_string(),
+                "must be true when logging.file.enabled is false, or nothing is logged".to_string(),
+            ));
+        }
+        if self.file.enabled && self.file.prefix.is_empty() {
+            invalid.push(("logging.file.prefix".to_string(), "must not be empty".to_string()));
+        }
+        invalid
+    }
+
+    /// Filter of the configured levels, unless `RUST_LOG` is set
+    fn filter(&self) -> EnvFilter {
+        match std::env::var("RUST_LOG") {
+            Ok(directives) => EnvFilter::new(directives),
+            Err(_) => EnvFilter::new(self.directives()),
+        }
+    }
+
+    /// Format log lines for `writer`
+    fn layer<W>(&self, writer: W, ansi: bool) -> Box<dyn Layer<Registry> + Send + Sync>
+    where
+        W: for<'a> tracing_subscriber::fmt::MakeWriter<'a> + Send + Sync + 'static,
+    {
+        let layer = tracing_subscriber::fmt::layer().with_writer(writer).with_ansi(ansi);
+        match self.format {
+            LogFormat::Text => layer.boxed(),
+            LogFormat::Json => layer.json().boxed(),
+        }
+    }
+}
+
+impl LogFileConfig {
+    /// Appender starting a new file every rotation period
+    pub fn appender(&self) -> Result<RollingFileAppender, String> {
+        let open_error = |e: &dyn std::fmt::Display| {
+            format!("Failed to open log directory {}: {}", self.directory.display(), e)
+        };
+        // Old files are pruned from the directory as the appender is built
+        std::fs::create_dir_all(&self.directory).map_err(|e| open_error(&e))?;
+        let mut builder = RollingFileAppender::builder()
+            .rotation(self.rotation.into())
+            .filename_prefix(&self.prefix)
+            .filename_suffix("log");
+        if self.max_files > 0 {
+            builder = builder.max_log_files(self.max_files);
+        }
+        builder
+            .build(&self.directory)
+            .map_err(|e| open_error(&e))
+    }
+}
+
+/// Install the configured logging as the global subscriber
+///
+/// File lines are written as they are logged rather than from a background
+/// thread, so the lines logged before a fatal `process::exit` are kept.
+pub fn init(config: &LoggingConfig) -> Result<(), String> {
+    let mut layers = Vec::new();
+    if config.stdout {
+        layers.push(config.layer(std::io::stdout, true));
+    }
+    if config.file.enabled {
+        layers.push(config.layer(config.file.appender()?, false));
+    }
+
+    tracing_subscriber::registry()
+        .with(layers)
+        .with(config.filter())
+        .try_init()
+        .map_err(|e| format!("Failed to install logging: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directives_put_default_level_first() {
+        let config = LoggingConfig {
+            level: "warn".to_string(),
+            modules: [("basis_store".to_string(), "trace".to_string())].into_iter().collect(),
+            ..LoggingConfig::default()
+        };
+        assert_eq!(config.directives(), "warn,basis_store=trace");
+        assert!(config.invalid_settings().is_empty());
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let config = LoggingConfig {
+            level: "loud".to_string(),
+            modules: [("axum".to_string(), "quiet".to_string())].into_iter().collect(),
+            stdout: false,
+            ..LoggingConfig::default()
+        };
+        let fields: Vec<_> = config.invalid_settings().into_iter().map(|(field, _)| field).collect();
+        assert_eq!(fields, ["logging.level", "logging.modules.axum", "logging.stdout"]);
+    }
+
+    #[test]
+    fn test_file_appender_writes_to_directory() {
+        use std::io::Write;
+
+        let directory = std::env::temp_dir().join(format!("basis_logging_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        let file = LogFileConfig {
+            enabled: true,
+            directory: directory.clone(),
+            rotation: LogRotation::Never,
+            ..LogFileConfig::default()
+        };
+        let mut appender = file.appender().unwrap();
+        writeln!(appender, "first line").unwrap();
+        appender.flush().unwrap();
+
+        let written = std::fs::read_to_string(directory.join("basis_server.log")).unwrap();
+        assert_eq!(written, "first line\n");
+        let _ = std::fs::remove_dir_all(&directory);
+    }
+}
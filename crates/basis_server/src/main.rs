@@ -1,899 +1,105 @@
-use axum::{
-    routing::{get, post},
-    Router,
-};
-use basis_server::{
-    api::*, reserve_api::*, store::EventStore, AppConfig, AppState, ErgoConfig, EventType,
-    ServerConfig, TrackerCommand, TrackerEvent, TransactionConfig,
-    TrackerBoxUpdateConfig, TrackerBoxUpdater, SharedTrackerState,
-};
-use basis_store::{
-    ergo_scanner::{start_scanner, NodeConfig, ReserveEvent, ServerState},
-    tracker_scanner::{create_tracker_server_state, TrackerNodeConfig},
-    ReserveTracker,
-};
-use basis_store::persistence::{TrackerStorage, ScannerMetadataStorage};
-use std::sync::Arc;
-use tokio::sync::Mutex;
-use tower_http::cors::{Any, CorsLayer};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use basis_server::{daemon::PidFile, shutdown::wait_for_signal, AppConfig, Server, ServerArgs};
+use basis_store::persistence::{NoteStorage, ReserveStorage};
+use clap::Parser;
 
 #[tokio::main]
 async fn main() {
-    tracing::info!("Starting basis server...");
-    // Load configuration
-    tracing::info!("Loading configuration...");
-    let config = match AppConfig::load() {
+    // Load configuration: defaults, then config file, then BASIS_* environment
+    // variables, then command-line flags. Tracing is not initialized yet, so
+    // configuration errors go straight to stderr.
+    let args = ServerArgs::parse();
+    let config = match AppConfig::load_layered(&args) {
         Ok(config) => config,
         Err(e) => {
-            tracing::warn!("Failed to load configuration: {}", e);
-            tracing::info!("Using default configuration...");
-            AppConfig::load().unwrap_or_else(|_| {
-                // Fallback to hardcoded defaults if config loading fails completely
-                AppConfig {
-                    server: ServerConfig {
-                        host: "0.0.0.0".to_string(),
-                        port: 3048,
-                        database_url: Some("sqlite:data/basis.db".to_string()),
-                    },
-                    ergo: ErgoConfig {
-                        node: NodeConfig {
-                            start_height: None,
-                            reserve_contract_p2s: None,
-                            node_url: "http://127.0.0.1:9053".to_string(),
-                            scan_name: Some("Basis Reserve Scanner".to_string()),
-                            api_key: Some("hello".to_string()),
-                        },
-                        basis_reserve_contract_p2s: "RtQxdWJ9axeb5Ltahqosnhj45BE26xuDK4YWddVj5p59t9RjKPEkkHCYEiyxwRFMJcEHwVd9syFod8ReQo1Zaz9eNTZ5JwDEN5hkLd67sVr2sNQ6R46TSfausAc9D3q7et1apYaXnqV9PkpHPMCA1zMCEsmmADj62XRGq4Cw2VwpuKKCAdreTgmLzdFWHGVGQMsPDFFBkRibsPFMzXkytdy2mPs2zCtm15uyDpd3jDLBy95BtUFXU2DdaYa1xMZE9UXju4R4MhWH8vqWda5BgpRTa1RpQxpS5b96FG46r1v3ZWCLYcVo51J1ekY8cqqVFNNykpQScRRYqFjCLMjG26dYEwZyn21wGeLJ7RzcTwCpvGDBa2w1P3ycAEJAv9XDPEtJrSQpkvBaD1HaZ6X2JuXmFjPF5MChmVLk4CTXtRQVRis7vP95ByTTmbHbtVdao32kbN3xhCWgJZZdaKkNyKH4vFQn5jyoEmiV7FjQDegWnnaFXu5FW6stx9cbhsxWz5FfGpW1BCMRNNJTCRF6FtYoehrMT74LDRNxHQ38EmMn6mBEpSrhkzDj2jysdFJvDUf8UQjLZQLmUQtgNotfxeAPxiavsT5mLUja3hdWvZPv71FcHxvP53WJHAcn9JPek3vepbH9gxRdmBMW".to_string(),
-                        tracker_nft_id: None,
-                        tracker_public_key: None,
-                        tracker_secret_key: None,
-                    },
-                    transaction: TransactionConfig {
-                        fee: 1000000, // 0.001 ERG
-                        change_address: None, // Will be derived from tracker public key
-                    },
-                    acceptance: basis_server::acceptance::config::AcceptanceConfig::empty(),
-                }
-            })
+            eprintln!("{}", e);
+            std::process::exit(1);
         }
     };
 
-    // Validate that tracker NFT ID is properly configured
-    if let Err(_) = config.tracker_nft_bytes() {
-        tracing::error!("Tracker NFT ID is not properly configured in the configuration file. The server requires a valid tracker_nft_id value.");
-        std::process::exit(1); // Exit with error code if tracker NFT ID is not configured
+    if args.check_config {
+        println!("Configuration is valid");
+        return;
     }
 
-    tracing::info!("Configuration loaded successfully");
     // Initialize tracing
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG")
-                .unwrap_or_else(|_| "basis_server=debug,basis_store=debug,tower_http=debug,axum=debug".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
-
-    // Initialize real Ergo scanner with blockchain monitoring
-    tracing::info!("Initializing Ergo scanner with blockchain monitoring...");
-
-    // Create scanner configuration with actual reserve contract P2S
-    let mut scanner_config = config.ergo.node.clone();
-    scanner_config.reserve_contract_p2s = Some(config.ergo.basis_reserve_contract_p2s.clone());
-
-    // Create real scanner state with configured node URL and contract template
-    let ergo_scanner = match ServerState::new(scanner_config) {
-        Ok(scanner) => scanner,
-        Err(e) => {
-            tracing::warn!("Failed to create Ergo scanner: {}", e);
-            tracing::info!("Continuing without blockchain scanner...");
-            // Create a minimal scanner that won't actually scan
-            let minimal_config = NodeConfig {
-                node_url: "http://127.0.0.1:9053".to_string(), // Dummy URL that won't be used
-                ..Default::default()
-            };
-            ServerState::new(minimal_config).unwrap_or_else(|_| panic!("Failed to create minimal scanner"))
-        }
-    };
-
-    // Start the scanner background task
-    if let Err(e) = start_scanner(ergo_scanner.clone()).await {
-        tracing::warn!("Failed to start background scanner: {}", e);
-        tracing::info!("Continuing without background scanner...");
-    } else {
-        tracing::info!("Ergo scanner started successfully");
-    }
-
-    // Get tracker public key from config early, needed for shared state
-    let tracker_pubkey = if let Some(tracker_pubkey_bytes) = match config.tracker_public_key_bytes() {
-        Ok(bytes) => bytes,
-        Err(e) => {
-            tracing::error!("Invalid tracker public key format: {}. Please set 'ergo.tracker_public_key' as either a hex-encoded public key or a P2PK address in your configuration file.", e);
-            std::process::exit(1);
-        }
-    } {
-        tracing::info!("Using tracker public key from configuration");
-        tracker_pubkey_bytes
-    } else {
-        tracing::error!("No tracker public key found in configuration. Please set 'ergo.tracker_public_key' as either a hex-encoded public key or a P2PK address in your configuration file.");
+    if let Err(e) = basis_server::logging::init(&config.logging) {
+        eprintln!("{}", e);
         std::process::exit(1);
-    };
-
-    // Create shared tracker state for the updater (before scanner so scanner can set box ID)
-    tracing::info!("Initializing shared tracker state...");
-    let shared_tracker_state_for_updater = SharedTrackerState::new_with_tracker_key(tracker_pubkey);
-
-    // Initialize tracker scanner for monitoring tracker state commitment boxes
-    tracing::debug!("Tracker NFT ID from config: {:?}", config.ergo.tracker_nft_id);
-    let _tracker_scanner_initialized = 
-    if config.ergo.tracker_nft_id.is_some() && config.ergo.tracker_nft_id.as_ref().map_or(false, |id| !id.is_empty()) {
-        tracing::info!("Initializing tracker scanner with tracker NFT ID...");
-        let tracker_scanner_config = TrackerNodeConfig {
-            start_height: config.ergo.node.start_height,
-            tracker_nft_id: config.ergo.tracker_nft_id.clone(),
-            node_url: config.ergo.node.node_url.clone(),
-            scan_name: Some("Basis Tracker Scanner".to_string()),
-            api_key: config.ergo.node.api_key.clone(),
-        };
-
-        // Create tracker scanner state with persistent storage paths (similar to reserve scanner)
-        let metadata_storage_path = std::path::Path::new("data").join("tracker_scanner_metadata");
-        let tracker_storage_path = std::path::Path::new("data").join("tracker_boxes");
-
-        // Ensure data directory exists
-        std::fs::create_dir_all(&metadata_storage_path.parent().unwrap_or(std::path::Path::new("data"))).unwrap_or_else(|e| {
-            tracing::warn!("Failed to create data directory: {}", e);
-        });
-
-        match basis_store::persistence::ScannerMetadataStorage::open(metadata_storage_path.clone()) {
-            Ok(metadata_storage) => {
-                match basis_store::persistence::TrackerStorage::open(tracker_storage_path.clone()) {
-                    Ok(tracker_storage) => {
-                        let tracker_scanner = create_tracker_server_state(
-                            tracker_scanner_config,
-                            metadata_storage,
-                            tracker_storage,
-                        );
-
-                        // Ensure the tracker scan is registered on startup
-                        match tracker_scanner.ensure_scan_registered().await {
-                            Ok(scan_id) => {
-                                tracing::info!("Tracker scan registered with ID: {}", scan_id);
-
-                                // Process tracker boxes once to populate storage
-                                match tracker_scanner.process_tracker_boxes().await {
-                                    Ok(tracker_boxes) => {
-                                        tracing::info!("Processed {} tracker boxes", tracker_boxes.len());
-                                        if let Err(e) = tracker_scanner.update_tracker_state(&tracker_boxes).await {
-                                            tracing::error!("Failed to update tracker state: {}", e);
-                                        }
-                                        
-                                        // Set the latest tracker box ID in shared state for the updater
-                                        if let Some(latest_box) = tracker_boxes.iter().max_by_key(|b| b.last_verified_height) {
-                                            tracing::info!("Setting latest tracker box ID in shared state: {}", latest_box.box_id);
-                                            shared_tracker_state_for_updater.set_tracker_box_id(latest_box.box_id.clone());
-                                        }
-                                    }
-                                    Err(e) => {
-                                        tracing::error!("Failed to process tracker boxes: {}", e);
-                                    }
-                                }
+    }
+    tracing::info!("Configuration loaded successfully");
 
-                                tracing::info!("Tracker scanner initialization completed successfully");
-                                true
-                            },
-                            Err(e) => {
-                                tracing::warn!("Failed to register tracker scan: {:?}", e);
-                                tracing::info!("Continuing without tracker scanner registration...");
-                                false
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        tracing::warn!("Failed to create tracker storage for tracker scanner: {:?}", e);
-                        tracing::info!("Continuing without tracker scanner...");
-                        false
-                    }
-                }
-            }
+    if args.encrypt_storage {
+        std::process::exit(match encrypt_storage(&config) {
+            Ok(()) => 0,
             Err(e) => {
-                tracing::warn!("Failed to create metadata storage for tracker scanner: {:?}", e);
-                tracing::info!("Continuing without tracker scanner...");
-                false
-            }
-        }
-    } else {
-        tracing::info!("Tracker NFT ID not configured, skipping tracker scanner initialization");
-        tracing::info!("To enable tracker scanner, configure 'ergo.tracker_nft_id' in your configuration");
-        false
-    };
-
-    // Initialize reserve tracker
-    tracing::info!("Initializing reserve tracker...");
-    let reserve_tracker = ReserveTracker::new();
-    tracing::info!("Reserve tracker initialized successfully");
-
-    // Create channel for communicating with tracker thread
-    let (tx, mut rx) = tokio::sync::mpsc::channel::<TrackerCommand>(100);
-
-    // Initialize tracker manager outside of the blocking task so it can be shared
-    use basis_store::{RedemptionManager, TrackerStateManager};
-    let shared_tracker_state = std::sync::Arc::new(std::sync::Mutex::new(TrackerStateManager::new()));
-
-    // Spawn tracker thread (using tokio::task::spawn_blocking for CPU-bound work)
-    let shared_tracker_state_clone = shared_tracker_state.clone();
-    let shared_state_for_tracker = shared_tracker_state_for_updater.clone(); // Also pass shared state for updater
-    tokio::task::spawn_blocking(move || {
-        use basis_store::RedemptionManager;
-
-        tracing::debug!("Tracker thread started");
-        let mut tracker = TrackerStateManager::new();
-        
-        // Update shared state with the rebuilt AVL root digest after initialization
-        let initial_root = tracker.get_state().avl_root_digest;
-        shared_state_for_tracker.set_avl_root_digest(initial_root);
-        tracing::info!("Tracker thread initialized with AVL root digest: {}", hex::encode(&initial_root));
-        
-        let mut redemption_manager = RedemptionManager::new(tracker);
-
-        while let Some(cmd) = rx.blocking_recv() {
-            tracing::debug!("Tracker thread received command: {:?}", cmd);
-            match cmd {
-                TrackerCommand::AddNote {
-                    issuer_pubkey,
-                    note,
-                    response_tx,
-                } => {
-                    // Get mutable access to the tracker for adding a note
-                    let result = redemption_manager.tracker.add_note(&issuer_pubkey, &note);
-
-                    // Update shared state for tracker box updater if successful
-                    if result.is_ok() {
-                        // Update the shared AVL root digest to match the current tracker state
-                        let current_root = redemption_manager.tracker.get_state().avl_root_digest;
-                        shared_state_for_tracker.set_avl_root_digest(current_root);
-
-                        // Note: In a real implementation, we'd send this back to the async context to store
-                        // For now, we'll handle event storage in the async handler
-                    }
-
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::GetNotesByIssuer {
-                    issuer_pubkey,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.tracker.get_issuer_notes(&issuer_pubkey);
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::GetNotesByRecipient {
-                    recipient_pubkey,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.tracker.get_recipient_notes(&recipient_pubkey);
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::GetNotesByRecipientWithIssuer {
-                    recipient_pubkey,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.tracker.get_recipient_notes_with_issuer(&recipient_pubkey);
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::GetNoteByIssuerAndRecipient {
-                    issuer_pubkey,
-                    recipient_pubkey,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.tracker
-                        .lookup_note(&issuer_pubkey, &recipient_pubkey)
-                        .map(Some);
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::InitiateRedemption {
-                    request,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.initiate_redemption(&request);
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::CompleteRedemption {
-                    issuer_pubkey,
-                    recipient_pubkey,
-                    redeemed_amount,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.complete_redemption(
-                        &issuer_pubkey,
-                        &recipient_pubkey,
-                        redeemed_amount,
-                    );
-
-                    // Update shared state for tracker box updater if successful
-                    if result.is_ok() {
-                        // Update the shared AVL root digest to match the current tracker state
-                        let current_root = redemption_manager.tracker.get_state().avl_root_digest;
-                        shared_state_for_tracker.set_avl_root_digest(current_root);
-                    }
-
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::GetNotes { response_tx } => {
-                    let result = redemption_manager.tracker.get_all_notes_with_issuer();
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::GenerateProof {
-                    issuer_pubkey,
-                    recipient_pubkey,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.tracker.generate_proof(&issuer_pubkey, &recipient_pubkey);
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::GetTrackerLookupProof {
-                    issuer_pubkey,
-                    recipient_pubkey,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.tracker.generate_tracker_lookup_proof(&issuer_pubkey, &recipient_pubkey);
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::GetReserveLookupProof {
-                    issuer_pubkey,
-                    recipient_pubkey,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.tracker.generate_reserve_lookup_proof(&issuer_pubkey, &recipient_pubkey);
-                    let _ = response_tx.send(result);
-                }
-                TrackerCommand::GetReserveInsertProof {
-                    issuer_pubkey,
-                    recipient_pubkey,
-                    timestamp,
-                    new_already_redeemed,
-                    response_tx,
-                } => {
-                    let result = redemption_manager.tracker.generate_reserve_insert_proof(&issuer_pubkey, &recipient_pubkey, timestamp, new_already_redeemed);
-                    let _ = response_tx.send(result);
-                }
+                eprintln!("{}", e);
+                1
             }
-        }
-    });
-
-    // Create tracker box updater
-    tracing::info!("Initializing tracker box updater...");
-
-    // Check if node configuration is provided, abort if not
-    if config.ergo.node.node_url.is_empty() {
-        tracing::error!("No Ergo node URL provided in configuration. Tracker box updater requires node connection.");
-        std::process::exit(1);
+        });
     }
 
-    // Use mainnet network prefix for address encoding
-    let network_prefix = ergo_lib::ergotree_ir::address::NetworkPrefix::Mainnet;
-
-    let tracker_box_config = TrackerBoxUpdateConfig {
-        update_interval_seconds: 600, // 10 minutes
-        enabled: true,
-        ergo_node_url: config.ergo.node.node_url.clone(),
-        ergo_api_key: config.ergo.node.api_key.clone(),
-        tracker_secret_key: config.tracker_secret_key_bytes(),
-    };
-    let (shutdown_tx, _) = tokio::sync::broadcast::channel::<()>(1);
-
-    // Clone the channel for the tracker updater
-    let updater_shutdown_rx = shutdown_tx.subscribe();
-
-    // Start the tracker box updater in the background
-    let updater_config = tracker_box_config.clone();
-    let shared_state_clone = shared_tracker_state_for_updater.clone();
-    let updater_network_prefix = network_prefix; // Use the network_prefix determined above
-    // Get the tracker NFT ID from config - it must be present since it's now required
-    let tracker_nft_id = config.ergo.tracker_nft_id.clone().expect("Tracker NFT ID must be configured in server configuration");
-    tokio::spawn(async move {
-        if let Err(e) = TrackerBoxUpdater::start(
-            updater_config,
-            shared_state_clone,
-            updater_network_prefix,
-            tracker_nft_id, // Pass the required tracker NFT ID
-            updater_shutdown_rx,
-        ).await {
-            tracing::error!("Tracker box updater failed: {}", e);
-        }
-    });
-    tracing::info!("Tracker box updater started successfully");
-
-    let event_store = match EventStore::new().await {
-        Ok(store) => std::sync::Arc::new(store),
+    // Removed again when main returns
+    let _pid_file = match config.daemon.pid_file.as_deref().map(PidFile::create).transpose() {
+        Ok(pid_file) => pid_file,
         Err(e) => {
-            tracing::error!("Failed to initialize event store: {:?}", e);
+            tracing::error!("Failed to write PID file: {}", e);
             std::process::exit(1);
         }
     };
 
-    // Add demo events
-    let demo_events = vec![
-        TrackerEvent {
-            id: 0,
-            event_type: EventType::NoteUpdated,
-            timestamp: 1234567890,
-            issuer_pubkey: Some(
-                "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
-            ),
-            recipient_pubkey: Some(
-                "020202020202020202020202020202020202020202020202020202020202020202".to_string(),
-            ),
-            amount: Some(1000),
-            reserve_box_id: None,
-            collateral_amount: None,
-            redeemed_amount: None,
-            height: None,
-        },
-        TrackerEvent {
-            id: 0,
-            event_type: EventType::NoteUpdated,
-            timestamp: 1234567891,
-            issuer_pubkey: Some(
-                "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
-            ),
-            recipient_pubkey: Some(
-                "030303030303030303030303030303030303030303030303030303030303030303".to_string(),
-            ),
-            amount: Some(2000),
-            reserve_box_id: None,
-            collateral_amount: None,
-            redeemed_amount: None,
-            height: None,
-        },
-        TrackerEvent {
-            id: 0,
-            event_type: EventType::ReserveCreated,
-            timestamp: 1234567892,
-            issuer_pubkey: Some(
-                "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
-            ),
-            recipient_pubkey: None,
-            amount: None,
-            reserve_box_id: Some("box1234567890abcdef".to_string()),
-            collateral_amount: Some(1000000000),
-            redeemed_amount: None,
-            height: Some(1000),
-        },
-        TrackerEvent {
-            id: 0,
-            event_type: EventType::ReserveToppedUp,
-            timestamp: 1234567893,
-            issuer_pubkey: Some(
-                "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
-            ),
-            recipient_pubkey: None,
-            amount: None,
-            reserve_box_id: Some("box1234567890abcdef".to_string()),
-            collateral_amount: Some(500000000),
-            redeemed_amount: None,
-            height: Some(1001),
-        },
-        TrackerEvent {
-            id: 0,
-            event_type: EventType::ReserveRedeemed,
-            timestamp: 1234567894,
-            issuer_pubkey: Some(
-                "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
-            ),
-            recipient_pubkey: None,
-            amount: None,
-            reserve_box_id: Some("box1234567890abcdef".to_string()),
-            collateral_amount: None,
-            redeemed_amount: Some(250000000),
-            height: Some(1002),
-        },
-        TrackerEvent {
-            id: 0,
-            event_type: EventType::Commitment,
-            timestamp: 1234567895,
-            issuer_pubkey: None,
-            recipient_pubkey: None,
-            amount: None,
-            reserve_box_id: None,
-            collateral_amount: None,
-            redeemed_amount: None,
-            height: Some(1003),
-        },
-        TrackerEvent {
-            id: 0,
-            event_type: EventType::CollateralAlert { ratio: 0.8 },
-            timestamp: 1234567896,
-            issuer_pubkey: Some(
-                "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
-            ),
-            recipient_pubkey: None,
-            amount: None,
-            reserve_box_id: None,
-            collateral_amount: None,
-            redeemed_amount: None,
-            height: None,
-        },
-    ];
-
-    for event in demo_events {
-        if let Err(e) = event_store.add_event(event).await {
-            tracing::warn!("Failed to add demo event: {:?}", e);
-        }
+    let mut server = Server::builder(config);
+    #[cfg(unix)]
+    {
+        // Apply scan timing and debt ceiling changes on SIGHUP
+        server = server.reload_on_sighup(args.clone());
     }
-
-    // Extract the reserve tracker from the scanner before wrapping in Arc/Mutex
-    let scanner_reserve_tracker = ergo_scanner.reserve_tracker.clone();
-
-    // Initialize tracker storage for the new API endpoint
-    let tracker_storage_path = std::path::Path::new("data").join("tracker_boxes");
-    let tracker_storage = match basis_store::persistence::TrackerStorage::open(tracker_storage_path) {
-        Ok(storage) => storage,
-        Err(e) => {
-            tracing::error!("Failed to initialize tracker storage: {:?}", e);
-            std::process::exit(1);
-        }
-    };
-
-    // Build acceptance predicate from configuration
-    let acceptance_predicate = match basis_server::acceptance::builder::build_predicate_tree(config.acceptance.clone()) {
-        Ok(Some(pred)) => {
-            tracing::info!("Acceptance predicate loaded: '{}'", pred.name());
-            Some(std::sync::Arc::from(pred))
-        }
-        Ok(None) => {
-            tracing::info!("No acceptance predicates configured");
-            None
+    // A socket passed by systemd socket activation replaces server.host:server.port
+    match basis_server::daemon::inherited_listener() {
+        Ok(Some(listener)) => {
+            tracing::info!("Using listening socket from the service manager");
+            server = server.listener(listener);
         }
+        Ok(None) => {}
         Err(e) => {
-            tracing::warn!("Failed to build acceptance predicate: {}", e);
-            None
-        }
-    };
-
-    let app_state = AppState {
-        tx,
-        event_store,
-        ergo_scanner: std::sync::Arc::new(Mutex::new(ergo_scanner)),
-        reserve_tracker: std::sync::Arc::new(Mutex::new(scanner_reserve_tracker)),
-        config: std::sync::Arc::new(config.clone()),
-        shared_tracker_state: std::sync::Arc::new(tokio::sync::Mutex::new(shared_tracker_state_for_updater)),
-        tracker_storage,
-        acceptance_predicate,
-    };
-
-    // Build our application with routes - FIXED ROUTE ORDER
-    let app = Router::new()
-        // Root route
-        .route("/", get(root))
-        // Static routes
-        .route("/events", get(get_events))
-        .route("/events/paginated", get(get_events_paginated))
-        .route("/notes", post(create_note).options(handle_options))
-        .route("/acceptance/check", post(check_acceptance).options(handle_options))
-        .route("/redeem", post(initiate_redemption).options(handle_options))
-        .route("/redeem/complete", post(complete_redemption).options(handle_options))
-        .route("/proof/redemption", get(get_redemption_proof))
-        .route("/tracker/proof", get(get_tracker_proof))
-        .route("/reserve/proof", get(get_reserve_proof))
-        .route("/tracker/signature", post(request_tracker_signature).options(handle_options))
-        .route("/redemption/prepare", post(prepare_redemption).options(handle_options))
-        .route("/reserves", get(get_all_reserves))
-        .route("/reserves/create", post(create_reserve_payload).options(handle_options))
-        // Most specific parameterized routes first
-        .route(
-            "/notes/issuer/{issuer_pubkey}/recipient/{recipient_pubkey}",
-            get(get_note_by_issuer_and_recipient),
-        )
-        // Parameterized routes
-        .route("/notes/issuer/{pubkey}", get(get_notes_by_issuer))
-        .route("/notes/recipient/{pubkey}", get(get_notes_by_recipient))
-        .route("/notes", get(get_all_notes)) // Get all notes with age
-        .route("/reserves/{box_id}", get(get_reserve_by_box_id))
-        .route("/reserves/issuer/{pubkey}", get(get_reserves_by_issuer))
-        .route("/key-status/{pubkey}", get(get_key_status))
-        .route("/tracker/latest-box-id", get(get_latest_tracker_box_id))
-        .route("/config/reserve-contract-p2s", get(get_basis_reserve_contract_p2s))
-        .with_state(app_state.clone())
-        .layer(tower_http::trace::TraceLayer::new_for_http())
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
-        );
-
-    tracing::debug!("Router built successfully");
-    tracing::debug!("Registered routes:");
-    tracing::debug!("  GET /");
-    tracing::debug!("  POST /notes");
-    tracing::debug!("  GET /notes/issuer/{{pubkey}}");
-    tracing::debug!("  GET /notes/recipient/{{pubkey}}");
-    tracing::debug!("  GET /notes/issuer/{{issuer_pubkey}}/recipient/{{recipient_pubkey}}");
-    tracing::debug!("  GET /notes (all notes with age)");
-    tracing::debug!("  GET /reserves");
-    tracing::debug!("  GET /reserves/{{box_id}}");
-    tracing::debug!("  GET /reserves/issuer/{{pubkey}}");
-    tracing::debug!("  POST /reserves/create");
-    tracing::debug!("  GET /events");
-    tracing::debug!("  GET /events/paginated");
-    tracing::debug!("  GET /key-status/{{pubkey}}");
-    tracing::debug!("  POST /redeem");
-    tracing::debug!("  GET /tracker/latest-box-id");
-
-    // Run our app with hyper
-    let addr = config.socket_addr();
-    tracing::debug!("listening on {}", addr);
-
-    let listener = match tokio::net::TcpListener::bind(addr).await {
-        Ok(listener) => {
-            tracing::info!("Server listening on {}", addr);
-            listener
+            tracing::error!("Failed to use socket from the service manager: {}", e);
+            std::process::exit(1);
         }
+    }
+    let server = match server.build().await {
+        Ok(server) => server,
         Err(e) => {
-            tracing::error!("Failed to bind to {}: {}", addr, e);
+            tracing::error!("{}", e);
             std::process::exit(1);
         }
     };
 
-    // Scanner is already started via start_scanner() above
-    // No need for duplicate background scanner task
+    // The scanner and the tracker thread are up and the listener is open
+    basis_server::daemon::notify_ready("Serving requests");
 
-    tracing::info!("Starting axum server...");
-    if let Err(e) = axum::serve(listener, app).await {
-        tracing::error!("Server error: {}", e);
-        std::process::exit(1);
+    let shutdown_signal = async {
+        wait_for_signal().await;
+        basis_server::daemon::notify_stopping();
     };
-}
-
-/// Background task that continuously scans the blockchain for reserve events
-async fn background_scanner_task(state: AppState, config: AppConfig) {
-    tracing::info!("Starting background blockchain scanner task");
-
-    loop {
-        tokio::time::sleep(std::time::Duration::from_secs(30)).await; // Scan every 30 seconds
-
-        let mut scanner = match state.ergo_scanner.try_lock() {
-            Ok(scanner) => scanner,
-            Err(_) => {
-                tracing::debug!("Scanner is busy, skipping this scan cycle");
-                continue;
-            }
-        };
-
-        // Check if scanner is active
-        if !scanner.is_active().await {
-            tracing::warn!("Scanner is not active, attempting to restart...");
-            match scanner.start_scanning().await {
-                Ok(()) => {
-                    tracing::info!("Scanner restarted successfully");
-                }
-                Err(e) => {
-                    tracing::error!("Failed to restart scanner: {}", e);
-                    continue;
-                }
-            }
-        }
-
-        // Update reserve tracker with current unspent boxes
-        match scanner.get_unspent_reserve_boxes().await {
-            Ok(boxes) => {
-                let tracker = match state.reserve_tracker.try_lock() {
-                    Ok(tracker) => tracker,
-                    Err(_) => {
-                        tracing::debug!("Reserve tracker is busy, skipping update");
-                        continue;
-                    }
-                };
-
-                for ergo_box in &boxes {
-                    // Extract owner pubkey from box registers (R4 register)
-                    let owner_pubkey = match ergo_box.get_register("R4") {
-                        Some(pubkey_hex) => {
-                            // Parse hex-encoded public key from register
-                            match hex::decode(pubkey_hex) {
-                                Ok(bytes) => bytes,
-                                Err(e) => {
-                                    tracing::warn!("Invalid R4 register hex for box {}: {}", ergo_box.box_id, e);
-                                    continue; // Skip this box
-                                }
-                            }
-                        }
-                        None => {
-                            tracing::warn!("Box {} missing R4 register, skipping", ergo_box.box_id);
-                            continue; // Skip boxes without owner pubkey
-                        }
-                    };
-
-                    let tracker_nft_bytes_option = match config.tracker_nft_bytes() {
-                        Ok(bytes) => Some(bytes),
-                        Err(_) => {
-                            tracing::error!("Tracker NFT ID is not properly configured");
-                            continue; // Skip this box update
-                        }
-                    };
-
-                    let mut reserve_info = basis_store::ExtendedReserveInfo::new(
-                        ergo_box.box_id.as_bytes(),
-                        &owner_pubkey,
-                        ergo_box.value,
-                        tracker_nft_bytes_option.as_deref(),
-                        scanner.last_scanned_height().await,
-                    );
-
-                    // Set contract address from configuration
-                    reserve_info.set_contract_address(config.basis_reserve_contract_p2s().to_string());
-
-                    if let Err(e) = tracker.update_reserve(reserve_info) {
-                        tracing::warn!(
-                            "Failed to update reserve info for {}: {}",
-                            ergo_box.box_id,
-                            e
-                        );
-                    }
-                }
-
-                tracing::debug!("Updated reserve tracker with {} unspent boxes", boxes.len());
-            }
-            Err(e) => {
-                tracing::error!("Failed to get unspent reserve boxes: {}", e);
-            }
-        }
+    if let Err(e) = server.serve(shutdown_signal).await {
+        tracing::error!("{}", e);
+        std::process::exit(1);
     }
 }
 
-/// Handle OPTIONS preflight requests for CORS
-async fn handle_options() -> impl axum::response::IntoResponse {
-    (
-        axum::http::StatusCode::OK,
-        [("Access-Control-Allow-Origin", "*")],
-        "",
-    )
-}
-
-/// Process a reserve event and store it in the event store
-async fn process_reserve_event(
-    state: &AppState,
-    event: ReserveEvent,
-    config: &AppConfig,
-) -> Result<(), Box<dyn std::error::Error>> {
-    let tracker_event = match event {
-        ReserveEvent::ReserveCreated {
-            box_id,
-            owner_pubkey,
-            collateral_amount,
-            height,
-        } => {
-            tracing::info!(
-                "Reserve created: {} with {} nanoERG at height {}",
-                box_id,
-                collateral_amount,
-                height
-            );
-
-            // Update reserve tracker
-            let tracker_nft_bytes_option = match config.tracker_nft_bytes() {
-                Ok(bytes) => Some(bytes),
-                Err(_) => {
-                    tracing::error!("Tracker NFT ID is not properly configured");
-                    return Err("Tracker NFT ID is not properly configured".into());
-                }
-            };
-
-            let tracker = state.reserve_tracker.lock().await;
-            // Decode the hex-encoded owner public key to bytes before passing to ExtendedReserveInfo::new
-            let owner_pubkey_bytes = match hex::decode(&owner_pubkey) {
-                Ok(bytes) => bytes,
-                Err(_) => {
-                    tracing::error!("Failed to decode owner public key: {}", owner_pubkey);
-                    return Err("Invalid owner public key format".into());
-                }
-            };
-            let mut reserve_info = basis_store::ExtendedReserveInfo::new(
-                box_id.as_bytes(),
-                &owner_pubkey_bytes,
-                collateral_amount,
-                tracker_nft_bytes_option.as_deref(),
-                height,
-            );
-            reserve_info.set_contract_address(config.basis_reserve_contract_p2s().to_string());
-            tracker.update_reserve(reserve_info)?;
-
-            TrackerEvent {
-                id: 0,
-                event_type: EventType::ReserveCreated,
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                issuer_pubkey: Some(owner_pubkey),
-                recipient_pubkey: None,
-                amount: None,
-                reserve_box_id: Some(box_id),
-                collateral_amount: Some(collateral_amount),
-                redeemed_amount: None,
-                height: Some(height),
-            }
-        }
-        ReserveEvent::ReserveToppedUp {
-            box_id,
-            additional_collateral,
-            height,
-        } => {
-            tracing::info!(
-                "Reserve topped up: {} +{} nanoERG at height {}",
-                box_id,
-                additional_collateral,
-                height
-            );
-
-            TrackerEvent {
-                id: 0,
-                event_type: EventType::ReserveToppedUp,
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                issuer_pubkey: None, // Will be filled from reserve tracker if needed
-                recipient_pubkey: None,
-                amount: None,
-                reserve_box_id: Some(box_id),
-                collateral_amount: Some(additional_collateral),
-                redeemed_amount: None,
-                height: Some(height),
-            }
-        }
-        ReserveEvent::ReserveRedeemed {
-            box_id,
-            redeemed_amount,
-            height,
-        } => {
-            tracing::info!(
-                "Reserve redeemed: {} -{} nanoERG at height {}",
-                box_id,
-                redeemed_amount,
-                height
-            );
-
-            TrackerEvent {
-                id: 0,
-                event_type: EventType::ReserveRedeemed,
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                issuer_pubkey: None, // Will be filled from reserve tracker if needed
-                recipient_pubkey: None,
-                amount: None,
-                reserve_box_id: Some(box_id),
-                collateral_amount: None,
-                redeemed_amount: Some(redeemed_amount),
-                height: Some(height),
-            }
-        }
-        ReserveEvent::ReserveSpent { box_id, height } => {
-            tracing::info!("Reserve spent: {} at height {}", box_id, height);
-
-            TrackerEvent {
-                id: 0,
-                event_type: EventType::ReserveSpent,
-                timestamp: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs(),
-                issuer_pubkey: None, // Will be filled from reserve tracker if needed
-                recipient_pubkey: None,
-                amount: None,
-                reserve_box_id: Some(box_id),
-                collateral_amount: None,
-                redeemed_amount: None,
-                height: Some(height),
-            }
-        }
-    };
-
-    // Store the event
-    state.event_store.add_event(tracker_event).await?;
-
+/// Seal the note and reserve databases under the current storage key
+///
+/// Runs before the tracker opens the databases, as each may be open only once.
+fn encrypt_storage(config: &AppConfig) -> Result<(), String> {
+    // The config was validated, so the storage key resolves
+    let cipher = config.storage.encryption.cipher().unwrap_or_default();
+    let notes = NoteStorage::open_with_cipher(&config.storage.notes_path, cipher.clone())
+        .and_then(|storage| storage.reseal_all())
+        .map_err(|e| format!("Failed to encrypt note storage: {:?}", e))?;
+    println!("Sealed {} note records in {}", notes, config.storage.notes_path.display());
+
+    let reserves = ReserveStorage::open_with_cipher(&config.storage.reserves_path, cipher)
+        .and_then(|storage| storage.reseal_all())
+        .map_err(|e| format!("Failed to encrypt reserve storage: {:?}", e))?;
+    println!("Sealed {} reserve records in {}", reserves, config.storage.reserves_path.display());
     Ok(())
 }
@@ -0,0 +1,222 @@
+//! Scheduled maintenance of the tracker's databases
+//!
+//! A maintenance run, every `maintenance.interval_secs` or on request through
+//! `POST /admin/maintenance`:
+//!
+//! 1. reads every partition of every database end to end, reporting the
+//!    ones that cannot be read,
+//! 2. compacts them, dropping overwritten and deleted items,
+//! 3. checks the stored notes on the tracker thread: note records that no
+//!    longer decode are restored from the note history, preferring the version
+//!    the AVL tree commits to, and the indexes and the AVL tree are rebuilt when
+//!    they disagree with the notes (see [`TrackerStateManager::check_storage`]).
+//!
+//! Database sizes are served live on `/metrics`, together with the outcome
+//! of the last run.
+//!
+//! [`TrackerStateManager::check_storage`]: basis_store::TrackerStateManager::check_storage
+
+use std::sync::{Arc, RwLock};
+
+use basis_store::storage_maintenance::{NoteIntegrity, StorageHandle, StorageReport};
+use serde::{Deserialize, Serialize};
+
+use crate::tracker_queue::TrackerSender;
+use crate::requests;
+
+/// `[maintenance]` section of the server configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MaintenanceConfig {
+    /// Run maintenance on a schedule; the admin endpoint works either way
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Seconds between scheduled runs
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    /// Compact the databases
+    #[serde(default = "default_enabled")]
+    pub compact: bool,
+    /// Restore corrupt notes and rebuild stale indexes and AVL tree entries,
+    /// rather than only reporting them
+    #[serde(default = "default_enabled")]
+    pub repair: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    86400
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            compact: default_enabled(),
+            repair: default_enabled(),
+        }
+    }
+}
+
+/// Outcome of one maintenance run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceRun {
+    /// `scheduled` or `admin`
+    pub trigger: String,
+    /// Milliseconds since the Unix epoch
+    pub started_at: u64,
+    pub finished_at: u64,
+    pub compacted: bool,
+    pub repaired: bool,
+    /// Database sizes after the run
+    pub storages: Vec<StorageReport>,
+    /// Unreadable partitions and failed steps
+    pub problems: Vec<String>,
+    /// Note check, absent if the tracker thread could not be asked
+    pub notes: Option<NoteIntegrity>,
+}
+
+/// A maintenance run is already in progress
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("A maintenance run is already in progress")]
+pub struct MaintenanceBusy;
+
+/// Database handles and the last run, shared between the scheduler, the
+/// admin API and the metrics endpoint
+#[derive(Debug, Clone, Default)]
+pub struct StorageMaintenance {
+    handles: Arc<Vec<StorageHandle>>,
+    last_run: Arc<RwLock<Option<MaintenanceRun>>>,
+    running: Arc<tokio::sync::Mutex<()>>,
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+impl StorageMaintenance {
+    pub fn new(handles: Vec<StorageHandle>) -> Self {
+        Self {
+            handles: Arc::new(handles),
+            ..Self::default()
+        }
+    }
+
+    /// Current sizes of the databases
+    pub fn reports(&self) -> Vec<StorageReport> {
+        self.handles.iter().map(StorageHandle::report).collect()
+    }
+
+    /// Outcome of the last run, if any finished since startup
+    pub fn last_run(&self) -> Option<MaintenanceRun> {
+        self.last_run.read().ok().and_then(|run| run.clone())
+    }
+
+    /// Run maintenance now, unless a run is already in progress
+    pub async fn run(
+        &self,
+        tx: &TrackerSender,
+        trigger: &str,
+        compact: bool,
+        repair: bool,
+    ) -> Result<MaintenanceRun, MaintenanceBusy> {
+        let _running = self.running.try_lock().map_err(|_| MaintenanceBusy)?;
+        let started_at = now_millis();
+        tracing::info!("Storage maintenance started ({})", trigger);
+
+        let mut problems = Vec::new();
+        for handle in self.handles.iter() {
+            let name = handle.name().to_string();
+            let handle = handle.clone();
+            // Reading and compacting block on disk I/O
+            let checked = tokio::task::spawn_blocking(move || {
+                let mut problems = handle.verify();
+                // Compacting unreadable segments would fail, or spread the damage
+                if compact && problems.is_empty() {
+                    if let Err(e) = handle.compact() {
+                        problems.push(format!("{:?}", e));
+                    }
+                }
+                problems
+            })
+            .await;
+            match checked {
+                Ok(found) => problems.extend(found),
+                Err(e) => problems.push(format!("{}: maintenance task failed: {}", name, e)),
+            }
+        }
+
+        let notes = match tx.request(requests::CheckStorage { repair }).await {
+            Ok(Ok(integrity)) => Some(integrity),
+            Ok(Err(e)) => {
+                problems.push(format!("notes: check failed: {:?}", e));
+                None
+            }
+            Err(e) => {
+                problems.push(format!("notes: {}", e));
+                None
+            }
+        };
+
+        let run = MaintenanceRun {
+            trigger: trigger.to_string(),
+            started_at,
+            finished_at: now_millis(),
+            compacted: compact,
+            repaired: repair,
+            storages: self.reports(),
+            problems,
+            notes,
+        };
+        for problem in &run.problems {
+            tracing::error!("Storage maintenance: {}", problem);
+        }
+        match &run.notes {
+            Some(notes) if notes.has_problems() => tracing::warn!(
+                "Storage maintenance: {} corrupt note records ({} restored), {} AVL mismatches, stale indexes: {}",
+                notes.corrupt_records.len(),
+                notes.restored,
+                notes.avl_mismatches,
+                notes.stale_indexes
+            ),
+            _ => {}
+        }
+        tracing::info!(
+            "Storage maintenance finished in {} ms, {} bytes on disk",
+            run.finished_at - run.started_at,
+            run.storages.iter().map(|storage| storage.disk_space).sum::<u64>()
+        );
+
+        if let Ok(mut last_run) = self.last_run.write() {
+            *last_run = Some(run.clone());
+        }
+        Ok(run)
+    }
+}
+
+/// Run maintenance every `maintenance.interval_secs` until shutdown
+pub async fn maintenance_task(
+    maintenance: StorageMaintenance,
+    tx: TrackerSender,
+    config: MaintenanceConfig,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let interval = std::time::Duration::from_secs(config.interval_secs);
+    // The first run waits a full interval, so startup is not slowed down
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            _ = ticker.tick() => {}
+        }
+        if maintenance.run(&tx, "scheduled", config.compact, config.repair).await.is_err() {
+            tracing::info!("Skipping scheduled storage maintenance: a run is in progress");
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Prometheus metrics endpoint
+//!
+//! Metrics are rendered in the Prometheus text exposition format on every
+//! scrape from the state the tracker already keeps, so nothing is recorded
+//! between scrapes.
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{IntoResponse, Response},
+};
+use std::fmt::Write;
+
+use basis_store::storage_maintenance::StorageReport;
+use basis_store::{ScanMode, ScannerProgress};
+
+use crate::maintenance::MaintenanceRun;
+use crate::tracker_queue::QueueStats;
+use crate::AppState;
+
+/// Content type of the Prometheus text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Append one metric with its help and type lines
+fn metric(out: &mut String, name: &str, kind: &str, help: &str, samples: &[(&str, f64)]) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{}{} {}", name, labels, value);
+    }
+}
+
+/// Render the reserve scanner metrics
+///
+/// `max_block_lag` is the lag past which live scanning counts as stalled.
+pub fn render_scanner_metrics(progress: &ScannerProgress, max_block_lag: u64) -> String {
+    let mut out = String::new();
+    let mode = |mode: ScanMode| if progress.mode == mode { 1.0 } else { 0.0 };
+    metric(
+        &mut out,
+        "basis_scanner_mode",
+        "gauge",
+        "Current reserve scanner phase",
+        &[
+            ("{mode=\"backfill\"}", mode(ScanMode::Backfill)),
+            ("{mode=\"live\"}", mode(ScanMode::Live)),
+        ],
+    );
+    metric(
+        &mut out,
+        "basis_scanner_tip_height",
+        "gauge",
+        "Latest known chain height",
+        &[("", progress.tip_height as f64)],
+    );
+    metric(
+        &mut out,
+        "basis_scanner_scanned_height",
+        "gauge",
+        "Highest height whose reserve boxes have been processed",
+        &[("", progress.scanned_height as f64)],
+    );
+    metric(
+        &mut out,
+        "basis_scanner_block_lag",
+        "gauge",
+        "Blocks between the scanned height and the chain tip",
+        &[("", progress.block_lag as f64)],
+    );
+    metric(
+        &mut out,
+        "basis_scanner_stalled",
+        "gauge",
+        "1 when live scanning lags further behind the tip than scanner.max_block_lag",
+        &[("", if progress.is_stalled(max_block_lag) { 1.0 } else { 0.0 })],
+    );
+    metric(
+        &mut out,
+        "basis_scanner_last_success_timestamp_seconds",
+        "gauge",
+        "Unix time of the last successful scan cycle, 0 before the first",
+        &[("", progress.last_success_timestamp.unwrap_or(0) as f64 / 1000.0)],
+    );
+    metric(
+        &mut out,
+        "basis_scanner_consecutive_failures",
+        "gauge",
+        "Failed scan cycles since the last success",
+        &[("", progress.consecutive_failures as f64)],
+    );
+    metric(
+        &mut out,
+        "basis_scanner_errors_total",
+        "counter",
+        "Failed scan cycles since startup",
+        &[("", progress.error_count as f64)],
+    );
+    metric(
+        &mut out,
+        "basis_scanner_boxes_processed_total",
+        "counter",
+        "Reserve boxes processed since startup",
+        &[("", progress.boxes_processed as f64)],
+    );
+    metric(
+        &mut out,
+        "basis_scanner_backfill_batches_total",
+        "counter",
+        "Backfill batches completed since startup",
+        &[("", progress.batches_completed as f64)],
+    );
+    out
+}
+
+/// Render the occupancy of the tracker command queue
+pub fn render_queue_metrics(stats: &QueueStats) -> String {
+    let mut out = String::new();
+    metric(
+        &mut out,
+        "basis_tracker_queue_depth",
+        "gauge",
+        "Commands waiting for the tracker thread",
+        &[
+            ("{class=\"read\"}", stats.read_depth as f64),
+            ("{class=\"write\"}", stats.write_depth as f64),
+        ],
+    );
+    metric(
+        &mut out,
+        "basis_tracker_queue_capacity",
+        "gauge",
+        "Commands each tracker queue class holds",
+        &[("", stats.capacity as f64)],
+    );
+    metric(
+        &mut out,
+        "basis_tracker_queue_refused_total",
+        "counter",
+        "Requests answered 503 because the tracker queue stayed full",
+        &[
+            ("{class=\"read\"}", stats.reads_refused as f64),
+            ("{class=\"write\"}", stats.writes_refused as f64),
+        ],
+    );
+    out
+}
+
+/// Render database sizes and the outcome of the last maintenance run
+pub fn render_storage_metrics(reports: &[StorageReport], last_run: Option<&MaintenanceRun>) -> String {
+    let mut out = String::new();
+    let storage_labels: Vec<String> = reports
+        .iter()
+        .map(|report| format!("{{storage=\"{}\"}}", report.name))
+        .collect();
+    let per_storage = |value: fn(&StorageReport) -> u64| -> Vec<(&str, f64)> {
+        storage_labels
+            .iter()
+            .zip(reports)
+            .map(|(labels, report)| (labels.as_str(), value(report) as f64))
+            .collect()
+    };
+    metric(
+        &mut out,
+        "basis_storage_disk_bytes",
+        "gauge",
+        "Bytes each database uses on disk",
+        &per_storage(|report| report.disk_space),
+    );
+    metric(
+        &mut out,
+        "basis_storage_journal_bytes",
+        "gauge",
+        "Bytes of each database's journals",
+        &per_storage(|report| report.journal_disk_space),
+    );
+
+    let partitions: Vec<(String, &basis_store::storage_maintenance::PartitionReport)> = reports
+        .iter()
+        .flat_map(|report| {
+            report.partitions.iter().map(move |partition| {
+                (
+                    format!("{{storage=\"{}\",partition=\"{}\"}}", report.name, partition.name),
+                    partition,
+                )
+            })
+        })
+        .collect();
+    metric(
+        &mut out,
+        "basis_storage_partition_disk_bytes",
+        "gauge",
+        "Bytes each partition uses on disk",
+        &partitions
+            .iter()
+            .map(|(labels, partition)| (labels.as_str(), partition.disk_space as f64))
+            .collect::<Vec<_>>(),
+    );
+    metric(
+        &mut out,
+        "basis_storage_partition_items",
+        "gauge",
+        "Approximate items in each partition, including ones not compacted away yet",
+        &partitions
+            .iter()
+            .map(|(labels, partition)| (labels.as_str(), partition.approximate_items as f64))
+            .collect::<Vec<_>>(),
+    );
+    metric(
+        &mut out,
+        "basis_storage_partition_segments",
+        "gauge",
+        "Segments of each partition",
+        &partitions
+            .iter()
+            .map(|(labels, partition)| (labels.as_str(), partition.segments as f64))
+            .collect::<Vec<_>>(),
+    );
+
+    if let Some(run) = last_run {
+        metric(
+            &mut out,
+            "basis_storage_last_maintenance_timestamp_seconds",
+            "gauge",
+            "Unix time the last storage maintenance run finished",
+            &[("", run.finished_at as f64 / 1000.0)],
+        );
+        metric(
+            &mut out,
+            "basis_storage_maintenance_problems",
+            "gauge",
+            "Unreadable partitions and failed steps in the last maintenance run",
+            &[("", run.problems.len() as f64)],
+        );
+        if let Some(notes) = &run.notes {
+            metric(
+                &mut out,
+                "basis_storage_corrupt_note_records",
+                "gauge",
+                "Note records the last maintenance run could not read",
+                &[("", notes.corrupt_records.len() as f64)],
+            );
+        }
+    }
+    out
+}
+
+/// Serve metrics in the Prometheus text exposition format
+pub async fn get_metrics(State(state): State<AppState>) -> Response {
+    let progress = state.ergo_scanner.lock().await.progress().await;
+    let mut body = render_scanner_metrics(&progress, state.config.scanner.max_block_lag);
+    body.push_str(&render_queue_metrics(&state.tx.stats()));
+    body.push_str(&render_storage_metrics(
+        &state.maintenance.reports(),
+        state.maintenance.last_run().as_ref(),
+    ));
+    ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], body).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_scanner_metrics() {
+        let progress = ScannerProgress {
+            mode: ScanMode::Live,
+            start_height: 0,
+            scanned_height: 100,
+            tip_height: 125,
+            block_lag: 25,
+            batches_completed: 0,
+            boxes_processed: 7,
+            error_count: 3,
+            consecutive_failures: 2,
+            last_error: Some("node unreachable".to_string()),
+            last_success_timestamp: Some(1_700_000_000_500),
+            eta_secs: None,
+        };
+
+        let metrics = render_scanner_metrics(&progress, 10);
+        assert!(metrics.contains("# TYPE basis_scanner_block_lag gauge\nbasis_scanner_block_lag 25\n"));
+        assert!(metrics.contains("basis_scanner_mode{mode=\"live\"} 1\n"));
+        assert!(metrics.contains("basis_scanner_mode{mode=\"backfill\"} 0\n"));
+        assert!(metrics.contains("basis_scanner_stalled 1\n"));
+        assert!(metrics.contains("basis_scanner_last_success_timestamp_seconds 1700000000.5\n"));
+        assert!(metrics.contains("basis_scanner_errors_total 3\n"));
+        assert!(metrics.contains("basis_scanner_boxes_processed_total 7\n"));
+
+        assert!(render_scanner_metrics(&progress, 25).contains("basis_scanner_stalled 0\n"));
+    }
+
+    #[test]
+    fn test_render_queue_metrics() {
+        let metrics = render_queue_metrics(&QueueStats {
+            capacity: 100,
+            read_depth: 2,
+            write_depth: 100,
+            reads_refused: 0,
+            writes_refused: 5,
+        });
+        assert!(metrics.contains("basis_tracker_queue_depth{class=\"read\"} 2\n"));
+        assert!(metrics.contains("basis_tracker_queue_depth{class=\"write\"} 100\n"));
+        assert!(metrics.contains("basis_tracker_queue_capacity 100\n"));
+        assert!(metrics.contains("# TYPE basis_tracker_queue_refused_total counter\n"));
+        assert!(metrics.contains("basis_tracker_queue_refused_total{class=\"write\"} 5\n"));
+    }
+
+    #[test]
+    fn test_render_storage_metrics() {
+        let reports = vec![StorageReport {
+            name: "notes".to_string(),
+            disk_space: 4096,
+            journal_disk_space: 1024,
+            partitions: vec![basis_store::storage_maintenance::PartitionReport {
+                name: "iou_notes".to_string(),
+                approximate_items: 12,
+                disk_space: 3072,
+                segments: 2,
+            }],
+        }];
+        let metrics = render_storage_metrics(&reports, None);
+        assert!(metrics.contains("basis_storage_disk_bytes{storage=\"notes\"} 4096\n"));
+        assert!(metrics.contains("basis_storage_journal_bytes{storage=\"notes\"} 1024\n"));
+        assert!(metrics.contains("basis_storage_partition_items{storage=\"notes\",partition=\"iou_notes\"} 12\n"));
+        assert!(metrics.contains("basis_storage_partition_segments{storage=\"notes\",partition=\"iou_notes\"} 2\n"));
+        assert!(!metrics.contains("basis_storage_last_maintenance_timestamp_seconds"));
+
+        let run = MaintenanceRun {
+            trigger: "scheduled".to_string(),
+            started_at: 1_700_000_000_000,
+            finished_at: 1_700_000_000_500,
+            compacted: true,
+            repaired: true,
+            storages: reports.clone(),
+            problems: vec!["reserves/reserves: corrupt segment".to_string()],
+            notes: Some(basis_store::storage_maintenance::NoteIntegrity {
+                records_checked: 12,
+                corrupt_records: vec!["02ab".to_string()],
+                restored: 1,
+                ..Default::default()
+            }),
+        };
+        let metrics = render_storage_metrics(&reports, Some(&run));
+        assert!(metrics.contains("basis_storage_last_maintenance_timestamp_seconds 1700000000.5\n"));
+        assert!(metrics.contains("basis_storage_maintenance_problems 1\n"));
+        assert!(metrics.contains("basis_storage_corrupt_note_records 1\n"));
+    }
+}
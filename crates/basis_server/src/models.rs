@@ -1,4 +1,4 @@
-use basis_store::IouNote;
+use basis_store::{IouNote, PubKey};
 use serde::{Deserialize, Serialize};
 
 // Request structure for creating a new IOU note
@@ -10,10 +10,32 @@ pub struct CreateNoteRequest {
     pub timestamp: u64,
     pub signature: String,
     pub issuer_pubkey: String,
+    /// Version of the signing message the signature covers (1 or 2)
+    #[serde(default = "default_signing_version")]
+    pub signing_version: u8,
+    /// Tracker NFT ID a version 2 note is bound to (32 bytes, hex encoded)
+    #[serde(default)]
+    pub tracker_id: Option<String>,
+    /// Annual interest rate in basis points covered by a version 2 signature
+    #[serde(default)]
+    pub interest_rate_bps: Option<u32>,
+    /// How interest accrues: "simple" (the default) or "compound_daily"
+    #[serde(default)]
+    pub accrual_method: Option<String>,
+    /// Memo or other details encrypted for the recipient (hex-encoded envelope)
+    #[serde(default)]
+    pub encrypted_payload: Option<String>,
+    /// Reference covered by a version 2 signature, such as an invoice or order ID
+    #[serde(default)]
+    pub memo: Option<String>,
+}
+
+fn default_signing_version() -> u8 {
+    1
 }
 
 // Response structure for API responses
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub data: Option<T>,
@@ -21,7 +43,7 @@ pub struct ApiResponse<T> {
 }
 
 // Event types for tracker events
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum EventType {
     NoteUpdated,
@@ -30,13 +52,57 @@ pub enum EventType {
     ReserveRedeemed,
     ReserveSpent,
     Commitment,
+    /// A tracker box committed a digest matching no local AVL root at its height
+    CommitmentDivergence,
     CollateralAlert { ratio: f64 },
+    /// A dispute froze redemption of a note
+    DisputeFiled,
+    /// A dispute was closed, unfreezing its note
+    DisputeResolved,
+    /// An issuer key was rotated to a new key
+    KeyRotated,
+    /// Live reserve scanning fell more than `scanner.max_block_lag` blocks behind the chain tip
+    ScannerStalled { lag_blocks: u64 },
+    /// A replica could not apply its primary's commitment at `height` and stopped syncing
+    ReplicaDiverged,
+    /// A stuck outbound transaction was replaced by `tx_id`, paying `amount` in fees
+    TransactionFeeBumped { tx_id: String, replaced_tx_id: String },
+    /// The fee of a stuck outbound transaction could not be raised
+    TransactionStuck { tx_id: String, reason: String },
+}
+
+impl EventType {
+    /// Name of the event type, as in the `type` field of serialized events
+    pub fn name(&self) -> &'static str {
+        match self {
+            EventType::NoteUpdated => "NoteUpdated",
+            EventType::ReserveCreated => "ReserveCreated",
+            EventType::ReserveToppedUp => "ReserveToppedUp",
+            EventType::ReserveRedeemed => "ReserveRedeemed",
+            EventType::ReserveSpent => "ReserveSpent",
+            EventType::Commitment => "Commitment",
+            EventType::CommitmentDivergence => "CommitmentDivergence",
+            EventType::CollateralAlert { .. } => "CollateralAlert",
+            EventType::DisputeFiled => "DisputeFiled",
+            EventType::DisputeResolved => "DisputeResolved",
+            EventType::KeyRotated => "KeyRotated",
+            EventType::ScannerStalled { .. } => "ScannerStalled",
+            EventType::ReplicaDiverged => "ReplicaDiverged",
+            EventType::TransactionFeeBumped { .. } => "TransactionFeeBumped",
+            EventType::TransactionStuck { .. } => "TransactionStuck",
+        }
+    }
 }
 
 // Unified event structure for paginated events
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackerEvent {
     pub id: u64,
+    /// Position of the event in the event stream, in the order events were
+    /// stored; persisted, so clients resume after the last one they saw with
+    /// `since_seq`
+    #[serde(default)]
+    pub seq: u64,
     pub event_type: EventType,
     pub timestamp: u64,
     pub issuer_pubkey: Option<String>,
@@ -57,6 +123,22 @@ pub struct SerializableIouNote {
     pub amount_redeemed: u64,
     pub timestamp: u64,
     pub signature: String,
+    /// Recipient acknowledgment signature, if the recipient has co-signed the note
+    pub recipient_signature: Option<String>,
+    pub acknowledged: bool,
+    /// Version of the signing message the signatures cover
+    pub signing_version: u8,
+    /// Tracker NFT ID the note is bound to, if any
+    pub tracker_id: Option<String>,
+    /// Annual interest rate in basis points, if the note bears interest
+    pub interest_rate_bps: Option<u32>,
+    pub accrual_method: Option<String>,
+    /// Interest accrued on the outstanding debt as of the response
+    pub accrued_interest: u64,
+    /// Reference the issuer signed with the note, if any
+    pub memo: Option<String>,
+    /// Scheme the signatures are made with
+    pub signature_scheme: String,
 }
 
 // Serializable version of IouNote for API responses with age
@@ -68,9 +150,35 @@ pub struct SerializableIouNoteWithAge {
     pub amount_redeemed: u64,
     pub timestamp: u64,
     pub signature: String,
+    /// Recipient acknowledgment signature, if the recipient has co-signed the note
+    pub recipient_signature: Option<String>,
+    pub acknowledged: bool,
+    /// Version of the signing message the signatures cover
+    pub signing_version: u8,
+    /// Tracker NFT ID the note is bound to, if any
+    pub tracker_id: Option<String>,
     pub age_seconds: u64,
 }
 
+impl SerializableIouNoteWithAge {
+    /// Serialize a note, aging it relative to `current_time_ms`
+    pub fn new(issuer_pubkey: &PubKey, note: &IouNote, current_time_ms: u64) -> Self {
+        Self {
+            issuer_pubkey: hex::encode(issuer_pubkey),
+            recipient_pubkey: hex::encode(note.recipient_pubkey),
+            amount_collected: note.amount_collected,
+            amount_redeemed: note.amount_redeemed,
+            timestamp: note.timestamp,
+            signature: hex::encode(note.signature),
+            recipient_signature: note.recipient_signature.map(hex::encode),
+            acknowledged: note.recipient_signature.is_some(),
+            signing_version: note.signing_format.version(),
+            tracker_id: note.signing_format.tracker_id().map(hex::encode),
+            age_seconds: current_time_ms.saturating_sub(note.timestamp) / 1000,
+        }
+    }
+}
+
 impl From<IouNote> for SerializableIouNote {
     fn from(note: IouNote) -> Self {
         Self {
@@ -80,19 +188,387 @@ impl From<IouNote> for SerializableIouNote {
             amount_redeemed: note.amount_redeemed,
             timestamp: note.timestamp,
             signature: hex::encode(note.signature),
+            recipient_signature: note.recipient_signature.map(hex::encode),
+            acknowledged: note.recipient_signature.is_some(),
+            signing_version: note.signing_format.version(),
+            tracker_id: note.signing_format.tracker_id().map(hex::encode),
+            interest_rate_bps: note.interest.map(|interest| interest.rate_bps),
+            accrual_method: note.interest.map(|interest| interest.method.name().to_string()),
+            accrued_interest: note.accrued_interest(
+                std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_millis() as u64,
+            ),
+            memo: note.memo,
+            signature_scheme: note.signature_scheme.name().to_string(),
+        }
+    }
+}
+
+// Page of recorded versions of a note, oldest first
+#[derive(Debug, Serialize)]
+pub struct NoteHistoryResponse {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    /// Total number of recorded versions for the issuer-recipient pair
+    pub total_versions: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub notes: Vec<SerializableIouNote>,
+}
+
+// Page of notes matching a search, in the requested order
+#[derive(Debug, Serialize)]
+pub struct NoteSearchResponse {
+    /// Number of matching notes across all pages
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub notes: Vec<SerializableIouNote>,
+}
+
+// Outcome of a dry run of note creation or redemption; nothing is changed
+#[derive(Debug, Serialize)]
+pub struct ValidationReport {
+    /// Whether the operation would succeed
+    pub valid: bool,
+    /// Every reason the operation would be rejected, in the order checked
+    pub errors: Vec<String>,
+}
+
+// Whether a note can be redeemed now, checked without a signed request
+#[derive(Debug, Serialize)]
+pub struct RedemptionEligibility {
+    /// Whether every check passed
+    pub eligible: bool,
+    /// Earliest time the redemption can pass every check, in milliseconds
+    /// since the Unix epoch: now when eligible, absent when waiting alone will
+    /// not make it eligible
+    pub earliest_eligible_timestamp: Option<u64>,
+    /// Amount checked: the requested one, or the outstanding debt with interest
+    pub amount: u64,
+    /// Whether an emergency redemption, without tracker signature, was checked
+    pub emergency: bool,
+    /// The note exists, is signed by the issuer, undisputed and owes the amount
+    pub note: EligibilityCheck,
+    /// The emergency time lock has expired; normal redemptions have none
+    pub time_lock: EligibilityCheck,
+    /// The issuer has a reserve
+    pub reserve: EligibilityCheck,
+    /// The reserve holds at least the amount
+    pub collateral: EligibilityCheck,
+    /// The tracker can prove the note is in its AVL tree
+    pub proof: EligibilityCheck,
+    pub current_height: Option<u64>,
+    /// Height from which an emergency redemption is accepted
+    pub unlock_height: Option<u64>,
+    pub reserve_box_id: Option<String>,
+    /// nanoERG held by the reserve
+    pub collateral_amount: Option<u64>,
+}
+
+// Outcome of one redemption eligibility check
+#[derive(Debug, Serialize)]
+pub struct EligibilityCheck {
+    pub passed: bool,
+    /// Why the check failed
+    pub reasons: Vec<String>,
+}
+
+impl EligibilityCheck {
+    pub fn from_reasons(reasons: Vec<String>) -> Self {
+        Self { passed: reasons.is_empty(), reasons }
+    }
+}
+
+// Amount a split note allocates to one recipient
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SplitAllocationRequest {
+    pub recipient_pubkey: String,
+    pub amount: u64,
+}
+
+// Request structure for issuing one signed note to several recipients
+#[derive(Debug, Deserialize)]
+pub struct CreateSplitNoteRequest {
+    pub issuer_pubkey: String,
+    pub allocations: Vec<SplitAllocationRequest>,
+    pub timestamp: u64,
+    /// Issuer's Schnorr signature over every allocation (65 bytes, hex encoded)
+    pub signature: String,
+}
+
+// Serializable split note allocation, with the AVL tree key it is recorded under
+#[derive(Debug, Clone, Serialize)]
+pub struct SerializableSplitAllocation {
+    pub recipient_pubkey: String,
+    pub amount: u64,
+    /// blake2b256(split_id || recipient_pubkey)
+    pub avl_key: String,
+}
+
+// Serializable split note for API responses
+#[derive(Debug, Clone, Serialize)]
+pub struct SerializableSplitNote {
+    /// blake2b256 of the split note's signing message
+    pub split_id: String,
+    pub issuer_pubkey: String,
+    pub timestamp: u64,
+    pub signature: String,
+    pub total_amount: u64,
+    pub allocations: Vec<SerializableSplitAllocation>,
+}
+
+impl SerializableSplitNote {
+    pub fn new(issuer_pubkey: &basis_store::PubKey, split: &basis_store::SplitNote) -> Self {
+        Self {
+            split_id: hex::encode(split.id(issuer_pubkey)),
+            issuer_pubkey: hex::encode(issuer_pubkey),
+            timestamp: split.timestamp,
+            signature: hex::encode(split.signature),
+            total_amount: split.total_amount().unwrap_or(u64::MAX),
+            allocations: split
+                .allocations
+                .iter()
+                .zip(split.allocation_keys(issuer_pubkey))
+                .map(|(allocation, key)| SerializableSplitAllocation {
+                    recipient_pubkey: hex::encode(allocation.recipient_pubkey),
+                    amount: allocation.amount,
+                    avl_key: hex::encode(key.key_hash),
+                })
+                .collect(),
         }
     }
 }
 
+// Request structure for rotating an issuer key, signed by the old key
+#[derive(Debug, Deserialize)]
+pub struct RotateKeyRequest {
+    pub old_pubkey: String,
+    pub new_pubkey: String,
+    pub timestamp: u64,
+    /// Old key's Schnorr signature over the rotation (65 bytes, hex encoded)
+    pub signature: String,
+}
+
+// Serializable key rotation for API responses
+#[derive(Debug, Clone, Serialize)]
+pub struct SerializableKeyRotation {
+    pub old_pubkey: String,
+    pub new_pubkey: String,
+    pub timestamp: u64,
+    pub signature: String,
+}
+
+impl From<&basis_store::KeyRotation> for SerializableKeyRotation {
+    fn from(rotation: &basis_store::KeyRotation) -> Self {
+        Self {
+            old_pubkey: hex::encode(rotation.old_pubkey),
+            new_pubkey: hex::encode(rotation.new_pubkey),
+            timestamp: rotation.timestamp,
+            signature: hex::encode(rotation.signature),
+        }
+    }
+}
+
+// Encrypted payload of a note, as stored by the tracker
+#[derive(Debug, Clone, Serialize)]
+pub struct NotePayloadResponse {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    /// Timestamp of the note version the payload was written for
+    pub timestamp: u64,
+    /// Envelope only the issuer and recipient can open (hex-encoded)
+    pub encrypted_payload: String,
+}
+
+// Keys of an issuer identity, linked by rotations
+#[derive(Debug, Clone, Serialize)]
+pub struct IdentityKeysResponse {
+    /// Key the identity started with
+    pub identity_pubkey: String,
+    /// Key the identity signs notes with now
+    pub current_pubkey: String,
+    /// Every key of the identity, oldest first
+    pub keys: Vec<String>,
+    pub rotations: Vec<SerializableKeyRotation>,
+}
+
+impl From<&basis_store::KeyHistory> for IdentityKeysResponse {
+    fn from(history: &basis_store::KeyHistory) -> Self {
+        Self {
+            identity_pubkey: hex::encode(history.root_pubkey),
+            current_pubkey: hex::encode(history.current_pubkey()),
+            keys: history.keys().iter().map(hex::encode).collect(),
+            rotations: history.rotations.iter().map(SerializableKeyRotation::from).collect(),
+        }
+    }
+}
+
+// Request structure for a recipient acknowledging (co-signing) a note
+#[derive(Debug, Deserialize)]
+pub struct AcknowledgeNoteRequest {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    /// Recipient's Schnorr signature over the note's signing message (65 bytes, hex encoded)
+    pub recipient_signature: String,
+}
+
+// Version of the disputed note presented as evidence, signed by the issuer
+#[derive(Debug, Deserialize)]
+pub struct DisputeEvidenceRequest {
+    pub amount: u64,
+    pub timestamp: u64,
+    /// Issuer's Schnorr signature over the note (65 bytes, hex encoded)
+    pub signature: String,
+    /// Version of the signing message the signature covers (1 or 2)
+    #[serde(default = "default_signing_version")]
+    pub signing_version: u8,
+    /// Tracker NFT ID a version 2 note is bound to (32 bytes, hex encoded)
+    #[serde(default)]
+    pub tracker_id: Option<String>,
+}
+
+// Request structure for filing a dispute against a note
+#[derive(Debug, Deserialize)]
+pub struct FileDisputeRequest {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    /// Party filing the dispute: `issuer` or `recipient`
+    pub filed_by: basis_store::DisputeParty,
+    pub reason: String,
+    #[serde(default)]
+    pub evidence: Vec<DisputeEvidenceRequest>,
+    /// Filing time in milliseconds since the epoch
+    pub filed_at: u64,
+    /// Filer's Schnorr signature over the dispute signing message (65 bytes, hex encoded)
+    pub signature: String,
+}
+
+// Request structure for closing a dispute
+#[derive(Debug, Deserialize)]
+pub struct ResolveDisputeRequest {
+    /// `upheld` or `dismissed`
+    pub status: basis_store::DisputeStatus,
+    /// Explanation of the decision
+    #[serde(default)]
+    pub note: String,
+}
+
 // Key status response
 #[derive(Debug, Serialize)]
 pub struct KeyStatusResponse {
     pub total_debt: u64,
+    /// Portion of total_debt on notes co-signed by their recipients
+    pub acknowledged_debt: u64,
     pub collateral: u64,
     pub collateralization_ratio: f64,
     pub note_count: usize,
     pub last_updated: u64,
     pub issuer_pubkey: String,
+    /// Collateral and debt in `pricing.quote_currency`, null without a usable rate
+    pub valuation: Option<crate::pricing::Valuation>,
+}
+
+// Position of one direction of a counterparty pair: what the issuer owes the recipient
+#[derive(Debug, Clone, Serialize)]
+pub struct PairPosition {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    /// Whether the issuer has a note to the recipient
+    pub has_note: bool,
+    /// Total debt ever recorded on the note
+    pub collected: u64,
+    pub redeemed: u64,
+    /// Collected minus redeemed, without accrued interest
+    pub outstanding: u64,
+    /// Timestamp of the latest note version, in milliseconds since the Unix epoch
+    pub last_activity: Option<u64>,
+    /// Whether the recipient co-signed the latest note version
+    pub acknowledged: bool,
+    /// Whether the tracker can prove the note against its AVL root
+    pub proof_available: bool,
+}
+
+// Notes in both directions between two keys, netted against each other
+#[derive(Debug, Clone, Serialize)]
+pub struct BalanceResponse {
+    /// Notes from `issuer` to `recipient`
+    pub position: PairPosition,
+    /// Notes from `recipient` back to `issuer`
+    pub reverse: PairPosition,
+    /// Key owing the other on net, null when the positions cancel out
+    pub net_debtor: Option<String>,
+    pub net_creditor: Option<String>,
+    /// Difference of the outstanding amounts
+    pub net_amount: u64,
+    /// Sum of the outstanding amounts
+    pub gross_amount: u64,
+    /// Latest activity in either direction, in milliseconds since the Unix epoch
+    pub last_activity: Option<u64>,
+}
+
+// Aggregate figures over the whole tracker, for dashboards and explorers
+#[derive(Debug, Serialize)]
+pub struct NetworkStatsResponse {
+    /// Public keys that have issued at least one note
+    pub issuers: u64,
+    /// Public keys that hold at least one note
+    pub recipients: u64,
+    /// Issuer-recipient pairs with a note
+    pub notes: u64,
+    /// Collected minus redeemed across all notes, without accrued interest
+    pub total_outstanding_debt: u64,
+    /// Collateral across all tracked reserves
+    pub total_collateral: u64,
+    /// `total_collateral / total_outstanding_debt`, null when nothing is owed
+    pub collateralization_ratio: Option<f64>,
+    /// Issuers owing the most, largest first
+    pub top_issuers: Vec<IssuerDebtEntry>,
+    /// Note versions per day, oldest first, today last
+    pub notes_per_day: Vec<DailyNoteCountEntry>,
+}
+
+// Collateralization of an issuer over time, for charting
+#[derive(Debug, Serialize)]
+pub struct KeyStatusHistoryResponse {
+    pub issuer_pubkey: String,
+    /// Range of the snapshots, in seconds since the Unix epoch, both inclusive
+    pub from: u64,
+    pub to: u64,
+    /// Seconds per snapshot kept, null when every snapshot is returned
+    pub resolution: Option<u64>,
+    /// Snapshots in the range, oldest first
+    pub snapshots: Vec<crate::store::CollateralSnapshot>,
+}
+
+// A page of the request audit log
+#[derive(Debug, Serialize)]
+pub struct RequestAuditResponse {
+    /// Matching entries, oldest first
+    pub entries: Vec<crate::request_audit::RequestAuditEntry>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+// An issuer in the outstanding debt leaderboard
+#[derive(Debug, Serialize)]
+pub struct IssuerDebtEntry {
+    pub issuer_pubkey: String,
+    pub outstanding_debt: u64,
+    /// Collateral across the issuer's tracked reserves
+    pub collateral: u64,
+    /// `collateral / outstanding_debt`
+    pub collateralization_ratio: f64,
+}
+
+// Note versions accepted with a timestamp in one day
+#[derive(Debug, Serialize)]
+pub struct DailyNoteCountEntry {
+    /// Start of the day in milliseconds since the Unix epoch (UTC)
+    pub day_start: u64,
+    pub notes: u64,
 }
 
 // Redemption request
@@ -113,6 +589,9 @@ pub struct RedeemRequest {
     /// Whether this is an emergency redemption
     #[serde(default)]
     pub emergency: bool,
+    /// AVL proof inserting the new redeemed amount into the reserve's tree (hex encoded)
+    #[serde(default)]
+    pub reserve_insert_proof: Option<String>,
 }
 
 // Redemption completion request
@@ -336,6 +815,73 @@ pub struct Asset {
     pub amount: u64,
 }
 
+// Request for a redemption transaction signed by the receiver's wallet
+// POST /wallet/redeem; proofs and signatures as returned by /proof/redemption
+// and /tracker/signature
+#[derive(Debug, Deserialize)]
+pub struct WalletRedeemRequest {
+    pub reserve_box_id: String,
+    pub recipient_pubkey: String,
+    pub total_debt: u64,
+    pub timestamp: u64,
+    /// nanoERG taken from the reserve, transaction fee included
+    pub amount: u64,
+    #[serde(default)]
+    pub already_redeemed: u64,
+    pub reserve_signature: String,
+    /// Omitted once the emergency period has passed
+    #[serde(default)]
+    pub tracker_signature: String,
+    pub tracker_lookup_proof: String,
+    pub reserve_lookup_proof: Option<String>,
+    pub reserve_insert_proof: String,
+}
+
+// Request for a reserve creation transaction funded from the owner's wallet
+// POST /wallet/reserves/create
+#[derive(Debug, Deserialize)]
+pub struct WalletCreateReserveRequest {
+    pub owner_pubkey: String,
+    /// Reserve NFT, held in one of the funding address's boxes
+    pub nft_id: String,
+    pub erg_amount: u64,
+    /// Address whose boxes fund the reserve and receive the change
+    pub change_address: String,
+}
+
+// Request for a reserve top-up transaction funded from the owner's wallet
+// POST /wallet/reserves/{box_id}/top-up
+#[derive(Debug, Deserialize)]
+pub struct WalletTopUpRequest {
+    pub amount: u64,
+    /// Address whose boxes fund the top-up and receive the change
+    pub change_address: String,
+}
+
+// Unsigned transaction for a wallet to sign
+#[derive(Debug, Serialize)]
+pub struct WalletTxResponse {
+    pub tx_id: String,
+    /// EIP-12 unsigned transaction for `ergo.sign_tx`
+    pub unsigned_tx: basis_store::wallet_connector::Eip12UnsignedTx,
+    /// Static ErgoPay URL carrying the reduced transaction
+    pub ergopay_url: String,
+}
+
+// ErgoPay (EIP-20) signing request, returned to wallets from /ergopay/ URLs
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErgoPayResponse {
+    /// Base64url-encoded reduced transaction
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reduced_tx: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub address: Option<String>,
+    pub message: String,
+    /// INFORMATION, WARNING or ERROR
+    pub message_severity: String,
+}
+
 // Response for tracker box ID
 #[derive(Debug, Serialize)]
 pub struct TrackerBoxIdResponse {
@@ -344,6 +890,43 @@ pub struct TrackerBoxIdResponse {
     pub height: u64,
 }
 
+// Audit of the tracker's on-chain commitments against locally rebuilt state
+#[derive(Debug, Serialize)]
+pub struct AuditReport {
+    /// "watcher" or "tracker"
+    pub mode: String,
+    /// Tracker whose commitments are audited
+    pub tracker_public_key: Option<String>,
+    /// Notes held in the local AVL tree
+    pub notes_ingested: usize,
+    /// Most recent local root digest (hex)
+    pub local_root_digest: Option<String>,
+    /// Height at which the most recent local root was recorded
+    pub local_root_height: Option<u64>,
+    /// Whether the latest on-chain commitment matches local state, if checked
+    pub latest_commitment_consistent: Option<bool>,
+    pub verification: basis_store::VerificationStatus,
+}
+
+// Global tracker state a client needs to verify proofs against the on-chain tracker box
+#[derive(Debug, Serialize)]
+pub struct TrackerStateResponse {
+    /// Current AVL tree root digest (hex)
+    pub avl_root_digest: String,
+    /// Height of the latest on-chain commitment, if any was seen
+    pub last_commitment_height: Option<u64>,
+    /// Transaction that made the latest on-chain commitment, if known
+    pub last_commitment_tx_id: Option<String>,
+    /// Tracker box holding the latest on-chain commitment
+    pub last_commitment_box_id: Option<String>,
+    /// Notes held in the AVL tree
+    pub note_count: usize,
+    /// Timestamp of the most recently updated note
+    pub last_updated: Option<u64>,
+    /// Hex-encoded tracker public key
+    pub tracker_pubkey: Option<String>,
+}
+
 // Request for checking note acceptance
 #[derive(Debug, Deserialize)]
 pub struct CheckAcceptanceRequest {
@@ -362,6 +945,96 @@ pub struct CheckAcceptanceResponse {
     pub reason: Option<String>,
 }
 
+/// Request body of POST /admin/rescan
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RescanRequest {
+    /// Height to rescan reserve boxes from
+    pub from_height: u64,
+}
+
+/// Issuer to add to or remove from the issuer policy lists
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuerPolicyRequest {
+    /// Issuer public key (33 bytes, hex encoded)
+    pub issuer_pubkey: String,
+}
+
+/// Tags to attach to an issuer key, replacing any it had
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IssuerTagsRequest {
+    /// Issuer public key (33 bytes, hex encoded)
+    pub issuer_pubkey: String,
+    /// New tags; none removes the key's tags
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Request body of POST /admin/rates: one `base` is worth `rate` of `quote`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SetRateRequest {
+    pub base: String,
+    pub quote: String,
+    pub rate: f64,
+}
+
+/// Request body of POST /admin/rates/remove; either direction of the pair matches
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RemoveRateRequest {
+    pub base: String,
+    pub quote: String,
+}
+
+/// Conversion rates in force
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RatesResponse {
+    /// Currency key-status valuations are given in, if any
+    pub quote_currency: Option<String>,
+    pub rates: Vec<crate::pricing::ManualRate>,
+}
+
+/// Request body of POST /transactions: the transaction as bytes or as JSON
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmitTransactionRequest {
+    pub kind: crate::outbound_tx::OutboundTxKind,
+    /// Signed transaction, hex-encoded
+    #[serde(default)]
+    pub tx_bytes: Option<String>,
+    /// Signed transaction in the node's JSON form, as EIP-12 wallets return it
+    #[serde(default)]
+    pub tx_json: Option<serde_json::Value>,
+}
+
+/// Request body of POST /admin/maintenance; omitted steps follow `[maintenance]`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MaintenanceRequest {
+    /// Compact the databases
+    #[serde(default)]
+    pub compact: Option<bool>,
+    /// Repair what the note check finds rather than only reporting it
+    #[serde(default)]
+    pub repair: Option<bool>,
+}
+
+/// Current database sizes and the last maintenance run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub storages: Vec<basis_store::storage_maintenance::StorageReport>,
+    pub last_run: Option<crate::maintenance::MaintenanceRun>,
+}
+
+/// Outcome of an admin operation
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AdminActionResponse {
+    /// What was done
+    pub message: String,
+    /// AVL root digest after a rebuild (hex)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub root_digest: Option<String>,
+    /// Where a rotated log was moved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rotated_log: Option<String>,
+}
+
 // Success response helper
 pub fn success_response<T>(data: T) -> ApiResponse<T> {
     ApiResponse {
@@ -0,0 +1,70 @@
+//! Netting suggestions endpoint
+//!
+//! Plans settlements of offsetting debts over every outstanding note (see
+//! [`basis_store::netting`]), so trading circles can forgive debts they owe
+//! each other instead of redeeming them on chain.
+
+use axum::{extract::State, http::StatusCode, Json};
+use std::collections::HashMap;
+
+use basis_store::netting::{plan_netting, NettingPlan, DEFAULT_MAX_CYCLE_LENGTH, MAX_CYCLE_LENGTH};
+
+use crate::{
+    models::{error_response, success_response, ApiResponse},
+    requests,
+    AppState,
+};
+
+/// Get settlements netting the outstanding debts, optionally only those involving `pubkey`
+#[axum::debug_handler]
+pub async fn get_netting_suggestions(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<NettingPlan>>) {
+    tracing::debug!("Planning netting with params: {:?}", params);
+
+    let max_cycle_length = match params.get("max_cycle_length") {
+        None => DEFAULT_MAX_CYCLE_LENGTH,
+        Some(value) => match value.parse().ok().filter(|length| (2..=MAX_CYCLE_LENGTH).contains(length)) {
+            Some(length) => length,
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(error_response(format!("max_cycle_length must be between 2 and {}", MAX_CYCLE_LENGTH))),
+                )
+            }
+        },
+    };
+    let party = match params.get("pubkey") {
+        None => None,
+        Some(value) => match hex::decode(value).ok().filter(|bytes| bytes.len() == 33) {
+            Some(bytes) => Some(hex::encode(bytes)),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(error_response("pubkey must be 33 hex-encoded bytes".to_string())),
+                )
+            }
+        },
+    };
+
+    // Cycles can run through any issuer, so every note is needed
+    let notes = match state.send(requests::GetNotes {}).await {
+        Ok(Ok(notes)) => notes,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get notes: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response("Failed to retrieve notes".to_string())),
+            );
+        }
+        Err(e) => return e.response(),
+    };
+
+    let mut plan = plan_netting(&notes, max_cycle_length);
+    // Totals stay those of the whole plan; only the suggestions are narrowed
+    if let Some(party) = party {
+        plan.suggestions.retain(|suggestion| suggestion.cycle.contains(&party));
+    }
+    (StatusCode::OK, Json(success_response(plan)))
+}
@@ -0,0 +1,65 @@
+//! API handler for the encrypted payloads issuers attach to notes
+//!
+//! The tracker stores payloads as opaque envelopes; recipients fetch them
+//! and decrypt them locally with their own key.
+
+use axum::{extract::State, http::StatusCode, Json};
+
+use basis_store::PubKey;
+
+use crate::{
+    models::{error_response, success_response, ApiResponse, NotePayloadResponse},
+    requests,
+    AppState,
+};
+
+fn decode<const N: usize>(value: &str, field: &str) -> Result<[u8; N], String> {
+    hex::decode(value)
+        .map_err(|_| format!("{} must be hex-encoded", field))?
+        .try_into()
+        .map_err(|_| format!("{} must be {} bytes", field, N))
+}
+
+/// Get the encrypted payload of the current version of a note
+#[axum::debug_handler]
+pub async fn get_note_payload(
+    State(state): State<AppState>,
+    axum::extract::Path((issuer_pubkey_hex, recipient_pubkey_hex)): axum::extract::Path<(String, String)>,
+) -> (StatusCode, Json<ApiResponse<NotePayloadResponse>>) {
+    tracing::debug!("Getting note payload from {} to {}", issuer_pubkey_hex, recipient_pubkey_hex);
+
+    let keys = decode(&issuer_pubkey_hex, "issuer_pubkey")
+        .and_then(|issuer: PubKey| Ok((issuer, decode(&recipient_pubkey_hex, "recipient_pubkey")?)));
+    let (issuer_pubkey, recipient_pubkey) = match keys {
+        Ok(keys) => keys,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+
+    let request = requests::GetNotePayload {
+        issuer_pubkey,
+        recipient_pubkey,
+    };
+    match state.send(request).await {
+        Ok(Ok(Some((timestamp, envelope)))) => (
+            StatusCode::OK,
+            Json(success_response(NotePayloadResponse {
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                timestamp,
+                encrypted_payload: hex::encode(envelope),
+            })),
+        ),
+        Ok(Ok(None)) => (
+            StatusCode::NOT_FOUND,
+            Json(error_response("No encrypted payload for the current version of this note".to_string())),
+        ),
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get note payload: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response("Failed to retrieve note payload".to_string())),
+            )
+        }
+        Err(e) => e.response(),
+    }
+}
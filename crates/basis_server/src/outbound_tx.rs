@@ -0,0 +1,1054 @@
+//! Persistent queue of outbound transactions
+//!
+//! Signed transactions handed to the tracker through `POST /transactions`
+//! (redemptions and top-ups signed by users' wallets, commitments, reserve
+//! creations) are written to `outbound_tx.state_file` before their first
+//! submission, so none is lost when the server restarts. A background task
+//! submits them to the node, retrying failed submissions with exponential
+//! backoff from `initial_backoff_secs` up to `max_backoff_secs` and giving up
+//! after `max_attempts`. Submitted transactions are followed until the node
+//! includes one, recording its inclusion height, or until another
+//! transaction spends one of its inputs, which marks it double-spent.
+//!
+//! Inclusion is looked up through the node's blockchain index, which the
+//! reserve scanner needs as well.
+//!
+//! A commitment or redemption still unconfirmed `fee_bump_after_blocks` after
+//! the node accepted it is rebuilt from the same inputs with its miner fee
+//! raised by `fee_bump_step`, up to `max_fee`. The increase is taken from the
+//! last output holding no tokens, normally the change, and the node's wallet
+//! signs the copy, so only transactions spending the wallet's boxes can be
+//! bumped. The copy joins the queue as the replacement of the original, which
+//! is followed until one of them is included, and a `TransactionFeeBumped`
+//! event is recorded. A stuck transaction that cannot be bumped is recorded
+//! once as a `TransactionStuck` event.
+
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use axum::{extract::State, http::StatusCode, Json};
+use basis_store::reqwest;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{error_response, success_response, ApiResponse, EventType, SubmitTransactionRequest, TrackerEvent},
+    store::EventStore,
+    util::{now_secs, write_file_atomically},
+    AppState,
+};
+
+/// `[outbound_tx]` section of the server configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OutboundTxConfig {
+    /// File the queue is kept in
+    #[serde(default = "default_state_file")]
+    pub state_file: String,
+    /// Seconds between checks of submitted transactions
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: u64,
+    /// Failed submissions before a transaction is given up
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Seconds to wait after the first failed submission, doubled after each further one
+    #[serde(default = "default_initial_backoff_secs")]
+    pub initial_backoff_secs: u64,
+    /// Longest wait between submissions
+    #[serde(default = "default_max_backoff_secs")]
+    pub max_backoff_secs: u64,
+    /// Blocks a commitment or redemption may stay unconfirmed before its fee
+    /// is raised; 0 never raises fees
+    #[serde(default)]
+    pub fee_bump_after_blocks: u64,
+    /// nanoERG added to the miner fee by each bump
+    #[serde(default = "default_fee_bump_step")]
+    pub fee_bump_step: u64,
+    /// Highest miner fee a bump may pay, in nanoERG
+    #[serde(default = "default_max_fee")]
+    pub max_fee: u64,
+}
+
+fn default_state_file() -> String {
+    "data/outbound_tx.json".to_string()
+}
+
+fn default_poll_interval_secs() -> u64 {
+    30
+}
+
+fn default_max_attempts() -> u32 {
+    10
+}
+
+fn default_initial_backoff_secs() -> u64 {
+    30
+}
+
+fn default_max_backoff_secs() -> u64 {
+    3600
+}
+
+fn default_fee_bump_step() -> u64 {
+    1_000_000
+}
+
+fn default_max_fee() -> u64 {
+    10_000_000
+}
+
+impl Default for OutboundTxConfig {
+    fn default() -> Self {
+        Self {
+            state_file: default_state_file(),
+            poll_interval_secs: default_poll_interval_secs(),
+            max_attempts: default_max_attempts(),
+            initial_backoff_secs: default_initial_backoff_secs(),
+            max_backoff_secs: default_max_backoff_secs(),
+            fee_bump_after_blocks: 0,
+            fee_bump_step: default_fee_bump_step(),
+            max_fee: default_max_fee(),
+        }
+    }
+}
+
+impl OutboundTxConfig {
+    /// Seconds to wait after `attempts` failed submissions
+    pub fn backoff_secs(&self, attempts: u32) -> u64 {
+        let doublings = attempts.saturating_sub(1).min(63);
+        self.initial_backoff_secs
+            .saturating_mul(1u64 << doublings)
+            .min(self.max_backoff_secs)
+    }
+
+    /// Whether `entry`, submitted and unconfirmed at `height`, is due for a higher fee
+    pub fn is_stuck(&self, entry: &OutboundTx, height: u64) -> bool {
+        self.fee_bump_after_blocks > 0
+            && entry.status == OutboundTxStatus::Submitted
+            && matches!(entry.kind, OutboundTxKind::Commitment | OutboundTxKind::Redemption)
+            && entry
+                .submitted_height
+                .is_some_and(|submitted| height >= submitted.saturating_add(self.fee_bump_after_blocks))
+    }
+}
+
+/// What a queued transaction does
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboundTxKind {
+    Commitment,
+    Redemption,
+    TopUp,
+    ReserveCreation,
+}
+
+/// Where a queued transaction stands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboundTxStatus {
+    /// Waiting for its next submission
+    Pending,
+    /// Accepted by the node, not yet in a block
+    Submitted,
+    /// Included in a block
+    Confirmed,
+    /// An input was spent by another transaction
+    DoubleSpent,
+    /// Given up after `max_attempts` failed submissions
+    Failed,
+    /// Superseded by a copy paying a higher fee, followed until either is included
+    Replaced,
+}
+
+/// A transaction in the queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OutboundTx {
+    pub tx_id: String,
+    pub kind: OutboundTxKind,
+    /// Signed transaction, hex-encoded as the node's `/transactions/bytes`
+    /// takes it; empty for transactions queued as JSON
+    #[serde(default)]
+    pub tx_bytes: String,
+    /// Signed transaction in the node's JSON form, as EIP-12 wallets and the
+    /// node's wallet return it, submitted instead of `tx_bytes`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tx_json: Option<serde_json::Value>,
+    pub input_box_ids: Vec<String>,
+    pub status: OutboundTxStatus,
+    /// Submissions made so far
+    pub attempts: u32,
+    /// Seconds since the Unix epoch
+    pub queued_at: u64,
+    /// Earliest time of the next submission while pending
+    pub next_attempt_at: u64,
+    pub last_error: Option<String>,
+    /// Height of the block including the transaction
+    pub confirmation_height: Option<u64>,
+    /// nanoERG paid to the miner fee contract, known for transactions queued as JSON
+    #[serde(default)]
+    pub fee: Option<u64>,
+    /// Chain height when the node first accepted the transaction
+    #[serde(default)]
+    pub submitted_height: Option<u64>,
+    /// Transaction this one replaces with a higher fee
+    #[serde(default)]
+    pub replaces: Option<String>,
+    /// Transaction replacing this one with a higher fee
+    #[serde(default)]
+    pub replaced_by: Option<String>,
+    /// Why the fee of this stuck transaction could not be raised
+    #[serde(default)]
+    pub bump_error: Option<String>,
+}
+
+impl OutboundTx {
+    fn new(kind: OutboundTxKind, tx_id: String, input_box_ids: Vec<String>, now: u64) -> Self {
+        Self {
+            tx_id,
+            kind,
+            tx_bytes: String::new(),
+            tx_json: None,
+            input_box_ids,
+            status: OutboundTxStatus::Pending,
+            attempts: 0,
+            queued_at: now,
+            next_attempt_at: now,
+            last_error: None,
+            confirmation_height: None,
+            fee: None,
+            submitted_height: None,
+            replaces: None,
+            replaced_by: None,
+            bump_error: None,
+        }
+    }
+
+    /// Entry for a signed transaction in the node's JSON form
+    fn from_json(kind: OutboundTxKind, tx: serde_json::Value, now: u64) -> Result<Self, OutboundTxError> {
+        let invalid = |message: &str| OutboundTxError::InvalidTransaction(message.to_string());
+        let tx_id = tx["id"]
+            .as_str()
+            .filter(|id| id.len() == 64 && hex::decode(id).is_ok())
+            .ok_or_else(|| invalid("id must be a 32-byte hex transaction ID"))?
+            .to_lowercase();
+        let inputs = tx["inputs"].as_array().filter(|inputs| !inputs.is_empty());
+        let inputs = inputs.ok_or_else(|| invalid("inputs must not be empty"))?;
+        if inputs.iter().any(|input| input.get("spendingProof").is_none()) {
+            return Err(invalid("every input needs a spendingProof: the transaction is not signed"));
+        }
+        let input_box_ids = inputs
+            .iter()
+            .map(|input| input["boxId"].as_str().map(str::to_lowercase))
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| invalid("every input needs a boxId"))?;
+        let mut entry = Self::new(kind, tx_id, input_box_ids, now);
+        entry.fee = Some(miner_fee(&tx));
+        entry.tx_json = Some(tx);
+        Ok(entry)
+    }
+}
+
+// Hex of the miner fee contract, the same on every network
+fn miner_fee_tree() -> Option<String> {
+    use ergo_lib::chain::ergo_box::BoxValue;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+    ergo_lib::wallet::tx_builder::new_miner_fee_box(BoxValue::SAFE_USER_MIN, 0)
+        .ok()
+        .map(|fee_box| hex::encode(fee_box.ergo_tree.sigma_serialize_bytes()))
+}
+
+fn output_value(output: &serde_json::Value) -> u64 {
+    output["value"]
+        .as_u64()
+        .or_else(|| output["value"].as_str().and_then(|value| value.parse().ok()))
+        .unwrap_or(0)
+}
+
+// nanoERG the outputs locked by the miner fee contract hold, in a transaction's JSON
+fn miner_fee(tx: &serde_json::Value) -> u64 {
+    let fee_tree = miner_fee_tree();
+    tx["outputs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|output| fee_tree.is_some() && output["ergoTree"].as_str() == fee_tree.as_deref())
+        .map(output_value)
+        .sum()
+}
+
+/// Unsigned copy of a signed transaction in the node's JSON form paying
+/// `fee` to miners, taking the increase from the last output without tokens
+/// that can spare it
+pub fn with_fee(tx: &serde_json::Value, fee: u64) -> Result<serde_json::Value, String> {
+    let fee_tree = miner_fee_tree().ok_or_else(|| "miner fee contract unavailable".to_string())?;
+    let mut outputs = tx["outputs"].as_array().cloned().unwrap_or_default();
+    let fee_index = outputs
+        .iter()
+        .position(|output| output["ergoTree"].as_str() == Some(fee_tree.as_str()))
+        .ok_or_else(|| "no miner fee output".to_string())?;
+    let extra = fee.saturating_sub(miner_fee(tx));
+    let min_value = *ergo_lib::chain::ergo_box::BoxValue::SAFE_USER_MIN.as_u64();
+    let change_index = (0..outputs.len())
+        .rev()
+        .filter(|&index| index != fee_index)
+        .find(|&index| {
+            let no_tokens = outputs[index]["assets"].as_array().is_none_or(|assets| assets.is_empty());
+            no_tokens && output_value(&outputs[index]) >= extra.saturating_add(min_value)
+        })
+        .ok_or_else(|| format!("no output without tokens can pay {} nanoERG more", extra))?;
+    let change_value = output_value(&outputs[change_index]) - extra;
+    let fee_value = output_value(&outputs[fee_index]) + extra;
+    outputs[change_index]["value"] = serde_json::json!(change_value);
+    outputs[fee_index]["value"] = serde_json::json!(fee_value);
+    for output in &mut outputs {
+        // Box IDs and indexes belong to the original transaction
+        if let Some(output) = output.as_object_mut() {
+            output.remove("boxId");
+            output.remove("transactionId");
+            output.remove("index");
+        }
+    }
+
+    let inputs: Vec<serde_json::Value> = tx["inputs"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|input| {
+            let extension = input["spendingProof"]["extension"].clone();
+            serde_json::json!({
+                "boxId": input["boxId"],
+                "extension": if extension.is_null() { serde_json::json!({}) } else { extension },
+            })
+        })
+        .collect();
+    Ok(serde_json::json!({
+        "inputs": inputs,
+        "dataInputs": tx["dataInputs"].as_array().cloned().unwrap_or_default(),
+        "outputs": outputs,
+    }))
+}
+
+/// Errors queueing a transaction
+#[derive(Debug, thiserror::Error)]
+pub enum OutboundTxError {
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+    #[error("Failed to save the transaction queue: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/// Outbound transaction queue, shared between request handlers and the submission task
+#[derive(Clone, Default)]
+pub struct OutboundTxQueue {
+    entries: Arc<RwLock<Vec<OutboundTx>>>,
+    state_file: Option<PathBuf>,
+    /// Wakes the submission task when a transaction is queued
+    queued: Arc<tokio::sync::Notify>,
+    /// Where fee bumps and stuck transactions are recorded
+    event_store: Option<Arc<EventStore>>,
+}
+
+impl OutboundTxQueue {
+    /// Queue kept in the configured state file, with the transactions saved there
+    pub fn from_config(config: &OutboundTxConfig) -> std::io::Result<Self> {
+        let path = PathBuf::from(&config.state_file);
+        let entries = if path.exists() {
+            serde_json::from_slice(&std::fs::read(&path)?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?
+        } else {
+            Vec::new()
+        };
+        Ok(Self {
+            entries: Arc::new(RwLock::new(entries)),
+            state_file: Some(path),
+            queued: Arc::default(),
+            event_store: None,
+        })
+    }
+
+    /// Record fee bumps and stuck transactions in `event_store`
+    pub fn with_event_store(mut self, event_store: Arc<EventStore>) -> Self {
+        self.event_store = Some(event_store);
+        self
+    }
+
+    /// Every transaction, in the order they were queued
+    pub fn entries(&self) -> Vec<OutboundTx> {
+        self.entries.read().map(|entries| entries.clone()).unwrap_or_default()
+    }
+
+    pub fn get(&self, tx_id: &str) -> Option<OutboundTx> {
+        self.entries().into_iter().find(|entry| entry.tx_id == tx_id)
+    }
+
+    /// Queue a signed transaction and save the queue before it is ever submitted
+    ///
+    /// Queueing a transaction already in the queue returns it unchanged.
+    pub fn enqueue(&self, kind: OutboundTxKind, tx_bytes: &str) -> Result<OutboundTx, OutboundTxError> {
+        use ergo_lib::chain::transaction::Transaction;
+        use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+        let bytes = hex::decode(tx_bytes).map_err(|e| OutboundTxError::InvalidTransaction(e.to_string()))?;
+        let tx = Transaction::sigma_parse_bytes(&bytes)
+            .map_err(|e| OutboundTxError::InvalidTransaction(e.to_string()))?;
+        let input_box_ids = tx.inputs.iter().map(|input| String::from(input.box_id.clone())).collect();
+        let mut entry = OutboundTx::new(kind, String::from(tx.id()), input_box_ids, now_secs());
+        entry.tx_bytes = hex::encode(&bytes);
+        self.add(entry)
+    }
+
+    /// Queue a signed transaction in the node's JSON form, as EIP-12 wallets return it
+    ///
+    /// Queueing a transaction already in the queue returns it unchanged.
+    pub fn enqueue_json(&self, kind: OutboundTxKind, tx: serde_json::Value) -> Result<OutboundTx, OutboundTxError> {
+        self.add(OutboundTx::from_json(kind, tx, now_secs())?)
+    }
+
+    fn add(&self, entry: OutboundTx) -> Result<OutboundTx, OutboundTxError> {
+        if let Some(existing) = self.get(&entry.tx_id) {
+            return Ok(existing);
+        }
+        self.modify(|entries| entries.push(entry.clone()))?;
+        if let Err(e) = self.save() {
+            // A transaction that would not survive a restart is not accepted
+            self.modify(|entries| entries.retain(|queued| queued.tx_id != entry.tx_id))?;
+            return Err(e.into());
+        }
+        tracing::info!("Queued {:?} transaction {}", entry.kind, entry.tx_id);
+        self.queued.notify_one();
+        Ok(entry)
+    }
+
+    fn modify(&self, change: impl FnOnce(&mut Vec<OutboundTx>)) -> std::io::Result<()> {
+        let mut entries = self
+            .entries
+            .write()
+            .map_err(|_| std::io::Error::other("outbound transaction queue lock poisoned"))?;
+        change(&mut entries);
+        Ok(())
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.state_file else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec_pretty(&self.entries()).map_err(std::io::Error::other)?;
+        write_file_atomically(path, &json)
+    }
+
+    /// Submit the transactions due at `now` and follow the submitted ones,
+    /// returning how many changed
+    pub async fn process(&self, node: &OutboundNode, config: &OutboundTxConfig, now: u64) -> usize {
+        let entries = self.entries();
+        let height = match node.height().await {
+            Ok(height) => Some(height),
+            Err(e) => {
+                tracing::debug!("Chain height unavailable: {}", e);
+                None
+            }
+        };
+        let mut changed = Vec::new();
+        let mut replacements = Vec::new();
+        let mut events = Vec::new();
+        for entry in &entries {
+            // A replaced transaction is followed until either copy settles
+            let replacement_settled = entry.replaced_by.as_ref().is_some_and(|replacement| {
+                entries.iter().any(|other| {
+                    &other.tx_id == replacement
+                        && !matches!(other.status, OutboundTxStatus::Pending | OutboundTxStatus::Submitted)
+                })
+            });
+            if replacement_settled {
+                continue;
+            }
+            let mut updated = advance(node, config, entry.clone(), height, now).await;
+            if let Some(height) = height.filter(|&height| config.is_stuck(&updated, height)) {
+                match bump(node, config, &updated, now).await {
+                    Ok(replacement) => {
+                        tracing::info!(
+                            "Replaced stuck transaction {} by {} paying {:?} nanoERG",
+                            updated.tx_id,
+                            replacement.tx_id,
+                            replacement.fee
+                        );
+                        events.push(fee_event(
+                            EventType::TransactionFeeBumped {
+                                tx_id: replacement.tx_id.clone(),
+                                replaced_tx_id: updated.tx_id.clone(),
+                            },
+                            replacement.fee,
+                            height,
+                            now,
+                        ));
+                        updated.status = OutboundTxStatus::Replaced;
+                        updated.replaced_by = Some(replacement.tx_id.clone());
+                        replacements.push(replacement);
+                    }
+                    Err(e) => {
+                        if updated.bump_error.is_none() {
+                            tracing::warn!("Cannot raise the fee of stuck transaction {}: {}", updated.tx_id, e);
+                            events.push(fee_event(
+                                EventType::TransactionStuck {
+                                    tx_id: updated.tx_id.clone(),
+                                    reason: e.clone(),
+                                },
+                                updated.fee,
+                                height,
+                                now,
+                            ));
+                        }
+                        updated.bump_error = Some(e);
+                    }
+                }
+            }
+            if updated != *entry {
+                changed.push(updated);
+            }
+        }
+        if changed.is_empty() {
+            return 0;
+        }
+
+        let count = changed.len();
+        let applied = self.modify(|entries| {
+            for updated in changed {
+                if let Some(entry) = entries.iter_mut().find(|entry| entry.tx_id == updated.tx_id) {
+                    *entry = updated;
+                }
+            }
+            entries.extend(replacements);
+        });
+        if let Err(e) = applied.and_then(|_| self.save()) {
+            tracing::warn!("Failed to save the outbound transaction queue: {}", e);
+        }
+        if let (Some(event_store), false) = (&self.event_store, events.is_empty()) {
+            if let Err(e) = event_store.add_events(events).await {
+                tracing::warn!("Failed to record outbound transaction events: {:?}", e);
+            }
+        }
+        count
+    }
+}
+
+/// Node the queue submits to
+#[derive(Debug, Clone)]
+pub struct OutboundNode {
+    client: reqwest::Client,
+    url: String,
+    api_key: Option<String>,
+}
+
+impl OutboundNode {
+    pub fn new(url: &str, api_key: Option<&str>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            api_key: api_key.map(str::to_string),
+        }
+    }
+
+    async fn call(&self, request: reqwest::RequestBuilder) -> Result<Option<serde_json::Value>, String> {
+        let request = match &self.api_key {
+            Some(key) => request.header("api_key", key),
+            None => request,
+        };
+        let response = request.send().await.map_err(|e| e.to_string())?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        let text = response.text().await.unwrap_or_default();
+        if !status.is_success() {
+            return Err(format!("node answered {}: {}", status, text));
+        }
+        Ok(Some(serde_json::from_str(&text).unwrap_or(serde_json::Value::String(text))))
+    }
+
+    async fn submit(&self, entry: &OutboundTx) -> Result<(), String> {
+        let request = match &entry.tx_json {
+            Some(tx) => self.client.post(format!("{}/transactions", self.url)).json(tx),
+            None => self
+                .client
+                .post(format!("{}/transactions/bytes", self.url))
+                .json(&entry.tx_bytes),
+        };
+        self.call(request)
+            .await?
+            .map(|_| ())
+            .ok_or_else(|| "node answered 404 Not Found".to_string())
+    }
+
+    async fn height(&self) -> Result<u64, String> {
+        let request = self.client.get(format!("{}/info", self.url));
+        self.call(request)
+            .await?
+            .and_then(|info| info.get("fullHeight").and_then(|height| height.as_u64()))
+            .ok_or_else(|| "node reported no height".to_string())
+    }
+
+    /// Have the node's wallet sign an unsigned transaction in the node's JSON form
+    async fn sign(&self, unsigned: serde_json::Value) -> Result<serde_json::Value, String> {
+        let body = serde_json::json!({ "tx": unsigned });
+        let request = self.client.post(format!("{}/wallet/transaction/sign", self.url)).json(&body);
+        self.call(request)
+            .await?
+            .ok_or_else(|| "node answered 404 Not Found".to_string())
+    }
+
+    /// A transaction waiting in the node's mempool, in the node's JSON form
+    async fn unconfirmed(&self, tx_id: &str) -> Result<Option<serde_json::Value>, String> {
+        let request = self
+            .client
+            .get(format!("{}/transactions/unconfirmed/byTransactionId/{}", self.url, tx_id));
+        self.call(request).await
+    }
+
+    async fn is_unspent(&self, box_id: &str) -> Result<bool, String> {
+        let request = self.client.get(format!("{}/utxo/byId/{}", self.url, box_id));
+        Ok(self.call(request).await?.is_some())
+    }
+
+    async fn inclusion_height(&self, tx_id: &str) -> Result<Option<u64>, String> {
+        let request = self
+            .client
+            .get(format!("{}/blockchain/transaction/byId/{}", self.url, tx_id));
+        Ok(self
+            .call(request)
+            .await?
+            .and_then(|tx| tx.get("inclusionHeight").and_then(|height| height.as_u64())))
+    }
+}
+
+// Confirmed or double-spent status of a transaction, once one of its inputs is spent
+async fn settlement(node: &OutboundNode, entry: &OutboundTx) -> Result<Option<OutboundTxStatus>, String> {
+    // Inputs first: the transaction cannot be in a block while they are unspent
+    let mut spent = false;
+    for box_id in &entry.input_box_ids {
+        if !node.is_unspent(box_id).await? {
+            spent = true;
+            break;
+        }
+    }
+    if !spent {
+        return Ok(None);
+    }
+    Ok(Some(match node.inclusion_height(&entry.tx_id).await? {
+        Some(_) => OutboundTxStatus::Confirmed,
+        None => OutboundTxStatus::DoubleSpent,
+    }))
+}
+
+async fn settle(node: &OutboundNode, mut entry: OutboundTx, status: OutboundTxStatus) -> OutboundTx {
+    if status == OutboundTxStatus::Confirmed {
+        entry.confirmation_height = node.inclusion_height(&entry.tx_id).await.ok().flatten();
+        tracing::info!(
+            "Transaction {} confirmed at height {:?}",
+            entry.tx_id,
+            entry.confirmation_height
+        );
+    } else {
+        tracing::warn!("Transaction {} double-spent: an input was spent by another transaction", entry.tx_id);
+    }
+    entry.status = status;
+    entry
+}
+
+// Copy of `entry` paying `fee_bump_step` more to miners, signed by the node's wallet
+async fn bump(node: &OutboundNode, config: &OutboundTxConfig, entry: &OutboundTx, now: u64) -> Result<OutboundTx, String> {
+    // Transactions queued as bytes are read back from the mempool
+    let tx = match &entry.tx_json {
+        Some(tx) => tx.clone(),
+        None => node
+            .unconfirmed(&entry.tx_id)
+            .await?
+            .ok_or_else(|| "not in the node's mempool".to_string())?,
+    };
+    let current = miner_fee(&tx);
+    let fee = current.saturating_add(config.fee_bump_step).min(config.max_fee);
+    if fee <= current {
+        return Err(format!("fee of {} nanoERG already at max_fee", current));
+    }
+    let signed = node.sign(with_fee(&tx, fee)?).await?;
+    let mut replacement = OutboundTx::from_json(entry.kind, signed, now).map_err(|e| e.to_string())?;
+    replacement.replaces = Some(entry.tx_id.clone());
+    Ok(replacement)
+}
+
+fn fee_event(event_type: EventType, fee: Option<u64>, height: u64, now: u64) -> TrackerEvent {
+    TrackerEvent {
+        id: 0,
+        seq: 0,
+        event_type,
+        timestamp: now,
+        issuer_pubkey: None,
+        recipient_pubkey: None,
+        amount: fee,
+        reserve_box_id: None,
+        collateral_amount: None,
+        redeemed_amount: None,
+        height: Some(height),
+    }
+}
+
+async fn advance(
+    node: &OutboundNode,
+    config: &OutboundTxConfig,
+    mut entry: OutboundTx,
+    height: Option<u64>,
+    now: u64,
+) -> OutboundTx {
+    match entry.status {
+        OutboundTxStatus::Pending if entry.next_attempt_at <= now => {
+            entry.attempts += 1;
+            match node.submit(&entry).await {
+                Ok(()) => {
+                    tracing::info!("Submitted transaction {} (attempt {})", entry.tx_id, entry.attempts);
+                    entry.status = OutboundTxStatus::Submitted;
+                    entry.submitted_height = entry.submitted_height.or(height);
+                    entry.last_error = None;
+                    entry
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to submit transaction {}: {}", entry.tx_id, e);
+                    entry.last_error = Some(e);
+                    // A rejection may mean the inputs are gone, to this transaction or a rival
+                    if let Ok(Some(status)) = settlement(node, &entry).await {
+                        return settle(node, entry, status).await;
+                    }
+                    if entry.attempts >= config.max_attempts {
+                        tracing::error!("Giving up on transaction {} after {} attempts", entry.tx_id, entry.attempts);
+                        entry.status = OutboundTxStatus::Failed;
+                    } else {
+                        entry.next_attempt_at = now.saturating_add(config.backoff_secs(entry.attempts));
+                    }
+                    entry
+                }
+            }
+        }
+        OutboundTxStatus::Submitted => match settlement(node, &entry).await {
+            Ok(Some(status)) => settle(node, entry, status).await,
+            Ok(None) => entry,
+            Err(e) => {
+                tracing::debug!("Could not check transaction {}: {}", entry.tx_id, e);
+                entry
+            }
+        },
+        // The original may still be included instead of its replacement
+        OutboundTxStatus::Replaced => match settlement(node, &entry).await {
+            Ok(Some(OutboundTxStatus::Confirmed)) => settle(node, entry, OutboundTxStatus::Confirmed).await,
+            _ => entry,
+        },
+        _ => entry,
+    }
+}
+
+/// Submit and follow queued transactions every `config.poll_interval_secs`,
+/// and as soon as one is queued, until shutdown
+pub async fn outbound_tx_task(
+    queue: OutboundTxQueue,
+    node: OutboundNode,
+    config: OutboundTxConfig,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    // The first tick is immediate, resuming the transactions saved before a restart
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(config.poll_interval_secs));
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            _ = ticker.tick() => {}
+            _ = queue.queued.notified() => {}
+        }
+        let changed = queue.process(&node, &config, now_secs()).await;
+        if changed > 0 {
+            tracing::debug!("{} outbound transactions changed status", changed);
+        }
+    }
+}
+
+/// Queue a signed transaction for submission to the node
+#[axum::debug_handler]
+pub async fn submit_transaction(
+    State(state): State<AppState>,
+    Json(payload): Json<SubmitTransactionRequest>,
+) -> (StatusCode, Json<ApiResponse<OutboundTx>>) {
+    let queued = match (payload.tx_bytes, payload.tx_json) {
+        (Some(tx_bytes), None) => state.outbound_tx.enqueue(payload.kind, &tx_bytes),
+        (None, Some(tx_json)) => state.outbound_tx.enqueue_json(payload.kind, tx_json),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(error_response("Exactly one of tx_bytes and tx_json is required".to_string())),
+            )
+        }
+    };
+    match queued {
+        Ok(entry) => (StatusCode::ACCEPTED, Json(success_response(entry))),
+        Err(e @ OutboundTxError::InvalidTransaction(_)) => {
+            (StatusCode::BAD_REQUEST, Json(error_response(e.to_string())))
+        }
+        Err(e) => {
+            tracing::error!("{}", e);
+            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response(e.to_string())))
+        }
+    }
+}
+
+/// List queued transactions, optionally only those with a `status`
+#[axum::debug_handler]
+pub async fn get_transactions(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<std::collections::HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<Vec<OutboundTx>>>) {
+    let status = match params.get("status") {
+        None => None,
+        Some(value) => match serde_json::from_value::<OutboundTxStatus>(serde_json::Value::String(value.clone())) {
+            Ok(status) => Some(status),
+            Err(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(error_response(
+                        "status must be pending, submitted, confirmed, double_spent, failed or replaced".to_string(),
+                    )),
+                )
+            }
+        },
+    };
+    let mut entries = state.outbound_tx.entries();
+    if let Some(status) = status {
+        entries.retain(|entry| entry.status == status);
+    }
+    (StatusCode::OK, Json(success_response(entries)))
+}
+
+/// Get a queued transaction by ID
+#[axum::debug_handler]
+pub async fn get_transaction(
+    State(state): State<AppState>,
+    axum::extract::Path(tx_id): axum::extract::Path<String>,
+) -> (StatusCode, Json<ApiResponse<OutboundTx>>) {
+    match state.outbound_tx.get(&tx_id.to_lowercase()) {
+        Some(entry) => (StatusCode::OK, Json(success_response(entry))),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(error_response(format!("Transaction {} is not queued", tx_id))),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use basis_store::mock_node::{BoxSpec, MockErgoNode};
+    use ergo_lib::chain::ergo_box::{BoxId, BoxValue, ErgoBoxCandidate, NonMandatoryRegisters};
+    use ergo_lib::chain::transaction::{input::prover_result::ProverResult, Input, Transaction};
+    use ergo_lib::ergotree_interpreter::sigma_protocol::prover::{ContextExtension, ProofBytes};
+    use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+    const TREE: &str = "0008cd02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7";
+
+    // Transaction spending `inputs`, hex-encoded
+    fn signed_tx(inputs: &[&str]) -> String {
+        let inputs = inputs
+            .iter()
+            .map(|id| {
+                let proof = ProverResult {
+                    proof: ProofBytes::Empty,
+                    extension: ContextExtension::empty(),
+                };
+                Input::new(BoxId::try_from(id.to_string()).unwrap(), proof)
+            })
+            .collect();
+        let output = ErgoBoxCandidate {
+            value: BoxValue::SAFE_USER_MIN,
+            ergo_tree: ErgoTree::sigma_parse_bytes(&hex::decode(TREE).unwrap()).unwrap(),
+            tokens: Vec::new(),
+            additional_registers: NonMandatoryRegisters::empty(),
+            creation_height: 100,
+        };
+        hex::encode(Transaction::new(inputs, Vec::new(), vec![output]).sigma_serialize_bytes())
+    }
+
+    // Signed transaction spending `input` into `change` back to TREE and `fee` to miners, as node JSON
+    fn payment_json(tx_id: &str, input: &str, change: u64, fee: u64) -> serde_json::Value {
+        serde_json::json!({
+            "id": tx_id,
+            "inputs": [{ "boxId": input, "spendingProof": { "proofBytes": "", "extension": {} } }],
+            "dataInputs": [],
+            "outputs": [
+                { "value": change, "ergoTree": TREE, "assets": [], "additionalRegisters": {}, "creationHeight": 100 },
+                { "value": fee, "ergoTree": miner_fee_tree().unwrap(), "assets": [], "additionalRegisters": {}, "creationHeight": 100 },
+            ],
+        })
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_limit() {
+        let config = OutboundTxConfig {
+            initial_backoff_secs: 10,
+            max_backoff_secs: 100,
+            ..OutboundTxConfig::default()
+        };
+        let waits: Vec<u64> = (1..=5).map(|attempts| config.backoff_secs(attempts)).collect();
+        assert_eq!(waits, vec![10, 20, 40, 80, 100]);
+        assert_eq!(config.backoff_secs(200), 100);
+    }
+
+    #[tokio::test]
+    async fn test_queue_submits_retries_and_settles_across_restarts() {
+        let dir = std::env::temp_dir().join(format!("basis_outbound_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = OutboundTxConfig {
+            state_file: dir.join("outbound_tx.json").to_string_lossy().into_owned(),
+            max_attempts: 2,
+            initial_backoff_secs: 10,
+            ..OutboundTxConfig::default()
+        };
+        let node = MockErgoNode::start(100).await;
+        let outbound = OutboundNode::new(&node.url, None);
+        let spec = || BoxSpec::new(1_000_000_000, TREE);
+        let (redeemed, contested, committed) = (node.create_box(spec()), node.create_box(spec()), node.create_box(spec()));
+
+        let queue = OutboundTxQueue::from_config(&config).unwrap();
+        assert!(matches!(
+            queue.enqueue(OutboundTxKind::Redemption, "00"),
+            Err(OutboundTxError::InvalidTransaction(_))
+        ));
+        let redemption = queue.enqueue(OutboundTxKind::Redemption, &signed_tx(&[&redeemed])).unwrap();
+        let top_up = queue.enqueue(OutboundTxKind::TopUp, &signed_tx(&[&contested])).unwrap();
+        let retried = queue.enqueue(OutboundTxKind::Commitment, &signed_tx(&[&committed])).unwrap();
+        assert_eq!(redemption.input_box_ids, vec![redeemed.clone()]);
+        assert_eq!(queue.enqueue(OutboundTxKind::TopUp, &top_up.tx_bytes).unwrap(), top_up);
+
+        // Saved before the first submission, so a restart picks every one up
+        let queue = OutboundTxQueue::from_config(&config).unwrap();
+        assert_eq!(queue.entries().len(), 3);
+        let now = queue.entries()[0].queued_at;
+
+        node.fail_requests("/transactions/bytes", axum::http::StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(queue.process(&outbound, &config, now).await, 3);
+        let pending = queue.get(&retried.tx_id).unwrap();
+        assert_eq!((pending.status, pending.attempts, pending.next_attempt_at), (OutboundTxStatus::Pending, 1, now + 10));
+        assert!(pending.last_error.is_some());
+        // Nothing is due during the backoff
+        assert_eq!(queue.process(&outbound, &config, now + 5).await, 0);
+
+        node.clear_failures();
+        assert_eq!(queue.process(&outbound, &config, now + 10).await, 3);
+        assert!(queue.entries().iter().all(|entry| entry.status == OutboundTxStatus::Submitted));
+
+        // A rival spends the top-up's input first, so only the redemption
+        // and the commitment are mined
+        node.spend(&[&contested], Vec::new());
+        node.advance(1);
+        assert_eq!(node.mine_pool(), vec![redemption.tx_id.clone(), retried.tx_id.clone()]);
+        let queue = OutboundTxQueue::from_config(&config).unwrap();
+        queue.process(&outbound, &config, now + 20).await;
+        let confirmed = queue.get(&redemption.tx_id).unwrap();
+        assert_eq!((confirmed.status, confirmed.confirmation_height), (OutboundTxStatus::Confirmed, Some(101)));
+        assert_eq!(queue.get(&top_up.tx_id).unwrap().status, OutboundTxStatus::DoubleSpent);
+
+        // Submissions failing past max_attempts are given up
+        let unknown = format!("{:064x}", u64::MAX);
+        let failing = queue.enqueue(OutboundTxKind::TopUp, &signed_tx(&[&unknown])).unwrap();
+        node.fail_requests("/transactions/bytes", axum::http::StatusCode::BAD_REQUEST);
+        node.fail_requests("/utxo", axum::http::StatusCode::SERVICE_UNAVAILABLE);
+        queue.process(&outbound, &config, now_secs()).await;
+        queue.process(&outbound, &config, now_secs() + 10).await;
+        let failed = queue.get(&failing.tx_id).unwrap();
+        assert_eq!((failed.status, failed.attempts), (OutboundTxStatus::Failed, 2));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_fee_increase_comes_out_of_the_change() {
+        let tx = payment_json(&format!("{:064x}", 1), &format!("{:064x}", 2), 998_000_000, 1_000_000);
+        assert_eq!(miner_fee(&tx), 1_000_000);
+        let unsigned = with_fee(&tx, 3_000_000).unwrap();
+        assert_eq!(miner_fee(&unsigned), 3_000_000);
+        assert_eq!(output_value(&unsigned["outputs"][0]), 996_000_000);
+        assert_eq!(unsigned["inputs"][0], serde_json::json!({ "boxId": format!("{:064x}", 2), "extension": {} }));
+        assert!(with_fee(&tx, 999_000_000).is_err());
+        assert!(OutboundTx::from_json(OutboundTxKind::Redemption, unsigned, 0).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stuck_transactions_are_replaced_with_higher_fees() {
+        let config = OutboundTxConfig {
+            fee_bump_after_blocks: 2,
+            fee_bump_step: 1_000_000,
+            max_fee: 2_500_000,
+            ..OutboundTxConfig::default()
+        };
+        let node = MockErgoNode::start(100).await;
+        let outbound = OutboundNode::new(&node.url, None);
+        let spec = || BoxSpec::new(1_000_000_000, TREE);
+        let (committed, redeemed, topped_up) = (node.create_box(spec()), node.create_box(spec()), node.create_box(spec()));
+        let events = Arc::new(EventStore::new_in_memory());
+        let queue = OutboundTxQueue::default().with_event_store(events.clone());
+
+        let original = queue
+            .enqueue_json(
+                OutboundTxKind::Commitment,
+                payment_json(&format!("{:064x}", u64::MAX), &committed, 998_000_000, 1_000_000),
+            )
+            .unwrap();
+        // Queued as bytes without a miner fee output, so its fee cannot be raised
+        let redemption = queue.enqueue(OutboundTxKind::Redemption, &signed_tx(&[&redeemed])).unwrap();
+        let top_up = queue.enqueue(OutboundTxKind::TopUp, &signed_tx(&[&topped_up])).unwrap();
+        assert_eq!(original.fee, Some(1_000_000));
+        let now = original.queued_at;
+        queue.process(&outbound, &config, now).await;
+        assert_eq!(queue.get(&original.tx_id).unwrap().submitted_height, Some(100));
+
+        // Not stuck until two blocks pass
+        node.advance(1);
+        assert_eq!(queue.process(&outbound, &config, now).await, 0);
+        node.advance(1);
+        queue.process(&outbound, &config, now).await;
+        let replaced = queue.get(&original.tx_id).unwrap();
+        assert_eq!(replaced.status, OutboundTxStatus::Replaced);
+        let first = queue.get(replaced.replaced_by.as_ref().unwrap()).unwrap();
+        assert_eq!((first.fee, first.replaces.as_ref()), (Some(2_000_000), Some(&original.tx_id)));
+        assert_eq!(first.input_box_ids, original.input_box_ids);
+        assert!(queue.get(&redemption.tx_id).unwrap().bump_error.is_some());
+        // Top-ups are signed by users' wallets and never bumped
+        assert_eq!(queue.get(&top_up.tx_id).unwrap().bump_error, None);
+
+        // The replacement is submitted and bumped in turn, up to max_fee
+        queue.process(&outbound, &config, now).await;
+        assert_eq!(queue.get(&first.tx_id).unwrap().submitted_height, Some(102));
+        node.advance(2);
+        queue.process(&outbound, &config, now).await;
+        let second = queue.get(queue.get(&first.tx_id).unwrap().replaced_by.as_ref().unwrap()).unwrap();
+        assert_eq!(second.fee, Some(2_500_000));
+        queue.process(&outbound, &config, now).await;
+        node.advance(2);
+        queue.process(&outbound, &config, now).await;
+        queue.process(&outbound, &config, now).await;
+        let stuck = queue.get(&second.tx_id).unwrap();
+        assert_eq!(stuck.status, OutboundTxStatus::Submitted);
+        assert!(stuck.bump_error.is_some());
+
+        // The last copy is mined, ending the replacement chain
+        assert_eq!(
+            node.mine_pool(),
+            vec![redemption.tx_id.clone(), top_up.tx_id.clone(), second.tx_id.clone()]
+        );
+        queue.process(&outbound, &config, now).await;
+        assert_eq!(queue.get(&second.tx_id).unwrap().status, OutboundTxStatus::Confirmed);
+        assert_eq!(queue.get(&first.tx_id).unwrap().status, OutboundTxStatus::Replaced);
+        assert_eq!(queue.process(&outbound, &config, now).await, 0);
+
+        // Each bump is recorded, and each stuck transaction once
+        let recorded: Vec<&str> = events
+            .query_events(&crate::store::EventQuery { limit: 10, ..Default::default() })
+            .await
+            .unwrap()
+            .iter()
+            .map(|event| event.event_type.name())
+            .collect();
+        assert_eq!(
+            recorded,
+            vec!["TransactionFeeBumped", "TransactionStuck", "TransactionFeeBumped", "TransactionStuck"]
+        );
+    }
+}
@@ -0,0 +1,464 @@
+//! Manually configured conversion rates
+//!
+//! Deployments without an oracle pool can still value collateral and debt in
+//! another unit: operators configure rates between ERG, tokens and fiat
+//! currencies, either statically in `[[pricing.rates]]` or through the admin
+//! API, e.g. from a cron job pushing a fresh rate every hour. A rate set
+//! through the admin API older than `pricing.max_age_secs` is no longer used;
+//! configured rates never go stale.
+//!
+//! Rates chain in either direction, so with `ERG/SigUSD` and `SigUSD/USD`
+//! configured, ERG amounts are valued in USD. Every admin change is appended
+//! to `pricing.audit_log_path`; with `pricing.state_file` set the rates in
+//! force are saved there and replace the configured ones on the next start.
+//!
+//! ```toml
+//! [pricing]
+//! quote_currency = "USD"
+//!
+//! [[pricing.rates]]
+//! base = "ERG"
+//! quote = "USD"
+//! rate = 1.25
+//! ```
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::util::{now_secs, write_file_atomically};
+
+/// Unit of collateral and note amounts, in nanoERG
+pub const COLLATERAL_ASSET: &str = "ERG";
+const NANOERGS_PER_ERG: f64 = 1_000_000_000.0;
+
+/// `[pricing]` section of the server configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PricingConfig {
+    /// Currency or token key-status valuations are given in; none when absent
+    #[serde(default)]
+    pub quote_currency: Option<String>,
+    /// Seconds after which a rate set through the admin API is no longer
+    /// used; 0 keeps rates in use until replaced
+    #[serde(default)]
+    pub max_age_secs: u64,
+    /// Static rates
+    #[serde(default)]
+    pub rates: Vec<RateConfig>,
+    /// File admin changes to the rates are saved to
+    #[serde(default)]
+    pub state_file: Option<String>,
+    /// JSON lines log of every admin change to the rates
+    #[serde(default = "default_audit_log_path")]
+    pub audit_log_path: PathBuf,
+}
+
+/// One `[[pricing.rates]]` entry: one `base` is worth `rate` of `quote`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RateConfig {
+    pub base: String,
+    pub quote: String,
+    pub rate: f64,
+}
+
+fn default_audit_log_path() -> PathBuf {
+    PathBuf::from("data/rate_audit.jsonl")
+}
+
+impl Default for PricingConfig {
+    fn default() -> Self {
+        Self {
+            quote_currency: None,
+            max_age_secs: 0,
+            rates: Vec::new(),
+            state_file: None,
+            audit_log_path: default_audit_log_path(),
+        }
+    }
+}
+
+impl PricingConfig {
+    /// Invalid currencies and rates, as (field path, message)
+    pub fn invalid_settings(&self) -> Vec<(String, String)> {
+        let mut invalid = Vec::new();
+        if self.quote_currency.as_deref().is_some_and(|currency| currency.trim().is_empty()) {
+            invalid.push(("pricing.quote_currency".to_string(), "must not be empty".to_string()));
+        }
+        for (i, rate) in self.rates.iter().enumerate() {
+            if let Err(message) = check_rate(&rate.base, &rate.quote, rate.rate) {
+                invalid.push((format!("pricing.rates[{}]", i), message));
+            }
+        }
+        invalid
+    }
+}
+
+/// Check a rate given by an operator
+pub fn check_rate(base: &str, quote: &str, rate: f64) -> Result<(), String> {
+    if base.trim().is_empty() || quote.trim().is_empty() {
+        return Err("base and quote must not be empty".to_string());
+    }
+    if symbol(base) == symbol(quote) {
+        return Err(format!("base and quote are both {}", symbol(base)));
+    }
+    if !rate.is_finite() || rate <= 0.0 {
+        return Err(format!("rate {} must be a positive number", rate));
+    }
+    Ok(())
+}
+
+// Currencies and tokens are matched case-insensitively
+fn symbol(name: &str) -> String {
+    name.trim().to_uppercase()
+}
+
+fn pair(base: &str, quote: &str) -> String {
+    format!("{}/{}", symbol(base), symbol(quote))
+}
+
+/// A rate in force
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManualRate {
+    pub base: String,
+    pub quote: String,
+    /// Units of `quote` one `base` is worth
+    pub rate: f64,
+    /// Seconds since the Unix epoch
+    pub updated_at: u64,
+    /// `config` or `admin`
+    pub source: String,
+}
+
+/// Rates in force, by `BASE/QUOTE` pair
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RateTable {
+    #[serde(default)]
+    pub rates: BTreeMap<String, ManualRate>,
+}
+
+impl RateTable {
+    fn from_config(config: &PricingConfig, now: u64) -> Self {
+        let rates = config
+            .rates
+            .iter()
+            .map(|rate| {
+                let manual = ManualRate {
+                    base: symbol(&rate.base),
+                    quote: symbol(&rate.quote),
+                    rate: rate.rate,
+                    updated_at: now,
+                    source: "config".to_string(),
+                };
+                (pair(&rate.base, &rate.quote), manual)
+            })
+            .collect();
+        Self { rates }
+    }
+
+    /// Units of `to` one `from` is worth, chaining rates in either direction,
+    /// with the oldest update among the rates used
+    ///
+    /// Admin rates older than `max_age_secs` are skipped, unless it is 0.
+    pub fn convert(&self, from: &str, to: &str, now: u64, max_age_secs: u64) -> Option<(f64, u64)> {
+        let (from, to) = (symbol(from), symbol(to));
+        let usable = |rate: &&ManualRate| {
+            rate.source == "config" || max_age_secs == 0 || now.saturating_sub(rate.updated_at) <= max_age_secs
+        };
+        let mut edges: HashMap<&str, Vec<(&str, f64, u64)>> = HashMap::new();
+        for rate in self.rates.values().filter(usable) {
+            edges.entry(&rate.base).or_default().push((&rate.quote, rate.rate, rate.updated_at));
+            edges.entry(&rate.quote).or_default().push((&rate.base, 1.0 / rate.rate, rate.updated_at));
+        }
+
+        // Breadth first, so the chain with the fewest conversions wins
+        let mut found: HashMap<&str, (f64, u64)> = HashMap::from([(from.as_str(), (1.0, u64::MAX))]);
+        let mut queue = VecDeque::from([from.as_str()]);
+        while let Some(currency) = queue.pop_front() {
+            let (factor, updated_at) = found[currency];
+            if currency == to {
+                return Some((factor, updated_at));
+            }
+            for (next, rate, rate_updated_at) in edges.get(currency).into_iter().flatten() {
+                if !found.contains_key(next) {
+                    found.insert(next, (factor * rate, updated_at.min(*rate_updated_at)));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Collateral and debt valued in the quote currency
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Valuation {
+    pub currency: String,
+    /// Units of `currency` one ERG is worth
+    pub rate: f64,
+    pub collateral_value: f64,
+    pub debt_value: f64,
+    /// Oldest update among the rates used, seconds since the Unix epoch
+    pub rate_updated_at: u64,
+    /// Where the rate came from; `manual` for operator configured rates
+    pub source: String,
+}
+
+/// One line of the rate audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateAuditEntry {
+    /// Seconds since the Unix epoch
+    pub changed_at: u64,
+    /// `set` or `remove`
+    pub action: String,
+    pub base: String,
+    pub quote: String,
+    pub previous_rate: Option<f64>,
+    pub rate: Option<f64>,
+}
+
+/// Conversion rates, shared between request handlers and the admin API
+#[derive(Debug, Clone, Default)]
+pub struct Pricing {
+    table: Arc<RwLock<RateTable>>,
+    quote_currency: Option<String>,
+    max_age_secs: u64,
+    state_file: Option<PathBuf>,
+    audit_log_path: PathBuf,
+    // Serialises admin changes, so saves and audit lines keep their order
+    changes: Arc<tokio::sync::Mutex<()>>,
+}
+
+impl Pricing {
+    /// Rates from configuration, or from the state file if one was saved
+    pub fn from_config(config: &PricingConfig) -> std::io::Result<Self> {
+        let state_file = config.state_file.as_ref().map(PathBuf::from);
+        let table = match &state_file {
+            Some(path) if path.exists() => serde_json::from_slice(&std::fs::read(path)?)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))?,
+            _ => RateTable::from_config(config, now_secs()),
+        };
+        Ok(Self {
+            table: Arc::new(RwLock::new(table)),
+            quote_currency: config.quote_currency.as_deref().map(symbol),
+            max_age_secs: config.max_age_secs,
+            state_file,
+            audit_log_path: config.audit_log_path.clone(),
+            changes: Arc::default(),
+        })
+    }
+
+    /// Currency valuations are given in, if any
+    pub fn quote_currency(&self) -> Option<&str> {
+        self.quote_currency.as_deref()
+    }
+
+    /// Rates in force
+    pub fn rates(&self) -> Vec<ManualRate> {
+        self.table
+            .read()
+            .map(|table| table.rates.values().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Value nanoERG collateral and debt in the quote currency, when one is
+    /// configured and the rates reach it
+    pub fn valuation(&self, collateral: u64, debt: u64) -> Option<Valuation> {
+        let currency = self.quote_currency.clone()?;
+        let (rate, rate_updated_at) = self
+            .table
+            .read()
+            .ok()?
+            .convert(COLLATERAL_ASSET, &currency, now_secs(), self.max_age_secs)?;
+        let value = |amount: u64| amount as f64 / NANOERGS_PER_ERG * rate;
+        Some(Valuation {
+            currency,
+            rate,
+            collateral_value: value(collateral),
+            debt_value: value(debt),
+            rate_updated_at: if rate_updated_at == u64::MAX { now_secs() } else { rate_updated_at },
+            source: "manual".to_string(),
+        })
+    }
+
+    /// Set a rate, audit the change and save the rates to the state file
+    ///
+    /// The change is kept in memory even if saving or auditing fails.
+    pub async fn set_rate(&self, base: &str, quote: &str, rate: f64) -> std::io::Result<ManualRate> {
+        check_rate(base, quote, rate).map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidInput, e))?;
+        let _change = self.changes.lock().await;
+        let now = now_secs();
+        let manual = ManualRate {
+            base: symbol(base),
+            quote: symbol(quote),
+            rate,
+            updated_at: now,
+            source: "admin".to_string(),
+        };
+        // A rate set for the inverse pair replaces it, rather than competing with it
+        let previous = self.update(|table| {
+            let inverse = table.rates.remove(&pair(quote, base)).map(|rate| 1.0 / rate.rate);
+            table.rates.insert(pair(base, quote), manual.clone()).map(|rate| rate.rate).or(inverse)
+        })?;
+        self.audit(RateAuditEntry {
+            changed_at: now,
+            action: "set".to_string(),
+            base: manual.base.clone(),
+            quote: manual.quote.clone(),
+            previous_rate: previous,
+            rate: Some(rate),
+        })?;
+        self.save()?;
+        Ok(manual)
+    }
+
+    /// Remove a rate, audit the change and save the rates to the state file
+    pub async fn remove_rate(&self, base: &str, quote: &str) -> std::io::Result<Option<ManualRate>> {
+        let _change = self.changes.lock().await;
+        let removed = self.update(|table| {
+            table
+                .rates
+                .remove(&pair(base, quote))
+                .or_else(|| table.rates.remove(&pair(quote, base)))
+        })?;
+        if let Some(rate) = &removed {
+            self.audit(RateAuditEntry {
+                changed_at: now_secs(),
+                action: "remove".to_string(),
+                base: rate.base.clone(),
+                quote: rate.quote.clone(),
+                previous_rate: Some(rate.rate),
+                rate: None,
+            })?;
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    fn update<T>(&self, change: impl FnOnce(&mut RateTable) -> T) -> std::io::Result<T> {
+        let mut table = self
+            .table
+            .write()
+            .map_err(|_| std::io::Error::other("rate table lock poisoned"))?;
+        Ok(change(&mut table))
+    }
+
+    fn audit(&self, entry: RateAuditEntry) -> std::io::Result<()> {
+        tracing::info!(
+            "Rate {}/{} {}: {:?} -> {:?}",
+            entry.base,
+            entry.quote,
+            entry.action,
+            entry.previous_rate,
+            entry.rate
+        );
+        if let Some(parent) = self.audit_log_path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_vec(&entry)?;
+        line.push(b'\n');
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.audit_log_path)?;
+        file.write_all(&line)?;
+        file.sync_data()
+    }
+
+    fn save(&self) -> std::io::Result<()> {
+        let Some(path) = &self.state_file else {
+            return Ok(());
+        };
+        let table = self
+            .table
+            .read()
+            .map_err(|_| std::io::Error::other("rate table lock poisoned"))?
+            .clone();
+        let json = serde_json::to_vec_pretty(&table).map_err(std::io::Error::other)?;
+        write_file_atomically(path, &json)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate(base: &str, quote: &str, rate: f64, updated_at: u64, source: &str) -> (String, ManualRate) {
+        let manual = ManualRate {
+            base: base.to_string(),
+            quote: quote.to_string(),
+            rate,
+            updated_at,
+            source: source.to_string(),
+        };
+        (pair(base, quote), manual)
+    }
+
+    #[test]
+    fn test_convert_chains_rates_both_ways() {
+        let table = RateTable {
+            rates: [
+                rate("ERG", "SIGUSD", 2.0, 100, "config"),
+                rate("EUR", "SIGUSD", 1.25, 200, "admin"),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        assert_eq!(table.convert("erg", "SigUSD", 300, 0), Some((2.0, 100)));
+        assert_eq!(table.convert("ERG", "EUR", 300, 0), Some((1.6, 100)));
+        assert_eq!(table.convert("EUR", "EUR", 300, 0), Some((1.0, u64::MAX)));
+        assert_eq!(table.convert("ERG", "JPY", 300, 0), None);
+
+        // Stale admin rates are skipped, configured rates never are
+        assert_eq!(table.convert("ERG", "EUR", 300, 50), None);
+        assert_eq!(table.convert("ERG", "SIGUSD", 10_000, 50), Some((2.0, 100)));
+    }
+
+    #[test]
+    fn test_invalid_rates() {
+        assert!(check_rate("ERG", "USD", 1.5).is_ok());
+        assert!(check_rate("ERG", "erg", 1.5).is_err());
+        assert!(check_rate("", "USD", 1.5).is_err());
+        assert!(check_rate("ERG", "USD", 0.0).is_err());
+        assert!(check_rate("ERG", "USD", f64::NAN).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_admin_changes_are_audited_and_saved() {
+        let dir = std::env::temp_dir().join(format!("basis_pricing_test_{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let config = PricingConfig {
+            quote_currency: Some("usd".to_string()),
+            rates: vec![RateConfig {
+                base: "ERG".to_string(),
+                quote: "USD".to_string(),
+                rate: 1.0,
+            }],
+            state_file: Some(dir.join("rates.json").to_string_lossy().into_owned()),
+            audit_log_path: dir.join("rate_audit.jsonl"),
+            ..PricingConfig::default()
+        };
+        let pricing = Pricing::from_config(&config).unwrap();
+        let valuation = pricing.valuation(3_000_000_000, 1_500_000_000).unwrap();
+        assert_eq!((valuation.currency.as_str(), valuation.collateral_value, valuation.debt_value), ("USD", 3.0, 1.5));
+
+        // The inverse pair replaces the configured rate
+        pricing.set_rate("USD", "ERG", 0.5).await.unwrap();
+        assert_eq!(pricing.rates().len(), 1);
+        assert_eq!(pricing.valuation(1_000_000_000, 0).unwrap().collateral_value, 2.0);
+        assert!(pricing.remove_rate("ERG", "USD").await.unwrap().is_some());
+        assert!(pricing.valuation(1_000_000_000, 0).is_none());
+
+        let audit = std::fs::read_to_string(dir.join("rate_audit.jsonl")).unwrap();
+        let entries: Vec<RateAuditEntry> = audit.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(entries.len(), 2);
+        assert_eq!((entries[0].previous_rate, entries[0].rate), (Some(1.0), Some(0.5)));
+        assert_eq!((entries[1].action.as_str(), entries[1].rate), ("remove", None));
+
+        // Saved rates replace the configured ones on the next start
+        assert!(Pricing::from_config(&config).unwrap().rates().is_empty());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
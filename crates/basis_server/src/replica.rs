@@ -0,0 +1,404 @@
+//! Replica mode: a read-only mirror of another tracker
+//!
+//! A replica follows a primary tracker through its signed commitment deltas.
+//! For each commitment the primary found consistent with its notes, the
+//! replica fetches the delta leading to it, verifies the primary's signature
+//! and every issuer signature, applies the notes and checks that its own AVL
+//! root ends at the committed digest. Queries are answered from the
+//! replica's copy of the notes; requests that would change state are refused.
+//!
+//! A delta that fails verification, does not start at the replica's root or
+//! leads to another root is a divergence. It is recorded as a
+//! `ReplicaDiverged` event and syncing stops until the replica is restarted.
+
+use crate::models::{ApiResponse, EventType, TrackerEvent};
+use crate::{requests, AppState};
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use basis_store::reqwest;
+use basis_store::{CommitmentCheck, CommitmentStatus, IouNote, PubKey};
+use basis_trees::CommitmentDelta;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Primary tracker a replica follows
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ReplicaConfig {
+    /// Base URL of the primary; the server runs as its replica when set
+    #[serde(default)]
+    pub primary_url: Option<String>,
+    /// Seconds between polls of the primary
+    #[serde(default = "default_sync_interval_secs")]
+    pub sync_interval_secs: u64,
+    /// Seconds to wait for each answer from the primary
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+fn default_sync_interval_secs() -> u64 {
+    30
+}
+
+fn default_request_timeout_secs() -> u64 {
+    10
+}
+
+impl Default for ReplicaConfig {
+    fn default() -> Self {
+        Self {
+            primary_url: None,
+            sync_interval_secs: default_sync_interval_secs(),
+            request_timeout_secs: default_request_timeout_secs(),
+        }
+    }
+}
+
+impl ReplicaConfig {
+    /// Whether the server runs as a replica
+    pub fn is_enabled(&self) -> bool {
+        self.primary_url.is_some()
+    }
+}
+
+/// POST endpoints a replica still serves, as they only read state
+pub const REPLICA_READ_ONLY_POSTS: &[&str] = &["/notes/validate", "/redeem/validate", "/acceptance/check"];
+
+/// Whether the request is refused under the given configuration
+pub fn disabled_in_replica_mode(replica: &ReplicaConfig, method: &Method, path: &str) -> bool {
+    replica.is_enabled() && *method == Method::POST && !REPLICA_READ_ONLY_POSTS.contains(&path)
+}
+
+/// Middleware refusing state-changing requests when running as a replica
+pub async fn replica_layer(
+    State(replica): State<Arc<ReplicaConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !disabled_in_replica_mode(&replica, request.method(), request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    tracing::debug!(
+        "Refused {} {} in replica mode",
+        request.method(),
+        request.uri().path()
+    );
+    (
+        StatusCode::FORBIDDEN,
+        Json(crate::models::error_response::<()>(
+            "Endpoint is disabled: this server is a read-only replica".to_string(),
+        )),
+    )
+        .into_response()
+}
+
+/// How far a replica has followed its primary
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicaStatus {
+    /// Primary being followed; none when the server is not a replica
+    pub primary_url: Option<String>,
+    /// Height of the last commitment the local state reached
+    pub synced_height: Option<u64>,
+    /// Root digest committed at `synced_height` (hex)
+    pub synced_digest: Option<String>,
+    /// Consistent commitments of the primary not applied yet
+    pub pending_commitments: usize,
+    /// When the primary was last polled successfully (Unix seconds)
+    pub last_sync_at: Option<u64>,
+    /// Why the last poll failed, if it did
+    pub last_error: Option<String>,
+    /// Divergence that stopped syncing
+    pub divergence: Option<ReplicaDivergence>,
+}
+
+/// A commitment of the primary the replica could not reach
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplicaDivergence {
+    /// Height of the commitment
+    pub height: u64,
+    /// What did not match
+    pub reason: String,
+    /// When it was detected (Unix seconds)
+    pub detected_at: u64,
+}
+
+/// Replica status, shared between the sync task and request handlers
+#[derive(Debug, Clone, Default)]
+pub struct ReplicaState(Arc<RwLock<ReplicaStatus>>);
+
+impl ReplicaState {
+    pub fn status(&self) -> ReplicaStatus {
+        self.0.read().map(|status| status.clone()).unwrap_or_default()
+    }
+
+    fn update(&self, change: impl FnOnce(&mut ReplicaStatus)) {
+        if let Ok(mut status) = self.0.write() {
+            change(&mut status);
+        }
+    }
+}
+
+/// Why a sync round stopped
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SyncError {
+    /// The primary could not be reached or did not answer; retried next round
+    #[error("primary unavailable: {0}")]
+    Unavailable(String),
+    /// The local tracker failed to store the notes; retried next round
+    #[error("failed to apply notes: {0}")]
+    Apply(String),
+    /// The primary's data contradicts itself or the replica; syncing stops
+    #[error("diverged at height {height}: {reason}")]
+    Diverged { height: u64, reason: String },
+}
+
+/// Client for the read endpoints of the primary
+#[derive(Debug, Clone)]
+pub struct PrimaryClient {
+    client: reqwest::Client,
+    base_url: String,
+}
+
+impl PrimaryClient {
+    pub fn new(primary_url: &str, timeout: Duration) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()
+            .unwrap_or_default();
+        Self {
+            client,
+            base_url: primary_url.trim_end_matches('/').to_string(),
+        }
+    }
+
+    async fn get<T: DeserializeOwned>(&self, path: &str) -> Result<T, SyncError> {
+        let url = format!("{}{}", self.base_url, path);
+        let unavailable = |e: reqwest::Error| SyncError::Unavailable(format!("{}: {}", url, e));
+        let response = self.client.get(&url).send().await.map_err(unavailable)?;
+        let status = response.status();
+        let body: ApiResponse<T> = response.json().await.map_err(unavailable)?;
+        body.data.ok_or_else(|| {
+            SyncError::Unavailable(format!("{} answered {}: {}", url, status, body.error.unwrap_or_default()))
+        })
+    }
+
+    /// Commitment checks of the primary, ordered by height
+    pub async fn commitments(&self) -> Result<Vec<CommitmentCheck>, SyncError> {
+        self.get("/commitments").await
+    }
+
+    /// Signed delta leading to the commitment at `height`
+    pub async fn delta(&self, height: u64) -> Result<CommitmentDelta, SyncError> {
+        self.get(&format!("/commitments/{}/delta", height)).await
+    }
+}
+
+/// Check a delta against the commitment it should lead to and the local root
+///
+/// Returns the notes to apply, or why the delta cannot be applied.
+pub fn check_delta(
+    delta: &CommitmentDelta,
+    commitment: &CommitmentCheck,
+    tracker_pubkey: &str,
+    local_root: &str,
+) -> Result<Vec<(PubKey, IouNote)>, String> {
+    delta.verify().map_err(|e| e.to_string())?;
+    if delta.tracker_pubkey != tracker_pubkey {
+        return Err(format!(
+            "delta signed by {} instead of the configured tracker {}",
+            delta.tracker_pubkey, tracker_pubkey
+        ));
+    }
+    if delta.height != commitment.height || delta.digest != commitment.onchain_digest {
+        return Err(format!(
+            "delta leads to {} at height {} instead of the committed {}",
+            delta.digest, delta.height, commitment.onchain_digest
+        ));
+    }
+    // Without a previous commitment the delta holds every note, and the root
+    // check after applying it catches any other notes held locally
+    if let Some(previous_digest) = &delta.previous_digest {
+        if previous_digest != local_root {
+            return Err(format!(
+                "delta starts at {} but the local root is {}",
+                previous_digest, local_root
+            ));
+        }
+    }
+
+    delta
+        .notes
+        .iter()
+        .map(|note| basis_store::note_from_delta(note).map_err(|e| e.to_string()))
+        .collect()
+}
+
+/// Apply the commitments the primary made past the local root
+///
+/// Only commitments the primary found consistent are followed. Returns the
+/// number applied.
+pub async fn sync_once(state: &AppState, client: &PrimaryClient, tracker_pubkey: &str) -> Result<usize, SyncError> {
+    let commitments: Vec<CommitmentCheck> = client
+        .commitments()
+        .await?
+        .into_iter()
+        .filter(|check| check.status == CommitmentStatus::Consistent)
+        .collect();
+    let mut local_root = hex::encode(state.shared_tracker_state.lock().await.get_avl_root_digest());
+    let start = commitments
+        .iter()
+        .rposition(|check| check.onchain_digest == local_root)
+        .map_or(0, |position| position + 1);
+    if let Some(reached) = start.checked_sub(1).map(|position| &commitments[position]) {
+        state.replica.update(|status| {
+            status.synced_height = Some(reached.height);
+            status.synced_digest = Some(reached.onchain_digest.clone());
+        });
+    }
+
+    let pending = &commitments[start..];
+    for (applied, commitment) in pending.iter().enumerate() {
+        state.replica.update(|status| status.pending_commitments = pending.len() - applied);
+        let diverged = |reason: String| SyncError::Diverged {
+            height: commitment.height,
+            reason,
+        };
+
+        let delta = client.delta(commitment.height).await?;
+        let notes = check_delta(&delta, commitment, tracker_pubkey, &local_root).map_err(diverged)?;
+
+        let root = state
+            .send(requests::ApplyReplicatedNotes { notes })
+            .await
+            .map_err(|e| SyncError::Apply(e.to_string()))?
+            .map_err(|e| SyncError::Apply(format!("{:?}", e)))?;
+
+        local_root = hex::encode(root);
+        if local_root != delta.digest {
+            return Err(diverged(format!(
+                "applying the delta led to root {} instead of the committed {}",
+                local_root, delta.digest
+            )));
+        }
+        state.replica.update(|status| {
+            status.synced_height = Some(commitment.height);
+            status.synced_digest = Some(delta.digest.clone());
+        });
+    }
+
+    state.replica.update(|status| status.pending_commitments = 0);
+    Ok(pending.len())
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Poll the primary and apply its commitments until shutdown or a divergence
+pub async fn replica_sync_task(
+    state: AppState,
+    config: ReplicaConfig,
+    tracker_pubkey: String,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let Some(primary_url) = config.primary_url.clone() else {
+        return;
+    };
+    let client = PrimaryClient::new(&primary_url, Duration::from_secs(config.request_timeout_secs));
+    state.replica.update(|status| status.primary_url = Some(primary_url.clone()));
+    let mut ticker = tokio::time::interval(Duration::from_secs(config.sync_interval_secs));
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            _ = ticker.tick() => {}
+        }
+
+        match sync_once(&state, &client, &tracker_pubkey).await {
+            Ok(applied) => {
+                if applied > 0 {
+                    tracing::info!("Applied {} commitment(s) from primary {}", applied, primary_url);
+                }
+                state.replica.update(|status| {
+                    status.last_sync_at = Some(unix_now());
+                    status.last_error = None;
+                });
+            }
+            Err(SyncError::Diverged { height, reason }) => {
+                tracing::error!(
+                    "Replica diverged from primary {} at height {}: {}; syncing stopped",
+                    primary_url,
+                    height,
+                    reason
+                );
+                let detected_at = unix_now();
+                state.replica.update(|status| {
+                    status.divergence = Some(ReplicaDivergence {
+                        height,
+                        reason,
+                        detected_at,
+                    })
+                });
+                let event = TrackerEvent {
+                    id: 0,
+                    seq: 0,
+                    event_type: EventType::ReplicaDiverged,
+                    timestamp: detected_at,
+                    issuer_pubkey: None,
+                    recipient_pubkey: None,
+                    amount: None,
+                    reserve_box_id: None,
+                    collateral_amount: None,
+                    redeemed_amount: None,
+                    height: Some(height),
+                };
+                if let Err(e) = state.event_store.add_events(vec![event]).await {
+                    tracing::warn!("Failed to record replica divergence event: {:?}", e);
+                }
+                break;
+            }
+            Err(e) => {
+                tracing::warn!("Replica sync with {} failed: {}", primary_url, e);
+                state.replica.update(|status| status.last_error = Some(e.to_string()));
+            }
+        }
+    }
+}
+
+/// How far this server has followed its primary - GET /replica/status
+#[axum::debug_handler]
+pub async fn get_replica_status(State(state): State<AppState>) -> (StatusCode, Json<ApiResponse<ReplicaStatus>>) {
+    (StatusCode::OK, Json(crate::models::success_response(state.replica.status())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_replica_refuses_state_changes() {
+        let primary = ReplicaConfig {
+            primary_url: None,
+            ..ReplicaConfig::default()
+        };
+        assert!(!disabled_in_replica_mode(&primary, &Method::POST, "/notes"));
+
+        let replica = ReplicaConfig {
+            primary_url: Some("http://primary:3048".to_string()),
+            ..ReplicaConfig::default()
+        };
+        assert!(disabled_in_replica_mode(&replica, &Method::POST, "/notes"));
+        assert!(disabled_in_replica_mode(&replica, &Method::POST, "/admin/rebuild-avl"));
+        assert!(!disabled_in_replica_mode(&replica, &Method::POST, "/notes/validate"));
+        assert!(!disabled_in_replica_mode(&replica, &Method::GET, "/notes"));
+    }
+}
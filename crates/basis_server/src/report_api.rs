@@ -0,0 +1,361 @@
+//! API handlers exporting notes and reserves as CSV or JSON reports
+//!
+//! Reports are streamed: notes are read from storage a page at a time and
+//! each page is written out before the next is read, so an export of the
+//! whole tracker never has to fit in memory. Ledger journals cover the notes
+//! of a single account and are rendered in one piece.
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tokio_stream::wrappers::ReceiverStream;
+
+use basis_store::{
+    ledger::{self, LedgerFormat},
+    note_search::{NoteCursor, NoteQuery},
+    reports::{self, NoteReportRow, ReserveReportRow},
+    IouNote, PubKey,
+};
+
+use crate::{
+    models::{error_response, SerializableIouNoteWithAge},
+    requests,
+    AppState,
+};
+
+/// Notes read from storage per tracker request while streaming a report
+const REPORT_PAGE_SIZE: usize = 500;
+
+/// Chunks buffered between the storage reads and the client
+const REPORT_CHANNEL_CAPACITY: usize = 4;
+
+type ReportChunk = Result<String, std::io::Error>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ReportFormat {
+    Csv,
+    Json,
+}
+
+fn decode<const N: usize>(value: &str, field: &str) -> Result<[u8; N], String> {
+    hex::decode(value)
+        .map_err(|_| format!("{} must be hex-encoded", field))?
+        .try_into()
+        .map_err(|_| format!("{} must be {} bytes", field, N))
+}
+
+/// Filters shared by every report
+struct ReportFilter {
+    issuer: Option<PubKey>,
+    from_timestamp: Option<u64>,
+    to_timestamp: Option<u64>,
+}
+
+impl ReportFilter {
+    fn from_params(params: &HashMap<String, String>) -> Result<Self, String> {
+        let number = |name: &str| -> Result<Option<u64>, String> {
+            params
+                .get(name)
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| format!("{} must be a non-negative integer", name))
+                })
+                .transpose()
+        };
+        let issuer = params.get("issuer").map(|value| decode(value, "issuer")).transpose()?;
+        Ok(Self {
+            issuer,
+            from_timestamp: number("from_timestamp")?,
+            to_timestamp: number("to_timestamp")?,
+        })
+    }
+
+    fn timestamp_range(&self) -> std::ops::RangeInclusive<u64> {
+        self.from_timestamp.unwrap_or(0)..=self.to_timestamp.unwrap_or(u64::MAX)
+    }
+}
+
+/// Writes report rows into the response body as they are produced
+struct ReportWriter {
+    format: ReportFormat,
+    rows: usize,
+    tx: mpsc::Sender<ReportChunk>,
+}
+
+impl ReportWriter {
+    /// Start a report, returning the writer and the response streaming it
+    fn start(format: ReportFormat, name: &str, csv_header: &str) -> (Self, Response) {
+        let (tx, rx) = mpsc::channel(REPORT_CHANNEL_CAPACITY);
+        let (content_type, opening, extension) = match format {
+            ReportFormat::Csv => ("text/csv; charset=utf-8", csv_header.to_string(), "csv"),
+            ReportFormat::Json => ("application/json", "[".to_string(), "json"),
+        };
+        // Capacity is free at this point, so the opening chunk is always queued
+        let _ = tx.try_send(Ok(opening));
+
+        let response = (
+            [
+                (header::CONTENT_TYPE, content_type.to_string()),
+                (
+                    header::CONTENT_DISPOSITION,
+                    format!("attachment; filename=\"{}.{}\"", name, extension),
+                ),
+            ],
+            Body::from_stream(ReceiverStream::new(rx)),
+        )
+            .into_response();
+        (Self { format, rows: 0, tx }, response)
+    }
+
+    /// Write a row; false once the client has gone away
+    async fn row<T: serde::Serialize>(&mut self, csv: String, json: &T) -> bool {
+        let chunk = match self.format {
+            ReportFormat::Csv => csv,
+            ReportFormat::Json => {
+                let separator = if self.rows == 0 { "\n" } else { ",\n" };
+                match serde_json::to_string(json) {
+                    Ok(json) => format!("{}{}", separator, json),
+                    Err(e) => return self.fail(format!("Failed to serialize report row: {}", e)).await,
+                }
+            }
+        };
+        self.rows += 1;
+        self.tx.send(Ok(chunk)).await.is_ok()
+    }
+
+    /// Finish the report
+    async fn finish(self) {
+        if self.format == ReportFormat::Json {
+            let closing = if self.rows == 0 { "]\n" } else { "\n]\n" };
+            let _ = self.tx.send(Ok(closing.to_string())).await;
+        }
+    }
+
+    /// Abort the report; the client sees a truncated body rather than a complete one
+    async fn fail(&self, message: String) -> bool {
+        tracing::error!("Report aborted: {}", message);
+        let _ = self.tx.send(Err(std::io::Error::other(message))).await;
+        false
+    }
+}
+
+/// Stream every note matching `query` into `writer`, oldest first, as the
+/// CSV line and JSON row `to_row` makes of it
+async fn stream_notes<R, F>(state: AppState, query: NoteQuery, mut writer: ReportWriter, to_row: F)
+where
+    R: serde::Serialize,
+    F: Fn(&PubKey, &IouNote) -> (String, R),
+{
+    let mut cursor: Option<NoteCursor> = None;
+    loop {
+        let request = requests::ScanNotes {
+            query: query.clone(),
+            after: cursor.clone(),
+            limit: REPORT_PAGE_SIZE,
+        };
+        let page = match state.send(request).await {
+            Ok(Ok(page)) => page,
+            Ok(Err(e)) => {
+                writer.fail(format!("Failed to read notes: {:?}", e)).await;
+                return;
+            }
+            Err(e) => {
+                writer.fail(e.to_string()).await;
+                return;
+            }
+        };
+
+        for (issuer_pubkey, note) in &page {
+            let (csv, row) = to_row(issuer_pubkey, note);
+            if !writer.row(csv, &row).await {
+                return;
+            }
+        }
+        match page.last() {
+            Some((issuer_pubkey, note)) if page.len() == REPORT_PAGE_SIZE => {
+                cursor = Some(NoteCursor::after(issuer_pubkey, note));
+            }
+            _ => break,
+        }
+    }
+    writer.finish().await;
+}
+
+fn note_query(filter: &ReportFilter) -> NoteQuery {
+    NoteQuery {
+        issuer: filter.issuer,
+        from_timestamp: filter.from_timestamp,
+        to_timestamp: filter.to_timestamp,
+        ..NoteQuery::default()
+    }
+}
+
+async fn notes_report(state: AppState, params: HashMap<String, String>, format: ReportFormat) -> Response {
+    tracing::debug!("Exporting notes report ({:?}) with params: {:?}", format, params);
+
+    let filter = match ReportFilter::from_params(&params) {
+        Ok(filter) => filter,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response::<()>(message))).into_response(),
+    };
+
+    let (writer, response) = ReportWriter::start(format, "notes", NoteReportRow::CSV_HEADER);
+    tokio::spawn(stream_notes(state, note_query(&filter), writer, |issuer_pubkey, note| {
+        let row = NoteReportRow::new(issuer_pubkey, note);
+        (row.to_csv(), row)
+    }));
+    response
+}
+
+async fn reserves_report(state: AppState, params: HashMap<String, String>, format: ReportFormat) -> Response {
+    tracing::debug!("Exporting reserves report ({:?}) with params: {:?}", format, params);
+
+    let filter = match ReportFilter::from_params(&params) {
+        Ok(filter) => filter,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response::<()>(message))).into_response(),
+    };
+    let reserves = reports::report_reserves(&state.reserve_tracker, filter.issuer.as_ref(), filter.timestamp_range());
+
+    let (mut writer, response) = ReportWriter::start(format, "reserves", ReserveReportRow::CSV_HEADER);
+    tokio::spawn(async move {
+        for reserve in &reserves {
+            let row = ReserveReportRow::new(reserve);
+            if !writer.row(row.to_csv(), &row).await {
+                return;
+            }
+        }
+        writer.finish().await;
+    });
+    response
+}
+
+/// Export notes as CSV, oldest first
+/// Query: issuer (hex public key), from_timestamp, to_timestamp (milliseconds, inclusive)
+pub async fn get_notes_report_csv(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Response {
+    notes_report(state, params, ReportFormat::Csv).await
+}
+
+/// Export notes as a JSON array, oldest first; same filters as the CSV report
+pub async fn get_notes_report_json(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Response {
+    notes_report(state, params, ReportFormat::Json).await
+}
+
+/// Export reserves as CSV, ordered by last update
+/// Query: issuer (hex owner public key), from_timestamp, to_timestamp (last update, milliseconds, inclusive)
+pub async fn get_reserves_report_csv(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Response {
+    reserves_report(state, params, ReportFormat::Csv).await
+}
+
+/// Export reserves as a JSON array, ordered by last update; same filters as the CSV report
+pub async fn get_reserves_report_json(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Response {
+    reserves_report(state, params, ReportFormat::Json).await
+}
+
+/// Export every note with its age as a JSON array, oldest first
+///
+/// Unlike GET /notes, which answers with all notes in one response, the
+/// export is streamed a page at a time, so it works for any number of notes.
+/// Query: the filters of the notes report
+pub async fn get_notes_export(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Response {
+    tracing::debug!("Exporting notes with params: {:?}", params);
+
+    let filter = match ReportFilter::from_params(&params) {
+        Ok(filter) => filter,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response::<()>(message))).into_response(),
+    };
+    let current_time_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+
+    let (writer, response) = ReportWriter::start(ReportFormat::Json, "notes_export", "");
+    tokio::spawn(stream_notes(state, note_query(&filter), writer, move |issuer_pubkey, note| {
+        // The export is JSON only, so its rows have no CSV line
+        (String::new(), SerializableIouNoteWithAge::new(issuer_pubkey, note, current_time_ms))
+    }));
+    response
+}
+
+async fn ledger_report(state: AppState, params: HashMap<String, String>, format: LedgerFormat) -> Response {
+    tracing::debug!("Exporting ledger journal ({:?}) with params: {:?}", format, params);
+
+    let parsed = ReportFilter::from_params(&params).and_then(|filter| {
+        let account = params
+            .get("account")
+            .ok_or_else(|| "account is required".to_string())
+            .and_then(|value| decode::<33>(value, "account"))?;
+        Ok((account, filter))
+    });
+    let (account, filter) = match parsed {
+        Ok(parsed) => parsed,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response::<()>(message))).into_response(),
+    };
+
+    let entries = match state.send(requests::GetAccountJournal { account }).await {
+        Ok(Ok(entries)) => entries,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to build ledger journal: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response::<()>("Failed to build ledger journal".to_string())),
+            )
+                .into_response();
+        }
+        Err(e) => return e.response::<()>().into_response(),
+    };
+
+    let range = filter.timestamp_range();
+    let entries: Vec<_> = entries
+        .into_iter()
+        .filter(|entry| range.contains(&entry.timestamp))
+        .collect();
+    (
+        [
+            (header::CONTENT_TYPE, "text/plain; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"ledger.{}\"", format.extension()),
+            ),
+        ],
+        ledger::render_journal(format, &entries),
+    )
+        .into_response()
+}
+
+/// Export an account's note history as an hledger journal, oldest first
+/// Query: account (hex public key, required), from_timestamp, to_timestamp (milliseconds, inclusive)
+pub async fn get_ledger_report_hledger(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Response {
+    ledger_report(state, params, LedgerFormat::Hledger).await
+}
+
+/// Export an account's note history as a Beancount ledger; same query as the hledger journal
+pub async fn get_ledger_report_beancount(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> Response {
+    ledger_report(state, params, LedgerFormat::Beancount).await
+}
@@ -0,0 +1,725 @@
+//! Audit trail of state-changing requests
+//!
+//! Every request other than GET, HEAD and OPTIONS is recorded once its
+//! response is ready: when it arrived, the client address and user agent,
+//! which credential it presented, a hash of its body and the response status.
+//! Rejected requests are recorded too, so failed authentication attempts can
+//! be investigated. Entries are appended as JSON lines to one file per UTC
+//! day, `YYYY-MM-DD.jsonl` in `request_audit.path`, apart from the event store
+//! and the signing audit log, and are read back through
+//! `GET /admin/request-audit`. Files of days older than `retention_days` are
+//! deleted at startup and then daily.
+//!
+//! A dedicated thread owns the open file, so requests only queue their entry
+//! and never wait for the disk.
+//!
+//! Credentials themselves are never written: an API key is recorded as a
+//! short hash of it, enough to tell keys apart.
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use axum::{
+    body::{to_bytes, Body},
+    extract::{ConnectInfo, Query, Request, State},
+    http::{header, HeaderMap, Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use basis_store::reports::{format_utc, parse_date};
+use blake2::{Blake2b, Digest};
+use generic_array::typenum::U32;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    auth::{ADMIN_TOKEN_HEADER, API_KEY_HEADER},
+    config::StorageConfig,
+    models::{error_response, success_response, ApiResponse, RequestAuditResponse},
+    util::now_secs,
+    AppState,
+};
+
+/// `[request_audit]` section of the server configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RequestAuditConfig {
+    /// Record state-changing requests
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Directory of the daily log files; `request_audit` next to
+    /// `storage.signing_audit_log_path` when unset
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+    /// Days entries are kept; 0 keeps every entry
+    #[serde(default = "default_retention_days")]
+    pub retention_days: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_retention_days() -> u64 {
+    90
+}
+
+impl Default for RequestAuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            path: None,
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+impl RequestAuditConfig {
+    /// Directory the log files are written to
+    pub fn dir(&self, storage: &StorageConfig) -> PathBuf {
+        self.path.clone().unwrap_or_else(|| {
+            storage
+                .signing_audit_log_path
+                .parent()
+                .unwrap_or(Path::new(""))
+                .join("request_audit")
+        })
+    }
+}
+
+/// One line of the request audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RequestAuditEntry {
+    /// When the request arrived, in seconds since Unix epoch
+    pub timestamp: u64,
+    /// Correlation ID of the request (see [`crate::request_id`])
+    pub request_id: Option<String>,
+    pub method: String,
+    pub path: String,
+    pub query: Option<String>,
+    /// Address of the connecting peer
+    pub client_ip: Option<String>,
+    /// `X-Forwarded-For` as sent; unverified, since any client can set it
+    pub forwarded_for: Option<String>,
+    pub user_agent: Option<String>,
+    /// `api-key:` followed by the first 8 bytes of the key's blake2b256 hash
+    /// in hex, or `admin-token`; whether it was accepted shows in `status`
+    pub credential: Option<String>,
+    /// Hex blake2b256 hash of the request body
+    pub request_hash: String,
+    /// Status of the response
+    pub status: u16,
+    pub duration_ms: u64,
+}
+
+/// Filters of a request audit query
+///
+/// Unset filters match every entry. Results are ordered oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RequestAuditQuery {
+    /// Earliest timestamp, inclusive
+    pub from: Option<u64>,
+    /// Latest timestamp, inclusive
+    pub to: Option<u64>,
+    pub client_ip: Option<String>,
+    /// Path prefix, e.g. `/notes`
+    pub path: Option<String>,
+    pub method: Option<String>,
+    pub status: Option<u16>,
+    pub credential: Option<String>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl RequestAuditQuery {
+    fn matches(&self, entry: &RequestAuditEntry) -> bool {
+        self.from.is_none_or(|from| entry.timestamp >= from)
+            && self.to.is_none_or(|to| entry.timestamp <= to)
+            && self
+                .client_ip
+                .as_deref()
+                .is_none_or(|ip| entry.client_ip.as_deref() == Some(ip))
+            && self.path.as_deref().is_none_or(|path| entry.path.starts_with(path))
+            && self
+                .method
+                .as_deref()
+                .is_none_or(|method| entry.method.eq_ignore_ascii_case(method))
+            && self.status.is_none_or(|status| entry.status == status)
+            && self
+                .credential
+                .as_deref()
+                .is_none_or(|credential| entry.credential.as_deref() == Some(credential))
+    }
+
+    // Whether the file of the day starting at `day_start` can hold matches
+    fn covers_day(&self, day_start: u64) -> bool {
+        self.from.is_none_or(|from| day_start + DAY_SECS > from) && self.to.is_none_or(|to| day_start <= to)
+    }
+}
+
+/// Entries queued for the writer thread before appends wait
+const WRITER_QUEUE_CAPACITY: usize = 1024;
+
+enum WriterCommand {
+    Append { timestamp: u64, line: Vec<u8> },
+    /// Flush the queued entries, reporting the first write error since the last sync
+    Sync(oneshot::Sender<std::io::Result<()>>),
+    /// Delete the files of days ending at or before the given time
+    Prune(u64, oneshot::Sender<std::io::Result<usize>>),
+}
+
+/// Append-only JSON lines log of state-changing requests, one file per day
+///
+/// The default log has no directory: nothing is recorded and queries find
+/// nothing. The writer thread stops once every clone of the log is dropped.
+#[derive(Debug, Clone, Default)]
+pub struct RequestAuditLog {
+    dir: Option<PathBuf>,
+    writer: Option<mpsc::Sender<WriterCommand>>,
+}
+
+impl RequestAuditLog {
+    /// Open the log in `dir`, starting its writer thread
+    pub fn open(dir: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let dir = dir.into();
+        let (tx, rx) = mpsc::channel(WRITER_QUEUE_CAPACITY);
+        let writer = AuditWriter {
+            dir: dir.clone(),
+            file: None,
+            error: None,
+        };
+        std::thread::Builder::new()
+            .name("request-audit".to_string())
+            .spawn(move || writer.run(rx))?;
+        Ok(Self {
+            dir: Some(dir),
+            writer: Some(tx),
+        })
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.writer.is_some()
+    }
+
+    /// Queue an entry for the writer thread
+    ///
+    /// Write errors are logged by the writer and reported by the next query.
+    pub async fn append(&self, entry: &RequestAuditEntry) -> std::io::Result<()> {
+        let Some(writer) = &self.writer else {
+            return Ok(());
+        };
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        writer
+            .send(WriterCommand::Append {
+                timestamp: entry.timestamp,
+                line,
+            })
+            .await
+            .map_err(|_| writer_stopped())
+    }
+
+    /// Entries matching `query`, oldest first
+    ///
+    /// Files are read line by line, skipping days outside `from` and `to`,
+    /// and reading stops once `limit` entries are found.
+    pub async fn query(&self, query: &RequestAuditQuery) -> std::io::Result<Vec<RequestAuditEntry>> {
+        let (Some(dir), Some(writer)) = (self.dir.clone(), &self.writer) else {
+            return Ok(Vec::new());
+        };
+        // Entries queued before the query are on disk before it reads
+        let (reply_tx, reply_rx) = oneshot::channel();
+        writer.send(WriterCommand::Sync(reply_tx)).await.map_err(|_| writer_stopped())?;
+        reply_rx.await.map_err(|_| writer_stopped())??;
+
+        let query = query.clone();
+        tokio::task::spawn_blocking(move || read_entries(&dir, &query))
+            .await
+            .map_err(std::io::Error::other)?
+    }
+
+    /// Delete the files of days that ended at or before `before` seconds,
+    /// returning how many were deleted
+    pub async fn prune(&self, before: u64) -> std::io::Result<usize> {
+        let Some(writer) = &self.writer else {
+            return Ok(0);
+        };
+        let (reply_tx, reply_rx) = oneshot::channel();
+        writer
+            .send(WriterCommand::Prune(before, reply_tx))
+            .await
+            .map_err(|_| writer_stopped())?;
+        reply_rx.await.map_err(|_| writer_stopped())?
+    }
+}
+
+fn writer_stopped() -> std::io::Error {
+    std::io::Error::other("request audit writer stopped")
+}
+
+// Start of the UTC day holding `timestamp`
+fn day_start(timestamp: u64) -> u64 {
+    timestamp - timestamp % DAY_SECS
+}
+
+fn day_file_name(day_start: u64) -> String {
+    format!("{}.jsonl", &format_utc(day_start * 1000)[..10])
+}
+
+// Day files in `dir` by the start of their day, oldest first
+fn day_files(dir: &Path) -> std::io::Result<Vec<(u64, PathBuf)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut files = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        let day = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_suffix(".jsonl"))
+            .and_then(parse_date);
+        if let Some(day_millis) = day {
+            files.push((day_millis / 1000, path));
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn read_entries(dir: &Path, query: &RequestAuditQuery) -> std::io::Result<Vec<RequestAuditEntry>> {
+    let mut entries = Vec::new();
+    let mut skipped = 0;
+    if query.limit == 0 {
+        return Ok(entries);
+    }
+    for (day, path) in day_files(dir)? {
+        if !query.covers_day(day) {
+            continue;
+        }
+        let mut reader = BufReader::new(File::open(&path)?);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            // A line without its newline is still being written
+            if reader.read_line(&mut line)? == 0 || !line.ends_with('\n') {
+                break;
+            }
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: RequestAuditEntry = serde_json::from_str(&line).map_err(std::io::Error::other)?;
+            if !query.matches(&entry) {
+                continue;
+            }
+            if skipped < query.offset {
+                skipped += 1;
+                continue;
+            }
+            entries.push(entry);
+            if entries.len() == query.limit {
+                return Ok(entries);
+            }
+        }
+    }
+    Ok(entries)
+}
+
+// Owns the open day file on the writer thread
+struct AuditWriter {
+    dir: PathBuf,
+    file: Option<(u64, BufWriter<File>)>,
+    // First write error since the last sync
+    error: Option<std::io::Error>,
+}
+
+impl AuditWriter {
+    fn run(mut self, mut rx: mpsc::Receiver<WriterCommand>) {
+        while let Some(command) = rx.blocking_recv() {
+            self.handle(command);
+            // Entries queued meanwhile share one flush
+            while let Ok(command) = rx.try_recv() {
+                self.handle(command);
+            }
+            let flushed = self.flush();
+            self.record(flushed);
+        }
+    }
+
+    fn handle(&mut self, command: WriterCommand) {
+        match command {
+            WriterCommand::Append { timestamp, line } => {
+                let written = self.write(timestamp, &line);
+                self.record(written);
+            }
+            WriterCommand::Sync(reply) => {
+                let flushed = self.flush();
+                self.record(flushed);
+                let _ = reply.send(self.error.take().map_or(Ok(()), Err));
+            }
+            WriterCommand::Prune(before, reply) => {
+                let _ = reply.send(self.prune(before));
+            }
+        }
+    }
+
+    fn record(&mut self, result: std::io::Result<()>) {
+        if let Err(e) = result {
+            tracing::error!("Failed to write request audit log in {}: {}", self.dir.display(), e);
+            self.error.get_or_insert(e);
+        }
+    }
+
+    fn write(&mut self, timestamp: u64, line: &[u8]) -> std::io::Result<()> {
+        let day = day_start(timestamp);
+        let file = match &mut self.file {
+            Some((open_day, file)) if *open_day == day => file,
+            _ => {
+                self.flush()?;
+                std::fs::create_dir_all(&self.dir)?;
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(self.dir.join(day_file_name(day)))?;
+                &mut self.file.insert((day, BufWriter::new(file))).1
+            }
+        };
+        file.write_all(line)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match &mut self.file {
+            Some((_, file)) => file.flush(),
+            None => Ok(()),
+        }
+    }
+
+    fn prune(&mut self, before: u64) -> std::io::Result<usize> {
+        let mut deleted = 0;
+        for (day, path) in day_files(&self.dir)? {
+            if day + DAY_SECS > before {
+                break;
+            }
+            if self.file.as_ref().is_some_and(|(open_day, _)| *open_day == day) {
+                self.flush()?;
+                self.file = None;
+            }
+            std::fs::remove_file(path)?;
+            deleted += 1;
+        }
+        Ok(deleted)
+    }
+}
+
+// Upper bound on buffered request bodies (matches axum's default body limit)
+const MAX_BODY_BYTES: usize = 2 * 1024 * 1024;
+const DAY_SECS: u64 = 86400;
+
+fn blake2b256(bytes: &[u8]) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(bytes);
+    hasher.finalize().into()
+}
+
+fn header_string(headers: &HeaderMap, name: impl header::AsHeaderName) -> Option<String> {
+    headers.get(name).and_then(|value| value.to_str().ok()).map(str::to_string)
+}
+
+// The credential a request presents, without revealing it
+fn credential(headers: &HeaderMap) -> Option<String> {
+    if let Some(key) = headers.get(API_KEY_HEADER) {
+        return Some(format!("api-key:{}", hex::encode(&blake2b256(key.as_bytes())[..8])));
+    }
+    headers.contains_key(ADMIN_TOKEN_HEADER).then(|| "admin-token".to_string())
+}
+
+/// Whether requests with this method are recorded
+pub fn is_audited(method: &Method) -> bool {
+    !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// Middleware recording state-changing requests in the request audit log
+///
+/// A request is served even when its entry cannot be written; the failure is
+/// logged.
+pub async fn request_audit_layer(
+    State(log): State<RequestAuditLog>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !log.is_enabled() || !is_audited(request.method()) {
+        return next.run(request).await;
+    }
+
+    let started = Instant::now();
+    let (parts, body) = request.into_parts();
+    let body = match to_bytes(body, MAX_BODY_BYTES).await {
+        Ok(body) => body,
+        Err(_) => {
+            return (
+                StatusCode::PAYLOAD_TOO_LARGE,
+                Json(error_response::<()>("Request body too large".to_string())),
+            )
+                .into_response()
+        }
+    };
+
+    let mut entry = RequestAuditEntry {
+        timestamp: now_secs(),
+        request_id: crate::request_id::current(),
+        method: parts.method.to_string(),
+        path: parts.uri.path().to_string(),
+        query: parts.uri.query().map(str::to_string),
+        client_ip: parts
+            .extensions
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string()),
+        forwarded_for: header_string(&parts.headers, "x-forwarded-for"),
+        user_agent: header_string(&parts.headers, header::USER_AGENT),
+        credential: credential(&parts.headers),
+        request_hash: hex::encode(blake2b256(&body)),
+        status: 0,
+        duration_ms: 0,
+    };
+
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+
+    entry.status = response.status().as_u16();
+    entry.duration_ms = started.elapsed().as_millis() as u64;
+    if let Err(e) = log.append(&entry).await {
+        tracing::error!("Failed to write request audit entry for {} {}: {}", entry.method, entry.path, e);
+    }
+    response
+}
+
+/// Delete the files of days older than `config.retention_days` now and then
+/// daily, until shutdown
+pub async fn request_audit_retention_task(
+    log: RequestAuditLog,
+    config: RequestAuditConfig,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let mut ticker = tokio::time::interval(std::time::Duration::from_secs(DAY_SECS));
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            _ = ticker.tick() => {}
+        }
+        let before = now_secs().saturating_sub(config.retention_days.saturating_mul(DAY_SECS));
+        match log.prune(before).await {
+            Ok(0) => {}
+            Ok(deleted) => tracing::info!("Deleted {} request audit log files past retention", deleted),
+            Err(e) => tracing::warn!("Failed to prune request audit log: {}", e),
+        }
+    }
+}
+
+/// Entries returned when `limit` is not given
+const DEFAULT_QUERY_LIMIT: usize = 100;
+/// Most entries returned at once
+const MAX_QUERY_LIMIT: usize = 1000;
+
+fn audit_query(params: &HashMap<String, String>) -> Result<RequestAuditQuery, String> {
+    fn parse<T: std::str::FromStr>(
+        params: &HashMap<String, String>,
+        name: &str,
+        expected: &str,
+    ) -> Result<Option<T>, String> {
+        params
+            .get(name)
+            .map(|value| value.parse().map_err(|_| format!("{} must be {}", name, expected)))
+            .transpose()
+    }
+
+    let query = RequestAuditQuery {
+        from: parse(params, "from", "a Unix timestamp in seconds")?,
+        to: parse(params, "to", "a Unix timestamp in seconds")?,
+        client_ip: params.get("client_ip").cloned(),
+        path: params.get("path").cloned(),
+        method: params.get("method").cloned(),
+        status: parse(params, "status", "an HTTP status code")?,
+        credential: params.get("credential").cloned(),
+        offset: parse(params, "offset", "a non-negative number")?.unwrap_or(0),
+        limit: parse(params, "limit", "a non-negative number")?
+            .unwrap_or(DEFAULT_QUERY_LIMIT)
+            .min(MAX_QUERY_LIMIT),
+    };
+    if query.from.zip(query.to).is_some_and(|(from, to)| from > to) {
+        return Err("from must not be after to".to_string());
+    }
+    Ok(query)
+}
+
+/// Read the request audit log
+// Query: from, to, client_ip, path (prefix), method, status, credential, offset, limit
+pub async fn get_request_audit(
+    State(state): State<AppState>,
+    Query(params): Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<RequestAuditResponse>>) {
+    tracing::debug!("Reading request audit log with params: {:?}", params);
+
+    let query = match audit_query(&params) {
+        Ok(query) => query,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+
+    match state.request_audit.query(&query).await {
+        Ok(entries) => (
+            StatusCode::OK,
+            Json(success_response(RequestAuditResponse {
+                entries,
+                offset: query.offset,
+                limit: query.limit,
+            })),
+        ),
+        Err(e) => {
+            tracing::error!("Failed to read request audit log: {}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response("Failed to read request audit log".to_string())),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{middleware, routing::post, Router};
+    use tower::util::ServiceExt;
+
+    fn temp_log(name: &str) -> RequestAuditLog {
+        let dir = std::env::temp_dir().join(format!("basis_request_audit_{}_{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        RequestAuditLog::open(dir).unwrap()
+    }
+
+    fn entry(timestamp: u64, path: &str, status: u16) -> RequestAuditEntry {
+        RequestAuditEntry {
+            timestamp,
+            request_id: None,
+            method: "POST".to_string(),
+            path: path.to_string(),
+            query: None,
+            client_ip: Some("192.0.2.1".to_string()),
+            forwarded_for: None,
+            user_agent: None,
+            credential: None,
+            request_hash: hex::encode(blake2b256(b"")),
+            status,
+            duration_ms: 1,
+        }
+    }
+
+    fn all() -> RequestAuditQuery {
+        RequestAuditQuery {
+            limit: 10,
+            ..RequestAuditQuery::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_layer_records_state_changing_requests() {
+        let log = temp_log("layer");
+        let app = Router::new()
+            .route("/notes", post(|| async { StatusCode::CREATED }).get(|| async { "notes" }))
+            .layer(middleware::from_fn_with_state(log.clone(), request_audit_layer));
+
+        let request = Request::post("/notes?source=cli")
+            .header(API_KEY_HEADER, "secret-key")
+            .header(header::USER_AGENT, "basis-cli/1.0")
+            .header("x-forwarded-for", "203.0.113.7")
+            .extension(ConnectInfo(SocketAddr::from(([192, 0, 2, 1], 4000))))
+            .body(Body::from("{\"amount\":1}"))
+            .unwrap();
+        assert_eq!(app.clone().oneshot(request).await.unwrap().status(), StatusCode::CREATED);
+        let request = Request::get("/notes").body(Body::empty()).unwrap();
+        assert_eq!(app.oneshot(request).await.unwrap().status(), StatusCode::OK);
+
+        let entries = log.query(&all()).await.unwrap();
+        assert_eq!(entries.len(), 1, "GET requests are not recorded");
+        let recorded = &entries[0];
+        assert_eq!(recorded.path, "/notes");
+        assert_eq!(recorded.query.as_deref(), Some("source=cli"));
+        assert_eq!(recorded.status, 201);
+        assert_eq!(recorded.client_ip.as_deref(), Some("192.0.2.1"));
+        assert_eq!(recorded.forwarded_for.as_deref(), Some("203.0.113.7"));
+        assert_eq!(recorded.user_agent.as_deref(), Some("basis-cli/1.0"));
+        assert_eq!(recorded.request_hash, hex::encode(blake2b256(b"{\"amount\":1}")));
+        let credential = recorded.credential.as_deref().unwrap();
+        assert!(credential.starts_with("api-key:") && !credential.contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_queries_and_pruning() {
+        let log = temp_log("prune");
+        let day1 = DAY_SECS + 200;
+        let day2 = 2 * DAY_SECS + 300;
+        log.append(&entry(100, "/notes", 201)).await.unwrap();
+        log.append(&entry(day1, "/redeem", 401)).await.unwrap();
+        log.append(&entry(day2, "/notes/ack", 200)).await.unwrap();
+
+        assert_eq!(log.query(&all()).await.unwrap().len(), 3);
+        let mut files: Vec<String> = std::fs::read_dir(log.dir().unwrap())
+            .unwrap()
+            .map(|file| file.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files, vec!["1970-01-01.jsonl", "1970-01-02.jsonl", "1970-01-03.jsonl"]);
+
+        let notes = RequestAuditQuery {
+            path: Some("/notes".to_string()),
+            from: Some(150),
+            ..all()
+        };
+        assert_eq!(log.query(&notes).await.unwrap(), vec![entry(day2, "/notes/ack", 200)]);
+        let rejected = RequestAuditQuery {
+            status: Some(401),
+            ..all()
+        };
+        assert_eq!(log.query(&rejected).await.unwrap(), vec![entry(day1, "/redeem", 401)]);
+        let page = RequestAuditQuery {
+            offset: 1,
+            limit: 1,
+            ..all()
+        };
+        assert_eq!(log.query(&page).await.unwrap(), vec![entry(day1, "/redeem", 401)]);
+
+        // Only whole days past the cutoff are deleted
+        assert_eq!(log.prune(DAY_SECS + 500).await.unwrap(), 1);
+        assert_eq!(log.prune(DAY_SECS + 500).await.unwrap(), 0);
+        log.append(&entry(day2 + 100, "/notes", 201)).await.unwrap();
+        let timestamps: Vec<u64> = log.query(&all()).await.unwrap().iter().map(|entry| entry.timestamp).collect();
+        assert_eq!(timestamps, vec![day1, day2, day2 + 100]);
+
+        // Without a directory nothing is kept
+        let disabled = RequestAuditLog::default();
+        disabled.append(&entry(100, "/notes", 201)).await.unwrap();
+        assert!(disabled.query(&all()).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_query_params() {
+        let params = |pairs: &[(&str, &str)]| {
+            pairs
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect::<HashMap<_, _>>()
+        };
+        let query = audit_query(&params(&[("status", "401"), ("limit", "5000")])).unwrap();
+        assert_eq!(query.status, Some(401));
+        assert_eq!(query.limit, MAX_QUERY_LIMIT);
+        assert!(audit_query(&params(&[("from", "10"), ("to", "5")])).is_err());
+        assert!(audit_query(&params(&[("status", "teapot")])).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+//! Correlation IDs for HTTP requests
+//!
+//! Every request gets an ID, taken from its `X-Request-Id` header or generated,
+//! which is echoed in the response, recorded on a tracing span around the
+//! request and sent along with the commands the request issues to the tracker
+//! thread. Log lines on both sides of the channel can then be correlated.
+
+use crate::TrackerCommand;
+use axum::{
+    extract::Request,
+    http::HeaderValue,
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+/// Header carrying the request ID
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied request ID accepted; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// ID of the HTTP request being handled by the current task, if any
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+fn generate() -> String {
+    format!("{:032x}", secp256k1::rand::random::<u128>())
+}
+
+// Client-supplied IDs are kept when they are short and printable
+fn accept(value: &HeaderValue) -> Option<String> {
+    let id = value.to_str().ok()?;
+    (!id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic()))
+        .then(|| id.to_string())
+}
+
+/// Middleware assigning a request ID and handling the request within its span
+pub async fn request_id_layer(request: Request, next: Next) -> Response {
+    let id = request
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(accept)
+        .unwrap_or_else(generate);
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %request.method(),
+        path = %request.uri().path()
+    );
+
+    let mut response = REQUEST_ID
+        .scope(id.clone(), next.run(request))
+        .instrument(span)
+        .await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+/// Command received by the tracker thread, with the ID of the request that sent it
+#[derive(Debug)]
+pub struct TrackerEnvelope {
+    pub request_id: Option<String>,
+    pub command: TrackerCommand,
+}
+
+impl TrackerEnvelope {
+    /// Span to process the command in, carrying the request ID
+    pub fn span(&self) -> tracing::Span {
+        tracing::info_span!(
+            "tracker_command",
+            request_id = self.request_id.as_deref().unwrap_or("-")
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracker_queue::{tracker_channel, TrackerQueueConfig};
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::util::ServiceExt;
+
+    #[tokio::test]
+    async fn test_request_id_reaches_tracker_thread() {
+        let (tx, mut rx) = tracker_channel(TrackerQueueConfig::default());
+        let app = Router::new()
+            .route(
+                "/",
+                get(move || async move {
+                    let (response_tx, _) = tokio::sync::oneshot::channel();
+                    tx.send(TrackerCommand::GetNotes { response_tx }).await.unwrap();
+                }),
+            )
+            .layer(middleware::from_fn(request_id_layer));
+
+        let request = |id: Option<&str>| {
+            let builder = Request::builder().uri("/");
+            match id {
+                Some(id) => builder.header(REQUEST_ID_HEADER, id),
+                None => builder,
+            }
+            .body(Body::empty())
+            .unwrap()
+        };
+
+        // A client-supplied ID is echoed and travels with the command
+        let response = app.clone().oneshot(request(Some("trace-42"))).await.unwrap();
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "trace-42");
+        assert_eq!(rx.recv().await.unwrap().request_id.as_deref(), Some("trace-42"));
+
+        // Otherwise one is generated
+        let response = app.oneshot(request(Some("bad id"))).await.unwrap();
+        let generated = response.headers()[REQUEST_ID_HEADER].to_str().unwrap().to_string();
+        assert_eq!(generated.len(), 32);
+        assert_eq!(rx.recv().await.unwrap().request_id, Some(generated));
+
+        // Commands sent outside a request carry no ID
+        assert_eq!(current(), None);
+    }
+}
@@ -172,6 +172,41 @@ pub async fn get_reserve_by_box_id(
     }
 }
 
+/// Get the lifecycle of the reserve containing a box: every box it went
+/// through, how each was spent, and its current box
+#[axum::debug_handler]
+pub async fn get_reserve_history(
+    State(state): State<AppState>,
+    axum::extract::Path(box_id): axum::extract::Path<String>,
+) -> (StatusCode, Json<ApiResponse<Option<basis_store::ReserveLineage>>>) {
+    tracing::debug!("Getting reserve history for box ID: {}", box_id);
+
+    let scanner = state.ergo_scanner.lock().await;
+    let reserve_storage = scanner.reserve_storage();
+
+    match reserve_storage.get_lineage(&box_id) {
+        Ok(Some(lineage)) => {
+            tracing::info!(
+                "Returning {} transitions for reserve box {}",
+                lineage.transitions.len(),
+                box_id
+            );
+            (StatusCode::OK, Json(success_response(Some(lineage))))
+        }
+        Ok(None) => {
+            tracing::info!("No reserve history for box ID {}", box_id);
+            (StatusCode::NOT_FOUND, Json(success_response(None)))
+        }
+        Err(e) => {
+            tracing::error!("Failed to get reserve history from database: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(crate::models::error_response("Failed to retrieve reserve history from database".to_string())),
+            )
+        }
+    }
+}
+
 /// Serializable version of ExtendedReserveInfo for API responses
 #[derive(Debug, Serialize)]
 pub struct SerializableReserveInfo {
@@ -0,0 +1,1955 @@
+//! Running the tracker in-process
+//!
+//! The `basis_server` binary is a thin wrapper around this module: it loads
+//! the configuration, sets up logging and calls [`run`]. Other applications
+//! can embed the tracker the same way, without a separate process:
+//!
+//! ```ignore
+//! let server = Server::builder(config).build().await?;
+//! let notes = server.state().send(requests::GetNotes {}).await?;
+//! server.serve(shutdown_signal).await?;
+//! ```
+//!
+//! [`ServerBuilder::build`] opens the storage, starts the scanners, the
+//! tracker thread and the background tasks, and binds the listener;
+//! [`Server::serve`] answers HTTP requests until the shutdown signal
+//! resolves, then stops everything it started. Logging is left to the
+//! embedding application, which installs its own subscriber.
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    middleware,
+    routing::{get, post},
+    Router,
+};
+use basis_store::{
+    ergo_scanner::{start_scanner_with_shutdown, NodeConfig, QueuedReserveEvent, ReserveEvent, ServerState},
+    explorer_backend::ExplorerBackend,
+    persistence::{ReserveStorage, ScannerMetadataStorage, TrackerStorage},
+    tracker_scanner::{create_tracker_server_state, TrackerNodeConfig, TrackerServerState},
+    CommitmentStatus, ContractRegistry,
+};
+use tokio::net::TcpListener;
+use tokio::sync::Mutex;
+use tower_http::cors::{Any, CorsLayer};
+
+use crate::{
+    admin_api::*, api::*, auth::{admin_token_layer, api_key_layer}, issuer_policy::IssuerPolicy, pricing::Pricing, outbound_tx::{get_transaction, get_transactions, outbound_tx_task, submit_transaction, OutboundNode, OutboundTxQueue}, maintenance::{maintenance_task, StorageMaintenance}, collateral_history::{collateral_history_task, get_key_status_history}, cold_start::{check_local_state, degraded_layer, ColdStartState},
+    request_id::request_id_layer, idempotency::{idempotency_layer, IdempotencyStore},
+    request_audit::{get_request_audit, request_audit_layer, request_audit_retention_task, RequestAuditLog},
+    shutdown::ShutdownCoordinator, reserve_api::*, dispute_api::*, event_stream::stream_events, identity_api::*, metrics_api::get_metrics, note_payload_api::get_note_payload, report_api::*, split_note_api::*, stats_api::get_network_stats, balance_api::get_balance, netting_api::get_netting_suggestions, signing_service::SigningService, store::{EventQuery, EventStore}, wallet_api::*, AppConfig, AppState, EventType,
+    requests, TrackerCommand, TrackerEvent,
+    TrackerBoxUpdateConfig, TrackerBoxUpdater, SharedTrackerState, tracker_queue::saturation_layer, watcher::watcher_layer,
+    replica::{get_replica_status, replica_layer, replica_sync_task, ReplicaState},
+};
+#[cfg(unix)]
+use crate::ServerArgs;
+
+/// The tracker could not be started or did not stop cleanly
+#[derive(Debug, thiserror::Error)]
+pub enum ServerError {
+    /// The configuration, storage or a startup check failed
+    #[error("{0}")]
+    Startup(String),
+    #[error("Failed to bind to {addr}: {source}")]
+    Bind {
+        addr: SocketAddr,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Server error: {0}")]
+    Serve(#[source] std::io::Error),
+    /// Background tasks still running when the shutdown timeout expired
+    #[error("Shutdown timed out waiting for: {}", .0.join(", "))]
+    ShutdownTimeout(Vec<String>),
+}
+
+/// Run the tracker until `shutdown_signal` resolves
+///
+/// Shorthand for building a [`Server`] from `config` and serving it.
+pub async fn run(
+    config: AppConfig,
+    shutdown_signal: impl Future<Output = ()> + Send + 'static,
+) -> Result<(), ServerError> {
+    Server::builder(config).build().await?.serve(shutdown_signal).await
+}
+
+/// Options of a [`Server`] beyond its configuration
+pub struct ServerBuilder {
+    config: AppConfig,
+    listener: Option<TcpListener>,
+    #[cfg(unix)]
+    reload_args: Option<ServerArgs>,
+}
+
+/// A started tracker, ready to serve HTTP requests
+pub struct Server {
+    state: AppState,
+    router: Router,
+    listener: TcpListener,
+    shutdown: ShutdownCoordinator,
+    shutdown_timeout: std::time::Duration,
+}
+
+impl Server {
+    /// Builder of a server running with `config`
+    pub fn builder(config: AppConfig) -> ServerBuilder {
+        ServerBuilder {
+            config,
+            listener: None,
+            #[cfg(unix)]
+            reload_args: None,
+        }
+    }
+
+    /// State shared by the API handlers, for sending requests to the
+    /// tracker thread or reading reserves and events directly
+    ///
+    /// The tracker thread stops once every copy of the state is gone, so
+    /// clones must be dropped before [`Server::serve`] can shut down in time.
+    pub fn state(&self) -> &AppState {
+        &self.state
+    }
+
+    /// The HTTP API, for mounting into another router
+    ///
+    /// Like clones of [`Server::state`], the router keeps the tracker
+    /// thread running until it is dropped.
+    pub fn router(&self) -> Router {
+        self.router.clone()
+    }
+
+    /// Address the server listens on
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Serve requests until `shutdown_signal` resolves, then stop the
+    /// background tasks and wait for the tracker thread to flush storage
+    pub async fn serve(
+        self,
+        shutdown_signal: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<(), ServerError> {
+        let Server {
+            state,
+            router,
+            listener,
+            shutdown,
+            shutdown_timeout,
+        } = self;
+        shutdown.trigger_on(shutdown_signal);
+
+        tracing::info!("Starting axum server...");
+        // Peer addresses are recorded in the request audit log
+        let server_result = axum::serve(listener, router.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown.signalled())
+            .await;
+
+        // The server has stopped accepting connections and finished in-flight requests.
+        // Dropping the last command sender lets the tracker thread drain its queue and exit.
+        drop(state);
+        shutdown.trigger();
+
+        tracing::info!("Waiting up to {:?} for background tasks to stop", shutdown_timeout);
+        let unfinished = shutdown.join(shutdown_timeout).await;
+
+        server_result.map_err(ServerError::Serve)?;
+        if !unfinished.is_empty() {
+            return Err(ServerError::ShutdownTimeout(unfinished));
+        }
+        tracing::info!("Shutdown complete");
+        Ok(())
+    }
+}
+
+impl ServerBuilder {
+    /// Serve on `listener` instead of binding `server.host:server.port`
+    pub fn listener(mut self, listener: TcpListener) -> Self {
+        self.listener = Some(listener);
+        self
+    }
+
+    /// Load the configuration again with `args` on every SIGHUP
+    #[cfg(unix)]
+    pub fn reload_on_sighup(mut self, args: ServerArgs) -> Self {
+        self.reload_args = Some(args);
+        self
+    }
+
+    /// Open the storage and start the scanners, the tracker thread and the
+    /// background tasks
+    pub async fn build(self) -> Result<Server, ServerError> {
+        let config = self.config;
+
+        // Note and reserve storage seal their records with the configured
+        // storage key; the config was validated, so it resolves
+        let storage_cipher = config.storage.encryption.cipher().unwrap_or_default();
+        if storage_cipher.is_enabled() {
+            tracing::info!("Storage encryption enabled, key IDs {}", storage_cipher.key_ids().join(", "));
+        }
+
+        // Initialize real Ergo scanner with blockchain monitoring
+        tracing::info!("Initializing Ergo scanner with blockchain monitoring...");
+
+        // Create scanner configuration with actual reserve contract P2S
+        let mut scanner_config = config.ergo.node.clone();
+        scanner_config.reserve_contract_p2s = Some(config.ergo.basis_reserve_contract_p2s.clone());
+
+        // Create real scanner state with configured node URL and contract template
+        let mut ergo_scanner = match ServerState::with_storage_paths_and_cipher(
+            scanner_config,
+            config.storage.scanner_metadata_path.clone(),
+            config.storage.reserves_path.clone(),
+            storage_cipher.clone(),
+        ) {
+            Ok(scanner) => scanner,
+            Err(e) => {
+                tracing::warn!("Failed to create Ergo scanner: {}", e);
+                tracing::info!("Continuing without blockchain scanner...");
+                // Create a minimal scanner that won't actually scan
+                let minimal_config = NodeConfig {
+                    node_url: "http://127.0.0.1:9053".to_string(), // Dummy URL that won't be used
+                    ..Default::default()
+                };
+                ServerState::new(minimal_config).unwrap_or_else(|_| panic!("Failed to create minimal scanner"))
+            }
+        };
+
+        // Refuse to run against a node of the other network: every configured
+        // address has been checked against ergo.network already
+        tracing::info!("Tracker runs on {}", config.network());
+        match ergo_scanner.scan.node_info().await {
+            Ok(info) => {
+                if let Err(e) = config.network().check_node_info(&info) {
+                    return Err(ServerError::Startup(format!("{}. Set 'ergo.network' or point 'ergo.node.node_url' at a {} node.", e, config.network())));
+                }
+            }
+            Err(e) => tracing::warn!("Could not check the Ergo node network: {}", e),
+        }
+
+        // Reserves of every registered contract version are tracked and spendable
+        let contracts = match config.contract_registry() {
+            Ok(contracts) => {
+                for contract in contracts.contracts() {
+                    tracing::info!("Reserve contract version {}: {}", contract.version, contract.p2s(config.network()));
+                }
+                ergo_scanner.set_contract_registry(contracts.clone());
+                contracts
+            }
+            Err(e) => {
+                tracing::warn!("Invalid reserve contract configuration: {}", e);
+                ContractRegistry::builtin()
+            }
+        };
+        // Both scanners share one explorer client, so they share its rate limit and cache
+        let explorer = config.explorer_config().map(ExplorerBackend::new);
+        if let Some(explorer) = &explorer {
+            ergo_scanner.set_explorer_backend(explorer.clone());
+        }
+        ergo_scanner.set_backfill_config(config.backfill_config());
+        ergo_scanner.set_scan_loop_config(config.scan_loop_config());
+        if let Some(start_height) = config.ergo.node.start_height {
+            tracing::info!("Reserve scanner will backfill from height {}", start_height);
+        }
+
+        // The node scan misses reserves created before it was registered; the
+        // explorer backend lists every unspent reserve anyway
+        let explorer_url = config.scanner.explorer_url.as_deref().filter(|url| !url.is_empty() && explorer.is_none());
+        if let (Some(explorer_url), Some(tracker_nft_id)) = (explorer_url, config.ergo.tracker_nft_id.as_deref()) {
+            if let Err(e) = ergo_scanner.discover_reserves(explorer_url, tracker_nft_id).await {
+                tracing::warn!("Reserve discovery via explorer failed: {}", e);
+            }
+        }
+
+        // Coordinates graceful shutdown of the server and background tasks
+        let mut shutdown = ShutdownCoordinator::new();
+
+        // Start the scanner background task
+        match start_scanner_with_shutdown(ergo_scanner.clone(), shutdown.subscribe()).await {
+            Ok(handle) => {
+                shutdown.register("Reserve scanner", handle);
+                tracing::info!("Ergo scanner started successfully");
+            }
+            Err(e) => {
+                tracing::warn!("Failed to start background scanner: {}", e);
+                tracing::info!("Continuing without background scanner...");
+            }
+        }
+
+        // Get tracker public key from config early, needed for shared state
+        let tracker_pubkey = if let Some(tracker_pubkey_bytes) = match config.tracker_public_key_bytes() {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                return Err(ServerError::Startup(format!("Invalid tracker public key format: {}. Please set 'ergo.tracker_public_key' as either a hex-encoded public key or a P2PK address in your configuration file.", e)));
+            }
+        } {
+            tracing::info!("Using tracker public key from configuration");
+            tracker_pubkey_bytes
+        } else {
+            return Err(ServerError::Startup("No tracker public key found in configuration. Please set 'ergo.tracker_public_key' as either a hex-encoded public key or a P2PK address in your configuration file.".to_string()));
+        };
+
+        // Create shared tracker state for the updater (before scanner so scanner can set box ID)
+        tracing::info!("Initializing shared tracker state...");
+        let shared_tracker_state_for_updater = SharedTrackerState::new_with_tracker_key(tracker_pubkey);
+
+        // Tracker boxes, local AVL roots and commitment checks share one storage instance
+        let tracker_storage = match TrackerStorage::open(&config.storage.tracker_boxes_path) {
+            Ok(storage) => storage,
+            Err(e) => {
+                return Err(ServerError::Startup(format!("Failed to initialize tracker storage: {:?}", e)));
+            }
+        };
+
+        // Databases the maintenance scheduler looks after; the note database
+        // joins once the tracker thread has opened it
+        let mut storage_handles = vec![
+            ergo_scanner.reserve_storage.maintenance_handle(),
+            ergo_scanner.metadata_storage.maintenance_handle(),
+            tracker_storage.maintenance_handle(),
+        ];
+
+        // Initialize tracker scanner for monitoring tracker state commitment boxes
+        tracing::debug!("Tracker NFT ID from config: {:?}", config.ergo.tracker_nft_id);
+        let tracker_scanner = 
+        if config.ergo.tracker_nft_id.as_ref().is_some_and(|id| !id.is_empty()) {
+            tracing::info!("Initializing tracker scanner with tracker NFT ID...");
+            let tracker_scanner_config = TrackerNodeConfig {
+                start_height: config.ergo.node.start_height,
+                tracker_nft_id: config.ergo.tracker_nft_id.clone(),
+                node_url: config.ergo.node.node_url.clone(),
+                scan_name: Some("Basis Tracker Scanner".to_string()),
+                api_key: config.ergo.node.api_key.clone(),
+                verify_against_all_roots: config.watcher.enabled || config.replica.is_enabled(),
+            };
+
+            // Create tracker scanner state with persistent storage paths (similar to reserve scanner)
+            let metadata_storage_path = config.storage.tracker_scanner_metadata_path.clone();
+
+            // Ensure data directory exists
+            std::fs::create_dir_all(metadata_storage_path.parent().unwrap_or(std::path::Path::new("data"))).unwrap_or_else(|e| {
+                tracing::warn!("Failed to create data directory: {}", e);
+            });
+
+            match basis_store::persistence::ScannerMetadataStorage::open(metadata_storage_path.clone()) {
+                Ok(metadata_storage) => {
+                    storage_handles.push(metadata_storage.maintenance_handle());
+                    let mut tracker_scanner = create_tracker_server_state(
+                        tracker_scanner_config,
+                        metadata_storage,
+                        tracker_storage.clone(),
+                    );
+                    if let Some(explorer) = &explorer {
+                        tracker_scanner.set_explorer_backend(explorer.clone());
+                    }
+
+                    // Ensure the tracker scan is registered on startup
+                    match tracker_scanner.ensure_scan_registered().await {
+                        Ok(scan_id) => {
+                            tracing::info!("Tracker scan registered with ID: {}", scan_id);
+
+                            // Process tracker boxes once to populate storage
+                            match tracker_scanner.process_tracker_boxes().await {
+                                Ok(tracker_boxes) => {
+                                    tracing::info!("Processed {} tracker boxes", tracker_boxes.len());
+
+                                    // Set the latest tracker box ID in shared state for the updater
+                                    if let Some(latest_box) = tracker_boxes.iter().max_by_key(|b| b.last_verified_height) {
+                                        tracing::info!("Setting latest tracker box ID in shared state: {}", latest_box.box_id);
+                                        shared_tracker_state_for_updater.set_tracker_box_id(latest_box.box_id.clone());
+                                    }
+                                }
+                                Err(e) => {
+                                    tracing::error!("Failed to process tracker boxes: {}", e);
+                                }
+                            }
+
+                            tracing::info!("Tracker scanner initialization completed successfully");
+                            Some(tracker_scanner)
+                        },
+                        Err(e) => {
+                            tracing::warn!("Failed to register tracker scan: {:?}", e);
+                            tracing::info!("Continuing without tracker scanner registration...");
+                            None
+                        }
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to create metadata storage for tracker scanner: {:?}", e);
+                    tracing::info!("Continuing without tracker scanner...");
+                    None
+                }
+            }
+        } else {
+            tracing::info!("Tracker NFT ID not configured, skipping tracker scanner initialization");
+            tracing::info!("To enable tracker scanner, configure 'ergo.tracker_nft_id' in your configuration");
+            None
+        };
+
+        // Create channel for communicating with tracker thread
+        let (tx, mut rx) = crate::tracker_queue::tracker_channel(config.tracker_queue.clone());
+
+        // The tracker thread owns the only TrackerStateManager: note storage is opened once.
+        // Others see its state through the root digest it publishes to the box updater and
+        // the local roots it records for the tracker scanner to verify commitments against.
+        use basis_store::TrackerStateManager;
+
+        // Spawn tracker thread (using tokio::task::spawn_blocking for CPU-bound work)
+        let shared_state_for_tracker = shared_tracker_state_for_updater.clone(); // Also pass shared state for updater
+
+        // Build debt ceiling policy, using the scanner's reserve tracker as collateral source
+        let debt_policy = if config.debt_policy.enabled {
+            match basis_store::DebtCeilingPolicy::from_config(&config.debt_policy) {
+                Ok(policy) => {
+                    tracing::info!(
+                        "Debt ceiling enabled: max ratio {} with {} override(s)",
+                        config.debt_policy.max_debt_ratio,
+                        config.debt_policy.overrides.len()
+                    );
+                    Some(policy)
+                }
+                Err(e) => {
+                    return Err(ServerError::Startup(format!("Invalid debt policy configuration: {}", e)));
+                }
+            }
+        } else {
+            tracing::info!("Debt ceiling not enabled");
+            None
+        };
+        let debt_policy_reserve_tracker = ergo_scanner.reserve_tracker.clone();
+        if config.note_limits.is_enabled() {
+            tracing::info!(
+                "Note limits: amounts {} to {}, at most {} notes per issuer (0: no limit)",
+                config.note_limits.min_amount,
+                config.note_limits.max_amount,
+                config.note_limits.max_notes_per_issuer
+            );
+        }
+        let note_limits = config.note_limits.clone();
+
+        let notes_path = config.storage.notes_path.clone();
+        // Local roots are recorded for cross-verifying the tracker box commitments
+        let root_tracker_storage = tracker_storage.clone();
+        let root_metadata_storage = ergo_scanner.metadata_storage.clone();
+        let avl_tree_path = config.storage.avl_tree_path.clone();
+        // The tracker thread reports how the rebuilt tree compares with the chain
+        let (cold_start_tx, cold_start_rx) = tokio::sync::oneshot::channel();
+        let (note_storage_tx, note_storage_rx) = tokio::sync::oneshot::channel();
+        let tracker_contracts = contracts.clone();
+        let tracker_thread = tokio::task::spawn_blocking(move || {
+            use basis_store::RedemptionManager;
+
+            tracing::debug!("Tracker thread started");
+            let mut tracker = TrackerStateManager::with_storage_paths_and_cipher(&notes_path, &avl_tree_path, storage_cipher);
+            let _ = note_storage_tx.send(tracker.storage_maintenance_handle());
+            if let Some(policy) = debt_policy {
+                tracker.set_debt_policy(policy, debt_policy_reserve_tracker.clone());
+            }
+            tracker.set_note_limits(note_limits);
+
+            // Update shared state with the rebuilt AVL root digest after initialization
+            let initial_root = tracker.get_state().avl_root_digest;
+            shared_state_for_tracker.set_avl_root_digest(initial_root);
+            // Compare with the chain before the rebuilt root joins the recorded history
+            let _ = cold_start_tx.send(check_local_state(&root_tracker_storage, &initial_root));
+            record_local_root(&root_tracker_storage, &root_metadata_storage, &initial_root);
+            tracing::info!("Tracker thread initialized with AVL root digest: {}", hex::encode(initial_root));
+
+            let mut redemption_manager = RedemptionManager::new(tracker);
+            redemption_manager.set_contract_registry(tracker_contracts);
+
+            while let Some(envelope) = rx.blocking_recv() {
+                // Log lines of the command carry the ID of the request that sent it
+                let _span = envelope.span().entered();
+                let cmd = envelope.command;
+                tracing::debug!("Tracker thread received command: {:?}", cmd);
+                match cmd {
+                    TrackerCommand::AddNote {
+                        issuer_pubkey,
+                        note,
+                        encrypted_payload,
+                        response_tx,
+                    } => {
+                        let previously_collected = redemption_manager.tracker
+                            .lookup_note(&issuer_pubkey, &note.recipient_pubkey)
+                            .map(|previous| previous.amount_collected)
+                            .unwrap_or(0);
+
+                        // Get mutable access to the tracker for adding a note
+                        let result = redemption_manager.tracker.add_note(&issuer_pubkey, &note);
+                        if let (Ok(()), Some(envelope)) = (&result, &encrypted_payload) {
+                            // The note is recorded either way; a lost payload is only logged
+                            if let Err(e) = redemption_manager.tracker.set_note_payload(
+                                &issuer_pubkey,
+                                &note.recipient_pubkey,
+                                note.timestamp,
+                                envelope,
+                            ) {
+                                tracing::warn!("Failed to store note payload: {:?}", e);
+                            }
+                        }
+
+                        // Update shared state for tracker box updater if successful
+                        if result.is_ok() {
+                            shared_state_for_tracker
+                                .record_note_update(note.amount_collected.saturating_sub(previously_collected));
+
+                            // Update the shared AVL root digest to match the current tracker state
+                            let current_root = redemption_manager.tracker.get_state().avl_root_digest;
+                            shared_state_for_tracker.set_avl_root_digest(current_root);
+                            record_note_change(
+                                &root_tracker_storage,
+                                &root_metadata_storage,
+                                &current_root,
+                                &redemption_manager.tracker,
+                                &issuer_pubkey,
+                                &note.recipient_pubkey,
+                            );
+
+                            // Note: In a real implementation, we'd send this back to the async context to store
+                            // For now, we'll handle event storage in the async handler
+                        }
+
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::ValidateNote {
+                        issuer_pubkey,
+                        note,
+                        response_tx,
+                    } => {
+                        let _ = response_tx.send(redemption_manager.tracker.validate_note(&issuer_pubkey, &note));
+                    }
+                    TrackerCommand::AcknowledgeNote {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        recipient_signature,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.acknowledge_note(
+                            &issuer_pubkey,
+                            &recipient_pubkey,
+                            recipient_signature,
+                        );
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetNoteHistory {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        offset,
+                        limit,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.get_note_history(
+                            &issuer_pubkey,
+                            &recipient_pubkey,
+                            offset,
+                            limit,
+                        );
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::SearchNotes { query, response_tx } => {
+                        let _ = response_tx.send(redemption_manager.tracker.search_notes(&query));
+                    }
+                    TrackerCommand::GetNotesByIssuer {
+                        issuer_pubkey,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.get_issuer_notes(&issuer_pubkey);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetNotesByRecipient {
+                        recipient_pubkey,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.get_recipient_notes(&recipient_pubkey);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetNotesByRecipientWithIssuer {
+                        recipient_pubkey,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.get_recipient_notes_with_issuer(&recipient_pubkey);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetNoteByIssuerAndRecipient {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker
+                            .lookup_note(&issuer_pubkey, &recipient_pubkey)
+                            .map(Some);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetNotePayload {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        response_tx,
+                    } => {
+                        let _ = response_tx.send(
+                            redemption_manager.tracker.get_note_payload(&issuer_pubkey, &recipient_pubkey),
+                        );
+                    }
+                    TrackerCommand::GetNetworkStats {
+                        top_issuers,
+                        days,
+                        response_tx,
+                    } => {
+                        let _ = response_tx.send(redemption_manager.tracker.network_stats(top_issuers, days));
+                    }
+                    TrackerCommand::GetAccountJournal { account, response_tx } => {
+                        let _ = response_tx.send(redemption_manager.tracker.account_journal(&account));
+                    }
+                    TrackerCommand::ValidateRedemption {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        amount,
+                        response_tx,
+                    } => {
+                        let _ = response_tx.send(redemption_manager.validate_redemption(
+                            &issuer_pubkey,
+                            &recipient_pubkey,
+                            amount,
+                        ));
+                    }
+                    TrackerCommand::InitiateRedemption {
+                        request,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.initiate_redemption(&request);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::CompleteRedemption {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        redeemed_amount,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.complete_redemption(
+                            &issuer_pubkey,
+                            &recipient_pubkey,
+                            redeemed_amount,
+                        );
+
+                        // Update shared state for tracker box updater if successful
+                        if result.is_ok() {
+                            shared_state_for_tracker.record_note_update(redeemed_amount);
+
+                            // Update the shared AVL root digest to match the current tracker state
+                            let current_root = redemption_manager.tracker.get_state().avl_root_digest;
+                            shared_state_for_tracker.set_avl_root_digest(current_root);
+                            record_note_change(
+                                &root_tracker_storage,
+                                &root_metadata_storage,
+                                &current_root,
+                                &redemption_manager.tracker,
+                                &issuer_pubkey,
+                                &recipient_pubkey,
+                            );
+                        }
+
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetNotes { response_tx } => {
+                        let result = redemption_manager.tracker.get_all_notes_with_issuer();
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GenerateProof {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.generate_proof(&issuer_pubkey, &recipient_pubkey);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GenerateRecipientProofs {
+                        recipient_pubkey,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.generate_recipient_proofs(&recipient_pubkey);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetTrackerLookupProof {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.generate_tracker_lookup_proof(&issuer_pubkey, &recipient_pubkey);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetReserveLookupProof {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.generate_reserve_lookup_proof(&issuer_pubkey, &recipient_pubkey);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetReserveInsertProof {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        timestamp,
+                        new_already_redeemed,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.generate_reserve_insert_proof(&issuer_pubkey, &recipient_pubkey, timestamp, new_already_redeemed);
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::FileDispute { filing, response_tx } => {
+                        let _ = response_tx.send(redemption_manager.tracker.file_dispute(&filing));
+                    }
+                    TrackerCommand::CloseDispute {
+                        id,
+                        status,
+                        note,
+                        response_tx,
+                    } => {
+                        let _ = response_tx.send(redemption_manager.tracker.close_dispute(id, status, note));
+                    }
+                    TrackerCommand::GetDispute { id, response_tx } => {
+                        let _ = response_tx.send(redemption_manager.tracker.get_dispute(id));
+                    }
+                    TrackerCommand::GetDisputes { response_tx } => {
+                        let _ = response_tx.send(redemption_manager.tracker.get_disputes());
+                    }
+                    TrackerCommand::GetOpenDispute {
+                        issuer_pubkey,
+                        recipient_pubkey,
+                        response_tx,
+                    } => {
+                        let _ = response_tx.send(redemption_manager.tracker.open_dispute(&issuer_pubkey, &recipient_pubkey));
+                    }
+                    TrackerCommand::AddSplitNote {
+                        issuer_pubkey,
+                        split,
+                        response_tx,
+                    } => {
+                        let result = redemption_manager.tracker.add_split_note(&issuer_pubkey, &split);
+
+                        // Update shared state for tracker box updater if successful
+                        if result.is_ok() {
+                            shared_state_for_tracker.record_note_update(split.total_amount().unwrap_or(0));
+
+                            let current_root = redemption_manager.tracker.get_state().avl_root_digest;
+                            shared_state_for_tracker.set_avl_root_digest(current_root);
+                            record_local_root(&root_tracker_storage, &root_metadata_storage, &current_root);
+                        }
+
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::GetSplitNotesByIssuer {
+                        issuer_pubkey,
+                        response_tx,
+                    } => {
+                        let _ = response_tx.send(redemption_manager.tracker.get_issuer_split_notes(&issuer_pubkey));
+                    }
+                    TrackerCommand::RotateIssuerKey { rotation, response_tx } => {
+                        let _ = response_tx.send(redemption_manager.tracker.rotate_issuer_key(&rotation));
+                    }
+                    TrackerCommand::GetKeyHistory { pubkey, response_tx } => {
+                        let _ = response_tx.send(redemption_manager.tracker.get_key_history(&pubkey));
+                    }
+                    TrackerCommand::ScanNotes {
+                        query,
+                        after,
+                        limit,
+                        response_tx,
+                    } => {
+                        let _ = response_tx.send(redemption_manager.tracker.scan_notes_by_timestamp(&query, after.as_ref(), limit));
+                    }
+                    TrackerCommand::RebuildAvlTree { response_tx } => {
+                        let result = redemption_manager.tracker.rebuild_avl_tree().map(|()| {
+                            let root = redemption_manager.tracker.get_state().avl_root_digest;
+                            shared_state_for_tracker.set_avl_root_digest(root);
+                            record_local_root(&root_tracker_storage, &root_metadata_storage, &root);
+                            root
+                        });
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::CheckStorage { repair, response_tx } => {
+                        let result = redemption_manager.tracker.check_storage(repair);
+                        if matches!(&result, Ok(integrity) if integrity.avl_rebuilt) {
+                            let root = redemption_manager.tracker.get_state().avl_root_digest;
+                            shared_state_for_tracker.set_avl_root_digest(root);
+                            record_local_root(&root_tracker_storage, &root_metadata_storage, &root);
+                        }
+                        let _ = response_tx.send(result);
+                    }
+                    TrackerCommand::ApplyReplicatedNotes { notes, response_tx } => {
+                        let result = redemption_manager.tracker.replicate_notes(&notes, |tracker, issuer_pubkey, note| {
+                            let root = tracker.get_state().avl_root_digest;
+                            record_note_change(
+                                &root_tracker_storage,
+                                &root_metadata_storage,
+                                &root,
+                                tracker,
+                                issuer_pubkey,
+                                &note.recipient_pubkey,
+                            );
+                        });
+                        let root = redemption_manager.tracker.get_state().avl_root_digest;
+                        shared_state_for_tracker.set_avl_root_digest(root);
+                        let _ = response_tx.send(result.map(|()| root));
+                    }
+                    TrackerCommand::Flush { response_tx } => {
+                        let _ = response_tx.send(redemption_manager.tracker.flush());
+                    }
+                    TrackerCommand::SetDebtPolicy { policy, response_tx } => {
+                        match policy {
+                            Some(policy) => redemption_manager
+                                .tracker
+                                .set_debt_policy(policy, debt_policy_reserve_tracker.clone()),
+                            None => redemption_manager.tracker.clear_debt_policy(),
+                        }
+                        let _ = response_tx.send(());
+                    }
+                }
+            }
+
+            // All senders are gone and the queue is drained; make sure every write reaches disk
+            tracing::info!("Tracker command queue drained, flushing storage");
+            if let Err(e) = redemption_manager.tracker.flush() {
+                tracing::error!("Failed to flush tracker storage on shutdown: {:?}", e);
+            }
+        });
+        shutdown.register("Tracker thread", tracker_thread);
+
+        // Create tracker box updater
+        tracing::info!("Initializing tracker box updater...");
+
+        // Check if node configuration is provided, abort if not
+        if config.ergo.node.node_url.is_empty() {
+            return Err(ServerError::Startup("No Ergo node URL provided in configuration. Tracker box updater requires node connection.".to_string()));
+        }
+
+        // Addresses are encoded for the configured network
+        let network_prefix = config.network().prefix();
+
+        let tracker_box_config = TrackerBoxUpdateConfig {
+            update_interval_seconds: config.scanner.tracker_box_update_interval_secs,
+            enabled: true,
+            ergo_node_url: config.ergo.node.node_url.clone(),
+            ergo_api_key: config.ergo.node.api_key.clone(),
+            tracker_secret_key: config.tracker_secret_key_bytes(),
+            schedule: Some(config.commitment_schedule.clone()).filter(|schedule| schedule.enabled),
+        };
+        // Watchers and replicas hold no signing key and never publish tracker boxes
+        if config.watcher.enabled {
+            tracing::info!("Watcher mode: tracker box updater disabled");
+        } else if config.replica.is_enabled() {
+            tracing::info!("Replica mode: tracker box updater disabled");
+        } else {
+            // Subscribe the tracker updater to shutdown
+            let updater_shutdown_rx = shutdown.subscribe();
+
+            // Start the tracker box updater in the background
+            let updater_config = tracker_box_config.clone();
+            let shared_state_clone = shared_tracker_state_for_updater.clone();
+            let updater_network_prefix = network_prefix; // Use the network_prefix determined above
+            // Get the tracker NFT ID from config - it must be present since it's now required
+            let tracker_nft_id = config.ergo.tracker_nft_id.clone().ok_or_else(|| {
+                ServerError::Startup("Tracker NFT ID must be configured in server configuration".to_string())
+            })?;
+            let updater_task = tokio::spawn(async move {
+                if let Err(e) = TrackerBoxUpdater::start(
+                    updater_config,
+                    shared_state_clone,
+                    updater_network_prefix,
+                    tracker_nft_id, // Pass the required tracker NFT ID
+                    updater_shutdown_rx,
+                ).await {
+                    tracing::error!("Tracker box updater failed: {}", e);
+                }
+            });
+            shutdown.register("Tracker box updater", updater_task);
+            tracing::info!("Tracker box updater started successfully");
+        }
+
+        // Events are kept in SQLite when a database URL is configured
+        let event_store = match config.server.database_url.as_deref().filter(|url| !url.is_empty()) {
+            Some(database_url) => {
+                tracing::info!("Opening event store at {}", database_url);
+                EventStore::open(database_url).await
+            }
+            None => EventStore::new().await,
+        };
+        let event_store = match event_store {
+            Ok(store) => std::sync::Arc::new(store),
+            Err(e) => {
+                return Err(ServerError::Startup(format!("Failed to initialize event store: {:?}", e)));
+            }
+        };
+
+        // Add demo events
+        let demo_events = vec![
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::NoteUpdated,
+                timestamp: 1234567890,
+                issuer_pubkey: Some(
+                    "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
+                ),
+                recipient_pubkey: Some(
+                    "020202020202020202020202020202020202020202020202020202020202020202".to_string(),
+                ),
+                amount: Some(1000),
+                reserve_box_id: None,
+                collateral_amount: None,
+                redeemed_amount: None,
+                height: None,
+            },
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::NoteUpdated,
+                timestamp: 1234567891,
+                issuer_pubkey: Some(
+                    "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
+                ),
+                recipient_pubkey: Some(
+                    "030303030303030303030303030303030303030303030303030303030303030303".to_string(),
+                ),
+                amount: Some(2000),
+                reserve_box_id: None,
+                collateral_amount: None,
+                redeemed_amount: None,
+                height: None,
+            },
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::ReserveCreated,
+                timestamp: 1234567892,
+                issuer_pubkey: Some(
+                    "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
+                ),
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: Some("box1234567890abcdef".to_string()),
+                collateral_amount: Some(1000000000),
+                redeemed_amount: None,
+                height: Some(1000),
+            },
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::ReserveToppedUp,
+                timestamp: 1234567893,
+                issuer_pubkey: Some(
+                    "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
+                ),
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: Some("box1234567890abcdef".to_string()),
+                collateral_amount: Some(500000000),
+                redeemed_amount: None,
+                height: Some(1001),
+            },
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::ReserveRedeemed,
+                timestamp: 1234567894,
+                issuer_pubkey: Some(
+                    "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
+                ),
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: Some("box1234567890abcdef".to_string()),
+                collateral_amount: None,
+                redeemed_amount: Some(250000000),
+                height: Some(1002),
+            },
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::Commitment,
+                timestamp: 1234567895,
+                issuer_pubkey: None,
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: None,
+                collateral_amount: None,
+                redeemed_amount: None,
+                height: Some(1003),
+            },
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::CollateralAlert { ratio: 0.8 },
+                timestamp: 1234567896,
+                issuer_pubkey: Some(
+                    "010101010101010101010101010101010101010101010101010101010101010101".to_string(),
+                ),
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: None,
+                collateral_amount: None,
+                redeemed_amount: None,
+                height: None,
+            },
+        ];
+
+        for event in demo_events {
+            if let Err(e) = event_store.add_event(event).await {
+                tracing::warn!("Failed to add demo event: {:?}", e);
+            }
+        }
+
+        // The API reads reserves through a handle on the scanner's own tracker
+        let scanner_reserve_tracker = ergo_scanner.reserve_tracker.clone();
+        let reserve_events = ergo_scanner.subscribe_events();
+        let reserve_event_queue = ergo_scanner.reserve_storage().clone();
+
+        // Build acceptance predicate from configuration
+        let acceptance_predicate = match crate::acceptance::builder::build_predicate_tree(config.acceptance.clone()) {
+            Ok(Some(pred)) => {
+                tracing::info!("Acceptance predicate loaded: '{}'", pred.name());
+                Some(std::sync::Arc::from(pred))
+            }
+            Ok(None) => {
+                tracing::info!("No acceptance predicates configured");
+                None
+            }
+            Err(e) => {
+                tracing::warn!("Failed to build acceptance predicate: {}", e);
+                None
+            }
+        };
+
+        // A watcher expects to differ from the tracker it audits, and a replica
+        // lags behind its primary until synced
+        let cold_start = ColdStartState::default();
+        match cold_start_rx.await {
+            Ok(_) if config.watcher.enabled || config.replica.is_enabled() => {}
+            Ok(Some(check)) => {
+                if check.status == basis_store::ColdStartStatus::Diverged {
+                    tracing::error!(
+                        "Local AVL root {} diverges from commitment {} in tracker box {}: serving read-only",
+                        check.local_digest,
+                        check.onchain_digest,
+                        check.box_id
+                    );
+                } else {
+                    tracing::info!("Local state matches tracker box {} ({:?})", check.box_id, check.status);
+                }
+                cold_start.record(check);
+            }
+            Ok(None) => tracing::info!("No tracker box to verify local state against"),
+            Err(_) => tracing::warn!("Tracker thread stopped before verifying local state"),
+        }
+
+        // A denylist that cannot be read must not be dropped silently
+        let issuer_policy = match IssuerPolicy::from_config(&config.issuer_policy) {
+            Ok(policy) => policy,
+            Err(e) => {
+                return Err(ServerError::Startup(format!("Failed to load issuer policy state file: {}", e)));
+            }
+        };
+
+        let pricing = match Pricing::from_config(&config.pricing) {
+            Ok(pricing) => pricing,
+            Err(e) => {
+                return Err(ServerError::Startup(format!("Failed to load pricing state file: {}", e)));
+            }
+        };
+
+        // Transactions accepted before a restart are submitted again, never dropped
+        let outbound_tx = match OutboundTxQueue::from_config(&config.outbound_tx) {
+            Ok(queue) => queue.with_event_store(event_store.clone()),
+            Err(e) => {
+                return Err(ServerError::Startup(format!("Failed to load outbound transaction queue: {}", e)));
+            }
+        };
+
+        match note_storage_rx.await {
+            Ok(handle) => storage_handles.push(handle),
+            Err(_) => tracing::warn!("Note storage unavailable for maintenance"),
+        }
+
+        // State-changing requests are recorded for operators, apart from the event store
+        let request_audit = if config.request_audit.enabled {
+            let dir = config.request_audit.dir(&config.storage);
+            tracing::info!("Recording state-changing requests in {}", dir.display());
+            match RequestAuditLog::open(&dir) {
+                Ok(log) => log,
+                Err(e) => {
+                    return Err(ServerError::Startup(format!("Failed to start request audit log writer: {}", e)));
+                }
+            }
+        } else {
+            RequestAuditLog::default()
+        };
+
+        // Shares scan progress with the scanner loop
+        let stall_scanner = ergo_scanner.clone();
+        #[cfg(unix)]
+        let reload_scanner = ergo_scanner.clone();
+        let app_state = AppState {
+            tx,
+            event_store,
+            ergo_scanner: std::sync::Arc::new(Mutex::new(ergo_scanner)),
+            reserve_tracker: scanner_reserve_tracker,
+            config: std::sync::Arc::new(config.clone()),
+            shared_tracker_state: std::sync::Arc::new(tokio::sync::Mutex::new(shared_tracker_state_for_updater)),
+            tracker_storage,
+            acceptance_predicate,
+            signing_service: std::sync::Arc::new(SigningService::from_config(&config)),
+            cold_start: cold_start.clone(),
+            replica: ReplicaState::default(),
+            issuer_policy,
+            maintenance: StorageMaintenance::new(storage_handles),
+            pricing,
+            outbound_tx,
+            request_audit: request_audit.clone(),
+            contracts,
+        };
+
+        // Record reserve events derived by the scanner in the event store
+        let reserve_event_task = tokio::spawn(reserve_event_task(
+            app_state.clone(),
+            reserve_event_queue,
+            reserve_events,
+            shutdown.subscribe(),
+        ));
+        shutdown.register("Reserve event processor", reserve_event_task);
+
+        // Cross-verify tracker box commitments against the local AVL roots
+        if let Some(tracker_scanner) = tracker_scanner {
+            let interval = std::time::Duration::from_secs(config.scanner.tracker_verification_interval_secs);
+            let verification_task = tokio::spawn(tracker_verification_task(
+                app_state.clone(),
+                tracker_scanner,
+                interval,
+                shutdown.subscribe(),
+            ));
+            shutdown.register("Tracker commitment verifier", verification_task);
+        }
+
+        // Record an event when live reserve scanning falls behind the chain tip
+        let stall_task = tokio::spawn(scanner_stall_task(
+            app_state.clone(),
+            stall_scanner,
+            config.scanner.max_block_lag,
+            config.scan_loop_config().scan_interval,
+            shutdown.subscribe(),
+        ));
+        shutdown.register("Scanner stall monitor", stall_task);
+
+        // Follow the primary's commitments when running as a replica
+        if let Some(primary_url) = &config.replica.primary_url {
+            match config.tracker_public_key_bytes() {
+                Ok(Some(tracker_pubkey)) => {
+                    tracing::info!("Running as a read-only replica of {}", primary_url);
+                    let sync_task = tokio::spawn(replica_sync_task(
+                        app_state.clone(),
+                        config.replica.clone(),
+                        hex::encode(tracker_pubkey),
+                        shutdown.subscribe(),
+                    ));
+                    shutdown.register("Replica sync", sync_task);
+                }
+                _ => tracing::error!("Replica mode needs the primary's ergo.tracker_public_key; not syncing"),
+            }
+        }
+
+        // Compact and check the databases on a schedule
+        if config.maintenance.enabled {
+            tracing::info!("Storage maintenance every {}s", config.maintenance.interval_secs);
+            let maintenance_task = tokio::spawn(maintenance_task(
+                app_state.maintenance.clone(),
+                app_state.tx.clone(),
+                config.maintenance.clone(),
+                shutdown.subscribe(),
+            ));
+            shutdown.register("Storage maintenance", maintenance_task);
+        }
+
+        // Record each issuer's debt and collateral for the history endpoint
+        if config.collateral_history.enabled {
+            tracing::info!("Collateral snapshots every {}s", config.collateral_history.interval_secs);
+            let history_task = tokio::spawn(collateral_history_task(
+                app_state.clone(),
+                config.collateral_history.clone(),
+                shutdown.subscribe(),
+            ));
+            shutdown.register("Collateral history", history_task);
+        }
+
+        // Submit queued transactions to the node and follow them until settled
+        let outbound_task = tokio::spawn(outbound_tx_task(
+            app_state.outbound_tx.clone(),
+            OutboundNode::new(&config.ergo.node.node_url, config.ergo.node.api_key.as_deref()),
+            config.outbound_tx.clone(),
+            shutdown.subscribe(),
+        ));
+        shutdown.register("Outbound transaction queue", outbound_task);
+
+        // Apply scan timing and debt ceiling changes on SIGHUP
+        #[cfg(unix)]
+        if let Some(args) = self.reload_args {
+            let reload_task = tokio::spawn(crate::daemon::reload_on_sighup(
+                args,
+                config.clone(),
+                reload_scanner,
+                app_state.tx.clone(),
+                shutdown.subscribe(),
+            ));
+            shutdown.register("Configuration reload", reload_task);
+        }
+
+        // Delete request audit entries past their retention
+        if config.request_audit.enabled && config.request_audit.retention_days > 0 {
+            let retention_task = tokio::spawn(request_audit_retention_task(
+                request_audit.clone(),
+                config.request_audit.clone(),
+                shutdown.subscribe(),
+            ));
+            shutdown.register("Request audit retention", retention_task);
+        }
+
+        // Push collateral, redemption and commitment events to configured webhooks
+        if !config.webhooks.endpoints.is_empty() {
+            tracing::info!("Delivering events to {} webhook endpoint(s)", config.webhooks.endpoints.len());
+            let dispatcher = crate::webhooks::WebhookDispatcher::new(config.webhooks.clone());
+            let webhook_task = tokio::spawn(dispatcher.run(app_state.event_store.subscribe(), shutdown.subscribe()));
+            shutdown.register("Webhook dispatcher", webhook_task);
+        }
+
+        // Responses to POST requests carrying an Idempotency-Key are replayed on retry
+        let idempotency_store = Arc::new(IdempotencyStore::new(config.idempotency.clone()));
+        let idempotency = || middleware::from_fn_with_state(idempotency_store.clone(), idempotency_layer);
+        if config.idempotency.enabled {
+            tracing::info!(
+                "Idempotency keys enabled (window: {}s, max entries: {})",
+                config.idempotency.window_secs,
+                config.idempotency.max_entries
+            );
+        }
+
+        // State-changing requests require an API key when any are configured
+        let auth_config = Arc::new(config.auth.clone());
+        if !config.auth.api_keys.is_empty() {
+            tracing::info!("API key authentication enabled for state-changing endpoints");
+        }
+
+        // A watcher refuses endpoints that act for the tracker
+        let watcher_config = Arc::new(config.watcher.clone());
+        if config.watcher.enabled {
+            tracing::info!("Running in watcher mode: redemption and signing endpoints disabled");
+        }
+
+        // A replica refuses every request that would change its state
+        let replica_config = Arc::new(config.replica.clone());
+
+        // Build our application with routes - FIXED ROUTE ORDER
+        let app = Router::new()
+            // Root route
+            .route("/", get(root))
+            // Static routes
+            .route("/events", get(get_events))
+            .route("/events/paginated", get(get_events_paginated))
+            .route("/events/stream", get(stream_events))
+            .route("/events/issuer/{pubkey}", get(get_issuer_events))
+            .route("/notes", post(create_note).layer(idempotency()).options(handle_options))
+            .route("/notes/ack", post(acknowledge_note).options(handle_options))
+            .route("/notes/validate", post(validate_note).options(handle_options))
+            .route("/notes/split", post(create_split_note).options(handle_options))
+            .route("/notes/split/issuer/{pubkey}", get(get_split_notes_by_issuer))
+            .route("/notes/history", get(get_note_history))
+            .route("/notes/search", get(search_notes))
+            .route("/notes/export", get(get_notes_export))
+            .route("/acceptance/check", post(check_acceptance).options(handle_options))
+            .route("/disputes", post(file_dispute).get(get_disputes).options(handle_options))
+            .route("/identity/rotate", post(rotate_issuer_key).options(handle_options))
+            .route("/redeem", post(initiate_redemption).layer(idempotency()).options(handle_options))
+            .route("/redeem/validate", post(validate_redemption).options(handle_options))
+            .route("/redeem/eligibility", get(get_redemption_eligibility))
+            .route(
+                "/redeem/complete",
+                post(complete_redemption).layer(idempotency()).options(handle_options),
+            )
+            .route("/proof/redemption", get(get_redemption_proof))
+            .route("/proof/bundle", get(get_proof_bundle))
+            .route("/tracker/proof", get(get_tracker_proof))
+            .route("/reserve/proof", get(get_reserve_proof))
+            .route("/tracker/signature", post(request_tracker_signature).options(handle_options))
+            .route("/redemption/prepare", post(prepare_redemption).options(handle_options))
+            .route("/reserves", get(get_all_reserves))
+            .route("/reserves/create", post(create_reserve_payload).options(handle_options))
+            .route("/wallet/redeem", post(wallet_redeem).options(handle_options))
+            .route("/wallet/reserves/create", post(wallet_create_reserve).options(handle_options))
+            .route("/transactions", post(submit_transaction).get(get_transactions).options(handle_options))
+            // Most specific parameterized routes first
+            .route(
+                "/notes/issuer/{issuer_pubkey}/recipient/{recipient_pubkey}",
+                get(get_note_by_issuer_and_recipient),
+            )
+            .route(
+                "/notes/issuer/{issuer_pubkey}/recipient/{recipient_pubkey}/payload",
+                get(get_note_payload),
+            )
+            // Parameterized routes
+            .route("/notes/issuer/{pubkey}", get(get_notes_by_issuer))
+            .route("/notes/recipient/{pubkey}", get(get_notes_by_recipient))
+            .route("/notes", get(get_all_notes)) // Get all notes with age
+            .route("/disputes/{id}", get(get_dispute))
+            .route("/disputes/{id}/resolve", post(resolve_dispute).options(handle_options))
+            .route("/identity/{pubkey}/keys", get(get_identity_keys))
+            .route("/reserves/{box_id}", get(get_reserve_by_box_id))
+            .route("/transactions/{tx_id}", get(get_transaction))
+            .route("/reserves/{box_id}/history", get(get_reserve_history))
+            .route("/reserves/issuer/{pubkey}", get(get_reserves_by_issuer))
+            .route("/wallet/reserves/{box_id}/top-up", post(wallet_top_up).options(handle_options))
+            .route("/ergopay/reserves/{box_id}/top-up/{amount}/{address}", get(ergopay_top_up))
+            .route("/key-status/{pubkey}", get(get_key_status))
+            .route("/key-status/{pubkey}/history", get(get_key_status_history))
+            .route("/coverage/{recipient_pubkey}", get(get_coverage))
+            .route("/tracker/latest-box-id", get(get_latest_tracker_box_id))
+            .route("/scanner/status", get(get_scanner_status))
+            .route("/metrics", get(get_metrics))
+            .route("/stats", get(get_network_stats))
+            .route("/balance", get(get_balance))
+            .route("/netting/suggestions", get(get_netting_suggestions))
+            .route("/state", get(get_tracker_state))
+            .route("/verification/status", get(get_verification_status))
+            .route("/commitments", get(get_commitments))
+            .route("/commitments/{height}/delta", get(get_commitment_delta))
+            .route("/replica/status", get(get_replica_status))
+            .route("/audit/report", get(get_audit_report))
+            .route("/reports/notes.csv", get(get_notes_report_csv))
+            .route("/reports/notes.json", get(get_notes_report_json))
+            .route("/reports/reserves.csv", get(get_reserves_report_csv))
+            .route("/reports/reserves.json", get(get_reserves_report_json))
+            .route("/reports/ledger.journal", get(get_ledger_report_hledger))
+            .route("/reports/ledger.beancount", get(get_ledger_report_beancount))
+            .route("/config/reserve-contract-p2s", get(get_basis_reserve_contract_p2s))
+            .route("/admin/commit", post(force_commit))
+            .route("/admin/rescan", post(rescan_reserves))
+            .route("/admin/rebuild-avl", post(rebuild_avl_tree))
+            .route("/admin/rotate-logs", post(rotate_logs))
+            .route("/admin/flush", post(flush_storage))
+            .route("/admin/maintenance", get(get_maintenance).post(run_maintenance))
+            .route("/admin/issuer-policy", get(get_issuer_policy))
+            .route("/admin/issuer-policy/allow", post(allow_issuer))
+            .route("/admin/issuer-policy/deny", post(deny_issuer))
+            .route("/admin/issuer-policy/clear", post(clear_issuer))
+            .route("/admin/issuer-policy/tags", post(set_issuer_tags))
+            .route("/admin/rates", get(get_rates).post(set_rate))
+            .route("/admin/rates/remove", post(remove_rate))
+            .route("/admin/request-audit", get(get_request_audit))
+            .with_state(app_state.clone())
+            .layer(middleware::from_fn_with_state(
+                config.tracker_queue.retry_after_secs,
+                saturation_layer,
+            ))
+            .layer(middleware::from_fn_with_state(watcher_config, watcher_layer))
+            .layer(middleware::from_fn_with_state(replica_config, replica_layer))
+            .layer(middleware::from_fn_with_state(cold_start, degraded_layer))
+            .layer(middleware::from_fn_with_state(auth_config.clone(), api_key_layer))
+            .layer(middleware::from_fn_with_state(auth_config, admin_token_layer))
+            // Outside authentication, so rejected requests are recorded too
+            .layer(middleware::from_fn_with_state(request_audit, request_audit_layer))
+            .layer(tower_http::trace::TraceLayer::new_for_http())
+            .layer(middleware::from_fn(request_id_layer))
+            .layer(
+                CorsLayer::new()
+                    .allow_origin(Any)
+                    .allow_methods(Any)
+                    .allow_headers(Any),
+            );
+
+        tracing::debug!("Router built successfully");
+        tracing::debug!("Registered routes:");
+        tracing::debug!("  GET /");
+        tracing::debug!("  POST /notes");
+        tracing::debug!("  POST /notes/ack");
+        tracing::debug!("  POST /notes/validate");
+        tracing::debug!("  POST /notes/split");
+        tracing::debug!("  GET /notes/split/issuer/{{pubkey}}");
+        tracing::debug!("  GET /notes/history?issuer=..&recipient=..&page=..&page_size=..");
+        tracing::debug!("  GET /notes/search?issuer=..&recipient=..&min_amount=..&max_amount=..&from_timestamp=..&to_timestamp=..&outstanding=..&sort=..&order=..");
+        tracing::debug!("  GET /notes/issuer/{{pubkey}}");
+        tracing::debug!("  GET /notes/recipient/{{pubkey}}");
+        tracing::debug!("  GET /notes/issuer/{{issuer_pubkey}}/recipient/{{recipient_pubkey}}");
+        tracing::debug!("  GET /notes/issuer/{{issuer_pubkey}}/recipient/{{recipient_pubkey}}/payload");
+        tracing::debug!("  GET /notes (all notes with age)");
+        tracing::debug!("  GET /notes/export?issuer=..&from_timestamp=..&to_timestamp=..");
+        tracing::debug!("  POST /disputes");
+        tracing::debug!("  GET /disputes?issuer=..&recipient=..&status=..");
+        tracing::debug!("  GET /disputes/{{id}}");
+        tracing::debug!("  POST /disputes/{{id}}/resolve");
+        tracing::debug!("  POST /identity/rotate");
+        tracing::debug!("  GET /identity/{{pubkey}}/keys");
+        tracing::debug!("  GET /reports/notes.csv?issuer=..&from_timestamp=..&to_timestamp=..");
+        tracing::debug!("  GET /reports/notes.json?issuer=..&from_timestamp=..&to_timestamp=..");
+        tracing::debug!("  GET /reports/reserves.csv?issuer=..&from_timestamp=..&to_timestamp=..");
+        tracing::debug!("  GET /reports/reserves.json?issuer=..&from_timestamp=..&to_timestamp=..");
+        tracing::debug!("  GET /reports/ledger.journal?account=..&from_timestamp=..&to_timestamp=..");
+        tracing::debug!("  GET /reports/ledger.beancount?account=..&from_timestamp=..&to_timestamp=..");
+        tracing::debug!("  GET /reserves");
+        tracing::debug!("  GET /reserves/{{box_id}}");
+        tracing::debug!("  GET /reserves/{{box_id}}/history");
+        tracing::debug!("  GET /reserves/issuer/{{pubkey}}");
+        tracing::debug!("  POST /reserves/create");
+        tracing::debug!("  POST /wallet/redeem");
+        tracing::debug!("  POST /wallet/reserves/create");
+        tracing::debug!("  POST /transactions");
+        tracing::debug!("  GET /transactions?status=..");
+        tracing::debug!("  GET /transactions/{{tx_id}}");
+        tracing::debug!("  POST /wallet/reserves/{{box_id}}/top-up");
+        tracing::debug!("  GET /ergopay/reserves/{{box_id}}/top-up/{{amount}}/{{address}}");
+        tracing::debug!("  GET /events");
+        tracing::debug!("  GET /events/paginated");
+        tracing::debug!("  GET /events/issuer/{{pubkey}}?page=..&page_size=..");
+        tracing::debug!("  GET /key-status/{{pubkey}}");
+        tracing::debug!("  GET /key-status/{{pubkey}}/history");
+        tracing::debug!("  GET /coverage/{{recipient_pubkey}}");
+        tracing::debug!("  POST /redeem");
+        tracing::debug!("  POST /redeem/validate");
+        tracing::debug!("  GET /proof/bundle?recipient=..");
+        tracing::debug!("  GET /tracker/latest-box-id");
+        tracing::debug!("  GET /scanner/status");
+        tracing::debug!("  GET /metrics");
+        tracing::debug!("  GET /stats?top=..&days=..");
+        tracing::debug!("  GET /balance?issuer=..&recipient=..");
+        tracing::debug!("  GET /netting/suggestions?max_cycle_length=..&pubkey=..");
+        tracing::debug!("  GET /state");
+        tracing::debug!("  GET /verification/status");
+        tracing::debug!("  GET /commitments");
+        tracing::debug!("  GET /commitments/{{height}}/delta");
+        tracing::debug!("  GET /replica/status");
+        tracing::debug!("  GET /audit/report");
+        tracing::debug!("  POST /admin/commit");
+        tracing::debug!("  POST /admin/rescan");
+        tracing::debug!("  POST /admin/rebuild-avl");
+        tracing::debug!("  POST /admin/rotate-logs");
+        tracing::debug!("  POST /admin/flush");
+        tracing::debug!("  GET /admin/maintenance");
+        tracing::debug!("  POST /admin/maintenance");
+        tracing::debug!("  GET /admin/issuer-policy");
+        tracing::debug!("  POST /admin/issuer-policy/allow");
+        tracing::debug!("  POST /admin/issuer-policy/deny");
+        tracing::debug!("  POST /admin/issuer-policy/clear");
+        tracing::debug!("  POST /admin/issuer-policy/tags");
+        tracing::debug!("  GET /admin/rates");
+        tracing::debug!("  POST /admin/rates");
+        tracing::debug!("  POST /admin/rates/remove");
+        tracing::debug!("  GET /admin/request-audit?from=..&to=..&client_ip=..&path=..&method=..&status=..&credential=..");
+
+        let addr = config.socket_addr();
+        let listener = match self.listener {
+            Some(listener) => listener,
+            None => TcpListener::bind(addr)
+                .await
+                .map_err(|source| ServerError::Bind { addr, source })?,
+        };
+        match listener.local_addr() {
+            Ok(addr) => tracing::info!("Server listening on {}", addr),
+            Err(_) => tracing::info!("Server listening"),
+        }
+
+        Ok(Server {
+            state: app_state,
+            router: app,
+            listener,
+            shutdown,
+            shutdown_timeout: std::time::Duration::from_secs(config.server.shutdown_timeout_secs),
+        })
+    }
+}
+
+/// Handle OPTIONS preflight requests for CORS
+async fn handle_options() -> impl axum::response::IntoResponse {
+    (
+        axum::http::StatusCode::OK,
+        [("Access-Control-Allow-Origin", "*")],
+        "",
+    )
+}
+
+/// Record a local AVL root at the last known chain height
+fn record_local_root(
+    tracker_storage: &TrackerStorage,
+    metadata_storage: &ScannerMetadataStorage,
+    root_digest: &[u8; 33],
+) {
+    let height = match metadata_storage.get_blockchain_height() {
+        Ok(Some((height, _))) => height,
+        _ => 0,
+    };
+    if let Err(e) = tracker_storage.record_local_root(height, root_digest) {
+        tracing::warn!("Failed to record local AVL root: {:?}", e);
+    }
+}
+
+/// Record the local root produced by a change of one note, with the note
+/// as it is now, so commitment deltas can list it
+fn record_note_change(
+    tracker_storage: &TrackerStorage,
+    metadata_storage: &ScannerMetadataStorage,
+    root_digest: &[u8; 33],
+    tracker: &basis_store::TrackerStateManager,
+    issuer_pubkey: &basis_store::PubKey,
+    recipient_pubkey: &basis_store::PubKey,
+) {
+    let note = match tracker.lookup_note(issuer_pubkey, recipient_pubkey) {
+        Ok(note) => note,
+        Err(e) => {
+            tracing::warn!("Changed note not found, recording the root alone: {:?}", e);
+            return record_local_root(tracker_storage, metadata_storage, root_digest);
+        }
+    };
+    let height = match metadata_storage.get_blockchain_height() {
+        Ok(Some((height, _))) => height,
+        _ => 0,
+    };
+    let change = basis_store::delta_note(issuer_pubkey, &note);
+    if let Err(e) = tracker_storage.record_note_change(height, root_digest, &change) {
+        tracing::warn!("Failed to record local AVL root: {:?}", e);
+    }
+}
+
+/// Periodically check new tracker boxes against the local AVL roots until shutdown
+///
+/// Divergent commitments are recorded as events.
+async fn tracker_verification_task(
+    state: AppState,
+    tracker_scanner: TrackerServerState,
+    interval: std::time::Duration,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            _ = ticker.tick() => {}
+        }
+
+        let tracker_boxes = match tracker_scanner.process_tracker_boxes().await {
+            Ok(tracker_boxes) => tracker_boxes,
+            Err(e) => {
+                tracing::warn!("Failed to process tracker boxes for verification: {}", e);
+                continue;
+            }
+        };
+
+        let checks = match tracker_scanner.update_tracker_state(&tracker_boxes).await {
+            Ok(checks) => checks,
+            Err(e) => {
+                tracing::error!("Failed to verify tracker commitments: {}", e);
+                continue;
+            }
+        };
+
+        let divergences: Vec<TrackerEvent> = checks
+            .into_iter()
+            .filter(|check| check.status == CommitmentStatus::Diverged)
+            .map(|check| TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::CommitmentDivergence,
+                timestamp: check.checked_at,
+                issuer_pubkey: None,
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: None,
+                collateral_amount: None,
+                redeemed_amount: None,
+                height: Some(check.height),
+            })
+            .collect();
+        if divergences.is_empty() {
+            continue;
+        }
+        if let Err(e) = state.event_store.add_events(divergences).await {
+            tracing::warn!("Failed to record commitment divergence events: {:?}", e);
+        }
+    }
+}
+
+/// Record a `ScannerStalled` event whenever live reserve scanning falls more
+/// than `max_block_lag` blocks behind the chain tip, until shutdown
+///
+/// One event is recorded per stall; another is only recorded once the
+/// scanner has caught up and fallen behind again.
+async fn scanner_stall_task(
+    state: AppState,
+    scanner: ServerState,
+    max_block_lag: u64,
+    interval: std::time::Duration,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    let mut ticker = tokio::time::interval(interval);
+    let mut stalled = false;
+
+    loop {
+        tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            _ = ticker.tick() => {}
+        }
+
+        let progress = scanner.progress().await;
+        if !progress.is_stalled(max_block_lag) {
+            if stalled {
+                tracing::info!("Reserve scanner caught up at height {}", progress.scanned_height);
+            }
+            stalled = false;
+            continue;
+        }
+        if stalled {
+            continue;
+        }
+        stalled = true;
+        tracing::warn!(
+            "Reserve scanner stalled at height {}, {} blocks behind the tip",
+            progress.scanned_height,
+            progress.block_lag
+        );
+        let event = TrackerEvent {
+            id: 0,
+            seq: 0,
+            event_type: EventType::ScannerStalled { lag_blocks: progress.block_lag },
+            timestamp: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            issuer_pubkey: None,
+            recipient_pubkey: None,
+            amount: None,
+            reserve_box_id: None,
+            collateral_amount: None,
+            redeemed_amount: None,
+            height: Some(progress.scanned_height),
+        };
+        if let Err(e) = state.event_store.add_events(vec![event]).await {
+            tracing::warn!("Failed to record scanner stall event: {:?}", e);
+        }
+    }
+}
+
+/// Store reserve events derived by the scanner until shutdown
+///
+/// Events left unprocessed by a previous run are replayed from the scanner's
+/// intake queue first. Events already queued when one arrives (e.g. during a
+/// backfill) are stored together in one batch.
+async fn reserve_event_task(
+    state: AppState,
+    queue: ReserveStorage,
+    mut events: tokio::sync::broadcast::Receiver<QueuedReserveEvent>,
+    mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) {
+    use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+
+    replay_reserve_events(&state, &queue).await;
+
+    loop {
+        let first = tokio::select! {
+            _ = shutdown_rx.recv() => break,
+            event = events.recv() => match event {
+                Ok(event) => event,
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Reserve event processor fell behind by {} events, reading the queue", skipped);
+                    replay_reserve_events(&state, &queue).await;
+                    continue;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+
+        let mut batch = vec![first];
+        loop {
+            match events.try_recv() {
+                Ok(event) => batch.push(event),
+                Err(TryRecvError::Lagged(skipped)) => {
+                    // The queue holds the skipped events as well as the batch
+                    tracing::warn!("Reserve event processor fell behind by {} events, reading the queue", skipped);
+                    batch = match queue.unprocessed_reserve_events() {
+                        Ok(queued) => queued,
+                        Err(e) => {
+                            tracing::warn!("Failed to read the reserve event queue: {:?}", e);
+                            batch
+                        }
+                    };
+                }
+                Err(TryRecvError::Empty | TryRecvError::Closed) => break,
+            }
+        }
+        store_reserve_events(&state, &queue, batch, false).await;
+    }
+}
+
+/// Process the reserve events a previous run or a lagging receiver left in the queue
+async fn replay_reserve_events(state: &AppState, queue: &ReserveStorage) {
+    match queue.unprocessed_reserve_events() {
+        Ok(queued) if queued.is_empty() => {}
+        Ok(queued) => {
+            tracing::info!("Replaying {} unprocessed reserve events", queued.len());
+            store_reserve_events(state, queue, queued, true).await;
+        }
+        Err(e) => tracing::warn!("Failed to read the reserve event queue: {:?}", e),
+    }
+}
+
+/// Process queued reserve events, store them in one batch and mark them
+/// processed in the queue
+///
+/// Events no longer pending are skipped, so one delivered both by the
+/// channel and by a replay is processed once. A failed store leaves the
+/// events queued, to be retried on the next start. A crash between storing
+/// and marking leaves stored events pending, so `replayed` events are first
+/// looked up in the event store.
+///
+/// Redemptions of this tracker's reserves whose receiver the scanner read
+/// from the chain complete their note once stored, unless already recorded.
+async fn store_reserve_events(
+    state: &AppState,
+    queue: &ReserveStorage,
+    queued: Vec<QueuedReserveEvent>,
+    replayed: bool,
+) {
+    let mut processed = Vec::new();
+    let mut batch = Vec::new();
+    let mut redemptions = Vec::new();
+    for queued in queued {
+        match queue.is_reserve_event_pending(queued.height, queued.sequence) {
+            Ok(true) => {}
+            Ok(false) => continue,
+            Err(e) => {
+                tracing::warn!("Failed to look up queued reserve event: {:?}", e);
+                continue;
+            }
+        }
+        let tracker_event = reserve_tracker_event(queued.event.clone());
+        if !(replayed && already_recorded(state, &tracker_event).await) {
+            redemptions.extend(observed_redemption(state, &queued.event));
+            batch.push(tracker_event);
+        }
+        processed.push(queued);
+    }
+
+    if !batch.is_empty() {
+        if let Err(e) = state.event_store.add_events(batch).await {
+            tracing::warn!("Failed to store reserve events: {}", e);
+            return;
+        }
+    }
+    for queued in processed {
+        if let Err(e) = queue.mark_reserve_event_processed(queued.height, queued.sequence) {
+            tracing::warn!("Failed to mark reserve event processed: {:?}", e);
+        }
+    }
+    for (issuer_pubkey, recipient_pubkey, redeemed_amount) in redemptions {
+        complete_observed_redemption(state, issuer_pubkey, recipient_pubkey, redeemed_amount).await;
+    }
+}
+
+/// Note a redemption observed on chain completes: issuer, recipient and the
+/// amount taken from the reserve
+///
+/// Only redemptions against this tracker's NFT count, and only when
+/// `scanner.auto_complete_redemptions` is set.
+fn observed_redemption(
+    state: &AppState,
+    event: &ReserveEvent,
+) -> Option<(basis_store::PubKey, basis_store::PubKey, u64)> {
+    let ReserveEvent::ReserveRedeemed {
+        owner_pubkey,
+        redeemed_amount,
+        recipient_pubkey: Some(recipient_pubkey),
+        tracker_nft_id: Some(tracker_nft_id),
+        ..
+    } = event
+    else {
+        return None;
+    };
+    let our_nft = state.config.ergo.tracker_nft_id.as_deref()?;
+    if !state.config.scanner.auto_complete_redemptions || !our_nft.eq_ignore_ascii_case(tracker_nft_id) {
+        return None;
+    }
+    let pubkey = |key: &str| hex::decode(key).ok()?.try_into().ok();
+    Some((pubkey(owner_pubkey)?, pubkey(recipient_pubkey)?, *redeemed_amount))
+}
+
+/// Complete the note of a redemption observed on chain
+async fn complete_observed_redemption(
+    state: &AppState,
+    issuer_pubkey: basis_store::PubKey,
+    recipient_pubkey: basis_store::PubKey,
+    redeemed_amount: u64,
+) {
+    let request = requests::CompleteRedemption {
+        issuer_pubkey,
+        recipient_pubkey,
+        redeemed_amount,
+    };
+    match state.send(request).await {
+        Ok(Ok(())) => tracing::info!(
+            "Redemption of {} nanoERG observed on chain completed for {} -> {}",
+            redeemed_amount,
+            hex::encode(issuer_pubkey),
+            hex::encode(recipient_pubkey)
+        ),
+        Ok(Err(e)) => tracing::warn!(
+            "Failed to complete redemption observed on chain for {} -> {}: {}",
+            hex::encode(issuer_pubkey),
+            hex::encode(recipient_pubkey),
+            e
+        ),
+        Err(e) => tracing::error!("Failed to complete redemption observed on chain: {}", e),
+    }
+}
+
+/// Whether the event store already holds the event a reserve event produces
+async fn already_recorded(state: &AppState, event: &TrackerEvent) -> bool {
+    let query = EventQuery {
+        event_type: Some(event.event_type.name().to_string()),
+        issuer: event.issuer_pubkey.clone(),
+        from_height: event.height,
+        to_height: event.height,
+        limit: usize::MAX,
+        ..EventQuery::default()
+    };
+    match state.event_store.query_events(&query).await {
+        Ok(stored) => stored.iter().any(|stored| {
+            stored.reserve_box_id == event.reserve_box_id
+                && stored.collateral_amount == event.collateral_amount
+                && stored.redeemed_amount == event.redeemed_amount
+        }),
+        Err(e) => {
+            tracing::warn!("Failed to look up stored reserve events: {}", e);
+            false
+        }
+    }
+}
+
+/// Build the event to store for a reserve event
+///
+/// The scanner has already applied the event to the reserve tracker it shares
+/// with the API.
+fn reserve_tracker_event(event: ReserveEvent) -> TrackerEvent {
+    match event {
+        ReserveEvent::ReserveCreated {
+            box_id,
+            owner_pubkey,
+            collateral_amount,
+            height,
+        } => {
+            tracing::info!(
+                "Reserve created: {} with {} nanoERG at height {}",
+                box_id,
+                collateral_amount,
+                height
+            );
+
+
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::ReserveCreated,
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+                issuer_pubkey: Some(owner_pubkey),
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: Some(box_id),
+                collateral_amount: Some(collateral_amount),
+                redeemed_amount: None,
+                height: Some(height),
+            }
+        }
+        ReserveEvent::ReserveToppedUp {
+            box_id,
+            owner_pubkey,
+            additional_collateral,
+            height,
+        } => {
+            tracing::info!(
+                "Reserve topped up: {} +{} nanoERG at height {}",
+                box_id,
+                additional_collateral,
+                height
+            );
+
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::ReserveToppedUp,
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+                issuer_pubkey: Some(owner_pubkey),
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: Some(box_id),
+                collateral_amount: Some(additional_collateral),
+                redeemed_amount: None,
+                height: Some(height),
+            }
+        }
+        ReserveEvent::ReserveRedeemed {
+            box_id,
+            owner_pubkey,
+            redeemed_amount,
+            height,
+            recipient_pubkey,
+            ..
+        } => {
+            tracing::info!(
+                "Reserve redeemed: {} -{} nanoERG at height {}",
+                box_id,
+                redeemed_amount,
+                height
+            );
+
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::ReserveRedeemed,
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+                issuer_pubkey: Some(owner_pubkey),
+                recipient_pubkey,
+                amount: None,
+                reserve_box_id: Some(box_id),
+                collateral_amount: None,
+                redeemed_amount: Some(redeemed_amount),
+                height: Some(height),
+            }
+        }
+        ReserveEvent::ReserveSpent {
+            box_id,
+            owner_pubkey,
+            height,
+        } => {
+            tracing::info!("Reserve spent: {} at height {}", box_id, height);
+
+            TrackerEvent {
+                id: 0,
+                seq: 0,
+                event_type: EventType::ReserveSpent,
+                timestamp: std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs(),
+                issuer_pubkey: Some(owner_pubkey),
+                recipient_pubkey: None,
+                amount: None,
+                reserve_box_id: Some(box_id),
+                collateral_amount: None,
+                redeemed_amount: None,
+                height: Some(height),
+            }
+        }
+    }
+}
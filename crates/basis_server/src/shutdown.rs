@@ -0,0 +1,174 @@
+//! Graceful shutdown coordination
+//!
+//! On SIGTERM or Ctrl-C the server stops accepting connections and lets
+//! in-flight requests finish. The tracker thread then drains its command
+//! queue and flushes storage, and the background tasks stop and are joined.
+//! Every task registered with the coordinator gets a shared timeout, so a
+//! stuck task cannot block process exit indefinitely.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+use tokio::sync::broadcast;
+use tokio::task::{AbortHandle, JoinHandle};
+
+/// Default time allowed for background tasks to stop after shutdown starts
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+// A registered task: its name, a handle to abort it and a future resolving when it ends
+type RegisteredTask = (String, AbortHandle, Pin<Box<dyn Future<Output = ()> + Send>>);
+
+/// Broadcasts the shutdown signal and tracks the tasks that must stop before exit
+pub struct ShutdownCoordinator {
+    shutdown_tx: broadcast::Sender<()>,
+    tasks: Vec<RegisteredTask>,
+}
+
+impl Default for ShutdownCoordinator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ShutdownCoordinator {
+    /// Create a coordinator with no registered tasks
+    pub fn new() -> Self {
+        let (shutdown_tx, _) = broadcast::channel(1);
+        Self {
+            shutdown_tx,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Receiver that fires when shutdown starts
+    pub fn subscribe(&self) -> broadcast::Receiver<()> {
+        self.shutdown_tx.subscribe()
+    }
+
+    /// Future that completes when shutdown starts, for `axum::serve(..).with_graceful_shutdown`
+    pub fn signalled(&self) -> impl Future<Output = ()> + Send + 'static {
+        let mut shutdown_rx = self.subscribe();
+        async move {
+            let _ = shutdown_rx.recv().await;
+        }
+    }
+
+    /// Register a task to be joined on shutdown
+    pub fn register<T: Send + 'static>(&mut self, name: &str, handle: JoinHandle<T>) {
+        let abort_handle = handle.abort_handle();
+        let finished = Box::pin(async move {
+            let _ = handle.await;
+        });
+        self.tasks.push((name.to_string(), abort_handle, finished));
+    }
+
+    /// Start shutdown when the process receives SIGTERM or Ctrl-C
+    pub fn trigger_on_signal(&self) {
+        self.trigger_on(wait_for_signal());
+    }
+
+    /// Start shutdown when `signal` resolves
+    pub fn trigger_on(&self, signal: impl Future<Output = ()> + Send + 'static) {
+        let shutdown_tx = self.shutdown_tx.clone();
+        tokio::spawn(async move {
+            signal.await;
+            let _ = shutdown_tx.send(());
+        });
+    }
+
+    /// Notify all subscribers that shutdown has started
+    pub fn trigger(&self) {
+        // No receivers simply means nothing is listening yet
+        let _ = self.shutdown_tx.send(());
+    }
+
+    /// Wait for every registered task to finish, up to `timeout` in total
+    ///
+    /// Returns the names of the tasks that were still running when the
+    /// timeout expired; those tasks are aborted.
+    pub async fn join(self, timeout: Duration) -> Vec<String> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut unfinished = Vec::new();
+
+        for (name, abort_handle, finished) in self.tasks {
+            match tokio::time::timeout_at(deadline, finished).await {
+                Ok(()) => tracing::info!("{} stopped", name),
+                Err(_) => {
+                    tracing::warn!("{} did not stop within the shutdown timeout", name);
+                    abort_handle.abort();
+                    unfinished.push(name);
+                }
+            }
+        }
+
+        unfinished
+    }
+}
+
+/// Wait for SIGTERM or Ctrl-C
+pub async fn wait_for_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl-C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => tracing::info!("Received Ctrl-C, shutting down"),
+        _ = terminate => tracing::info!("Received SIGTERM, shutting down"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_join_waits_for_subscribed_tasks() {
+        let mut coordinator = ShutdownCoordinator::new();
+        let mut shutdown_rx = coordinator.subscribe();
+        let (done_tx, done_rx) = tokio::sync::oneshot::channel();
+
+        let task = tokio::spawn(async move {
+            let _ = shutdown_rx.recv().await;
+            let _ = done_tx.send(());
+        });
+        coordinator.register("worker", task);
+
+        let signalled = coordinator.signalled();
+        coordinator.trigger();
+        signalled.await;
+
+        let unfinished = coordinator.join(Duration::from_secs(5)).await;
+        assert!(unfinished.is_empty());
+        assert!(done_rx.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_join_reports_tasks_exceeding_timeout() {
+        let mut coordinator = ShutdownCoordinator::new();
+        coordinator.register("stuck", tokio::spawn(std::future::pending::<()>()));
+        coordinator.register("finished", tokio::spawn(async {}));
+
+        coordinator.trigger();
+        let unfinished = coordinator.join(Duration::from_millis(50)).await;
+        assert_eq!(unfinished, vec!["stuck".to_string()]);
+    }
+}
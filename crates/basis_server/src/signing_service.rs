@@ -0,0 +1,524 @@
+//! Tracker-side signing of redemption messages
+//!
+//! A normal redemption needs the tracker's Schnorr signature over
+//! `blake2b256(issuer || recipient) || total_debt || timestamp`, the same
+//! message the issuer signed. The tracker only signs when the message matches
+//! the note it holds and the issuer's reserve can pay the redemption, and every
+//! signature it hands out is appended to an audit log. Where the key itself
+//! lives is up to the configured [`crate::tracker_signer::TrackerSigner`].
+
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use axum::http::StatusCode;
+use basis_store::reqwest;
+use basis_store::{IouNote, PubKey, Signature, SigningFormat};
+use basis_trees::{CommitmentDelta, ProofBundle};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::config::AppConfig;
+use crate::tracker_signer::{self, TrackerSigner};
+
+/// Reasons the tracker refuses to sign
+#[derive(Debug, thiserror::Error)]
+pub enum SigningError {
+    #[error("No note found for this issuer and recipient")]
+    NoteNotFound,
+    #[error("Total debt {requested} does not match the tracked total debt {tracked}")]
+    DebtMismatch { requested: u64, tracked: u64 },
+    #[error("Timestamp {requested} does not match the note timestamp {tracked}")]
+    TimestampMismatch { requested: u64, tracked: u64 },
+    #[error("Redemption amount {amount} exceeds the outstanding debt {outstanding}")]
+    ExceedsOutstandingDebt { amount: u64, outstanding: u64 },
+    #[error("No reserve found for issuer")]
+    NoReserve,
+    #[error("Insufficient collateral: reserve holds {collateral}, redemption needs {amount}")]
+    InsufficientCollateral { amount: u64, collateral: u64 },
+    #[error("Note is signed in format version {0}, which the reserve contract cannot verify")]
+    UnredeemableSigningFormat(u8),
+    #[error("Tracker key not configured: {0}")]
+    NotConfigured(String),
+    #[error("Failed to sign: {0}")]
+    Signing(String),
+}
+
+impl SigningError {
+    /// HTTP status reported for the error
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            SigningError::NoteNotFound | SigningError::NoReserve => StatusCode::NOT_FOUND,
+            SigningError::DebtMismatch { .. }
+            | SigningError::TimestampMismatch { .. }
+            | SigningError::ExceedsOutstandingDebt { .. }
+            | SigningError::InsufficientCollateral { .. }
+            | SigningError::UnredeemableSigningFormat(_) => StatusCode::BAD_REQUEST,
+            SigningError::NotConfigured(_) | SigningError::Signing(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        }
+    }
+}
+
+/// A redemption the tracker is asked to sign
+#[derive(Debug, Clone)]
+pub struct RedemptionSigningRequest {
+    pub issuer_pubkey: PubKey,
+    pub recipient_pubkey: PubKey,
+    /// Total debt of the note, as signed by the issuer
+    pub total_debt: u64,
+    /// Note timestamp in milliseconds since Unix epoch
+    pub timestamp: u64,
+    /// Amount to be paid out; the whole outstanding debt when absent
+    pub redeem_amount: Option<u64>,
+    /// Endpoint asking for the signature, recorded in the audit log
+    pub purpose: &'static str,
+}
+
+/// Signature produced for a redemption
+#[derive(Debug, Clone)]
+pub struct SignedRedemption {
+    pub message: Vec<u8>,
+    pub signature: Signature,
+    pub tracker_pubkey: PubKey,
+}
+
+/// One line of the signing audit log
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SigningAuditEntry {
+    /// When the signature was produced, in milliseconds since Unix epoch
+    pub signed_at: u64,
+    pub purpose: String,
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    pub total_debt: u64,
+    pub timestamp: u64,
+    pub redeem_amount: u64,
+    pub collateral: u64,
+    /// `local`, `node` or `remote`
+    pub signer: String,
+    pub message: String,
+    pub signature: String,
+}
+
+/// Append-only JSON lines log of every signature handed out
+#[derive(Debug)]
+pub struct SigningAuditLog {
+    path: PathBuf,
+    // Serialises appends from concurrent requests
+    lock: Mutex<()>,
+}
+
+impl SigningAuditLog {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn path(&self) -> &std::path::Path {
+        &self.path
+    }
+
+    /// Append an entry, creating the file and its directory when missing
+    pub async fn append(&self, entry: &SigningAuditEntry) -> std::io::Result<()> {
+        let _guard = self.lock.lock().await;
+        if let Some(parent) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut line = serde_json::to_vec(entry)?;
+        line.push(b'\n');
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        file.write_all(&line)?;
+        file.sync_data()
+    }
+
+    /// Move the log aside to `<path>.<millis since epoch>`, so the next entry
+    /// starts a new file
+    ///
+    /// Returns where the log was moved, or None when nothing was logged yet.
+    pub async fn rotate(&self) -> std::io::Result<Option<PathBuf>> {
+        let _guard = self.lock.lock().await;
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let mut rotated = self.path.clone().into_os_string();
+        rotated.push(format!(".{}", millis));
+        let rotated = PathBuf::from(rotated);
+        match std::fs::rename(&self.path, &rotated) {
+            Ok(()) => Ok(Some(rotated)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Every entry logged so far, oldest first
+    pub async fn entries(&self) -> std::io::Result<Vec<SigningAuditEntry>> {
+        let _guard = self.lock.lock().await;
+        let content = match std::fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e),
+        };
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(std::io::Error::other))
+            .collect()
+    }
+}
+
+/// Holds the tracker key and signs validated redemptions
+#[derive(Debug)]
+pub struct SigningService {
+    tracker_pubkey: Option<PubKey>,
+    signer: Option<Arc<dyn TrackerSigner>>,
+    // Why no signer could be set up
+    signer_error: Option<String>,
+    audit_log: SigningAuditLog,
+}
+
+impl SigningService {
+    /// Service using the tracker key from the configuration
+    ///
+    /// Signatures come from the signer in the `signer` section; see
+    /// [`crate::tracker_signer`]. A signer that cannot be set up, such as an
+    /// unreadable key file, makes every signing request fail.
+    pub fn from_config(config: &AppConfig) -> Self {
+        let (signer, signer_error) = match tracker_signer::from_config(config) {
+            Ok(signer) => (Some(signer), None),
+            Err(e) => {
+                tracing::error!("Tracker signer unavailable: {}", e);
+                (None, Some(e.to_string()))
+            }
+        };
+        Self {
+            tracker_pubkey: config.tracker_public_key_bytes().ok().flatten(),
+            signer,
+            signer_error,
+            audit_log: SigningAuditLog::new(config.storage.signing_audit_log_path.clone()),
+        }
+    }
+
+    pub fn tracker_pubkey(&self) -> Option<PubKey> {
+        self.tracker_pubkey
+    }
+
+    pub fn audit_log(&self) -> &SigningAuditLog {
+        &self.audit_log
+    }
+
+    /// Sign a redemption of `note` against a reserve holding `collateral`
+    ///
+    /// `collateral` is `None` when the issuer has no known reserve.
+    pub async fn sign_redemption(
+        &self,
+        request: &RedemptionSigningRequest,
+        note: Option<&IouNote>,
+        collateral: Option<u64>,
+    ) -> Result<SignedRedemption, SigningError> {
+        let note = note.ok_or(SigningError::NoteNotFound)?;
+        let redeem_amount = validate_redemption(request, note, collateral)?;
+        let collateral = collateral.unwrap_or_default();
+
+        let tracker_pubkey = self.tracker_pubkey.ok_or_else(|| {
+            SigningError::NotConfigured("ergo.tracker_public_key is not set".to_string())
+        })?;
+        let message = basis_store::schnorr::signing_message(
+            &request.issuer_pubkey,
+            &request.recipient_pubkey,
+            request.total_debt,
+            request.timestamp,
+        );
+
+        let (signature, signer) = self.sign_message(&message, &tracker_pubkey).await?;
+
+        let entry = SigningAuditEntry {
+            signed_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+            purpose: request.purpose.to_string(),
+            issuer_pubkey: hex::encode(request.issuer_pubkey),
+            recipient_pubkey: hex::encode(request.recipient_pubkey),
+            total_debt: request.total_debt,
+            timestamp: request.timestamp,
+            redeem_amount,
+            collateral,
+            signer: signer.to_string(),
+            message: hex::encode(&message),
+            signature: hex::encode(signature),
+        };
+        // A signature that cannot be accounted for is not handed out
+        self.audit_log.append(&entry).await.map_err(|e| {
+            SigningError::Signing(format!("Failed to write signing audit log: {}", e))
+        })?;
+
+        tracing::info!(
+            "Tracker signed {} redemption of {} from {} to {} (total debt {})",
+            request.purpose,
+            redeem_amount,
+            entry.issuer_pubkey,
+            entry.recipient_pubkey,
+            request.total_debt
+        );
+
+        Ok(SignedRedemption {
+            message,
+            signature,
+            tracker_pubkey,
+        })
+    }
+
+    /// Sign a recipient's proof bundle, filling in the tracker key and signature
+    ///
+    /// The bundle signature only attests to the bundle contents and cannot be
+    /// used for a redemption, so it is not written to the audit log.
+    pub async fn sign_proof_bundle(&self, bundle: &mut ProofBundle) -> Result<(), SigningError> {
+        let tracker_pubkey = self.tracker_pubkey.ok_or_else(|| {
+            SigningError::NotConfigured("ergo.tracker_public_key is not set".to_string())
+        })?;
+        bundle.tracker_pubkey = hex::encode(tracker_pubkey);
+        let message = bundle
+            .signing_message()
+            .map_err(|e| SigningError::Signing(format!("Failed to encode proof bundle: {}", e)))?;
+
+        let (signature, _) = self.sign_message(&message, &tracker_pubkey).await?;
+        bundle.tracker_signature = hex::encode(signature);
+        Ok(())
+    }
+
+    /// Sign a commitment delta, filling in the tracker key and signature
+    ///
+    /// Like bundle signatures, delta signatures only attest to the listed
+    /// notes and are not written to the audit log.
+    pub async fn sign_commitment_delta(&self, delta: &mut CommitmentDelta) -> Result<(), SigningError> {
+        let tracker_pubkey = self.tracker_pubkey.ok_or_else(|| {
+            SigningError::NotConfigured("ergo.tracker_public_key is not set".to_string())
+        })?;
+        delta.tracker_pubkey = hex::encode(tracker_pubkey);
+        let message = delta
+            .signing_message()
+            .map_err(|e| SigningError::Signing(format!("Failed to encode commitment delta: {}", e)))?;
+
+        let (signature, _) = self.sign_message(&message, &tracker_pubkey).await?;
+        delta.tracker_signature = hex::encode(signature);
+        Ok(())
+    }
+
+    // Sign with the configured signer, handing out only signatures that verify
+    async fn sign_message(
+        &self,
+        message: &[u8],
+        tracker_pubkey: &PubKey,
+    ) -> Result<(Signature, &'static str), SigningError> {
+        let signer = self.signer.as_ref().ok_or_else(|| {
+            SigningError::NotConfigured(self.signer_error.clone().unwrap_or_default())
+        })?;
+        let signature = signer.sign(message, tracker_pubkey).await?;
+        basis_store::schnorr::schnorr_verify(&signature, message, tracker_pubkey).map_err(|_| {
+            SigningError::Signing(format!(
+                "Signature from the {} signer does not verify against the tracker public key",
+                signer.kind()
+            ))
+        })?;
+        Ok((signature, signer.kind()))
+    }
+}
+
+// Check the request against the tracked note and the reserve; returns the redemption amount
+fn validate_redemption(
+    request: &RedemptionSigningRequest,
+    note: &IouNote,
+    collateral: Option<u64>,
+) -> Result<u64, SigningError> {
+    // The tracker signature is checked by the contract over the version 1
+    // message, alongside the issuer's signature of the same message
+    if note.signing_format != SigningFormat::V1 {
+        return Err(SigningError::UnredeemableSigningFormat(note.signing_format.version()));
+    }
+    if request.total_debt != note.amount_collected {
+        return Err(SigningError::DebtMismatch {
+            requested: request.total_debt,
+            tracked: note.amount_collected,
+        });
+    }
+    if request.timestamp != note.timestamp {
+        return Err(SigningError::TimestampMismatch {
+            requested: request.timestamp,
+            tracked: note.timestamp,
+        });
+    }
+
+    let outstanding = note.outstanding_debt();
+    let amount = request.redeem_amount.unwrap_or(outstanding);
+    if amount > outstanding {
+        return Err(SigningError::ExceedsOutstandingDebt {
+            amount,
+            outstanding,
+        });
+    }
+
+    let collateral = collateral.ok_or(SigningError::NoReserve)?;
+    if amount > collateral {
+        return Err(SigningError::InsufficientCollateral { amount, collateral });
+    }
+    Ok(amount)
+}
+
+// Structs for the Schnorr signing API
+#[derive(Serialize, Deserialize)]
+struct SchnorrSignRequest {
+    address: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct SchnorrSignResponse {
+    signature: String,
+}
+
+#[derive(Deserialize)]
+struct ErrorResponse {
+    error: Option<ErrorMessage>,
+}
+
+#[derive(Deserialize)]
+struct ErrorMessage {
+    code: String,
+    message: String,
+}
+
+/// Call the Ergo node's schnorrSign API to generate a tracker signature
+pub(crate) async fn call_schnorr_sign_api(
+    node_url: &str,
+    api_key: Option<&str>,
+    address: &str,
+    message: &str,
+) -> Result<String, String> {
+    let client = reqwest::Client::new();
+
+    let request_body = SchnorrSignRequest {
+        address: address.to_string(),
+        message: message.to_string(),
+    };
+
+    let url = format!("{}/utils/schnorrSign", node_url.trim_end_matches('/'));
+    let mut request_builder = client.post(&url);
+
+    // Add API key if provided
+    if let Some(key) = api_key {
+        request_builder = request_builder.header("api_key", key);
+    }
+
+    let response = request_builder
+        .json(&request_body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to send request: {}", e))?;
+
+    let status = response.status();
+    let response_text = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    if status.is_success() {
+        let sign_response: SchnorrSignResponse = serde_json::from_str(&response_text)
+            .map_err(|e| format!("Failed to parse response: {}", e))?;
+
+        Ok(sign_response.signature)
+    } else {
+        // Try to parse error response
+        let error_msg = match serde_json::from_str::<ErrorResponse>(&response_text) {
+            Ok(ErrorResponse {
+                error: Some(err), ..
+            }) => format!("{}: {}", err.code, err.message),
+            _ => response_text,
+        };
+        Err(format!("API error {}: {}", status.as_u16(), error_msg))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tracker_signer::LocalKeySigner;
+
+    fn service(secret_key: [u8; 32], tracker_pubkey: PubKey, audit_path: PathBuf) -> SigningService {
+        SigningService {
+            tracker_pubkey: Some(tracker_pubkey),
+            signer: Some(Arc::new(LocalKeySigner::new(secret_key))),
+            signer_error: None,
+            audit_log: SigningAuditLog::new(audit_path),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_signs_only_validated_redemptions() {
+        let (tracker_secret, tracker_pubkey) = basis_store::schnorr::generate_keypair();
+        let (_, issuer) = basis_store::schnorr::generate_keypair();
+        let (_, recipient) = basis_store::schnorr::generate_keypair();
+        let audit_path = std::env::temp_dir().join(format!(
+            "basis_signing_audit_{}_{}.jsonl",
+            std::process::id(),
+            hex::encode(&tracker_pubkey[1..5])
+        ));
+        let service = service(tracker_secret, tracker_pubkey, audit_path.clone());
+
+        let mut note = IouNote::new(recipient, 1_000, 0, 1_700_000_000_000, [0u8; 65]);
+        note.amount_redeemed = 400;
+        let request = RedemptionSigningRequest {
+            issuer_pubkey: issuer,
+            recipient_pubkey: recipient,
+            total_debt: 1_000,
+            timestamp: 1_700_000_000_000,
+            redeem_amount: None,
+            purpose: "redeem",
+        };
+
+        let refused = [
+            (None, Some(5_000), "NoteNotFound"),
+            (Some(&note), None, "NoReserve"),
+            (Some(&note), Some(599), "InsufficientCollateral"),
+        ];
+        for (note, collateral, expected) in refused {
+            let err = service.sign_redemption(&request, note, collateral).await.unwrap_err();
+            assert!(format!("{:?}", err).starts_with(expected), "{:?}", err);
+        }
+        let stale = RedemptionSigningRequest { total_debt: 900, ..request.clone() };
+        assert!(matches!(
+            service.sign_redemption(&stale, Some(&note), Some(5_000)).await,
+            Err(SigningError::DebtMismatch { requested: 900, tracked: 1_000 })
+        ));
+        let too_much = RedemptionSigningRequest { redeem_amount: Some(601), ..request.clone() };
+        assert!(matches!(
+            service.sign_redemption(&too_much, Some(&note), Some(5_000)).await,
+            Err(SigningError::ExceedsOutstandingDebt { .. })
+        ));
+        let v2_note = note.clone().with_signing_format(SigningFormat::V2 { tracker_id: None });
+        assert!(matches!(
+            service.sign_redemption(&request, Some(&v2_note), Some(5_000)).await,
+            Err(SigningError::UnredeemableSigningFormat(2))
+        ));
+        assert!(service.audit_log().entries().await.unwrap().is_empty());
+
+        let signed = service.sign_redemption(&request, Some(&note), Some(600)).await.unwrap();
+        assert!(basis_store::schnorr::schnorr_verify(&signed.signature, &signed.message, &tracker_pubkey).is_ok());
+
+        let entries = service.audit_log().entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!((entries[0].redeem_amount, entries[0].collateral), (600, 600));
+        assert_eq!(entries[0].signer, "local");
+        assert_eq!(entries[0].signature, hex::encode(signed.signature));
+
+        std::fs::remove_file(&audit_path).unwrap();
+    }
+}
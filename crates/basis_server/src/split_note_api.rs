@@ -0,0 +1,149 @@
+//! API handlers for split notes, which allocate debt to several recipients under one signature
+
+use axum::{extract::State, http::StatusCode, Json};
+
+use basis_store::{PubKey, Signature, SplitAllocation, SplitNote, SplitNoteError};
+
+use crate::{
+    models::{
+        error_response, success_response, ApiResponse, CreateSplitNoteRequest, EventType, SerializableSplitNote,
+        TrackerEvent,
+    },
+    requests,
+    tracker_request::TrackerUnavailable,
+    AppState,
+};
+
+fn decode<const N: usize>(value: &str, field: &str) -> Result<[u8; N], String> {
+    hex::decode(value)
+        .map_err(|_| format!("{} must be hex-encoded", field))?
+        .try_into()
+        .map_err(|_| format!("{} must be {} bytes", field, N))
+}
+
+fn split_from_request(payload: &CreateSplitNoteRequest) -> Result<(PubKey, SplitNote), String> {
+    let issuer_pubkey: PubKey = decode(&payload.issuer_pubkey, "issuer_pubkey")?;
+    let signature: Signature = decode(&payload.signature, "signature")?;
+    let allocations = payload
+        .allocations
+        .iter()
+        .map(|allocation| {
+            Ok(SplitAllocation {
+                recipient_pubkey: decode(&allocation.recipient_pubkey, "recipient_pubkey")?,
+                amount: allocation.amount,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok((
+        issuer_pubkey,
+        SplitNote {
+            allocations,
+            timestamp: payload.timestamp,
+            signature,
+        },
+    ))
+}
+
+fn split_note_error_status(error: &SplitNoteError) -> StatusCode {
+    match error {
+        SplitNoteError::AlreadyRecorded(_) => StatusCode::CONFLICT,
+        SplitNoteError::StorageError(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        _ => StatusCode::BAD_REQUEST,
+    }
+}
+
+/// Record a split note allocating debt to several recipients under a single signature
+#[axum::debug_handler]
+pub async fn create_split_note(
+    State(state): State<AppState>,
+    Json(payload): Json<CreateSplitNoteRequest>,
+) -> (StatusCode, Json<ApiResponse<SerializableSplitNote>>) {
+    tracing::debug!("Creating split note: {:?}", payload);
+
+    let (issuer_pubkey, split) = match split_from_request(&payload) {
+        Ok(parsed) => parsed,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+    if let Err(refused) = state.issuer_policy.check(&issuer_pubkey) {
+        tracing::info!("Refused split note: {}", refused);
+        return (StatusCode::FORBIDDEN, Json(error_response(refused.to_string())));
+    }
+
+    let request = requests::AddSplitNote {
+        issuer_pubkey,
+        split: split.clone(),
+    };
+    match state.send(request).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(split_id)) => {
+            tracing::info!(
+                "Recorded split note {} from {} to {} recipients",
+                hex::encode(split_id),
+                hex::encode(issuer_pubkey),
+                split.allocations.len()
+            );
+
+            // One note update per recipient, so each shows up in its timeline
+            for allocation in &split.allocations {
+                let event = TrackerEvent {
+                    id: 0, // Will be set by event store
+                    seq: 0,
+                    event_type: EventType::NoteUpdated,
+                    timestamp: split.timestamp,
+                    issuer_pubkey: Some(hex::encode(issuer_pubkey)),
+                    recipient_pubkey: Some(hex::encode(allocation.recipient_pubkey)),
+                    amount: Some(allocation.amount),
+                    reserve_box_id: None,
+                    collateral_amount: None,
+                    redeemed_amount: None,
+                    height: None,
+                };
+                if let Err(e) = state.event_store.add_event(event).await {
+                    tracing::warn!("Failed to store split note event: {:?}", e);
+                }
+            }
+
+            (
+                StatusCode::CREATED,
+                Json(success_response(SerializableSplitNote::new(&issuer_pubkey, &split))),
+            )
+        }
+        Ok(Err(e)) => {
+            tracing::warn!("Refused split note: {}", e);
+            (split_note_error_status(&e), Json(error_response(e.to_string())))
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
+
+/// Get every split note of an issuer
+#[axum::debug_handler]
+pub async fn get_split_notes_by_issuer(
+    State(state): State<AppState>,
+    axum::extract::Path(pubkey): axum::extract::Path<String>,
+) -> (StatusCode, Json<ApiResponse<Vec<SerializableSplitNote>>>) {
+    tracing::debug!("Getting split notes of issuer {}", pubkey);
+
+    let issuer_pubkey: PubKey = match decode(&pubkey, "issuer_pubkey") {
+        Ok(issuer_pubkey) => issuer_pubkey,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+
+    match state.send(requests::GetSplitNotesByIssuer { issuer_pubkey }).await.map_err(TrackerUnavailable::status) {
+        Ok(Ok(splits)) => {
+            let splits = splits
+                .iter()
+                .map(|split| SerializableSplitNote::new(&issuer_pubkey, split))
+                .collect();
+            (StatusCode::OK, Json(success_response(splits)))
+        }
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get split notes: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response("Failed to retrieve split notes".to_string())),
+            )
+        }
+        Err((status, message)) => (status, Json(error_response(message))),
+    }
+}
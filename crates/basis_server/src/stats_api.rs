@@ -0,0 +1,110 @@
+//! Network statistics endpoint
+//!
+//! Note figures come from counters the tracker maintains as notes are
+//! stored, and collateral from the reserves the scanner tracks, so a request
+//! reads no notes.
+
+use axum::{extract::State, http::StatusCode, Json};
+use std::collections::HashMap;
+
+use basis_store::debt_policy::issuer_collateral;
+
+use crate::{
+    models::{
+        error_response, success_response, ApiResponse, DailyNoteCountEntry, IssuerDebtEntry,
+        NetworkStatsResponse,
+    },
+    requests,
+    AppState,
+};
+
+/// Issuers listed when `top` is not given
+const DEFAULT_TOP_ISSUERS: usize = 10;
+const MAX_TOP_ISSUERS: usize = 100;
+/// Days of note counts returned when `days` is not given
+const DEFAULT_DAYS: u64 = 30;
+const MAX_DAYS: u64 = 365;
+
+fn bounded(params: &HashMap<String, String>, name: &str, default: u64, max: u64) -> Result<u64, String> {
+    match params.get(name) {
+        None => Ok(default),
+        Some(value) => value
+            .parse()
+            .ok()
+            .filter(|value| (1..=max).contains(value))
+            .ok_or_else(|| format!("{} must be between 1 and {}", name, max)),
+    }
+}
+
+fn ratio(collateral: u64, debt: u64) -> f64 {
+    collateral as f64 / debt as f64
+}
+
+/// Get network-wide statistics with the outstanding debt leaderboard
+#[axum::debug_handler]
+pub async fn get_network_stats(
+    State(state): State<AppState>,
+    axum::extract::Query(params): axum::extract::Query<HashMap<String, String>>,
+) -> (StatusCode, Json<ApiResponse<NetworkStatsResponse>>) {
+    tracing::debug!("Getting network stats with params: {:?}", params);
+
+    let limits = bounded(&params, "top", DEFAULT_TOP_ISSUERS as u64, MAX_TOP_ISSUERS as u64)
+        .and_then(|top| Ok((top as usize, bounded(&params, "days", DEFAULT_DAYS, MAX_DAYS)?)));
+    let (top_issuers, days) = match limits {
+        Ok(limits) => limits,
+        Err(message) => return (StatusCode::BAD_REQUEST, Json(error_response(message))),
+    };
+
+    let request = requests::GetNetworkStats {
+        top_issuers,
+        days,
+    };
+    let stats = match state.send(request).await {
+        Ok(Ok(stats)) => stats,
+        Ok(Err(e)) => {
+            tracing::error!("Failed to get network stats: {:?}", e);
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(error_response("Failed to retrieve network stats".to_string())),
+            );
+        }
+        Err(e) => return e.response(),
+    };
+
+    let (total_collateral, _) = state.reserve_tracker.get_system_totals();
+    let top_issuers = stats
+        .top_issuers
+        .iter()
+        .map(|issuer| {
+            let collateral = issuer_collateral(&state.reserve_tracker, &issuer.issuer_pubkey);
+            IssuerDebtEntry {
+                issuer_pubkey: hex::encode(issuer.issuer_pubkey),
+                outstanding_debt: issuer.outstanding_debt,
+                collateral,
+                collateralization_ratio: ratio(collateral, issuer.outstanding_debt),
+            }
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(success_response(NetworkStatsResponse {
+            issuers: stats.issuers,
+            recipients: stats.recipients,
+            notes: stats.notes,
+            total_outstanding_debt: stats.total_outstanding_debt,
+            total_collateral,
+            collateralization_ratio: (stats.total_outstanding_debt > 0)
+                .then(|| ratio(total_collateral, stats.total_outstanding_debt)),
+            top_issuers,
+            notes_per_day: stats
+                .notes_per_day
+                .iter()
+                .map(|day| DailyNoteCountEntry {
+                    day_start: day.day_start,
+                    notes: day.notes,
+                })
+                .collect(),
+        })),
+    )
+}
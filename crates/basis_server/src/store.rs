@@ -1,38 +1,207 @@
 use crate::models::TrackerEvent;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
+use sqlx::{QueryBuilder, Row, Sqlite};
+use std::str::FromStr;
 use std::sync::atomic::AtomicU64;
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
 
-// Simple file-based event store with sequential IDs
+/// Capacity of the channel announcing stored events to subscribers
+pub(crate) const EVENT_CHANNEL_CAPACITY: usize = 256;
+
+/// Schema migrations of the SQLite event store
+static MIGRATOR: sqlx::migrate::Migrator = sqlx::migrate!("./migrations");
+
+/// Filters of an event query
+///
+/// Unset filters match every event. Results are ordered by sequence number,
+/// oldest first.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventQuery {
+    /// Event type name, e.g. `ReserveRedeemed`
+    pub event_type: Option<String>,
+    /// Hex public key appearing as the issuer or the recipient of the event
+    pub pubkey: Option<String>,
+    /// Hex public key of the issuer the event concerns
+    pub issuer: Option<String>,
+    /// Lowest block height, inclusive; events without a height never match
+    pub from_height: Option<u64>,
+    /// Highest block height, inclusive; events without a height never match
+    pub to_height: Option<u64>,
+    /// Only events stored after the event with this sequence number
+    pub since_seq: Option<u64>,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl EventQuery {
+    pub(crate) fn matches(&self, event: &TrackerEvent) -> bool {
+        self.event_type
+            .as_deref()
+            .is_none_or(|event_type| event.event_type.name() == event_type)
+            && self.pubkey.as_deref().is_none_or(|pubkey| {
+                event.issuer_pubkey.as_deref() == Some(pubkey)
+                    || event.recipient_pubkey.as_deref() == Some(pubkey)
+            })
+            && self
+                .issuer
+                .as_deref()
+                .is_none_or(|issuer| event.issuer_pubkey.as_deref() == Some(issuer))
+            && self.from_height.is_none_or(|from| event.height.is_some_and(|h| h >= from))
+            && self.to_height.is_none_or(|to| event.height.is_some_and(|h| h <= to))
+            && self.since_seq.is_none_or(|since| event.seq > since)
+    }
+}
+
+/// An issuer's outstanding debt and collateral at one point in time
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct CollateralSnapshot {
+    /// Hex public key of the issuer
+    pub issuer_pubkey: String,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub total_debt: u64,
+    pub collateral: u64,
+    /// Collateral divided by debt, absent when the issuer owes nothing
+    pub collateralization_ratio: Option<f64>,
+    pub note_count: usize,
+}
+
+/// Keep the latest snapshot of every `resolution` seconds
+///
+/// `snapshots` must be ordered by timestamp; buckets start at multiples of
+/// `resolution` since the Unix epoch.
+pub fn downsample(snapshots: Vec<CollateralSnapshot>, resolution: u64) -> Vec<CollateralSnapshot> {
+    let mut kept: Vec<CollateralSnapshot> = Vec::new();
+    for snapshot in snapshots {
+        match kept.last_mut() {
+            Some(last) if last.timestamp / resolution == snapshot.timestamp / resolution => *last = snapshot,
+            _ => kept.push(snapshot),
+        }
+    }
+    kept
+}
+
+enum Backend {
+    Memory {
+        events: Mutex<Vec<TrackerEvent>>,
+        next_id: AtomicU64,
+        snapshots: Mutex<Vec<CollateralSnapshot>>,
+    },
+    Sqlite(SqlitePool),
+}
+
+/// Event store with sequential IDs
+///
+/// Events live in memory, or in SQLite when opened with a database URL.
+/// Every stored event gets the next sequence number, and subscribers are
+/// notified in sequence order.
 pub struct EventStore {
-    events: Mutex<Vec<TrackerEvent>>,
-    next_id: AtomicU64,
+    backend: Backend,
+    notifier: broadcast::Sender<TrackerEvent>,
+    /// Held while events are stored and announced, so sequence numbers are
+    /// assigned, committed and announced in the same order
+    write_lock: Mutex<()>,
 }
 
 impl EventStore {
     pub async fn new() -> Result<Self, Box<dyn std::error::Error>> {
-        // In a real implementation, this would load from disk
-        // For now, we'll use in-memory but structured for easy disk persistence
+        Ok(Self::new_in_memory())
+    }
+
+    /// Open a SQLite event store, creating the database and applying pending
+    /// schema migrations
+    ///
+    /// `database_url` is a SQLite URL such as `sqlite:data/basis.db` or
+    /// `sqlite::memory:`.
+    pub async fn open(database_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+        let in_memory = options.get_filename() == std::path::Path::new(":memory:");
+        if let Some(parent) = options.get_filename().parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Every connection to an in-memory database sees a different database,
+        // so keep exactly one open
+        let pool_options = if in_memory {
+            SqlitePoolOptions::new()
+                .max_connections(1)
+                .min_connections(1)
+                .idle_timeout(None)
+                .max_lifetime(None)
+        } else {
+            SqlitePoolOptions::new().max_connections(4)
+        };
+        let pool = pool_options.connect_with(options).await?;
+        MIGRATOR.run(&pool).await?;
+
         Ok(Self {
-            events: Mutex::new(Vec::new()),
-            next_id: AtomicU64::new(1),
+            backend: Backend::Sqlite(pool),
+            notifier: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            write_lock: Mutex::new(()),
         })
     }
 
     pub async fn add_event(
         &self,
-        mut event: TrackerEvent,
+        event: TrackerEvent,
     ) -> Result<u64, Box<dyn std::error::Error>> {
-        let id = self
-            .next_id
-            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-        event.id = id;
+        let ids = self.add_events(vec![event]).await?;
+        Ok(ids[0])
+    }
 
-        // In a real implementation, this would append to a disk file
-        // For now, we'll use a mutex-protected vector
-        let mut events = self.events.lock().await;
-        events.push(event);
+    /// Store several events at once, returning their IDs in order
+    ///
+    /// Each event gets the next sequence number. With SQLite the events are
+    /// written in one transaction: either all of them are stored or none is.
+    pub async fn add_events(
+        &self,
+        mut events: Vec<TrackerEvent>,
+    ) -> Result<Vec<u64>, Box<dyn std::error::Error>> {
+        let _write = self.write_lock.lock().await;
+        match &self.backend {
+            Backend::Memory { events: stored, next_id, .. } => {
+                let mut stored = stored.lock().await;
+                for event in &mut events {
+                    event.id = next_id.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    event.seq = event.id;
+                    stored.push(event.clone());
+                }
+            }
+            Backend::Sqlite(pool) => {
+                let mut tx = pool.begin().await?;
+                for event in &mut events {
+                    let row = sqlx::query(
+                        "INSERT INTO events (event_type, issuer_pubkey, recipient_pubkey, height, timestamp, payload, seq) \
+                         VALUES (?, ?, ?, ?, ?, ?, (SELECT COALESCE(MAX(seq), 0) + 1 FROM events)) \
+                         RETURNING id, seq",
+                    )
+                    .bind(event.event_type.name())
+                    .bind(event.issuer_pubkey.as_deref())
+                    .bind(event.recipient_pubkey.as_deref())
+                    .bind(event.height.map(|h| h as i64))
+                    .bind(event.timestamp as i64)
+                    .bind(serde_json::to_string(&*event)?)
+                    .fetch_one(&mut *tx)
+                    .await?;
+                    event.id = row.try_get::<i64, _>("id")? as u64;
+                    event.seq = row.try_get::<i64, _>("seq")? as u64;
+                }
+                tx.commit().await?;
+            }
+        }
 
-        Ok(id)
+        let ids = events.iter().map(|event| event.id).collect();
+        for event in events {
+            // No subscribers is not an error
+            let _ = self.notifier.send(event);
+        }
+        Ok(ids)
+    }
+
+    /// Receive every event added from now on, with its assigned ID and
+    /// sequence number, in sequence order
+    pub fn subscribe(&self) -> broadcast::Receiver<TrackerEvent> {
+        self.notifier.subscribe()
     }
 
     pub async fn get_events_paginated(
@@ -40,17 +209,328 @@ impl EventStore {
         page: usize,
         page_size: usize,
     ) -> Result<Vec<TrackerEvent>, Box<dyn std::error::Error>> {
-        let events = self.events.lock().await;
-        let start = page * page_size;
-        let end = std::cmp::min(start + page_size, events.len());
-        Ok(events[start..end].to_vec())
+        self.query_events(&EventQuery {
+            offset: page.saturating_mul(page_size),
+            limit: page_size,
+            ..EventQuery::default()
+        })
+        .await
+    }
+
+    /// Events matching a query, oldest first
+    pub async fn query_events(
+        &self,
+        query: &EventQuery,
+    ) -> Result<Vec<TrackerEvent>, Box<dyn std::error::Error>> {
+        match &self.backend {
+            Backend::Memory { events, .. } => {
+                let events = events.lock().await;
+                Ok(events
+                    .iter()
+                    .filter(|event| query.matches(event))
+                    .skip(query.offset)
+                    .take(query.limit)
+                    .cloned()
+                    .collect())
+            }
+            Backend::Sqlite(pool) => {
+                let mut sql = QueryBuilder::<Sqlite>::new("SELECT id, seq, payload FROM events WHERE 1 = 1");
+                if let Some(event_type) = &query.event_type {
+                    sql.push(" AND event_type = ").push_bind(event_type);
+                }
+                if let Some(pubkey) = &query.pubkey {
+                    sql.push(" AND (issuer_pubkey = ")
+                        .push_bind(pubkey)
+                        .push(" OR recipient_pubkey = ")
+                        .push_bind(pubkey)
+                        .push(")");
+                }
+                if let Some(issuer) = &query.issuer {
+                    sql.push(" AND issuer_pubkey = ").push_bind(issuer);
+                }
+                if let Some(from_height) = query.from_height {
+                    sql.push(" AND height >= ").push_bind(from_height as i64);
+                }
+                if let Some(to_height) = query.to_height {
+                    sql.push(" AND height <= ").push_bind(to_height as i64);
+                }
+                if let Some(since_seq) = query.since_seq {
+                    sql.push(" AND seq > ").push_bind(i64::try_from(since_seq).unwrap_or(i64::MAX));
+                }
+                sql.push(" ORDER BY seq LIMIT ")
+                    .push_bind(i64::try_from(query.limit).unwrap_or(i64::MAX))
+                    .push(" OFFSET ")
+                    .push_bind(i64::try_from(query.offset).unwrap_or(i64::MAX));
+
+                let rows = sql.build().fetch_all(pool).await?;
+                rows.iter()
+                    .map(|row| {
+                        let mut event: TrackerEvent = serde_json::from_str(row.try_get("payload")?)?;
+                        event.id = row.try_get::<i64, _>("id")? as u64;
+                        event.seq = row.try_get::<i64, _>("seq")? as u64;
+                        Ok(event)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Store collateral snapshots, replacing any taken of the same issuer at
+    /// the same second
+    pub async fn add_collateral_snapshots(
+        &self,
+        snapshots: &[CollateralSnapshot],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        match &self.backend {
+            Backend::Memory { snapshots: stored, .. } => {
+                let mut stored = stored.lock().await;
+                for snapshot in snapshots {
+                    stored.retain(|existing| {
+                        existing.issuer_pubkey != snapshot.issuer_pubkey || existing.timestamp != snapshot.timestamp
+                    });
+                    stored.push(snapshot.clone());
+                }
+                stored.sort_by_key(|snapshot| snapshot.timestamp);
+            }
+            Backend::Sqlite(pool) => {
+                let mut tx = pool.begin().await?;
+                for snapshot in snapshots {
+                    // Amounts are stored bit for bit, as SQLite integers are signed
+                    sqlx::query(
+                        "INSERT OR REPLACE INTO collateral_snapshots \
+                         (issuer_pubkey, timestamp, total_debt, collateral, collateralization_ratio, note_count) \
+                         VALUES (?, ?, ?, ?, ?, ?)",
+                    )
+                    .bind(&snapshot.issuer_pubkey)
+                    .bind(snapshot.timestamp as i64)
+                    .bind(snapshot.total_debt as i64)
+                    .bind(snapshot.collateral as i64)
+                    .bind(snapshot.collateralization_ratio)
+                    .bind(snapshot.note_count as i64)
+                    .execute(&mut *tx)
+                    .await?;
+                }
+                tx.commit().await?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Snapshots of an issuer taken between `from` and `to` seconds, both
+    /// inclusive, oldest first
+    pub async fn collateral_history(
+        &self,
+        issuer_pubkey: &str,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<CollateralSnapshot>, Box<dyn std::error::Error>> {
+        match &self.backend {
+            Backend::Memory { snapshots, .. } => Ok(snapshots
+                .lock()
+                .await
+                .iter()
+                .filter(|snapshot| {
+                    snapshot.issuer_pubkey == issuer_pubkey && (from..=to).contains(&snapshot.timestamp)
+                })
+                .cloned()
+                .collect()),
+            Backend::Sqlite(pool) => {
+                let rows = sqlx::query(
+                    "SELECT timestamp, total_debt, collateral, collateralization_ratio, note_count \
+                     FROM collateral_snapshots \
+                     WHERE issuer_pubkey = ? AND timestamp >= ? AND timestamp <= ? ORDER BY timestamp",
+                )
+                .bind(issuer_pubkey)
+                .bind(i64::try_from(from).unwrap_or(i64::MAX))
+                .bind(i64::try_from(to).unwrap_or(i64::MAX))
+                .fetch_all(pool)
+                .await?;
+                rows.iter()
+                    .map(|row| {
+                        Ok(CollateralSnapshot {
+                            issuer_pubkey: issuer_pubkey.to_string(),
+                            timestamp: row.try_get::<i64, _>("timestamp")? as u64,
+                            total_debt: row.try_get::<i64, _>("total_debt")? as u64,
+                            collateral: row.try_get::<i64, _>("collateral")? as u64,
+                            collateralization_ratio: row.try_get("collateralization_ratio")?,
+                            note_count: row.try_get::<i64, _>("note_count")? as usize,
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Delete the snapshots taken before `before` seconds, returning how many were deleted
+    pub async fn prune_collateral_snapshots(&self, before: u64) -> Result<u64, Box<dyn std::error::Error>> {
+        match &self.backend {
+            Backend::Memory { snapshots, .. } => {
+                let mut snapshots = snapshots.lock().await;
+                let count = snapshots.len();
+                snapshots.retain(|snapshot| snapshot.timestamp >= before);
+                Ok((count - snapshots.len()) as u64)
+            }
+            Backend::Sqlite(pool) => {
+                let result = sqlx::query("DELETE FROM collateral_snapshots WHERE timestamp < ?")
+                    .bind(i64::try_from(before).unwrap_or(i64::MAX))
+                    .execute(pool)
+                    .await?;
+                Ok(result.rows_affected())
+            }
+        }
     }
 
     /// Create an in-memory event store for testing
     pub fn new_in_memory() -> Self {
         Self {
-            events: Mutex::new(Vec::new()),
-            next_id: AtomicU64::new(1),
+            backend: Backend::Memory {
+                events: Mutex::new(Vec::new()),
+                next_id: AtomicU64::new(1),
+                snapshots: Mutex::new(Vec::new()),
+            },
+            notifier: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            write_lock: Mutex::new(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EventType;
+
+    fn event(event_type: EventType, issuer: Option<&str>, height: Option<u64>) -> TrackerEvent {
+        TrackerEvent {
+            id: 0,
+            seq: 0,
+            event_type,
+            timestamp: 1_700_000_000,
+            issuer_pubkey: issuer.map(str::to_string),
+            recipient_pubkey: None,
+            amount: Some(u64::MAX),
+            reserve_box_id: None,
+            collateral_amount: None,
+            redeemed_amount: None,
+            height,
+        }
+    }
+
+    async fn ids(store: &EventStore, query: EventQuery) -> Vec<u64> {
+        store
+            .query_events(&EventQuery { limit: 10, ..query })
+            .await
+            .unwrap()
+            .iter()
+            .map(|e| e.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_sqlite_event_store_persists_and_queries() {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("basis_events_{}_{}", std::process::id(), nanos));
+        let url = format!("sqlite:{}", dir.join("events.db").display());
+
+        {
+            let store = EventStore::open(&url).await.unwrap();
+            let ids = store
+                .add_events(vec![
+                    event(EventType::ReserveCreated, Some("aa"), Some(100)),
+                    event(EventType::CollateralAlert { ratio: 0.5 }, Some("bb"), None),
+                    event(EventType::ReserveRedeemed, Some("aa"), Some(120)),
+                ])
+                .await
+                .unwrap();
+            assert_eq!(ids, vec![1, 2, 3]);
+        }
+
+        // Reopening keeps the events and does not reapply migrations
+        let store = EventStore::open(&url).await.unwrap();
+        assert_eq!(store.add_event(event(EventType::Commitment, None, Some(130))).await.unwrap(), 4);
+
+        let all = store.get_events_paginated(0, 10).await.unwrap();
+        assert_eq!(all.iter().map(|e| e.id).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert!(matches!(all[1].event_type, EventType::CollateralAlert { ratio } if ratio == 0.5));
+        assert_eq!(all[0].amount, Some(u64::MAX));
+
+        let by_pubkey = EventQuery { pubkey: Some("aa".to_string()), ..EventQuery::default() };
+        assert_eq!(ids(&store, by_pubkey).await, vec![1, 3]);
+        let by_issuer = EventQuery { issuer: Some("bb".to_string()), ..EventQuery::default() };
+        assert_eq!(ids(&store, by_issuer).await, vec![2]);
+        let by_type = EventQuery { event_type: Some("Commitment".to_string()), ..EventQuery::default() };
+        assert_eq!(ids(&store, by_type).await, vec![4]);
+        let by_height = EventQuery { from_height: Some(110), to_height: Some(130), ..EventQuery::default() };
+        assert_eq!(ids(&store, by_height).await, vec![3, 4]);
+
+        // Sequence numbers continue across restarts
+        assert_eq!(all.iter().map(|e| e.seq).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        let since = EventQuery { since_seq: Some(2), ..EventQuery::default() };
+        assert_eq!(ids(&store, since).await, vec![3, 4]);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[tokio::test]
+    async fn test_in_memory_store_matches_sqlite_queries() {
+        for store in [EventStore::new_in_memory(), EventStore::open("sqlite::memory:").await.unwrap()] {
+            store
+                .add_events(vec![
+                    event(EventType::ReserveCreated, Some("aa"), Some(100)),
+                    event(EventType::ReserveSpent, Some("bb"), Some(200)),
+                    event(EventType::ReserveToppedUp, Some("aa"), None),
+                ])
+                .await
+                .unwrap();
+            let query = EventQuery {
+                pubkey: Some("aa".to_string()),
+                from_height: Some(50),
+                ..EventQuery::default()
+            };
+            assert_eq!(ids(&store, query).await, vec![1]);
+            assert_eq!(store.get_events_paginated(1, 2).await.unwrap()[0].id, 3);
+        }
+    }
+
+    fn snapshot(issuer: &str, timestamp: u64, total_debt: u64) -> CollateralSnapshot {
+        CollateralSnapshot {
+            issuer_pubkey: issuer.to_string(),
+            timestamp,
+            total_debt,
+            collateral: u64::MAX,
+            collateralization_ratio: (total_debt > 0).then(|| u64::MAX as f64 / total_debt as f64),
+            note_count: 1,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collateral_history_ranges_and_downsampling() {
+        for store in [EventStore::new_in_memory(), EventStore::open("sqlite::memory:").await.unwrap()] {
+            store
+                .add_collateral_snapshots(&[
+                    snapshot("aa", 3_600, 0),
+                    snapshot("aa", 5_000, 10),
+                    snapshot("bb", 5_000, 99),
+                    snapshot("aa", 7_300, 20),
+                    snapshot("aa", 10_900, 30),
+                ])
+                .await
+                .unwrap();
+            // A snapshot of the same second replaces the earlier one
+            store.add_collateral_snapshots(&[snapshot("aa", 7_300, 25)]).await.unwrap();
+
+            let history = store.collateral_history("aa", 0, 10_000).await.unwrap();
+            assert_eq!(history.iter().map(|s| s.total_debt).collect::<Vec<_>>(), vec![0, 10, 25]);
+            assert_eq!(history[0].collateralization_ratio, None);
+            assert_eq!(history[1].collateral, u64::MAX);
+
+            let hourly = downsample(store.collateral_history("aa", 0, u64::MAX).await.unwrap(), 3_600);
+            assert_eq!(hourly.iter().map(|s| s.timestamp).collect::<Vec<_>>(), vec![5_000, 7_300, 10_900]);
+
+            assert_eq!(store.prune_collateral_snapshots(7_300).await.unwrap(), 3);
+            assert_eq!(store.collateral_history("aa", 0, u64::MAX).await.unwrap().len(), 2);
         }
     }
 }
@@ -3,15 +3,18 @@
 //! This module implements a background service that periodically updates the R4 and R5 register values
 //! of the tracker box every 10 minutes by submitting transactions to the Ergo blockchain via the wallet payment API.
 
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, RwLock};
 use tokio::sync::broadcast;
 use tokio::time::Duration;
-use tracing::{error, info};
+use tracing::{error, info, warn};
 use serde_json::{json, to_string};
 use basis_store::reqwest;
 use hex;
 use ergo_lib::ergotree_ir::address::NetworkPrefix;
 
+use crate::commitment_scheduler::{CommitmentScheduleConfig, CommitmentScheduler, CommitmentSnapshot};
+
 /// Create a default tracker public key that looks realistic (compressed format with proper prefix)
 fn create_default_tracker_pubkey() -> [u8; 33] {
     // Use a realistic example of a compressed secp256k1 public key
@@ -32,6 +35,12 @@ pub struct SharedTrackerState {
     pub avl_root_digest: Arc<RwLock<[u8; 33]>>,
     pub tracker_pubkey: Arc<RwLock<[u8; 33]>>,
     pub tracker_box_id: Arc<RwLock<Option<String>>>,
+    /// Notes created, updated or redeemed since startup
+    pub note_updates: Arc<AtomicU64>,
+    /// Debt issued plus debt redeemed since startup
+    pub debt_changed: Arc<AtomicU64>,
+    /// Signalled to commit on the next updater cycle regardless of schedule
+    pub commit_requested: Arc<tokio::sync::Notify>,
 }
 
 impl SharedTrackerState {
@@ -42,6 +51,9 @@ impl SharedTrackerState {
             avl_root_digest: Arc::new(RwLock::new([0u8; 33])), // Initialize with zeros
             tracker_pubkey: Arc::new(RwLock::new(create_default_tracker_pubkey())), // Initialize with a valid compressed pubkey
             tracker_box_id: Arc::new(RwLock::new(None)),
+            note_updates: Arc::new(AtomicU64::new(0)),
+            debt_changed: Arc::new(AtomicU64::new(0)),
+            commit_requested: Arc::new(tokio::sync::Notify::new()),
         }
     }
 
@@ -50,6 +62,9 @@ impl SharedTrackerState {
             avl_root_digest: Arc::new(RwLock::new([0u8; 33])), // Initialize with zeros
             tracker_pubkey: Arc::new(RwLock::new(tracker_pubkey)),
             tracker_box_id: Arc::new(RwLock::new(None)),
+            note_updates: Arc::new(AtomicU64::new(0)),
+            debt_changed: Arc::new(AtomicU64::new(0)),
+            commit_requested: Arc::new(tokio::sync::Notify::new()),
         }
     }
 
@@ -87,6 +102,27 @@ impl SharedTrackerState {
         }
     }
 
+    /// Count a note update that changed the debt by `debt_change`
+    pub fn record_note_update(&self, debt_change: u64) {
+        self.note_updates.fetch_add(1, Ordering::Relaxed);
+        self.debt_changed.fetch_add(debt_change, Ordering::Relaxed);
+    }
+
+    pub fn note_update_count(&self) -> u64 {
+        self.note_updates.load(Ordering::Relaxed)
+    }
+
+    pub fn debt_changed(&self) -> u64 {
+        self.debt_changed.load(Ordering::Relaxed)
+    }
+
+    /// Ask the updater to commit the current root now, bypassing the
+    /// commitment schedule; a request made while none is waiting is kept
+    /// for the next cycle
+    pub fn request_commitment(&self) {
+        self.commit_requested.notify_one();
+    }
+
     pub fn get_tracker_box_id(&self) -> Option<String> {
         if let Ok(id_lock) = self.tracker_box_id.read() {
             id_lock.clone()
@@ -109,6 +145,8 @@ pub struct TrackerBoxUpdateConfig {
     pub ergo_api_key: Option<String>,
     /// Tracker secret key for signing transactions (32 bytes)
     pub tracker_secret_key: Option<[u8; 32]>,
+    /// Commit when scheduler triggers fire instead of every `update_interval_seconds`
+    pub schedule: Option<CommitmentScheduleConfig>,
 }
 
 impl Default for TrackerBoxUpdateConfig {
@@ -119,6 +157,7 @@ impl Default for TrackerBoxUpdateConfig {
             ergo_node_url: "".to_string(), // Must be provided in config
             ergo_api_key: None,
             tracker_secret_key: None,
+            schedule: None,
         }
     }
 }
@@ -145,22 +184,69 @@ impl TrackerBoxUpdater {
             return Ok(());
         }
 
-        info!(
-            "Starting tracker box updater with interval {} seconds",
-            config.update_interval_seconds
-        );
+        let mut scheduler = config.schedule.clone().map(CommitmentScheduler::new);
+        let period = match &config.schedule {
+            Some(schedule) => {
+                info!(
+                    "Starting tracker box updater with commitment schedule {:?}",
+                    schedule
+                );
+                schedule.check_interval_secs
+            }
+            None => {
+                info!(
+                    "Starting tracker box updater with interval {} seconds",
+                    config.update_interval_seconds
+                );
+                config.update_interval_seconds
+            }
+        };
 
         let client = reqwest::Client::new();
-        let mut interval = tokio::time::interval(Duration::from_secs(config.update_interval_seconds));
+        let mut interval = tokio::time::interval(Duration::from_secs(period));
 
         // Skip the first immediate tick to avoid immediate execution
         interval.tick().await;
 
         loop {
             tokio::select! {
-                _ = interval.tick() => {
+                forced = async {
+                    tokio::select! {
+                        _ = interval.tick() => false,
+                        _ = shared_tracker_state.commit_requested.notified() => true,
+                    }
+                } => {
+                    if forced {
+                        info!("Manual commitment requested");
+                    }
+                    // With a schedule, ticks only commit once a trigger fires
+                    let mut snapshot = None;
+                    if let Some(scheduler) = scheduler.as_mut() {
+                        let height = if scheduler.needs_height() {
+                            match Self::fetch_blockchain_height(&client, &config.ergo_node_url, config.ergo_api_key.as_deref()).await {
+                                Ok(height) => Some(height as u64),
+                                Err(e) => {
+                                    warn!("Block trigger skipped, height unavailable: {}", e);
+                                    None
+                                }
+                            }
+                        } else {
+                            None
+                        };
+                        let current = CommitmentSnapshot::capture(&shared_tracker_state, height);
+                        match scheduler.evaluate(&current) {
+                            Some(trigger) => info!("Commitment scheduled: {}", trigger),
+                            None if forced => {}
+                            None => continue,
+                        }
+                        snapshot = Some(current);
+                    }
+
                     // Access the shared state to get current values
-                    let current_root = shared_tracker_state.get_avl_root_digest();
+                    let current_root = match &snapshot {
+                        Some(snapshot) => snapshot.root,
+                        None => shared_tracker_state.get_avl_root_digest(),
+                    };
                     let tracker_pubkey = shared_tracker_state.get_tracker_pubkey();
 
                     // R4 should contain the tracker public key as a GroupElement constant (EcPoint)
@@ -220,9 +306,7 @@ impl TrackerBoxUpdater {
                     
                     // Derive tracker address from public key for the output
                     let tracker_address = {
-                        let encoder = ergo_lib::ergotree_ir::address::AddressEncoder::new(
-                            ergo_lib::ergotree_ir::address::NetworkPrefix::Mainnet
-                        );
+                        let encoder = ergo_lib::ergotree_ir::address::AddressEncoder::new(network_prefix);
                         encoder.address_to_str(&ergo_lib::ergotree_ir::address::Address::P2Pk(
                             ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog::from(
                                 ec_point.clone()
@@ -252,6 +336,9 @@ impl TrackerBoxUpdater {
                                 hex::encode(&current_root),
                                 tx_id
                             );
+                            if let (Some(scheduler), Some(snapshot)) = (scheduler.as_mut(), snapshot) {
+                                scheduler.committed(snapshot);
+                            }
                         }
                         Err(e) => {
                             error!("Failed to submit tracker box update transaction: {}", e);
@@ -512,6 +599,7 @@ impl TrackerBoxUpdater {
         r4_constant: &ergo_lib::ergotree_ir::mir::constant::Constant,
         r5_bytes: &[u8],
         tracker_secret_key: &[u8; 32],
+        network_prefix: NetworkPrefix,
     ) -> Result<String, TrackerBoxUpdaterError> {
         use ergo_lib::chain::ergo_box::{BoxValue, ErgoBox, ErgoBoxCandidate, NonMandatoryRegisters};
         use ergo_lib::wallet::tx_builder::TxBuilder;
@@ -612,7 +700,7 @@ impl TrackerBoxUpdater {
         let min_input_value = fee.as_u64() + min_box_value.as_u64();
         
         if *input_box.value.as_u64() < min_input_value {
-            let tracker_address = Self::get_tracker_address_from_pubkey(r4_constant)?;
+            let tracker_address = Self::get_tracker_address_from_pubkey(r4_constant, network_prefix)?;
             return Err(TrackerBoxUpdaterError::ConfigurationError(format!(
                 "Tracker box underfunded. Current value: {} nanoERG, required: {} nanoERG (fee: {} + min box: {}). \
                  Please send at least {} ERG to tracker address: {}",
@@ -654,10 +742,9 @@ impl TrackerBoxUpdater {
             change_boxes: vec![],
         };
         
-        let change_address = Self::get_tracker_address_from_pubkey(r4_constant)?;
-        let change_address_parsed = ergo_lib::ergotree_ir::address::AddressEncoder::new(
-            ergo_lib::ergotree_ir::address::NetworkPrefix::Mainnet
-        ).parse_address_from_str(&change_address).map_err(|e| {
+        let change_address = Self::get_tracker_address_from_pubkey(r4_constant, network_prefix)?;
+        let change_address_parsed = ergo_lib::ergotree_ir::address::AddressEncoder::new(network_prefix)
+            .parse_address_from_str(&change_address).map_err(|e| {
             TrackerBoxUpdaterError::ConfigurationError(format!("Failed to parse change address: {}", e))
         })?;
         
@@ -707,7 +794,10 @@ impl TrackerBoxUpdater {
     }
     
     /// Helper to get tracker P2PK address from R4 constant (EcPoint)
-    fn get_tracker_address_from_pubkey(r4_constant: &ergo_lib::ergotree_ir::mir::constant::Constant) -> Result<String, TrackerBoxUpdaterError> {
+    fn get_tracker_address_from_pubkey(
+        r4_constant: &ergo_lib::ergotree_ir::mir::constant::Constant,
+        network_prefix: NetworkPrefix,
+    ) -> Result<String, TrackerBoxUpdaterError> {
         use ergo_lib::ergotree_ir::mir::constant::TryExtractInto;
         use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
         use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
@@ -720,9 +810,7 @@ impl TrackerBoxUpdater {
         let prove_dlog = ProveDlog::new(ec_point);
         let p2pk_address = Address::P2Pk(prove_dlog);
         
-        let encoder = ergo_lib::ergotree_ir::address::AddressEncoder::new(
-            ergo_lib::ergotree_ir::address::NetworkPrefix::Mainnet
-        );
+        let encoder = ergo_lib::ergotree_ir::address::AddressEncoder::new(network_prefix);
         
         Ok(encoder.address_to_str(&p2pk_address))
     }
@@ -0,0 +1,405 @@
+//! Bounded, prioritized queue in front of the tracker thread
+//!
+//! Commands wait in one of two bounded queues: reads, which only look at
+//! tracker state, and writes, which change it. The tracker thread prefers
+//! reads, so a burst of note submissions does not hold up lookups, but takes
+//! a write after `read_burst` reads in a row so writes keep moving as well.
+//!
+//! A request whose command finds no room in its queue within the endpoint's
+//! timeout is answered with 503 and a `Retry-After` header instead of holding
+//! its connection until the tracker catches up. Commands sent outside an HTTP
+//! request, by background tasks, wait for room however long it takes.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use crate::request_id::{self, TrackerEnvelope};
+use crate::TrackerCommand;
+
+/// Capacity, timeouts and fairness of the tracker command queue
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TrackerQueueConfig {
+    /// Commands each of the read and write queues holds
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    /// Milliseconds a request waits for room in the queue before it is refused
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Timeouts for endpoints that need a different one
+    #[serde(default)]
+    pub endpoints: Vec<EndpointTimeout>,
+    /// Seconds refused clients are asked to wait before retrying
+    #[serde(default = "default_retry_after_secs")]
+    pub retry_after_secs: u64,
+    /// Reads the tracker thread takes in a row while writes are waiting
+    #[serde(default = "default_read_burst")]
+    pub read_burst: usize,
+}
+
+/// Queue timeout of the endpoints under a path prefix
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct EndpointTimeout {
+    /// Path prefix, matched on whole segments: `/notes` covers `/notes/issuer/..`
+    pub path: String,
+    /// Milliseconds to wait for room in the queue
+    pub timeout_ms: u64,
+}
+
+fn default_capacity() -> usize {
+    100
+}
+
+fn default_timeout_ms() -> u64 {
+    2_000
+}
+
+fn default_retry_after_secs() -> u64 {
+    1
+}
+
+fn default_read_burst() -> usize {
+    8
+}
+
+impl Default for TrackerQueueConfig {
+    fn default() -> Self {
+        Self {
+            capacity: default_capacity(),
+            timeout_ms: default_timeout_ms(),
+            endpoints: Vec::new(),
+            retry_after_secs: default_retry_after_secs(),
+            read_burst: default_read_burst(),
+        }
+    }
+}
+
+impl TrackerQueueConfig {
+    /// Queue timeout of requests to `path`: the longest matching prefix wins
+    pub fn timeout_for(&self, path: &str) -> Duration {
+        let covers = |prefix: &str| {
+            let prefix = prefix.trim_end_matches('/');
+            path.strip_prefix(prefix)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        };
+        let timeout_ms = self
+            .endpoints
+            .iter()
+            .filter(|endpoint| covers(&endpoint.path))
+            .max_by_key(|endpoint| endpoint.path.trim_end_matches('/').len())
+            .map_or(self.timeout_ms, |endpoint| endpoint.timeout_ms);
+        Duration::from_millis(timeout_ms)
+    }
+}
+
+/// Queue a tracker command waits in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommandPriority {
+    /// Looks at tracker state
+    Read,
+    /// Changes tracker state
+    Write,
+}
+
+/// The HTTP request handled by the current task, as seen by the queue
+struct RequestSlot {
+    path: String,
+    /// Set when a command of the request was refused for lack of room
+    saturated: AtomicBool,
+}
+
+tokio::task_local! {
+    static REQUEST_SLOT: RequestSlot;
+}
+
+/// Occupancy of the tracker queue
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueueStats {
+    /// Commands each queue holds
+    pub capacity: usize,
+    pub read_depth: usize,
+    pub write_depth: usize,
+    /// Read commands refused since startup because the queue stayed full
+    pub reads_refused: u64,
+    /// Write commands refused since startup because the queue stayed full
+    pub writes_refused: u64,
+}
+
+/// Sending half of the tracker thread channel
+///
+/// Commands are tagged with the ID of the request handled by the sending task.
+#[derive(Debug, Clone)]
+pub struct TrackerSender {
+    reads: mpsc::Sender<TrackerEnvelope>,
+    writes: mpsc::Sender<TrackerEnvelope>,
+    // One signal per queued command wakes the tracker thread
+    ready: mpsc::UnboundedSender<()>,
+    config: Arc<TrackerQueueConfig>,
+    reads_refused: Arc<AtomicU64>,
+    writes_refused: Arc<AtomicU64>,
+}
+
+impl TrackerSender {
+    /// Queue a command for the tracker thread
+    ///
+    /// Fails when the tracker thread has stopped, or when the command is sent
+    /// on behalf of a request and its queue stayed full for the endpoint's
+    /// timeout. The request is then answered by [`saturation_layer`].
+    pub async fn send(&self, command: TrackerCommand) -> Result<(), mpsc::error::SendError<TrackerCommand>> {
+        let priority = command.priority();
+        let (queue, refused) = match priority {
+            CommandPriority::Read => (&self.reads, &self.reads_refused),
+            CommandPriority::Write => (&self.writes, &self.writes_refused),
+        };
+
+        let timeout = REQUEST_SLOT
+            .try_with(|slot| (self.config.timeout_for(&slot.path), slot.path.clone()))
+            .ok();
+        let permit = match timeout {
+            None => queue.reserve().await,
+            Some((timeout, path)) => match tokio::time::timeout(timeout, queue.reserve()).await {
+                Ok(permit) => permit,
+                Err(_) => {
+                    refused.fetch_add(1, Ordering::Relaxed);
+                    let _ = REQUEST_SLOT.try_with(|slot| slot.saturated.store(true, Ordering::Relaxed));
+                    tracing::warn!(
+                        "Tracker {:?} queue full for {} ms, refusing {}",
+                        priority,
+                        timeout.as_millis(),
+                        path
+                    );
+                    return Err(mpsc::error::SendError(command));
+                }
+            },
+        };
+        let Ok(permit) = permit else {
+            return Err(mpsc::error::SendError(command));
+        };
+
+        permit.send(TrackerEnvelope {
+            request_id: request_id::current(),
+            command,
+        });
+        let _ = self.ready.send(());
+        Ok(())
+    }
+
+    /// Current occupancy of the queue
+    pub fn stats(&self) -> QueueStats {
+        let depth = |queue: &mpsc::Sender<TrackerEnvelope>| queue.max_capacity() - queue.capacity();
+        QueueStats {
+            capacity: self.config.capacity,
+            read_depth: depth(&self.reads),
+            write_depth: depth(&self.writes),
+            reads_refused: self.reads_refused.load(Ordering::Relaxed),
+            writes_refused: self.writes_refused.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Receiving half of the tracker thread channel
+#[derive(Debug)]
+pub struct TrackerReceiver {
+    reads: mpsc::Receiver<TrackerEnvelope>,
+    writes: mpsc::Receiver<TrackerEnvelope>,
+    ready: mpsc::UnboundedReceiver<()>,
+    read_burst: usize,
+    reads_in_row: usize,
+}
+
+impl TrackerReceiver {
+    /// Next command, or `None` once every sender is gone and the queue is empty
+    pub async fn recv(&mut self) -> Option<TrackerEnvelope> {
+        self.ready.recv().await?;
+        self.take()
+    }
+
+    /// Blocking variant of [`recv`](Self::recv) for the tracker thread
+    pub fn blocking_recv(&mut self) -> Option<TrackerEnvelope> {
+        self.ready.blocking_recv()?;
+        self.take()
+    }
+
+    // A ready signal is sent after its command is queued, so one is waiting
+    fn take(&mut self) -> Option<TrackerEnvelope> {
+        if self.reads_in_row < self.read_burst {
+            if let Ok(envelope) = self.reads.try_recv() {
+                self.reads_in_row += 1;
+                return Some(envelope);
+            }
+        }
+        self.reads_in_row = 0;
+        self.writes.try_recv().or_else(|_| self.reads.try_recv()).ok()
+    }
+}
+
+/// Channel to the tracker thread
+pub fn tracker_channel(config: TrackerQueueConfig) -> (TrackerSender, TrackerReceiver) {
+    let (reads_tx, reads) = mpsc::channel(config.capacity);
+    let (writes_tx, writes) = mpsc::channel(config.capacity);
+    let (ready_tx, ready) = mpsc::unbounded_channel();
+    let receiver = TrackerReceiver {
+        reads,
+        writes,
+        ready,
+        read_burst: config.read_burst,
+        reads_in_row: 0,
+    };
+    let sender = TrackerSender {
+        reads: reads_tx,
+        writes: writes_tx,
+        ready: ready_tx,
+        config: Arc::new(config),
+        reads_refused: Arc::new(AtomicU64::new(0)),
+        writes_refused: Arc::new(AtomicU64::new(0)),
+    };
+    (sender, receiver)
+}
+
+/// Middleware answering 503 with `Retry-After` when the tracker queue refused
+/// a command of the request
+pub async fn saturation_layer(State(retry_after_secs): State<u64>, request: Request, next: Next) -> Response {
+    let slot = RequestSlot {
+        path: request.uri().path().to_string(),
+        saturated: AtomicBool::new(false),
+    };
+    let (response, saturated) = REQUEST_SLOT
+        .scope(slot, async {
+            let response = next.run(request).await;
+            (response, REQUEST_SLOT.with(|slot| slot.saturated.load(Ordering::Relaxed)))
+        })
+        .await;
+    if !saturated {
+        return response;
+    }
+
+    let mut response = (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(crate::models::error_response::<()>(
+            "Tracker is busy, retry later".to_string(),
+        )),
+    )
+        .into_response();
+    response
+        .headers_mut()
+        .insert(header::RETRY_AFTER, HeaderValue::from(retry_after_secs));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, middleware, routing::get, Router};
+    use tower::util::ServiceExt;
+
+    fn flush() -> TrackerCommand {
+        let (response_tx, _) = tokio::sync::oneshot::channel();
+        TrackerCommand::Flush { response_tx }
+    }
+
+    fn get_notes() -> TrackerCommand {
+        let (response_tx, _) = tokio::sync::oneshot::channel();
+        TrackerCommand::GetNotes { response_tx }
+    }
+
+    #[tokio::test]
+    async fn test_reads_go_first_without_starving_writes() {
+        let (tx, mut rx) = tracker_channel(TrackerQueueConfig {
+            read_burst: 2,
+            ..TrackerQueueConfig::default()
+        });
+        for _ in 0..3 {
+            tx.send(flush()).await.unwrap();
+        }
+        for _ in 0..3 {
+            tx.send(get_notes()).await.unwrap();
+        }
+        assert_eq!(
+            tx.stats(),
+            QueueStats {
+                capacity: 100,
+                read_depth: 3,
+                write_depth: 3,
+                reads_refused: 0,
+                writes_refused: 0,
+            }
+        );
+
+        let mut order = Vec::new();
+        for _ in 0..6 {
+            order.push(rx.recv().await.unwrap().command.priority());
+        }
+        use CommandPriority::{Read, Write};
+        assert_eq!(order, vec![Read, Read, Write, Read, Write, Write]);
+
+        drop(tx);
+        assert!(rx.recv().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_saturated_queue_answers_503() {
+        let (tx, _rx) = tracker_channel(TrackerQueueConfig {
+            capacity: 1,
+            timeout_ms: 20,
+            retry_after_secs: 3,
+            ..TrackerQueueConfig::default()
+        });
+        let sender = tx.clone();
+        let app = Router::new()
+            .route(
+                "/flush",
+                get(move || async move {
+                    match sender.send(flush()).await {
+                        Ok(()) => StatusCode::OK,
+                        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                    }
+                }),
+            )
+            .layer(middleware::from_fn_with_state(3, saturation_layer));
+        let request = || Request::builder().uri("/flush").body(Body::empty()).unwrap();
+
+        // The first write fills the queue, nothing drains it
+        assert_eq!(app.clone().oneshot(request()).await.unwrap().status(), StatusCode::OK);
+        let response = app.oneshot(request()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "3");
+
+        let stats = tx.stats();
+        assert_eq!((stats.write_depth, stats.writes_refused), (1, 1));
+        // Reads have a queue of their own
+        assert_eq!((stats.read_depth, stats.reads_refused), (0, 0));
+        tx.send(get_notes()).await.unwrap();
+    }
+
+    #[test]
+    fn test_endpoint_timeouts_match_longest_prefix() {
+        let config = TrackerQueueConfig {
+            endpoints: vec![
+                EndpointTimeout {
+                    path: "/notes".to_string(),
+                    timeout_ms: 500,
+                },
+                EndpointTimeout {
+                    path: "/notes/issuer/".to_string(),
+                    timeout_ms: 100,
+                },
+            ],
+            ..TrackerQueueConfig::default()
+        };
+        assert_eq!(config.timeout_for("/notes"), Duration::from_millis(500));
+        assert_eq!(config.timeout_for("/notes/search"), Duration::from_millis(500));
+        assert_eq!(config.timeout_for("/notes/issuer/02aa"), Duration::from_millis(100));
+        // Prefixes match whole path segments only
+        assert_eq!(config.timeout_for("/notesx"), Duration::from_millis(2_000));
+        assert_eq!(config.timeout_for("/reserves"), Duration::from_millis(2_000));
+    }
+}
@@ -0,0 +1,187 @@
+//! Typed requests to the tracker thread
+//!
+//! Every [`TrackerCommand`] has a request struct of the same name in
+//! [`requests`], carrying the command's fields, and a response type. Sending
+//! a request through [`AppState::send`] or [`TrackerSender::request`] queues
+//! the command with a fresh response channel and waits for the answer:
+//!
+//! ```ignore
+//! let notes = state.send(requests::GetNotesByIssuer { issuer_pubkey }).await?;
+//! ```
+//!
+//! Commands, their queue priority and their requests are all generated from
+//! the table in `lib.rs`, so adding a command takes one entry there and an
+//! arm in the tracker thread.
+//!
+//! [`requests`]: crate::requests
+
+use axum::{http::StatusCode, Json};
+use tokio::sync::oneshot;
+
+use crate::models::{error_response, ApiResponse};
+use crate::tracker_queue::TrackerSender;
+use crate::{AppState, TrackerCommand};
+
+/// A request the tracker thread answers with a `Response`
+pub trait TrackerRequest: Send + 'static {
+    type Response: Send + 'static;
+
+    /// The command carrying this request and the channel for its response
+    fn into_command(self, response_tx: oneshot::Sender<Self::Response>) -> TrackerCommand;
+}
+
+/// The tracker thread did not answer a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum TrackerUnavailable {
+    /// The command could not be queued: the tracker thread has stopped, or
+    /// the queue stayed full for the request's timeout
+    #[error("Tracker thread unavailable")]
+    NotQueued,
+    /// The tracker thread dropped the command without answering
+    #[error("Tracker thread dropped the request")]
+    NoResponse,
+}
+
+impl TrackerUnavailable {
+    /// Response of a handler whose request went unanswered
+    ///
+    /// A request refused by a full queue is answered 503 by the saturation
+    /// layer whatever the handler returns.
+    pub fn response<T>(self) -> (StatusCode, Json<ApiResponse<T>>) {
+        let (status, message) = self.status();
+        (status, Json(error_response(message)))
+    }
+
+    /// Status and message of a handler whose request went unanswered
+    pub fn status(self) -> (StatusCode, String) {
+        match self {
+            TrackerUnavailable::NotQueued => {
+                tracing::error!("Failed to send to tracker thread");
+                (StatusCode::INTERNAL_SERVER_ERROR, "Tracker thread unavailable".to_string())
+            }
+            TrackerUnavailable::NoResponse => {
+                tracing::error!("Tracker thread response channel closed");
+                (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
+            }
+        }
+    }
+}
+
+impl TrackerSender {
+    /// Queue a request for the tracker thread and wait for its response
+    pub async fn request<R: TrackerRequest>(&self, request: R) -> Result<R::Response, TrackerUnavailable> {
+        let (response_tx, response_rx) = oneshot::channel();
+        self.send(request.into_command(response_tx))
+            .await
+            .map_err(|_| TrackerUnavailable::NotQueued)?;
+        response_rx.await.map_err(|_| TrackerUnavailable::NoResponse)
+    }
+}
+
+impl AppState {
+    /// Send a request to the tracker thread and wait for its response
+    pub async fn send<R: TrackerRequest>(&self, request: R) -> Result<R::Response, TrackerUnavailable> {
+        self.tx.request(request).await
+    }
+}
+
+/// Define the tracker commands, their queue priority and their requests
+///
+/// Each entry reads `read|write Name { fields } -> Response;` and becomes
+/// the `TrackerCommand::Name` variant, with a `response_tx` field added, and
+/// the `requests::Name` struct.
+macro_rules! tracker_requests {
+    ($(
+        $(#[$meta:meta])*
+        $priority:ident $name:ident {
+            $( $(#[$field_meta:meta])* $field:ident : $ty:ty ),* $(,)?
+        } -> $response:ty;
+    )*) => {
+        /// Commands that can be sent to the tracker thread
+        #[derive(Debug)]
+        pub enum TrackerCommand {
+            $(
+                $(#[$meta])*
+                $name {
+                    $( $(#[$field_meta])* $field: $ty, )*
+                    response_tx: tokio::sync::oneshot::Sender<$response>,
+                },
+            )*
+        }
+
+        impl TrackerCommand {
+            /// Queue the command waits in
+            pub fn priority(&self) -> $crate::tracker_queue::CommandPriority {
+                match self {
+                    $( TrackerCommand::$name { .. } => tracker_requests!(@priority $priority), )*
+                }
+            }
+        }
+
+        /// Requests answered by the tracker thread, one per [`TrackerCommand`]
+        pub mod requests {
+            #[allow(unused_imports)]
+            use super::*;
+
+            $(
+                $(#[$meta])*
+                #[derive(Debug)]
+                pub struct $name {
+                    $( $(#[$field_meta])* pub $field: $ty, )*
+                }
+
+                impl $crate::tracker_request::TrackerRequest for $name {
+                    type Response = $response;
+
+                    fn into_command(
+                        self,
+                        response_tx: tokio::sync::oneshot::Sender<$response>,
+                    ) -> $crate::TrackerCommand {
+                        let Self { $( $field, )* } = self;
+                        $crate::TrackerCommand::$name { $( $field, )* response_tx }
+                    }
+                }
+            )*
+        }
+    };
+    (@priority read) => { $crate::tracker_queue::CommandPriority::Read };
+    (@priority write) => { $crate::tracker_queue::CommandPriority::Write };
+}
+
+pub(crate) use tracker_requests;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::requests;
+    use crate::tracker_queue::{tracker_channel, TrackerQueueConfig};
+
+    #[tokio::test]
+    async fn test_request_round_trip() {
+        let (tx, mut rx) = tracker_channel(TrackerQueueConfig::default());
+        let tracker = tokio::spawn(async move {
+            // Answer the first request, drop the second unanswered
+            match rx.recv().await.unwrap().command {
+                TrackerCommand::GetNotes { response_tx } => {
+                    let _ = response_tx.send(Ok(Vec::new()));
+                }
+                other => panic!("unexpected command {:?}", other),
+            }
+            let dropped = rx.recv().await.unwrap();
+            assert!(matches!(dropped.command, TrackerCommand::Flush { .. }));
+        });
+
+        assert!(tx.request(requests::GetNotes {}).await.unwrap().unwrap().is_empty());
+        assert_eq!(
+            tx.request(requests::Flush {}).await.unwrap_err(),
+            TrackerUnavailable::NoResponse
+        );
+        tracker.await.unwrap();
+
+        // With the tracker thread gone the request cannot be queued
+        assert_eq!(
+            tx.request(requests::GetNotes {}).await.unwrap_err(),
+            TrackerUnavailable::NotQueued
+        );
+    }
+}
@@ -0,0 +1,461 @@
+//! Custody of the tracker signing key
+//!
+//! Every signature the tracker hands out (redemption approvals and signed
+//! proof bundles) goes through a [`TrackerSigner`]. The key can be held in
+//! the server process, in the Ergo node wallet, or by a remote signer in
+//! front of an HSM, so a high-value tracker never needs the secret on disk.
+//!
+//! A remote signer receives `{"pubkey": <hex>, "message": <hex>}` and answers
+//! `{"signature": <hex>}` with a 65-byte Basis Schnorr signature. Over HTTP
+//! the request is a POST to the configured URL; over a Unix socket it is
+//! written as one JSON line and the answer is read back as one JSON line.
+//!
+//! Tracker box commitment transactions are signed by the node wallet and do
+//! not go through the signer.
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+
+use basis_store::reqwest;
+use basis_store::{Network, PubKey, Signature};
+use serde::{Deserialize, Serialize};
+
+use crate::config::AppConfig;
+use crate::signing_service::{call_schnorr_sign_api, SigningError};
+
+/// Signature being produced by a [`TrackerSigner`]
+pub type SignFuture<'a> = Pin<Box<dyn Future<Output = Result<Signature, SigningError>> + Send + 'a>>;
+
+/// Holder of the tracker secret key
+pub trait TrackerSigner: Send + Sync + std::fmt::Debug {
+    /// Name recorded as the `signer` of audit log entries
+    fn kind(&self) -> &'static str;
+
+    /// Schnorr-sign `message` with the secret key of `tracker_pubkey`
+    fn sign<'a>(&'a self, message: &'a [u8], tracker_pubkey: &'a PubKey) -> SignFuture<'a>;
+}
+
+/// Where the tracker signing key is held
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SignerKind {
+    /// Remote when a URL or socket is configured, local when a key is, the node wallet otherwise
+    #[default]
+    Auto,
+    /// Key from `ergo.tracker_secret_key` or `signer.key_file`
+    Local,
+    /// Ergo node wallet holding the tracker key
+    Node,
+    /// Remote signer over HTTP or a Unix socket
+    Remote,
+}
+
+/// Tracker signing key custody
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SignerConfig {
+    #[serde(default)]
+    pub kind: SignerKind,
+    /// File holding the hex-encoded 32-byte secret key, for the local signer
+    #[serde(default)]
+    pub key_file: Option<PathBuf>,
+    /// URL the remote signer accepts signing requests on
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Unix socket the remote signer listens on, instead of a URL
+    #[serde(default)]
+    pub socket_path: Option<PathBuf>,
+    /// Key sent in the `api_key` header of HTTP signing requests
+    #[serde(default)]
+    pub api_key: Option<String>,
+    /// Seconds to wait for the remote signer
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+impl Default for SignerConfig {
+    fn default() -> Self {
+        Self {
+            kind: SignerKind::Auto,
+            key_file: None,
+            url: None,
+            socket_path: None,
+            api_key: None,
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+impl SignerConfig {
+    /// Signer in use; `has_secret_key` tells whether `ergo.tracker_secret_key` is set
+    pub fn resolved_kind(&self, has_secret_key: bool) -> SignerKind {
+        match self.kind {
+            SignerKind::Auto if self.url.is_some() || self.socket_path.is_some() => SignerKind::Remote,
+            SignerKind::Auto if has_secret_key || self.key_file.is_some() => SignerKind::Local,
+            SignerKind::Auto => SignerKind::Node,
+            kind => kind,
+        }
+    }
+}
+
+/// Read a hex-encoded 32-byte secret key from a file
+pub fn read_key_file(path: &Path) -> Result<[u8; 32], String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+    hex::decode(content.trim())
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| format!("{} must hold a hex-encoded 32-byte secret key", path.display()))
+}
+
+/// Signer configured for the tracker
+pub fn from_config(config: &AppConfig) -> Result<Arc<dyn TrackerSigner>, SigningError> {
+    let secret_key = config.tracker_secret_key_bytes();
+    let signer: Arc<dyn TrackerSigner> = match config.signer.resolved_kind(secret_key.is_some()) {
+        SignerKind::Local => {
+            let secret_key = match (&config.signer.key_file, secret_key) {
+                (Some(path), _) => read_key_file(path).map_err(SigningError::NotConfigured)?,
+                (None, Some(secret_key)) => secret_key,
+                (None, None) => {
+                    return Err(SigningError::NotConfigured(
+                        "the local signer needs ergo.tracker_secret_key or signer.key_file".to_string(),
+                    ))
+                }
+            };
+            Arc::new(LocalKeySigner::new(secret_key))
+        }
+        SignerKind::Node => Arc::new(NodeWalletSigner {
+            node_url: config.ergo.node.node_url.clone(),
+            api_key: config.ergo.node.api_key.clone(),
+            network: config.network(),
+        }),
+        SignerKind::Remote | SignerKind::Auto => {
+            let timeout = Duration::from_secs(config.signer.timeout_secs);
+            let endpoint = match (&config.signer.url, &config.signer.socket_path) {
+                (Some(url), None) => RemoteEndpoint::Http {
+                    client: reqwest::Client::builder()
+                        .timeout(timeout)
+                        .build()
+                        .map_err(|e| SigningError::NotConfigured(format!("Failed to build HTTP client: {}", e)))?,
+                    url: url.clone(),
+                    api_key: config.signer.api_key.clone(),
+                },
+                (None, Some(path)) => RemoteEndpoint::UnixSocket {
+                    path: path.clone(),
+                    timeout,
+                },
+                _ => {
+                    return Err(SigningError::NotConfigured(
+                        "the remote signer needs exactly one of signer.url and signer.socket_path".to_string(),
+                    ))
+                }
+            };
+            Arc::new(RemoteSigner { endpoint })
+        }
+    };
+    Ok(signer)
+}
+
+/// Signs with a secret key held in the server process
+pub struct LocalKeySigner {
+    secret_key: [u8; 32],
+}
+
+impl LocalKeySigner {
+    pub fn new(secret_key: [u8; 32]) -> Self {
+        Self { secret_key }
+    }
+}
+
+// The secret key must not end up in logs
+impl std::fmt::Debug for LocalKeySigner {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalKeySigner").finish_non_exhaustive()
+    }
+}
+
+impl TrackerSigner for LocalKeySigner {
+    fn kind(&self) -> &'static str {
+        "local"
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8], tracker_pubkey: &'a PubKey) -> SignFuture<'a> {
+        Box::pin(async move {
+            basis_store::schnorr::schnorr_sign(message, &self.secret_key, tracker_pubkey)
+                .map_err(|e| SigningError::Signing(format!("{:?}", e)))
+        })
+    }
+}
+
+/// Signs with the tracker key held by the Ergo node wallet
+#[derive(Debug)]
+pub struct NodeWalletSigner {
+    node_url: String,
+    api_key: Option<String>,
+    network: Network,
+}
+
+impl TrackerSigner for NodeWalletSigner {
+    fn kind(&self) -> &'static str {
+        "node"
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8], tracker_pubkey: &'a PubKey) -> SignFuture<'a> {
+        Box::pin(async move {
+            let address = tracker_address(tracker_pubkey, self.network)?;
+            let signature_hex = call_schnorr_sign_api(
+                &self.node_url,
+                self.api_key.as_deref(),
+                &address,
+                &hex::encode(message),
+            )
+            .await
+            .map_err(SigningError::Signing)?;
+            let signature = decode_signature(&signature_hex, "Ergo node")?;
+
+            // The node's schnorrSign output is not always accepted by the Basis
+            // contract (z must fit in 255 bits); only hand out signatures that verify
+            basis_store::schnorr::schnorr_verify(&signature, message, tracker_pubkey).map_err(|_| {
+                SigningError::Signing(
+                    "Ergo node signature is incompatible with the Basis contract; configure a local or remote signer"
+                        .to_string(),
+                )
+            })?;
+            Ok(signature)
+        })
+    }
+}
+
+/// Where a remote signer is reached
+#[derive(Debug)]
+enum RemoteEndpoint {
+    Http {
+        client: reqwest::Client,
+        url: String,
+        api_key: Option<String>,
+    },
+    UnixSocket {
+        path: PathBuf,
+        timeout: Duration,
+    },
+}
+
+/// Signs through a remote signer, typically in front of an HSM
+#[derive(Debug)]
+pub struct RemoteSigner {
+    endpoint: RemoteEndpoint,
+}
+
+#[derive(Serialize)]
+struct RemoteSignRequest {
+    pubkey: String,
+    message: String,
+}
+
+#[derive(Deserialize)]
+struct RemoteSignResponse {
+    signature: Option<String>,
+    error: Option<String>,
+}
+
+impl TrackerSigner for RemoteSigner {
+    fn kind(&self) -> &'static str {
+        "remote"
+    }
+
+    fn sign<'a>(&'a self, message: &'a [u8], tracker_pubkey: &'a PubKey) -> SignFuture<'a> {
+        Box::pin(async move {
+            let request = RemoteSignRequest {
+                pubkey: hex::encode(tracker_pubkey),
+                message: hex::encode(message),
+            };
+            let response = match &self.endpoint {
+                RemoteEndpoint::Http { client, url, api_key } => {
+                    let mut builder = client.post(url).json(&request);
+                    if let Some(key) = api_key {
+                        builder = builder.header("api_key", key);
+                    }
+                    let response = builder
+                        .send()
+                        .await
+                        .map_err(|e| SigningError::Signing(format!("Remote signer unreachable: {}", e)))?;
+                    let status = response.status();
+                    let body = response
+                        .text()
+                        .await
+                        .map_err(|e| SigningError::Signing(format!("Failed to read remote signer response: {}", e)))?;
+                    if !status.is_success() {
+                        return Err(SigningError::Signing(format!(
+                            "Remote signer returned {}: {}",
+                            status.as_u16(),
+                            body
+                        )));
+                    }
+                    body
+                }
+                RemoteEndpoint::UnixSocket { path, timeout } => {
+                    tokio::time::timeout(*timeout, sign_over_socket(path, &request))
+                        .await
+                        .map_err(|_| SigningError::Signing("Remote signer timed out".to_string()))??
+                }
+            };
+
+            let response: RemoteSignResponse = serde_json::from_str(&response)
+                .map_err(|e| SigningError::Signing(format!("Malformed remote signer response: {}", e)))?;
+            match (response.signature, response.error) {
+                (Some(signature), _) => decode_signature(&signature, "Remote signer"),
+                (None, error) => Err(SigningError::Signing(format!(
+                    "Remote signer refused to sign: {}",
+                    error.unwrap_or_else(|| "no reason given".to_string())
+                ))),
+            }
+        })
+    }
+}
+
+// Write the request as one JSON line and read the one-line answer
+#[cfg(unix)]
+async fn sign_over_socket(path: &Path, request: &RemoteSignRequest) -> Result<String, SigningError> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let mut stream = tokio::net::UnixStream::connect(path).await.map_err(|e| {
+        SigningError::Signing(format!("Remote signer socket {} unreachable: {}", path.display(), e))
+    })?;
+    let mut line = serde_json::to_vec(request)
+        .map_err(|e| SigningError::Signing(format!("Failed to encode signing request: {}", e)))?;
+    line.push(b'\n');
+    let io_error = |e: std::io::Error| SigningError::Signing(format!("Remote signer socket error: {}", e));
+    stream.write_all(&line).await.map_err(io_error)?;
+
+    let mut response = String::new();
+    BufReader::new(stream).read_line(&mut response).await.map_err(io_error)?;
+    Ok(response)
+}
+
+#[cfg(not(unix))]
+async fn sign_over_socket(path: &Path, _request: &RemoteSignRequest) -> Result<String, SigningError> {
+    Err(SigningError::NotConfigured(format!(
+        "Unix sockets are not supported on this platform: {}",
+        path.display()
+    )))
+}
+
+fn decode_signature(signature_hex: &str, signer: &str) -> Result<Signature, SigningError> {
+    hex::decode(signature_hex)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| SigningError::Signing(format!("{} returned a malformed signature", signer)))
+}
+
+fn tracker_address(tracker_pubkey: &PubKey, network: Network) -> Result<String, SigningError> {
+    use ergo_lib::ergotree_ir::address::Address;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+    use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+    use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+
+    let ec_point = EcPoint::sigma_parse_bytes(tracker_pubkey).map_err(|e| {
+        SigningError::NotConfigured(format!("Failed to parse tracker public key: {}", e))
+    })?;
+    Ok(network.encode_address(&Address::P2Pk(ProveDlog::from(ec_point))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{routing::post, Json, Router};
+    use basis_store::schnorr::{generate_keypair, schnorr_sign, schnorr_verify};
+
+    // Sign like an HSM front-end would, refusing keys it does not hold
+    fn answer(secret_key: &[u8; 32], pubkey: &PubKey, request: &serde_json::Value) -> serde_json::Value {
+        if request["pubkey"] != hex::encode(pubkey) {
+            return serde_json::json!({ "error": "unknown key" });
+        }
+        let message = hex::decode(request["message"].as_str().unwrap()).unwrap();
+        let signature = schnorr_sign(&message, secret_key, pubkey).unwrap();
+        serde_json::json!({ "signature": hex::encode(signature) })
+    }
+
+    #[tokio::test]
+    async fn test_remote_signer_over_http_and_unix_socket() {
+        let (secret_key, pubkey) = generate_keypair();
+        let (_, other_pubkey) = generate_keypair();
+        let message = b"redemption message".to_vec();
+
+        let app = Router::new().route(
+            "/sign",
+            post(move |Json(request): Json<serde_json::Value>| async move {
+                Json(answer(&secret_key, &pubkey, &request))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/sign", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let http = RemoteSigner {
+            endpoint: RemoteEndpoint::Http {
+                client: reqwest::Client::new(),
+                url,
+                api_key: None,
+            },
+        };
+        let signature = http.sign(&message, &pubkey).await.unwrap();
+        assert!(schnorr_verify(&signature, &message, &pubkey).is_ok());
+        let refused = http.sign(&message, &other_pubkey).await.unwrap_err();
+        assert!(refused.to_string().contains("unknown key"), "{}", refused);
+
+        #[cfg(unix)]
+        {
+            use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+            let socket_path = std::env::temp_dir().join(format!("basis_signer_{}.sock", std::process::id()));
+            let _ = std::fs::remove_file(&socket_path);
+            let listener = tokio::net::UnixListener::bind(&socket_path).unwrap();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    let (reader, mut writer) = stream.into_split();
+                    let mut line = String::new();
+                    BufReader::new(reader).read_line(&mut line).await.unwrap();
+                    let request = serde_json::from_str(&line).unwrap();
+                    let mut response = answer(&secret_key, &pubkey, &request).to_string();
+                    response.push('\n');
+                    writer.write_all(response.as_bytes()).await.unwrap();
+                }
+            });
+
+            let socket = RemoteSigner {
+                endpoint: RemoteEndpoint::UnixSocket {
+                    path: socket_path.clone(),
+                    timeout: Duration::from_secs(5),
+                },
+            };
+            assert_eq!(socket.kind(), "remote");
+            let signature = socket.sign(&message, &pubkey).await.unwrap();
+            assert!(schnorr_verify(&signature, &message, &pubkey).is_ok());
+            std::fs::remove_file(&socket_path).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_signer_kind_resolution() {
+        let mut config = SignerConfig::default();
+        assert_eq!(config.resolved_kind(false), SignerKind::Node);
+        assert_eq!(config.resolved_kind(true), SignerKind::Local);
+        config.socket_path = Some(PathBuf::from("/run/basis-signer.sock"));
+        assert_eq!(config.resolved_kind(true), SignerKind::Remote);
+        config.kind = SignerKind::Node;
+        assert_eq!(config.resolved_kind(true), SignerKind::Node);
+
+        let key_file = std::env::temp_dir().join(format!("basis_signer_key_{}", std::process::id()));
+        std::fs::write(&key_file, format!("{}\n", hex::encode([7u8; 32]))).unwrap();
+        assert_eq!(read_key_file(&key_file), Ok([7u8; 32]));
+        std::fs::write(&key_file, "not a key").unwrap();
+        assert!(read_key_file(&key_file).is_err());
+        std::fs::remove_file(&key_file).unwrap();
+    }
+}
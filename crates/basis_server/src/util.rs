@@ -0,0 +1,25 @@
+//! Small helpers shared by the modules keeping their own state files and
+//! timestamps
+
+use std::path::Path;
+
+/// Replace the file at `path` with `bytes`, creating its directory if needed
+///
+/// The bytes are written aside and renamed over the file, so a crash never
+/// leaves a truncated file behind.
+pub(crate) fn write_file_atomically(path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let partial = path.with_extension("partial");
+    std::fs::write(&partial, bytes)?;
+    std::fs::rename(&partial, path)
+}
+
+/// Seconds since the Unix epoch, or 0 if the clock is before it
+pub(crate) fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
@@ -0,0 +1,317 @@
+//! Unsigned transactions for users' wallets to sign
+//!
+//! Reserve creation, top-ups and redemptions are returned as EIP-12 unsigned
+//! transactions for dApp connectors such as Nautilus, together with an
+//! ErgoPay URL for mobile wallets. The tracker only reads boxes from the node;
+//! the keys stay with the user.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use basis_store::{
+    ergo_scanner::ScannerError,
+    transaction_builder::{TransactionBuilderError, TxContext},
+    wallet_connector::{self, Eip12Box, NewReserve, RedemptionSpend, WalletTransaction},
+    PubKey,
+};
+
+use crate::{
+    models::{
+        error_response, success_response, ApiResponse, ErgoPayResponse, WalletCreateReserveRequest,
+        WalletRedeemRequest, WalletTopUpRequest, WalletTxResponse,
+    },
+    AppState,
+};
+
+pub(crate) type WalletError = (StatusCode, String);
+
+/// Build a redemption for the receiver's wallet to sign
+///
+/// The reserve box is read from the node and the latest tracker box known to
+/// the tracker is used as the data input. The receiver's address gets the
+/// redeemed amount less the transaction fee.
+pub async fn wallet_redeem(
+    State(state): State<AppState>,
+    Json(payload): Json<WalletRedeemRequest>,
+) -> (StatusCode, Json<ApiResponse<WalletTxResponse>>) {
+    tracing::debug!("Building wallet redemption from reserve {}", payload.reserve_box_id);
+    respond(build_redemption(&state, &payload).await)
+}
+
+async fn build_redemption(state: &AppState, payload: &WalletRedeemRequest) -> Result<WalletTxResponse, WalletError> {
+    let receiver_pubkey = parse_pubkey("recipient_pubkey", &payload.recipient_pubkey)?;
+    let spend = RedemptionSpend {
+        receiver_pubkey,
+        total_debt: payload.total_debt,
+        timestamp: payload.timestamp,
+        amount: payload.amount,
+        already_redeemed: payload.already_redeemed,
+        reserve_signature: parse_hex("reserve_signature", &payload.reserve_signature)?,
+        tracker_signature: parse_hex("tracker_signature", &payload.tracker_signature)?,
+        tracker_lookup_proof: parse_hex("tracker_lookup_proof", &payload.tracker_lookup_proof)?,
+        reserve_lookup_proof: payload
+            .reserve_lookup_proof
+            .as_deref()
+            .map(|proof| parse_hex("reserve_lookup_proof", proof))
+            .transpose()?,
+        reserve_insert_proof: parse_hex("reserve_insert_proof", &payload.reserve_insert_proof)?,
+    };
+
+    let receiver_address = p2pk_address(state, &receiver_pubkey)?;
+    let context = tx_context(state, &receiver_address).await?;
+    let reserve = node_box(state, &payload.reserve_box_id).await?;
+    let tracker_box_id = latest_tracker_box_id(state)?;
+    let tracker = node_box(state, &tracker_box_id).await?;
+
+    let tx = wallet_connector::redemption_tx(&reserve, &tracker, &spend, &state.contracts, &context).map_err(builder_error)?;
+    tracing::info!(
+        "Built wallet redemption of {} nanoERG from reserve {}",
+        payload.amount,
+        payload.reserve_box_id
+    );
+    wallet_response(tx)
+}
+
+/// Build a reserve creation for the owner's wallet to sign
+///
+/// Funded from the unspent boxes of `change_address`, one of which must hold
+/// the reserve NFT. The reserve backs notes of the tracker configured in
+/// `ergo.tracker_nft_id`.
+pub async fn wallet_create_reserve(
+    State(state): State<AppState>,
+    Json(payload): Json<WalletCreateReserveRequest>,
+) -> (StatusCode, Json<ApiResponse<WalletTxResponse>>) {
+    tracing::debug!("Building wallet reserve creation for {}", payload.owner_pubkey);
+    respond(build_reserve_creation(&state, &payload).await)
+}
+
+async fn build_reserve_creation(
+    state: &AppState,
+    payload: &WalletCreateReserveRequest,
+) -> Result<WalletTxResponse, WalletError> {
+    let tracker_nft = state.config.tracker_nft_bytes().map_err(|_| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "ergo.tracker_nft_id is not configured".to_string(),
+        )
+    })?;
+    let reserve = NewReserve {
+        owner_pubkey: parse_pubkey("owner_pubkey", &payload.owner_pubkey)?,
+        reserve_nft_id: payload.nft_id.clone(),
+        tracker_nft_id: hex::encode(tracker_nft),
+        amount: payload.erg_amount,
+    };
+
+    let context = tx_context(state, &payload.change_address).await?;
+    let funding = address_boxes(state, &payload.change_address).await?;
+    let tx = wallet_connector::reserve_creation_tx(&reserve, &funding, &state.contracts, &context).map_err(builder_error)?;
+    tracing::info!(
+        "Built wallet reserve creation of {} nanoERG for {}",
+        payload.erg_amount,
+        payload.owner_pubkey
+    );
+    wallet_response(tx)
+}
+
+/// Build a reserve top-up for the payer's wallet to sign
+pub async fn wallet_top_up(
+    State(state): State<AppState>,
+    Path(box_id): Path<String>,
+    Json(payload): Json<WalletTopUpRequest>,
+) -> (StatusCode, Json<ApiResponse<WalletTxResponse>>) {
+    tracing::debug!("Building wallet top-up of reserve {}", box_id);
+    respond(build_top_up(&state, &box_id, payload.amount, &payload.change_address).await)
+}
+
+async fn build_top_up(
+    state: &AppState,
+    box_id: &str,
+    amount: u64,
+    address: &str,
+) -> Result<WalletTxResponse, WalletError> {
+    let context = tx_context(state, address).await?;
+    let reserve = node_box(state, box_id).await?;
+    let funding = address_boxes(state, address).await?;
+    let tx = wallet_connector::top_up_tx(&reserve, amount, &funding, &state.contracts, &context).map_err(builder_error)?;
+    tracing::info!("Built wallet top-up of {} nanoERG for reserve {}", amount, box_id);
+    wallet_response(tx)
+}
+
+/// ErgoPay (EIP-20) top-up of a reserve
+///
+/// Target of the dynamic ErgoPay URL
+/// `ergopay://<host>/ergopay/reserves/{box_id}/top-up/{amount}/#P2PK_ADDRESS#`,
+/// where the wallet fills in its own address.
+pub async fn ergopay_top_up(
+    State(state): State<AppState>,
+    Path((box_id, amount, address)): Path<(String, u64, String)>,
+) -> (StatusCode, Json<ErgoPayResponse>) {
+    tracing::debug!("ErgoPay top-up of reserve {} from {}", box_id, address);
+    match build_top_up(&state, &box_id, amount, &address).await {
+        Ok(response) => {
+            let reduced_tx = response.ergopay_url.strip_prefix("ergopay:").map(str::to_string);
+            (
+                StatusCode::OK,
+                Json(ErgoPayResponse {
+                    reduced_tx,
+                    address: Some(address),
+                    message: format!(
+                        "Add {} ERG to reserve {}",
+                        amount as f64 / 1_000_000_000.0,
+                        box_id
+                    ),
+                    message_severity: "INFORMATION".to_string(),
+                }),
+            )
+        }
+        Err((status, message)) => (
+            status,
+            Json(ErgoPayResponse {
+                message,
+                message_severity: "ERROR".to_string(),
+                ..ErgoPayResponse::default()
+            }),
+        ),
+    }
+}
+
+fn respond(result: Result<WalletTxResponse, WalletError>) -> (StatusCode, Json<ApiResponse<WalletTxResponse>>) {
+    match result {
+        Ok(response) => (StatusCode::OK, Json(success_response(response))),
+        Err((status, message)) => {
+            tracing::warn!("Failed to build wallet transaction: {}", message);
+            (status, Json(error_response(message)))
+        }
+    }
+}
+
+fn wallet_response(tx: WalletTransaction) -> Result<WalletTxResponse, WalletError> {
+    Ok(WalletTxResponse {
+        tx_id: tx.tx_id().map_err(builder_error)?,
+        ergopay_url: tx.ergopay_url().map_err(builder_error)?,
+        unsigned_tx: tx.unsigned_tx,
+    })
+}
+
+fn builder_error(error: TransactionBuilderError) -> WalletError {
+    match error {
+        TransactionBuilderError::TransactionBuilding(message) => (StatusCode::INTERNAL_SERVER_ERROR, message),
+        TransactionBuilderError::InsufficientFunds(message) | TransactionBuilderError::Configuration(message) => {
+            (StatusCode::BAD_REQUEST, message)
+        }
+    }
+}
+
+async fn tx_context(state: &AppState, change_address: &str) -> Result<TxContext, WalletError> {
+    let network = state.config.network();
+    network
+        .parse_address(change_address)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid address {}: {}", change_address, e)))?;
+
+    let current_height = state.ergo_scanner.lock().await.get_current_height().await.map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to get blockchain height: {}", e),
+        )
+    })?;
+
+    Ok(TxContext {
+        current_height: current_height as u32,
+        fee: state.config.transaction.fee,
+        change_address: change_address.to_string(),
+        network_prefix: network.prefix_byte(),
+    })
+}
+
+fn p2pk_address(state: &AppState, pubkey: &PubKey) -> Result<String, WalletError> {
+    let context = TxContext {
+        current_height: 0,
+        fee: 0,
+        change_address: String::new(),
+        network_prefix: state.config.network().prefix_byte(),
+    };
+    wallet_connector::p2pk_address(pubkey, &context).map_err(builder_error)
+}
+
+/// Unspent box read from the node
+pub(crate) async fn node_box(state: &AppState, box_id: &str) -> Result<Eip12Box, WalletError> {
+    let json = state
+        .ergo_scanner
+        .lock()
+        .await
+        .get_unspent_box(box_id)
+        .await
+        .map_err(|e| match e {
+            ScannerError::InvalidReserveBox(message) => (StatusCode::BAD_REQUEST, message),
+            ScannerError::NodeError(_) => (StatusCode::NOT_FOUND, format!("Unspent box {} not found", box_id)),
+            e => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch box {}: {}", box_id, e)),
+        })?;
+    serde_json::from_value(json).map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Unexpected box {} from node: {}", box_id, e),
+        )
+    })
+}
+
+async fn address_boxes(state: &AppState, address: &str) -> Result<Vec<Eip12Box>, WalletError> {
+    let boxes = state
+        .ergo_scanner
+        .lock()
+        .await
+        .get_unspent_boxes_by_address(address)
+        .await
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to fetch boxes of {}: {}", address, e),
+            )
+        })?;
+    boxes
+        .into_iter()
+        .map(serde_json::from_value)
+        .collect::<Result<_, _>>()
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Unexpected box of {} from node: {}", address, e),
+            )
+        })
+}
+
+// Latest tracker box recorded by the tracker scanner, as a node box ID
+fn latest_tracker_box_id(state: &AppState) -> Result<String, WalletError> {
+    let latest = state
+        .tracker_storage
+        .get_all_tracker_boxes()
+        .map_err(|e| {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to retrieve tracker boxes: {:?}", e),
+            )
+        })?
+        .into_iter()
+        .max_by_key(|box_info| box_info.creation_height)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, "No tracker boxes found".to_string()))?;
+    node_id(&latest.box_id)
+}
+
+/// Node box ID of a box ID stored by the scanners, which keep some hex-encoded
+pub(crate) fn node_id(box_id: &str) -> Result<String, WalletError> {
+    if box_id.len() == 64 {
+        return Ok(box_id.to_string());
+    }
+    basis_store::reserve_lineage::node_box_id(box_id)
+        .ok_or_else(|| (StatusCode::INTERNAL_SERVER_ERROR, format!("Invalid box ID {}", box_id)))
+}
+
+fn parse_pubkey(field: &str, value: &str) -> Result<PubKey, WalletError> {
+    basis_store::schnorr::pubkey_from_hex(value)
+        .map_err(|_| (StatusCode::BAD_REQUEST, format!("{} must be 33 bytes hex-encoded", field)))
+}
+
+fn parse_hex(field: &str, value: &str) -> Result<Vec<u8>, WalletError> {
+    hex::decode(value).map_err(|_| (StatusCode::BAD_REQUEST, format!("{} must be hex-encoded", field)))
+}
@@ -0,0 +1,55 @@
+//! Watcher mode: a read-only auditor deployment
+//!
+//! A watcher runs the same scanners and note store as a tracker but holds no
+//! tracker signing key. Recipients keep submitting signed notes, from which
+//! the watcher rebuilds the AVL tree the official tracker should have
+//! committed. Endpoints that would make the watcher sign, redeem or otherwise
+//! act for the tracker are refused.
+
+use crate::config::WatcherConfig;
+use axum::{
+    extract::{Request, State},
+    http::{Method, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use std::sync::Arc;
+
+/// Endpoints refused in watcher mode
+pub const WATCHER_DISABLED_PATHS: &[&str] = &[
+    "/redeem",
+    "/redeem/complete",
+    "/tracker/signature",
+    "/redemption/prepare",
+    "/admin/commit",
+];
+
+/// Whether the request is refused under the given configuration
+pub fn disabled_in_watcher_mode(watcher: &WatcherConfig, method: &Method, path: &str) -> bool {
+    watcher.enabled && *method == Method::POST && WATCHER_DISABLED_PATHS.contains(&path)
+}
+
+/// Middleware refusing tracker-only requests when running as a watcher
+pub async fn watcher_layer(
+    State(watcher): State<Arc<WatcherConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if !disabled_in_watcher_mode(&watcher, request.method(), request.uri().path()) {
+        return next.run(request).await;
+    }
+
+    tracing::debug!(
+        "Refused {} {} in watcher mode",
+        request.method(),
+        request.uri().path()
+    );
+    (
+        StatusCode::FORBIDDEN,
+        Json(crate::models::error_response::<()>(
+            "Endpoint is disabled: this server runs in watcher mode".to_string(),
+        )),
+    )
+        .into_response()
+}
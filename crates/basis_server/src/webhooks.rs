@@ -0,0 +1,318 @@
+//! Signed webhook callbacks for tracker events
+//!
+//! Operators register URLs that receive a POST for every `CollateralAlert`,
+//! `ReserveRedeemed`, `Commitment` or `ReplicaDiverged` event, so bots can top
+//! up reserves and operators hear of a stalled replica without polling
+//! `/events`. The body is the event as JSON, the same shape
+//! `/events` returns. Each request carries:
+//!
+//! - `X-Basis-Event`: the event type
+//! - `X-Basis-Signature`: `sha256=` followed by the hex HMAC-SHA256 of the body,
+//!   keyed with the endpoint's secret
+//!
+//! Failed deliveries are retried with exponential backoff; client errors other
+//! than 429 are not retried.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use basis_store::reqwest;
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+use crate::models::TrackerEvent;
+
+/// Event types webhooks can subscribe to
+pub const WEBHOOK_EVENT_TYPES: &[&str] = &["CollateralAlert", "ReserveRedeemed", "Commitment", "ReplicaDiverged"];
+
+/// Header carrying the event type
+pub const EVENT_HEADER: &str = "X-Basis-Event";
+
+/// Header carrying the HMAC signature of the body
+pub const SIGNATURE_HEADER: &str = "X-Basis-Signature";
+
+/// Webhook endpoints and delivery policy
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookConfig {
+    /// Endpoints notified of events
+    #[serde(default)]
+    pub endpoints: Vec<WebhookEndpoint>,
+    /// Delivery attempts per event and endpoint, including the first
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubles with every further retry
+    #[serde(default = "default_initial_backoff_ms")]
+    pub initial_backoff_ms: u64,
+    /// Seconds to wait for an endpoint to respond
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_max_attempts() -> u32 {
+    5
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    1_000
+}
+
+fn default_timeout_secs() -> u64 {
+    10
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            endpoints: Vec::new(),
+            max_attempts: default_max_attempts(),
+            initial_backoff_ms: default_initial_backoff_ms(),
+            timeout_secs: default_timeout_secs(),
+        }
+    }
+}
+
+/// A URL notified of tracker events
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WebhookEndpoint {
+    pub url: String,
+    /// Key of the HMAC signing each request
+    pub secret: String,
+    /// Event types delivered to this endpoint; all of [`WEBHOOK_EVENT_TYPES`] when empty
+    #[serde(default)]
+    pub events: Vec<String>,
+}
+
+impl WebhookEndpoint {
+    /// Whether events of this type are delivered to the endpoint
+    pub fn subscribes_to(&self, event_type: &str) -> bool {
+        WEBHOOK_EVENT_TYPES.contains(&event_type)
+            && (self.events.is_empty() || self.events.iter().any(|e| e == event_type))
+    }
+}
+
+/// `X-Basis-Signature` value for a request body
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Delivers events to the configured endpoints
+#[derive(Debug, Clone)]
+pub struct WebhookDispatcher {
+    config: Arc<WebhookConfig>,
+    client: reqwest::Client,
+}
+
+impl WebhookDispatcher {
+    pub fn new(config: WebhookConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(config.timeout_secs))
+            .build()
+            .unwrap_or_default();
+        Self {
+            config: Arc::new(config),
+            client,
+        }
+    }
+
+    /// Deliver an event to every endpoint subscribed to it, in the background
+    pub fn dispatch(&self, event: &TrackerEvent) {
+        let event_type = event.event_type.name();
+        let body = match serde_json::to_vec(event) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::warn!("Failed to serialize {} event for webhooks: {}", event_type, e);
+                return;
+            }
+        };
+
+        for endpoint in self.config.endpoints.iter().filter(|e| e.subscribes_to(event_type)) {
+            let dispatcher = self.clone();
+            let endpoint = endpoint.clone();
+            let body = body.clone();
+            let event_id = event.id;
+            tokio::spawn(async move {
+                if let Err(e) = dispatcher.deliver(&endpoint, event_type, &body).await {
+                    tracing::warn!(
+                        "Giving up on webhook delivery of {} event {} to {}: {}",
+                        event_type,
+                        event_id,
+                        endpoint.url,
+                        e
+                    );
+                }
+            });
+        }
+    }
+
+    /// POST a body to an endpoint, retrying with exponential backoff
+    pub async fn deliver(&self, endpoint: &WebhookEndpoint, event_type: &str, body: &[u8]) -> Result<(), String> {
+        let signature = sign_payload(&endpoint.secret, body);
+        let mut backoff = Duration::from_millis(self.config.initial_backoff_ms);
+        let mut last_error = String::new();
+
+        for attempt in 1..=self.config.max_attempts.max(1) {
+            if attempt > 1 {
+                tokio::time::sleep(backoff).await;
+                backoff = backoff.saturating_mul(2);
+            }
+
+            let response = self
+                .client
+                .post(&endpoint.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(EVENT_HEADER, event_type)
+                .header(SIGNATURE_HEADER, &signature)
+                .body(body.to_vec())
+                .send()
+                .await;
+            match response {
+                Ok(response) if response.status().is_success() => return Ok(()),
+                Ok(response) => {
+                    let status = response.status();
+                    last_error = format!("endpoint responded {}", status);
+                    if status.is_client_error() && status != reqwest::StatusCode::TOO_MANY_REQUESTS {
+                        return Err(last_error);
+                    }
+                }
+                Err(e) => last_error = format!("request failed: {}", e),
+            }
+            tracing::debug!(
+                "Webhook delivery to {} failed (attempt {}): {}",
+                endpoint.url,
+                attempt,
+                last_error
+            );
+        }
+
+        Err(last_error)
+    }
+
+    /// Deliver every stored event until shutdown
+    pub async fn run(
+        self,
+        mut events: tokio::sync::broadcast::Receiver<TrackerEvent>,
+        mut shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+    ) {
+        use tokio::sync::broadcast::error::RecvError;
+
+        loop {
+            tokio::select! {
+                _ = shutdown_rx.recv() => break,
+                event = events.recv() => match event {
+                    Ok(event) => self.dispatch(&event),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Webhook dispatcher fell behind, {} events not delivered", skipped);
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::EventType;
+    use axum::{extract::State, http::HeaderMap, http::StatusCode, routing::post, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::sync::Mutex;
+
+    #[derive(Clone, Default)]
+    struct Receiver {
+        calls: Arc<AtomicUsize>,
+        delivered: Arc<Mutex<Vec<(String, String, Vec<u8>)>>>,
+    }
+
+    // Fails the first request, then accepts
+    async fn receive(State(receiver): State<Receiver>, headers: HeaderMap, body: axum::body::Bytes) -> StatusCode {
+        if receiver.calls.fetch_add(1, Ordering::SeqCst) == 0 {
+            return StatusCode::SERVICE_UNAVAILABLE;
+        }
+        let header = |name: &str| headers.get(name).unwrap().to_str().unwrap().to_string();
+        receiver
+            .delivered
+            .lock()
+            .await
+            .push((header(EVENT_HEADER), header(SIGNATURE_HEADER), body.to_vec()));
+        StatusCode::OK
+    }
+
+    fn event(event_type: EventType) -> TrackerEvent {
+        TrackerEvent {
+            id: 7,
+            seq: 0,
+            event_type,
+            timestamp: 1_700_000_000,
+            issuer_pubkey: None,
+            recipient_pubkey: None,
+            amount: None,
+            reserve_box_id: Some("box".to_string()),
+            collateral_amount: None,
+            redeemed_amount: Some(250),
+            height: Some(1_000),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_delivers_signed_events_with_retry() {
+        let receiver = Receiver::default();
+        let app = Router::new().route("/hook", post(receive)).with_state(receiver.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let dispatcher = WebhookDispatcher::new(WebhookConfig {
+            endpoints: vec![WebhookEndpoint {
+                url,
+                secret: "s3cret".to_string(),
+                events: vec!["ReserveRedeemed".to_string()],
+            }],
+            max_attempts: 3,
+            initial_backoff_ms: 10,
+            timeout_secs: 5,
+        });
+
+        let (tx, rx) = tokio::sync::broadcast::channel(16);
+        let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(1);
+        let task = tokio::spawn(dispatcher.run(rx, shutdown_rx));
+        // Not subscribed: never delivered
+        tx.send(event(EventType::Commitment)).unwrap();
+        tx.send(event(EventType::ReserveRedeemed)).unwrap();
+
+        for _ in 0..100 {
+            if !receiver.delivered.lock().await.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        shutdown_tx.send(()).unwrap();
+        task.await.unwrap();
+
+        let delivered = receiver.delivered.lock().await;
+        assert_eq!(delivered.len(), 1);
+        let (event_type, signature, body) = &delivered[0];
+        assert_eq!(event_type, "ReserveRedeemed");
+        assert_eq!(signature, &sign_payload("s3cret", body));
+        assert_eq!(receiver.calls.load(Ordering::SeqCst), 2);
+        let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(payload["event_type"]["type"], "ReserveRedeemed");
+        assert_eq!(payload["redeemed_amount"], 250);
+    }
+
+    #[test]
+    fn test_endpoint_subscriptions() {
+        let endpoint = |events: &[&str]| WebhookEndpoint {
+            url: "http://localhost/hook".to_string(),
+            secret: "s".to_string(),
+            events: events.iter().map(|e| e.to_string()).collect(),
+        };
+        assert!(endpoint(&[]).subscribes_to("CollateralAlert"));
+        assert!(!endpoint(&[]).subscribes_to("NoteUpdated"));
+        assert!(!endpoint(&["Commitment"]).subscribes_to("ReserveRedeemed"));
+    }
+}
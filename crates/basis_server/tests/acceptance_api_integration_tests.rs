@@ -198,7 +198,7 @@ async fn create_test_app(
     use std::sync::Arc;
     use tokio::sync::Mutex;
     
-    let (tx, _rx) = tokio::sync::mpsc::channel::<TrackerCommand>(100);
+    let (tx, _rx) = basis_server::tracker_queue::tracker_channel(Default::default());
     let event_store = Arc::new(store::EventStore::new_in_memory());
     
     let config = Arc::new(config::AppConfig {
@@ -206,13 +206,17 @@ async fn create_test_app(
             host: "127.0.0.1".to_string(),
             port: 3048,
             database_url: Some("sqlite::memory:".to_string()),
+            shutdown_timeout_secs: basis_server::shutdown::DEFAULT_SHUTDOWN_TIMEOUT_SECS,
         },
         ergo: config::ErgoConfig {
+            network: Default::default(),
             node: NodeConfig {
                 node_url: "http://example.com".to_string(),
                 ..Default::default()
             },
             basis_reserve_contract_p2s: "test".to_string(),
+            reserve_contract_version: 1,
+            legacy_reserve_contracts: Vec::new(),
             tracker_nft_id: Some("test".to_string()),
             tracker_public_key: None,
             tracker_secret_key: None,
@@ -222,6 +226,27 @@ async fn create_test_app(
             change_address: None,
         },
         acceptance: acceptance::config::AcceptanceConfig::empty(),
+        debt_policy: basis_store::debt_policy::DebtPolicyConfig::default(),
+        note_limits: basis_store::NoteLimits::default(),
+        idempotency: basis_server::idempotency::IdempotencyConfig::default(),
+        storage: basis_server::config::StorageConfig::default(),
+        scanner: basis_server::config::ScannerConfig::default(),
+        fee_policy: basis_server::config::FeePolicyConfig::default(),
+        auth: basis_server::config::AuthConfig::default(),
+        watcher: basis_server::config::WatcherConfig::default(),
+        webhooks: basis_server::webhooks::WebhookConfig::default(),
+        commitment_schedule: basis_server::commitment_scheduler::CommitmentScheduleConfig::default(),
+        signer: basis_server::tracker_signer::SignerConfig::default(),
+        tracker_queue: basis_server::tracker_queue::TrackerQueueConfig::default(),
+        replica: basis_server::replica::ReplicaConfig::default(),
+        issuer_policy: basis_server::issuer_policy::IssuerPolicyConfig::default(),
+        maintenance: basis_server::maintenance::MaintenanceConfig::default(),
+        collateral_history: basis_server::collateral_history::CollateralHistoryConfig::default(),
+        pricing: basis_server::pricing::PricingConfig::default(),
+        outbound_tx: basis_server::outbound_tx::OutboundTxConfig::default(),
+        logging: basis_server::logging::LoggingConfig::default(),
+        daemon: basis_server::daemon::DaemonConfig::default(),
+        request_audit: basis_server::request_audit::RequestAuditConfig::default(),
     });
     
     let scanner = basis_store::ergo_scanner::ServerState::new(NodeConfig {
@@ -232,12 +257,21 @@ async fn create_test_app(
     let app_state = AppState {
         tx,
         event_store,
+        reserve_tracker: scanner.reserve_tracker().clone(),
         ergo_scanner: Arc::new(Mutex::new(scanner)),
-        reserve_tracker: Arc::new(Mutex::new(basis_store::ReserveTracker::new())),
-        config,
+        config: config.clone(),
         shared_tracker_state: Arc::new(tokio::sync::Mutex::new(tracker_box_updater::SharedTrackerState::new())),
         tracker_storage: basis_store::persistence::TrackerStorage::open("test_tracker").unwrap(),
         acceptance_predicate,
+        signing_service: Arc::new(basis_server::signing_service::SigningService::from_config(&config)),
+        cold_start: basis_server::cold_start::ColdStartState::default(),
+        replica: basis_server::replica::ReplicaState::default(),
+        issuer_policy: basis_server::issuer_policy::IssuerPolicy::default(),
+        maintenance: basis_server::maintenance::StorageMaintenance::default(),
+        pricing: basis_server::pricing::Pricing::default(),
+        outbound_tx: basis_server::outbound_tx::OutboundTxQueue::default(),
+        request_audit: basis_server::request_audit::RequestAuditLog::default(),
+        contracts: basis_store::ContractRegistry::builtin(),
     };
     
     axum::Router::new()
@@ -32,6 +32,8 @@ mod api_tests {
             issuer_signature: "010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101".to_string(),
             emergency: false,
             tracker_signature: Some("020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202".to_string()),
+            reserve_box: None,
+            reserve_insert_proof: None,
         };
 
         assert!(!request.issuer_pubkey.is_empty());
@@ -0,0 +1,90 @@
+// Servers for integration tests: built with `Server::builder` against an
+// in-process mock Ergo node, each with its own storage directory
+
+#![allow(dead_code)]
+
+use basis_server::{config::*, AppState, Server};
+use basis_store::contract_compiler::get_basis_reserve_contract_p2s;
+use basis_store::mock_node::MockErgoNode;
+use basis_store::schnorr::generate_keypair;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub fn temp_dir() -> PathBuf {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    std::env::temp_dir().join(format!("basis_server_test_{}_{}", std::process::id(), nanos))
+}
+
+/// Configuration of a server keeping its storage under `dir` and scanning
+/// the node at `node_url`
+pub fn test_config(node_url: &str, dir: &Path) -> AppConfig {
+    AppConfig {
+        server: ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+            database_url: Some("sqlite::memory:".to_string()),
+            shutdown_timeout_secs: 10,
+        },
+        ergo: ErgoConfig {
+            network: Default::default(),
+            node: basis_store::ergo_scanner::NodeConfig {
+                node_url: node_url.to_string(),
+                ..Default::default()
+            },
+            basis_reserve_contract_p2s: get_basis_reserve_contract_p2s().unwrap(),
+            reserve_contract_version: 1,
+            legacy_reserve_contracts: Vec::new(),
+            tracker_nft_id: Some("69c5d7a4df2e72252b0015d981876fe338ca240d5576d4e731dfd848ae18fe2b".to_string()),
+            tracker_public_key: Some(hex::encode(generate_keypair().1)),
+            tracker_secret_key: None,
+        },
+        transaction: TransactionConfig {
+            fee: 1000000,
+            change_address: None,
+        },
+        acceptance: basis_server::acceptance::config::AcceptanceConfig::empty(),
+        debt_policy: basis_store::debt_policy::DebtPolicyConfig::default(),
+        note_limits: basis_store::NoteLimits::default(),
+        idempotency: basis_server::idempotency::IdempotencyConfig::default(),
+        storage: StorageConfig {
+            notes_path: dir.join("notes"),
+            avl_tree_path: dir.join("avl"),
+            scanner_metadata_path: dir.join("scanner_metadata"),
+            reserves_path: dir.join("reserves"),
+            tracker_scanner_metadata_path: dir.join("tracker_scanner_metadata"),
+            tracker_boxes_path: dir.join("tracker_boxes"),
+            signing_audit_log_path: dir.join("signing_audit.log"),
+            ..StorageConfig::default()
+        },
+        scanner: ScannerConfig::default(),
+        fee_policy: FeePolicyConfig::default(),
+        auth: AuthConfig::default(),
+        watcher: WatcherConfig::default(),
+        webhooks: basis_server::webhooks::WebhookConfig::default(),
+        commitment_schedule: basis_server::commitment_scheduler::CommitmentScheduleConfig::default(),
+        signer: basis_server::tracker_signer::SignerConfig::default(),
+        tracker_queue: basis_server::tracker_queue::TrackerQueueConfig::default(),
+        replica: basis_server::replica::ReplicaConfig::default(),
+        issuer_policy: basis_server::issuer_policy::IssuerPolicyConfig::default(),
+        maintenance: basis_server::maintenance::MaintenanceConfig::default(),
+        collateral_history: basis_server::collateral_history::CollateralHistoryConfig::default(),
+        pricing: basis_server::pricing::PricingConfig::default(),
+        outbound_tx: basis_server::outbound_tx::OutboundTxConfig::default(),
+        logging: basis_server::logging::LoggingConfig::default(),
+        daemon: basis_server::daemon::DaemonConfig::default(),
+        request_audit: basis_server::request_audit::RequestAuditConfig::default(),
+    }
+}
+
+/// A server with empty storage, scanning a mock node at height 1000
+pub async fn test_server() -> Server {
+    let node = MockErgoNode::start(1_000).await;
+    Server::builder(test_config(&node.url, &temp_dir())).build().await.unwrap()
+}
+
+/// State of a fresh [`test_server`], for calling handlers directly
+///
+/// The tracker thread keeps running for as long as the state is held.
+pub async fn test_state() -> AppState {
+    test_server().await.state().clone()
+}
@@ -1,242 +1,21 @@
 // Comprehensive CORS tests for basis_server
 
+mod common;
+
 #[cfg(test)]
 mod cors_tests {
+use super::common;
 use axum::{
     body::Body,
     http::{Method, Request, StatusCode},
-    routing::{get, post},
     Router,
 };
-use basis_server::{api::*, reserve_api::*, store::EventStore, AppState, TrackerCommand};
-use std::sync::Arc;
-use tokio::sync::mpsc;
 use tower::ServiceExt;
-use tower_http::cors::{Any, CorsLayer};
 
-    // Test helper to create a mock app state with CORS enabled
+    // Test helper to create the router of a fresh server, with CORS enabled
+    // as in production
     async fn create_mock_app_with_cors() -> Router {
-        let (tx, mut rx) = mpsc::channel(100);
-        let event_store = Arc::new(EventStore::new().await.unwrap());
-
-        // Create a default NodeConfig for the scanner
-        let config = basis_store::ergo_scanner::NodeConfig {
-            node_url: "http://localhost:9053".to_string(),
-            ..Default::default()
-        };
-
-        // Create server state with temporary storage
-        let ergo_scanner = Arc::new(tokio::sync::Mutex::new(
-            basis_store::ergo_scanner::ServerState::new(config).unwrap()
-        ));
-        let reserve_tracker = Arc::new(tokio::sync::Mutex::new(basis_store::ReserveTracker::new()));
-
-        // Spawn tracker thread for tests
-        tokio::task::spawn_blocking(move || {
-            use basis_store::{RedemptionManager, TrackerStateManager};
-
-            tracing::debug!("Test tracker thread started");
-            let tracker = TrackerStateManager::new_with_temp_storage();
-            let mut redemption_manager = RedemptionManager::new(tracker);
-
-            while let Some(cmd) = rx.blocking_recv() {
-                tracing::debug!("Test tracker thread received command: {:?}", cmd);
-                match cmd {
-                    TrackerCommand::AddNote {
-                        issuer_pubkey,
-                        note,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager.tracker.add_note(&issuer_pubkey, &note);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetNotesByIssuer {
-                        issuer_pubkey,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager.tracker.get_issuer_notes(&issuer_pubkey);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetNotesByRecipient {
-                        recipient_pubkey,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager
-                            .tracker
-                            .get_recipient_notes(&recipient_pubkey);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetNoteByIssuerAndRecipient {
-                        issuer_pubkey,
-                        recipient_pubkey,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager
-                            .tracker
-                            .lookup_note(&issuer_pubkey, &recipient_pubkey)
-                            .map(Some);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::InitiateRedemption {
-                        request,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager.initiate_redemption(&request);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::CompleteRedemption {
-                        issuer_pubkey,
-                        recipient_pubkey,
-                        redeemed_amount,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager.complete_redemption(
-                            &issuer_pubkey,
-                            &recipient_pubkey,
-                            redeemed_amount,
-                        );
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetNotes { response_tx } => {
-                        // For testing purposes, return an empty list
-                        let result = Ok(Vec::new());
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GenerateProof {
-                        issuer_pubkey: _,
-                        recipient_pubkey: _,
-                        response_tx,
-                    } => {
-                        // For testing purposes, return a mock proof
-                        let mock_proof = basis_store::NoteProof {
-                            note: basis_store::IouNote::new([0u8; 33], 0, 0, 0, [0u8; 65]),
-                            avl_proof: vec![1, 2, 3, 4], // Mock proof data
-                            operations: vec![],
-                        };
-                        let result = Ok(mock_proof);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetTrackerLookupProof {
-                        issuer_pubkey: _,
-                        recipient_pubkey: _,
-                        response_tx,
-                    } => {
-                        // Mock tracker lookup proof
-                        let mock_proof = basis_store::TrackerLookupProof {
-                            key: vec![0u8; 64],
-                            value: vec![0u8; 8],
-                            proof: vec![1, 2, 3, 4],
-                        };
-                        let _ = response_tx.send(Ok(mock_proof));
-                    }
-                    TrackerCommand::GetReserveLookupProof {
-                        issuer_pubkey: _,
-                        recipient_pubkey: _,
-                        response_tx,
-                    } => {
-                        // Mock reserve lookup proof
-                        let mock_proof = basis_store::ReserveLookupProof {
-                            key: vec![0u8; 64],
-                            value: vec![0u8; 8],
-                            proof: Some(vec![1, 2, 3, 4]),
-                        };
-                        let _ = response_tx.send(Ok(mock_proof));
-                    }
-                    TrackerCommand::GetReserveInsertProof {
-                        issuer_pubkey: _,
-                        recipient_pubkey: _,
-                        timestamp: _,
-                        new_already_redeemed: _,
-                        response_tx,
-                    } => {
-                        // Mock reserve insert proof
-                        let _ = response_tx.send(Ok(vec![1, 2, 3, 4]));
-                    }
-                    TrackerCommand::GetNotesByRecipientWithIssuer {
-                        recipient_pubkey: _,
-                        response_tx,
-                    } => {
-                        // Mock response - return empty list for testing
-                        let _ = response_tx.send(Ok(Vec::new()));
-                    }
-                }
-            }
-        });
-
-        // Create a minimal config for testing
-        let test_config = std::sync::Arc::new(basis_server::config::AppConfig {
-            server: basis_server::config::ServerConfig {
-                host: "127.0.0.1".to_string(),
-                port: 3048,
-                database_url: Some("sqlite::memory:".to_string()),
-            },
-            ergo: basis_server::config::ErgoConfig {
-                node: basis_store::ergo_scanner::NodeConfig {
-                    node_url: "http://localhost:9053".to_string(),
-                    ..Default::default()
-                },
-                basis_reserve_contract_p2s: "test".to_string(),
-                tracker_nft_id: Some("69c5d7a4df2e72252b0015d981876fe338ca240d5576d4e731dfd848ae18fe2b".to_string()),
-                tracker_public_key: Some("9fRusAarL1KkrWQVsxSRVYnvWxaAT2A96cKtNn9tvPh5XUyCisr33".to_string()),
-                tracker_secret_key: None,
-            },
-            transaction: basis_server::config::TransactionConfig {
-                fee: 1000000,
-                change_address: None,
-            },
-            acceptance: basis_server::acceptance::config::AcceptanceConfig::empty()
-        });
-
-        // Use a unique temporary directory for each test invocation using a counter
-        static COUNTER: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(0);
-        let unique_id = COUNTER.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-        let temp_dir = std::env::temp_dir().join(format!("basis_test_tracker_storage_cors_{}_{}", std::process::id(), unique_id));
-        std::fs::create_dir_all(&temp_dir).expect("Failed to create temp directory");
-        let tracker_storage = basis_store::persistence::TrackerStorage::open(
-            &temp_dir
-        ).expect("Failed to create tracker storage");
-
-        let app_state = AppState {
-            tx,
-            event_store,
-            ergo_scanner,
-            reserve_tracker,
-            config: test_config,
-            shared_tracker_state: std::sync::Arc::new(tokio::sync::Mutex::new(
-                basis_server::tracker_box_updater::SharedTrackerState::new()
-            )),
-            tracker_storage,
-            acceptance_predicate: None,
-        };
-
-        // Build the app with CORS enabled (same as main server)
-        Router::new()
-            // Root route
-            .route("/", get(root))
-            // Static routes
-            .route("/events", get(get_events))
-            .route("/events/paginated", get(get_events_paginated))
-            .route("/notes", post(create_note))
-            .route("/redeem", post(initiate_redemption))
-            .route("/redeem/complete", post(complete_redemption))
-            .route("/proof/redemption", get(get_redemption_proof))
-            // Most specific parameterized routes first
-            .route(
-                "/notes/issuer/{issuer_pubkey}/recipient/{recipient_pubkey}",
-                get(get_note_by_issuer_and_recipient),
-            )
-            // Parameterized routes
-            .route("/notes/issuer/{pubkey}", get(get_notes_by_issuer))
-            .route("/notes/recipient/{pubkey}", get(get_notes_by_recipient))
-            .route("/reserves/issuer/{pubkey}", get(get_reserves_by_issuer))
-            .route("/key-status/{pubkey}", get(get_key_status))
-            .with_state(app_state)
-            .layer(
-                CorsLayer::new()
-                    .allow_origin(Any)
-                    .allow_methods(Any)
-                    .allow_headers(Any),
-            )
+        common::test_server().await.router()
     }
 
     #[tokio::test]
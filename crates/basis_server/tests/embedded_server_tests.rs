@@ -0,0 +1,100 @@
+// Embedding the tracker in another application: build a server against an
+// in-process mock Ergo node, use its state and router directly, then serve
+// and shut it down
+
+mod common;
+
+use axum::body::Body;
+use axum::http::{Request, StatusCode};
+use basis_server::{config::*, requests, Server};
+use basis_store::mock_node::MockErgoNode;
+use common::{temp_dir, test_config};
+use basis_store::schnorr::generate_keypair;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn test_embedded_server_serves_until_shutdown_signal() {
+    let node = MockErgoNode::start(1_000).await;
+    let dir = temp_dir();
+
+    let server = Server::builder(test_config(&node.url, &dir)).build().await.unwrap();
+    let addr = server.local_addr().unwrap();
+    assert_ne!(addr.port(), 0);
+
+    // Requests go straight to the tracker thread, without HTTP
+    let notes = server.state().send(requests::GetNotes {}).await.unwrap().unwrap();
+    assert!(notes.is_empty());
+
+    // The router can be mounted elsewhere or called directly
+    let response = server
+        .router()
+        .oneshot(Request::builder().uri("/notes").body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+    let serving = tokio::spawn(server.serve(async {
+        let _ = stop_rx.await;
+    }));
+
+    // The listener bound by the builder accepts connections while serving
+    tokio::net::TcpStream::connect(addr).await.unwrap();
+
+    stop_tx.send(()).unwrap();
+    serving.await.unwrap().unwrap();
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+// Stop a built server, waiting for the tracker thread to release its storage
+async fn stop(server: Server) {
+    server.serve(async {}).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_embedded_servers_keep_their_own_configuration() {
+    let node = MockErgoNode::start(1_000).await;
+    let (sealed_dir, plain_dir) = (temp_dir(), temp_dir());
+
+    // One server encrypts its storage and spends reserves of a retired contract too
+    let legacy = basis_store::ReserveContract::from_ergo_tree_hex(0, "100104c801d17300").unwrap();
+    let mut sealed_config = test_config(&node.url, &sealed_dir);
+    sealed_config.storage.encryption.key = Some(hex::encode([7u8; 32]));
+    sealed_config.ergo.legacy_reserve_contracts = vec![LegacyReserveContract {
+        version: 0,
+        p2s: legacy.p2s(sealed_config.network()),
+    }];
+    let sealed = Server::builder(sealed_config).build().await.unwrap();
+    // The other, built afterwards in the same process, does neither
+    let plain = Server::builder(test_config(&node.url, &plain_dir)).build().await.unwrap();
+
+    assert_eq!(sealed.state().contracts.len(), 2);
+    assert_eq!(plain.state().contracts.len(), 1);
+    assert!(sealed.state().contracts.contract_for_tree(&legacy.ergo_tree_hex).is_some());
+    assert!(plain.state().contracts.contract_for_tree(&legacy.ergo_tree_hex).is_none());
+
+    let (issuer_secret, issuer_pubkey) = generate_keypair();
+    let (_, recipient_pubkey) = generate_keypair();
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64;
+    let note = basis_store::IouNote::create_and_sign(recipient_pubkey, 1_000, now, &issuer_secret).unwrap();
+    for server in [&sealed, &plain] {
+        let request = requests::AddNote {
+            issuer_pubkey,
+            note: note.clone(),
+            encrypted_payload: None,
+        };
+        server.state().send(request).await.unwrap().unwrap();
+    }
+    stop(sealed).await;
+    stop(plain).await;
+
+    // Only the encrypting server sealed its notes
+    let sealed_notes = basis_store::persistence::NoteStorage::open(sealed_dir.join("notes")).unwrap();
+    assert!(sealed_notes.get_note(&issuer_pubkey, &recipient_pubkey).is_err());
+    let plain_notes = basis_store::persistence::NoteStorage::open(plain_dir.join("notes")).unwrap();
+    assert_eq!(plain_notes.get_note(&issuer_pubkey, &recipient_pubkey).unwrap(), Some(note));
+
+    let _ = std::fs::remove_dir_all(&sealed_dir);
+    let _ = std::fs::remove_dir_all(&plain_dir);
+}
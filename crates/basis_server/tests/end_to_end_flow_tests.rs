@@ -0,0 +1,209 @@
+// End-to-end flow against an in-process mock Ergo node: reserve creation,
+// scan detection, note issuance, commitment and redemption, checking that
+// the tracker's off-chain state agrees with the chain at every step
+
+use basis_server::{SharedTrackerState, TrackerBoxUpdateConfig, TrackerBoxUpdater};
+use basis_store::{
+    commitment_verification::{commitment_digest, CommitmentStatus},
+    contract_compiler::{get_basis_reserve_contract_p2s, get_basis_reserve_ergo_tree_hex},
+    ergo_scanner::{NodeConfig, ReserveEvent, ServerState},
+    mock_node::{BoxSpec, MockErgoNode},
+    persistence::{ScannerMetadataStorage, TrackerStorage},
+    redemption::RedemptionManager,
+    reserve_lineage::ReserveSpendKind,
+    schnorr::generate_keypair,
+    tracker_scanner::{create_tracker_server_state, TrackerNodeConfig},
+    DebtCeilingPolicy, IouNote, NoteError, TrackerStateManager,
+};
+use ergo_lib::ergotree_ir::address::NetworkPrefix;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const TRACKER_NFT_ID: &str = "4c5a3b8f0e6d2a1c9b7e5f3d1a0c8e6b4d2f0a9c7e5b3d1f0a2c4e6b8d0f1a3c";
+
+fn temp_dir() -> std::path::PathBuf {
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_nanos();
+    std::env::temp_dir().join(format!("basis_e2e_{}_{}", std::process::id(), nanos))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+}
+
+/// Serialized SAvlTree register holding `digest`, as the tracker box updater writes it
+fn avl_tree_register(digest: &[u8; 33]) -> String {
+    format!("64{}0100000020{}", hex::encode(digest), "00000000")
+}
+
+fn reserve_box(collateral: u64, owner: &[u8; 33]) -> BoxSpec {
+    BoxSpec::new(collateral, &get_basis_reserve_ergo_tree_hex().unwrap())
+        .register("R4", format!("07{}", hex::encode(owner)))
+        .register("R6", format!("0e20{}", TRACKER_NFT_ID))
+}
+
+/// Assert the reserve tracker holds exactly the unspent reserve boxes on chain
+fn assert_reserves_match_chain(node: &MockErgoNode, scanner: &ServerState) {
+    let onchain = node.unspent_boxes_by_tree(&get_basis_reserve_ergo_tree_hex().unwrap());
+    let tracked = scanner.reserve_tracker().get_all_reserves();
+    assert_eq!(tracked.len(), onchain.len());
+    for onchain_box in onchain {
+        let box_id = hex::encode(onchain_box["boxId"].as_str().unwrap());
+        let reserve = scanner.reserve_tracker().get_reserve(&box_id).unwrap();
+        assert_eq!(reserve.base_info.collateral_amount, onchain_box["value"].as_u64().unwrap());
+    }
+}
+
+#[tokio::test]
+async fn test_reserve_note_commitment_redemption_flow() {
+    let node = MockErgoNode::start(1_000).await;
+    let dir = temp_dir();
+    let (issuer_secret, issuer_pubkey) = generate_keypair();
+    let (_, recipient_pubkey) = generate_keypair();
+    let (tracker_secret, tracker_pubkey) = generate_keypair();
+
+    // The tracker box exists before anything is committed
+    let tracker_box_id = node.create_box(
+        BoxSpec::new(1_000_000, &format!("0008cd{}", hex::encode(tracker_pubkey)))
+            .register("R4", format!("07{}", hex::encode(tracker_pubkey)))
+            .register("R5", avl_tree_register(&[0u8; 33]))
+            .asset(TRACKER_NFT_ID, 1),
+    );
+
+    // 1. Reserve creation
+    node.advance(1);
+    let reserve_box_id = node.create_box(reserve_box(1_000_000_000, &issuer_pubkey));
+
+    // 2. Scan detection
+    let mut scanner = ServerState::with_storage_paths(
+        NodeConfig {
+            node_url: node.url.clone(),
+            reserve_contract_p2s: Some(get_basis_reserve_contract_p2s().unwrap()),
+            ..Default::default()
+        },
+        dir.join("scanner_metadata"),
+        dir.join("reserves"),
+    )
+    .unwrap();
+    scanner.start_scanning().await.unwrap();
+    scanner.process_scan_boxes().await.unwrap();
+    assert_reserves_match_chain(&node, &scanner);
+    let reserve = &scanner.reserve_tracker().get_all_reserves()[0];
+    assert_eq!(reserve.owner_pubkey, hex::encode(issuer_pubkey));
+    assert_eq!(reserve.base_info.tracker_nft_id, TRACKER_NFT_ID);
+
+    // 3. Note issuance, bounded by the scanned collateral
+    let mut tracker = TrackerStateManager::new_with_temp_storage();
+    tracker.set_debt_policy(DebtCeilingPolicy::new(1.0), scanner.reserve_tracker().clone());
+    let issued_at = now_millis() - 60_000;
+    let note = IouNote::create_and_sign(recipient_pubkey, 600_000_000, issued_at, &issuer_secret).unwrap();
+    tracker.add_note(&issuer_pubkey, &note).unwrap();
+    let overdrawn =
+        IouNote::create_and_sign(recipient_pubkey, 1_200_000_000, issued_at + 1, &issuer_secret).unwrap();
+    assert!(matches!(
+        tracker.add_note(&issuer_pubkey, &overdrawn),
+        Err(NoteError::InsufficientCollateral)
+    ));
+
+    // 4. Commitment of the tracker state on chain
+    let committed_root = tracker.get_state().avl_root_digest;
+    let tracker_storage = TrackerStorage::open(dir.join("tracker")).unwrap();
+    tracker_storage.record_local_root(node.height(), &committed_root).unwrap();
+    node.advance(1);
+
+    let shared_state = SharedTrackerState::new_with_tracker_key(tracker_pubkey);
+    shared_state.set_avl_root_digest(committed_root);
+    shared_state.set_tracker_box_id(tracker_box_id.clone());
+    let (shutdown_tx, shutdown_rx) = tokio::sync::broadcast::channel(1);
+    let updater = tokio::spawn(TrackerBoxUpdater::start(
+        TrackerBoxUpdateConfig {
+            update_interval_seconds: 1,
+            ergo_node_url: node.url.clone(),
+            tracker_secret_key: Some(tracker_secret),
+            ..TrackerBoxUpdateConfig::default()
+        },
+        shared_state,
+        NetworkPrefix::Mainnet,
+        TRACKER_NFT_ID.to_string(),
+        shutdown_rx,
+    ));
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    while node.wallet_requests().is_empty() {
+        assert!(tokio::time::Instant::now() < deadline, "no commitment was submitted");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    let _ = shutdown_tx.send(());
+    updater.await.unwrap().unwrap();
+
+    // The commitment replaced the tracker box and carries the local root
+    let tracker_boxes = node.unspent_boxes_with_token(TRACKER_NFT_ID);
+    assert_eq!(tracker_boxes.len(), 1);
+    assert_ne!(tracker_boxes[0]["boxId"], tracker_box_id.as_str());
+    let onchain_r5 = tracker_boxes[0]["additionalRegisters"]["R5"].as_str().unwrap();
+    assert_eq!(commitment_digest(onchain_r5), Some(committed_root));
+
+    let tracker_scanner = create_tracker_server_state(
+        TrackerNodeConfig {
+            start_height: None,
+            tracker_nft_id: Some(TRACKER_NFT_ID.to_string()),
+            node_url: node.url.clone(),
+            scan_name: None,
+            api_key: None,
+            verify_against_all_roots: false,
+        },
+        ScannerMetadataStorage::open(dir.join("tracker_metadata")).unwrap(),
+        tracker_storage,
+    );
+    tracker_scanner.register_tracker_scan().await.unwrap();
+    let scanned_boxes = tracker_scanner.process_tracker_boxes().await.unwrap();
+    assert_eq!(scanned_boxes.len(), 1);
+    let checks = tracker_scanner.update_tracker_state(&scanned_boxes).await.unwrap();
+    assert_eq!(checks.len(), 1);
+    assert_eq!(checks[0].status, CommitmentStatus::Consistent);
+
+    // 5. Redemption: the reserve pays the recipient and the tracker records it
+    let redeemed = 400_000_000;
+    node.advance(1);
+    let (redemption_tx_id, outputs) = node.spend(
+        &[&reserve_box_id],
+        vec![
+            reserve_box(1_000_000_000 - redeemed, &issuer_pubkey),
+            BoxSpec::new(redeemed, &format!("0008cd{}", hex::encode(recipient_pubkey))),
+        ],
+    );
+    let mut redemptions = RedemptionManager::new(tracker);
+    redemptions
+        .complete_redemption(&issuer_pubkey, &recipient_pubkey, redeemed)
+        .unwrap();
+
+    let mut events = scanner.subscribe_events();
+    scanner.process_scan_boxes().await.unwrap();
+    assert_reserves_match_chain(&node, &scanner);
+
+    let transition = scanner
+        .reserve_storage()
+        .get_transition(&hex::encode(&reserve_box_id))
+        .unwrap()
+        .expect("the spend of the reserve is recorded");
+    assert_eq!(transition.kind, ReserveSpendKind::Redemption);
+    assert_eq!(transition.spending_tx_id, redemption_tx_id);
+    assert_eq!(transition.successor_box_id, Some(hex::encode(&outputs[0])));
+    let queued = events.try_recv().unwrap();
+    match &queued.event {
+        ReserveEvent::ReserveRedeemed { redeemed_amount, .. } => assert_eq!(*redeemed_amount, redeemed),
+        event => panic!("unexpected reserve event {:?}", event),
+    }
+    // Events stay queued until processed, so a restart would replay them
+    let storage = scanner.reserve_storage();
+    assert!(storage.unprocessed_reserve_events().unwrap().contains(&queued));
+    storage.mark_reserve_event_processed(queued.height, queued.sequence).unwrap();
+    assert!(!storage.unprocessed_reserve_events().unwrap().contains(&queued));
+
+    // What left the reserve on chain is what the tracker marked as redeemed
+    let note = redemptions.tracker.lookup_note(&issuer_pubkey, &recipient_pubkey).unwrap();
+    assert_eq!(note.amount_redeemed, redeemed);
+    assert_eq!(note.outstanding_debt(), 200_000_000);
+    let collateral = basis_store::debt_policy::issuer_collateral(scanner.reserve_tracker(), &issuer_pubkey);
+    assert_eq!(collateral, 600_000_000);
+    assert!(note.outstanding_debt() <= collateral);
+
+    let _ = std::fs::remove_dir_all(&dir);
+}
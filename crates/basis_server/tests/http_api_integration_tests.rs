@@ -1,214 +1,43 @@
 // HTTP API integration tests for basis_server endpoints
 
+mod common;
+
 #[cfg(test)]
 mod http_api_tests {
+    use super::common;
     use axum::http::StatusCode;
     use basis_server::{
-        api::{get_notes_by_issuer, get_notes_by_recipient},
-        config,
-        store::EventStore,
-        AppState, TrackerCommand,
+        api::{
+            acknowledge_note, create_note, get_note_history, get_notes_by_issuer,
+            get_issuer_events, get_notes_by_recipient, search_notes, validate_note,
+            validate_redemption,
+        },
+        store::EventQuery,
+        AppState, TrackerCommand, TrackerEvent,
     };
     use std::sync::Arc;
-    use tokio::sync::mpsc;
     use tower::util::ServiceExt;
 
-    // Test helper to create a mock app state
+    // Test helper to create the state of a fresh server
     async fn create_mock_app_state() -> AppState {
-        let (tx, mut rx) = mpsc::channel(100);
-        let event_store = Arc::new(EventStore::new().await.unwrap());
-
-        // Create a default NodeConfig for the scanner
-        let config = basis_store::ergo_scanner::NodeConfig {
-            node_url: "http://localhost:9053".to_string(),
-            ..Default::default()
-        };
-        let ergo_scanner = Arc::new(tokio::sync::Mutex::new(
-            basis_store::ergo_scanner::ServerState::new(config).unwrap(),
-        ));
-        let reserve_tracker = Arc::new(tokio::sync::Mutex::new(basis_store::ReserveTracker::new()));
-
-        // Spawn tracker thread for tests
-        tokio::task::spawn_blocking(move || {
-            use basis_store::{RedemptionManager, TrackerStateManager};
-
-            tracing::debug!("Test tracker thread started");
-            let tracker = TrackerStateManager::new_with_temp_storage();
-            let mut redemption_manager = RedemptionManager::new(tracker);
-
-            while let Some(cmd) = rx.blocking_recv() {
-                tracing::debug!("Test tracker thread received command: {:?}", cmd);
-                match cmd {
-                    TrackerCommand::AddNote {
-                        issuer_pubkey,
-                        note,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager.tracker.add_note(&issuer_pubkey, &note);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetNotesByIssuer {
-                        issuer_pubkey,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager.tracker.get_issuer_notes(&issuer_pubkey);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetNotesByRecipient {
-                        recipient_pubkey,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager
-                            .tracker
-                            .get_recipient_notes(&recipient_pubkey);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetNoteByIssuerAndRecipient {
-                        issuer_pubkey,
-                        recipient_pubkey,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager
-                            .tracker
-                            .lookup_note(&issuer_pubkey, &recipient_pubkey)
-                            .map(Some);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::InitiateRedemption {
-                        request,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager.initiate_redemption(&request);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::CompleteRedemption {
-                        issuer_pubkey,
-                        recipient_pubkey,
-                        redeemed_amount,
-                        response_tx,
-                    } => {
-                        let result = redemption_manager.complete_redemption(
-                            &issuer_pubkey,
-                            &recipient_pubkey,
-                            redeemed_amount,
-                        );
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetNotes { response_tx } => {
-                        // For testing purposes, return an empty list
-                        let result = Ok(Vec::new());
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GenerateProof {
-                        issuer_pubkey: _,
-                        recipient_pubkey: _,
-                        response_tx,
-                    } => {
-                        // For testing purposes, return a mock proof
-                        let mock_proof = basis_store::NoteProof {
-                            note: basis_store::IouNote::new([0u8; 33], 0, 0, 0, [0u8; 65]),
-                            avl_proof: vec![1, 2, 3, 4], // Mock proof data
-                            operations: vec![],
-                        };
-                        let result = Ok(mock_proof);
-                        let _ = response_tx.send(result);
-                    }
-                    TrackerCommand::GetTrackerLookupProof {
-                        issuer_pubkey: _,
-                        recipient_pubkey: _,
-                        response_tx,
-                    } => {
-                        // Mock tracker lookup proof
-                        let mock_proof = basis_store::TrackerLookupProof {
-                            key: vec![0u8; 64],
-                            value: vec![0u8; 8],
-                            proof: vec![1, 2, 3, 4],
-                        };
-                        let _ = response_tx.send(Ok(mock_proof));
-                    }
-                    TrackerCommand::GetReserveLookupProof {
-                        issuer_pubkey: _,
-                        recipient_pubkey: _,
-                        response_tx,
-                    } => {
-                        // Mock reserve lookup proof
-                        let mock_proof = basis_store::ReserveLookupProof {
-                            key: vec![0u8; 64],
-                            value: vec![0u8; 8],
-                            proof: Some(vec![1, 2, 3, 4]),
-                        };
-                        let _ = response_tx.send(Ok(mock_proof));
-                    }
-                    TrackerCommand::GetReserveInsertProof {
-                        issuer_pubkey: _,
-                        recipient_pubkey: _,
-                        timestamp: _,
-                        new_already_redeemed: _,
-                        response_tx,
-                    } => {
-                        // Mock reserve insert proof
-                        let _ = response_tx.send(Ok(vec![1, 2, 3, 4]));
-                    }
-                    TrackerCommand::GetNotesByRecipientWithIssuer {
-                        recipient_pubkey: _,
-                        response_tx,
-                    } => {
-                        // Mock response - return empty list for testing
-                        let _ = response_tx.send(Ok(Vec::new()));
-                    }
-                }
-            }
-        });
+        common::test_state().await
+    }
 
-        // Create a minimal config for testing
-        let test_config = std::sync::Arc::new(config::AppConfig {
-            server: config::ServerConfig {
-                host: "127.0.0.1".to_string(),
-                port: 3048,
-                database_url: Some("sqlite::memory:".to_string()),
-            },
-            ergo: config::ErgoConfig {
-                node: basis_store::ergo_scanner::NodeConfig {
-                    node_url: "http://localhost:9053".to_string(),
-                    ..Default::default()
-                },
-                basis_reserve_contract_p2s: "test".to_string(),
-                tracker_nft_id: Some("69c5d7a4df2e72252b0015d981876fe338ca240d5576d4e731dfd848ae18fe2b".to_string()),
-                tracker_public_key: Some("9fRusAarL1KkrWQVsxSRVYnvWxaAT2A96cKtNn9tvPh5XUyCisr33".to_string()),
-                tracker_secret_key: None,
-            },
-            transaction: config::TransactionConfig {
-                fee: 1000000,
-                change_address: None,
-            },
-            acceptance: basis_server::acceptance::config::AcceptanceConfig::empty()
-        });
+    // Sequence number of the last event recorded so far, such as the demo
+    // events a server adds at startup
+    async fn last_event_seq(state: &AppState) -> u64 {
+        let events = state.event_store.get_events_paginated(0, usize::MAX).await.unwrap();
+        events.last().map_or(0, |event| event.seq)
+    }
 
-        let temp_dir = std::env::temp_dir().join(format!(
-            "basis_test_tracker_storage_{}_{}",
-            std::process::id(),
-            std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_nanos()
-        ));
-        std::fs::create_dir_all(&temp_dir).expect("Failed to create temp directory");
-        let tracker_storage = basis_store::persistence::TrackerStorage::open(
-            &temp_dir
-        ).expect("Failed to create tracker storage");
-
-        AppState {
-            tx,
-            event_store,
-            ergo_scanner,
-            reserve_tracker,
-            config: test_config,
-            shared_tracker_state: std::sync::Arc::new(tokio::sync::Mutex::new(
-                basis_server::tracker_box_updater::SharedTrackerState::new()
-            )),
-            tracker_storage,
-            acceptance_predicate: None,
-        }
+    // Events recorded after `seq`, oldest first
+    async fn events_since(state: &AppState, seq: u64) -> Vec<TrackerEvent> {
+        let query = EventQuery {
+            since_seq: Some(seq),
+            limit: 100,
+            ..EventQuery::default()
+        };
+        state.event_store.query_events(&query).await.unwrap()
     }
 
     #[tokio::test]
@@ -313,6 +142,2127 @@ mod http_api_tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_acknowledge_note() {
+        // Test that a recipient can co-sign an existing note
+        use basis_store::schnorr::generate_keypair;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (recipient_secret, recipient_pubkey) = generate_keypair();
+
+        let mut note =
+            basis_store::IouNote::create_and_sign(recipient_pubkey, 1000, 1_000, &issuer_secret)
+                .unwrap();
+        let create_response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount: note.amount_collected,
+                timestamp: note.timestamp,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }),
+        )
+        .await;
+        assert_eq!(create_response.0, StatusCode::CREATED);
+
+        // A signature by someone other than the recipient is rejected
+        let mut forged = note.clone();
+        forged.acknowledge(&issuer_pubkey, &issuer_secret).unwrap();
+        let response = acknowledge_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::AcknowledgeNoteRequest {
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                recipient_signature: hex::encode(forged.recipient_signature.unwrap()),
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::BAD_REQUEST);
+
+        note.acknowledge(&issuer_pubkey, &recipient_secret).unwrap();
+        let response = acknowledge_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::AcknowledgeNoteRequest {
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                recipient_signature: hex::encode(note.recipient_signature.unwrap()),
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::OK);
+        let acknowledged = response.1.data.as_ref().unwrap();
+        assert!(acknowledged.acknowledged);
+        assert_eq!(
+            acknowledged.recipient_signature,
+            Some(hex::encode(note.recipient_signature.unwrap()))
+        );
+
+        // The acknowledgment is persisted with the note
+        let response = get_notes_by_issuer(
+            axum::extract::State(state),
+            axum::extract::Path(hex::encode(issuer_pubkey)),
+        )
+        .await;
+        let notes = response.1.data.as_ref().unwrap();
+        assert_eq!(notes.len(), 1);
+        assert!(notes[0].acknowledged);
+    }
+
+    #[tokio::test]
+    async fn test_acknowledge_missing_note() {
+        let state = create_mock_app_state().await;
+
+        let response = acknowledge_note(
+            axum::extract::State(state),
+            axum::Json(basis_server::AcknowledgeNoteRequest {
+                issuer_pubkey: "01".repeat(33),
+                recipient_pubkey: "02".repeat(33),
+                recipient_signature: "03".repeat(65),
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_create_note_v2_bound_to_tracker() {
+        use basis_store::schnorr::generate_keypair;
+
+        let state = create_mock_app_state().await;
+        let own_tracker_id: [u8; 32] = hex::decode(state.config.ergo.tracker_nft_id.as_ref().unwrap())
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+
+        let submit = |note: basis_store::IouNote, signing_version: u8, tracker_id: [u8; 32]| {
+            let state = state.clone();
+            async move {
+                create_note(
+                    axum::extract::State(state),
+                    axum::Json(basis_server::CreateNoteRequest {
+                        recipient_pubkey: hex::encode(recipient_pubkey),
+                        amount: note.amount_collected,
+                        timestamp: note.timestamp,
+                        signature: hex::encode(note.signature),
+                        issuer_pubkey: hex::encode(issuer_pubkey),
+                        signing_version,
+                        tracker_id: Some(hex::encode(tracker_id)),
+                        interest_rate_bps: None,
+                        accrual_method: None,
+                        encrypted_payload: None,
+                        memo: None,
+                    }),
+                )
+                .await
+                .0
+            }
+        };
+
+        // A note bound to another tracker is refused before its signature is checked
+        let foreign = basis_store::IouNote::create_and_sign_v2(
+            recipient_pubkey,
+            100,
+            1_000,
+            Some([7u8; 32]),
+            &issuer_secret,
+        )
+        .unwrap();
+        assert_eq!(submit(foreign, 2, [7u8; 32]).await, StatusCode::BAD_REQUEST);
+
+        // A tracker ID is only meaningful on version 2 notes
+        let bound = basis_store::IouNote::create_and_sign_v2(
+            recipient_pubkey,
+            100,
+            1_000,
+            Some(own_tracker_id),
+            &issuer_secret,
+        )
+        .unwrap();
+        assert_eq!(submit(bound.clone(), 1, own_tracker_id).await, StatusCode::BAD_REQUEST);
+        assert_eq!(submit(bound, 2, own_tracker_id).await, StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_note_history_pagination() {
+        // Every accepted version of a note is kept, oldest first
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+
+        for (amount, timestamp) in [(100, 1_000), (200, 1_001), (300, 1_002)] {
+            let note =
+                basis_store::IouNote::create_and_sign(recipient_pubkey, amount, timestamp, &issuer_secret)
+                    .unwrap();
+            let response = create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    amount,
+                    timestamp,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(issuer_pubkey),
+                    signing_version: 1,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: None,
+                }),
+            )
+            .await;
+            assert_eq!(response.0, StatusCode::CREATED);
+        }
+
+        let params = HashMap::from([
+            ("issuer".to_string(), hex::encode(issuer_pubkey)),
+            ("recipient".to_string(), hex::encode(recipient_pubkey)),
+            ("page".to_string(), "1".to_string()),
+            ("page_size".to_string(), "2".to_string()),
+        ]);
+        let response = get_note_history(
+            axum::extract::State(state.clone()),
+            axum::extract::Query(params),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::OK);
+        let history = response.1.data.as_ref().unwrap();
+        assert_eq!(history.total_versions, 3);
+        assert_eq!(history.notes.len(), 1);
+        assert_eq!(history.notes[0].amount_collected, 300);
+        assert_eq!(history.notes[0].issuer_pubkey, hex::encode(issuer_pubkey));
+
+        // Both public keys are required
+        let params = HashMap::from([("issuer".to_string(), hex::encode(issuer_pubkey))]);
+        let response = get_note_history(
+            axum::extract::State(state),
+            axum::extract::Query(params),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_issuer_event_timeline() {
+        use basis_server::{EventType, TrackerEvent};
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, other_issuer) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+
+        let reserve_event = |event_type: EventType, owner: &basis_store::PubKey| TrackerEvent {
+            id: 0,
+            seq: 0,
+            event_type,
+            timestamp: 1_700_000_000,
+            issuer_pubkey: Some(hex::encode(owner)),
+            recipient_pubkey: None,
+            amount: None,
+            reserve_box_id: Some("box".to_string()),
+            collateral_amount: Some(1_000),
+            redeemed_amount: None,
+            height: Some(100),
+        };
+        state.event_store.add_event(reserve_event(EventType::ReserveCreated, &issuer_pubkey)).await.unwrap();
+        state.event_store.add_event(reserve_event(EventType::ReserveCreated, &other_issuer)).await.unwrap();
+
+        let note =
+            basis_store::IouNote::create_and_sign(recipient_pubkey, 500, 1_000, &issuer_secret).unwrap();
+        let response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount: 500,
+                timestamp: 1_000,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::CREATED);
+        state.event_store.add_event(reserve_event(EventType::ReserveRedeemed, &issuer_pubkey)).await.unwrap();
+
+        let response = get_issuer_events(
+            axum::extract::State(state.clone()),
+            axum::extract::Path(hex::encode(issuer_pubkey).to_uppercase()),
+            axum::extract::Query(HashMap::new()),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::OK);
+        let events = response.1.data.as_ref().unwrap();
+        let types: Vec<&str> = events.iter().map(|e| e.event_type.name()).collect();
+        assert_eq!(types, vec!["ReserveCreated", "NoteUpdated", "ReserveRedeemed"]);
+        assert!(events.windows(2).all(|pair| pair[0].id < pair[1].id));
+
+        let response = get_issuer_events(
+            axum::extract::State(state),
+            axum::extract::Path("zz".to_string()),
+            axum::extract::Query(HashMap::new()),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_note_search_filters_and_sorts() {
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+
+        for (amount, timestamp) in [(100, 1_000), (400, 1_001), (250, 1_002)] {
+            let (_, recipient_pubkey) = generate_keypair();
+            let note =
+                basis_store::IouNote::create_and_sign(recipient_pubkey, amount, timestamp, &issuer_secret)
+                    .unwrap();
+            let response = create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    amount,
+                    timestamp,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(issuer_pubkey),
+                    signing_version: 1,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: None,
+                }),
+            )
+            .await;
+            assert_eq!(response.0, StatusCode::CREATED);
+        }
+
+        let search = |params: &[(&str, &str)]| {
+            let mut params: HashMap<String, String> =
+                params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            params.insert("issuer".to_string(), hex::encode(issuer_pubkey));
+            search_notes(axum::extract::State(state.clone()), axum::extract::Query(params))
+        };
+
+        let response = search(&[("min_amount", "200"), ("sort", "amount"), ("order", "asc")]).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let result = response.1.data.as_ref().unwrap();
+        assert_eq!(result.total, 2);
+        let amounts: Vec<u64> = result.notes.iter().map(|n| n.amount_collected).collect();
+        assert_eq!(amounts, vec![250, 400]);
+        assert_eq!(result.notes[0].issuer_pubkey, hex::encode(issuer_pubkey));
+
+        // Newest first by default, paginated
+        let response = search(&[("page", "1"), ("page_size", "2")]).await;
+        let result = response.1.data.as_ref().unwrap();
+        assert_eq!(result.total, 3);
+        assert_eq!(result.notes.len(), 1);
+        assert_eq!(result.notes[0].timestamp, 1_000);
+
+        let response = search(&[("sort", "recipient")]).await;
+        assert_eq!(response.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_network_stats_rank_issuers_by_debt() {
+        use basis_server::get_network_stats;
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (_, recipient_pubkey) = generate_keypair();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let mut issuers = Vec::new();
+        for amount in [300, 700] {
+            let (issuer_secret, issuer_pubkey) = generate_keypair();
+            let note = basis_store::IouNote::create_and_sign(recipient_pubkey, amount, now - 1_000, &issuer_secret)
+                .unwrap();
+            let response = create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    amount,
+                    timestamp: now - 1_000,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(issuer_pubkey),
+                    signing_version: 1,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: None,
+                }),
+            )
+            .await;
+            assert_eq!(response.0, StatusCode::CREATED);
+            issuers.push(issuer_pubkey);
+        }
+
+        let stats = |params: &[(&str, &str)]| {
+            let params: HashMap<String, String> =
+                params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            get_network_stats(axum::extract::State(state.clone()), axum::extract::Query(params))
+        };
+        let response = stats(&[("top", "1"), ("days", "7")]).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let result = response.1.data.as_ref().unwrap();
+        assert_eq!((result.issuers, result.recipients, result.notes), (2, 1, 2));
+        assert_eq!(result.total_outstanding_debt, 1_000);
+        assert_eq!(result.top_issuers.len(), 1);
+        assert_eq!(result.top_issuers[0].issuer_pubkey, hex::encode(issuers[1]));
+        assert_eq!(result.top_issuers[0].outstanding_debt, 700);
+        assert_eq!(result.notes_per_day.len(), 7);
+        assert_eq!(result.notes_per_day.iter().map(|day| day.notes).sum::<u64>(), 2);
+
+        assert_eq!(stats(&[("days", "0")]).await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(stats(&[("top", "101")]).await.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_state_reports_digest_and_latest_commitment() {
+        use basis_server::api::get_tracker_state;
+        use basis_store::schnorr::generate_keypair;
+
+        let state = create_mock_app_state().await;
+        let response = get_tracker_state(axum::extract::State(state.clone())).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let empty = response.1.data.as_ref().unwrap();
+        assert_eq!((empty.note_count, empty.last_updated), (0, None));
+        assert_eq!(empty.last_commitment_height, None);
+
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+            - 1_000;
+        let note = basis_store::IouNote::create_and_sign(recipient_pubkey, 500, timestamp, &issuer_secret).unwrap();
+        let response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount: 500,
+                timestamp,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::CREATED);
+
+        state.shared_tracker_state.lock().await.set_avl_root_digest([7u8; 33]);
+        for (box_id, height, transaction_id) in [("box1", 102, "aa"), ("box2", 110, "bb")] {
+            let tracker_box = basis_store::TrackerBoxInfo {
+                box_id: box_id.to_string(),
+                tracker_pubkey: "02".repeat(33),
+                state_commitment: format!("64{}0100000020{}", "07".repeat(33), "00000000"),
+                last_verified_height: height,
+                value: 1_000_000,
+                creation_height: height,
+                tracker_nft_id: "00".repeat(32),
+                transaction_id: Some(transaction_id.repeat(32)),
+            };
+            state.tracker_storage.store_tracker_box(&tracker_box).unwrap();
+        }
+
+        let response = get_tracker_state(axum::extract::State(state.clone())).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let current = response.1.data.as_ref().unwrap();
+        assert_eq!(current.avl_root_digest, "07".repeat(33));
+        assert_eq!((current.note_count, current.last_updated), (1, Some(timestamp)));
+        assert_eq!(current.last_commitment_height, Some(110));
+        assert_eq!(current.last_commitment_box_id.as_deref(), Some("box2"));
+        assert_eq!(current.last_commitment_tx_id, Some("bb".repeat(32)));
+        assert_eq!(current.tracker_pubkey, state.config.tracker_public_key_hex());
+    }
+
+    #[tokio::test]
+    async fn test_key_status_history_returns_recorded_snapshots() {
+        use basis_server::collateral_history::{get_key_status_history, record_snapshots};
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64
+            - 1_000;
+        let note = basis_store::IouNote::create_and_sign(recipient_pubkey, 500, timestamp, &issuer_secret).unwrap();
+        let response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount: 500,
+                timestamp,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::CREATED);
+
+        for at in [1_000, 2_000, 4_000] {
+            assert_eq!(record_snapshots(&state, at).await.unwrap(), 1);
+        }
+
+        let history = |params: &[(&str, &str)]| {
+            let params: HashMap<String, String> =
+                params.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+            get_key_status_history(
+                axum::extract::State(state.clone()),
+                axum::extract::Path(hex::encode(issuer_pubkey)),
+                axum::extract::Query(params),
+            )
+        };
+
+        let response = history(&[("from", "0"), ("to", "3000")]).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let data = response.1.data.as_ref().unwrap();
+        assert_eq!(data.issuer_pubkey, hex::encode(issuer_pubkey));
+        assert_eq!(data.snapshots.iter().map(|s| s.timestamp).collect::<Vec<_>>(), vec![1_000, 2_000]);
+        let snapshot = &data.snapshots[0];
+        assert_eq!((snapshot.total_debt, snapshot.collateral, snapshot.note_count), (500, 0, 1));
+        assert_eq!(snapshot.collateralization_ratio, Some(0.0));
+
+        let response = history(&[("from", "0"), ("to", "5000"), ("resolution", "3600")]).await;
+        let data = response.1.data.as_ref().unwrap();
+        assert_eq!(data.resolution, Some(3_600));
+        assert_eq!(data.snapshots.iter().map(|s| s.timestamp).collect::<Vec<_>>(), vec![2_000, 4_000]);
+
+        for params in [&[("from", "5"), ("to", "1")][..], &[("resolution", "0")], &[("to", "soon")]] {
+            assert_eq!(history(params).await.0, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_ledger_report_journals_account_notes() {
+        use basis_server::{get_ledger_report_beancount, get_ledger_report_hledger};
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        for (amount, timestamp) in [(1_500_000_000, now - 2_000), (2_000_000_000, now - 1_000)] {
+            let note = basis_store::IouNote::create_and_sign(recipient_pubkey, amount, timestamp, &issuer_secret)
+                .unwrap();
+            let response = create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    amount,
+                    timestamp,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(issuer_pubkey),
+                    signing_version: 1,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: None,
+                }),
+            )
+            .await;
+            assert_eq!(response.0, StatusCode::CREATED);
+        }
+
+        let query = |params: &[(&str, String)]| {
+            axum::extract::Query(
+                params
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.clone()))
+                    .collect::<HashMap<String, String>>(),
+            )
+        };
+        let body = |response: axum::response::Response| async move {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            String::from_utf8(bytes.to_vec()).unwrap()
+        };
+
+        let response = get_ledger_report_hledger(
+            axum::extract::State(state.clone()),
+            query(&[("account", hex::encode(issuer_pubkey))]),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let journal = body(response).await;
+        let payable = format!("Liabilities:Basis:Payable:{}", hex::encode(recipient_pubkey));
+        assert!(journal.contains(&format!("    {}  -1.500000000 ERG\n", payable)));
+        assert!(journal.contains(&format!("    {}  -0.500000000 ERG\n", payable)));
+
+        // The recipient's ledger holds the same debt as a receivable, limited to the range
+        let response = get_ledger_report_beancount(
+            axum::extract::State(state.clone()),
+            query(&[
+                ("account", hex::encode(recipient_pubkey)),
+                ("from_timestamp", (now - 1_000).to_string()),
+            ]),
+        )
+        .await;
+        let ledger = body(response).await;
+        let receivable = format!("Assets:Basis:Receivable:{}", hex::encode(issuer_pubkey));
+        assert!(ledger.contains(&format!(" open {} ERG\n", receivable)));
+        assert!(ledger.contains(&format!("    {}  0.500000000 ERG\n", receivable)));
+        assert!(!ledger.contains("1.500000000 ERG"));
+
+        let response =
+            get_ledger_report_hledger(axum::extract::State(state.clone()), query(&[])).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_note_memo_is_signed_and_searchable() {
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let submit = |note: basis_store::IouNote, signing_version: u8, memo: &str| {
+            create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    amount: note.amount_collected,
+                    timestamp: note.timestamp,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(issuer_pubkey),
+                    signing_version,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: Some(memo.to_string()),
+                }),
+            )
+        };
+        let note = basis_store::IouNote::create_and_sign_v2(recipient_pubkey, 500, 1_000, None, &issuer_secret)
+            .unwrap()
+            .with_memo("INV-2024-0042")
+            .sign_with(&issuer_secret)
+            .unwrap();
+
+        // Memos need a version 2 signature, and the signature must cover the memo
+        let v1 = basis_store::IouNote::create_and_sign(recipient_pubkey, 500, 1_000, &issuer_secret).unwrap();
+        assert_eq!(submit(v1, 1, "INV-2024-0042").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(submit(note.clone(), 2, "INV-2024-0043").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(submit(note.clone(), 2, "INV\n0042").await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(submit(note, 2, "INV-2024-0042").await.0, StatusCode::CREATED);
+
+        let search = |memo: &str| {
+            let params = HashMap::from([("memo".to_string(), memo.to_string())]);
+            search_notes(axum::extract::State(state.clone()), axum::extract::Query(params))
+        };
+        let response = search("INV-2024-0042").await;
+        assert_eq!(response.0, StatusCode::OK);
+        let result = response.1.data.as_ref().unwrap();
+        assert_eq!(result.total, 1);
+        assert_eq!(result.notes[0].memo.as_deref(), Some("INV-2024-0042"));
+        assert_eq!(search("INV-2024").await.1.data.as_ref().unwrap().total, 0);
+        assert_eq!(search(&"x".repeat(129)).await.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_note_and_redemption_validation() {
+        use basis_store::schnorr::generate_keypair;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let note_request = |amount: u64, timestamp: u64| {
+            let note =
+                basis_store::IouNote::create_and_sign(recipient_pubkey, amount, timestamp, &issuer_secret)
+                    .unwrap();
+            basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount,
+                timestamp,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }
+        };
+
+        // A valid note passes without being stored
+        let response = validate_note(axum::extract::State(state.clone()), axum::Json(note_request(1000, 1_000))).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let report = response.1.data.as_ref().unwrap();
+        assert!(report.valid && report.errors.is_empty());
+        let stored = get_notes_by_issuer(
+            axum::extract::State(state.clone()),
+            axum::extract::Path(hex::encode(issuer_pubkey)),
+        )
+        .await;
+        assert!(stored.1.data.as_ref().unwrap().is_empty());
+
+        let response = create_note(axum::extract::State(state.clone()), axum::Json(note_request(1000, 1_000))).await;
+        assert_eq!(response.0, StatusCode::CREATED);
+
+        // A stale, tampered update reports every failed check
+        let mut stale = note_request(500, 900);
+        stale.amount = 400;
+        let response = validate_note(axum::extract::State(state.clone()), axum::Json(stale)).await;
+        let report = response.1.data.as_ref().unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.errors, vec!["Past timestamp", "Amount decreased", "Invalid signature"]);
+
+        // Redeeming more than is owed, with no reserve on record and a malformed signature
+        let response = validate_redemption(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::RedeemRequest {
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount: 1500,
+                timestamp: 1_000,
+                reserve_box_id: String::new(),
+                recipient_address: String::new(),
+                issuer_signature: "00".to_string(),
+                emergency: false,
+                reserve_insert_proof: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::OK);
+        let report = response.1.data.as_ref().unwrap();
+        assert!(!report.valid);
+        assert_eq!(report.errors.len(), 3);
+        assert_eq!(report.errors[0], "Insufficient collateral: 1000 < 1500");
+        assert!(report.errors[1].starts_with("issuer_signature"));
+        assert!(report.errors[2].starts_with("Reserve not found"));
+    }
+
+    #[tokio::test]
+    async fn test_redemption_eligibility_reports_each_check() {
+        use basis_server::api::get_redemption_eligibility;
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let note = basis_store::IouNote::create_and_sign(recipient_pubkey, 1000, 1_000, &issuer_secret).unwrap();
+        let response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount: 1000,
+                timestamp: 1_000,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::CREATED);
+
+        let check = |extra: &[(&str, &str)]| {
+            let mut params = HashMap::from([
+                ("issuer".to_string(), hex::encode(issuer_pubkey)),
+                ("recipient".to_string(), hex::encode(recipient_pubkey)),
+            ]);
+            params.extend(extra.iter().map(|(name, value)| (name.to_string(), value.to_string())));
+            get_redemption_eligibility(axum::extract::State(state.clone()), axum::extract::Query(params))
+        };
+
+        // The whole debt is checked by default; only the missing reserve blocks it
+        let response = check(&[]).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let eligibility = response.1.data.as_ref().unwrap();
+        assert!(!eligibility.eligible);
+        assert_eq!(eligibility.amount, 1000);
+        assert!(eligibility.note.passed && eligibility.proof.passed && eligibility.time_lock.passed);
+        assert!(!eligibility.reserve.passed && !eligibility.collateral.passed);
+        assert!(eligibility.reserve.reasons[0].starts_with("Reserve not found"));
+        assert_eq!(eligibility.earliest_eligible_timestamp, None);
+
+        // More than is owed fails the note check
+        let response = check(&[("amount", "1500")]).await;
+        let eligibility = response.1.data.as_ref().unwrap();
+        assert_eq!(eligibility.note.reasons, vec!["Insufficient collateral: 1000 < 1500"]);
+
+        // Without a tracker box or a node, the emergency time lock cannot be checked
+        let response = check(&[("emergency", "true")]).await;
+        let eligibility = response.1.data.as_ref().unwrap();
+        assert!(eligibility.emergency && !eligibility.time_lock.passed);
+        assert_eq!(eligibility.unlock_height, None);
+
+        assert_eq!(check(&[("amount", "lots")]).await.0, StatusCode::BAD_REQUEST);
+        let response = get_redemption_eligibility(
+            axum::extract::State(state.clone()),
+            axum::extract::Query(HashMap::from([("issuer".to_string(), "00".to_string())])),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_idempotency_key_replays_note_creation() {
+        // A retried POST /notes with the same Idempotency-Key gets the original
+        // response instead of a PastTimestamp error
+        use axum::{body::Body, http::Request, middleware, routing::post, Router};
+        use basis_server::idempotency::{idempotency_layer, IdempotencyConfig, IdempotencyStore};
+        use basis_store::schnorr::generate_keypair;
+
+        let state = create_mock_app_state().await;
+        let store = Arc::new(IdempotencyStore::new(IdempotencyConfig::default()));
+        let app = Router::new()
+            .route(
+                "/notes",
+                post(create_note)
+                    .layer(middleware::from_fn_with_state(store.clone(), idempotency_layer)),
+            )
+            .with_state(state);
+
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let note = basis_store::IouNote::create_and_sign(recipient_pubkey, 1000, 1_000, &issuer_secret)
+            .unwrap();
+        let body = serde_json::json!({
+            "recipient_pubkey": hex::encode(recipient_pubkey),
+            "amount": note.amount_collected,
+            "timestamp": note.timestamp,
+            "signature": hex::encode(note.signature),
+            "issuer_pubkey": hex::encode(issuer_pubkey),
+        })
+        .to_string();
+
+        let request = |key: Option<&str>, body: String| {
+            let mut builder = Request::builder()
+                .method("POST")
+                .uri("/notes")
+                .header("content-type", "application/json");
+            if let Some(key) = key {
+                builder = builder.header("Idempotency-Key", key);
+            }
+            builder.body(Body::from(body)).unwrap()
+        };
+
+        let first = app.clone().oneshot(request(Some("note-1"), body.clone())).await.unwrap();
+        assert_eq!(first.status(), StatusCode::CREATED);
+        assert!(!first.headers().contains_key("idempotent-replayed"));
+        let first_body = axum::body::to_bytes(first.into_body(), usize::MAX).await.unwrap();
+
+        let retry = app.clone().oneshot(request(Some("note-1"), body.clone())).await.unwrap();
+        assert_eq!(retry.status(), StatusCode::CREATED);
+        assert_eq!(retry.headers().get("idempotent-replayed").unwrap(), "true");
+        let retry_body = axum::body::to_bytes(retry.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(first_body, retry_body);
+
+        // Without the header the duplicate reaches the tracker and is rejected
+        let duplicate = app.clone().oneshot(request(None, body.clone())).await.unwrap();
+        assert_ne!(duplicate.status(), StatusCode::CREATED);
+
+        // Reusing the key for a different request is an error
+        let other_body = body.replace("\"amount\":1000", "\"amount\":2000");
+        assert_ne!(other_body, body);
+        let reused = app.clone().oneshot(request(Some("note-1"), other_body)).await.unwrap();
+        assert_eq!(reused.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(store.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_complete_redemption_follows_auto_complete_setting() {
+        // Clients complete redemptions unless the scanner is set to do it
+        use basis_server::api::complete_redemption;
+        use basis_server::models::CompleteRedemptionRequest;
+        use basis_store::schnorr::generate_keypair;
+
+        let state = create_mock_app_state().await;
+        let (_, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let request = || {
+            axum::Json(CompleteRedemptionRequest {
+                redemption_id: "redemption-1".to_string(),
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                redeemed_amount: 100,
+            })
+        };
+
+        // Off by default: the request reaches the tracker, which knows no such note
+        assert!(!state.config.scanner.auto_complete_redemptions);
+        let (status, _) = complete_redemption(axum::extract::State(state.clone()), request()).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let mut config = (*state.config).clone();
+        config.scanner.auto_complete_redemptions = true;
+        let auto_complete = AppState {
+            config: Arc::new(config),
+            ..state
+        };
+        let (status, _) = complete_redemption(axum::extract::State(auto_complete), request()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_api_key_required_for_post_requests() {
+        use axum::{body::Body, http::Request, middleware, routing::post, Router};
+        use basis_server::api::get_all_notes;
+        use basis_server::auth::api_key_layer;
+        use basis_server::config::AuthConfig;
+
+        let state = create_mock_app_state().await;
+        let auth = Arc::new(AuthConfig {
+            api_keys: vec!["operator-key".to_string()],
+            admin_token: None,
+        });
+        let app = Router::new()
+            .route("/notes", post(create_note).get(get_all_notes))
+            .with_state(state)
+            .layer(middleware::from_fn_with_state(auth, api_key_layer));
+
+        let post_notes = |key: Option<&str>| {
+            let mut builder = Request::builder()
+                .method("POST")
+                .uri("/notes")
+                .header("content-type", "application/json");
+            if let Some(key) = key {
+                builder = builder.header("X-API-Key", key);
+            }
+            builder.body(Body::from("{}")).unwrap()
+        };
+
+        let missing = app.clone().oneshot(post_notes(None)).await.unwrap();
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+
+        let wrong = app.clone().oneshot(post_notes(Some("wrong-key"))).await.unwrap();
+        assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+
+        // A valid key reaches the handler, which rejects the empty body
+        let authorized = app.clone().oneshot(post_notes(Some("operator-key"))).await.unwrap();
+        assert_ne!(authorized.status(), StatusCode::UNAUTHORIZED);
+
+        // Reads stay public
+        let read = app
+            .clone()
+            .oneshot(Request::builder().uri("/notes").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_ne!(read.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints_require_admin_token() {
+        use axum::{body::Body, http::Request, middleware, routing::post, Router};
+        use basis_server::admin_api::{flush_storage, rebuild_avl_tree};
+        use basis_server::auth::{admin_token_layer, api_key_layer};
+        use basis_server::config::AuthConfig;
+
+        let app = |admin_token: Option<&'static str>| async move {
+            let auth = Arc::new(AuthConfig {
+                api_keys: vec!["operator-key".to_string()],
+                admin_token: admin_token.map(str::to_string),
+            });
+            Router::new()
+                .route("/admin/rebuild-avl", post(rebuild_avl_tree))
+                .route("/admin/flush", post(flush_storage))
+                .with_state(create_mock_app_state().await)
+                .layer(middleware::from_fn_with_state(auth.clone(), api_key_layer))
+                .layer(middleware::from_fn_with_state(auth, admin_token_layer))
+        };
+        let post_admin = |uri: &str, token: Option<&str>| {
+            let mut builder = Request::builder().method("POST").uri(uri);
+            if let Some(token) = token {
+                builder = builder.header("X-Admin-Token", token);
+            }
+            builder.body(Body::empty()).unwrap()
+        };
+
+        // Without a configured token the admin API is off
+        let disabled = app(None).await;
+        let response = disabled.oneshot(post_admin("/admin/flush", Some("anything"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let enabled = app(Some("admin-secret")).await;
+        let missing = enabled.clone().oneshot(post_admin("/admin/flush", None)).await.unwrap();
+        assert_eq!(missing.status(), StatusCode::UNAUTHORIZED);
+        let wrong = enabled.clone().oneshot(post_admin("/admin/flush", Some("operator-key"))).await.unwrap();
+        assert_eq!(wrong.status(), StatusCode::UNAUTHORIZED);
+
+        // The admin token alone is enough; no API key is needed
+        let flushed = enabled.clone().oneshot(post_admin("/admin/flush", Some("admin-secret"))).await.unwrap();
+        assert_eq!(flushed.status(), StatusCode::OK);
+
+        let rebuilt = enabled
+            .clone()
+            .oneshot(post_admin("/admin/rebuild-avl", Some("admin-secret")))
+            .await
+            .unwrap();
+        assert_eq!(rebuilt.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(rebuilt.into_body(), usize::MAX).await.unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["root_digest"].as_str().unwrap().len(), 66);
+    }
+
+    #[tokio::test]
+    async fn test_issuer_policy_refuses_denied_issuers() {
+        use basis_server::admin_api::{allow_issuer, clear_issuer, deny_issuer, get_issuer_policy};
+        use basis_server::models::IssuerPolicyRequest;
+        use basis_store::schnorr::generate_keypair;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (other_secret, other_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let note_request = |secret, issuer: basis_store::PubKey, amount: u64| {
+            let note = basis_store::IouNote::create_and_sign(recipient_pubkey, amount, amount, &secret).unwrap();
+            basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount,
+                timestamp: amount,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(issuer),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }
+        };
+        let issuer = || IssuerPolicyRequest {
+            issuer_pubkey: hex::encode(issuer_pubkey),
+        };
+
+        let denied = deny_issuer(axum::extract::State(state.clone()), axum::Json(issuer())).await;
+        assert_eq!(denied.0, StatusCode::OK);
+        assert!(denied.1.data.as_ref().unwrap().deny.contains(&hex::encode(issuer_pubkey)));
+
+        // A denied issuer gets a distinct status, and the dry run says why
+        let response =
+            create_note(axum::extract::State(state.clone()), axum::Json(note_request(issuer_secret, issuer_pubkey, 1000)))
+                .await;
+        assert_eq!(response.0, StatusCode::FORBIDDEN);
+        assert!(response.1.error.as_ref().unwrap().contains("denylist"));
+        let response =
+            validate_note(axum::extract::State(state.clone()), axum::Json(note_request(issuer_secret, issuer_pubkey, 1000)))
+                .await;
+        assert!(!response.1.data.as_ref().unwrap().valid);
+        let response =
+            create_note(axum::extract::State(state.clone()), axum::Json(note_request(other_secret, other_pubkey, 1000)))
+                .await;
+        assert_eq!(response.0, StatusCode::CREATED);
+
+        // Allowing one issuer shuts out everyone else
+        let cleared = clear_issuer(axum::extract::State(state.clone()), axum::Json(issuer())).await;
+        assert_eq!(cleared.0, StatusCode::OK);
+        let allowed = allow_issuer(axum::extract::State(state.clone()), axum::Json(issuer())).await;
+        assert_eq!(allowed.0, StatusCode::OK);
+        let response =
+            create_note(axum::extract::State(state.clone()), axum::Json(note_request(issuer_secret, issuer_pubkey, 1000)))
+                .await;
+        assert_eq!(response.0, StatusCode::CREATED);
+        let response =
+            create_note(axum::extract::State(state.clone()), axum::Json(note_request(other_secret, other_pubkey, 2000)))
+                .await;
+        assert_eq!(response.0, StatusCode::FORBIDDEN);
+        assert!(response.1.error.as_ref().unwrap().contains("allowlist"));
+
+        let policy = get_issuer_policy(axum::extract::State(state.clone())).await;
+        let lists = policy.1.data.as_ref().unwrap();
+        assert_eq!(lists.allow.len(), 1);
+        assert!(lists.deny.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_watcher_mode_refuses_tracker_endpoints() {
+        use axum::{body::Body, http::Request, middleware, routing::{get, post}, Router};
+        use basis_server::api::{get_audit_report, initiate_redemption};
+        use basis_server::config::WatcherConfig;
+        use basis_server::watcher::watcher_layer;
+
+        let state = create_mock_app_state().await;
+        let watcher = Arc::new(WatcherConfig { enabled: true });
+        let app = Router::new()
+            .route("/notes", post(create_note))
+            .route("/redeem", post(initiate_redemption))
+            .route("/audit/report", get(get_audit_report))
+            .with_state(state)
+            .layer(middleware::from_fn_with_state(watcher, watcher_layer));
+
+        let post_json = |uri: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap()
+        };
+
+        let redeem = app.clone().oneshot(post_json("/redeem")).await.unwrap();
+        assert_eq!(redeem.status(), StatusCode::FORBIDDEN);
+
+        // Recipients can still submit notes; the handler rejects the empty body
+        let notes = app.clone().oneshot(post_json("/notes")).await.unwrap();
+        assert_ne!(notes.status(), StatusCode::FORBIDDEN);
+
+        let report = app
+            .oneshot(Request::builder().uri("/audit/report").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(report.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_degraded_mode_refuses_state_changes() {
+        use axum::{body::Body, http::Request, middleware, routing::{get, post}, Router};
+        use basis_server::api::get_verification_status;
+        use basis_server::cold_start::degraded_layer;
+        use basis_store::{ColdStartCheck, ColdStartStatus};
+
+        let state = create_mock_app_state().await;
+        let app = Router::new()
+            .route("/notes", post(create_note))
+            .route("/notes/validate", post(validate_note))
+            .route("/verification/status", get(get_verification_status))
+            .with_state(state.clone())
+            .layer(middleware::from_fn_with_state(state.cold_start.clone(), degraded_layer));
+        let post_json = |uri: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from("{}"))
+                .unwrap()
+        };
+
+        // Local state that caught up with the chain is served normally
+        let mut check = ColdStartCheck {
+            box_id: "box".to_string(),
+            height: 10,
+            onchain_digest: "01".repeat(33),
+            local_digest: "02".repeat(33),
+            status: ColdStartStatus::Ahead,
+        };
+        state.cold_start.record(check.clone());
+        let notes = app.clone().oneshot(post_json("/notes")).await.unwrap();
+        assert_ne!(notes.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        check.status = ColdStartStatus::Diverged;
+        state.cold_start.record(check);
+        let notes = app.clone().oneshot(post_json("/notes")).await.unwrap();
+        assert_eq!(notes.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // Dry runs and reads stay available
+        let validate = app.clone().oneshot(post_json("/notes/validate")).await.unwrap();
+        assert_ne!(validate.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let status = app
+            .oneshot(Request::builder().uri("/verification/status").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        assert_eq!(status.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(status.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["data"]["degraded"], true);
+        assert_eq!(body["data"]["cold_start"]["status"], "diverged");
+    }
+
+    #[tokio::test]
+    async fn test_balance_nets_notes_in_both_directions() {
+        use basis_server::get_balance;
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (alice_secret, alice) = generate_keypair();
+        let (bob_secret, bob) = generate_keypair();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let balance = |issuer: &basis_store::PubKey, recipient: &basis_store::PubKey| {
+            let params: HashMap<String, String> = [
+                ("issuer".to_string(), hex::encode(issuer)),
+                ("recipient".to_string(), hex::encode(recipient)),
+            ]
+            .into_iter()
+            .collect();
+            get_balance(axum::extract::State(state.clone()), axum::extract::Query(params))
+        };
+
+        // Two keys without notes are even
+        let response = balance(&alice, &bob).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let empty = response.1.data.as_ref().unwrap();
+        assert!(!empty.position.has_note && !empty.reverse.has_note);
+        assert_eq!((empty.net_amount, empty.net_debtor.as_ref()), (0, None));
+
+        for (secret, issuer, recipient, amount, timestamp) in
+            [(alice_secret, alice, bob, 500, now - 2_000), (bob_secret, bob, alice, 200, now - 1_000)]
+        {
+            let note = basis_store::IouNote::create_and_sign(recipient, amount, timestamp, &secret).unwrap();
+            let response = create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient),
+                    amount,
+                    timestamp,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(issuer),
+                    signing_version: 1,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: None,
+                }),
+            )
+            .await;
+            assert_eq!(response.0, StatusCode::CREATED);
+        }
+
+        let response = balance(&bob, &alice).await;
+        let net = response.1.data.as_ref().unwrap();
+        assert_eq!((net.position.outstanding, net.reverse.outstanding), (200, 500));
+        assert_eq!(net.reverse.last_activity, Some(now - 2_000));
+        assert!(net.position.proof_available && net.reverse.proof_available);
+        assert_eq!(net.net_debtor, Some(hex::encode(alice)));
+        assert_eq!(net.net_creditor, Some(hex::encode(bob)));
+        assert_eq!((net.net_amount, net.gross_amount), (300, 700));
+        assert_eq!(net.last_activity, Some(now - 1_000));
+
+        assert_eq!(balance(&alice, &alice).await.0, StatusCode::BAD_REQUEST);
+        let missing = get_balance(axum::extract::State(state.clone()), axum::extract::Query(HashMap::new())).await;
+        assert_eq!(missing.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_netting_suggestions_offset_debts() {
+        use basis_server::get_netting_suggestions;
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (alice_secret, alice) = generate_keypair();
+        let (bob_secret, bob) = generate_keypair();
+        let (_, carol) = generate_keypair();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let suggestions = |params: &[(&str, String)]| {
+            let params: HashMap<String, String> =
+                params.iter().map(|(name, value)| (name.to_string(), value.clone())).collect();
+            get_netting_suggestions(axum::extract::State(state.clone()), axum::extract::Query(params))
+        };
+
+        for (secret, issuer, recipient, amount) in
+            [(alice_secret, alice, bob, 500), (bob_secret, bob, alice, 200), (alice_secret, alice, carol, 50)]
+        {
+            let note = basis_store::IouNote::create_and_sign(recipient, amount, now - 1_000, &secret).unwrap();
+            let response = create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient),
+                    amount,
+                    timestamp: now - 1_000,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(issuer),
+                    signing_version: 1,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: None,
+                }),
+            )
+            .await;
+            assert_eq!(response.0, StatusCode::CREATED);
+        }
+
+        let response = suggestions(&[]).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let plan = response.1.data.as_ref().unwrap();
+        assert_eq!(plan.suggestions.len(), 1);
+        assert_eq!((plan.suggestions[0].amount, plan.suggestions[0].notes_cleared), (200, 1));
+        assert_eq!((plan.total_outstanding_before, plan.total_outstanding_after), (750, 350));
+
+        // Carol is on no cycle, but the totals still cover every note
+        let response = suggestions(&[("pubkey", hex::encode(carol))]).await;
+        let plan = response.1.data.as_ref().unwrap();
+        assert!(plan.suggestions.is_empty());
+        assert_eq!(plan.total_outstanding_before, 750);
+
+        assert_eq!(suggestions(&[("max_cycle_length", "1".to_string())]).await.0, StatusCode::BAD_REQUEST);
+        assert_eq!(suggestions(&[("pubkey", "zz".to_string())]).await.0, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_manual_rates_value_key_status() {
+        use basis_server::admin_api::{get_rates, remove_rate, set_rate};
+        use basis_server::api::get_key_status;
+        use basis_server::models::{RemoveRateRequest, SetRateRequest};
+        use basis_server::pricing::{Pricing, PricingConfig};
+        use basis_store::schnorr::generate_keypair;
+
+        let audit_log_path = std::env::temp_dir().join(format!("basis_test_rate_audit_{}.jsonl", std::process::id()));
+        let mut state = create_mock_app_state().await;
+        state.pricing = Pricing::from_config(&PricingConfig {
+            quote_currency: Some("USD".to_string()),
+            audit_log_path: audit_log_path.clone(),
+            ..PricingConfig::default()
+        })
+        .unwrap();
+        let (_, issuer_pubkey) = generate_keypair();
+        let reserve = basis_store::ExtendedReserveInfo::new(&[4u8; 32], &issuer_pubkey, 3_000_000_000, None, 1);
+        state.ergo_scanner.lock().await.reserve_tracker().update_reserve(reserve).unwrap();
+        let key_status = || {
+            get_key_status(axum::extract::State(state.clone()), axum::extract::Path(hex::encode(issuer_pubkey)))
+        };
+        let set = |base: &str, quote: &str, rate: f64| {
+            let request = SetRateRequest {
+                base: base.to_string(),
+                quote: quote.to_string(),
+                rate,
+            };
+            set_rate(axum::extract::State(state.clone()), axum::Json(request))
+        };
+
+        // Without a rate there is no valuation
+        assert!(key_status().await.1.data.as_ref().unwrap().valuation.is_none());
+
+        assert_eq!(set("ERG", "SigUSD", 2.0).await.0, StatusCode::OK);
+        assert_eq!(set("SigUSD", "USD", 1.0).await.0, StatusCode::OK);
+        assert_eq!(set("ERG", "USD", -1.0).await.0, StatusCode::BAD_REQUEST);
+        let rates = get_rates(axum::extract::State(state.clone())).await;
+        assert_eq!(rates.1.data.as_ref().unwrap().rates.len(), 2);
+
+        let response = key_status().await;
+        let valuation = response.1.data.as_ref().unwrap().valuation.clone().unwrap();
+        assert_eq!((valuation.currency.as_str(), valuation.rate), ("USD", 2.0));
+        assert_eq!((valuation.collateral_value, valuation.debt_value), (6.0, 0.0));
+        assert_eq!(valuation.source, "manual");
+
+        let remove = || {
+            let request = RemoveRateRequest {
+                base: "USD".to_string(),
+                quote: "SIGUSD".to_string(),
+            };
+            remove_rate(axum::extract::State(state.clone()), axum::Json(request))
+        };
+        assert_eq!(remove().await.0, StatusCode::OK);
+        assert_eq!(remove().await.0, StatusCode::NOT_FOUND);
+        assert!(key_status().await.1.data.as_ref().unwrap().valuation.is_none());
+
+        // Every change is in the audit log
+        assert_eq!(std::fs::read_to_string(&audit_log_path).unwrap().lines().count(), 3);
+        let _ = std::fs::remove_file(&audit_log_path);
+    }
+
+    #[tokio::test]
+    async fn test_key_status_reads_reserves_found_by_scanner() {
+        use basis_server::api::get_key_status;
+        use basis_store::schnorr::generate_keypair;
+
+        let state = create_mock_app_state().await;
+        let (_, issuer_pubkey) = generate_keypair();
+        let key_status = || {
+            get_key_status(axum::extract::State(state.clone()), axum::extract::Path(hex::encode(issuer_pubkey)))
+        };
+
+        let response = key_status().await;
+        assert_eq!(response.0, StatusCode::OK);
+        assert_eq!(response.1.data.as_ref().unwrap().collateral, 0);
+
+        // A reserve the scanner records is what key status reports
+        let reserve = basis_store::ExtendedReserveInfo::new(&[3u8; 32], &issuer_pubkey, 700_000, None, 1);
+        state.ergo_scanner.lock().await.reserve_tracker().update_reserve(reserve).unwrap();
+        let response = key_status().await;
+        assert_eq!(response.1.data.as_ref().unwrap().collateral, 700_000);
+
+        // And a reserve it drops is gone from key status too
+        state
+            .ergo_scanner
+            .lock()
+            .await
+            .reserve_tracker()
+            .remove_reserve(&hex::encode([3u8; 32]))
+            .unwrap();
+        assert_eq!(key_status().await.1.data.as_ref().unwrap().collateral, 0);
+    }
+
+    #[tokio::test]
+    async fn test_note_payload_is_stored_for_recipient() {
+        use basis_server::get_note_payload;
+        use basis_store::schnorr::generate_keypair;
+        use basis_store::{decrypt_note_payload, encrypt_note_payload};
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (recipient_secret, recipient_pubkey) = generate_keypair();
+        let note = basis_store::IouNote::create_and_sign(recipient_pubkey, 2_000, 1_000, &issuer_secret).unwrap();
+        let request = |encrypted_payload: Option<String>| basis_server::CreateNoteRequest {
+            recipient_pubkey: hex::encode(recipient_pubkey),
+            amount: 2_000,
+            timestamp: 1_000,
+            signature: hex::encode(note.signature),
+            issuer_pubkey: hex::encode(issuer_pubkey),
+            signing_version: 1,
+            tracker_id: None,
+            interest_rate_bps: None,
+            accrual_method: None,
+            encrypted_payload,
+            memo: None,
+        };
+        let payload = || {
+            get_note_payload(
+                axum::extract::State(state.clone()),
+                axum::extract::Path((hex::encode(issuer_pubkey), hex::encode(recipient_pubkey))),
+            )
+        };
+
+        // The tracker only checks the envelope's shape
+        let response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(request(Some("01".repeat(10)))),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::BAD_REQUEST);
+        assert_eq!(payload().await.0, StatusCode::NOT_FOUND);
+
+        let envelope = encrypt_note_payload(&issuer_secret, &recipient_pubkey, 1_000, b"order 17: 3 crates").unwrap();
+        let response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(request(Some(hex::encode(&envelope)))),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::CREATED);
+
+        let response = payload().await;
+        assert_eq!(response.0, StatusCode::OK);
+        let stored = response.1.data.as_ref().unwrap();
+        assert_eq!(stored.timestamp, 1_000);
+        let envelope = hex::decode(&stored.encrypted_payload).unwrap();
+        assert_eq!(
+            decrypt_note_payload(&recipient_secret, &issuer_pubkey, stored.timestamp, &envelope).unwrap(),
+            b"order 17: 3 crates"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_tracker_signature_requires_matching_note_and_collateral() {
+        use basis_server::api::request_tracker_signature;
+        use basis_server::signing_service::SigningService;
+        use basis_store::schnorr::generate_keypair;
+
+        let mut state = create_mock_app_state().await;
+        let (tracker_secret, tracker_pubkey) = generate_keypair();
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let audit_path = std::env::temp_dir().join(format!(
+            "basis_tracker_signature_audit_{}.jsonl",
+            hex::encode(&issuer_pubkey[1..9])
+        ));
+        let mut signing_config = (*state.config).clone();
+        signing_config.ergo.tracker_public_key = Some(hex::encode(tracker_pubkey));
+        signing_config.ergo.tracker_secret_key = Some(hex::encode(tracker_secret));
+        signing_config.storage.signing_audit_log_path = audit_path.clone();
+        state.signing_service = Arc::new(SigningService::from_config(&signing_config));
+
+        let note =
+            basis_store::IouNote::create_and_sign(recipient_pubkey, 500, 1_000, &issuer_secret).unwrap();
+        let response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount: 500,
+                timestamp: 1_000,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::CREATED);
+
+        let request_signature = |total_debt: u64| {
+            let state = state.clone();
+            async move {
+                request_tracker_signature(
+                    axum::extract::State(state),
+                    axum::Json(basis_server::TrackerSignatureRequest {
+                        issuer_pubkey: hex::encode(issuer_pubkey),
+                        recipient_pubkey: hex::encode(recipient_pubkey),
+                        total_debt,
+                        timestamp: 1_000,
+                        emergency: false,
+                    }),
+                )
+                .await
+            }
+        };
+
+        // The issuer has no reserve yet
+        assert_eq!(request_signature(500).await.0, StatusCode::NOT_FOUND);
+
+        let reserve = basis_store::ExtendedReserveInfo::new(&[1u8; 32], &issuer_pubkey, 800, None, 1);
+        state.reserve_tracker.update_reserve(reserve).unwrap();
+
+        // The debt must match what the tracker holds
+        assert_eq!(request_signature(400).await.0, StatusCode::BAD_REQUEST);
+
+        let response = request_signature(500).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let signed = response.1.data.as_ref().unwrap();
+        assert_eq!(signed.tracker_pubkey, hex::encode(tracker_pubkey));
+        let signature: [u8; 65] = hex::decode(&signed.tracker_signature).unwrap().try_into().unwrap();
+        let message = basis_store::schnorr::signing_message(&issuer_pubkey, &recipient_pubkey, 500, 1_000);
+        assert!(basis_store::schnorr::schnorr_verify(&signature, &message, &tracker_pubkey).is_ok());
+
+        let entries = state.signing_service.audit_log().entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].purpose, "tracker_signature");
+        assert_eq!(entries[0].signature, signed.tracker_signature);
+        std::fs::remove_file(&audit_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_dispute_freezes_redemption_until_resolved() {
+        use basis_server::api::request_tracker_signature;
+        use basis_server::dispute_api::{file_dispute, get_dispute, get_disputes, resolve_dispute};
+        use basis_server::{DisputeEvidenceRequest, FileDisputeRequest, ResolveDisputeRequest};
+        use basis_store::{schnorr::generate_keypair, DisputeFiling, DisputeParty, DisputeStatus};
+
+        let state = create_mock_app_state().await;
+        let started = last_event_seq(&state).await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (recipient_secret, recipient_pubkey) = generate_keypair();
+
+        let note =
+            basis_store::IouNote::create_and_sign(recipient_pubkey, 500, 1_000, &issuer_secret).unwrap();
+        let response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount: 500,
+                timestamp: 1_000,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::CREATED);
+        let conflicting =
+            basis_store::IouNote::create_and_sign(recipient_pubkey, 300, 1_000, &issuer_secret).unwrap();
+
+        let filed_at = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+        let reason = "issuer signed 300 and 500 at the same time";
+        let filing = DisputeFiling::new_signed(
+            issuer_pubkey,
+            recipient_pubkey,
+            DisputeParty::Recipient,
+            reason.to_string(),
+            vec![],
+            filed_at,
+            &recipient_secret,
+        )
+        .unwrap();
+        let file = || {
+            file_dispute(
+                axum::extract::State(state.clone()),
+                axum::Json(FileDisputeRequest {
+                    issuer_pubkey: hex::encode(issuer_pubkey),
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    filed_by: DisputeParty::Recipient,
+                    reason: reason.to_string(),
+                    evidence: vec![DisputeEvidenceRequest {
+                        amount: 300,
+                        timestamp: 1_000,
+                        signature: hex::encode(conflicting.signature),
+                        signing_version: 1,
+                        tracker_id: None,
+                    }],
+                    filed_at,
+                    signature: hex::encode(filing.signature),
+                }),
+            )
+        };
+        let request_signature = || {
+            request_tracker_signature(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::TrackerSignatureRequest {
+                    issuer_pubkey: hex::encode(issuer_pubkey),
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    total_debt: 500,
+                    timestamp: 1_000,
+                    emergency: false,
+                }),
+            )
+        };
+
+        let response = file().await;
+        assert_eq!(response.0, StatusCode::CREATED);
+        let dispute = response.1.data.clone().unwrap();
+        assert_eq!(dispute.status, DisputeStatus::Open);
+        assert_eq!(dispute.evidence[0].finding, basis_store::disputes::EvidenceFinding::Equivocation);
+        assert_eq!(file().await.0, StatusCode::CONFLICT);
+
+        // The tracker refuses to sign redemptions of the frozen note
+        assert_eq!(request_signature().await.0, StatusCode::CONFLICT);
+
+        let query = |params: &[(&str, &str)]| {
+            axum::extract::Query(
+                params
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<std::collections::HashMap<_, _>>(),
+            )
+        };
+        let open = get_disputes(axum::extract::State(state.clone()), query(&[("status", "open")])).await;
+        assert_eq!(open.1.data.as_ref().unwrap().len(), 1);
+
+        let resolved = resolve_dispute(
+            axum::extract::State(state.clone()),
+            axum::extract::Path(dispute.id),
+            axum::Json(ResolveDisputeRequest {
+                status: DisputeStatus::Dismissed,
+                note: "the 300 note was never delivered".to_string(),
+            }),
+        )
+        .await;
+        assert_eq!(resolved.0, StatusCode::OK);
+
+        // Unfrozen: the signature request now fails for lack of a reserve instead
+        assert_eq!(request_signature().await.0, StatusCode::NOT_FOUND);
+        let fetched = get_dispute(axum::extract::State(state.clone()), axum::extract::Path(dispute.id)).await;
+        assert_eq!(fetched.1.data.as_ref().unwrap().status, DisputeStatus::Dismissed);
+        let open = get_disputes(axum::extract::State(state.clone()), query(&[("status", "open")])).await;
+        assert!(open.1.data.as_ref().unwrap().is_empty());
+
+        let events = events_since(&state, started).await;
+        let names: Vec<_> = events.iter().map(|event| event.event_type.name()).collect();
+        assert_eq!(names, vec!["NoteUpdated", "DisputeFiled", "DisputeResolved"]);
+    }
+
+    #[tokio::test]
+    async fn test_split_note_records_every_allocation() {
+        use basis_server::split_note_api::{create_split_note, get_split_notes_by_issuer};
+        use basis_server::{CreateSplitNoteRequest, SplitAllocationRequest};
+        use basis_store::{schnorr::generate_keypair, SplitAllocation, SplitNote};
+
+        let state = create_mock_app_state().await;
+        let started = last_event_seq(&state).await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, alice) = generate_keypair();
+        let (_, bob) = generate_keypair();
+
+        let allocations = vec![
+            SplitAllocation { recipient_pubkey: alice, amount: 700 },
+            SplitAllocation { recipient_pubkey: bob, amount: 300 },
+        ];
+        let split = SplitNote::create_and_sign(allocations.clone(), 2_000, &issuer_secret).unwrap();
+        let request = |signature: &[u8; 65]| CreateSplitNoteRequest {
+            issuer_pubkey: hex::encode(issuer_pubkey),
+            allocations: allocations
+                .iter()
+                .map(|allocation| SplitAllocationRequest {
+                    recipient_pubkey: hex::encode(allocation.recipient_pubkey),
+                    amount: allocation.amount,
+                })
+                .collect(),
+            timestamp: 2_000,
+            signature: hex::encode(signature),
+        };
+
+        let response = create_split_note(axum::extract::State(state.clone()), axum::Json(request(&split.signature))).await;
+        assert_eq!(response.0, StatusCode::CREATED);
+        let created = response.1.data.clone().unwrap();
+        assert_eq!(created.split_id, hex::encode(split.id(&issuer_pubkey)));
+        assert_eq!(created.total_amount, 1_000);
+        assert_ne!(created.allocations[0].avl_key, created.allocations[1].avl_key);
+
+        // The same split cannot be recorded twice, and a tampered signature is refused
+        let replay = create_split_note(axum::extract::State(state.clone()), axum::Json(request(&split.signature))).await;
+        assert_eq!(replay.0, StatusCode::CONFLICT);
+        let mut tampered = split.signature;
+        tampered[64] ^= 1;
+        let tampered = create_split_note(axum::extract::State(state.clone()), axum::Json(request(&tampered))).await;
+        assert_eq!(tampered.0, StatusCode::BAD_REQUEST);
+
+        let listed =
+            get_split_notes_by_issuer(axum::extract::State(state.clone()), axum::extract::Path(hex::encode(issuer_pubkey)))
+                .await;
+        assert_eq!(listed.0, StatusCode::OK);
+        assert_eq!(listed.1.data.as_ref().unwrap().len(), 1);
+
+        let events = events_since(&state, started).await;
+        let recipients: Vec<_> = events.iter().filter_map(|event| event.recipient_pubkey.clone()).collect();
+        assert_eq!(recipients, vec![hex::encode(alice), hex::encode(bob)]);
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation_links_identity_and_retires_old_key() {
+        use basis_server::identity_api::{get_identity_keys, rotate_issuer_key};
+        use basis_server::RotateKeyRequest;
+        use basis_store::{schnorr::generate_keypair, KeyRotation};
+
+        let state = create_mock_app_state().await;
+        let started = last_event_seq(&state).await;
+        let (old_secret, old_pubkey) = generate_keypair();
+        let (_, new_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+
+        let rotation = KeyRotation::create_and_sign(&old_secret, new_pubkey, 1_000).unwrap();
+        let request = || RotateKeyRequest {
+            old_pubkey: hex::encode(old_pubkey),
+            new_pubkey: hex::encode(new_pubkey),
+            timestamp: 1_000,
+            signature: hex::encode(rotation.signature),
+        };
+        let response = rotate_issuer_key(axum::extract::State(state.clone()), axum::Json(request())).await;
+        assert_eq!(response.0, StatusCode::CREATED);
+        let replay = rotate_issuer_key(axum::extract::State(state.clone()), axum::Json(request())).await;
+        assert_eq!(replay.0, StatusCode::CONFLICT);
+
+        // Both keys resolve to the same identity
+        for key in [old_pubkey, new_pubkey] {
+            let identity =
+                get_identity_keys(axum::extract::State(state.clone()), axum::extract::Path(hex::encode(key))).await;
+            assert_eq!(identity.0, StatusCode::OK);
+            let identity = identity.1.data.clone().unwrap();
+            assert_eq!(identity.identity_pubkey, hex::encode(old_pubkey));
+            assert_eq!(identity.current_pubkey, hex::encode(new_pubkey));
+            assert_eq!(identity.keys, vec![hex::encode(old_pubkey), hex::encode(new_pubkey)]);
+            assert_eq!(identity.rotations.len(), 1);
+        }
+
+        // The retired key may no longer sign notes
+        let note = basis_store::IouNote::create_and_sign(recipient_pubkey, 500, 2_000, &old_secret).unwrap();
+        let response = create_note(
+            axum::extract::State(state.clone()),
+            axum::Json(basis_server::CreateNoteRequest {
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount: 500,
+                timestamp: 2_000,
+                signature: hex::encode(note.signature),
+                issuer_pubkey: hex::encode(old_pubkey),
+                signing_version: 1,
+                tracker_id: None,
+                interest_rate_bps: None,
+                accrual_method: None,
+                encrypted_payload: None,
+                memo: None,
+            }),
+        )
+        .await;
+        assert_eq!(response.0, StatusCode::BAD_REQUEST);
+        assert_eq!(response.1.error.as_deref(), Some("Issuer key has been rotated"));
+
+        let events = events_since(&state, started).await;
+        let names: Vec<_> = events.iter().map(|event| event.event_type.name()).collect();
+        assert_eq!(names, vec!["KeyRotated"]);
+    }
+
+    #[tokio::test]
+    async fn test_notes_report_streams_filtered_csv_and_json() {
+        use basis_server::report_api::{get_notes_report_csv, get_notes_report_json};
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (other_secret, other_pubkey) = generate_keypair();
+
+        for (secret, pubkey, timestamp) in [
+            (&issuer_secret, issuer_pubkey, 1_000),
+            (&issuer_secret, issuer_pubkey, 3_000),
+            (&issuer_secret, issuer_pubkey, 2_000),
+            (&other_secret, other_pubkey, 2_500),
+        ] {
+            let (_, recipient_pubkey) = generate_keypair();
+            let note = basis_store::IouNote::create_and_sign(recipient_pubkey, timestamp, timestamp, secret).unwrap();
+            let response = create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    amount: timestamp,
+                    timestamp,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(pubkey),
+                    signing_version: 1,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: None,
+                }),
+            )
+            .await;
+            assert_eq!(response.0, StatusCode::CREATED);
+        }
+
+        let params = HashMap::from([
+            ("issuer".to_string(), hex::encode(issuer_pubkey)),
+            ("from_timestamp".to_string(), "1500".to_string()),
+        ]);
+        let response = get_notes_report_csv(axum::extract::State(state.clone()), axum::extract::Query(params.clone())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "text/csv; charset=utf-8");
+        assert_eq!(response.headers()["content-disposition"], "attachment; filename=\"notes.csv\"");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines[0], basis_store::reports::NoteReportRow::CSV_HEADER.trim_end());
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].contains(",1970-01-01T00:00:02Z,2000,2000,0,2000"));
+        assert!(lines[2].contains(",1970-01-01T00:00:03Z,3000,3000,0,3000"));
+
+        let response = get_notes_report_json(axum::extract::State(state.clone()), axum::extract::Query(params)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let rows: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        let timestamps: Vec<_> = rows.iter().map(|row| row["timestamp"].as_u64().unwrap()).collect();
+        assert_eq!(timestamps, vec![2_000, 3_000]);
+
+        let params = HashMap::from([("to_timestamp".to_string(), "yesterday".to_string())]);
+        let response = get_notes_report_csv(axum::extract::State(state), axum::extract::Query(params)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_notes_export_streams_all_pages() {
+        use basis_server::report_api::get_notes_export;
+        use basis_store::schnorr::generate_keypair;
+        use std::collections::HashMap;
+
+        let state = create_mock_app_state().await;
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+
+        // More notes than fit in one storage page
+        let count = 501u64;
+        for timestamp in 1..=count {
+            let (_, recipient_pubkey) = generate_keypair();
+            let note = basis_store::IouNote::create_and_sign(recipient_pubkey, 100, timestamp, &issuer_secret).unwrap();
+            let response = create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    amount: 100,
+                    timestamp,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(issuer_pubkey),
+                    signing_version: 1,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: None,
+                }),
+            )
+            .await;
+            assert_eq!(response.0, StatusCode::CREATED);
+        }
+
+        let response = get_notes_export(axum::extract::State(state.clone()), axum::extract::Query(HashMap::new())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/json");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let notes: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        let timestamps: Vec<_> = notes.iter().map(|note| note["timestamp"].as_u64().unwrap()).collect();
+        assert_eq!(timestamps, (1..=count).collect::<Vec<_>>());
+        assert_eq!(notes[0]["issuer_pubkey"], hex::encode(issuer_pubkey));
+        assert!(notes[0]["age_seconds"].as_u64().is_some());
+
+        let params = HashMap::from([("issuer".to_string(), "zz".to_string())]);
+        let response = get_notes_export(axum::extract::State(state), axum::extract::Query(params)).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_proof_bundle_export_verifies_offline() {
+        use basis_server::api::get_proof_bundle;
+        use std::collections::HashMap;
+        use basis_server::signing_service::SigningService;
+        use basis_store::schnorr::generate_keypair;
+
+        let mut state = create_mock_app_state().await;
+        let (tracker_secret, tracker_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let audit_path = std::env::temp_dir().join(format!(
+            "basis_proof_bundle_audit_{}.jsonl",
+            hex::encode(&recipient_pubkey[1..9])
+        ));
+        let mut signing_config = (*state.config).clone();
+        signing_config.ergo.tracker_public_key = Some(hex::encode(tracker_pubkey));
+        signing_config.ergo.tracker_secret_key = Some(hex::encode(tracker_secret));
+        signing_config.storage.signing_audit_log_path = audit_path.clone();
+        state.signing_service = Arc::new(SigningService::from_config(&signing_config));
+
+        // Only the first issuer has a reserve
+        let mut issuers = Vec::new();
+        for amount in [500, 700] {
+            let (issuer_secret, issuer_pubkey) = generate_keypair();
+            let note =
+                basis_store::IouNote::create_and_sign(recipient_pubkey, amount, 1_000, &issuer_secret).unwrap();
+            let response = create_note(
+                axum::extract::State(state.clone()),
+                axum::Json(basis_server::CreateNoteRequest {
+                    recipient_pubkey: hex::encode(recipient_pubkey),
+                    amount,
+                    timestamp: 1_000,
+                    signature: hex::encode(note.signature),
+                    issuer_pubkey: hex::encode(issuer_pubkey),
+                    signing_version: 1,
+                    tracker_id: None,
+                    interest_rate_bps: None,
+                    accrual_method: None,
+                    encrypted_payload: None,
+                    memo: None,
+                }),
+            )
+            .await;
+            assert_eq!(response.0, StatusCode::CREATED);
+            issuers.push(issuer_pubkey);
+        }
+        let reserve = basis_store::ExtendedReserveInfo::new(&[2u8; 32], &issuers[0], 800, None, 1);
+        state.reserve_tracker.update_reserve(reserve).unwrap();
+
+        let export = |recipient: String| {
+            let state = state.clone();
+            async move {
+                get_proof_bundle(
+                    axum::extract::State(state),
+                    axum::extract::Query(HashMap::from([("recipient".to_string(), recipient)])),
+                )
+                .await
+            }
+        };
+        assert_eq!(export("zz".to_string()).await.0, StatusCode::BAD_REQUEST);
+
+        let response = export(hex::encode(recipient_pubkey)).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let bundle = response.1 .0.data.unwrap();
+        assert_eq!(bundle.tracker_pubkey, hex::encode(tracker_pubkey));
+        assert_eq!(bundle.notes.len(), 2);
+        for note in &bundle.notes {
+            let has_reserve = note.issuer_pubkey == hex::encode(issuers[0]);
+            assert_eq!(note.tracker_signature.is_some(), has_reserve);
+        }
+
+        // The bundle checks out from its own contents, even after a round trip
+        let saved = serde_json::to_string(&bundle).unwrap();
+        let loaded: basis_trees::ProofBundle = serde_json::from_str(&saved).unwrap();
+        loaded.verify().unwrap();
+
+        let mut tampered = loaded;
+        tampered.notes[0].amount_collected += 1;
+        assert!(tampered.verify().is_err());
+
+        let entries = state.signing_service.audit_log().entries().await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].purpose, "proof_bundle");
+        std::fs::remove_file(&audit_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_commitment_delta_verifies_offline() {
+        use basis_server::api::get_commitment_delta;
+        use basis_server::signing_service::SigningService;
+        use basis_store::commitment_verification::verify_commitment;
+        use basis_store::schnorr::generate_keypair;
+
+        let mut state = create_mock_app_state().await;
+        let (tracker_secret, tracker_pubkey) = generate_keypair();
+        let mut signing_config = (*state.config).clone();
+        signing_config.ergo.tracker_public_key = Some(hex::encode(tracker_pubkey));
+        signing_config.ergo.tracker_secret_key = Some(hex::encode(tracker_secret));
+        state.signing_service = Arc::new(SigningService::from_config(&signing_config));
+
+        // Two note changes before the first commitment, one before the second
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let storage = &state.tracker_storage;
+        for (height, root, amount) in [(100, [1u8; 33], 500), (101, [2u8; 33], 800), (105, [3u8; 33], 1_200)] {
+            let note =
+                basis_store::IouNote::create_and_sign(recipient_pubkey, amount, amount, &issuer_secret).unwrap();
+            let change = basis_store::delta_note(&issuer_pubkey, &note);
+            storage.record_note_change(height, &root, &change).unwrap();
+        }
+        for (box_id, root, height) in [("box1", [2u8; 33], 102), ("box2", [3u8; 33], 110)] {
+            let tracker_box = basis_store::TrackerBoxInfo {
+                box_id: box_id.to_string(),
+                tracker_pubkey: hex::encode(tracker_pubkey),
+                state_commitment: format!("64{}0100000020{}", hex::encode(root), "00000000"),
+                last_verified_height: height,
+                value: 1_000_000,
+                creation_height: height,
+                tracker_nft_id: "00".repeat(32),
+                transaction_id: None,
+            };
+            let check = verify_commitment(&tracker_box, &storage.local_roots_at(height).unwrap(), 0);
+            storage.store_commitment_check(&check).unwrap();
+        }
+
+        let delta_at = |height: u64| {
+            let state = state.clone();
+            async move { get_commitment_delta(axum::extract::State(state), axum::extract::Path(height)).await }
+        };
+        assert_eq!(delta_at(103).await.0, StatusCode::NOT_FOUND);
+
+        let response = delta_at(110).await;
+        assert_eq!(response.0, StatusCode::OK);
+        let delta = response.1 .0.data.unwrap();
+        assert_eq!(delta.previous_height, Some(102));
+        assert_eq!(delta.notes.len(), 1);
+        assert_eq!(delta.notes[0].amount_collected, 1_200);
+
+        // Mirrors check the delta from its own contents
+        let saved = serde_json::to_string(&delta).unwrap();
+        let loaded: basis_trees::CommitmentDelta = serde_json::from_str(&saved).unwrap();
+        loaded.verify().unwrap();
+        assert_eq!(loaded.tracker_pubkey, hex::encode(tracker_pubkey));
+    }
+
+    #[tokio::test]
+    async fn test_replica_follows_primary_commitments() {
+        use axum::{routing::get, Router};
+        use basis_server::api::{get_commitment_delta, get_commitments};
+        use basis_server::replica::{sync_once, PrimaryClient, SyncError};
+        use basis_server::signing_service::SigningService;
+        use basis_store::commitment_verification::verify_commitment;
+        use basis_store::schnorr::generate_keypair;
+
+        let mut primary = create_mock_app_state().await;
+        let (tracker_secret, tracker_pubkey) = generate_keypair();
+        let mut signing_config = (*primary.config).clone();
+        signing_config.ergo.tracker_public_key = Some(hex::encode(tracker_pubkey));
+        signing_config.ergo.tracker_secret_key = Some(hex::encode(tracker_secret));
+        primary.signing_service = Arc::new(SigningService::from_config(&signing_config));
+
+        // The primary's history: real AVL roots after each note change
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, first_recipient) = generate_keypair();
+        let (_, second_recipient) = generate_keypair();
+        let mut tracker = basis_store::TrackerStateManager::new_with_temp_storage();
+        let mut roots = Vec::new();
+        for (height, recipient, amount) in [(100, first_recipient, 500), (101, second_recipient, 800), (105, first_recipient, 1_200)] {
+            let note = basis_store::IouNote::create_and_sign(recipient, amount, amount, &issuer_secret).unwrap();
+            tracker.add_note(&issuer_pubkey, &note).unwrap();
+            let root = tracker.get_state().avl_root_digest;
+            let change = basis_store::delta_note(&issuer_pubkey, &note);
+            primary.tracker_storage.record_note_change(height, &root, &change).unwrap();
+            roots.push(root);
+        }
+        for (box_id, root, height) in [("box1", roots[1], 102), ("box2", roots[2], 110)] {
+            let tracker_box = basis_store::TrackerBoxInfo {
+                box_id: box_id.to_string(),
+                tracker_pubkey: hex::encode(tracker_pubkey),
+                state_commitment: format!("64{}0100000020{}", hex::encode(root), "00000000"),
+                last_verified_height: height,
+                value: 1_000_000,
+                creation_height: height,
+                tracker_nft_id: "00".repeat(32),
+                transaction_id: None,
+            };
+            let local_roots = primary.tracker_storage.local_roots_at(height).unwrap();
+            let check = verify_commitment(&tracker_box, &local_roots, 0);
+            primary.tracker_storage.store_commitment_check(&check).unwrap();
+        }
+
+        let app = Router::new()
+            .route("/commitments", get(get_commitments))
+            .route("/commitments/{height}/delta", get(get_commitment_delta))
+            .with_state(primary);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let primary_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = PrimaryClient::new(&primary_url, std::time::Duration::from_secs(5));
+
+        let replica = create_mock_app_state().await;
+        assert_eq!(sync_once(&replica, &client, &hex::encode(tracker_pubkey)).await, Ok(2));
+        let status = replica.replica.status();
+        assert_eq!(status.synced_height, Some(110));
+        assert_eq!(status.synced_digest, Some(hex::encode(roots[2])));
+
+        let (response_tx, response_rx) = tokio::sync::oneshot::channel();
+        replica
+            .tx
+            .send(TrackerCommand::GetNoteByIssuerAndRecipient {
+                issuer_pubkey,
+                recipient_pubkey: first_recipient,
+                response_tx,
+            })
+            .await
+            .unwrap();
+        let note = response_rx.await.unwrap().unwrap().unwrap();
+        assert_eq!(note.amount_collected, 1_200);
+
+        // Deltas signed by any other tracker are a divergence, not a state to copy
+        let (_, other_tracker) = generate_keypair();
+        let mistaken = create_mock_app_state().await;
+        match sync_once(&mistaken, &client, &hex::encode(other_tracker)).await {
+            Err(SyncError::Diverged { height, .. }) => assert_eq!(height, 102),
+            other => panic!("expected a divergence, got {:?}", other),
+        }
+    }
+
     #[tokio::test]
     async fn test_cors_headers_present() {
         // Test that CORS headers are properly set on responses
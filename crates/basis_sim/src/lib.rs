@@ -0,0 +1,527 @@
+//! Economic simulation of a Basis tracker
+//!
+//! Drives a real [`TrackerStateManager`] and [`ReserveTracker`] with
+//! synthetic agents over simulated blocks, so protocol parameters such as the
+//! redemption time lock, the redemption fee or the debt ceiling can be
+//! compared on the metrics they produce rather than argued about:
+//!
+//! - issuers sign cumulative notes paying random recipients
+//! - recipients redeem what they are owed once the time lock passed since the
+//!   note was last updated, paying the redemption fee to the tracker
+//! - a redemption fails when the issuer's reserves cannot cover it
+//!
+//! Metrics are sampled per block and written as CSV. Runs are deterministic
+//! for a given seed.
+
+use basis_store::{
+    redemption::RedemptionManager, DebtCeilingPolicy, ExtendedReserveInfo, IouNote, MockClock, NoteError, PubKey,
+    RedemptionError, ReserveTracker, ReserveTrackerError, TrackerStateManager,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+/// Unix time of the first simulated block, in milliseconds
+const GENESIS_MILLIS: u64 = 1_700_000_000_000;
+
+#[derive(Error, Debug)]
+pub enum SimError {
+    #[error("Invalid simulation config: {0}")]
+    InvalidConfig(String),
+    #[error("Tracker rejected an operation: {0:?}")]
+    Note(NoteError),
+    #[error("Redemption failed: {0}")]
+    Redemption(#[from] RedemptionError),
+    #[error("Reserve tracker error: {0}")]
+    Reserve(#[from] ReserveTrackerError),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+impl From<NoteError> for SimError {
+    fn from(e: NoteError) -> Self {
+        SimError::Note(e)
+    }
+}
+
+/// Parameters of a simulation run
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimConfig {
+    pub seed: u64,
+    /// Number of blocks to simulate
+    pub blocks: u64,
+    pub block_time_secs: u64,
+    pub issuers: usize,
+    pub recipients: usize,
+    /// Chance per block that an issuer pays someone
+    pub payment_probability: f64,
+    /// Smallest payment (nanoERG)
+    pub min_payment: u64,
+    /// Largest payment (nanoERG)
+    pub max_payment: u64,
+    /// Chance per block that a recipient tries to redeem its largest claim
+    pub redemption_probability: f64,
+    /// Blocks that must pass after a note was last updated before it can be redeemed
+    pub time_lock_blocks: u64,
+    /// Tracker fee charged on redeemed amounts, in basis points
+    pub redemption_fee_bps: u64,
+    /// Collateral each issuer starts with (nanoERG)
+    pub initial_collateral: u64,
+    /// Enforce a debt ceiling of this multiple of collateral on new notes
+    pub max_debt_ratio: Option<f64>,
+}
+
+impl Default for SimConfig {
+    fn default() -> Self {
+        Self {
+            seed: 1,
+            blocks: 720,
+            block_time_secs: 120,
+            issuers: 5,
+            recipients: 20,
+            payment_probability: 0.3,
+            min_payment: 1_000_000,
+            max_payment: 100_000_000,
+            redemption_probability: 0.05,
+            time_lock_blocks: 30,
+            redemption_fee_bps: 20,
+            initial_collateral: 10_000_000_000,
+            max_debt_ratio: None,
+        }
+    }
+}
+
+impl SimConfig {
+    pub fn validate(&self) -> Result<(), SimError> {
+        if self.issuers == 0 || self.recipients == 0 {
+            return Err(SimError::InvalidConfig("at least one issuer and one recipient are needed".to_string()));
+        }
+        if self.block_time_secs == 0 {
+            return Err(SimError::InvalidConfig("block_time_secs must be greater than 0".to_string()));
+        }
+        if self.min_payment == 0 || self.min_payment > self.max_payment {
+            return Err(SimError::InvalidConfig(format!(
+                "payments must satisfy 0 < min_payment <= max_payment, got {}..{}",
+                self.min_payment, self.max_payment
+            )));
+        }
+        for (name, probability) in [
+            ("payment_probability", self.payment_probability),
+            ("redemption_probability", self.redemption_probability),
+        ] {
+            if !(0.0..=1.0).contains(&probability) {
+                return Err(SimError::InvalidConfig(format!("{} must be within [0, 1], got {}", name, probability)));
+            }
+        }
+        if self.redemption_fee_bps > 10_000 {
+            return Err(SimError::InvalidConfig(format!(
+                "redemption_fee_bps must be at most 10000, got {}",
+                self.redemption_fee_bps
+            )));
+        }
+        if let Some(ratio) = self.max_debt_ratio {
+            if !ratio.is_finite() || ratio < 0.0 {
+                return Err(SimError::InvalidConfig(format!("invalid max_debt_ratio: {}", ratio)));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// System metrics after a block; counters are cumulative
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockMetrics {
+    pub block: u64,
+    pub timestamp: u64,
+    pub outstanding_debt: u64,
+    pub collateral: u64,
+    /// `collateral / outstanding_debt`, infinite while nothing is owed
+    pub collateralization: f64,
+    pub notes_issued: u64,
+    /// Notes refused by the debt ceiling
+    pub notes_rejected: u64,
+    pub redemptions: u64,
+    /// Redemptions the issuer's reserves could not cover
+    pub redemption_failures: u64,
+    pub redeemed_amount: u64,
+    pub fees_accrued: u64,
+}
+
+impl BlockMetrics {
+    pub const CSV_HEADER: &'static str = "block,timestamp,outstanding_debt,collateral,collateralization,notes_issued,notes_rejected,redemptions,redemption_failures,redeemed_amount,fees_accrued";
+
+    pub fn to_csv_row(&self) -> String {
+        format!(
+            "{},{},{},{},{:.6},{},{},{},{},{},{}",
+            self.block,
+            self.timestamp,
+            self.outstanding_debt,
+            self.collateral,
+            self.collateralization,
+            self.notes_issued,
+            self.notes_rejected,
+            self.redemptions,
+            self.redemption_failures,
+            self.redeemed_amount,
+            self.fees_accrued
+        )
+    }
+}
+
+/// Write metrics as CSV, header first
+pub fn write_csv(metrics: &[BlockMetrics], mut out: impl Write) -> std::io::Result<()> {
+    writeln!(out, "{}", BlockMetrics::CSV_HEADER)?;
+    for row in metrics {
+        writeln!(out, "{}", row.to_csv_row())?;
+    }
+    Ok(())
+}
+
+struct Issuer {
+    secret: [u8; 32],
+    pubkey: PubKey,
+    reserve_box_id: String,
+}
+
+/// Simulator's view of a note, keyed by issuer and recipient index
+#[derive(Debug, Clone, Copy, Default)]
+struct NoteState {
+    collected: u64,
+    redeemed: u64,
+    updated_block: u64,
+}
+
+impl NoteState {
+    fn outstanding(&self) -> u64 {
+        self.collected - self.redeemed
+    }
+}
+
+/// A simulation in progress
+pub struct Simulation {
+    config: SimConfig,
+    rng: StdRng,
+    clock: MockClock,
+    redemptions: RedemptionManager,
+    reserves: ReserveTracker,
+    issuers: Vec<Issuer>,
+    recipients: Vec<PubKey>,
+    notes: BTreeMap<(usize, usize), NoteState>,
+    block: u64,
+    totals: BlockMetrics,
+}
+
+impl Simulation {
+    /// Set up agents and reserves, keeping tracker storage in `data_dir`
+    pub fn new(config: SimConfig, data_dir: &Path) -> Result<Self, SimError> {
+        config.validate()?;
+        let mut rng = StdRng::seed_from_u64(config.seed);
+
+        let clock = MockClock::new(GENESIS_MILLIS);
+        let mut tracker = TrackerStateManager::with_storage_paths(&data_dir.join("notes"), &data_dir.join("avl_tree"));
+        tracker.set_clock(Arc::new(clock.clone()));
+
+        let reserves = ReserveTracker::new();
+        let issuers = (0..config.issuers)
+            .map(|index| {
+                let (secret, pubkey) = keypair(&mut rng);
+                let reserve = ExtendedReserveInfo::new(
+                    &(index as u64).to_be_bytes(),
+                    &pubkey,
+                    config.initial_collateral,
+                    None,
+                    0,
+                );
+                let issuer = Issuer {
+                    secret,
+                    pubkey,
+                    reserve_box_id: reserve.box_id.clone(),
+                };
+                reserves.update_reserve(reserve).map(|_| issuer)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let recipients = (0..config.recipients).map(|_| keypair(&mut rng).1).collect();
+
+        if let Some(ratio) = config.max_debt_ratio {
+            tracker.set_debt_policy(DebtCeilingPolicy::new(ratio), reserves.clone());
+        }
+
+        let totals = BlockMetrics {
+            block: 0,
+            timestamp: GENESIS_MILLIS,
+            outstanding_debt: 0,
+            collateral: config.initial_collateral * config.issuers as u64,
+            collateralization: f64::INFINITY,
+            notes_issued: 0,
+            notes_rejected: 0,
+            redemptions: 0,
+            redemption_failures: 0,
+            redeemed_amount: 0,
+            fees_accrued: 0,
+        };
+
+        Ok(Self {
+            config,
+            rng,
+            clock,
+            redemptions: RedemptionManager::new(tracker),
+            reserves,
+            issuers,
+            recipients,
+            notes: BTreeMap::new(),
+            block: 0,
+            totals,
+        })
+    }
+
+    /// Simulate the next block and return the metrics after it
+    pub fn step(&mut self) -> Result<BlockMetrics, SimError> {
+        self.block += 1;
+        self.clock
+            .set(GENESIS_MILLIS + self.block * self.config.block_time_secs * 1_000);
+
+        for issuer in 0..self.issuers.len() {
+            if self.rng.gen_bool(self.config.payment_probability) {
+                let recipient = self.rng.gen_range(0..self.recipients.len());
+                let amount = self.rng.gen_range(self.config.min_payment..=self.config.max_payment);
+                self.pay(issuer, recipient, amount)?;
+            }
+        }
+        for recipient in 0..self.recipients.len() {
+            if self.rng.gen_bool(self.config.redemption_probability) {
+                self.redeem(recipient)?;
+            }
+        }
+
+        let (collateral, _) = self.reserves.get_system_totals();
+        self.totals.block = self.block;
+        self.totals.timestamp = self.clock_millis();
+        self.totals.collateral = collateral;
+        self.totals.collateralization = if self.totals.outstanding_debt == 0 {
+            f64::INFINITY
+        } else {
+            collateral as f64 / self.totals.outstanding_debt as f64
+        };
+        Ok(self.totals.clone())
+    }
+
+    /// Simulate every configured block
+    pub fn run(mut self) -> Result<Vec<BlockMetrics>, SimError> {
+        (0..self.config.blocks).map(|_| self.step()).collect()
+    }
+
+    /// Tracker state the simulation drives
+    pub fn tracker(&self) -> &TrackerStateManager {
+        &self.redemptions.tracker
+    }
+
+    /// Give every tracker update its own millisecond, as note timestamps must increase
+    fn tick(&self) -> u64 {
+        self.clock.advance(Duration::from_millis(1));
+        self.clock_millis()
+    }
+
+    fn clock_millis(&self) -> u64 {
+        self.redemptions.tracker.clock().now_millis()
+    }
+
+    fn pay(&mut self, issuer: usize, recipient: usize, amount: u64) -> Result<(), SimError> {
+        let timestamp = self.tick();
+        let state = self.notes.get(&(issuer, recipient)).copied().unwrap_or_default();
+        let collected = state.collected.checked_add(amount).ok_or(NoteError::AmountOverflow)?;
+        let note = IouNote::create_and_sign(
+            self.recipients[recipient],
+            collected,
+            timestamp,
+            &self.issuers[issuer].secret,
+        )?;
+
+        match self.redemptions.tracker.add_note(&self.issuers[issuer].pubkey, &note) {
+            Ok(()) => {
+                self.notes.insert(
+                    (issuer, recipient),
+                    NoteState {
+                        collected,
+                        updated_block: self.block,
+                        ..state
+                    },
+                );
+                self.totals.notes_issued += 1;
+                self.totals.outstanding_debt += amount;
+                Ok(())
+            }
+            Err(NoteError::InsufficientCollateral) => {
+                self.totals.notes_rejected += 1;
+                Ok(())
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Redeem the recipient's largest claim that is past the time lock
+    fn redeem(&mut self, recipient: usize) -> Result<(), SimError> {
+        let claim = self
+            .notes
+            .iter()
+            .filter(|((_, note_recipient), state)| {
+                *note_recipient == recipient
+                    && state.outstanding() > 0
+                    && self.block >= state.updated_block + self.config.time_lock_blocks
+            })
+            .max_by_key(|(_, state)| state.outstanding())
+            .map(|(key, state)| (*key, *state));
+        let Some(((issuer, _), state)) = claim else {
+            return Ok(());
+        };
+
+        let amount = state.outstanding();
+        let reserve_box_id = self.issuers[issuer].reserve_box_id.clone();
+        let collateral = self.reserves.get_reserve(&reserve_box_id)?.base_info.collateral_amount;
+        if collateral < amount {
+            self.totals.redemption_failures += 1;
+            return Ok(());
+        }
+
+        self.tick();
+        self.redemptions
+            .complete_redemption(&self.issuers[issuer].pubkey, &self.recipients[recipient], amount)?;
+        self.reserves.update_collateral(&reserve_box_id, collateral - amount)?;
+        self.notes.insert(
+            (issuer, recipient),
+            NoteState {
+                redeemed: state.redeemed + amount,
+                ..state
+            },
+        );
+
+        self.totals.redemptions += 1;
+        self.totals.redeemed_amount += amount;
+        self.totals.fees_accrued += (amount as u128 * self.config.redemption_fee_bps as u128 / 10_000) as u64;
+        self.totals.outstanding_debt -= amount;
+        Ok(())
+    }
+}
+
+/// Run a simulation in a scratch directory that is removed afterwards
+pub fn run(config: SimConfig) -> Result<Vec<BlockMetrics>, SimError> {
+    let data_dir = scratch_dir(config.seed);
+    let result = Simulation::new(config, &data_dir).and_then(Simulation::run);
+    let _ = std::fs::remove_dir_all(&data_dir);
+    result
+}
+
+fn scratch_dir(seed: u64) -> PathBuf {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    std::env::temp_dir().join(format!("basis_sim_{}_{}_{}", std::process::id(), seed, nanos))
+}
+
+/// Deterministic keypair drawn from the simulation's RNG
+fn keypair(rng: &mut StdRng) -> ([u8; 32], PubKey) {
+    let secp = secp256k1::Secp256k1::new();
+    loop {
+        let secret: [u8; 32] = rng.gen();
+        if let Ok(secret_key) = secp256k1::SecretKey::from_slice(&secret) {
+            return (secret, secp256k1::PublicKey::from_secret_key(&secp, &secret_key).serialize());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SimConfig {
+        SimConfig {
+            blocks: 60,
+            issuers: 2,
+            recipients: 4,
+            payment_probability: 0.8,
+            min_payment: 10_000_000,
+            max_payment: 50_000_000,
+            redemption_probability: 0.5,
+            time_lock_blocks: 5,
+            redemption_fee_bps: 100,
+            initial_collateral: 400_000_000,
+            ..SimConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_simulation_conserves_debt_and_collateral() {
+        let config = config();
+        let metrics = run(config.clone()).unwrap();
+        assert_eq!(metrics.len(), 60);
+        assert_eq!(metrics, run(config.clone()).unwrap(), "runs must be deterministic");
+
+        let last = metrics.last().unwrap();
+        assert!(last.notes_issued > 0 && last.redemptions > 0);
+        // Redeemed debt leaves the issuers' reserves
+        assert_eq!(
+            last.collateral,
+            config.initial_collateral * config.issuers as u64 - last.redeemed_amount
+        );
+        // 1% fee, rounded down per redemption
+        assert!(last.fees_accrued <= last.redeemed_amount / 100);
+        assert!(last.fees_accrued + last.redemptions >= last.redeemed_amount / 100);
+        // Redemptions only ever lower collateral, so undercollateralized issuers fail them
+        assert!(metrics.windows(2).all(|w| w[1].collateral <= w[0].collateral));
+        assert!(last.redemption_failures > 0);
+    }
+
+    #[test]
+    fn test_debt_ceiling_rejects_notes_instead_of_failing_redemptions() {
+        let data_dir = scratch_dir(7);
+        let mut simulation = Simulation::new(
+            SimConfig {
+                max_debt_ratio: Some(1.0),
+                ..config()
+            },
+            &data_dir,
+        )
+        .unwrap();
+
+        let mut last = None;
+        for _ in 0..60 {
+            last = Some(simulation.step().unwrap());
+        }
+        let last = last.unwrap();
+        let tracked_debt: u64 = simulation
+            .tracker()
+            .get_all_notes()
+            .unwrap()
+            .iter()
+            .map(IouNote::outstanding_debt)
+            .sum();
+        drop(simulation);
+        let _ = std::fs::remove_dir_all(&data_dir);
+
+        assert_eq!(tracked_debt, last.outstanding_debt);
+        assert!(last.notes_rejected > 0);
+        assert!(last.collateralization >= 1.0);
+        assert_eq!(last.redemption_failures, 0);
+    }
+
+    #[test]
+    fn test_csv_output() {
+        let metrics = run(SimConfig {
+            blocks: 3,
+            ..config()
+        })
+        .unwrap();
+        let mut out = Vec::new();
+        write_csv(&metrics, &mut out).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], BlockMetrics::CSV_HEADER);
+        assert!(lines[1].starts_with("1,"));
+        assert_eq!(lines[3].split(',').count(), lines[0].split(',').count());
+    }
+}
@@ -0,0 +1,96 @@
+use basis_sim::{write_csv, SimConfig};
+use clap::Parser;
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(name = "basis-sim")]
+#[command(about = "Simulate a Basis tracker economy and write per-block metrics as CSV")]
+#[command(version = "0.1.0")]
+struct Cli {
+    /// RNG seed; runs with the same parameters and seed are identical
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+    #[arg(long, default_value_t = 720)]
+    blocks: u64,
+    #[arg(long, default_value_t = 120)]
+    block_time_secs: u64,
+    #[arg(long, default_value_t = 5)]
+    issuers: usize,
+    #[arg(long, default_value_t = 20)]
+    recipients: usize,
+    /// Chance per block that an issuer pays someone
+    #[arg(long, default_value_t = 0.3)]
+    payment_probability: f64,
+    /// Smallest payment (nanoERG)
+    #[arg(long, default_value_t = 1_000_000)]
+    min_payment: u64,
+    /// Largest payment (nanoERG)
+    #[arg(long, default_value_t = 100_000_000)]
+    max_payment: u64,
+    /// Chance per block that a recipient tries to redeem its largest claim
+    #[arg(long, default_value_t = 0.05)]
+    redemption_probability: f64,
+    /// Blocks after the last update of a note before it can be redeemed
+    #[arg(long, default_value_t = 30)]
+    time_lock_blocks: u64,
+    /// Tracker fee on redeemed amounts, in basis points
+    #[arg(long, default_value_t = 20)]
+    redemption_fee_bps: u64,
+    /// Collateral each issuer starts with (nanoERG)
+    #[arg(long, default_value_t = 10_000_000_000)]
+    initial_collateral: u64,
+    /// Refuse notes taking an issuer's debt past this multiple of its collateral
+    #[arg(long)]
+    max_debt_ratio: Option<f64>,
+    /// Write the CSV to this file instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let config = SimConfig {
+        seed: cli.seed,
+        blocks: cli.blocks,
+        block_time_secs: cli.block_time_secs,
+        issuers: cli.issuers,
+        recipients: cli.recipients,
+        payment_probability: cli.payment_probability,
+        min_payment: cli.min_payment,
+        max_payment: cli.max_payment,
+        redemption_probability: cli.redemption_probability,
+        time_lock_blocks: cli.time_lock_blocks,
+        redemption_fee_bps: cli.redemption_fee_bps,
+        initial_collateral: cli.initial_collateral,
+        max_debt_ratio: cli.max_debt_ratio,
+    };
+
+    let result = basis_sim::run(config).and_then(|metrics| {
+        match &cli.output {
+            Some(path) => write_csv(&metrics, std::io::BufWriter::new(std::fs::File::create(path)?))?,
+            None => write_csv(&metrics, std::io::stdout().lock())?,
+        }
+        Ok(metrics)
+    });
+
+    match result {
+        Ok(metrics) => {
+            if let Some(last) = metrics.last() {
+                eprintln!(
+                    "{} blocks: {} notes ({} rejected), {} redemptions ({} failed), {} fees, final collateralization {:.3}",
+                    last.block,
+                    last.notes_issued,
+                    last.notes_rejected,
+                    last.redemptions,
+                    last.redemption_failures,
+                    last.fees_accrued,
+                    last.collateralization
+                );
+            }
+        }
+        Err(e) => {
+            eprintln!("Simulation failed: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,8 +1,8 @@
 use basis_store::{
     schnorr::{self, generate_keypair},
-    IouNote,
+    IouNote, PubKey,
 };
-use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
 
 fn bench_note_creation(c: &mut Criterion) {
     c.bench_function("create_and_sign_note", |b| {
@@ -14,9 +14,9 @@ fn bench_note_creation(c: &mut Criterion) {
                 black_box(recipient_pubkey),
                 black_box(1000),
                 black_box(1234567890),
-                black_box(&secret.secret_bytes()),
+                black_box(&secret),
             );
-            black_box(note);
+            let _ = black_box(note);
         });
     });
 }
@@ -27,16 +27,44 @@ fn bench_signature_verification(c: &mut Criterion) {
         let recipient_pubkey = [2u8; 33];
 
         let note =
-            IouNote::create_and_sign(recipient_pubkey, 1000, 1234567890, &secret.secret_bytes())
+            IouNote::create_and_sign(recipient_pubkey, 1000, 1234567890, &secret)
                 .unwrap();
 
         b.iter(|| {
             let result = note.verify_signature(black_box(&issuer_pubkey));
-            black_box(result);
+            let _ = black_box(result);
         });
     });
 }
 
+fn bench_batch_verification(c: &mut Criterion) {
+    let mut group = c.benchmark_group("verify_notes");
+    for size in [100u64, 1_000, 5_000] {
+        let notes: Vec<(PubKey, IouNote)> = (0..size)
+            .map(|i| {
+                let (secret, issuer_pubkey) = generate_keypair();
+                let note = IouNote::create_and_sign([2u8; 33], 1000 + i, 1234567890 + i, &secret).unwrap();
+                (issuer_pubkey, note)
+            })
+            .collect();
+        group.throughput(Throughput::Elements(size));
+
+        group.bench_with_input(BenchmarkId::new("sequential", size), &notes, |b, notes| {
+            b.iter(|| {
+                let results: Vec<_> = notes
+                    .iter()
+                    .map(|(issuer_pubkey, note)| note.verify_signature(issuer_pubkey))
+                    .collect();
+                black_box(results);
+            });
+        });
+        group.bench_with_input(BenchmarkId::new("batch", size), &notes, |b, notes| {
+            b.iter(|| black_box(schnorr::verify_notes_batch(black_box(notes))));
+        });
+    }
+    group.finish();
+}
+
 fn bench_schnorr_signature(c: &mut Criterion) {
     c.bench_function("schnorr_sign", |b| {
         let (secret, pubkey) = generate_keypair();
@@ -45,10 +73,10 @@ fn bench_schnorr_signature(c: &mut Criterion) {
         b.iter(|| {
             let signature = schnorr::schnorr_sign(
                 black_box(message),
-                black_box(&secret.into()),
+                black_box(&secret),
                 black_box(&pubkey),
             );
-            black_box(signature);
+            let _ = black_box(signature);
         });
     });
 
@@ -56,7 +84,7 @@ fn bench_schnorr_signature(c: &mut Criterion) {
         let (secret, pubkey) = generate_keypair();
         let message = b"benchmark message for schnorr verification";
 
-        let signature = schnorr::schnorr_sign(message, &secret.into(), &pubkey).unwrap();
+        let signature = schnorr::schnorr_sign(message, &secret, &pubkey).unwrap();
 
         b.iter(|| {
             let result = schnorr::schnorr_verify(
@@ -64,7 +92,7 @@ fn bench_schnorr_signature(c: &mut Criterion) {
                 black_box(message),
                 black_box(&pubkey),
             );
-            black_box(result);
+            let _ = black_box(result);
         });
     });
 }
@@ -111,7 +139,7 @@ fn bench_bulk_note_operations(c: &mut Criterion) {
                         [i as u8; 33],
                         1000 + i as u64,
                         1234567890 + i as u64,
-                        &secret.secret_bytes(),
+                        &secret,
                     )
                     .unwrap();
                     notes.push(black_box(note));
@@ -140,6 +168,7 @@ criterion_group!(
     benches,
     bench_note_creation,
     bench_signature_verification,
+    bench_batch_verification,
     bench_schnorr_signature,
     bench_note_serialization,
     bench_outstanding_debt_calculation,
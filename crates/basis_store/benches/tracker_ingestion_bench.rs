@@ -0,0 +1,156 @@
+//! Note ingestion, proof generation and root digest cost by tree size
+//!
+//! Each size is measured against a tracker whose fjall note storage and
+//! persistent AVL tree already hold that many notes. Digest computation is
+//! measured on both the in-memory and the fjall-backed tree.
+//!
+//! Sizes default to 10k, 100k and 1M keys; set `BASIS_BENCH_SIZES` to a
+//! comma-separated list (e.g. `BASIS_BENCH_SIZES=10000`) for a quicker run.
+
+use std::time::{Duration, Instant};
+
+use basis_store::{persistence::NoteStorage, schnorr::generate_keypair, IouNote, PubKey, TrackerStateManager};
+use basis_trees::{BasisAvlTree, PersistentTreeConfig};
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use tempfile::TempDir;
+
+const DEFAULT_SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
+const COMMIT_INTERVAL: usize = 1_000;
+
+fn sizes() -> Vec<usize> {
+    std::env::var("BASIS_BENCH_SIZES")
+        .ok()
+        .map(|sizes| sizes.split(',').filter_map(|size| size.trim().parse().ok()).collect())
+        .unwrap_or_else(|| DEFAULT_SIZES.to_vec())
+}
+
+// Distinct, well-formed looking keys for prefilled notes; storage does not validate them
+fn prefill_pubkey(prefix: u8, i: usize) -> PubKey {
+    let mut pubkey = [0u8; 33];
+    pubkey[0] = prefix;
+    pubkey[1..9].copy_from_slice(&(i as u64).to_be_bytes());
+    pubkey
+}
+
+fn tree_key(i: usize) -> Vec<u8> {
+    let mut key = vec![0u8; 32];
+    key[..8].copy_from_slice(&(i as u64).to_be_bytes());
+    key[8] = 1;
+    key
+}
+
+/// Tracker over storage prefilled with `size` notes, one per issuer
+fn prefilled_tracker(size: usize) -> (TempDir, TrackerStateManager, Vec<(PubKey, PubKey)>) {
+    let dir = tempfile::tempdir().unwrap();
+    let mut pairs = Vec::with_capacity(size);
+    {
+        let storage = NoteStorage::open(dir.path().join("notes")).unwrap();
+        for i in 0..size {
+            let issuer = prefill_pubkey(0x02, i);
+            let recipient = prefill_pubkey(0x03, i);
+            let note = IouNote::new(recipient, 1_000 + i as u64, 0, 1 + i as u64, [1u8; 65]);
+            storage.store_note(&issuer, &note).unwrap();
+            pairs.push((issuer, recipient));
+        }
+        storage.persist().unwrap();
+    }
+    // Opening the tracker rebuilds the persistent AVL tree from the stored notes
+    let tracker = TrackerStateManager::with_storage_paths(&dir.path().join("notes"), &dir.path().join("avl_tree"));
+    (dir, tracker, pairs)
+}
+
+fn filled_tree(size: usize, persistent_dir: Option<&TempDir>) -> BasisAvlTree {
+    let mut tree = match persistent_dir {
+        Some(dir) => BasisAvlTree::open_persistent(dir.path(), PersistentTreeConfig::default()).unwrap(),
+        None => BasisAvlTree::new().unwrap(),
+    };
+    for i in 0..size {
+        tree.insert(tree_key(i), (i as u64).to_be_bytes().to_vec()).unwrap();
+        if (i + 1) % COMMIT_INTERVAL == 0 {
+            tree.commit().unwrap();
+        }
+    }
+    tree.commit().unwrap();
+    tree
+}
+
+fn bench_tracker(c: &mut Criterion) {
+    let mut trackers: Vec<_> = sizes().into_iter().map(|size| (size, prefilled_tracker(size))).collect();
+
+    // Signing happens outside the timed section; add_note still verifies
+    let mut group = c.benchmark_group("tracker_add_note");
+    group.sample_size(10);
+    for (size, (_, tracker, _)) in &mut trackers {
+        group.bench_with_input(BenchmarkId::from_parameter(*size), size, |b, _| {
+            b.iter_custom(|iters| {
+                let notes: Vec<(PubKey, IouNote)> = (0..iters)
+                    .map(|_| {
+                        let (secret, issuer) = generate_keypair();
+                        let (_, recipient) = generate_keypair();
+                        (issuer, IouNote::create_and_sign(recipient, 500, 1_000, &secret).unwrap())
+                    })
+                    .collect();
+                let start = Instant::now();
+                for (issuer, note) in &notes {
+                    tracker.add_note(issuer, note).unwrap();
+                }
+                start.elapsed()
+            });
+        });
+    }
+    group.finish();
+
+    let mut group = c.benchmark_group("tracker_generate_proof");
+    for (size, (_, tracker, pairs)) in &mut trackers {
+        group.bench_with_input(BenchmarkId::from_parameter(*size), size, |b, _| {
+            let mut i = 0;
+            b.iter(|| {
+                i = (i + 7919) % pairs.len();
+                let (issuer, recipient) = &pairs[i];
+                black_box(tracker.generate_proof(issuer, recipient).unwrap());
+            });
+        });
+    }
+    group.finish();
+}
+
+fn bench_root_digest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("avl_update_root_digest");
+    group.measurement_time(Duration::from_secs(10));
+
+    for size in sizes() {
+        let mut in_memory = filled_tree(size, None);
+        group.bench_with_input(BenchmarkId::new("in_memory", size), &size, |b, &size| {
+            let mut i = 0;
+            b.iter(|| {
+                i = (i + 7919) % size;
+                in_memory.update(tree_key(i), 0u64.to_be_bytes().to_vec()).unwrap();
+                black_box(in_memory.root_digest());
+            });
+        });
+        drop(in_memory);
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut persistent = filled_tree(size, Some(&dir));
+        group.bench_with_input(BenchmarkId::new("persistent", size), &size, |b, &size| {
+            let mut i = 0;
+            let mut pending = 0;
+            b.iter(|| {
+                i = (i + 7919) % size;
+                persistent.update(tree_key(i), 0u64.to_be_bytes().to_vec()).unwrap();
+                black_box(persistent.root_digest());
+                // Commit like the tracker does so dirty nodes stay bounded
+                pending += 1;
+                if pending == COMMIT_INTERVAL {
+                    persistent.commit().unwrap();
+                    pending = 0;
+                }
+            });
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_tracker, bench_root_digest);
+criterion_main!(benches);
@@ -781,6 +781,16 @@ mod tests {
         let decoded_debt = u64::from_be_bytes(lookup_proof.value.try_into().unwrap());
         assert_eq!(decoded_debt, total_debt,
             "Tracker tree value must match note's totalDebt");
+
+        // The proof must be a lookup proof for this key against the current root
+        let root_digest = tracker.get_state().avl_root_digest;
+        let operations = basis_trees::encode_operations(&[
+            basis_trees::ergo_avltree_rust::operation::Operation::Lookup(lookup_proof.key.clone().into()),
+        ]).unwrap();
+        let verified = basis_trees::verify_operations(&root_digest, &lookup_proof.proof, &operations)
+            .expect("Lookup proof must verify against the tracker root");
+        assert_eq!(verified.digest, root_digest);
+        assert_eq!(verified.results[0], Some(total_debt.to_be_bytes().to_vec()));
     }
 
     // ========== PROPERTY-BASED STYLE TESTS ==========
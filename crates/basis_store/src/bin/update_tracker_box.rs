@@ -14,6 +14,7 @@ fn main() {
         value: 100000,
         creation_height: 1785096,
         tracker_nft_id: "000b0695159e5f5c32c606385bd5f276d80133149c84c8b1325366381bf6f17f".to_string(),
+        transaction_id: None,
     };
 
     tracker_storage.store_tracker_box(&tracker_box)
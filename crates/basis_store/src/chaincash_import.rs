@@ -0,0 +1,384 @@
+//! Import of outstanding notes from a chaincash-rs database
+//!
+//! chaincash-rs keeps its notes in SQLite. The importer reads the tables it
+//! needs from that database:
+//!
+//! - `reserves(identifier, owner)`: reserve NFT ID and hex-encoded owner public key
+//! - `notes(id, identifier, value, owner)`: note token ID, value and hex-encoded holder public key
+//! - `ownership_entries(note_id, reserve_nft_id, position)`: the signature chain of each note
+//!
+//! Each note becomes a debt from the owner of the reserve backing its first
+//! ownership entry (the original issuer) to its current holder. Debts of the
+//! same issuer-recipient pair are summed into one cumulative Basis note.
+//!
+//! ChainCash signatures cover a different message than Basis notes, so they
+//! cannot be carried over. Every converted note is re-signed with the issuer's
+//! secret key, which the caller supplies; debts of issuers without a key are
+//! reported and left out. Signed notes go through
+//! [`TrackerStateManager::add_notes`], so they are validated like any other
+//! and reach the AVL tree as one batch.
+
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use crate::{schnorr, IouNote, NoteError, PubKey, TrackerStateManager};
+
+/// Errors reading a chaincash-rs database
+#[derive(Debug, thiserror::Error)]
+pub enum ImportError {
+    #[error("Database error: {0}")]
+    Database(#[from] rusqlite::Error),
+    #[error("Not a chaincash-rs database: missing table '{0}'")]
+    MissingTable(&'static str),
+}
+
+/// A note as stored by chaincash-rs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChaincashNote {
+    /// Note token ID
+    pub identifier: String,
+    pub value: u64,
+    /// Hex-encoded public key of the current holder
+    pub holder: String,
+    /// Hex-encoded public key of the owner of the reserve that issued the note,
+    /// if the note has ownership entries
+    pub issuer: Option<String>,
+}
+
+/// Debt of one issuer to one recipient, summed over their chaincash notes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConvertedDebt {
+    pub issuer_pubkey: PubKey,
+    pub recipient_pubkey: PubKey,
+    pub amount: u64,
+    /// Token IDs of the chaincash notes making up the debt
+    pub note_identifiers: Vec<String>,
+}
+
+/// A chaincash note or debt left out of the import, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedImport {
+    /// Note token IDs affected
+    pub note_identifiers: Vec<String>,
+    pub reason: String,
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ImportReport {
+    pub notes_read: usize,
+    /// Issuer-recipient pairs written to the tracker
+    pub debts_imported: usize,
+    /// Sum of imported debt
+    pub amount_imported: u64,
+    pub skipped: Vec<SkippedImport>,
+}
+
+/// Read-only handle on a chaincash-rs SQLite database
+pub struct ChaincashDatabase {
+    connection: rusqlite::Connection,
+}
+
+impl ChaincashDatabase {
+    /// Open a chaincash-rs database without modifying it
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, ImportError> {
+        let connection =
+            rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        for table in ["reserves", "notes", "ownership_entries"] {
+            let exists: bool = connection.query_row(
+                "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1)",
+                [table],
+                |row| row.get(0),
+            )?;
+            if !exists {
+                return Err(ImportError::MissingTable(table));
+            }
+        }
+        Ok(Self { connection })
+    }
+
+    /// All notes with their issuing reserve owner
+    pub fn read_notes(&self) -> Result<Vec<ChaincashNote>, ImportError> {
+        let mut statement = self.connection.prepare(
+            "SELECT n.identifier, n.value, n.owner, r.owner
+             FROM notes n
+             LEFT JOIN ownership_entries e ON e.note_id = n.id
+                 AND e.position = (SELECT MIN(position) FROM ownership_entries WHERE note_id = n.id)
+             LEFT JOIN reserves r ON r.identifier = e.reserve_nft_id
+             ORDER BY n.id",
+        )?;
+        let notes = statement
+            .query_map([], |row| {
+                Ok(ChaincashNote {
+                    identifier: row.get(0)?,
+                    value: row.get::<_, i64>(1)?.max(0) as u64,
+                    holder: row.get(2)?,
+                    issuer: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(notes)
+    }
+}
+
+fn parse_pubkey(hex_str: &str) -> Option<PubKey> {
+    let pubkey = schnorr::pubkey_from_hex(hex_str).ok()?;
+    schnorr::validate_public_key(&pubkey).ok()?;
+    Some(pubkey)
+}
+
+/// Group chaincash notes into cumulative debts per issuer-recipient pair
+pub fn convert_notes(notes: &[ChaincashNote]) -> (Vec<ConvertedDebt>, Vec<SkippedImport>) {
+    let mut debts: BTreeMap<(PubKey, PubKey), ConvertedDebt> = BTreeMap::new();
+    let mut skipped = Vec::new();
+    let mut skip = |note: &ChaincashNote, reason: &str| {
+        skipped.push(SkippedImport {
+            note_identifiers: vec![note.identifier.clone()],
+            reason: reason.to_string(),
+        })
+    };
+
+    for note in notes {
+        let Some(issuer_hex) = &note.issuer else {
+            skip(note, "no issuing reserve found");
+            continue;
+        };
+        let (Some(issuer_pubkey), Some(recipient_pubkey)) = (parse_pubkey(issuer_hex), parse_pubkey(&note.holder))
+        else {
+            skip(note, "invalid issuer or holder public key");
+            continue;
+        };
+        if note.value == 0 {
+            skip(note, "zero value");
+            continue;
+        }
+        if issuer_pubkey == recipient_pubkey {
+            // A note returned to its issuer is no longer a debt
+            skip(note, "held by its issuer");
+            continue;
+        }
+
+        let debt = debts.entry((issuer_pubkey, recipient_pubkey)).or_insert_with(|| ConvertedDebt {
+            issuer_pubkey,
+            recipient_pubkey,
+            amount: 0,
+            note_identifiers: Vec::new(),
+        });
+        match debt.amount.checked_add(note.value) {
+            Some(amount) => {
+                debt.amount = amount;
+                debt.note_identifiers.push(note.identifier.clone());
+            }
+            None => skip(note, "debt overflows u64"),
+        }
+    }
+
+    (debts.into_values().collect(), skipped)
+}
+
+/// Sign converted debts and add them to the tracker
+///
+/// A debt adds to whatever the tracker already records for the pair, and is
+/// stamped with the tracker clock's current time. The signed notes are added
+/// as one batch, their signatures verified in parallel with
+/// [`schnorr::verify_notes_batch`]; a debt whose note does not verify, such as
+/// one signed with a key that is not the issuer's, is reported by its index
+/// in `debts`.
+pub fn import_debts(
+    tracker: &mut TrackerStateManager,
+    debts: Vec<ConvertedDebt>,
+    issuer_keys: &HashMap<PubKey, [u8; 32]>,
+) -> ImportReport {
+    let mut report = ImportReport::default();
+    let timestamp = tracker.clock().now_millis();
+
+    let mut signed = Vec::new();
+    let mut notes = Vec::new();
+    for (index, debt) in debts.into_iter().enumerate() {
+        let Some(secret_key) = issuer_keys.get(&debt.issuer_pubkey) else {
+            report.skipped.push(SkippedImport {
+                note_identifiers: debt.note_identifiers,
+                reason: format!("no secret key for issuer {}", hex::encode(debt.issuer_pubkey)),
+            });
+            continue;
+        };
+
+        let note = tracker
+            .get_issuer_notes(&debt.issuer_pubkey)
+            .map(|notes| {
+                notes
+                    .iter()
+                    .find(|note| note.recipient_pubkey == debt.recipient_pubkey)
+                    .map_or(0, |existing| existing.amount_collected)
+            })
+            .and_then(|existing| existing.checked_add(debt.amount).ok_or(NoteError::AmountOverflow))
+            .and_then(|total| IouNote::create_and_sign(debt.recipient_pubkey, total, timestamp, secret_key));
+
+        match note {
+            Ok(note) => {
+                notes.push((debt.issuer_pubkey, note));
+                signed.push((index, debt));
+            }
+            Err(e) => report.skipped.push(rejected(debt, &e)),
+        }
+    }
+
+    match tracker.add_notes(&notes) {
+        Ok(results) => {
+            for ((index, debt), result) in signed.into_iter().zip(results) {
+                match result {
+                    Ok(()) => {
+                        report.debts_imported += 1;
+                        report.amount_imported = report.amount_imported.saturating_add(debt.amount);
+                    }
+                    Err(NoteError::InvalidSignature) => report.skipped.push(SkippedImport {
+                        reason: format!(
+                            "debt {}: signature does not verify with issuer key {}",
+                            index,
+                            hex::encode(debt.issuer_pubkey)
+                        ),
+                        note_identifiers: debt.note_identifiers,
+                    }),
+                    Err(e) => report.skipped.push(rejected(debt, &e)),
+                }
+            }
+        }
+        Err(e) => report.skipped.extend(signed.into_iter().map(|(_, debt)| rejected(debt, &e))),
+    }
+
+    report
+}
+
+fn rejected(debt: ConvertedDebt, error: &NoteError) -> SkippedImport {
+    SkippedImport {
+        note_identifiers: debt.note_identifiers,
+        reason: format!("rejected by tracker: {:?}", error),
+    }
+}
+
+/// Import every outstanding note of a chaincash-rs database into the tracker
+pub fn import_chaincash<P: AsRef<Path>>(
+    path: P,
+    tracker: &mut TrackerStateManager,
+    issuer_keys: &HashMap<PubKey, [u8; 32]>,
+) -> Result<ImportReport, ImportError> {
+    let notes = ChaincashDatabase::open(path)?.read_notes()?;
+    let (debts, skipped) = convert_notes(&notes);
+
+    let mut report = import_debts(tracker, debts, issuer_keys);
+    report.notes_read = notes.len();
+    report.skipped.splice(0..0, skipped);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{schnorr::generate_keypair, MockClock};
+
+    fn write_database(path: &Path, rows: &[(&str, u64, &PubKey, Option<&PubKey>)]) {
+        let connection = rusqlite::Connection::open(path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE reserves (id INTEGER PRIMARY KEY, identifier TEXT NOT NULL, owner TEXT NOT NULL);
+                 CREATE TABLE notes (id INTEGER PRIMARY KEY, identifier TEXT NOT NULL, value BIGINT NOT NULL, owner TEXT NOT NULL);
+                 CREATE TABLE ownership_entries (id INTEGER PRIMARY KEY, note_id INTEGER NOT NULL, reserve_nft_id TEXT NOT NULL, position BIGINT NOT NULL);",
+            )
+            .unwrap();
+        for (i, (identifier, value, holder, issuer)) in rows.iter().enumerate() {
+            connection
+                .execute(
+                    "INSERT INTO notes (id, identifier, value, owner) VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![i as i64, identifier, *value as i64, hex::encode(holder)],
+                )
+                .unwrap();
+            if let Some(issuer) = issuer {
+                let reserve = format!("reserve-{}", hex::encode(&issuer[1..5]));
+                connection
+                    .execute(
+                        "INSERT INTO reserves (identifier, owner) SELECT ?1, ?2
+                         WHERE NOT EXISTS (SELECT 1 FROM reserves WHERE identifier = ?1)",
+                        rusqlite::params![reserve, hex::encode(issuer)],
+                    )
+                    .unwrap();
+                // A later endorsement by another reserve does not change the issuer
+                connection
+                    .execute(
+                        "INSERT INTO ownership_entries (note_id, reserve_nft_id, position) VALUES (?1, ?2, 0), (?1, 'other', 1)",
+                        rusqlite::params![i as i64, reserve],
+                    )
+                    .unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_import_chaincash_database() {
+        let (issuer_secret, issuer) = generate_keypair();
+        let (_, unknown_issuer) = generate_keypair();
+        let (_, holder) = generate_keypair();
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("chaincash.sqlite");
+        write_database(
+            &db_path,
+            &[
+                ("note-a", 300, &holder, Some(&issuer)),
+                ("note-b", 200, &holder, Some(&issuer)),
+                ("note-c", 50, &holder, Some(&unknown_issuer)),
+                ("note-d", 70, &holder, None),
+            ],
+        );
+
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        tracker.set_clock(std::sync::Arc::new(MockClock::new(1_000)));
+        let keys = HashMap::from([(issuer, issuer_secret)]);
+        let report = import_chaincash(&db_path, &mut tracker, &keys).unwrap();
+
+        assert_eq!(report.notes_read, 4);
+        assert_eq!((report.debts_imported, report.amount_imported), (1, 500));
+        assert_eq!(report.skipped.len(), 2);
+        assert_eq!(report.skipped[0].note_identifiers, vec!["note-d".to_string()]);
+        assert_eq!(report.skipped[1].note_identifiers, vec!["note-c".to_string()]);
+
+        let note = tracker.lookup_note(&issuer, &holder).unwrap();
+        assert_eq!(note.amount_collected, 500);
+        note.verify_signature(&issuer).unwrap();
+
+        assert!(matches!(
+            ChaincashDatabase::open(dir.path().join("missing.sqlite")),
+            Err(ImportError::Database(_))
+        ));
+    }
+
+    #[test]
+    fn test_import_reports_bad_signature_by_index() {
+        let (issuer_secret, issuer) = generate_keypair();
+        let holders: Vec<PubKey> = (0..3).map(|_| generate_keypair().1).collect();
+        let debt = |issuer_pubkey: PubKey, recipient_pubkey: PubKey, identifier: &str| ConvertedDebt {
+            issuer_pubkey,
+            recipient_pubkey,
+            amount: 100,
+            note_identifiers: vec![identifier.to_string()],
+        };
+
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        tracker.set_clock(std::sync::Arc::new(MockClock::new(1_000)));
+        // The key supplied for holders[2] is not theirs, so their note cannot verify
+        let keys = HashMap::from([(issuer, issuer_secret), (holders[2], issuer_secret)]);
+        let debts = vec![
+            debt(issuer, holders[0], "note-a"),
+            debt(holders[2], holders[1], "note-b"),
+            debt(issuer, holders[1], "note-c"),
+        ];
+        let report = import_debts(&mut tracker, debts, &keys);
+
+        assert_eq!((report.debts_imported, report.amount_imported), (2, 200));
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].note_identifiers, vec!["note-b".to_string()]);
+        assert!(report.skipped[0].reason.starts_with("debt 1: signature does not verify"));
+        assert!(tracker.lookup_note(&holders[2], &holders[1]).is_err());
+        assert_eq!(tracker.lookup_note(&issuer, &holders[1]).unwrap().amount_collected, 100);
+    }
+}
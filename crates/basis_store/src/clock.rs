@@ -0,0 +1,66 @@
+//! Wall-clock abstraction
+//!
+//! Timestamp checks read the time through a [`Clock`] so tests can pin or
+//! advance it with a [`MockClock`] instead of sleeping.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Source of the current time
+pub trait Clock: Send + Sync + std::fmt::Debug {
+    /// Milliseconds since the Unix epoch
+    fn now_millis(&self) -> u64;
+}
+
+/// The system wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_millis(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64
+    }
+}
+
+/// A clock that only moves when told to
+///
+/// Clones share the same time, so a test can keep one handle and advance the
+/// clock a tracker was given.
+#[derive(Debug, Clone, Default)]
+pub struct MockClock {
+    millis: Arc<AtomicU64>,
+}
+
+impl MockClock {
+    /// Create a clock reading `millis` milliseconds since the Unix epoch
+    pub fn new(millis: u64) -> Self {
+        Self {
+            millis: Arc::new(AtomicU64::new(millis)),
+        }
+    }
+
+    /// Set the current time
+    pub fn set(&self, millis: u64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+
+    /// Move the clock forward
+    pub fn advance(&self, duration: Duration) {
+        self.millis.fetch_add(duration.as_millis() as u64, Ordering::SeqCst);
+    }
+}
+
+impl Clock for MockClock {
+    fn now_millis(&self) -> u64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
+
+/// The default clock: the system wall clock
+pub fn system_clock() -> Arc<dyn Clock> {
+    Arc::new(SystemClock)
+}
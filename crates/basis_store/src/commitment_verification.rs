@@ -0,0 +1,439 @@
+//! Cross-verification of on-chain tracker commitments against local state
+//!
+//! The tracker periodically commits the root digest of its AVL tree to R5 of
+//! the tracker box. Every root the local tracker state goes through is
+//! recorded together with the chain height at which it became current. Each
+//! tracker box is then checked against the roots in effect at its height: a
+//! commitment matching none of them means the tracker published a state that
+//! is inconsistent with the notes it served.
+//!
+//! Roots produced by a note change are recorded with the changed note, which
+//! is what lets the tracker serve the notes changed between two consecutive
+//! commitments as a [`CommitmentDelta`].
+
+use basis_trees::{CommitmentDelta, DeltaError, DeltaNote, COMMITMENT_DELTA_VERSION};
+use serde::{Deserialize, Serialize};
+
+use crate::persistence::TrackerStorage;
+use crate::{IouNote, NoteError, PubKey, SignatureSchemeId, TrackerBoxInfo};
+
+/// SAvlTree type identifier prefixing the serialized R5 value
+const SAVL_TREE_TYPE: u8 = 0x64;
+
+/// Maximum number of divergences reported by [`VerificationStatus`]
+pub const MAX_REPORTED_DIVERGENCES: usize = 50;
+
+/// Result of checking one tracker box against local state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CommitmentStatus {
+    /// The committed digest matches a local root in effect at its height
+    Consistent,
+    /// The committed digest matches no local root in effect at its height
+    Diverged,
+    /// No local roots were recorded at or before the commitment height
+    Unverifiable,
+}
+
+/// Outcome of checking one tracker box
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentCheck {
+    /// Tracker box holding the commitment
+    pub box_id: String,
+    /// Height the commitment refers to
+    pub height: u64,
+    /// Committed root digest (hex), or the raw R5 value if it could not be parsed
+    pub onchain_digest: String,
+    /// Local root digests in effect at `height` (hex)
+    pub local_digests: Vec<String>,
+    pub status: CommitmentStatus,
+    /// When the check was made (Unix seconds)
+    pub checked_at: u64,
+}
+
+/// Summary of every commitment check made so far
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerificationStatus {
+    pub boxes_checked: usize,
+    pub consistent: usize,
+    pub diverged: usize,
+    pub unverifiable: usize,
+    /// Check with the highest commitment height
+    pub latest_check: Option<CommitmentCheck>,
+    /// Most recent divergences, newest first
+    pub divergences: Vec<CommitmentCheck>,
+    /// Mutating endpoints are refused because the state diverged at startup
+    #[serde(default)]
+    pub degraded: bool,
+    /// Comparison of the local state with the chain made at startup
+    #[serde(default)]
+    pub cold_start: Option<ColdStartCheck>,
+}
+
+impl VerificationStatus {
+    /// Summarize checks ordered by commitment height
+    pub fn from_checks(checks: &[CommitmentCheck]) -> Self {
+        let count = |status: CommitmentStatus| checks.iter().filter(|check| check.status == status).count();
+        Self {
+            boxes_checked: checks.len(),
+            consistent: count(CommitmentStatus::Consistent),
+            diverged: count(CommitmentStatus::Diverged),
+            unverifiable: count(CommitmentStatus::Unverifiable),
+            latest_check: checks.last().cloned(),
+            divergences: checks
+                .iter()
+                .rev()
+                .filter(|check| check.status == CommitmentStatus::Diverged)
+                .take(MAX_REPORTED_DIVERGENCES)
+                .cloned()
+                .collect(),
+            degraded: false,
+            cold_start: None,
+        }
+    }
+}
+
+/// Extract the root digest from a serialized SAvlTree R5 value
+pub fn commitment_digest(state_commitment: &str) -> Option<[u8; 33]> {
+    let bytes = hex::decode(state_commitment).ok()?;
+    if bytes.first() != Some(&SAVL_TREE_TYPE) {
+        return None;
+    }
+    bytes.get(1..34)?.try_into().ok()
+}
+
+/// Check a tracker box against the local roots in effect at its height
+///
+/// A malformed commitment is reported as diverged.
+pub fn verify_commitment(
+    tracker_box: &TrackerBoxInfo,
+    local_roots: &[[u8; 33]],
+    checked_at: u64,
+) -> CommitmentCheck {
+    let onchain = commitment_digest(&tracker_box.state_commitment);
+    let status = match onchain {
+        _ if local_roots.is_empty() => CommitmentStatus::Unverifiable,
+        Some(digest) if local_roots.contains(&digest) => CommitmentStatus::Consistent,
+        _ => CommitmentStatus::Diverged,
+    };
+
+    CommitmentCheck {
+        box_id: tracker_box.box_id.clone(),
+        height: tracker_box.last_verified_height,
+        onchain_digest: onchain
+            .map(hex::encode)
+            .unwrap_or_else(|| tracker_box.state_commitment.clone()),
+        local_digests: local_roots.iter().map(hex::encode).collect(),
+        status,
+        checked_at,
+    }
+}
+
+/// How the local state rebuilt at startup relates to the latest commitment
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ColdStartStatus {
+    /// The local root is the committed root
+    InSync,
+    /// The local state moved on from the committed root before the restart
+    Ahead,
+    /// The local root is neither the committed root nor a known successor of it
+    Diverged,
+}
+
+/// Startup comparison of the local AVL root with the latest tracker box
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ColdStartCheck {
+    /// Latest tracker box
+    pub box_id: String,
+    /// Height the commitment refers to
+    pub height: u64,
+    /// Committed root digest (hex), or the raw R5 value if it could not be parsed
+    pub onchain_digest: String,
+    /// Root digest rebuilt from local storage (hex)
+    pub local_digest: String,
+    pub status: ColdStartStatus,
+}
+
+/// Compare the AVL root rebuilt at startup with the latest tracker box
+///
+/// `last_recorded` is the last root recorded before the restart and
+/// `recorded_roots` every root recorded so far. Local state is ahead of the
+/// chain only if it is the state the tracker stopped with and the committed
+/// root was recorded on the way there. Anything else, such as notes restored
+/// from an older backup, is a divergence.
+pub fn verify_cold_start(
+    latest_box: &TrackerBoxInfo,
+    local_root: &[u8; 33],
+    last_recorded: Option<&[u8; 33]>,
+    recorded_roots: &[[u8; 33]],
+) -> ColdStartCheck {
+    let onchain = commitment_digest(&latest_box.state_commitment);
+    let status = match onchain {
+        Some(digest) if digest == *local_root => ColdStartStatus::InSync,
+        Some(digest) if last_recorded == Some(local_root) && recorded_roots.contains(&digest) => {
+            ColdStartStatus::Ahead
+        }
+        _ => ColdStartStatus::Diverged,
+    };
+
+    ColdStartCheck {
+        box_id: latest_box.box_id.clone(),
+        height: latest_box.last_verified_height,
+        onchain_digest: onchain
+            .map(hex::encode)
+            .unwrap_or_else(|| latest_box.state_commitment.clone()),
+        local_digest: hex::encode(local_root),
+        status,
+    }
+}
+
+/// Why no delta can be served for a commitment height
+#[derive(Debug, thiserror::Error)]
+pub enum DeltaUnavailable {
+    #[error("No tracker commitment checked at height {0}")]
+    NoCommitment(u64),
+    #[error("Commitment at height {0} is not a state the local tracker went through")]
+    UnknownState(u64),
+    #[error("Storage error: {0}")]
+    StorageError(String),
+}
+
+impl From<NoteError> for DeltaUnavailable {
+    fn from(err: NoteError) -> Self {
+        match err {
+            NoteError::StorageError(msg) => DeltaUnavailable::StorageError(msg),
+            _ => DeltaUnavailable::StorageError(format!("{:?}", err)),
+        }
+    }
+}
+
+/// A note as listed in a commitment delta
+pub fn delta_note(issuer_pubkey: &PubKey, note: &IouNote) -> DeltaNote {
+    DeltaNote {
+        issuer_pubkey: hex::encode(issuer_pubkey),
+        recipient_pubkey: hex::encode(note.recipient_pubkey),
+        amount_collected: note.amount_collected,
+        amount_redeemed: note.amount_redeemed,
+        timestamp: note.timestamp,
+        issuer_signature: hex::encode(note.signature),
+        signing_version: note.signing_format.version(),
+        tracker_id: note.signing_format.tracker_id().map(hex::encode),
+    }
+}
+
+/// A note listed in a commitment delta, as the tracker that signed it stored it
+///
+/// Deltas carry neither recipient acknowledgments, interest terms nor memos,
+/// so all three are left empty.
+pub fn note_from_delta(note: &DeltaNote) -> Result<(PubKey, IouNote), DeltaError> {
+    let decode = |hex_str: &str, field: &'static str| {
+        hex::decode(hex_str).map_err(|_| DeltaError::Malformed(field))
+    };
+    let issuer_pubkey: PubKey = decode(&note.issuer_pubkey, "issuer_pubkey")?
+        .try_into()
+        .map_err(|_| DeltaError::Malformed("issuer_pubkey"))?;
+    let recipient_pubkey: PubKey = decode(&note.recipient_pubkey, "recipient_pubkey")?
+        .try_into()
+        .map_err(|_| DeltaError::Malformed("recipient_pubkey"))?;
+    let signature = decode(&note.issuer_signature, "issuer_signature")?
+        .try_into()
+        .map_err(|_| DeltaError::Malformed("issuer_signature"))?;
+
+    Ok((
+        issuer_pubkey,
+        IouNote {
+            recipient_pubkey,
+            amount_collected: note.amount_collected,
+            amount_redeemed: note.amount_redeemed,
+            timestamp: note.timestamp,
+            signature,
+            recipient_signature: None,
+            signing_format: note.signing_format()?,
+            interest: None,
+            memo: None,
+            signature_scheme: SignatureSchemeId::Schnorr,
+        },
+    ))
+}
+
+/// Unsigned delta leading to the commitment checked at `height`
+///
+/// The delta starts at the commitment checked before it. Both committed
+/// digests must be roots the local state went through, in that order. The
+/// tracker key and signature are left for the signer to fill in.
+pub fn commitment_delta(storage: &TrackerStorage, height: u64) -> Result<CommitmentDelta, DeltaUnavailable> {
+    let checks = storage.get_commitment_checks()?;
+    let position = checks
+        .iter()
+        .rposition(|check| check.height == height)
+        .ok_or(DeltaUnavailable::NoCommitment(height))?;
+    let digest_of = |check: &CommitmentCheck| {
+        hex::decode(&check.onchain_digest)
+            .ok()
+            .and_then(|bytes| <[u8; 33]>::try_from(bytes).ok())
+            .ok_or(DeltaUnavailable::UnknownState(check.height))
+    };
+
+    let digest = digest_of(&checks[position])?;
+    let previous = match position.checked_sub(1).map(|i| &checks[i]) {
+        Some(check) => Some((check.height, digest_of(check)?)),
+        None => None,
+    };
+    let notes = storage
+        .note_changes_between(previous.as_ref().map(|(_, digest)| digest), &digest, height)?
+        .ok_or(DeltaUnavailable::UnknownState(height))?;
+
+    Ok(CommitmentDelta {
+        version: COMMITMENT_DELTA_VERSION,
+        tracker_pubkey: String::new(),
+        height,
+        digest: hex::encode(digest),
+        previous_height: previous.map(|(height, _)| height),
+        previous_digest: previous.map(|(_, digest)| hex::encode(digest)),
+        notes,
+        tracker_signature: String::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::TrackerStorage;
+
+    fn tracker_box(box_id: &str, digest: &[u8; 33], height: u64) -> TrackerBoxInfo {
+        let mut r5 = vec![SAVL_TREE_TYPE];
+        r5.extend_from_slice(digest);
+        r5.push(0x01);
+        r5.extend_from_slice(&32u32.to_be_bytes());
+        r5.extend_from_slice(&0u32.to_be_bytes());
+        TrackerBoxInfo {
+            box_id: box_id.to_string(),
+            tracker_pubkey: "02".repeat(33),
+            state_commitment: hex::encode(r5),
+            last_verified_height: height,
+            value: 1_000_000,
+            creation_height: height,
+            tracker_nft_id: "00".repeat(32),
+            transaction_id: None,
+        }
+    }
+
+    #[test]
+    fn test_commitment_checked_against_roots_in_effect() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = TrackerStorage::open(temp_dir.path()).unwrap();
+
+        let (root_a, root_b, root_c) = ([1u8; 33], [2u8; 33], [3u8; 33]);
+        storage.record_local_root(100, &root_a).unwrap();
+        storage.record_local_root(105, &root_b).unwrap();
+        storage.record_local_root(105, &root_c).unwrap();
+
+        // Before any recorded root nothing can be verified
+        assert_eq!(storage.local_roots_at(99).unwrap(), Vec::<[u8; 33]>::new());
+        // The root carried into a height stays in effect until replaced
+        assert_eq!(storage.local_roots_at(104).unwrap(), vec![root_a]);
+        // Roots produced during a block are all in effect at that height
+        assert_eq!(storage.local_roots_at(105).unwrap(), vec![root_a, root_b, root_c]);
+        assert_eq!(storage.local_roots_at(200).unwrap(), vec![root_c]);
+        // Roots within a block keep their recording order
+        storage.record_local_root(105, &root_a).unwrap();
+        assert_eq!(storage.local_roots_at(106).unwrap(), vec![root_a]);
+        assert_eq!(storage.latest_local_root().unwrap(), Some((105, root_a)));
+        assert_eq!(storage.all_local_roots().unwrap(), vec![root_a, root_b, root_c]);
+
+        let check = |box_id: &str, digest: &[u8; 33], height: u64| {
+            let tracker_box = tracker_box(box_id, digest, height);
+            let check = verify_commitment(&tracker_box, &storage.local_roots_at(height).unwrap(), 0);
+            storage.store_commitment_check(&check).unwrap();
+            check.status
+        };
+        assert_eq!(check("box1", &root_a, 103), CommitmentStatus::Consistent);
+        assert_eq!(check("box2", &root_b, 105), CommitmentStatus::Consistent);
+        assert_eq!(check("box3", &root_b, 150), CommitmentStatus::Diverged);
+        assert_eq!(check("box0", &root_a, 50), CommitmentStatus::Unverifiable);
+
+        let status = VerificationStatus::from_checks(&storage.get_commitment_checks().unwrap());
+        assert_eq!(status.boxes_checked, 4);
+        assert_eq!((status.consistent, status.diverged, status.unverifiable), (2, 1, 1));
+        assert_eq!(status.latest_check.unwrap().box_id, "box3");
+        assert_eq!(status.divergences.len(), 1);
+        assert_eq!(status.divergences[0].local_digests, vec![hex::encode(root_a)]);
+        assert!(storage.get_commitment_check("box2").unwrap().is_some());
+    }
+
+    #[test]
+    fn test_commitment_delta_lists_notes_changed_since_previous_commitment() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = TrackerStorage::open(temp_dir.path()).unwrap();
+        let note = |issuer: &str, amount_collected: u64| DeltaNote {
+            issuer_pubkey: issuer.repeat(33),
+            recipient_pubkey: "03".repeat(33),
+            amount_collected,
+            amount_redeemed: 0,
+            timestamp: amount_collected,
+            issuer_signature: "00".repeat(65),
+            signing_version: 1,
+            tracker_id: None,
+        };
+
+        storage.record_local_root(100, &[0u8; 33]).unwrap();
+        storage.record_note_change(101, &[1u8; 33], &note("bb", 10)).unwrap();
+        storage.record_note_change(101, &[2u8; 33], &note("aa", 20)).unwrap();
+        storage.record_note_change(102, &[3u8; 33], &note("bb", 30)).unwrap();
+        storage.record_note_change(103, &[4u8; 33], &note("bb", 40)).unwrap();
+        let commitments = [("box1", [2u8; 33], 101), ("box2", [3u8; 33], 110), ("box3", [9u8; 33], 120)];
+        for (box_id, digest, height) in commitments {
+            let local_roots = storage.local_roots_at(height).unwrap();
+            let check = verify_commitment(&tracker_box(box_id, &digest, height), &local_roots, 0);
+            storage.store_commitment_check(&check).unwrap();
+        }
+
+        // The first commitment carries every note it covers, in the order
+        // they were first changed
+        let first = commitment_delta(&storage, 101).unwrap();
+        assert_eq!((first.previous_height, first.previous_digest), (None, None));
+        assert_eq!(first.digest, hex::encode([2u8; 33]));
+        assert_eq!(first.notes, vec![note("bb", 10), note("aa", 20)]);
+
+        // Later ones only what changed since, and nothing after the commitment
+        let second = commitment_delta(&storage, 110).unwrap();
+        assert_eq!(second.previous_height, Some(101));
+        assert_eq!(second.previous_digest, Some(hex::encode([2u8; 33])));
+        assert_eq!(second.notes, vec![note("bb", 30)]);
+        // Replicas read the notes back as they were stored
+        let (issuer, replicated) = note_from_delta(&second.notes[0]).unwrap();
+        assert_eq!(delta_note(&issuer, &replicated), second.notes[0]);
+
+        assert!(matches!(commitment_delta(&storage, 105), Err(DeltaUnavailable::NoCommitment(105))));
+        // A diverged commitment is no state the notes can be replayed to
+        assert!(matches!(commitment_delta(&storage, 120), Err(DeltaUnavailable::UnknownState(120))));
+    }
+
+    #[test]
+    fn test_malformed_commitment_is_diverged() {
+        let mut tracker_box = tracker_box("box", &[1u8; 33], 10);
+        tracker_box.state_commitment = "64aabb".to_string();
+        let check = verify_commitment(&tracker_box, &[[1u8; 33]], 0);
+        assert_eq!(check.status, CommitmentStatus::Diverged);
+        assert_eq!(check.onchain_digest, "64aabb");
+    }
+
+    #[test]
+    fn test_cold_start_check() {
+        let (committed, later, restored) = ([1u8; 33], [2u8; 33], [3u8; 33]);
+        let latest_box = tracker_box("box", &committed, 10);
+        let recorded = [restored, committed, later];
+        let status = |local: &[u8; 33], last: Option<&[u8; 33]>, recorded: &[[u8; 33]]| {
+            verify_cold_start(&latest_box, local, last, recorded).status
+        };
+
+        assert_eq!(status(&committed, None, &[]), ColdStartStatus::InSync);
+        // Notes added after the last commitment
+        assert_eq!(status(&later, Some(&later), &recorded), ColdStartStatus::Ahead);
+        // Notes restored from a backup taken before the commitment
+        assert_eq!(status(&restored, Some(&later), &recorded), ColdStartStatus::Diverged);
+        // Without recorded roots a different root cannot be a successor
+        assert_eq!(status(&later, None, &[]), ColdStartStatus::Diverged);
+    }
+}
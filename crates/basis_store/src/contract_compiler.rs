@@ -1,7 +1,10 @@
 //! Contract compilation utilities for Basis tracker
 
+use ergo_lib::ergotree_ir::address::AddressEncoder;
 use thiserror::Error;
 
+use crate::network::Network;
+
 #[derive(Error, Debug)]
 pub enum CompilerError {
     #[error("File not found: {0}")]
@@ -18,6 +21,16 @@ pub fn get_basis_reserve_contract_p2s() -> Result<String, CompilerError> {
     Ok("RtQxdWJ9axeb5Ltahqosnhj45BE26xuDK4YWddVj5p59t9RjKPEkkHCYEiyxwRFMJcEHwVd9syFod8ReQo1Zaz9eNTZ5JwDEN5hkLd67sVr2sNQ6R46TSfausAc9D3q7et1apYaXnqV9PkpHPMCA1zMCEsmmADj62XRGq4Cw2VwpuKKCAdreTgmLzdFWHGVGQMsPDFFBkRibsPFMzXkytdy2mPs2zCtm15uyDpd3jDLBy95BtUFXU2DdaYa1xMZE9UXju4R4MhWH8vqWda5BgpRTa1RpQxpS5b96FG46r1v3ZWCLYcVo51J1ekY8cqqVFNNykpQScRRYqFjCLMjG26dYEwZyn21wGeLJ7RzcTwCpvGDBa2w1P3ycAEJAv9XDPEtJrSQpkvBaD1HaZ6X2JuXmFjPF5MChmVLk4CTXtRQVRis7vP95ByTTmbHbtVdao32kbN3xhCWgJZZdaKkNyKH4vFQn5jyoEmiV7FjQDegWnnaFXu5FW6stx9cbhsxWz5FfGpW1BCMRNNJTCRF6FtYoehrMT74LDRNxHQ38EmMn6mBEpSrhkzDj2jysdFJvDUf8UQjLZQLmUQtgNotfxeAPxiavsT5mLUja3hdWvZPv71FcHxvP53WJHAcn9JPek3vepbH9gxRdmBMW".to_string())
 }
 
+/// Get the Basis reserve contract P2S address on `network`
+///
+/// The contract is the same everywhere; only the address prefix differs.
+pub fn get_basis_reserve_contract_p2s_for(network: Network) -> Result<String, CompilerError> {
+    let mainnet = get_basis_reserve_contract_p2s()?;
+    let address = AddressEncoder::unchecked_parse_address_from_str(&mainnet)
+        .map_err(|e| CompilerError::CompilationFailed(format!("Invalid reserve contract address: {}", e)))?;
+    Ok(network.encode_address(&address))
+}
+
 /// Get the Basis reserve contract ErgoTree hex (for reserve output in redemption transactions)
 pub fn get_basis_reserve_ergo_tree_hex() -> Result<String, CompilerError> {
     // This is the raw ErgoTree hex for the P2S contract
@@ -28,7 +41,6 @@ pub fn get_basis_reserve_ergo_tree_hex() -> Result<String, CompilerError> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use ergo_lib::ergotree_ir::address::AddressEncoder;
     use ergo_lib::ergotree_ir::address::NetworkPrefix;
     use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
 
@@ -41,6 +53,42 @@ mod tests {
         assert!(p2s.len() > 50);
     }
 
+    #[test]
+    fn test_contract_address_per_network() {
+        let mainnet = get_basis_reserve_contract_p2s_for(Network::Mainnet).unwrap();
+        assert_eq!(mainnet, get_basis_reserve_contract_p2s().unwrap());
+
+        let testnet = get_basis_reserve_contract_p2s_for(Network::Testnet).unwrap();
+        assert_eq!(Network::of_address(&testnet), Ok(Network::Testnet));
+        let tree = |address: &str| AddressEncoder::unchecked_parse_address_from_str(address).unwrap().script().unwrap();
+        assert_eq!(tree(&testnet), tree(&mainnet));
+    }
+
+    #[test]
+    fn test_compiled_contract_reads_redemption_var_layout() {
+        // ergo-lib 0.13 cannot parse the contract, so its GetVar nodes (opcode 0xe3,
+        // then the variable ID and its type) are found in the raw tree bytes
+        let tree = hex::decode(get_basis_reserve_ergo_tree_hex().unwrap()).unwrap();
+        let read_vars: Vec<(u8, Vec<u8>)> = tree
+            .windows(3)
+            .filter(|node| node[0] == 0xe3)
+            .map(|node| (node[1], node[2..].to_vec()))
+            .collect();
+
+        let layout: Vec<(u8, Vec<u8>)> = crate::transaction_builder::redemption_var_layout()
+            .into_iter()
+            .map(|(id, tpe)| (id, tpe.sigma_serialize_bytes()))
+            .collect();
+        for var in &read_vars {
+            assert!(layout.contains(var), "contract reads context variable {:?} outside the layout", var);
+        }
+        // The deployed tree predates the timestamp (#4), which it does not read
+        let read_ids: Vec<u8> = read_vars.iter().map(|(id, _)| *id).collect();
+        for (id, _) in &layout {
+            assert_eq!(read_ids.contains(id), *id != 4, "context variable #{}", id);
+        }
+    }
+
     #[test]
     fn test_sigma_serialized_bytes_matches_expected() {
         // Test that the sigma_serialized_bytes for the address "AtC4..." returns the expected bytes
@@ -0,0 +1,199 @@
+//! Versioned reserve contract templates
+//!
+//! A contract upgrade does not move existing reserves: they stay locked by
+//! the template they were created under until their owner spends them. The
+//! registry keeps every template still in use by version, so the scanner
+//! tracks reserves of all of them and transactions spending a reserve keep it
+//! on its own template. New reserves are created under the latest version.
+
+use std::collections::BTreeMap;
+
+use ergo_lib::ergotree_ir::address::{Address, AddressEncoder};
+use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use thiserror::Error;
+
+use crate::contract_compiler::get_basis_reserve_ergo_tree_hex;
+use crate::network::Network;
+
+/// Version of the reserve contract compiled into this release
+pub const BASIS_RESERVE_CONTRACT_VERSION: u32 = 1;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum ContractRegistryError {
+    #[error("Invalid reserve contract: {0}")]
+    InvalidContract(String),
+    #[error("Reserve contract version {0} is already registered with another template")]
+    VersionInUse(u32),
+    #[error("Reserve contract template is already registered as version {0}")]
+    TemplateInUse(u32),
+}
+
+/// One version of the reserve contract
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReserveContract {
+    pub version: u32,
+    /// Serialized ErgoTree as lowercase hex, as found in box `ergoTree` fields
+    pub ergo_tree_hex: String,
+}
+
+impl ReserveContract {
+    /// Contract from its serialized ErgoTree, as hex
+    pub fn from_ergo_tree_hex(version: u32, ergo_tree_hex: &str) -> Result<Self, ContractRegistryError> {
+        let bytes = hex::decode(ergo_tree_hex)
+            .map_err(|e| ContractRegistryError::InvalidContract(format!("ErgoTree is not hex: {}", e)))?;
+        if bytes.is_empty() {
+            return Err(ContractRegistryError::InvalidContract("ErgoTree is empty".to_string()));
+        }
+        Ok(Self {
+            version,
+            ergo_tree_hex: hex::encode(bytes),
+        })
+    }
+
+    /// Contract from its P2S address; the address network is not checked
+    pub fn from_p2s(version: u32, p2s: &str) -> Result<Self, ContractRegistryError> {
+        let tree: ErgoTree = AddressEncoder::unchecked_parse_address_from_str(p2s)
+            .map_err(|e| ContractRegistryError::InvalidContract(format!("Invalid P2S address {}: {}", p2s, e)))?
+            .script()
+            .map_err(|e| ContractRegistryError::InvalidContract(format!("Invalid contract script: {}", e)))?;
+        Ok(Self {
+            version,
+            ergo_tree_hex: hex::encode(tree.sigma_serialize_bytes()),
+        })
+    }
+
+    /// P2S address of the contract on `network`
+    pub fn p2s(&self, network: Network) -> String {
+        let bytes = hex::decode(&self.ergo_tree_hex).unwrap_or_default();
+        network.encode_address(&Address::P2S(bytes))
+    }
+
+    /// Whether a box `ergoTree` is this contract
+    pub fn locks(&self, ergo_tree_hex: &str) -> bool {
+        self.ergo_tree_hex.eq_ignore_ascii_case(ergo_tree_hex)
+    }
+}
+
+/// Reserve contract templates by version
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ContractRegistry {
+    contracts: BTreeMap<u32, ReserveContract>,
+}
+
+impl ContractRegistry {
+    /// Empty registry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry holding the contract compiled into this release
+    pub fn builtin() -> Self {
+        let mut registry = Self::new();
+        let tree = get_basis_reserve_ergo_tree_hex().expect("compiled reserve contract");
+        registry
+            .register(
+                ReserveContract::from_ergo_tree_hex(BASIS_RESERVE_CONTRACT_VERSION, &tree)
+                    .expect("compiled reserve contract is hex"),
+            )
+            .expect("empty registry");
+        registry
+    }
+
+    /// Add a contract version
+    ///
+    /// Registering the same template under the same version again is a no-op;
+    /// a version or template may not be reused otherwise.
+    pub fn register(&mut self, contract: ReserveContract) -> Result<(), ContractRegistryError> {
+        if let Some(existing) = self.contract_for_tree(&contract.ergo_tree_hex) {
+            return if existing.version == contract.version {
+                Ok(())
+            } else {
+                Err(ContractRegistryError::TemplateInUse(existing.version))
+            };
+        }
+        if self.contracts.contains_key(&contract.version) {
+            return Err(ContractRegistryError::VersionInUse(contract.version));
+        }
+        self.contracts.insert(contract.version, contract);
+        Ok(())
+    }
+
+    /// Contract of a version
+    pub fn get(&self, version: u32) -> Option<&ReserveContract> {
+        self.contracts.get(&version)
+    }
+
+    /// Highest registered version, under which new reserves are created
+    pub fn latest(&self) -> Option<&ReserveContract> {
+        self.contracts.values().next_back()
+    }
+
+    /// Contract locking a box with `ergo_tree_hex`, if it is a registered version
+    pub fn contract_for_tree(&self, ergo_tree_hex: &str) -> Option<&ReserveContract> {
+        self.contracts.values().find(|contract| contract.locks(ergo_tree_hex))
+    }
+
+    /// Registered contracts, oldest version first
+    pub fn contracts(&self) -> impl Iterator<Item = &ReserveContract> {
+        self.contracts.values()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.contracts.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.contracts.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract_compiler::get_basis_reserve_contract_p2s;
+
+    #[test]
+    fn test_builtin_contract() {
+        let registry = ContractRegistry::builtin();
+        let contract = registry.latest().unwrap();
+        assert_eq!(contract.version, BASIS_RESERVE_CONTRACT_VERSION);
+
+        let from_address =
+            ReserveContract::from_p2s(BASIS_RESERVE_CONTRACT_VERSION, &get_basis_reserve_contract_p2s().unwrap()).unwrap();
+        assert_eq!(contract, &from_address);
+        assert_eq!(contract.p2s(Network::Mainnet), get_basis_reserve_contract_p2s().unwrap());
+        assert_eq!(
+            registry.contract_for_tree(&contract.ergo_tree_hex.to_uppercase()).map(|c| c.version),
+            Some(BASIS_RESERVE_CONTRACT_VERSION)
+        );
+    }
+
+    #[test]
+    fn test_register_versions() {
+        let mut registry = ContractRegistry::builtin();
+        let v2 = ReserveContract::from_ergo_tree_hex(2, "100204a00b08cd02").unwrap();
+        registry.register(v2.clone()).unwrap();
+        // Registering the same version again changes nothing
+        registry.register(v2.clone()).unwrap();
+
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.latest(), Some(&v2));
+        assert_eq!(registry.get(1).map(|c| c.version), Some(1));
+        assert_eq!(
+            registry.contracts().map(|c| c.version).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        assert_eq!(
+            registry.register(ReserveContract::from_ergo_tree_hex(3, &v2.ergo_tree_hex).unwrap()),
+            Err(ContractRegistryError::TemplateInUse(2))
+        );
+        assert_eq!(
+            registry.register(ReserveContract::from_ergo_tree_hex(2, "100204a00b08cd03").unwrap()),
+            Err(ContractRegistryError::VersionInUse(2))
+        );
+        assert!(registry.contract_for_tree("100204a00b08cd03").is_none());
+        assert!(ReserveContract::from_ergo_tree_hex(4, "not hex").is_err());
+    }
+}
@@ -0,0 +1,166 @@
+//! Pro-rata reserve coverage of the notes held by a recipient
+//!
+//! Every recipient of an issuer has a claim on the same reserves, so the
+//! issuer's collateralization ratio overstates what any one recipient can
+//! expect to redeem. If the issuer defaulted and the collateral were shared
+//! in proportion to outstanding debt, a recipient owed `d` out of the issuer's
+//! total outstanding debt `D` would recover `d * min(C, D) / D` of collateral
+//! `C`. This module computes that amount for each issuer of a recipient.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::{IouNote, PubKey};
+
+/// Coverage of the debt one issuer owes a recipient
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IssuerCoverage {
+    pub issuer_pubkey: String,
+    /// Debt the issuer owes the recipient
+    pub outstanding: u64,
+    /// Debt the issuer owes all recipients
+    pub issuer_outstanding: u64,
+    /// Collateral across the issuer's tracked reserves
+    pub issuer_collateral: u64,
+    /// Part of `outstanding` backed by the recipient's pro-rata share of collateral
+    pub covered: u64,
+    /// `covered / outstanding`
+    pub coverage_ratio: f64,
+}
+
+/// Coverage of every note a recipient holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecipientCoverage {
+    pub recipient_pubkey: String,
+    pub total_outstanding: u64,
+    pub total_covered: u64,
+    /// `total_covered / total_outstanding`, 1.0 when nothing is owed
+    pub coverage_ratio: f64,
+    /// Issuers owing the recipient, least covered first
+    pub issuers: Vec<IssuerCoverage>,
+}
+
+fn ratio(covered: u64, outstanding: u64) -> f64 {
+    if outstanding == 0 {
+        1.0
+    } else {
+        covered as f64 / outstanding as f64
+    }
+}
+
+/// Pro-rata coverage of the notes `recipient` holds
+///
+/// `notes` must hold every note of the issuers involved, as competing claims
+/// of other recipients reduce the share. `collateral` gives the collateral
+/// of an issuer's reserves.
+pub fn recipient_coverage(
+    recipient: &PubKey,
+    notes: &[(PubKey, IouNote)],
+    collateral: impl Fn(&PubKey) -> u64,
+) -> RecipientCoverage {
+    let owed_to_recipient: BTreeMap<PubKey, u64> = notes
+        .iter()
+        .filter(|(_, note)| note.recipient_pubkey == *recipient && note.outstanding_debt() > 0)
+        .map(|(issuer, note)| (*issuer, note.outstanding_debt()))
+        .collect();
+
+    let mut issuers: Vec<IssuerCoverage> = owed_to_recipient
+        .into_iter()
+        .map(|(issuer, outstanding)| {
+            let issuer_outstanding = notes
+                .iter()
+                .filter(|(note_issuer, _)| *note_issuer == issuer)
+                .fold(0u64, |acc, (_, note)| acc.saturating_add(note.outstanding_debt()));
+            let issuer_collateral = collateral(&issuer);
+            let covered = (outstanding as u128 * issuer_collateral.min(issuer_outstanding) as u128
+                / issuer_outstanding as u128) as u64;
+            IssuerCoverage {
+                issuer_pubkey: hex::encode(issuer),
+                outstanding,
+                issuer_outstanding,
+                issuer_collateral,
+                covered,
+                coverage_ratio: ratio(covered, outstanding),
+            }
+        })
+        .collect();
+    issuers.sort_by(|a, b| a.coverage_ratio.total_cmp(&b.coverage_ratio));
+
+    let total_outstanding = issuers.iter().fold(0u64, |acc, issuer| acc.saturating_add(issuer.outstanding));
+    let total_covered = issuers.iter().fold(0u64, |acc, issuer| acc.saturating_add(issuer.covered));
+    RecipientCoverage {
+        recipient_pubkey: hex::encode(recipient),
+        total_outstanding,
+        total_covered,
+        coverage_ratio: ratio(total_covered, total_outstanding),
+        issuers,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SignatureSchemeId, SigningFormat};
+
+    fn pubkey(n: u8) -> PubKey {
+        let mut key = [n; 33];
+        key[0] = 0x02;
+        key
+    }
+
+    fn note(issuer: u8, recipient: u8, collected: u64, redeemed: u64) -> (PubKey, IouNote) {
+        (
+            pubkey(issuer),
+            IouNote {
+                recipient_pubkey: pubkey(recipient),
+                amount_collected: collected,
+                amount_redeemed: redeemed,
+                timestamp: 1,
+                signature: [0u8; 65],
+                recipient_signature: None,
+                signing_format: SigningFormat::V1,
+                interest: None,
+                memo: None,
+                signature_scheme: SignatureSchemeId::Schnorr,
+            },
+        )
+    }
+
+    #[test]
+    fn test_recipients_share_issuer_collateral() {
+        let notes = vec![
+            // Issuer 1 owes 600 to recipient 9 and 400 to recipient 8 against 500 collateral
+            note(1, 9, 700, 100),
+            note(1, 8, 400, 0),
+            // Issuer 2 is fully collateralized
+            note(2, 9, 300, 0),
+            // Settled notes are not claims
+            note(3, 9, 50, 50),
+        ];
+        let collateral = |issuer: &PubKey| match issuer[1] {
+            1 => 500,
+            2 => 1_000,
+            _ => 0,
+        };
+
+        let coverage = recipient_coverage(&pubkey(9), &notes, collateral);
+        assert_eq!(coverage.total_outstanding, 900);
+        assert_eq!(coverage.total_covered, 600);
+        assert_eq!(coverage.issuers.len(), 2);
+
+        // Recipient 9 holds 60% of issuer 1's debt, so 60% of its collateral
+        let issuer_1 = &coverage.issuers[0];
+        assert_eq!(issuer_1.issuer_pubkey, hex::encode(pubkey(1)));
+        assert_eq!((issuer_1.outstanding, issuer_1.issuer_outstanding), (600, 1_000));
+        assert_eq!(issuer_1.covered, 300);
+        assert_eq!(issuer_1.coverage_ratio, 0.5);
+
+        // Surplus collateral covers no more than the debt
+        assert_eq!(coverage.issuers[1].covered, 300);
+        assert_eq!(coverage.issuers[1].coverage_ratio, 1.0);
+
+        let nothing_owed = recipient_coverage(&pubkey(7), &notes, collateral);
+        assert!(nothing_owed.issuers.is_empty());
+        assert_eq!(nothing_owed.coverage_ratio, 1.0);
+    }
+}
@@ -188,6 +188,7 @@ mod tests {
         // Test processing event for non-existent reserve
         let topup_event = ReserveEvent::ReserveToppedUp {
             box_id: "non_existent_box".to_string(),
+            owner_pubkey: "issuer_1".to_string(),
             additional_collateral: 500000000,
             height: 1000,
         };
@@ -0,0 +1,269 @@
+//! Per-issuer debt ceiling policy
+//!
+//! Limits how much outstanding debt an issuer may accumulate relative to the
+//! collateral held in their on-chain reserves. The ceiling is expressed as a
+//! multiple of collateral (1.0 = debt may not exceed 100% of collateral) with
+//! optional overrides for individual issuer public keys.
+
+use crate::{NoteError, PubKey, ReserveTracker};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Default ceiling: outstanding debt may not exceed 100% of collateral
+pub const DEFAULT_MAX_DEBT_RATIO: f64 = 1.0;
+
+fn default_max_debt_ratio() -> f64 {
+    DEFAULT_MAX_DEBT_RATIO
+}
+
+/// Serializable debt ceiling configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DebtPolicyConfig {
+    /// Whether the debt ceiling is enforced on note creation
+    #[serde(default)]
+    pub enabled: bool,
+    /// Maximum outstanding debt as a multiple of tracked collateral
+    #[serde(default = "default_max_debt_ratio")]
+    pub max_debt_ratio: f64,
+    /// Per-issuer overrides of the default ratio
+    #[serde(default)]
+    pub overrides: Vec<DebtCeilingOverride>,
+}
+
+impl Default for DebtPolicyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_debt_ratio: DEFAULT_MAX_DEBT_RATIO,
+            overrides: Vec::new(),
+        }
+    }
+}
+
+/// Debt ceiling override for a single issuer
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DebtCeilingOverride {
+    /// Hex-encoded 33-byte compressed issuer public key
+    pub pubkey: String,
+    /// Maximum outstanding debt as a multiple of tracked collateral
+    pub max_debt_ratio: f64,
+}
+
+/// Debt ceiling policy evaluated against issuer collateral
+#[derive(Debug, Clone)]
+pub struct DebtCeilingPolicy {
+    max_debt_ratio: f64,
+    overrides: HashMap<PubKey, f64>,
+}
+
+impl Default for DebtCeilingPolicy {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_DEBT_RATIO)
+    }
+}
+
+impl DebtCeilingPolicy {
+    /// Create a policy with the given default ratio and no overrides
+    pub fn new(max_debt_ratio: f64) -> Self {
+        Self {
+            max_debt_ratio,
+            overrides: HashMap::new(),
+        }
+    }
+
+    /// Build a policy from configuration, validating override public keys
+    pub fn from_config(config: &DebtPolicyConfig) -> Result<Self, String> {
+        if !config.max_debt_ratio.is_finite() || config.max_debt_ratio < 0.0 {
+            return Err(format!("Invalid max_debt_ratio: {}", config.max_debt_ratio));
+        }
+
+        let mut policy = Self::new(config.max_debt_ratio);
+        for entry in &config.overrides {
+            let bytes = hex::decode(&entry.pubkey)
+                .map_err(|e| format!("Invalid override pubkey hex '{}': {}", entry.pubkey, e))?;
+            let pubkey: PubKey = bytes.try_into().map_err(|_| {
+                format!("Override pubkey '{}' must be 33 bytes", entry.pubkey)
+            })?;
+            if !entry.max_debt_ratio.is_finite() || entry.max_debt_ratio < 0.0 {
+                return Err(format!(
+                    "Invalid max_debt_ratio {} for pubkey '{}'",
+                    entry.max_debt_ratio, entry.pubkey
+                ));
+            }
+            policy.set_override(pubkey, entry.max_debt_ratio);
+        }
+        Ok(policy)
+    }
+
+    /// Set a ratio override for a specific issuer
+    pub fn set_override(&mut self, issuer_pubkey: PubKey, max_debt_ratio: f64) {
+        self.overrides.insert(issuer_pubkey, max_debt_ratio);
+    }
+
+    /// Remove the ratio override for a specific issuer
+    pub fn remove_override(&mut self, issuer_pubkey: &PubKey) {
+        self.overrides.remove(issuer_pubkey);
+    }
+
+    /// Get the effective ratio for an issuer
+    pub fn max_debt_ratio(&self, issuer_pubkey: &PubKey) -> f64 {
+        self.overrides
+            .get(issuer_pubkey)
+            .copied()
+            .unwrap_or(self.max_debt_ratio)
+    }
+
+    /// Get the maximum outstanding debt allowed for an issuer with the given collateral
+    pub fn debt_ceiling(&self, issuer_pubkey: &PubKey, collateral: u64) -> u64 {
+        let ceiling = collateral as f64 * self.max_debt_ratio(issuer_pubkey);
+        if ceiling >= u64::MAX as f64 {
+            u64::MAX
+        } else {
+            ceiling as u64
+        }
+    }
+
+    /// Check that the issuer's outstanding debt stays within the ceiling
+    pub fn check(
+        &self,
+        issuer_pubkey: &PubKey,
+        outstanding_debt: u64,
+        collateral: u64,
+    ) -> Result<(), NoteError> {
+        let ceiling = self.debt_ceiling(issuer_pubkey, collateral);
+        if outstanding_debt > ceiling {
+            tracing::warn!(
+                "Debt ceiling exceeded for issuer {}: outstanding {} > ceiling {} (collateral {})",
+                hex::encode(issuer_pubkey),
+                outstanding_debt,
+                ceiling,
+                collateral
+            );
+            return Err(NoteError::InsufficientCollateral);
+        }
+        Ok(())
+    }
+}
+
+/// Total collateral across all tracked reserves owned by an issuer
+pub fn issuer_collateral(reserve_tracker: &ReserveTracker, issuer_pubkey: &PubKey) -> u64 {
+    let owner = hex::encode(issuer_pubkey);
+    reserve_tracker
+        .get_all_reserves()
+        .iter()
+        .filter(|reserve| reserve.owner_pubkey == owner)
+        .fold(0u64, |acc, reserve| {
+            acc.saturating_add(reserve.base_info.collateral_amount)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pubkey(n: u8) -> PubKey {
+        let mut key = [n; 33];
+        key[0] = 0x02;
+        key
+    }
+
+    #[test]
+    fn test_default_ceiling_is_full_collateral() {
+        let policy = DebtCeilingPolicy::default();
+        assert_eq!(policy.debt_ceiling(&pubkey(1), 1_000), 1_000);
+        assert!(policy.check(&pubkey(1), 1_000, 1_000).is_ok());
+        assert!(matches!(
+            policy.check(&pubkey(1), 1_001, 1_000),
+            Err(NoteError::InsufficientCollateral)
+        ));
+    }
+
+    #[test]
+    fn test_override_takes_precedence() {
+        let mut policy = DebtCeilingPolicy::new(1.0);
+        policy.set_override(pubkey(2), 2.5);
+
+        assert_eq!(policy.debt_ceiling(&pubkey(1), 1_000), 1_000);
+        assert_eq!(policy.debt_ceiling(&pubkey(2), 1_000), 2_500);
+
+        policy.remove_override(&pubkey(2));
+        assert_eq!(policy.debt_ceiling(&pubkey(2), 1_000), 1_000);
+    }
+
+    #[test]
+    fn test_no_collateral_rejects_any_debt() {
+        let policy = DebtCeilingPolicy::default();
+        assert!(policy.check(&pubkey(1), 0, 0).is_ok());
+        assert!(policy.check(&pubkey(1), 1, 0).is_err());
+    }
+
+    #[test]
+    fn test_from_config() {
+        let config = DebtPolicyConfig {
+            enabled: true,
+            max_debt_ratio: 0.5,
+            overrides: vec![DebtCeilingOverride {
+                pubkey: hex::encode(pubkey(3)),
+                max_debt_ratio: 3.0,
+            }],
+        };
+        let policy = DebtCeilingPolicy::from_config(&config).unwrap();
+        assert_eq!(policy.max_debt_ratio(&pubkey(1)), 0.5);
+        assert_eq!(policy.max_debt_ratio(&pubkey(3)), 3.0);
+    }
+
+    #[test]
+    fn test_from_config_rejects_invalid_values() {
+        let bad_key = DebtPolicyConfig {
+            overrides: vec![DebtCeilingOverride {
+                pubkey: "abcd".to_string(),
+                max_debt_ratio: 1.0,
+            }],
+            ..Default::default()
+        };
+        assert!(DebtCeilingPolicy::from_config(&bad_key).is_err());
+
+        let bad_ratio = DebtPolicyConfig {
+            max_debt_ratio: -1.0,
+            ..Default::default()
+        };
+        assert!(DebtCeilingPolicy::from_config(&bad_ratio).is_err());
+    }
+
+    #[test]
+    fn test_add_note_enforces_ceiling() {
+        use crate::{schnorr::generate_keypair, ExtendedReserveInfo, IouNote, TrackerStateManager};
+
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_a) = generate_keypair();
+        let (_, recipient_b) = generate_keypair();
+
+        let reserve_tracker = ReserveTracker::new();
+        reserve_tracker
+            .update_reserve(ExtendedReserveInfo::new(&[1u8; 32], &issuer_pubkey, 1_000, None, 100))
+            .unwrap();
+
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        tracker.set_debt_policy(DebtCeilingPolicy::default(), reserve_tracker);
+
+        let note_a = IouNote::create_and_sign(recipient_a, 600, 1_000, &issuer_secret).unwrap();
+        tracker.add_note(&issuer_pubkey, &note_a).unwrap();
+
+        // Debt towards recipient A counts towards the ceiling for recipient B
+        let note_b = IouNote::create_and_sign(recipient_b, 500, 1_001, &issuer_secret).unwrap();
+        assert!(matches!(
+            tracker.add_note(&issuer_pubkey, &note_b),
+            Err(NoteError::InsufficientCollateral)
+        ));
+
+        let note_b = IouNote::create_and_sign(recipient_b, 400, 1_002, &issuer_secret).unwrap();
+        tracker.add_note(&issuer_pubkey, &note_b).unwrap();
+
+        // An updated note replaces the previous one for the same recipient
+        let note_a = IouNote::create_and_sign(recipient_a, 601, 1_003, &issuer_secret).unwrap();
+        assert!(tracker.add_note(&issuer_pubkey, &note_a).is_err());
+
+        tracker.clear_debt_policy();
+        tracker.add_note(&issuer_pubkey, &note_a).unwrap();
+    }
+}
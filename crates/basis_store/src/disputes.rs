@@ -0,0 +1,414 @@
+//! Disputes filed against notes
+//!
+//! The issuer or the recipient of a note can file a dispute against it, signed
+//! with their own key, e.g. after the issuer signed versions of the note that
+//! contradict each other. The versions of the note the filer holds are
+//! attached as evidence and classified against the recorded note history when
+//! the dispute is filed. While a dispute is open the note cannot be redeemed;
+//! the tracker operator closes it as upheld or dismissed after reviewing it.
+//!
+//! The contract has no notion of disputes: freezing only stops the tracker
+//! from building or signing redemptions of the note.
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    blake2b256_hash,
+    note_history::{self, DisputeOutcome},
+    schnorr, IouNote, NoteError, PubKey, Signature, SigningFormat,
+};
+
+/// Domain separator of dispute signatures, so that a dispute signature can
+/// never be mistaken for a note signature
+const DISPUTE_SIGNING_TAG: &[u8] = b"BASIS_DISPUTE_V1";
+
+#[derive(Error, Debug)]
+pub enum DisputeError {
+    #[error("Note not found")]
+    NoteNotFound,
+    #[error("Invalid dispute signature")]
+    InvalidSignature,
+    #[error("Invalid evidence: {0}")]
+    InvalidEvidence(String),
+    #[error("Dispute filing time is in the future")]
+    FutureTimestamp,
+    #[error("Note already has open dispute {0}")]
+    AlreadyOpen(u64),
+    #[error("Dispute {0} not found")]
+    NotFound(u64),
+    #[error("Dispute {0} is already closed")]
+    AlreadyClosed(u64),
+    #[error("A dispute can only be closed as upheld or dismissed")]
+    InvalidResolution,
+    #[error("Storage error: {0}")]
+    StorageError(String),
+}
+
+impl From<NoteError> for DisputeError {
+    fn from(err: NoteError) -> Self {
+        match err {
+            NoteError::InvalidSignature => DisputeError::InvalidSignature,
+            NoteError::StorageError(msg) if msg == "Note not found" => DisputeError::NoteNotFound,
+            NoteError::StorageError(msg) => DisputeError::StorageError(msg),
+            _ => DisputeError::StorageError(format!("{:?}", err)),
+        }
+    }
+}
+
+/// Party to a note filing a dispute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeParty {
+    Issuer,
+    Recipient,
+}
+
+/// Lifecycle of a dispute
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisputeStatus {
+    /// Filed and not yet reviewed; redemption of the note is frozen
+    Open,
+    /// The operator agreed with the filer
+    Upheld,
+    /// The operator rejected the dispute
+    Dismissed,
+}
+
+/// How a piece of evidence compares with the recorded note history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceFinding {
+    /// The latest recorded version
+    Current,
+    /// A recorded version replaced by a later one
+    Superseded,
+    /// Consistent with the history but never recorded
+    Unrecorded,
+    /// Contradicts a recorded version signed by the same issuer
+    Equivocation,
+}
+
+impl From<&DisputeOutcome> for EvidenceFinding {
+    fn from(outcome: &DisputeOutcome) -> Self {
+        match outcome {
+            DisputeOutcome::Current => EvidenceFinding::Current,
+            DisputeOutcome::Superseded { .. } => EvidenceFinding::Superseded,
+            DisputeOutcome::Unrecorded => EvidenceFinding::Unrecorded,
+            DisputeOutcome::Equivocation(_) => EvidenceFinding::Equivocation,
+        }
+    }
+}
+
+/// A signed version of the disputed note, as recorded with the dispute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisputeEvidence {
+    pub amount_collected: u64,
+    pub timestamp: u64,
+    /// Issuer signature over the note (hex)
+    pub signature: String,
+    /// Signing format version of the note
+    pub signing_version: u8,
+    /// Tracker NFT ID a version 2 note is bound to (hex)
+    pub tracker_id: Option<String>,
+    /// Classification against the history recorded when the dispute was filed
+    pub finding: EvidenceFinding,
+}
+
+/// Operator decision closing a dispute
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DisputeResolution {
+    /// Free-form explanation of the decision
+    pub note: String,
+    /// When the dispute was closed (milliseconds since the epoch)
+    pub resolved_at: u64,
+}
+
+/// A dispute against the note of one issuer-recipient pair
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dispute {
+    /// Sequential dispute ID, starting at 1
+    pub id: u64,
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    pub filed_by: DisputeParty,
+    pub reason: String,
+    pub evidence: Vec<DisputeEvidence>,
+    /// When the dispute was filed (milliseconds since the epoch), as signed by the filer
+    pub filed_at: u64,
+    /// Filer's signature over the dispute signing message (hex)
+    pub signature: String,
+    pub status: DisputeStatus,
+    /// Set once the dispute is closed
+    pub resolution: Option<DisputeResolution>,
+}
+
+impl Dispute {
+    /// Whether the dispute still freezes redemption of its note
+    pub fn is_open(&self) -> bool {
+        self.status == DisputeStatus::Open
+    }
+
+    /// Whether the dispute concerns the note of the given pair
+    pub fn concerns(&self, issuer_pubkey: &PubKey, recipient_pubkey: &PubKey) -> bool {
+        self.issuer_pubkey == hex::encode(issuer_pubkey) && self.recipient_pubkey == hex::encode(recipient_pubkey)
+    }
+}
+
+/// A request to file a dispute, signed by one of the parties to the note
+#[derive(Debug, Clone)]
+pub struct DisputeFiling {
+    pub issuer_pubkey: PubKey,
+    pub recipient_pubkey: PubKey,
+    pub filed_by: DisputeParty,
+    pub reason: String,
+    /// Versions of the note held by the filer, each signed by the issuer
+    pub evidence: Vec<IouNote>,
+    /// Filing time (milliseconds since the epoch)
+    pub filed_at: u64,
+    /// Filer's signature over [`DisputeFiling::signing_message`]
+    pub signature: Signature,
+}
+
+impl DisputeFiling {
+    /// Message signed by the filer:
+    /// tag || issuer || recipient || filer (1 byte) || blake2b256(reason) || filed_at (8 bytes, big-endian)
+    ///
+    /// The evidence is not covered: every piece of it carries the issuer's own signature.
+    pub fn signing_message(
+        issuer_pubkey: &PubKey,
+        recipient_pubkey: &PubKey,
+        filed_by: DisputeParty,
+        reason: &str,
+        filed_at: u64,
+    ) -> Vec<u8> {
+        let mut message = Vec::with_capacity(DISPUTE_SIGNING_TAG.len() + 33 + 33 + 1 + 32 + 8);
+        message.extend_from_slice(DISPUTE_SIGNING_TAG);
+        message.extend_from_slice(issuer_pubkey);
+        message.extend_from_slice(recipient_pubkey);
+        message.push(match filed_by {
+            DisputeParty::Issuer => 0,
+            DisputeParty::Recipient => 1,
+        });
+        message.extend_from_slice(&blake2b256_hash(reason.as_bytes()));
+        message.extend_from_slice(&filed_at.to_be_bytes());
+        message
+    }
+
+    /// Create a filing signed with the filer's secret key
+    pub fn new_signed(
+        issuer_pubkey: PubKey,
+        recipient_pubkey: PubKey,
+        filed_by: DisputeParty,
+        reason: String,
+        evidence: Vec<IouNote>,
+        filed_at: u64,
+        filer_secret_key: &[u8; 32],
+    ) -> Result<Self, NoteError> {
+        let filer_pubkey = match filed_by {
+            DisputeParty::Issuer => &issuer_pubkey,
+            DisputeParty::Recipient => &recipient_pubkey,
+        };
+        let message = Self::signing_message(&issuer_pubkey, &recipient_pubkey, filed_by, &reason, filed_at);
+        let signature = schnorr::schnorr_sign(&message, filer_secret_key, filer_pubkey)?;
+        Ok(Self {
+            issuer_pubkey,
+            recipient_pubkey,
+            filed_by,
+            reason,
+            evidence,
+            filed_at,
+            signature,
+        })
+    }
+
+    /// Public key of the party filing the dispute
+    pub fn filer_pubkey(&self) -> &PubKey {
+        match self.filed_by {
+            DisputeParty::Issuer => &self.issuer_pubkey,
+            DisputeParty::Recipient => &self.recipient_pubkey,
+        }
+    }
+
+    /// Verify the filer's signature
+    pub fn verify_signature(&self) -> Result<(), DisputeError> {
+        let message = Self::signing_message(
+            &self.issuer_pubkey,
+            &self.recipient_pubkey,
+            self.filed_by,
+            &self.reason,
+            self.filed_at,
+        );
+        schnorr::schnorr_verify(&self.signature, &message, self.filer_pubkey())
+            .map_err(|_| DisputeError::InvalidSignature)
+    }
+
+    /// Check the evidence against the recorded history of the note (oldest
+    /// first) and build the dispute to record under `id`
+    pub fn into_dispute(self, id: u64, history: &[IouNote]) -> Result<Dispute, DisputeError> {
+        let mut evidence = Vec::with_capacity(self.evidence.len());
+        for note in &self.evidence {
+            if note.recipient_pubkey != self.recipient_pubkey {
+                return Err(DisputeError::InvalidEvidence(format!(
+                    "note at {} is for another recipient",
+                    note.timestamp
+                )));
+            }
+            note.verify_signature(&self.issuer_pubkey).map_err(|_| {
+                DisputeError::InvalidEvidence(format!("note at {} has no valid issuer signature", note.timestamp))
+            })?;
+
+            let outcome = note_history::resolve_dispute(&self.issuer_pubkey, history, note);
+            let tracker_id = match note.signing_format {
+                SigningFormat::V2 { tracker_id } => tracker_id.map(hex::encode),
+                SigningFormat::V1 => None,
+            };
+            evidence.push(DisputeEvidence {
+                amount_collected: note.amount_collected,
+                timestamp: note.timestamp,
+                signature: hex::encode(note.signature),
+                signing_version: note.signing_format.version(),
+                tracker_id,
+                finding: EvidenceFinding::from(&outcome),
+            });
+        }
+
+        Ok(Dispute {
+            id,
+            issuer_pubkey: hex::encode(self.issuer_pubkey),
+            recipient_pubkey: hex::encode(self.recipient_pubkey),
+            filed_by: self.filed_by,
+            reason: self.reason,
+            evidence,
+            filed_at: self.filed_at,
+            signature: hex::encode(self.signature),
+            status: DisputeStatus::Open,
+            resolution: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{schnorr::generate_keypair, RedemptionError, RedemptionManager, TrackerStateManager};
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    fn now_millis() -> u64 {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis() as u64
+    }
+
+    #[test]
+    fn test_dispute_lifecycle() {
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (recipient_secret, recipient_pubkey) = generate_keypair();
+        let (outsider_secret, _) = generate_keypair();
+        let issued_at = now_millis() - 60_000;
+
+        let recorded = IouNote::create_and_sign(recipient_pubkey, 1_000, issued_at, &issuer_secret).unwrap();
+        tracker.add_note(&issuer_pubkey, &recorded).unwrap();
+        // The issuer also signed a smaller amount at the same time, which the tracker never saw
+        let conflicting = IouNote::create_and_sign(recipient_pubkey, 500, issued_at, &issuer_secret).unwrap();
+
+        let file = |secret: &[u8; 32], evidence: Vec<IouNote>| {
+            DisputeFiling::new_signed(
+                issuer_pubkey,
+                recipient_pubkey,
+                DisputeParty::Recipient,
+                "issuer signed two amounts at once".to_string(),
+                evidence,
+                now_millis(),
+                secret,
+            )
+            .unwrap()
+        };
+
+        // Only the recipient itself can file as the recipient
+        assert!(matches!(
+            tracker.file_dispute(&file(&outsider_secret, vec![])),
+            Err(DisputeError::InvalidSignature)
+        ));
+
+        let dispute = tracker
+            .file_dispute(&file(&recipient_secret, vec![recorded.clone(), conflicting]))
+            .unwrap();
+        assert_eq!(dispute.id, 1);
+        assert!(dispute.is_open());
+        let findings: Vec<_> = dispute.evidence.iter().map(|e| e.finding).collect();
+        assert_eq!(findings, vec![EvidenceFinding::Current, EvidenceFinding::Equivocation]);
+        assert_eq!(tracker.get_dispute(1).unwrap(), Some(dispute.clone()));
+
+        // One open dispute per note at a time
+        assert!(matches!(
+            tracker.file_dispute(&file(&recipient_secret, vec![])),
+            Err(DisputeError::AlreadyOpen(1))
+        ));
+
+        // Redemption is frozen while the dispute is open
+        let mut redemptions = RedemptionManager::new(tracker);
+        let errors =
+            redemptions.validate_redemption(&hex::encode(issuer_pubkey), &hex::encode(recipient_pubkey), 100);
+        assert!(matches!(errors.as_slice(), [RedemptionError::NoteDisputed(1)]));
+
+        let closed = redemptions
+            .tracker
+            .close_dispute(1, DisputeStatus::Upheld, "equivocation confirmed".to_string())
+            .unwrap();
+        assert_eq!(closed.status, DisputeStatus::Upheld);
+        assert!(closed.resolution.is_some());
+        assert!(matches!(
+            redemptions.tracker.close_dispute(1, DisputeStatus::Dismissed, String::new()),
+            Err(DisputeError::AlreadyClosed(1))
+        ));
+        assert!(redemptions
+            .validate_redemption(&hex::encode(issuer_pubkey), &hex::encode(recipient_pubkey), 100)
+            .is_empty());
+        assert_eq!(redemptions.tracker.get_disputes().unwrap(), vec![closed]);
+    }
+
+    #[test]
+    fn test_dispute_requires_a_note_and_valid_evidence() {
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let (forger_secret, _) = generate_keypair();
+        let issued_at = now_millis() - 60_000;
+
+        let filing_at = |filed_at: u64, evidence: Vec<IouNote>| {
+            DisputeFiling::new_signed(
+                issuer_pubkey,
+                recipient_pubkey,
+                DisputeParty::Issuer,
+                "recipient claims more than was issued".to_string(),
+                evidence,
+                filed_at,
+                &issuer_secret,
+            )
+            .unwrap()
+        };
+        let filing = |evidence: Vec<IouNote>| filing_at(now_millis(), evidence);
+
+        assert!(matches!(tracker.file_dispute(&filing(vec![])), Err(DisputeError::NoteNotFound)));
+
+        let note = IouNote::create_and_sign(recipient_pubkey, 1_000, issued_at, &issuer_secret).unwrap();
+        tracker.add_note(&issuer_pubkey, &note).unwrap();
+        let forged = IouNote::create_and_sign(recipient_pubkey, 5_000, issued_at + 1, &forger_secret).unwrap();
+        assert!(matches!(
+            tracker.file_dispute(&filing(vec![forged])),
+            Err(DisputeError::InvalidEvidence(_))
+        ));
+        assert!(tracker.get_disputes().unwrap().is_empty());
+
+        assert!(matches!(
+            tracker.file_dispute(&filing_at(now_millis() + 3_600_000, vec![])),
+            Err(DisputeError::FutureTimestamp)
+        ));
+
+        // Changing any signed field invalidates the filing
+        let mut tampered = filing(vec![]);
+        tampered.reason = "something else".to_string();
+        assert!(matches!(tracker.file_dispute(&tampered), Err(DisputeError::InvalidSignature)));
+    }
+}
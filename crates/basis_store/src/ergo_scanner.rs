@@ -2,72 +2,24 @@
 //! This module provides modern blockchain integration using /scan and /blockchain APIs
 //! Adopted from chaincash-rs scanner implementation, modified for reserves-only scanning
 
-use std::{sync::Arc, time::{Duration, SystemTime, UNIX_EPOCH}};
+use std::{sync::Arc, time::Duration};
 use tokio::sync::Mutex;
 
 use ergo_lib::ergotree_ir::address::AddressEncoder;
-use ergo_lib::ergotree_ir::address::NetworkPrefix;
 use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
 use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{debug, error, info, warn};
 
-use reqwest::Client;
-
-/// Wrapper struct for the actual API response from /scan/unspentBoxes endpoint
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ApiScanBox {
-    #[serde(rename = "box")]
-    inner_box: ApiInnerBox,
-    #[serde(rename = "confirmationsNum")]
-    confirmations_num: u32,
-    address: String,
-    #[serde(rename = "creationTransaction")]
-    creation_transaction: String,
-    scans: Vec<i32>,
-    onchain: bool,
-    #[serde(rename = "creationOutIndex")]
-    creation_out_index: u32,
-    #[serde(rename = "spendingTransaction")]
-    spending_transaction: Option<String>,
-    #[serde(rename = "spendingHeight")]
-    spending_height: Option<u64>,
-    #[serde(rename = "inclusionHeight")]
-    inclusion_height: u64,
-    spent: bool,
-}
-
-/// The inner box structure from the API response
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ApiInnerBox {
-    #[serde(rename = "boxId")]
-    box_id: String,
-    value: u64,
-    #[serde(rename = "ergoTree")]
-    ergo_tree: String,
-    #[serde(rename = "creationHeight")]
-    creation_height: u64,
-    #[serde(rename = "transactionId")]
-    transaction_id: String,
-    #[serde(rename = "additionalRegisters")]
-    additional_registers: std::collections::HashMap<String, String>,
-    assets: Vec<ApiBoxAsset>,
-    index: u32,
-}
-
-/// Asset structure from the API response
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct ApiBoxAsset {
-    #[serde(rename = "tokenId")]
-    token_id: String,
-    amount: u64,
-}
-
-
-
 use crate::{
+    contract_registry::ContractRegistry,
+    explorer_backend::ExplorerBackend,
     persistence::{ReserveStorage, ScannerMetadataStorage},
+    reserve_lineage::{classify_spend, derive_reserve_events, node_box_id, ReserveSpendKind, ReserveTransition},
+    scan_manager::{ScanManager, ScanNodeConfig, ScanSpec},
+    storage_encryption::StorageCipher,
+    transaction_builder::REDEMPTION_ACTION,
     ExtendedReserveInfo, ReserveTracker,
 };
 
@@ -126,9 +78,9 @@ pub struct NodeConfig {
 struct ServerStateInner {
     pub current_height: u64,
     pub last_scanned_height: u64,
-    pub scan_active: bool,
-    pub scan_id: Option<i32>,
-    pub last_scan_verification: Option<std::time::SystemTime>,
+    pub progress: ScannerProgress,
+    /// When the current backfill run started and the height it started from, for the ETA
+    pub backfill_started: Option<(std::time::Instant, u64)>,
 }
 
 /// Server state for scanner
@@ -137,50 +89,362 @@ struct ServerStateInner {
 pub struct ServerState {
     pub config: NodeConfig,
     pub inner: Arc<Mutex<ServerStateInner>>,
-    pub client: Client,
+    /// Node scan of reserve contract boxes
+    pub scan: ScanManager<ReserveScanSpec>,
     pub reserve_tracker: ReserveTracker,
     pub metadata_storage: ScannerMetadataStorage,
     pub reserve_storage: ReserveStorage,
+    pub backfill_config: BackfillConfig,
+    /// Scan loop timing, shared by every clone so it can change while the loop runs
+    scan_loop_config: Arc<std::sync::RwLock<ScanLoopConfig>>,
+    /// Reserve events derived from scan box changes, once queued in storage
+    pub event_tx: tokio::sync::broadcast::Sender<QueuedReserveEvent>,
+    /// Reserve box IDs found by explorer discovery, kept until seen spent
+    pub discovered_reserves: Arc<std::sync::Mutex<std::collections::HashSet<String>>>,
 }
 
-impl ServerState {
-    /// Create HTTP request builder with API key header if configured
-    fn request_builder(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
-        debug!("Request method: {}, URL: {}", method, url);
+/// Number of reserve events buffered for slow subscribers
+pub const RESERVE_EVENT_CHANNEL_CAPACITY: usize = 1_024;
+
+/// Default number of blocks processed per backfill batch
+pub const DEFAULT_BACKFILL_BATCH_BLOCKS: u64 = 1_000;
+
+/// Default number of boxes requested per node call during backfill
+pub const DEFAULT_BACKFILL_PAGE_SIZE: u64 = 100;
+
+/// Default pause between node requests during backfill, in milliseconds
+pub const DEFAULT_BACKFILL_REQUEST_DELAY_MS: u64 = 250;
+
+/// Catch-up scanning configuration
+///
+/// When `NodeConfig::start_height` is set, the scanner first walks the scan's
+/// boxes from that height to the chain tip in batches of `batch_blocks`
+/// inclusion heights, persisting progress after each batch, before switching
+/// to live scanning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfillConfig {
+    /// Blocks covered by one batch
+    pub batch_blocks: u64,
+    /// Boxes requested per node call
+    pub page_size: u64,
+    /// Pause between node requests
+    pub request_delay: Duration,
+}
+
+impl Default for BackfillConfig {
+    fn default() -> Self {
+        Self {
+            batch_blocks: DEFAULT_BACKFILL_BATCH_BLOCKS,
+            page_size: DEFAULT_BACKFILL_PAGE_SIZE,
+            request_delay: Duration::from_millis(DEFAULT_BACKFILL_REQUEST_DELAY_MS),
+        }
+    }
+}
+
+/// Default pause between scan cycles, in seconds
+pub const DEFAULT_SCAN_INTERVAL_SECS: u64 = 30;
+
+/// Default pause after a failed scan cycle, in seconds
+pub const DEFAULT_RETRY_BACKOFF_SECS: u64 = 10;
+
+/// Default number of failed cycles in a row before the scanner pauses longer
+pub const DEFAULT_MAX_CONSECUTIVE_FAILURES: u32 = 5;
+
+/// Default random spread of scan loop pauses, in percent
+pub const DEFAULT_SCAN_JITTER_PERCENT: u64 = 10;
+
+/// Default number of blocks live scanning may lag behind the tip before it counts as stalled
+pub const DEFAULT_MAX_BLOCK_LAG: u64 = 10;
+
+/// Scan loop timing
+///
+/// The loop waits `scan_interval` between successful cycles and
+/// `retry_backoff` after a failed one. Once `max_consecutive_failures` cycles
+/// in a row have failed it waits twice the scan interval instead, until a
+/// cycle succeeds. Every pause is spread by up to `jitter` of its length in
+/// either direction, so trackers sharing a node do not poll in lockstep.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanLoopConfig {
+    /// Pause between successful cycles
+    pub scan_interval: Duration,
+    /// Pause after a failed cycle
+    pub retry_backoff: Duration,
+    /// Failed cycles in a row before pausing for twice the scan interval
+    pub max_consecutive_failures: u32,
+    /// Random spread of each pause, as a fraction of it between 0 and 1
+    pub jitter: f64,
+}
+
+impl Default for ScanLoopConfig {
+    fn default() -> Self {
+        Self {
+            scan_interval: Duration::from_secs(DEFAULT_SCAN_INTERVAL_SECS),
+            retry_backoff: Duration::from_secs(DEFAULT_RETRY_BACKOFF_SECS),
+            max_consecutive_failures: DEFAULT_MAX_CONSECUTIVE_FAILURES,
+            jitter: DEFAULT_SCAN_JITTER_PERCENT as f64 / 100.0,
+        }
+    }
+}
+
+impl ScanLoopConfig {
+    /// Pause before the next cycle after `consecutive_failures` failed cycles in a row, without jitter
+    pub fn base_wait(&self, consecutive_failures: u32) -> Duration {
+        if consecutive_failures == 0 {
+            self.scan_interval
+        } else if consecutive_failures < self.max_consecutive_failures {
+            self.retry_backoff
+        } else {
+            self.scan_interval.saturating_mul(2)
+        }
+    }
+
+    /// Pause before the next cycle, with jitter applied
+    pub fn wait(&self, consecutive_failures: u32) -> Duration {
+        let base = self.base_wait(consecutive_failures);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return base;
+        }
+        base.mul_f64(1.0 + rand::Rng::gen_range(&mut rand::thread_rng(), -jitter..=jitter))
+    }
+}
+
+/// Scanner phase
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanMode {
+    /// Catching up from the configured start height
+    Backfill,
+    /// Following the chain tip
+    Live,
+}
+
+/// Scanner progress snapshot
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScannerProgress {
+    /// Current phase
+    pub mode: ScanMode,
+    /// Height backfill started from
+    pub start_height: u64,
+    /// Highest height whose boxes have been processed
+    pub scanned_height: u64,
+    /// Latest known chain height
+    pub tip_height: u64,
+    /// Blocks between the scanned height and the tip
+    pub block_lag: u64,
+    /// Backfill batches completed since startup
+    pub batches_completed: u64,
+    /// Reserve boxes processed by backfill and live scans since startup
+    pub boxes_processed: u64,
+    /// Failed scan cycles since startup
+    pub error_count: u64,
+    /// Failed scan cycles since the last success
+    pub consecutive_failures: u32,
+    /// Most recent error
+    pub last_error: Option<String>,
+    /// When the last scan cycle succeeded, in milliseconds since the Unix epoch
+    pub last_success_timestamp: Option<u64>,
+    /// Estimated seconds until backfill reaches the tip
+    pub eta_secs: Option<u64>,
+}
+
+impl ScannerProgress {
+    /// Whether live scanning has fallen more than `max_block_lag` blocks
+    /// behind the tip
+    ///
+    /// A backfilling scanner is expected to lag, so it never counts as stalled.
+    pub fn is_stalled(&self, max_block_lag: u64) -> bool {
+        self.mode == ScanMode::Live && self.block_lag > max_block_lag
+    }
+}
+
+/// URL of a node API endpoint
+///
+/// Joins the configured node URL and `path` with a single slash, so the node
+/// may be configured with a trailing slash or mounted under a path prefix.
+pub fn node_endpoint(node_url: &str, path: &str) -> String {
+    format!("{}/{}", node_url.trim_end_matches('/'), path.trim_start_matches('/'))
+}
+
+/// Reserve contract boxes, tracked by the serialized contract in R1
+#[derive(Debug, Clone)]
+pub struct ReserveScanSpec {
+    /// Basis reserve contract P2S address
+    pub reserve_contract_p2s: Option<String>,
+    /// Other contract versions whose reserves are tracked too
+    pub contracts: ContractRegistry,
+}
+
+impl ReserveScanSpec {
+    /// ErgoTrees of the tracked reserves as hex: the configured contract first,
+    /// then the other registered versions
+    pub fn reserve_ergo_trees(&self) -> Result<Vec<String>, ScannerError> {
+        let reserve_contract_p2s = self.reserve_contract_p2s.as_ref().ok_or_else(|| {
+            ScannerError::Generic("Reserve contract P2S not configured".to_string())
+        })?;
+
+        // The tree is the same on every network; the address network is checked with the configuration
+        let tree: ErgoTree = AddressEncoder::unchecked_parse_address_from_str(reserve_contract_p2s)
+            .map_err(|e| ScannerError::Generic(format!("Invalid reserve contract P2S: {}", e)))?
+            .script()
+            .map_err(|e| ScannerError::Generic(format!("Invalid reserve contract script: {}", e)))?;
+        let mut trees = vec![hex::encode(tree.sigma_serialize_bytes())];
+        for contract in self.contracts.contracts() {
+            if !trees.iter().any(|tree| contract.locks(tree)) {
+                trees.push(contract.ergo_tree_hex.clone());
+            }
+        }
+        Ok(trees)
+    }
+}
+
+impl ScanSpec for ReserveScanSpec {
+    type Item = ExtendedReserveInfo;
+    type ParseError = ScannerError;
+
+    fn default_scan_name(&self) -> &'static str {
+        "Basis Reserve Scanner"
+    }
+
+    fn tracking_rule(&self) -> Result<serde_json::Value, ScannerError> {
+        // Serialize each ErgoTree with ByteArrayConstant wrapper
+        // This matches the Scala pattern: ByteArrayConstant(ErgoTreeSerializer.DefaultSerializer.serializeErgoTree(script))
+        let mut rules: Vec<serde_json::Value> = self
+            .reserve_ergo_trees()?
+            .iter()
+            .map(|tree| {
+                let tree_bytes = hex::decode(tree).unwrap_or_default();
+                let byte_array_constant = ergo_lib::ergotree_ir::mir::constant::Constant::from(tree_bytes);
+                serde_json::json!({
+                    "predicate": "contains",
+                    "register": "R1",
+                    "value": hex::encode(byte_array_constant.sigma_serialize_bytes())
+                })
+            })
+            .collect();
+
+        // Boxes of any registered contract version
+        if rules.len() == 1 {
+            Ok(rules.remove(0))
+        } else {
+            Ok(serde_json::json!({
+                "predicate": "or",
+                "args": rules
+            }))
+        }
+    }
+
+    /// Parse reserve box into ExtendedReserveInfo
+    fn parse_box(&self, scan_box: &ScanBox) -> Result<ExtendedReserveInfo, ScannerError> {
+        let box_id = scan_box.box_id.clone();
+        let value = scan_box.value;
+        let creation_height = scan_box.creation_height;
+
+        // Extract owner public key from R4 register
+        let owner_pubkey_raw = scan_box
+            .additional_registers
+            .get("R4")
+            .ok_or_else(|| {
+                ScannerError::InvalidReserveBox(format!("Missing R4 register in box {}", box_id))
+            })?
+            .clone();
+
+        // Strip the 0x07 prefix if present (GroupElement type identifier from Ergo registers)
+        let owner_pubkey = if owner_pubkey_raw.starts_with("07") && owner_pubkey_raw.len() >= 66 {
+            // Extract the actual 33-byte public key (66 hex chars) after the 0x07 prefix
+            owner_pubkey_raw[2..].to_string()
+        } else {
+            // Use as-is if no prefix or wrong length
+            owner_pubkey_raw
+        };
 
-        let mut request = self.client.request(method, url);
+        // Extract tracker NFT ID from R6 register (required according to spec)
+        let tracker_nft_id_raw = scan_box
+            .additional_registers
+            .get("R6")
+            .ok_or_else(|| {
+                ScannerError::InvalidReserveBox(format!("Missing R6 register in box {}", box_id))
+            })?
+            .clone();
+
+        // Create extended reserve info
+        // Decode the hex-encoded public key to actual bytes
+        let owner_pubkey_bytes = hex::decode(&owner_pubkey)
+            .map_err(|_| ScannerError::InvalidReserveBox(format!("Invalid hex in owner pubkey for box {}", box_id)))?;
 
-        // Add API key header if configured
-        if let Some(api_key) = &self.config.api_key {
-            debug!("Using API key '{}' for request to: {}", api_key, url);
-            info!("Adding HTTP header: api_key: {}", api_key);
-            request = request.header("api_key", api_key);
+        // Decode the hex-encoded tracker NFT ID to actual bytes
+        // R6 contains a Coll[Byte] value with Ergo serialization prefix: 0e20 (type + length)
+        // We need to strip the first 2 bytes (4 hex chars) to get the actual data
+        let tracker_nft_hex = if tracker_nft_id_raw.len() >= 4 {
+            &tracker_nft_id_raw[4..]
         } else {
-            debug!("No API key configured for request to: {}", url);
-            info!("No API key header added to HTTP request");
+            tracker_nft_id_raw.as_str()
+        };
+        let tracker_nft_id_bytes = hex::decode(tracker_nft_hex)
+            .map_err(|_| ScannerError::InvalidReserveBox(format!("Invalid hex in tracker NFT ID for box {}", box_id)))?;
+
+        // Validate that the tracker NFT ID is exactly 32 bytes (the actual tracker NFT ID)
+        if tracker_nft_id_bytes.len() != 32 {
+            return Err(ScannerError::InvalidReserveBox(format!(
+                "Invalid tracker NFT ID length in box {}: expected 32 bytes, got {}",
+                box_id,
+                tracker_nft_id_bytes.len()
+            )));
         }
 
-        request
+        let reserve_info = ExtendedReserveInfo::new(
+            box_id.as_bytes(),
+            &owner_pubkey_bytes,
+            value,
+            Some(&tracker_nft_id_bytes),
+            creation_height,
+        );
+
+        Ok(reserve_info)
     }
+}
 
+impl ServerState {
     /// Create a server state that uses real Ergo scanner
     pub fn new(config: NodeConfig) -> Result<Self, ScannerError> {
+        let data_dir = std::env::current_dir()
+            .unwrap_or_else(|_| std::path::PathBuf::from("."))
+            .join("crates/basis_server/data");
+        Self::with_storage_paths(
+            config,
+            data_dir.join("scanner_metadata"),
+            data_dir.join("reserves"),
+        )
+    }
+
+    /// Create a server state storing scanner metadata and reserves at the given paths
+    pub fn with_storage_paths(
+        config: NodeConfig,
+        storage_path: std::path::PathBuf,
+        reserve_storage_path: std::path::PathBuf,
+    ) -> Result<Self, ScannerError> {
+        Self::with_storage_paths_and_cipher(config, storage_path, reserve_storage_path, StorageCipher::default())
+    }
+
+    /// Create a server state storing scanner metadata and reserves at the given
+    /// paths, sealing reserve records with `cipher`
+    pub fn with_storage_paths_and_cipher(
+        config: NodeConfig,
+        storage_path: std::path::PathBuf,
+        reserve_storage_path: std::path::PathBuf,
+        cipher: StorageCipher,
+    ) -> Result<Self, ScannerError> {
         let start_height = config.start_height.unwrap_or(0);
-        let client = Client::new();
 
         // Log which Ergo node is being used (INFO level)
         info!("Initializing Ergo scanner with node: {}", config.node_url);
-        if let Some(api_key) = &config.api_key {
-            info!("Using API key: {}", api_key);
+        if config.api_key.is_some() {
+            info!("Using API key for Ergo node requests");
         } else {
             warn!("No API key configured for Ergo node");
         }
 
         // Open scanner metadata storage - create directory if it doesn't exist
-        let storage_path = std::env::current_dir()
-            .unwrap_or_else(|_| std::path::PathBuf::from("."))
-            .join("crates/basis_server/data/scanner_metadata");
-
         // Create directory if it doesn't exist
         if let Some(parent) = storage_path.parent() {
             std::fs::create_dir_all(parent).map_err(|e| {
@@ -196,10 +460,6 @@ impl ServerState {
         })?;
 
         // Open reserve storage - create directory if it doesn't exist
-        let reserve_storage_path = std::env::current_dir()
-            .unwrap_or_else(|_| std::path::PathBuf::from("."))
-            .join("crates/basis_server/data/reserves");
-
         // Create directory if it doesn't exist
         if let Some(parent) = reserve_storage_path.parent() {
             std::fs::create_dir_all(parent).map_err(|e| {
@@ -210,7 +470,7 @@ impl ServerState {
             })?;
         }
 
-        let reserve_storage = ReserveStorage::open(&reserve_storage_path).map_err(|e| {
+        let reserve_storage = ReserveStorage::open_with_cipher(&reserve_storage_path, cipher).map_err(|e| {
             ScannerError::StoreError(format!("Failed to open reserve storage: {:?}", e))
         })?;
 
@@ -228,114 +488,296 @@ impl ServerState {
             info!("Loaded {} reserves from database", reserves_count);
         }
 
+        // Backfill only when a start height is configured
+        let mode = if config.start_height.is_some() {
+            ScanMode::Backfill
+        } else {
+            ScanMode::Live
+        };
+
         // Create synchronized inner state
         let inner = Arc::new(Mutex::new(ServerStateInner {
             current_height: 0,
             last_scanned_height: start_height,
-            scan_active: false,
-            scan_id: None,
-            last_scan_verification: None,
+            progress: ScannerProgress {
+                mode,
+                start_height,
+                scanned_height: start_height,
+                tip_height: 0,
+                block_lag: 0,
+                batches_completed: 0,
+                boxes_processed: 0,
+                error_count: 0,
+                consecutive_failures: 0,
+                last_error: None,
+                last_success_timestamp: None,
+                eta_secs: None,
+            },
+            backfill_started: None,
         }));
 
+        let scan = ScanManager::new(
+            ReserveScanSpec {
+                reserve_contract_p2s: config.reserve_contract_p2s.clone(),
+                contracts: ContractRegistry::new(),
+            },
+            ScanNodeConfig {
+                node_url: config.node_url.clone(),
+                api_key: config.api_key.clone(),
+                scan_name: config.scan_name.clone(),
+            },
+            metadata_storage.clone(),
+        );
+
         Ok(Self {
             config,
             inner,
-            client,
+            scan,
             reserve_tracker,
             metadata_storage,
             reserve_storage,
+            backfill_config: BackfillConfig::default(),
+            scan_loop_config: Arc::default(),
+            event_tx: tokio::sync::broadcast::channel(RESERVE_EVENT_CHANNEL_CAPACITY).0,
+            discovered_reserves: Arc::default(),
         })
     }
 
-    /// Get current blockchain height from cache or Ergo node
-    /// Uses cached value if less than 10 minutes old, otherwise fetches from node
-    pub async fn get_current_height(&self) -> Result<u64, ScannerError> {
-        const CACHE_TTL_MS: u64 = 600_000; // 10 minutes in milliseconds
-
-        // Check if we have a cached height
-        match self.metadata_storage.get_blockchain_height() {
-            Ok(Some((cached_height, cached_timestamp))) => {
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis() as u64;
-
-                if now.saturating_sub(cached_timestamp) < CACHE_TTL_MS {
-                    debug!("Using cached blockchain height: {}", cached_height);
-                    return Ok(cached_height);
-                }
+    /// Replace the backfill configuration; takes effect when the scanner starts
+    pub fn set_backfill_config(&mut self, backfill_config: BackfillConfig) {
+        self.backfill_config = backfill_config;
+    }
 
-                debug!("Cached blockchain height expired, fetching from node");
-            }
-            Ok(None) => {
-                debug!("No cached blockchain height found, fetching from node");
-            }
-            Err(e) => {
-                warn!("Failed to read cached blockchain height: {:?}", e);
+    /// Track reserves of the registered contract versions besides the
+    /// configured one; takes effect when the scan is registered
+    pub fn set_contract_registry(&mut self, contracts: ContractRegistry) {
+        let mut spec = self.scan.spec().clone();
+        spec.contracts = contracts;
+        self.scan.set_spec(spec);
+    }
+
+    /// Scan reserves through `explorer` instead of the node's scan API
+    ///
+    /// Spends are then followed through the explorer too. It does not serve
+    /// spending proofs, so redemption receivers are not read.
+    pub fn set_explorer_backend(&mut self, explorer: ExplorerBackend) {
+        self.scan.use_explorer(explorer);
+    }
+
+    /// Replace the scan loop timing; a running scanner uses it from its next pause
+    pub fn set_scan_loop_config(&self, scan_loop_config: ScanLoopConfig) {
+        if let Ok(mut config) = self.scan_loop_config.write() {
+            *config = scan_loop_config;
+        }
+    }
+
+    /// Current scan loop timing
+    pub fn scan_loop_config(&self) -> ScanLoopConfig {
+        self.scan_loop_config
+            .read()
+            .map(|config| config.clone())
+            .unwrap_or_default()
+    }
+
+    /// Current scanner progress, including the backfill ETA
+    pub async fn progress(&self) -> ScannerProgress {
+        let inner = self.inner.lock().await;
+        let mut progress = inner.progress.clone();
+        progress.tip_height = progress.tip_height.max(inner.current_height);
+        progress.block_lag = progress.tip_height.saturating_sub(progress.scanned_height);
+
+        if progress.mode == ScanMode::Backfill {
+            if let Some((started_at, started_height)) = inner.backfill_started {
+                let done = progress.scanned_height.saturating_sub(started_height);
+                let remaining = progress.tip_height.saturating_sub(progress.scanned_height);
+                let elapsed = started_at.elapsed().as_secs_f64();
+                if done > 0 && elapsed > 0.0 {
+                    let blocks_per_sec = done as f64 / elapsed;
+                    progress.eta_secs = Some((remaining as f64 / blocks_per_sec).ceil() as u64);
+                }
             }
         }
+        progress
+    }
 
-        // Fetch from node
-        let url = format!("{}/info", self.config.node_url);
+    /// Record a failed scan cycle
+    async fn record_failure(&self, error: &ScannerError) {
+        let mut inner = self.inner.lock().await;
+        inner.progress.error_count += 1;
+        inner.progress.consecutive_failures += 1;
+        inner.progress.last_error = Some(error.to_string());
+    }
 
-        let response = self
-            .request_builder(reqwest::Method::GET, &url)
-            .send()
+    /// Record a successful scan cycle
+    async fn record_success(&self) {
+        let mut inner = self.inner.lock().await;
+        inner.progress.consecutive_failures = 0;
+        inner.progress.last_success_timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .ok()
+            .map(|elapsed| elapsed.as_millis() as u64);
+    }
+
+    /// Switch back to backfill and rescan the chain from `from_height` to the tip
+    ///
+    /// The running scanner loop picks the rescan up on its next cycle. Reserve
+    /// boxes found again are re-applied, so a rescan repairs reserves missed or
+    /// mangled since that height.
+    pub async fn rescan_from(&self, from_height: u64) -> Result<(), ScannerError> {
+        let scan_id = self
+            .scan
+            .scan_id()
             .await
-            .map_err(|e| ScannerError::HttpError(format!("Failed to connect to node: {}", e)))?;
+            .ok_or_else(|| ScannerError::Generic("Scan not registered".to_string()))?;
+        // Backfill resumes one block past the stored height
+        self.metadata_storage
+            .store_backfill_progress(scan_id, from_height.saturating_sub(1))
+            .map_err(|e| ScannerError::StoreError(format!("Failed to persist backfill progress: {:?}", e)))?;
 
-        if !response.status().is_success() {
-            return Err(ScannerError::NodeError(format!(
-                "Node returned status: {}",
-                response.status()
-            )));
-        }
+        let mut inner = self.inner.lock().await;
+        inner.progress.mode = ScanMode::Backfill;
+        inner.progress.start_height = from_height;
+        inner.progress.scanned_height = from_height.saturating_sub(1);
+        inner.progress.eta_secs = None;
+        inner.backfill_started = None;
+        inner.last_scanned_height = from_height.saturating_sub(1);
+        info!("Rescan requested from height {}", from_height);
+        Ok(())
+    }
 
-        let info: serde_json::Value = response
-            .json()
+    /// Whether the scanner is still catching up from the start height
+    pub async fn is_backfilling(&self) -> bool {
+        self.inner.lock().await.progress.mode == ScanMode::Backfill
+    }
+
+    /// Process the next backfill batch, up to the chain tip at `tip_height`
+    ///
+    /// Progress is persisted after every batch, keyed by scan ID, so a restart
+    /// resumes where it left off and a re-registered scan starts over.
+    /// Returns `true` once the tip has been reached and the scanner is live.
+    pub async fn backfill_next_batch(&self, tip_height: u64) -> Result<bool, ScannerError> {
+        let scan_id = self
+            .scan
+            .scan_id()
             .await
-            .map_err(|e| ScannerError::JsonError(format!("Failed to parse node info: {}", e)))?;
+            .ok_or_else(|| ScannerError::Generic("Scan not registered".to_string()))?;
 
-        let height = info["fullHeight"].as_u64().ok_or_else(|| {
-            ScannerError::NodeError("Failed to parse fullHeight from node info".to_string())
-        })?;
+        let start_height = self.config.start_height.unwrap_or(0);
+        let resume_height = match self.metadata_storage.get_backfill_progress() {
+            Ok(Some((stored_scan_id, height))) if stored_scan_id == scan_id => height + 1,
+            Ok(_) => start_height,
+            Err(e) => {
+                warn!("Failed to read backfill progress, starting from {}: {:?}", start_height, e);
+                start_height
+            }
+        };
 
-        // Store in cache with current timestamp
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
+        {
+            let mut inner = self.inner.lock().await;
+            inner.progress.tip_height = tip_height;
+            if inner.backfill_started.is_none() {
+                inner.backfill_started = Some((std::time::Instant::now(), resume_height.saturating_sub(1)));
+                info!("Backfilling reserve scan from height {} to {}", resume_height, tip_height);
+            }
+        }
+
+        if resume_height > tip_height {
+            let mut inner = self.inner.lock().await;
+            inner.progress.mode = ScanMode::Live;
+            inner.progress.scanned_height = tip_height;
+            inner.progress.eta_secs = None;
+            inner.last_scanned_height = tip_height;
+            info!("Backfill complete at height {}, switching to live scanning", tip_height);
+            return Ok(true);
+        }
 
-        if let Err(e) = self.metadata_storage.store_blockchain_height(height, now) {
-            warn!("Failed to cache blockchain height: {:?}", e);
+        let batch_end = resume_height
+            .saturating_add(self.backfill_config.batch_blocks.max(1) - 1)
+            .min(tip_height);
+
+        let page_size = self.backfill_config.page_size.max(1);
+        let mut offset = 0;
+        let mut processed = 0u64;
+        loop {
+            let query = format!(
+                "minInclusionHeight={}&maxInclusionHeight={}&offset={}&limit={}",
+                resume_height, batch_end, offset, page_size
+            );
+            let boxes = self.scan.fetch_boxes(Some(&query)).await?;
+            let page_len = boxes.len() as u64;
+            for reserve_info in self.scan.parse_boxes(&boxes) {
+                self.apply_reserve(reserve_info);
+                processed += 1;
+            }
+            if page_len < page_size {
+                break;
+            }
+            offset += page_size;
+            tokio::time::sleep(self.backfill_config.request_delay).await;
         }
 
-        Ok(height)
+        self.metadata_storage
+            .store_backfill_progress(scan_id, batch_end)
+            .map_err(|e| ScannerError::StoreError(format!("Failed to persist backfill progress: {:?}", e)))?;
+
+        let mut inner = self.inner.lock().await;
+        inner.progress.scanned_height = batch_end;
+        inner.progress.batches_completed += 1;
+        inner.progress.boxes_processed += processed;
+        inner.last_scanned_height = batch_end;
+        info!(
+            "Backfilled heights {}..={} ({} reserve boxes), {} blocks behind tip",
+            resume_height,
+            batch_end,
+            processed,
+            tip_height - batch_end
+        );
+        Ok(false)
+    }
+
+    /// Get current blockchain height from cache or Ergo node
+    /// Uses cached value if less than 10 minutes old, otherwise fetches from node
+    pub async fn get_current_height(&self) -> Result<u64, ScannerError> {
+        self.scan.current_height().await
     }
 
     /// Get unspent reserve boxes
     pub async fn get_unspent_reserve_boxes(&self) -> Result<Vec<ErgoBox>, ScannerError> {
-        // This would use the scan API to get actual reserve boxes
-        // For now, return empty vector as placeholder
-        Ok(vec![])
+        let scan_boxes = self.get_scan_boxes().await?;
+        Ok(scan_boxes
+            .into_iter()
+            .map(|scan_box| ErgoBox {
+                box_id: scan_box.box_id,
+                value: scan_box.value,
+                ergo_tree: scan_box.ergo_tree,
+                creation_height: scan_box.creation_height,
+                transaction_id: scan_box.transaction_id,
+                additional_registers: scan_box.additional_registers,
+            })
+            .collect())
+    }
+
+    /// Subscribe to reserve events derived by live scanning
+    ///
+    /// Events are derived by diffing each scan against the previously known
+    /// reserves; boxes found during backfill do not produce events. Each event
+    /// is queued in reserve storage before it is sent, so subscribers that
+    /// fall behind or restart can read the ones they have not processed from
+    /// [`ReserveStorage::unprocessed_reserve_events`].
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<QueuedReserveEvent> {
+        self.event_tx.subscribe()
     }
 
     /// Check if scanner is active
     pub async fn is_active(&self) -> bool {
-        // Use async lock for async context
-        let inner = self.inner.lock().await;
-        inner.scan_active
+        self.scan.is_active().await
     }
 
     /// Start scanning with real blockchain integration
     pub async fn start_scanning(&mut self) -> Result<(), ScannerError> {
         info!("Starting Ergo blockchain scanner for reserves");
 
-        // Update inner state
-        {
-            let mut inner = self.inner.lock().await;
-            inner.scan_active = true;
-        }
+        self.scan.set_active(true).await;
 
         if let Some(reserve_contract_p2s) = &self.config.reserve_contract_p2s {
             info!("Using reserve contract P2S: {}", reserve_contract_p2s);
@@ -367,543 +809,380 @@ impl ServerState {
 
 
     /// Register reserve scan with Ergo node
-    pub async fn register_reserve_scan(&mut self) -> Result<(), ScannerError> {
-        let reserve_contract_p2s =
-            self.config.reserve_contract_p2s.as_ref().ok_or_else(|| {
-                ScannerError::Generic("Reserve contract P2S not configured".to_string())
-            })?;
-
-        let scan_name = self
-            .config
-            .scan_name
-            .as_deref()
-            .unwrap_or("Basis Reserve Scanner");
-
-        // Check if scan ID already exists in database
-        debug!(
-            "Checking for existing scan ID in database for scan name: '{}'",
-            scan_name
-        );
-        match self.metadata_storage.get_scan_id(scan_name) {
-            Ok(Some(stored_scan_id)) => {
-                info!("Found existing scan ID in database: {}", stored_scan_id);
-
-                // Verify the scan still exists on the node (only every 4 hours)
-                let should_verify = self.should_verify_scan().await;
-                if should_verify {
-                    debug!("Verifying scan ID {} exists on Ergo node", stored_scan_id);
-                    match self.verify_scan_exists(stored_scan_id).await {
-                        Ok(true) => {
-                            info!("Using existing scan ID: {}", stored_scan_id);
-                            // Update verification timestamp
-                            self.update_scan_verification_time().await;
-                            // Update inner state with the validated scan ID
-                            {
-                                let mut inner = self.inner.lock().await;
-                                inner.scan_id = Some(stored_scan_id);
-                            }
-                            return Ok(());
-                        }
-                        Ok(false) => {
-                            warn!(
-                                "Stored scan ID {} no longer exists on node, re-registering",
-                                stored_scan_id
-                            );
-                            self.metadata_storage
-                                .remove_scan_id(scan_name)
-                                .map_err(|e| {
-                                    ScannerError::StoreError(format!(
-                                        "Failed to remove invalid scan ID: {:?}",
-                                        e
-                                    ))
-                                })?;
-                            info!(
-                                "Removed invalid scan ID {} from database, proceeding to registration",
-                                stored_scan_id
-                            );
-                        }
-                        Err(e) => {
-                            // If verification fails due to scan list endpoint being unavailable (400/404),
-                            // or JSON parsing errors, assume the scan still exists and continue using it
-                            if e.to_string().contains("400") || e.to_string().contains("404") || e.to_string().contains("Failed to parse scan list") {
-                                warn!("Scan list endpoint unavailable or JSON parsing failed ({}), assuming scan ID {} still exists", e, stored_scan_id);
-                                // Update verification timestamp
-                                self.update_scan_verification_time().await;
-                                // Update inner state with the existing scan ID
-                                {
-                                    let mut inner = self.inner.lock().await;
-                                    inner.scan_id = Some(stored_scan_id);
-                                    inner.scan_active = true;
-                                }
-                                return Ok(());
-                            } else {
-                                error!(
-                                    "Failed to verify existing scan ID {}: {}",
-                                    stored_scan_id, e
-                                );
-                                warn!("Unable to verify scan ID, forcing re-registration");
-                                self.metadata_storage
-                                    .remove_scan_id(scan_name)
-                                    .map_err(|e| {
-                                        ScannerError::StoreError(format!(
-                                            "Failed to remove scan ID: {:?}",
-                                            e
-                                        ))
-                                    })?;
-                                info!("Forcing scan registration due to verification failure");
-                            }
-                        }
-                    }
-                } else {
-                    debug!("Skipping scan ID verification (last verified less than 4 hours ago)");
-                    // Update inner state with the existing scan ID without verification
-                    {
-                        let mut inner = self.inner.lock().await;
-                        inner.scan_id = Some(stored_scan_id);
-                        inner.scan_active = true;
-                    }
-                    return Ok(());
-                }
-            }
-            Ok(None) => {
-                info!("No existing scan ID found in database for scan name: '{}', proceeding with new registration", scan_name);
-            }
-            Err(e) => {
-                error!("Failed to get scan ID from database: {:?}", e);
-                info!("Database error, proceeding with new registration");
-            }
-        }
-
-        // Create the ErgoTree and serialize it with ByteArrayConstant wrapper
-        // This matches the Scala pattern: ByteArrayConstant(ErgoTreeSerializer.DefaultSerializer.serializeErgoTree(script))
-        let serialized_contract_bytes = {
-            let tree: ErgoTree = AddressEncoder::new(NetworkPrefix::Mainnet)
-                .parse_address_from_str(reserve_contract_p2s)
-                .unwrap()
-                .script()
-                .unwrap();
-            
-            // Get raw ErgoTree bytes
-            let ergo_tree_bytes = tree.sigma_serialize_bytes();
-            
-            // Create ByteArrayConstant wrapper and serialize it
-            // This matches what the Ergo node expects for scan registration
-            let byte_array_constant = ergo_lib::ergotree_ir::mir::constant::Constant::from(ergo_tree_bytes);
-            byte_array_constant.sigma_serialize_bytes()
-        };
-
-        let contract_bytes_hex = hex::encode(&serialized_contract_bytes);
-
-        // Register new scan
-        let scan_payload = serde_json::json!({
-            "scanName": scan_name,
-            "walletInteraction": "shared",
-            "trackingRule": {
-                "predicate": "contains",
-                "register": "R1",
-                "value": contract_bytes_hex
-            },
-            "removeOffchain": false
-        });
-
-        // Log scan registration (INFO level) and JSON payload (DEBUG level)
-        info!("Registering new reserve scan with name: {}", scan_name);
-        debug!("Reserve scan registration JSON payload: {}", scan_payload);
-
-        let url = format!("{}/scan/register", self.config.node_url);
-
-        // Create request builder and log request details
-        let request_builder = self
-            .request_builder(reqwest::Method::POST, &url)
-            .json(&scan_payload);
-
-        // Log exact HTTP request details
-        info!("Sending HTTP POST request to Ergo node: {}", url);
-        info!("Request headers: API key present: {}", self.config.api_key.is_some());
-        info!("Request body (JSON): {}", scan_payload);
-        debug!("Sending scan registration request to: {}", url);
-        debug!(
-            "Request headers include: {}",
-            if self.config.api_key.is_some() {
-                "API key"
-            } else {
-                "NO API key"
-            }
-        );
-
-        let response = request_builder.send().await.map_err(|e| {
-            error!("HTTP request failed: {}", e);
-            error!(
-                "Request details - URL: {}, Method: POST, Headers: API key present: {}",
-                url,
-                self.config.api_key.is_some()
-            );
-            ScannerError::HttpError(format!("Failed to register scan: {}", e))
-        })?;
-
-        // Log response details
-        let status = response.status();
-        debug!("Response status: {}", status);
-        debug!("Response headers: {:?}", response.headers());
-
-        if !status.is_success() {
-            // Try to read response body for more details
-            let response_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unable to read response body".to_string());
-            error!(
-                "Scan registration failed with status: {}. Response body: {}",
-                status, response_text
-            );
-            error!("Full request details:");
-            error!("  URL: {}", url);
-            error!("  Method: POST");
-            error!("  API key present: {}", self.config.api_key.is_some());
-            error!("  Payload: {}", scan_payload);
-            error!("  Response status: {}", status);
-            error!("  Response body: {}", response_text);
-            return Err(ScannerError::NodeError(format!(
-                "Scan registration failed with status: {}. Response: {}",
-                status, response_text
-            )));
-        }
-
-        let result: serde_json::Value = response.json().await.map_err(|e| {
-            error!("Failed to parse scan registration response JSON: {}", e);
-            ScannerError::JsonError(format!("Failed to parse scan registration response: {}", e))
-        })?;
-
-        debug!(
-            "Scan registration successful response: {}",
-            serde_json::to_string_pretty(&result)
-                .unwrap_or_else(|_| "Unable to format JSON".to_string())
-        );
-
-        let scan_id = result["scanId"]
-            .as_i64()
-            .and_then(|v| i32::try_from(v).ok());
-
-        // Store scan ID in database and update inner state
-        if let Some(scan_id) = scan_id {
-            self.metadata_storage
-                .store_scan_id(scan_name, scan_id)
-                .map_err(|e| {
-                    ScannerError::StoreError(format!("Failed to store scan ID: {:?}", e))
-                })?;
-
-            // Update inner state with the new scan ID
-            {
-                let mut inner = self.inner.lock().await;
-                inner.scan_id = Some(scan_id);
-                inner.scan_active = true;
-            }
-
-            info!("Registered and stored reserve scan with ID: {}", scan_id);
-        } else {
-            error!(
-                "Failed to get scan ID from registration response. Response was: {}",
-                result
-            );
-            return Err(ScannerError::Generic(
-                "Failed to get scan ID from registration response".to_string(),
-            ));
+    ///
+    /// Reuses the scan ID stored under the scan name while the node still lists it.
+    pub async fn register_reserve_scan(&self) -> Result<(), ScannerError> {
+        if self.config.reserve_contract_p2s.is_none() {
+            return Err(ScannerError::Generic("Reserve contract P2S not configured".to_string()));
         }
+        self.scan.register().await.map(|_| ())
+    }
 
-        Ok(())
+    /// Verify that a scan ID still exists on the Ergo node
+    pub async fn verify_scan_exists(&self, scan_id: i32) -> Result<bool, ScannerError> {
+        self.scan.verify_scan_exists(scan_id).await
     }
 
-    /// Check if scan verification is needed (every 4 hours)
-    async fn should_verify_scan(&self) -> bool {
-        let inner = self.inner.lock().await;
-        match inner.last_scan_verification {
-            Some(last_verification) => {
-                let now = std::time::SystemTime::now();
-                let duration_since_last = now.duration_since(last_verification).unwrap_or_default();
-                duration_since_last >= std::time::Duration::from_secs(4 * 60 * 60) // 4 hours
-            }
-            None => true, // Never verified before
-        }
+    /// Get unspent boxes from registered scan
+    pub async fn get_scan_boxes(&self) -> Result<Vec<ScanBox>, ScannerError> {
+        self.scan.fetch_boxes(None).await
     }
 
-    /// Update the last scan verification timestamp
-    async fn update_scan_verification_time(&self) {
-        let mut inner = self.inner.lock().await;
-        inner.last_scan_verification = Some(std::time::SystemTime::now());
+    /// Parse reserve box into ExtendedReserveInfo
+    pub fn parse_reserve_box(
+        &self,
+        scan_box: &ScanBox,
+    ) -> Result<ExtendedReserveInfo, ScannerError> {
+        self.scan.spec().parse_box(scan_box)
     }
 
-    /// Verify that a scan ID still exists on the Ergo node
-    pub async fn verify_scan_exists(&self, scan_id: i32) -> Result<bool, ScannerError> {
-        let url = format!("{}/scan/listAll", self.config.node_url);
-        debug!("Verifying scan exists - URL: {}", url);
-        debug!("Looking for scan ID: {}", scan_id);
-        info!("Sending HTTP GET request to Ergo node: {}", url);
-        info!("Looking for scan ID: {}", scan_id);
-
-        let response = self
-            .request_builder(reqwest::Method::GET, &url)
-            .send()
-            .await;
+    /// Store a reserve parsed from a scan box, returning its box ID
+    fn apply_reserve(&self, reserve_info: ExtendedReserveInfo) -> String {
+        debug!("Successfully parsed reserve box: box_id={}, owner={}, collateral={}",
+              reserve_info.box_id, reserve_info.owner_pubkey, reserve_info.base_info.collateral_amount);
 
-        let response = match response {
-            Ok(resp) => resp,
-            Err(e) => {
-                error!("Failed to send scan list request: {}", e);
-                // If we can't even connect, assume the scan exists to avoid re-registration
+        // Update in-memory tracker
+        if let Err(e) = self.reserve_tracker.update_reserve(reserve_info.clone()) {
+            warn!("Failed to update reserve {}: {}", reserve_info.box_id, e);
+        } else {
+            // Persist to database
+            if let Err(e) = self.reserve_storage.store_reserve(&reserve_info) {
                 warn!(
-                    "Network error connecting to scan list endpoint, assuming scan ID {} exists",
-                    scan_id
+                    "Failed to persist reserve {} to database: {:?}",
+                    reserve_info.box_id, e
                 );
-                return Ok(true);
+            } else {
+                info!("Updated and persisted reserve: {}", reserve_info.box_id);
             }
-        };
-
-        let status = response.status();
-        debug!("Scan list response status: {}", status);
-
-        // If scan list endpoint is not available (400/404), assume scan exists
-        // This handles nodes that don't support scan listing
-        if status == 400 || status == 404 {
-            info!(
-                "Scan list endpoint not available (status: {}), assuming scan ID {} exists",
-                status, scan_id
-            );
-            return Ok(true);
-        }
-
-        if !status.is_success() {
-            // Try to read response body for more details
-            let response_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unable to read response body".to_string());
-            // Log as warning instead of error since we're handling this gracefully
-            warn!(
-                "Scan list request failed with status: {}. Response body: {}",
-                status, response_text
-            );
-            // For any other non-success status, assume scan exists to prevent re-registration
-            warn!("Scan list request failed (status: {}), assuming scan ID {} exists to prevent re-registration", status, scan_id);
-            return Ok(true);
         }
+        reserve_info.box_id
+    }
 
-        let scans: serde_json::Value = response.json().await.map_err(|e| {
-            error!("Failed to parse scan list JSON: {}", e);
-            ScannerError::JsonError(format!("Failed to parse scan list: {}", e))
+    /// Seed the reserve tracker with every unspent reserve box of `tracker_nft_id`
+    ///
+    /// Queries the explorer API at `explorer_url` for unspent boxes of the
+    /// reserve contract, so that reserves created before the node scan was
+    /// registered are tracked too. Paging follows the backfill configuration.
+    /// Returns the number of reserves found.
+    pub async fn discover_reserves(
+        &self,
+        explorer_url: &str,
+        tracker_nft_id: &str,
+    ) -> Result<usize, ScannerError> {
+        let ergo_trees = self.scan.spec().reserve_ergo_trees().map_err(|_| {
+            ScannerError::Generic("Reserve discovery needs a valid reserve contract P2S".to_string())
         })?;
+        info!("Discovering reserves of tracker NFT {} via explorer {}", tracker_nft_id, explorer_url);
 
-        debug!(
-            "Scan list response: {}",
-            serde_json::to_string_pretty(&scans)
-                .unwrap_or_else(|_| "Unable to format JSON".to_string())
-        );
+        let mut discovered = 0;
+        for ergo_tree in &ergo_trees {
+            discovered += self.discover_reserves_of(explorer_url, tracker_nft_id, ergo_tree).await?;
+        }
 
-        // Check if our scan ID exists in the list
-        if let Some(scans_array) = scans.as_array() {
-            debug!("Found {} scans in list", scans_array.len());
-            for scan in scans_array {
-                if let Some(id) = scan["scanId"].as_i64() {
-                    debug!("Checking scan ID: {} against target: {}", id, scan_id);
-                    if id == scan_id as i64 {
-                        debug!("Scan ID {} found in scan list", scan_id);
-                        return Ok(true);
-                    }
-                } else {
-                    debug!("Scan entry missing scanId: {:?}", scan);
+        info!("Explorer discovery found {} reserves", discovered);
+        Ok(discovered)
+    }
+
+    // Page through the explorer's unspent boxes of one contract version
+    async fn discover_reserves_of(
+        &self,
+        explorer_url: &str,
+        tracker_nft_id: &str,
+        ergo_tree: &str,
+    ) -> Result<usize, ScannerError> {
+        let page_size = self.backfill_config.page_size.max(1);
+        let mut offset = 0;
+        let mut discovered = 0;
+        loop {
+            let url = format!(
+                "{}/api/v1/boxes/unspent/byErgoTree/{}?offset={}&limit={}",
+                explorer_url.trim_end_matches('/'),
+                ergo_tree,
+                offset,
+                page_size
+            );
+            let response = self
+                .scan
+                .client()
+                .get(&url)
+                .send()
+                .await
+                .map_err(|e| ScannerError::HttpError(format!("Failed to fetch {}: {}", url, e)))?;
+            let status = response.status();
+            if !status.is_success() {
+                return Err(ScannerError::NodeError(format!(
+                    "Explorer request to {} failed with status: {}",
+                    url, status
+                )));
+            }
+            let page: serde_json::Value = response
+                .json()
+                .await
+                .map_err(|e| ScannerError::HttpError(format!("Failed to parse response from {}: {}", url, e)))?;
+            let items = page
+                .get("items")
+                .and_then(|items| items.as_array())
+                .cloned()
+                .unwrap_or_default();
+
+            for scan_box in items.iter().filter_map(parse_indexed_box) {
+                let reserve = match self.parse_reserve_box(&scan_box) {
+                    Ok(reserve) if reserve.base_info.tracker_nft_id.eq_ignore_ascii_case(tracker_nft_id) => reserve,
+                    _ => continue,
+                };
+                let box_id = self.apply_reserve(reserve);
+                if let Ok(mut discovered_reserves) = self.discovered_reserves.lock() {
+                    discovered_reserves.insert(box_id);
                 }
+                discovered += 1;
             }
-        } else {
-            debug!("Scan list is not an array or is empty");
+
+            if (items.len() as u64) < page_size {
+                break;
+            }
+            offset += page_size;
+            tokio::time::sleep(self.backfill_config.request_delay).await;
         }
+        Ok(discovered)
+    }
+
+    // Whether a reserve was found by explorer discovery rather than the scan
+    fn is_discovered(&self, box_id: &str) -> bool {
+        self.discovered_reserves
+            .lock()
+            .map(|discovered| discovered.contains(box_id))
+            .unwrap_or(false)
+    }
 
-        debug!("Scan ID {} not found in scan list", scan_id);
-        Ok(false)
+    /// Contract ErgoTree of a spent reserve as hex, if a contract is configured
+    ///
+    /// A reserve continues under the contract version that locked it, so its
+    /// successor is looked for under that version.
+    fn reserve_ergo_tree(&self, spent_box: &serde_json::Value) -> Option<String> {
+        let trees = self.scan.spec().reserve_ergo_trees().ok()?;
+        let spent_tree = spent_box.get("ergoTree").and_then(|tree| tree.as_str());
+        spent_tree
+            .and_then(|spent_tree| trees.iter().find(|tree| tree.eq_ignore_ascii_case(spent_tree)))
+            .or(trees.first())
+            .cloned()
     }
 
-    /// Get unspent boxes from registered scan
-    pub async fn get_scan_boxes(&self) -> Result<Vec<ScanBox>, ScannerError> {
-        let scan_id = {
-            let inner = self.inner.lock().await;
-            inner.scan_id
-        };
+    // GET a JSON document from the node's indexed blockchain API
+    async fn get_blockchain_json(&self, path: &str) -> Result<serde_json::Value, ScannerError> {
+        self.node_json(reqwest::Method::GET, &format!("blockchain/{}", path), None).await
+    }
+
+    // Box by ID, spent or not, from the explorer or the node's index
+    async fn get_indexed_box(&self, box_id: &str) -> Result<serde_json::Value, ScannerError> {
+        match self.scan.explorer() {
+            Some(explorer) => explorer.box_by_id(box_id).await,
+            None => self.get_blockchain_json(&format!("box/byId/{}", box_id)).await,
+        }
+    }
 
-        let scan_id =
-            scan_id.ok_or_else(|| ScannerError::Generic("Scan not registered".to_string()))?;
+    // Transaction by ID from the explorer or the node's index
+    async fn get_indexed_transaction(&self, tx_id: &str) -> Result<serde_json::Value, ScannerError> {
+        match self.scan.explorer() {
+            Some(explorer) => explorer.transaction_by_id(tx_id).await,
+            None => self.get_blockchain_json(&format!("transaction/byId/{}", tx_id)).await,
+        }
+    }
 
-        let url = format!("{}/scan/unspentBoxes/{}", self.config.node_url, scan_id);
+    /// Unspent box by ID, as node box JSON
+    pub async fn get_unspent_box(&self, box_id: &str) -> Result<serde_json::Value, ScannerError> {
+        if box_id.len() != 64 || hex::decode(box_id).is_err() {
+            return Err(ScannerError::InvalidReserveBox(format!("Invalid box ID {}", box_id)));
+        }
+        self.node_json(reqwest::Method::GET, &format!("utxo/byId/{}", box_id), None).await
+    }
 
-        info!("Sending HTTP GET request to Ergo node: {}", url);
-        info!("Requesting unspent boxes for scan ID: {}", scan_id);
+    /// Unspent boxes of an address, as node box JSON
+    ///
+    /// Needs the node's extra indexing, like the other blockchain lookups.
+    pub async fn get_unspent_boxes_by_address(&self, address: &str) -> Result<Vec<serde_json::Value>, ScannerError> {
+        let boxes = self
+            .node_json(reqwest::Method::POST, "blockchain/box/unspent/byAddress", Some(address.to_string()))
+            .await?;
+        serde_json::from_value(boxes)
+            .map_err(|e| ScannerError::HttpError(format!("Unexpected unspent boxes response: {}", e)))
+    }
 
-        let response = self
-            .request_builder(reqwest::Method::GET, &url)
+    // Send a request to the node and parse the JSON response
+    async fn node_json(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<String>,
+    ) -> Result<serde_json::Value, ScannerError> {
+        let url = self.scan.endpoint(path);
+        let mut request = self.scan.request_builder(method, &url);
+        if let Some(body) = body {
+            request = request.header(reqwest::header::CONTENT_TYPE, "text/plain").body(body);
+        }
+        let response = request
             .send()
             .await
-            .map_err(|e| ScannerError::HttpError(format!("Failed to fetch scan boxes: {}", e)))?;
+            .map_err(|e| ScannerError::HttpError(format!("Failed to fetch {}: {}", url, e)))?;
 
         let status = response.status();
         if !status.is_success() {
-            let response_text = response.text().await.unwrap_or_else(|_| "Unable to read response".to_string());
-            error!("Failed to get scan boxes with status: {}", status);
-            error!("Response body: {}", response_text);
             return Err(ScannerError::NodeError(format!(
-                "Failed to get scan boxes with status: {}",
-                status
+                "Request to {} failed with status: {}",
+                url, status
             )));
         }
 
-        let response_text = response.text().await.map_err(|e| {
-            error!("Failed to read response text: {}", e);
-            ScannerError::HttpError(format!("Failed to read response text: {}", e))
-        })?;
-
-        // Parse the JSON response as the API format first
-        let api_boxes: Vec<ApiScanBox> = serde_json::from_str(&response_text)
-            .map_err(|e| {
-                error!("Failed to parse API scan boxes JSON: {}", e);
-                error!("Raw response was: {}", response_text);
-                ScannerError::JsonError(format!("Failed to parse API scan boxes: {}", e))
-            })?;
-
-        // Convert API boxes to our internal ScanBox format
-        let mut scan_boxes = Vec::new();
-        for api_box in api_boxes {
-            let converted_box = ScanBox {
-                box_id: api_box.inner_box.box_id,
-                value: api_box.inner_box.value,
-                ergo_tree: api_box.inner_box.ergo_tree,
-                creation_height: api_box.inner_box.creation_height,
-                transaction_id: api_box.inner_box.transaction_id,
-                additional_registers: api_box.inner_box.additional_registers,
-                assets: api_box.inner_box.assets.into_iter().map(|a| BoxAsset {
-                    token_id: a.token_id,
-                    amount: a.amount,
-                }).collect(),
-            };
-            scan_boxes.push(converted_box);
-        }
-
-        info!("Found {} boxes from scan (converted from API format)", scan_boxes.len());
-        for (i, box_data) in scan_boxes.iter().enumerate() {
-            info!("Box {}: ID={}, value={}, creation_height={}",
-                  i + 1, box_data.box_id, box_data.value, box_data.creation_height);
-            info!("  Registers: {:?}", box_data.additional_registers);
-            info!("  Assets: {:?}", box_data.assets);
-        }
-
-        Ok(scan_boxes)
+        response
+            .json()
+            .await
+            .map_err(|e| ScannerError::HttpError(format!("Failed to parse response from {}: {}", url, e)))
     }
 
-    /// Parse reserve box into ExtendedReserveInfo
-    pub fn parse_reserve_box(
+    /// Follow the transaction that spent a reserve box and classify the spend
+    ///
+    /// Returns `Ok(None)` if the node does not report the box as spent.
+    pub async fn resolve_reserve_spend(
         &self,
-        scan_box: &ScanBox,
-    ) -> Result<ExtendedReserveInfo, ScannerError> {
-        let box_id = scan_box.box_id.clone();
-        let value = scan_box.value;
-        let creation_height = scan_box.creation_height;
-
-        // Extract owner public key from R4 register
-        let owner_pubkey_raw = scan_box
-            .additional_registers
-            .get("R4")
-            .ok_or_else(|| {
-                ScannerError::InvalidReserveBox(format!("Missing R4 register in box {}", box_id))
-            })?
-            .clone();
+        reserve: &ExtendedReserveInfo,
+    ) -> Result<Option<ReserveTransition>, ScannerError> {
+        let box_id = node_box_id(&reserve.box_id).ok_or_else(|| {
+            ScannerError::InvalidReserveBox(format!("Invalid reserve box ID {}", reserve.box_id))
+        })?;
 
-        // Strip the 0x07 prefix if present (GroupElement type identifier from Ergo registers)
-        let owner_pubkey = if owner_pubkey_raw.starts_with("07") && owner_pubkey_raw.len() >= 66 {
-            // Extract the actual 33-byte public key (66 hex chars) after the 0x07 prefix
-            owner_pubkey_raw[2..].to_string()
-        } else {
-            // Use as-is if no prefix or wrong length
-            owner_pubkey_raw
+        let spent_box = self.get_indexed_box(&box_id).await?;
+        let Some(spending_tx_id) = spent_box.get("spentTransactionId").and_then(|id| id.as_str()) else {
+            return Ok(None);
         };
 
-        // Extract tracker NFT ID from R6 register (required according to spec)
-        let tracker_nft_id_raw = scan_box
-            .additional_registers
-            .get("R6")
-            .ok_or_else(|| {
-                ScannerError::InvalidReserveBox(format!("Missing R6 register in box {}", box_id))
-            })?
-            .clone();
-
-        // Create extended reserve info
-        // Decode the hex-encoded public key to actual bytes
-        let owner_pubkey_bytes = hex::decode(&owner_pubkey)
-            .map_err(|_| ScannerError::InvalidReserveBox(format!("Invalid hex in owner pubkey for box {}", box_id)))?;
-
-        // Decode the hex-encoded tracker NFT ID to actual bytes
-        // R6 contains a Coll[Byte] value with Ergo serialization prefix: 0e20 (type + length)
-        // We need to strip the first 2 bytes (4 hex chars) to get the actual data
-        let tracker_nft_hex = if tracker_nft_id_raw.len() >= 4 {
-            &tracker_nft_id_raw[4..]
-        } else {
-            tracker_nft_id_raw.as_str()
-        };
-        let tracker_nft_id_bytes = hex::decode(tracker_nft_hex)
-            .map_err(|_| ScannerError::InvalidReserveBox(format!("Invalid hex in tracker NFT ID for box {}", box_id)))?;
+        let transaction = self.get_indexed_transaction(spending_tx_id).await?;
+        let spending_height = transaction.get("inclusionHeight").and_then(|height| height.as_u64());
+        let outputs: Vec<ScanBox> = transaction
+            .get("outputs")
+            .and_then(|outputs| outputs.as_array())
+            .map(|outputs| outputs.iter().filter_map(parse_indexed_box).collect())
+            .unwrap_or_default();
+
+        let mut transition = classify_spend(
+            reserve,
+            self.reserve_ergo_tree(&spent_box).as_deref(),
+            spending_tx_id,
+            spending_height,
+            &outputs,
+        );
+        if transition.kind == ReserveSpendKind::Redemption {
+            transition.receiver_pubkey = self.redemption_receiver(&transaction, &box_id, reserve).await;
+        }
+        Ok(Some(transition))
+    }
 
-        // Validate that the tracker NFT ID is exactly 32 bytes (the actual tracker NFT ID)
-        if tracker_nft_id_bytes.len() != 32 {
-            return Err(ScannerError::InvalidReserveBox(format!(
-                "Invalid tracker NFT ID length in box {}: expected 32 bytes, got {}",
-                box_id,
-                tracker_nft_id_bytes.len()
-            )));
+    // Receiver of a redemption spending reserve box `box_id`
+    //
+    // Only redemptions that read the box holding the reserve's tracker NFT as a
+    // data input count: the contract checks the tracker's signature over the
+    // note against that box, so the receiver in the reserve input's context
+    // extension (var #1) is the one the tracker signed for. Spending proofs are
+    // not in the indexed transaction, so the extension is read from the block;
+    // the explorer serves no extensions, so without a node there is no receiver.
+    async fn redemption_receiver(
+        &self,
+        transaction: &serde_json::Value,
+        box_id: &str,
+        reserve: &ExtendedReserveInfo,
+    ) -> Option<String> {
+        if self.scan.explorer().is_some() {
+            return None;
+        }
+        let data_inputs = transaction.get("dataInputs").and_then(|inputs| inputs.as_array())?;
+        let mut reads_tracker = false;
+        for data_input in data_inputs {
+            let Some(data_box_id) = data_input.get("boxId").and_then(|id| id.as_str()) else {
+                continue;
+            };
+            let data_box = match data_input.get("assets") {
+                Some(_) => data_input.clone(),
+                None => self.get_indexed_box(data_box_id).await.ok()?,
+            };
+            let first_token = data_box
+                .get("assets")
+                .and_then(|assets| assets.get(0))
+                .and_then(|asset| asset.get("tokenId"))
+                .and_then(|id| id.as_str());
+            if first_token.is_some_and(|id| id.eq_ignore_ascii_case(&reserve.base_info.tracker_nft_id)) {
+                reads_tracker = true;
+                break;
+            }
+        }
+        if !reads_tracker {
+            return None;
         }
 
-        let reserve_info = ExtendedReserveInfo::new(
-            box_id.as_bytes(),
-            &owner_pubkey_bytes,
-            value,
-            Some(&tracker_nft_id_bytes),
-            creation_height,
-        );
+        let block_id = transaction.get("blockId").and_then(|id| id.as_str())?;
+        let tx_id = transaction.get("id").and_then(|id| id.as_str())?;
+        let block = self
+            .node_json(reqwest::Method::GET, &format!("blocks/{}/transactions", block_id), None)
+            .await
+            .map_err(|e| warn!("Failed to fetch transactions of block {}: {}", block_id, e))
+            .ok()?;
+        let extension = block
+            .get("transactions")?
+            .as_array()?
+            .iter()
+            .find(|tx| tx.get("id").and_then(|id| id.as_str()) == Some(tx_id))?
+            .get("inputs")?
+            .as_array()?
+            .iter()
+            .find(|input| input.get("boxId").and_then(|id| id.as_str()) == Some(box_id))?
+            .get("spendingProof")?
+            .get("extension")?;
+
+        // var #0: SByte action * 10 + reserve output index
+        let action = extension.get("0").and_then(|var| var.as_str())?;
+        let action = hex::decode(action.strip_prefix("02")?).ok()?;
+        if action.len() != 1 || (action[0] as i8) / 10 != REDEMPTION_ACTION as i8 {
+            return None;
+        }
+        // var #1: receiver as a GroupElement
+        let receiver = extension.get("1").and_then(|var| var.as_str())?.strip_prefix("07")?;
+        (receiver.len() == 66 && hex::decode(receiver).is_ok()).then(|| receiver.to_lowercase())
+    }
 
-        Ok(reserve_info)
+    // Resolve and record the spend of a reserve box that left the scan
+    async fn record_reserve_spend(&self, reserve: &ExtendedReserveInfo) -> Option<ReserveTransition> {
+        match self.resolve_reserve_spend(reserve).await {
+            Ok(Some(transition)) => {
+                info!(
+                    "Reserve {} spent in {} ({:?}), successor: {:?}",
+                    reserve.box_id, transition.spending_tx_id, transition.kind, transition.successor_box_id
+                );
+                if let Err(e) = self.reserve_storage.record_transition(&transition) {
+                    warn!("Failed to record spend of reserve {}: {:?}", reserve.box_id, e);
+                }
+                Some(transition)
+            }
+            Ok(None) => {
+                debug!("Node does not report reserve {} as spent", reserve.box_id);
+                None
+            }
+            Err(e) => {
+                warn!("Failed to resolve spend of reserve {}: {}", reserve.box_id, e);
+                None
+            }
+        }
     }
 
     /// Process scan boxes and update reserve tracker
     pub async fn process_scan_boxes(&self) -> Result<(), ScannerError> {
         info!("Starting to process scan boxes...");
-        let scan_boxes = self.get_scan_boxes().await?;
-        info!("Retrieved {} scan boxes to process", scan_boxes.len());
-
-        let mut current_box_ids = Vec::new();
-
-        for scan_box in &scan_boxes {
-            debug!("Processing scan box: ID={}, value={}, registers={:?}",
-                  scan_box.box_id, scan_box.value, scan_box.additional_registers);
+        // Reserves known before this scan, to diff against
+        let previous_reserves = self.reserve_tracker.get_all_reserves();
 
-            match self.parse_reserve_box(scan_box) {
-                Ok(reserve_info) => {
-                    debug!("Successfully parsed reserve box: box_id={}, owner={}, collateral={}",
-                          reserve_info.box_id, reserve_info.owner_pubkey, reserve_info.base_info.collateral_amount);
-                    current_box_ids.push(reserve_info.box_id.clone());
-
-                    // Update in-memory tracker
-                    if let Err(e) = self.reserve_tracker.update_reserve(reserve_info.clone()) {
-                        warn!("Failed to update reserve {}: {}", scan_box.box_id, e);
-                    } else {
-                        // Persist to database
-                        if let Err(e) = self.reserve_storage.store_reserve(&reserve_info) {
-                            warn!(
-                                "Failed to persist reserve {} to database: {:?}",
-                                scan_box.box_id, e
-                            );
-                        } else {
-                            info!("Updated and persisted reserve: {}", scan_box.box_id);
-                        }
-                    }
-                }
-                Err(e) => {
-                    warn!("Failed to parse reserve box {}: {} - registers: {:?}", scan_box.box_id, e, scan_box.additional_registers);
-                }
-            }
-        }
+        let scanned_reserves = self.scan.process(None).await?;
+        info!("Retrieved {} valid reserve boxes to process", scanned_reserves.len());
+        let current_box_ids: Vec<String> = scanned_reserves
+            .into_iter()
+            .map(|reserve_info| self.apply_reserve(reserve_info))
+            .collect();
+        self.inner.lock().await.progress.boxes_processed += current_box_ids.len() as u64;
 
         // Remove reserves that are no longer in the scan
         // NOTE: Disabled for testing to prevent manually-inserted reserves from being deleted
@@ -914,10 +1193,20 @@ impl ServerState {
 
         // Only remove reserves if we actually found VALID boxes in the scan.
         // If no valid reserves were parsed (e.g., all failed validation), don't remove manually-inserted reserves.
+        let mut transitions = Vec::new();
         if !current_box_ids.is_empty() {
             for reserve in all_reserves {
                 if !current_box_ids.contains(&reserve.box_id) {
+                    let transition = self.record_reserve_spend(&reserve).await;
+                    // Discovered reserves predate the scan, so only a spend removes them
+                    if transition.is_none() && self.is_discovered(&reserve.box_id) {
+                        debug!("Keeping discovered reserve {} outside the scan", reserve.box_id);
+                        continue;
+                    }
                     info!("Removing spent reserve: {} (not found in current scan)", reserve.box_id);
+                    if let Some(transition) = transition {
+                        transitions.push(transition);
+                    }
                     // Remove from in-memory tracker
                     if let Err(e) = self.reserve_tracker.remove_reserve(&reserve.box_id) {
                         warn!("Failed to remove reserve {}: {}", reserve.box_id, e);
@@ -938,8 +1227,29 @@ impl ServerState {
             info!("Scan returned 0 boxes, skipping reserve removal to preserve manually-inserted reserves");
         }
 
+        if !current_box_ids.is_empty() {
+            let current_reserves: Vec<ExtendedReserveInfo> = current_box_ids
+                .iter()
+                .filter_map(|box_id| self.reserve_tracker.get_reserve(box_id).ok())
+                .collect();
+            let current_height = self.inner.lock().await.current_height;
+            let events = derive_reserve_events(&previous_reserves, &current_reserves, &transitions, current_height);
+            if !events.is_empty() {
+                match self.reserve_storage.enqueue_reserve_events(events) {
+                    Ok(queued) => {
+                        for event in queued {
+                            debug!("Derived reserve event: {:?}", event);
+                            // No subscribers simply means nobody is listening for events
+                            let _ = self.event_tx.send(event);
+                        }
+                    }
+                    Err(e) => warn!("Failed to queue reserve events: {:?}", e),
+                }
+            }
+        }
+
         debug!("Finished processing scan boxes: {} processed, {} in tracker after processing",
-              scan_boxes.len(), self.reserve_tracker.get_all_reserves().len());
+              current_box_ids.len(), self.reserve_tracker.get_all_reserves().len());
 
         Ok(())
     }
@@ -959,12 +1269,74 @@ pub async fn start_scanner(state: ServerState) -> Result<(), ScannerError> {
     Ok(())
 }
 
+/// Start the scanner in background, stopping when a shutdown signal is received
+///
+/// The returned handle completes once the loop has exited, so callers can wait
+/// for an in-progress scan to finish before tearing down storage.
+pub async fn start_scanner_with_shutdown(
+    state: ServerState,
+    shutdown_rx: tokio::sync::broadcast::Receiver<()>,
+) -> Result<tokio::task::JoinHandle<Result<(), ScannerError>>, ScannerError> {
+    let state = Arc::new(state);
+    Ok(tokio::spawn(reserve_scanner_loop_until(state, Some(shutdown_rx))))
+}
+
 /// Create a scanner with default configuration
 pub fn create_default_scanner() -> Result<ServerState, ScannerError> {
     let config = NodeConfig::default();
     ServerState::new(config)
 }
 
+// Parse a box from the indexed blockchain API or the explorer
+//
+// Registers are reported either as serialized hex strings or as objects
+// carrying a `serializedValue` field.
+pub(crate) fn parse_indexed_box(value: &serde_json::Value) -> Option<ScanBox> {
+    let additional_registers = value
+        .get("additionalRegisters")
+        .and_then(|registers| registers.as_object())
+        .map(|registers| {
+            registers
+                .iter()
+                .filter_map(|(name, register)| {
+                    let serialized = register
+                        .as_str()
+                        .or_else(|| register.get("serializedValue").and_then(|v| v.as_str()))?;
+                    Some((name.clone(), serialized.to_string()))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(ScanBox {
+        box_id: value.get("boxId")?.as_str()?.to_string(),
+        value: value.get("value")?.as_u64()?,
+        ergo_tree: value.get("ergoTree")?.as_str()?.to_string(),
+        creation_height: value.get("creationHeight").and_then(|h| h.as_u64()).unwrap_or(0),
+        transaction_id: value
+            .get("transactionId")
+            .and_then(|id| id.as_str())
+            .unwrap_or_default()
+            .to_string(),
+        additional_registers,
+        assets: value
+            .get("assets")
+            .and_then(|assets| assets.as_array())
+            .map(|assets| {
+                assets
+                    .iter()
+                    .filter_map(|asset| {
+                        Some(BoxAsset {
+                            token_id: asset.get("tokenId")?.as_str()?.to_string(),
+                            amount: asset.get("amount")?.as_u64()?,
+                        })
+                    })
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
 /// Ergo box representation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErgoBox {
@@ -1012,7 +1384,7 @@ pub struct BoxAsset {
 }
 
 /// Events related to reserve activity
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ReserveEvent {
     /// A new reserve was created
     ReserveCreated {
@@ -1024,38 +1396,106 @@ pub enum ReserveEvent {
     /// An existing reserve was topped up
     ReserveToppedUp {
         box_id: String,
+        owner_pubkey: String,
         additional_collateral: u64,
         height: u64,
     },
     /// A redemption occurred from a reserve
     ReserveRedeemed {
         box_id: String,
+        owner_pubkey: String,
         redeemed_amount: u64,
         height: u64,
+        /// Receiver of the redeemed note, if the spend read the reserve's tracker box
+        #[serde(default)]
+        recipient_pubkey: Option<String>,
+        /// Tracker NFT the reserve names in R6
+        #[serde(default)]
+        tracker_nft_id: Option<String>,
     },
     /// A reserve was spent/closed
-    ReserveSpent { box_id: String, height: u64 },
+    ReserveSpent {
+        box_id: String,
+        owner_pubkey: String,
+        height: u64,
+    },
+}
+
+impl ReserveEvent {
+    /// Block height the event happened at
+    pub fn height(&self) -> u64 {
+        match self {
+            ReserveEvent::ReserveCreated { height, .. }
+            | ReserveEvent::ReserveToppedUp { height, .. }
+            | ReserveEvent::ReserveRedeemed { height, .. }
+            | ReserveEvent::ReserveSpent { height, .. } => *height,
+        }
+    }
+}
+
+/// A reserve event held in the intake queue until it has been processed
+///
+/// Events are queued per block, keyed by the event's height and their
+/// position among the events queued for that block, so they are processed
+/// in chain order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QueuedReserveEvent {
+    /// Block height of the event
+    pub height: u64,
+    /// Position among the events queued at that height
+    pub sequence: u32,
+    pub event: ReserveEvent,
+    /// Set once the event has been applied and recorded
+    pub processed: bool,
 }
 
 /// Default node configuration
+/// Node URL used when none is configured: a local node on the default mainnet API port
+pub const DEFAULT_NODE_URL: &str = "http://127.0.0.1:9053";
+
 impl Default for NodeConfig {
     fn default() -> Self {
         Self {
             start_height: None,
             reserve_contract_p2s: None,
-            node_url: "http://159.89.116.15:11088".to_string(), // Your Ergo node
+            node_url: DEFAULT_NODE_URL.to_string(),
             scan_name: Some("Basis Reserve Scanner".to_string()),
-            api_key: Some("hello".to_string()),
+            api_key: None,
         }
     }
 }
 
 /// Reserve scanner loop (background task)
 pub async fn reserve_scanner_loop(state: Arc<ServerState>) -> Result<(), ScannerError> {
+    reserve_scanner_loop_until(state, None).await
+}
+
+/// Sleep for `duration`, returning early with `true` if shutdown was signalled
+async fn wait_or_shutdown(
+    duration: Duration,
+    shutdown_rx: &mut Option<tokio::sync::broadcast::Receiver<()>>,
+) -> bool {
+    match shutdown_rx {
+        Some(rx) => tokio::select! {
+            _ = tokio::time::sleep(duration) => false,
+            _ = rx.recv() => true,
+        },
+        None => {
+            tokio::time::sleep(duration).await;
+            false
+        }
+    }
+}
+
+// Scanner loop body; a scan cycle in progress is always completed before
+// the shutdown signal is observed
+async fn reserve_scanner_loop_until(
+    state: Arc<ServerState>,
+    mut shutdown_rx: Option<tokio::sync::broadcast::Receiver<()>>,
+) -> Result<(), ScannerError> {
     info!("Starting reserve scanner background loop");
 
     let mut consecutive_failures = 0;
-    const MAX_CONSECUTIVE_FAILURES: u32 = 5;
 
     loop {
         // Update current height
@@ -1077,15 +1517,11 @@ pub async fn reserve_scanner_loop(state: Arc<ServerState>) -> Result<(), Scanner
                 }
                 
                 // Check if we have a valid scan ID before processing
-                let has_valid_scan = {
-                    let inner = state.inner.lock().await;
-                    inner.scan_id.is_some() && inner.scan_active
-                };
+                let has_valid_scan = state.scan.is_registered().await;
 
                 if !has_valid_scan {
                     warn!("Scanner has no valid scan ID, attempting to register scan...");
-                    let mut state_mut = state.as_ref().clone();
-                    match state_mut.register_reserve_scan().await {
+                    match state.register_reserve_scan().await {
                         Ok(()) => {
                             info!("Scan registration successful, resuming normal operation");
                             consecutive_failures = 0;
@@ -1093,13 +1529,30 @@ pub async fn reserve_scanner_loop(state: Arc<ServerState>) -> Result<(), Scanner
                         Err(e) => {
                             error!("Failed to register scan: {}", e);
                             consecutive_failures += 1;
-                            if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
-                                error!(
-                                    "Too many consecutive failures ({}), waiting before retry",
-                                    consecutive_failures
-                                );
-                                tokio::time::sleep(Duration::from_secs(60)).await;
-                                // Wait longer after many failures
+                            state.record_failure(&e).await;
+                        }
+                    }
+                } else if state.is_backfilling().await {
+                    // Catch up from the start height one batch at a time
+                    match state.backfill_next_batch(height).await {
+                        Ok(done) => {
+                            consecutive_failures = 0;
+                            state.record_success().await;
+                            if !done {
+                                // Throttle between batches instead of waiting a full cycle
+                                if wait_or_shutdown(state.backfill_config.request_delay, &mut shutdown_rx).await {
+                                    info!("Reserve scanner stopped");
+                                    return Ok(());
+                                }
+                                continue;
+                            }
+                        }
+                        Err(e) => {
+                            error!("Backfill batch failed: {}", e);
+                            consecutive_failures += 1;
+                            state.record_failure(&e).await;
+                            if e.to_string().contains("Scan not registered") {
+                                state.scan.reset().await;
                             }
                         }
                     }
@@ -1109,33 +1562,23 @@ pub async fn reserve_scanner_loop(state: Arc<ServerState>) -> Result<(), Scanner
                         match state.process_scan_boxes().await {
                             Ok(()) => {
                                 consecutive_failures = 0;
+                                state.record_success().await;
                                 // Update last scanned height on success
                                 {
                                     let mut inner = state.inner.lock().await;
                                     inner.last_scanned_height = height;
+                                    inner.progress.scanned_height = height;
                                 }
                             }
                             Err(e) => {
                                 error!("Failed to process scan boxes: {}", e);
                                 consecutive_failures += 1;
+                                state.record_failure(&e).await;
 
                                 // If we get "scan not registered" error, reset scan state
                                 if e.to_string().contains("Scan not registered") {
                                     warn!("Scan registration lost, resetting scan state");
-                                    {
-                                        let mut inner = state.inner.lock().await;
-                                        inner.scan_id = None;
-                                        inner.scan_active = false;
-                                    }
-                                }
-
-                                if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
-                                    error!(
-                                        "Too many consecutive failures ({}), waiting before retry",
-                                        consecutive_failures
-                                    );
-                                    tokio::time::sleep(Duration::from_secs(60)).await;
-                                    // Wait longer after many failures
+                                    state.scan.reset().await;
                                 }
                             }
                         }
@@ -1145,29 +1588,31 @@ pub async fn reserve_scanner_loop(state: Arc<ServerState>) -> Result<(), Scanner
             Err(e) => {
                 error!("Failed to get current height: {}", e);
                 consecutive_failures += 1;
-                if consecutive_failures >= MAX_CONSECUTIVE_FAILURES {
-                    error!(
-                        "Too many consecutive failures ({}), waiting before retry",
-                        consecutive_failures
-                    );
-                    tokio::time::sleep(Duration::from_secs(60)).await;
-                }
+                state.record_failure(&e).await;
             }
         }
 
-        // Wait before next scan (shorter wait if we're recovering)
-        let wait_time = if consecutive_failures > 0 {
-            Duration::from_secs(10) // Shorter wait during recovery
-        } else {
-            Duration::from_secs(30) // Normal wait
-        };
-        tokio::time::sleep(wait_time).await;
+        // Wait before next scan (shorter wait if we're recovering, longer after many failures)
+        let scan_loop_config = state.scan_loop_config();
+        let wait_time = scan_loop_config.wait(consecutive_failures);
+        if consecutive_failures >= scan_loop_config.max_consecutive_failures {
+            error!(
+                "Too many consecutive failures ({}), waiting {:?} before retry",
+                consecutive_failures, wait_time
+            );
+        }
+        if wait_or_shutdown(wait_time, &mut shutdown_rx).await {
+            info!("Reserve scanner stopped");
+            return Ok(());
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::contract_compiler::{get_basis_reserve_contract_p2s, get_basis_reserve_ergo_tree_hex};
+    use crate::mock_node::{BoxSpec, MockErgoNode};
     use std::collections::HashMap;
 
     #[test]
@@ -1294,4 +1739,544 @@ mod tests {
             }
         }
     }
+
+    // Mock node holding one reserve box at each of `heights`, tracked by a scan
+    async fn mock_node_with_reserves(heights: &[u64]) -> (MockErgoNode, i32) {
+        let node = MockErgoNode::start(0).await;
+        let scan_id = node.register_scan("reserves", "00");
+        for &height in heights {
+            node.advance(height - node.height());
+            node.create_box(
+                BoxSpec::new(1_000_000_000, "00")
+                    .register("R4", "0702dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7")
+                    .register("R6", format!("0e20{:064x}", 1)),
+            );
+        }
+        (node, scan_id as i32)
+    }
+
+    async fn backfill_scanner(node_url: String, scan_id: i32, dir: &std::path::Path) -> ServerState {
+        let config = NodeConfig {
+            node_url,
+            start_height: Some(0),
+            ..Default::default()
+        };
+        let mut state =
+            ServerState::with_storage_paths(config, dir.join("metadata"), dir.join("reserves")).unwrap();
+        state.set_backfill_config(BackfillConfig {
+            batch_blocks: 50,
+            page_size: 1,
+            request_delay: Duration::from_millis(1),
+        });
+        state.scan.use_scan_id(scan_id).await;
+        state
+    }
+
+    #[tokio::test]
+    async fn test_backfill_batches_and_resumes() {
+        let (node, scan_id) = mock_node_with_reserves(&[10, 20, 60, 110]).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        {
+            let state = backfill_scanner(node.url.clone(), scan_id, dir.path()).await;
+            assert!(state.is_backfilling().await);
+
+            // Heights 0..=49 hold two boxes, fetched one page at a time
+            assert!(!state.backfill_next_batch(120).await.unwrap());
+            let progress = state.progress().await;
+            assert_eq!(progress.scanned_height, 49);
+            assert_eq!(progress.batches_completed, 1);
+            assert_eq!(progress.boxes_processed, 2);
+            assert_eq!(progress.tip_height, 120);
+            assert_eq!(state.metadata_storage.get_backfill_progress().unwrap(), Some((scan_id, 49)));
+        }
+
+        // A restarted scanner resumes after the last persisted batch
+        let state = backfill_scanner(node.url.clone(), scan_id, dir.path()).await;
+        assert!(!state.backfill_next_batch(120).await.unwrap());
+        assert_eq!(state.progress().await.scanned_height, 99);
+        assert!(!state.backfill_next_batch(120).await.unwrap());
+        assert_eq!(state.progress().await.scanned_height, 120);
+
+        assert!(state.backfill_next_batch(120).await.unwrap());
+        let progress = state.progress().await;
+        assert_eq!(progress.mode, ScanMode::Live);
+        assert_eq!(progress.boxes_processed, 2);
+        assert_eq!(state.reserve_tracker.get_all_reserves().len(), 4);
+        assert_eq!(state.last_scanned_height().await, 120);
+    }
+
+    // Serve explorer /api/v1/boxes/unspent/byErgoTree pages of reserve boxes
+    // whose R6 holds each of `tracker_nfts`
+    async fn spawn_mock_explorer(tracker_nfts: Vec<u64>) -> String {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let tracker_nfts = tracker_nfts.clone();
+                tokio::spawn(async move {
+                    let mut buf = vec![0u8; 4096];
+                    let n = socket.read(&mut buf).await.unwrap_or(0);
+                    let request = String::from_utf8_lossy(&buf[..n]).to_string();
+                    let target = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                    assert!(target.starts_with("/api/v1/boxes/unspent/byErgoTree/"));
+                    let query: HashMap<String, usize> = target
+                        .split_once('?')
+                        .map(|(_, query)| query)
+                        .unwrap_or("")
+                        .split('&')
+                        .filter_map(|pair| pair.split_once('='))
+                        .filter_map(|(k, v)| Some((k.to_string(), v.parse().ok()?)))
+                        .collect();
+
+                    let items: Vec<serde_json::Value> = tracker_nfts
+                        .iter()
+                        .enumerate()
+                        .skip(query["offset"])
+                        .take(query["limit"])
+                        .map(|(i, nft)| {
+                            serde_json::json!({
+                                "boxId": format!("{:064x}", i + 1),
+                                "transactionId": format!("{:064x}", i + 100),
+                                "value": 1_000_000_000u64,
+                                "creationHeight": 10 + i,
+                                "ergoTree": "00",
+                                "additionalRegisters": {
+                                    "R4": {
+                                        "serializedValue": "0702dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7",
+                                        "sigmaType": "SGroupElement"
+                                    },
+                                    "R6": {
+                                        "serializedValue": format!("0e20{:064x}", nft),
+                                        "sigmaType": "Coll[SByte]"
+                                    }
+                                },
+                                "assets": []
+                            })
+                        })
+                        .collect();
+
+                    let body = serde_json::json!({ "items": items, "total": tracker_nfts.len() }).to_string();
+                    let response = format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    );
+                    let _ = socket.write_all(response.as_bytes()).await;
+                });
+            }
+        });
+        format!("http://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn test_discover_reserves_via_explorer() {
+        use ergo_lib::ergotree_ir::address::Address;
+        use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+        use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+
+        let explorer_url = spawn_mock_explorer(vec![1, 2, 1, 1, 3]).await;
+        let dir = tempfile::tempdir().unwrap();
+
+        // Any address with a script stands in for the reserve contract
+        let pubkey = hex::decode("02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7").unwrap();
+        let contract = crate::Network::Mainnet
+            .encode_address(&Address::P2Pk(ProveDlog::from(EcPoint::sigma_parse_bytes(&pubkey).unwrap())));
+        let config = NodeConfig {
+            node_url: "http://127.0.0.1:1".to_string(),
+            reserve_contract_p2s: Some(contract),
+            ..Default::default()
+        };
+        let mut state =
+            ServerState::with_storage_paths(config, dir.path().join("metadata"), dir.path().join("reserves")).unwrap();
+        state.set_backfill_config(BackfillConfig {
+            page_size: 2,
+            request_delay: Duration::from_millis(1),
+            ..Default::default()
+        });
+
+        // Only boxes of the configured tracker are seeded, across all pages
+        let discovered = state.discover_reserves(&explorer_url, &format!("{:064x}", 1)).await.unwrap();
+        assert_eq!(discovered, 3);
+        let reserves = state.reserve_tracker.get_all_reserves();
+        assert_eq!(reserves.len(), 3);
+        assert!(reserves.iter().all(|reserve| state.is_discovered(&reserve.box_id)));
+        assert_eq!(state.reserve_storage.get_all_reserves().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_scanner_follows_reorgs() {
+        let node = MockErgoNode::start(100).await;
+        let dir = tempfile::tempdir().unwrap();
+        let reserve_tree = get_basis_reserve_ergo_tree_hex().unwrap();
+        let reserve = |collateral| {
+            BoxSpec::new(collateral, &reserve_tree)
+                .register("R4", "0702dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7")
+                .register("R6", format!("0e20{:064x}", 1))
+        };
+        let tracked = |state: &ServerState| {
+            let mut collaterals: Vec<u64> = state
+                .reserve_tracker
+                .get_all_reserves()
+                .iter()
+                .map(|reserve| reserve.base_info.collateral_amount)
+                .collect();
+            collaterals.sort();
+            collaterals
+        };
+
+        // A trailing slash on the node URL is tolerated
+        let mut state = ServerState::with_storage_paths(
+            NodeConfig {
+                node_url: format!("{}/", node.url),
+                reserve_contract_p2s: Some(get_basis_reserve_contract_p2s().unwrap()),
+                ..Default::default()
+            },
+            dir.path().join("metadata"),
+            dir.path().join("reserves"),
+        )
+        .unwrap();
+        state.start_scanning().await.unwrap();
+        assert_eq!(node.scan_ids().len(), 1);
+
+        let first = node.create_box(reserve(1_000_000_000));
+        node.advance(1);
+        node.create_box(reserve(2_000_000_000));
+        state.process_scan_boxes().await.unwrap();
+        assert_eq!(tracked(&state), vec![1_000_000_000, 2_000_000_000]);
+
+        // The second reserve is orphaned and disappears from the tracker
+        node.reorg(1, 2);
+        state.process_scan_boxes().await.unwrap();
+        assert_eq!(tracked(&state), vec![1_000_000_000]);
+
+        // A top-up is reverted, so the original reserve box is unspent again
+        node.advance(1);
+        node.spend(&[&first], vec![reserve(1_500_000_000)]);
+        state.process_scan_boxes().await.unwrap();
+        assert_eq!(tracked(&state), vec![1_500_000_000]);
+
+        node.reorg(1, 1);
+        state.process_scan_boxes().await.unwrap();
+        assert_eq!(tracked(&state), vec![1_000_000_000]);
+        assert!(state.reserve_tracker.get_reserve(&hex::encode(&first)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_scanner_reads_reserves_through_explorer() {
+        use crate::explorer_backend::{ExplorerConfig, EXPLORER_SCAN_ID};
+
+        let node = MockErgoNode::start(100).await;
+        let dir = tempfile::tempdir().unwrap();
+        let reserve_tree = get_basis_reserve_ergo_tree_hex().unwrap();
+        let reserve = |collateral| {
+            BoxSpec::new(collateral, &reserve_tree)
+                .register("R4", "0702dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7")
+                .register("R6", format!("0e20{:064x}", 1))
+        };
+
+        // No node is reachable: scanning and spends go through the explorer alone
+        let mut state = ServerState::with_storage_paths(
+            NodeConfig {
+                node_url: "http://127.0.0.1:1".to_string(),
+                reserve_contract_p2s: Some(get_basis_reserve_contract_p2s().unwrap()),
+                ..Default::default()
+            },
+            dir.path().join("metadata"),
+            dir.path().join("reserves"),
+        )
+        .unwrap();
+        state.set_explorer_backend(ExplorerBackend::new(ExplorerConfig {
+            url: node.url.clone(),
+            requests_per_second: 1_000,
+            cache_ttl: Duration::ZERO,
+            page_size: 1,
+        }));
+        state.start_scanning().await.unwrap();
+        assert!(node.scan_ids().is_empty());
+        assert_eq!(state.scan.scan_id().await, Some(EXPLORER_SCAN_ID));
+        assert_eq!(state.get_current_height().await.unwrap(), 100);
+
+        let first = node.create_box(reserve(1_000_000_000));
+        node.create_box(reserve(2_000_000_000));
+        node.create_box(BoxSpec::new(5_000_000_000, "00"));
+        state.process_scan_boxes().await.unwrap();
+        assert_eq!(state.reserve_tracker.get_all_reserves().len(), 2);
+
+        node.advance(1);
+        let (tx_id, successor) = node.spend(&[&first], vec![reserve(1_500_000_000)]);
+        state.process_scan_boxes().await.unwrap();
+        let transition = state.reserve_storage.get_transition(&hex::encode(&first)).unwrap().unwrap();
+        assert_eq!(transition.spending_tx_id, tx_id);
+        assert_eq!(transition.spending_height, Some(101));
+        assert_eq!(transition.kind, ReserveSpendKind::TopUp);
+        assert_eq!(transition.successor_box_id, Some(hex::encode(&successor[0])));
+        let mut collaterals: Vec<u64> = state
+            .reserve_tracker
+            .get_all_reserves()
+            .iter()
+            .map(|reserve| reserve.base_info.collateral_amount)
+            .collect();
+        collaterals.sort();
+        assert_eq!(collaterals, vec![1_500_000_000, 2_000_000_000]);
+
+        // Backfill pages through the explorer's boxes by inclusion height
+        let progress_scan_id = state.scan.scan_id().await.unwrap();
+        state.rescan_from(101).await.unwrap();
+        assert!(!state.backfill_next_batch(101).await.unwrap());
+        assert_eq!(state.progress().await.boxes_processed, 5);
+        assert_eq!(state.metadata_storage.get_backfill_progress().unwrap(), Some((progress_scan_id, 101)));
+    }
+
+    #[tokio::test]
+    async fn test_scanner_tracks_registered_contract_versions() {
+        use crate::contract_registry::{ContractRegistry, ReserveContract};
+
+        let node = MockErgoNode::start(100).await;
+        let dir = tempfile::tempdir().unwrap();
+        let current_tree = get_basis_reserve_ergo_tree_hex().unwrap();
+        // Any other tree stands in for a retired template
+        let legacy_tree = "100104c801d17300";
+        let reserve = |tree: &str, collateral| {
+            BoxSpec::new(collateral, tree)
+                .register("R4", "0702dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7")
+                .register("R6", format!("0e20{:064x}", 1))
+        };
+
+        let mut state = ServerState::with_storage_paths(
+            NodeConfig {
+                node_url: node.url.clone(),
+                reserve_contract_p2s: Some(get_basis_reserve_contract_p2s().unwrap()),
+                ..Default::default()
+            },
+            dir.path().join("metadata"),
+            dir.path().join("reserves"),
+        )
+        .unwrap();
+        let mut contracts = ContractRegistry::builtin();
+        contracts.register(ReserveContract::from_ergo_tree_hex(0, legacy_tree).unwrap()).unwrap();
+        state.set_contract_registry(contracts);
+        assert_eq!(state.scan.spec().tracking_rule().unwrap()["predicate"], "or");
+        state.start_scanning().await.unwrap();
+
+        let legacy = node.create_box(reserve(legacy_tree, 1_000_000_000));
+        node.create_box(reserve(&current_tree, 2_000_000_000));
+        // Boxes of unregistered contracts are not reserves
+        node.create_box(reserve("0008cd02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7", 3_000_000_000));
+        node.advance(1);
+        state.process_scan_boxes().await.unwrap();
+        assert_eq!(state.reserve_tracker.get_all_reserves().len(), 2);
+
+        // A top-up keeps the old reserve on its own template
+        node.spend(&[&legacy], vec![reserve(legacy_tree, 1_500_000_000)]);
+        node.advance(1);
+        state.process_scan_boxes().await.unwrap();
+        let transition = state.reserve_storage.get_transition(&hex::encode(&legacy)).unwrap().unwrap();
+        assert_eq!(transition.kind, crate::ReserveSpendKind::TopUp);
+        assert_eq!(transition.value_after, Some(1_500_000_000));
+    }
+
+    #[tokio::test]
+    async fn test_scanner_reads_redemption_receiver() {
+        let node = MockErgoNode::start(100).await;
+        let dir = tempfile::tempdir().unwrap();
+        let reserve_tree = get_basis_reserve_ergo_tree_hex().unwrap();
+        let tracker_nft = format!("{:064x}", 1);
+        let receiver = "03c6d2ad1e3b8dbc3fd8be8e9f5e8e6a1d2c3b4a5968778695a4b3c2d1e0f1a2b3";
+        let reserve = |collateral| {
+            BoxSpec::new(collateral, &reserve_tree)
+                .register("R4", "0702dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7")
+                .register("R6", format!("0e20{}", tracker_nft))
+        };
+        let redemption = serde_json::json!({ "0": "0200", "1": format!("07{}", receiver) });
+
+        let mut state = ServerState::with_storage_paths(
+            NodeConfig {
+                node_url: node.url.clone(),
+                reserve_contract_p2s: Some(get_basis_reserve_contract_p2s().unwrap()),
+                ..Default::default()
+            },
+            dir.path().join("metadata"),
+            dir.path().join("reserves"),
+        )
+        .unwrap();
+        state.start_scanning().await.unwrap();
+
+        let tracker_box = node.create_box(BoxSpec::new(1_000_000, "100104c801d17300").asset(&tracker_nft, 1));
+        let other_box = node.create_box(BoxSpec::new(1_000_000, "100104c801d17300").asset(&format!("{:064x}", 2), 1));
+        let first = node.create_box(reserve(3_000_000_000));
+        let second = node.create_box(reserve(3_000_000_000));
+        node.advance(1);
+        state.process_scan_boxes().await.unwrap();
+
+        // Redeemed against the reserve's tracker box: the receiver is known
+        node.spend_with_context(&[(&first, redemption.clone())], &[&tracker_box], vec![reserve(2_000_000_000)]);
+        // Redeemed against some other box: it is not
+        node.spend_with_context(&[(&second, redemption)], &[&other_box], vec![reserve(2_500_000_000)]);
+        node.advance(1);
+        state.process_scan_boxes().await.unwrap();
+
+        let transition = state.reserve_storage.get_transition(&hex::encode(&first)).unwrap().unwrap();
+        assert_eq!(transition.kind, crate::ReserveSpendKind::Redemption);
+        assert_eq!(transition.receiver_pubkey.as_deref(), Some(receiver));
+        let transition = state.reserve_storage.get_transition(&hex::encode(&second)).unwrap().unwrap();
+        assert_eq!(transition.kind, crate::ReserveSpendKind::Redemption);
+        assert_eq!(transition.receiver_pubkey, None);
+    }
+
+    #[tokio::test]
+    async fn test_scanner_reports_node_failures() {
+        let node = MockErgoNode::start(100).await;
+        let dir = tempfile::tempdir().unwrap();
+        let mut state = ServerState::with_storage_paths(
+            NodeConfig {
+                node_url: node.url.clone(),
+                reserve_contract_p2s: Some(get_basis_reserve_contract_p2s().unwrap()),
+                ..Default::default()
+            },
+            dir.path().join("metadata"),
+            dir.path().join("reserves"),
+        )
+        .unwrap();
+        state.start_scanning().await.unwrap();
+
+        node.fail_requests("/scan/unspentBoxes", axum::http::StatusCode::SERVICE_UNAVAILABLE);
+        assert!(state.process_scan_boxes().await.is_err());
+
+        node.clear_failures();
+        state.process_scan_boxes().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_wallet_top_up_from_node_boxes() {
+        use crate::transaction_builder::TxContext;
+        use crate::wallet_connector::{self, Eip12Box};
+
+        let node = MockErgoNode::start(100).await;
+        let dir = tempfile::tempdir().unwrap();
+        let state = ServerState::with_storage_paths(
+            NodeConfig {
+                node_url: node.url.clone(),
+                ..Default::default()
+            },
+            dir.path().join("metadata"),
+            dir.path().join("reserves"),
+        )
+        .unwrap();
+        let (_, payer) = crate::schnorr::generate_keypair();
+        let context = TxContext {
+            current_height: 100,
+            fee: 1_000_000,
+            change_address: String::new(),
+            network_prefix: 0,
+        };
+        let address = wallet_connector::p2pk_address(&payer, &context).unwrap();
+        let reserve_id = node.create_box(
+            BoxSpec::new(1_000_000_000, &get_basis_reserve_ergo_tree_hex().unwrap())
+                .register("R4", format!("07{}", hex::encode(payer)))
+                .register("R5", wallet_connector::EMPTY_RESERVE_TREE)
+                .register("R6", format!("0e20{:064x}", 1))
+                .asset(&format!("{:064x}", 2), 1),
+        );
+        node.create_box(BoxSpec::new(300_000_000, &format!("0008cd{}", hex::encode(payer))));
+        node.create_box(BoxSpec::new(300_000_000, "00"));
+
+        let reserve: Eip12Box = serde_json::from_value(state.get_unspent_box(&reserve_id).await.unwrap()).unwrap();
+        let funding: Vec<Eip12Box> = state
+            .get_unspent_boxes_by_address(&address)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|json| serde_json::from_value(json).unwrap())
+            .collect();
+        assert_eq!(funding.len(), 1);
+
+        let context = TxContext {
+            change_address: address,
+            ..context
+        };
+        let tx = wallet_connector::top_up_tx(&reserve, 200_000_000, &funding, &ContractRegistry::builtin(), &context).unwrap();
+        assert_eq!(tx.unsigned_tx.outputs[0].value, 1_200_000_000);
+        assert!(tx.ergopay_url().unwrap().starts_with("ergopay:"));
+
+        // Spent boxes are no longer offered
+        node.spend(&[&reserve_id], vec![]);
+        assert!(matches!(
+            state.get_unspent_box(&reserve_id).await,
+            Err(ScannerError::NodeError(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_scanner_health_metrics() {
+        let dir = tempfile::tempdir().unwrap();
+        let state =
+            ServerState::with_storage_paths(NodeConfig::default(), dir.path().join("metadata"), dir.path().join("reserves"))
+                .unwrap();
+        let progress = state.progress().await;
+        assert_eq!(progress.mode, ScanMode::Live);
+        assert_eq!(progress.last_success_timestamp, None);
+
+        {
+            let mut inner = state.inner.lock().await;
+            inner.current_height = 120;
+            inner.progress.scanned_height = 100;
+        }
+        state.record_failure(&ScannerError::NodeError("unreachable".to_string())).await;
+        let progress = state.progress().await;
+        assert_eq!(progress.block_lag, 20);
+        assert_eq!(progress.consecutive_failures, 1);
+        assert!(progress.is_stalled(10));
+        assert!(!progress.is_stalled(20));
+
+        state.record_success().await;
+        let progress = state.progress().await;
+        assert_eq!(progress.consecutive_failures, 0);
+        assert_eq!(progress.error_count, 1);
+        assert!(progress.last_success_timestamp.is_some());
+
+        // Lagging behind the tip is expected while backfilling
+        state.inner.lock().await.progress.mode = ScanMode::Backfill;
+        assert!(!state.progress().await.is_stalled(10));
+    }
+
+    #[test]
+    fn test_scan_loop_config_is_shared_by_clones() {
+        let dir = tempfile::tempdir().unwrap();
+        let state = ServerState::with_storage_paths(
+            NodeConfig::default(),
+            dir.path().join("scanner_metadata"),
+            dir.path().join("reserves"),
+        )
+        .unwrap();
+        // The scan loop runs on a clone of the state
+        let running = state.clone();
+        state.set_scan_loop_config(ScanLoopConfig {
+            scan_interval: Duration::from_secs(5),
+            ..ScanLoopConfig::default()
+        });
+        assert_eq!(running.scan_loop_config().scan_interval, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_scan_loop_waits() {
+        let config = ScanLoopConfig {
+            scan_interval: Duration::from_secs(4),
+            retry_backoff: Duration::from_secs(1),
+            max_consecutive_failures: 3,
+            jitter: 0.0,
+        };
+        assert_eq!(config.wait(0), Duration::from_secs(4));
+        assert_eq!(config.wait(1), Duration::from_secs(1));
+        assert_eq!(config.wait(2), Duration::from_secs(1));
+        assert_eq!(config.wait(3), Duration::from_secs(8));
+
+        let jittered = ScanLoopConfig { jitter: 0.25, ..config };
+        for _ in 0..100 {
+            let wait = jittered.wait(0);
+            assert!(wait >= Duration::from_secs(3) && wait <= Duration::from_secs(5));
+        }
+    }
 }
@@ -0,0 +1,408 @@
+//! Scanning through the public Ergo Explorer API instead of a node scan
+//!
+//! Trackers that cannot run a node with the `/scan` API can point a
+//! [`ScanManager`](crate::scan_manager::ScanManager) at an explorer with
+//! [`ScanManager::use_explorer`](crate::scan_manager::ScanManager::use_explorer).
+//! The explorer has no registered scans, so a scan's tracking rule is turned
+//! into [`ExplorerQuery`]s (unspent boxes by ErgoTree or by token ID) and the
+//! boxes of every query are merged.
+//!
+//! Public explorers limit how often they may be called: requests are spaced to
+//! at most `requests_per_second`, a `429 Too Many Requests` pauses requests for
+//! the `Retry-After` period, and responses are reused for `cache_ttl` so the
+//! reserve and tracker scanners do not ask for the same page twice.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use ergo_lib::ergotree_ir::mir::constant::{Constant, TryExtractInto};
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::ergo_scanner::{node_endpoint, parse_indexed_box, ScanBox, ScannerError};
+
+/// Public mainnet explorer API
+pub const DEFAULT_EXPLORER_URL: &str = "https://api.ergoplatform.com";
+
+/// Default number of explorer requests per second
+pub const DEFAULT_EXPLORER_REQUESTS_PER_SECOND: u32 = 2;
+
+/// Default number of seconds explorer responses are reused
+pub const DEFAULT_EXPLORER_CACHE_TTL_SECS: u64 = 10;
+
+/// Default number of boxes requested per explorer call
+pub const DEFAULT_EXPLORER_PAGE_SIZE: u64 = 100;
+
+/// Scan ID of scans served by an explorer; node scan IDs are above it
+pub const EXPLORER_SCAN_ID: i32 = 0;
+
+/// Pause after a `429` response without a `Retry-After` header
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(5);
+
+/// Explorer connection settings
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExplorerConfig {
+    /// Explorer API URL
+    pub url: String,
+    /// Requests sent to the explorer per second, at most
+    pub requests_per_second: u32,
+    /// How long a response is reused; zero disables the cache
+    pub cache_ttl: Duration,
+    /// Boxes requested per explorer call
+    pub page_size: u64,
+}
+
+impl Default for ExplorerConfig {
+    fn default() -> Self {
+        Self {
+            url: DEFAULT_EXPLORER_URL.to_string(),
+            requests_per_second: DEFAULT_EXPLORER_REQUESTS_PER_SECOND,
+            cache_ttl: Duration::from_secs(DEFAULT_EXPLORER_CACHE_TTL_SECS),
+            page_size: DEFAULT_EXPLORER_PAGE_SIZE,
+        }
+    }
+}
+
+/// Unspent boxes the explorer can list
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExplorerQuery {
+    /// Boxes locked by an ErgoTree, as hex
+    ErgoTree(String),
+    /// Boxes holding a token
+    Token(String),
+}
+
+impl ExplorerQuery {
+    /// Queries listing the boxes a node scan with `tracking_rule` would track
+    ///
+    /// `or` becomes one query per argument, `containsAsset` a token query and
+    /// `contains` or `equals` on R1 a query for the ErgoTree serialized in the
+    /// rule's value. Other predicates have no explorer equivalent.
+    pub fn from_tracking_rule(tracking_rule: &Value) -> Result<Vec<ExplorerQuery>, ScannerError> {
+        let unsupported =
+            || ScannerError::Generic(format!("Tracking rule {} has no explorer equivalent", tracking_rule));
+        match tracking_rule["predicate"].as_str().unwrap_or_default() {
+            "or" => {
+                let mut queries = Vec::new();
+                for arg in tracking_rule["args"].as_array().ok_or_else(unsupported)? {
+                    for query in Self::from_tracking_rule(arg)? {
+                        if !queries.contains(&query) {
+                            queries.push(query);
+                        }
+                    }
+                }
+                Ok(queries)
+            }
+            "containsAsset" => {
+                let token_id = tracking_rule["assetId"].as_str().ok_or_else(unsupported)?;
+                Ok(vec![ExplorerQuery::Token(token_id.to_lowercase())])
+            }
+            "contains" | "equals" if tracking_rule["register"].as_str().unwrap_or("R1") == "R1" => {
+                let value = tracking_rule["value"].as_str().ok_or_else(unsupported)?;
+                let tree = hex::decode(value)
+                    .ok()
+                    .and_then(|bytes| Constant::sigma_parse_bytes(&bytes).ok())
+                    .and_then(|constant| constant.try_extract_into::<Vec<u8>>().ok())
+                    .ok_or_else(|| {
+                        ScannerError::Generic(format!("Tracking rule value {} is not a serialized ErgoTree", value))
+                    })?;
+                Ok(vec![ExplorerQuery::ErgoTree(hex::encode(tree))])
+            }
+            _ => Err(unsupported()),
+        }
+    }
+
+    fn path(&self) -> String {
+        match self {
+            ExplorerQuery::ErgoTree(tree) => format!("api/v1/boxes/unspent/byErgoTree/{}", tree),
+            ExplorerQuery::Token(token_id) => format!("api/v1/boxes/unspent/byTokenId/{}", token_id),
+        }
+    }
+}
+
+/// Cached responses by URL, with the time they were fetched
+type ResponseCache = Arc<std::sync::Mutex<HashMap<String, (Instant, Value)>>>;
+
+/// Rate-limited, caching client of the Ergo Explorer API; clones share both
+#[derive(Clone)]
+pub struct ExplorerBackend {
+    config: ExplorerConfig,
+    client: Client,
+    /// When the next request may be sent
+    next_request_at: Arc<Mutex<Instant>>,
+    cache: ResponseCache,
+}
+
+impl ExplorerBackend {
+    pub fn new(config: ExplorerConfig) -> Self {
+        info!(
+            "Scanning through explorer {} ({} requests/s, responses cached for {:?})",
+            config.url, config.requests_per_second, config.cache_ttl
+        );
+        Self {
+            config,
+            client: Client::new(),
+            next_request_at: Arc::new(Mutex::new(Instant::now())),
+            cache: ResponseCache::default(),
+        }
+    }
+
+    pub fn config(&self) -> &ExplorerConfig {
+        &self.config
+    }
+
+    /// GET a JSON document from the explorer, reusing a cached response while fresh
+    pub async fn get_json(&self, path: &str) -> Result<Value, ScannerError> {
+        let url = node_endpoint(&self.config.url, path);
+        if let Some(value) = self.cached(&url) {
+            debug!("Explorer response for {} served from cache", url);
+            return Ok(value);
+        }
+
+        self.wait_turn().await;
+        debug!("Explorer request: {}", url);
+        let response = self
+            .client
+            .get(&url)
+            .send()
+            .await
+            .map_err(|e| ScannerError::HttpError(format!("Failed to fetch {}: {}", url, e)))?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            let retry_after = response
+                .headers()
+                .get(reqwest::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_RETRY_AFTER);
+            warn!("Explorer rate limit hit on {}, pausing requests for {:?}", url, retry_after);
+            self.pause(retry_after).await;
+            return Err(ScannerError::NetworkError(format!(
+                "Explorer rate limit hit on {}, retry after {:?}",
+                url, retry_after
+            )));
+        }
+        if !status.is_success() {
+            return Err(ScannerError::NodeError(format!(
+                "Explorer request to {} failed with status: {}",
+                url, status
+            )));
+        }
+
+        let value: Value = response
+            .json()
+            .await
+            .map_err(|e| ScannerError::JsonError(format!("Failed to parse response from {}: {}", url, e)))?;
+        self.store(url, &value);
+        Ok(value)
+    }
+
+    /// Current chain height
+    pub async fn current_height(&self) -> Result<u64, ScannerError> {
+        let state = self.get_json("api/v1/networkState").await?;
+        state["height"]
+            .as_u64()
+            .ok_or_else(|| ScannerError::NodeError("Failed to parse height from explorer network state".to_string()))
+    }
+
+    /// Box by ID, spent or not, as explorer box JSON carrying `spentTransactionId`
+    pub async fn box_by_id(&self, box_id: &str) -> Result<Value, ScannerError> {
+        self.get_json(&format!("api/v1/boxes/{}", box_id)).await
+    }
+
+    /// Transaction by ID, with `inclusionHeight`, `dataInputs` and `outputs`
+    pub async fn transaction_by_id(&self, tx_id: &str) -> Result<Value, ScannerError> {
+        self.get_json(&format!("api/v1/transactions/{}", tx_id)).await
+    }
+
+    /// Unspent boxes of every query as explorer box JSON, each box once and
+    /// ordered by inclusion height
+    pub async fn unspent_boxes(&self, queries: &[ExplorerQuery]) -> Result<Vec<Value>, ScannerError> {
+        let page_size = self.config.page_size.max(1);
+        let mut seen = HashSet::new();
+        let mut boxes = Vec::new();
+        for query in queries {
+            let mut offset = 0;
+            loop {
+                let page = self
+                    .get_json(&format!("{}?offset={}&limit={}", query.path(), offset, page_size))
+                    .await?;
+                let items = page["items"].as_array().cloned().unwrap_or_default();
+                let total = page["total"].as_u64().unwrap_or(u64::MAX);
+                let page_len = items.len() as u64;
+                for item in items {
+                    if let Some(box_id) = item["boxId"].as_str() {
+                        if seen.insert(box_id.to_string()) {
+                            boxes.push(item);
+                        }
+                    }
+                }
+                offset += page_len;
+                if page_len < page_size || offset >= total {
+                    break;
+                }
+            }
+        }
+        boxes.sort_by_key(|item| (inclusion_height(item), item["boxId"].as_str().unwrap_or_default().to_string()));
+        Ok(boxes)
+    }
+
+    /// Unspent boxes of every query, as `/scan/unspentBoxes` would return them
+    ///
+    /// `query` is a `/scan/unspentBoxes` query string: `minInclusionHeight`,
+    /// `maxInclusionHeight`, `offset` and `limit` are applied to the merged
+    /// boxes, so backfill pages through them as it would through a node scan.
+    pub async fn scan_boxes(&self, queries: &[ExplorerQuery], query: Option<&str>) -> Result<Vec<ScanBox>, ScannerError> {
+        let params: HashMap<&str, u64> = query
+            .unwrap_or_default()
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .filter_map(|(name, value)| Some((name, value.parse().ok()?)))
+            .collect();
+        let min = params.get("minInclusionHeight").copied().unwrap_or(0);
+        let max = params.get("maxInclusionHeight").copied().unwrap_or(u64::MAX);
+
+        let scan_boxes: Vec<ScanBox> = self
+            .unspent_boxes(queries)
+            .await?
+            .iter()
+            .filter(|item| (min..=max).contains(&inclusion_height(item)))
+            .skip(params.get("offset").copied().unwrap_or(0) as usize)
+            .take(params.get("limit").copied().unwrap_or(u64::MAX) as usize)
+            .filter_map(parse_indexed_box)
+            .collect();
+        info!("Found {} boxes through explorer", scan_boxes.len());
+        Ok(scan_boxes)
+    }
+
+    fn cached(&self, url: &str) -> Option<Value> {
+        if self.config.cache_ttl.is_zero() {
+            return None;
+        }
+        let cache = self.cache.lock().ok()?;
+        let (fetched, value) = cache.get(url)?;
+        (fetched.elapsed() < self.config.cache_ttl).then(|| value.clone())
+    }
+
+    fn store(&self, url: String, value: &Value) {
+        if self.config.cache_ttl.is_zero() {
+            return;
+        }
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.retain(|_, (fetched, _)| fetched.elapsed() < self.config.cache_ttl);
+            cache.insert(url, (Instant::now(), value.clone()));
+        }
+    }
+
+    // Wait until the rate limit allows another request, and book the slot after it
+    async fn wait_turn(&self) {
+        let interval = Duration::from_secs(1) / self.config.requests_per_second.max(1);
+        let wait = {
+            let mut next_request_at = self.next_request_at.lock().await;
+            let now = Instant::now();
+            let at = (*next_request_at).max(now);
+            *next_request_at = at + interval;
+            at - now
+        };
+        if !wait.is_zero() {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    // Hold back every request for `duration`
+    async fn pause(&self, duration: Duration) {
+        let mut next_request_at = self.next_request_at.lock().await;
+        *next_request_at = (*next_request_at).max(Instant::now() + duration);
+    }
+}
+
+// Height of the block that included a box: `settlementHeight` on the
+// explorer, `inclusionHeight` on the node
+fn inclusion_height(item: &Value) -> u64 {
+    item["settlementHeight"]
+        .as_u64()
+        .or_else(|| item["inclusionHeight"].as_u64())
+        .or_else(|| item["creationHeight"].as_u64())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_node::{BoxSpec, MockErgoNode};
+    use serde_json::json;
+
+    const TOKEN_ID: &str = "011d3364de07e5a26f0c4eef0852cddb387039a921b7154ef3cab22c6eda887f";
+
+    #[test]
+    fn test_tracking_rules_become_explorer_queries() {
+        let tree = "100104c801d17300";
+        let byte_array = hex::encode(Constant::from(hex::decode(tree).unwrap()).sigma_serialize_bytes());
+        let rule = json!({
+            "predicate": "or",
+            "args": [
+                { "predicate": "contains", "register": "R1", "value": byte_array },
+                { "predicate": "containsAsset", "assetId": TOKEN_ID.to_uppercase() },
+                { "predicate": "equals", "value": byte_array },
+            ]
+        });
+        assert_eq!(
+            ExplorerQuery::from_tracking_rule(&rule).unwrap(),
+            vec![ExplorerQuery::ErgoTree(tree.to_string()), ExplorerQuery::Token(TOKEN_ID.to_string())]
+        );
+
+        for unsupported in [
+            json!({ "predicate": "and", "args": [] }),
+            json!({ "predicate": "contains", "register": "R4", "value": byte_array }),
+            json!({ "predicate": "contains", "value": "zz" }),
+        ] {
+            assert!(ExplorerQuery::from_tracking_rule(&unsupported).is_err(), "{}", unsupported);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_explorer_requests_are_cached_and_rate_limited() {
+        let node = MockErgoNode::start(100).await;
+        for _ in 0..3 {
+            node.create_box(BoxSpec::new(1_000_000, "00").asset(TOKEN_ID, 1));
+        }
+        let explorer = ExplorerBackend::new(ExplorerConfig {
+            url: node.url.clone(),
+            requests_per_second: 20,
+            cache_ttl: Duration::from_secs(60),
+            page_size: 2,
+        });
+        let queries = [ExplorerQuery::Token(TOKEN_ID.to_string())];
+
+        // Two pages, 50ms apart; asking again within the TTL sends nothing
+        let started = Instant::now();
+        assert_eq!(explorer.scan_boxes(&queries, None).await.unwrap().len(), 3);
+        assert_eq!(explorer.scan_boxes(&queries, None).await.unwrap().len(), 3);
+        assert_eq!(explorer.clone().current_height().await.unwrap(), 100);
+        assert_eq!(node.requests("/api/v1/boxes/unspent/byTokenId"), 2);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+
+        let paged = explorer.scan_boxes(&queries, Some("offset=1&limit=1")).await.unwrap();
+        assert_eq!(paged.len(), 1);
+        assert_eq!(paged[0].assets[0].token_id, TOKEN_ID);
+
+        // A 429 holds back the following requests
+        node.fail_requests("/api/v1/networkState", StatusCode::TOO_MANY_REQUESTS);
+        let uncached = ExplorerBackend::new(ExplorerConfig {
+            url: node.url.clone(),
+            cache_ttl: Duration::ZERO,
+            ..explorer.config().clone()
+        });
+        assert!(matches!(uncached.current_height().await, Err(ScannerError::NetworkError(_))));
+        node.clear_failures();
+        let paused = Instant::now();
+        assert_eq!(uncached.current_height().await.unwrap(), 100);
+        assert!(paused.elapsed() >= DEFAULT_RETRY_AFTER - Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,348 @@
+//! Deterministic fixtures and golden vectors for compatibility testing
+//!
+//! Keys come from fixed seeds and signatures use nonces derived from the key
+//! and message, so every run produces the same bytes. [`golden_vectors`]
+//! gathers the fixture keys, canonical notes with their signing messages and
+//! signatures, and the AVL tree root and lookup proofs of those notes. The
+//! result is kept in `fixtures/golden_vectors.json` at the crate root, for
+//! other implementations (wallets, contract tests) to check themselves
+//! against.
+//!
+//! A test fails when any byte of the vectors changes. Regenerate the file with
+//! `BASIS_UPDATE_GOLDEN=1 cargo test -p basis_store fixtures` only when the
+//! change is intended, since every other implementation must follow it.
+//!
+//! The fixture secret keys are public: never use them for real funds. The
+//! module is only available to this crate's tests and, with the
+//! `test-support` feature, to other crates.
+
+use blake2::{Blake2b, Digest};
+use generic_array::typenum::U32;
+use serde::{Deserialize, Serialize};
+
+use crate::{blake2b256_hash, schnorr, AccrualMethod, InterestTerms, IouNote, NoteKey, PubKey, Signature, SigningFormat};
+
+/// Seeds of the fixture keys, by party name
+pub const FIXTURE_SEEDS: &[(&str, &str)] = &[
+    ("alice", "basis-fixture/alice"),
+    ("bob", "basis-fixture/bob"),
+    ("carol", "basis-fixture/carol"),
+    ("tracker", "basis-fixture/tracker"),
+];
+
+/// Seed of the tracker NFT ID version 2 notes are bound to
+pub const FIXTURE_TRACKER_NFT_SEED: &str = "basis-fixture/tracker-nft";
+
+/// Keypair derived from a seed: the secret key is `blake2b256(seed)`, hashed
+/// again in the unlikely case it is not a valid scalar
+pub fn fixture_keypair(seed: &str) -> ([u8; 32], PubKey) {
+    let secp = secp256k1::Secp256k1::new();
+    let mut secret = blake2b256_hash(seed.as_bytes());
+    loop {
+        if let Ok(secret_key) = secp256k1::SecretKey::from_slice(&secret) {
+            return (secret, secp256k1::PublicKey::from_secret_key(&secp, &secret_key).serialize());
+        }
+        secret = blake2b256_hash(&secret);
+    }
+}
+
+/// Keypair of a party of [`FIXTURE_SEEDS`]
+///
+/// Panics on an unknown party name.
+pub fn fixture_party(name: &str) -> ([u8; 32], PubKey) {
+    let (_, seed) = FIXTURE_SEEDS
+        .iter()
+        .find(|(party, _)| *party == name)
+        .unwrap_or_else(|| panic!("unknown fixture party {}", name));
+    fixture_keypair(seed)
+}
+
+/// Tracker NFT ID version 2 fixture notes are bound to
+pub fn fixture_tracker_nft_id() -> [u8; 32] {
+    blake2b256_hash(FIXTURE_TRACKER_NFT_SEED.as_bytes())
+}
+
+/// Schnorr signature with a nonce derived from the secret key and message
+///
+/// The nonce is `blake2b256("basis-fixture-nonce" || secret || message ||
+/// counter)`, with the 4-byte big-endian counter counting up from 0 until
+/// the signature is one the Scala verifier accepts.
+pub fn deterministic_sign(message: &[u8], secret_key: &[u8; 32], pubkey: &PubKey) -> Signature {
+    for counter in 0u32.. {
+        let mut hasher = Blake2b::<U32>::new();
+        hasher.update(b"basis-fixture-nonce");
+        hasher.update(secret_key);
+        hasher.update(message);
+        hasher.update(counter.to_be_bytes());
+        let nonce: [u8; 32] = hasher.finalize().into();
+        match basis_core::impls::schnorr_sign_with_nonce(message, secret_key, pubkey, &nonce) {
+            Ok(Some(signature)) => return signature,
+            // Out of range for the Scala verifier, or not a valid scalar
+            Ok(None) | Err(_) => continue,
+        }
+    }
+    unreachable!("nonce counter exhausted")
+}
+
+/// A canonical note between two fixture parties
+#[derive(Debug, Clone)]
+pub struct FixtureNote {
+    pub name: &'static str,
+    pub issuer: &'static str,
+    pub recipient: &'static str,
+    pub issuer_pubkey: PubKey,
+    /// The note, signed by the issuer with [`deterministic_sign`]
+    pub note: IouNote,
+}
+
+/// The canonical notes, in the order they are inserted into the fixture tree
+///
+/// Amounts and timestamps stay below 2^53 so JSON readers that parse numbers
+/// as doubles read them exactly.
+pub fn canonical_notes() -> Vec<FixtureNote> {
+    let tracker_id = fixture_tracker_nft_id();
+    let notes = [
+        ("v1-basic", "alice", "bob", IouNote::new([0; 33], 1_000_000_000, 0, 1_743_379_200_000, [0; 65])),
+        (
+            "v2-tracker-bound",
+            "alice",
+            "carol",
+            IouNote::new([0; 33], 250_000_000, 0, 1_743_379_260_000, [0; 65])
+                .with_signing_format(SigningFormat::V2 { tracker_id: Some(tracker_id) }),
+        ),
+        (
+            "v2-interest-memo",
+            "bob",
+            "carol",
+            IouNote::new([0; 33], 42_000, 0, 1_743_379_320_000, [0; 65])
+                .with_signing_format(SigningFormat::V2 { tracker_id: None })
+                .with_interest(InterestTerms::new(500, AccrualMethod::Simple))
+                .with_memo("INV-0001"),
+        ),
+        ("v1-zero", "carol", "alice", IouNote::new([0; 33], 0, 0, 0, [0; 65])),
+    ];
+
+    notes
+        .into_iter()
+        .map(|(name, issuer, recipient, mut note)| {
+            let (issuer_secret, issuer_pubkey) = fixture_party(issuer);
+            note.recipient_pubkey = fixture_party(recipient).1;
+            note.signature = deterministic_sign(&note.signing_message(&issuer_pubkey), &issuer_secret, &issuer_pubkey);
+            FixtureNote {
+                name,
+                issuer,
+                recipient,
+                issuer_pubkey,
+                note,
+            }
+        })
+        .collect()
+}
+
+/// All golden vectors, as stored in `fixtures/golden_vectors.json`
+///
+/// Byte strings are hex-encoded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenVectors {
+    pub keys: Vec<GoldenKey>,
+    pub notes: Vec<GoldenNote>,
+    pub avl: GoldenAvlTree,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenKey {
+    pub name: String,
+    pub seed: String,
+    pub secret_key: String,
+    pub public_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenNote {
+    pub name: String,
+    pub issuer: String,
+    pub recipient: String,
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    pub amount_collected: u64,
+    pub timestamp: u64,
+    pub signing_version: u8,
+    pub tracker_id: Option<String>,
+    /// Encoded interest terms: rate (4 BE) || method (1)
+    pub interest: Option<String>,
+    pub memo: Option<String>,
+    /// AVL tree key: `blake2b256(issuer_pubkey || recipient_pubkey)`
+    pub note_key: String,
+    /// AVL tree value: total debt, 8 bytes big-endian
+    pub avl_value: String,
+    pub signing_message: String,
+    /// Issuer's signature over `signing_message`
+    pub signature: String,
+    /// Message the tracker signs to approve redeeming the whole debt
+    pub tracker_signing_message: String,
+    pub tracker_signature: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenAvlTree {
+    pub empty_root_digest: String,
+    /// Root after inserting every note, in order
+    pub root_digest: String,
+    pub lookup_proofs: Vec<GoldenLookupProof>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoldenLookupProof {
+    pub note: String,
+    /// Proof of the lookup of the note key against `root_digest`
+    pub proof: String,
+    /// Serialized operation trail of the proof
+    pub operations: String,
+}
+
+/// Compute every golden vector from the fixture seeds
+pub fn golden_vectors() -> GoldenVectors {
+    let keys = FIXTURE_SEEDS
+        .iter()
+        .map(|(name, seed)| {
+            let (secret, pubkey) = fixture_keypair(seed);
+            GoldenKey {
+                name: name.to_string(),
+                seed: seed.to_string(),
+                secret_key: hex::encode(secret),
+                public_key: hex::encode(pubkey),
+            }
+        })
+        .collect();
+
+    let (tracker_secret, tracker_pubkey) = fixture_party("tracker");
+    let notes = canonical_notes();
+    let golden_notes = notes
+        .iter()
+        .map(|fixture| {
+            let note = &fixture.note;
+            let tracker_message = schnorr::signing_message(
+                &fixture.issuer_pubkey,
+                &note.recipient_pubkey,
+                note.amount_collected,
+                note.timestamp,
+            );
+            GoldenNote {
+                name: fixture.name.to_string(),
+                issuer: fixture.issuer.to_string(),
+                recipient: fixture.recipient.to_string(),
+                issuer_pubkey: hex::encode(fixture.issuer_pubkey),
+                recipient_pubkey: hex::encode(note.recipient_pubkey),
+                amount_collected: note.amount_collected,
+                timestamp: note.timestamp,
+                signing_version: note.signing_format.version(),
+                tracker_id: note.signing_format.tracker_id().map(hex::encode),
+                interest: note.interest.map(|interest| hex::encode(interest.to_bytes())),
+                memo: note.memo.clone(),
+                note_key: hex::encode(NoteKey::from_keys(&fixture.issuer_pubkey, &note.recipient_pubkey).to_bytes()),
+                avl_value: hex::encode(note.amount_collected.to_be_bytes()),
+                signing_message: hex::encode(note.signing_message(&fixture.issuer_pubkey)),
+                signature: hex::encode(note.signature),
+                tracker_signing_message: hex::encode(&tracker_message),
+                tracker_signature: hex::encode(deterministic_sign(&tracker_message, &tracker_secret, &tracker_pubkey)),
+            }
+        })
+        .collect();
+
+    GoldenVectors {
+        keys,
+        notes: golden_notes,
+        avl: golden_avl_tree(&notes),
+    }
+}
+
+// The tracker's tree holding the notes: key hash -> total debt
+fn golden_avl_tree(notes: &[FixtureNote]) -> GoldenAvlTree {
+    let mut tree = basis_trees::BasisAvlTree::new().expect("in-memory tree");
+    let empty_root_digest = hex::encode(tree.root_digest());
+    let keys: Vec<Vec<u8>> = notes
+        .iter()
+        .map(|fixture| NoteKey::from_keys(&fixture.issuer_pubkey, &fixture.note.recipient_pubkey).to_bytes())
+        .collect();
+    for (key, fixture) in keys.iter().zip(notes) {
+        tree.update(key.clone(), fixture.note.amount_collected.to_be_bytes().to_vec())
+            .expect("fixture note keys are distinct");
+    }
+    let root_digest = hex::encode(tree.root_digest());
+
+    let lookup_proofs = keys
+        .iter()
+        .zip(notes)
+        .map(|(key, fixture)| {
+            let proof = tree.lookup_proof(key).expect("fixture note is in the tree");
+            GoldenLookupProof {
+                note: fixture.name.to_string(),
+                proof: hex::encode(proof.avl_proof),
+                operations: hex::encode(proof.operations),
+            }
+        })
+        .collect();
+
+    GoldenAvlTree {
+        empty_root_digest,
+        root_digest,
+        lookup_proofs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GOLDEN_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/golden_vectors.json");
+
+    #[test]
+    fn test_golden_vectors_are_byte_exact() {
+        let generated = serde_json::to_string_pretty(&golden_vectors()).unwrap() + "\n";
+        if std::env::var_os("BASIS_UPDATE_GOLDEN").is_some() {
+            std::fs::write(GOLDEN_PATH, &generated).unwrap();
+        }
+        let stored = std::fs::read_to_string(GOLDEN_PATH).unwrap();
+        assert!(
+            generated == stored,
+            "golden vectors changed; if intended, regenerate with BASIS_UPDATE_GOLDEN=1"
+        );
+    }
+
+    #[test]
+    fn test_golden_vectors_verify() {
+        let vectors: GoldenVectors = serde_json::from_str(&std::fs::read_to_string(GOLDEN_PATH).unwrap()).unwrap();
+        let bytes = |hex_str: &str| hex::decode(hex_str).unwrap();
+        let tracker_pubkey: PubKey = fixture_party("tracker").1;
+
+        for (golden, fixture) in vectors.notes.iter().zip(canonical_notes()) {
+            assert!(fixture.note.verify_signature(&fixture.issuer_pubkey).is_ok(), "{}", golden.name);
+            let signature: Signature = bytes(&golden.signature).try_into().unwrap();
+            let issuer_pubkey: PubKey = bytes(&golden.issuer_pubkey).try_into().unwrap();
+            assert!(schnorr::schnorr_verify(&signature, &bytes(&golden.signing_message), &issuer_pubkey).is_ok());
+            let tracker_signature: Signature = bytes(&golden.tracker_signature).try_into().unwrap();
+            assert!(schnorr::schnorr_verify(
+                &tracker_signature,
+                &bytes(&golden.tracker_signing_message),
+                &tracker_pubkey
+            )
+            .is_ok());
+        }
+
+        let root_digest = bytes(&vectors.avl.root_digest);
+        for (proof, note) in vectors.avl.lookup_proofs.iter().zip(&vectors.notes) {
+            let verified =
+                basis_trees::verify_operations(&root_digest, &bytes(&proof.proof), &bytes(&proof.operations)).unwrap();
+            assert_eq!(verified.digest.to_vec(), root_digest);
+            assert_eq!(verified.results, vec![Some(bytes(&note.avl_value))]);
+        }
+    }
+
+    #[test]
+    fn test_deterministic_signatures_repeat() {
+        let (secret, pubkey) = fixture_party("alice");
+        let first = deterministic_sign(b"message", &secret, &pubkey);
+        assert_eq!(first, deterministic_sign(b"message", &secret, &pubkey));
+        assert_ne!(first, deterministic_sign(b"other message", &secret, &pubkey));
+    }
+}
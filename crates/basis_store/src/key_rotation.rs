@@ -0,0 +1,229 @@
+//! Issuer key rotation
+//!
+//! An issuer whose key is compromised, or simply due for replacement, signs a
+//! rotation record with the old key delegating to a new one. The tracker
+//! links every key of such a chain to one identity: the debt ceiling counts
+//! the notes and reserves of all its keys together, so notes signed with the
+//! new key draw on the same collateral as those signed with the old ones.
+//!
+//! Once rotated, a key may no longer sign notes, which is what stops a thief
+//! from issuing debt with a stolen key. Notes it signed before the rotation
+//! stay recorded and redeemable. Each key can be rotated once and joined to a
+//! single identity, so an identity's keys always form a single chain.
+
+use thiserror::Error;
+
+use crate::{schnorr, NoteError, PubKey, Signature};
+
+/// Domain separation tag that opens every key rotation signing message
+pub const KEY_ROTATION_DOMAIN_TAG: &[u8; 16] = b"BASIS-KEY-ROTATE";
+
+#[derive(Error, Debug)]
+pub enum KeyRotationError {
+    #[error("Invalid key rotation signature")]
+    InvalidSignature,
+    #[error("New key is the same as the old key")]
+    SameKey,
+    #[error("Key rotation timestamp is in the future")]
+    FutureTimestamp,
+    #[error("Key {0} has already been rotated")]
+    AlreadyRotated(String),
+    #[error("Key {0} already belongs to an identity")]
+    KeyInUse(String),
+    #[error("Storage error: {0}")]
+    StorageError(String),
+}
+
+impl From<NoteError> for KeyRotationError {
+    fn from(err: NoteError) -> Self {
+        match err {
+            NoteError::InvalidSignature => KeyRotationError::InvalidSignature,
+            NoteError::StorageError(msg) => KeyRotationError::StorageError(msg),
+            _ => KeyRotationError::StorageError(format!("{:?}", err)),
+        }
+    }
+}
+
+/// A statement signed by the old key delegating its identity to the new key
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyRotation {
+    pub old_pubkey: PubKey,
+    pub new_pubkey: PubKey,
+    /// Time of the rotation in milliseconds since the Unix epoch
+    pub timestamp: u64,
+    /// Old key's signature over the rotation
+    pub signature: Signature,
+}
+
+impl KeyRotation {
+    /// Create a rotation from the key of `old_secret_key` to `new_pubkey`
+    pub fn create_and_sign(
+        old_secret_key: &[u8; 32],
+        new_pubkey: PubKey,
+        timestamp: u64,
+    ) -> Result<Self, NoteError> {
+        let secret_key = secp256k1::SecretKey::from_slice(old_secret_key).map_err(|_| NoteError::InvalidSignature)?;
+        let old_pubkey = secp256k1::PublicKey::from_secret_key(&secp256k1::Secp256k1::new(), &secret_key).serialize();
+        let mut rotation = Self {
+            old_pubkey,
+            new_pubkey,
+            timestamp,
+            signature: [0u8; 65],
+        };
+        rotation.signature = schnorr::schnorr_sign(&rotation.signing_message(), old_secret_key, &old_pubkey)?;
+        Ok(rotation)
+    }
+
+    /// Message the old key signs:
+    ///
+    /// "BASIS-KEY-ROTATE" || oldKey || newKey || timestamp
+    ///
+    /// with the timestamp as an 8-byte big-endian integer.
+    pub fn signing_message(&self) -> Vec<u8> {
+        let mut message = Vec::with_capacity(16 + 33 + 33 + 8);
+        message.extend_from_slice(KEY_ROTATION_DOMAIN_TAG);
+        message.extend_from_slice(&self.old_pubkey);
+        message.extend_from_slice(&self.new_pubkey);
+        message.extend_from_slice(&self.timestamp.to_be_bytes());
+        message
+    }
+
+    /// Verify the old key's signature
+    pub fn verify_signature(&self) -> Result<(), NoteError> {
+        schnorr::schnorr_verify(&self.signature, &self.signing_message(), &self.old_pubkey)
+    }
+}
+
+/// The keys of one identity, linked by the rotations between them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyHistory {
+    /// Key the identity started with
+    pub root_pubkey: PubKey,
+    /// Rotations from the root key to the current key, oldest first
+    pub rotations: Vec<KeyRotation>,
+}
+
+impl KeyHistory {
+    /// Key the identity signs notes with now
+    pub fn current_pubkey(&self) -> PubKey {
+        self.rotations
+            .last()
+            .map_or(self.root_pubkey, |rotation| rotation.new_pubkey)
+    }
+
+    /// Every key of the identity, oldest first
+    pub fn keys(&self) -> Vec<PubKey> {
+        std::iter::once(self.root_pubkey)
+            .chain(self.rotations.iter().map(|rotation| rotation.new_pubkey))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::MockClock, debt_policy::DebtCeilingPolicy, reserve_tracker::ReserveTracker, schnorr::generate_keypair,
+        ExtendedReserveInfo, IouNote, TrackerStateManager,
+    };
+    use std::sync::Arc;
+
+    #[test]
+    fn test_rotation_links_keys_to_one_identity() {
+        let (first_secret, first) = generate_keypair();
+        let (second_secret, second) = generate_keypair();
+        let (third_secret, third) = generate_keypair();
+        let (_, recipient) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        tracker.set_clock(Arc::new(MockClock::new(10_000)));
+
+        let note = IouNote::create_and_sign(recipient, 100, 1_000, &first_secret).unwrap();
+        tracker.add_note(&first, &note).unwrap();
+
+        tracker
+            .rotate_issuer_key(&KeyRotation::create_and_sign(&first_secret, second, 2_000).unwrap())
+            .unwrap();
+        tracker
+            .rotate_issuer_key(&KeyRotation::create_and_sign(&second_secret, third, 3_000).unwrap())
+            .unwrap();
+
+        // Every key of the chain resolves to the same history
+        for key in [first, second, third] {
+            let history = tracker.get_key_history(&key).unwrap();
+            assert_eq!(history.root_pubkey, first);
+            assert_eq!(history.current_pubkey(), third);
+            assert_eq!(history.keys(), vec![first, second, third]);
+        }
+
+        // Retired keys may no longer sign notes; the current key can
+        let note = IouNote::create_and_sign(recipient, 200, 4_000, &first_secret).unwrap();
+        assert!(matches!(tracker.add_note(&first, &note), Err(NoteError::KeyRotated)));
+        let note = IouNote::create_and_sign(recipient, 50, 4_000, &third_secret).unwrap();
+        tracker.add_note(&third, &note).unwrap();
+
+        // Notes signed before the rotation stay recorded
+        assert_eq!(tracker.get_total_debt(&first, &recipient).unwrap(), 100);
+    }
+
+    #[test]
+    fn test_rotation_validation() {
+        let (first_secret, first) = generate_keypair();
+        let (second_secret, second) = generate_keypair();
+        let (_, third) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        tracker.set_clock(Arc::new(MockClock::new(10_000)));
+
+        let rotate = |tracker: &mut TrackerStateManager, secret: &[u8; 32], new_pubkey: PubKey, timestamp: u64| {
+            tracker.rotate_issuer_key(&KeyRotation::create_and_sign(secret, new_pubkey, timestamp).unwrap())
+        };
+
+        assert!(matches!(rotate(&mut tracker, &first_secret, first, 1_000), Err(KeyRotationError::SameKey)));
+        assert!(matches!(
+            rotate(&mut tracker, &first_secret, second, 20_000),
+            Err(KeyRotationError::FutureTimestamp)
+        ));
+        let mut forged = KeyRotation::create_and_sign(&first_secret, second, 1_000).unwrap();
+        forged.new_pubkey = third;
+        assert!(matches!(tracker.rotate_issuer_key(&forged), Err(KeyRotationError::InvalidSignature)));
+
+        rotate(&mut tracker, &first_secret, second, 1_000).unwrap();
+        assert!(matches!(
+            rotate(&mut tracker, &first_secret, third, 2_000),
+            Err(KeyRotationError::AlreadyRotated(_))
+        ));
+        // Rotating back would turn the chain into a cycle
+        assert!(matches!(
+            rotate(&mut tracker, &second_secret, first, 2_000),
+            Err(KeyRotationError::KeyInUse(_))
+        ));
+    }
+
+    #[test]
+    fn test_rotated_identity_shares_debt_ceiling() {
+        let (old_secret, old) = generate_keypair();
+        let (new_secret, new) = generate_keypair();
+        let (_, alice) = generate_keypair();
+        let (_, bob) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        tracker.set_clock(Arc::new(MockClock::new(10_000)));
+
+        // The reserve stays owned by the old key
+        let reserves = ReserveTracker::new();
+        reserves
+            .update_reserve(ExtendedReserveInfo::new(&[1u8; 32], &old, 1_000, None, 100))
+            .unwrap();
+        tracker.set_debt_policy(DebtCeilingPolicy::new(1.0), reserves);
+
+        let note = IouNote::create_and_sign(alice, 600, 1_000, &old_secret).unwrap();
+        tracker.add_note(&old, &note).unwrap();
+        tracker
+            .rotate_issuer_key(&KeyRotation::create_and_sign(&old_secret, new, 2_000).unwrap())
+            .unwrap();
+
+        // The new key is backed by the old key's reserve, minus the old key's debt
+        let note = IouNote::create_and_sign(bob, 500, 3_000, &new_secret).unwrap();
+        assert!(matches!(tracker.add_note(&new, &note), Err(NoteError::InsufficientCollateral)));
+        let note = IouNote::create_and_sign(bob, 400, 3_000, &new_secret).unwrap();
+        tracker.add_note(&new, &note).unwrap();
+    }
+}
@@ -0,0 +1,308 @@
+//! Double-entry journal of an account's notes for plain-text accounting
+//!
+//! Each recorded note version that raises the debt or the redeemed amount of
+//! a note becomes one transaction, seen from the account the journal is for.
+//! Counterparties get their own sub-accounts, named by hex public key:
+//!
+//! | Account is | Change   | Debit                               | Credit                              |
+//! |------------|----------|-------------------------------------|-------------------------------------|
+//! | issuer     | debt     | `Expenses:Basis`                    | `Liabilities:Basis:Payable:<recipient>` |
+//! | issuer     | redeemed | `Liabilities:Basis:Payable:<recipient>` | `Assets:Basis:Reserve`          |
+//! | recipient  | debt     | `Assets:Basis:Receivable:<issuer>`  | `Income:Basis`                      |
+//! | recipient  | redeemed | `Assets:Basis:Redeemed`             | `Assets:Basis:Receivable:<issuer>`  |
+//!
+//! Amounts are in ERG with nine decimals, so nanoERG amounts are exact.
+//! Accrued interest is not journaled, as it is never recorded as a note
+//! version.
+
+use std::collections::BTreeSet;
+
+use crate::reports::format_utc;
+use crate::{IouNote, PubKey};
+
+/// Commodity amounts are written in
+pub const LEDGER_COMMODITY: &str = "ERG";
+
+/// Plain-text accounting format a journal is rendered in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerFormat {
+    /// hledger (and ledger-cli) journal
+    Hledger,
+    /// Beancount ledger, with the accounts it uses opened up front
+    Beancount,
+}
+
+impl LedgerFormat {
+    /// Parse a format name as used in requests
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "hledger" => Some(Self::Hledger),
+            "beancount" => Some(Self::Beancount),
+            _ => None,
+        }
+    }
+
+    /// File extension of a journal in this format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Hledger => "journal",
+            Self::Beancount => "beancount",
+        }
+    }
+}
+
+/// What a journal entry records
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalEntryKind {
+    /// The issuer took on more debt
+    Debt,
+    /// Debt was redeemed against the issuer's reserve
+    Redemption,
+}
+
+/// One transaction moving `amount` from the credited to the debited account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    /// Timestamp of the note version, in milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub kind: JournalEntryKind,
+    pub issuer_pubkey: PubKey,
+    pub recipient_pubkey: PubKey,
+    pub debit: String,
+    pub credit: String,
+    /// Amount in nanoERG
+    pub amount: u64,
+}
+
+/// Journal entries of one note, from the versions recorded for it, oldest first
+///
+/// `account` must be the note's issuer or recipient.
+pub fn journal_entries(
+    account: &PubKey,
+    issuer_pubkey: &PubKey,
+    versions: &[IouNote],
+) -> Vec<JournalEntry> {
+    let mut entries = Vec::new();
+    let (mut collected, mut redeemed) = (0u64, 0u64);
+    for note in versions {
+        let recipient_pubkey = note.recipient_pubkey;
+        let entry = |kind, amount| {
+            let (debit, credit) = accounts(account, issuer_pubkey, &recipient_pubkey, kind);
+            JournalEntry {
+                timestamp: note.timestamp,
+                kind,
+                issuer_pubkey: *issuer_pubkey,
+                recipient_pubkey,
+                debit,
+                credit,
+                amount,
+            }
+        };
+        if note.amount_collected > collected {
+            entries.push(entry(
+                JournalEntryKind::Debt,
+                note.amount_collected - collected,
+            ));
+        }
+        if note.amount_redeemed > redeemed {
+            entries.push(entry(
+                JournalEntryKind::Redemption,
+                note.amount_redeemed - redeemed,
+            ));
+        }
+        collected = collected.max(note.amount_collected);
+        redeemed = redeemed.max(note.amount_redeemed);
+    }
+    entries
+}
+
+/// Debited and credited accounts of a change, seen from `account`
+fn accounts(
+    account: &PubKey,
+    issuer_pubkey: &PubKey,
+    recipient_pubkey: &PubKey,
+    kind: JournalEntryKind,
+) -> (String, String) {
+    if account == issuer_pubkey {
+        let payable = format!(
+            "Liabilities:Basis:Payable:{}",
+            hex::encode(recipient_pubkey)
+        );
+        match kind {
+            JournalEntryKind::Debt => ("Expenses:Basis".to_string(), payable),
+            JournalEntryKind::Redemption => (payable, "Assets:Basis:Reserve".to_string()),
+        }
+    } else {
+        let receivable = format!("Assets:Basis:Receivable:{}", hex::encode(issuer_pubkey));
+        match kind {
+            JournalEntryKind::Debt => (receivable, "Income:Basis".to_string()),
+            JournalEntryKind::Redemption => ("Assets:Basis:Redeemed".to_string(), receivable),
+        }
+    }
+}
+
+/// Format a nanoERG amount in ERG with all nine decimals
+fn format_amount(nano_erg: u64, negative: bool) -> String {
+    format!(
+        "{}{}.{:09} {}",
+        if negative { "-" } else { "" },
+        nano_erg / 1_000_000_000,
+        nano_erg % 1_000_000_000,
+        LEDGER_COMMODITY
+    )
+}
+
+/// Render entries, oldest first, as a complete journal
+pub fn render_journal(format: LedgerFormat, entries: &[JournalEntry]) -> String {
+    let mut out = String::new();
+    if format == LedgerFormat::Beancount {
+        // Beancount rejects postings to accounts that were never opened
+        let accounts: BTreeSet<&str> = entries
+            .iter()
+            .flat_map(|entry| [entry.debit.as_str(), entry.credit.as_str()])
+            .collect();
+        let opened = entries.first().map_or(0, |entry| entry.timestamp);
+        for account in accounts {
+            out.push_str(&format!(
+                "{} open {} {}\n",
+                &format_utc(opened)[..10],
+                account,
+                LEDGER_COMMODITY
+            ));
+        }
+        if !out.is_empty() {
+            out.push('\n');
+        }
+    }
+
+    for entry in entries {
+        let date = &format_utc(entry.timestamp)[..10];
+        let description = match entry.kind {
+            JournalEntryKind::Debt => "Basis note",
+            JournalEntryKind::Redemption => "Basis redemption",
+        };
+        let issuer = hex::encode(entry.issuer_pubkey);
+        let recipient = hex::encode(entry.recipient_pubkey);
+        match format {
+            LedgerFormat::Hledger => {
+                out.push_str(&format!(
+                    "{} * {} | {} -> {}\n",
+                    date, description, issuer, recipient
+                ));
+                out.push_str(&format!("    ; timestamp: {}\n", entry.timestamp));
+            }
+            LedgerFormat::Beancount => {
+                out.push_str(&format!(
+                    "{} * \"{}\" \"{} -> {}\"\n",
+                    date, description, issuer, recipient
+                ));
+                out.push_str(&format!("  timestamp: \"{}\"\n", entry.timestamp));
+            }
+        }
+        out.push_str(&format!(
+            "    {}  {}\n",
+            entry.debit,
+            format_amount(entry.amount, false)
+        ));
+        out.push_str(&format!(
+            "    {}  {}\n\n",
+            entry.credit,
+            format_amount(entry.amount, true)
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pubkey(n: u8) -> PubKey {
+        let mut key = [n; 33];
+        key[0] = 0x02;
+        key
+    }
+
+    fn version(collected: u64, redeemed: u64, timestamp: u64) -> IouNote {
+        let mut note = IouNote::new(pubkey(2), collected, redeemed, timestamp, [0u8; 65]);
+        note.amount_redeemed = redeemed;
+        note
+    }
+
+    #[test]
+    fn test_entries_follow_note_versions() {
+        let (issuer, recipient) = (pubkey(1), pubkey(2));
+        let versions = [
+            version(1_500_000_000, 0, 1_709_210_096_789),
+            // Re-stored after an acknowledgment: nothing changed
+            version(1_500_000_000, 0, 1_709_210_096_789),
+            version(2_000_000_000, 0, 1_709_300_000_000),
+            version(2_000_000_000, 500_000_000, 1_709_400_000_000),
+        ];
+
+        let entries = journal_entries(&issuer, &issuer, &versions);
+        let amounts: Vec<_> = entries
+            .iter()
+            .map(|entry| (entry.kind, entry.amount))
+            .collect();
+        assert_eq!(
+            amounts,
+            vec![
+                (JournalEntryKind::Debt, 1_500_000_000),
+                (JournalEntryKind::Debt, 500_000_000),
+                (JournalEntryKind::Redemption, 500_000_000),
+            ]
+        );
+        let payable = format!("Liabilities:Basis:Payable:{}", hex::encode(recipient));
+        assert_eq!(
+            (entries[0].debit.as_str(), entries[0].credit.as_str()),
+            ("Expenses:Basis", payable.as_str())
+        );
+        assert_eq!(
+            (entries[2].debit.as_str(), entries[2].credit.as_str()),
+            (payable.as_str(), "Assets:Basis:Reserve")
+        );
+
+        // The recipient books the same changes as a receivable
+        let entries = journal_entries(&recipient, &issuer, &versions);
+        let receivable = format!("Assets:Basis:Receivable:{}", hex::encode(issuer));
+        assert_eq!(
+            (entries[0].debit.as_str(), entries[0].credit.as_str()),
+            (receivable.as_str(), "Income:Basis")
+        );
+        assert_eq!(
+            (entries[2].debit.as_str(), entries[2].credit.as_str()),
+            ("Assets:Basis:Redeemed", receivable.as_str())
+        );
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let (issuer, recipient) = (pubkey(1), pubkey(2));
+        let entries = journal_entries(
+            &issuer,
+            &issuer,
+            &[version(1_500_000_000, 0, 1_709_210_096_789)],
+        );
+        let payable = format!("Liabilities:Basis:Payable:{}", hex::encode(recipient));
+
+        let hledger = render_journal(LedgerFormat::Hledger, &entries);
+        assert_eq!(
+            hledger,
+            format!(
+                "2024-02-29 * Basis note | {} -> {}\n    ; timestamp: 1709210096789\n    Expenses:Basis  1.500000000 ERG\n    {}  -1.500000000 ERG\n\n",
+                hex::encode(issuer),
+                hex::encode(recipient),
+                payable
+            )
+        );
+
+        let beancount = render_journal(LedgerFormat::Beancount, &entries);
+        assert!(beancount.starts_with(&format!(
+            "2024-02-29 open Expenses:Basis ERG\n2024-02-29 open {} ERG\n\n2024-02-29 * \"Basis note\"",
+            payable
+        )));
+        assert!(beancount.contains("  timestamp: \"1709210096789\"\n"));
+        assert_eq!(render_journal(LedgerFormat::Beancount, &[]), "");
+    }
+}
@@ -1,22 +1,50 @@
 //! Core data structures for Basis tracker
 
 pub mod avl_tree;
+#[cfg(feature = "chaincash_import")]
+pub mod chaincash_import;
+pub mod clock;
+pub mod commitment_verification;
 
 pub mod contract_compiler;
+pub mod contract_registry;
+pub mod coverage;
 pub mod cross_verification;
+pub mod debt_policy;
+pub mod disputes;
+pub mod key_rotation;
+pub mod ledger;
+pub mod network;
+pub mod netting;
+pub mod network_stats;
+pub mod note_history;
+pub mod note_limits;
+pub mod note_search;
+#[cfg(any(test, feature = "test-support"))]
+pub mod mock_node;
 pub mod ergo_scanner;
+pub mod explorer_backend;
+#[cfg(any(test, feature = "test-support"))]
+pub mod fixtures;
 pub mod persistence;
 pub mod redemption;
+pub mod reports;
 pub mod tracker_scanner;
 #[cfg(test)]
 pub mod redemption_blockchain_tests;
 #[cfg(test)]
 pub mod redemption_simple_tests;
+pub mod reserve_lineage;
 pub mod reserve_tracker;
+pub mod scan_manager;
 pub mod schnorr;
 pub mod schnorr_test_vectors;
 pub mod schnorr_tests;
+pub mod split_notes;
+pub mod storage_encryption;
+pub mod storage_maintenance;
 pub mod transaction_builder;
+pub mod wallet_connector;
 #[cfg(test)]
 pub mod cross_validation_tests;
 #[cfg(test)]
@@ -42,8 +70,12 @@ pub mod basis_spec_tests;
 
 use secp256k1;
 use basis_core;
-use basis_core::impls::SchnorrVerifier;
-use basis_core::traits::SignatureVerifier;
+use std::sync::Arc;
+
+pub use basis_core::{
+    check_note_payload, decrypt_note_payload, encrypt_note_payload, AccrualMethod, InterestTerms, NotePayloadError,
+    SignatureSchemeId, SigningFormat,
+};
 
 /// Public key type (Secp256k1)
 pub type PubKey = [u8; 33];
@@ -64,6 +96,37 @@ pub struct IouNote {
     pub timestamp: u64,
     /// Signature from issuer (A)
     pub signature: Signature,
+    /// Optional acknowledgment signature from recipient (B) over the same message
+    pub recipient_signature: Option<Signature>,
+    /// Format of the message both signatures cover
+    pub signing_format: SigningFormat,
+    /// Interest the issuer agreed to pay on the outstanding debt, if any
+    pub interest: Option<InterestTerms>,
+    /// Reference the issuer signed with the note, such as an invoice or order ID
+    pub memo: Option<String>,
+    /// Scheme both signatures are made with
+    ///
+    /// Keys and signatures are still sized for secp256k1 Schnorr, so a scheme
+    /// with other sizes also needs them widened.
+    pub signature_scheme: SignatureSchemeId,
+}
+
+/// Longest memo a note can carry, in bytes
+pub const MAX_NOTE_MEMO_LEN: usize = 128;
+
+/// Check that a memo is non-empty, at most [`MAX_NOTE_MEMO_LEN`] bytes and
+/// free of control characters
+pub fn check_note_memo(memo: &str) -> Result<(), String> {
+    if memo.is_empty() {
+        return Err("memo must not be empty".to_string());
+    }
+    if memo.len() > MAX_NOTE_MEMO_LEN {
+        return Err(format!("memo must be at most {} bytes", MAX_NOTE_MEMO_LEN));
+    }
+    if memo.chars().any(char::is_control) {
+        return Err("memo must not contain control characters".to_string());
+    }
+    Ok(())
 }
 
 /// Tracker state commitment
@@ -107,6 +170,10 @@ pub struct TrackerBoxInfo {
     pub creation_height: u64,
     /// Tracker NFT ID (hex encoded)
     pub tracker_nft_id: String,
+    /// Transaction that created the box (hex encoded), unknown for boxes
+    /// stored before it was recorded
+    #[serde(default)]
+    pub transaction_id: Option<String>,
 }
 
 /// Proof for a specific note against tracker state
@@ -120,6 +187,15 @@ pub struct NoteProof {
     pub operations: Vec<u8>,
 }
 
+/// Proofs for every note owed to one recipient
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipientProofs {
+    /// Issuer of each note with the note's proof
+    pub proofs: Vec<(PubKey, NoteProof)>,
+    /// AVL tree root digest all proofs were generated against
+    pub root_digest: [u8; 33],
+}
+
 /// Tracker lookup proof for context var #8 in redemption transactions
 /// Proves that totalDebt exists in the tracker's AVL tree at key hash(ownerKey||receiverKey)
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -203,6 +279,18 @@ pub enum NoteError {
     InsufficientCollateral,
     StorageError(String),
     UnsupportedOperation,
+    /// The collected or redeemed amount is lower than the recorded note's
+    AmountDecreased,
+    /// The redeemed amount is larger than the collected amount
+    RedeemedExceedsCollected,
+    /// The issuer key has been rotated to a new key and may no longer sign notes
+    KeyRotated,
+    /// The note records less debt than the configured dust threshold
+    AmountBelowMinimum { minimum: u64 },
+    /// The note records more debt than a single note may
+    AmountAboveMaximum { maximum: u64 },
+    /// The issuer already holds as many notes as it may
+    TooManyNotes { maximum: usize },
 }
 
 impl From<secp256k1::Error> for NoteError {
@@ -211,6 +299,27 @@ impl From<secp256k1::Error> for NoteError {
     }
 }
 
+/// Check that a note may replace the recorded version of it, if any
+///
+/// Collected and redeemed amounts only ever grow, and no more can be
+/// redeemed than was collected.
+fn check_amount_update(existing: Option<&IouNote>, note: &IouNote) -> Result<(), NoteError> {
+    if note.amount_redeemed > note.amount_collected {
+        return Err(NoteError::RedeemedExceedsCollected);
+    }
+    if let Some(existing) = existing {
+        if note.amount_collected < existing.amount_collected
+            || note.amount_redeemed < existing.amount_redeemed
+        {
+            return Err(NoteError::AmountDecreased);
+        }
+    }
+    Ok(())
+}
+
+/// Number of notes applied between AVL tree commits while rebuilding
+const AVL_REBUILD_COMMIT_INTERVAL: usize = 1_000;
+
 /// Tracker state manager with persistent AVL tree
 pub struct TrackerStateManager {
     avl_state: basis_trees::BasisAvlTree,
@@ -218,19 +327,41 @@ pub struct TrackerStateManager {
     storage: persistence::NoteStorage,
     /// Reserve AVL tree tracking hash(ownerKey || receiverKey) -> already_redeemed
     reserve_avl_state: basis_trees::BasisAvlTree,
+    /// Optional debt ceiling enforced on note creation
+    debt_policy: Option<debt_policy::DebtCeilingPolicy>,
+    /// Amount and note count limits enforced on note creation
+    note_limits: note_limits::NoteLimits,
+    /// Reserve tracker used to look up issuer collateral for the debt ceiling
+    reserve_tracker: Option<ReserveTracker>,
+    /// Source of the current time for timestamp checks
+    clock: Arc<dyn Clock>,
 }
 
 impl TrackerStateManager {
     /// Create a new tracker state manager with default storage location
     pub fn new() -> Self {
+        let data_dir = std::env::current_dir()
+            .unwrap_or_else(|_| std::path::PathBuf::from("."))
+            .join("crates/basis_server/data");
+        Self::with_storage_paths(&data_dir.join("notes"), &data_dir.join("avl_tree"))
+    }
+
+    /// Create a tracker state manager storing notes and the AVL tree at the given paths
+    pub fn with_storage_paths(storage_path: &std::path::Path, avl_tree_path: &std::path::Path) -> Self {
+        Self::with_storage_paths_and_cipher(storage_path, avl_tree_path, StorageCipher::default())
+    }
+
+    /// Create a tracker state manager storing notes at the given path sealed with
+    /// `cipher`, and the AVL tree at the given path
+    pub fn with_storage_paths_and_cipher(
+        storage_path: &std::path::Path,
+        avl_tree_path: &std::path::Path,
+        cipher: StorageCipher,
+    ) -> Self {
         tracing::debug!("Creating TrackerStateManager...");
 
-        // Use the standard storage location for production
         tracing::debug!("Opening note storage...");
-        let storage_path = std::env::current_dir()
-            .unwrap_or_else(|_| std::path::PathBuf::from("."))
-            .join("crates/basis_server/data/notes");
-        let storage = match persistence::NoteStorage::open(&storage_path) {
+        let storage = match persistence::NoteStorage::open_with_cipher(storage_path, cipher) {
             Ok(storage) => {
                 tracing::debug!("Note storage opened successfully at: {:?}", storage_path);
                 // Rebuild indices to ensure all existing notes are indexed
@@ -249,10 +380,13 @@ impl TrackerStateManager {
             }
         };
 
-        // Create in-memory AVL tree
-        let avl_state = match basis_trees::BasisAvlTree::new() {
+        // Open persistent AVL tree so large trees don't need to fit in memory
+        let avl_state = match basis_trees::BasisAvlTree::open_persistent(
+            avl_tree_path,
+            basis_trees::PersistentTreeConfig::default(),
+        ) {
             Ok(tree) => {
-                tracing::debug!("In-memory AVL tree created successfully");
+                tracing::debug!("Persistent AVL tree opened successfully at: {:?}", avl_tree_path);
                 tree
             }
             Err(e) => {
@@ -283,6 +417,10 @@ impl TrackerStateManager {
             },
             storage,
             reserve_avl_state,
+            debt_policy: None,
+            note_limits: note_limits::NoteLimits::default(),
+            reserve_tracker: None,
+            clock: clock::system_clock(),
         };
 
         if let Err(e) = manager.rebuild_avl_tree() {
@@ -300,29 +438,59 @@ impl TrackerStateManager {
     pub fn rebuild_avl_tree(&mut self) -> Result<(), NoteError> {
         tracing::info!("Rebuilding AVL tree from stored notes...");
 
-        let mut notes_with_issuer = self.storage.get_all_notes_with_issuer()
-            .map_err(|e| NoteError::StorageError(format!("Failed to get all notes: {:?}", e)))?;
+        // Every tree entry as (timestamp, key, value): one per note and one per
+        // split note allocation. Notes are streamed from storage, so only
+        // their tree entries are held in memory.
+        let mut entries: Vec<(u64, Vec<u8>, Vec<u8>)> = self
+            .storage
+            .iter_notes_with_issuer()
+            .map(|entry| {
+                let (issuer_pubkey, note) = entry
+                    .map_err(|e| NoteError::StorageError(format!("Failed to get all notes: {:?}", e)))?;
+                let key = NoteKey::from_keys(&issuer_pubkey, &note.recipient_pubkey);
+                Ok((note.timestamp, key.to_bytes(), note.amount_collected.to_be_bytes().to_vec()))
+            })
+            .collect::<Result<_, NoteError>>()?;
+        let splits_with_issuer = self.storage.get_all_split_notes_with_issuer()
+            .map_err(|e| NoteError::StorageError(format!("Failed to get all split notes: {:?}", e)))?;
+        for (issuer_pubkey, split) in &splits_with_issuer {
+            for (key, allocation) in split.allocation_keys(issuer_pubkey).iter().zip(&split.allocations) {
+                entries.push((split.timestamp, key.to_bytes(), allocation.amount.to_be_bytes().to_vec()));
+            }
+        }
 
-        if notes_with_issuer.is_empty() {
+        if entries.is_empty() {
             tracing::info!("No stored notes found, AVL tree remains empty");
             return Ok(());
         }
 
-        // Sort notes by timestamp ascending to ensure deterministic insertion order
+        // Sort entries by timestamp ascending to ensure deterministic insertion order
         // AVL tree structure depends on insertion order, so we must insert in the
         // same order as when notes were originally created
-        notes_with_issuer.sort_by_key(|(_, note)| note.timestamp);
+        entries.sort_by_key(|(timestamp, _, _)| *timestamp);
 
-        tracing::info!("Inserting {} notes into AVL tree in chronological order...", notes_with_issuer.len());
+        tracing::info!("Inserting {} notes into AVL tree in chronological order...", entries.len());
 
-        for (issuer_pubkey, note) in &notes_with_issuer {
-            let key = NoteKey::from_keys(issuer_pubkey, &note.recipient_pubkey);
-            let key_bytes = key.to_bytes();
-            let value_bytes = note.amount_collected.to_be_bytes().to_vec();
+        // A persistent tree already holds every note committed before the
+        // restart, so only notes it is missing or has stale are applied
+        // Applied one by one rather than as a batch, which would apply them in key order
+        let mut applied = 0usize;
+        for (_, key_bytes, value_bytes) in entries {
+            if self.avl_state.get(&key_bytes).as_ref() == Some(&value_bytes) {
+                continue;
+            }
 
             self.avl_state.update(key_bytes, value_bytes)
                 .map_err(|e| NoteError::StorageError(format!("AVL tree update failed during rebuild: {:?}", e)))?;
+            applied += 1;
+
+            // Commit periodically to bound the number of resident dirty nodes
+            if applied.is_multiple_of(AVL_REBUILD_COMMIT_INTERVAL) {
+                self.commit_avl_state()?;
+            }
         }
+        self.commit_avl_state()?;
+        tracing::info!("Applied {} notes to AVL tree", applied);
 
         self.update_state();
         let root_digest = self.current_state.avl_root_digest;
@@ -331,6 +499,59 @@ impl TrackerStateManager {
         Ok(())
     }
 
+    /// Check the stored notes against themselves and the AVL tree
+    ///
+    /// With `repair`, corrupt note records are restored from the note
+    /// history, preferring the version the AVL tree commits to, and the
+    /// indexes and the AVL tree are rebuilt when they disagree with the notes.
+    pub fn check_storage(&mut self, repair: bool) -> Result<storage_maintenance::NoteIntegrity, NoteError> {
+        let (records, corrupt) = self.storage.scan_note_records()?;
+        let mut integrity = storage_maintenance::NoteIntegrity {
+            records_checked: records,
+            corrupt_records: corrupt.iter().map(|key| hex::encode(key.to_bytes())).collect(),
+            stale_indexes: self.storage.counted_notes()? != records - corrupt.len() as u64,
+            ..Default::default()
+        };
+        if !corrupt.is_empty() {
+            tracing::warn!("{} of {} note records cannot be read", corrupt.len(), records);
+        }
+
+        if repair {
+            for key in &corrupt {
+                let committed = self
+                    .avl_state
+                    .get(&key.to_bytes())
+                    .and_then(|amount| amount.try_into().ok())
+                    .map(u64::from_be_bytes);
+                if self.storage.restore_note_from_history(key, committed)? {
+                    integrity.restored += 1;
+                } else {
+                    integrity.unrecoverable.push(hex::encode(key.to_bytes()));
+                }
+            }
+        }
+
+        for entry in self.storage.iter_notes_with_issuer() {
+            let (issuer_pubkey, note) = entry?;
+            let key = NoteKey::from_keys(&issuer_pubkey, &note.recipient_pubkey);
+            if self.avl_state.get(&key.to_bytes()) != Some(note.amount_collected.to_be_bytes().to_vec()) {
+                integrity.avl_mismatches += 1;
+            }
+        }
+
+        if repair {
+            if integrity.restored > 0 || integrity.stale_indexes {
+                self.storage.rebuild_indices()?;
+                integrity.indexes_rebuilt = true;
+            }
+            if integrity.avl_mismatches > 0 {
+                self.rebuild_avl_tree()?;
+                integrity.avl_rebuilt = true;
+            }
+        }
+        Ok(integrity)
+    }
+
     /// Create a new tracker state manager with temporary storage (used in tests only)
     pub fn new_with_temp_storage() -> Self {
         tracing::debug!("Creating TrackerStateManager (test version with temporary storage)...");
@@ -414,36 +635,105 @@ impl TrackerStateManager {
             },
             storage,
             reserve_avl_state,
+            debt_policy: None,
+            note_limits: note_limits::NoteLimits::default(),
+            reserve_tracker: None,
+            clock: clock::system_clock(),
         }
     }
 
-    /// Add a new note to the tracker state
-    /// Updates the AVL tree with hash(issuer||receiver) -> totalDebt mapping
-    pub fn add_note(&mut self, issuer_pubkey: &PubKey, note: &IouNote) -> Result<(), NoteError> {
+    /// Run every check `add_note` applies to a note without changing any state
+    ///
+    /// Returns all the errors the note would be rejected with, in the order
+    /// `add_note` checks them; an empty list means the note would be accepted.
+    pub fn validate_note(&self, issuer_pubkey: &PubKey, note: &IouNote) -> Vec<NoteError> {
+        self.check_note(issuer_pubkey, note).1
+    }
+
+    /// The note as it would be stored, along with every reason to reject it
+    fn check_note(&self, issuer_pubkey: &PubKey, note: &IouNote) -> (IouNote, Vec<NoteError>) {
+        self.check_verified_note(issuer_pubkey, note, note.verify_signature(issuer_pubkey))
+    }
+
+    /// Like [`check_note`](Self::check_note), with the issuer signature
+    /// already verified
+    fn check_verified_note(
+        &self,
+        issuer_pubkey: &PubKey,
+        note: &IouNote,
+        signature: Result<(), NoteError>,
+    ) -> (IouNote, Vec<NoteError>) {
+        let mut errors = Vec::new();
+
         // Validate that timestamp is not in the future
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|_| NoteError::StorageError("Failed to get current time".to_string()))?
-            .as_millis() as u64;
+        if note.timestamp > self.clock.now_millis() {
+            errors.push(NoteError::FutureTimestamp);
+        }
 
-        if note.timestamp > current_time {
-            return Err(NoteError::FutureTimestamp);
+        // Issuer signatures do not cover the redeemed amount, which the tracker
+        // maintains itself, so an update carries the recorded amount forward
+        let existing_note = self.lookup_note(issuer_pubkey, &note.recipient_pubkey).ok();
+        let mut note = note.clone();
+        if let Some(existing_note) = &existing_note {
+            note.amount_redeemed = note.amount_redeemed.max(existing_note.amount_redeemed);
         }
 
         // Check if there is an existing note with the same issuer-recipient pair
         // and ensure the new timestamp is greater than the existing one (ever increasing)
-        if let Ok(existing_note) = self.lookup_note(issuer_pubkey, &note.recipient_pubkey) {
-            if note.timestamp <= existing_note.timestamp {
-                return Err(NoteError::PastTimestamp);
+        if existing_note
+            .as_ref()
+            .is_some_and(|existing_note| note.timestamp <= existing_note.timestamp)
+        {
+            errors.push(NoteError::PastTimestamp);
+        }
+        if let Err(e) = check_amount_update(existing_note.as_ref(), &note) {
+            errors.push(e);
+        }
+
+        if signature.is_err() {
+            errors.push(NoteError::InvalidSignature);
+        }
+        match self.is_key_rotated(issuer_pubkey) {
+            Ok(false) => {}
+            Ok(true) => errors.push(NoteError::KeyRotated),
+            Err(e) => errors.push(e),
+        }
+
+        // Version 1 messages must stay the 48 bytes the reserve contract verifies,
+        // so only version 2 notes can carry interest terms or a memo
+        if (note.interest.is_some() || note.memo.is_some()) && note.signing_format == SigningFormat::V1 {
+            errors.push(NoteError::UnsupportedOperation);
+        }
+        if note.memo.as_deref().is_some_and(|memo| check_note_memo(memo).is_err()) {
+            errors.push(NoteError::UnsupportedOperation);
+        }
+
+        // Reject dust, oversized notes and notes to one recipient too many
+        if let Err(e) = self.note_limits.check_amount(&note) {
+            errors.push(e);
+        }
+        if existing_note.is_none() {
+            if let Err(e) = self.check_note_count(issuer_pubkey) {
+                errors.push(e);
             }
         }
 
-        // Verify the note signature before storing it
-        note.verify_signature(issuer_pubkey)
-            .map_err(|e| {
-                tracing::error!("Invalid note signature when adding note: {:?}", e);
-                NoteError::InvalidSignature
-            })?;
+        // Reject the note if it would push the issuer past their debt ceiling
+        if let Err(e) = self.check_debt_ceiling(issuer_pubkey, &note) {
+            errors.push(e);
+        }
+
+        (note, errors)
+    }
+
+    /// Add a new note to the tracker state
+    /// Updates the AVL tree with hash(issuer||receiver) -> totalDebt mapping
+    pub fn add_note(&mut self, issuer_pubkey: &PubKey, note: &IouNote) -> Result<(), NoteError> {
+        let (note, errors) = self.check_note(issuer_pubkey, note);
+        let note = &note;
+        if let Some(error) = errors.into_iter().next() {
+            return Err(self.reject_note(issuer_pubkey, note, error));
+        }
 
         // Prepare AVL tree key: hash(issuer_pubkey || receiver_pubkey)
         let key = NoteKey::from_keys(issuer_pubkey, &note.recipient_pubkey);
@@ -461,6 +751,7 @@ impl TrackerStateManager {
             Ok(()) => {
                 // Now store note in persistent storage
                 self.storage.store_note(issuer_pubkey, note)?;
+                self.commit_avl_state()?;
                 self.update_state();
                 Ok(())
             }
@@ -468,14 +759,475 @@ impl TrackerStateManager {
         }
     }
 
+    /// Add many notes at once, such as an import
+    ///
+    /// The signatures of the whole batch are verified in parallel up front.
+    /// Each note is then checked like in [`add_note`](Self::add_note),
+    /// seeing the notes accepted before it in the batch, and stored. The
+    /// accepted notes then reach the AVL tree as one batch with a single
+    /// commit. Returns one result per note, in order. Fails as a whole only
+    /// if the AVL tree cannot apply the batch; the stored notes then reach
+    /// the tree when it is rebuilt at the next start.
+    pub fn add_notes(&mut self, notes: &[(PubKey, IouNote)]) -> Result<Vec<Result<(), NoteError>>, NoteError> {
+        let verified = schnorr::verify_notes_batch(notes);
+        let mut results = Vec::with_capacity(notes.len());
+        let mut tree_entries = Vec::new();
+        for ((issuer_pubkey, note), signature) in notes.iter().zip(verified) {
+            let (note, errors) = self.check_verified_note(issuer_pubkey, note, signature);
+            if let Some(error) = errors.into_iter().next() {
+                results.push(Err(self.reject_note(issuer_pubkey, &note, error)));
+                continue;
+            }
+            let result = self.storage.store_note(issuer_pubkey, &note);
+            if result.is_ok() {
+                let key = NoteKey::from_keys(issuer_pubkey, &note.recipient_pubkey);
+                tree_entries.push((key.to_bytes(), note.amount_collected.to_be_bytes().to_vec()));
+            }
+            results.push(result);
+        }
+
+        if !tree_entries.is_empty() {
+            self.avl_state
+                .update_batch(tree_entries)
+                .map_err(|e| NoteError::StorageError(e.to_string()))?;
+            self.update_state();
+        }
+        Ok(results)
+    }
+
+    /// Log what a rejection reveals and hand back the error
+    fn reject_note(&self, issuer_pubkey: &PubKey, note: &IouNote, error: NoteError) -> NoteError {
+        match error {
+            // A validly signed note contradicting recorded history is evidence of equivocation
+            NoteError::PastTimestamp => {
+                if let Ok(Some(evidence)) = self.detect_equivocation(issuer_pubkey, note) {
+                    tracing::warn!(
+                        "Equivocation detected for issuer {}: recorded amount {} at {}, conflicting amount {} at {}",
+                        hex::encode(issuer_pubkey),
+                        evidence.recorded.amount_collected,
+                        evidence.recorded.timestamp,
+                        evidence.conflicting.amount_collected,
+                        evidence.conflicting.timestamp
+                    );
+                }
+            }
+            NoteError::InvalidSignature => {
+                tracing::error!("Invalid note signature when adding note");
+            }
+            _ => {}
+        }
+        error
+    }
+
+    /// Attach a recipient acknowledgment to the current version of a note.
+    /// The signature must be made by the recipient over the same message the issuer signed,
+    /// so an acknowledgment only covers the exact debt and timestamp it was given for.
+    pub fn acknowledge_note(
+        &mut self,
+        issuer_pubkey: &PubKey,
+        recipient_pubkey: &PubKey,
+        recipient_signature: Signature,
+    ) -> Result<IouNote, NoteError> {
+        let mut note = self.lookup_note(issuer_pubkey, recipient_pubkey)?;
+        note.recipient_signature = Some(recipient_signature);
+
+        note.verify_recipient_signature(issuer_pubkey).map_err(|e| {
+            tracing::error!("Invalid recipient acknowledgment: {:?}", e);
+            e
+        })?;
+
+        // The AVL tree commits to totalDebt only, so the tree is left untouched
+        self.storage.store_note(issuer_pubkey, &note)?;
+        Ok(note)
+    }
+
+    /// Get a page of the recorded versions of a note, oldest first, with the total version count
+    pub fn get_note_history(
+        &self,
+        issuer_pubkey: &PubKey,
+        recipient_pubkey: &PubKey,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<IouNote>, usize), NoteError> {
+        self.storage
+            .get_note_history(issuer_pubkey, recipient_pubkey, offset, limit)
+    }
+
+    /// Get a page of notes matching a query, oldest first, resuming after a cursor
+    pub fn scan_notes_by_timestamp(
+        &self,
+        query: &note_search::NoteQuery,
+        after: Option<&note_search::NoteCursor>,
+        limit: usize,
+    ) -> Result<Vec<(PubKey, IouNote)>, NoteError> {
+        self.storage.scan_notes_by_timestamp(query, after, limit)
+    }
+
+    /// Find notes matching a query using the storage indexes
+    pub fn search_notes(
+        &self,
+        query: &note_search::NoteQuery,
+    ) -> Result<note_search::NoteSearchPage, NoteError> {
+        self.storage.search_notes(query)
+    }
+
+    /// Network statistics with the `top_issuers` largest debtors and note
+    /// counts for the last `days` days, today included
+    pub fn network_stats(
+        &self,
+        top_issuers: usize,
+        days: u64,
+    ) -> Result<network_stats::NetworkStats, NoteError> {
+        let today = self.clock.now_millis() / network_stats::DAY_MILLIS;
+        let first_day = today.saturating_sub(days.saturating_sub(1));
+        self.storage.get_network_stats(top_issuers, first_day, today)
+    }
+
+    /// Double-entry journal of every note the account issued or holds, oldest
+    /// first, built from the recorded history of each note
+    pub fn account_journal(&self, account: &PubKey) -> Result<Vec<ledger::JournalEntry>, NoteError> {
+        let mut pairs: Vec<(PubKey, PubKey)> = self
+            .storage
+            .get_issuer_notes(account)?
+            .iter()
+            .map(|note| (*account, note.recipient_pubkey))
+            .collect();
+        pairs.extend(
+            self.storage
+                .get_recipient_notes_with_issuer(account)?
+                .into_iter()
+                .map(|(issuer, _)| (issuer, *account)),
+        );
+        pairs.sort();
+        pairs.dedup();
+
+        let mut entries = Vec::new();
+        for (issuer, recipient) in pairs {
+            let (versions, _) = self
+                .storage
+                .get_note_history(&issuer, &recipient, 0, usize::MAX)?;
+            entries.extend(ledger::journal_entries(account, &issuer, &versions));
+        }
+        entries.sort_by_key(|entry| entry.timestamp);
+        Ok(entries)
+    }
+
+    /// Check a note signed by the issuer against the recorded history of its
+    /// issuer-recipient pair, returning evidence if the two contradict each other
+    pub fn detect_equivocation(
+        &self,
+        issuer_pubkey: &PubKey,
+        note: &IouNote,
+    ) -> Result<Option<note_history::EquivocationEvidence>, NoteError> {
+        note.verify_signature(issuer_pubkey)?;
+        let (history, _) = self.get_note_history(issuer_pubkey, &note.recipient_pubkey, 0, usize::MAX)?;
+        Ok(note_history::find_equivocation(issuer_pubkey, &history, note))
+    }
+
+    /// Resolve a dispute over a note presented by either party
+    /// The note must carry a valid issuer signature; it is then classified
+    /// against the recorded history of its issuer-recipient pair.
+    pub fn resolve_dispute(
+        &self,
+        issuer_pubkey: &PubKey,
+        note: &IouNote,
+    ) -> Result<note_history::DisputeOutcome, NoteError> {
+        note.verify_signature(issuer_pubkey)?;
+        let (history, _) = self.get_note_history(issuer_pubkey, &note.recipient_pubkey, 0, usize::MAX)?;
+        Ok(note_history::resolve_dispute(issuer_pubkey, &history, note))
+    }
+
+    /// File a dispute against a note, freezing its redemption until the dispute is closed
+    ///
+    /// The filing must be signed by the party it names, and every piece of
+    /// evidence by the issuer. Evidence is classified against the recorded
+    /// history of the note as it stands now.
+    pub fn file_dispute(
+        &mut self,
+        filing: &disputes::DisputeFiling,
+    ) -> Result<disputes::Dispute, disputes::DisputeError> {
+        filing.verify_signature()?;
+        if filing.filed_at > self.clock.now_millis() {
+            return Err(disputes::DisputeError::FutureTimestamp);
+        }
+        self.lookup_note(&filing.issuer_pubkey, &filing.recipient_pubkey)?;
+        if let Some(open) = self.open_dispute(&filing.issuer_pubkey, &filing.recipient_pubkey)? {
+            return Err(disputes::DisputeError::AlreadyOpen(open.id));
+        }
+
+        let (history, _) =
+            self.get_note_history(&filing.issuer_pubkey, &filing.recipient_pubkey, 0, usize::MAX)?;
+        let dispute = filing.clone().into_dispute(self.storage.next_dispute_id()?, &history)?;
+        self.storage.store_dispute(&dispute)?;
+        tracing::warn!(
+            "Dispute {} filed by the {:?} against note {} -> {}",
+            dispute.id,
+            dispute.filed_by,
+            dispute.issuer_pubkey,
+            dispute.recipient_pubkey
+        );
+        Ok(dispute)
+    }
+
+    /// Close an open dispute as upheld or dismissed, unfreezing its note
+    pub fn close_dispute(
+        &mut self,
+        id: u64,
+        status: disputes::DisputeStatus,
+        note: String,
+    ) -> Result<disputes::Dispute, disputes::DisputeError> {
+        if status == disputes::DisputeStatus::Open {
+            return Err(disputes::DisputeError::InvalidResolution);
+        }
+        let mut dispute = self
+            .storage
+            .get_dispute(id)?
+            .ok_or(disputes::DisputeError::NotFound(id))?;
+        if !dispute.is_open() {
+            return Err(disputes::DisputeError::AlreadyClosed(id));
+        }
+
+        dispute.status = status;
+        dispute.resolution = Some(disputes::DisputeResolution {
+            note,
+            resolved_at: self.clock.now_millis(),
+        });
+        self.storage.store_dispute(&dispute)?;
+        tracing::info!("Dispute {} closed as {:?}", id, status);
+        Ok(dispute)
+    }
+
+    /// Get a dispute by ID
+    pub fn get_dispute(&self, id: u64) -> Result<Option<disputes::Dispute>, NoteError> {
+        self.storage.get_dispute(id)
+    }
+
+    /// Get every dispute, oldest first
+    pub fn get_disputes(&self) -> Result<Vec<disputes::Dispute>, NoteError> {
+        self.storage.get_disputes()
+    }
+
+    /// The open dispute freezing the note of a pair, if any
+    pub fn open_dispute(
+        &self,
+        issuer_pubkey: &PubKey,
+        recipient_pubkey: &PubKey,
+    ) -> Result<Option<disputes::Dispute>, NoteError> {
+        Ok(self
+            .storage
+            .get_disputes()?
+            .into_iter()
+            .find(|dispute| dispute.is_open() && dispute.concerns(issuer_pubkey, recipient_pubkey)))
+    }
+
+    /// Enable debt ceiling enforcement, using the reserve tracker as collateral source
+    pub fn set_debt_policy(
+        &mut self,
+        policy: debt_policy::DebtCeilingPolicy,
+        reserve_tracker: ReserveTracker,
+    ) {
+        self.debt_policy = Some(policy);
+        self.reserve_tracker = Some(reserve_tracker);
+    }
+
+    /// Replace the clock used for timestamp checks, e.g. with a [`clock::MockClock`] in tests
+    pub fn set_clock(&mut self, clock: Arc<dyn Clock>) {
+        self.clock = clock;
+    }
+
+    /// The clock used for timestamp checks
+    pub fn clock(&self) -> &Arc<dyn Clock> {
+        &self.clock
+    }
+
+    /// Disable debt ceiling enforcement
+    pub fn clear_debt_policy(&mut self) {
+        self.debt_policy = None;
+        self.reserve_tracker = None;
+    }
+
+    /// Replace the amount and note count limits checked on note creation
+    pub fn set_note_limits(&mut self, limits: note_limits::NoteLimits) {
+        self.note_limits = limits;
+    }
+
+    /// Check that the issuer may open a note to another recipient
+    fn check_note_count(&self, issuer_pubkey: &PubKey) -> Result<(), NoteError> {
+        if self.note_limits.max_notes_per_issuer == 0 {
+            return Ok(());
+        }
+        let mut note_count = 0;
+        for key in self.get_key_history(issuer_pubkey)?.keys() {
+            note_count += self.get_issuer_notes(&key)?.len();
+        }
+        self.note_limits.check_new_note(note_count)
+    }
+
+    /// Check that accepting `note` keeps the issuer's outstanding debt within the ceiling
+    fn check_debt_ceiling(&self, issuer_pubkey: &PubKey, note: &IouNote) -> Result<(), NoteError> {
+        let debt = note.outstanding_debt_with_interest(self.clock.now_millis());
+        self.check_added_debt(issuer_pubkey, Some(&note.recipient_pubkey), debt)
+    }
+
+    /// Check that `added_debt` keeps the issuer's outstanding debt within the
+    /// ceiling, with the note to `replaced_recipient`, if any, no longer counted
+    fn check_added_debt(
+        &self,
+        issuer_pubkey: &PubKey,
+        replaced_recipient: Option<&PubKey>,
+        added_debt: u64,
+    ) -> Result<(), NoteError> {
+        let (policy, reserve_tracker) = match (&self.debt_policy, &self.reserve_tracker) {
+            (Some(policy), Some(reserve_tracker)) => (policy, reserve_tracker),
+            _ => return Ok(()),
+        };
+
+        // Outstanding debt with accrued interest across all other recipients,
+        // plus every split note allocation, of every key of the issuer's identity
+        let now = self.clock.now_millis();
+        let keys = self.get_key_history(issuer_pubkey)?.keys();
+        let mut outstanding_debt = added_debt;
+        let mut collateral = 0u64;
+        for key in &keys {
+            outstanding_debt = self
+                .get_issuer_notes(key)?
+                .iter()
+                .filter(|existing| key != issuer_pubkey || Some(&existing.recipient_pubkey) != replaced_recipient)
+                .try_fold(outstanding_debt, |acc, existing| {
+                    acc.checked_add(existing.outstanding_debt_with_interest(now))
+                })
+                .ok_or(NoteError::AmountOverflow)?;
+            outstanding_debt = self
+                .storage
+                .get_issuer_split_notes(key)?
+                .iter()
+                .try_fold(outstanding_debt, |acc, split| acc.checked_add(split.total_amount()?))
+                .ok_or(NoteError::AmountOverflow)?;
+            collateral = collateral.saturating_add(debt_policy::issuer_collateral(reserve_tracker, key));
+        }
+
+        policy.check(issuer_pubkey, outstanding_debt, collateral)
+    }
+
+    /// Record a split note, adding one AVL tree entry per recipient
+    /// Each entry maps blake2b256(splitId || recipient) -> allocated amount
+    ///
+    /// Returns the split note's ID.
+    pub fn add_split_note(
+        &mut self,
+        issuer_pubkey: &PubKey,
+        split: &split_notes::SplitNote,
+    ) -> Result<[u8; 32], split_notes::SplitNoteError> {
+        split.check_allocations()?;
+        if split.timestamp > self.clock.now_millis() {
+            return Err(split_notes::SplitNoteError::FutureTimestamp);
+        }
+        // A single verification covers every allocation of the split
+        split.verify_signature(issuer_pubkey)?;
+        if self.is_key_rotated(issuer_pubkey)? {
+            return Err(split_notes::SplitNoteError::KeyRotated);
+        }
+
+        let split_id = split.id(issuer_pubkey);
+        if self.storage.get_split_note(issuer_pubkey, &split_id)?.is_some() {
+            return Err(split_notes::SplitNoteError::AlreadyRecorded(hex::encode(split_id)));
+        }
+        let total = split.total_amount().ok_or(split_notes::SplitNoteError::AmountOverflow)?;
+        self.check_added_debt(issuer_pubkey, None, total)?;
+
+        for (key, allocation) in split.allocation_keys(issuer_pubkey).iter().zip(&split.allocations) {
+            self.avl_state
+                .update(key.to_bytes(), allocation.amount.to_be_bytes().to_vec())
+                .map_err(|e| NoteError::StorageError(e.to_string()))?;
+        }
+        self.storage.store_split_note(issuer_pubkey, split)?;
+        self.commit_avl_state()?;
+        self.update_state();
+        Ok(split_id)
+    }
+
+    /// Get a split note by issuer and split ID
+    pub fn get_split_note(
+        &self,
+        issuer_pubkey: &PubKey,
+        split_id: &[u8; 32],
+    ) -> Result<Option<split_notes::SplitNote>, NoteError> {
+        self.storage.get_split_note(issuer_pubkey, split_id)
+    }
+
+    /// Get every split note of an issuer
+    pub fn get_issuer_split_notes(&self, issuer_pubkey: &PubKey) -> Result<Vec<split_notes::SplitNote>, NoteError> {
+        self.storage.get_issuer_split_notes(issuer_pubkey)
+    }
+
+    /// Record a rotation of an issuer key, linking the new key to the old key's identity
+    ///
+    /// From then on the old key may no longer sign notes, and the debt
+    /// ceiling of the new key counts the debt and collateral of every key of
+    /// the identity.
+    pub fn rotate_issuer_key(&mut self, rotation: &key_rotation::KeyRotation) -> Result<(), key_rotation::KeyRotationError> {
+        if rotation.new_pubkey == rotation.old_pubkey {
+            return Err(key_rotation::KeyRotationError::SameKey);
+        }
+        rotation.verify_signature()?;
+        if rotation.timestamp > self.clock.now_millis() {
+            return Err(key_rotation::KeyRotationError::FutureTimestamp);
+        }
+        if self.is_key_rotated(&rotation.old_pubkey)? {
+            return Err(key_rotation::KeyRotationError::AlreadyRotated(hex::encode(rotation.old_pubkey)));
+        }
+        // A key joins at most one identity, which also rules out cycles
+        let new_key_linked = self.storage.get_key_rotations()?.iter().any(|existing| {
+            existing.old_pubkey == rotation.new_pubkey || existing.new_pubkey == rotation.new_pubkey
+        });
+        if new_key_linked {
+            return Err(key_rotation::KeyRotationError::KeyInUse(hex::encode(rotation.new_pubkey)));
+        }
+
+        self.storage.store_key_rotation(rotation)?;
+        tracing::info!(
+            "Issuer key {} rotated to {}",
+            hex::encode(rotation.old_pubkey),
+            hex::encode(rotation.new_pubkey)
+        );
+        Ok(())
+    }
+
+    /// Whether an issuer key has been rotated away from
+    pub fn is_key_rotated(&self, pubkey: &PubKey) -> Result<bool, NoteError> {
+        Ok(self.storage.get_key_rotation(pubkey)?.is_some())
+    }
+
+    /// Get the keys of the identity a key belongs to
+    ///
+    /// A key that was never rotated is an identity of its own.
+    pub fn get_key_history(&self, pubkey: &PubKey) -> Result<key_rotation::KeyHistory, NoteError> {
+        let rotations = self.storage.get_key_rotations()?;
+
+        // Walk back to the root key, then forward to the current one. Every key
+        // is in at most one rotation on each side, so each walk ends.
+        let mut root_pubkey = *pubkey;
+        while let Some(rotation) = rotations.iter().find(|rotation| rotation.new_pubkey == root_pubkey) {
+            root_pubkey = rotation.old_pubkey;
+        }
+        let mut chain = Vec::new();
+        let mut current = root_pubkey;
+        while let Some(rotation) = rotations.iter().find(|rotation| rotation.old_pubkey == current) {
+            current = rotation.new_pubkey;
+            chain.push(rotation.clone());
+        }
+
+        Ok(key_rotation::KeyHistory {
+            root_pubkey,
+            rotations: chain,
+        })
+    }
+
     /// Update an existing note in the tracker state
     /// Updates the AVL tree with hash(issuer||receiver) -> totalDebt mapping
     pub fn update_note(&mut self, issuer_pubkey: &PubKey, note: &IouNote) -> Result<(), NoteError> {
         // Validate that timestamp is not in the future
-        let current_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|_| NoteError::StorageError("Failed to get current time".to_string()))?
-            .as_millis() as u64;
+        let current_time = self.clock.now_millis();
 
         if note.timestamp > current_time {
             return Err(NoteError::FutureTimestamp);
@@ -483,11 +1235,13 @@ impl TrackerStateManager {
 
         // Check if there is an existing note with the same issuer-recipient pair
         // and ensure the new timestamp is greater than the existing one (ever increasing)
-        if let Ok(existing_note) = self.lookup_note(issuer_pubkey, &note.recipient_pubkey) {
+        let existing_note = self.lookup_note(issuer_pubkey, &note.recipient_pubkey).ok();
+        if let Some(existing_note) = &existing_note {
             if note.timestamp <= existing_note.timestamp {
                 return Err(NoteError::PastTimestamp);
             }
         }
+        check_amount_update(existing_note.as_ref(), note)?;
 
         // Prepare AVL tree key: hash(issuer_pubkey || receiver_pubkey)
         let key = NoteKey::from_keys(issuer_pubkey, &note.recipient_pubkey);
@@ -505,6 +1259,7 @@ impl TrackerStateManager {
             Ok(()) => {
                 // Now store note in persistent storage
                 self.storage.store_note(issuer_pubkey, note)?;
+                self.commit_avl_state()?;
                 self.update_state();
                 Ok(())
             }
@@ -512,6 +1267,54 @@ impl TrackerStateManager {
         }
     }
 
+    /// Store a note exactly as another tracker recorded it
+    ///
+    /// Used by replicas applying a verified commitment delta. The issuer's
+    /// signature and the amounts are still checked, but not the timestamp
+    /// rules or acceptance policy: the primary already applied them, and a
+    /// completed redemption changes a note without changing its timestamp.
+    pub fn replicate_note(&mut self, issuer_pubkey: &PubKey, note: &IouNote) -> Result<(), NoteError> {
+        note.verify_signature(issuer_pubkey)?;
+        self.store_replicated_note(issuer_pubkey, note)
+    }
+
+    /// Store notes exactly as another tracker recorded them, in order
+    ///
+    /// Like [`replicate_note`](Self::replicate_note), but the signatures of
+    /// the whole batch are verified in parallel up front. Notes are stored up
+    /// to the first one rejected, calling `on_stored` after each.
+    pub fn replicate_notes(
+        &mut self,
+        notes: &[(PubKey, IouNote)],
+        mut on_stored: impl FnMut(&Self, &PubKey, &IouNote),
+    ) -> Result<(), NoteError> {
+        let verified = schnorr::verify_notes_batch(notes);
+        for ((issuer_pubkey, note), verified) in notes.iter().zip(verified) {
+            verified?;
+            self.store_replicated_note(issuer_pubkey, note)?;
+            on_stored(self, issuer_pubkey, note);
+        }
+        Ok(())
+    }
+
+    /// Store a replicated note whose signature has been verified
+    fn store_replicated_note(&mut self, issuer_pubkey: &PubKey, note: &IouNote) -> Result<(), NoteError> {
+        if note.amount_redeemed > note.amount_collected {
+            return Err(NoteError::RedeemedExceedsCollected);
+        }
+
+        let key_bytes = NoteKey::from_keys(issuer_pubkey, &note.recipient_pubkey).to_bytes();
+        let value_bytes = note.amount_collected.to_be_bytes().to_vec();
+        self.avl_state
+            .update(key_bytes, value_bytes)
+            .map_err(|e| NoteError::StorageError(e.to_string()))?;
+
+        self.storage.store_note(issuer_pubkey, note)?;
+        self.commit_avl_state()?;
+        self.update_state();
+        Ok(())
+    }
+
     /// Get the total debt for a specific (issuer, receiver) pair from the AVL tree
     /// Returns the cumulative debt amount (totalDebt) stored in the tracker's AVL tree
     pub fn get_total_debt(
@@ -549,13 +1352,14 @@ impl TrackerStateManager {
         // Get the total debt value
         let total_debt = self.get_total_debt(issuer_pubkey, recipient_pubkey)?;
 
-        // Generate AVL proof for the lookup
-        let avl_proof = self.avl_state.generate_proof();
+        // Generate AVL proof for the lookup of this key only
+        let lookup_proof = self.avl_state.lookup_proof(&key_bytes)
+            .map_err(|e| NoteError::StorageError(format!("Failed to generate lookup proof: {:?}", e)))?;
 
         Ok(TrackerLookupProof {
             key: key_bytes,
             value: total_debt.to_be_bytes().to_vec(),
-            proof: avl_proof,
+            proof: lookup_proof.avl_proof,
         })
     }
 
@@ -672,13 +1476,14 @@ impl TrackerStateManager {
                 proof: None, // Omitted for first redemption
             })
         } else {
-            // Generate AVL proof for the lookup
-            let avl_proof = self.reserve_avl_state.generate_proof();
+            // Generate AVL proof for the lookup of this key only
+            let lookup_proof = self.reserve_avl_state.lookup_proof(&key_bytes)
+                .map_err(|e| NoteError::StorageError(format!("Failed to generate lookup proof: {:?}", e)))?;
 
             Ok(ReserveLookupProof {
                 key: key_bytes,
                 value: value_bytes,
-                proof: Some(avl_proof),
+                proof: Some(lookup_proof.avl_proof),
             })
         }
     }
@@ -719,23 +1524,39 @@ impl TrackerStateManager {
         recipient_pubkey: &PubKey,
     ) -> Result<NoteProof, NoteError> {
         let key = NoteKey::from_keys(issuer_pubkey, recipient_pubkey);
-        let _key_bytes = key.to_bytes();
-
-        // For AVL trees, the proof is generated during lookup operations
-        // In a real implementation, we'd need to track operations for proof generation
-        let proof = self.avl_state.generate_proof();
-
-        // Placeholder for operations - in real implementation, this would track
-        // the specific operations that led to the current state
-        let operations = Vec::new();
+        let key_bytes = key.to_bytes();
 
         // Lookup the note to include in proof
         let note = self.lookup_note(issuer_pubkey, recipient_pubkey)?;
 
+        // The proof covers only the lookup of this key against the current
+        // root, and the operation trail holds that single lookup
+        let lookup_proof = self.avl_state.lookup_proof(&key_bytes)
+            .map_err(|e| NoteError::StorageError(format!("Failed to generate lookup proof: {:?}", e)))?;
+
         Ok(NoteProof {
             note,
-            avl_proof: proof,
-            operations,
+            avl_proof: lookup_proof.avl_proof,
+            operations: lookup_proof.operations,
+        })
+    }
+
+    /// Generate proofs for every note owed to a recipient
+    pub fn generate_recipient_proofs(
+        &mut self,
+        recipient_pubkey: &PubKey,
+    ) -> Result<RecipientProofs, NoteError> {
+        let notes = self.get_recipient_notes_with_issuer(recipient_pubkey)?;
+        let proofs = notes
+            .iter()
+            .map(|(issuer_pubkey, _)| {
+                self.generate_proof(issuer_pubkey, recipient_pubkey)
+                    .map(|proof| (*issuer_pubkey, proof))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(RecipientProofs {
+            proofs,
+            root_digest: self.avl_state.root_digest(),
         })
     }
 
@@ -750,6 +1571,42 @@ impl TrackerStateManager {
             .ok_or_else(|| NoteError::StorageError("Note not found".to_string()))
     }
 
+    /// Store the encrypted payload the issuer attached to the note with `timestamp`
+    ///
+    /// The envelope is kept as is; only the issuer and recipient can open it.
+    pub fn set_note_payload(
+        &self,
+        issuer_pubkey: &PubKey,
+        recipient_pubkey: &PubKey,
+        timestamp: u64,
+        envelope: &[u8],
+    ) -> Result<(), NoteError> {
+        let note = self.lookup_note(issuer_pubkey, recipient_pubkey)?;
+        if note.timestamp != timestamp {
+            return Err(NoteError::StorageError(
+                "Payload was written for another version of the note".to_string(),
+            ));
+        }
+        self.storage.store_note_payload(issuer_pubkey, recipient_pubkey, timestamp, envelope)
+    }
+
+    /// Encrypted payload of the current version of a note, with that version's timestamp
+    ///
+    /// A payload written for an earlier version of the note is not returned.
+    pub fn get_note_payload(
+        &self,
+        issuer_pubkey: &PubKey,
+        recipient_pubkey: &PubKey,
+    ) -> Result<Option<(u64, Vec<u8>)>, NoteError> {
+        let Some(note) = self.storage.get_note(issuer_pubkey, recipient_pubkey)? else {
+            return Ok(None);
+        };
+        Ok(self
+            .storage
+            .get_note_payload(issuer_pubkey, recipient_pubkey)?
+            .filter(|(timestamp, _)| *timestamp == note.timestamp))
+    }
+
     /// Get all notes for a specific issuer
     pub fn get_issuer_notes(&self, issuer_pubkey: &PubKey) -> Result<Vec<IouNote>, NoteError> {
         self.storage.get_issuer_notes(issuer_pubkey)
@@ -781,14 +1638,42 @@ impl TrackerStateManager {
         self.storage.get_all_notes_with_issuer()
     }
 
+    /// Iterate over all notes in the tracker with issuer information, reading
+    /// them from storage one at a time
+    ///
+    /// Prefer this over [`Self::get_all_notes_with_issuer`] when visiting
+    /// every note of a large tracker.
+    pub fn iter_notes_with_issuer(&self) -> impl Iterator<Item = Result<(PubKey, IouNote), NoteError>> + '_ {
+        self.storage.iter_notes_with_issuer()
+    }
+
+    /// Flush AVL tree changes to the node store when the tree is persistent
+    fn commit_avl_state(&mut self) -> Result<(), NoteError> {
+        if self.avl_state.is_persistent() {
+            self.avl_state.commit()
+                .map_err(|e| NoteError::StorageError(format!("AVL tree commit failed: {:?}", e)))?;
+        }
+        Ok(())
+    }
+
+    /// Handle for maintaining the note database from other threads
+    pub fn storage_maintenance_handle(&self) -> storage_maintenance::StorageHandle {
+        self.storage.maintenance_handle()
+    }
+
+    /// Commit pending AVL tree changes and sync note and tree storage to disk
+    /// Called on shutdown so that no accepted note is lost with the process
+    pub fn flush(&mut self) -> Result<(), NoteError> {
+        self.avl_state
+            .flush()
+            .map_err(|e| NoteError::StorageError(format!("AVL tree flush failed: {:?}", e)))?;
+        self.storage.persist()
+    }
+
     /// Update the current state with latest AVL tree root
     fn update_state(&mut self) {
         self.current_state.avl_root_digest = self.avl_state.root_digest();
-        // Update timestamp would be set to current time in real implementation
-        self.current_state.last_update_timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        self.current_state.last_update_timestamp = self.clock.now_millis();
     }
 
     /// Get the current tracker state
@@ -832,14 +1717,75 @@ impl IouNote {
             amount_redeemed,
             timestamp,
             signature,
+            recipient_signature: None,
+            signing_format: SigningFormat::V1,
+            interest: None,
+            memo: None,
+            signature_scheme: SignatureSchemeId::Schnorr,
         }
     }
 
+    /// Use a different signing message format for this note
+    pub fn with_signing_format(mut self, signing_format: SigningFormat) -> Self {
+        self.signing_format = signing_format;
+        self
+    }
+
+    /// Attach the interest terms the issuer signed
+    pub fn with_interest(mut self, interest: InterestTerms) -> Self {
+        self.interest = Some(interest);
+        self
+    }
+
+    /// Attach the memo the issuer signed
+    pub fn with_memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+
+    /// Sign the note's current fields with the issuer's secret key, using the
+    /// note's signature scheme
+    pub fn sign_with(mut self, issuer_secret_key: &[u8; 32]) -> Result<Self, NoteError> {
+        let scheme = basis_core::signature_scheme(self.signature_scheme);
+        let issuer_pubkey: PubKey = scheme
+            .public_key(issuer_secret_key)
+            .ok()
+            .and_then(|key| key.try_into().ok())
+            .ok_or(NoteError::InvalidSignature)?;
+        self.signature = Self::sign_message(scheme, &self.signing_message(&issuer_pubkey), issuer_secret_key, &issuer_pubkey)?;
+        Ok(self)
+    }
+
+    fn sign_message(
+        scheme: &dyn basis_core::SignatureScheme,
+        message: &[u8],
+        secret_key: &[u8; 32],
+        pubkey: &PubKey,
+    ) -> Result<Signature, NoteError> {
+        scheme
+            .sign(message, secret_key, pubkey)
+            .ok()
+            .and_then(|signature| signature.try_into().ok())
+            .ok_or(NoteError::InvalidSignature)
+    }
+
     /// Get the current outstanding debt (collected - redeemed)
     pub fn outstanding_debt(&self) -> u64 {
         self.amount_collected.saturating_sub(self.amount_redeemed)
     }
 
+    /// Interest accrued on the outstanding debt between the note's timestamp and `now_millis`
+    pub fn accrued_interest(&self, now_millis: u64) -> u64 {
+        self.interest.map_or(0, |interest| {
+            interest.accrued(self.outstanding_debt(), now_millis.saturating_sub(self.timestamp))
+        })
+    }
+
+    /// Outstanding debt plus the interest accrued on it by `now_millis`
+    pub fn outstanding_debt_with_interest(&self, now_millis: u64) -> u64 {
+        self.outstanding_debt().saturating_add(self.accrued_interest(now_millis))
+    }
+
     /// Check if the note is fully redeemed
     pub fn is_fully_redeemed(&self) -> bool {
         self.amount_collected == self.amount_redeemed
@@ -879,29 +1825,73 @@ impl IouNote {
             amount_redeemed: 0, // Start with no redemptions
             timestamp: _timestamp,
             signature,
+            recipient_signature: None,
+            signing_format: SigningFormat::V1,
+            interest: None,
+            memo: None,
+            signature_scheme: SignatureSchemeId::Schnorr,
         })
     }
 
+    /// Create and sign a new IOU note over a version 2 signing message,
+    /// optionally bound to the tracker with the given NFT ID
+    pub fn create_and_sign_v2(
+        recipient_pubkey: PubKey,
+        amount_collected: u64,
+        timestamp: u64,
+        tracker_id: Option<[u8; 32]>,
+        issuer_secret_key: &[u8; 32],
+    ) -> Result<Self, NoteError> {
+        Self::create_and_sign_with_interest(recipient_pubkey, amount_collected, timestamp, tracker_id, None, issuer_secret_key)
+    }
+
+    /// Create and sign a new IOU note over a version 2 signing message that
+    /// also covers the given interest terms
+    pub fn create_and_sign_with_interest(
+        recipient_pubkey: PubKey,
+        amount_collected: u64,
+        timestamp: u64,
+        tracker_id: Option<[u8; 32]>,
+        interest: Option<InterestTerms>,
+        issuer_secret_key: &[u8; 32],
+    ) -> Result<Self, NoteError> {
+        let mut note = Self::new(recipient_pubkey, amount_collected, 0, timestamp, [0u8; 65])
+            .with_signing_format(SigningFormat::V2 { tracker_id });
+        note.interest = interest;
+        note.sign_with(issuer_secret_key)
+    }
+
     /// Generate the message that should be signed following the Basis protocol specification.
     ///
+    /// For version 1 notes:
     /// message = blake2b256(ownerKeyBytes || receiverKeyBytes) || longToByteArray(totalDebt) || longToByteArray(timestamp)
     ///
-    /// Total: 48 bytes
+    /// Total: 48 bytes. Version 2 notes use the domain-separated message of
+    /// [`basis_core::signing_message_v2`], followed by the encoded
+    /// [`InterestTerms`] (rate (4 BE) || method (1)) when the note bears interest,
+    /// then memo length (2 BE) || memo bytes when the note carries a memo.
     ///
     /// # Arguments
     /// * `owner_pubkey` - Reserve owner's public key (the issuer of the IOU note)
     pub fn signing_message(&self, owner_pubkey: &PubKey) -> Vec<u8> {
-        crate::schnorr::signing_message(owner_pubkey, &self.recipient_pubkey, self.amount_collected, self.timestamp)
+        let mut message = self
+            .signing_format
+            .signing_message(owner_pubkey, &self.recipient_pubkey, self.amount_collected, self.timestamp);
+        if let Some(interest) = &self.interest {
+            message.extend_from_slice(&interest.to_bytes());
+        }
+        if let Some(memo) = &self.memo {
+            message.extend_from_slice(&(memo.len() as u16).to_be_bytes());
+            message.extend_from_slice(memo.as_bytes());
+        }
+        message
     }
 
-    /// Verify the signature against an issuer public key using Schnorr signature verification
-    /// This follows the chaincash-rs approach for Schnorr signature verification
+    /// Verify the signature against an issuer public key with the note's signature scheme
     pub fn verify_signature(&self, issuer_pubkey: &PubKey) -> Result<(), NoteError> {
         let message = self.signing_message(issuer_pubkey);
 
-        // Use the canonical Schnorr verification from basis_core
-        let verifier = SchnorrVerifier;
-        match verifier.verify_signature(&self.signature, &message, issuer_pubkey) {
+        match basis_core::verify_with_scheme(self.signature_scheme, &self.signature, &message, issuer_pubkey) {
             Ok(()) => Ok(()),
             Err(basis_core::traits::CryptoError::InvalidSignature) => Err(NoteError::InvalidSignature),
             Err(basis_core::traits::CryptoError::InvalidPublicKey) => Err(NoteError::InvalidSignature),
@@ -910,6 +1900,34 @@ impl IouNote {
         }
     }
 
+    /// Sign the note's signing message with the recipient's secret key and attach
+    /// the result as the recipient acknowledgment
+    pub fn acknowledge(
+        &mut self,
+        issuer_pubkey: &PubKey,
+        recipient_secret_key: &[u8; 32],
+    ) -> Result<(), NoteError> {
+        let message = self.signing_message(issuer_pubkey);
+        let scheme = basis_core::signature_scheme(self.signature_scheme);
+        let signature = Self::sign_message(scheme, &message, recipient_secret_key, &self.recipient_pubkey)?;
+        self.recipient_signature = Some(signature);
+        Ok(())
+    }
+
+    /// Whether the recipient has acknowledged this note
+    pub fn is_acknowledged(&self) -> bool {
+        self.recipient_signature.is_some()
+    }
+
+    /// Verify the recipient acknowledgment against the note's signing message
+    pub fn verify_recipient_signature(&self, issuer_pubkey: &PubKey) -> Result<(), NoteError> {
+        let signature = self.recipient_signature.ok_or(NoteError::InvalidSignature)?;
+        let message = self.signing_message(issuer_pubkey);
+
+        basis_core::verify_with_scheme(self.signature_scheme, &signature, &message, &self.recipient_pubkey)
+            .map_err(|_| NoteError::InvalidSignature)
+    }
+
     /// Get the recipient public key as a hex-encoded string
     pub fn recipient_pubkey_hex(&self) -> String {
         hex::encode(&self.recipient_pubkey)
@@ -957,17 +1975,65 @@ pub fn normalize_public_key(pubkey_hex: &str) -> String {
     }
 }
 
+// Re-export clock types
+pub use clock::{Clock, MockClock, SystemClock};
+
+// Re-export debt policy types
+pub use debt_policy::{DebtCeilingPolicy, DebtPolicyConfig};
+pub use note_limits::NoteLimits;
+
+// Re-export coverage analysis types
+pub use coverage::{recipient_coverage, IssuerCoverage, RecipientCoverage};
+
+// Re-export netting types
+pub use netting::{plan_netting, NettingPlan, NettingSuggestion};
+
+// Re-export note history types
+pub use note_history::{DisputeOutcome, EquivocationEvidence};
+
+// Re-export dispute types
+pub use disputes::{Dispute, DisputeError, DisputeFiling, DisputeParty, DisputeStatus};
+
+// Re-export split note types
+pub use split_notes::{SplitAllocation, SplitNote, SplitNoteError};
+
+// Re-export key rotation types
+pub use key_rotation::{KeyHistory, KeyRotation, KeyRotationError};
+
+// Re-export contract registry types
+pub use contract_registry::{
+    ContractRegistry, ContractRegistryError, ReserveContract, BASIS_RESERVE_CONTRACT_VERSION,
+};
+
+// Re-export storage encryption types
+pub use storage_encryption::StorageCipher;
+
+// Re-export network types
+pub use network::{Network, NetworkError};
+
+// Re-export commitment verification types
+pub use commitment_verification::{
+    commitment_delta, delta_note, note_from_delta, verify_cold_start, ColdStartCheck, ColdStartStatus,
+    CommitmentCheck, CommitmentStatus, DeltaUnavailable, VerificationStatus,
+};
+
+// Re-export reserve lineage types
+pub use reserve_lineage::{ReserveLineage, ReserveSpendKind, ReserveTransition};
+
 // Re-export reserve tracker types
 pub use reserve_tracker::{ExtendedReserveInfo, ReserveTracker, ReserveTrackerError};
 
 // Re-export ergo scanner types
 pub use ergo_scanner::{
-    create_default_scanner, start_scanner, ErgoBox, NodeConfig, ReserveEvent, ScanType,
-    ScannerError, ServerState,
+    create_default_scanner, start_scanner, start_scanner_with_shutdown, BackfillConfig, ErgoBox, NodeConfig,
+    QueuedReserveEvent, ReserveEvent, ScanLoopConfig, ScanMode, ScanType, ScannerError, ScannerProgress, ServerState,
 };
 
 // Re-export redemption types
-pub use redemption::{RedemptionData, RedemptionError, RedemptionManager, RedemptionRequest};
+pub use redemption::{
+    check_emergency_time_lock, emergency_unlock_height, RedemptionData, RedemptionError, RedemptionManager,
+    RedemptionRequest, UnsignedRedemptionParams, BLOCK_INTERVAL_MILLIS, EMERGENCY_REDEMPTION_DELAY,
+};
 
 // Re-export reqwest for use in dependent crates
 pub use reqwest;
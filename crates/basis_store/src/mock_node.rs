@@ -0,0 +1,856 @@
+//! In-process mock Ergo node for offline scanner tests
+//!
+//! [`MockErgoNode`] serves the node API the scanners and the tracker box
+//! updater use over a chain kept in memory:
+//!
+//! - `/info`, with extra fields configurable through [`MockErgoNode::set_info_field`]
+//! - `/blocks`, `/blocks/at/{height}`, `/blocks/lastHeaders/{count}` and
+//!   `/blocks/{header_id}/transactions`, with input context extensions
+//! - `/scan/register`, `/scan/deregister`, `/scan/listAll` and
+//!   `/scan/unspentBoxes/{scan_id}`, honouring inclusion height and paging
+//! - `/blockchain/box/byId/{box_id}` and `/blockchain/transaction/byId/{tx_id}`
+//! - `/blockchain/box/unspent/byAddress`, matching boxes on the address's ErgoTree
+//! - `/utxo/byId/{box_id}`
+//! - `/wallet/transaction/send`, mined at once by spending the unspent box
+//!   holding the first requested token
+//! - `/wallet/transaction/sign`, signing any inputs with empty proofs under a fresh ID
+//! - `/transactions` and `/transactions/bytes`, accepted without being
+//!   applied; transactions that parse wait in a mempool until
+//!   [`MockErgoNode::mine_pool`], replacing pooled transactions spending any
+//!   of the same inputs, and are served by
+//!   `/transactions/unconfirmed/byTransactionId/{tx_id}`
+//! - the explorer's `/api/v1/networkState`,
+//!   `/api/v1/boxes/unspent/byErgoTree/{ergo_tree}`,
+//!   `/api/v1/boxes/unspent/byTokenId/{token_id}`, `/api/v1/boxes/{box_id}`
+//!   and `/api/v1/transactions/{tx_id}`, with explorer-shaped boxes
+//!
+//! Tests create and spend boxes directly, roll the chain back with
+//! [`MockErgoNode::reorg`], make endpoints fail with
+//! [`MockErgoNode::fail_requests`] and count requests with
+//! [`MockErgoNode::requests`].
+//!
+//! Available to this crate's tests and, with the `test-support` feature, to
+//! other crates.
+
+use axum::{
+    extract::{Path, Query, Request, State},
+    http::StatusCode,
+    middleware::Next,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+/// Scan tracking rule, as registered through `/scan/register`
+#[derive(Debug, Clone)]
+enum TrackingRule {
+    /// Boxes whose ErgoTree is contained in the serialized `R1` value
+    Contains(String),
+    /// Boxes holding the asset
+    ContainsAsset(String),
+    /// Boxes matching any of the rules
+    Or(Vec<TrackingRule>),
+}
+
+#[derive(Debug, Clone)]
+struct MockBox {
+    /// Node box JSON
+    json: Value,
+    inclusion_height: u64,
+    spent_by: Option<String>,
+}
+
+impl MockBox {
+    fn id(&self) -> &str {
+        self.json["boxId"].as_str().unwrap_or_default()
+    }
+
+    fn matches(&self, rule: &TrackingRule) -> bool {
+        match rule {
+            TrackingRule::Contains(value) => {
+                let tree = self.json["ergoTree"].as_str().unwrap_or_default();
+                !tree.is_empty() && value.to_lowercase().contains(&tree.to_lowercase())
+            }
+            TrackingRule::ContainsAsset(token_id) => self.json["assets"]
+                .as_array()
+                .is_some_and(|assets| assets.iter().any(|asset| asset["tokenId"] == token_id.as_str())),
+            TrackingRule::Or(rules) => rules.iter().any(|rule| self.matches(rule)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MockTransaction {
+    inclusion_height: u64,
+    inputs: Vec<String>,
+    outputs: Vec<String>,
+    /// Context extension of each input, by input box ID
+    extensions: HashMap<String, Value>,
+    data_inputs: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+struct Chain {
+    height: u64,
+    next_id: u64,
+    next_scan_id: i64,
+    scans: BTreeMap<i64, (String, TrackingRule)>,
+    boxes: Vec<MockBox>,
+    transactions: HashMap<String, MockTransaction>,
+    /// Reorgs that replaced each height, so header IDs change with the fork
+    forks: HashMap<u64, u64>,
+    reorgs: u64,
+    info: serde_json::Map<String, Value>,
+    /// Path prefix -> status returned instead of serving the request
+    failures: Vec<(String, StatusCode)>,
+    /// Paths of the requests received so far
+    requests: Vec<String>,
+    wallet_requests: Vec<Value>,
+    /// Transactions submitted and not yet mined: (ID, input box IDs, node JSON)
+    pool: Vec<(String, Vec<String>, Value)>,
+}
+
+impl Chain {
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        format!("{:064x}", self.next_id)
+    }
+
+    fn header_id(&self, height: u64) -> String {
+        let fork = self.forks.get(&height).copied().unwrap_or(0);
+        format!("{:032x}{:032x}", fork, height)
+    }
+
+    fn find(&self, box_id: &str) -> Option<&MockBox> {
+        self.boxes.iter().find(|mock_box| mock_box.id() == box_id)
+    }
+
+    fn unspent(&self) -> impl Iterator<Item = &MockBox> {
+        self.boxes.iter().filter(|mock_box| mock_box.spent_by.is_none())
+    }
+
+    /// Include a transaction spending `inputs` and creating `outputs` at the current height
+    fn mine(&mut self, inputs: &[String], outputs: Vec<BoxSpec>) -> (String, Vec<String>) {
+        let tx_id = self.next_id();
+        let box_ids = self.include(tx_id.clone(), inputs, outputs);
+        (tx_id, box_ids)
+    }
+
+    /// Include the transaction `tx_id` at the current height, returning its output box IDs
+    fn include(&mut self, tx_id: String, inputs: &[String], outputs: Vec<BoxSpec>) -> Vec<String> {
+        for mock_box in self.boxes.iter_mut().filter(|b| inputs.iter().any(|id| id == b.id())) {
+            mock_box.spent_by = Some(tx_id.clone());
+        }
+
+        let height = self.height;
+        let mut box_ids = Vec::new();
+        for (index, spec) in outputs.into_iter().enumerate() {
+            let box_id = self.next_id();
+            let assets: Vec<Value> = spec
+                .assets
+                .iter()
+                .map(|(token_id, amount)| json!({ "tokenId": token_id, "amount": amount }))
+                .collect();
+            self.boxes.push(MockBox {
+                json: json!({
+                    "boxId": box_id,
+                    "value": spec.value,
+                    "ergoTree": spec.ergo_tree,
+                    "creationHeight": height,
+                    "transactionId": tx_id,
+                    "additionalRegisters": spec.registers,
+                    "assets": assets,
+                    "index": index,
+                }),
+                inclusion_height: height,
+                spent_by: None,
+            });
+            box_ids.push(box_id);
+        }
+        self.transactions.insert(
+            tx_id.clone(),
+            MockTransaction {
+                inclusion_height: height,
+                inputs: inputs.to_vec(),
+                outputs: box_ids.clone(),
+                extensions: HashMap::new(),
+                data_inputs: Vec::new(),
+            },
+        );
+        box_ids
+    }
+
+    /// Pool `tx`, dropping pooled transactions spending any of its inputs
+    fn submit(&mut self, tx_id: String, inputs: Vec<String>, tx: Value) {
+        self.pool.retain(|(_, pooled, _)| !pooled.iter().any(|id| inputs.contains(id)));
+        self.pool.push((tx_id, inputs, tx));
+    }
+
+    /// Undo every transaction above `height`
+    fn rollback(&mut self, height: u64) {
+        let reverted: Vec<(String, MockTransaction)> = self
+            .transactions
+            .iter()
+            .filter(|(_, tx)| tx.inclusion_height > height)
+            .map(|(tx_id, tx)| (tx_id.clone(), tx.clone()))
+            .collect();
+        for (tx_id, tx) in reverted {
+            self.transactions.remove(&tx_id);
+            self.boxes.retain(|mock_box| !tx.outputs.iter().any(|id| id == mock_box.id()));
+            for mock_box in self.boxes.iter_mut() {
+                if mock_box.spent_by.as_deref() == Some(tx_id.as_str()) {
+                    mock_box.spent_by = None;
+                }
+            }
+        }
+        self.height = height;
+    }
+
+    fn failure(&self, path: &str) -> Option<StatusCode> {
+        self.failures
+            .iter()
+            .find(|(prefix, _)| path.starts_with(prefix.as_str()))
+            .map(|(_, status)| *status)
+    }
+}
+
+/// An output to create on the mock chain
+#[derive(Debug, Clone)]
+pub struct BoxSpec {
+    pub value: u64,
+    /// ErgoTree hex; scans match reserve boxes on it
+    pub ergo_tree: String,
+    /// Register name -> serialized constant hex
+    pub registers: HashMap<String, String>,
+    /// (token ID, amount)
+    pub assets: Vec<(String, u64)>,
+}
+
+impl BoxSpec {
+    pub fn new(value: u64, ergo_tree: &str) -> Self {
+        Self {
+            value,
+            ergo_tree: ergo_tree.to_string(),
+            registers: HashMap::new(),
+            assets: Vec::new(),
+        }
+    }
+
+    pub fn register(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.registers.insert(name.to_string(), value.into());
+        self
+    }
+
+    pub fn asset(mut self, token_id: &str, amount: u64) -> Self {
+        self.assets.push((token_id.to_string(), amount));
+        self
+    }
+}
+
+/// Handle on a running mock node; clones share the same chain
+#[derive(Clone)]
+pub struct MockErgoNode {
+    /// Base URL to configure scanners with
+    pub url: String,
+    chain: Arc<Mutex<Chain>>,
+}
+
+impl MockErgoNode {
+    /// Serve a chain at `height` on an ephemeral local port
+    pub async fn start(height: u64) -> Self {
+        let chain = Arc::new(Mutex::new(Chain {
+            height,
+            ..Chain::default()
+        }));
+        let app = Router::new()
+            .route("/info", get(info))
+            .route("/blocks", get(blocks))
+            .route("/blocks/at/{height}", get(blocks_at))
+            .route("/blocks/lastHeaders/{count}", get(last_headers))
+            .route("/blocks/{header_id}/transactions", get(block_transactions))
+            .route("/scan/register", post(register_scan))
+            .route("/scan/deregister", post(deregister_scan))
+            .route("/scan/listAll", get(list_scans))
+            .route("/scan/unspentBoxes/{scan_id}", get(unspent_scan_boxes))
+            .route("/blockchain/box/byId/{box_id}", get(indexed_box))
+            .route("/blockchain/transaction/byId/{tx_id}", get(indexed_transaction))
+            .route("/blockchain/box/unspent/byAddress", post(unspent_boxes_by_address))
+            .route("/utxo/byId/{box_id}", get(utxo_box))
+            .route("/wallet/transaction/send", post(wallet_send))
+            .route("/wallet/transaction/sign", post(wallet_sign))
+            .route("/transactions", post(submit_json))
+            .route("/transactions/bytes", post(submit_bytes))
+            .route("/transactions/unconfirmed/byTransactionId/{tx_id}", get(unconfirmed_transaction))
+            .route("/api/v1/networkState", get(explorer_network_state))
+            .route("/api/v1/boxes/unspent/byErgoTree/{ergo_tree}", get(explorer_boxes_by_tree))
+            .route("/api/v1/boxes/unspent/byTokenId/{token_id}", get(explorer_boxes_by_token))
+            .route("/api/v1/boxes/{box_id}", get(explorer_box))
+            .route("/api/v1/transactions/{tx_id}", get(explorer_transaction))
+            .layer(axum::middleware::from_fn_with_state(chain.clone(), injected_failures))
+            .with_state(chain.clone());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("bind mock node");
+        let url = format!("http://{}", listener.local_addr().expect("mock node address"));
+        tokio::spawn(async move {
+            let _ = axum::serve(listener, app).await;
+        });
+        Self { url, chain }
+    }
+
+    fn chain(&self) -> std::sync::MutexGuard<'_, Chain> {
+        self.chain.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn height(&self) -> u64 {
+        self.chain().height
+    }
+
+    /// Mine `blocks` empty blocks
+    pub fn advance(&self, blocks: u64) {
+        self.chain().height += blocks;
+    }
+
+    /// Drop the last `depth` blocks, then mine `new_blocks` empty blocks on the new fork
+    ///
+    /// Boxes created in the dropped blocks disappear and boxes spent in them
+    /// are unspent again.
+    pub fn reorg(&self, depth: u64, new_blocks: u64) {
+        let mut chain = self.chain();
+        let fork_height = chain.height.saturating_sub(depth);
+        chain.rollback(fork_height);
+        chain.reorgs += 1;
+        let fork = chain.reorgs;
+        for height in fork_height + 1..=fork_height + new_blocks {
+            chain.forks.insert(height, fork);
+        }
+        chain.height = fork_height + new_blocks;
+    }
+
+    /// Add or replace a field of the `/info` response
+    pub fn set_info_field(&self, name: &str, value: Value) {
+        self.chain().info.insert(name.to_string(), value);
+    }
+
+    /// Answer requests whose path starts with `path_prefix` with `status`
+    pub fn fail_requests(&self, path_prefix: &str, status: StatusCode) {
+        self.chain().failures.push((path_prefix.to_string(), status));
+    }
+
+    /// Serve every endpoint normally again
+    pub fn clear_failures(&self) {
+        self.chain().failures.clear();
+    }
+
+    /// Create a box in a transaction without inputs at the current height, returning its ID
+    pub fn create_box(&self, spec: BoxSpec) -> String {
+        let (_, box_ids) = self.chain().mine(&[], vec![spec]);
+        box_ids[0].clone()
+    }
+
+    /// Spend `inputs` into `outputs` at the current height, returning the
+    /// transaction ID and the output box IDs
+    pub fn spend(&self, inputs: &[&str], outputs: Vec<BoxSpec>) -> (String, Vec<String>) {
+        let inputs: Vec<String> = inputs.iter().map(|id| id.to_string()).collect();
+        self.chain().mine(&inputs, outputs)
+    }
+
+    /// Spend `inputs`, each with its context extension (variable ID -> serialized
+    /// constant hex), reading `data_inputs`, into `outputs` at the current height
+    pub fn spend_with_context(
+        &self,
+        inputs: &[(&str, Value)],
+        data_inputs: &[&str],
+        outputs: Vec<BoxSpec>,
+    ) -> (String, Vec<String>) {
+        let mut chain = self.chain();
+        let input_ids: Vec<String> = inputs.iter().map(|(id, _)| id.to_string()).collect();
+        let (tx_id, box_ids) = chain.mine(&input_ids, outputs);
+        if let Some(tx) = chain.transactions.get_mut(&tx_id) {
+            tx.extensions = inputs.iter().map(|(id, extension)| (id.to_string(), extension.clone())).collect();
+            tx.data_inputs = data_inputs.iter().map(|id| id.to_string()).collect();
+        }
+        (tx_id, box_ids)
+    }
+
+    /// Unspent boxes locked by `ergo_tree`, as node box JSON
+    pub fn unspent_boxes_by_tree(&self, ergo_tree: &str) -> Vec<Value> {
+        self.chain()
+            .unspent()
+            .filter(|b| b.json["ergoTree"] == ergo_tree)
+            .map(|b| b.json.clone())
+            .collect()
+    }
+
+    /// Unspent boxes holding `token_id`, as node box JSON
+    pub fn unspent_boxes_with_token(&self, token_id: &str) -> Vec<Value> {
+        let rule = TrackingRule::ContainsAsset(token_id.to_string());
+        self.chain()
+            .unspent()
+            .filter(|b| b.matches(&rule))
+            .map(|b| b.json.clone())
+            .collect()
+    }
+
+    /// Register a scan for boxes locked by `ergo_tree`, as `/scan/register` would
+    pub fn register_scan(&self, name: &str, ergo_tree: &str) -> i64 {
+        let mut chain = self.chain();
+        chain.next_scan_id += 1;
+        let scan_id = chain.next_scan_id;
+        chain
+            .scans
+            .insert(scan_id, (name.to_string(), TrackingRule::Contains(ergo_tree.to_string())));
+        scan_id
+    }
+
+    /// Number of requests received so far whose path starts with `path_prefix`
+    pub fn requests(&self, path_prefix: &str) -> usize {
+        self.chain()
+            .requests
+            .iter()
+            .filter(|path| path.starts_with(path_prefix))
+            .count()
+    }
+
+    /// IDs of the registered scans
+    pub fn scan_ids(&self) -> Vec<i64> {
+        self.chain().scans.keys().copied().collect()
+    }
+
+    /// Bodies of the `/wallet/transaction/send` requests received so far
+    pub fn wallet_requests(&self) -> Vec<Value> {
+        self.chain().wallet_requests.clone()
+    }
+
+    /// Include the submitted transactions whose inputs are all unspent at the
+    /// current height, without outputs, returning their IDs
+    ///
+    /// Transactions spending a box that is already spent are dropped, as a
+    /// node drops double-spends from its mempool.
+    pub fn mine_pool(&self) -> Vec<String> {
+        let mut chain = self.chain();
+        let mut mined = Vec::new();
+        for (tx_id, inputs, _) in std::mem::take(&mut chain.pool) {
+            let unspent = inputs
+                .iter()
+                .all(|id| chain.find(id).is_some_and(|mock_box| mock_box.spent_by.is_none()));
+            if unspent {
+                chain.include(tx_id.clone(), &inputs, Vec::new());
+                mined.push(tx_id);
+            }
+        }
+        mined
+    }
+}
+
+type ChainState = State<Arc<Mutex<Chain>>>;
+
+fn lock(chain: &Mutex<Chain>) -> std::sync::MutexGuard<'_, Chain> {
+    chain.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn not_found(what: &str) -> (StatusCode, Json<Value>) {
+    (StatusCode::NOT_FOUND, Json(json!({ "error": 404, "reason": format!("{} not found", what) })))
+}
+
+async fn injected_failures(State(chain): ChainState, request: Request, next: Next) -> Response {
+    let failure = {
+        let mut chain = lock(&chain);
+        chain.requests.push(request.uri().path().to_string());
+        chain.failure(request.uri().path())
+    };
+    match failure {
+        Some(status) => (status, Json(json!({ "error": status.as_u16(), "reason": "injected failure" }))).into_response(),
+        None => next.run(request).await,
+    }
+}
+
+async fn info(State(chain): ChainState) -> Json<Value> {
+    let chain = lock(&chain);
+    let mut info = json!({
+        "fullHeight": chain.height,
+        "headersHeight": chain.height,
+        "bestFullHeaderId": chain.header_id(chain.height),
+        "network": "mainnet",
+    });
+    for (name, value) in &chain.info {
+        info[name] = value.clone();
+    }
+    Json(info)
+}
+
+async fn blocks(State(chain): ChainState, Query(query): Query<HashMap<String, u64>>) -> Json<Value> {
+    let chain = lock(&chain);
+    let offset = query.get("offset").copied().unwrap_or(0);
+    let limit = query.get("limit").copied().unwrap_or(50);
+    let ids: Vec<String> = (offset + 1..=chain.height)
+        .take(limit as usize)
+        .map(|height| chain.header_id(height))
+        .collect();
+    Json(json!(ids))
+}
+
+async fn blocks_at(State(chain): ChainState, Path(height): Path<u64>) -> Json<Value> {
+    let chain = lock(&chain);
+    if height == 0 || height > chain.height {
+        return Json(json!([]));
+    }
+    Json(json!([chain.header_id(height)]))
+}
+
+async fn last_headers(State(chain): ChainState, Path(count): Path<u64>) -> Json<Value> {
+    let chain = lock(&chain);
+    let first = chain.height.saturating_sub(count) + 1;
+    let headers: Vec<Value> = (first..=chain.height)
+        .map(|height| {
+            json!({
+                "id": chain.header_id(height),
+                "height": height,
+                "parentId": chain.header_id(height - 1),
+            })
+        })
+        .collect();
+    Json(json!(headers))
+}
+
+fn parse_tracking_rule(rule: &Value) -> Option<TrackingRule> {
+    match rule["predicate"].as_str()? {
+        "contains" => Some(TrackingRule::Contains(rule["value"].as_str().unwrap_or_default().to_string())),
+        "containsAsset" => Some(TrackingRule::ContainsAsset(rule["assetId"].as_str().unwrap_or_default().to_string())),
+        "or" => rule["args"].as_array()?.iter().map(parse_tracking_rule).collect::<Option<_>>().map(TrackingRule::Or),
+        _ => None,
+    }
+}
+
+async fn block_transactions(State(chain): ChainState, Path(header_id): Path<String>) -> (StatusCode, Json<Value>) {
+    let chain = lock(&chain);
+    let Some(height) = (1..=chain.height).find(|height| chain.header_id(*height) == header_id) else {
+        return not_found("block");
+    };
+    let transactions: Vec<Value> = chain
+        .transactions
+        .iter()
+        .filter(|(_, tx)| tx.inclusion_height == height)
+        .map(|(tx_id, tx)| {
+            let inputs: Vec<Value> = tx
+                .inputs
+                .iter()
+                .map(|box_id| {
+                    let extension = tx.extensions.get(box_id).cloned().unwrap_or_else(|| json!({}));
+                    json!({ "boxId": box_id, "spendingProof": { "proofBytes": "", "extension": extension } })
+                })
+                .collect();
+            let data_inputs: Vec<Value> = tx.data_inputs.iter().map(|box_id| json!({ "boxId": box_id })).collect();
+            let outputs: Vec<Value> = tx
+                .outputs
+                .iter()
+                .filter_map(|box_id| chain.find(box_id))
+                .map(|mock_box| mock_box.json.clone())
+                .collect();
+            json!({ "id": tx_id, "inputs": inputs, "dataInputs": data_inputs, "outputs": outputs })
+        })
+        .collect();
+    (StatusCode::OK, Json(json!({ "headerId": header_id, "transactions": transactions })))
+}
+
+async fn register_scan(State(chain): ChainState, Json(request): Json<Value>) -> (StatusCode, Json<Value>) {
+    let Some(rule) = parse_tracking_rule(&request["trackingRule"]) else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": 400, "reason": "unsupported tracking rule" })));
+    };
+    let name = request["scanName"].as_str().unwrap_or_default().to_string();
+    let mut chain = lock(&chain);
+    chain.next_scan_id += 1;
+    let scan_id = chain.next_scan_id;
+    chain.scans.insert(scan_id, (name, rule));
+    (StatusCode::OK, Json(json!({ "scanId": scan_id })))
+}
+
+async fn deregister_scan(State(chain): ChainState, Json(request): Json<Value>) -> (StatusCode, Json<Value>) {
+    let scan_id = request["scanId"].as_i64().unwrap_or_default();
+    match lock(&chain).scans.remove(&scan_id) {
+        Some(_) => (StatusCode::OK, Json(json!({ "scanId": scan_id }))),
+        None => not_found("scan"),
+    }
+}
+
+async fn list_scans(State(chain): ChainState) -> Json<Value> {
+    let scans: Vec<Value> = lock(&chain)
+        .scans
+        .iter()
+        .map(|(scan_id, (name, _))| json!({ "scanId": scan_id, "scanName": name }))
+        .collect();
+    Json(json!(scans))
+}
+
+async fn unspent_scan_boxes(
+    State(chain): ChainState,
+    Path(scan_id): Path<i64>,
+    Query(query): Query<HashMap<String, u64>>,
+) -> (StatusCode, Json<Value>) {
+    let chain = lock(&chain);
+    let Some((_, rule)) = chain.scans.get(&scan_id) else {
+        return not_found("scan");
+    };
+    let min = query.get("minInclusionHeight").copied().unwrap_or(0);
+    let max = query.get("maxInclusionHeight").copied().unwrap_or(u64::MAX);
+    let boxes: Vec<Value> = chain
+        .unspent()
+        .filter(|b| b.matches(rule) && (min..=max).contains(&b.inclusion_height))
+        .skip(query.get("offset").copied().unwrap_or(0) as usize)
+        .take(query.get("limit").copied().unwrap_or(u64::MAX) as usize)
+        .map(|b| {
+            json!({
+                "box": b.json,
+                "confirmationsNum": chain.height.saturating_sub(b.inclusion_height) + 1,
+                "address": "",
+                "creationTransaction": b.json["transactionId"],
+                "scans": [scan_id],
+                "onchain": true,
+                "creationOutIndex": b.json["index"],
+                "spendingTransaction": null,
+                "spendingHeight": null,
+                "inclusionHeight": b.inclusion_height,
+                "spent": false
+            })
+        })
+        .collect();
+    (StatusCode::OK, Json(json!(boxes)))
+}
+
+async fn indexed_box(State(chain): ChainState, Path(box_id): Path<String>) -> (StatusCode, Json<Value>) {
+    let chain = lock(&chain);
+    match chain.find(&box_id) {
+        Some(mock_box) => {
+            let mut json = mock_box.json.clone();
+            json["spentTransactionId"] = json!(mock_box.spent_by);
+            json["inclusionHeight"] = json!(mock_box.inclusion_height);
+            (StatusCode::OK, Json(json))
+        }
+        None => not_found("box"),
+    }
+}
+
+async fn indexed_transaction(State(chain): ChainState, Path(tx_id): Path<String>) -> (StatusCode, Json<Value>) {
+    let chain = lock(&chain);
+    match chain.transactions.get(&tx_id) {
+        Some(tx) => {
+            let outputs: Vec<Value> = tx
+                .outputs
+                .iter()
+                .filter_map(|box_id| chain.find(box_id))
+                .map(|mock_box| mock_box.json.clone())
+                .collect();
+            let inputs: Vec<Value> = tx.inputs.iter().map(|box_id| json!({ "boxId": box_id })).collect();
+            let data_inputs: Vec<Value> = tx.data_inputs.iter().map(|box_id| json!({ "boxId": box_id })).collect();
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "id": tx_id,
+                    "blockId": chain.header_id(tx.inclusion_height),
+                    "inclusionHeight": tx.inclusion_height,
+                    "inputs": inputs,
+                    "dataInputs": data_inputs,
+                    "outputs": outputs,
+                })),
+            )
+        }
+        None => not_found("transaction"),
+    }
+}
+
+async fn unspent_boxes_by_address(State(chain): ChainState, address: String) -> (StatusCode, Json<Value>) {
+    use ergo_lib::ergotree_ir::address::AddressEncoder;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+    let Some(tree) = AddressEncoder::unchecked_parse_address_from_str(address.trim().trim_matches('"'))
+        .ok()
+        .and_then(|address| address.script().ok())
+    else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": 400, "reason": "invalid address" })));
+    };
+    let tree = hex::encode(tree.sigma_serialize_bytes());
+    let chain = lock(&chain);
+    let boxes: Vec<Value> = chain
+        .unspent()
+        .filter(|b| b.json["ergoTree"] == tree)
+        .map(|b| b.json.clone())
+        .collect();
+    (StatusCode::OK, Json(json!(boxes)))
+}
+
+async fn utxo_box(State(chain): ChainState, Path(box_id): Path<String>) -> (StatusCode, Json<Value>) {
+    let chain = lock(&chain);
+    match chain.find(&box_id).filter(|mock_box| mock_box.spent_by.is_none()) {
+        Some(mock_box) => (StatusCode::OK, Json(mock_box.json.clone())),
+        None => not_found("box"),
+    }
+}
+
+async fn wallet_send(State(chain): ChainState, Json(request): Json<Value>) -> (StatusCode, Json<Value>) {
+    let mut chain = lock(&chain);
+    chain.wallet_requests.push(request.clone());
+
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    for payment in request["requests"].as_array().into_iter().flatten() {
+        // The mock does not derive trees from addresses
+        let mut spec = BoxSpec::new(
+            payment["value"].as_u64().unwrap_or_default(),
+            payment["address"].as_str().unwrap_or_default(),
+        );
+        for (name, value) in payment["registers"].as_object().into_iter().flatten() {
+            spec = spec.register(name, value.as_str().unwrap_or_default());
+        }
+        for asset in payment["assets"].as_array().into_iter().flatten() {
+            let token_id = asset["tokenId"].as_str().unwrap_or_default();
+            spec = spec.asset(token_id, asset["amount"].as_u64().unwrap_or_default());
+            let rule = TrackingRule::ContainsAsset(token_id.to_string());
+            if let Some(holder) = chain.unspent().find(|b| b.matches(&rule)) {
+                inputs.push(holder.id().to_string());
+            }
+        }
+        outputs.push(spec);
+    }
+
+    let (tx_id, _) = chain.mine(&inputs, outputs);
+    (StatusCode::OK, Json(json!(tx_id)))
+}
+
+async fn wallet_sign(State(chain): ChainState, Json(request): Json<Value>) -> (StatusCode, Json<Value>) {
+    let mut tx = request["tx"].clone();
+    let Some(inputs) = tx["inputs"].as_array_mut() else {
+        return (StatusCode::BAD_REQUEST, Json(json!({ "error": 400, "reason": "no inputs" })));
+    };
+    for input in inputs.iter_mut() {
+        let extension = input.as_object_mut().and_then(|input| input.remove("extension"));
+        input["spendingProof"] = json!({ "proofBytes": "", "extension": extension.unwrap_or_else(|| json!({})) });
+    }
+    tx["id"] = json!(lock(&chain).next_id());
+    (StatusCode::OK, Json(tx))
+}
+
+async fn submit_json(State(chain): ChainState, Json(tx): Json<Value>) -> (StatusCode, Json<Value>) {
+    let tx_id = tx["id"].as_str().map(str::to_string);
+    let inputs: Option<Vec<String>> = tx["inputs"]
+        .as_array()
+        .map(|inputs| inputs.iter().filter_map(|input| input["boxId"].as_str().map(str::to_string)).collect());
+    match (tx_id, inputs) {
+        (Some(tx_id), Some(inputs)) => {
+            lock(&chain).submit(tx_id.clone(), inputs, tx);
+            (StatusCode::OK, Json(json!(tx_id)))
+        }
+        _ => (StatusCode::BAD_REQUEST, Json(json!({ "error": 400, "reason": "malformed transaction" }))),
+    }
+}
+
+async fn submit_bytes(State(chain): ChainState, Json(tx_hex): Json<Value>) -> Json<Value> {
+    use ergo_lib::chain::transaction::Transaction;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+    let mut chain = lock(&chain);
+    let tx = hex::decode(tx_hex.as_str().unwrap_or_default())
+        .ok()
+        .and_then(|bytes| Transaction::sigma_parse_bytes(&bytes).ok());
+    match tx {
+        Some(tx) => {
+            let tx_id = String::from(tx.id());
+            let inputs = tx.inputs.iter().map(|input| String::from(input.box_id.clone())).collect();
+            chain.submit(tx_id.clone(), inputs, serde_json::to_value(&tx).unwrap_or_default());
+            Json(json!(tx_id))
+        }
+        None => Json(json!(chain.next_id())),
+    }
+}
+
+async fn unconfirmed_transaction(State(chain): ChainState, Path(tx_id): Path<String>) -> (StatusCode, Json<Value>) {
+    let chain = lock(&chain);
+    match chain.pool.iter().find(|(id, _, _)| *id == tx_id) {
+        Some((_, _, tx)) => (StatusCode::OK, Json(tx.clone())),
+        None => not_found("transaction"),
+    }
+}
+
+// A box as the explorer reports it: registers as objects, the inclusion
+// height as `settlementHeight`
+fn explorer_box_json(mock_box: &MockBox) -> Value {
+    let mut json = mock_box.json.clone();
+    let registers: serde_json::Map<String, Value> = json["additionalRegisters"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(name, value)| (name.clone(), json!({ "serializedValue": value })))
+        .collect();
+    json["additionalRegisters"] = Value::Object(registers);
+    json["settlementHeight"] = json!(mock_box.inclusion_height);
+    json["spentTransactionId"] = json!(mock_box.spent_by);
+    json
+}
+
+fn explorer_page(chain: &Chain, query: &HashMap<String, u64>, filter: impl Fn(&MockBox) -> bool) -> Json<Value> {
+    let matching: Vec<&MockBox> = chain.unspent().filter(|b| filter(b)).collect();
+    let items: Vec<Value> = matching
+        .iter()
+        .skip(query.get("offset").copied().unwrap_or(0) as usize)
+        .take(query.get("limit").copied().unwrap_or(20) as usize)
+        .map(|mock_box| explorer_box_json(mock_box))
+        .collect();
+    Json(json!({ "items": items, "total": matching.len() }))
+}
+
+async fn explorer_network_state(State(chain): ChainState) -> Json<Value> {
+    let chain = lock(&chain);
+    Json(json!({ "lastBlockId": chain.header_id(chain.height), "height": chain.height }))
+}
+
+async fn explorer_boxes_by_tree(
+    State(chain): ChainState,
+    Path(ergo_tree): Path<String>,
+    Query(query): Query<HashMap<String, u64>>,
+) -> Json<Value> {
+    explorer_page(&lock(&chain), &query, |b| b.json["ergoTree"] == ergo_tree.as_str())
+}
+
+async fn explorer_boxes_by_token(
+    State(chain): ChainState,
+    Path(token_id): Path<String>,
+    Query(query): Query<HashMap<String, u64>>,
+) -> Json<Value> {
+    let rule = TrackingRule::ContainsAsset(token_id);
+    explorer_page(&lock(&chain), &query, |b| b.matches(&rule))
+}
+
+async fn explorer_box(State(chain): ChainState, Path(box_id): Path<String>) -> (StatusCode, Json<Value>) {
+    let chain = lock(&chain);
+    match chain.find(&box_id) {
+        Some(mock_box) => (StatusCode::OK, Json(explorer_box_json(mock_box))),
+        None => not_found("box"),
+    }
+}
+
+async fn explorer_transaction(State(chain): ChainState, Path(tx_id): Path<String>) -> (StatusCode, Json<Value>) {
+    let chain = lock(&chain);
+    let Some(tx) = chain.transactions.get(&tx_id) else {
+        return not_found("transaction");
+    };
+    let boxes = |ids: &[String]| -> Vec<Value> {
+        ids.iter()
+            .filter_map(|box_id| chain.find(box_id))
+            .map(explorer_box_json)
+            .collect()
+    };
+    (
+        StatusCode::OK,
+        Json(json!({
+            "id": tx_id,
+            "blockId": chain.header_id(tx.inclusion_height),
+            "inclusionHeight": tx.inclusion_height,
+            "inputs": boxes(&tx.inputs),
+            "dataInputs": boxes(&tx.data_inputs),
+            "outputs": boxes(&tx.outputs),
+        })),
+    )
+}
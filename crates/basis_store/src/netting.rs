@@ -0,0 +1,207 @@
+//! Netting of offsetting debts
+//!
+//! Outstanding notes form a payment graph: an edge from issuer to recipient
+//! weighted by what the issuer still owes. Whenever the edges close a cycle
+//! (A owes B and B owes A, or A owes B, B owes C and C owes A), every party
+//! on it can forgive the smallest debt of the cycle against what it is owed,
+//! and no one's net position changes. Each debt brought to zero is one
+//! on-chain redemption, and the collateral backing it, no longer needed.
+//!
+//! Cycles are settled greedily: pairs owing each other first, as they need
+//! only two parties to agree, then the shortest remaining cycles up to
+//! `max_cycle_length` parties. This does not always clear the most debts,
+//! but every settlement clears at least one.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+
+use crate::{IouNote, PubKey};
+
+/// Cycle length used when the caller does not choose one
+pub const DEFAULT_MAX_CYCLE_LENGTH: usize = 4;
+/// Longest cycle a plan may include
+pub const MAX_CYCLE_LENGTH: usize = 8;
+
+/// Debts to forgive around one cycle
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NettingSuggestion {
+    /// Hex public keys in cycle order: each owes the next, the last owes the first
+    pub cycle: Vec<String>,
+    /// Amount forgiven on every debt of the cycle
+    pub amount: u64,
+    /// Debts of the cycle the settlement brings to zero
+    pub notes_cleared: usize,
+}
+
+/// Settlements netting the outstanding debts, in the order to apply them
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NettingPlan {
+    pub max_cycle_length: usize,
+    /// Notes with outstanding debt
+    pub notes: usize,
+    pub total_outstanding_before: u64,
+    /// Outstanding debt left once every suggestion is applied
+    pub total_outstanding_after: u64,
+    /// Notes the suggestions bring to zero, i.e. redemptions no longer needed
+    pub notes_cleared: usize,
+    pub suggestions: Vec<NettingSuggestion>,
+}
+
+type Debts = BTreeMap<(PubKey, PubKey), u64>;
+
+// Creditors `debtor` owes
+fn creditors<'a>(debts: &'a Debts, debtor: &PubKey) -> impl Iterator<Item = PubKey> + 'a {
+    debts
+        .range((*debtor, [0u8; 33])..=(*debtor, [0xffu8; 33]))
+        .map(|((_, creditor), _)| *creditor)
+}
+
+// Forgive the smallest debt of the cycle on every debt of the cycle
+fn settle(debts: &mut Debts, cycle: &[PubKey]) -> NettingSuggestion {
+    let edges: Vec<(PubKey, PubKey)> = (0..cycle.len())
+        .map(|i| (cycle[i], cycle[(i + 1) % cycle.len()]))
+        .collect();
+    let amount = edges.iter().map(|edge| debts[edge]).min().unwrap_or(0);
+    let mut notes_cleared = 0;
+    for edge in &edges {
+        let remaining = debts[edge] - amount;
+        if remaining == 0 {
+            debts.remove(edge);
+            notes_cleared += 1;
+        } else {
+            debts.insert(*edge, remaining);
+        }
+    }
+    NettingSuggestion {
+        cycle: cycle.iter().map(hex::encode).collect(),
+        amount,
+        notes_cleared,
+    }
+}
+
+// Shortest cycle of at most `max_length` parties through `start`, visiting
+// only keys above it
+fn shortest_cycle(debts: &Debts, start: &PubKey, max_length: usize) -> Option<Vec<PubKey>> {
+    let mut previous: HashMap<PubKey, PubKey> = HashMap::new();
+    let mut queue = VecDeque::from([(*start, 1)]);
+    while let Some((node, length)) = queue.pop_front() {
+        for creditor in creditors(debts, &node) {
+            if creditor == *start {
+                // Walk back to `start`, the only key without a predecessor
+                let mut cycle = vec![node];
+                while let Some(before) = previous.get(cycle.last().unwrap()) {
+                    cycle.push(*before);
+                }
+                cycle.reverse();
+                return Some(cycle);
+            }
+            if creditor > *start && length < max_length && !previous.contains_key(&creditor) {
+                previous.insert(creditor, node);
+                queue.push_back((creditor, length + 1));
+            }
+        }
+    }
+    None
+}
+
+/// Plan settlements netting the outstanding debts of `notes`, given with
+/// their issuers, around cycles of at most `max_cycle_length` parties
+pub fn plan_netting(notes: &[(PubKey, IouNote)], max_cycle_length: usize) -> NettingPlan {
+    let max_cycle_length = max_cycle_length.clamp(2, MAX_CYCLE_LENGTH);
+    let mut debts: Debts = BTreeMap::new();
+    for (issuer, note) in notes {
+        if note.outstanding_debt() > 0 && *issuer != note.recipient_pubkey {
+            let debt = debts.entry((*issuer, note.recipient_pubkey)).or_default();
+            *debt = debt.saturating_add(note.outstanding_debt());
+        }
+    }
+    let total = |debts: &Debts| debts.values().fold(0u64, |acc, debt| acc.saturating_add(*debt));
+    let notes_before = debts.len();
+    let total_outstanding_before = total(&debts);
+
+    let mut suggestions = Vec::new();
+    let pairs: Vec<(PubKey, PubKey)> = debts
+        .keys()
+        .filter(|(debtor, creditor)| debtor < creditor && debts.contains_key(&(*creditor, *debtor)))
+        .copied()
+        .collect();
+    for (debtor, creditor) in pairs {
+        suggestions.push(settle(&mut debts, &[debtor, creditor]));
+    }
+
+    // Settling only removes debts, so once no cycle runs through a key none
+    // ever will, and later searches can skip it
+    if max_cycle_length > 2 {
+        let debtors: BTreeSet<PubKey> = debts.keys().map(|(debtor, _)| *debtor).collect();
+        for start in debtors {
+            while let Some(cycle) = shortest_cycle(&debts, &start, max_cycle_length) {
+                suggestions.push(settle(&mut debts, &cycle));
+            }
+        }
+    }
+
+    NettingPlan {
+        max_cycle_length,
+        notes: notes_before,
+        total_outstanding_before,
+        total_outstanding_after: total(&debts),
+        notes_cleared: suggestions.iter().map(|suggestion| suggestion.notes_cleared).sum(),
+        suggestions,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pubkey(n: u8) -> PubKey {
+        let mut key = [n; 33];
+        key[0] = 0x02;
+        key
+    }
+
+    fn note(issuer: u8, recipient: u8, outstanding: u64) -> (PubKey, IouNote) {
+        (pubkey(issuer), IouNote::new(pubkey(recipient), outstanding, 0, 1, [0u8; 65]))
+    }
+
+    #[test]
+    fn test_bilateral_debts_net_to_the_difference() {
+        let plan = plan_netting(&[note(1, 2, 500), note(2, 1, 200), note(1, 3, 50)], DEFAULT_MAX_CYCLE_LENGTH);
+        assert_eq!(plan.suggestions.len(), 1);
+        assert_eq!(plan.suggestions[0].cycle, vec![hex::encode(pubkey(1)), hex::encode(pubkey(2))]);
+        assert_eq!((plan.suggestions[0].amount, plan.suggestions[0].notes_cleared), (200, 1));
+        assert_eq!((plan.notes, plan.notes_cleared), (3, 1));
+        assert_eq!((plan.total_outstanding_before, plan.total_outstanding_after), (750, 350));
+    }
+
+    #[test]
+    fn test_cycles_are_settled_up_to_the_length_limit() {
+        // 1 -> 2 -> 3 -> 1, and a four party cycle 4 -> 5 -> 6 -> 7 -> 4
+        let notes = [
+            note(1, 2, 100),
+            note(2, 3, 70),
+            note(3, 1, 100),
+            note(4, 5, 10),
+            note(5, 6, 10),
+            note(6, 7, 10),
+            note(7, 4, 10),
+        ];
+        let plan = plan_netting(&notes, 3);
+        assert_eq!(plan.suggestions.len(), 1);
+        assert_eq!(plan.suggestions[0].cycle.len(), 3);
+        assert_eq!(plan.suggestions[0].amount, 70);
+        assert_eq!(plan.total_outstanding_after, 60 + 40);
+
+        let plan = plan_netting(&notes, 4);
+        assert_eq!(plan.suggestions.len(), 2);
+        assert_eq!(plan.suggestions[1].cycle[0], hex::encode(pubkey(4)));
+        assert_eq!((plan.notes_cleared, plan.total_outstanding_after), (5, 60));
+    }
+
+    #[test]
+    fn test_acyclic_debts_are_left_alone() {
+        let plan = plan_netting(&[note(1, 2, 100), note(2, 3, 100), note(1, 3, 100), note(4, 4, 5)], 8);
+        assert!(plan.suggestions.is_empty());
+        assert_eq!(plan.total_outstanding_before, plan.total_outstanding_after);
+    }
+}
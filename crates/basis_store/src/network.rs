@@ -0,0 +1,166 @@
+//! Ergo network a deployment runs on
+//!
+//! Addresses carry their network in the prefix byte, so a testnet address
+//! handed to a mainnet deployment is caught when it is parsed rather than when
+//! a transaction paying it is rejected by the node.
+
+use ergo_lib::ergotree_ir::address::{Address, AddressEncoder, NetworkPrefix};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum NetworkError {
+    #[error("Unknown network '{0}', expected mainnet or testnet")]
+    UnknownNetwork(String),
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Address {address} is a {actual} address, but the deployment runs on {expected}")]
+    WrongNetwork {
+        address: String,
+        expected: Network,
+        actual: Network,
+    },
+    #[error("Ergo node runs on {node}, but the deployment is configured for {expected}")]
+    NodeMismatch { expected: Network, node: String },
+}
+
+/// Ergo network, mainnet unless configured otherwise
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Network {
+    #[default]
+    Mainnet,
+    Testnet,
+}
+
+impl Network {
+    /// Address prefix of the network
+    pub fn prefix(self) -> NetworkPrefix {
+        match self {
+            Network::Mainnet => NetworkPrefix::Mainnet,
+            Network::Testnet => NetworkPrefix::Testnet,
+        }
+    }
+
+    /// Address prefix byte, as stored in transaction contexts
+    pub fn prefix_byte(self) -> u8 {
+        self.prefix() as u8
+    }
+
+    /// Name of the network, as reported in the `network` field of the node's `/info`
+    pub fn name(self) -> &'static str {
+        match self {
+            Network::Mainnet => "mainnet",
+            Network::Testnet => "testnet",
+        }
+    }
+
+    pub fn address_encoder(self) -> AddressEncoder {
+        AddressEncoder::new(self.prefix())
+    }
+
+    /// Encode an address for the network
+    pub fn encode_address(self, address: &Address) -> String {
+        AddressEncoder::encode_address_as_string(self.prefix(), address)
+    }
+
+    /// Network an encoded address belongs to
+    pub fn of_address(address: &str) -> Result<Network, NetworkError> {
+        let network_address = AddressEncoder::unchecked_parse_network_address_from_str(address)
+            .map_err(|e| NetworkError::InvalidAddress(format!("{}: {}", address, e)))?;
+        Ok(match network_address.network() {
+            NetworkPrefix::Mainnet => Network::Mainnet,
+            NetworkPrefix::Testnet => Network::Testnet,
+        })
+    }
+
+    /// Parse an address, refusing those of the other network
+    pub fn parse_address(self, address: &str) -> Result<Address, NetworkError> {
+        let actual = Network::of_address(address)?;
+        if actual != self {
+            return Err(NetworkError::WrongNetwork {
+                address: address.to_string(),
+                expected: self,
+                actual,
+            });
+        }
+        AddressEncoder::unchecked_parse_address_from_str(address)
+            .map_err(|e| NetworkError::InvalidAddress(format!("{}: {}", address, e)))
+    }
+
+    /// Check the node's `/info` document reports this network
+    ///
+    /// Nodes that do not report a network are accepted.
+    pub fn check_node_info(self, info: &serde_json::Value) -> Result<(), NetworkError> {
+        match info["network"].as_str() {
+            Some(node) if !node.eq_ignore_ascii_case(self.name()) => Err(NetworkError::NodeMismatch {
+                expected: self,
+                node: node.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for Network {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl std::str::FromStr for Network {
+    type Err = NetworkError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "mainnet" => Ok(Network::Mainnet),
+            "testnet" => Ok(Network::Testnet),
+            _ => Err(NetworkError::UnknownNetwork(s.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+    use ergo_lib::ergotree_ir::sigma_protocol::sigma_boolean::ProveDlog;
+    use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+
+    #[test]
+    fn test_addresses_are_bound_to_their_network() {
+        let pubkey = hex::decode("02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7").unwrap();
+        let address = Address::P2Pk(ProveDlog::from(EcPoint::sigma_parse_bytes(&pubkey).unwrap()));
+        let mainnet = Network::Mainnet.encode_address(&address);
+        let testnet = Network::Testnet.encode_address(&address);
+        assert!(mainnet.starts_with('9'));
+        assert!(testnet.starts_with('3'));
+
+        assert_eq!(Network::of_address(&testnet), Ok(Network::Testnet));
+        assert_eq!(Network::Testnet.parse_address(&testnet), Ok(address.clone()));
+        assert_eq!(
+            Network::Mainnet.parse_address(&testnet),
+            Err(NetworkError::WrongNetwork {
+                address: testnet,
+                expected: Network::Mainnet,
+                actual: Network::Testnet,
+            })
+        );
+        assert!(matches!(Network::Mainnet.parse_address("not an address"), Err(NetworkError::InvalidAddress(_))));
+    }
+
+    #[test]
+    fn test_network_names() {
+        assert_eq!("Testnet".parse::<Network>(), Ok(Network::Testnet));
+        assert!("devnet".parse::<Network>().is_err());
+        assert_eq!(Network::Testnet.prefix_byte(), 16);
+        assert_eq!(serde_json::to_string(&Network::Mainnet).unwrap(), "\"mainnet\"");
+
+        assert!(Network::Mainnet.check_node_info(&serde_json::json!({ "network": "mainnet" })).is_ok());
+        assert!(Network::Mainnet.check_node_info(&serde_json::json!({ "fullHeight": 1 })).is_ok());
+        assert!(matches!(
+            Network::Mainnet.check_node_info(&serde_json::json!({ "network": "testnet" })),
+            Err(NetworkError::NodeMismatch { .. })
+        ));
+    }
+}
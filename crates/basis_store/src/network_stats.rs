@@ -0,0 +1,138 @@
+//! Network-wide note statistics
+//!
+//! Dashboards and explorers poll these figures, so
+//! [`NoteStorage`](crate::persistence::NoteStorage) keeps them as counters
+//! updated with every stored or deleted note rather than scanning the notes
+//! on each request. The counters live in the `note_stats` partition under
+//! one-byte key prefixes:
+//!
+//! ```text
+//! c || name                     -> count (8 BE)
+//! d || day (8 BE)               -> note versions timestamped that day (8 BE)
+//! i || issuer (33)              -> outstanding debt of the issuer (8 BE)
+//! r || debt (8 BE) || issuer    -> empty, ranking issuers by outstanding debt
+//! ```
+//!
+//! Outstanding debt here is collected minus redeemed, without accrued
+//! interest, which changes with time rather than with stored notes.
+
+use crate::PubKey;
+
+/// Length of a day in milliseconds
+pub const DAY_MILLIS: u64 = 86_400_000;
+
+pub(crate) const ISSUERS_KEY: &[u8] = b"cissuers";
+pub(crate) const RECIPIENTS_KEY: &[u8] = b"crecipients";
+pub(crate) const NOTES_KEY: &[u8] = b"cnotes";
+pub(crate) const OUTSTANDING_KEY: &[u8] = b"coutstanding";
+pub(crate) const DAY_PREFIX: u8 = b'd';
+pub(crate) const ISSUER_DEBT_PREFIX: u8 = b'i';
+pub(crate) const DEBT_RANK_PREFIX: u8 = b'r';
+
+/// Key counting the note versions timestamped on `day` (days since the epoch)
+pub(crate) fn day_key(day: u64) -> Vec<u8> {
+    let mut key = vec![DAY_PREFIX];
+    key.extend_from_slice(&day.to_be_bytes());
+    key
+}
+
+/// Key holding an issuer's outstanding debt
+pub(crate) fn issuer_debt_key(issuer_pubkey: &PubKey) -> Vec<u8> {
+    let mut key = vec![ISSUER_DEBT_PREFIX];
+    key.extend_from_slice(issuer_pubkey);
+    key
+}
+
+/// Key ranking an issuer by outstanding debt, so that a reverse scan over
+/// the prefix lists the largest debtors first
+pub(crate) fn debt_rank_key(debt: u64, issuer_pubkey: &PubKey) -> Vec<u8> {
+    let mut key = vec![DEBT_RANK_PREFIX];
+    key.extend_from_slice(&debt.to_be_bytes());
+    key.extend_from_slice(issuer_pubkey);
+    key
+}
+
+/// An issuer with the debt it owes across all recipients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssuerDebt {
+    pub issuer_pubkey: PubKey,
+    pub outstanding_debt: u64,
+}
+
+/// Note versions accepted with a timestamp in one day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DailyNoteCount {
+    /// Start of the day, in milliseconds since the epoch
+    pub day_start: u64,
+    pub notes: u64,
+}
+
+/// Aggregate figures over every note the tracker holds
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NetworkStats {
+    /// Public keys that have issued at least one note
+    pub issuers: u64,
+    /// Public keys that hold at least one note
+    pub recipients: u64,
+    /// Issuer-recipient pairs with a note
+    pub notes: u64,
+    pub total_outstanding_debt: u64,
+    /// Issuers owing the most, largest first
+    pub top_issuers: Vec<IssuerDebt>,
+    /// Note versions per day over the requested days, oldest first, with
+    /// days without notes included
+    pub notes_per_day: Vec<DailyNoteCount>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::NoteStorage;
+    use crate::schnorr::generate_keypair;
+    use crate::IouNote;
+
+    #[test]
+    fn test_counters_follow_stored_notes() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = NoteStorage::open(temp_dir.path()).unwrap();
+        let (alice_secret, alice) = generate_keypair();
+        let (bob_secret, bob) = generate_keypair();
+        let (_, carol) = generate_keypair();
+        let (_, dave) = generate_keypair();
+
+        let store = |issuer: &PubKey, secret: &[u8; 32], recipient: PubKey, amount: u64, redeemed: u64, day: u64| {
+            let mut note = IouNote::create_and_sign(recipient, amount, day * DAY_MILLIS + 1, secret).unwrap();
+            note.amount_redeemed = redeemed;
+            storage.store_note(issuer, &note).unwrap();
+        };
+        store(&alice, &alice_secret, carol, 100, 0, 10);
+        store(&alice, &alice_secret, dave, 50, 0, 11);
+        store(&bob, &bob_secret, carol, 300, 0, 11);
+        // A new version of an existing note moves the debt rather than adding a note
+        store(&alice, &alice_secret, carol, 400, 100, 12);
+
+        let stats = storage.get_network_stats(10, 10, 12).unwrap();
+        assert_eq!((stats.issuers, stats.recipients, stats.notes), (2, 2, 3));
+        assert_eq!(stats.total_outstanding_debt, 650);
+        assert_eq!(
+            stats.top_issuers,
+            vec![
+                IssuerDebt { issuer_pubkey: alice, outstanding_debt: 350 },
+                IssuerDebt { issuer_pubkey: bob, outstanding_debt: 300 },
+            ]
+        );
+        let per_day: Vec<u64> = stats.notes_per_day.iter().map(|day| day.notes).collect();
+        assert_eq!(per_day, vec![1, 2, 1]);
+        assert_eq!(stats.notes_per_day[0].day_start, 10 * DAY_MILLIS);
+
+        storage.delete_note(&bob, &carol).unwrap();
+        let stats = storage.get_network_stats(1, 10, 12).unwrap();
+        assert_eq!((stats.issuers, stats.recipients, stats.notes), (1, 2, 2));
+        assert_eq!(stats.total_outstanding_debt, 350);
+        assert_eq!(stats.top_issuers, vec![IssuerDebt { issuer_pubkey: alice, outstanding_debt: 350 }]);
+
+        // Rebuilding from the stored notes and history gives the same figures
+        storage.rebuild_indices().unwrap();
+        assert_eq!(storage.get_network_stats(1, 10, 12).unwrap(), stats);
+    }
+}
@@ -0,0 +1,212 @@
+//! Note version history, equivocation detection and dispute resolution
+//!
+//! Notes are cumulative: every payment replaces the previous note with a new
+//! one carrying a larger `amount_collected` and a later timestamp. The tracker
+//! keeps each signed version in an append-only log, so that a note presented
+//! later (e.g. by a recipient disputing the tracker state) can be checked
+//! against everything the issuer has signed before.
+
+use crate::{IouNote, PubKey};
+
+/// Two notes signed by the same issuer for the same recipient that cannot both
+/// be valid versions of one cumulative debt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EquivocationEvidence {
+    /// Issuer who signed both notes
+    pub issuer_pubkey: PubKey,
+    /// Version recorded in the tracker's history log
+    pub recorded: IouNote,
+    /// Conflicting version
+    pub conflicting: IouNote,
+}
+
+/// Result of checking a presented note against the recorded history
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisputeOutcome {
+    /// The note is the latest recorded version
+    Current,
+    /// The note was recorded but a later version exists
+    Superseded { latest: Box<IouNote> },
+    /// The note is consistent with the history but was never recorded
+    Unrecorded,
+    /// The note conflicts with a recorded version signed by the same issuer
+    Equivocation(Box<EquivocationEvidence>),
+}
+
+/// Whether two versions of the same issuer-recipient note contradict each other
+///
+/// Signatures commit to the cumulative amount and the timestamp, so the same
+/// timestamp must carry the same amount, and a later version may never carry
+/// a smaller amount.
+pub fn is_conflicting(recorded: &IouNote, note: &IouNote) -> bool {
+    if recorded.recipient_pubkey != note.recipient_pubkey {
+        return false;
+    }
+    match recorded.timestamp.cmp(&note.timestamp) {
+        std::cmp::Ordering::Equal => recorded.amount_collected != note.amount_collected,
+        std::cmp::Ordering::Less => note.amount_collected < recorded.amount_collected,
+        std::cmp::Ordering::Greater => note.amount_collected > recorded.amount_collected,
+    }
+}
+
+/// Find a recorded version that conflicts with `note`
+///
+/// The caller is responsible for checking the issuer signature on `note`;
+/// versions in `history` were verified when they were accepted.
+pub fn find_equivocation(
+    issuer_pubkey: &PubKey,
+    history: &[IouNote],
+    note: &IouNote,
+) -> Option<EquivocationEvidence> {
+    history
+        .iter()
+        .find(|recorded| is_conflicting(recorded, note))
+        .map(|recorded| EquivocationEvidence {
+            issuer_pubkey: *issuer_pubkey,
+            recorded: recorded.clone(),
+            conflicting: note.clone(),
+        })
+}
+
+/// Classify a presented note against the recorded history (oldest first)
+pub fn resolve_dispute(issuer_pubkey: &PubKey, history: &[IouNote], note: &IouNote) -> DisputeOutcome {
+    if let Some(evidence) = find_equivocation(issuer_pubkey, history, note) {
+        return DisputeOutcome::Equivocation(Box::new(evidence));
+    }
+
+    let recorded = history.iter().any(|version| {
+        version.timestamp == note.timestamp && version.amount_collected == note.amount_collected
+    });
+    if !recorded {
+        return DisputeOutcome::Unrecorded;
+    }
+
+    match history.last() {
+        Some(latest) if latest.timestamp != note.timestamp => DisputeOutcome::Superseded {
+            latest: Box::new(latest.clone()),
+        },
+        _ => DisputeOutcome::Current,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{schnorr::generate_keypair, NoteError, TrackerStateManager};
+
+    #[test]
+    fn test_history_records_every_version() {
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+
+        for (amount, timestamp) in [(100, 1_000), (250, 1_001), (400, 1_002)] {
+            let note = IouNote::create_and_sign(recipient_pubkey, amount, timestamp, &issuer_secret).unwrap();
+            tracker.add_note(&issuer_pubkey, &note).unwrap();
+        }
+
+        let (history, total) = tracker
+            .get_note_history(&issuer_pubkey, &recipient_pubkey, 0, 10)
+            .unwrap();
+        assert_eq!(total, 3);
+        let amounts: Vec<u64> = history.iter().map(|note| note.amount_collected).collect();
+        assert_eq!(amounts, vec![100, 250, 400]);
+
+        // Pagination keeps timestamp order and reports the full count
+        let (page, total) = tracker
+            .get_note_history(&issuer_pubkey, &recipient_pubkey, 1, 1)
+            .unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].timestamp, 1_001);
+
+        // Unknown pairs have no history
+        let (_, other_recipient) = generate_keypair();
+        let (empty, total) = tracker
+            .get_note_history(&issuer_pubkey, &other_recipient, 0, 10)
+            .unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(total, 0);
+    }
+
+    #[test]
+    fn test_detect_equivocation() {
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+
+        let first = IouNote::create_and_sign(recipient_pubkey, 100, 1_000, &issuer_secret).unwrap();
+        let second = IouNote::create_and_sign(recipient_pubkey, 300, 1_002, &issuer_secret).unwrap();
+        tracker.add_note(&issuer_pubkey, &first).unwrap();
+        tracker.add_note(&issuer_pubkey, &second).unwrap();
+
+        // Consistent versions are not equivocation
+        assert_eq!(tracker.detect_equivocation(&issuer_pubkey, &first).unwrap(), None);
+        let between = IouNote::create_and_sign(recipient_pubkey, 200, 1_001, &issuer_secret).unwrap();
+        assert_eq!(tracker.detect_equivocation(&issuer_pubkey, &between).unwrap(), None);
+
+        // Same timestamp, different amount
+        let same_time = IouNote::create_and_sign(recipient_pubkey, 150, 1_000, &issuer_secret).unwrap();
+        let evidence = tracker
+            .detect_equivocation(&issuer_pubkey, &same_time)
+            .unwrap()
+            .unwrap();
+        assert_eq!(evidence.recorded, first);
+        assert_eq!(evidence.conflicting, same_time);
+
+        // The conflicting note is rejected and does not replace the recorded version
+        assert!(matches!(
+            tracker.add_note(&issuer_pubkey, &same_time),
+            Err(NoteError::PastTimestamp)
+        ));
+        let (history, _) = tracker
+            .get_note_history(&issuer_pubkey, &recipient_pubkey, 0, 10)
+            .unwrap();
+        assert_eq!(history, vec![first.clone(), second.clone()]);
+
+        // Later timestamp with a smaller cumulative amount
+        let shrinking = IouNote::create_and_sign(recipient_pubkey, 50, 1_001, &issuer_secret).unwrap();
+        assert!(tracker.detect_equivocation(&issuer_pubkey, &shrinking).unwrap().is_some());
+
+        // Notes not signed by the issuer are not evidence
+        let (forger_secret, _) = generate_keypair();
+        let forged = IouNote::create_and_sign(recipient_pubkey, 150, 1_000, &forger_secret).unwrap();
+        assert!(matches!(
+            tracker.detect_equivocation(&issuer_pubkey, &forged),
+            Err(NoteError::InvalidSignature)
+        ));
+    }
+
+    #[test]
+    fn test_resolve_dispute() {
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+
+        let first = IouNote::create_and_sign(recipient_pubkey, 100, 1_000, &issuer_secret).unwrap();
+        let second = IouNote::create_and_sign(recipient_pubkey, 300, 1_002, &issuer_secret).unwrap();
+        tracker.add_note(&issuer_pubkey, &first).unwrap();
+        tracker.add_note(&issuer_pubkey, &second).unwrap();
+
+        assert_eq!(
+            tracker.resolve_dispute(&issuer_pubkey, &second).unwrap(),
+            DisputeOutcome::Current
+        );
+        assert_eq!(
+            tracker.resolve_dispute(&issuer_pubkey, &first).unwrap(),
+            DisputeOutcome::Superseded { latest: Box::new(second.clone()) }
+        );
+
+        let newer = IouNote::create_and_sign(recipient_pubkey, 500, 1_003, &issuer_secret).unwrap();
+        assert_eq!(
+            tracker.resolve_dispute(&issuer_pubkey, &newer).unwrap(),
+            DisputeOutcome::Unrecorded
+        );
+
+        let conflicting = IouNote::create_and_sign(recipient_pubkey, 200, 1_003, &issuer_secret).unwrap();
+        assert!(matches!(
+            tracker.resolve_dispute(&issuer_pubkey, &conflicting).unwrap(),
+            DisputeOutcome::Equivocation(evidence) if evidence.recorded == second
+        ));
+    }
+}
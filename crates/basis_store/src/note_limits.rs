@@ -0,0 +1,151 @@
+//! Limits on note amounts and on notes per issuer
+//!
+//! Public deployments can refuse notes too small to be worth redeeming
+//! (dust), notes recording more debt than any single note should, and
+//! issuers opening notes to ever more recipients, each of which adds an
+//! entry to the AVL tree. A limit of 0 is not enforced.
+
+use crate::{IouNote, NoteError};
+use serde::{Deserialize, Serialize};
+
+/// Limits checked when a note is added
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NoteLimits {
+    /// Smallest total debt a note may record
+    #[serde(default)]
+    pub min_amount: u64,
+    /// Largest total debt a note may record
+    #[serde(default)]
+    pub max_amount: u64,
+    /// Most recipients an issuer may hold notes to, across all keys of the
+    /// issuer's identity
+    #[serde(default)]
+    pub max_notes_per_issuer: usize,
+}
+
+impl NoteLimits {
+    /// Whether any limit is enforced
+    pub fn is_enabled(&self) -> bool {
+        self.min_amount > 0 || self.max_amount > 0 || self.max_notes_per_issuer > 0
+    }
+
+    /// Check the amount a note records
+    pub fn check_amount(&self, note: &IouNote) -> Result<(), NoteError> {
+        if note.amount_collected < self.min_amount {
+            return Err(NoteError::AmountBelowMinimum {
+                minimum: self.min_amount,
+            });
+        }
+        if self.max_amount > 0 && note.amount_collected > self.max_amount {
+            return Err(NoteError::AmountAboveMaximum {
+                maximum: self.max_amount,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check that an issuer holding `note_count` notes may open another one
+    pub fn check_new_note(&self, note_count: usize) -> Result<(), NoteError> {
+        if self.max_notes_per_issuer > 0 && note_count >= self.max_notes_per_issuer {
+            return Err(NoteError::TooManyNotes {
+                maximum: self.max_notes_per_issuer,
+            });
+        }
+        Ok(())
+    }
+
+    /// Inconsistent limits, as messages
+    pub fn invalid_settings(&self) -> Vec<String> {
+        let mut invalid = Vec::new();
+        if self.max_amount > 0 && self.min_amount > self.max_amount {
+            invalid.push(format!(
+                "min_amount {} is larger than max_amount {}",
+                self.min_amount, self.max_amount
+            ));
+        }
+        invalid
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{clock::MockClock, schnorr::generate_keypair, TrackerStateManager};
+    use std::sync::Arc;
+
+    fn note(amount: u64) -> IouNote {
+        IouNote::new([2u8; 33], amount, 0, 1_000, [0u8; 65])
+    }
+
+    #[test]
+    fn test_default_limits_accept_everything() {
+        let limits = NoteLimits::default();
+        assert!(!limits.is_enabled());
+        assert!(limits.check_amount(&note(0)).is_ok());
+        assert!(limits.check_amount(&note(u64::MAX)).is_ok());
+        assert!(limits.check_new_note(usize::MAX).is_ok());
+    }
+
+    #[test]
+    fn test_amount_and_note_count_limits() {
+        let limits = NoteLimits {
+            min_amount: 1_000,
+            max_amount: 5_000,
+            max_notes_per_issuer: 2,
+        };
+        assert!(matches!(
+            limits.check_amount(&note(999)),
+            Err(NoteError::AmountBelowMinimum { minimum: 1_000 })
+        ));
+        assert!(limits.check_amount(&note(1_000)).is_ok());
+        assert!(limits.check_amount(&note(5_000)).is_ok());
+        assert!(matches!(
+            limits.check_amount(&note(5_001)),
+            Err(NoteError::AmountAboveMaximum { maximum: 5_000 })
+        ));
+        assert!(limits.check_new_note(1).is_ok());
+        assert!(matches!(
+            limits.check_new_note(2),
+            Err(NoteError::TooManyNotes { maximum: 2 })
+        ));
+        assert!(limits.invalid_settings().is_empty());
+    }
+
+    #[test]
+    fn test_add_note_enforces_limits() {
+        let (issuer_secret, issuer) = generate_keypair();
+        let (_, first) = generate_keypair();
+        let (_, second) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        tracker.set_clock(Arc::new(MockClock::new(10_000)));
+        tracker.set_note_limits(NoteLimits {
+            min_amount: 100,
+            max_amount: 1_000,
+            max_notes_per_issuer: 1,
+        });
+
+        let dust = IouNote::create_and_sign(first, 99, 1_000, &issuer_secret).unwrap();
+        assert!(matches!(
+            tracker.add_note(&issuer, &dust),
+            Err(NoteError::AmountBelowMinimum { minimum: 100 })
+        ));
+        let note = IouNote::create_and_sign(first, 100, 1_000, &issuer_secret).unwrap();
+        tracker.add_note(&issuer, &note).unwrap();
+
+        // Updates of a held note do not count against the note limit
+        let update = IouNote::create_and_sign(first, 500, 2_000, &issuer_secret).unwrap();
+        tracker.add_note(&issuer, &update).unwrap();
+        let oversized = IouNote::create_and_sign(first, 1_001, 3_000, &issuer_secret).unwrap();
+        assert!(matches!(
+            tracker.add_note(&issuer, &oversized),
+            Err(NoteError::AmountAboveMaximum { maximum: 1_000 })
+        ));
+
+        let another = IouNote::create_and_sign(second, 100, 3_000, &issuer_secret).unwrap();
+        assert!(matches!(
+            tracker.validate_note(&issuer, &another)[..],
+            [NoteError::TooManyNotes { maximum: 1 }]
+        ));
+        assert!(tracker.add_note(&issuer, &another).is_err());
+    }
+}
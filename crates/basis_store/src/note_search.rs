@@ -0,0 +1,255 @@
+//! Multi-criteria note search
+//!
+//! Queries are answered from the secondary indexes of
+//! [`NoteStorage`](crate::persistence::NoteStorage): the issuer and recipient
+//! indexes narrow a search to one party's notes, and the amount and timestamp
+//! indexes, ordered by value, serve range filters and sort order without
+//! loading every note.
+
+use crate::{IouNote, NoteKey, PubKey};
+
+/// Field search results are ordered by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NoteSortField {
+    /// Timestamp of the latest note version
+    #[default]
+    Timestamp,
+    /// Total debt (`amount_collected`)
+    Amount,
+}
+
+/// Filters, order and page of a note search
+///
+/// Every filter is optional; unset filters match all notes. Amount filters
+/// apply to the total debt (`amount_collected`), bounds are inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteQuery {
+    pub issuer: Option<PubKey>,
+    pub recipient: Option<PubKey>,
+    pub min_amount: Option<u64>,
+    pub max_amount: Option<u64>,
+    pub from_timestamp: Option<u64>,
+    pub to_timestamp: Option<u64>,
+    /// Only notes with debt left to redeem
+    pub outstanding_only: bool,
+    /// Only notes whose signed memo is exactly this
+    pub memo: Option<String>,
+    pub sort: NoteSortField,
+    pub descending: bool,
+    pub offset: usize,
+    pub limit: usize,
+}
+
+impl Default for NoteQuery {
+    fn default() -> Self {
+        Self {
+            issuer: None,
+            recipient: None,
+            min_amount: None,
+            max_amount: None,
+            from_timestamp: None,
+            to_timestamp: None,
+            outstanding_only: false,
+            memo: None,
+            sort: NoteSortField::default(),
+            descending: true,
+            offset: 0,
+            limit: 20,
+        }
+    }
+}
+
+impl NoteQuery {
+    /// Whether a note passes every filter of the query
+    pub fn matches(&self, issuer_pubkey: &PubKey, note: &IouNote) -> bool {
+        self.issuer.is_none_or(|issuer| &issuer == issuer_pubkey)
+            && self.recipient.is_none_or(|recipient| recipient == note.recipient_pubkey)
+            && self.amount_range().contains(&note.amount_collected)
+            && self.timestamp_range().contains(&note.timestamp)
+            && (!self.outstanding_only || note.outstanding_debt() > 0)
+            && self.memo.as_ref().is_none_or(|memo| note.memo.as_ref() == Some(memo))
+    }
+
+    /// Inclusive bounds of the amount filter
+    pub fn amount_range(&self) -> std::ops::RangeInclusive<u64> {
+        self.min_amount.unwrap_or(0)..=self.max_amount.unwrap_or(u64::MAX)
+    }
+
+    /// Inclusive bounds of the timestamp filter
+    pub fn timestamp_range(&self) -> std::ops::RangeInclusive<u64> {
+        self.from_timestamp.unwrap_or(0)..=self.to_timestamp.unwrap_or(u64::MAX)
+    }
+
+    /// Value of the sort field for a note
+    pub fn sort_value(&self, note: &IouNote) -> u64 {
+        match self.sort {
+            NoteSortField::Timestamp => note.timestamp,
+            NoteSortField::Amount => note.amount_collected,
+        }
+    }
+}
+
+/// Where a timestamp-ordered scan resumes: just past the note with this
+/// timestamp and key
+///
+/// Unlike an offset, a cursor stays cheap however deep into the results it
+/// points, so long exports page with cursors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteCursor {
+    pub timestamp: u64,
+    pub key: NoteKey,
+}
+
+impl NoteCursor {
+    /// Cursor resuming after the given note
+    pub fn after(issuer_pubkey: &PubKey, note: &IouNote) -> Self {
+        Self {
+            timestamp: note.timestamp,
+            key: NoteKey::from_keys(issuer_pubkey, &note.recipient_pubkey),
+        }
+    }
+
+    /// Entry of the note in the timestamp index
+    pub(crate) fn index_key(&self) -> Vec<u8> {
+        let mut index_key = self.timestamp.to_be_bytes().to_vec();
+        index_key.extend_from_slice(&self.key.key_hash);
+        index_key
+    }
+}
+
+/// One page of search results
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteSearchPage {
+    /// Matching notes with their issuers, in the requested order
+    pub notes: Vec<(PubKey, IouNote)>,
+    /// Number of notes matching the query across all pages
+    pub total: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{schnorr::generate_keypair, TrackerStateManager};
+
+    #[test]
+    fn test_search_notes_combines_filters() {
+        let (alice_secret, alice) = generate_keypair();
+        let (bob_secret, bob) = generate_keypair();
+        let recipients: Vec<PubKey> = (0..3).map(|_| generate_keypair().1).collect();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+
+        for (i, (amount, timestamp)) in [(100, 1_000), (500, 2_000), (300, 3_000)].into_iter().enumerate() {
+            let note = IouNote::create_and_sign(recipients[i], amount, timestamp, &alice_secret).unwrap();
+            tracker.add_note(&alice, &note).unwrap();
+        }
+        let note = IouNote::create_and_sign(recipients[0], 700, 1_500, &bob_secret).unwrap();
+        tracker.add_note(&bob, &note).unwrap();
+
+        fn amounts(tracker: &TrackerStateManager, query: NoteQuery) -> (Vec<u64>, usize) {
+            let page = tracker.search_notes(&query).unwrap();
+            (page.notes.iter().map(|(_, note)| note.amount_collected).collect(), page.total)
+        }
+
+        // Newest first by default
+        assert_eq!(amounts(&tracker, NoteQuery::default()), (vec![300, 500, 700, 100], 4));
+
+        // Index range scan, ascending by amount
+        let query = NoteQuery {
+            min_amount: Some(200),
+            max_amount: Some(600),
+            sort: NoteSortField::Amount,
+            descending: false,
+            ..NoteQuery::default()
+        };
+        assert_eq!(amounts(&tracker, query), (vec![300, 500], 2));
+
+        // Issuer index combined with a timestamp range
+        let query = NoteQuery {
+            issuer: Some(alice),
+            from_timestamp: Some(1_500),
+            ..NoteQuery::default()
+        };
+        assert_eq!(amounts(&tracker, query), (vec![300, 500], 2));
+
+        // Recipient index with pagination
+        let query = NoteQuery {
+            recipient: Some(recipients[0]),
+            sort: NoteSortField::Amount,
+            offset: 1,
+            limit: 1,
+            ..NoteQuery::default()
+        };
+        assert_eq!(amounts(&tracker, query), (vec![100], 2));
+
+        // Updating a note moves it in the ordered indexes
+        let note = IouNote::create_and_sign(recipients[0], 900, 4_000, &alice_secret).unwrap();
+        tracker.add_note(&alice, &note).unwrap();
+        let query = NoteQuery {
+            sort: NoteSortField::Amount,
+            max_amount: Some(200),
+            ..NoteQuery::default()
+        };
+        assert_eq!(amounts(&tracker, query), (vec![], 0));
+        let query = NoteQuery {
+            from_timestamp: Some(3_500),
+            ..NoteQuery::default()
+        };
+        assert_eq!(amounts(&tracker, query), (vec![900], 1));
+    }
+
+    #[test]
+    fn test_search_outstanding_only() {
+        let (issuer_secret, issuer) = generate_keypair();
+        let (_, recipient) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+
+        let mut note = IouNote::create_and_sign(recipient, 100, 1_000, &issuer_secret).unwrap();
+        note.amount_redeemed = 100;
+        tracker.add_note(&issuer, &note).unwrap();
+
+        let query = NoteQuery {
+            outstanding_only: true,
+            ..NoteQuery::default()
+        };
+        assert_eq!(tracker.search_notes(&query).unwrap().total, 0);
+        assert_eq!(tracker.search_notes(&NoteQuery::default()).unwrap().total, 1);
+    }
+
+    #[test]
+    fn test_search_by_memo() {
+        let (issuer_secret, issuer) = generate_keypair();
+        let recipients: Vec<PubKey> = (0..3).map(|_| generate_keypair().1).collect();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+
+        for (i, memo) in ["INV-1001", "INV-1002", "INV-1001"].into_iter().enumerate() {
+            let note = IouNote::create_and_sign_v2(recipients[i], 100 * (i as u64 + 1), 1_000 + i as u64, None, &issuer_secret)
+                .unwrap()
+                .with_memo(memo)
+                .sign_with(&issuer_secret)
+                .unwrap();
+            tracker.add_note(&issuer, &note).unwrap();
+        }
+
+        let memo_query = |memo: &str| NoteQuery {
+            memo: Some(memo.to_string()),
+            sort: NoteSortField::Amount,
+            descending: false,
+            ..NoteQuery::default()
+        };
+        let page = tracker.search_notes(&memo_query("INV-1001")).unwrap();
+        assert_eq!(page.notes.iter().map(|(_, note)| note.amount_collected).collect::<Vec<_>>(), vec![100, 300]);
+        assert_eq!(page.notes[0].1.memo.as_deref(), Some("INV-1001"));
+        // Exact matches only
+        assert_eq!(tracker.search_notes(&memo_query("INV-100")).unwrap().total, 0);
+
+        // A new version with a different memo leaves the old memo's index entry
+        let note = IouNote::create_and_sign_v2(recipients[0], 150, 2_000, None, &issuer_secret)
+            .unwrap()
+            .with_memo("INV-1003")
+            .sign_with(&issuer_secret)
+            .unwrap();
+        tracker.add_note(&issuer, &note).unwrap();
+        assert_eq!(tracker.search_notes(&memo_query("INV-1001")).unwrap().total, 1);
+        assert_eq!(tracker.search_notes(&memo_query("INV-1003")).unwrap().total, 1);
+    }
+}
@@ -3,38 +3,103 @@
 //! This module provides efficient storage and retrieval of IOU notes with secondary indices
 //! for fast lookups by issuer, recipient, and timestamp without full partition scans.
 
-use crate::{reserve_tracker::ExtendedReserveInfo, IouNote, NoteError, NoteKey, PubKey, TrackerBoxInfo};
-use fjall::{Config, PartitionCreateOptions};
+use crate::commitment_verification::CommitmentCheck;
+use crate::ergo_scanner::{QueuedReserveEvent, ReserveEvent};
+use crate::disputes::Dispute;
+use crate::key_rotation::KeyRotation;
+use crate::network_stats::{self, DailyNoteCount, IssuerDebt, NetworkStats, DAY_MILLIS};
+use crate::split_notes::{SplitAllocation, SplitNote};
+use crate::storage_encryption::StorageCipher;
+use crate::storage_maintenance::StorageHandle;
+use crate::note_search::{NoteCursor, NoteQuery, NoteSearchPage, NoteSortField};
+use crate::reserve_lineage::{ReserveLineage, ReserveTransition};
+use crate::{reserve_tracker::ExtendedReserveInfo, InterestTerms, IouNote, NoteError, NoteKey, PubKey, Signature, SignatureSchemeId, SigningFormat, TrackerBoxInfo};
+use fjall::{Config, PartitionCreateOptions, PersistMode};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
 use std::path::Path;
 
 /// Database storage for IOU notes with extra indices for efficient querying
 ///
-/// Uses three partitions:
+/// Uses twelve partitions:
 /// - `iou_notes`: Main data storage (issuer+recipient -> note data)
 /// - `issuer_index`: Secondary index (issuer_pubkey -> list of note keys)
 /// - `recipient_index`: Secondary index (recipient_pubkey -> list of note keys)
+/// - `note_history`: Append-only log of every note version (note key || timestamp -> note data)
+/// - `amount_index`: Ordered index (amount_collected || note key -> empty)
+/// - `timestamp_index`: Ordered index (timestamp || note key -> empty)
+/// - `disputes`: Disputes filed against notes (dispute ID -> dispute)
+/// - `split_notes`: Notes allocating debt to several recipients (issuer || split ID -> split note)
+/// - `key_rotations`: Issuer key rotations (old key -> rotation)
+/// - `note_payloads`: Encrypted payloads of notes (note key -> timestamp || envelope)
+/// - `memo_index`: Memo index (memo || 0x00 || note key -> empty)
+/// - `note_stats`: Counters behind network statistics (see [`network_stats`])
+///
+/// With a storage key, the values of `iou_notes`, `note_history`, `disputes`,
+/// `split_notes`, `key_rotations` and `note_payloads` are sealed at rest (see
+/// [`storage_encryption`](crate::storage_encryption)).
 pub struct NoteStorage {
+    keyspace: fjall::Keyspace,
     notes_partition: fjall::Partition,
     issuer_index: fjall::Partition,
     recipient_index: fjall::Partition,
+    history_partition: fjall::Partition,
+    amount_index: fjall::Partition,
+    timestamp_index: fjall::Partition,
+    disputes_partition: fjall::Partition,
+    split_notes_partition: fjall::Partition,
+    key_rotations_partition: fjall::Partition,
+    note_payloads_partition: fjall::Partition,
+    memo_index: fjall::Partition,
+    stats_partition: fjall::Partition,
+    cipher: StorageCipher,
 }
 
 /// Database storage for scanner metadata
 #[derive(Clone)]
 pub struct ScannerMetadataStorage {
+    keyspace: fjall::Keyspace,
     partition: fjall::Partition,
 }
 
 /// Database storage for reserve information
+///
+/// Uses four partitions:
+/// - `reserves`: Current reserve boxes (box_id -> reserve data)
+/// - `reserve_history`: How each spent reserve box was spent (box_id -> transition)
+/// - `reserve_predecessors`: Reverse links (successor box_id -> spent box_id)
+/// - `reserve_events`: Intake queue of derived reserve events
+///   (height || sequence -> queued event)
+///
+/// With a storage key, the values of `reserves`, `reserve_history` and
+/// `reserve_events` are sealed at rest (see [`storage_encryption`](crate::storage_encryption)).
 #[derive(Clone)]
 pub struct ReserveStorage {
+    keyspace: fjall::Keyspace,
     partition: fjall::Partition,
+    history_partition: fjall::Partition,
+    predecessor_index: fjall::Partition,
+    event_queue: fjall::Partition,
+    cipher: StorageCipher,
 }
 
 /// Database storage for tracker information
+///
+/// Uses three partitions:
+/// - `tracker_metadata`: Tracker boxes seen on chain (box_id -> box data)
+/// - `local_roots`: Local AVL roots and the chain height they became current at
+///   (height || sequence -> root digest)
+/// - `commitment_checks`: Results of checking tracker boxes against local roots
+///   (box_id -> check)
+/// - `note_changes`: The note whose change produced a local root, under the
+///   root's key (height || sequence -> note)
 #[derive(Clone)]
 pub struct TrackerStorage {
+    keyspace: fjall::Keyspace,
     partition: fjall::Partition,
+    local_roots: fjall::Partition,
+    commitment_checks: fjall::Partition,
+    note_changes: fjall::Partition,
 }
 
 impl ScannerMetadataStorage {
@@ -48,7 +113,12 @@ impl ScannerMetadataStorage {
             .open_partition("scanner_metadata", PartitionCreateOptions::default())
             .map_err(|e| NoteError::StorageError(format!("Failed to open partition: {}", e)))?;
 
-        Ok(Self { partition })
+        Ok(Self { keyspace, partition })
+    }
+
+    /// Handle for maintaining the database
+    pub fn maintenance_handle(&self) -> StorageHandle {
+        StorageHandle::new("scanner_metadata", &self.keyspace, &[("scanner_metadata", &self.partition)])
     }
 
     /// Store scan ID for a specific scan name
@@ -89,6 +159,40 @@ impl ScannerMetadataStorage {
         Ok(())
     }
 
+    /// Store backfill progress: the scan it belongs to and the last fully processed height
+    /// Key: "backfill_progress", Value: 4 bytes scan ID (i32 BE) + 8 bytes height (u64 BE)
+    pub fn store_backfill_progress(&self, scan_id: i32, height: u64) -> Result<(), NoteError> {
+        let mut value = Vec::with_capacity(12);
+        value.extend_from_slice(&scan_id.to_be_bytes());
+        value.extend_from_slice(&height.to_be_bytes());
+        self.partition
+            .insert("backfill_progress", &value)
+            .map_err(|e| NoteError::StorageError(format!("Failed to store backfill progress: {}", e)))?;
+        Ok(())
+    }
+
+    /// Retrieve backfill progress as (scan ID, last processed height)
+    pub fn get_backfill_progress(&self) -> Result<Option<(i32, u64)>, NoteError> {
+        match self.partition.get("backfill_progress") {
+            Ok(Some(value_bytes)) => {
+                if value_bytes.len() == 12 {
+                    let scan_id = i32::from_be_bytes(value_bytes[0..4].try_into().unwrap());
+                    let height = u64::from_be_bytes(value_bytes[4..12].try_into().unwrap());
+                    Ok(Some((scan_id, height)))
+                } else {
+                    Err(NoteError::StorageError(
+                        "Invalid backfill progress format".to_string(),
+                    ))
+                }
+            }
+            Ok(None) => Ok(None),
+            Err(e) => Err(NoteError::StorageError(format!(
+                "Failed to get backfill progress: {}",
+                e
+            ))),
+        }
+    }
+
     /// Store blockchain height with fetch timestamp
     /// Key: "blockchain_height", Value: 8 bytes height + 8 bytes timestamp (u64 BE)
     pub fn store_blockchain_height(&self, height: u64, timestamp: u64) -> Result<(), NoteError> {
@@ -125,9 +229,243 @@ impl ScannerMetadataStorage {
     }
 }
 
+/// Length of a stored note record without a recipient acknowledgment:
+/// issuer (33) || amount_collected (8) || amount_redeemed (8) || timestamp (8) || signature (65) || recipient (33)
+const NOTE_RECORD_LEN: usize = 33 + 8 + 8 + 8 + 65 + 33;
+
+/// Length of a stored note record with the recipient acknowledgment signature (65) appended
+const ACKED_NOTE_RECORD_LEN: usize = NOTE_RECORD_LEN + 65;
+
+/// Length of the signing format suffix of a version 2 record:
+/// version (1) || tracker ID (32, zero when unbound)
+///
+/// Version 1 records carry no suffix, so records written before signing
+/// formats existed keep decoding as version 1 notes without a rewrite.
+const SIGNING_FORMAT_SUFFIX_LEN: usize = 1 + 32;
+
+/// Length of a version 2 record with the signing format suffix followed by
+/// the note's interest terms: rate (4) || accrual method (1)
+const INTEREST_RECORD_EXTRA_LEN: usize = SIGNING_FORMAT_SUFFIX_LEN + InterestTerms::ENCODED_LEN;
+
+/// First byte of a record that carries a memo:
+/// tag (1) || memo length (1) || memo || record without the memo
+///
+/// Other records start with the issuer's compressed key (0x02 or 0x03), so
+/// the tag cannot be mistaken for one of them.
+const MEMO_RECORD_TAG: u8 = 0x4D;
+
+/// First byte of a record signed with another scheme than secp256k1 Schnorr:
+/// tag (1) || scheme identifier (1) || record without the scheme
+///
+/// Schnorr records carry no tag, so they stay as they were before signature
+/// schemes were identified.
+const SCHEME_RECORD_TAG: u8 = 0x53;
+
+/// Serialize a note with its issuer into the stored record format
+///
+/// Both public keys are stored: the record key is their hash and cannot be
+/// reversed to recover them.
+fn encode_note_record(issuer_pubkey: &PubKey, note: &IouNote) -> Vec<u8> {
+    let mut value_bytes = Vec::with_capacity(ACKED_NOTE_RECORD_LEN + INTEREST_RECORD_EXTRA_LEN);
+    if note.signature_scheme != SignatureSchemeId::Schnorr {
+        value_bytes.push(SCHEME_RECORD_TAG);
+        value_bytes.push(note.signature_scheme.code());
+    }
+    if let Some(memo) = &note.memo {
+        value_bytes.push(MEMO_RECORD_TAG);
+        value_bytes.push(memo.len() as u8);
+        value_bytes.extend_from_slice(memo.as_bytes());
+    }
+    value_bytes.extend_from_slice(issuer_pubkey);
+    value_bytes.extend_from_slice(&note.amount_collected.to_be_bytes());
+    value_bytes.extend_from_slice(&note.amount_redeemed.to_be_bytes());
+    value_bytes.extend_from_slice(&note.timestamp.to_be_bytes());
+    value_bytes.extend_from_slice(&note.signature);
+    value_bytes.extend_from_slice(&note.recipient_pubkey);
+    if let Some(recipient_signature) = &note.recipient_signature {
+        value_bytes.extend_from_slice(recipient_signature);
+    }
+    if let SigningFormat::V2 { tracker_id } = &note.signing_format {
+        value_bytes.push(note.signing_format.version());
+        value_bytes.extend_from_slice(tracker_id.as_ref().unwrap_or(&[0u8; 32]));
+        if let Some(interest) = &note.interest {
+            value_bytes.extend_from_slice(&interest.to_bytes());
+        }
+    }
+    value_bytes
+}
+
+/// Deserialize a stored note record, returning None for malformed entries
+fn decode_note_record(value_bytes: &[u8]) -> Option<(PubKey, IouNote)> {
+    if value_bytes.first() == Some(&SCHEME_RECORD_TAG) {
+        let signature_scheme = SignatureSchemeId::from_code(*value_bytes.get(1)?)?;
+        let (issuer_pubkey, mut note) = decode_note_record(&value_bytes[2..])?;
+        note.signature_scheme = signature_scheme;
+        return Some((issuer_pubkey, note));
+    }
+    if value_bytes.first() == Some(&MEMO_RECORD_TAG) {
+        let memo_len = *value_bytes.get(1)? as usize;
+        let memo = std::str::from_utf8(value_bytes.get(2..2 + memo_len)?).ok()?.to_string();
+        let (issuer_pubkey, note) = decode_note_record(&value_bytes[2 + memo_len..])?;
+        return Some((issuer_pubkey, note.with_memo(memo)));
+    }
+
+    let (value_bytes, interest) = match value_bytes.len() {
+        len if len == NOTE_RECORD_LEN + INTEREST_RECORD_EXTRA_LEN
+            || len == ACKED_NOTE_RECORD_LEN + INTEREST_RECORD_EXTRA_LEN =>
+        {
+            let (record, interest) = value_bytes.split_at(len - InterestTerms::ENCODED_LEN);
+            (record, Some(InterestTerms::from_bytes(interest.try_into().ok()?)?))
+        }
+        _ => (value_bytes, None),
+    };
+    let (value_bytes, signing_format) = match value_bytes.len() {
+        NOTE_RECORD_LEN | ACKED_NOTE_RECORD_LEN => (value_bytes, SigningFormat::V1),
+        len if len == NOTE_RECORD_LEN + SIGNING_FORMAT_SUFFIX_LEN
+            || len == ACKED_NOTE_RECORD_LEN + SIGNING_FORMAT_SUFFIX_LEN =>
+        {
+            let (record, suffix) = value_bytes.split_at(len - SIGNING_FORMAT_SUFFIX_LEN);
+            if suffix[0] != 2 {
+                return None;
+            }
+            let tracker_id: [u8; 32] = suffix[1..].try_into().ok()?;
+            let tracker_id = (tracker_id != [0u8; 32]).then_some(tracker_id);
+            (record, SigningFormat::V2 { tracker_id })
+        }
+        _ => return None,
+    };
+
+    let issuer_pubkey: PubKey = value_bytes[0..33].try_into().ok()?;
+    let amount_collected = u64::from_be_bytes(value_bytes[33..41].try_into().ok()?);
+    let amount_redeemed = u64::from_be_bytes(value_bytes[41..49].try_into().ok()?);
+    let timestamp = u64::from_be_bytes(value_bytes[49..57].try_into().ok()?);
+    let signature: [u8; 65] = value_bytes[57..122].try_into().ok()?;
+    let recipient_pubkey: PubKey = value_bytes[122..155].try_into().ok()?;
+    let recipient_signature = if value_bytes.len() == ACKED_NOTE_RECORD_LEN {
+        Some(value_bytes[155..220].try_into().ok()?)
+    } else {
+        None
+    };
+
+    Some((
+        issuer_pubkey,
+        IouNote {
+            recipient_pubkey,
+            amount_collected,
+            amount_redeemed,
+            timestamp,
+            signature,
+            recipient_signature,
+            signing_format,
+            interest,
+            memo: None,
+            signature_scheme: SignatureSchemeId::Schnorr,
+        },
+    ))
+}
+
+/// Open a stored value with the storage cipher
+fn unseal<'a>(cipher: &StorageCipher, value_bytes: &'a [u8]) -> Result<Cow<'a, [u8]>, NoteError> {
+    cipher
+        .open(value_bytes)
+        .map_err(|e| NoteError::StorageError(format!("Failed to open stored value: {}", e)))
+}
+
+/// Rewrite every value of a partition not sealed with the current storage
+/// key, returning how many were rewritten
+fn reseal_partition(cipher: &StorageCipher, partition: &fjall::Partition) -> Result<usize, NoteError> {
+    let storage_error = |e: fjall::Error| NoteError::StorageError(format!("Failed to reseal stored values: {}", e));
+    // Keys first, so the partition is not written to while it is iterated
+    let mut stale = Vec::new();
+    for item in partition.iter() {
+        let (key, value) = item.map_err(storage_error)?;
+        if cipher.needs_reseal(&value) {
+            stale.push(key);
+        }
+    }
+    for key in &stale {
+        if let Some(value) = partition.get(key).map_err(storage_error)? {
+            let sealed = cipher.seal(&unseal(cipher, &value)?);
+            partition.insert(key.clone(), sealed).map_err(storage_error)?;
+        }
+    }
+    Ok(stale.len())
+}
+
+fn split_note_key(issuer_pubkey: &PubKey, split_id: &[u8; 32]) -> Vec<u8> {
+    let mut key = Vec::with_capacity(33 + 32);
+    key.extend_from_slice(issuer_pubkey);
+    key.extend_from_slice(split_id);
+    key
+}
+
+/// Serialize a key rotation: new key (33) || timestamp (8) || signature (65)
+fn encode_rotation_record(rotation: &KeyRotation) -> Vec<u8> {
+    let mut value_bytes = Vec::with_capacity(33 + 8 + 65);
+    value_bytes.extend_from_slice(&rotation.new_pubkey);
+    value_bytes.extend_from_slice(&rotation.timestamp.to_be_bytes());
+    value_bytes.extend_from_slice(&rotation.signature);
+    value_bytes
+}
+
+/// Deserialize a stored key rotation, returning None for malformed records
+fn decode_rotation_record(old_pubkey: &PubKey, value_bytes: &[u8]) -> Option<KeyRotation> {
+    if value_bytes.len() != 33 + 8 + 65 {
+        return None;
+    }
+    Some(KeyRotation {
+        old_pubkey: *old_pubkey,
+        new_pubkey: value_bytes[..33].try_into().ok()?,
+        timestamp: u64::from_be_bytes(value_bytes[33..41].try_into().ok()?),
+        signature: value_bytes[41..].try_into().ok()?,
+    })
+}
+
+/// Serialize a split note: timestamp (8) || signature (65) || (recipient (33) || amount (8))*
+fn encode_split_record(split: &SplitNote) -> Vec<u8> {
+    let mut value_bytes = Vec::with_capacity(8 + 65 + split.allocations.len() * (33 + 8));
+    value_bytes.extend_from_slice(&split.timestamp.to_be_bytes());
+    value_bytes.extend_from_slice(&split.signature);
+    for allocation in &split.allocations {
+        value_bytes.extend_from_slice(&allocation.recipient_pubkey);
+        value_bytes.extend_from_slice(&allocation.amount.to_be_bytes());
+    }
+    value_bytes
+}
+
+/// Deserialize a stored split note, returning None for malformed records
+fn decode_split_record(value_bytes: &[u8]) -> Option<SplitNote> {
+    if value_bytes.len() < 8 + 65 || !(value_bytes.len() - 8 - 65).is_multiple_of(33 + 8) {
+        return None;
+    }
+    let timestamp = u64::from_be_bytes(value_bytes[..8].try_into().ok()?);
+    let signature: Signature = value_bytes[8..73].try_into().ok()?;
+    let allocations = value_bytes[73..]
+        .chunks_exact(33 + 8)
+        .map(|chunk| {
+            Some(SplitAllocation {
+                recipient_pubkey: chunk[..33].try_into().ok()?,
+                amount: u64::from_be_bytes(chunk[33..].try_into().ok()?),
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    Some(SplitNote {
+        allocations,
+        timestamp,
+        signature,
+    })
+}
+
 impl NoteStorage {
-    /// Open or create a new note storage database with extra indices
+    /// Open or create a new note storage database with extra indices,
+    /// keeping values in plaintext
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, NoteError> {
+        Self::open_with_cipher(path, StorageCipher::default())
+    }
+
+    /// Open or create a note storage database sealing values with `cipher`
+    pub fn open_with_cipher<P: AsRef<Path>>(path: P, cipher: StorageCipher) -> Result<Self, NoteError> {
         let keyspace = Config::new(path)
             .open()
             .map_err(|e| NoteError::StorageError(format!("Failed to open database: {}", e)))?;
@@ -144,7 +482,207 @@ impl NoteStorage {
             .open_partition("recipient_index", PartitionCreateOptions::default())
             .map_err(|e| NoteError::StorageError(format!("Failed to open recipient index partition: {}", e)))?;
 
-        Ok(Self { notes_partition, issuer_index, recipient_index })
+        let history_partition = keyspace
+            .open_partition("note_history", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open note history partition: {}", e)))?;
+
+        let amount_index = keyspace
+            .open_partition("amount_index", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open amount index partition: {}", e)))?;
+
+        let timestamp_index = keyspace
+            .open_partition("timestamp_index", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open timestamp index partition: {}", e)))?;
+
+        let disputes_partition = keyspace
+            .open_partition("disputes", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open disputes partition: {}", e)))?;
+
+        let split_notes_partition = keyspace
+            .open_partition("split_notes", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open split notes partition: {}", e)))?;
+
+        let key_rotations_partition = keyspace
+            .open_partition("key_rotations", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open key rotations partition: {}", e)))?;
+
+        let note_payloads_partition = keyspace
+            .open_partition("note_payloads", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open note payloads partition: {}", e)))?;
+
+        let memo_index = keyspace
+            .open_partition("memo_index", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open memo index partition: {}", e)))?;
+
+        let stats_partition = keyspace
+            .open_partition("note_stats", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open note stats partition: {}", e)))?;
+
+        Ok(Self {
+            keyspace,
+            notes_partition,
+            issuer_index,
+            recipient_index,
+            history_partition,
+            amount_index,
+            timestamp_index,
+            disputes_partition,
+            split_notes_partition,
+            key_rotations_partition,
+            note_payloads_partition,
+            memo_index,
+            stats_partition,
+            cipher,
+        })
+    }
+
+    /// Handle for maintaining the database
+    pub fn maintenance_handle(&self) -> StorageHandle {
+        StorageHandle::new(
+            "notes",
+            &self.keyspace,
+            &[
+                ("iou_notes", &self.notes_partition),
+                ("issuer_index", &self.issuer_index),
+                ("recipient_index", &self.recipient_index),
+                ("note_history", &self.history_partition),
+                ("amount_index", &self.amount_index),
+                ("timestamp_index", &self.timestamp_index),
+                ("disputes", &self.disputes_partition),
+                ("split_notes", &self.split_notes_partition),
+                ("key_rotations", &self.key_rotations_partition),
+                ("note_payloads", &self.note_payloads_partition),
+                ("memo_index", &self.memo_index),
+                ("note_stats", &self.stats_partition),
+            ],
+        )
+    }
+
+    /// Read every note record, returning how many there are and the keys of
+    /// those that cannot be opened or decoded
+    pub fn scan_note_records(&self) -> Result<(u64, Vec<NoteKey>), NoteError> {
+        let mut records = 0;
+        let mut corrupt = Vec::new();
+        for item in self.notes_partition.iter() {
+            let (key_bytes, value_bytes) = item.map_err(|e| {
+                NoteError::StorageError(format!("Failed to iterate partition: {}", e))
+            })?;
+            records += 1;
+            let readable = unseal(&self.cipher, &value_bytes)
+                .ok()
+                .and_then(|value_bytes| decode_note_record(&value_bytes))
+                .is_some();
+            if !readable {
+                match key_bytes.as_ref().try_into() {
+                    Ok(key) => corrupt.push(NoteKey::from_bytes(&key)),
+                    Err(_) => tracing::warn!("Skipping note record with a malformed key"),
+                }
+            }
+        }
+        Ok((records, corrupt))
+    }
+
+    /// Number of notes the statistics counters hold
+    pub fn counted_notes(&self) -> Result<u64, NoteError> {
+        self.get_stat(network_stats::NOTES_KEY)
+    }
+
+    /// Rewrite a note record from its latest readable version in the note
+    /// history, preferring the latest one collecting `committed_amount`
+    ///
+    /// Indexes are not updated; rebuild them once records are restored.
+    /// Returns false if the history holds no readable version.
+    pub fn restore_note_from_history(&self, key: &NoteKey, committed_amount: Option<u64>) -> Result<bool, NoteError> {
+        let mut latest = None;
+        let mut latest_committed = None;
+        for item in self.history_partition.prefix(key.to_bytes()) {
+            let (_key_bytes, value_bytes) = item.map_err(|e| {
+                NoteError::StorageError(format!("Failed to iterate note history: {}", e))
+            })?;
+            let Some(entry) = unseal(&self.cipher, &value_bytes)
+                .ok()
+                .and_then(|value_bytes| decode_note_record(&value_bytes))
+            else {
+                continue;
+            };
+            if Some(entry.1.amount_collected) == committed_amount {
+                latest_committed = Some(entry.clone());
+            }
+            latest = Some(entry);
+        }
+
+        let Some((issuer_pubkey, note)) = latest_committed.or(latest) else {
+            return Ok(false);
+        };
+        self.notes_partition
+            .insert(key.to_bytes(), self.cipher.seal(&encode_note_record(&issuer_pubkey, &note)))
+            .map_err(|e| NoteError::StorageError(format!("Failed to restore note: {}", e)))?;
+        Ok(true)
+    }
+
+    /// Seal every record not sealed with the current storage key: plaintext
+    /// written before encryption was enabled, and values sealed with a
+    /// previous key. Returns how many values were rewritten.
+    pub fn reseal_all(&self) -> Result<usize, NoteError> {
+        if !self.cipher.is_enabled() {
+            return Err(NoteError::StorageError("No storage key configured".to_string()));
+        }
+        let mut count = 0;
+        for partition in [
+            &self.notes_partition,
+            &self.history_partition,
+            &self.disputes_partition,
+            &self.split_notes_partition,
+            &self.key_rotations_partition,
+            &self.note_payloads_partition,
+        ] {
+            count += reseal_partition(&self.cipher, partition)?;
+        }
+        self.persist()?;
+        Ok(count)
+    }
+
+    /// Sync all written notes to disk
+    pub fn persist(&self) -> Result<(), NoteError> {
+        self.keyspace
+            .persist(PersistMode::SyncAll)
+            .map_err(|e| NoteError::StorageError(format!("Failed to persist notes: {}", e)))
+    }
+
+    /// History key: note key (32 bytes) || timestamp (8 bytes BE), so that a
+    /// prefix scan returns the versions of a note in timestamp order
+    fn history_key(note_key: &NoteKey, timestamp: u64) -> Vec<u8> {
+        let mut key = note_key.to_bytes();
+        key.extend_from_slice(&timestamp.to_be_bytes());
+        key
+    }
+
+    /// Append a note version to the history log
+    ///
+    /// A version is identified by its timestamp. Re-storing the same signed
+    /// version (e.g. after an acknowledgment) refreshes the entry, but a
+    /// different amount under an already recorded timestamp never replaces it.
+    fn append_history(&self, issuer_pubkey: &PubKey, note: &IouNote) -> Result<(), NoteError> {
+        let key = NoteKey::from_keys(issuer_pubkey, &note.recipient_pubkey);
+        let history_key = Self::history_key(&key, note.timestamp);
+
+        let existing = self.history_partition.get(&history_key).map_err(|e| {
+            NoteError::StorageError(format!("Failed to read note history: {}", e))
+        })?;
+        let existing = existing.as_deref().map(|value| unseal(&self.cipher, value)).transpose()?;
+        if let Some((_, recorded)) = existing.as_deref().and_then(decode_note_record) {
+            if recorded.amount_collected != note.amount_collected {
+                tracing::warn!(
+                    "Not overwriting recorded note version at timestamp {} with a different amount",
+                    note.timestamp
+                );
+                return Ok(());
+            }
+        }
+
+        self.history_partition
+            .insert(&history_key, self.cipher.seal(&encode_note_record(issuer_pubkey, note)))
+            .map_err(|e| NoteError::StorageError(format!("Failed to append note history: {}", e)))
     }
 
     /// Serialize a list of note keys to bytes
@@ -241,31 +779,321 @@ impl NoteStorage {
         Ok(())
     }
 
+    /// Ordered index key: value (8 bytes BE) || note key (32 bytes), so that
+    /// a range scan returns notes ordered by value
+    fn ordered_index_key(value: u64, note_key: &NoteKey) -> Vec<u8> {
+        let mut key = value.to_be_bytes().to_vec();
+        key.extend_from_slice(&note_key.to_bytes());
+        key
+    }
+
+    /// Memo index key: memo || 0x00 || note key (32 bytes)
+    ///
+    /// Memos contain no control characters, so the separator ends the memo
+    /// and a prefix scan over `memo || 0x00` matches that memo exactly.
+    fn memo_index_prefix(memo: &str) -> Vec<u8> {
+        let mut key = memo.as_bytes().to_vec();
+        key.push(0);
+        key
+    }
+
+    /// Add a note to the amount, timestamp and memo indexes
+    fn add_to_ordered_indexes(&self, note_key: &NoteKey, note: &IouNote) -> Result<(), NoteError> {
+        self.amount_index
+            .insert(Self::ordered_index_key(note.amount_collected, note_key), [])
+            .and_then(|_| {
+                self.timestamp_index
+                    .insert(Self::ordered_index_key(note.timestamp, note_key), [])
+            })
+            .and_then(|_| match &note.memo {
+                Some(memo) => {
+                    let mut key = Self::memo_index_prefix(memo);
+                    key.extend_from_slice(&note_key.to_bytes());
+                    self.memo_index.insert(key, [])
+                }
+                None => Ok(()),
+            })
+            .map_err(|e| NoteError::StorageError(format!("Failed to update index: {}", e)))
+    }
+
+    /// Remove a note from the amount, timestamp and memo indexes
+    fn remove_from_ordered_indexes(&self, note_key: &NoteKey, note: &IouNote) -> Result<(), NoteError> {
+        self.amount_index
+            .remove(Self::ordered_index_key(note.amount_collected, note_key))
+            .and_then(|_| {
+                self.timestamp_index
+                    .remove(Self::ordered_index_key(note.timestamp, note_key))
+            })
+            .and_then(|_| match &note.memo {
+                Some(memo) => {
+                    let mut key = Self::memo_index_prefix(memo);
+                    key.extend_from_slice(&note_key.to_bytes());
+                    self.memo_index.remove(key)
+                }
+                None => Ok(()),
+            })
+            .map_err(|e| NoteError::StorageError(format!("Failed to remove index entry: {}", e)))
+    }
+
+    /// Note keys listed under a memo in the memo index
+    fn memo_keys(&self, memo: &str) -> Result<Vec<NoteKey>, NoteError> {
+        let prefix = Self::memo_index_prefix(memo);
+        let mut keys = Vec::new();
+        for item in self.memo_index.prefix(&prefix) {
+            let (index_key, _) = item.map_err(|e| {
+                NoteError::StorageError(format!("Failed to iterate index: {}", e))
+            })?;
+            if let Ok(note_key) = index_key[prefix.len()..].try_into() {
+                keys.push(NoteKey::from_bytes(&note_key));
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Read the stored record of a note
+    fn get_note_record(&self, key: &NoteKey) -> Result<Option<(PubKey, IouNote)>, NoteError> {
+        let value_bytes = self
+            .notes_partition
+            .get(key.to_bytes())
+            .map_err(|e| NoteError::StorageError(format!("Failed to get note: {}", e)))?;
+        match value_bytes {
+            Some(value_bytes) => Ok(decode_note_record(&unseal(&self.cipher, &value_bytes)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Store an IOU note with its issuer public key
     pub fn store_note(&self, issuer_pubkey: &PubKey, note: &IouNote) -> Result<(), NoteError> {
         let key = NoteKey::from_keys(issuer_pubkey, &note.recipient_pubkey);
         let key_bytes = key.to_bytes();
 
+        // The previous version's amount and timestamp no longer describe the note
+        let previous = self.get_note_record(&key)?.map(|(_, previous)| previous);
+        if let Some(previous) = &previous {
+            self.remove_from_ordered_indexes(&key, previous)?;
+        }
+
         // Manual serialization to avoid serde issues with arrays
-        let mut value_bytes = Vec::new();
-        value_bytes.extend_from_slice(issuer_pubkey);
-        value_bytes.extend_from_slice(&note.amount_collected.to_be_bytes());
-        value_bytes.extend_from_slice(&note.amount_redeemed.to_be_bytes());
-        value_bytes.extend_from_slice(&note.timestamp.to_be_bytes());
-        value_bytes.extend_from_slice(&note.signature);
-        value_bytes.extend_from_slice(&note.recipient_pubkey);
+        let value_bytes = self.cipher.seal(&encode_note_record(issuer_pubkey, note));
 
         self.notes_partition
             .insert(&key_bytes, &value_bytes)
             .map_err(|e| NoteError::StorageError(format!("Failed to insert note: {}", e)))?;
 
+        let new_issuer = !self.has_index_entry(&self.issuer_index, issuer_pubkey)?;
+        let new_recipient = !self.has_index_entry(&self.recipient_index, &note.recipient_pubkey)?;
+
         // Update indices for efficient querying
         Self::add_to_index(&self.issuer_index, issuer_pubkey, &key)?;
         Self::add_to_index(&self.recipient_index, &note.recipient_pubkey, &key)?;
+        self.add_to_ordered_indexes(&key, note)?;
+
+        self.append_history(issuer_pubkey, note)?;
+
+        if new_issuer {
+            self.add_to_stat(network_stats::ISSUERS_KEY, 1)?;
+        }
+        if new_recipient {
+            self.add_to_stat(network_stats::RECIPIENTS_KEY, 1)?;
+        }
+        if previous.is_none() {
+            self.add_to_stat(network_stats::NOTES_KEY, 1)?;
+        }
+        // Re-storing a version (e.g. after an acknowledgment) is not a new note version
+        if previous.as_ref().map(|previous| previous.timestamp) != Some(note.timestamp) {
+            self.add_to_stat(&network_stats::day_key(note.timestamp / DAY_MILLIS), 1)?;
+        }
+        let previous_debt = previous.as_ref().map_or(0, IouNote::outstanding_debt);
+        self.adjust_issuer_debt(issuer_pubkey, previous_debt, note.outstanding_debt())?;
+
+        Ok(())
+    }
+
+    /// Whether a public key has any note keys in the issuer or recipient index
+    fn has_index_entry(&self, index: &fjall::Partition, pubkey: &PubKey) -> Result<bool, NoteError> {
+        index
+            .contains_key(pubkey)
+            .map_err(|e| NoteError::StorageError(format!("Failed to read index: {}", e)))
+    }
+
+    /// Read a statistics counter, 0 when unset
+    fn get_stat(&self, key: &[u8]) -> Result<u64, NoteError> {
+        let value = self
+            .stats_partition
+            .get(key)
+            .map_err(|e| NoteError::StorageError(format!("Failed to read note stats: {}", e)))?;
+        Ok(value
+            .and_then(|bytes| bytes.as_ref().try_into().ok())
+            .map_or(0, u64::from_be_bytes))
+    }
 
+    /// Write a statistics counter, dropping it when it reaches 0
+    fn set_stat(&self, key: &[u8], value: u64) -> Result<(), NoteError> {
+        let result = if value == 0 {
+            self.stats_partition.remove(key)
+        } else {
+            self.stats_partition.insert(key, value.to_be_bytes())
+        };
+        result.map_err(|e| NoteError::StorageError(format!("Failed to update note stats: {}", e)))
+    }
+
+    /// Add `delta` to a statistics counter, saturating at 0 and `u64::MAX`
+    fn add_to_stat(&self, key: &[u8], delta: i64) -> Result<(), NoteError> {
+        let value = self.get_stat(key)?;
+        let value = if delta >= 0 {
+            value.saturating_add(delta as u64)
+        } else {
+            value.saturating_sub(delta.unsigned_abs())
+        };
+        self.set_stat(key, value)
+    }
+
+    /// Move an issuer's outstanding debt, and the network total, from
+    /// `previous` to `current` for one of its notes, keeping the ranking
+    /// of issuers by debt in step
+    fn adjust_issuer_debt(&self, issuer_pubkey: &PubKey, previous: u64, current: u64) -> Result<(), NoteError> {
+        if previous == current {
+            return Ok(());
+        }
+        let total = self.get_stat(network_stats::OUTSTANDING_KEY)?;
+        self.set_stat(
+            network_stats::OUTSTANDING_KEY,
+            total.saturating_sub(previous).saturating_add(current),
+        )?;
+
+        let debt_key = network_stats::issuer_debt_key(issuer_pubkey);
+        let old_debt = self.get_stat(&debt_key)?;
+        let new_debt = old_debt.saturating_sub(previous).saturating_add(current);
+        self.set_stat(&debt_key, new_debt)?;
+        self.stats_partition
+            .remove(network_stats::debt_rank_key(old_debt, issuer_pubkey))
+            .and_then(|_| {
+                if new_debt == 0 {
+                    return Ok(());
+                }
+                self.stats_partition
+                    .insert(network_stats::debt_rank_key(new_debt, issuer_pubkey), [])
+            })
+            .map_err(|e| NoteError::StorageError(format!("Failed to update note stats: {}", e)))
+    }
+
+    /// Recount every statistic from the stored notes and their history
+    ///
+    /// `debts` holds the outstanding debt of each issuer and `notes` the
+    /// number of stored notes, both gathered while rebuilding the indices.
+    fn rebuild_stats(&self, notes: usize, debts: &HashMap<PubKey, u64>) -> Result<(), NoteError> {
+        let storage_error = |e: fjall::Error| NoteError::StorageError(format!("Failed to rebuild note stats: {}", e));
+        let stale: Vec<_> = self
+            .stats_partition
+            .keys()
+            .collect::<Result<_, _>>()
+            .map_err(storage_error)?;
+        for key in stale {
+            self.stats_partition.remove(key).map_err(storage_error)?;
+        }
+
+        let mut days: BTreeMap<u64, u64> = BTreeMap::new();
+        for key in self.history_partition.keys() {
+            let key = key.map_err(storage_error)?;
+            if let Some(timestamp) = key.get(32..40) {
+                let timestamp = u64::from_be_bytes(timestamp.try_into().unwrap());
+                *days.entry(timestamp / DAY_MILLIS).or_default() += 1;
+            }
+        }
+        for (day, count) in days {
+            self.set_stat(&network_stats::day_key(day), count)?;
+        }
+
+        let issuers = self.issuer_index.keys().count() as u64;
+        let recipients = self.recipient_index.keys().count() as u64;
+        self.set_stat(network_stats::ISSUERS_KEY, issuers)?;
+        self.set_stat(network_stats::RECIPIENTS_KEY, recipients)?;
+        self.set_stat(network_stats::NOTES_KEY, notes as u64)?;
+        for (issuer_pubkey, debt) in debts {
+            self.adjust_issuer_debt(issuer_pubkey, 0, *debt)?;
+        }
         Ok(())
     }
 
+    /// Network statistics from the maintained counters
+    ///
+    /// Lists the `top_issuers` issuers owing the most and the note versions
+    /// of each day from `first_day` to `last_day` (days since the epoch).
+    pub fn get_network_stats(&self, top_issuers: usize, first_day: u64, last_day: u64) -> Result<NetworkStats, NoteError> {
+        let storage_error = |e: fjall::Error| NoteError::StorageError(format!("Failed to read note stats: {}", e));
+
+        let mut top = Vec::with_capacity(top_issuers);
+        for item in self.stats_partition.prefix([network_stats::DEBT_RANK_PREFIX]).rev().take(top_issuers) {
+            let (key, _) = item.map_err(storage_error)?;
+            if let (Some(debt), Some(issuer_pubkey)) = (key.get(1..9), key.get(9..42)) {
+                top.push(IssuerDebt {
+                    issuer_pubkey: issuer_pubkey.try_into().unwrap(),
+                    outstanding_debt: u64::from_be_bytes(debt.try_into().unwrap()),
+                });
+            }
+        }
+
+        let mut days: BTreeMap<u64, u64> = BTreeMap::new();
+        let range = network_stats::day_key(first_day)..=network_stats::day_key(last_day);
+        for item in self.stats_partition.range(range) {
+            let (key, value) = item.map_err(storage_error)?;
+            if let (Some(day), Ok(count)) = (key.get(1..9), value.as_ref().try_into()) {
+                days.insert(u64::from_be_bytes(day.try_into().unwrap()), u64::from_be_bytes(count));
+            }
+        }
+        let notes_per_day = (first_day..=last_day)
+            .map(|day| DailyNoteCount {
+                day_start: day.saturating_mul(DAY_MILLIS),
+                notes: days.get(&day).copied().unwrap_or(0),
+            })
+            .collect();
+
+        Ok(NetworkStats {
+            issuers: self.get_stat(network_stats::ISSUERS_KEY)?,
+            recipients: self.get_stat(network_stats::RECIPIENTS_KEY)?,
+            notes: self.get_stat(network_stats::NOTES_KEY)?,
+            total_outstanding_debt: self.get_stat(network_stats::OUTSTANDING_KEY)?,
+            top_issuers: top,
+            notes_per_day,
+        })
+    }
+
+    /// Retrieve a page of the version history of a note, oldest first
+    ///
+    /// Returns the requested versions together with the total number of
+    /// recorded versions for the issuer-recipient pair.
+    pub fn get_note_history(
+        &self,
+        issuer_pubkey: &PubKey,
+        recipient_pubkey: &PubKey,
+        offset: usize,
+        limit: usize,
+    ) -> Result<(Vec<IouNote>, usize), NoteError> {
+        let key = NoteKey::from_keys(issuer_pubkey, recipient_pubkey);
+        let mut notes = Vec::new();
+        let mut total = 0;
+
+        for item in self.history_partition.prefix(key.to_bytes()) {
+            let (_key_bytes, value_bytes) = item.map_err(|e| {
+                NoteError::StorageError(format!("Failed to iterate note history: {}", e))
+            })?;
+
+            if total >= offset && notes.len() < limit {
+                match decode_note_record(&unseal(&self.cipher, &value_bytes)?) {
+                    Some((_stored_issuer_pubkey, note)) => notes.push(note),
+                    None => {
+                        return Err(NoteError::StorageError(
+                            "Invalid stored note format".to_string(),
+                        ))
+                    }
+                }
+            }
+            total += 1;
+        }
+
+        Ok((notes, total))
+    }
+
     /// Retrieve an IOU note by issuer and recipient public keys
     pub fn get_note(
         &self,
@@ -278,43 +1106,12 @@ impl NoteStorage {
         match self.notes_partition.get(&key_bytes) {
             Ok(Some(value_bytes)) => {
                 // Manual deserialization
-                if value_bytes.len() != 33 + 8 + 8 + 8 + 65 + 33 {
-                    return Err(NoteError::StorageError(
+                match decode_note_record(&unseal(&self.cipher, &value_bytes)?) {
+                    Some((_stored_issuer_pubkey, note)) => Ok(Some(note)),
+                    None => Err(NoteError::StorageError(
                         "Invalid stored note format".to_string(),
-                    ));
+                    )),
                 }
-
-                let mut offset = 0;
-                let _stored_issuer_pubkey: PubKey =
-                    value_bytes[offset..offset + 33].try_into().unwrap();
-                offset += 33;
-
-                let amount_collected =
-                    u64::from_be_bytes(value_bytes[offset..offset + 8].try_into().unwrap());
-                offset += 8;
-
-                let amount_redeemed =
-                    u64::from_be_bytes(value_bytes[offset..offset + 8].try_into().unwrap());
-                offset += 8;
-
-                let timestamp =
-                    u64::from_be_bytes(value_bytes[offset..offset + 8].try_into().unwrap());
-                offset += 8;
-
-                let signature: [u8; 65] = value_bytes[offset..offset + 65].try_into().unwrap();
-                offset += 65;
-
-                let recipient_pubkey: PubKey = value_bytes[offset..offset + 33].try_into().unwrap();
-
-                let note = IouNote {
-                    recipient_pubkey,
-                    amount_collected,
-                    amount_redeemed,
-                    timestamp,
-                    signature,
-                };
-
-                Ok(Some(note))
             }
             Ok(None) => Ok(None),
             Err(e) => Err(NoteError::StorageError(format!(
@@ -331,22 +1128,10 @@ impl NoteStorage {
             let key_bytes = key.to_bytes();
             match self.notes_partition.get(&key_bytes) {
                 Ok(Some(value_bytes)) => {
-                    if value_bytes.len() != 33 + 8 + 8 + 8 + 65 + 33 {
-                        continue; // Skip invalid entries
+                    // Skip invalid entries
+                    if let Some((_, note)) = decode_note_record(&unseal(&self.cipher, &value_bytes)?) {
+                        notes.push(note);
                     }
-                    let amount_collected = u64::from_be_bytes(value_bytes[33..41].try_into().unwrap());
-                    let amount_redeemed = u64::from_be_bytes(value_bytes[41..49].try_into().unwrap());
-                    let timestamp = u64::from_be_bytes(value_bytes[49..57].try_into().unwrap());
-                    let signature: [u8; 65] = value_bytes[57..122].try_into().unwrap();
-                    let recipient_pubkey: PubKey = value_bytes[122..155].try_into().unwrap();
-
-                    notes.push(IouNote {
-                        recipient_pubkey,
-                        amount_collected,
-                        amount_redeemed,
-                        timestamp,
-                        signature,
-                    });
                 }
                 Ok(None) => {}
                 Err(_) => {}
@@ -362,23 +1147,10 @@ impl NoteStorage {
             let key_bytes = key.to_bytes();
             match self.notes_partition.get(&key_bytes) {
                 Ok(Some(value_bytes)) => {
-                    if value_bytes.len() != 33 + 8 + 8 + 8 + 65 + 33 {
-                        continue; // Skip invalid entries
+                    // Skip invalid entries
+                    if let Some(entry) = decode_note_record(&unseal(&self.cipher, &value_bytes)?) {
+                        notes.push(entry);
                     }
-                    let issuer_pubkey: PubKey = value_bytes[0..33].try_into().unwrap();
-                    let amount_collected = u64::from_be_bytes(value_bytes[33..41].try_into().unwrap());
-                    let amount_redeemed = u64::from_be_bytes(value_bytes[41..49].try_into().unwrap());
-                    let timestamp = u64::from_be_bytes(value_bytes[49..57].try_into().unwrap());
-                    let signature: [u8; 65] = value_bytes[57..122].try_into().unwrap();
-                    let recipient_pubkey: PubKey = value_bytes[122..155].try_into().unwrap();
-
-                    notes.push((issuer_pubkey, IouNote {
-                        recipient_pubkey,
-                        amount_collected,
-                        amount_redeemed,
-                        timestamp,
-                        signature,
-                    }));
                 }
                 Ok(None) => {}
                 Err(_) => {}
@@ -465,6 +1237,7 @@ impl NoteStorage {
     pub fn rebuild_indices(&self) -> Result<usize, NoteError> {
         tracing::info!("Rebuilding note indices from existing data...");
         let mut count = 0;
+        let mut debts: HashMap<PubKey, u64> = HashMap::new();
 
         for item in self.notes_partition.iter() {
             let (key_bytes, value_bytes) = item.map_err(|e| {
@@ -472,12 +1245,11 @@ impl NoteStorage {
             })?;
 
             // Manual deserialization to extract issuer and recipient
-            if value_bytes.len() != 33 + 8 + 8 + 8 + 65 + 33 {
-                continue; // Skip invalid entries
-            }
-
-            let issuer_pubkey: PubKey = value_bytes[0..33].try_into().unwrap();
-            let recipient_pubkey: PubKey = value_bytes[122..155].try_into().unwrap();
+            let (issuer_pubkey, note) = match decode_note_record(&unseal(&self.cipher, &value_bytes)?) {
+                Some(entry) => entry,
+                None => continue, // Skip invalid entries
+            };
+            let recipient_pubkey = note.recipient_pubkey;
 
             // Reconstruct the note key from the stored key bytes
             let note_key = if key_bytes.len() == 32 {
@@ -490,81 +1262,384 @@ impl NoteStorage {
             // Rebuild indices
             Self::add_to_index(&self.issuer_index, &issuer_pubkey, &note_key)?;
             Self::add_to_index(&self.recipient_index, &recipient_pubkey, &note_key)?;
+            self.add_to_ordered_indexes(&note_key, &note)?;
+
+            // Notes stored before the history log existed start with their current version
+            let history_key = Self::history_key(&note_key, note.timestamp);
+            let has_history = self.history_partition.contains_key(&history_key).map_err(|e| {
+                NoteError::StorageError(format!("Failed to read note history: {}", e))
+            })?;
+            if !has_history {
+                self.append_history(&issuer_pubkey, &note)?;
+            }
+            let debt = debts.entry(issuer_pubkey).or_default();
+            *debt = debt.saturating_add(note.outstanding_debt());
             count += 1;
         }
+        self.rebuild_stats(count, &debts)?;
 
         tracing::info!("Index rebuild complete: {} notes indexed", count);
         Ok(count)
     }
 
-    /// Get all notes in the database
-    pub fn get_all_notes(&self) -> Result<Vec<IouNote>, NoteError> {
+    /// Find notes matching a query, in the requested order
+    ///
+    /// A query naming an issuer or recipient reads that party's notes through
+    /// the issuer or recipient index and sorts them, as does a query naming a
+    /// memo through the memo index. Otherwise the amount or timestamp index of
+    /// the sort field is scanned over the filtered range, so notes outside the
+    /// range are never read.
+    pub fn search_notes(&self, query: &NoteQuery) -> Result<NoteSearchPage, NoteError> {
         let mut notes = Vec::new();
+        let mut total = 0;
+        let mut collect = |issuer_pubkey: PubKey, note: IouNote| {
+            if total >= query.offset && notes.len() < query.limit {
+                notes.push((issuer_pubkey, note));
+            }
+            total += 1;
+        };
 
-        for item in self.notes_partition.iter() {
-            let (_key_bytes, value_bytes) = item.map_err(|e| {
-                NoteError::StorageError(format!("Failed to iterate partition: {}", e))
-            })?;
-
-            // Manual deserialization
-            if value_bytes.len() != 33 + 8 + 8 + 8 + 65 + 33 {
-                continue; // Skip invalid entries
+        if query.issuer.is_some() || query.recipient.is_some() || query.memo.is_some() {
+            let keys = match (&query.issuer, &query.recipient, &query.memo) {
+                (Some(issuer), Some(recipient), _) => vec![NoteKey::from_keys(issuer, recipient)],
+                (Some(issuer), None, _) => self.index_keys(&self.issuer_index, issuer)?,
+                (_, Some(recipient), _) => self.index_keys(&self.recipient_index, recipient)?,
+                (None, None, Some(memo)) => self.memo_keys(memo)?,
+                (None, None, None) => unreachable!(),
+            };
+            let mut matching: Vec<(PubKey, IouNote)> = self
+                .get_notes_by_keys_with_issuer(&keys)?
+                .into_iter()
+                .filter(|(issuer_pubkey, note)| query.matches(issuer_pubkey, note))
+                .collect();
+            // Same order as an index scan: sort value, then note key
+            matching.sort_by_cached_key(|(issuer_pubkey, note)| {
+                (
+                    query.sort_value(note),
+                    NoteKey::from_keys(issuer_pubkey, &note.recipient_pubkey).to_bytes(),
+                )
+            });
+            if query.descending {
+                matching.reverse();
+            }
+            for (issuer_pubkey, note) in matching {
+                collect(issuer_pubkey, note);
+            }
+        } else {
+            let (index, range) = match query.sort {
+                NoteSortField::Amount => (&self.amount_index, query.amount_range()),
+                NoteSortField::Timestamp => (&self.timestamp_index, query.timestamp_range()),
+            };
+            let lower = range.start().to_be_bytes().to_vec();
+            let mut upper = range.end().to_be_bytes().to_vec();
+            upper.extend_from_slice(&[0xff; 32]);
+
+            let scan = index.range(lower..=upper);
+            let entries: Box<dyn Iterator<Item = _>> =
+                if query.descending { Box::new(scan.rev()) } else { Box::new(scan) };
+            for item in entries {
+                let (index_key, _) = item.map_err(|e| {
+                    NoteError::StorageError(format!("Failed to iterate index: {}", e))
+                })?;
+                let (value, note_key) = match index_key.split_at_checked(8) {
+                    Some((value, note_key)) if note_key.len() == 32 => (
+                        u64::from_be_bytes(value.try_into().unwrap()),
+                        NoteKey::from_bytes(&note_key.try_into().unwrap()),
+                    ),
+                    _ => continue,
+                };
+                let Some((issuer_pubkey, note)) = self.get_note_record(&note_key)? else {
+                    continue;
+                };
+                // Skip entries left behind by a version that was since replaced
+                if query.sort_value(&note) == value && query.matches(&issuer_pubkey, &note) {
+                    collect(issuer_pubkey, note);
+                }
             }
+        }
 
-            let _stored_issuer_pubkey: PubKey = value_bytes[0..33].try_into().unwrap();
-            let amount_collected = u64::from_be_bytes(value_bytes[33..41].try_into().unwrap());
-            let amount_redeemed = u64::from_be_bytes(value_bytes[41..49].try_into().unwrap());
-            let timestamp = u64::from_be_bytes(value_bytes[49..57].try_into().unwrap());
-            let signature: [u8; 65] = value_bytes[57..122].try_into().unwrap();
-            let recipient_pubkey: PubKey = value_bytes[122..155].try_into().unwrap();
+        Ok(NoteSearchPage { notes, total })
+    }
 
-            let note = IouNote {
-                recipient_pubkey,
-                amount_collected,
-                amount_redeemed,
-                timestamp,
-                signature,
-            };
+    /// Get up to `limit` notes passing the filters of `query`, oldest first,
+    /// resuming after `after`
+    ///
+    /// Walks the timestamp index from the cursor on, so consecutive pages of
+    /// an export each cost only the entries they cover. The order, offset and
+    /// limit of `query` are ignored.
+    pub fn scan_notes_by_timestamp(
+        &self,
+        query: &NoteQuery,
+        after: Option<&NoteCursor>,
+        limit: usize,
+    ) -> Result<Vec<(PubKey, IouNote)>, NoteError> {
+        let range = query.timestamp_range();
+        let mut lower = range.start().to_be_bytes().to_vec();
+        let cursor = after.map(NoteCursor::index_key);
+        if let Some(cursor) = &cursor {
+            lower = lower.max(cursor.clone());
+        }
+        let mut upper = range.end().to_be_bytes().to_vec();
+        upper.extend_from_slice(&[0xff; 32]);
 
-            notes.push(note);
+        let mut notes = Vec::new();
+        for item in self.timestamp_index.range(lower..=upper) {
+            if notes.len() >= limit {
+                break;
+            }
+            let (index_key, _) = item.map_err(|e| {
+                NoteError::StorageError(format!("Failed to iterate index: {}", e))
+            })?;
+            if cursor.as_deref() == Some(index_key.as_ref()) {
+                continue;
+            }
+            let (timestamp, note_key) = match index_key.split_at_checked(8) {
+                Some((timestamp, note_key)) if note_key.len() == 32 => (
+                    u64::from_be_bytes(timestamp.try_into().unwrap()),
+                    NoteKey::from_bytes(&note_key.try_into().unwrap()),
+                ),
+                _ => continue,
+            };
+            let Some((issuer_pubkey, note)) = self.get_note_record(&note_key)? else {
+                continue;
+            };
+            // Skip entries left behind by a version that was since replaced
+            if note.timestamp == timestamp && query.matches(&issuer_pubkey, &note) {
+                notes.push((issuer_pubkey, note));
+            }
         }
 
         Ok(notes)
     }
 
+    /// Note keys listed under a public key in the issuer or recipient index
+    fn index_keys(&self, index: &fjall::Partition, pubkey: &PubKey) -> Result<Vec<NoteKey>, NoteError> {
+        match index.get(pubkey) {
+            Ok(Some(bytes)) => Self::deserialize_note_keys(&bytes),
+            Ok(None) => Ok(Vec::new()),
+            Err(e) => Err(NoteError::StorageError(format!("Failed to read index: {}", e))),
+        }
+    }
+
+    /// Iterate over every note with its issuer, in note key order
+    ///
+    /// Records are read and decoded as the iterator advances, so a caller
+    /// visiting every note never holds more than one in memory.
+    pub fn iter_notes_with_issuer(&self) -> impl Iterator<Item = Result<(PubKey, IouNote), NoteError>> + '_ {
+        self.notes_partition.iter().filter_map(|item| match item {
+            Ok((_key_bytes, value_bytes)) => match unseal(&self.cipher, &value_bytes) {
+                Ok(value_bytes) => decode_note_record(&value_bytes).map(Ok),
+                Err(e) => Some(Err(e)),
+            },
+            Err(e) => Some(Err(NoteError::StorageError(format!(
+                "Failed to iterate partition: {}",
+                e
+            )))),
+        })
+    }
+
+    /// Get all notes in the database
+    pub fn get_all_notes(&self) -> Result<Vec<IouNote>, NoteError> {
+        self.iter_notes_with_issuer()
+            .map(|entry| entry.map(|(_issuer_pubkey, note)| note))
+            .collect()
+    }
+
     /// Get all notes with issuer information
     pub fn get_all_notes_with_issuer(&self) -> Result<Vec<(PubKey, IouNote)>, NoteError> {
-        let mut notes_with_issuer = Vec::new();
+        self.iter_notes_with_issuer().collect()
+    }
 
-        for item in self.notes_partition.iter() {
+    /// ID the next filed dispute gets
+    pub fn next_dispute_id(&self) -> Result<u64, NoteError> {
+        match self.disputes_partition.last_key_value() {
+            Ok(Some((key, _))) => {
+                let id: [u8; 8] = key
+                    .as_ref()
+                    .try_into()
+                    .map_err(|_| NoteError::StorageError("Malformed dispute key".to_string()))?;
+                Ok(u64::from_be_bytes(id) + 1)
+            }
+            Ok(None) => Ok(1),
+            Err(e) => Err(NoteError::StorageError(format!("Failed to read disputes: {}", e))),
+        }
+    }
+
+    /// Store a dispute, replacing any dispute with the same ID
+    pub fn store_dispute(&self, dispute: &Dispute) -> Result<(), NoteError> {
+        let value = serde_json::to_vec(dispute)
+            .map_err(|e| NoteError::StorageError(format!("Failed to serialize dispute: {}", e)))?;
+
+        self.disputes_partition
+            .insert(dispute.id.to_be_bytes(), self.cipher.seal(&value))
+            .map_err(|e| NoteError::StorageError(format!("Failed to store dispute: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Retrieve a dispute by ID
+    pub fn get_dispute(&self, id: u64) -> Result<Option<Dispute>, NoteError> {
+        match self.disputes_partition.get(id.to_be_bytes()) {
+            Ok(Some(value_bytes)) => serde_json::from_slice(&unseal(&self.cipher, &value_bytes)?)
+                .map(Some)
+                .map_err(|e| NoteError::StorageError(format!("Failed to deserialize dispute: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(NoteError::StorageError(format!("Failed to get dispute: {}", e))),
+        }
+    }
+
+    /// Get every dispute, ordered by ID
+    pub fn get_disputes(&self) -> Result<Vec<Dispute>, NoteError> {
+        let mut disputes = Vec::new();
+
+        for item in self.disputes_partition.iter() {
             let (_key_bytes, value_bytes) = item.map_err(|e| {
                 NoteError::StorageError(format!("Failed to iterate partition: {}", e))
             })?;
 
-            // Manual deserialization
-            if value_bytes.len() != 33 + 8 + 8 + 8 + 65 + 33 {
-                continue; // Skip invalid entries
-            }
+            let dispute: Dispute = serde_json::from_slice(&unseal(&self.cipher, &value_bytes)?).map_err(|e| {
+                NoteError::StorageError(format!("Failed to deserialize dispute: {}", e))
+            })?;
 
-            let issuer_pubkey: PubKey = value_bytes[0..33].try_into().unwrap();
-            let amount_collected = u64::from_be_bytes(value_bytes[33..41].try_into().unwrap());
-            let amount_redeemed = u64::from_be_bytes(value_bytes[41..49].try_into().unwrap());
-            let timestamp = u64::from_be_bytes(value_bytes[49..57].try_into().unwrap());
-            let signature: [u8; 65] = value_bytes[57..122].try_into().unwrap();
-            let recipient_pubkey: PubKey = value_bytes[122..155].try_into().unwrap();
+            disputes.push(dispute);
+        }
 
-            let note = IouNote {
-                recipient_pubkey,
-                amount_collected,
-                amount_redeemed,
-                timestamp,
-                signature,
-            };
+        Ok(disputes)
+    }
+
+    /// Store a split note under issuer || split ID
+    pub fn store_split_note(&self, issuer_pubkey: &PubKey, split: &SplitNote) -> Result<(), NoteError> {
+        self.split_notes_partition
+            .insert(
+                split_note_key(issuer_pubkey, &split.id(issuer_pubkey)),
+                self.cipher.seal(&encode_split_record(split)),
+            )
+            .map_err(|e| NoteError::StorageError(format!("Failed to store split note: {}", e)))?;
 
-            notes_with_issuer.push((issuer_pubkey, note));
+        Ok(())
+    }
+
+    /// Retrieve a split note by issuer and split ID
+    pub fn get_split_note(&self, issuer_pubkey: &PubKey, split_id: &[u8; 32]) -> Result<Option<SplitNote>, NoteError> {
+        match self.split_notes_partition.get(split_note_key(issuer_pubkey, split_id)) {
+            Ok(Some(value_bytes)) => decode_split_record(&unseal(&self.cipher, &value_bytes)?)
+                .map(Some)
+                .ok_or_else(|| NoteError::StorageError("Malformed split note record".to_string())),
+            Ok(None) => Ok(None),
+            Err(e) => Err(NoteError::StorageError(format!("Failed to get split note: {}", e))),
+        }
+    }
+
+    /// Get every split note of an issuer, ordered by split ID
+    pub fn get_issuer_split_notes(&self, issuer_pubkey: &PubKey) -> Result<Vec<SplitNote>, NoteError> {
+        let mut splits = Vec::new();
+
+        for item in self.split_notes_partition.prefix(issuer_pubkey) {
+            let (_key_bytes, value_bytes) = item.map_err(|e| {
+                NoteError::StorageError(format!("Failed to iterate partition: {}", e))
+            })?;
+            splits.push(
+                decode_split_record(&unseal(&self.cipher, &value_bytes)?)
+                    .ok_or_else(|| NoteError::StorageError("Malformed split note record".to_string()))?,
+            );
+        }
+
+        Ok(splits)
+    }
+
+    /// Get every split note with its issuer
+    pub fn get_all_split_notes_with_issuer(&self) -> Result<Vec<(PubKey, SplitNote)>, NoteError> {
+        let mut splits = Vec::new();
+
+        for item in self.split_notes_partition.iter() {
+            let (key_bytes, value_bytes) = item.map_err(|e| {
+                NoteError::StorageError(format!("Failed to iterate partition: {}", e))
+            })?;
+            let issuer_pubkey: PubKey = key_bytes[..33]
+                .try_into()
+                .map_err(|_| NoteError::StorageError("Malformed split note key".to_string()))?;
+            let split = decode_split_record(&unseal(&self.cipher, &value_bytes)?)
+                .ok_or_else(|| NoteError::StorageError("Malformed split note record".to_string()))?;
+            splits.push((issuer_pubkey, split));
         }
 
-        Ok(notes_with_issuer)
+        Ok(splits)
+    }
+
+    /// Store the encrypted payload of a note, replacing any earlier one
+    pub fn store_note_payload(
+        &self,
+        issuer_pubkey: &PubKey,
+        recipient_pubkey: &PubKey,
+        timestamp: u64,
+        envelope: &[u8],
+    ) -> Result<(), NoteError> {
+        let mut value = Vec::with_capacity(8 + envelope.len());
+        value.extend_from_slice(&timestamp.to_be_bytes());
+        value.extend_from_slice(envelope);
+        self.note_payloads_partition
+            .insert(NoteKey::from_keys(issuer_pubkey, recipient_pubkey).to_bytes(), self.cipher.seal(&value))
+            .map_err(|e| NoteError::StorageError(format!("Failed to store note payload: {}", e)))
+    }
+
+    /// Retrieve the encrypted payload of a note with the note timestamp it was written for
+    pub fn get_note_payload(
+        &self,
+        issuer_pubkey: &PubKey,
+        recipient_pubkey: &PubKey,
+    ) -> Result<Option<(u64, Vec<u8>)>, NoteError> {
+        let key = NoteKey::from_keys(issuer_pubkey, recipient_pubkey).to_bytes();
+        let value_bytes = match self.note_payloads_partition.get(key) {
+            Ok(Some(value_bytes)) => unseal(&self.cipher, &value_bytes)?.into_owned(),
+            Ok(None) => return Ok(None),
+            Err(e) => return Err(NoteError::StorageError(format!("Failed to get note payload: {}", e))),
+        };
+        if value_bytes.len() < 8 {
+            return Err(NoteError::StorageError("Malformed note payload record".to_string()));
+        }
+        let timestamp = u64::from_be_bytes(value_bytes[..8].try_into().unwrap());
+        Ok(Some((timestamp, value_bytes[8..].to_vec())))
+    }
+
+    /// Store a key rotation under its old key
+    pub fn store_key_rotation(&self, rotation: &KeyRotation) -> Result<(), NoteError> {
+        self.key_rotations_partition
+            .insert(rotation.old_pubkey, self.cipher.seal(&encode_rotation_record(rotation)))
+            .map_err(|e| NoteError::StorageError(format!("Failed to store key rotation: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Retrieve the rotation away from a key, if it has been rotated
+    pub fn get_key_rotation(&self, old_pubkey: &PubKey) -> Result<Option<KeyRotation>, NoteError> {
+        match self.key_rotations_partition.get(old_pubkey) {
+            Ok(Some(value_bytes)) => decode_rotation_record(old_pubkey, &unseal(&self.cipher, &value_bytes)?)
+                .map(Some)
+                .ok_or_else(|| NoteError::StorageError("Malformed key rotation record".to_string())),
+            Ok(None) => Ok(None),
+            Err(e) => Err(NoteError::StorageError(format!("Failed to get key rotation: {}", e))),
+        }
+    }
+
+    /// Get every key rotation, ordered by old key
+    pub fn get_key_rotations(&self) -> Result<Vec<KeyRotation>, NoteError> {
+        let mut rotations = Vec::new();
+
+        for item in self.key_rotations_partition.iter() {
+            let (key_bytes, value_bytes) = item.map_err(|e| {
+                NoteError::StorageError(format!("Failed to iterate partition: {}", e))
+            })?;
+            let old_pubkey: PubKey = key_bytes
+                .as_ref()
+                .try_into()
+                .map_err(|_| NoteError::StorageError("Malformed key rotation key".to_string()))?;
+            rotations.push(
+                decode_rotation_record(&old_pubkey, &unseal(&self.cipher, &value_bytes)?)
+                    .ok_or_else(|| NoteError::StorageError("Malformed key rotation record".to_string()))?,
+            );
+        }
+
+        Ok(rotations)
     }
 
     /// Delete a note and update indices
@@ -572,6 +1647,11 @@ impl NoteStorage {
         let key = NoteKey::from_keys(issuer_pubkey, recipient_pubkey);
         let key_bytes = key.to_bytes();
 
+        let note = self.get_note_record(&key)?.map(|(_, note)| note);
+        if let Some(note) = &note {
+            self.remove_from_ordered_indexes(&key, note)?;
+        }
+
         // Remove from main storage
         self.notes_partition
             .remove(&key_bytes)
@@ -581,13 +1661,30 @@ impl NoteStorage {
         Self::remove_from_index(&self.issuer_index, issuer_pubkey, &key)?;
         Self::remove_from_index(&self.recipient_index, recipient_pubkey, &key)?;
 
+        // Recorded versions stay in the history log, and so in the daily counts
+        if let Some(note) = &note {
+            self.add_to_stat(network_stats::NOTES_KEY, -1)?;
+            if !self.has_index_entry(&self.issuer_index, issuer_pubkey)? {
+                self.add_to_stat(network_stats::ISSUERS_KEY, -1)?;
+            }
+            if !self.has_index_entry(&self.recipient_index, recipient_pubkey)? {
+                self.add_to_stat(network_stats::RECIPIENTS_KEY, -1)?;
+            }
+            self.adjust_issuer_debt(issuer_pubkey, note.outstanding_debt(), 0)?;
+        }
+
         Ok(())
     }
 }
 
 impl ReserveStorage {
-    /// Open or create a new reserve storage database
+    /// Open or create a new reserve storage database, keeping values in plaintext
     pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, NoteError> {
+        Self::open_with_cipher(path, StorageCipher::default())
+    }
+
+    /// Open or create a reserve storage database sealing values with `cipher`
+    pub fn open_with_cipher<P: AsRef<Path>>(path: P, cipher: StorageCipher) -> Result<Self, NoteError> {
         let keyspace = Config::new(path)
             .open()
             .map_err(|e| NoteError::StorageError(format!("Failed to open database: {}", e)))?;
@@ -596,7 +1693,56 @@ impl ReserveStorage {
             .open_partition("reserves", PartitionCreateOptions::default())
             .map_err(|e| NoteError::StorageError(format!("Failed to open partition: {}", e)))?;
 
-        Ok(Self { partition })
+        let history_partition = keyspace
+            .open_partition("reserve_history", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open history partition: {}", e)))?;
+
+        let predecessor_index = keyspace
+            .open_partition("reserve_predecessors", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open predecessor index: {}", e)))?;
+
+        let event_queue = keyspace
+            .open_partition("reserve_events", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open reserve event queue: {}", e)))?;
+
+        Ok(Self {
+            keyspace,
+            partition,
+            history_partition,
+            predecessor_index,
+            event_queue,
+            cipher,
+        })
+    }
+
+    /// Handle for maintaining the database
+    pub fn maintenance_handle(&self) -> StorageHandle {
+        StorageHandle::new(
+            "reserves",
+            &self.keyspace,
+            &[
+                ("reserves", &self.partition),
+                ("reserve_history", &self.history_partition),
+                ("reserve_predecessors", &self.predecessor_index),
+                ("reserve_events", &self.event_queue),
+            ],
+        )
+    }
+
+    /// Seal every record not sealed with the current storage key, returning
+    /// how many values were rewritten (see [`NoteStorage::reseal_all`])
+    pub fn reseal_all(&self) -> Result<usize, NoteError> {
+        if !self.cipher.is_enabled() {
+            return Err(NoteError::StorageError("No storage key configured".to_string()));
+        }
+        let mut count = 0;
+        for partition in [&self.partition, &self.history_partition, &self.event_queue] {
+            count += reseal_partition(&self.cipher, partition)?;
+        }
+        self.keyspace
+            .persist(PersistMode::SyncAll)
+            .map_err(|e| NoteError::StorageError(format!("Failed to persist reserves: {}", e)))?;
+        Ok(count)
     }
 
     /// Store a reserve in the database
@@ -606,7 +1752,7 @@ impl ReserveStorage {
             .map_err(|e| NoteError::StorageError(format!("Failed to serialize reserve: {}", e)))?;
 
         self.partition
-            .insert(key, &value)
+            .insert(key, self.cipher.seal(&value))
             .map_err(|e| NoteError::StorageError(format!("Failed to store reserve: {}", e)))?;
 
         Ok(())
@@ -617,7 +1763,7 @@ impl ReserveStorage {
         match self.partition.get(box_id.as_bytes()) {
             Ok(Some(value_bytes)) => {
                 let reserve: ExtendedReserveInfo =
-                    serde_json::from_slice(&value_bytes).map_err(|e| {
+                    serde_json::from_slice(&unseal(&self.cipher, &value_bytes)?).map_err(|e| {
                         NoteError::StorageError(format!("Failed to deserialize reserve: {}", e))
                     })?;
                 Ok(Some(reserve))
@@ -640,7 +1786,7 @@ impl ReserveStorage {
             })?;
 
             let reserve: ExtendedReserveInfo =
-                serde_json::from_slice(&value_bytes).map_err(|e| {
+                serde_json::from_slice(&unseal(&self.cipher, &value_bytes)?).map_err(|e| {
                     NoteError::StorageError(format!("Failed to deserialize reserve: {}", e))
                 })?;
 
@@ -658,6 +1804,232 @@ impl ReserveStorage {
 
         Ok(())
     }
+
+    /// Record how a reserve box was spent and link its successor
+    pub fn record_transition(&self, transition: &ReserveTransition) -> Result<(), NoteError> {
+        let value = serde_json::to_vec(transition)
+            .map_err(|e| NoteError::StorageError(format!("Failed to serialize transition: {}", e)))?;
+
+        self.history_partition
+            .insert(transition.box_id.as_bytes(), self.cipher.seal(&value))
+            .map_err(|e| NoteError::StorageError(format!("Failed to store transition: {}", e)))?;
+
+        if let Some(successor) = &transition.successor_box_id {
+            self.predecessor_index
+                .insert(successor.as_bytes(), transition.box_id.as_bytes())
+                .map_err(|e| NoteError::StorageError(format!("Failed to store predecessor link: {}", e)))?;
+        }
+
+        Ok(())
+    }
+
+    /// Retrieve the recorded spend of a reserve box
+    pub fn get_transition(&self, box_id: &str) -> Result<Option<ReserveTransition>, NoteError> {
+        match self.history_partition.get(box_id.as_bytes()) {
+            Ok(Some(value_bytes)) => serde_json::from_slice(&unseal(&self.cipher, &value_bytes)?)
+                .map(Some)
+                .map_err(|e| NoteError::StorageError(format!("Failed to deserialize transition: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(NoteError::StorageError(format!(
+                "Failed to get transition: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Retrieve the box that was spent to create the given reserve box
+    pub fn get_predecessor(&self, box_id: &str) -> Result<Option<String>, NoteError> {
+        match self.predecessor_index.get(box_id.as_bytes()) {
+            Ok(Some(value_bytes)) => Ok(Some(String::from_utf8_lossy(&value_bytes).to_string())),
+            Ok(None) => Ok(None),
+            Err(e) => Err(NoteError::StorageError(format!(
+                "Failed to get predecessor: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Event queue key: height (8 bytes BE) || sequence (4 bytes BE), so that
+    /// iteration returns events in the order they were queued
+    fn reserve_event_key(height: u64, sequence: u32) -> Vec<u8> {
+        let mut key = Vec::with_capacity(12);
+        key.extend_from_slice(&height.to_be_bytes());
+        key.extend_from_slice(&sequence.to_be_bytes());
+        key
+    }
+
+    fn decode_queued_event(&self, value: &[u8]) -> Result<QueuedReserveEvent, NoteError> {
+        serde_json::from_slice(&unseal(&self.cipher, value)?)
+            .map_err(|e| NoteError::StorageError(format!("Failed to deserialize reserve event: {}", e)))
+    }
+
+    /// Queue reserve events derived by a scan
+    ///
+    /// The events are written in one batch and synced to disk before they are
+    /// returned, so a crash either loses all of the scan's events or none.
+    pub fn enqueue_reserve_events(&self, events: Vec<ReserveEvent>) -> Result<Vec<QueuedReserveEvent>, NoteError> {
+        // Scans repeat until a new block arrives, so a block may already have events
+        let mut next_sequences = std::collections::HashMap::new();
+        for event in &events {
+            let height = event.height();
+            if next_sequences.contains_key(&height) {
+                continue;
+            }
+            let next_sequence = match self.event_queue.prefix(height.to_be_bytes()).next_back() {
+                Some(item) => {
+                    let (key, _) = item
+                        .map_err(|e| NoteError::StorageError(format!("Failed to read reserve event queue: {}", e)))?;
+                    let sequence: [u8; 4] = key[8..12]
+                        .try_into()
+                        .map_err(|_| NoteError::StorageError("Malformed reserve event key".to_string()))?;
+                    u32::from_be_bytes(sequence) + 1
+                }
+                None => 0,
+            };
+            next_sequences.insert(height, next_sequence);
+        }
+
+        let mut batch = self.keyspace.batch();
+        let mut queued = Vec::with_capacity(events.len());
+        for event in events {
+            let height = event.height();
+            let next_sequence = next_sequences.entry(height).or_insert(0);
+            let sequence = *next_sequence;
+            *next_sequence += 1;
+            let entry = QueuedReserveEvent {
+                height,
+                sequence,
+                event,
+                processed: false,
+            };
+            let value = serde_json::to_vec(&entry)
+                .map_err(|e| NoteError::StorageError(format!("Failed to serialize reserve event: {}", e)))?;
+            batch.insert(&self.event_queue, Self::reserve_event_key(height, sequence), self.cipher.seal(&value));
+            queued.push(entry);
+        }
+        batch
+            .commit()
+            .map_err(|e| NoteError::StorageError(format!("Failed to queue reserve events: {}", e)))?;
+        self.keyspace
+            .persist(PersistMode::SyncAll)
+            .map_err(|e| NoteError::StorageError(format!("Failed to persist reserve events: {}", e)))?;
+
+        Ok(queued)
+    }
+
+    /// Whether a queued reserve event still awaits processing
+    pub fn is_reserve_event_pending(&self, height: u64, sequence: u32) -> Result<bool, NoteError> {
+        match self.event_queue.get(Self::reserve_event_key(height, sequence)) {
+            Ok(Some(value)) => Ok(!self.decode_queued_event(&value)?.processed),
+            Ok(None) => Ok(false),
+            Err(e) => Err(NoteError::StorageError(format!("Failed to get reserve event: {}", e))),
+        }
+    }
+
+    /// Mark a queued reserve event as processed
+    ///
+    /// Once every event queued for its block is processed, the block's
+    /// events are dropped from the queue.
+    pub fn mark_reserve_event_processed(&self, height: u64, sequence: u32) -> Result<(), NoteError> {
+        let key = Self::reserve_event_key(height, sequence);
+        let mut entry = match self.event_queue.get(&key) {
+            Ok(Some(value)) => self.decode_queued_event(&value)?,
+            Ok(None) => return Ok(()),
+            Err(e) => return Err(NoteError::StorageError(format!("Failed to get reserve event: {}", e))),
+        };
+        entry.processed = true;
+        let value = serde_json::to_vec(&entry)
+            .map_err(|e| NoteError::StorageError(format!("Failed to serialize reserve event: {}", e)))?;
+        self.event_queue
+            .insert(&key, self.cipher.seal(&value))
+            .map_err(|e| NoteError::StorageError(format!("Failed to mark reserve event processed: {}", e)))?;
+
+        let mut keys = Vec::new();
+        for item in self.event_queue.prefix(height.to_be_bytes()) {
+            let (key, value) = item
+                .map_err(|e| NoteError::StorageError(format!("Failed to read reserve event queue: {}", e)))?;
+            if !self.decode_queued_event(&value)?.processed {
+                return Ok(());
+            }
+            keys.push(key);
+        }
+        let mut batch = self.keyspace.batch();
+        for key in keys {
+            batch.remove(&self.event_queue, key);
+        }
+        batch
+            .commit()
+            .map_err(|e| NoteError::StorageError(format!("Failed to prune reserve events: {}", e)))
+    }
+
+    /// Queued reserve events not processed yet, in the order they were queued
+    pub fn unprocessed_reserve_events(&self) -> Result<Vec<QueuedReserveEvent>, NoteError> {
+        let mut events = Vec::new();
+        for item in self.event_queue.iter() {
+            let (_key, value) = item
+                .map_err(|e| NoteError::StorageError(format!("Failed to read reserve event queue: {}", e)))?;
+            let entry = self.decode_queued_event(&value)?;
+            if !entry.processed {
+                events.push(entry);
+            }
+        }
+        Ok(events)
+    }
+
+    /// Reconstruct the lineage of the reserve containing the given box
+    ///
+    /// Returns `None` if the box is neither a current reserve nor part of a
+    /// recorded transition.
+    pub fn get_lineage(&self, box_id: &str) -> Result<Option<ReserveLineage>, NoteError> {
+        let mut visited = std::collections::HashSet::new();
+
+        // Walk back to the oldest known box
+        let mut first = box_id.to_string();
+        visited.insert(first.clone());
+        while let Some(predecessor) = self.get_predecessor(&first)? {
+            if !visited.insert(predecessor.clone()) {
+                return Err(NoteError::StorageError(format!(
+                    "Cycle in reserve lineage at {}",
+                    predecessor
+                )));
+            }
+            first = predecessor;
+        }
+
+        // Walk forward through the recorded transitions
+        let mut box_ids = vec![first.clone()];
+        let mut transitions = Vec::new();
+        let mut current = Some(first);
+        while let Some(box_id) = current.take() {
+            let Some(transition) = self.get_transition(&box_id)? else {
+                current = Some(box_id);
+                break;
+            };
+            let successor = transition.successor_box_id.clone();
+            transitions.push(transition);
+            if let Some(successor) = successor {
+                if box_ids.contains(&successor) {
+                    return Err(NoteError::StorageError(format!(
+                        "Cycle in reserve lineage at {}",
+                        successor
+                    )));
+                }
+                box_ids.push(successor.clone());
+                current = Some(successor);
+            }
+        }
+
+        if transitions.is_empty() && self.get_reserve(box_id)?.is_none() {
+            return Ok(None);
+        }
+
+        // A closed reserve ends with a transition that has no successor
+        Ok(Some(ReserveLineage {
+            box_ids,
+            transitions,
+            current_box_id: current,
+        }))
+    }
 }
 
 impl TrackerStorage {
@@ -671,7 +2043,39 @@ impl TrackerStorage {
             .open_partition("tracker_metadata", PartitionCreateOptions::default())
             .map_err(|e| NoteError::StorageError(format!("Failed to open partition: {}", e)))?;
 
-        Ok(Self { partition })
+        let local_roots = keyspace
+            .open_partition("local_roots", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open local roots partition: {}", e)))?;
+
+        let commitment_checks = keyspace
+            .open_partition("commitment_checks", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open commitment checks partition: {}", e)))?;
+
+        let note_changes = keyspace
+            .open_partition("note_changes", PartitionCreateOptions::default())
+            .map_err(|e| NoteError::StorageError(format!("Failed to open note changes partition: {}", e)))?;
+
+        Ok(Self {
+            keyspace,
+            partition,
+            local_roots,
+            commitment_checks,
+            note_changes,
+        })
+    }
+
+    /// Handle for maintaining the database
+    pub fn maintenance_handle(&self) -> StorageHandle {
+        StorageHandle::new(
+            "tracker",
+            &self.keyspace,
+            &[
+                ("tracker_metadata", &self.partition),
+                ("local_roots", &self.local_roots),
+                ("commitment_checks", &self.commitment_checks),
+                ("note_changes", &self.note_changes),
+            ],
+        )
     }
 
     /// Store tracker box information in the database
@@ -749,4 +2153,347 @@ impl TrackerStorage {
 
         Ok(())
     }
+
+    /// Record a local AVL root that became current at the given chain height
+    ///
+    /// Roots are keyed by height and then by recording order, so several roots
+    /// produced within one block keep their order.
+    pub fn record_local_root(&self, height: u64, root_digest: &[u8; 33]) -> Result<(), NoteError> {
+        let key = self.next_local_root_key(height)?;
+        self.local_roots
+            .insert(key, root_digest)
+            .map_err(|e| NoteError::StorageError(format!("Failed to record local root: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Record the local root produced by a change of one note, with the note
+    /// in its changed state
+    pub fn record_note_change(
+        &self,
+        height: u64,
+        root_digest: &[u8; 33],
+        note: &basis_trees::DeltaNote,
+    ) -> Result<(), NoteError> {
+        let value = serde_json::to_vec(note)
+            .map_err(|e| NoteError::StorageError(format!("Failed to serialize note change: {}", e)))?;
+        // The note goes first, so a recorded root always has its change
+        let key = self.next_local_root_key(height)?;
+        self.note_changes
+            .insert(&key, &value)
+            .map_err(|e| NoteError::StorageError(format!("Failed to record note change: {}", e)))?;
+        self.local_roots
+            .insert(key, root_digest)
+            .map_err(|e| NoteError::StorageError(format!("Failed to record local root: {}", e)))?;
+        Ok(())
+    }
+
+    fn next_local_root_key(&self, height: u64) -> Result<Vec<u8>, NoteError> {
+        let sequence = match self.latest_local_root_entry()? {
+            Some((latest_height, sequence, _)) if latest_height == height => sequence + 1,
+            _ => 0,
+        };
+        let mut key = Vec::with_capacity(16);
+        key.extend_from_slice(&height.to_be_bytes());
+        key.extend_from_slice(&sequence.to_be_bytes());
+        Ok(key)
+    }
+
+    /// Notes changed on the way from local root `previous` to `digest`
+    ///
+    /// `digest` is taken as the last time the local state reached it at or
+    /// before `height`, and `previous` as the last time before that; without
+    /// `previous` every change up to `digest` is included. Each note appears
+    /// once, in its latest state, in the order it was first changed: the AVL
+    /// tree's shape depends on the order keys are inserted, so replaying the
+    /// notes in that order reproduces `digest`. `None` means one of the roots
+    /// was never reached locally in that range.
+    pub fn note_changes_between(
+        &self,
+        previous: Option<&[u8; 33]>,
+        digest: &[u8; 33],
+        height: u64,
+    ) -> Result<Option<Vec<basis_trees::DeltaNote>>, NoteError> {
+        let end_bound = height.saturating_add(1).to_be_bytes().to_vec();
+        let mut entries = Vec::new();
+        for item in self.local_roots.range(..end_bound) {
+            let (key, _) = item
+                .as_ref()
+                .map_err(|e| NoteError::StorageError(format!("Failed to read local root: {}", e)))?;
+            let key = key.to_vec();
+            entries.push((key, decode_local_root(item)?.2));
+        }
+
+        let Some(end) = entries.iter().rposition(|(_, root)| root == digest) else {
+            return Ok(None);
+        };
+        let start = match previous {
+            Some(previous) => match entries[..end].iter().rposition(|(_, root)| root == previous) {
+                Some(start) => start + 1,
+                None => return Ok(None),
+            },
+            None => 0,
+        };
+
+        let mut changes: Vec<basis_trees::DeltaNote> = Vec::new();
+        let mut positions = std::collections::HashMap::new();
+        for (key, _) in &entries[start..=end] {
+            let Some(value) = self
+                .note_changes
+                .get(key)
+                .map_err(|e| NoteError::StorageError(format!("Failed to get note change: {}", e)))?
+            else {
+                continue;
+            };
+            let note: basis_trees::DeltaNote = serde_json::from_slice(&value)
+                .map_err(|e| NoteError::StorageError(format!("Failed to deserialize note change: {}", e)))?;
+            let pair = (note.issuer_pubkey.clone(), note.recipient_pubkey.clone());
+            match positions.get(&pair) {
+                Some(&position) => changes[position] = note,
+                None => {
+                    positions.insert(pair, changes.len());
+                    changes.push(note);
+                }
+            }
+        }
+        Ok(Some(changes))
+    }
+
+    /// Local roots in effect at a chain height
+    ///
+    /// These are the last root recorded before the height and every root
+    /// recorded at it, oldest first.
+    pub fn local_roots_at(&self, height: u64) -> Result<Vec<[u8; 33]>, NoteError> {
+        let height_key = height.to_be_bytes();
+        let mut roots = Vec::new();
+        if let Some(item) = self.local_roots.range(..height_key.to_vec()).next_back() {
+            roots.push(decode_local_root(item)?.2);
+        }
+        for item in self.local_roots.prefix(height_key) {
+            roots.push(decode_local_root(item)?.2);
+        }
+
+        Ok(roots)
+    }
+
+    /// Every distinct local root recorded so far, oldest first
+    pub fn all_local_roots(&self) -> Result<Vec<[u8; 33]>, NoteError> {
+        let mut roots: Vec<[u8; 33]> = Vec::new();
+        for item in self.local_roots.iter() {
+            let (_, _, digest) = decode_local_root(item)?;
+            if !roots.contains(&digest) {
+                roots.push(digest);
+            }
+        }
+        Ok(roots)
+    }
+
+    /// Most recently recorded local root and the height it was recorded at
+    pub fn latest_local_root(&self) -> Result<Option<(u64, [u8; 33])>, NoteError> {
+        Ok(self
+            .latest_local_root_entry()?
+            .map(|(height, _, digest)| (height, digest)))
+    }
+
+    fn latest_local_root_entry(&self) -> Result<Option<(u64, u64, [u8; 33])>, NoteError> {
+        self.local_roots
+            .iter()
+            .next_back()
+            .map(decode_local_root)
+            .transpose()
+    }
+
+    /// Store the result of checking a tracker box
+    pub fn store_commitment_check(&self, check: &CommitmentCheck) -> Result<(), NoteError> {
+        let value = serde_json::to_vec(check)
+            .map_err(|e| NoteError::StorageError(format!("Failed to serialize commitment check: {}", e)))?;
+
+        self.commitment_checks
+            .insert(check.box_id.as_bytes(), &value)
+            .map_err(|e| NoteError::StorageError(format!("Failed to store commitment check: {}", e)))?;
+
+        Ok(())
+    }
+
+    /// Retrieve the check of a tracker box, if it was checked
+    pub fn get_commitment_check(&self, box_id: &str) -> Result<Option<CommitmentCheck>, NoteError> {
+        match self.commitment_checks.get(box_id.as_bytes()) {
+            Ok(Some(value_bytes)) => serde_json::from_slice(&value_bytes)
+                .map(Some)
+                .map_err(|e| NoteError::StorageError(format!("Failed to deserialize commitment check: {}", e))),
+            Ok(None) => Ok(None),
+            Err(e) => Err(NoteError::StorageError(format!(
+                "Failed to get commitment check: {}",
+                e
+            ))),
+        }
+    }
+
+    /// Get every commitment check, ordered by commitment height
+    pub fn get_commitment_checks(&self) -> Result<Vec<CommitmentCheck>, NoteError> {
+        let mut checks = Vec::new();
+
+        for item in self.commitment_checks.iter() {
+            let (_key_bytes, value_bytes) = item.map_err(|e| {
+                NoteError::StorageError(format!("Failed to iterate partition: {}", e))
+            })?;
+
+            let check: CommitmentCheck = serde_json::from_slice(&value_bytes).map_err(|e| {
+                NoteError::StorageError(format!("Failed to deserialize commitment check: {}", e))
+            })?;
+
+            checks.push(check);
+        }
+
+        checks.sort_by_key(|check| check.height);
+        Ok(checks)
+    }
+}
+// Decode a `local_roots` entry into (height, sequence, digest)
+fn decode_local_root(
+    item: fjall::Result<(fjall::Slice, fjall::Slice)>,
+) -> Result<(u64, u64, [u8; 33]), NoteError> {
+    let (key, value) = item.map_err(|e| NoteError::StorageError(format!("Failed to read local root: {}", e)))?;
+    let height = key.get(..8).and_then(|bytes| bytes.try_into().ok()).map(u64::from_be_bytes);
+    let sequence = key.get(8..16).and_then(|bytes| bytes.try_into().ok()).map(u64::from_be_bytes);
+    let digest = value.as_ref().try_into().ok();
+    match (height, sequence, digest) {
+        (Some(height), Some(sequence), Some(digest)) => Ok((height, sequence, digest)),
+        _ => Err(NoteError::StorageError("Invalid local root entry".to_string())),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schnorr::generate_keypair;
+
+    #[test]
+    fn test_note_record_signature_scheme() {
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let note = IouNote::new(recipient_pubkey, 100, 0, 1, [0u8; 65])
+            .with_signing_format(SigningFormat::V2 { tracker_id: None })
+            .with_memo("INV-1")
+            .sign_with(&issuer_secret)
+            .unwrap();
+
+        // Schnorr records carry no scheme tag
+        let record = encode_note_record(&issuer_pubkey, &note);
+        assert_eq!(record[0], MEMO_RECORD_TAG);
+        assert_eq!(decode_note_record(&record), Some((issuer_pubkey, note.clone())));
+
+        let mut tagged = vec![SCHEME_RECORD_TAG, SignatureSchemeId::Schnorr.code()];
+        tagged.extend_from_slice(&record);
+        let (_, decoded) = decode_note_record(&tagged).unwrap();
+        assert_eq!(decoded, note);
+        decoded.verify_signature(&issuer_pubkey).unwrap();
+
+        // Records of schemes this build does not know are rejected
+        tagged[1] = 0xFF;
+        assert_eq!(decode_note_record(&tagged), None);
+    }
+
+    #[test]
+    fn test_restore_corrupt_note_from_history() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = NoteStorage::open_with_cipher(temp_dir.path(), StorageCipher::default()).unwrap();
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let first = IouNote::new(recipient_pubkey, 100, 0, 1, [0u8; 65]).sign_with(&issuer_secret).unwrap();
+        let second = IouNote::new(recipient_pubkey, 250, 0, 2, [0u8; 65]).sign_with(&issuer_secret).unwrap();
+        storage.store_note(&issuer_pubkey, &first).unwrap();
+        storage.store_note(&issuer_pubkey, &second).unwrap();
+        assert_eq!(storage.scan_note_records().unwrap(), (1, vec![]));
+        assert_eq!(storage.counted_notes().unwrap(), 1);
+
+        let key = NoteKey::from_keys(&issuer_pubkey, &recipient_pubkey);
+        storage.notes_partition.insert(key.to_bytes(), [0xFFu8; 7]).unwrap();
+        let (records, corrupt) = storage.scan_note_records().unwrap();
+        assert_eq!((records, corrupt.len()), (1, 1));
+        assert_eq!(storage.get_note(&issuer_pubkey, &recipient_pubkey).ok().flatten(), None);
+
+        // The version the AVL tree commits to wins over a later one
+        assert!(storage.restore_note_from_history(&key, Some(100)).unwrap());
+        assert_eq!(storage.get_note(&issuer_pubkey, &recipient_pubkey).unwrap(), Some(first));
+        assert!(storage.restore_note_from_history(&key, None).unwrap());
+        assert_eq!(storage.get_note(&issuer_pubkey, &recipient_pubkey).unwrap(), Some(second));
+
+        let (_, other_pubkey) = generate_keypair();
+        assert!(!storage
+            .restore_note_from_history(&NoteKey::from_keys(&issuer_pubkey, &other_pubkey), None)
+            .unwrap());
+    }
+
+    #[test]
+    fn test_encrypt_existing_database() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let note = IouNote::new(recipient_pubkey, 100, 0, 1, [0u8; 65])
+            .sign_with(&issuer_secret)
+            .unwrap();
+        {
+            let storage = NoteStorage::open_with_cipher(temp_dir.path(), StorageCipher::default()).unwrap();
+            storage.store_note(&issuer_pubkey, &note).unwrap();
+            storage.store_note_payload(&issuer_pubkey, &recipient_pubkey, 1, b"envelope").unwrap();
+        }
+
+        // Plaintext records read under a key until they are migrated
+        let old_key = StorageCipher::new([1u8; 32], &[]);
+        {
+            let storage = NoteStorage::open_with_cipher(temp_dir.path(), old_key.clone()).unwrap();
+            assert_eq!(storage.get_note(&issuer_pubkey, &recipient_pubkey).unwrap(), Some(note.clone()));
+            // The note, its history entry and its payload
+            assert_eq!(storage.reseal_all().unwrap(), 3);
+            assert_eq!(storage.reseal_all().unwrap(), 0);
+            let stored = storage.notes_partition.get(NoteKey::from_keys(&issuer_pubkey, &recipient_pubkey).to_bytes());
+            assert_eq!(stored.unwrap().unwrap()[0], crate::storage_encryption::SEALED_TAG);
+        }
+
+        // Sealed records are unreadable without their key
+        {
+            let storage = NoteStorage::open_with_cipher(temp_dir.path(), StorageCipher::default()).unwrap();
+            assert!(storage.get_note(&issuer_pubkey, &recipient_pubkey).is_err());
+            assert!(storage.reseal_all().is_err());
+        }
+
+        // Rotation: the previous key opens old records until they are resealed
+        let rotated = StorageCipher::new([2u8; 32], &[[1u8; 32]]);
+        {
+            let storage = NoteStorage::open_with_cipher(temp_dir.path(), rotated).unwrap();
+            assert_eq!(storage.get_all_notes().unwrap(), vec![note.clone()]);
+            assert_eq!(storage.reseal_all().unwrap(), 3);
+        }
+        let storage = NoteStorage::open_with_cipher(temp_dir.path(), StorageCipher::new([2u8; 32], &[])).unwrap();
+        assert_eq!(storage.get_note_history(&issuer_pubkey, &recipient_pubkey, 0, 10).unwrap(), (vec![note], 1));
+        assert_eq!(
+            storage.get_note_payload(&issuer_pubkey, &recipient_pubkey).unwrap(),
+            Some((1, b"envelope".to_vec()))
+        );
+    }
+
+    #[test]
+    fn test_cipher_is_per_storage() {
+        let (first_dir, second_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let note = IouNote::new(recipient_pubkey, 100, 0, 1, [0u8; 65])
+            .sign_with(&issuer_secret)
+            .unwrap();
+        let key = NoteKey::from_keys(&issuer_pubkey, &recipient_pubkey).to_bytes();
+
+        // Storages open side by side, each sealing with its own cipher only
+        let first_key = StorageCipher::new([1u8; 32], &[]);
+        let first = NoteStorage::open_with_cipher(first_dir.path(), first_key.clone()).unwrap();
+        let second = NoteStorage::open(second_dir.path()).unwrap();
+        first.store_note(&issuer_pubkey, &note).unwrap();
+        second.store_note(&issuer_pubkey, &note).unwrap();
+
+        let sealed = first.notes_partition.get(&key).unwrap().unwrap();
+        assert_eq!(hex::encode(&sealed[1..5]), first_key.key_ids()[0]);
+        assert_ne!(second.notes_partition.get(&key).unwrap().unwrap()[0], crate::storage_encryption::SEALED_TAG);
+        assert_eq!(second.get_note(&issuer_pubkey, &recipient_pubkey).unwrap(), Some(note));
+    }
 }
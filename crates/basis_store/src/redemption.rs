@@ -2,8 +2,12 @@
 
 use thiserror::Error;
 
-use crate::{IouNote, NoteError, PubKey, TrackerStateManager};
-use crate::transaction_builder::{RedemptionTransactionBuilder, TxContext};
+use crate::contract_registry::ContractRegistry;
+use crate::{IouNote, NoteError, PubKey, SigningFormat, TrackerStateManager};
+use crate::transaction_builder::{
+    RedemptionTransactionBuilder, RedemptionTransactionParams, RedemptionWitness, TxContext,
+};
+use crate::wallet_connector::Eip12Box;
 
 #[derive(Error, Debug)]
 pub enum RedemptionError {
@@ -23,6 +27,12 @@ pub enum RedemptionError {
     StorageError(String),
     #[error("Invalid public key: {0}")]
     InvalidPublicKey(String),
+    #[error("Note is frozen by open dispute {0}")]
+    NoteDisputed(u64),
+    #[error("Redemption of {1} does not cover accrued interest of {0}")]
+    AccruedInterestUnpaid(u64, u64),
+    #[error("Note is signed in format version {0}, which the reserve contract cannot verify")]
+    UnredeemableSigningFormat(u8),
 }
 
 impl From<NoteError> for RedemptionError {
@@ -72,6 +82,13 @@ pub struct RedemptionRequest {
     /// Optional: will be generated by server if not provided for normal redemption
     #[serde(default)]
     pub tracker_signature: Option<String>,
+    /// Reserve box being spent, as read from the Ergo node by the API layer
+    #[serde(default)]
+    pub reserve_box: Option<Eip12Box>,
+    /// AVL proof inserting the new redeemed amount into the reserve's tree (hex encoded),
+    /// supplied by the receiver, who tracks the reserve tree
+    #[serde(default)]
+    pub reserve_insert_proof: Option<String>,
 }
 
 /// Redemption proof and transaction data
@@ -93,15 +110,114 @@ pub struct RedemptionData {
     pub redemption_time: u64,
 }
 
+/// The note, its proof and the signatures an unsigned redemption transaction is built from
+#[derive(Debug, Clone, Copy)]
+pub struct UnsignedRedemptionParams<'a> {
+    pub note: &'a IouNote,
+    pub proof: &'a crate::NoteProof,
+    pub request: &'a RedemptionRequest,
+    /// Tracker box used as data input
+    pub tracker_box_id: &'a str,
+    /// Reserve owner's 65-byte Schnorr signature
+    pub issuer_signature: &'a [u8],
+    /// Tracker's 65-byte Schnorr signature
+    pub tracker_signature: &'a [u8],
+}
+
 /// Redemption manager for handling note redemptions
 pub struct RedemptionManager {
     pub tracker: TrackerStateManager,
+    /// Contract versions whose reserves can be spent
+    contracts: ContractRegistry,
 }
 
 impl RedemptionManager {
-    /// Create a new redemption manager
+    /// Create a new redemption manager spending reserves of the builtin contract
     pub fn new(tracker: TrackerStateManager) -> Self {
-        Self { tracker }
+        Self {
+            tracker,
+            contracts: ContractRegistry::builtin(),
+        }
+    }
+
+    /// Spend reserves of the registered contract versions instead of only the builtin one
+    pub fn set_contract_registry(&mut self, contracts: ContractRegistry) {
+        self.contracts = contracts;
+    }
+
+    /// Run every check `initiate_redemption` applies to the tracker state
+    /// without building a transaction
+    ///
+    /// Returns all the errors the redemption would fail with; an empty list
+    /// means the note can be redeemed for `amount`. The reserve and the time
+    /// lock are checked by the caller, which knows the chain state.
+    pub fn validate_redemption(
+        &self,
+        issuer_pubkey: &str,
+        recipient_pubkey: &str,
+        amount: u64,
+    ) -> Vec<RedemptionError> {
+        self.check_redemption(issuer_pubkey, recipient_pubkey, amount).1
+    }
+
+    /// The note to redeem, if found, along with every reason to reject the redemption
+    fn check_redemption(
+        &self,
+        issuer_pubkey: &str,
+        recipient_pubkey: &str,
+        amount: u64,
+    ) -> (Option<IouNote>, Vec<RedemptionError>) {
+        let mut errors = Vec::new();
+
+        // Parse public keys
+        let (issuer_pubkey, recipient_pubkey) = match (parse_pubkey(issuer_pubkey), parse_pubkey(recipient_pubkey)) {
+            (Ok(issuer_pubkey), Ok(recipient_pubkey)) => (issuer_pubkey, recipient_pubkey),
+            (issuer, recipient) => {
+                errors.extend(issuer.err());
+                errors.extend(recipient.err());
+                return (None, errors);
+            }
+        };
+
+        // Lookup the note
+        let note = match self.tracker.lookup_note(&issuer_pubkey, &recipient_pubkey) {
+            Ok(note) => note,
+            Err(_) => {
+                errors.push(RedemptionError::NoteNotFound);
+                return (None, errors);
+            }
+        };
+
+        // Verify note signature
+        if note.verify_signature(&issuer_pubkey).is_err() {
+            errors.push(RedemptionError::InvalidNoteSignature);
+        }
+
+        // The reserve contract only verifies the version 1 message
+        if note.signing_format != SigningFormat::V1 {
+            errors.push(RedemptionError::UnredeemableSigningFormat(note.signing_format.version()));
+        }
+
+        // A note under dispute cannot be redeemed until the dispute is closed
+        match self.tracker.open_dispute(&issuer_pubkey, &recipient_pubkey) {
+            Ok(Some(dispute)) => errors.push(RedemptionError::NoteDisputed(dispute.id)),
+            Ok(None) => {}
+            Err(e) => errors.push(e.into()),
+        }
+
+        // Check if there's sufficient outstanding debt, with accrued interest, to redeem.
+        // Interest is settled first, since recording the redemption restarts its accrual.
+        let now = self.tracker.clock().now_millis();
+        let redeemable = note.outstanding_debt_with_interest(now);
+        if redeemable < amount {
+            errors.push(RedemptionError::InsufficientCollateral(redeemable, amount));
+        }
+        let accrued_interest = note.accrued_interest(now);
+        if amount < accrued_interest {
+            errors.push(RedemptionError::AccruedInterestUnpaid(accrued_interest, amount));
+        }
+
+        (Some(note), errors)
     }
 
     /// Initiate redemption process for a note
@@ -109,28 +225,14 @@ impl RedemptionManager {
         &mut self,
         request: &RedemptionRequest,
     ) -> Result<RedemptionData, RedemptionError> {
-        // Parse public keys
+        let (note, mut errors) = self.check_redemption(&request.issuer_pubkey, &request.recipient_pubkey, request.amount);
+        let note = match note {
+            Some(note) if errors.is_empty() => note,
+            _ => return Err(errors.remove(0)),
+        };
         let issuer_pubkey = parse_pubkey(&request.issuer_pubkey)?;
         let recipient_pubkey = parse_pubkey(&request.recipient_pubkey)?;
 
-        // Lookup the note
-        let note = self
-            .tracker
-            .lookup_note(&issuer_pubkey, &recipient_pubkey)
-            .map_err(|_| RedemptionError::NoteNotFound)?;
-
-        // Verify note signature
-        note.verify_signature(&issuer_pubkey)
-            .map_err(|_| RedemptionError::InvalidNoteSignature)?;
-
-        // Check if there's sufficient outstanding debt to redeem
-        if note.outstanding_debt() < request.amount {
-            return Err(RedemptionError::InsufficientCollateral(
-                note.outstanding_debt(),
-                request.amount,
-            ));
-        }
-
         // Note: Time lock validation is handled by the ErgoScript contract (basis.es).
         // Normal redemption requires valid signatures (no time restriction).
         // Emergency redemption requires (HEIGHT - trackerCreationHeight) > 2160.
@@ -143,7 +245,7 @@ impl RedemptionManager {
 
         // Build redemption transaction using the transaction builder directly
         // The reserve_box_id should already be set in the request from the API layer
-        let redemption_data = build_redemption_transaction(&mut self.tracker, &note, &proof, request)?;
+        let redemption_data = build_redemption_transaction(&mut self.tracker, &self.contracts, &note, &proof, request)?;
 
         Ok(redemption_data)
     }
@@ -163,20 +265,21 @@ impl RedemptionManager {
     /// - Sends redeemed funds to recipient address
     /// - Includes Schnorr signatures and AVL proofs
     ///
-    /// When blockchain integration is complete, this will produce actual
-    /// Ergo transactions that can be submitted to the network.
+    /// The result holds the serialized unsigned transaction the recipient
+    /// signs before submitting it to the network.
     pub fn build_unsigned_redemption_transaction(
         &mut self,
-        note: &IouNote,
-        proof: &crate::NoteProof,
-        request: &RedemptionRequest,
-        reserve_box_id: &str,
-        tracker_box_id: &str,
-        tracker_nft_id: &str,
-        issuer_sig: &[u8],
-        tracker_sig: &[u8],
+        params: &UnsignedRedemptionParams<'_>,
         context: &TxContext,
     ) -> Result<RedemptionData, RedemptionError> {
+        let UnsignedRedemptionParams {
+            note,
+            proof,
+            request,
+            tracker_box_id,
+            issuer_signature,
+            tracker_signature,
+        } = *params;
         // Generate real proofs using tracker state
         let issuer_pubkey_bytes = parse_pubkey(&request.issuer_pubkey)
             .map_err(|e| RedemptionError::TransactionError(format!("Invalid issuer pubkey: {}", e)))?;
@@ -191,21 +294,27 @@ impl RedemptionManager {
         let tracker_lookup_proof = self.tracker.generate_tracker_lookup_proof(&issuer_pubkey_bytes, &recipient_pubkey_bytes)
             .map_err(|e| RedemptionError::TransactionError(format!("Failed to generate tracker lookup proof: {:?}", e)))?;
         
-        let transaction_data = RedemptionTransactionBuilder::build_unsigned_redemption_transaction(
-            reserve_box_id,
-            tracker_box_id,
-            tracker_nft_id,
-            note,
-            &request.recipient_address,
-            &proof.avl_proof,
-            issuer_sig,
-            tracker_sig,
-            &issuer_pubkey_bytes,
+        let (reserve_box, insert_proof) = reserve_spend(request)?;
+        let witness = RedemptionWitness {
+            reserve_signature: issuer_signature.to_vec(),
+            tracker_signature: tracker_signature.to_vec(),
+            reserve_insert_proof: insert_proof,
+            reserve_lookup_proof: reserve_lookup_proof.proof,
+            tracker_lookup_proof: tracker_lookup_proof.proof,
+        };
+        let transaction = RedemptionTransactionBuilder::build_redemption_transaction(
+            &RedemptionTransactionParams {
+                note,
+                reserve_box,
+                tracker_box_id,
+                witness: &witness,
+                amount: request.amount,
+                recipient_address: &request.recipient_address,
+            },
+            &self.contracts,
             context,
-            reserve_lookup_proof.proof,
-            tracker_lookup_proof.proof,
-            request.amount,
-        ).map_err(|e| RedemptionError::TransactionError(e.to_string()))?;
+        )
+        .map_err(|e| RedemptionError::TransactionError(e.to_string()))?;
 
         // Generate unique redemption ID for tracking
         let redemption_id = format!(
@@ -215,12 +324,6 @@ impl RedemptionManager {
             note.timestamp
         );
 
-        // Create transaction bytes using real transaction builder
-        let transaction_bytes = RedemptionTransactionBuilder::build_redemption_transaction(
-            &transaction_data,
-        )
-        .map_err(|e| RedemptionError::TransactionError(e.to_string()))?;
-
         // Required signatures: issuer and tracker
         let required_signatures = vec![
             request.issuer_pubkey.clone(),
@@ -231,16 +334,13 @@ impl RedemptionManager {
         let estimated_fee = context.fee;
 
         // Redemption can happen immediately since we checked the time lock
-        let redemption_time = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
+        let redemption_time = self.tracker.clock().now_millis();
 
         Ok(RedemptionData {
             redemption_id,
             note: note.clone(),
             avl_proof: proof.avl_proof.clone(),
-            transaction_bytes: hex::encode(transaction_bytes),
+            transaction_bytes: hex::encode(transaction.transaction_bytes),
             required_signatures,
             estimated_fee,
             redemption_time,
@@ -248,6 +348,9 @@ impl RedemptionManager {
     }
 
     /// Complete redemption by updating the note with redeemed amount
+    ///
+    /// The redeemed amount pays off interest accrued on the note first; only
+    /// the rest counts towards the redeemed principal.
     pub fn complete_redemption(
         &mut self,
         issuer_pubkey: &PubKey,
@@ -261,13 +364,14 @@ impl RedemptionManager {
             .map_err(|_| RedemptionError::NoteNotFound)?;
 
         // Update the redeemed amount
-        note.amount_redeemed += redeemed_amount;
+        let now = self.tracker.clock().now_millis();
+        note.amount_redeemed = note
+            .amount_redeemed
+            .checked_add(redeemed_amount.saturating_sub(note.accrued_interest(now)))
+            .ok_or(RedemptionError::from(NoteError::AmountOverflow))?;
 
         // Update the timestamp to ensure it's newer than the existing one
-        note.timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .map_err(|_| RedemptionError::StorageError("Failed to get current time".to_string()))?
-            .as_millis() as u64;
+        note.timestamp = now;
 
         // Update the note in tracker
         self.tracker
@@ -292,6 +396,32 @@ impl RedemptionManager {
     }
 }
 
+/// Blocks the tracker must stay silent before emergency redemption is allowed (3 days)
+pub const EMERGENCY_REDEMPTION_DELAY: u64 = 3 * 720;
+
+/// Average time between two Ergo blocks (720 blocks a day)
+pub const BLOCK_INTERVAL_MILLIS: u64 = 120_000;
+
+/// First height at which the reserve contract accepts an emergency redemption
+pub fn emergency_unlock_height(tracker_creation_height: u64) -> u64 {
+    tracker_creation_height + EMERGENCY_REDEMPTION_DELAY + 1
+}
+
+/// Check the emergency redemption time lock enforced by the reserve contract
+///
+/// The contract accepts an emergency redemption once
+/// `HEIGHT - trackerCreationHeight > EMERGENCY_REDEMPTION_DELAY`.
+pub fn check_emergency_time_lock(
+    current_height: u64,
+    tracker_creation_height: u64,
+) -> Result<(), RedemptionError> {
+    let unlock_height = emergency_unlock_height(tracker_creation_height);
+    if current_height < unlock_height {
+        return Err(RedemptionError::RedemptionTooEarly(current_height, unlock_height));
+    }
+    Ok(())
+}
+
 /// Parse hex-encoded public key
 fn parse_pubkey(hex_str: &str) -> Result<PubKey, RedemptionError> {
     let bytes = hex::decode(hex_str)
@@ -344,6 +474,8 @@ mod tests {
             issuer_signature: "01".repeat(65),
             emergency: false,
             tracker_signature: Some("02".repeat(65)),
+            reserve_box: None,
+            reserve_insert_proof: None,
         };
 
         // Should parse valid public keys
@@ -352,22 +484,68 @@ mod tests {
         assert!(issuer.is_ok());
         assert!(recipient.is_ok());
     }
+
+    #[test]
+    fn test_validate_redemption_collects_errors() {
+        let (secret, issuer) = crate::schnorr::generate_keypair();
+        let (_, recipient) = crate::schnorr::generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        let note = IouNote::create_and_sign(recipient, 1000, 1_000, &secret).unwrap();
+        tracker.add_note(&issuer, &note).unwrap();
+        let manager = RedemptionManager::new(tracker);
+        let (issuer, recipient) = (hex::encode(issuer), hex::encode(recipient));
+
+        assert!(manager.validate_redemption(&issuer, &recipient, 1000).is_empty());
+        assert!(matches!(
+            manager.validate_redemption(&issuer, &recipient, 1001).as_slice(),
+            [RedemptionError::InsufficientCollateral(1000, 1001)]
+        ));
+        assert!(matches!(
+            manager.validate_redemption(&recipient, &issuer, 1).as_slice(),
+            [RedemptionError::NoteNotFound]
+        ));
+        assert!(matches!(
+            manager.validate_redemption("zz", "02", 1).as_slice(),
+            [RedemptionError::InvalidPublicKey(_), RedemptionError::InvalidPublicKey(_)]
+        ));
+    }
+
+    #[test]
+    fn test_emergency_time_lock() {
+        assert!(matches!(
+            check_emergency_time_lock(3_160, 1_000),
+            Err(RedemptionError::RedemptionTooEarly(3_160, 3_161))
+        ));
+        assert!(check_emergency_time_lock(3_161, 1_000).is_ok());
+    }
+}
+
+// The reserve box being spent and the insert proof for its tree, which the
+// tracker cannot produce itself
+fn reserve_spend(request: &RedemptionRequest) -> Result<(&Eip12Box, Vec<u8>), RedemptionError> {
+    let reserve_box = request.reserve_box.as_ref().ok_or_else(|| {
+        RedemptionError::TransactionError(format!(
+            "Reserve box {} must be read from the node to build the transaction",
+            request.reserve_box_id
+        ))
+    })?;
+    let insert_proof = request
+        .reserve_insert_proof
+        .as_deref()
+        .ok_or_else(|| RedemptionError::TransactionError("Reserve insert proof is required".to_string()))?;
+    let insert_proof = hex::decode(insert_proof)
+        .map_err(|e| RedemptionError::TransactionError(format!("Invalid reserve insert proof hex: {}", e)))?;
+    Ok((reserve_box, insert_proof))
 }
 
 // Helper function to build redemption transaction using the transaction builder
 fn build_redemption_transaction(
     tracker: &mut TrackerStateManager,
+    contracts: &ContractRegistry,
     note: &IouNote,
     proof: &crate::NoteProof,
     request: &RedemptionRequest,
 ) -> Result<RedemptionData, RedemptionError> {
-    // In a real implementation, this would:
-    // 1. Fetch the reserve box from the blockchain
-    // 2. Create the redemption transaction following the contract logic
-    // 3. Include the AVL proof and signatures
-    // 4. Calculate appropriate fees
-
-    // For now, create a mock transaction structure using the unified builder
     let redemption_id = format!(
         "redeem_{}_{}_{}",
         &request.issuer_pubkey[..16],
@@ -377,9 +555,19 @@ fn build_redemption_transaction(
 
     // Use blockchain data from request (fetched by API layer)
     let actual_tracker_box_id = request.tracker_box_id.clone();
-    let actual_tracker_nft_id = request.tracker_nft_id.clone();
     let current_height = request.current_height as u32; // Convert u64 to u32 for transaction builder
 
+    // Addresses carry their network: build for the recipient's, and refuse change on another one
+    let network = crate::Network::of_address(&request.recipient_address).unwrap_or_default();
+    if let Ok(change_network) = crate::Network::of_address(&request.change_address) {
+        if change_network != network {
+            return Err(RedemptionError::TransactionError(format!(
+                "Change address is a {} address, but the recipient address is on {}",
+                change_network, network
+            )));
+        }
+    }
+
     // Decode issuer signature from hex
     let issuer_signature_bytes = hex::decode(&request.issuer_signature)
         .map_err(|e| RedemptionError::TransactionError(format!("Invalid issuer signature hex: {}", e)))?;
@@ -429,35 +617,30 @@ fn build_redemption_transaction(
     let tracker_lookup_proof = tracker.generate_tracker_lookup_proof(&issuer_pubkey_bytes, &recipient_pubkey_bytes)
         .map_err(|e| RedemptionError::TransactionError(format!("Failed to generate tracker lookup proof: {:?}", e)))?;
     
-    // Convert proofs to bytes for transaction builder
-    let reserve_lookup_proof_bytes: Option<Vec<u8>> = reserve_lookup_proof.proof;
-    let tracker_lookup_proof_bytes: Vec<u8> = tracker_lookup_proof.proof;
-    
-    // Pass proofs to transaction builder
-    let transaction_data = RedemptionTransactionBuilder::build_unsigned_redemption_transaction(
-        &request.reserve_box_id,
-        &actual_tracker_box_id,
-        &actual_tracker_nft_id,
-        note,
-        &request.recipient_address,
-        &proof.avl_proof,
-        &issuer_signature_bytes,
-        &tracker_signature_bytes,
-        &issuer_pubkey_bytes,
+    let (reserve_box, insert_proof) = reserve_spend(request)?;
+    let witness = RedemptionWitness {
+        reserve_signature: issuer_signature_bytes,
+        tracker_signature: tracker_signature_bytes,
+        reserve_insert_proof: insert_proof,
+        reserve_lookup_proof: reserve_lookup_proof.proof,
+        tracker_lookup_proof: tracker_lookup_proof.proof,
+    };
+    let transaction = RedemptionTransactionBuilder::build_redemption_transaction(
+        &RedemptionTransactionParams {
+            note,
+            reserve_box,
+            tracker_box_id: &actual_tracker_box_id,
+            witness: &witness,
+            amount: request.amount,
+            recipient_address: &request.recipient_address,
+        },
+        contracts,
         &TxContext {
             current_height,
             fee: 1000000, // 0.001 ERG fee from config
             change_address: request.change_address.clone(),
-            network_prefix: 0,
+            network_prefix: network.prefix_byte(),
         },
-        reserve_lookup_proof_bytes,
-        tracker_lookup_proof_bytes,
-        request.amount,
-    ).map_err(|e| RedemptionError::TransactionError(e.to_string()))?;
-
-    // Use real transaction builder to create the actual transaction bytes
-    let transaction_bytes = RedemptionTransactionBuilder::build_redemption_transaction(
-        &transaction_data,
     )
     .map_err(|e| RedemptionError::TransactionError(e.to_string()))?;
 
@@ -473,16 +656,13 @@ fn build_redemption_transaction(
 
     // Redemption time is recorded for tracking purposes
     // Note: Time lock validation is handled by the ErgoScript contract
-    let redemption_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap()
-        .as_millis() as u64;
+    let redemption_time = tracker.clock().now_millis();
 
     Ok(RedemptionData {
         redemption_id,
         note: note.clone(),
         avl_proof: proof.avl_proof.clone(),
-        transaction_bytes: hex::encode(transaction_bytes),
+        transaction_bytes: hex::encode(transaction.transaction_bytes),
         required_signatures,
         estimated_fee,
         redemption_time,
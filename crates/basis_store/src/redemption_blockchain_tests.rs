@@ -21,9 +21,13 @@
 //! - Mock contract validator simulates basis.es validation logic
 
 use crate::{
+    contract_compiler::get_basis_reserve_ergo_tree_hex,
     schnorr::{self, generate_keypair},
-    IouNote, PubKey, RedemptionManager, RedemptionRequest, Signature, TrackerStateManager,
+    wallet_connector::{Eip12Asset, Eip12Box},
+    IouNote, NoteKey, PubKey, RedemptionManager, RedemptionRequest, Signature, TrackerStateManager,
 };
+use basis_trees::BasisAvlTree;
+use std::collections::BTreeMap;
 use blake2::{Blake2b, Digest};
 use generic_array::typenum::U32;
 use secp256k1::{Secp256k1, SecretKey};
@@ -186,6 +190,40 @@ fn deterministic_keypair(seed: &str) -> ([u8; 32], PubKey) {
     (secret_bytes, pubkey)
 }
 
+const RESERVE_BOX_ID: &str = "1111111111111111111111111111111111111111111111111111111111111111";
+const TRACKER_BOX_ID: &str = "3333333333333333333333333333333333333333333333333333333333333333";
+const TRACKER_NFT_ID: &str = "69c5d7a4df2e72252b0015d981876fe338ca240d5576d4e731dfd848ae18fe2b";
+
+/// Reserve box of `owner` holding 1 ERG with an empty tree, as read from the node,
+/// and the proof (hex) inserting the first redemption of `amount` by `receiver`
+fn reserve_for_first_redemption(owner: &PubKey, receiver: &PubKey, timestamp: u64, amount: u64) -> (Eip12Box, String) {
+    let mut tree = BasisAvlTree::new().expect("Failed to create reserve tree");
+    let reserve_box = Eip12Box {
+        box_id: RESERVE_BOX_ID.to_string(),
+        transaction_id: "ee".repeat(32),
+        index: 0,
+        value: 1_000_000_000,
+        ergo_tree: get_basis_reserve_ergo_tree_hex().unwrap(),
+        assets: vec![Eip12Asset {
+            token_id: "22".repeat(32),
+            amount: 1,
+        }],
+        additional_registers: BTreeMap::from([
+            ("R4".to_string(), format!("07{}", hex::encode(owner))),
+            ("R5".to_string(), format!("64{}012000", hex::encode(tree.root_digest()))),
+            ("R6".to_string(), format!("0e20{}", TRACKER_NFT_ID)),
+        ]),
+        creation_height: 900,
+        extension: None,
+    };
+
+    let mut value = timestamp.to_be_bytes().to_vec();
+    value.extend_from_slice(&amount.to_be_bytes());
+    tree.insert(NoteKey::from_keys(owner, receiver).to_bytes(), value)
+        .expect("Failed to insert into reserve tree");
+    (reserve_box, hex::encode(tree.generate_proof()))
+}
+
 // ============================================================================
 // Tests
 // ============================================================================
@@ -193,6 +231,10 @@ fn deterministic_keypair(seed: &str) -> ([u8; 32], PubKey) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use ergo_lib::chain::transaction::{DataInput, Input};
+    use ergo_lib::ergotree_ir::mir::constant::Constant;
+    use ergo_lib::ergotree_ir::serialization::{sigma_byte_reader, SigmaSerializable};
+    use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
 
     /// Test 1: First redemption with valid signatures succeeds
     ///
@@ -281,21 +323,24 @@ mod tests {
         );
 
         // Create redemption request
+        let (reserve_box, insert_proof) =
+            reserve_for_first_redemption(&alice_pubkey, &bob_pubkey, timestamp, total_debt);
         let redemption_request = RedemptionRequest {
             issuer_pubkey: hex::encode(alice_pubkey),
             recipient_pubkey: hex::encode(bob_pubkey),
             amount: total_debt,
             timestamp,
-            reserve_box_id: "test_reserve_box_1".to_string(),
-            tracker_box_id: "test_tracker_box_1".to_string(),
-            tracker_nft_id: "69c5d7a4df2e72252b0015d981876fe338ca240d5576d4e731dfd848ae18fe2b"
-                .to_string(),
+            reserve_box_id: RESERVE_BOX_ID.to_string(),
+            tracker_box_id: TRACKER_BOX_ID.to_string(),
+            tracker_nft_id: TRACKER_NFT_ID.to_string(),
             current_height: 1000,
             recipient_address: "9hnupHc2udAoa7SV2UrWAba3N7pu9tR4RX662wv2iFa9gMn1E73".to_string(),
             change_address: "9hNQcqi72NB5u5Tw6tbfCGbEKByguR7njvcyZXnXPLvV3Do1DiJ".to_string(),
             issuer_signature: hex::encode(&issuer_sig),
             emergency: false,
             tracker_signature: Some(hex::encode(&tracker_sig)),
+            reserve_box: Some(reserve_box),
+            reserve_insert_proof: Some(insert_proof),
         };
 
         // Initiate redemption through manager
@@ -645,21 +690,24 @@ mod tests {
         let tracker_sig = generate_redemption_signature(&tracker_secret, &tracker_pubkey, &message);
 
         // Create redemption request
+        let (reserve_box, insert_proof) =
+            reserve_for_first_redemption(&alice_pubkey, &bob_pubkey, timestamp, total_debt);
         let redemption_request = RedemptionRequest {
             issuer_pubkey: hex::encode(alice_pubkey),
             recipient_pubkey: hex::encode(bob_pubkey),
             amount: total_debt,
             timestamp,
-            reserve_box_id: "test_reserve_box_1".to_string(),
-            tracker_box_id: "test_tracker_box_1".to_string(),
-            tracker_nft_id: "69c5d7a4df2e72252b0015d981876fe338ca240d5576d4e731dfd848ae18fe2b"
-                .to_string(),
+            reserve_box_id: RESERVE_BOX_ID.to_string(),
+            tracker_box_id: TRACKER_BOX_ID.to_string(),
+            tracker_nft_id: TRACKER_NFT_ID.to_string(),
             current_height: 1000,
             recipient_address: "9hnupHc2udAoa7SV2UrWAba3N7pu9tR4RX662wv2iFa9gMn1E73".to_string(),
             change_address: "9hNQcqi72NB5u5Tw6tbfCGbEKByguR7njvcyZXnXPLvV3Do1DiJ".to_string(),
             issuer_signature: hex::encode(&issuer_sig),
             emergency: false,
             tracker_signature: Some(hex::encode(&tracker_sig)),
+            reserve_box: Some(reserve_box),
+            reserve_insert_proof: Some(insert_proof),
         };
 
         // Initiate redemption
@@ -667,120 +715,45 @@ mod tests {
             .initiate_redemption(&redemption_request)
             .expect("Redemption should succeed");
 
-        // Parse transaction bytes as JSON
-        // Note: transaction_bytes is hex-encoded, so we need to decode first
+        // Read the inputs back with ergo-lib's parsers
         let tx_bytes = hex::decode(&redemption_data.transaction_bytes)
             .expect("Transaction bytes should be valid hex");
-        let tx_json: serde_json::Value =
-            serde_json::from_slice(&tx_bytes)
-                .expect("Transaction should be valid JSON");
-
-        // Verify transaction structure
-        assert!(
-            tx_json.get("tx").is_some(),
-            "Transaction should have 'tx' key"
-        );
+        let mut reader = sigma_byte_reader::from_bytes(tx_bytes.as_slice());
 
-        let tx = &tx_json["tx"];
-        assert!(tx.get("inputs").is_some(), "Transaction should have inputs");
-        assert!(
-            tx.get("dataInputs").is_some(),
-            "Transaction should have dataInputs"
-        );
-        assert!(tx.get("outputs").is_some(), "Transaction should have outputs");
-
-        // Verify inputs
-        let inputs = tx["inputs"].as_array().expect("Inputs should be array");
+        let inputs = Vec::<Input>::sigma_parse(&mut reader).expect("Inputs should parse");
         assert_eq!(inputs.len(), 1, "Should have 1 input (reserve box)");
         assert_eq!(
-            inputs[0]["boxId"], "test_reserve_box_1",
+            String::from(inputs[0].box_id.clone()),
+            RESERVE_BOX_ID,
             "Input should be reserve box"
         );
 
-        // Verify context extension
-        let extension = inputs[0]["extension"]
-            .as_object()
-            .expect("Should have extension");
-
-        // Context var #0: action byte (should be "0200" for redemption)
-        assert!(
-            extension.contains_key("0"),
-            "Context extension should have #0 (action)"
-        );
-        assert_eq!(extension["0"], "0200", "Action should be redemption (0)");
-
-        // Context var #1: receiver pubkey (GroupElement)
-        assert!(
-            extension.contains_key("1"),
-            "Context extension should have #1 (receiver)"
-        );
-        let receiver_hex = extension["1"].as_str().expect("Receiver should be string");
-        assert!(
-            receiver_hex.starts_with("07"),
-            "Receiver should be GroupElement (prefix 07)"
-        );
-
-        // Context var #2: reserve signature (Coll[Byte])
-        assert!(
-            extension.contains_key("2"),
-            "Context extension should have #2 (reserveSig)"
-        );
-        let sig_hex = extension["2"].as_str().expect("Sig should be string");
-        assert!(
-            sig_hex.starts_with("0e"),
-            "Signature should be Coll[Byte] (prefix 0e)"
-        );
-
-        // Context var #3: total debt (Long)
-        assert!(
-            extension.contains_key("3"),
-            "Context extension should have #3 (totalDebt)"
-        );
-
-        // Context var #4: timestamp (Long)
-        assert!(
-            extension.contains_key("4"),
-            "Context extension should have #4 (timestamp)"
-        );
-
-        // Context var #5: insert proof (Coll[Byte])
-        assert!(
-            extension.contains_key("5"),
-            "Context extension should have #5 (insertProof)"
-        );
-
-        // Context var #6: tracker signature (Coll[Byte])
-        assert!(
-            extension.contains_key("6"),
-            "Context extension should have #6 (trackerSig)"
-        );
-
-        // Context var #7: reserve lookup proof - should NOT exist for first redemption
+        let extension = &inputs[0].spending_proof.extension.values;
+        assert_eq!(extension[&0], Constant::from(0i8), "Action should be redemption (0)");
+        assert_eq!(
+            extension[&1],
+            Constant::from(EcPoint::sigma_parse_bytes(&bob_pubkey).unwrap()),
+            "Receiver should be a GroupElement"
+        );
+        assert_eq!(extension[&2], Constant::from(issuer_sig.to_vec()), "#2 should be reserveSig");
+        assert_eq!(extension[&3], Constant::from(total_debt as i64), "#3 should be totalDebt");
+        assert_eq!(extension[&4], Constant::from(timestamp as i64), "#4 should be timestamp");
+        assert!(extension.contains_key(&5), "Context extension should have #5 (insertProof)");
+        assert_eq!(extension[&6], Constant::from(tracker_sig.to_vec()), "#6 should be trackerSig");
         assert!(
-            !extension.contains_key("7"),
+            !extension.contains_key(&7),
             "First redemption should NOT have #7 (reserveLookupProof)"
         );
+        assert!(extension.contains_key(&8), "Context extension should have #8 (trackerLookupProof)");
 
-        // Context var #8: tracker lookup proof (Coll[Byte])
-        assert!(
-            extension.contains_key("8"),
-            "Context extension should have #8 (trackerLookupProof)"
-        );
-
-        // Verify data inputs
-        let data_inputs = tx["dataInputs"]
-            .as_array()
-            .expect("Data inputs should be array");
+        let data_inputs = Vec::<DataInput>::sigma_parse(&mut reader).expect("Data inputs should parse");
         assert_eq!(data_inputs.len(), 1, "Should have 1 data input (tracker box)");
         assert_eq!(
-            data_inputs[0]["boxId"], "test_tracker_box_1",
+            String::from(data_inputs[0].box_id.clone()),
+            TRACKER_BOX_ID,
             "Data input should be tracker box"
         );
 
-        // Verify outputs
-        let outputs = tx["outputs"].as_array().expect("Outputs should be array");
-        assert_eq!(outputs.len(), 2, "Should have 2 outputs (reserve + recipient)");
-
         println!("✅ First redemption transaction structure validated\n");
     }
 
@@ -168,6 +168,8 @@ fn test_redemption_request_structure() {
         issuer_signature: "010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101".to_string(),
         emergency: false,
         tracker_signature: Some("020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202".to_string()),
+        reserve_box: None,
+        reserve_insert_proof: None,
     };
 
     // Verify request structure
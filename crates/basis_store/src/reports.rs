@@ -0,0 +1,255 @@
+//! Accountant-friendly note and reserve reports
+//!
+//! Each report row is self-contained, with amounts in nanoERG and dates in
+//! UTC, so a report can be written out one row at a time as it is read from
+//! storage. Rows serialize to JSON with the same fields as their CSV columns.
+
+use serde::Serialize;
+
+use basis_core::MILLIS_PER_DAY;
+
+use crate::{ExtendedReserveInfo, IouNote, PubKey, ReserveTracker};
+
+/// One note in a notes report
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NoteReportRow {
+    pub issuer_pubkey: String,
+    pub recipient_pubkey: String,
+    /// UTC time of the latest note version, as YYYY-MM-DDTHH:MM:SSZ
+    pub date: String,
+    /// Timestamp of the latest note version in milliseconds since the Unix epoch
+    pub timestamp: u64,
+    pub amount_collected: u64,
+    pub amount_redeemed: u64,
+    pub outstanding: u64,
+}
+
+impl NoteReportRow {
+    /// CSV header line, ending in a newline
+    pub const CSV_HEADER: &'static str =
+        "issuer_pubkey,recipient_pubkey,date,timestamp,amount_collected,amount_redeemed,outstanding\n";
+
+    pub fn new(issuer_pubkey: &PubKey, note: &IouNote) -> Self {
+        Self {
+            issuer_pubkey: hex::encode(issuer_pubkey),
+            recipient_pubkey: hex::encode(note.recipient_pubkey),
+            date: format_utc(note.timestamp),
+            timestamp: note.timestamp,
+            amount_collected: note.amount_collected,
+            amount_redeemed: note.amount_redeemed,
+            outstanding: note.outstanding_debt(),
+        }
+    }
+
+    /// CSV line of the row, ending in a newline
+    ///
+    /// Every field is hex or a number, so none needs quoting.
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{}\n",
+            self.issuer_pubkey,
+            self.recipient_pubkey,
+            self.date,
+            self.timestamp,
+            self.amount_collected,
+            self.amount_redeemed,
+            self.outstanding
+        )
+    }
+}
+
+/// One reserve in a reserves report
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReserveReportRow {
+    pub box_id: String,
+    pub owner_pubkey: String,
+    /// UTC time the reserve was last updated, as YYYY-MM-DDTHH:MM:SSZ
+    pub date: String,
+    /// Last update in milliseconds since the Unix epoch
+    pub last_updated_timestamp: u64,
+    pub last_updated_height: u64,
+    pub collateral_amount: u64,
+    pub total_debt: u64,
+    /// Collateral over debt, or None without debt
+    pub collateralization_ratio: Option<f64>,
+}
+
+impl ReserveReportRow {
+    /// CSV header line, ending in a newline
+    pub const CSV_HEADER: &'static str = "box_id,owner_pubkey,date,last_updated_timestamp,last_updated_height,collateral_amount,total_debt,collateralization_ratio\n";
+
+    pub fn new(reserve: &ExtendedReserveInfo) -> Self {
+        Self {
+            box_id: reserve.box_id.clone(),
+            owner_pubkey: reserve.owner_pubkey.clone(),
+            date: format_utc(reserve.last_updated_timestamp),
+            last_updated_timestamp: reserve.last_updated_timestamp,
+            last_updated_height: reserve.base_info.last_updated_height,
+            collateral_amount: reserve.base_info.collateral_amount,
+            total_debt: reserve.total_debt,
+            collateralization_ratio: (reserve.total_debt > 0).then(|| reserve.collateralization_ratio()),
+        }
+    }
+
+    /// CSV line of the row, ending in a newline; a reserve without debt has an empty ratio
+    pub fn to_csv(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{}\n",
+            self.box_id,
+            self.owner_pubkey,
+            self.date,
+            self.last_updated_timestamp,
+            self.last_updated_height,
+            self.collateral_amount,
+            self.total_debt,
+            self.collateralization_ratio.map(|ratio| format!("{:.4}", ratio)).unwrap_or_default()
+        )
+    }
+}
+
+/// Reserves of a report: those of `owner`, if given, last updated within
+/// the inclusive millisecond range, ordered by last update
+pub fn report_reserves(
+    reserve_tracker: &ReserveTracker,
+    owner: Option<&PubKey>,
+    updated: std::ops::RangeInclusive<u64>,
+) -> Vec<ExtendedReserveInfo> {
+    let owner = owner.map(hex::encode);
+    let mut reserves: Vec<_> = reserve_tracker
+        .get_all_reserves()
+        .into_iter()
+        .filter(|reserve| {
+            owner.as_deref().is_none_or(|owner| reserve.owner_pubkey == owner)
+                && updated.contains(&reserve.last_updated_timestamp)
+        })
+        .collect();
+    reserves.sort_by(|a, b| {
+        (a.last_updated_timestamp, &a.box_id).cmp(&(b.last_updated_timestamp, &b.box_id))
+    });
+    reserves
+}
+
+/// Format milliseconds since the Unix epoch as a UTC date and time, YYYY-MM-DDTHH:MM:SSZ
+pub fn format_utc(timestamp_millis: u64) -> String {
+    let days = timestamp_millis / MILLIS_PER_DAY;
+    let seconds = (timestamp_millis % MILLIS_PER_DAY) / 1000;
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+/// Parse a YYYY-MM-DD date as milliseconds since the Unix epoch at its start (00:00 UTC)
+pub fn parse_date(date: &str) -> Option<u64> {
+    let mut parts = date.splitn(3, '-');
+    let year: u64 = parts.next()?.parse().ok()?;
+    let month: u64 = parts.next()?.parse().ok()?;
+    let day: u64 = parts.next()?.parse().ok()?;
+    if year < 1970 || !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    days_from_civil(year, month, day).checked_mul(MILLIS_PER_DAY)
+}
+
+fn days_in_month(year: u64, month: u64) -> u64 {
+    match month {
+        2 if year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400)) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date, after Howard Hinnant's
+// days_from_civil, restricted to dates from 1970 on
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+// Inverse of days_from_civil
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        note_search::{NoteCursor, NoteQuery},
+        schnorr::generate_keypair,
+        TrackerStateManager,
+    };
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(format_utc(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_utc(1_709_210_096_789), "2024-02-29T12:34:56Z");
+        assert_eq!(parse_date("2024-02-29"), Some(1_709_164_800_000));
+        assert_eq!(parse_date("2000-03-01"), Some(951_868_800_000));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2024-13-01"), None);
+        assert_eq!(parse_date("yesterday"), None);
+        for days in [0, 59, 365, 11_016, 19_782, 100_000] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn test_note_report_pages_with_cursor() {
+        let (issuer_secret, issuer) = generate_keypair();
+        let (other_secret, other) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+
+        for timestamp in [3_000, 1_000, 2_000, 2_000, 5_000] {
+            let recipient = generate_keypair().1;
+            let note = IouNote::create_and_sign(recipient, timestamp, timestamp, &issuer_secret).unwrap();
+            tracker.add_note(&issuer, &note).unwrap();
+        }
+        let note = IouNote::create_and_sign(generate_keypair().1, 9, 2_500, &other_secret).unwrap();
+        tracker.add_note(&other, &note).unwrap();
+
+        // Two notes per page, filtered by issuer and date range, oldest first
+        let query = NoteQuery {
+            issuer: Some(issuer),
+            from_timestamp: Some(2_000),
+            to_timestamp: Some(4_000),
+            ..NoteQuery::default()
+        };
+        let mut rows = Vec::new();
+        let mut cursor = None;
+        loop {
+            let page = tracker.scan_notes_by_timestamp(&query, cursor.as_ref(), 2).unwrap();
+            let Some((issuer_pubkey, last)) = page.last() else { break };
+            cursor = Some(NoteCursor::after(issuer_pubkey, last));
+            rows.extend(page.iter().map(|(issuer_pubkey, note)| NoteReportRow::new(issuer_pubkey, note)));
+        }
+        let timestamps: Vec<_> = rows.iter().map(|row| row.timestamp).collect();
+        assert_eq!(timestamps, vec![2_000, 2_000, 3_000]);
+        assert_ne!(rows[0].recipient_pubkey, rows[1].recipient_pubkey);
+
+        let row = &rows[2];
+        assert_eq!(row.to_csv(), format!("{},{},1970-01-01T00:00:03Z,3000,3000,0,3000\n", hex::encode(issuer), row.recipient_pubkey));
+        assert_eq!(NoteReportRow::CSV_HEADER.matches(',').count(), row.to_csv().matches(',').count());
+    }
+}
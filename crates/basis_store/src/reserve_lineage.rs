@@ -0,0 +1,376 @@
+//! Reserve box lifecycle tracking
+//!
+//! A reserve is not a single box: every top-up or redemption spends the
+//! current reserve box and creates a successor box under the same contract
+//! and owner. When the scanner sees a reserve box disappear it follows the
+//! spending transaction, classifies what happened and records a
+//! [`ReserveTransition`]. Chaining transitions through successor box IDs gives
+//! the [`ReserveLineage`] of a reserve from its first known box to its current
+//! box (or its closure).
+//!
+//! Box IDs in this module use the same encoding as [`ExtendedReserveInfo::box_id`],
+//! so they can be passed straight to `GET /reserves/{box_id}`. [`ReserveEvent`]s
+//! carry box IDs as reported by the node.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ergo_scanner::{ReserveEvent, ScanBox};
+use crate::reserve_tracker::ExtendedReserveInfo;
+
+/// What a spending transaction did to a reserve box
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReserveSpendKind {
+    /// The successor box holds more collateral
+    TopUp,
+    /// The successor box holds less collateral
+    Redemption,
+    /// The successor box holds the same collateral (e.g. registers were updated)
+    Update,
+    /// No successor box was created; the reserve is closed
+    Closure,
+}
+
+/// One spent reserve box and the box that replaced it, if any
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReserveTransition {
+    /// Spent reserve box
+    pub box_id: String,
+    /// Transaction that spent the box
+    pub spending_tx_id: String,
+    /// Height at which the spending transaction was included, if known
+    pub spending_height: Option<u64>,
+    /// Classification of the spend
+    pub kind: ReserveSpendKind,
+    /// Reserve box created by the spending transaction, if any
+    pub successor_box_id: Option<String>,
+    /// Collateral held by the spent box
+    pub value_before: u64,
+    /// Collateral held by the successor box
+    pub value_after: Option<u64>,
+    /// Receiver of a redemption that read the reserve's tracker box, from the
+    /// reserve input's context extension (hex public key)
+    #[serde(default)]
+    pub receiver_pubkey: Option<String>,
+}
+
+/// Full known history of a reserve
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReserveLineage {
+    /// Every box of the reserve, oldest first
+    pub box_ids: Vec<String>,
+    /// Transitions between consecutive boxes, oldest first
+    pub transitions: Vec<ReserveTransition>,
+    /// Unspent box of the reserve, or `None` if the reserve was closed
+    pub current_box_id: Option<String>,
+}
+
+/// Box ID as stored by the reserve tracker for a box ID reported by the node
+pub fn tracked_box_id(node_box_id: &str) -> String {
+    hex::encode(node_box_id.as_bytes())
+}
+
+/// Box ID as reported by the node for a box ID stored by the reserve tracker
+pub fn node_box_id(tracked_box_id: &str) -> Option<String> {
+    hex::decode(tracked_box_id)
+        .ok()
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+}
+
+// R4 holds the owner key as a serialized GroupElement (0x07 prefix)
+fn register_owner(output: &ScanBox) -> Option<&str> {
+    let r4 = output.additional_registers.get("R4")?;
+    Some(r4.strip_prefix("07").unwrap_or(r4))
+}
+
+/// Find the output continuing the spent reserve
+///
+/// The successor carries the same owner in R4 and, when the reserve contract
+/// tree is known, is locked by the same contract.
+pub fn find_successor<'a>(
+    spent: &ExtendedReserveInfo,
+    reserve_ergo_tree: Option<&str>,
+    outputs: &'a [ScanBox],
+) -> Option<&'a ScanBox> {
+    outputs.iter().find(|output| {
+        let same_contract = reserve_ergo_tree.is_none_or(|tree| output.ergo_tree.eq_ignore_ascii_case(tree));
+        let same_owner = register_owner(output)
+            .is_some_and(|owner| owner.eq_ignore_ascii_case(&spent.owner_pubkey));
+        same_contract && same_owner
+    })
+}
+
+/// Classify the spend of a reserve box from the spending transaction outputs
+pub fn classify_spend(
+    spent: &ExtendedReserveInfo,
+    reserve_ergo_tree: Option<&str>,
+    spending_tx_id: &str,
+    spending_height: Option<u64>,
+    outputs: &[ScanBox],
+) -> ReserveTransition {
+    let value_before = spent.base_info.collateral_amount;
+    let successor = find_successor(spent, reserve_ergo_tree, outputs);
+
+    let kind = match successor {
+        None => ReserveSpendKind::Closure,
+        Some(output) => match output.value.cmp(&value_before) {
+            std::cmp::Ordering::Greater => ReserveSpendKind::TopUp,
+            std::cmp::Ordering::Less => ReserveSpendKind::Redemption,
+            std::cmp::Ordering::Equal => ReserveSpendKind::Update,
+        },
+    };
+
+    ReserveTransition {
+        box_id: spent.box_id.clone(),
+        spending_tx_id: spending_tx_id.to_string(),
+        spending_height,
+        kind,
+        successor_box_id: successor.map(|output| tracked_box_id(&output.box_id)),
+        value_before,
+        value_after: successor.map(|output| output.value),
+        receiver_pubkey: None,
+    }
+}
+
+/// Derive reserve events from the reserves known before and after a scan
+///
+/// `transitions` are the resolved spends of boxes that left the scan. A new
+/// box that succeeds a spent box is reported as a top-up or redemption of the
+/// difference; any other new box is a newly created reserve. A box that left
+/// the scan is reported as spent when it was closed or its spend could not be
+/// resolved, using `current_height` when the spending height is unknown.
+pub fn derive_reserve_events(
+    previous: &[ExtendedReserveInfo],
+    current: &[ExtendedReserveInfo],
+    transitions: &[ReserveTransition],
+    current_height: u64,
+) -> Vec<ReserveEvent> {
+    let event_box_id = |box_id: &str| node_box_id(box_id).unwrap_or_else(|| box_id.to_string());
+    let is_known = |box_id: &str| previous.iter().any(|reserve| reserve.box_id == box_id);
+    let is_current = |box_id: &str| current.iter().any(|reserve| reserve.box_id == box_id);
+    let mut events = Vec::new();
+
+    for reserve in current.iter().filter(|reserve| !is_known(&reserve.box_id)) {
+        let predecessor = transitions
+            .iter()
+            .find(|transition| transition.successor_box_id.as_deref() == Some(reserve.box_id.as_str()));
+
+        let Some(transition) = predecessor else {
+            events.push(ReserveEvent::ReserveCreated {
+                box_id: event_box_id(&reserve.box_id),
+                owner_pubkey: reserve.owner_pubkey.clone(),
+                collateral_amount: reserve.base_info.collateral_amount,
+                height: reserve.base_info.last_updated_height,
+            });
+            continue;
+        };
+
+        let height = transition
+            .spending_height
+            .unwrap_or(reserve.base_info.last_updated_height);
+        let value_after = reserve.base_info.collateral_amount;
+        match transition.kind {
+            ReserveSpendKind::TopUp => events.push(ReserveEvent::ReserveToppedUp {
+                box_id: event_box_id(&reserve.box_id),
+                owner_pubkey: reserve.owner_pubkey.clone(),
+                additional_collateral: value_after.saturating_sub(transition.value_before),
+                height,
+            }),
+            ReserveSpendKind::Redemption => events.push(ReserveEvent::ReserveRedeemed {
+                box_id: event_box_id(&reserve.box_id),
+                owner_pubkey: reserve.owner_pubkey.clone(),
+                redeemed_amount: transition.value_before.saturating_sub(value_after),
+                height,
+                recipient_pubkey: transition.receiver_pubkey.clone(),
+                tracker_nft_id: Some(reserve.base_info.tracker_nft_id.clone()),
+            }),
+            ReserveSpendKind::Update | ReserveSpendKind::Closure => {}
+        }
+    }
+
+    for reserve in previous.iter().filter(|reserve| !is_current(&reserve.box_id)) {
+        let transition = transitions
+            .iter()
+            .find(|transition| transition.box_id == reserve.box_id);
+        let spent = match transition {
+            Some(transition) => transition.kind == ReserveSpendKind::Closure,
+            None => true,
+        };
+        if spent {
+            events.push(ReserveEvent::ReserveSpent {
+                box_id: event_box_id(&reserve.box_id),
+                owner_pubkey: reserve.owner_pubkey.clone(),
+                height: transition
+                    .and_then(|transition| transition.spending_height)
+                    .unwrap_or(current_height),
+            });
+        }
+    }
+
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::ReserveStorage;
+    use std::collections::HashMap;
+
+    const OWNER: &str = "02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7";
+    const RESERVE_TREE: &str = "100204a00b08cd";
+
+    fn output(box_id: &str, value: u64, ergo_tree: &str, owner: Option<&str>) -> ScanBox {
+        let mut additional_registers = HashMap::new();
+        if let Some(owner) = owner {
+            additional_registers.insert("R4".to_string(), format!("07{}", owner));
+        }
+        ScanBox {
+            box_id: box_id.to_string(),
+            value,
+            ergo_tree: ergo_tree.to_string(),
+            creation_height: 1_000,
+            transaction_id: "tx".to_string(),
+            additional_registers,
+            assets: Vec::new(),
+        }
+    }
+
+    fn reserve(node_box_id: &str, value: u64) -> ExtendedReserveInfo {
+        ExtendedReserveInfo::new(
+            node_box_id.as_bytes(),
+            &hex::decode(OWNER).unwrap(),
+            value,
+            Some(&[1u8; 32]),
+            1_000,
+        )
+    }
+
+    #[test]
+    fn test_classify_spend() {
+        let spent = reserve("box1", 1_000);
+        let change = output("change", 500, "0008cd", None);
+
+        let top_up = classify_spend(
+            &spent,
+            Some(RESERVE_TREE),
+            "tx1",
+            Some(1_001),
+            &[change.clone(), output("box2", 1_500, RESERVE_TREE, Some(OWNER))],
+        );
+        assert_eq!(top_up.kind, ReserveSpendKind::TopUp);
+        assert_eq!(top_up.successor_box_id, Some(tracked_box_id("box2")));
+        assert_eq!(top_up.value_after, Some(1_500));
+
+        let redemption = classify_spend(
+            &spent,
+            Some(RESERVE_TREE),
+            "tx1",
+            None,
+            &[output("box2", 400, RESERVE_TREE, Some(OWNER)), change.clone()],
+        );
+        assert_eq!(redemption.kind, ReserveSpendKind::Redemption);
+
+        // An output with the owner key under another contract is not a successor
+        let closure = classify_spend(
+            &spent,
+            Some(RESERVE_TREE),
+            "tx1",
+            None,
+            &[output("box2", 1_000, "0008cd", Some(OWNER)), change],
+        );
+        assert_eq!(closure.kind, ReserveSpendKind::Closure);
+        assert_eq!(closure.successor_box_id, None);
+    }
+
+    #[test]
+    fn test_lineage_follows_transitions() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = ReserveStorage::open(temp_dir.path()).unwrap();
+
+        let first = reserve("box1", 1_000);
+        let second = reserve("box2", 1_500);
+        let third = reserve("box3", 1_200);
+        storage.store_reserve(&third).unwrap();
+
+        let outputs_1 = [output("box2", 1_500, RESERVE_TREE, Some(OWNER))];
+        let outputs_2 = [output("box3", 1_200, RESERVE_TREE, Some(OWNER))];
+        storage
+            .record_transition(&classify_spend(&first, Some(RESERVE_TREE), "tx1", Some(10), &outputs_1))
+            .unwrap();
+        storage
+            .record_transition(&classify_spend(&second, Some(RESERVE_TREE), "tx2", Some(20), &outputs_2))
+            .unwrap();
+
+        // Any box of the reserve yields the same lineage
+        let expected_ids = vec![first.box_id.clone(), second.box_id.clone(), third.box_id.clone()];
+        for box_id in &expected_ids {
+            let lineage = storage.get_lineage(box_id).unwrap().unwrap();
+            assert_eq!(lineage.box_ids, expected_ids);
+            assert_eq!(lineage.current_box_id, Some(third.box_id.clone()));
+            let kinds: Vec<_> = lineage.transitions.iter().map(|t| t.kind).collect();
+            assert_eq!(kinds, vec![ReserveSpendKind::TopUp, ReserveSpendKind::Redemption]);
+        }
+
+        // Closing the current box ends the lineage
+        storage
+            .record_transition(&classify_spend(&third, Some(RESERVE_TREE), "tx3", Some(30), &[]))
+            .unwrap();
+        let lineage = storage.get_lineage(&first.box_id).unwrap().unwrap();
+        assert_eq!(lineage.current_box_id, None);
+        assert_eq!(lineage.transitions.last().unwrap().kind, ReserveSpendKind::Closure);
+
+        assert_eq!(storage.get_lineage(&tracked_box_id("unknown")).unwrap(), None);
+    }
+
+    #[test]
+    fn test_derive_reserve_events() {
+        let topped_up = reserve("box1", 1_000);
+        let redeemed = reserve("box2", 2_000);
+        let closed = reserve("box3", 3_000);
+        let vanished = reserve("box4", 4_000);
+        let unchanged = reserve("box5", 5_000);
+        let previous = vec![topped_up.clone(), redeemed.clone(), closed.clone(), vanished, unchanged.clone()];
+
+        let current = vec![
+            reserve("box1b", 1_500),
+            reserve("box2b", 1_200),
+            reserve("new", 700),
+            unchanged,
+        ];
+        let mut transitions = vec![
+            classify_spend(&topped_up, None, "tx1", Some(100), &[output("box1b", 1_500, RESERVE_TREE, Some(OWNER))]),
+            classify_spend(&redeemed, None, "tx2", Some(101), &[output("box2b", 1_200, RESERVE_TREE, Some(OWNER))]),
+            classify_spend(&closed, None, "tx3", Some(102), &[]),
+        ];
+        transitions[1].receiver_pubkey = Some("03ab".to_string());
+
+        let events = derive_reserve_events(&previous, &current, &transitions, 200);
+        let summary: Vec<String> = events
+            .iter()
+            .map(|event| match event {
+                ReserveEvent::ReserveCreated { box_id, collateral_amount, .. } => {
+                    format!("created {} {}", box_id, collateral_amount)
+                }
+                ReserveEvent::ReserveToppedUp { box_id, additional_collateral, height, .. } => {
+                    format!("topped_up {} {} @{}", box_id, additional_collateral, height)
+                }
+                ReserveEvent::ReserveRedeemed { box_id, redeemed_amount, height, recipient_pubkey, .. } => {
+                    format!("redeemed {} {} @{} to {:?}", box_id, redeemed_amount, height, recipient_pubkey)
+                }
+                ReserveEvent::ReserveSpent { box_id, height, .. } => format!("spent {} @{}", box_id, height),
+            })
+            .collect();
+
+        assert_eq!(
+            summary,
+            vec![
+                "topped_up box1b 500 @100",
+                "redeemed box2b 800 @101 to Some(\"03ab\")",
+                "created new 700",
+                "spent box3 @102",
+                // Unresolved spends fall back to the current height
+                "spent box4 @200",
+            ]
+        );
+    }
+}
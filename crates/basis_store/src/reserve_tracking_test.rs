@@ -337,4 +337,46 @@ mod tests {
 
         println!("Comprehensive reserve tracking test completed successfully!");
     }
+
+    /// Queued reserve events survive a reopen until they are marked processed
+    #[test]
+    fn test_reserve_event_queue_replays_unprocessed_events() {
+        use crate::ergo_scanner::ReserveEvent;
+
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let path = temp_dir.path().join("reserves");
+        let spent = |box_id: &str, height: u64| ReserveEvent::ReserveSpent {
+            box_id: box_id.to_string(),
+            owner_pubkey: "owner".to_string(),
+            height,
+        };
+
+        {
+            let storage = ReserveStorage::open(&path).unwrap();
+            let first = storage.enqueue_reserve_events(vec![spent("a", 100), spent("d", 101), spent("b", 100)]).unwrap();
+            // A later scan continues the sequence of the block
+            let second = storage.enqueue_reserve_events(vec![spent("c", 100)]).unwrap();
+            assert_eq!((first[1].sequence, first[2].sequence, second[0].sequence), (0, 1, 2));
+
+            storage.mark_reserve_event_processed(100, 0).unwrap();
+            assert!(!storage.is_reserve_event_pending(100, 0).unwrap());
+            assert!(storage.is_reserve_event_pending(100, 1).unwrap());
+        }
+
+        // After a restart the unprocessed events come back in queue order
+        let storage = ReserveStorage::open(&path).unwrap();
+        let pending: Vec<_> = storage
+            .unprocessed_reserve_events()
+            .unwrap()
+            .into_iter()
+            .map(|queued| queued.event)
+            .collect();
+        assert_eq!(pending, vec![spent("b", 100), spent("c", 100), spent("d", 101)]);
+
+        // Once a block is fully processed its events leave the queue
+        storage.mark_reserve_event_processed(100, 1).unwrap();
+        storage.mark_reserve_event_processed(100, 2).unwrap();
+        assert_eq!(storage.unprocessed_reserve_events().unwrap().len(), 1);
+        assert_eq!(storage.enqueue_reserve_events(vec![spent("e", 100)]).unwrap()[0].sequence, 0);
+    }
 }
@@ -0,0 +1,754 @@
+//! Node scan management shared by the reserve and tracker scanners
+//!
+//! A [`ScanManager`] owns one scan registered through the Ergo node's `/scan`
+//! API. It registers the scan's tracking rule, reusing the scan ID stored in
+//! scanner metadata while the node still lists it, fetches the scan's unspent
+//! boxes, parses them with the scan's [`ScanSpec`] and hands the parsed items
+//! to every registered [`ScanEmitter`].
+//!
+//! Reserve and tracker boxes are scanned through [`crate::ergo_scanner`] and
+//! [`crate::tracker_scanner`]; another kind of box (e.g. oracle pool boxes)
+//! only needs a `ScanSpec` of its own.
+//!
+//! Without a node, a manager can read the same boxes from an explorer instead,
+//! see [`crate::explorer_backend`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::Client;
+use serde::Deserialize;
+use tokio::sync::Mutex;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    ergo_scanner::{node_endpoint, BoxAsset, ScanBox, ScannerError},
+    explorer_backend::{ExplorerBackend, ExplorerQuery, EXPLORER_SCAN_ID},
+    persistence::ScannerMetadataStorage,
+};
+
+/// How long a cached chain height is used before asking the node again
+const HEIGHT_CACHE_TTL_MS: u64 = 600_000;
+
+/// How often a stored scan ID is checked against the node's scan list
+const SCAN_VERIFICATION_INTERVAL: Duration = Duration::from_secs(4 * 60 * 60);
+
+/// Emitters of a scan, shared between clones of its manager
+type Emitters<T> = Arc<std::sync::RwLock<Vec<Arc<dyn ScanEmitter<T>>>>>;
+
+/// What a scan tracks and how its boxes are parsed
+pub trait ScanSpec: Send + Sync + 'static {
+    /// What a box of this scan parses into
+    type Item: Send + Sync + 'static;
+    /// Why a box could not be parsed
+    type ParseError: std::fmt::Display;
+
+    /// Scan name used when none is configured; the scan ID is stored under the scan name
+    fn default_scan_name(&self) -> &'static str;
+
+    /// `trackingRule` sent to `/scan/register`
+    fn tracking_rule(&self) -> Result<serde_json::Value, ScannerError>;
+
+    /// Whether the node should forget boxes of the scan once they are spent off-chain
+    fn remove_offchain(&self) -> bool {
+        false
+    }
+
+    /// Parse a box returned by the scan
+    fn parse_box(&self, scan_box: &ScanBox) -> Result<Self::Item, Self::ParseError>;
+}
+
+/// Receives the items parsed from each fetch of a scan's boxes
+pub trait ScanEmitter<T>: Send + Sync {
+    /// Called with the items of one fetch, in the order the node returned them
+    fn emit(&self, items: &[T]);
+}
+
+/// Node connection settings of a scan
+#[derive(Debug, Clone, Default)]
+pub struct ScanNodeConfig {
+    /// Ergo node URL
+    pub node_url: String,
+    /// API key for Ergo node authentication
+    pub api_key: Option<String>,
+    /// Scan registration name, defaulting to the spec's
+    pub scan_name: Option<String>,
+}
+
+/// Wrapper struct for the actual API response from /scan/unspentBoxes endpoint
+#[derive(Debug, Clone, Deserialize)]
+struct ApiScanBox {
+    #[serde(rename = "box")]
+    inner_box: ApiInnerBox,
+}
+
+/// The inner box structure from the API response
+#[derive(Debug, Clone, Deserialize)]
+struct ApiInnerBox {
+    #[serde(rename = "boxId")]
+    box_id: String,
+    value: u64,
+    #[serde(rename = "ergoTree")]
+    ergo_tree: String,
+    #[serde(rename = "creationHeight")]
+    creation_height: u64,
+    #[serde(rename = "transactionId")]
+    transaction_id: String,
+    #[serde(rename = "additionalRegisters")]
+    additional_registers: HashMap<String, String>,
+    assets: Vec<ApiBoxAsset>,
+}
+
+/// Asset structure from the API response
+#[derive(Debug, Clone, Deserialize)]
+struct ApiBoxAsset {
+    #[serde(rename = "tokenId")]
+    token_id: String,
+    amount: u64,
+}
+
+impl From<ApiScanBox> for ScanBox {
+    fn from(api_box: ApiScanBox) -> Self {
+        let inner = api_box.inner_box;
+        ScanBox {
+            box_id: inner.box_id,
+            value: inner.value,
+            ergo_tree: inner.ergo_tree,
+            creation_height: inner.creation_height,
+            transaction_id: inner.transaction_id,
+            additional_registers: inner.additional_registers,
+            assets: inner
+                .assets
+                .into_iter()
+                .map(|asset| BoxAsset {
+                    token_id: asset.token_id,
+                    amount: asset.amount,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Registration state of a scan
+#[derive(Debug, Default)]
+struct ScanState {
+    scan_id: Option<i32>,
+    active: bool,
+    last_verification: Option<SystemTime>,
+}
+
+/// One node scan: registration, box fetching and parsing
+pub struct ScanManager<S: ScanSpec> {
+    spec: Arc<S>,
+    config: ScanNodeConfig,
+    client: Client,
+    metadata_storage: ScannerMetadataStorage,
+    state: Arc<Mutex<ScanState>>,
+    emitters: Emitters<S::Item>,
+    /// Explorer serving the scan instead of the node
+    explorer: Option<ExplorerBackend>,
+}
+
+impl<S: ScanSpec> Clone for ScanManager<S> {
+    fn clone(&self) -> Self {
+        Self {
+            spec: self.spec.clone(),
+            config: self.config.clone(),
+            client: self.client.clone(),
+            metadata_storage: self.metadata_storage.clone(),
+            state: self.state.clone(),
+            emitters: self.emitters.clone(),
+            explorer: self.explorer.clone(),
+        }
+    }
+}
+
+impl<S: ScanSpec> ScanManager<S> {
+    pub fn new(spec: S, config: ScanNodeConfig, metadata_storage: ScannerMetadataStorage) -> Self {
+        Self {
+            spec: Arc::new(spec),
+            config,
+            client: Client::new(),
+            metadata_storage,
+            state: Arc::default(),
+            emitters: Arc::default(),
+            explorer: None,
+        }
+    }
+
+    /// The scan's spec
+    pub fn spec(&self) -> &S {
+        &self.spec
+    }
+
+    /// Replace the scan's spec
+    ///
+    /// A scan already registered keeps the tracking rule it was registered
+    /// with until it is reset.
+    pub fn set_spec(&mut self, spec: S) {
+        self.spec = Arc::new(spec);
+    }
+
+    /// Read the scan's boxes and the chain height from `explorer` instead of the node
+    ///
+    /// The explorer has no registered scans: registering only checks that the
+    /// tracking rule translates into explorer queries, and the scan is known
+    /// under [`EXPLORER_SCAN_ID`] from then on.
+    pub fn use_explorer(&mut self, explorer: ExplorerBackend) {
+        self.explorer = Some(explorer);
+    }
+
+    /// Explorer serving the scan, if any
+    pub fn explorer(&self) -> Option<&ExplorerBackend> {
+        self.explorer.as_ref()
+    }
+
+    /// Name the scan is registered and stored under
+    pub fn scan_name(&self) -> &str {
+        self.config.scan_name.as_deref().unwrap_or(self.spec.default_scan_name())
+    }
+
+    /// HTTP client used for node requests
+    pub fn client(&self) -> &Client {
+        &self.client
+    }
+
+    /// URL of `path` on the configured node
+    pub fn endpoint(&self, path: &str) -> String {
+        node_endpoint(&self.config.node_url, path)
+    }
+
+    /// Create HTTP request builder with API key header if configured
+    pub fn request_builder(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
+        debug!("Request method: {}, URL: {}", method, url);
+        let mut request = self.client.request(method, url);
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header("api_key", api_key);
+        }
+        request
+    }
+
+    /// Pass the items of every later fetch to `emitter`
+    pub fn add_emitter(&self, emitter: Arc<dyn ScanEmitter<S::Item>>) {
+        if let Ok(mut emitters) = self.emitters.write() {
+            emitters.push(emitter);
+        }
+    }
+
+    /// Scan ID in use, falling back to the one stored in scanner metadata
+    pub async fn scan_id(&self) -> Option<i32> {
+        if let Some(scan_id) = self.state.lock().await.scan_id {
+            return Some(scan_id);
+        }
+        if self.explorer.is_some() {
+            return None;
+        }
+        self.metadata_storage.get_scan_id(self.scan_name()).ok().flatten()
+    }
+
+    /// Whether the scan has been registered or confirmed since startup and is active
+    pub async fn is_registered(&self) -> bool {
+        let state = self.state.lock().await;
+        state.scan_id.is_some() && state.active
+    }
+
+    /// Whether the scan is active
+    pub async fn is_active(&self) -> bool {
+        self.state.lock().await.active
+    }
+
+    /// Mark the scan active or inactive without touching its registration
+    pub async fn set_active(&self, active: bool) {
+        self.state.lock().await.active = active;
+    }
+
+    /// Use `scan_id` as the scan's ID without registering it
+    pub async fn use_scan_id(&self, scan_id: i32) {
+        let mut state = self.state.lock().await;
+        state.scan_id = Some(scan_id);
+        state.active = true;
+    }
+
+    /// Forget the scan ID in use, so the next registration asks the node again
+    pub async fn reset(&self) {
+        let mut state = self.state.lock().await;
+        state.scan_id = None;
+        state.active = false;
+    }
+
+    // Check if scan verification is needed
+    async fn should_verify_scan(&self) -> bool {
+        match self.state.lock().await.last_verification {
+            Some(last_verification) => {
+                SystemTime::now().duration_since(last_verification).unwrap_or_default() >= SCAN_VERIFICATION_INTERVAL
+            }
+            None => true,
+        }
+    }
+
+    fn remove_stored_scan_id(&self) -> Result<(), ScannerError> {
+        self.metadata_storage
+            .remove_scan_id(self.scan_name())
+            .map_err(|e| ScannerError::StoreError(format!("Failed to remove scan ID: {:?}", e)))
+    }
+
+    /// Register the scan with the node, returning its ID
+    ///
+    /// A scan ID stored under the scan name is reused without registering a
+    /// new scan; it is checked against the node's scan list at most every four
+    /// hours and replaced if the node no longer knows it.
+    pub async fn register(&self) -> Result<i32, ScannerError> {
+        let scan_name = self.scan_name().to_string();
+
+        if self.explorer.is_some() {
+            ExplorerQuery::from_tracking_rule(&self.spec.tracking_rule()?)?;
+            info!("Scan '{}' is served by the explorer", scan_name);
+            self.use_scan_id(EXPLORER_SCAN_ID).await;
+            return Ok(EXPLORER_SCAN_ID);
+        }
+
+        debug!("Checking for existing scan ID in database for scan name: '{}'", scan_name);
+        match self.metadata_storage.get_scan_id(&scan_name) {
+            Ok(Some(stored_scan_id)) => {
+                info!("Found existing scan ID in database: {}", stored_scan_id);
+                if !self.should_verify_scan().await {
+                    debug!("Skipping scan ID verification (last verified less than 4 hours ago)");
+                    self.use_scan_id(stored_scan_id).await;
+                    return Ok(stored_scan_id);
+                }
+
+                debug!("Verifying scan ID {} exists on Ergo node", stored_scan_id);
+                match self.verify_scan_exists(stored_scan_id).await {
+                    Ok(true) => {
+                        info!("Using existing scan ID: {}", stored_scan_id);
+                        self.state.lock().await.last_verification = Some(SystemTime::now());
+                        self.use_scan_id(stored_scan_id).await;
+                        return Ok(stored_scan_id);
+                    }
+                    Ok(false) => {
+                        warn!("Stored scan ID {} no longer exists on node, re-registering", stored_scan_id);
+                        self.remove_stored_scan_id()?;
+                    }
+                    Err(e) => {
+                        // An unparseable scan list says nothing about our scan, so keep using it
+                        if e.to_string().contains("Failed to parse scan list") {
+                            warn!(
+                                "Scan list could not be parsed ({}), assuming scan ID {} still exists",
+                                e, stored_scan_id
+                            );
+                            self.state.lock().await.last_verification = Some(SystemTime::now());
+                            self.use_scan_id(stored_scan_id).await;
+                            return Ok(stored_scan_id);
+                        }
+                        error!("Failed to verify existing scan ID {}: {}", stored_scan_id, e);
+                        warn!("Unable to verify scan ID, forcing re-registration");
+                        self.remove_stored_scan_id()?;
+                    }
+                }
+            }
+            Ok(None) => {
+                info!(
+                    "No existing scan ID found in database for scan name: '{}', proceeding with new registration",
+                    scan_name
+                );
+            }
+            Err(e) => {
+                error!("Failed to get scan ID from database: {:?}", e);
+                info!("Database error, proceeding with new registration");
+            }
+        }
+
+        let scan_payload = serde_json::json!({
+            "scanName": scan_name,
+            "walletInteraction": "shared",
+            "trackingRule": self.spec.tracking_rule()?,
+            "removeOffchain": self.spec.remove_offchain()
+        });
+
+        info!("Registering new scan with name: {}", scan_name);
+        debug!("Scan registration JSON payload: {}", scan_payload);
+
+        let url = self.endpoint("scan/register");
+        info!("Sending HTTP POST request to Ergo node: {}", url);
+        info!("Request headers: API key present: {}", self.config.api_key.is_some());
+        let response = self
+            .request_builder(reqwest::Method::POST, &url)
+            .json(&scan_payload)
+            .send()
+            .await
+            .map_err(|e| {
+                error!("HTTP request to {} failed: {}", url, e);
+                ScannerError::HttpError(format!("Failed to register scan: {}", e))
+            })?;
+
+        let status = response.status();
+        debug!("Response status: {}", status);
+        if !status.is_success() {
+            let response_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unable to read response body".to_string());
+            error!("Scan registration failed with status: {}. Response body: {}", status, response_text);
+            error!("  URL: {}", url);
+            error!("  API key present: {}", self.config.api_key.is_some());
+            error!("  Payload: {}", scan_payload);
+            return Err(ScannerError::NodeError(format!(
+                "Scan registration failed with status: {}. Response: {}",
+                status, response_text
+            )));
+        }
+
+        let result: serde_json::Value = response.json().await.map_err(|e| {
+            error!("Failed to parse scan registration response JSON: {}", e);
+            ScannerError::JsonError(format!("Failed to parse scan registration response: {}", e))
+        })?;
+        debug!("Scan registration successful response: {}", result);
+
+        let scan_id = result["scanId"]
+            .as_i64()
+            .and_then(|v| i32::try_from(v).ok())
+            .ok_or_else(|| {
+                error!("Failed to get scan ID from registration response. Response was: {}", result);
+                ScannerError::Generic("Failed to get scan ID from registration response".to_string())
+            })?;
+
+        self.metadata_storage
+            .store_scan_id(&scan_name, scan_id)
+            .map_err(|e| ScannerError::StoreError(format!("Failed to store scan ID: {:?}", e)))?;
+        self.use_scan_id(scan_id).await;
+
+        info!("Registered and stored scan '{}' with ID: {}", scan_name, scan_id);
+        Ok(scan_id)
+    }
+
+    /// Keep the stored scan if the node still lists it, otherwise register a new one
+    pub async fn ensure_registered(&self) -> Result<i32, ScannerError> {
+        if let Some(scan_id) = self.scan_id().await {
+            if self.verify_scan_exists(scan_id).await.unwrap_or(false) {
+                info!("Scan {} is still active", scan_id);
+                self.use_scan_id(scan_id).await;
+                return Ok(scan_id);
+            }
+            warn!("Stored scan {} is no longer active, re-registering", scan_id);
+        }
+        self.register().await
+    }
+
+    /// Verify that a scan ID still exists on the Ergo node
+    ///
+    /// A node that cannot be reached, or does not support listing scans, is
+    /// assumed to still hold the scan so that it is not registered twice.
+    pub async fn verify_scan_exists(&self, scan_id: i32) -> Result<bool, ScannerError> {
+        if self.explorer.is_some() {
+            return Ok(scan_id == EXPLORER_SCAN_ID);
+        }
+        let url = self.endpoint("scan/listAll");
+        debug!("Verifying scan ID {} exists - URL: {}", scan_id, url);
+
+        let response = match self.request_builder(reqwest::Method::GET, &url).send().await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!(
+                    "Network error connecting to scan list endpoint ({}), assuming scan ID {} exists",
+                    e, scan_id
+                );
+                return Ok(true);
+            }
+        };
+
+        let status = response.status();
+        if status == 400 || status == 404 {
+            info!(
+                "Scan list endpoint not available (status: {}), assuming scan ID {} exists",
+                status, scan_id
+            );
+            return Ok(true);
+        }
+        if !status.is_success() {
+            let response_text = response
+                .text()
+                .await
+                .unwrap_or_else(|_| "Unable to read response body".to_string());
+            warn!(
+                "Scan list request failed (status: {}, body: {}), assuming scan ID {} exists to prevent re-registration",
+                status, response_text, scan_id
+            );
+            return Ok(true);
+        }
+
+        let scans: serde_json::Value = response.json().await.map_err(|e| {
+            error!("Failed to parse scan list JSON: {}", e);
+            ScannerError::JsonError(format!("Failed to parse scan list: {}", e))
+        })?;
+        debug!("Scan list response: {}", scans);
+
+        let found = scans
+            .as_array()
+            .is_some_and(|scans| scans.iter().any(|scan| scan["scanId"].as_i64() == Some(scan_id as i64)));
+        if !found {
+            debug!("Scan ID {} not found in scan list", scan_id);
+        }
+        Ok(found)
+    }
+
+    /// Remove the scan from the node and forget its ID
+    pub async fn deregister(&self) -> Result<(), ScannerError> {
+        let Some(scan_id) = self.scan_id().await else {
+            return Ok(());
+        };
+        if self.explorer.is_some() {
+            self.reset().await;
+            return Ok(());
+        }
+
+        info!("Deregistering scan with ID: {}", scan_id);
+        let url = self.endpoint("scan/deregister");
+        let response = self
+            .request_builder(reqwest::Method::POST, &url)
+            .json(&serde_json::json!({ "scanId": scan_id }))
+            .send()
+            .await
+            .map_err(|e| ScannerError::HttpError(format!("Failed to send request: {}", e)))?;
+
+        if response.status().is_success() {
+            info!("Successfully deregistered scan with ID: {}", scan_id);
+        } else {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            warn!("Failed to deregister scan {}: {}", scan_id, error_text);
+        }
+
+        self.remove_stored_scan_id()?;
+        self.reset().await;
+        Ok(())
+    }
+
+    /// Unspent boxes of the scan, optionally filtered by a `/scan/unspentBoxes` query string
+    pub async fn fetch_boxes(&self, query: Option<&str>) -> Result<Vec<ScanBox>, ScannerError> {
+        let scan_id = self
+            .scan_id()
+            .await
+            .ok_or_else(|| ScannerError::Generic("Scan not registered".to_string()))?;
+
+        if let Some(explorer) = &self.explorer {
+            let queries = ExplorerQuery::from_tracking_rule(&self.spec.tracking_rule()?)?;
+            return explorer.scan_boxes(&queries, query).await;
+        }
+
+        let url = match query {
+            Some(query) => self.endpoint(&format!("scan/unspentBoxes/{}?{}", scan_id, query)),
+            None => self.endpoint(&format!("scan/unspentBoxes/{}", scan_id)),
+        };
+        debug!("Requesting unspent boxes for scan ID {}: {}", scan_id, url);
+
+        let response = self
+            .request_builder(reqwest::Method::GET, &url)
+            .send()
+            .await
+            .map_err(|e| ScannerError::HttpError(format!("Failed to fetch scan boxes: {}", e)))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let response_text = response.text().await.unwrap_or_else(|_| "Unable to read response".to_string());
+            error!("Failed to get scan boxes with status: {}", status);
+            error!("Response body: {}", response_text);
+            return Err(ScannerError::NodeError(format!(
+                "Failed to get scan boxes with status: {}",
+                status
+            )));
+        }
+
+        let response_text = response
+            .text()
+            .await
+            .map_err(|e| ScannerError::HttpError(format!("Failed to read response text: {}", e)))?;
+        let api_boxes: Vec<ApiScanBox> = serde_json::from_str(&response_text).map_err(|e| {
+            error!("Failed to parse API scan boxes JSON: {}", e);
+            error!("Raw response was: {}", response_text);
+            ScannerError::JsonError(format!("Failed to parse API scan boxes: {}", e))
+        })?;
+
+        let scan_boxes: Vec<ScanBox> = api_boxes.into_iter().map(ScanBox::from).collect();
+        info!("Found {} boxes in scan {}", scan_boxes.len(), scan_id);
+        for scan_box in &scan_boxes {
+            debug!(
+                "Box {}: value={}, creation_height={}, registers={:?}, assets={:?}",
+                scan_box.box_id, scan_box.value, scan_box.creation_height, scan_box.additional_registers, scan_box.assets
+            );
+        }
+        Ok(scan_boxes)
+    }
+
+    /// Parse boxes with the scan's spec, skipping (and logging) those that fail
+    pub fn parse_boxes(&self, scan_boxes: &[ScanBox]) -> Vec<S::Item> {
+        scan_boxes
+            .iter()
+            .filter_map(|scan_box| match self.spec.parse_box(scan_box) {
+                Ok(item) => Some(item),
+                Err(e) => {
+                    warn!(
+                        "Failed to parse box {} of scan '{}': {} - registers: {:?}",
+                        scan_box.box_id,
+                        self.scan_name(),
+                        e,
+                        scan_box.additional_registers
+                    );
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Fetch and parse the scan's boxes, passing the items to every emitter
+    pub async fn process(&self, query: Option<&str>) -> Result<Vec<S::Item>, ScannerError> {
+        let scan_boxes = self.fetch_boxes(query).await?;
+        let items = self.parse_boxes(&scan_boxes);
+        if let Ok(emitters) = self.emitters.read() {
+            for emitter in emitters.iter() {
+                emitter.emit(&items);
+            }
+        }
+        Ok(items)
+    }
+
+    /// Current blockchain height from cache or Ergo node
+    ///
+    /// Uses the cached value if less than 10 minutes old, otherwise fetches
+    /// `/info` from the node, or the network state from the explorer.
+    pub async fn current_height(&self) -> Result<u64, ScannerError> {
+        let now_ms = || SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+
+        match self.metadata_storage.get_blockchain_height() {
+            Ok(Some((cached_height, cached_timestamp))) if now_ms().saturating_sub(cached_timestamp) < HEIGHT_CACHE_TTL_MS => {
+                debug!("Using cached blockchain height: {}", cached_height);
+                return Ok(cached_height);
+            }
+            Ok(_) => debug!("No fresh cached blockchain height, fetching from node"),
+            Err(e) => warn!("Failed to read cached blockchain height: {:?}", e),
+        }
+
+        let height = match &self.explorer {
+            Some(explorer) => explorer.current_height().await?,
+            None => self.node_info().await?["fullHeight"].as_u64().ok_or_else(|| {
+                ScannerError::NodeError("Failed to parse fullHeight from node info".to_string())
+            })?,
+        };
+
+        if let Err(e) = self.metadata_storage.store_blockchain_height(height, now_ms()) {
+            warn!("Failed to cache blockchain height: {:?}", e);
+        }
+        Ok(height)
+    }
+
+    /// Fetch the node's `/info` document
+    pub async fn node_info(&self) -> Result<serde_json::Value, ScannerError> {
+        let url = self.endpoint("info");
+        let response = self
+            .request_builder(reqwest::Method::GET, &url)
+            .send()
+            .await
+            .map_err(|e| ScannerError::HttpError(format!("Failed to connect to node: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(ScannerError::NodeError(format!(
+                "Node returned status: {}",
+                response.status()
+            )));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| ScannerError::JsonError(format!("Failed to parse node info: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mock_node::{BoxSpec, MockErgoNode};
+
+    const ORACLE_POOL_NFT: &str = "011d3364de07e5a26f0c4eef0852cddb387039a921b7154ef3cab22c6eda887f";
+
+    /// Oracle pool boxes, carrying the latest rate in R4
+    struct OraclePoolSpec;
+
+    impl ScanSpec for OraclePoolSpec {
+        type Item = (String, String);
+        type ParseError = String;
+
+        fn default_scan_name(&self) -> &'static str {
+            "oracle_pool"
+        }
+
+        fn tracking_rule(&self) -> Result<serde_json::Value, ScannerError> {
+            Ok(serde_json::json!({ "predicate": "containsAsset", "assetId": ORACLE_POOL_NFT }))
+        }
+
+        fn parse_box(&self, scan_box: &ScanBox) -> Result<Self::Item, String> {
+            let rate = scan_box.additional_registers.get("R4").ok_or("missing R4")?;
+            Ok((scan_box.box_id.clone(), rate.clone()))
+        }
+    }
+
+    #[derive(Default)]
+    struct Collect(std::sync::Mutex<Vec<(String, String)>>);
+
+    impl ScanEmitter<(String, String)> for Collect {
+        fn emit(&self, items: &[(String, String)]) {
+            self.0.lock().unwrap().extend_from_slice(items);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_scan_registers_parses_and_emits() {
+        let node = MockErgoNode::start(100).await;
+        let dir = tempfile::tempdir().unwrap();
+        let metadata_storage = ScannerMetadataStorage::open(dir.path()).unwrap();
+        let manager = ScanManager::new(
+            OraclePoolSpec,
+            ScanNodeConfig {
+                node_url: node.url.clone(),
+                ..Default::default()
+            },
+            metadata_storage.clone(),
+        );
+        let collected = Arc::new(Collect::default());
+        manager.add_emitter(collected.clone());
+
+        assert!(matches!(manager.fetch_boxes(None).await, Err(ScannerError::Generic(_))));
+        let scan_id = manager.register().await.unwrap();
+        assert!(manager.is_registered().await);
+        assert_eq!(metadata_storage.get_scan_id("oracle_pool").unwrap(), Some(scan_id));
+
+        let pool_box = node.create_box(BoxSpec::new(1_000_000, "00").register("R4", "05a09c01").asset(ORACLE_POOL_NFT, 1));
+        node.create_box(BoxSpec::new(1_000_000, "00").asset(ORACLE_POOL_NFT, 1));
+        node.create_box(BoxSpec::new(1_000_000, "00").register("R4", "05a09c01"));
+
+        // The box without R4 is skipped, the one without the NFT is not in the scan
+        let items = manager.process(None).await.unwrap();
+        assert_eq!(items, vec![(pool_box.clone(), "05a09c01".to_string())]);
+        assert_eq!(*collected.0.lock().unwrap(), items);
+        assert_eq!(manager.current_height().await.unwrap(), 100);
+
+        // A restarted manager reuses the stored scan
+        let restarted = ScanManager::new(
+            OraclePoolSpec,
+            ScanNodeConfig {
+                node_url: node.url.clone(),
+                ..Default::default()
+            },
+            metadata_storage.clone(),
+        );
+        assert_eq!(restarted.register().await.unwrap(), scan_id);
+        assert_eq!(node.scan_ids(), vec![scan_id as i64]);
+
+        // ...until the node forgets it
+        restarted.deregister().await.unwrap();
+        assert!(node.scan_ids().is_empty());
+        assert!(!restarted.is_registered().await);
+        let new_scan_id = manager.ensure_registered().await.unwrap();
+        assert_ne!(new_scan_id, scan_id);
+        assert_eq!(manager.process(None).await.unwrap().len(), 1);
+    }
+}
@@ -24,12 +24,9 @@ impl SimpleIntegrationTestSuite {
         let height = self.scanner.get_current_height().await?;
         assert!(height > 0, "Height should be positive");
 
-        // Test unspent boxes
+        // Test unspent boxes (requires a registered scan)
         let boxes = self.scanner.get_unspent_reserve_boxes().await?;
-        assert!(
-            boxes.is_empty(),
-            "Current implementation should return empty boxes"
-        );
+        println!("Found {} unspent reserve boxes", boxes.len());
 
         println!("✓ Basic scanner functionality test passed");
         Ok(())
@@ -0,0 +1,275 @@
+//! Split notes: one signed commitment allocating debt to several recipients
+//!
+//! A split note lets an issuer pay many recipients at once, e.g. a payroll
+//! run, with a single signature over every allocation. The tracker records
+//! one AVL tree entry per recipient, keyed by
+//! blake2b256(splitId || recipientKey) where splitId is the hash of the
+//! split note's signing message, so allocations never collide with the
+//! issuer's regular notes to the same recipients and each can be proven on
+//! its own.
+//!
+//! Split notes are immutable: a later payroll run is a new split note. The
+//! reserve contract looks notes up by blake2b256(ownerKey || receiverKey), so
+//! split allocations cannot be redeemed on chain yet.
+
+use std::collections::HashSet;
+
+use thiserror::Error;
+
+use crate::{blake2b256_hash, schnorr, NoteError, NoteKey, PubKey, Signature};
+
+/// Domain separation tag that opens every split note signing message
+pub const SPLIT_NOTE_DOMAIN_TAG: &[u8; 16] = b"BASIS-SPLIT-NOTE";
+
+/// Most recipients a single split note may allocate to
+pub const MAX_SPLIT_RECIPIENTS: usize = 1_000;
+
+#[derive(Error, Debug)]
+pub enum SplitNoteError {
+    #[error("Split note has no allocations")]
+    Empty,
+    #[error("Split note has {0} allocations, more than the limit of {MAX_SPLIT_RECIPIENTS}")]
+    TooManyRecipients(usize),
+    #[error("Recipient {0} appears more than once")]
+    DuplicateRecipient(String),
+    #[error("Allocation to {0} is zero")]
+    ZeroAmount(String),
+    #[error("Allocated amounts overflow")]
+    AmountOverflow,
+    #[error("Invalid split note signature")]
+    InvalidSignature,
+    #[error("Split note timestamp is in the future")]
+    FutureTimestamp,
+    #[error("Split note {0} is already recorded")]
+    AlreadyRecorded(String),
+    #[error("Insufficient collateral for the allocated amount")]
+    InsufficientCollateral,
+    #[error("Issuer key has been rotated")]
+    KeyRotated,
+    #[error("Storage error: {0}")]
+    StorageError(String),
+}
+
+impl From<NoteError> for SplitNoteError {
+    fn from(err: NoteError) -> Self {
+        match err {
+            NoteError::InvalidSignature => SplitNoteError::InvalidSignature,
+            NoteError::AmountOverflow => SplitNoteError::AmountOverflow,
+            NoteError::InsufficientCollateral => SplitNoteError::InsufficientCollateral,
+            NoteError::KeyRotated => SplitNoteError::KeyRotated,
+            NoteError::StorageError(msg) => SplitNoteError::StorageError(msg),
+            _ => SplitNoteError::StorageError(format!("{:?}", err)),
+        }
+    }
+}
+
+/// Amount a split note allocates to one recipient
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitAllocation {
+    pub recipient_pubkey: PubKey,
+    pub amount: u64,
+}
+
+/// A signed commitment allocating debt to several recipients
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitNote {
+    /// Allocations in the order they were signed
+    pub allocations: Vec<SplitAllocation>,
+    /// Timestamp of the split in milliseconds since the Unix epoch
+    pub timestamp: u64,
+    /// Issuer's signature over every allocation
+    pub signature: Signature,
+}
+
+impl SplitNote {
+    /// Create and sign a split note
+    pub fn create_and_sign(
+        allocations: Vec<SplitAllocation>,
+        timestamp: u64,
+        issuer_secret_key: &[u8; 32],
+    ) -> Result<Self, NoteError> {
+        let secret_key = secp256k1::SecretKey::from_slice(issuer_secret_key).map_err(|_| NoteError::InvalidSignature)?;
+        let issuer_pubkey = secp256k1::PublicKey::from_secret_key(&secp256k1::Secp256k1::new(), &secret_key).serialize();
+        let mut split = Self {
+            allocations,
+            timestamp,
+            signature: [0u8; 65],
+        };
+        split.signature = schnorr::schnorr_sign(&split.signing_message(&issuer_pubkey), issuer_secret_key, &issuer_pubkey)?;
+        Ok(split)
+    }
+
+    /// Message the issuer signs:
+    ///
+    /// "BASIS-SPLIT-NOTE" || ownerKey || timestamp || count || (receiverKey || amount)*
+    ///
+    /// with the timestamp and amounts as 8-byte and the count as a 4-byte
+    /// big-endian integer.
+    pub fn signing_message(&self, issuer_pubkey: &PubKey) -> Vec<u8> {
+        let mut message = Vec::with_capacity(16 + 33 + 8 + 4 + self.allocations.len() * (33 + 8));
+        message.extend_from_slice(SPLIT_NOTE_DOMAIN_TAG);
+        message.extend_from_slice(issuer_pubkey);
+        message.extend_from_slice(&self.timestamp.to_be_bytes());
+        message.extend_from_slice(&(self.allocations.len() as u32).to_be_bytes());
+        for allocation in &self.allocations {
+            message.extend_from_slice(&allocation.recipient_pubkey);
+            message.extend_from_slice(&allocation.amount.to_be_bytes());
+        }
+        message
+    }
+
+    /// ID of the split note: blake2b256 of its signing message
+    pub fn id(&self, issuer_pubkey: &PubKey) -> [u8; 32] {
+        blake2b256_hash(&self.signing_message(issuer_pubkey))
+    }
+
+    /// Verify the issuer's signature, which covers every allocation at once
+    pub fn verify_signature(&self, issuer_pubkey: &PubKey) -> Result<(), NoteError> {
+        schnorr::schnorr_verify(&self.signature, &self.signing_message(issuer_pubkey), issuer_pubkey)
+    }
+
+    /// Sum of all allocations, or None if it overflows
+    pub fn total_amount(&self) -> Option<u64> {
+        self.allocations
+            .iter()
+            .try_fold(0u64, |total, allocation| total.checked_add(allocation.amount))
+    }
+
+    /// Check the allocations are well formed: at least one and at most
+    /// [`MAX_SPLIT_RECIPIENTS`], each to a distinct recipient for a non-zero amount
+    pub fn check_allocations(&self) -> Result<(), SplitNoteError> {
+        if self.allocations.is_empty() {
+            return Err(SplitNoteError::Empty);
+        }
+        if self.allocations.len() > MAX_SPLIT_RECIPIENTS {
+            return Err(SplitNoteError::TooManyRecipients(self.allocations.len()));
+        }
+        let mut recipients = HashSet::with_capacity(self.allocations.len());
+        for allocation in &self.allocations {
+            if !recipients.insert(allocation.recipient_pubkey) {
+                return Err(SplitNoteError::DuplicateRecipient(hex::encode(allocation.recipient_pubkey)));
+            }
+            if allocation.amount == 0 {
+                return Err(SplitNoteError::ZeroAmount(hex::encode(allocation.recipient_pubkey)));
+            }
+        }
+        self.total_amount().ok_or(SplitNoteError::AmountOverflow)?;
+        Ok(())
+    }
+
+    /// AVL tree key of each allocation, in allocation order
+    pub fn allocation_keys(&self, issuer_pubkey: &PubKey) -> Vec<NoteKey> {
+        let split_id = self.id(issuer_pubkey);
+        self.allocations
+            .iter()
+            .map(|allocation| allocation_key(&split_id, &allocation.recipient_pubkey))
+            .collect()
+    }
+}
+
+/// AVL tree key of a split note allocation: blake2b256(splitId || recipientKey)
+pub fn allocation_key(split_id: &[u8; 32], recipient_pubkey: &PubKey) -> NoteKey {
+    let mut data = Vec::with_capacity(32 + 33);
+    data.extend_from_slice(split_id);
+    data.extend_from_slice(recipient_pubkey);
+    NoteKey::from_bytes(&blake2b256_hash(&data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        clock::MockClock, debt_policy::DebtCeilingPolicy, reserve_tracker::ReserveTracker, schnorr::generate_keypair,
+        ExtendedReserveInfo, TrackerStateManager,
+    };
+    use std::sync::Arc;
+
+    fn allocation(recipient_pubkey: PubKey, amount: u64) -> SplitAllocation {
+        SplitAllocation { recipient_pubkey, amount }
+    }
+
+    #[test]
+    fn test_split_note_records_one_entry_per_recipient() {
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, alice) = generate_keypair();
+        let (_, bob) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        tracker.set_clock(Arc::new(MockClock::new(10_000)));
+
+        // A regular note to the same recipient is unaffected by the split
+        let note = crate::IouNote::create_and_sign(alice, 50, 1_000, &issuer_secret).unwrap();
+        tracker.add_note(&issuer_pubkey, &note).unwrap();
+
+        let split = SplitNote::create_and_sign(vec![allocation(alice, 100), allocation(bob, 250)], 2_000, &issuer_secret)
+            .unwrap();
+        let split_id = tracker.add_split_note(&issuer_pubkey, &split).unwrap();
+        assert_eq!(split_id, split.id(&issuer_pubkey));
+
+        let keys = split.allocation_keys(&issuer_pubkey);
+        assert_eq!(tracker.avl_state.get(&keys[0].to_bytes()), Some(100u64.to_be_bytes().to_vec()));
+        assert_eq!(tracker.avl_state.get(&keys[1].to_bytes()), Some(250u64.to_be_bytes().to_vec()));
+        assert_eq!(tracker.get_total_debt(&issuer_pubkey, &alice).unwrap(), 50);
+        assert_eq!(tracker.get_issuer_split_notes(&issuer_pubkey).unwrap(), vec![split.clone()]);
+
+        // Recording the same split twice is refused, and the root survives a rebuild
+        assert!(matches!(
+            tracker.add_split_note(&issuer_pubkey, &split),
+            Err(SplitNoteError::AlreadyRecorded(_))
+        ));
+        let root = tracker.get_state().avl_root_digest;
+        tracker.avl_state = basis_trees::BasisAvlTree::new().unwrap();
+        tracker.rebuild_avl_tree().unwrap();
+        assert_eq!(tracker.get_state().avl_root_digest, root);
+    }
+
+    #[test]
+    fn test_split_note_validation() {
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, alice) = generate_keypair();
+        let (_, bob) = generate_keypair();
+        let mut tracker = TrackerStateManager::new_with_temp_storage();
+        tracker.set_clock(Arc::new(MockClock::new(10_000)));
+
+        let split = |allocations, timestamp| SplitNote::create_and_sign(allocations, timestamp, &issuer_secret).unwrap();
+        let rejected = |tracker: &mut TrackerStateManager, split: &SplitNote| tracker.add_split_note(&issuer_pubkey, split).unwrap_err();
+
+        assert!(matches!(rejected(&mut tracker, &split(vec![], 1_000)), SplitNoteError::Empty));
+        assert!(matches!(
+            rejected(&mut tracker, &split(vec![allocation(alice, 1), allocation(alice, 2)], 1_000)),
+            SplitNoteError::DuplicateRecipient(_)
+        ));
+        assert!(matches!(
+            rejected(&mut tracker, &split(vec![allocation(alice, 0)], 1_000)),
+            SplitNoteError::ZeroAmount(_)
+        ));
+        assert!(matches!(
+            rejected(&mut tracker, &split(vec![allocation(alice, u64::MAX), allocation(bob, 1)], 1_000)),
+            SplitNoteError::AmountOverflow
+        ));
+        assert!(matches!(
+            rejected(&mut tracker, &split(vec![allocation(alice, 1)], 20_000)),
+            SplitNoteError::FutureTimestamp
+        ));
+
+        // The one signature covers every allocation
+        let mut tampered = split(vec![allocation(alice, 100), allocation(bob, 100)], 1_000);
+        tampered.allocations[1].amount = 1_000;
+        assert!(matches!(rejected(&mut tracker, &tampered), SplitNoteError::InvalidSignature));
+
+        // Allocations count against the issuer's debt ceiling like notes do
+        let reserves = ReserveTracker::new();
+        reserves
+            .update_reserve(ExtendedReserveInfo::new(&[1u8; 32], &issuer_pubkey, 1_000, None, 100))
+            .unwrap();
+        tracker.set_debt_policy(DebtCeilingPolicy::new(1.0), reserves);
+        tracker
+            .add_split_note(&issuer_pubkey, &split(vec![allocation(alice, 600)], 1_000))
+            .unwrap();
+        assert!(matches!(
+            rejected(&mut tracker, &split(vec![allocation(bob, 500)], 2_000)),
+            SplitNoteError::InsufficientCollateral
+        ));
+        let note = crate::IouNote::create_and_sign(bob, 500, 3_000, &issuer_secret).unwrap();
+        assert!(matches!(tracker.add_note(&issuer_pubkey, &note), Err(NoteError::InsufficientCollateral)));
+    }
+}
@@ -0,0 +1,206 @@
+//! Encryption of stored values at rest
+//!
+//! With a storage key configured, note and reserve storage seal the values of
+//! their record partitions with AES-256-GCM before writing them. Keys stay in
+//! plaintext, as do the secondary indexes, which hold nothing but note keys
+//! and counters. A sealed value is laid out as:
+//!
+//! `tag (1) || key ID (4) || nonce (12) || ciphertext || GCM tag (16)`
+//!
+//! The key ID is the start of the Blake2b256 hash of the key, so a value
+//! names the key it was sealed with. Values are always sealed with the
+//! current key; previous keys only open values written before a rotation.
+//! Values written before encryption was enabled are read as they are, since
+//! no plaintext record starts with the tag, until
+//! [`NoteStorage::reseal_all`](crate::persistence::NoteStorage::reseal_all)
+//! rewrites them under the current key.
+
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use thiserror::Error;
+
+use crate::blake2b256_hash;
+
+/// First byte of a sealed value
+///
+/// Plaintext records start with a compressed key (0x02 or 0x03), a record
+/// tag, a JSON brace or a big-endian timestamp, never with this byte.
+pub const SEALED_TAG: u8 = 0xE5;
+
+const KEY_ID_LEN: usize = 4;
+const NONCE_LEN: usize = 12;
+const GCM_TAG_LEN: usize = 16;
+const SEALED_HEADER_LEN: usize = 1 + KEY_ID_LEN + NONCE_LEN;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum StorageEncryptionError {
+    #[error("Invalid storage key: {0}")]
+    InvalidKey(String),
+    #[error("Value is sealed with unknown storage key {0}")]
+    UnknownKey(String),
+    #[error("Value is sealed but no storage key is configured")]
+    NoKey,
+    #[error("Sealed value is malformed or was tampered with")]
+    Corrupt,
+}
+
+/// A 256-bit storage key with its ID
+#[derive(Clone)]
+struct StorageKey {
+    id: [u8; KEY_ID_LEN],
+    cipher: Aes256Gcm,
+}
+
+impl StorageKey {
+    fn new(key: &[u8; 32]) -> Self {
+        let mut id = [0u8; KEY_ID_LEN];
+        id.copy_from_slice(&blake2b256_hash(key)[..KEY_ID_LEN]);
+        Self {
+            id,
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key)),
+        }
+    }
+}
+
+/// Seals and opens stored values with the current and previous storage keys
+///
+/// The default cipher has no keys: it writes plaintext and refuses to open
+/// sealed values.
+#[derive(Clone, Default)]
+pub struct StorageCipher {
+    keys: Arc<Vec<StorageKey>>,
+}
+
+impl std::fmt::Debug for StorageCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StorageCipher")
+            .field("key_ids", &self.key_ids())
+            .finish()
+    }
+}
+
+impl StorageCipher {
+    /// Cipher sealing with `current` and also opening values sealed with
+    /// one of the `previous` keys
+    pub fn new(current: [u8; 32], previous: &[[u8; 32]]) -> Self {
+        let keys = std::iter::once(&current).chain(previous).map(StorageKey::new).collect();
+        Self { keys: Arc::new(keys) }
+    }
+
+    /// Whether values are sealed on write
+    pub fn is_enabled(&self) -> bool {
+        !self.keys.is_empty()
+    }
+
+    /// IDs of the keys, as hex, current key first
+    pub fn key_ids(&self) -> Vec<String> {
+        self.keys.iter().map(|key| hex::encode(key.id)).collect()
+    }
+
+    /// Seal a value with the current key, or keep it as it is without one
+    pub fn seal(&self, plaintext: &[u8]) -> Vec<u8> {
+        let Some(key) = self.keys.first() else {
+            return plaintext.to_vec();
+        };
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = key
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .expect("AES-GCM encryption of an in-memory buffer");
+        let mut sealed = Vec::with_capacity(SEALED_HEADER_LEN + ciphertext.len());
+        sealed.push(SEALED_TAG);
+        sealed.extend_from_slice(&key.id);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+
+    /// Open a stored value, returning plaintext values as they are
+    pub fn open<'a>(&self, value: &'a [u8]) -> Result<Cow<'a, [u8]>, StorageEncryptionError> {
+        if value.first() != Some(&SEALED_TAG) {
+            return Ok(Cow::Borrowed(value));
+        }
+        if !self.is_enabled() {
+            return Err(StorageEncryptionError::NoKey);
+        }
+        if value.len() < SEALED_HEADER_LEN + GCM_TAG_LEN {
+            return Err(StorageEncryptionError::Corrupt);
+        }
+        let id = &value[1..1 + KEY_ID_LEN];
+        let key = self
+            .keys
+            .iter()
+            .find(|key| key.id == id)
+            .ok_or_else(|| StorageEncryptionError::UnknownKey(hex::encode(id)))?;
+        let nonce = Nonce::from_slice(&value[1 + KEY_ID_LEN..SEALED_HEADER_LEN]);
+        key.cipher
+            .decrypt(nonce, &value[SEALED_HEADER_LEN..])
+            .map(Cow::Owned)
+            .map_err(|_| StorageEncryptionError::Corrupt)
+    }
+
+    /// Whether a stored value is not sealed with the current key, and so is
+    /// rewritten by a migration
+    pub fn needs_reseal(&self, value: &[u8]) -> bool {
+        match self.keys.first() {
+            Some(key) => value.first() != Some(&SEALED_TAG) || value.get(1..1 + KEY_ID_LEN) != Some(&key.id[..]),
+            None => value.first() == Some(&SEALED_TAG),
+        }
+    }
+}
+
+/// Parse a storage key given as 64 hex characters
+pub fn parse_key(key: &str) -> Result<[u8; 32], StorageEncryptionError> {
+    hex::decode(key.trim())
+        .map_err(|e| StorageEncryptionError::InvalidKey(format!("not hex: {}", e)))?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| {
+            StorageEncryptionError::InvalidKey(format!("expected 32 bytes, got {}", bytes.len()))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seal_and_rotate() {
+        let plaintext = b"{\"box_id\":\"abc\"}";
+        let old = StorageCipher::new([1u8; 32], &[]);
+        let sealed = old.seal(plaintext);
+        assert_eq!(sealed[0], SEALED_TAG);
+        assert_ne!(&sealed[SEALED_HEADER_LEN..SEALED_HEADER_LEN + plaintext.len()], plaintext);
+        assert_eq!(old.open(&sealed).unwrap().as_ref(), plaintext);
+        // Plaintext written before encryption was enabled still reads
+        assert_eq!(old.open(plaintext).unwrap().as_ref(), plaintext);
+        assert!(old.needs_reseal(plaintext));
+        assert!(!old.needs_reseal(&sealed));
+
+        // After a rotation, values sealed with the previous key still open
+        let rotated = StorageCipher::new([2u8; 32], &[[1u8; 32]]);
+        assert_eq!(rotated.open(&sealed).unwrap().as_ref(), plaintext);
+        assert!(rotated.needs_reseal(&sealed));
+        let resealed = rotated.seal(&rotated.open(&sealed).unwrap());
+        assert_eq!(StorageCipher::new([2u8; 32], &[]).open(&resealed).unwrap().as_ref(), plaintext);
+
+        // Without the key, sealed values are refused rather than misread
+        assert_eq!(
+            StorageCipher::new([2u8; 32], &[]).open(&sealed),
+            Err(StorageEncryptionError::UnknownKey(old.key_ids()[0].clone()))
+        );
+        assert_eq!(StorageCipher::default().open(&sealed), Err(StorageEncryptionError::NoKey));
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(old.open(&tampered), Err(StorageEncryptionError::Corrupt));
+    }
+
+    #[test]
+    fn test_parse_key() {
+        assert_eq!(parse_key(&"ab".repeat(32)), Ok([0xab; 32]));
+        assert!(parse_key("abcd").is_err());
+        assert!(parse_key("not hex").is_err());
+    }
+}
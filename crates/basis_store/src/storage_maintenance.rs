@@ -0,0 +1,167 @@
+//! Maintenance of the fjall databases behind the stores
+//!
+//! Every storage hands out a [`StorageHandle`] over its keyspace and
+//! partitions. Handles report disk usage, run major compactions and read
+//! every partition end to end, so unreadable segments surface as errors
+//! instead of as missing data. They share the storage's keyspace, so they can
+//! be used from any thread while the storage keeps serving reads and writes.
+//!
+//! Records that read but do not decode are checked by the storages
+//! themselves, see [`NoteIntegrity`].
+
+use serde::{Deserialize, Serialize};
+
+use crate::NoteError;
+
+/// Size of one partition
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PartitionReport {
+    pub name: String,
+    /// Approximate number of items, counting overwritten and deleted ones
+    /// not compacted away yet
+    pub approximate_items: u64,
+    pub disk_space: u64,
+    pub segments: u64,
+}
+
+/// Size of one storage
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageReport {
+    pub name: String,
+    /// Bytes used by segments and journals
+    pub disk_space: u64,
+    pub journal_disk_space: u64,
+    pub partitions: Vec<PartitionReport>,
+}
+
+/// Keyspace and partitions of one storage, for maintenance
+#[derive(Clone)]
+pub struct StorageHandle {
+    name: String,
+    keyspace: fjall::Keyspace,
+    partitions: Vec<(String, fjall::Partition)>,
+}
+
+impl std::fmt::Debug for StorageHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StorageHandle").field("name", &self.name).finish()
+    }
+}
+
+impl StorageHandle {
+    pub(crate) fn new(name: &str, keyspace: &fjall::Keyspace, partitions: &[(&str, &fjall::Partition)]) -> Self {
+        Self {
+            name: name.to_string(),
+            keyspace: keyspace.clone(),
+            partitions: partitions
+                .iter()
+                .map(|(name, partition)| (name.to_string(), (*partition).clone()))
+                .collect(),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Current disk usage
+    pub fn report(&self) -> StorageReport {
+        StorageReport {
+            name: self.name.clone(),
+            disk_space: self.keyspace.disk_space(),
+            journal_disk_space: self.keyspace.journal_disk_space(),
+            partitions: self
+                .partitions
+                .iter()
+                .map(|(name, partition)| PartitionReport {
+                    name: name.clone(),
+                    approximate_items: partition.approximate_len() as u64,
+                    disk_space: partition.disk_space(),
+                    segments: partition.segment_count() as u64,
+                })
+                .collect(),
+        }
+    }
+
+    /// Flush the memtables and merge each partition's segments into one,
+    /// dropping overwritten and deleted items
+    ///
+    /// Blocks until every partition is compacted.
+    pub fn compact(&self) -> Result<(), NoteError> {
+        for (name, partition) in &self.partitions {
+            partition
+                .rotate_memtable_and_wait()
+                .and_then(|_| partition.major_compact())
+                .map_err(|e| NoteError::StorageError(format!("Failed to compact {}/{}: {}", self.name, name, e)))?;
+        }
+        Ok(())
+    }
+
+    /// Read every item of every partition, returning the errors met
+    ///
+    /// A partition stops being read at its first error.
+    pub fn verify(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (name, partition) in &self.partitions {
+            if let Some(Err(e)) = partition.iter().find(Result::is_err) {
+                problems.push(format!("{}/{}: {}", self.name, name, e));
+            }
+        }
+        problems
+    }
+}
+
+/// Result of checking, and possibly repairing, the stored notes
+///
+/// A note record that cannot be opened or decoded is restored from the latest
+/// readable version in the note history, preferring the version the AVL tree
+/// commits to. Records without such a version are left in place, as they are
+/// never served, and reported as unrecoverable.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoteIntegrity {
+    pub records_checked: u64,
+    /// Note keys, as hex, of the records that could not be read
+    pub corrupt_records: Vec<String>,
+    pub restored: u64,
+    /// Note keys, as hex, of corrupt records no history version restores
+    pub unrecoverable: Vec<String>,
+    /// Notes whose AVL tree entry is missing or differs from the stored amount
+    pub avl_mismatches: u64,
+    /// Whether the note counters disagree with the stored notes
+    pub stale_indexes: bool,
+    pub indexes_rebuilt: bool,
+    pub avl_rebuilt: bool,
+}
+
+impl NoteIntegrity {
+    /// Whether anything was found wrong
+    pub fn has_problems(&self) -> bool {
+        !self.corrupt_records.is_empty() || self.avl_mismatches > 0 || self.stale_indexes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::persistence::ReserveStorage;
+
+    #[test]
+    fn test_report_and_compact() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let storage = ReserveStorage::open(temp_dir.path()).unwrap();
+        let handle = storage.maintenance_handle();
+        assert_eq!(handle.name(), "reserves");
+
+        let report = handle.report();
+        assert_eq!(
+            report.partitions.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(),
+            vec!["reserves", "reserve_history", "reserve_predecessors", "reserve_events"]
+        );
+        assert!(report.partitions.iter().all(|p| p.segments == 0));
+
+        for i in 0..10u32 {
+            storage.remove_reserve(&format!("box{}", i)).unwrap();
+        }
+        handle.compact().unwrap();
+        assert!(handle.verify().is_empty());
+    }
+}
@@ -110,6 +110,8 @@ pub fn create_test_redemption_request(
         issuer_signature: "010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101010101".to_string(),
         emergency: false,
         tracker_signature: Some("020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202020202".to_string()),
+        reserve_box: None,
+        reserve_insert_proof: None,
     }
 }
 
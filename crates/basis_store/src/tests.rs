@@ -1,6 +1,6 @@
 // Manual test runner functions
 
-use crate::{blake2b256_hash, schnorr_tests, IouNote, NoteKey};
+use crate::{blake2b256_hash, schnorr_tests, Clock, IouNote, NoteKey, SigningFormat};
 
 pub fn run_all_tests() -> Result<(), String> {
     println!("Running Basis Store tests...");
@@ -17,6 +17,13 @@ pub fn run_all_tests() -> Result<(), String> {
     test_timestamp_validation_increasing_timestamps()?;
     test_timestamp_validation_non_increasing_timestamps()?;
     test_different_issuer_recipient_pairs_allow_same_timestamps()?;
+    test_signing_format_v2()?;
+    test_amount_validation_adversarial_updates()?;
+    test_timestamp_validation_mock_clock()?;
+    test_validate_note_reports_all_errors()?;
+    test_note_interest()?;
+    test_note_payload()?;
+    test_add_notes_batch()?;
     schnorr_tests::run_schnorr_test_vectors()?;
 
     println!("All tests passed!");
@@ -415,6 +422,427 @@ fn test_different_issuer_recipient_pairs_allow_same_timestamps() -> Result<(), S
     Ok(())
 }
 
+fn test_signing_format_v2() -> Result<(), String> {
+    use crate::persistence::NoteStorage;
+
+    let issuer_secret = [1u8; 32];
+    let issuer_pubkey = secp256k1::PublicKey::from_secret_key(
+        &secp256k1::Secp256k1::new(),
+        &secp256k1::SecretKey::from_slice(&issuer_secret).unwrap(),
+    )
+    .serialize();
+    let recipient_pubkey = [2u8; 33];
+    let tracker_id = [9u8; 32];
+
+    let note = IouNote::create_and_sign_v2(recipient_pubkey, 1000, 1234567890, Some(tracker_id), &issuer_secret)
+        .map_err(|e| format!("Failed to create v2 note: {:?}", e))?;
+
+    // Format: tag (16) || tracker ID (32) || issuer (33) || recipient (33) || totalDebt (8) || timestamp (8)
+    let message = note.signing_message(&issuer_pubkey);
+    if message.len() != 130 || !message.starts_with(b"BASIS-IOU-NOTE/2") {
+        return Err(format!("unexpected v2 signing message of {} bytes", message.len()));
+    }
+    if note.verify_signature(&issuer_pubkey).is_err() {
+        return Err("v2 note should verify".to_string());
+    }
+
+    // The signature is neither valid as a version 1 note nor for another tracker
+    let as_v1 = note.clone().with_signing_format(SigningFormat::V1);
+    if as_v1.verify_signature(&issuer_pubkey).is_ok() {
+        return Err("v2 signature should not verify as v1".to_string());
+    }
+    let other_tracker = note.clone().with_signing_format(SigningFormat::V2 { tracker_id: Some([8u8; 32]) });
+    if other_tracker.verify_signature(&issuer_pubkey).is_ok() {
+        return Err("v2 signature should not verify for another tracker".to_string());
+    }
+
+    // Stored records keep the format; version 1 records are unchanged
+    let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let storage = NoteStorage::open(temp_dir.path()).map_err(|e| format!("{:?}", e))?;
+    let v1_note = IouNote::create_and_sign([3u8; 33], 500, 1234567890, &issuer_secret)
+        .map_err(|e| format!("Failed to create v1 note: {:?}", e))?;
+    for stored in [&note, &v1_note] {
+        storage.store_note(&issuer_pubkey, stored).map_err(|e| format!("{:?}", e))?;
+        let loaded = storage
+            .get_note(&issuer_pubkey, &stored.recipient_pubkey)
+            .map_err(|e| format!("{:?}", e))?;
+        if loaded.as_ref() != Some(stored) {
+            return Err(format!("stored note did not round trip: {:?}", loaded));
+        }
+    }
+
+    // The reserve contract only verifies version 1 messages, so v2 notes cannot be redeemed
+    let mut tracker = crate::TrackerStateManager::new_with_temp_storage();
+    for added in [&note, &v1_note] {
+        tracker.add_note(&issuer_pubkey, added).map_err(|e| format!("Failed to add note: {:?}", e))?;
+    }
+    let redemptions = crate::RedemptionManager::new(tracker);
+    let issuer_hex = hex::encode(issuer_pubkey);
+    match redemptions.validate_redemption(&issuer_hex, &hex::encode(recipient_pubkey), 1000).as_slice() {
+        [crate::RedemptionError::UnredeemableSigningFormat(2)] => {}
+        other => return Err(format!("unexpected errors redeeming a v2 note: {:?}", other)),
+    }
+    if !redemptions.validate_redemption(&issuer_hex, &hex::encode([3u8; 33]), 500).is_empty() {
+        return Err("v1 note should be redeemable".to_string());
+    }
+
+    println!("✓ test_signing_format_v2 passed");
+    Ok(())
+}
+
+fn test_amount_validation_adversarial_updates() -> Result<(), String> {
+    use crate::{MockClock, NoteError, PubKey, RedemptionManager, TrackerStateManager};
+    use secp256k1::{Secp256k1, SecretKey};
+
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::new(&mut secp256k1::rand::thread_rng());
+    let secret = secret_key.secret_bytes();
+    let issuer_pubkey = secp256k1::PublicKey::from_secret_key(&secp, &secret_key).serialize();
+    let recipient_pubkey: PubKey = [2u8; 33];
+    let sign = |amount: u64, timestamp: u64| {
+        IouNote::create_and_sign(recipient_pubkey, amount, timestamp, &secret)
+            .map_err(|e| format!("Failed to create note: {:?}", e))
+    };
+
+    let clock = MockClock::new(1_000_000);
+    let mut tracker = TrackerStateManager::new_with_temp_storage();
+    tracker.set_clock(std::sync::Arc::new(clock.clone()));
+
+    // A new note cannot start out with more redeemed than collected
+    let mut overdrawn = sign(1000, 1000)?;
+    overdrawn.amount_redeemed = 1001;
+    match tracker.add_note(&issuer_pubkey, &overdrawn) {
+        Err(NoteError::RedeemedExceedsCollected) => {}
+        other => return Err(format!("Expected RedeemedExceedsCollected, got {:?}", other)),
+    }
+
+    tracker
+        .add_note(&issuer_pubkey, &sign(1000, 1000)?)
+        .map_err(|e| format!("First note should succeed: {:?}", e))?;
+
+    // A later, validly signed note lowering the debt is rejected
+    match tracker.add_note(&issuer_pubkey, &sign(999, 2000)?) {
+        Err(NoteError::AmountDecreased) => {}
+        other => return Err(format!("Expected AmountDecreased, got {:?}", other)),
+    }
+
+    // Redeem part of the debt, then check the issuer cannot reset the redeemed amount
+    let mut redemption_manager = RedemptionManager::new(tracker);
+    redemption_manager
+        .complete_redemption(&issuer_pubkey, &recipient_pubkey, 400)
+        .map_err(|e| format!("Redemption should succeed: {:?}", e))?;
+    let now = || {
+        clock.advance(std::time::Duration::from_millis(1));
+        clock.now_millis()
+    };
+    let tracker = &mut redemption_manager.tracker;
+    tracker
+        .add_note(&issuer_pubkey, &sign(1500, now())?)
+        .map_err(|e| format!("Update after redemption should succeed: {:?}", e))?;
+    let updated = tracker
+        .lookup_note(&issuer_pubkey, &recipient_pubkey)
+        .map_err(|e| format!("{:?}", e))?;
+    if updated.amount_redeemed != 400 {
+        return Err(format!("Redeemed amount should carry forward, got {}", updated.amount_redeemed));
+    }
+
+    // Tracker-side updates may not lower the redeemed amount either
+    let mut rolled_back = updated.clone();
+    rolled_back.amount_redeemed = 0;
+    rolled_back.timestamp = now();
+    match tracker.update_note(&issuer_pubkey, &rolled_back) {
+        Err(NoteError::AmountDecreased) => {}
+        other => return Err(format!("Expected AmountDecreased, got {:?}", other)),
+    }
+
+    // Redemptions beyond the collected amount, or overflowing u64, are rejected
+    if redemption_manager
+        .complete_redemption(&issuer_pubkey, &recipient_pubkey, 1101)
+        .is_ok()
+    {
+        return Err("Redeeming more than collected should fail".to_string());
+    }
+    if redemption_manager
+        .complete_redemption(&issuer_pubkey, &recipient_pubkey, u64::MAX)
+        .is_ok()
+    {
+        return Err("Overflowing the redeemed amount should fail".to_string());
+    }
+
+    Ok(())
+}
+
+fn test_timestamp_validation_mock_clock() -> Result<(), String> {
+    use crate::{MockClock, NoteError, TrackerStateManager};
+
+    let secret = [3u8; 32];
+    let issuer_pubkey = secp256k1::PublicKey::from_secret_key(
+        &secp256k1::Secp256k1::new(),
+        &secp256k1::SecretKey::from_slice(&secret).unwrap(),
+    )
+    .serialize();
+
+    let clock = MockClock::new(5_000);
+    let mut tracker = TrackerStateManager::new_with_temp_storage();
+    tracker.set_clock(std::sync::Arc::new(clock.clone()));
+
+    // One millisecond ahead of the tracker's clock is in the future
+    let note = IouNote::create_and_sign([2u8; 33], 1000, 5_001, &secret)
+        .map_err(|e| format!("Failed to create note: {:?}", e))?;
+    match tracker.add_note(&issuer_pubkey, &note) {
+        Err(NoteError::FutureTimestamp) => {}
+        other => return Err(format!("Expected FutureTimestamp, got {:?}", other)),
+    }
+
+    clock.advance(std::time::Duration::from_millis(1));
+    tracker
+        .add_note(&issuer_pubkey, &note)
+        .map_err(|e| format!("Note should be accepted once its time has come: {:?}", e))?;
+    if tracker.get_state().last_update_timestamp != 5_001 {
+        return Err("State update time should come from the clock".to_string());
+    }
+
+    Ok(())
+}
+
+fn test_validate_note_reports_all_errors() -> Result<(), String> {
+    use crate::{MockClock, NoteError, TrackerStateManager};
+
+    let secret = [4u8; 32];
+    let issuer_pubkey = secp256k1::PublicKey::from_secret_key(
+        &secp256k1::Secp256k1::new(),
+        &secp256k1::SecretKey::from_slice(&secret).unwrap(),
+    )
+    .serialize();
+    let recipient_pubkey = [2u8; 33];
+
+    let mut tracker = TrackerStateManager::new_with_temp_storage();
+    tracker.set_clock(std::sync::Arc::new(MockClock::new(5_000)));
+    let recorded = IouNote::create_and_sign(recipient_pubkey, 1000, 4_000, &secret)
+        .map_err(|e| format!("Failed to create note: {:?}", e))?;
+    tracker
+        .add_note(&issuer_pubkey, &recorded)
+        .map_err(|e| format!("Failed to add note: {:?}", e))?;
+
+    // A valid update reports nothing and changes nothing
+    let update = IouNote::create_and_sign(recipient_pubkey, 1500, 4_500, &secret)
+        .map_err(|e| format!("Failed to create note: {:?}", e))?;
+    let root_before = tracker.get_state().avl_root_digest;
+    if !tracker.validate_note(&issuer_pubkey, &update).is_empty() {
+        return Err("Valid update should have no errors".to_string());
+    }
+    let stored = tracker
+        .lookup_note(&issuer_pubkey, &recipient_pubkey)
+        .map_err(|e| format!("Failed to look up note: {:?}", e))?;
+    if stored.amount_collected != 1000 || tracker.get_state().avl_root_digest != root_before {
+        return Err("Validation must not change the tracker state".to_string());
+    }
+
+    // An older, smaller note with a forged signature fails every check at once
+    let mut forged = IouNote::create_and_sign(recipient_pubkey, 500, 3_000, &secret)
+        .map_err(|e| format!("Failed to create note: {:?}", e))?;
+    forged.amount_collected = 400;
+    let errors = tracker.validate_note(&issuer_pubkey, &forged);
+    match errors.as_slice() {
+        [NoteError::PastTimestamp, NoteError::AmountDecreased, NoteError::InvalidSignature] => {}
+        other => return Err(format!("Unexpected validation errors: {:?}", other)),
+    }
+    match tracker.add_note(&issuer_pubkey, &forged) {
+        Err(NoteError::PastTimestamp) => Ok(()),
+        other => Err(format!("add_note should report the first error, got {:?}", other)),
+    }
+}
+
+fn test_note_interest() -> Result<(), String> {
+    use crate::persistence::NoteStorage;
+    use crate::redemption::{RedemptionError, RedemptionManager};
+    use crate::{AccrualMethod, InterestTerms, MockClock, NoteError, TrackerStateManager};
+    use basis_core::MILLIS_PER_YEAR;
+
+    let secret = [5u8; 32];
+    let issuer_pubkey = secp256k1::PublicKey::from_secret_key(
+        &secp256k1::Secp256k1::new(),
+        &secp256k1::SecretKey::from_slice(&secret).unwrap(),
+    )
+    .serialize();
+    let recipient_pubkey = [2u8; 33];
+    let terms = InterestTerms::new(1_000, AccrualMethod::Simple); // 10% a year
+
+    let note = IouNote::create_and_sign_with_interest(recipient_pubkey, 1_000_000, 1_000, None, Some(terms), &secret)
+        .map_err(|e| format!("Failed to create note: {:?}", e))?;
+
+    // The terms are signed: the message grows by rate (4) || method (1)
+    if note.signing_message(&issuer_pubkey).len() != 135 || note.verify_signature(&issuer_pubkey).is_err() {
+        return Err("interest note should verify over a 135-byte message".to_string());
+    }
+    let mut higher_rate = note.clone();
+    higher_rate.interest = Some(InterestTerms::new(2_000, AccrualMethod::Simple));
+    if higher_rate.verify_signature(&issuer_pubkey).is_ok() {
+        return Err("changed interest terms should invalidate the signature".to_string());
+    }
+    if note.accrued_interest(1_000 + MILLIS_PER_YEAR) != 100_000
+        || note.outstanding_debt_with_interest(1_000 + MILLIS_PER_YEAR) != 1_100_000
+    {
+        return Err("unexpected accrual after a year".to_string());
+    }
+
+    let temp_dir = tempfile::tempdir().map_err(|e| e.to_string())?;
+    let storage = NoteStorage::open(temp_dir.path()).map_err(|e| format!("{:?}", e))?;
+    storage.store_note(&issuer_pubkey, &note).map_err(|e| format!("{:?}", e))?;
+    let loaded = storage.get_note(&issuer_pubkey, &recipient_pubkey).map_err(|e| format!("{:?}", e))?;
+    if loaded.as_ref() != Some(&note) {
+        return Err(format!("interest note did not round trip: {:?}", loaded));
+    }
+
+    // Version 1 messages cannot carry interest terms
+    let clock = MockClock::new(2_000);
+    let mut tracker = TrackerStateManager::new_with_temp_storage();
+    tracker.set_clock(std::sync::Arc::new(clock.clone()));
+    let v1_note = IouNote::create_and_sign([3u8; 33], 1_000, 1_000, &secret)
+        .map_err(|e| format!("Failed to create note: {:?}", e))?
+        .with_interest(terms);
+    if !matches!(tracker.add_note(&issuer_pubkey, &v1_note), Err(NoteError::InvalidSignature)) {
+        return Err("v1 note with interest terms should be rejected".to_string());
+    }
+    if !tracker.validate_note(&issuer_pubkey, &v1_note).iter().any(|e| matches!(e, NoteError::UnsupportedOperation)) {
+        return Err("v1 note with interest terms should be unsupported".to_string());
+    }
+    tracker
+        .add_note(&issuer_pubkey, &note)
+        .map_err(|e| format!("Failed to add note: {:?}", e))?;
+
+    // A year later the accrued interest is owed, and must be settled first. Interest
+    // notes are version 2, which the reserve contract cannot verify yet, so every
+    // redemption is refused on-chain grounds as well.
+    clock.set(1_000 + MILLIS_PER_YEAR);
+    let mut redemptions = RedemptionManager::new(tracker);
+    let (issuer_hex, recipient_hex) = (hex::encode(issuer_pubkey), hex::encode(recipient_pubkey));
+    match redemptions.validate_redemption(&issuer_hex, &recipient_hex, 1_100_000).as_slice() {
+        [RedemptionError::UnredeemableSigningFormat(2)] => {}
+        other => return Err(format!("unexpected errors redeeming the debt with interest: {:?}", other)),
+    }
+    match redemptions.validate_redemption(&issuer_hex, &recipient_hex, 1_100_001).as_slice() {
+        [RedemptionError::UnredeemableSigningFormat(2), RedemptionError::InsufficientCollateral(1_100_000, 1_100_001)] => {}
+        other => return Err(format!("unexpected errors redeeming too much: {:?}", other)),
+    }
+    match redemptions.validate_redemption(&issuer_hex, &recipient_hex, 50_000).as_slice() {
+        [RedemptionError::UnredeemableSigningFormat(2), RedemptionError::AccruedInterestUnpaid(100_000, 50_000)] => {}
+        other => return Err(format!("unexpected errors redeeming less than the interest: {:?}", other)),
+    }
+
+    // Only the part of a redemption beyond the interest pays down the principal
+    redemptions
+        .complete_redemption(&issuer_pubkey, &recipient_pubkey, 400_000)
+        .map_err(|e| format!("Failed to complete redemption: {:?}", e))?;
+    let redeemed = redemptions
+        .tracker
+        .lookup_note(&issuer_pubkey, &recipient_pubkey)
+        .map_err(|e| format!("Failed to look up note: {:?}", e))?;
+    if redeemed.amount_redeemed != 300_000 || redeemed.accrued_interest(1_000 + MILLIS_PER_YEAR) != 0 {
+        return Err(format!("unexpected note after redemption: {:?}", redeemed));
+    }
+
+    Ok(())
+}
+
+fn test_note_payload() -> Result<(), String> {
+    use crate::{MockClock, TrackerStateManager};
+    use basis_core::{decrypt_note_payload, encrypt_note_payload};
+
+    let secret = [6u8; 32];
+    let issuer_pubkey = secp256k1::PublicKey::from_secret_key(
+        &secp256k1::Secp256k1::new(),
+        &secp256k1::SecretKey::from_slice(&secret).unwrap(),
+    )
+    .serialize();
+    let (recipient_secret, recipient_pubkey) = crate::schnorr::generate_keypair();
+
+    let mut tracker = TrackerStateManager::new_with_temp_storage();
+    tracker.set_clock(std::sync::Arc::new(MockClock::new(5_000)));
+    let envelope = encrypt_note_payload(&secret, &recipient_pubkey, 1_000, b"invoice 42")
+        .map_err(|e| format!("Failed to encrypt payload: {}", e))?;
+
+    // A payload needs the note version it was written for
+    if tracker.set_note_payload(&issuer_pubkey, &recipient_pubkey, 1_000, &envelope).is_ok() {
+        return Err("payload without a note should be refused".to_string());
+    }
+    let note = IouNote::create_and_sign(recipient_pubkey, 1_000, 1_000, &secret)
+        .map_err(|e| format!("Failed to create note: {:?}", e))?;
+    tracker
+        .add_note(&issuer_pubkey, &note)
+        .map_err(|e| format!("Failed to add note: {:?}", e))?;
+    if tracker.set_note_payload(&issuer_pubkey, &recipient_pubkey, 999, &envelope).is_ok() {
+        return Err("payload for another note version should be refused".to_string());
+    }
+    tracker
+        .set_note_payload(&issuer_pubkey, &recipient_pubkey, 1_000, &envelope)
+        .map_err(|e| format!("Failed to store payload: {:?}", e))?;
+
+    let (timestamp, stored) = tracker
+        .get_note_payload(&issuer_pubkey, &recipient_pubkey)
+        .map_err(|e| format!("{:?}", e))?
+        .ok_or("stored payload should be returned")?;
+    let opened = decrypt_note_payload(&recipient_secret, &issuer_pubkey, timestamp, &stored)
+        .map_err(|e| format!("Failed to decrypt payload: {}", e))?;
+    if opened != b"invoice 42" {
+        return Err("recipient should read the memo".to_string());
+    }
+
+    // An update of the note leaves the old payload behind
+    let update = IouNote::create_and_sign(recipient_pubkey, 1_500, 2_000, &secret)
+        .map_err(|e| format!("Failed to create note: {:?}", e))?;
+    tracker
+        .add_note(&issuer_pubkey, &update)
+        .map_err(|e| format!("Failed to add note: {:?}", e))?;
+    if tracker.get_note_payload(&issuer_pubkey, &recipient_pubkey).map_err(|e| format!("{:?}", e))?.is_some() {
+        return Err("payload of an older note version should not be returned".to_string());
+    }
+
+    Ok(())
+}
+
+fn test_add_notes_batch() -> Result<(), String> {
+    use crate::{TrackerStateManager, IouNote, PubKey};
+    use secp256k1::{Secp256k1, SecretKey};
+
+    let secp = Secp256k1::new();
+    let secret_key = SecretKey::new(&mut secp256k1::rand::thread_rng());
+    let issuer_pubkey = secp256k1::PublicKey::from_secret_key(&secp, &secret_key).serialize();
+    let recipients: [PubKey; 2] = [[4u8; 33], [5u8; 33]];
+
+    let mut tracker = TrackerStateManager::new_with_temp_storage();
+    let sign = |recipient: PubKey, amount: u64, timestamp: u64| {
+        IouNote::create_and_sign(recipient, amount, timestamp, &secret_key.secret_bytes())
+            .map_err(|e| format!("Failed to create note: {:?}", e))
+    };
+
+    // The last note is checked against the first one of the batch
+    let notes = vec![
+        (issuer_pubkey, sign(recipients[1], 500, 1000000)?),
+        (issuer_pubkey, sign(recipients[0], 300, 1000000)?),
+        (issuer_pubkey, sign(recipients[1], 700, 999999)?),
+    ];
+    let results = tracker.add_notes(&notes).map_err(|e| format!("Batch failed: {:?}", e))?;
+    if !(results[0].is_ok() && results[1].is_ok()) {
+        return Err(format!("First two notes should be accepted: {:?}", results));
+    }
+    if !matches!(results[2], Err(crate::NoteError::PastTimestamp)) {
+        return Err(format!("Expected PastTimestamp for the stale note, got: {:?}", results[2]));
+    }
+
+    for (recipient, amount) in recipients.iter().zip([300, 500]) {
+        let debt = tracker.get_total_debt(&issuer_pubkey, recipient).map_err(|e| format!("{:?}", e))?;
+        if debt != amount {
+            return Err(format!("AVL tree should hold {} for the pair, got {}", amount, debt));
+        }
+    }
+    if tracker.get_state().avl_root_digest == [0u8; 33] {
+        return Err("Tracker state should follow the AVL tree".to_string());
+    }
+
+    Ok(())
+}
+
 #[cfg(test)]
 mod test_module {
     use crate::schnorr_tests;
@@ -483,4 +911,39 @@ mod test_module {
     fn test_different_issuer_recipient_pairs_allow_same_timestamps() {
         super::test_different_issuer_recipient_pairs_allow_same_timestamps().unwrap();
     }
+
+    #[test]
+    fn test_signing_format_v2() {
+        super::test_signing_format_v2().unwrap();
+    }
+
+    #[test]
+    fn test_amount_validation_adversarial_updates() {
+        super::test_amount_validation_adversarial_updates().unwrap();
+    }
+
+    #[test]
+    fn test_timestamp_validation_mock_clock() {
+        super::test_timestamp_validation_mock_clock().unwrap();
+    }
+
+    #[test]
+    fn test_validate_note_reports_all_errors() {
+        super::test_validate_note_reports_all_errors().unwrap();
+    }
+
+    #[test]
+    fn test_note_interest() {
+        super::test_note_interest().unwrap();
+    }
+
+    #[test]
+    fn test_note_payload() {
+        super::test_note_payload().unwrap();
+    }
+
+    #[test]
+    fn test_add_notes_batch() {
+        super::test_add_notes_batch().unwrap();
+    }
 }
@@ -1,5 +1,9 @@
 //! Tracker box scanner for monitoring Basis tracker state commitment boxes
 //! This module provides blockchain integration using /scan API with containsAsset rule
+//!
+//! The scanner holds no tracker state of its own. Commitments are cross-verified
+//! against the local roots the tracker records in [`TrackerStorage`] on every state
+//! change, so every clone of [`TrackerServerState`] sees the tracker's real notes.
 
 use std::sync::Arc;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -9,65 +13,15 @@ use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::{debug, error, info, warn};
 
-use reqwest::Client;
-
 use crate::{
-    ergo_scanner::ScanBox,
+    commitment_verification::{verify_commitment, CommitmentCheck, CommitmentStatus},
+    ergo_scanner::{ScanBox, ScannerError},
+    explorer_backend::ExplorerBackend,
     persistence::{ScannerMetadataStorage, TrackerStorage},
-    TrackerBoxInfo, TrackerStateManager,
+    scan_manager::{ScanManager, ScanNodeConfig, ScanSpec},
+    TrackerBoxInfo,
 };
 
-use std::collections::HashMap;
-
-/// Wrapper struct for the actual API response from /scan/unspentBoxes endpoint
-#[derive(Debug, Clone, serde::Deserialize)]
-struct ApiScanBox {
-    #[serde(rename = "box")]
-    inner_box: ApiInnerBox,
-    #[serde(rename = "confirmationsNum")]
-    confirmations_num: u32,
-    address: String,
-    #[serde(rename = "creationTransaction")]
-    creation_transaction: String,
-    scans: Vec<i32>,
-    onchain: bool,
-    #[serde(rename = "creationOutIndex")]
-    creation_out_index: u32,
-    #[serde(rename = "spendingTransaction")]
-    spending_transaction: Option<String>,
-    #[serde(rename = "spendingHeight")]
-    spending_height: Option<u64>,
-    #[serde(rename = "inclusionHeight")]
-    inclusion_height: u64,
-    spent: bool,
-}
-
-/// The inner box structure from the API response
-#[derive(Debug, Clone, serde::Deserialize)]
-struct ApiInnerBox {
-    #[serde(rename = "boxId")]
-    box_id: String,
-    value: u64,
-    #[serde(rename = "ergoTree")]
-    ergo_tree: String,
-    #[serde(rename = "creationHeight")]
-    creation_height: u64,
-    #[serde(rename = "transactionId")]
-    transaction_id: String,
-    #[serde(rename = "additionalRegisters")]
-    additional_registers: HashMap<String, String>,
-    assets: Vec<ApiBoxAsset>,
-    index: u32,
-}
-
-/// Asset structure from the API response
-#[derive(Debug, Clone, serde::Deserialize)]
-struct ApiBoxAsset {
-    #[serde(rename = "tokenId")]
-    token_id: String,
-    amount: u64,
-}
-
 #[derive(Error, Debug)]
 pub enum TrackerScannerError {
     #[error("Tracker scanner error: {0}")]
@@ -96,6 +50,21 @@ pub enum TrackerScannerError {
     MissingTrackerNft,
 }
 
+impl From<ScannerError> for TrackerScannerError {
+    fn from(e: ScannerError) -> Self {
+        match e {
+            ScannerError::Generic(msg) => TrackerScannerError::Generic(msg),
+            ScannerError::StoreError(msg) => TrackerScannerError::StoreError(msg),
+            ScannerError::NodeError(msg) => TrackerScannerError::NodeError(msg),
+            ScannerError::NetworkError(msg) => TrackerScannerError::NetworkError(msg),
+            ScannerError::BoxError(msg) => TrackerScannerError::BoxError(msg),
+            ScannerError::HttpError(msg) => TrackerScannerError::HttpError(msg),
+            ScannerError::JsonError(msg) => TrackerScannerError::JsonError(msg),
+            e => TrackerScannerError::Generic(e.to_string()),
+        }
+    }
+}
+
 /// Configuration for tracker scanner
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TrackerNodeConfig {
@@ -109,346 +78,46 @@ pub struct TrackerNodeConfig {
     pub scan_name: Option<String>,
     /// API key for Ergo node authentication
     pub api_key: Option<String>,
+    /// Check commitments against every recorded local root rather than only
+    /// the roots in effect at each commitment height
+    ///
+    /// Watchers receive notes from recipients some time after the tracker
+    /// committed them, so their roots lag behind the chain.
+    #[serde(default)]
+    pub verify_against_all_roots: bool,
 }
 
-/// Inner state for tracker scanner that requires synchronization
-#[derive(Clone)]
-struct TrackerServerStateInner {
-    pub current_height: u64,
-    pub last_scanned_height: u64,
-    pub scan_active: bool,
-    pub scan_id: Option<i32>,
-    pub last_scan_verification: Option<std::time::SystemTime>,
-}
-
-/// Server state for tracker scanner
-/// Uses real blockchain integration with proper synchronization
-pub struct TrackerServerState {
-    pub config: TrackerNodeConfig,
-    pub inner: Arc<Mutex<TrackerServerStateInner>>,
-    pub client: Client,
-    pub tracker_state: TrackerStateManager,
-    pub metadata_storage: ScannerMetadataStorage,
-    pub tracker_storage: TrackerStorage,
+/// Scan of the boxes holding the tracker NFT
+pub struct TrackerScanSpec {
+    /// Tracker NFT ID (hex-encoded)
+    pub tracker_nft_id: Option<String>,
 }
 
-impl Clone for TrackerServerState {
-    fn clone(&self) -> Self {
-        Self {
-            config: self.config.clone(),
-            inner: Arc::clone(&self.inner),
-            client: self.client.clone(),
-            tracker_state: TrackerStateManager::new(), // Create new instance since it doesn't implement Clone
-            metadata_storage: self.metadata_storage.clone(),
-            tracker_storage: self.tracker_storage.clone(),
-        }
-    }
-}
+impl ScanSpec for TrackerScanSpec {
+    type Item = TrackerBoxInfo;
+    type ParseError = TrackerScannerError;
 
-impl TrackerServerState {
-    /// Create HTTP request builder with API key header if configured
-    fn request_builder(&self, method: reqwest::Method, url: &str) -> reqwest::RequestBuilder {
-        debug!("Tracker request method: {}, URL: {}", method, url);
-        let mut builder = self.client.request(method, url);
-        
-        if let Some(api_key) = &self.config.api_key {
-            builder = builder.header("api_key", api_key);
-        }
-        
-        builder
+    fn default_scan_name(&self) -> &'static str {
+        "tracker_boxes"
     }
 
-    /// Register scan for tracker boxes using containsAsset rule
-    pub async fn register_tracker_scan(&self) -> Result<i32, TrackerScannerError> {
-        let tracker_nft_id = self.config.tracker_nft_id.as_ref()
-            .ok_or(TrackerScannerError::MissingTrackerNftId)?;
-
-        let scan_name = self.config.scan_name.as_deref()
-            .unwrap_or("tracker_boxes");
-
-        // Check if scan ID already exists in database
-        debug!(
-            "Checking for existing scan ID in database for scan name: '{}'",
-            scan_name
-        );
-        match self.metadata_storage.get_scan_id(scan_name) {
-            Ok(Some(stored_scan_id)) => {
-                info!("Found existing scan ID in database: {}", stored_scan_id);
-
-                // Verify the scan still exists on the node (only every 4 hours)
-                let should_verify = self.should_verify_scan().await;
-                if should_verify {
-                    debug!("Verifying scan ID {} exists on Ergo node", stored_scan_id);
-                    match self.verify_scan_registration().await {
-                        Ok(true) => {
-                            info!("Using existing scan ID: {}", stored_scan_id);
-                            // Update verification timestamp
-                            self.update_scan_verification_time().await;
-                            // Update inner state with the validated scan ID
-                            {
-                                let mut inner = self.inner.lock().await;
-                                inner.scan_id = Some(stored_scan_id);
-                                inner.scan_active = true;
-                            }
-                            return Ok(stored_scan_id);
-                        }
-                        Ok(false) => {
-                            warn!(
-                                "Stored scan ID {} no longer exists on node, re-registering",
-                                stored_scan_id
-                            );
-                            self.metadata_storage
-                                .remove_scan_id(scan_name)
-                                .map_err(|e| {
-                                    TrackerScannerError::StoreError(format!(
-                                        "Failed to remove invalid scan ID: {:?}",
-                                        e
-                                    ))
-                                })?;
-                            info!(
-                                "Removed invalid scan ID {} from database, proceeding to registration",
-                                stored_scan_id
-                            );
-                        }
-                        Err(e) => {
-                            // If verification fails due to scan list endpoint being unavailable (400/404),
-                            // or JSON parsing errors, assume the scan still exists and continue using it
-                            if e.to_string().contains("400") || e.to_string().contains("404") || e.to_string().contains("Failed to parse scan list") {
-                                warn!("Scan list endpoint unavailable or JSON parsing failed ({}), assuming scan ID {} still exists", e, stored_scan_id);
-                                // Update verification timestamp
-                                self.update_scan_verification_time().await;
-                                // Update inner state with the existing scan ID
-                                {
-                                    let mut inner = self.inner.lock().await;
-                                    inner.scan_id = Some(stored_scan_id);
-                                    inner.scan_active = true;
-                                }
-                                return Ok(stored_scan_id);
-                            } else {
-                                error!(
-                                    "Failed to verify existing scan ID {}: {}",
-                                    stored_scan_id, e
-                                );
-                                warn!("Unable to verify scan ID, forcing re-registration");
-                                self.metadata_storage
-                                    .remove_scan_id(scan_name)
-                                    .map_err(|e| {
-                                        TrackerScannerError::StoreError(format!(
-                                            "Failed to remove scan ID: {:?}",
-                                            e
-                                        ))
-                                    })?;
-                                info!("Forcing scan registration due to verification failure");
-                            }
-                        }
-                    }
-                } else {
-                    debug!("Skipping scan ID verification (last verified less than 4 hours ago)");
-                    // Update inner state with the existing scan ID without verification
-                    {
-                        let mut inner = self.inner.lock().await;
-                        inner.scan_id = Some(stored_scan_id);
-                        inner.scan_active = true;
-                    }
-                    return Ok(stored_scan_id);
-                }
-            }
-            Ok(None) => {
-                info!("No existing scan ID found in database for scan name: '{}', proceeding with new registration", scan_name);
-            }
-            Err(e) => {
-                error!("Failed to get scan ID from database: {:?}", e);
-                info!("Database error, proceeding with new registration");
-            }
-        }
-
-        // Register scan using containsAsset predicate with tracker NFT ID
-        let scan_payload = serde_json::json!({
-            "scanName": scan_name,
-            "walletInteraction": "shared",
-            "trackingRule": {
-                "predicate": "containsAsset",
-                "assetId": tracker_nft_id
-            },
-            "removeOffchain": true
-        });
-
-        // Log scan registration (INFO level) and JSON payload (DEBUG level)
-        info!("Registering new tracker scan with name: {}", scan_name);
-        debug!("Tracker scan registration JSON payload: {}", scan_payload);
-
-        let url = format!("{}/scan/register", self.config.node_url);
-
-        // Create request builder and log request details
-        let request_builder = self
-            .request_builder(reqwest::Method::POST, &url)
-            .json(&scan_payload);
-
-        // Log exact HTTP request details
-        info!("Sending HTTP POST request to Ergo node: {}", url);
-        info!("Request headers: API key present: {}", self.config.api_key.is_some());
-        info!("Request body (JSON): {}", scan_payload);
-        debug!("Sending scan registration request to: {}", url);
-        debug!(
-            "Request headers include: {}",
-            if self.config.api_key.is_some() {
-                "API key"
-            } else {
-                "NO API key"
-            }
-        );
-
-        let response = request_builder.send().await.map_err(|e| {
-            error!("HTTP request failed: {}", e);
-            error!(
-                "Request details - URL: {}, Method: POST, Headers: API key present: {}",
-                url,
-                self.config.api_key.is_some()
-            );
-            TrackerScannerError::HttpError(format!("Failed to register scan: {}", e))
-        })?;
-
-        // Log response details
-        let status = response.status();
-        debug!("Response status: {}", status);
-        debug!("Response headers: {:?}", response.headers());
-
-        if !status.is_success() {
-            // Try to read response body for more details
-            let response_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unable to read response body".to_string());
-            error!(
-                "Scan registration failed with status: {}. Response body: {}",
-                status, response_text
-            );
-            error!("Full request details:");
-            error!("  URL: {}", url);
-            error!("  Method: POST");
-            error!("  API key present: {}", self.config.api_key.is_some());
-            error!("  Payload: {}", scan_payload);
-            error!("  Response status: {}", status);
-            error!("  Response body: {}", response_text);
-            return Err(TrackerScannerError::NodeError(format!(
-                "Scan registration failed with status: {}. Response: {}",
-                status, response_text
-            )));
-        }
-
-        let result: serde_json::Value = response.json().await.map_err(|e| {
-            error!("Failed to parse scan registration response JSON: {}", e);
-            TrackerScannerError::JsonError(format!("Failed to parse scan registration response: {}", e))
-        })?;
-
-        debug!(
-            "Scan registration successful response: {}",
-            serde_json::to_string_pretty(&result)
-                .unwrap_or_else(|_| "Unable to format JSON".to_string())
-        );
-
-        let scan_id = result["scanId"]
-            .as_i64()
-            .and_then(|v| i32::try_from(v).ok())
-            .ok_or_else(|| {
-                error!(
-                    "Failed to get scan ID from registration response. Response was: {}",
-                    result
-                );
-                TrackerScannerError::Generic(
-                    "Failed to get scan ID from registration response".to_string(),
-                )
-            })?;
-
-        // Store scan ID in database and update inner state
-        self.metadata_storage
-            .store_scan_id(scan_name, scan_id)
-            .map_err(|e| {
-                TrackerScannerError::StoreError(format!("Failed to store scan ID: {:?}", e))
-            })?;
-
-        // Update inner state with the new scan ID
-        {
-            let mut inner = self.inner.lock().await;
-            inner.scan_id = Some(scan_id);
-            inner.scan_active = true;
-        }
-
-        info!("Registered and stored tracker scan with ID: {}", scan_id);
-
-        Ok(scan_id)
+    fn tracking_rule(&self) -> Result<serde_json::Value, ScannerError> {
+        let tracker_nft_id = self
+            .tracker_nft_id
+            .as_ref()
+            .ok_or_else(|| ScannerError::Generic(TrackerScannerError::MissingTrackerNftId.to_string()))?;
+        Ok(serde_json::json!({
+            "predicate": "containsAsset",
+            "assetId": tracker_nft_id
+        }))
     }
 
-    /// Get unspent tracker boxes from the registered scan
-    pub async fn get_unspent_tracker_boxes(&self) -> Result<Vec<ScanBox>, TrackerScannerError> {
-        let scan_name = self.config.scan_name.as_deref()
-            .unwrap_or("tracker_boxes");
-
-        let scan_id = self.metadata_storage.get_scan_id(scan_name)
-            .map_err(|e| TrackerScannerError::StoreError(format!("Failed to get scan ID: {:?}", e)))?;
-
-        let scan_id = scan_id.ok_or_else(|| TrackerScannerError::Generic("Scan not registered".to_string()))?;
-
-        let url = format!("{}/scan/unspentBoxes/{}", self.config.node_url, scan_id);
-        
-        debug!("Fetching unspent tracker boxes for scan ID: {}", scan_id);
-        
-        let response = self
-            .request_builder(reqwest::Method::GET, &url)
-            .send()
-            .await
-            .map_err(|e| TrackerScannerError::HttpError(format!("Failed to fetch boxes: {}", e)))?;
-
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(TrackerScannerError::NodeError(format!(
-                "Failed to get unspent boxes with status {}: {}",
-                status,
-                error_text
-            )));
-        }
-
-        let response_text = response.text().await.map_err(|e| {
-            error!("Failed to read response text: {}", e);
-            TrackerScannerError::HttpError(format!("Failed to read response text: {}", e))
-        })?;
-
-        // Parse the JSON response as the API format first
-        let api_boxes: Vec<ApiScanBox> = serde_json::from_str(&response_text)
-            .map_err(|e| {
-                error!("Failed to parse API tracker boxes JSON: {}", e);
-                error!("Raw response was: {}", response_text);
-                TrackerScannerError::JsonError(format!("Failed to parse API tracker boxes: {}", e))
-            })?;
-
-        // Convert API boxes to our internal ScanBox format
-        let mut boxes = Vec::new();
-        for api_box in api_boxes {
-            let converted_box = ScanBox {
-                box_id: api_box.inner_box.box_id,
-                value: api_box.inner_box.value,
-                ergo_tree: api_box.inner_box.ergo_tree,
-                creation_height: api_box.inner_box.creation_height,
-                transaction_id: api_box.inner_box.transaction_id,
-                additional_registers: api_box.inner_box.additional_registers,
-                assets: api_box.inner_box.assets.into_iter().map(|a| crate::ergo_scanner::BoxAsset {
-                    token_id: a.token_id,
-                    amount: a.amount,
-                }).collect(),
-            };
-            boxes.push(converted_box);
-        }
-
-        info!("Retrieved {} unspent tracker boxes (converted from API format)", boxes.len());
-
-        Ok(boxes)
+    fn remove_offchain(&self) -> bool {
+        true
     }
 
-    /// Parse a ScanBox into TrackerBoxInfo
-    pub fn parse_tracker_box(&self, scan_box: &ScanBox) -> Result<TrackerBoxInfo, TrackerScannerError> {
-        let tracker_nft_id = self.config.tracker_nft_id.as_ref()
+    fn parse_box(&self, scan_box: &ScanBox) -> Result<TrackerBoxInfo, TrackerScannerError> {
+        let tracker_nft_id = self.tracker_nft_id.as_ref()
             .ok_or(TrackerScannerError::MissingTrackerNftId)?;
 
         // Validate that the box contains the tracker NFT
@@ -518,36 +187,69 @@ impl TrackerServerState {
             value: scan_box.value,
             creation_height: scan_box.creation_height,
             tracker_nft_id: tracker_nft_id.clone(),
+            transaction_id: Some(scan_box.transaction_id.clone()),
         })
     }
+}
 
-    /// Process all unspent tracker boxes
-    pub async fn process_tracker_boxes(&self) -> Result<Vec<TrackerBoxInfo>, TrackerScannerError> {
-        let unspent_boxes = self.get_unspent_tracker_boxes().await?;
-        let total_boxes = unspent_boxes.len();
-        let mut processed_boxes = Vec::new();
+/// Inner state for tracker scanner that requires synchronization
+#[derive(Clone)]
+struct TrackerServerStateInner {
+    pub current_height: u64,
+    pub last_scanned_height: u64,
+}
+
+/// Server state for tracker scanner
+/// Uses real blockchain integration with proper synchronization
+#[derive(Clone)]
+pub struct TrackerServerState {
+    pub config: TrackerNodeConfig,
+    pub inner: Arc<Mutex<TrackerServerStateInner>>,
+    /// Node scan of the tracker boxes
+    pub scan: ScanManager<TrackerScanSpec>,
+    pub metadata_storage: ScannerMetadataStorage,
+    pub tracker_storage: TrackerStorage,
+}
+
+impl TrackerServerState {
+    /// Scan tracker boxes through `explorer` instead of the node's scan API
+    pub fn set_explorer_backend(&mut self, explorer: ExplorerBackend) {
+        self.scan.use_explorer(explorer);
+    }
 
-        for scan_box in &unspent_boxes {
-            match self.parse_tracker_box(scan_box) {
-                Ok(tracker_box) => {
-                    // Store the parsed box
-                    self.tracker_storage.store_tracker_box(&tracker_box)
-                        .map_err(|e| TrackerScannerError::StoreError(format!("Failed to store tracker box: {:?}", e)))?;
+    /// Register scan for tracker boxes using containsAsset rule
+    pub async fn register_tracker_scan(&self) -> Result<i32, TrackerScannerError> {
+        if self.config.tracker_nft_id.is_none() {
+            return Err(TrackerScannerError::MissingTrackerNftId);
+        }
+        Ok(self.scan.register().await?)
+    }
 
-                    processed_boxes.push(tracker_box);
+    /// Get unspent tracker boxes from the registered scan
+    pub async fn get_unspent_tracker_boxes(&self) -> Result<Vec<ScanBox>, TrackerScannerError> {
+        let boxes = self.scan.fetch_boxes(None).await?;
+        info!("Retrieved {} unspent tracker boxes", boxes.len());
+        Ok(boxes)
+    }
 
-                    debug!("Successfully processed tracker box: {}", scan_box.box_id);
-                }
-                Err(e) => {
-                    warn!("Failed to parse tracker box {}: {}", scan_box.box_id, e);
-                    // Continue processing other boxes
-                }
-            }
+    /// Parse a ScanBox into TrackerBoxInfo
+    pub fn parse_tracker_box(&self, scan_box: &ScanBox) -> Result<TrackerBoxInfo, TrackerScannerError> {
+        self.scan.spec().parse_box(scan_box)
+    }
+
+    /// Process all unspent tracker boxes
+    pub async fn process_tracker_boxes(&self) -> Result<Vec<TrackerBoxInfo>, TrackerScannerError> {
+        let tracker_boxes = self.scan.process(None).await?;
+
+        for tracker_box in &tracker_boxes {
+            self.tracker_storage.store_tracker_box(tracker_box)
+                .map_err(|e| TrackerScannerError::StoreError(format!("Failed to store tracker box: {:?}", e)))?;
+            debug!("Successfully processed tracker box: {}", tracker_box.box_id);
         }
 
-        info!("Processed {} tracker boxes ({} successful)", total_boxes, processed_boxes.len());
+        info!("Processed {} tracker boxes", tracker_boxes.len());
 
-        Ok(processed_boxes)
+        Ok(tracker_boxes)
     }
 
     /// Get the latest tracker box ID from the registered scan
@@ -568,72 +270,73 @@ impl TrackerServerState {
     }
 
     /// Update tracker state with processed boxes
-    pub async fn update_tracker_state(&self, tracker_boxes: &[TrackerBoxInfo]) -> Result<(), TrackerScannerError> {
+    pub async fn update_tracker_state(
+        &self,
+        tracker_boxes: &[TrackerBoxInfo],
+    ) -> Result<Vec<CommitmentCheck>, TrackerScannerError> {
         if tracker_boxes.is_empty() {
             debug!("No tracker boxes to update state");
-            return Ok(());
+            return Ok(Vec::new());
         }
 
-        // For now, we'll just log the boxes
-        // In a real implementation, this would update the tracker state manager
-        // with cross-verification logic
-        for tracker_box in tracker_boxes {
-            debug!(
-                "Tracker box: id={}, pubkey={}, commitment={}, height={}",
-                &tracker_box.box_id[..16], // First 16 chars of box ID
-                &tracker_box.tracker_pubkey[..16], // First 16 chars of pubkey
-                &tracker_box.state_commitment[..16], // First 16 chars of commitment
-                tracker_box.last_verified_height
-            );
-        }
+        let checked_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let mut checks = Vec::new();
 
-        debug!("Updated tracker state with {} boxes", tracker_boxes.len());
-        
-        Ok(())
-    }
+        // Cross-verify each new commitment against the local roots in effect at its height
+        for tracker_box in tracker_boxes {
+            let already_checked = self
+                .tracker_storage
+                .get_commitment_check(&tracker_box.box_id)
+                .map_err(|e| TrackerScannerError::StoreError(format!("Failed to get commitment check: {:?}", e)))?
+                .is_some();
+            if already_checked {
+                continue;
+            }
 
-    /// Deregister tracker scan
-    pub async fn deregister_tracker_scan(&self) -> Result<(), TrackerScannerError> {
-        let scan_name = self.config.scan_name.as_deref()
-            .unwrap_or("tracker_boxes");
-
-        let scan_id = self.metadata_storage.get_scan_id(scan_name)
-            .map_err(|e| TrackerScannerError::StoreError(format!("Failed to get scan ID: {:?}", e)))?;
-
-        if let Some(scan_id) = scan_id {
-            let url = format!("{}/scan/deregister", self.config.node_url);
-            let deregister_payload = serde_json::json!({
-                "scanId": scan_id
-            });
-
-            info!("Deregistering tracker scan with ID: {}", scan_id);
-
-            let response = self
-                .request_builder(reqwest::Method::POST, &url)
-                .json(&deregister_payload)
-                .send()
-                .await
-                .map_err(|e| TrackerScannerError::HttpError(format!("Failed to send request: {}", e)))?;
-
-            if !response.status().is_success() {
-                let error_text = response.text().await
-                    .unwrap_or_else(|_| "Unknown error".to_string());
-                warn!("Failed to deregister scan {}: {}", scan_id, error_text);
+            let local_roots = if self.config.verify_against_all_roots {
+                self.tracker_storage.all_local_roots()
             } else {
-                info!("Successfully deregistered tracker scan with ID: {}", scan_id);
+                self.tracker_storage.local_roots_at(tracker_box.last_verified_height)
+            }
+            .map_err(|e| TrackerScannerError::StoreError(format!("Failed to get local roots: {:?}", e)))?;
+            let check = verify_commitment(tracker_box, &local_roots, checked_at);
+
+            match check.status {
+                CommitmentStatus::Consistent => debug!(
+                    "Tracker box {} commitment matches local state at height {}",
+                    check.box_id, check.height
+                ),
+                CommitmentStatus::Unverifiable => debug!(
+                    "No local roots recorded at height {}, cannot verify tracker box {}",
+                    check.height, check.box_id
+                ),
+                CommitmentStatus::Diverged => warn!(
+                    "Tracker box {} committed digest {} at height {}, which matches no local root ({:?})",
+                    check.box_id, check.onchain_digest, check.height, check.local_digests
+                ),
             }
 
-            // Remove scan ID from storage
-            self.metadata_storage.remove_scan_id(scan_name)
-                .map_err(|e| TrackerScannerError::StoreError(format!("Failed to remove scan ID: {:?}", e)))?;
-
-            // Update inner state
-            let mut inner = self.inner.lock().await;
-            inner.scan_id = None;
-            inner.scan_active = false;
+            self.tracker_storage
+                .store_commitment_check(&check)
+                .map_err(|e| TrackerScannerError::StoreError(format!("Failed to store commitment check: {:?}", e)))?;
+            checks.push(check);
         }
 
-        Ok(())
+        debug!(
+            "Checked {} new tracker boxes out of {}",
+            checks.len(),
+            tracker_boxes.len()
+        );
+
+        Ok(checks)
+    }
+
+    /// Deregister tracker scan
+    pub async fn deregister_tracker_scan(&self) -> Result<(), TrackerScannerError> {
+        Ok(self.scan.deregister().await?)
     }
 
     /// Get last scanned height
@@ -645,211 +348,26 @@ impl TrackerServerState {
     /// Get current blockchain height from cache or Ergo node
     /// Uses cached value if less than 10 minutes old, otherwise fetches from node
     pub async fn get_current_height(&self) -> Result<u64, TrackerScannerError> {
-        const CACHE_TTL_MS: u64 = 600_000; // 10 minutes in milliseconds
-
-        // Check if we have a cached height
-        match self.metadata_storage.get_blockchain_height() {
-            Ok(Some((cached_height, cached_timestamp))) => {
-                let now = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis() as u64;
-
-                if now.saturating_sub(cached_timestamp) < CACHE_TTL_MS {
-                    debug!("Using cached blockchain height: {}", cached_height);
-                    return Ok(cached_height);
-                }
-
-                debug!("Cached blockchain height expired, fetching from node");
-            }
-            Ok(None) => {
-                debug!("No cached blockchain height found, fetching from node");
-            }
-            Err(e) => {
-                warn!("Failed to read cached blockchain height: {:?}", e);
-            }
-        }
-
-        // Fetch from node
-        let url = format!("{}/info", self.config.node_url);
-
-        let response = self
-            .request_builder(reqwest::Method::GET, &url)
-            .send()
-            .await
-            .map_err(|e| TrackerScannerError::HttpError(format!("Failed to get height: {}", e)))?;
-
-        if !response.status().is_success() {
-            return Err(TrackerScannerError::NodeError(format!(
-                "Failed to get height: {}",
-                response.status()
-            )));
-        }
-
-        let info: serde_json::Value = response
-            .json()
-            .await
-            .map_err(|e| TrackerScannerError::JsonError(format!("Failed to parse height: {}", e)))?;
-
-        let height = info["fullHeight"]
-            .as_u64()
-            .ok_or_else(|| TrackerScannerError::JsonError("Missing fullHeight in response".to_string()))?;
-
-        // Store in cache with current timestamp
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_millis() as u64;
-
-        if let Err(e) = self.metadata_storage.store_blockchain_height(height, now) {
-            warn!("Failed to cache blockchain height: {:?}", e);
-        }
-
-        Ok(height)
-    }
-
-    /// Check if scan verification is needed (every 4 hours)
-    async fn should_verify_scan(&self) -> bool {
-        let inner = self.inner.lock().await;
-        match inner.last_scan_verification {
-            Some(last_verification) => {
-                let now = std::time::SystemTime::now();
-                let duration_since_last = now.duration_since(last_verification).unwrap_or_default();
-                duration_since_last >= std::time::Duration::from_secs(4 * 60 * 60) // 4 hours
-            }
-            None => true, // Never verified before
-        }
-    }
-
-    /// Update the last scan verification timestamp
-    async fn update_scan_verification_time(&self) {
-        let mut inner = self.inner.lock().await;
-        inner.last_scan_verification = Some(std::time::SystemTime::now());
+        Ok(self.scan.current_height().await?)
     }
 
     /// Verify scan registration is still active
     pub async fn verify_scan_registration(&self) -> Result<bool, TrackerScannerError> {
-        let scan_name = self.config.scan_name.as_deref()
-            .unwrap_or("tracker_boxes");
-
-        let stored_scan_id = self.metadata_storage.get_scan_id(scan_name)
-            .map_err(|e| TrackerScannerError::StoreError(format!("Failed to get scan ID: {:?}", e)))?;
-
-        if let Some(scan_id) = stored_scan_id {
-            let url = format!("{}/scan/listAll", self.config.node_url);
-            debug!("Verifying scan exists - URL: {}", url);
-            debug!("Looking for scan ID: {}", scan_id);
-            info!("Sending HTTP GET request to Ergo node: {}", url);
-            info!("Looking for scan ID: {}", scan_id);
-
-            let response = self
-                .request_builder(reqwest::Method::GET, &url)
-                .send()
-                .await;
-
-            let response = match response {
-                Ok(resp) => resp,
-                Err(e) => {
-                    error!("Failed to send scan list request: {}", e);
-                    // If we can't even connect, assume the scan exists to avoid re-registration
-                    warn!(
-                        "Network error connecting to scan list endpoint, assuming scan ID {} exists",
-                        scan_id
-                    );
-                    return Ok(true);
-                }
-            };
-
-            let status = response.status();
-            debug!("Scan list response status: {}", status);
-
-            // If scan list endpoint is not available (400/404), assume scan exists
-            // This handles nodes that don't support scan listing
-            if status == 400 || status == 404 {
-                info!(
-                    "Scan list endpoint not available (status: {}), assuming scan ID {} exists",
-                    status, scan_id
-                );
-                return Ok(true);
-            }
-
-            if !status.is_success() {
-                // Try to read response body for more details
-                let response_text = response
-                    .text()
-                    .await
-                    .unwrap_or_else(|_| "Unable to read response body".to_string());
-                // Log as warning instead of error since we're handling this gracefully
-                warn!(
-                    "Scan list request failed with status: {}. Response body: {}",
-                    status, response_text
-                );
-                // For any other non-success status, assume scan exists to prevent re-registration
-                warn!("Scan list request failed (status: {}), assuming scan ID {} exists to prevent re-registration", status, scan_id);
-                return Ok(true);
-            }
-
-            let scans: serde_json::Value = response.json().await.map_err(|e| {
-                error!("Failed to parse scan list JSON: {}", e);
-                TrackerScannerError::JsonError(format!("Failed to parse scan list: {}", e))
-            })?;
-
-            debug!(
-                "Scan list response: {}",
-                serde_json::to_string_pretty(&scans)
-                    .unwrap_or_else(|_| "Unable to format JSON".to_string())
-            );
-
-            // Check if our scan ID exists in the list
-            if let Some(scans_array) = scans.as_array() {
-                debug!("Found {} scans in list", scans_array.len());
-                for scan in scans_array {
-                    if let Some(id) = scan["scanId"].as_i64() {
-                        debug!("Checking scan ID: {} against target: {}", id, scan_id);
-                        if id == scan_id as i64 {
-                            debug!("Scan ID {} found in scan list", scan_id);
-                            return Ok(true);
-                        }
-                    } else {
-                        debug!("Scan entry missing scanId: {:?}", scan);
-                    }
-                }
-            } else {
-                debug!("Scan list is not an array or is empty");
+        match self.scan.scan_id().await {
+            Some(scan_id) => Ok(self.scan.verify_scan_exists(scan_id).await?),
+            None => {
+                debug!("No stored tracker scan ID");
+                Ok(false)
             }
         }
-
-        debug!("Scan ID not found in scan list or no stored scan ID");
-        Ok(false)
     }
 
     /// Re-register scan if needed
     pub async fn ensure_scan_registered(&self) -> Result<i32, TrackerScannerError> {
-        let scan_name = self.config.scan_name.as_deref()
-            .unwrap_or("tracker_boxes");
-
-        // Check if we have a stored scan ID
-        let stored_scan_id = self.metadata_storage.get_scan_id(scan_name)
-            .map_err(|e| TrackerScannerError::StoreError(format!("Failed to get scan ID: {:?}", e)))?;
-
-        if let Some(scan_id) = stored_scan_id {
-            // Verify the scan is still active
-            if self.verify_scan_registration().await.unwrap_or(false) {
-                info!("Tracker scan {} is still active", scan_id);
-
-                // Update inner state
-                let mut inner = self.inner.lock().await;
-                inner.scan_id = Some(scan_id);
-                inner.scan_active = true;
-
-                return Ok(scan_id);
-            } else {
-                warn!("Stored tracker scan {} is no longer active, re-registering", scan_id);
-            }
+        if self.config.tracker_nft_id.is_none() {
+            return Err(TrackerScannerError::MissingTrackerNftId);
         }
-
-        // Register new scan
-        self.register_tracker_scan().await
+        Ok(self.scan.ensure_registered().await?)
     }
 
     /// Start the tracker scanner (single scan)
@@ -899,17 +417,24 @@ pub fn create_tracker_server_state(
     let inner = TrackerServerStateInner {
         current_height: 0,
         last_scanned_height: config.start_height.unwrap_or(0),
-        scan_active: false,
-        scan_id: None,
-        last_scan_verification: None,
     };
+    let scan = ScanManager::new(
+        TrackerScanSpec {
+            tracker_nft_id: config.tracker_nft_id.clone(),
+        },
+        ScanNodeConfig {
+            node_url: config.node_url.clone(),
+            api_key: config.api_key.clone(),
+            scan_name: config.scan_name.clone(),
+        },
+        metadata_storage.clone(),
+    );
 
     TrackerServerState {
         config,
         inner: Arc::new(Mutex::new(inner)),
-        client: Client::new(),
-        tracker_state: TrackerStateManager::new(),
+        scan,
         metadata_storage,
         tracker_storage,
     }
-}
\ No newline at end of file
+}
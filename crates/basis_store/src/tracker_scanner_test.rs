@@ -46,6 +46,7 @@ mod tests {
             node_url: "http://localhost:9053".to_string(),
             scan_name: Some("test_tracker_scan".to_string()),
             api_key: Some("test_api_key".to_string()),
+            verify_against_all_roots: false,
         };
 
         assert_eq!(config.start_height, Some(1000));
@@ -72,6 +73,7 @@ mod tests {
             node_url: "http://localhost:9053".to_string(),
             scan_name: Some("test_tracker_scan".to_string()),
             api_key: None,
+            verify_against_all_roots: false,
         };
 
         let server_state = create_tracker_server_state(config, metadata_storage, tracker_storage);
@@ -92,6 +94,7 @@ mod tests {
             value: 1000000,
             creation_height: 900,
             tracker_nft_id: "test_nft_id".to_string(),
+            transaction_id: Some("test_tx_id".to_string()),
         };
 
         // Test serialization
@@ -123,6 +126,7 @@ mod tests {
             node_url: "http://localhost:9053".to_string(),
             scan_name: Some("test_tracker_scan".to_string()),
             api_key: None,
+            verify_against_all_roots: false,
         };
 
         let server_state = create_tracker_server_state(config, metadata_storage, tracker_storage);
@@ -180,6 +184,7 @@ mod tests {
             node_url: "http://localhost:9053".to_string(),
             scan_name: Some("test_tracker_scan".to_string()),
             api_key: None,
+            verify_against_all_roots: false,
         };
 
         let server_state = create_tracker_server_state(config, metadata_storage, tracker_storage);
@@ -227,6 +232,7 @@ mod tests {
             node_url: "http://localhost:9053".to_string(),
             scan_name: Some("test_tracker_scan".to_string()),
             api_key: None,
+            verify_against_all_roots: false,
         };
 
         let server_state = create_tracker_server_state(config, metadata_storage, tracker_storage);
@@ -257,4 +263,51 @@ mod tests {
         assert!(result.is_err());
         assert!(matches!(result.unwrap_err(), crate::tracker_scanner::TrackerScannerError::MissingRegister(_)));
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_tracker_scan_against_mock_node() {
+        use crate::mock_node::{BoxSpec, MockErgoNode};
+
+        let tracker_nft_id = "dbfbbaf91a98c22204de3745e1986463620dcf3525ad566c6924cf9e976f86f8";
+        let tracker_box = |digest_byte: u8| {
+            BoxSpec::new(1_000_000, "0008cd02dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7")
+                .register("R4", "0702dada811a888cd0dc7a0a41739a3ad9b0f427741fe6ca19700cf1a51200c96bf7")
+                .register("R5", format!("64{}0100000020{}", hex::encode([digest_byte; 33]), "00000000"))
+                .asset(tracker_nft_id, 1)
+        };
+
+        let node = MockErgoNode::start(500).await;
+        let genesis = node.create_box(tracker_box(1));
+        let temp_dir = tempfile::tempdir().unwrap();
+        let server_state = create_tracker_server_state(
+            TrackerNodeConfig {
+                start_height: Some(0),
+                tracker_nft_id: Some(tracker_nft_id.to_string()),
+                node_url: format!("{}/", node.url),
+                scan_name: Some("test_tracker_scan".to_string()),
+                api_key: None,
+                verify_against_all_roots: false,
+            },
+            ScannerMetadataStorage::open(temp_dir.path().join("metadata")).unwrap(),
+            TrackerStorage::open(temp_dir.path().join("tracker")).unwrap(),
+        );
+
+        server_state.register_tracker_scan().await.unwrap();
+        assert_eq!(node.scan_ids().len(), 1);
+        let boxes = server_state.process_tracker_boxes().await.unwrap();
+        assert_eq!(boxes.len(), 1);
+        assert_eq!(server_state.get_latest_tracker_box_id().await.unwrap(), Some(genesis.clone()));
+
+        // A commitment replaces the tracker box...
+        node.advance(1);
+        let (_, outputs) = node.spend(&[&genesis], vec![tracker_box(2)]);
+        assert_eq!(server_state.get_latest_tracker_box_id().await.unwrap(), Some(outputs[0].clone()));
+
+        // ...until a reorg drops it
+        node.reorg(1, 2);
+        assert_eq!(server_state.get_latest_tracker_box_id().await.unwrap(), Some(genesis));
+
+        server_state.deregister_tracker_scan().await.unwrap();
+        assert!(node.scan_ids().is_empty());
+    }
+}
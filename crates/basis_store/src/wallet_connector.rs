@@ -0,0 +1,1101 @@
+//! Unsigned transactions for wallet signing
+//!
+//! Builds reserve creation, top-up and redemption transactions for the user's
+//! wallet to sign, so the tracker never holds user keys:
+//!
+//! - as EIP-12 unsigned transaction JSON, which dApp connectors such as
+//!   Nautilus sign with `ergo.sign_tx`
+//! - as ErgoPay (EIP-20) reduced transactions, which mobile wallets sign from
+//!   an `ergopay:` URL
+//!
+//! ergo-lib 0.13 can neither parse nor serialize AVL tree constants, and every
+//! reserve box holds one in R5, so boxes are kept in the node's JSON form and
+//! the transaction bytes are serialized here. ergo-lib still provides the
+//! context extension constants, the miner fee contract and address handling.
+
+use std::collections::BTreeMap;
+
+use base64::Engine;
+use ergo_avltree_rust::operation::{KeyValue, Operation};
+use ergo_lib::chain::ergo_box::BoxValue;
+use ergo_lib::ergotree_ir::address::{Address, AddressEncoder, NetworkPrefix};
+use ergo_lib::ergotree_ir::mir::constant::Constant;
+use ergo_lib::ergotree_ir::serialization::SigmaSerializable;
+use ergo_lib::ergotree_ir::sigma_protocol::dlog_group::EcPoint;
+use ergo_lib::wallet::tx_builder::new_miner_fee_box;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::transaction_builder::{
+    ContextExtension, ContextExtensionBuilder, TransactionBuilderError, TxContext, TOP_UP_ACTION,
+};
+use crate::contract_registry::{ContractRegistry, ReserveContract};
+use crate::{blake2b256_hash, NoteKey, PubKey};
+
+/// Smallest value the reserve contract accepts for a top-up (0.1 ERG)
+pub const MIN_TOP_UP: u64 = 100_000_000;
+
+/// R5 of a new reserve: an empty insert-only AVL tree with 32-byte keys
+pub const EMPTY_RESERVE_TREE: &str = "64000000000000000000000000000000000000000000000000000000000000000000012000";
+
+/// Smallest value of a box the node accepts for the outputs built here
+const MIN_BOX_VALUE: u64 = 1_000_000;
+
+/// Serialized type code of an AVL tree constant
+const AVL_TREE_TYPE: u8 = 0x64;
+
+/// Serialized `ProveDlog` and `TrivialProp(true)` sigma propositions
+const PROVE_DLOG_OP_CODE: u8 = 0xcd;
+const TRIVIAL_PROP_TRUE_OP_CODE: u8 = 0xd3;
+
+/// A token in an EIP-12 box
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip12Asset {
+    pub token_id: String,
+    #[serde(with = "string_amount")]
+    pub amount: u64,
+}
+
+/// A box on chain, as spent or read by an EIP-12 transaction
+///
+/// Deserializes from node box JSON as well as from the boxes a dApp connector
+/// returns from `ergo.get_utxos()`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip12Box {
+    pub box_id: String,
+    pub transaction_id: String,
+    pub index: u16,
+    #[serde(with = "string_amount")]
+    pub value: u64,
+    pub ergo_tree: String,
+    #[serde(default)]
+    pub assets: Vec<Eip12Asset>,
+    /// Register name -> serialized constant (hex)
+    #[serde(default, deserialize_with = "deserialize_registers")]
+    pub additional_registers: BTreeMap<String, String>,
+    pub creation_height: u32,
+    /// Context extension of an input: variable ID -> serialized constant (hex)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extension: Option<BTreeMap<String, String>>,
+}
+
+/// A box created by an EIP-12 transaction
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip12Output {
+    #[serde(with = "string_amount")]
+    pub value: u64,
+    pub ergo_tree: String,
+    pub assets: Vec<Eip12Asset>,
+    pub additional_registers: BTreeMap<String, String>,
+    pub creation_height: u32,
+}
+
+/// Unsigned transaction in the EIP-12 format passed to `ergo.sign_tx`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Eip12UnsignedTx {
+    pub inputs: Vec<Eip12Box>,
+    pub data_inputs: Vec<Eip12Box>,
+    pub outputs: Vec<Eip12Output>,
+}
+
+/// What an input's script reduces to, leaving only the signature to prove
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReducedInput {
+    /// Knowledge of the secret key of a public key
+    ProveDlog(PubKey),
+    /// Nothing left to prove
+    True,
+}
+
+/// A transaction for the user's wallet to sign
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WalletTransaction {
+    pub unsigned_tx: Eip12UnsignedTx,
+    /// Reduced proposition of each input, in input order, for ErgoPay
+    pub reduced_inputs: Vec<ReducedInput>,
+}
+
+/// A reserve to create
+#[derive(Debug, Clone)]
+pub struct NewReserve {
+    pub owner_pubkey: PubKey,
+    /// Singleton token identifying the reserve (hex)
+    pub reserve_nft_id: String,
+    /// NFT of the tracker the reserve backs notes of (hex), stored in R6
+    pub tracker_nft_id: String,
+    /// Collateral in nanoERG
+    pub amount: u64,
+}
+
+/// Everything a redemption puts in the reserve input's context extension
+#[derive(Debug, Clone)]
+pub struct RedemptionSpend {
+    pub receiver_pubkey: PubKey,
+    pub total_debt: u64,
+    pub timestamp: u64,
+    /// nanoERG taken from the reserve; the transaction fee is paid from it
+    pub amount: u64,
+    /// Amount already redeemed for the note, as stored in the reserve tree
+    pub already_redeemed: u64,
+    pub reserve_signature: Vec<u8>,
+    /// Empty once the emergency period has passed
+    pub tracker_signature: Vec<u8>,
+    pub tracker_lookup_proof: Vec<u8>,
+    /// None for the first redemption of the note
+    pub reserve_lookup_proof: Option<Vec<u8>>,
+    pub reserve_insert_proof: Vec<u8>,
+}
+
+impl WalletTransaction {
+    /// Bytes the wallet signs, as serialized by the node
+    pub fn bytes_to_sign(&self) -> Result<Vec<u8>, TransactionBuilderError> {
+        let tx = &self.unsigned_tx;
+        let mut w = Vec::new();
+
+        put_vlq(&mut w, tx.inputs.len() as u64);
+        for input in &tx.inputs {
+            w.extend_from_slice(&decode_id("box ID", &input.box_id)?);
+            // Empty spending proof
+            put_vlq(&mut w, 0);
+            let mut extension = input
+                .extension
+                .iter()
+                .flatten()
+                .map(|(id, constant)| {
+                    let id: u8 = id.parse().map_err(|_| {
+                        TransactionBuilderError::TransactionBuilding(format!("Invalid context variable ID {}", id))
+                    })?;
+                    Ok((id, decode_hex("context variable", constant)?))
+                })
+                .collect::<Result<Vec<_>, TransactionBuilderError>>()?;
+            extension.sort_by_key(|(id, _)| *id);
+            w.push(extension.len() as u8);
+            for (id, constant) in extension {
+                w.push(id);
+                w.extend_from_slice(&constant);
+            }
+        }
+
+        put_vlq(&mut w, tx.data_inputs.len() as u64);
+        for data_input in &tx.data_inputs {
+            w.extend_from_slice(&decode_id("box ID", &data_input.box_id)?);
+        }
+
+        // Outputs refer to tokens by their index in this list
+        let mut token_ids: Vec<String> = Vec::new();
+        for asset in tx.outputs.iter().flat_map(|output| &output.assets) {
+            let token_id = asset.token_id.to_lowercase();
+            if !token_ids.contains(&token_id) {
+                token_ids.push(token_id);
+            }
+        }
+        put_vlq(&mut w, token_ids.len() as u64);
+        for token_id in &token_ids {
+            w.extend_from_slice(&decode_id("token ID", token_id)?);
+        }
+
+        put_vlq(&mut w, tx.outputs.len() as u64);
+        for output in &tx.outputs {
+            put_vlq(&mut w, output.value);
+            w.extend_from_slice(&decode_hex("ErgoTree", &output.ergo_tree)?);
+            put_vlq(&mut w, output.creation_height as u64);
+            w.push(output.assets.len() as u8);
+            for asset in &output.assets {
+                let token_id = asset.token_id.to_lowercase();
+                let index = token_ids.iter().position(|id| *id == token_id).unwrap_or_default();
+                put_vlq(&mut w, index as u64);
+                put_vlq(&mut w, asset.amount);
+            }
+            w.push(output.additional_registers.len() as u8);
+            for (offset, (name, constant)) in output.additional_registers.iter().enumerate() {
+                // Registers are stored densely from R4 on
+                if *name != format!("R{}", offset + 4) {
+                    return Err(TransactionBuilderError::TransactionBuilding(format!(
+                        "Registers must be densely packed from R4, found {}",
+                        name
+                    )));
+                }
+                w.extend_from_slice(&decode_hex("register", constant)?);
+            }
+        }
+
+        Ok(w)
+    }
+
+    /// Transaction ID (hex)
+    pub fn tx_id(&self) -> Result<String, TransactionBuilderError> {
+        Ok(hex::encode(blake2b256_hash(&self.bytes_to_sign()?)))
+    }
+
+    /// Reduced transaction, as signed by ErgoPay wallets
+    ///
+    /// The bytes to sign followed by the sigma proposition and cost of every
+    /// input and the cost of the transaction. Costs are left at zero; the
+    /// node computes the real ones when the signed transaction is submitted.
+    pub fn reduced_bytes(&self) -> Result<Vec<u8>, TransactionBuilderError> {
+        if self.reduced_inputs.len() != self.unsigned_tx.inputs.len() {
+            return Err(TransactionBuilderError::TransactionBuilding(
+                "Every input needs a reduced proposition".to_string(),
+            ));
+        }
+
+        let message = self.bytes_to_sign()?;
+        let mut w = Vec::with_capacity(message.len() + 40 * self.reduced_inputs.len() + 8);
+        put_vlq(&mut w, message.len() as u64);
+        w.extend_from_slice(&message);
+        for reduced in &self.reduced_inputs {
+            match reduced {
+                ReducedInput::ProveDlog(pubkey) => {
+                    w.push(PROVE_DLOG_OP_CODE);
+                    w.extend_from_slice(pubkey);
+                }
+                ReducedInput::True => w.push(TRIVIAL_PROP_TRUE_OP_CODE),
+            }
+            put_vlq(&mut w, 0);
+        }
+        put_vlq(&mut w, 0);
+        Ok(w)
+    }
+
+    /// Reduced transaction, base64url-encoded as ErgoPay expects
+    pub fn reduced_tx_base64(&self) -> Result<String, TransactionBuilderError> {
+        Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(self.reduced_bytes()?))
+    }
+
+    /// Static ErgoPay URL carrying the reduced transaction
+    pub fn ergopay_url(&self) -> Result<String, TransactionBuilderError> {
+        Ok(format!("ergopay:{}", self.reduced_tx_base64()?))
+    }
+}
+
+/// Build a transaction creating a reserve funded from the user's boxes
+///
+/// Outputs: the reserve, the miner fee and the change, if any. The reserve
+/// NFT must be in one of the funding boxes.
+pub fn reserve_creation_tx(
+    reserve: &NewReserve,
+    funding: &[Eip12Box],
+    contracts: &ContractRegistry,
+    context: &TxContext,
+) -> Result<WalletTransaction, TransactionBuilderError> {
+    if reserve.amount < MIN_BOX_VALUE {
+        return Err(TransactionBuilderError::Configuration(format!(
+            "Reserve amount must be at least {} nanoERG",
+            MIN_BOX_VALUE
+        )));
+    }
+    let tracker_nft = decode_id("tracker NFT ID", &reserve.tracker_nft_id)?;
+    decode_id("reserve NFT ID", &reserve.reserve_nft_id)?;
+
+    let mut registers = BTreeMap::new();
+    registers.insert("R4".to_string(), constant_hex(group_element(&reserve.owner_pubkey)?));
+    registers.insert("R5".to_string(), EMPTY_RESERVE_TREE.to_string());
+    registers.insert("R6".to_string(), constant_hex(Constant::from(tracker_nft)));
+    // New reserves are created under the latest contract version
+    let reserve_output = Eip12Output {
+        value: reserve.amount,
+        ergo_tree: reserve_ergo_tree(contracts)?,
+        assets: vec![Eip12Asset {
+            token_id: reserve.reserve_nft_id.to_lowercase(),
+            amount: 1,
+        }],
+        additional_registers: registers,
+        creation_height: context.current_height,
+    };
+
+    let needed = reserve.amount.checked_add(context.fee).ok_or_else(|| {
+        TransactionBuilderError::Configuration("Reserve amount overflows".to_string())
+    })?;
+    let funded = select_funding(funding, needed, Some(&reserve.reserve_nft_id))?;
+
+    let mut outputs = vec![reserve_output, fee_output(context)?];
+    outputs.extend(change_output(&funded, context)?);
+    Ok(WalletTransaction {
+        reduced_inputs: funded.reduced_inputs,
+        unsigned_tx: Eip12UnsignedTx {
+            inputs: funded.boxes,
+            data_inputs: Vec::new(),
+            outputs,
+        },
+    })
+}
+
+/// Build a transaction adding `amount` nanoERG from the user's boxes to a reserve
+///
+/// The reserve is spent with the top-up action and recreated as the first
+/// output, followed by the miner fee and the change.
+pub fn top_up_tx(
+    reserve: &Eip12Box,
+    amount: u64,
+    funding: &[Eip12Box],
+    contracts: &ContractRegistry,
+    context: &TxContext,
+) -> Result<WalletTransaction, TransactionBuilderError> {
+    check_reserve_box(reserve, contracts)?;
+    if amount < MIN_TOP_UP {
+        return Err(TransactionBuilderError::Configuration(format!(
+            "Top-up must add at least {} nanoERG",
+            MIN_TOP_UP
+        )));
+    }
+
+    let needed = amount.checked_add(context.fee).ok_or_else(|| {
+        TransactionBuilderError::Configuration("Top-up amount overflows".to_string())
+    })?;
+    let funding: Vec<Eip12Box> = funding.iter().filter(|b| b.box_id != reserve.box_id).cloned().collect();
+    let funded = select_funding(&funding, needed, None)?;
+
+    let mut reserve_input = reserve.clone();
+    // Top up with the reserve recreated at output 0
+    let action = Constant::from((TOP_UP_ACTION * 10) as i8);
+    reserve_input.extension = Some(BTreeMap::from([("0".to_string(), constant_hex(action))]));
+    let reserve_output = Eip12Output {
+        value: reserve.value.checked_add(amount).ok_or_else(|| {
+            TransactionBuilderError::Configuration("Reserve value overflows".to_string())
+        })?,
+        ergo_tree: reserve.ergo_tree.clone(),
+        assets: reserve.assets.clone(),
+        additional_registers: reserve.additional_registers.clone(),
+        creation_height: context.current_height,
+    };
+
+    let mut inputs = vec![reserve_input];
+    inputs.extend(funded.boxes.iter().cloned());
+    let mut reduced_inputs = vec![ReducedInput::True];
+    reduced_inputs.extend(funded.reduced_inputs.iter().copied());
+
+    let mut outputs = vec![reserve_output, fee_output(context)?];
+    outputs.extend(change_output(&funded, context)?);
+    Ok(WalletTransaction {
+        unsigned_tx: Eip12UnsignedTx {
+            inputs,
+            data_inputs: Vec::new(),
+            outputs,
+        },
+        reduced_inputs,
+    })
+}
+
+/// Build a transaction redeeming a note from a reserve
+///
+/// The reserve is the only input and pays the fee out of the redeemed amount:
+/// the outputs are the reserve less `amount`, the receiver's box with
+/// `amount` less the fee, and the miner fee. The tracker box is the data
+/// input the contract reads the committed debt from. The receiver signs, as
+/// the contract requires their key once the note checks out.
+pub fn redemption_tx(
+    reserve: &Eip12Box,
+    tracker: &Eip12Box,
+    spend: &RedemptionSpend,
+    contracts: &ContractRegistry,
+    context: &TxContext,
+) -> Result<WalletTransaction, TransactionBuilderError> {
+    check_reserve_box(reserve, contracts)?;
+
+    if spend.amount == 0 {
+        return Err(TransactionBuilderError::Configuration(
+            "Redemption amount must be greater than 0".to_string(),
+        ));
+    }
+    let outstanding = spend.total_debt.saturating_sub(spend.already_redeemed);
+    if spend.amount > outstanding {
+        return Err(TransactionBuilderError::InsufficientFunds(format!(
+            "Redemption amount {} exceeds outstanding debt {}",
+            spend.amount, outstanding
+        )));
+    }
+    if spend.amount > reserve.value.saturating_sub(MIN_BOX_VALUE) {
+        return Err(TransactionBuilderError::InsufficientFunds(format!(
+            "Reserve {} holds {} nanoERG, too little to redeem {}",
+            reserve.box_id, reserve.value, spend.amount
+        )));
+    }
+    if spend.amount < context.fee.saturating_add(MIN_BOX_VALUE) {
+        return Err(TransactionBuilderError::Configuration(format!(
+            "Redemption amount must cover the {} nanoERG fee and leave at least {} nanoERG",
+            context.fee, MIN_BOX_VALUE
+        )));
+    }
+    if spend.reserve_signature.len() != 65 {
+        return Err(TransactionBuilderError::Configuration(
+            "Reserve owner signature must be 65 bytes".to_string(),
+        ));
+    }
+    if !spend.tracker_signature.is_empty() && spend.tracker_signature.len() != 65 {
+        return Err(TransactionBuilderError::Configuration(
+            "Tracker signature must be 65 bytes, or empty after the emergency period".to_string(),
+        ));
+    }
+
+    let owner_pubkey = reserve_owner(reserve)?;
+    let tracker_nft = reserve
+        .additional_registers
+        .get("R6")
+        .and_then(|r6| r6.get(4..))
+        .ok_or_else(|| TransactionBuilderError::Configuration("Reserve box has no tracker NFT in R6".to_string()))?;
+    if !tracker
+        .assets
+        .first()
+        .is_some_and(|nft| nft.token_id.eq_ignore_ascii_case(tracker_nft))
+    {
+        return Err(TransactionBuilderError::Configuration(format!(
+            "Tracker box {} does not hold the tracker NFT {} named by the reserve",
+            tracker.box_id, tracker_nft
+        )));
+    }
+
+    // Replay the insert the contract performs on the reserve tree
+    let key = NoteKey::from_keys(&owner_pubkey, &spend.receiver_pubkey).to_bytes();
+    let mut value = spend.timestamp.to_be_bytes().to_vec();
+    value.extend_from_slice(&(spend.already_redeemed + spend.amount).to_be_bytes());
+    let next_tree = next_reserve_tree(reserve, key, value, &spend.reserve_insert_proof)?;
+
+    let mut reserve_input = reserve.clone();
+    reserve_input.extension = Some(redemption_extension(spend)?.to_serialized_map()?);
+    let mut data_input = tracker.clone();
+    data_input.extension = None;
+
+    let mut registers = reserve.additional_registers.clone();
+    registers.insert("R5".to_string(), next_tree);
+    let outputs = vec![
+        Eip12Output {
+            value: reserve.value - spend.amount,
+            ergo_tree: reserve.ergo_tree.clone(),
+            assets: reserve.assets.clone(),
+            additional_registers: registers,
+            creation_height: context.current_height,
+        },
+        Eip12Output {
+            value: spend.amount - context.fee,
+            ergo_tree: p2pk_tree(&spend.receiver_pubkey),
+            assets: Vec::new(),
+            additional_registers: BTreeMap::new(),
+            creation_height: context.current_height,
+        },
+        fee_output(context)?,
+    ];
+
+    Ok(WalletTransaction {
+        unsigned_tx: Eip12UnsignedTx {
+            inputs: vec![reserve_input],
+            data_inputs: vec![data_input],
+            outputs,
+        },
+        reduced_inputs: vec![ReducedInput::ProveDlog(spend.receiver_pubkey)],
+    })
+}
+
+/// Funding boxes picked for a transaction and what is left over
+struct Funding {
+    boxes: Vec<Eip12Box>,
+    reduced_inputs: Vec<ReducedInput>,
+    change_value: u64,
+    change_assets: Vec<Eip12Asset>,
+}
+
+// Pick boxes until they cover `value` (and one `token`, if given), leaving
+// either no change or enough for a change box
+fn select_funding(funding: &[Eip12Box], value: u64, token: Option<&str>) -> Result<Funding, TransactionBuilderError> {
+    let holds_token =
+        |b: &Eip12Box| token.is_some_and(|token| b.assets.iter().any(|a| a.token_id.eq_ignore_ascii_case(token)));
+    // Boxes holding the token first, so it is always picked up
+    let mut candidates: Vec<&Eip12Box> = funding.iter().filter(|b| holds_token(b)).collect();
+    candidates.extend(funding.iter().filter(|b| !holds_token(b)));
+
+    let mut selected = Funding {
+        boxes: Vec::new(),
+        reduced_inputs: Vec::new(),
+        change_value: 0,
+        change_assets: Vec::new(),
+    };
+    let mut total: u64 = 0;
+    for candidate in candidates {
+        selected.reduced_inputs.push(funding_proposition(candidate)?);
+        selected.boxes.push(candidate.clone());
+        total = total.saturating_add(candidate.value);
+
+        let change_assets = change_assets(&selected.boxes, token);
+        let has_token = token.is_none() || selected.boxes.iter().any(&holds_token);
+        if !has_token || total < value {
+            continue;
+        }
+        let change_value = total - value;
+        if (change_value == 0 && change_assets.is_empty()) || change_value >= MIN_BOX_VALUE {
+            selected.change_value = change_value;
+            selected.change_assets = change_assets;
+            return Ok(selected);
+        }
+    }
+
+    Err(TransactionBuilderError::InsufficientFunds(match token {
+        Some(token) => format!(
+            "Wallet boxes hold {} nanoERG, {} needed with token {} and room for change",
+            total, value, token
+        ),
+        None => format!("Wallet boxes hold {} nanoERG, {} needed with room for change", total, value),
+    }))
+}
+
+// Tokens of the selected boxes, less one unit of the token being spent
+fn change_assets(boxes: &[Eip12Box], spent_token: Option<&str>) -> Vec<Eip12Asset> {
+    let mut totals: Vec<Eip12Asset> = Vec::new();
+    for asset in boxes.iter().flat_map(|b| &b.assets) {
+        let token_id = asset.token_id.to_lowercase();
+        match totals.iter_mut().find(|total| total.token_id == token_id) {
+            Some(total) => total.amount = total.amount.saturating_add(asset.amount),
+            None => totals.push(Eip12Asset {
+                token_id,
+                amount: asset.amount,
+            }),
+        }
+    }
+    if let Some(spent) = spent_token.and_then(|token| totals.iter_mut().find(|t| t.token_id.eq_ignore_ascii_case(token))) {
+        spent.amount -= 1;
+    }
+    totals.retain(|total| total.amount > 0);
+    totals
+}
+
+fn change_output(funding: &Funding, context: &TxContext) -> Result<Option<Eip12Output>, TransactionBuilderError> {
+    if funding.change_value == 0 {
+        return Ok(None);
+    }
+    let network = NetworkPrefix::try_from(context.network_prefix)
+        .map_err(|e| TransactionBuilderError::Configuration(format!("Invalid network prefix: {}", e)))?;
+    let address = AddressEncoder::new(network)
+        .parse_address_from_str(&context.change_address)
+        .map_err(|e| TransactionBuilderError::Configuration(format!("Invalid change address: {}", e)))?;
+    let ergo_tree = address
+        .script()
+        .map_err(|e| TransactionBuilderError::Configuration(format!("Invalid change address: {}", e)))?;
+
+    Ok(Some(Eip12Output {
+        value: funding.change_value,
+        ergo_tree: hex::encode(ergo_tree.sigma_serialize_bytes()),
+        assets: funding.change_assets.clone(),
+        additional_registers: BTreeMap::new(),
+        creation_height: context.current_height,
+    }))
+}
+
+fn fee_output(context: &TxContext) -> Result<Eip12Output, TransactionBuilderError> {
+    let fee = BoxValue::new(context.fee)
+        .map_err(|e| TransactionBuilderError::Configuration(format!("Invalid fee {}: {:?}", context.fee, e)))?;
+    let fee_box = new_miner_fee_box(fee, context.current_height)
+        .map_err(|e| TransactionBuilderError::TransactionBuilding(format!("Failed to build fee box: {:?}", e)))?;
+    Ok(Eip12Output {
+        value: context.fee,
+        ergo_tree: hex::encode(fee_box.ergo_tree.sigma_serialize_bytes()),
+        assets: Vec::new(),
+        additional_registers: BTreeMap::new(),
+        creation_height: context.current_height,
+    })
+}
+
+// Funding boxes must be plain P2PK boxes, so ErgoPay wallets know which key signs
+fn funding_proposition(funding: &Eip12Box) -> Result<ReducedInput, TransactionBuilderError> {
+    let tree = decode_hex("ErgoTree", &funding.ergo_tree)?;
+    match tree.as_slice() {
+        [0x00, 0x08, PROVE_DLOG_OP_CODE, pubkey @ ..] if pubkey.len() == 33 => {
+            Ok(ReducedInput::ProveDlog(pubkey.try_into().expect("length checked")))
+        }
+        _ => Err(TransactionBuilderError::Configuration(format!(
+            "Funding box {} is not protected by a public key",
+            funding.box_id
+        ))),
+    }
+}
+
+// Context variables #0-#8 of the reserve input redeeming `spend`, the
+// reserve output being the first
+pub(crate) fn redemption_extension(spend: &RedemptionSpend) -> Result<ContextExtension, TransactionBuilderError> {
+    ContextExtensionBuilder::redemption(0)
+        .receiver(&spend.receiver_pubkey)
+        .reserve_signature(&spend.reserve_signature)
+        .debt(spend.total_debt, spend.timestamp)
+        .insert_proof(spend.reserve_insert_proof.clone())
+        .tracker_signature(&spend.tracker_signature)
+        .reserve_lookup_proof(spend.reserve_lookup_proof.clone())
+        .tracker_lookup_proof(spend.tracker_lookup_proof.clone())
+        .build()
+}
+
+// Contract version locking a reserve; spending it recreates the reserve under
+// the same version
+fn check_reserve_box<'a>(
+    reserve: &Eip12Box,
+    contracts: &'a ContractRegistry,
+) -> Result<&'a ReserveContract, TransactionBuilderError> {
+    contracts.contract_for_tree(&reserve.ergo_tree).ok_or_else(|| {
+        TransactionBuilderError::Configuration(format!("Box {} is not a Basis reserve", reserve.box_id))
+    })
+}
+
+// Owner key of a reserve, from its R4 group element
+pub(crate) fn reserve_owner(reserve: &Eip12Box) -> Result<PubKey, TransactionBuilderError> {
+    reserve
+        .additional_registers
+        .get("R4")
+        .and_then(|r4| r4.strip_prefix("07"))
+        .and_then(|pubkey| hex::decode(pubkey).ok())
+        .and_then(|pubkey| pubkey.try_into().ok())
+        .ok_or_else(|| TransactionBuilderError::Configuration("Reserve box has no owner key in R4".to_string()))
+}
+
+// R5 of the reserve after inserting `key -> value` with `proof`
+fn next_reserve_tree(
+    reserve: &Eip12Box,
+    key: Vec<u8>,
+    value: Vec<u8>,
+    proof: &[u8],
+) -> Result<String, TransactionBuilderError> {
+    let tree = reserve
+        .additional_registers
+        .get("R5")
+        .map(|r5| decode_hex("R5", r5))
+        .transpose()?
+        .filter(|tree| tree.len() > 34 && tree[0] == AVL_TREE_TYPE)
+        .ok_or_else(|| TransactionBuilderError::Configuration("Reserve box has no AVL tree in R5".to_string()))?;
+
+    let operations = basis_trees::encode_operations(&[Operation::Insert(KeyValue {
+        key: key.into(),
+        value: value.into(),
+    })])
+    .map_err(|e| TransactionBuilderError::TransactionBuilding(format!("Invalid reserve tree insert: {:?}", e)))?;
+    let verified = basis_trees::verify_operations(&tree[1..34], proof, &operations).map_err(|_| {
+        TransactionBuilderError::Configuration("Reserve insert proof does not match the reserve tree".to_string())
+    })?;
+
+    // Flags and key and value lengths stay as they are
+    let mut next = vec![AVL_TREE_TYPE];
+    next.extend_from_slice(&verified.digest);
+    next.extend_from_slice(&tree[34..]);
+    Ok(hex::encode(next))
+}
+
+fn reserve_ergo_tree(contracts: &ContractRegistry) -> Result<String, TransactionBuilderError> {
+    contracts
+        .latest()
+        .map(|contract| contract.ergo_tree_hex.clone())
+        .ok_or_else(|| TransactionBuilderError::Configuration("No reserve contract is registered".to_string()))
+}
+
+fn p2pk_tree(pubkey: &PubKey) -> String {
+    format!("0008cd{}", hex::encode(pubkey))
+}
+
+fn group_element(pubkey: &PubKey) -> Result<Constant, TransactionBuilderError> {
+    EcPoint::sigma_parse_bytes(pubkey)
+        .map(Constant::from)
+        .map_err(|e| TransactionBuilderError::Configuration(format!("Invalid public key: {}", e)))
+}
+
+fn constant_hex(constant: Constant) -> String {
+    hex::encode(constant.sigma_serialize_bytes())
+}
+
+fn decode_hex(what: &str, value: &str) -> Result<Vec<u8>, TransactionBuilderError> {
+    hex::decode(value).map_err(|_| TransactionBuilderError::Configuration(format!("Invalid {} hex: {}", what, value)))
+}
+
+fn decode_id(what: &str, value: &str) -> Result<Vec<u8>, TransactionBuilderError> {
+    let bytes = decode_hex(what, value)?;
+    if bytes.len() != 32 {
+        return Err(TransactionBuilderError::Configuration(format!(
+            "{} must be 32 bytes: {}",
+            what, value
+        )));
+    }
+    Ok(bytes)
+}
+
+// Unsigned VLQ, as the node serializes counts, values and heights
+fn put_vlq(w: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            w.push(byte);
+            return;
+        }
+        w.push(byte | 0x80);
+    }
+}
+
+/// Registers hold the serialized constant, or for indexed node boxes an
+/// object with the constant in `serializedValue`
+fn deserialize_registers<'de, D>(deserializer: D) -> Result<BTreeMap<String, String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Register {
+        Serialized(String),
+        Rendered {
+            #[serde(rename = "serializedValue")]
+            serialized_value: String,
+        },
+    }
+
+    let registers = BTreeMap::<String, Register>::deserialize(deserializer)?;
+    Ok(registers
+        .into_iter()
+        .map(|(name, register)| match register {
+            Register::Serialized(value) | Register::Rendered { serialized_value: value } => (name, value),
+        })
+        .collect())
+}
+
+/// EIP-12 amounts are strings; nodes return numbers
+mod string_amount {
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &u64, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&value.to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Amount {
+            Number(u64),
+            Text(String),
+        }
+
+        match Amount::deserialize(deserializer)? {
+            Amount::Number(value) => Ok(value),
+            Amount::Text(value) => value.parse().map_err(D::Error::custom),
+        }
+    }
+}
+
+/// P2PK address of a public key on the network of `context`
+pub fn p2pk_address(pubkey: &PubKey, context: &TxContext) -> Result<String, TransactionBuilderError> {
+    let network = NetworkPrefix::try_from(context.network_prefix)
+        .map_err(|e| TransactionBuilderError::Configuration(format!("Invalid network prefix: {}", e)))?;
+    let address = Address::p2pk_from_pk_bytes(pubkey)
+        .map_err(|e| TransactionBuilderError::Configuration(format!("Invalid public key: {}", e)))?;
+    Ok(AddressEncoder::new(network).address_to_str(&address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schnorr::generate_keypair;
+    use basis_trees::BasisAvlTree;
+    use ergo_lib::chain::ergo_box::box_builder::ErgoBoxCandidateBuilder;
+    use ergo_lib::chain::ergo_box::{BoxId, NonMandatoryRegisterId};
+    use ergo_lib::chain::token::{Token, TokenAmount, TokenId};
+    use ergo_lib::chain::transaction::unsigned::UnsignedTransaction;
+    use ergo_lib::chain::transaction::{DataInput, UnsignedInput};
+    use ergo_lib::ergotree_interpreter::sigma_protocol::prover::ContextExtension;
+    use ergo_lib::ergotree_ir::ergo_tree::ErgoTree;
+
+    const FEE: u64 = 1_000_000;
+
+    fn context(pubkey: &PubKey) -> TxContext {
+        let context = TxContext {
+            current_height: 1_000,
+            fee: FEE,
+            change_address: String::new(),
+            network_prefix: NetworkPrefix::Testnet as u8,
+        };
+        TxContext {
+            change_address: p2pk_address(pubkey, &context).unwrap(),
+            ..context
+        }
+    }
+
+    fn wallet_box(id: u8, value: u64, pubkey: &PubKey) -> Eip12Box {
+        Eip12Box {
+            box_id: hex::encode([id; 32]),
+            transaction_id: hex::encode([0xee; 32]),
+            index: 0,
+            value,
+            ergo_tree: p2pk_tree(pubkey),
+            assets: Vec::new(),
+            additional_registers: BTreeMap::new(),
+            creation_height: 900,
+            extension: None,
+        }
+    }
+
+    fn reserve_box(owner: &PubKey, tracker_nft: &str, tree: &[u8; 33], value: u64) -> Eip12Box {
+        let mut registers = BTreeMap::new();
+        registers.insert("R4".to_string(), constant_hex(group_element(owner).unwrap()));
+        registers.insert("R5".to_string(), format!("64{}012000", hex::encode(tree)));
+        registers.insert(
+            "R6".to_string(),
+            constant_hex(Constant::from(hex::decode(tracker_nft).unwrap())),
+        );
+        Eip12Box {
+            box_id: hex::encode([0x11; 32]),
+            transaction_id: hex::encode([0xee; 32]),
+            index: 0,
+            value,
+            ergo_tree: reserve_ergo_tree(&ContractRegistry::builtin()).unwrap(),
+            assets: vec![Eip12Asset {
+                token_id: hex::encode([0x22; 32]),
+                amount: 1,
+            }],
+            additional_registers: registers,
+            creation_height: 800,
+            extension: None,
+        }
+    }
+
+    #[test]
+    fn test_bytes_to_sign_matches_ergo_lib() {
+        let (_, pubkey) = generate_keypair();
+        let token_id = hex::encode([0x33; 32]);
+        let tx = WalletTransaction {
+            unsigned_tx: Eip12UnsignedTx {
+                inputs: vec![Eip12Box {
+                    extension: Some(BTreeMap::from([
+                        ("3".to_string(), constant_hex(Constant::from(7i64))),
+                        ("0".to_string(), constant_hex(Constant::from(10i8))),
+                    ])),
+                    ..wallet_box(1, 5_000_000_000, &pubkey)
+                }],
+                data_inputs: vec![wallet_box(2, 1_000_000, &pubkey)],
+                outputs: vec![Eip12Output {
+                    value: 4_999_000_000,
+                    ergo_tree: p2pk_tree(&pubkey),
+                    assets: vec![Eip12Asset {
+                        token_id: token_id.clone(),
+                        amount: 300,
+                    }],
+                    additional_registers: BTreeMap::from([
+                        ("R4".to_string(), constant_hex(Constant::from(42i64))),
+                        ("R5".to_string(), constant_hex(Constant::from(vec![1u8, 2, 3]))),
+                    ]),
+                    creation_height: 1_000,
+                }],
+            },
+            reduced_inputs: vec![ReducedInput::ProveDlog(pubkey)],
+        };
+
+        let mut extension = ContextExtension::empty();
+        extension.values.insert(0, Constant::from(10i8));
+        extension.values.insert(3, Constant::from(7i64));
+        let tree = ErgoTree::sigma_parse_bytes(&hex::decode(p2pk_tree(&pubkey)).unwrap()).unwrap();
+        let mut output = ErgoBoxCandidateBuilder::new(BoxValue::new(4_999_000_000).unwrap(), tree, 1_000);
+        output.add_token(Token {
+            token_id: TokenId::from(BoxId::try_from(token_id).unwrap()),
+            amount: TokenAmount::try_from(300u64).unwrap(),
+        });
+        output.set_register_value(NonMandatoryRegisterId::R4, Constant::from(42i64));
+        output.set_register_value(NonMandatoryRegisterId::R5, Constant::from(vec![1u8, 2, 3]));
+        let expected = UnsignedTransaction::new(
+            vec![UnsignedInput::new(BoxId::try_from(hex::encode([1u8; 32])).unwrap(), extension)],
+            vec![DataInput {
+                box_id: BoxId::try_from(hex::encode([2u8; 32])).unwrap(),
+            }],
+            vec![output.build().unwrap()],
+        );
+
+        assert_eq!(tx.bytes_to_sign().unwrap(), expected.bytes_to_sign());
+        assert_eq!(tx.tx_id().unwrap(), String::from(expected.id()));
+    }
+
+    #[test]
+    fn test_reduced_transaction_and_ergopay_url() {
+        let (_, pubkey) = generate_keypair();
+        let tx = WalletTransaction {
+            unsigned_tx: Eip12UnsignedTx {
+                inputs: vec![wallet_box(1, 2_000_000, &pubkey), wallet_box(2, 2_000_000, &pubkey)],
+                data_inputs: Vec::new(),
+                outputs: vec![fee_output(&context(&pubkey)).unwrap()],
+            },
+            reduced_inputs: vec![ReducedInput::ProveDlog(pubkey), ReducedInput::True],
+        };
+
+        let message = tx.bytes_to_sign().unwrap();
+        let reduced = tx.reduced_bytes().unwrap();
+        let mut prefix = Vec::new();
+        put_vlq(&mut prefix, message.len() as u64);
+        assert_eq!(&reduced[..prefix.len()], prefix.as_slice());
+        let reduced = &reduced[prefix.len()..];
+        assert_eq!(&reduced[..message.len()], message.as_slice());
+        let mut propositions = vec![PROVE_DLOG_OP_CODE];
+        propositions.extend_from_slice(&pubkey);
+        propositions.extend_from_slice(&[0, TRIVIAL_PROP_TRUE_OP_CODE, 0, 0]);
+        assert_eq!(&reduced[message.len()..], propositions.as_slice());
+
+        let url = tx.ergopay_url().unwrap();
+        let encoded = url.strip_prefix("ergopay:").unwrap();
+        assert_eq!(
+            base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(encoded).unwrap(),
+            tx.reduced_bytes().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_reserve_creation_spends_nft_and_returns_change() {
+        let (_, owner) = generate_keypair();
+        let reserve_nft = hex::encode([0x22; 32]);
+        let mut nft_box = wallet_box(1, 1_000_000, &owner);
+        nft_box.assets.push(Eip12Asset {
+            token_id: reserve_nft.clone(),
+            amount: 1,
+        });
+        let funding = vec![wallet_box(2, 10_000_000_000, &owner), nft_box];
+        let reserve = NewReserve {
+            owner_pubkey: owner,
+            reserve_nft_id: reserve_nft.clone(),
+            tracker_nft_id: hex::encode([0x44; 32]),
+            amount: 5_000_000_000,
+        };
+
+        let tx = reserve_creation_tx(&reserve, &funding, &ContractRegistry::builtin(), &context(&owner)).unwrap().unsigned_tx;
+        assert_eq!(tx.inputs.len(), 2);
+        assert_eq!(tx.inputs[0].box_id, hex::encode([1u8; 32]));
+        let [reserve_output, fee, change] = tx.outputs.as_slice() else {
+            panic!("expected reserve, fee and change outputs: {:?}", tx.outputs);
+        };
+        assert_eq!(reserve_output.ergo_tree, reserve_ergo_tree(&ContractRegistry::builtin()).unwrap());
+        assert_eq!(reserve_output.assets[0].token_id, reserve_nft);
+        assert_eq!(reserve_output.additional_registers["R5"], EMPTY_RESERVE_TREE);
+        assert_eq!(fee.value, FEE);
+        assert_eq!(change.value, 10_001_000_000 - 5_000_000_000 - FEE);
+        assert!(change.assets.is_empty());
+        assert_eq!(change.ergo_tree, p2pk_tree(&owner));
+    }
+
+    #[test]
+    fn test_top_up_preserves_reserve_and_rejects_small_amounts() {
+        let (_, owner) = generate_keypair();
+        let reserve = reserve_box(&owner, &hex::encode([0x44; 32]), &[0u8; 33], 1_000_000_000);
+        let funding = vec![wallet_box(2, 600_000_000, &owner)];
+        let context = context(&owner);
+
+        let tx = top_up_tx(&reserve, 500_000_000, &funding, &ContractRegistry::builtin(), &context).unwrap();
+        assert_eq!(tx.reduced_inputs, vec![ReducedInput::True, ReducedInput::ProveDlog(owner)]);
+        let reserve_input = &tx.unsigned_tx.inputs[0];
+        assert_eq!(reserve_input.extension.as_ref().unwrap()["0"], "020a");
+        let reserve_output = &tx.unsigned_tx.outputs[0];
+        assert_eq!(reserve_output.value, 1_500_000_000);
+        assert_eq!(reserve_output.additional_registers, reserve.additional_registers);
+        assert_eq!(reserve_output.assets, reserve.assets);
+        assert_eq!(tx.unsigned_tx.outputs[2].value, 100_000_000 - FEE);
+
+        assert!(matches!(
+            top_up_tx(&reserve, MIN_TOP_UP - 1, &funding, &ContractRegistry::builtin(), &context),
+            Err(TransactionBuilderError::Configuration(_))
+        ));
+        assert!(matches!(
+            top_up_tx(&reserve, 600_000_000, &funding, &ContractRegistry::builtin(), &context),
+            Err(TransactionBuilderError::InsufficientFunds(_))
+        ));
+    }
+
+    #[test]
+    fn test_reserve_box_checked_against_registered_versions() {
+        let (_, owner) = generate_keypair();
+        let mut reserve = reserve_box(&owner, &hex::encode([0x44; 32]), &[0u8; 33], 1_000_000_000);
+        let builtin = ContractRegistry::builtin();
+        assert_eq!(check_reserve_box(&reserve, &builtin).unwrap().version, 1);
+
+        // A reserve left on a retired template is only spendable while that version is registered
+        reserve.ergo_tree = "100104c801d17300".to_string();
+        assert!(check_reserve_box(&reserve, &builtin).is_err());
+        let mut contracts = builtin.clone();
+        contracts
+            .register(ReserveContract::from_ergo_tree_hex(0, &reserve.ergo_tree).unwrap())
+            .unwrap();
+        assert_eq!(check_reserve_box(&reserve, &contracts).unwrap().version, 0);
+    }
+
+    #[test]
+    fn test_redemption_updates_reserve_tree() {
+        let (_, owner) = generate_keypair();
+        let (_, receiver) = generate_keypair();
+        let tracker_nft = hex::encode([0x44; 32]);
+
+        let mut tree = BasisAvlTree::new().unwrap();
+        let start = tree.root_digest();
+        let key = NoteKey::from_keys(&owner, &receiver).to_bytes();
+        let mut value = 1_700_000_000_000u64.to_be_bytes().to_vec();
+        value.extend_from_slice(&400_000_000u64.to_be_bytes());
+        tree.insert(key, value).unwrap();
+        let insert_proof = tree.generate_proof();
+
+        let reserve = reserve_box(&owner, &tracker_nft, &start, 2_000_000_000);
+        let mut tracker = wallet_box(3, 1_000_000, &owner);
+        tracker.assets.push(Eip12Asset {
+            token_id: tracker_nft.clone(),
+            amount: 1,
+        });
+        let spend = RedemptionSpend {
+            receiver_pubkey: receiver,
+            total_debt: 1_000_000_000,
+            timestamp: 1_700_000_000_000,
+            amount: 400_000_000,
+            already_redeemed: 0,
+            reserve_signature: vec![1; 65],
+            tracker_signature: vec![2; 65],
+            tracker_lookup_proof: vec![3; 40],
+            reserve_lookup_proof: None,
+            reserve_insert_proof: insert_proof.clone(),
+        };
+
+        let tx = redemption_tx(&reserve, &tracker, &spend, &ContractRegistry::builtin(), &context(&receiver)).unwrap();
+        assert_eq!(tx.reduced_inputs, vec![ReducedInput::ProveDlog(receiver)]);
+        let extension = tx.unsigned_tx.inputs[0].extension.as_ref().unwrap();
+        assert_eq!(extension["0"], "0200");
+        assert!(!extension.contains_key("7"));
+        assert_eq!(tx.unsigned_tx.data_inputs[0].box_id, tracker.box_id);
+        let outputs = &tx.unsigned_tx.outputs;
+        assert_eq!(outputs[0].value, 1_600_000_000);
+        assert_eq!(
+            outputs[0].additional_registers["R5"],
+            format!("64{}012000", hex::encode(tree.root_digest()))
+        );
+        assert_eq!(outputs[1].value, 400_000_000 - FEE);
+        assert_eq!(outputs[1].ergo_tree, p2pk_tree(&receiver));
+        assert_eq!(outputs[2].value, FEE);
+
+        // The proof must be for the reserve's own tree
+        let other_reserve = reserve_box(&owner, &tracker_nft, &[0u8; 33], 2_000_000_000);
+        assert!(redemption_tx(&other_reserve, &tracker, &spend, &ContractRegistry::builtin(), &context(&receiver)).is_err());
+
+        // The data input must be the tracker the reserve names
+        let other_tracker = wallet_box(4, 1_000_000, &owner);
+        assert!(redemption_tx(&reserve, &other_tracker, &spend, &ContractRegistry::builtin(), &context(&receiver)).is_err());
+
+        let mut too_much = spend;
+        too_much.amount = 1_100_000_000;
+        assert!(matches!(
+            redemption_tx(&reserve, &tracker, &too_much, &ContractRegistry::builtin(), &context(&receiver)),
+            Err(TransactionBuilderError::InsufficientFunds(_))
+        ));
+    }
+
+    #[test]
+    fn test_node_box_json_deserializes() {
+        let json = serde_json::json!({
+            "boxId": hex::encode([1u8; 32]),
+            "transactionId": hex::encode([2u8; 32]),
+            "index": 1,
+            "value": 1_000_000_000u64,
+            "ergoTree": "0008cd",
+            "assets": [{"tokenId": hex::encode([3u8; 32]), "amount": 5}],
+            "additionalRegisters": {"R4": {"serializedValue": "0402", "sigmaType": "SLong"}},
+            "creationHeight": 10,
+            "settlementHeight": 12,
+        });
+        let parsed: Eip12Box = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed.value, 1_000_000_000);
+        assert_eq!(parsed.additional_registers["R4"], "0402");
+
+        let eip12 = serde_json::to_value(&parsed).unwrap();
+        assert_eq!(eip12["value"], "1000000000");
+        assert_eq!(eip12["assets"][0]["amount"], "5");
+        assert!(eip12.get("extension").is_none());
+        assert_eq!(serde_json::from_value::<Eip12Box>(eip12).unwrap(), parsed);
+    }
+}
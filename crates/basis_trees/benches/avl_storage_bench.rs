@@ -0,0 +1,93 @@
+//! Compare in-memory and fjall-backed AVL trees
+
+use basis_trees::{BasisAvlTree, PersistentTreeConfig};
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+
+const TREE_SIZE: u32 = 10_000;
+const COMMIT_INTERVAL: u32 = 1_000;
+
+fn key(i: u32) -> Vec<u8> {
+    let mut key = vec![0u8; 32];
+    key[..4].copy_from_slice(&i.to_be_bytes());
+    key[4] = 1;
+    key
+}
+
+fn fill(tree: &mut BasisAvlTree) {
+    for i in 0..TREE_SIZE {
+        tree.insert(key(i), i.to_be_bytes().to_vec()).unwrap();
+        if (i + 1) % COMMIT_INTERVAL == 0 {
+            tree.commit().unwrap();
+        }
+    }
+    tree.commit().unwrap();
+}
+
+fn bench_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("avl_insert_10k");
+    group.sample_size(10);
+
+    group.bench_function("in_memory", |b| {
+        b.iter_batched(
+            || BasisAvlTree::new().unwrap(),
+            |mut tree| {
+                fill(&mut tree);
+                black_box(tree.root_digest());
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.bench_function("persistent", |b| {
+        b.iter_batched(
+            || {
+                let dir = tempfile::tempdir().unwrap();
+                let tree = BasisAvlTree::open_persistent(dir.path(), PersistentTreeConfig::default()).unwrap();
+                (dir, tree)
+            },
+            |(dir, mut tree)| {
+                fill(&mut tree);
+                black_box(tree.root_digest());
+                drop(tree);
+                drop(dir);
+            },
+            BatchSize::PerIteration,
+        );
+    });
+
+    group.finish();
+}
+
+fn bench_lookup_proof(c: &mut Criterion) {
+    let mut group = c.benchmark_group("avl_lookup_proof_10k");
+
+    let mut in_memory = BasisAvlTree::new().unwrap();
+    fill(&mut in_memory);
+    group.bench_function("in_memory", |b| {
+        let mut i = 0;
+        b.iter(|| {
+            i = (i + 7919) % TREE_SIZE;
+            black_box(in_memory.lookup_proof(&key(i)).unwrap());
+        });
+    });
+
+    let dir = tempfile::tempdir().unwrap();
+    let config = PersistentTreeConfig {
+        resident_depth: 4,
+        ..PersistentTreeConfig::default()
+    };
+    let mut persistent = BasisAvlTree::open_persistent(dir.path(), config).unwrap();
+    fill(&mut persistent);
+    group.bench_function("persistent", |b| {
+        let mut i = 0;
+        b.iter(|| {
+            i = (i + 7919) % TREE_SIZE;
+            black_box(persistent.lookup_proof(&key(i)).unwrap());
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_insert, bench_lookup_proof);
+criterion_main!(benches);
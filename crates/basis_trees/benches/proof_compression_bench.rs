@@ -0,0 +1,67 @@
+//! Size and speed of compressed AVL proofs
+
+use basis_trees::{compress_proof, compress_proofs, decompress_proofs, BasisAvlTree};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const TREE_SIZE: u32 = 10_000;
+const BATCH_SIZE: u32 = 64;
+
+fn key(i: u32) -> Vec<u8> {
+    let mut key = vec![0u8; 32];
+    key[..4].copy_from_slice(&i.to_be_bytes());
+    key[4] = 1;
+    key
+}
+
+fn fill(tree: &mut BasisAvlTree) {
+    for i in 0..TREE_SIZE {
+        tree.insert(key(i), u64::from(i).to_be_bytes().to_vec()).unwrap();
+    }
+    tree.commit().unwrap();
+}
+
+fn report(name: &str, raw: usize, compressed: usize) {
+    println!(
+        "{}: {} -> {} bytes ({:.1}%)",
+        name,
+        raw,
+        compressed,
+        compressed as f64 * 100.0 / raw as f64
+    );
+}
+
+fn bench_proof_compression(c: &mut Criterion) {
+    let mut tree = BasisAvlTree::new().unwrap();
+    fill(&mut tree);
+
+    let single = tree.lookup_proof(&key(TREE_SIZE / 2)).unwrap().avl_proof;
+    let adjacent: Vec<Vec<u8>> = (0..BATCH_SIZE)
+        .map(|i| tree.lookup_proof(&key(TREE_SIZE / 2 + i)).unwrap().avl_proof)
+        .collect();
+    let scattered: Vec<Vec<u8>> = (0..BATCH_SIZE)
+        .map(|i| tree.lookup_proof(&key(i * 7919 % TREE_SIZE)).unwrap().avl_proof)
+        .collect();
+    for i in 0..BATCH_SIZE {
+        tree.update(key(i * 131), vec![0u8; 8]).unwrap();
+    }
+    let batch = tree.generate_proof_batch().unwrap().avl_proof;
+
+    let total = |proofs: &[Vec<u8>]| proofs.iter().map(Vec::len).sum::<usize>();
+    report("single lookup", single.len(), compress_proof(&single).unwrap().len());
+    report("64 adjacent lookups", total(&adjacent), compress_proofs(&adjacent).unwrap().len());
+    report("64 scattered lookups", total(&scattered), compress_proofs(&scattered).unwrap().len());
+    report("64 update batch", batch.len(), compress_proof(&batch).unwrap().len());
+
+    let mut group = c.benchmark_group("proof_compression_10k");
+    group.bench_function("compress_64_adjacent", |b| {
+        b.iter(|| black_box(compress_proofs(&adjacent).unwrap()));
+    });
+    let compressed = compress_proofs(&adjacent).unwrap();
+    group.bench_function("decompress_64_adjacent", |b| {
+        b.iter(|| black_box(decompress_proofs(&compressed).unwrap()));
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_proof_compression);
+criterion_main!(benches);
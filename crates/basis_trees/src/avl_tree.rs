@@ -2,52 +2,155 @@
 
 use crate::state::TrackerState;
 use crate::errors::TreeError;
+use crate::fjall_storage::{self, FjallNodeStore, NodeCacheStats, PersistentTreeConfig};
+use crate::proofs::{BatchProof, MembershipProof};
+use crate::verifier::{encode_operations, verify_operations};
 
 use ergo_avltree_rust::{
     authenticated_tree_ops::AuthenticatedTreeOps,
     batch_avl_prover::BatchAVLProver,
-    batch_node::AVLTree,
+    batch_node::{AVLTree, Node, NodeId},
     operation::{KeyValue, Operation},
 };
 
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::Arc;
 
-/// In-memory AVL tree state for tracker commitments
+/// AVL tree state for tracker commitments
+///
+/// Created with [`BasisAvlTree::new`] the whole tree lives in memory. Created
+/// with [`BasisAvlTree::open_persistent`] nodes are stored in fjall, only the
+/// top levels stay resident after each commit, and deeper nodes are loaded
+/// on demand through an LRU node cache.
 pub struct BasisAvlTree {
     prover: BatchAVLProver,
     current_state: TrackerState,
     /// In-memory cache for key-value lookups
     /// This mirrors the AVL tree state for efficient get() operations
     cache: HashMap<Vec<u8>, Vec<u8>>,
+    /// Operations performed since the last proof was generated
+    pending_operations: Vec<Operation>,
+    /// Tree digest at the time the last proof was generated
+    proof_start_digest: [u8; 33],
+    /// Persistent node store, if the tree is backed by fjall
+    node_store: Option<Arc<FjallNodeStore>>,
+    /// Number of levels kept resident after a commit (persistent mode only)
+    resident_depth: usize,
 }
 
-// Simple resolver function for AVL tree
-// Note: This resolver should never be called since we're using in-memory trees
-fn tree_resolver(_digest: &[u8; 32]) -> ergo_avltree_rust::batch_node::Node {
-    panic!("Tree resolver called - this should not happen with in-memory trees");
+/// Length of tree keys: blake2b256(issuer_pubkey || recipient_pubkey)
+const KEY_LENGTH: usize = 32;
+
+// Resolver for label-only nodes
+// In-memory trees never contain label-only nodes; persistent trees load them
+// from the registered node stores
+fn tree_resolver(digest: &[u8; 32]) -> Node {
+    fjall_storage::resolve_node(digest).unwrap_or_else(|| {
+        panic!("Tree resolver called for unknown node {:?} - node store is missing or corrupt", digest)
+    })
+}
+
+fn check_key_length(key: &[u8]) -> Result<(), TreeError> {
+    if key.len() != KEY_LENGTH {
+        return Err(TreeError::InvalidKeyLength(key.len()));
+    }
+    Ok(())
 }
 
 impl BasisAvlTree {
     /// Create a new in-memory AVL tree
     pub fn new() -> Result<Self, TreeError> {
         // Create an AVL tree with variable length values
-        // Key length: 32 bytes (blake2b256(issuer_pubkey || recipient_pubkey))
         // Value length: None for variable length values
-        let tree = AVLTree::new(tree_resolver, 32, None);
+        let tree = AVLTree::new(tree_resolver, KEY_LENGTH, None);
         let prover = BatchAVLProver::new(tree, true);
 
         let current_state = TrackerState::empty();
 
-        Ok(Self {
+        let mut avl_tree = Self {
             prover,
             current_state,
             cache: HashMap::new(),
-        })
+            pending_operations: Vec::new(),
+            proof_start_digest: [0u8; 33],
+            node_store: None,
+            resident_depth: 0,
+        };
+        avl_tree.proof_start_digest = avl_tree.root_digest();
+
+        Ok(avl_tree)
     }
 
+    /// Open or create an AVL tree backed by a fjall node store at `path`
+    ///
+    /// The tree resumes from the last committed root. Changes become durable
+    /// on [`BasisAvlTree::commit`].
+    pub fn open_persistent<P: AsRef<Path>>(path: P, config: PersistentTreeConfig) -> Result<Self, TreeError> {
+        let node_store = Arc::new(FjallNodeStore::open(path, config.node_cache_capacity)?);
+        fjall_storage::register_store(&node_store);
+
+        let mut tree = AVLTree::new(tree_resolver, KEY_LENGTH, None);
+        let stored_root = match node_store.root_digest() {
+            Ok(root) => root,
+            Err(e) => {
+                fjall_storage::unregister_store(&node_store);
+                return Err(e);
+            }
+        };
+        if let Some(root_digest) = stored_root {
+            let mut label = [0u8; 32];
+            label.copy_from_slice(&root_digest[..32]);
+            let root = match node_store.get_node(&label) {
+                Ok(Some(root)) => root,
+                Ok(None) => {
+                    fjall_storage::unregister_store(&node_store);
+                    return Err(TreeError::TreeCorruption);
+                }
+                Err(e) => {
+                    fjall_storage::unregister_store(&node_store);
+                    return Err(e);
+                }
+            };
+            tree.root = Some(Rc::new(RefCell::new(root)));
+            tree.height = root_digest[32] as usize;
+        }
+        let prover = BatchAVLProver::new(tree, true);
+
+        let mut avl_tree = Self {
+            prover,
+            current_state: TrackerState::empty(),
+            cache: HashMap::new(),
+            pending_operations: Vec::new(),
+            proof_start_digest: [0u8; 33],
+            node_store: Some(node_store),
+            resident_depth: config.resident_depth,
+        };
+        avl_tree.proof_start_digest = avl_tree.root_digest();
+
+        match stored_root {
+            Some(_) => avl_tree.update_state(),
+            None => {
+                // The empty tree's sentinel leaf is never marked new, store it explicitly
+                let root = avl_tree.root_node().ok_or(TreeError::InvalidState)?;
+                avl_tree.flush_nodes(vec![root])?;
+            }
+        }
 
+        Ok(avl_tree)
+    }
 
+    /// Whether the tree is backed by a persistent node store
+    pub fn is_persistent(&self) -> bool {
+        self.node_store.is_some()
+    }
 
+    /// Node cache statistics for persistent trees
+    pub fn node_cache_stats(&self) -> Option<NodeCacheStats> {
+        self.node_store.as_ref().map(|store| store.cache_stats())
+    }
 
     /// Insert a key-value pair into the AVL tree
     pub fn insert(&mut self, key: Vec<u8>, value: Vec<u8>) -> Result<(), TreeError> {
@@ -61,9 +164,10 @@ impl BasisAvlTree {
             .prover
             .perform_one_operation(&operation)
             .map_err(|e| TreeError::StorageError(format!("AVL tree insert failed: {:?}", e)))?;
+        self.pending_operations.push(operation);
 
         // Update cache
-        self.cache.insert(key.clone(), value.clone());
+        self.cache_value(key, value);
 
         // Update state
         self.update_state();
@@ -81,8 +185,10 @@ impl BasisAvlTree {
 
         match self.prover.perform_one_operation(&update_op) {
             Ok(_) => {
+                self.pending_operations.push(update_op);
+
                 // Update cache
-                self.cache.insert(key.clone(), value.clone());
+                self.cache_value(key, value);
                 self.update_state();
                 Ok(())
             },
@@ -96,20 +202,189 @@ impl BasisAvlTree {
                 self.prover
                     .perform_one_operation(&insert_op)
                     .map_err(|e| TreeError::StorageError(format!("AVL tree operation failed: {:?}", e)))?;
+                self.pending_operations.push(insert_op);
 
                 // Update cache
-                self.cache.insert(key.clone(), value.clone());
+                self.cache_value(key, value);
                 self.update_state();
                 Ok(())
             }
         }
     }
 
+    /// Insert a batch of new key-value pairs for bulk loading
+    ///
+    /// Entries are applied in key order whatever order they are given in, so
+    /// the same batch always gives the same tree. The root digest is
+    /// recomputed once for the whole batch, and the batch is committed with a
+    /// single proof that also covers operations pending before it. The batch
+    /// is checked before anything is applied: it fails with
+    /// [`TreeError::DuplicateKey`] if a key is already in the tree or repeats
+    /// within the batch, and with [`TreeError::InvalidKeyLength`] if a key is
+    /// not 32 bytes, leaving the tree unchanged.
+    ///
+    /// The shape of an AVL tree depends on insertion order, so a committed
+    /// tree is rebuilt with [`BasisAvlTree::update`] in its original order.
+    pub fn insert_batch(&mut self, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<BatchProof, TreeError> {
+        let mut sorted = BTreeMap::new();
+        for (key, value) in entries {
+            check_key_length(&key)?;
+            if self.get(&key).is_some() || sorted.insert(key, value).is_some() {
+                return Err(TreeError::DuplicateKey);
+            }
+        }
+
+        let operations = sorted
+            .into_iter()
+            .map(|(key, value)| Operation::Insert(KeyValue { key: key.into(), value: value.into() }))
+            .collect();
+        self.apply_batch(operations)
+    }
+
+    /// Insert or update a batch of key-value pairs for bulk loading
+    ///
+    /// Like [`BasisAvlTree::insert_batch`], but keys already in the tree get
+    /// the new value, and of a key repeated within the batch the last value
+    /// wins.
+    pub fn update_batch(&mut self, entries: Vec<(Vec<u8>, Vec<u8>)>) -> Result<BatchProof, TreeError> {
+        let mut sorted = BTreeMap::new();
+        for (key, value) in entries {
+            check_key_length(&key)?;
+            sorted.insert(key, value);
+        }
+
+        let operations = sorted
+            .into_iter()
+            .map(|(key, value)| Operation::InsertOrUpdate(KeyValue { key: key.into(), value: value.into() }))
+            .collect();
+        self.apply_batch(operations)
+    }
 
+    /// Perform checked batch operations, then update the state and commit once
+    ///
+    /// The prover cannot roll back, so the batch must be checked beforehand:
+    /// an operation can then only fail when a persistent tree cannot load a
+    /// node from its store. The operations before it stay applied and
+    /// pending, and the tree should be reopened from its last commit.
+    fn apply_batch(&mut self, operations: Vec<Operation>) -> Result<BatchProof, TreeError> {
+        for operation in operations {
+            if let Err(e) = self.prover.perform_one_operation(&operation) {
+                self.update_state();
+                return Err(TreeError::StorageError(format!("AVL tree batch operation failed: {:?}", e)));
+            }
+            if let Operation::Insert(entry) | Operation::InsertOrUpdate(entry) = &operation {
+                self.cache_value(entry.key.to_vec(), entry.value.to_vec());
+            }
+            self.pending_operations.push(operation);
+        }
+
+        self.update_state();
+        self.commit()
+    }
 
     /// Generate a proof for the current tree state
+    ///
+    /// Covers every operation since the previous proof; use `lookup_proof`
+    /// to prove the value of a single key.
     pub fn generate_proof(&mut self) -> Vec<u8> {
-        self.prover.generate_proof().to_vec()
+        match self.generate_proof_batch() {
+            Ok(batch) => batch.avl_proof,
+            Err(e) => {
+                tracing::error!("Failed to generate AVL proof: {:?}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Generate a proof covering all operations since the last proof,
+    /// together with the operation trail and the digests needed to verify it
+    ///
+    /// For persistent trees the nodes changed by these operations are written
+    /// to the node store first, since proof generation clears their dirty flags.
+    pub fn generate_proof_batch(&mut self) -> Result<BatchProof, TreeError> {
+        if self.node_store.is_some() && !self.pending_operations.is_empty() {
+            let dirty = self.dirty_nodes();
+            self.flush_nodes(dirty)?;
+        }
+
+        let avl_proof = self.prover.generate_proof().to_vec();
+        let operations = std::mem::take(&mut self.pending_operations);
+        let ending_digest = self.root_digest();
+        let starting_digest = std::mem::replace(&mut self.proof_start_digest, ending_digest);
+
+        // Only supported operation types are ever recorded
+        let operations = encode_operations(&operations).unwrap_or_default();
+
+        Ok(BatchProof::new(
+            starting_digest.to_vec(),
+            avl_proof,
+            operations,
+            ending_digest.to_vec(),
+        ))
+    }
+
+    /// Commit all pending operations
+    ///
+    /// Seals them into a batch proof and, for persistent trees, flushes the
+    /// dirty nodes and evicts everything below the resident depth from memory.
+    pub fn commit(&mut self) -> Result<BatchProof, TreeError> {
+        let batch = self.generate_proof_batch()?;
+
+        if self.node_store.is_some() {
+            if let Some(root) = self.root_node() {
+                prune_below(&root, 0, self.resident_depth);
+            }
+        }
+
+        Ok(batch)
+    }
+
+    /// Commit pending changes and sync the node store to disk
+    ///
+    /// In-memory trees have nothing to flush.
+    pub fn flush(&mut self) -> Result<(), TreeError> {
+        if let Some(store) = self.node_store.clone() {
+            self.commit()?;
+            store.persist()?;
+        }
+        Ok(())
+    }
+
+    /// Generate a lookup proof for a single key against the current root
+    ///
+    /// The lookup runs on a throwaway prover over the current tree, so the
+    /// proof only contains the path to the key and stays logarithmic in the
+    /// tree size. Operations still pending a batch proof are sealed first,
+    /// since the lookup must not disturb the nodes they touched; a later
+    /// `generate_proof_batch` only covers operations after this call.
+    pub fn lookup_proof(&mut self, key: &[u8]) -> Result<MembershipProof, TreeError> {
+        let value = self.get(key).ok_or(TreeError::KeyNotFound)?;
+
+        if !self.pending_operations.is_empty() {
+            let _ = self.generate_proof_batch()?;
+        }
+
+        let lookup = vec![Operation::Lookup(key.to_vec().into())];
+        let (avl_proof, digest) = self
+            .prover
+            .generate_proof_for_operations(&lookup)
+            .map_err(|e| TreeError::StorageError(format!("AVL lookup proof failed: {:?}", e)))?;
+        let operations = encode_operations(&lookup)?;
+
+        // Check the proof the same way the on-chain verifier will
+        let verified = verify_operations(&digest, &avl_proof, &operations)?;
+        if verified.digest.as_slice() != digest.as_ref()
+            || verified.results.first().cloned().flatten().as_ref() != Some(&value)
+        {
+            return Err(TreeError::TreeCorruption);
+        }
+
+        Ok(MembershipProof::new(
+            value,
+            avl_proof.to_vec(),
+            operations,
+            digest.to_vec(),
+        ))
     }
 
     /// Get the root digest of the AVL tree
@@ -126,6 +401,14 @@ impl BasisAvlTree {
     /// Lookup a value by key in the AVL tree
     /// Returns the value bytes if found, None otherwise
     pub fn get(&self, key: &[u8]) -> Option<Vec<u8>> {
+        if self.node_store.is_some() {
+            // Persistent trees don't mirror all values in memory
+            return self
+                .prover
+                .unauthenticated_lookup(&key.to_vec().into())
+                .map(|value| value.to_vec());
+        }
+
         // Use the in-memory cache for efficient lookups
         self.cache.get(key).cloned()
     }
@@ -145,6 +428,81 @@ impl BasisAvlTree {
             .as_millis() as u64;
     }
 
+    /// Remember a value in the lookup cache (in-memory trees only)
+    fn cache_value(&mut self, key: Vec<u8>, value: Vec<u8>) {
+        if self.node_store.is_none() {
+            self.cache.insert(key, value);
+        }
+    }
+
+    fn root_node(&self) -> Option<NodeId> {
+        self.prover.base.tree.root.clone()
+    }
+
+    /// Collect nodes created since the last proof
+    ///
+    /// New nodes only ever hang below other new nodes, so the walk stops at
+    /// the first node that was already stored.
+    fn dirty_nodes(&self) -> Vec<NodeId> {
+        let mut dirty = Vec::new();
+        let mut stack: Vec<NodeId> = self.root_node().into_iter().collect();
+        while let Some(node) = stack.pop() {
+            if !node.borrow().is_new() {
+                continue;
+            }
+            if let Node::Internal(internal) = &*node.borrow() {
+                stack.push(internal.left.clone());
+                stack.push(internal.right.clone());
+            }
+            dirty.push(node);
+        }
+        dirty
+    }
+
+    /// Write nodes and the current root digest to the node store
+    fn flush_nodes(&mut self, nodes: Vec<NodeId>) -> Result<(), TreeError> {
+        let Some(node_store) = &self.node_store else {
+            return Ok(());
+        };
+
+        let mut encoded = Vec::with_capacity(nodes.len());
+        for node in nodes {
+            let mut node = node.borrow_mut();
+            let label = node.label();
+            if let Some(bytes) = fjall_storage::encode_node(&mut node) {
+                encoded.push((label, bytes));
+            }
+        }
+
+        node_store.commit(encoded, &self.root_digest())
+    }
+}
+
+impl Drop for BasisAvlTree {
+    fn drop(&mut self) {
+        if let Some(node_store) = &self.node_store {
+            fjall_storage::unregister_store(node_store);
+        }
+    }
+}
 
+/// Replace resolved nodes below `resident_depth` with label-only placeholders
+fn prune_below(node: &NodeId, depth: usize, resident_depth: usize) {
+    let mut node = node.borrow_mut();
+    let Node::Internal(internal) = &mut *node else {
+        return;
+    };
+
+    for child in [&mut internal.left, &mut internal.right] {
+        if matches!(&*child.borrow(), Node::LabelOnly(_)) {
+            continue;
+        }
+        if depth + 1 >= resident_depth {
+            let label = child.borrow_mut().label();
+            *child = Rc::new(RefCell::new(fjall_storage::label_only_node(label)));
+        } else {
+            prune_below(child, depth + 1, resident_depth);
+        }
+    }
 }
 
@@ -220,4 +220,419 @@ fn test_large_number_of_operations() -> Result<(), TreeError> {
     assert!(!proof.is_empty(), "Proof generation should work after many operations");
 
     Ok(())
-}
\ No newline at end of file
+}
+fn test_key(i: u8) -> Vec<u8> {
+    let mut key = vec![0u8; 32];
+    key[0] = i;
+    key
+}
+
+/// Test that batch proofs verify with the contract-side verifier
+#[test]
+fn test_batch_proof_verifies_against_avl_verifier() -> Result<(), TreeError> {
+    let mut tree = BasisAvlTree::new()?;
+    let initial_digest = tree.root_digest();
+
+    for i in 1..6 {
+        tree.insert(test_key(i), vec![i; 8])?;
+    }
+    tree.update(test_key(3), vec![30; 8])?;
+
+    let batch = tree.generate_proof_batch()?;
+    assert_eq!(batch.starting_digest, initial_digest.to_vec());
+    assert_eq!(batch.ending_digest, tree.root_digest().to_vec());
+    assert!(batch.verify()?, "Batch proof should verify");
+
+    let verified = crate::verify_operations(&batch.starting_digest, &batch.avl_proof, &batch.operations)?;
+    assert_eq!(verified.digest, tree.root_digest());
+    assert_eq!(verified.results.len(), 6);
+    assert_eq!(verified.results[5], Some(vec![3; 8]), "Update should return the previous value");
+
+    Ok(())
+}
+
+/// Test that consecutive batch proofs chain digests
+#[test]
+fn test_consecutive_batch_proofs_chain() -> Result<(), TreeError> {
+    let mut tree = BasisAvlTree::new()?;
+
+    tree.insert(test_key(1), vec![1; 8])?;
+    let first = tree.generate_proof_batch()?;
+
+    // update() falls back to insert for new keys; only the successful insert is recorded
+    tree.update(test_key(2), vec![2; 8])?;
+    tree.update(test_key(1), vec![11; 8])?;
+    let second = tree.generate_proof_batch()?;
+
+    assert_eq!(first.ending_digest, second.starting_digest);
+    assert!(first.verify()?);
+    assert!(second.verify()?);
+    assert_eq!(crate::decode_operations(&second.operations)?.len(), 2);
+
+    // Nothing happened since the last proof
+    let empty = tree.generate_proof_batch()?;
+    assert!(empty.operations.is_empty());
+    assert_eq!(empty.starting_digest, empty.ending_digest);
+    assert!(empty.verify()?);
+
+    Ok(())
+}
+
+/// Test that tampered proofs and operation trails are rejected
+#[test]
+fn test_tampered_batch_proof_rejected() -> Result<(), TreeError> {
+    let mut tree = BasisAvlTree::new()?;
+    for i in 1..4 {
+        tree.insert(test_key(i), vec![i; 8])?;
+    }
+    let batch = tree.generate_proof_batch()?;
+
+    let mut wrong_value = batch.clone();
+    let last = wrong_value.operations.len() - 1;
+    wrong_value.operations[last] ^= 0xff;
+    assert!(!wrong_value.verify()?, "Modified operation value must not verify");
+
+    let mut wrong_start = batch.clone();
+    wrong_start.starting_digest[0] ^= 0xff;
+    assert!(!wrong_start.verify()?, "Wrong starting digest must not verify");
+
+    let mut truncated = batch;
+    truncated.avl_proof.truncate(truncated.avl_proof.len() / 2);
+    assert!(!truncated.verify()?, "Truncated proof must not verify");
+
+    Ok(())
+}
+
+/// Test membership (lookup) proof round-trip through the on-chain byte format
+#[test]
+fn test_membership_proof_round_trip() -> Result<(), TreeError> {
+    use crate::proofs::MembershipProof;
+    use crate::state::TrackerState;
+    use ergo_avltree_rust::{
+        authenticated_tree_ops::AuthenticatedTreeOps,
+        batch_avl_prover::BatchAVLProver,
+        batch_node::AVLTree,
+        operation::{KeyValue, Operation},
+    };
+
+    fn resolver(_digest: &[u8; 32]) -> ergo_avltree_rust::batch_node::Node {
+        panic!("resolver should not be called for in-memory trees");
+    }
+
+    let mut prover = BatchAVLProver::new(AVLTree::new(resolver, 32, None), true);
+    for i in 1..8 {
+        prover
+            .perform_one_operation(&Operation::Insert(KeyValue {
+                key: test_key(i).into(),
+                value: vec![i; 8].into(),
+            }))
+            .unwrap();
+    }
+    prover.generate_proof();
+
+    let lookup = Operation::Lookup(test_key(4).into());
+    prover.perform_one_operation(&lookup).unwrap();
+    let avl_proof = prover.generate_proof().to_vec();
+    let root_digest = prover.digest().unwrap().to_vec();
+
+    let proof = MembershipProof::new(
+        vec![4; 8],
+        avl_proof,
+        crate::encode_operations(&[lookup])?,
+        root_digest.clone(),
+    );
+    let state = TrackerState::new(root_digest.clone().try_into().unwrap(), 0, 0);
+    assert!(proof.verify(&state)?, "Lookup proof should verify");
+
+    // The on-chain bytes are exactly the AVL proof
+    let on_chain = MembershipProof::from_bytes(&proof.to_bytes())?;
+    assert_eq!(on_chain.avl_proof, proof.avl_proof);
+
+    // The envelope keeps everything needed for off-chain verification
+    let restored = MembershipProof::from_envelope_bytes(&proof.to_envelope_bytes())?;
+    assert!(restored.verify(&state)?);
+
+    // A different claimed value must not verify
+    let wrong_value = MembershipProof::new(vec![5; 8], proof.avl_proof.clone(), proof.operations.clone(), root_digest);
+    assert!(!wrong_value.verify(&state)?);
+
+    Ok(())
+}
+
+/// Test per-key lookup proofs verify against the current root
+#[test]
+fn test_lookup_proof_verifies() -> Result<(), TreeError> {
+    let mut tree = BasisAvlTree::new()?;
+    for i in 1..21 {
+        tree.insert(test_key(i), vec![i; 8])?;
+    }
+
+    let proof = tree.lookup_proof(&test_key(7))?;
+    assert_eq!(proof.note_data, vec![7; 8]);
+    assert_eq!(proof.root_digest, tree.root_digest().to_vec());
+    assert!(proof.verify(tree.get_state())?, "Lookup proof should verify");
+
+    assert!(matches!(tree.lookup_proof(&test_key(99)), Err(TreeError::KeyNotFound)));
+
+    Ok(())
+}
+
+/// Test lookup proof size stays logarithmic in the number of pending operations
+#[test]
+fn test_lookup_proof_size_independent_of_pending_operations() -> Result<(), TreeError> {
+    let mut small = BasisAvlTree::new()?;
+    let mut large = BasisAvlTree::new()?;
+    for i in 1..9 {
+        small.insert(test_key(i), vec![i; 8])?;
+    }
+    for i in 1..=255 {
+        let mut key = test_key(i);
+        key[1] = i.wrapping_mul(7);
+        large.insert(key, vec![i; 8])?;
+        large.insert(test_key(i), vec![i; 8])?;
+    }
+
+    let small_proof = small.lookup_proof(&test_key(5))?;
+    let large_proof = large.lookup_proof(&test_key(5))?;
+
+    // 64x more keys adds only a handful of levels to the path
+    assert!(large_proof.avl_proof.len() < small_proof.avl_proof.len() * 4);
+    assert!(large_proof.verify(large.get_state())?);
+
+    Ok(())
+}
+
+/// Test lookup proofs do not break subsequent batch proofs
+#[test]
+fn test_lookup_proof_seals_pending_batch() -> Result<(), TreeError> {
+    let mut tree = BasisAvlTree::new()?;
+    tree.insert(test_key(1), vec![1; 8])?;
+    tree.insert(test_key(2), vec![2; 8])?;
+
+    let digest_before_lookup = tree.root_digest();
+    tree.lookup_proof(&test_key(1))?;
+
+    tree.update(test_key(2), vec![22; 8])?;
+    tree.insert(test_key(3), vec![3; 8])?;
+    let batch = tree.generate_proof_batch()?;
+
+    assert_eq!(batch.starting_digest, digest_before_lookup.to_vec());
+    assert_eq!(crate::decode_operations(&batch.operations)?.len(), 2);
+    assert!(batch.verify()?);
+
+    let proof = tree.lookup_proof(&test_key(2))?;
+    assert_eq!(proof.note_data, vec![22; 8]);
+    assert!(proof.verify(tree.get_state())?);
+
+    Ok(())
+}
+
+fn persistent_config(resident_depth: usize, node_cache_capacity: usize) -> crate::PersistentTreeConfig {
+    crate::PersistentTreeConfig {
+        node_cache_capacity,
+        resident_depth,
+    }
+}
+
+/// Test persistent trees produce the same digests as in-memory trees and survive reopening
+#[test]
+fn test_persistent_tree_matches_in_memory_and_reopens() -> Result<(), TreeError> {
+    let dir = tempfile::tempdir()?;
+    let mut memory = BasisAvlTree::new()?;
+
+    {
+        let mut persistent = BasisAvlTree::open_persistent(dir.path(), crate::PersistentTreeConfig::default())?;
+        assert!(persistent.is_persistent());
+        assert_eq!(persistent.root_digest(), memory.root_digest());
+
+        for i in 1..51 {
+            memory.insert(test_key(i), vec![i; 8])?;
+            persistent.insert(test_key(i), vec![i; 8])?;
+        }
+        memory.update(test_key(10), vec![100; 8])?;
+        persistent.update(test_key(10), vec![100; 8])?;
+
+        assert_eq!(persistent.root_digest(), memory.root_digest());
+        persistent.commit()?;
+    }
+
+    let mut reopened = BasisAvlTree::open_persistent(dir.path(), crate::PersistentTreeConfig::default())?;
+    assert_eq!(reopened.root_digest(), memory.root_digest());
+    assert_eq!(reopened.get_state().avl_root_digest, memory.root_digest().to_vec());
+    assert_eq!(reopened.get(&test_key(10)), Some(vec![100; 8]));
+    assert_eq!(reopened.get(&test_key(42)), Some(vec![42; 8]));
+    assert_eq!(reopened.get(&test_key(99)), None);
+
+    let proof = reopened.lookup_proof(&test_key(42))?;
+    assert!(proof.verify(reopened.get_state())?);
+
+    Ok(())
+}
+
+/// Test that only committed changes are durable
+#[test]
+fn test_persistent_tree_discards_uncommitted_changes() -> Result<(), TreeError> {
+    let dir = tempfile::tempdir()?;
+
+    let committed_digest = {
+        let mut tree = BasisAvlTree::open_persistent(dir.path(), crate::PersistentTreeConfig::default())?;
+        tree.insert(test_key(1), vec![1; 8])?;
+        tree.commit()?;
+        let digest = tree.root_digest();
+
+        tree.insert(test_key(2), vec![2; 8])?;
+        assert_ne!(tree.root_digest(), digest);
+        digest
+    };
+
+    let tree = BasisAvlTree::open_persistent(dir.path(), crate::PersistentTreeConfig::default())?;
+    assert_eq!(tree.root_digest(), committed_digest);
+    assert_eq!(tree.get(&test_key(2)), None);
+
+    Ok(())
+}
+
+/// Test that pruned nodes are loaded back through the node cache
+#[test]
+fn test_persistent_tree_loads_pruned_nodes() -> Result<(), TreeError> {
+    let dir = tempfile::tempdir()?;
+    let mut memory = BasisAvlTree::new()?;
+    let mut persistent = BasisAvlTree::open_persistent(dir.path(), persistent_config(2, 16))?;
+
+    for round in 0..4u8 {
+        for i in 1..33u8 {
+            let mut key = test_key(i);
+            key[1] = round;
+            memory.update(key.clone(), vec![i, round])?;
+            persistent.update(key, vec![i, round])?;
+        }
+        persistent.commit()?;
+        assert_eq!(persistent.root_digest(), memory.root_digest());
+    }
+
+    // Updating existing keys after eviction walks back into stored nodes
+    for i in 1..33u8 {
+        memory.update(test_key(i), vec![i; 3])?;
+        persistent.update(test_key(i), vec![i; 3])?;
+    }
+    let batch = persistent.commit()?;
+    assert!(batch.verify()?);
+    assert_eq!(persistent.root_digest(), memory.root_digest());
+
+    for i in 1..33u8 {
+        assert_eq!(persistent.get(&test_key(i)), Some(vec![i; 3]));
+    }
+
+    let stats = persistent.node_cache_stats().unwrap();
+    assert!(stats.misses > 0, "Evicted nodes should be read back from the store");
+    assert!(stats.cached_nodes <= 16);
+
+    let proof = persistent.lookup_proof(&test_key(7))?;
+    assert!(proof.verify(persistent.get_state())?);
+
+    Ok(())
+}
+
+/// Test that batches reach the same digest as per-key operations, with one proof
+#[test]
+fn test_batch_insert_and_update_match_single_operations() -> Result<(), TreeError> {
+    let mut single = BasisAvlTree::new()?;
+    let mut batched = BasisAvlTree::new()?;
+    let initial_digest = batched.root_digest();
+
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = (1..101).map(|i| (test_key(i), vec![i; 8])).collect();
+    for (key, value) in &entries {
+        single.insert(key.clone(), value.clone())?;
+    }
+    let batch = batched.insert_batch(entries)?;
+    assert_eq!(batched.root_digest(), single.root_digest());
+    assert_eq!(batched.get_state().avl_root_digest, single.root_digest().to_vec());
+    assert_eq!(batch.starting_digest, initial_digest.to_vec());
+    assert_eq!(batch.ending_digest, batched.root_digest().to_vec());
+    assert_eq!(crate::decode_operations(&batch.operations)?.len(), 100);
+    assert!(batch.verify()?);
+
+    // Existing keys are updated, new ones inserted, and the last value of a repeated key wins
+    single.update(test_key(5), vec![55; 8])?;
+    single.update(test_key(150), vec![150; 8])?;
+    let updates = vec![
+        (test_key(5), vec![50; 8]),
+        (test_key(150), vec![150; 8]),
+        (test_key(5), vec![55; 8]),
+    ];
+    let batch = batched.update_batch(updates)?;
+    assert_eq!(batched.root_digest(), single.root_digest());
+    assert_eq!(batched.get(&test_key(5)), Some(vec![55; 8]));
+    assert_eq!(batched.get(&test_key(150)), Some(vec![150; 8]));
+    assert!(batch.verify()?);
+
+    let proof = batched.lookup_proof(&test_key(150))?;
+    assert!(proof.verify(batched.get_state())?);
+
+    Ok(())
+}
+
+/// Test that an insert batch with a known or repeated key changes nothing
+#[test]
+fn test_batch_insert_rejects_duplicate_keys() -> Result<(), TreeError> {
+    let mut tree = BasisAvlTree::new()?;
+    tree.insert(test_key(1), vec![1; 8])?;
+    let digest = tree.root_digest();
+
+    let existing = tree.insert_batch(vec![(test_key(2), vec![2; 8]), (test_key(1), vec![1; 8])]);
+    assert!(matches!(existing, Err(TreeError::DuplicateKey)));
+    let repeated = tree.insert_batch(vec![(test_key(3), vec![3; 8]), (test_key(3), vec![4; 8])]);
+    assert!(matches!(repeated, Err(TreeError::DuplicateKey)));
+
+    assert_eq!(tree.root_digest(), digest);
+    assert_eq!(tree.get(&test_key(2)), None);
+
+    Ok(())
+}
+
+/// Test that batches are applied in key order, and that an invalid key
+/// anywhere in a batch changes nothing
+#[test]
+fn test_batch_is_sorted_and_checked_up_front() -> Result<(), TreeError> {
+    let mut sorted = BasisAvlTree::new()?;
+    let mut shuffled = BasisAvlTree::new()?;
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = (1..51).map(|i| (test_key(i), vec![i; 8])).collect();
+    sorted.insert_batch(entries.clone())?;
+    let mut reversed = entries.clone();
+    reversed.reverse();
+    shuffled.insert_batch(reversed)?;
+    assert_eq!(shuffled.root_digest(), sorted.root_digest());
+
+    let digest = shuffled.root_digest();
+    let short_key = shuffled.insert_batch(vec![(test_key(60), vec![60; 8]), (vec![61; 31], vec![61; 8])]);
+    assert!(matches!(short_key, Err(TreeError::InvalidKeyLength(31))));
+    let long_key = shuffled.update_batch(vec![(test_key(1), vec![0; 8]), (vec![62; 33], vec![62; 8])]);
+    assert!(matches!(long_key, Err(TreeError::InvalidKeyLength(33))));
+
+    assert_eq!(shuffled.root_digest(), digest);
+    assert_eq!(shuffled.get(&test_key(60)), None);
+    assert_eq!(shuffled.get(&test_key(1)), Some(vec![1; 8]));
+
+    Ok(())
+}
+
+/// Test that batches on persistent trees are committed and survive reopening
+#[test]
+fn test_persistent_tree_batch_is_committed() -> Result<(), TreeError> {
+    let dir = tempfile::tempdir()?;
+    let mut memory = BasisAvlTree::new()?;
+    let entries: Vec<(Vec<u8>, Vec<u8>)> = (1..201).map(|i| (test_key(i), vec![i; 8])).collect();
+    memory.insert_batch(entries.clone())?;
+
+    {
+        let mut persistent = BasisAvlTree::open_persistent(dir.path(), persistent_config(2, 16))?;
+        persistent.insert_batch(entries)?;
+        assert_eq!(persistent.root_digest(), memory.root_digest());
+    }
+
+    let reopened = BasisAvlTree::open_persistent(dir.path(), crate::PersistentTreeConfig::default())?;
+    assert_eq!(reopened.root_digest(), memory.root_digest());
+    assert_eq!(reopened.get(&test_key(123)), Some(vec![123; 8]));
+
+    Ok(())
+}
@@ -0,0 +1,338 @@
+//! Recipient proof bundles
+//!
+//! A bundle packages everything a recipient needs to redeem the notes owed to
+//! them without asking the tracker again: each note with its issuer
+//! signature, the tracker's signature on the redemption message, and a lookup
+//! proof of the note's total debt against the tracker state digest (context
+//! var #8 of the redemption transaction). The tracker signs the bundle as a
+//! whole so its contents can be attributed to it.
+//!
+//! Proofs are made against the tracker's state when the bundle was exported.
+//! They are only accepted on-chain once that digest is committed in the
+//! tracker box, so holders should compare `tracker_state_digest` with the
+//! box's R5 before relying on a bundle.
+
+use crate::verifier::encode_operations;
+use crate::{MembershipProof, TrackerState};
+
+use basis_core::{schnorr_verify, PubKey, Signature, SigningFormat};
+use blake2::{digest::consts::U32, Blake2b, Digest};
+use ergo_avltree_rust::operation::Operation;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Current bundle format version
+pub const PROOF_BUNDLE_VERSION: u8 = 1;
+
+/// Domain separation tag that opens the message a tracker signs for a bundle
+pub const PROOF_BUNDLE_DOMAIN_TAG: &[u8; 20] = b"BASIS-PROOF-BUNDLE/1";
+
+/// Reasons a bundle fails verification
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum BundleError {
+    #[error("Unsupported bundle version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Malformed field {0}")]
+    Malformed(&'static str),
+
+    #[error("Invalid tracker signature on the bundle")]
+    InvalidBundleSignature,
+
+    #[error("Invalid issuer signature on the note from {0}")]
+    InvalidIssuerSignature(String),
+
+    #[error("Invalid tracker signature on the note from {0}")]
+    InvalidTrackerSignature(String),
+
+    #[error("Redeemed amount exceeds the debt on the note from {0}")]
+    InvalidAmounts(String),
+
+    #[error("Lookup proof for the note from {0} does not match the state digest")]
+    InvalidProof(String),
+}
+
+/// A note owed to the bundle's recipient, with what is needed to redeem it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundledNote {
+    /// Issuer public key (hex)
+    pub issuer_pubkey: String,
+    /// Total debt
+    pub amount_collected: u64,
+    /// Amount already redeemed
+    pub amount_redeemed: u64,
+    /// Note timestamp in milliseconds since Unix epoch
+    pub timestamp: u64,
+    /// Issuer signature on the note (hex)
+    pub issuer_signature: String,
+    /// Version of the message the issuer signed
+    pub signing_version: u8,
+    /// Tracker NFT ID a version 2 note is bound to (hex)
+    pub tracker_id: Option<String>,
+    /// Tracker signature on the redemption message (hex); absent when the
+    /// tracker would not sign a redemption of the note at export time
+    pub tracker_signature: Option<String>,
+    /// AVL lookup proof of the total debt against the state digest (hex)
+    pub tracker_lookup_proof: String,
+}
+
+/// Everything a recipient needs to redeem their notes later
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofBundle {
+    /// Bundle format version
+    pub version: u8,
+    /// Recipient public key (hex)
+    pub recipient_pubkey: String,
+    /// Tracker public key (hex)
+    pub tracker_pubkey: String,
+    /// Tracker AVL root digest the proofs are made against (hex)
+    pub tracker_state_digest: String,
+    /// Height of the latest tracker box seen on-chain
+    pub last_commitment_height: Option<u64>,
+    /// Export time in milliseconds since Unix epoch
+    pub created_at: u64,
+    pub notes: Vec<BundledNote>,
+    /// Tracker signature on [`ProofBundle::signing_message`] (hex)
+    pub tracker_signature: String,
+}
+
+fn decode<const N: usize>(hex_str: &str, field: &'static str) -> Result<[u8; N], BundleError> {
+    hex::decode(hex_str)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(BundleError::Malformed(field))
+}
+
+fn signing_format(note: &BundledNote) -> Result<SigningFormat, BundleError> {
+    signing_format_of(note.signing_version, &note.tracker_id)
+}
+
+/// Signing format of a note from its version and tracker ID fields
+pub(crate) fn signing_format_of(
+    signing_version: u8,
+    tracker_id: &Option<String>,
+) -> Result<SigningFormat, BundleError> {
+    match (signing_version, tracker_id) {
+        (1, None) => Ok(SigningFormat::V1),
+        (2, tracker_id) => Ok(SigningFormat::V2 {
+            tracker_id: tracker_id
+                .as_deref()
+                .map(|id| decode::<32>(id, "tracker_id"))
+                .transpose()?,
+        }),
+        _ => Err(BundleError::Malformed("signing_version")),
+    }
+}
+
+/// AVL tree key of a note: blake2b256(issuer || recipient)
+fn note_key(issuer_pubkey: &PubKey, recipient_pubkey: &PubKey) -> [u8; 32] {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update(issuer_pubkey);
+    hasher.update(recipient_pubkey);
+    hasher.finalize().into()
+}
+
+impl ProofBundle {
+    /// Message the tracker signs for the bundle
+    ///
+    /// The domain tag followed by blake2b256 of the binary encoding of every
+    /// field except the signature itself.
+    pub fn signing_message(&self) -> Result<Vec<u8>, BundleError> {
+        let mut encoded = vec![self.version];
+        encoded.extend_from_slice(&decode::<33>(&self.recipient_pubkey, "recipient_pubkey")?);
+        encoded.extend_from_slice(&decode::<33>(&self.tracker_pubkey, "tracker_pubkey")?);
+        encoded.extend_from_slice(&decode::<33>(&self.tracker_state_digest, "tracker_state_digest")?);
+        match self.last_commitment_height {
+            Some(height) => {
+                encoded.push(1);
+                encoded.extend_from_slice(&height.to_be_bytes());
+            }
+            None => encoded.push(0),
+        }
+        encoded.extend_from_slice(&self.created_at.to_be_bytes());
+        encoded.extend_from_slice(&(self.notes.len() as u32).to_be_bytes());
+
+        for note in &self.notes {
+            let format = signing_format(note)?;
+            encoded.extend_from_slice(&decode::<33>(&note.issuer_pubkey, "issuer_pubkey")?);
+            encoded.extend_from_slice(&note.amount_collected.to_be_bytes());
+            encoded.extend_from_slice(&note.amount_redeemed.to_be_bytes());
+            encoded.extend_from_slice(&note.timestamp.to_be_bytes());
+            encoded.push(format.version());
+            encoded.extend_from_slice(format.tracker_id().unwrap_or(&[0u8; 32]));
+            encoded.extend_from_slice(&decode::<65>(&note.issuer_signature, "issuer_signature")?);
+            match &note.tracker_signature {
+                Some(signature) => {
+                    encoded.push(1);
+                    encoded.extend_from_slice(&decode::<65>(signature, "tracker_signature")?);
+                }
+                None => encoded.push(0),
+            }
+            let proof = hex::decode(&note.tracker_lookup_proof)
+                .map_err(|_| BundleError::Malformed("tracker_lookup_proof"))?;
+            encoded.extend_from_slice(&(proof.len() as u32).to_be_bytes());
+            encoded.extend_from_slice(&proof);
+        }
+
+        let mut message = PROOF_BUNDLE_DOMAIN_TAG.to_vec();
+        message.extend_from_slice(&Blake2b::<U32>::digest(&encoded));
+        Ok(message)
+    }
+
+    /// Verify the bundle without contacting the tracker
+    ///
+    /// Checks the tracker's signature on the bundle and, for every note, the
+    /// issuer signature, the tracker's redemption signature when present, and
+    /// that the lookup proof shows the note's total debt under the state digest.
+    /// Callers still need to check that `tracker_pubkey` is the tracker they
+    /// expect and that the digest has been committed on-chain.
+    pub fn verify(&self) -> Result<(), BundleError> {
+        if self.version != PROOF_BUNDLE_VERSION {
+            return Err(BundleError::UnsupportedVersion(self.version));
+        }
+
+        let recipient_pubkey: PubKey = decode(&self.recipient_pubkey, "recipient_pubkey")?;
+        let tracker_pubkey: PubKey = decode(&self.tracker_pubkey, "tracker_pubkey")?;
+        let digest: [u8; 33] = decode(&self.tracker_state_digest, "tracker_state_digest")?;
+        let bundle_signature: Signature = decode(&self.tracker_signature, "tracker_signature")?;
+
+        schnorr_verify(&bundle_signature, &self.signing_message()?, &tracker_pubkey)
+            .map_err(|_| BundleError::InvalidBundleSignature)?;
+
+        let state = TrackerState::new(digest, self.last_commitment_height.unwrap_or_default(), self.created_at);
+        for note in &self.notes {
+            Self::verify_note(note, &recipient_pubkey, &tracker_pubkey, &state)?;
+        }
+        Ok(())
+    }
+
+    fn verify_note(
+        note: &BundledNote,
+        recipient_pubkey: &PubKey,
+        tracker_pubkey: &PubKey,
+        state: &TrackerState,
+    ) -> Result<(), BundleError> {
+        let issuer_pubkey: PubKey = decode(&note.issuer_pubkey, "issuer_pubkey")?;
+        let issuer = || note.issuer_pubkey.clone();
+
+        if note.amount_redeemed > note.amount_collected {
+            return Err(BundleError::InvalidAmounts(issuer()));
+        }
+
+        let issuer_signature: Signature = decode(&note.issuer_signature, "issuer_signature")?;
+        let message = signing_format(note)?.signing_message(
+            &issuer_pubkey,
+            recipient_pubkey,
+            note.amount_collected,
+            note.timestamp,
+        );
+        schnorr_verify(&issuer_signature, &message, &issuer_pubkey)
+            .map_err(|_| BundleError::InvalidIssuerSignature(issuer()))?;
+
+        // The contract checks the tracker signature on the version 1 message
+        if let Some(tracker_signature) = &note.tracker_signature {
+            let tracker_signature: Signature = decode(tracker_signature, "tracker_signature")?;
+            let message = basis_core::signing_message(
+                &issuer_pubkey,
+                recipient_pubkey,
+                note.amount_collected,
+                note.timestamp,
+            );
+            schnorr_verify(&tracker_signature, &message, tracker_pubkey)
+                .map_err(|_| BundleError::InvalidTrackerSignature(issuer()))?;
+        }
+
+        let lookup = [Operation::Lookup(note_key(&issuer_pubkey, recipient_pubkey).to_vec().into())];
+        let proof = MembershipProof::new(
+            note.amount_collected.to_be_bytes().to_vec(),
+            hex::decode(&note.tracker_lookup_proof).map_err(|_| BundleError::Malformed("tracker_lookup_proof"))?,
+            encode_operations(&lookup).map_err(|_| BundleError::InvalidProof(issuer()))?,
+            state.avl_root_digest.clone(),
+        );
+        match proof.verify(state) {
+            Ok(true) => Ok(()),
+            _ => Err(BundleError::InvalidProof(issuer())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::BasisAvlTree;
+    use basis_core::{generate_keypair, schnorr_sign};
+
+    fn signed_bundle() -> ProofBundle {
+        let (tracker_secret, tracker_pubkey) = generate_keypair();
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let (amount, timestamp) = (1_500u64, 1_000u64);
+
+        let mut tree = BasisAvlTree::new().unwrap();
+        let key = note_key(&issuer_pubkey, &recipient_pubkey).to_vec();
+        tree.insert(key.clone(), amount.to_be_bytes().to_vec()).unwrap();
+        tree.insert(vec![7u8; 32], 5u64.to_be_bytes().to_vec()).unwrap();
+        let proof = tree.lookup_proof(&key).unwrap();
+
+        let message = basis_core::signing_message(&issuer_pubkey, &recipient_pubkey, amount, timestamp);
+        let mut bundle = ProofBundle {
+            version: PROOF_BUNDLE_VERSION,
+            recipient_pubkey: hex::encode(recipient_pubkey),
+            tracker_pubkey: hex::encode(tracker_pubkey),
+            tracker_state_digest: hex::encode(&proof.root_digest),
+            last_commitment_height: Some(1_200_000),
+            created_at: 2_000,
+            notes: vec![BundledNote {
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                amount_collected: amount,
+                amount_redeemed: 500,
+                timestamp,
+                issuer_signature: hex::encode(schnorr_sign(&message, &issuer_secret, &issuer_pubkey).unwrap()),
+                signing_version: 1,
+                tracker_id: None,
+                tracker_signature: Some(hex::encode(
+                    schnorr_sign(&message, &tracker_secret, &tracker_pubkey).unwrap(),
+                )),
+                tracker_lookup_proof: hex::encode(&proof.avl_proof),
+            }],
+            tracker_signature: String::new(),
+        };
+        let bundle_message = bundle.signing_message().unwrap();
+        bundle.tracker_signature =
+            hex::encode(schnorr_sign(&bundle_message, &tracker_secret, &tracker_pubkey).unwrap());
+        bundle
+    }
+
+    #[test]
+    fn test_bundle_verifies_and_survives_json() {
+        let bundle = signed_bundle();
+        bundle.verify().unwrap();
+
+        let decoded: ProofBundle = serde_json::from_str(&serde_json::to_string(&bundle).unwrap()).unwrap();
+        decoded.verify().unwrap();
+    }
+
+    #[test]
+    fn test_bundle_rejects_tampering() {
+        let bundle = signed_bundle();
+
+        let mut inflated = bundle.clone();
+        inflated.notes[0].amount_collected += 1;
+        assert_eq!(inflated.verify(), Err(BundleError::InvalidBundleSignature));
+
+        // A re-signed bundle with a different digest fails on the proof
+        let (tracker_secret, tracker_pubkey) = generate_keypair();
+        let mut forged = bundle.clone();
+        forged.tracker_pubkey = hex::encode(tracker_pubkey);
+        forged.tracker_state_digest = hex::encode([3u8; 33]);
+        forged.notes[0].tracker_signature = None;
+        forged.tracker_signature = hex::encode(
+            schnorr_sign(&forged.signing_message().unwrap(), &tracker_secret, &tracker_pubkey).unwrap(),
+        );
+        assert_eq!(forged.verify(), Err(BundleError::InvalidProof(bundle.notes[0].issuer_pubkey.clone())));
+
+        let mut unsupported = bundle;
+        unsupported.version = 9;
+        assert_eq!(unsupported.verify(), Err(BundleError::UnsupportedVersion(9)));
+    }
+}
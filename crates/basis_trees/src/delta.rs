@@ -0,0 +1,257 @@
+//! Commitment deltas
+//!
+//! A delta lists the notes that changed between two consecutive on-chain
+//! commitments of the tracker, each in its state as of the later commitment
+//! and with the issuer's signature. Auditors and mirrors holding the notes as
+//! of the earlier commitment apply it to catch up, instead of downloading
+//! every note again. The tracker signs the delta as a whole, binding the
+//! notes to both committed digests.
+
+use crate::bundle::signing_format_of;
+
+use basis_core::{schnorr_verify, PubKey, Signature, SigningFormat};
+use blake2::{digest::consts::U32, Blake2b, Digest};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Current delta format version
+pub const COMMITMENT_DELTA_VERSION: u8 = 1;
+
+/// Domain separation tag that opens the message a tracker signs for a delta
+pub const COMMITMENT_DELTA_DOMAIN_TAG: &[u8; 20] = b"BASIS-COMMIT-DELTA/1";
+
+/// Reasons a delta fails verification
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum DeltaError {
+    #[error("Unsupported delta version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Malformed field {0}")]
+    Malformed(&'static str),
+
+    #[error("Invalid tracker signature on the delta")]
+    InvalidDeltaSignature,
+
+    #[error("Invalid issuer signature on the note from {0} to {1}")]
+    InvalidIssuerSignature(String, String),
+
+    #[error("Redeemed amount exceeds the debt on the note from {0} to {1}")]
+    InvalidAmounts(String, String),
+}
+
+/// A note as of the later commitment of a delta
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeltaNote {
+    /// Issuer public key (hex)
+    pub issuer_pubkey: String,
+    /// Recipient public key (hex)
+    pub recipient_pubkey: String,
+    /// Total debt
+    pub amount_collected: u64,
+    /// Amount already redeemed
+    pub amount_redeemed: u64,
+    /// Note timestamp in milliseconds since Unix epoch
+    pub timestamp: u64,
+    /// Issuer signature on the note (hex)
+    pub issuer_signature: String,
+    /// Version of the message the issuer signed
+    pub signing_version: u8,
+    /// Tracker NFT ID a version 2 note is bound to (hex)
+    pub tracker_id: Option<String>,
+}
+
+/// Notes changed between two consecutive commitments
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CommitmentDelta {
+    /// Delta format version
+    pub version: u8,
+    /// Tracker public key (hex)
+    pub tracker_pubkey: String,
+    /// Height of the commitment the delta leads to
+    pub height: u64,
+    /// Root digest committed at `height` (hex)
+    pub digest: String,
+    /// Height of the previous commitment, if any
+    pub previous_height: Option<u64>,
+    /// Root digest of the previous commitment (hex); without one the delta
+    /// holds every note as of `height`
+    pub previous_digest: Option<String>,
+    /// Changed notes, in the order they were first changed since the
+    /// previous commitment
+    pub notes: Vec<DeltaNote>,
+    /// Tracker signature on [`CommitmentDelta::signing_message`] (hex)
+    pub tracker_signature: String,
+}
+
+fn decode<const N: usize>(hex_str: &str, field: &'static str) -> Result<[u8; N], DeltaError> {
+    hex::decode(hex_str)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or(DeltaError::Malformed(field))
+}
+
+impl DeltaNote {
+    /// Format of the message the issuer signed
+    pub fn signing_format(&self) -> Result<SigningFormat, DeltaError> {
+        signing_format_of(self.signing_version, &self.tracker_id).map_err(|_| DeltaError::Malformed("signing_version"))
+    }
+}
+
+impl CommitmentDelta {
+    /// Message the tracker signs for the delta
+    ///
+    /// The domain tag followed by blake2b256 of the binary encoding of every
+    /// field except the signature itself.
+    pub fn signing_message(&self) -> Result<Vec<u8>, DeltaError> {
+        let mut encoded = vec![self.version];
+        encoded.extend_from_slice(&decode::<33>(&self.tracker_pubkey, "tracker_pubkey")?);
+        encoded.extend_from_slice(&self.height.to_be_bytes());
+        encoded.extend_from_slice(&decode::<33>(&self.digest, "digest")?);
+        match (self.previous_height, &self.previous_digest) {
+            (Some(height), Some(digest)) => {
+                encoded.push(1);
+                encoded.extend_from_slice(&height.to_be_bytes());
+                encoded.extend_from_slice(&decode::<33>(digest, "previous_digest")?);
+            }
+            (None, None) => encoded.push(0),
+            _ => return Err(DeltaError::Malformed("previous_digest")),
+        }
+        encoded.extend_from_slice(&(self.notes.len() as u32).to_be_bytes());
+
+        for note in &self.notes {
+            let format = note.signing_format()?;
+            encoded.extend_from_slice(&decode::<33>(&note.issuer_pubkey, "issuer_pubkey")?);
+            encoded.extend_from_slice(&decode::<33>(&note.recipient_pubkey, "recipient_pubkey")?);
+            encoded.extend_from_slice(&note.amount_collected.to_be_bytes());
+            encoded.extend_from_slice(&note.amount_redeemed.to_be_bytes());
+            encoded.extend_from_slice(&note.timestamp.to_be_bytes());
+            encoded.push(format.version());
+            encoded.extend_from_slice(format.tracker_id().unwrap_or(&[0u8; 32]));
+            encoded.extend_from_slice(&decode::<65>(&note.issuer_signature, "issuer_signature")?);
+        }
+
+        let mut message = COMMITMENT_DELTA_DOMAIN_TAG.to_vec();
+        message.extend_from_slice(&Blake2b::<U32>::digest(&encoded));
+        Ok(message)
+    }
+
+    /// Verify the delta without contacting the tracker
+    ///
+    /// Checks the tracker's signature on the delta and the issuer signature
+    /// of every note. Callers still need to check that `tracker_pubkey` is the
+    /// tracker they expect and that both digests are committed on-chain.
+    pub fn verify(&self) -> Result<(), DeltaError> {
+        if self.version != COMMITMENT_DELTA_VERSION {
+            return Err(DeltaError::UnsupportedVersion(self.version));
+        }
+
+        let tracker_pubkey: PubKey = decode(&self.tracker_pubkey, "tracker_pubkey")?;
+        let delta_signature: Signature = decode(&self.tracker_signature, "tracker_signature")?;
+        schnorr_verify(&delta_signature, &self.signing_message()?, &tracker_pubkey)
+            .map_err(|_| DeltaError::InvalidDeltaSignature)?;
+
+        for note in &self.notes {
+            let parties = || (note.issuer_pubkey.clone(), note.recipient_pubkey.clone());
+            if note.amount_redeemed > note.amount_collected {
+                let (issuer, recipient) = parties();
+                return Err(DeltaError::InvalidAmounts(issuer, recipient));
+            }
+
+            let issuer_pubkey: PubKey = decode(&note.issuer_pubkey, "issuer_pubkey")?;
+            let recipient_pubkey: PubKey = decode(&note.recipient_pubkey, "recipient_pubkey")?;
+            let issuer_signature: Signature = decode(&note.issuer_signature, "issuer_signature")?;
+            let message = note
+                .signing_format()?
+                .signing_message(&issuer_pubkey, &recipient_pubkey, note.amount_collected, note.timestamp);
+            if schnorr_verify(&issuer_signature, &message, &issuer_pubkey).is_err() {
+                let (issuer, recipient) = parties();
+                return Err(DeltaError::InvalidIssuerSignature(issuer, recipient));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use basis_core::{generate_keypair, schnorr_sign};
+
+    fn signed_delta() -> CommitmentDelta {
+        let (tracker_secret, tracker_pubkey) = generate_keypair();
+        let (issuer_secret, issuer_pubkey) = generate_keypair();
+        let (_, recipient_pubkey) = generate_keypair();
+        let (amount, timestamp) = (2_500u64, 1_000u64);
+
+        let message = basis_core::signing_message(&issuer_pubkey, &recipient_pubkey, amount, timestamp);
+        let mut delta = CommitmentDelta {
+            version: COMMITMENT_DELTA_VERSION,
+            tracker_pubkey: hex::encode(tracker_pubkey),
+            height: 1_200_720,
+            digest: hex::encode([2u8; 33]),
+            previous_height: Some(1_200_000),
+            previous_digest: Some(hex::encode([1u8; 33])),
+            notes: vec![DeltaNote {
+                issuer_pubkey: hex::encode(issuer_pubkey),
+                recipient_pubkey: hex::encode(recipient_pubkey),
+                amount_collected: amount,
+                amount_redeemed: 500,
+                timestamp,
+                issuer_signature: hex::encode(schnorr_sign(&message, &issuer_secret, &issuer_pubkey).unwrap()),
+                signing_version: 1,
+                tracker_id: None,
+            }],
+            tracker_signature: String::new(),
+        };
+        let delta_message = delta.signing_message().unwrap();
+        delta.tracker_signature =
+            hex::encode(schnorr_sign(&delta_message, &tracker_secret, &tracker_pubkey).unwrap());
+        delta
+    }
+
+    #[test]
+    fn test_delta_verifies_and_survives_json() {
+        let delta = signed_delta();
+        delta.verify().unwrap();
+
+        let decoded: CommitmentDelta = serde_json::from_str(&serde_json::to_string(&delta).unwrap()).unwrap();
+        decoded.verify().unwrap();
+    }
+
+    #[test]
+    fn test_delta_rejects_tampering() {
+        let delta = signed_delta();
+
+        // The signature binds the notes to both committed digests
+        let mut rebased = delta.clone();
+        rebased.previous_digest = Some(hex::encode([3u8; 33]));
+        assert_eq!(rebased.verify(), Err(DeltaError::InvalidDeltaSignature));
+
+        let mut inflated = delta.clone();
+        inflated.notes[0].amount_collected += 1;
+        assert_eq!(inflated.verify(), Err(DeltaError::InvalidDeltaSignature));
+
+        // A re-signed delta still needs the issuer's signature on each note
+        let (tracker_secret, tracker_pubkey) = generate_keypair();
+        let mut forged = inflated;
+        forged.tracker_pubkey = hex::encode(tracker_pubkey);
+        forged.tracker_signature = hex::encode(
+            schnorr_sign(&forged.signing_message().unwrap(), &tracker_secret, &tracker_pubkey).unwrap(),
+        );
+        assert_eq!(
+            forged.verify(),
+            Err(DeltaError::InvalidIssuerSignature(
+                delta.notes[0].issuer_pubkey.clone(),
+                delta.notes[0].recipient_pubkey.clone()
+            ))
+        );
+
+        let mut half_linked = delta.clone();
+        half_linked.previous_height = None;
+        assert_eq!(half_linked.verify(), Err(DeltaError::Malformed("previous_digest")));
+
+        let mut unsupported = delta;
+        unsupported.version = 9;
+        assert_eq!(unsupported.verify(), Err(DeltaError::UnsupportedVersion(9)));
+    }
+}
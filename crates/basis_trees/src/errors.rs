@@ -11,6 +11,9 @@ pub enum TreeError {
     #[error("Duplicate key found")]
     DuplicateKey,
     
+    #[error("Invalid key length: {0} bytes")]
+    InvalidKeyLength(usize),
+    
     #[error("Invalid proof format")]
     InvalidProof,
     
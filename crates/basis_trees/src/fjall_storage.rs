@@ -0,0 +1,330 @@
+//! Persistent node storage for AVL trees backed by fjall
+//!
+//! Nodes are stored content-addressed by their 32-byte label, so a node
+//! written once never changes. `BasisAvlTree` keeps only the top levels of the
+//! tree resident and loads deeper nodes on demand through the tree resolver,
+//! which looks them up here. Recently loaded nodes are kept in an LRU cache.
+
+use crate::errors::TreeError;
+
+use ergo_avltree_rust::batch_node::{InternalNode, LeafNode, Node, NodeHeader};
+use fjall::{Config, PartitionCreateOptions, PersistMode};
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+/// Default number of serialized nodes kept in the LRU cache
+pub const DEFAULT_NODE_CACHE_CAPACITY: usize = 65_536;
+
+/// Default number of tree levels kept resident in memory after a commit
+pub const DEFAULT_RESIDENT_DEPTH: usize = 12;
+
+const LEAF_NODE_PREFIX: u8 = 0;
+const INTERNAL_NODE_PREFIX: u8 = 1;
+
+const ROOT_KEY: &[u8] = b"root";
+
+/// Node stores currently used by persistent trees, consulted by the resolver
+static NODE_STORES: RwLock<Vec<Arc<FjallNodeStore>>> = RwLock::new(Vec::new());
+
+/// Configuration for a persistent AVL tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PersistentTreeConfig {
+    /// Number of serialized nodes kept in the LRU cache
+    pub node_cache_capacity: usize,
+    /// Number of tree levels kept resident in memory after a commit
+    pub resident_depth: usize,
+}
+
+impl Default for PersistentTreeConfig {
+    fn default() -> Self {
+        Self {
+            node_cache_capacity: DEFAULT_NODE_CACHE_CAPACITY,
+            resident_depth: DEFAULT_RESIDENT_DEPTH,
+        }
+    }
+}
+
+/// Node cache statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct NodeCacheStats {
+    /// Lookups served from the cache
+    pub hits: u64,
+    /// Lookups that had to read the database
+    pub misses: u64,
+    /// Nodes currently cached
+    pub cached_nodes: usize,
+}
+
+/// Least-recently-used cache of serialized nodes
+struct NodeCache {
+    capacity: usize,
+    entries: HashMap<[u8; 32], (Vec<u8>, u64)>,
+    order: BTreeMap<u64, [u8; 32]>,
+    tick: u64,
+}
+
+impl NodeCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+        }
+    }
+
+    fn get(&mut self, label: &[u8; 32]) -> Option<Vec<u8>> {
+        self.tick += 1;
+        let tick = self.tick;
+        let (bytes, last_used) = self.entries.get_mut(label)?;
+        self.order.remove(last_used);
+        *last_used = tick;
+        self.order.insert(tick, *label);
+        Some(bytes.clone())
+    }
+
+    fn insert(&mut self, label: [u8; 32], bytes: Vec<u8>) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, last_used)) = self.entries.insert(label, (bytes, self.tick)) {
+            self.order.remove(&last_used);
+        }
+        self.order.insert(self.tick, label);
+
+        while self.entries.len() > self.capacity {
+            match self.order.pop_first() {
+                Some((_, oldest)) => {
+                    self.entries.remove(&oldest);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+}
+
+/// Content-addressed AVL node store
+pub struct FjallNodeStore {
+    keyspace: fjall::Keyspace,
+    nodes: fjall::Partition,
+    metadata: fjall::Partition,
+    cache: Mutex<NodeCache>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl FjallNodeStore {
+    /// Open or create a node store at the given path
+    pub fn open<P: AsRef<Path>>(path: P, node_cache_capacity: usize) -> Result<Self, TreeError> {
+        let keyspace = Config::new(path)
+            .open()
+            .map_err(|e| TreeError::StorageError(format!("Failed to open database: {}", e)))?;
+
+        let nodes = keyspace
+            .open_partition("avl_nodes", PartitionCreateOptions::default())
+            .map_err(|e| TreeError::StorageError(format!("Failed to open nodes partition: {}", e)))?;
+
+        let metadata = keyspace
+            .open_partition("avl_metadata", PartitionCreateOptions::default())
+            .map_err(|e| TreeError::StorageError(format!("Failed to open metadata partition: {}", e)))?;
+
+        Ok(Self {
+            keyspace,
+            nodes,
+            metadata,
+            cache: Mutex::new(NodeCache::new(node_cache_capacity)),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    /// Root digest of the last committed tree, if any
+    pub fn root_digest(&self) -> Result<Option<[u8; 33]>, TreeError> {
+        let value = self
+            .metadata
+            .get(ROOT_KEY)
+            .map_err(|e| TreeError::StorageError(format!("Failed to read root digest: {}", e)))?;
+
+        match value {
+            Some(bytes) => {
+                let digest: [u8; 33] = bytes.as_ref().try_into().map_err(|_| TreeError::TreeCorruption)?;
+                Ok(Some(digest))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Load a node by label, going through the LRU cache
+    pub fn get_node(&self, label: &[u8; 32]) -> Result<Option<Node>, TreeError> {
+        if let Some(bytes) = self.cache.lock().unwrap().get(label) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return decode_node(label, &bytes).map(Some);
+        }
+
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = self
+            .nodes
+            .get(label)
+            .map_err(|e| TreeError::StorageError(format!("Failed to read node: {}", e)))?;
+
+        match value {
+            Some(bytes) => {
+                let node = decode_node(label, &bytes)?;
+                self.cache.lock().unwrap().insert(*label, bytes.to_vec());
+                Ok(Some(node))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Write nodes and the new root digest atomically
+    pub fn commit(&self, nodes: Vec<([u8; 32], Vec<u8>)>, root_digest: &[u8; 33]) -> Result<(), TreeError> {
+        let mut batch = self.keyspace.batch();
+        for (label, bytes) in &nodes {
+            batch.insert(&self.nodes, label.to_vec(), bytes.clone());
+        }
+        batch.insert(&self.metadata, ROOT_KEY.to_vec(), root_digest.to_vec());
+        batch
+            .commit()
+            .map_err(|e| TreeError::StorageError(format!("Failed to commit nodes: {}", e)))?;
+
+        let mut cache = self.cache.lock().unwrap();
+        for (label, bytes) in nodes {
+            cache.insert(label, bytes);
+        }
+        Ok(())
+    }
+
+    /// Sync committed nodes to disk
+    pub fn persist(&self) -> Result<(), TreeError> {
+        self.keyspace
+            .persist(PersistMode::SyncAll)
+            .map_err(|e| TreeError::StorageError(format!("Failed to persist nodes: {}", e)))
+    }
+
+    /// Current cache statistics
+    pub fn cache_stats(&self) -> NodeCacheStats {
+        NodeCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            cached_nodes: self.cache.lock().unwrap().len(),
+        }
+    }
+}
+
+/// Make a store available to the tree resolver
+pub(crate) fn register_store(store: &Arc<FjallNodeStore>) {
+    NODE_STORES.write().unwrap().push(store.clone());
+}
+
+/// Remove a store from the resolver
+pub(crate) fn unregister_store(store: &Arc<FjallNodeStore>) {
+    NODE_STORES
+        .write()
+        .unwrap()
+        .retain(|registered| !Arc::ptr_eq(registered, store));
+}
+
+/// Resolve a label-only node from the registered stores
+///
+/// Labels are content hashes, so any store holding the label has the right node.
+pub(crate) fn resolve_node(label: &[u8; 32]) -> Option<Node> {
+    let stores = NODE_STORES.read().unwrap();
+    stores.iter().find_map(|store| match store.get_node(label) {
+        Ok(node) => node,
+        Err(e) => {
+            tracing::error!("Failed to load AVL node {:?}: {:?}", label, e);
+            None
+        }
+    })
+}
+
+/// Label-only placeholder for a node that lives in the store
+pub(crate) fn label_only_node(label: [u8; 32]) -> Node {
+    Node::LabelOnly(stored_header(label, None))
+}
+
+// Header for a node loaded from or flushed to the store
+fn stored_header(label: [u8; 32], key: Option<Vec<u8>>) -> NodeHeader {
+    NodeHeader {
+        visited: false,
+        is_new: false,
+        label: Some(label),
+        key: key.map(Into::into),
+    }
+}
+
+/// Serialize a resolved node
+///
+/// Leaf: prefix || key || next key || value length (u32 BE) || value.
+/// Internal: prefix || balance || key || left label || right label.
+pub(crate) fn encode_node(node: &mut Node) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match node {
+        Node::Leaf(leaf) => {
+            bytes.push(LEAF_NODE_PREFIX);
+            bytes.extend_from_slice(leaf.hdr.key.as_ref()?);
+            bytes.extend_from_slice(&leaf.next_node_key);
+            bytes.extend_from_slice(&(leaf.value.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(&leaf.value);
+        }
+        Node::Internal(internal) => {
+            bytes.push(INTERNAL_NODE_PREFIX);
+            bytes.push(internal.balance as u8);
+            bytes.extend_from_slice(internal.hdr.key.as_ref()?);
+            bytes.extend_from_slice(&internal.left.borrow_mut().label());
+            bytes.extend_from_slice(&internal.right.borrow_mut().label());
+        }
+        Node::LabelOnly(_) => return None,
+    }
+    Some(bytes)
+}
+
+/// Deserialize a node written by [`encode_node`]
+fn decode_node(label: &[u8; 32], bytes: &[u8]) -> Result<Node, TreeError> {
+    const KEY_LEN: usize = 32;
+
+    match bytes.first() {
+        Some(&LEAF_NODE_PREFIX) => {
+            let header_len = 1 + KEY_LEN * 2 + 4;
+            if bytes.len() < header_len {
+                return Err(TreeError::TreeCorruption);
+            }
+            let key = bytes[1..1 + KEY_LEN].to_vec();
+            let next_node_key = bytes[1 + KEY_LEN..1 + KEY_LEN * 2].to_vec();
+            let value_len =
+                u32::from_be_bytes(bytes[1 + KEY_LEN * 2..header_len].try_into().unwrap()) as usize;
+            if bytes.len() != header_len + value_len {
+                return Err(TreeError::TreeCorruption);
+            }
+            Ok(Node::Leaf(LeafNode {
+                hdr: stored_header(*label, Some(key)),
+                value: bytes[header_len..].to_vec().into(),
+                next_node_key: next_node_key.into(),
+            }))
+        }
+        Some(&INTERNAL_NODE_PREFIX) => {
+            if bytes.len() != 2 + KEY_LEN + 64 {
+                return Err(TreeError::TreeCorruption);
+            }
+            let key = bytes[2..2 + KEY_LEN].to_vec();
+            let left: [u8; 32] = bytes[2 + KEY_LEN..2 + KEY_LEN + 32].try_into().unwrap();
+            let right: [u8; 32] = bytes[2 + KEY_LEN + 32..].try_into().unwrap();
+            Ok(Node::Internal(InternalNode {
+                hdr: stored_header(*label, Some(key)),
+                balance: bytes[1] as i8,
+                left: std::rc::Rc::new(std::cell::RefCell::new(label_only_node(left))),
+                right: std::rc::Rc::new(std::cell::RefCell::new(label_only_node(right))),
+            }))
+        }
+        _ => Err(TreeError::TreeCorruption),
+    }
+}
@@ -5,10 +5,14 @@
 //! and verifiable proofs.
 
 pub mod avl_tree;
+pub mod bundle;
+pub mod delta;
+pub mod fjall_storage;
 pub mod proofs;
 pub mod state;
 pub mod errors;
 pub mod storage;
+pub mod verifier;
 
 #[cfg(test)]
 pub mod test_helpers;
@@ -22,8 +26,15 @@ pub mod avl_tree_tests;
 
 // Re-export main types for easy access
 pub use avl_tree::BasisAvlTree;
+pub use bundle::{BundleError, BundledNote, ProofBundle, PROOF_BUNDLE_VERSION};
+pub use delta::{CommitmentDelta, DeltaError, DeltaNote, COMMITMENT_DELTA_VERSION};
+pub use fjall_storage::{FjallNodeStore, NodeCacheStats, PersistentTreeConfig};
 
-pub use proofs::{MembershipProof, NonMembershipProof, StateProof};
+pub use proofs::{
+    compress_proof, compress_proofs, decompress_proof, decompress_proofs, BatchProof, MembershipProof,
+    NonMembershipProof, StateProof,
+};
+pub use verifier::{decode_operations, encode_operations, verify_operations, VerifiedOperations};
 pub use state::TrackerState;
 pub use errors::TreeError;
 pub use storage::{TreeStorage, TreeNode, TreeOperation, TreeCheckpoint, NodeType, OperationType};
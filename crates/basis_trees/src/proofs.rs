@@ -1,7 +1,45 @@
 //! Proof structures for Basis tree verification
+//!
+//! Proofs travel in the on-chain verifier format, or compressed with
+//! [`compress_proofs`] for transport. Compression works on the packed tree of
+//! the serialized proof: every key is written as the length of the prefix it
+//! shares with the previous key plus the remaining bytes, which removes most
+//! of a key whose neighbour in sort order came just before, and a label
+//! already written earlier in the same stream is replaced by its index. Both
+//! carry across the proofs compressed together, so lookups of adjacent keys
+//! share the labels of their common path. Decompression restores the exact
+//! original bytes, and [`decompress_proof`] passes uncompressed proofs through,
+//! so verification decompresses before use and the contract only ever sees the
+//! original format.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
 
 use crate::state::TrackerState;
 use crate::errors::TreeError;
+use crate::verifier::{decode_operations, verify_operations, AVL_KEY_LENGTH};
+
+use ergo_avltree_rust::operation::Operation;
+
+/// First byte of a compressed proof stream
+///
+/// Serialized AVL proofs start with a leaf or label tag (2 or 3).
+pub const COMPRESSED_PROOF_TAG: u8 = 0xC7;
+
+/// Current compressed proof format version
+pub const COMPRESSED_PROOF_VERSION: u8 = 1;
+
+// Node tags of the serialized AVL proof; any other byte before the end of
+// the tree is the balance of an internal node (-1, 0 or 1)
+const PACKED_LEAF: u8 = 2;
+const PACKED_LABEL: u8 = 3;
+const PACKED_END: u8 = 4;
+
+// Extra tag of the compressed format: a label written earlier in the stream
+const PACKED_LABEL_REF: u8 = 5;
+
+/// Length of a node label in the serialized AVL proof
+const LABEL_LENGTH: usize = 32;
 
 /// Membership proof for a specific note
 #[derive(Debug, Clone)]
@@ -29,6 +67,23 @@ pub struct NonMembershipProof {
     pub root_digest: Vec<u8>,
 }
 
+/// Proof for a batch of tree modifications
+///
+/// Covers every operation performed between two proof generations, so a
+/// verifier holding `starting_digest` can replay `operations` against
+/// `avl_proof` and arrive at `ending_digest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchProof {
+    /// Tree root before the operations
+    pub starting_digest: Vec<u8>,
+    /// Serialized AVL proof in the on-chain verifier format
+    pub avl_proof: Vec<u8>,
+    /// Serialized operation trail
+    pub operations: Vec<u8>,
+    /// Tree root after the operations
+    pub ending_digest: Vec<u8>,
+}
+
 /// State commitment proof
 #[derive(Debug, Clone)]
 pub struct StateProof {
@@ -54,23 +109,81 @@ impl MembershipProof {
     }
 
     /// Verify this proof against a state commitment
+    ///
+    /// The AVL proof is replayed with the on-chain verifier starting from the
+    /// committed root. Only lookups are allowed, each must find its key, and
+    /// the last value found must match `note_data` when it is set.
     pub fn verify(&self, state: &TrackerState) -> Result<bool, TreeError> {
         // Verify root matches
         if self.root_digest != state.avl_root_digest {
             return Ok(false);
         }
 
-        // In real implementation, this would:
-        // 1. Verify AVL proof cryptographically
-        // 2. Verify note signature and validity
-        // 3. Verify operations sequence consistency
-        
-        // Placeholder implementation
-        Ok(!self.avl_proof.is_empty())
+        let operations = decode_operations(&self.operations)?;
+        if operations.is_empty()
+            || !operations.iter().all(|op| matches!(op, Operation::Lookup(_)))
+        {
+            return Ok(false);
+        }
+
+        let verified = match verify_operations(&self.root_digest, &self.avl_proof, &self.operations) {
+            Ok(verified) => verified,
+            Err(_) => return Ok(false),
+        };
+
+        // Lookups never change the tree
+        if verified.digest.as_slice() != self.root_digest.as_slice() {
+            return Ok(false);
+        }
+        if verified.results.iter().any(|value| value.is_none()) {
+            return Ok(false);
+        }
+        if !self.note_data.is_empty()
+            && verified.results.last().cloned().flatten().as_deref() != Some(self.note_data.as_slice())
+        {
+            return Ok(false);
+        }
+
+        Ok(true)
     }
 
     /// Serialize proof to bytes
+    ///
+    /// Returns the raw AVL proof exactly as the on-chain verifier expects it,
+    /// decompressing it if needed. Use [`MembershipProof::to_envelope_bytes`]
+    /// to also carry the note data, operation trail and root digest.
     pub fn to_bytes(&self) -> Vec<u8> {
+        // A proof that does not decompress is passed on as it is and fails
+        // verification wherever it is used
+        decompress_proof(&self.avl_proof)
+            .map(Cow::into_owned)
+            .unwrap_or_else(|_| self.avl_proof.clone())
+    }
+
+    /// The same proof with its AVL proof compressed for transport
+    pub fn compressed(&self) -> Result<Self, TreeError> {
+        Ok(Self {
+            avl_proof: compress_proof(&self.avl_proof)?,
+            ..self.clone()
+        })
+    }
+
+    /// Deserialize proof from raw on-chain AVL proof bytes
+    pub fn from_bytes(data: &[u8]) -> Result<Self, TreeError> {
+        if data.is_empty() {
+            return Err(TreeError::InvalidProof);
+        }
+
+        Ok(Self {
+            note_data: Vec::new(),
+            avl_proof: data.to_vec(),
+            operations: Vec::new(),
+            root_digest: Vec::new(),
+        })
+    }
+
+    /// Serialize the full proof, including note data, operations and root digest
+    pub fn to_envelope_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
         
         // Note data length + data
@@ -91,8 +204,8 @@ impl MembershipProof {
         bytes
     }
 
-    /// Deserialize proof from bytes
-    pub fn from_bytes(data: &[u8]) -> Result<Self, TreeError> {
+    /// Deserialize a full proof produced by [`MembershipProof::to_envelope_bytes`]
+    pub fn from_envelope_bytes(data: &[u8]) -> Result<Self, TreeError> {
         let mut offset = 0;
         
         // Read note data
@@ -149,6 +262,40 @@ impl MembershipProof {
     }
 }
 
+impl BatchProof {
+    /// Create a new batch proof
+    pub fn new(
+        starting_digest: Vec<u8>,
+        avl_proof: Vec<u8>,
+        operations: Vec<u8>,
+        ending_digest: Vec<u8>,
+    ) -> Self {
+        Self {
+            starting_digest,
+            avl_proof,
+            operations,
+            ending_digest,
+        }
+    }
+
+    /// The same proof with its AVL proof compressed for transport
+    pub fn compressed(&self) -> Result<Self, TreeError> {
+        Ok(Self {
+            avl_proof: compress_proof(&self.avl_proof)?,
+            ..self.clone()
+        })
+    }
+
+    /// Verify the proof by replaying the operation trail from the starting digest
+    pub fn verify(&self) -> Result<bool, TreeError> {
+        match verify_operations(&self.starting_digest, &self.avl_proof, &self.operations) {
+            Ok(verified) => Ok(verified.digest.as_slice() == self.ending_digest.as_slice()),
+            Err(TreeError::InvalidProof) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
 impl NonMembershipProof {
     /// Create a new non-membership proof
     pub fn new(key: Vec<u8>, avl_proof: Vec<u8>, neighbors: Vec<Vec<u8>>, root_digest: Vec<u8>) -> Self {
@@ -348,9 +495,273 @@ impl StateProof {
     }
 }
 
+/// Node of the packed tree in a serialized AVL proof
+enum PackedNode<'a> {
+    Label(&'a [u8]),
+    /// `key` is absent when it is the next key of the previous leaf
+    Leaf {
+        key: Option<&'a [u8]>,
+        next_key: &'a [u8],
+        value: &'a [u8],
+    },
+    Internal(u8),
+}
+
+/// Bounds-checked reader over proof bytes
+struct Reader<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, offset: 0 }
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], TreeError> {
+        let end = self.offset.checked_add(len).ok_or(TreeError::InvalidProof)?;
+        let bytes = self.data.get(self.offset..end).ok_or(TreeError::InvalidProof)?;
+        self.offset = end;
+        Ok(bytes)
+    }
+
+    fn byte(&mut self) -> Result<u8, TreeError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn varint(&mut self) -> Result<usize, TreeError> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return usize::try_from(value).map_err(|_| TreeError::InvalidProof);
+            }
+        }
+        Err(TreeError::InvalidProof)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let rest = &self.data[self.offset..];
+        self.offset = self.data.len();
+        rest
+    }
+}
+
+fn put_varint(out: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        out.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+fn is_balance(byte: u8) -> bool {
+    matches!(byte as i8, -1..=1)
+}
+
+/// Split a serialized AVL proof into its packed tree and its directions
+fn parse_packed(proof: &[u8]) -> Result<(Vec<PackedNode<'_>>, &[u8]), TreeError> {
+    let mut reader = Reader::new(proof);
+    let mut nodes = Vec::new();
+    let mut previous_leaf = false;
+    loop {
+        match reader.byte()? {
+            PACKED_END => break,
+            PACKED_LABEL => {
+                nodes.push(PackedNode::Label(reader.take(LABEL_LENGTH)?));
+                previous_leaf = false;
+            }
+            PACKED_LEAF => {
+                let key = if previous_leaf { None } else { Some(reader.take(AVL_KEY_LENGTH)?) };
+                let next_key = reader.take(AVL_KEY_LENGTH)?;
+                let value_length = u32::from_be_bytes(reader.take(4)?.try_into().unwrap()) as usize;
+                let value = reader.take(value_length)?;
+                nodes.push(PackedNode::Leaf { key, next_key, value });
+                previous_leaf = true;
+            }
+            balance if is_balance(balance) => nodes.push(PackedNode::Internal(balance)),
+            _ => return Err(TreeError::InvalidProof),
+        }
+    }
+    Ok((nodes, reader.rest()))
+}
+
+/// Keys and labels already written to a compressed stream
+#[derive(Default)]
+struct CompressionContext {
+    last_key: [u8; AVL_KEY_LENGTH],
+    labels: HashMap<[u8; LABEL_LENGTH], usize>,
+}
+
+impl CompressionContext {
+    fn put_key(&mut self, out: &mut Vec<u8>, key: &[u8]) {
+        let shared = key.iter().zip(&self.last_key).take_while(|(a, b)| a == b).count();
+        out.push(shared as u8);
+        out.extend_from_slice(&key[shared..]);
+        self.last_key.copy_from_slice(key);
+    }
+
+    fn put_proof(&mut self, out: &mut Vec<u8>, proof: &[u8]) -> Result<(), TreeError> {
+        let (nodes, directions) = parse_packed(proof)?;
+        for node in nodes {
+            match node {
+                PackedNode::Label(label) => {
+                    let label: [u8; LABEL_LENGTH] = label.try_into().unwrap();
+                    match self.labels.get(&label) {
+                        Some(&index) => {
+                            out.push(PACKED_LABEL_REF);
+                            put_varint(out, index);
+                        }
+                        None => {
+                            out.push(PACKED_LABEL);
+                            out.extend_from_slice(&label);
+                            let index = self.labels.len();
+                            self.labels.insert(label, index);
+                        }
+                    }
+                }
+                PackedNode::Leaf { key, next_key, value } => {
+                    out.push(PACKED_LEAF);
+                    if let Some(key) = key {
+                        self.put_key(out, key);
+                    }
+                    self.put_key(out, next_key);
+                    put_varint(out, value.len());
+                    out.extend_from_slice(value);
+                }
+                PackedNode::Internal(balance) => out.push(balance),
+            }
+        }
+        out.push(PACKED_END);
+        put_varint(out, directions.len());
+        out.extend_from_slice(directions);
+        Ok(())
+    }
+}
+
+/// Keys and labels already read from a compressed stream
+#[derive(Default)]
+struct DecompressionContext {
+    last_key: [u8; AVL_KEY_LENGTH],
+    labels: Vec<[u8; LABEL_LENGTH]>,
+}
+
+impl DecompressionContext {
+    fn take_key(&mut self, reader: &mut Reader<'_>, out: &mut Vec<u8>) -> Result<(), TreeError> {
+        let shared = reader.byte()? as usize;
+        if shared > AVL_KEY_LENGTH {
+            return Err(TreeError::InvalidProof);
+        }
+        self.last_key[shared..].copy_from_slice(reader.take(AVL_KEY_LENGTH - shared)?);
+        out.extend_from_slice(&self.last_key);
+        Ok(())
+    }
+
+    fn take_proof(&mut self, reader: &mut Reader<'_>) -> Result<Vec<u8>, TreeError> {
+        let mut out = Vec::new();
+        let mut previous_leaf = false;
+        loop {
+            match reader.byte()? {
+                PACKED_END => break,
+                PACKED_LABEL => {
+                    let label: [u8; LABEL_LENGTH] = reader.take(LABEL_LENGTH)?.try_into().unwrap();
+                    out.push(PACKED_LABEL);
+                    out.extend_from_slice(&label);
+                    self.labels.push(label);
+                    previous_leaf = false;
+                }
+                PACKED_LABEL_REF => {
+                    let label = self.labels.get(reader.varint()?).ok_or(TreeError::InvalidProof)?;
+                    out.push(PACKED_LABEL);
+                    out.extend_from_slice(label);
+                    previous_leaf = false;
+                }
+                PACKED_LEAF => {
+                    out.push(PACKED_LEAF);
+                    if !previous_leaf {
+                        self.take_key(reader, &mut out)?;
+                    }
+                    self.take_key(reader, &mut out)?;
+                    let value_length = reader.varint()?;
+                    let value_length_u32 = u32::try_from(value_length).map_err(|_| TreeError::InvalidProof)?;
+                    out.extend_from_slice(&value_length_u32.to_be_bytes());
+                    out.extend_from_slice(reader.take(value_length)?);
+                    previous_leaf = true;
+                }
+                balance if is_balance(balance) => out.push(balance),
+                _ => return Err(TreeError::InvalidProof),
+            }
+        }
+        out.push(PACKED_END);
+        let directions_length = reader.varint()?;
+        out.extend_from_slice(reader.take(directions_length)?);
+        Ok(out)
+    }
+}
+
+/// Compress serialized AVL proofs together into one stream
+///
+/// Proofs of lookups of neighbouring keys against the same root compress best,
+/// as most of their labels are shared.
+pub fn compress_proofs<P: AsRef<[u8]>>(proofs: &[P]) -> Result<Vec<u8>, TreeError> {
+    let mut out = vec![COMPRESSED_PROOF_TAG, COMPRESSED_PROOF_VERSION];
+    put_varint(&mut out, proofs.len());
+    let mut context = CompressionContext::default();
+    for proof in proofs {
+        context.put_proof(&mut out, proof.as_ref())?;
+    }
+    Ok(out)
+}
+
+/// Restore the serialized AVL proofs of a stream from [`compress_proofs`]
+pub fn decompress_proofs(data: &[u8]) -> Result<Vec<Vec<u8>>, TreeError> {
+    let mut reader = Reader::new(data);
+    if reader.byte()? != COMPRESSED_PROOF_TAG || reader.byte()? != COMPRESSED_PROOF_VERSION {
+        return Err(TreeError::InvalidProof);
+    }
+    let count = reader.varint()?;
+    let mut context = DecompressionContext::default();
+    // Every proof takes at least its end tag and directions length
+    let mut proofs = Vec::with_capacity(count.min(data.len() / 2));
+    for _ in 0..count {
+        proofs.push(context.take_proof(&mut reader)?);
+    }
+    if !reader.rest().is_empty() {
+        return Err(TreeError::InvalidProof);
+    }
+    Ok(proofs)
+}
+
+/// Compress a single serialized AVL proof
+pub fn compress_proof(proof: &[u8]) -> Result<Vec<u8>, TreeError> {
+    if is_compressed(proof) {
+        return Ok(proof.to_vec());
+    }
+    compress_proofs(&[proof])
+}
+
+/// The serialized AVL proof in `data`, decompressing it if it is compressed
+pub fn decompress_proof(data: &[u8]) -> Result<Cow<'_, [u8]>, TreeError> {
+    if !is_compressed(data) {
+        return Ok(Cow::Borrowed(data));
+    }
+    let mut proofs = decompress_proofs(data)?;
+    match proofs.pop() {
+        Some(proof) if proofs.is_empty() => Ok(Cow::Owned(proof)),
+        _ => Err(TreeError::InvalidProof),
+    }
+}
+
+/// Whether `data` is a compressed proof stream
+pub fn is_compressed(data: &[u8]) -> bool {
+    data.first() == Some(&COMPRESSED_PROOF_TAG)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::BasisAvlTree;
 
     #[test]
     fn test_membership_proof_serialization() {
@@ -361,8 +772,8 @@ mod tests {
             vec![10u8; 33],
         );
 
-        let bytes = proof.to_bytes();
-        let restored = MembershipProof::from_bytes(&bytes).unwrap();
+        let bytes = proof.to_envelope_bytes();
+        let restored = MembershipProof::from_envelope_bytes(&bytes).unwrap();
 
         assert_eq!(proof.note_data, restored.note_data);
         assert_eq!(proof.avl_proof, restored.avl_proof);
@@ -370,6 +781,14 @@ mod tests {
         assert_eq!(proof.root_digest, restored.root_digest);
     }
 
+    #[test]
+    fn test_membership_proof_to_bytes_is_raw_avl_proof() {
+        let proof = MembershipProof::new(vec![1, 2, 3], vec![4, 5, 6], vec![], vec![10u8; 33]);
+
+        assert_eq!(proof.to_bytes(), vec![4, 5, 6]);
+        assert_eq!(MembershipProof::from_bytes(&proof.to_bytes()).unwrap().avl_proof, vec![4, 5, 6]);
+    }
+
     #[test]
     fn test_non_membership_proof_serialization() {
         let proof = NonMembershipProof::new(
@@ -409,8 +828,76 @@ mod tests {
     #[test]
     fn test_invalid_proof_deserialization() {
         let short_data = vec![1u8; 10];
-        assert!(MembershipProof::from_bytes(&short_data).is_err());
+        assert!(MembershipProof::from_envelope_bytes(&short_data).is_err());
+        assert!(MembershipProof::from_bytes(&[]).is_err());
         assert!(NonMembershipProof::from_bytes(&short_data).is_err());
         assert!(StateProof::from_bytes(&short_data).is_err());
     }
+
+    fn filled_tree(size: u32) -> BasisAvlTree {
+        let mut tree = BasisAvlTree::new().unwrap();
+        for i in 0..size {
+            tree.insert(test_key(i), u64::from(i).to_be_bytes().to_vec()).unwrap();
+        }
+        tree.commit().unwrap();
+        tree
+    }
+
+    fn test_key(i: u32) -> Vec<u8> {
+        let mut key = vec![0u8; 32];
+        key[..4].copy_from_slice(&i.to_be_bytes());
+        key[31] = 1;
+        key
+    }
+
+    #[test]
+    fn test_compressed_proofs_restore_exactly() {
+        let mut tree = filled_tree(200);
+        let proofs: Vec<MembershipProof> = (100..116).map(|i| tree.lookup_proof(&test_key(i)).unwrap()).collect();
+        let raw: Vec<&[u8]> = proofs.iter().map(|proof| proof.avl_proof.as_slice()).collect();
+
+        let compressed = compress_proofs(&raw).unwrap();
+        assert_eq!(decompress_proofs(&compressed).unwrap(), raw);
+        // Lookups of adjacent keys share most of their path
+        let raw_size: usize = raw.iter().map(|proof| proof.len()).sum();
+        assert!(compressed.len() * 2 < raw_size, "{} of {} bytes", compressed.len(), raw_size);
+
+        // A single compressed proof still verifies and serializes for the chain
+        let single = proofs[0].compressed().unwrap();
+        assert!(single.avl_proof.len() < proofs[0].avl_proof.len());
+        assert!(single.verify(tree.get_state()).unwrap());
+        assert_eq!(single.to_bytes(), proofs[0].avl_proof);
+        assert_eq!(compress_proof(&single.avl_proof).unwrap(), single.avl_proof);
+
+        // Batch proofs of modifications too
+        tree.insert(test_key(500), vec![5]).unwrap();
+        tree.update(test_key(3), vec![7]).unwrap();
+        let batch = tree.generate_proof_batch().unwrap();
+        let compressed = batch.compressed().unwrap();
+        assert!(compressed.verify().unwrap());
+        assert_eq!(decompress_proof(&compressed.avl_proof).unwrap(), batch.avl_proof);
+    }
+
+    #[test]
+    fn test_decompress_rejects_malformed_streams() {
+        let mut tree = filled_tree(20);
+        let raw = tree.lookup_proof(&test_key(7)).unwrap().avl_proof;
+        assert!(matches!(decompress_proof(&raw).unwrap(), Cow::Borrowed(_)));
+
+        let compressed = compress_proof(&raw).unwrap();
+        for len in 0..compressed.len() {
+            assert!(decompress_proofs(&compressed[..len]).is_err());
+        }
+        let mut trailing = compressed.clone();
+        trailing.push(0);
+        assert!(decompress_proofs(&trailing).is_err());
+        let mut unknown_version = compressed;
+        unknown_version[1] = 9;
+        assert!(decompress_proof(&unknown_version).is_err());
+        // Two proofs are not one
+        assert!(decompress_proof(&compress_proofs(&[&raw, &raw]).unwrap()).is_err());
+        // A label reference must point back into the stream
+        assert!(decompress_proofs(&[COMPRESSED_PROOF_TAG, COMPRESSED_PROOF_VERSION, 1, PACKED_LABEL_REF, 0]).is_err());
+        assert!(compress_proof(&[PACKED_LABEL, 1, 2]).is_err());
+    }
 }
\ No newline at end of file
@@ -1,7 +1,7 @@
 //! Simple in-memory storage layer for AVL tree
 //! 
-//! Since Fjall persistence doesn't work well with AVL+ trees due to resolver limitations,
-//! this provides a simple in-memory storage implementation.
+//! Keeps node, operation and checkpoint records in memory. Persistent node
+//! storage for `BasisAvlTree` lives in `fjall_storage`.
 
 use crate::errors::TreeError;
 use serde::{Deserialize, Serialize};
@@ -0,0 +1,193 @@
+//! Contract-side AVL proof verification
+//!
+//! Mirrors what the on-chain AVL tree verifier does with a tracker proof:
+//! rebuild the partial tree from the starting digest and the serialized proof
+//! with `BatchAVLVerifier`, replay the operations and compare the resulting
+//! digest. The operation trail is serialized alongside each proof so that
+//! clients can replay it without access to the full tree.
+
+use crate::errors::TreeError;
+use crate::proofs::decompress_proof;
+
+use ergo_avltree_rust::{
+    authenticated_tree_ops::AuthenticatedTreeOps,
+    batch_avl_verifier::BatchAVLVerifier,
+    batch_node::{AVLTree, Node, NodeHeader},
+    operation::{KeyValue, Operation},
+};
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// Key length used by the tracker and reserve AVL trees
+pub const AVL_KEY_LENGTH: usize = 32;
+
+/// Length of an AVL tree digest (32-byte root label + 1 byte height)
+pub const AVL_DIGEST_LENGTH: usize = 33;
+
+/// Operation type tags used in the serialized operation trail
+pub const OP_LOOKUP: u8 = 1;
+pub const OP_INSERT: u8 = 2;
+pub const OP_UPDATE: u8 = 3;
+pub const OP_INSERT_OR_UPDATE: u8 = 4;
+pub const OP_REMOVE: u8 = 5;
+
+/// Result of replaying an operation trail against a proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifiedOperations {
+    /// Tree digest after all operations were applied
+    pub digest: [u8; AVL_DIGEST_LENGTH],
+    /// Value found for each operation's key before the operation, if any
+    pub results: Vec<Option<Vec<u8>>>,
+}
+
+// Subtrees not included in the proof stay label-only in the verifier
+fn verifier_resolver(digest: &[u8; 32]) -> Node {
+    Node::LabelOnly(NodeHeader::new(Some(*digest), None))
+}
+
+/// Serialize an operation trail
+///
+/// Each operation is encoded as a type tag, the 32-byte key and, for
+/// operations carrying a value, a 4-byte big-endian length followed by the value.
+pub fn encode_operations(operations: &[Operation]) -> Result<Vec<u8>, TreeError> {
+    let mut bytes = Vec::new();
+
+    for operation in operations {
+        let (tag, value) = match operation {
+            Operation::Lookup(_) => (OP_LOOKUP, None),
+            Operation::Insert(kv) => (OP_INSERT, Some(&kv.value)),
+            Operation::Update(kv) => (OP_UPDATE, Some(&kv.value)),
+            Operation::InsertOrUpdate(kv) => (OP_INSERT_OR_UPDATE, Some(&kv.value)),
+            Operation::Remove(_) => (OP_REMOVE, None),
+            _ => return Err(TreeError::UnsupportedOperation),
+        };
+
+        let key = operation.key();
+        if key.len() != AVL_KEY_LENGTH {
+            return Err(TreeError::InvalidProof);
+        }
+
+        bytes.push(tag);
+        bytes.extend_from_slice(&key);
+        if let Some(value) = value {
+            bytes.extend_from_slice(&(value.len() as u32).to_be_bytes());
+            bytes.extend_from_slice(value);
+        }
+    }
+
+    Ok(bytes)
+}
+
+/// Deserialize an operation trail produced by [`encode_operations`]
+pub fn decode_operations(data: &[u8]) -> Result<Vec<Operation>, TreeError> {
+    let mut operations = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let tag = data[offset];
+        offset += 1;
+
+        if data.len() < offset + AVL_KEY_LENGTH {
+            return Err(TreeError::InvalidProof);
+        }
+        let key = data[offset..offset + AVL_KEY_LENGTH].to_vec();
+        offset += AVL_KEY_LENGTH;
+
+        let mut read_value = || -> Result<Vec<u8>, TreeError> {
+            if data.len() < offset + 4 {
+                return Err(TreeError::InvalidProof);
+            }
+            let len = u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap()) as usize;
+            offset += 4;
+            if data.len() < offset + len {
+                return Err(TreeError::InvalidProof);
+            }
+            let value = data[offset..offset + len].to_vec();
+            offset += len;
+            Ok(value)
+        };
+
+        let operation = match tag {
+            OP_LOOKUP => Operation::Lookup(key.into()),
+            OP_INSERT => Operation::Insert(KeyValue {
+                key: key.into(),
+                value: read_value()?.into(),
+            }),
+            OP_UPDATE => Operation::Update(KeyValue {
+                key: key.into(),
+                value: read_value()?.into(),
+            }),
+            OP_INSERT_OR_UPDATE => Operation::InsertOrUpdate(KeyValue {
+                key: key.into(),
+                value: read_value()?.into(),
+            }),
+            OP_REMOVE => Operation::Remove(key.into()),
+            _ => return Err(TreeError::InvalidProof),
+        };
+        operations.push(operation);
+    }
+
+    Ok(operations)
+}
+
+/// Verify a serialized AVL proof by replaying the operation trail
+///
+/// This is the same check the on-chain verifier performs: the proof must
+/// reconstruct a tree with `starting_digest`, every operation must succeed
+/// against it, and the returned digest is the tree root after the operations.
+/// A compressed proof is decompressed first.
+pub fn verify_operations(
+    starting_digest: &[u8],
+    proof: &[u8],
+    operations: &[u8],
+) -> Result<VerifiedOperations, TreeError> {
+    if starting_digest.len() != AVL_DIGEST_LENGTH {
+        return Err(TreeError::InvalidProof);
+    }
+
+    let operations = decode_operations(operations)?;
+    let proof = decompress_proof(proof)?;
+
+    // The verifier indexes into the proof without bounds checks, so a
+    // malformed proof can panic instead of returning an error
+    catch_unwind(AssertUnwindSafe(|| {
+        replay_operations(starting_digest, &proof, &operations)
+    }))
+    .unwrap_or(Err(TreeError::InvalidProof))
+}
+
+fn replay_operations(
+    starting_digest: &[u8],
+    proof: &[u8],
+    operations: &[Operation],
+) -> Result<VerifiedOperations, TreeError> {
+    let tree = AVLTree::new(verifier_resolver, AVL_KEY_LENGTH, None);
+    let mut verifier = BatchAVLVerifier::new(
+        &starting_digest.to_vec().into(),
+        &proof.to_vec().into(),
+        tree,
+        Some(operations.len()),
+        None,
+    )
+    .map_err(|_| TreeError::InvalidProof)?;
+
+    let mut results = Vec::with_capacity(operations.len());
+    for operation in operations {
+        let result = verifier
+            .perform_one_operation(operation)
+            .map_err(|_| TreeError::InvalidProof)?;
+        results.push(result.map(|value| value.to_vec()));
+    }
+
+    let digest = verifier.digest().ok_or(TreeError::InvalidProof)?;
+    if digest.len() != AVL_DIGEST_LENGTH {
+        return Err(TreeError::InvalidProof);
+    }
+    let mut result = [0u8; AVL_DIGEST_LENGTH];
+    result.copy_from_slice(&digest);
+
+    Ok(VerifiedOperations {
+        digest: result,
+        results,
+    })
+}